@@ -0,0 +1,175 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fields appended to the serialization of a type after values without them were stored.
+//!
+//! BCS has no optional fields. Instead, a type's new fields are serialized after the original
+//! ones, behind a tag byte and as a variant of an enum of versions, so that later versions can
+//! add more. A value serialized without them is a prefix of the new serialization: it is read
+//! without the appended fields only if the input ends right before them. That is the case for
+//! values that are stored on their own, or as the last part of a stored value, e.g. in an
+//! optional register; only such positions are compatible with the values stored before. Where
+//! an old value is followed by other data, e.g. nested in a message or in a sequence, reading
+//! it fails instead of misreading that data as appended fields, unless the byte that follows
+//! happens to be the tag. Containers that stored such values elsewhere must append the new
+//! fields themselves.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The byte serialized before the appended fields. It is neither a boolean nor the tag of
+/// an option or of one of the first variants of an enum, which are likely to follow an old
+/// value that is not the last part of its input.
+const APPENDED_FIELDS_TAG: u8 = 0xa5;
+
+/// Serializes the original `fields` of a value, followed by the `appended` ones.
+pub fn serialize<S, F, A>(fields: &F, appended: &A, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    F: Serialize,
+    A: Serialize,
+{
+    (fields, APPENDED_FIELDS_TAG, appended).serialize(serializer)
+}
+
+/// Deserializes the original fields of a value, followed by the appended ones if the input
+/// doesn't end before them. Any other failure to read the appended fields is an error.
+pub fn deserialize<'de, D, F, A>(deserializer: D) -> Result<(F, Option<A>), D::Error>
+where
+    D: Deserializer<'de>,
+    F: Deserialize<'de>,
+    A: Deserialize<'de>,
+{
+    struct AppendedVisitor<F, A> {
+        is_human_readable: bool,
+        _phantom: PhantomData<(F, A)>,
+    }
+
+    impl<'de, F, A> Visitor<'de> for AppendedVisitor<F, A>
+    where
+        F: Deserialize<'de>,
+        A: Deserialize<'de>,
+    {
+        type Value = (F, Option<A>);
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("fields followed by appended fields")
+        }
+
+        fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+        where
+            S: SeqAccess<'de>,
+        {
+            let fields = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let tag = match seq.next_element::<u8>() {
+                Ok(Some(tag)) => tag,
+                // Self-describing formats end the sequence.
+                Ok(None) => return Ok((fields, None)),
+                // Binary formats like BCS can only fail to read a byte at the end of the input.
+                Err(_) if !self.is_human_readable => return Ok((fields, None)),
+                Err(error) => return Err(error),
+            };
+            if tag != APPENDED_FIELDS_TAG {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Unsigned(tag.into()),
+                    &"the tag of appended fields",
+                ));
+            }
+            let appended = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+            Ok((fields, Some(appended)))
+        }
+    }
+
+    let visitor = AppendedVisitor {
+        is_human_readable: deserializer.is_human_readable(),
+        _phantom: PhantomData,
+    };
+    deserializer.deserialize_tuple(3, visitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Fields {
+        a: u64,
+        b: String,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Appended {
+        V1 { c: Option<u32> },
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Value {
+        fields: Fields,
+        appended: Option<Appended>,
+    }
+
+    impl Serialize for Value {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let appended = self.appended.as_ref().unwrap_or(&Appended::V1 { c: None });
+            super::serialize(&self.fields, appended, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let (fields, appended) = super::deserialize(deserializer)?;
+            Ok(Value { fields, appended })
+        }
+    }
+
+    #[test]
+    fn test_read_value_without_appended_fields() {
+        let fields = Fields {
+            a: 42,
+            b: "hello".to_string(),
+        };
+        let value = Value {
+            fields,
+            appended: Some(Appended::V1 { c: Some(7) }),
+        };
+        let bytes = bcs::to_bytes(&value).unwrap();
+        assert_eq!(bcs::from_bytes::<Value>(&bytes).unwrap(), value);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+
+        // Values serialized before the fields were appended can be read, if nothing follows.
+        let old_bytes = bcs::to_bytes(&value.fields).unwrap();
+        assert!(bytes.starts_with(&old_bytes));
+        let old_value = bcs::from_bytes::<Value>(&old_bytes).unwrap();
+        assert_eq!(old_value.fields, value.fields);
+        assert_eq!(old_value.appended, None);
+        assert!(bcs::from_bytes::<Value>(&[&bytes[..], &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_appended_fields_are_errors() {
+        let fields = Fields {
+            a: 42,
+            b: "hello".to_string(),
+        };
+        let old_bytes = bcs::to_bytes(&fields).unwrap();
+
+        // An old value followed by other data is not read as a value with appended fields.
+        let followed = [&old_bytes[..], &bcs::to_bytes(&true).unwrap()].concat();
+        assert!(bcs::from_bytes::<(Value, bool)>(&followed).is_err());
+
+        // Unknown versions and truncated appended fields are errors.
+        let unknown_version = [&old_bytes[..], &[super::APPENDED_FIELDS_TAG, 1]].concat();
+        assert!(bcs::from_bytes::<Value>(&unknown_version).is_err());
+        let truncated = [&old_bytes[..], &[super::APPENDED_FIELDS_TAG]].concat();
+        assert!(bcs::from_bytes::<Value>(&truncated).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Defines the BLS12-381 signatures that validators can aggregate, so that a certificate
+//! carries a single signature instead of one per validator.
+//!
+//! Public keys are points of G1 (48 bytes) and signatures points of G2 (96 bytes). All the
+//! validators sign the same message, so aggregated signatures are verified with a single
+//! pairing check. This is only secure with public keys whose owners proved they know the
+//! secret key, to prevent rogue key attacks: see [`BlsValidatorKey`].
+//!
+//! Keys and signatures can be serialized on all targets, but signing and verifying are not
+//! supported on Wasm.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use super::{BcsSignable, CryptoError};
+#[cfg(not(target_arch = "wasm32"))]
+use super::{CryptoHash, ValidatorSecretKey};
+use crate::doc_scalar;
+
+/// Length of a compressed BLS12-381 public key.
+const BLS_PUBLIC_KEY_SIZE: usize = 48;
+
+/// Length of a compressed BLS12-381 signature.
+const BLS_SIGNATURE_SIZE: usize = 96;
+
+/// The domain separation tag of signatures, for the proof-of-possession scheme.
+#[cfg(not(target_arch = "wasm32"))]
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The domain separation tag of proofs of possession.
+#[cfg(not(target_arch = "wasm32"))]
+const PROOF_OF_POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The key information used to derive the BLS key of a validator from its secret key.
+#[cfg(not(target_arch = "wasm32"))]
+const VALIDATOR_KEY_INFO: &[u8] = b"linera validator BLS12-381 key";
+
+/// A BLS12-381 secret key.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct BlsSecretKey(blst::min_pk::SecretKey);
+
+/// A compressed BLS12-381 public key.
+#[serde_as]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Serialize, Deserialize)]
+pub struct BlsPublicKey(#[serde_as(as = "[_; 48]")] pub [u8; BLS_PUBLIC_KEY_SIZE]);
+
+/// A compressed BLS12-381 signature, possibly aggregating the signatures of several keys.
+#[serde_as]
+#[derive(Eq, PartialEq, Copy, Clone, Hash, Serialize, Deserialize)]
+pub struct BlsSignature(#[serde_as(as = "[_; 96]")] pub [u8; BLS_SIGNATURE_SIZE]);
+
+/// A BLS12-381 public key that was decoded and checked to be valid, so that signatures can
+/// be verified without decoding it again.
+#[derive(Clone, Copy)]
+pub struct DecodedBlsPublicKey {
+    #[cfg(not(target_arch = "wasm32"))]
+    point: blst::min_pk::PublicKey,
+}
+
+/// The BLS12-381 public key of a validator, with the proof that the validator knows the
+/// secret key.
+#[derive(Eq, PartialEq, Copy, Clone, Hash, Debug, Serialize, Deserialize)]
+pub struct BlsValidatorKey {
+    /// The public key.
+    pub public_key: BlsPublicKey,
+    /// The signature of the public key itself.
+    pub proof_of_possession: BlsSignature,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BlsSecretKey {
+    /// Derives the BLS key of a validator from its secret key, so that validators don't
+    /// need to store another key.
+    pub fn from_validator_secret(secret: &ValidatorSecretKey) -> Self {
+        let key = blst::min_pk::SecretKey::key_gen(&secret.0.to_bytes(), VALIDATOR_KEY_INFO)
+            .expect("secp256k1 secret keys have enough entropy");
+        BlsSecretKey(key)
+    }
+
+    /// Returns the public key of this secret key.
+    pub fn public(&self) -> BlsPublicKey {
+        BlsPublicKey(self.0.sk_to_pk().compress())
+    }
+
+    /// Returns the public key of this secret key, with its proof of possession.
+    pub fn validator_key(&self) -> BlsValidatorKey {
+        let public_key = self.public();
+        let proof = self.0.sign(&public_key.0, PROOF_OF_POSSESSION_DST, &[]);
+        BlsValidatorKey {
+            public_key,
+            proof_of_possession: BlsSignature(proof.compress()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Debug for BlsSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted for BLS12-381 secret key>")
+    }
+}
+
+impl BlsPublicKey {
+    /// Decodes the public key, checking that it is a valid point of the right subgroup.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn decode(&self) -> Result<DecodedBlsPublicKey, CryptoError> {
+        let point = blst::min_pk::PublicKey::key_validate(&self.0)
+            .map_err(|error| CryptoError::InvalidBlsKey(format!("{error:?}")))?;
+        Ok(DecodedBlsPublicKey { point })
+    }
+
+    /// Decoding public keys is not supported on Wasm.
+    #[cfg(target_arch = "wasm32")]
+    pub fn decode(&self) -> Result<DecodedBlsPublicKey, CryptoError> {
+        Err(CryptoError::BlsUnsupported)
+    }
+}
+
+impl BlsSignature {
+    /// Computes a signature of `value` with the given secret key.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new<'de, T>(value: &T, secret: &BlsSecretKey) -> Self
+    where
+        T: BcsSignable<'de>,
+    {
+        let message = CryptoHash::new(value);
+        BlsSignature(
+            secret
+                .0
+                .sign(&message.as_bytes().0, SIGNATURE_DST, &[])
+                .compress(),
+        )
+    }
+
+    /// Aggregates signatures of the same value into a single signature.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn aggregate<'a>(
+        signatures: impl IntoIterator<Item = &'a BlsSignature>,
+    ) -> Result<Self, CryptoError> {
+        let signatures = signatures
+            .into_iter()
+            .map(BlsSignature::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        let signatures = signatures.iter().collect::<Vec<_>>();
+        let aggregate = blst::min_pk::AggregateSignature::aggregate(&signatures, false)
+            .map_err(|error| CryptoError::InvalidBlsSignature(format!("{error:?}")))?;
+        Ok(BlsSignature(aggregate.to_signature().compress()))
+    }
+
+    /// Checks a signature of `value` by the given key.
+    pub fn check<'de, T>(&self, value: &T, author: &BlsPublicKey) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de>,
+    {
+        self.check_aggregate(value, [&author.decode()?])
+    }
+
+    /// Checks that this signature aggregates the signatures of `value` by all the given
+    /// keys. The keys must have been checked with [`BlsValidatorKey::check`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn check_aggregate<'a, 'de, T>(
+        &self,
+        value: &T,
+        authors: impl IntoIterator<Item = &'a DecodedBlsPublicKey>,
+    ) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de>,
+    {
+        let public_keys = authors
+            .into_iter()
+            .map(|author| &author.point)
+            .collect::<Vec<_>>();
+        let message = CryptoHash::new(value);
+        let result = self.decode()?.fast_aggregate_verify(
+            false,
+            &message.as_bytes().0,
+            SIGNATURE_DST,
+            &public_keys,
+        );
+        match result {
+            blst::BLST_ERROR::BLST_SUCCESS => Ok(()),
+            error => Err(CryptoError::InvalidSignature {
+                error: format!("{error:?}"),
+                type_name: std::any::type_name::<T>().to_string(),
+            }),
+        }
+    }
+
+    /// Checking signatures is not supported on Wasm.
+    #[cfg(target_arch = "wasm32")]
+    pub fn check_aggregate<'a, 'de, T>(
+        &self,
+        _value: &T,
+        _authors: impl IntoIterator<Item = &'a DecodedBlsPublicKey>,
+    ) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de>,
+    {
+        Err(CryptoError::BlsUnsupported)
+    }
+
+    /// Decodes the signature, checking that it is in the right subgroup.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn decode(&self) -> Result<blst::min_pk::Signature, CryptoError> {
+        blst::min_pk::Signature::sig_validate(&self.0, true)
+            .map_err(|error| CryptoError::InvalidBlsSignature(format!("{error:?}")))
+    }
+}
+
+impl BlsValidatorKey {
+    /// Checks the proof of possession of the public key.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn check(&self) -> Result<(), CryptoError> {
+        let public_key = self.public_key.decode()?;
+        let result = self.proof_of_possession.decode()?.verify(
+            false,
+            &self.public_key.0,
+            PROOF_OF_POSSESSION_DST,
+            &[],
+            &public_key.point,
+            false,
+        );
+        match result {
+            blst::BLST_ERROR::BLST_SUCCESS => Ok(()),
+            error => Err(CryptoError::InvalidBlsKey(format!(
+                "invalid proof of possession: {error:?}"
+            ))),
+        }
+    }
+
+    /// Checking proofs of possession is not supported on Wasm.
+    #[cfg(target_arch = "wasm32")]
+    pub fn check(&self) -> Result<(), CryptoError> {
+        Err(CryptoError::BlsUnsupported)
+    }
+}
+
+impl fmt::Display for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..", hex::encode(&self.0[0..9]))
+    }
+}
+
+impl fmt::Debug for DecodedBlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodedBlsPublicKey")
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..", hex::encode(&self.0[0..9]))
+    }
+}
+
+impl std::str::FromStr for BlsValidatorKey {
+    type Err = CryptoError;
+
+    /// Parses a public key and its proof of possession, in hexadecimal and separated by a
+    /// colon.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (public_key, proof) = s.split_once(':').ok_or_else(|| {
+            CryptoError::InvalidBlsKey(format!("missing proof of possession: {s}"))
+        })?;
+        let mut key = BlsValidatorKey {
+            public_key: BlsPublicKey([0; BLS_PUBLIC_KEY_SIZE]),
+            proof_of_possession: BlsSignature([0; BLS_SIGNATURE_SIZE]),
+        };
+        hex::decode_to_slice(public_key, &mut key.public_key.0)?;
+        hex::decode_to_slice(proof, &mut key.proof_of_possession.0)?;
+        Ok(key)
+    }
+}
+
+impl fmt::Display for BlsValidatorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.public_key, self.proof_of_possession)
+    }
+}
+
+doc_scalar!(BlsPublicKey, "A BLS12-381 public key value");
+doc_scalar!(BlsSignature, "A BLS12-381 signature value");
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::{BlsSecretKey, BlsSignature};
+    use crate::crypto::{TestString, ValidatorKeypair};
+
+    #[test]
+    fn test_aggregate_signatures() {
+        let secrets = (0..3)
+            .map(|_| BlsSecretKey::from_validator_secret(&ValidatorKeypair::generate().secret_key))
+            .collect::<Vec<_>>();
+        let public_keys = secrets
+            .iter()
+            .map(|secret| {
+                let key = secret.validator_key();
+                key.check().unwrap();
+                key.public_key.decode().unwrap()
+            })
+            .collect::<Vec<_>>();
+        let value = TestString("hello".into());
+        let signatures = secrets
+            .iter()
+            .map(|secret| BlsSignature::new(&value, secret))
+            .collect::<Vec<_>>();
+        signatures[0].check(&value, &secrets[0].public()).unwrap();
+        assert!(signatures[0].check(&value, &secrets[1].public()).is_err());
+
+        let aggregate = BlsSignature::aggregate(&signatures).unwrap();
+        aggregate.check_aggregate(&value, &public_keys).unwrap();
+        assert!(aggregate
+            .check_aggregate(&value, &public_keys[1..])
+            .is_err());
+        let other_value = TestString("hellox".into());
+        assert!(aggregate
+            .check_aggregate(&other_value, &public_keys)
+            .is_err());
+    }
+
+    #[test]
+    fn test_validator_key_round_trip() {
+        let secret = BlsSecretKey::from_validator_secret(&ValidatorKeypair::generate().secret_key);
+        let key = secret.validator_key();
+        assert_eq!(
+            key.to_string().parse::<super::BlsValidatorKey>().unwrap(),
+            key
+        );
+
+        let other_key =
+            BlsSecretKey::from_validator_secret(&ValidatorKeypair::generate().secret_key)
+                .validator_key();
+        let mut invalid_key = key;
+        invalid_key.proof_of_possession = other_key.proof_of_possession;
+        assert!(invalid_key.check().is_err());
+    }
+}
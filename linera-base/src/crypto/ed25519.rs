@@ -62,6 +62,19 @@ impl Ed25519SecretKey {
     pub fn copy(&self) -> Ed25519SecretKey {
         Ed25519SecretKey(self.0.clone())
     }
+
+    /// Computes the X25519 Diffie-Hellman secret shared between this key and the owner of
+    /// `public_key`, using the Montgomery form of both keys.
+    ///
+    /// Returns `None` if `public_key` is not a valid point, or if the result is the
+    /// identity, e.g. because `public_key` has a small order.
+    pub fn shared_secret(&self, public_key: &Ed25519PublicKey) -> Option<[u8; 32]> {
+        let point = dalek::VerifyingKey::from_bytes(&public_key.0)
+            .ok()?
+            .to_montgomery();
+        let secret = point.mul_clamped(self.0.to_scalar_bytes()).to_bytes();
+        (secret != [0; 32]).then_some(secret)
+    }
 }
 
 impl Ed25519PublicKey {
@@ -307,17 +320,13 @@ impl Ed25519Signature {
         Ok(Ed25519Signature(sig))
     }
 
-    fn check_internal<'de, T>(
+    fn check_internal(
         &self,
-        value: &T,
+        prehash: CryptoHash,
         author: Ed25519PublicKey,
-    ) -> Result<(), dalek::SignatureError>
-    where
-        T: BcsSignable<'de>,
-    {
-        let prehash = CryptoHash::new(value).as_bytes().0;
+    ) -> Result<(), dalek::SignatureError> {
         let public_key = dalek::VerifyingKey::from_bytes(&author.0)?;
-        public_key.verify(&prehash, &self.0)
+        public_key.verify(&prehash.as_bytes().0, &self.0)
     }
 
     /// Checks a signature.
@@ -325,13 +334,26 @@ impl Ed25519Signature {
     where
         T: BcsSignable<'de> + fmt::Debug,
     {
-        self.check_internal(value, author)
+        self.check_internal(CryptoHash::new(value), author)
             .map_err(|error| CryptoError::InvalidSignature {
                 error: error.to_string(),
                 type_name: T::type_name().to_string(),
             })
     }
 
+    /// Checks a signature of a prehash, as computed by [`Self::sign_prehash`].
+    pub fn check_prehash(
+        &self,
+        prehash: CryptoHash,
+        author: Ed25519PublicKey,
+    ) -> Result<(), CryptoError> {
+        self.check_internal(prehash, author)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error: error.to_string(),
+                type_name: "CryptoHash".to_string(),
+            })
+    }
+
     fn verify_batch_internal<'a, 'de, T, I>(
         value: &'a T,
         votes: I,
@@ -456,6 +478,22 @@ mod tests {
         assert!(s.check(&foo, addr1).is_err());
     }
 
+    #[test]
+    fn test_shared_secret() {
+        use crate::crypto::ed25519::{Ed25519PublicKey, Ed25519SecretKey};
+
+        let key1 = Ed25519SecretKey::generate();
+        let key2 = Ed25519SecretKey::generate();
+        let key3 = Ed25519SecretKey::generate();
+        let secret = key1.shared_secret(&key2.public()).unwrap();
+        assert_eq!(key2.shared_secret(&key1.public()), Some(secret));
+        assert_ne!(key3.shared_secret(&key1.public()), Some(secret));
+        // The identity point has a small order.
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        assert_eq!(key1.shared_secret(&Ed25519PublicKey(identity)), None);
+    }
+
     #[test]
     fn test_public_key_serialization() {
         use crate::crypto::ed25519::Ed25519PublicKey;
@@ -0,0 +1,339 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hierarchical deterministic derivation of account keys from a mnemonic phrase.
+//!
+//! The seed of a [BIP-39] mnemonic phrase derives secp256k1 keys with [BIP-32] and Ed25519
+//! keys with [SLIP-10], so that all the keys of a wallet can be recovered from the phrase.
+//!
+//! [BIP-39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+//! [BIP-32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+//! [SLIP-10]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+
+use std::{fmt, str::FromStr};
+
+use hmac::{Hmac, Mac as _};
+use k256::elliptic_curve::{sec1::ToEncodedPoint as _, PrimeField as _};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ed25519::Ed25519SecretKey,
+    secp256k1::{evm::EvmSecretKey, Secp256k1SecretKey},
+    AccountSecretKey, CryptoError, SignatureScheme,
+};
+
+type HmacSha512 = Hmac<sha2::Sha512>;
+
+/// The first hardened child index.
+const HARDENED: u32 = 1 << 31;
+
+/// The number of bytes of entropy of the generated mnemonic phrases, i.e. 24 words.
+#[cfg(with_getrandom)]
+const MNEMONIC_ENTROPY_BYTES: usize = 32;
+
+/// The SLIP-44 coin type of the default derivation paths. Linera has no registered coin
+/// type, so this is the one shared by all testnets.
+const LINERA_COIN_TYPE: u32 = 1;
+
+/// The SLIP-44 coin type of Ethereum, so that EVM keys match those of EVM wallets.
+const ETHEREUM_COIN_TYPE: u32 = 60;
+
+/// A BIP-32 derivation path, such as `m/44'/60'/0'/0/0`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Returns the default path of the account key with the given index, for the given
+    /// signature scheme.
+    ///
+    /// Ed25519 keys only have hardened derivations. EVM keys use the path of EVM wallets.
+    pub fn for_account(scheme: SignatureScheme, index: u32) -> Self {
+        let index = index & !HARDENED;
+        let path = match scheme {
+            SignatureScheme::Ed25519 => {
+                vec![44 | HARDENED, LINERA_COIN_TYPE | HARDENED, index | HARDENED]
+            }
+            SignatureScheme::Secp256k1 => vec![
+                44 | HARDENED,
+                LINERA_COIN_TYPE | HARDENED,
+                index | HARDENED,
+                0,
+                0,
+            ],
+            SignatureScheme::EvmSecp256k1 => {
+                vec![
+                    44 | HARDENED,
+                    ETHEREUM_COIN_TYPE | HARDENED,
+                    HARDENED,
+                    0,
+                    index,
+                ]
+            }
+        };
+        DerivationPath(path)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = CryptoError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let invalid = || CryptoError::InvalidDerivationPath(path.to_string());
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(invalid());
+        }
+        components
+            .map(|component| {
+                let (index, hardened) = match component.strip_suffix(['\'', 'h', 'H']) {
+                    Some(index) => (index, true),
+                    None => (component, false),
+                };
+                let index = index.parse::<u32>().map_err(|_| invalid())?;
+                if index >= HARDENED {
+                    return Err(invalid());
+                }
+                Ok(if hardened { index | HARDENED } else { index })
+            })
+            .collect::<Result<_, _>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if index & HARDENED != 0 {
+                write!(f, "/{}'", index & !HARDENED)?;
+            } else {
+                write!(f, "/{index}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The seed of a mnemonic phrase, from which keys are derived.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HdSeed(Vec<u8>);
+
+impl HdSeed {
+    /// Returns the seed of the BIP-39 mnemonic phrase, with an optional passphrase.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, CryptoError> {
+        let phrase = phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mnemonic = bip39::Mnemonic::parse_normalized(&phrase)
+            .map_err(|error| CryptoError::InvalidMnemonic(error.to_string()))?;
+        Ok(HdSeed(mnemonic.to_seed_normalized(passphrase).to_vec()))
+    }
+
+    /// Derives the secret key at the given path, for the given signature scheme.
+    pub fn derive(
+        &self,
+        scheme: SignatureScheme,
+        path: &DerivationPath,
+    ) -> Result<AccountSecretKey, CryptoError> {
+        Ok(match scheme {
+            SignatureScheme::Ed25519 => AccountSecretKey::Ed25519(Ed25519SecretKey(
+                ed25519_dalek::SigningKey::from_bytes(&self.derive_ed25519(path)?),
+            )),
+            SignatureScheme::Secp256k1 => {
+                AccountSecretKey::Secp256k1(Secp256k1SecretKey(self.derive_secp256k1(path)?.into()))
+            }
+            SignatureScheme::EvmSecp256k1 => {
+                AccountSecretKey::EvmSecp256k1(EvmSecretKey(self.derive_secp256k1(path)?.into()))
+            }
+        })
+    }
+
+    /// Derives a secp256k1 key with BIP-32.
+    fn derive_secp256k1(&self, path: &DerivationPath) -> Result<k256::SecretKey, CryptoError> {
+        let (key, mut chain_code) = split(hmac_sha512(b"Bitcoin seed", &[&self.0]));
+        let mut secret = k256::SecretKey::from_slice(&key).map_err(|_| invalid_key(path))?;
+        for &index in &path.0 {
+            let output = if index & HARDENED != 0 {
+                hmac_sha512(
+                    &chain_code,
+                    &[&[0], &secret.to_bytes(), &index.to_be_bytes()],
+                )
+            } else {
+                let public_key = secret.public_key().to_encoded_point(true);
+                hmac_sha512(&chain_code, &[public_key.as_bytes(), &index.to_be_bytes()])
+            };
+            let (tweak, child_chain_code) = split(output);
+            let tweak = Option::<k256::Scalar>::from(k256::Scalar::from_repr(tweak.into()))
+                .ok_or_else(|| invalid_key(path))?;
+            let child = tweak + *secret.to_nonzero_scalar();
+            secret =
+                k256::SecretKey::from_bytes(&child.to_repr()).map_err(|_| invalid_key(path))?;
+            chain_code = child_chain_code;
+        }
+        Ok(secret)
+    }
+
+    /// Derives an Ed25519 key with SLIP-10, which only supports hardened indices.
+    fn derive_ed25519(&self, path: &DerivationPath) -> Result<[u8; 32], CryptoError> {
+        let (mut key, mut chain_code) = split(hmac_sha512(b"ed25519 seed", &[&self.0]));
+        for &index in &path.0 {
+            if index & HARDENED == 0 {
+                return Err(CryptoError::InvalidDerivationPath(format!(
+                    "{path}: Ed25519 keys only support hardened derivations"
+                )));
+            }
+            (key, chain_code) = split(hmac_sha512(
+                &chain_code,
+                &[&[0], &key, &index.to_be_bytes()],
+            ));
+        }
+        Ok(key)
+    }
+}
+
+impl fmt::Debug for HdSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted for HD seed>")
+    }
+}
+
+impl Serialize for HdSeed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for HdSeed {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        let seed = hex::decode(string).map_err(serde::de::Error::custom)?;
+        Ok(HdSeed(seed))
+    }
+}
+
+/// Generates a new 24-word BIP-39 mnemonic phrase.
+#[cfg(with_getrandom)]
+pub fn generate_mnemonic<R: super::CryptoRng + ?Sized>(rng: &mut R) -> String {
+    let mut entropy = [0; MNEMONIC_ENTROPY_BYTES];
+    rng.fill_bytes(&mut entropy);
+    bip39::Mnemonic::from_entropy(&entropy)
+        .expect("32 bytes are a valid entropy length")
+        .to_string()
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for data in data {
+        mac.update(data);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Splits the output of HMAC-SHA512 into a key and a chain code.
+fn split(output: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut key = [0; 32];
+    let mut chain_code = [0; 32];
+    key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    (key, chain_code)
+}
+
+fn invalid_key(path: &DerivationPath) -> CryptoError {
+    CryptoError::InvalidDerivationPath(format!("{path}: the derived key is invalid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DerivationPath, HdSeed};
+    use crate::crypto::SignatureScheme;
+
+    /// The seed of the first test vector of BIP-32 and SLIP-10.
+    fn test_seed() -> HdSeed {
+        HdSeed((0..16).collect())
+    }
+
+    #[test]
+    fn test_derivation_path_round_trip() {
+        let path = "m/44'/60'/0'/0/3".parse::<DerivationPath>().unwrap();
+        assert_eq!(path.to_string(), "m/44'/60'/0'/0/3");
+        assert_eq!(
+            path,
+            DerivationPath::for_account(SignatureScheme::EvmSecp256k1, 3)
+        );
+        assert_eq!(
+            "m/1h/2".parse::<DerivationPath>().unwrap().to_string(),
+            "m/1'/2"
+        );
+        assert!("44'/0".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn test_bip32_vectors() {
+        let derive = |path: &str| {
+            let key = test_seed()
+                .derive_secp256k1(&path.parse().unwrap())
+                .unwrap();
+            hex::encode(key.to_bytes())
+        };
+        assert_eq!(
+            derive("m"),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            derive("m/0'"),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        assert_eq!(
+            derive("m/0'/1"),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+    }
+
+    #[test]
+    fn test_slip10_ed25519_vectors() {
+        let derive =
+            |path: &str| hex::encode(test_seed().derive_ed25519(&path.parse().unwrap()).unwrap());
+        assert_eq!(
+            derive("m"),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            derive("m/0'"),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert!(test_seed().derive_ed25519(&"m/0".parse().unwrap()).is_err());
+    }
+
+    #[cfg(with_getrandom)]
+    #[test]
+    fn test_mnemonic() {
+        let phrase = super::generate_mnemonic(&mut rand::rngs::OsRng);
+        assert_eq!(phrase.split(' ').count(), 24);
+        let seed = HdSeed::from_mnemonic(&phrase.to_uppercase(), "").unwrap();
+        assert_eq!(seed, HdSeed::from_mnemonic(&phrase, "").unwrap());
+        assert_ne!(seed, HdSeed::from_mnemonic(&phrase, "passphrase").unwrap());
+
+        let path = DerivationPath::for_account(SignatureScheme::Ed25519, 0);
+        let key = seed.derive(SignatureScheme::Ed25519, &path).unwrap();
+        let same_key = seed.derive(SignatureScheme::Ed25519, &path).unwrap();
+        assert_eq!(key.public(), same_key.public());
+
+        // Changing a word breaks the checksum.
+        let mut words = phrase.split(' ').collect::<Vec<_>>();
+        words[0] = if words[0] == "abandon" {
+            "ability"
+        } else {
+            "abandon"
+        };
+        assert!(HdSeed::from_mnemonic(&words.join(" "), "").is_err());
+    }
+}
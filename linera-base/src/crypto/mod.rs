@@ -4,17 +4,26 @@
 
 //! Define the cryptographic primitives used by the Linera protocol.
 
+mod bls;
 mod ed25519;
 mod hash;
+mod hd;
 #[allow(dead_code)]
 mod secp256k1;
 mod signer;
+mod threshold;
 use std::{fmt::Display, io, num::ParseIntError, str::FromStr};
 
 use alloy_primitives::FixedBytes;
+#[cfg(not(target_arch = "wasm32"))]
+pub use bls::BlsSecretKey;
+pub use bls::{BlsPublicKey, BlsSignature, BlsValidatorKey, DecodedBlsPublicKey};
 use custom_debug_derive::Debug;
 pub use ed25519::{Ed25519PublicKey, Ed25519SecretKey, Ed25519Signature};
 pub use hash::*;
+#[cfg(with_getrandom)]
+pub use hd::generate_mnemonic;
+pub use hd::{DerivationPath, HdSeed};
 use linera_witty::{WitLoad, WitStore, WitType};
 pub use secp256k1::{
     evm::{EvmPublicKey, EvmSecretKey, EvmSignature},
@@ -23,6 +32,12 @@ pub use secp256k1::{
 use serde::{Deserialize, Serialize};
 pub use signer::*;
 use thiserror::Error;
+pub use threshold::{
+    ThresholdCommitment, ThresholdGroupKey, ThresholdKeyGeneration,
+    ThresholdKeyGenerationBroadcast, ThresholdKeyGenerationMessage, ThresholdKeyGenerationRound2,
+    ThresholdKeyShare, ThresholdNonces, ThresholdParticipant, ThresholdSignatureShare,
+    ThresholdSigningCeremony, ThresholdSigningRequest,
+};
 
 /// The public key of a validator.
 pub type ValidatorPublicKey = secp256k1::Secp256k1PublicKey;
@@ -153,6 +168,17 @@ impl AccountSecretKey {
         }
     }
 
+    /// Computes the secret shared between this key and the owner of `public_key`, with
+    /// X25519. Returns `None` unless both keys are valid Ed25519 keys.
+    pub fn shared_secret(&self, public_key: &AccountPublicKey) -> Option<[u8; 32]> {
+        match (self, public_key) {
+            (AccountSecretKey::Ed25519(secret), AccountPublicKey::Ed25519(public)) => {
+                secret.shared_secret(public)
+            }
+            _ => None,
+        }
+    }
+
     #[cfg(all(with_testing, with_getrandom))]
     /// Generates a new key pair using the operating system's RNG.
     pub fn generate() -> Self {
@@ -236,6 +262,31 @@ impl AccountSignature {
         }
     }
 
+    /// Verifies the signature of the `prehash`, as computed by a [`Signer`], using the
+    /// provided `public_key`.
+    pub fn verify_prehash(
+        &self,
+        prehash: CryptoHash,
+        author: AccountPublicKey,
+    ) -> Result<(), CryptoError> {
+        match (self, author) {
+            (AccountSignature::Ed25519(signature), AccountPublicKey::Ed25519(public_key)) => {
+                signature.check_prehash(prehash, public_key)
+            }
+            (AccountSignature::Secp256k1(signature), AccountPublicKey::Secp256k1(public_key)) => {
+                signature.check_prehash(prehash, &public_key)
+            }
+            (
+                AccountSignature::EvmSecp256k1(signature),
+                AccountPublicKey::EvmSecp256k1(public_key),
+            ) => signature.check_prehash(prehash, &public_key),
+            _ => Err(CryptoError::InvalidSignature {
+                error: "the signature scheme doesn't match the public key".to_string(),
+                type_name: "CryptoHash".to_string(),
+            }),
+        }
+    }
+
     /// Returns byte representation of the signatures.
     pub fn to_bytes(&self) -> Vec<u8> {
         bcs::to_bytes(&self).expect("serialization to bytes should not fail")
@@ -262,6 +313,12 @@ impl Display for AccountPublicKey {
     }
 }
 
+impl std::hash::Hash for AccountSignature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
 impl TryFrom<&[u8]> for AccountSignature {
     type Error = CryptoError;
 
@@ -311,6 +368,18 @@ pub enum CryptoError {
     PublicKeyParseError(bcs::Error),
     #[error("could not parse signature: {0}")]
     SignatureParseError(bcs::Error),
+    #[error("invalid mnemonic phrase: {0}")]
+    InvalidMnemonic(String),
+    #[error("invalid derivation path: {0}")]
+    InvalidDerivationPath(String),
+    #[error("invalid BLS12-381 key: {0}")]
+    InvalidBlsKey(String),
+    #[error("invalid BLS12-381 signature: {0}")]
+    InvalidBlsSignature(String),
+    #[error("BLS12-381 signatures are not supported on this target")]
+    BlsUnsupported,
+    #[error("threshold signature error: {0}")]
+    ThresholdSignature(String),
 }
 
 #[cfg(with_getrandom)]
@@ -454,7 +523,10 @@ pub fn u64_array_to_be_bytes(integers: [u64; 4]) -> [u8; 32] {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::crypto::{ed25519::Ed25519SecretKey, secp256k1::Secp256k1KeyPair};
+    use crate::crypto::{
+        ed25519::Ed25519SecretKey,
+        secp256k1::{evm::EvmSecretKey, Secp256k1KeyPair},
+    };
 
     #[test]
     fn test_u64_array_to_be_bytes() {
@@ -523,6 +595,30 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn verify_prehash_signatures() {
+        fn test(secret: AccountSecretKey) {
+            let prehash = CryptoHash::new(&TestString::new("test"));
+            let signature = secret.sign_prehash(prehash);
+            signature.verify_prehash(prehash, secret.public()).unwrap();
+            // The same signature is valid for the value whose hash was signed.
+            signature
+                .verify(&TestString::new("test"), secret.public())
+                .unwrap();
+            let other_prehash = CryptoHash::new(&TestString::new("other"));
+            assert!(signature
+                .verify_prehash(other_prehash, secret.public())
+                .is_err());
+            let other_key = AccountSecretKey::generate().public();
+            assert!(signature.verify_prehash(prehash, other_key).is_err());
+        }
+        test(AccountSecretKey::Ed25519(Ed25519SecretKey::generate()));
+        test(AccountSecretKey::Secp256k1(
+            Secp256k1KeyPair::generate().secret_key,
+        ));
+        test(AccountSecretKey::EvmSecp256k1(EvmSecretKey::generate()));
+    }
+
     #[test]
     fn roundtrip_display_from_str_pk() {
         fn test(secret: AccountSecretKey) {
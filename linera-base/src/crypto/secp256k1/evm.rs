@@ -398,7 +398,7 @@ impl EvmSignature {
         T: BcsSignable<'de> + fmt::Debug,
     {
         let prehash = CryptoHash::new(value).as_bytes().0;
-        self.verify_inner::<T>(prehash, author)
+        self.verify_inner(prehash, author, T::type_name())
     }
 
     /// Verifies a batch of signatures.
@@ -411,7 +411,7 @@ impl EvmSignature {
     {
         let prehash = CryptoHash::new(value).as_bytes().0;
         for (author, signature) in votes {
-            signature.verify_inner::<T>(prehash, author)?;
+            signature.verify_inner(prehash, author, T::type_name())?;
         }
         Ok(())
     }
@@ -421,14 +421,21 @@ impl EvmSignature {
         self.0.as_erc2098()
     }
 
-    fn verify_inner<'de, T>(
+    /// Checks a signature of a prehash, as computed by [`Self::sign_prehash`].
+    pub fn check_prehash(
+        &self,
+        prehash: CryptoHash,
+        author: &EvmPublicKey,
+    ) -> Result<(), CryptoError> {
+        self.verify_inner(prehash.as_bytes().0, author, "CryptoHash")
+    }
+
+    fn verify_inner(
         &self,
         prehash: [u8; 32],
         author: &EvmPublicKey,
-    ) -> Result<(), CryptoError>
-    where
-        T: BcsSignable<'de> + fmt::Debug,
-    {
+        type_name: &str,
+    ) -> Result<(), CryptoError> {
         use k256::ecdsa::signature::hazmat::PrehashVerifier;
 
         let message_hash = eip191_hash_message(prehash).0;
@@ -438,7 +445,7 @@ impl EvmSignature {
             .verify_prehash(&message_hash, &self.0.to_k256().unwrap())
             .map_err(|error| CryptoError::InvalidSignature {
                 error: error.to_string(),
-                type_name: T::type_name().to_string(),
+                type_name: type_name.to_string(),
             })
     }
 
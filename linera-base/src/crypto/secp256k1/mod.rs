@@ -371,7 +371,7 @@ impl Secp256k1Signature {
         T: BcsSignable<'de> + fmt::Debug,
     {
         let prehash = CryptoHash::new(value).as_bytes().0;
-        self.verify_inner::<T>(prehash, author)
+        self.verify_inner(prehash, author, T::type_name())
     }
 
     /// Verifies a batch of signatures.
@@ -384,7 +384,7 @@ impl Secp256k1Signature {
     {
         let prehash = CryptoHash::new(value).as_bytes().0;
         for (author, signature) in votes {
-            signature.verify_inner::<T>(prehash, author)?;
+            signature.verify_inner(prehash, author, T::type_name())?;
         }
         Ok(())
     }
@@ -394,14 +394,21 @@ impl Secp256k1Signature {
         self.0.to_bytes().into()
     }
 
-    fn verify_inner<'de, T>(
+    /// Checks a signature of a prehash, as computed by [`Self::sign_prehash`].
+    pub fn check_prehash(
+        &self,
+        prehash: CryptoHash,
+        author: &Secp256k1PublicKey,
+    ) -> Result<(), CryptoError> {
+        self.verify_inner(prehash.as_bytes().0, author, "CryptoHash")
+    }
+
+    fn verify_inner(
         &self,
         prehash: [u8; 32],
         author: &Secp256k1PublicKey,
-    ) -> Result<(), CryptoError>
-    where
-        T: BcsSignable<'de> + fmt::Debug,
-    {
+        type_name: &str,
+    ) -> Result<(), CryptoError> {
         use k256::ecdsa::signature::hazmat::PrehashVerifier;
 
         author
@@ -409,7 +416,7 @@ impl Secp256k1Signature {
             .verify_prehash(&prehash, &self.0)
             .map_err(|error| CryptoError::InvalidSignature {
                 error: error.to_string(),
-                type_name: T::type_name().to_string(),
+                type_name: type_name.to_string(),
             })
     }
 
@@ -68,7 +68,10 @@ mod in_mem {
     #[cfg(with_getrandom)]
     use crate::crypto::CryptoRng;
     use crate::{
-        crypto::{AccountPublicKey, AccountSecretKey, AccountSignature, CryptoHash, Signer},
+        crypto::{
+            AccountPublicKey, AccountSecretKey, AccountSignature, CryptoError, CryptoHash,
+            DerivationPath, HdSeed, SignatureScheme, Signer,
+        },
         identifiers::AccountOwner,
     };
 
@@ -90,9 +93,46 @@ mod in_mem {
             InMemorySigner(Arc::new(RwLock::new(InMemSignerInner::new())))
         }
 
+        /// Creates a new [`InMemorySigner`] whose keys are derived from the BIP-39 mnemonic
+        /// `phrase` and `passphrase`. No key is derived yet.
+        pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, CryptoError> {
+            let inner = InMemSignerInner {
+                hd: Some(HdState {
+                    seed: HdSeed::from_mnemonic(phrase, passphrase)?,
+                    next_index: 0,
+                }),
+                ..InMemSignerInner::default()
+            };
+            Ok(InMemorySigner(Arc::new(RwLock::new(inner))))
+        }
+
+        /// Returns whether the keys of this signer are derived from a mnemonic phrase.
+        pub fn is_hierarchical(&self) -> bool {
+            self.0.read().unwrap().hd.is_some()
+        }
+
+        /// Derives the Ed25519 key of the next account index from the mnemonic phrase.
+        pub fn derive_next_key(&mut self) -> Result<AccountPublicKey, CryptoError> {
+            let mut inner = self.0.write().unwrap();
+            let hd = inner.hd.as_mut().ok_or_else(|| {
+                CryptoError::InvalidMnemonic("the signer has no mnemonic phrase".to_string())
+            })?;
+            let secret = hd.derive_next(SignatureScheme::Ed25519)?;
+            let public = secret.public();
+            inner.keys.insert(AccountOwner::from(public), secret);
+            Ok(public)
+        }
+
         /// Generates a new key pair from Signer's RNG. Use with care.
+        ///
+        /// If the signer has a mnemonic phrase, the key is derived from it instead.
         #[cfg(with_getrandom)]
         pub fn generate_new(&mut self) -> AccountPublicKey {
+            if self.is_hierarchical() {
+                return self
+                    .derive_next_key()
+                    .expect("hardened Ed25519 derivations cannot fail");
+            }
             let mut inner = self.0.write().unwrap();
             let secret = AccountSecretKey::generate_from(&mut inner.rng_state.prng);
             if inner.rng_state.testing_seed.is_some() {
@@ -113,15 +153,48 @@ mod in_mem {
             let inner = self.0.read().unwrap();
             inner.keys()
         }
+
+        /// Computes the secret shared between the key of `owner` and `public_key`, without
+        /// revealing the secret key. Returns `None` if the key of `owner` is unknown or
+        /// doesn't support key agreement.
+        pub fn shared_secret(
+            &self,
+            owner: &AccountOwner,
+            public_key: &AccountPublicKey,
+        ) -> Option<[u8; 32]> {
+            let inner = self.0.read().unwrap();
+            inner.keys.get(owner)?.shared_secret(public_key)
+        }
     }
 
     /// In-memory signer.
     struct InMemSignerInner {
         keys: BTreeMap<AccountOwner, AccountSecretKey>,
+        /// The seed keys are derived from, if the signer was created from a mnemonic phrase.
+        hd: Option<HdState>,
         #[cfg(with_getrandom)]
         rng_state: RngState,
     }
 
+    #[derive(Debug, Serialize, Deserialize)]
+    struct HdState {
+        seed: HdSeed,
+        /// The account index of the next derived key.
+        next_index: u32,
+    }
+
+    impl HdState {
+        fn derive_next(
+            &mut self,
+            scheme: SignatureScheme,
+        ) -> Result<AccountSecretKey, CryptoError> {
+            let path = DerivationPath::for_account(scheme, self.next_index);
+            let secret = self.seed.derive(scheme, &path)?;
+            self.next_index += 1;
+            Ok(secret)
+        }
+    }
+
     #[cfg(with_getrandom)]
     struct RngState {
         prng: Box<dyn CryptoRng>,
@@ -148,6 +221,7 @@ mod in_mem {
         pub fn new(prng_seed: Option<u64>) -> Self {
             InMemSignerInner {
                 keys: BTreeMap::new(),
+                hd: None,
                 rng_state: RngState::new(prng_seed),
             }
         }
@@ -157,6 +231,7 @@ mod in_mem {
         pub fn new() -> Self {
             InMemSignerInner {
                 keys: BTreeMap::new(),
+                hd: None,
             }
         }
 
@@ -219,6 +294,7 @@ mod in_mem {
         {
             InMemorySigner(Arc::new(RwLock::new(InMemSignerInner {
                 keys: BTreeMap::from_iter(input),
+                hd: None,
                 #[cfg(with_getrandom)]
                 rng_state: RngState::new(None),
             })))
@@ -263,6 +339,8 @@ mod in_mem {
             #[derive(Serialize, Debug)]
             struct Inner<'a> {
                 keys: &'a Vec<(AccountOwner, Vec<u8>)>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                hd: Option<&'a HdState>,
                 #[cfg(with_getrandom)]
                 prng_seed: Option<u64>,
             }
@@ -272,6 +350,7 @@ mod in_mem {
 
             let inner = Inner {
                 keys: &self.keys(),
+                hd: self.hd.as_ref(),
                 #[cfg(with_getrandom)]
                 prng_seed,
             };
@@ -288,6 +367,8 @@ mod in_mem {
             #[derive(Deserialize)]
             struct Inner {
                 keys: Vec<(AccountOwner, Vec<u8>)>,
+                #[serde(default)]
+                hd: Option<HdState>,
                 #[cfg(with_getrandom)]
                 prng_seed: Option<u64>,
             }
@@ -306,6 +387,7 @@ mod in_mem {
 
             let signer = InMemSignerInner {
                 keys,
+                hd: inner.hd,
                 #[cfg(with_getrandom)]
                 rng_state: RngState::new(inner.prng_seed),
             };
@@ -0,0 +1,453 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Defines t-of-n threshold signatures (FROST) for chain owners.
+//!
+//! Any `min_signers` of the `max_signers` participants can jointly sign a value, without
+//! revealing their key shares to each other. The result is an ordinary Ed25519 signature
+//! for the group's public key, so a group can own a chain like any single Ed25519 owner.
+//!
+//! Signing is a ceremony in two rounds, coordinated by any party, e.g. one of the
+//! participants:
+//! 1. Each signing participant calls [`ThresholdKeyShare::commit`] and sends the commitment
+//!    to the coordinator, keeping the nonces.
+//! 2. The coordinator adds the commitments to a [`ThresholdSigningCeremony`] and sends its
+//!    [`ThresholdSigningRequest`] to the participants, who each answer with
+//!    [`ThresholdKeyShare::sign`]. The coordinator then adds the signature shares and
+//!    finishes the ceremony.
+
+use std::collections::BTreeMap;
+
+use frost_ed25519 as frost;
+use serde::{Deserialize, Serialize};
+
+use super::{CryptoError, CryptoHash, Ed25519PublicKey, Ed25519Signature};
+
+/// The index of a participant of a threshold group, from 1 to the number of participants.
+pub type ThresholdParticipant = u16;
+
+/// The share of a participant in a threshold signing key. This must be kept secret.
+#[derive(Serialize, Deserialize)]
+pub struct ThresholdKeyShare {
+    participant: ThresholdParticipant,
+    key_package: frost::keys::KeyPackage,
+}
+
+/// The public key of a threshold group, together with the public parts of all shares.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdGroupKey {
+    min_signers: u16,
+    public_key_package: frost::keys::PublicKeyPackage,
+}
+
+/// The secret nonces of a participant for one signing ceremony. They must never be reused.
+pub struct ThresholdNonces(frost::round1::SigningNonces);
+
+/// The commitment of a participant to its nonces for one signing ceremony.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdCommitment(frost::round1::SigningCommitments);
+
+/// What the participants of a signing ceremony are asked to sign: the value and the
+/// commitments of all the signers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdSigningRequest(frost::SigningPackage);
+
+/// The share of a participant in a threshold signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdSignatureShare(frost::round2::SignatureShare);
+
+/// The first round of a participant in a distributed key generation, where no party ever
+/// learns the whole secret key.
+pub struct ThresholdKeyGeneration {
+    participant: ThresholdParticipant,
+    secret_package: frost::keys::dkg::round1::SecretPackage,
+}
+
+/// The second and last round of a participant in a distributed key generation.
+pub struct ThresholdKeyGenerationRound2 {
+    participant: ThresholdParticipant,
+    round1_packages: BTreeMap<frost::Identifier, frost::keys::dkg::round1::Package>,
+    secret_package: frost::keys::dkg::round2::SecretPackage,
+}
+
+/// The message of a participant to all others in the first round of a key generation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdKeyGenerationBroadcast(frost::keys::dkg::round1::Package);
+
+/// The message of a participant to one other participant in the second round of a key
+/// generation. This must be sent confidentially.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdKeyGenerationMessage(frost::keys::dkg::round2::Package);
+
+/// Collects the commitments and then the signature shares of the participants to produce a
+/// threshold signature of a value.
+pub struct ThresholdSigningCeremony {
+    group_key: ThresholdGroupKey,
+    prehash: CryptoHash,
+    commitments: BTreeMap<frost::Identifier, frost::round1::SigningCommitments>,
+    request: Option<ThresholdSigningRequest>,
+    signature_shares: BTreeMap<frost::Identifier, frost::round2::SignatureShare>,
+}
+
+fn identifier(participant: ThresholdParticipant) -> Result<frost::Identifier, CryptoError> {
+    frost::Identifier::try_from(participant).map_err(threshold_error)
+}
+
+fn threshold_error(error: frost::Error) -> CryptoError {
+    CryptoError::ThresholdSignature(error.to_string())
+}
+
+impl ThresholdKeyShare {
+    /// Splits a new secret key into shares for `max_signers` participants, any `min_signers`
+    /// of which can sign. The secret key itself is discarded, but whoever runs this learns
+    /// all the shares: use [`ThresholdKeyGeneration`] if no party should.
+    #[cfg(with_getrandom)]
+    pub fn generate_with_dealer<R: super::CryptoRng>(
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<(ThresholdGroupKey, Vec<ThresholdKeyShare>), CryptoError> {
+        let (secret_shares, public_key_package) = frost::keys::generate_with_dealer(
+            max_signers,
+            min_signers,
+            frost::keys::IdentifierList::Default,
+            rng,
+        )
+        .map_err(threshold_error)?;
+        let shares = (1..=max_signers)
+            .map(|participant| {
+                let secret_share = secret_shares
+                    .get(&identifier(participant)?)
+                    .expect("the default identifiers should be used")
+                    .clone();
+                Ok(ThresholdKeyShare {
+                    participant,
+                    key_package: frost::keys::KeyPackage::try_from(secret_share)
+                        .map_err(threshold_error)?,
+                })
+            })
+            .collect::<Result<_, CryptoError>>()?;
+        let group_key = ThresholdGroupKey {
+            min_signers,
+            public_key_package,
+        };
+        Ok((group_key, shares))
+    }
+
+    /// Returns the participant this share belongs to.
+    pub fn participant(&self) -> ThresholdParticipant {
+        self.participant
+    }
+
+    /// Starts a signing ceremony: returns the nonces to keep until signing, and the
+    /// commitment to send to the coordinator.
+    #[cfg(with_getrandom)]
+    pub fn commit<R: super::CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> (ThresholdNonces, ThresholdCommitment) {
+        let (nonces, commitment) = frost::round1::commit(self.key_package.signing_share(), rng);
+        (ThresholdNonces(nonces), ThresholdCommitment(commitment))
+    }
+
+    /// Computes this participant's share of the signature requested by the coordinator.
+    ///
+    /// The nonces are consumed, so that they cannot be reused for another signature.
+    pub fn sign(
+        &self,
+        request: &ThresholdSigningRequest,
+        nonces: ThresholdNonces,
+    ) -> Result<ThresholdSignatureShare, CryptoError> {
+        let share = frost::round2::sign(&request.0, &nonces.0, &self.key_package)
+            .map_err(threshold_error)?;
+        Ok(ThresholdSignatureShare(share))
+    }
+}
+
+impl ThresholdGroupKey {
+    /// Returns the Ed25519 public key of the group, i.e. the key of the chain owner.
+    pub fn public_key(&self) -> Result<Ed25519PublicKey, CryptoError> {
+        let bytes = self
+            .public_key_package
+            .verifying_key()
+            .serialize()
+            .map_err(threshold_error)?;
+        Ed25519PublicKey::from_slice(&bytes)
+    }
+
+    /// Returns the number of participants needed to sign.
+    pub fn min_signers(&self) -> u16 {
+        self.min_signers
+    }
+}
+
+impl ThresholdKeyGeneration {
+    /// Starts generating keys for `max_signers` participants, any `min_signers` of which can
+    /// sign. The broadcast must be sent to all other participants.
+    #[cfg(with_getrandom)]
+    pub fn start<R: super::CryptoRng>(
+        participant: ThresholdParticipant,
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<(Self, ThresholdKeyGenerationBroadcast), CryptoError> {
+        let (secret_package, package) =
+            frost::keys::dkg::part1(identifier(participant)?, max_signers, min_signers, rng)
+                .map_err(threshold_error)?;
+        let generation = ThresholdKeyGeneration {
+            participant,
+            secret_package,
+        };
+        Ok((generation, ThresholdKeyGenerationBroadcast(package)))
+    }
+
+    /// Continues with the broadcasts received from all other participants. Each returned
+    /// message must be sent confidentially to its participant.
+    pub fn continue_with(
+        self,
+        broadcasts: BTreeMap<ThresholdParticipant, ThresholdKeyGenerationBroadcast>,
+    ) -> Result<
+        (
+            ThresholdKeyGenerationRound2,
+            BTreeMap<ThresholdParticipant, ThresholdKeyGenerationMessage>,
+        ),
+        CryptoError,
+    > {
+        let mut participants = BTreeMap::new();
+        let mut round1_packages = BTreeMap::new();
+        for (participant, broadcast) in broadcasts {
+            let id = identifier(participant)?;
+            participants.insert(id, participant);
+            round1_packages.insert(id, broadcast.0);
+        }
+        let (secret_package, packages) =
+            frost::keys::dkg::part2(self.secret_package, &round1_packages)
+                .map_err(threshold_error)?;
+        let messages = packages
+            .into_iter()
+            .map(|(id, package)| {
+                let participant = *participants
+                    .get(&id)
+                    .expect("messages are only addressed to known participants");
+                (participant, ThresholdKeyGenerationMessage(package))
+            })
+            .collect();
+        let round2 = ThresholdKeyGenerationRound2 {
+            participant: self.participant,
+            round1_packages,
+            secret_package,
+        };
+        Ok((round2, messages))
+    }
+}
+
+impl ThresholdKeyGenerationRound2 {
+    /// Finishes with the messages received from all other participants, returning this
+    /// participant's key share and the group's public key.
+    pub fn finish(
+        self,
+        messages: BTreeMap<ThresholdParticipant, ThresholdKeyGenerationMessage>,
+    ) -> Result<(ThresholdGroupKey, ThresholdKeyShare), CryptoError> {
+        let round2_packages = messages
+            .into_iter()
+            .map(|(participant, message)| Ok((identifier(participant)?, message.0)))
+            .collect::<Result<_, CryptoError>>()?;
+        let (key_package, public_key_package) = frost::keys::dkg::part3(
+            &self.secret_package,
+            &self.round1_packages,
+            &round2_packages,
+        )
+        .map_err(threshold_error)?;
+        let group_key = ThresholdGroupKey {
+            min_signers: *key_package.min_signers(),
+            public_key_package,
+        };
+        let share = ThresholdKeyShare {
+            participant: self.participant,
+            key_package,
+        };
+        Ok((group_key, share))
+    }
+}
+
+impl ThresholdSigningCeremony {
+    /// Starts a ceremony for the group to sign the given prehash, e.g. the hash of a block
+    /// proposal's content.
+    pub fn new(group_key: ThresholdGroupKey, prehash: CryptoHash) -> Self {
+        ThresholdSigningCeremony {
+            group_key,
+            prehash,
+            commitments: BTreeMap::new(),
+            request: None,
+            signature_shares: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the commitment of a participant willing to sign.
+    pub fn add_commitment(
+        &mut self,
+        participant: ThresholdParticipant,
+        commitment: ThresholdCommitment,
+    ) -> Result<(), CryptoError> {
+        if self.request.is_some() {
+            return Err(CryptoError::ThresholdSignature(
+                "the signers were already requested to sign".to_string(),
+            ));
+        }
+        self.commitments
+            .insert(identifier(participant)?, commitment.0);
+        Ok(())
+    }
+
+    /// Returns the request to send to all participants that committed. No more commitments
+    /// can be added afterwards.
+    pub fn request(&mut self) -> Result<ThresholdSigningRequest, CryptoError> {
+        if let Some(request) = &self.request {
+            return Ok(request.clone());
+        }
+        if self.commitments.len() < usize::from(self.group_key.min_signers) {
+            return Err(CryptoError::ThresholdSignature(format!(
+                "{} participants committed but {} are needed",
+                self.commitments.len(),
+                self.group_key.min_signers
+            )));
+        }
+        let package =
+            frost::SigningPackage::new(self.commitments.clone(), &self.prehash.as_bytes().0);
+        let request = ThresholdSigningRequest(package);
+        self.request = Some(request.clone());
+        Ok(request)
+    }
+
+    /// Adds the signature share of a participant that committed.
+    pub fn add_signature_share(
+        &mut self,
+        participant: ThresholdParticipant,
+        share: ThresholdSignatureShare,
+    ) -> Result<(), CryptoError> {
+        let id = identifier(participant)?;
+        if self.request.is_none() || !self.commitments.contains_key(&id) {
+            return Err(CryptoError::ThresholdSignature(format!(
+                "participant {participant} was not requested to sign"
+            )));
+        }
+        self.signature_shares.insert(id, share.0);
+        Ok(())
+    }
+
+    /// Combines the signature shares of all the requested signers into the group's signature.
+    pub fn finish(self) -> Result<Ed25519Signature, CryptoError> {
+        let Some(request) = self.request else {
+            return Err(CryptoError::ThresholdSignature(
+                "the signers were not requested to sign yet".to_string(),
+            ));
+        };
+        let signature = frost::aggregate(
+            &request.0,
+            &self.signature_shares,
+            &self.group_key.public_key_package,
+        )
+        .map_err(threshold_error)?;
+        Ed25519Signature::from_slice(&signature.serialize().map_err(threshold_error)?)
+    }
+}
+
+#[cfg(all(test, with_getrandom))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::SeedableRng as _;
+
+    use super::{ThresholdKeyGeneration, ThresholdKeyShare, ThresholdSigningCeremony};
+    use crate::crypto::{CryptoHash, Ed25519Signature, TestString};
+
+    fn sign_with(
+        ceremony: &mut ThresholdSigningCeremony,
+        shares: &[&ThresholdKeyShare],
+        rng: &mut rand::rngs::StdRng,
+    ) {
+        let nonces = shares
+            .iter()
+            .map(|share| {
+                let (nonces, commitment) = share.commit(rng);
+                ceremony
+                    .add_commitment(share.participant(), commitment)
+                    .unwrap();
+                nonces
+            })
+            .collect::<Vec<_>>();
+        let request = ceremony.request().unwrap();
+        for (share, nonces) in shares.iter().zip(nonces) {
+            let signature_share = share.sign(&request, nonces).unwrap();
+            ceremony
+                .add_signature_share(share.participant(), signature_share)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_threshold_signature() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let (group_key, shares) = ThresholdKeyShare::generate_with_dealer(2, 3, &mut rng).unwrap();
+        let public_key = group_key.public_key().unwrap();
+        let value = TestString("hello".into());
+
+        let mut ceremony =
+            ThresholdSigningCeremony::new(group_key.clone(), CryptoHash::new(&value));
+        sign_with(&mut ceremony, &[&shares[0], &shares[2]], &mut rng);
+        let signature: Ed25519Signature = ceremony.finish().unwrap();
+        signature.check(&value, public_key).unwrap();
+        let other_value = TestString("hellox".into());
+        assert!(signature.check(&other_value, public_key).is_err());
+
+        let mut ceremony = ThresholdSigningCeremony::new(group_key, CryptoHash::new(&value));
+        let (_nonces, commitment) = shares[1].commit(&mut rng);
+        ceremony.add_commitment(2, commitment).unwrap();
+        assert!(ceremony.request().is_err());
+    }
+
+    #[test]
+    fn test_distributed_key_generation() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let (generations, broadcasts): (Vec<_>, Vec<_>) = (1..=3)
+            .map(|participant| ThresholdKeyGeneration::start(participant, 2, 3, &mut rng).unwrap())
+            .unzip();
+        let mut messages = BTreeMap::<u16, BTreeMap<_, _>>::new();
+        let round2s = generations
+            .into_iter()
+            .map(|generation| {
+                let participant = generation.participant;
+                let others = (1..=3u16)
+                    .zip(&broadcasts)
+                    .filter(|(other, _)| *other != participant)
+                    .map(|(other, broadcast)| (other, broadcast.clone()))
+                    .collect();
+                let (round2, sent) = generation.continue_with(others).unwrap();
+                for (recipient, message) in sent {
+                    messages
+                        .entry(recipient)
+                        .or_default()
+                        .insert(participant, message);
+                }
+                round2
+            })
+            .collect::<Vec<_>>();
+        let (group_keys, shares): (Vec<_>, Vec<_>) = round2s
+            .into_iter()
+            .map(|round2| {
+                let received = messages.remove(&round2.participant).unwrap();
+                round2.finish(received).unwrap()
+            })
+            .unzip();
+        assert!(group_keys.iter().all(|key| *key == group_keys[0]));
+
+        let value = TestString("hello".into());
+        let mut ceremony =
+            ThresholdSigningCeremony::new(group_keys[0].clone(), CryptoHash::new(&value));
+        sign_with(&mut ceremony, &[&shares[1], &shares[2]], &mut rng);
+        let signature = ceremony.finish().unwrap();
+        signature
+            .check(&value, group_keys[0].public_key().unwrap())
+            .unwrap();
+    }
+}
@@ -142,17 +142,17 @@ pub struct TimeDelta(u64);
 
 impl TimeDelta {
     /// Returns the given number of microseconds as a [`TimeDelta`].
-    pub fn from_micros(micros: u64) -> Self {
+    pub const fn from_micros(micros: u64) -> Self {
         TimeDelta(micros)
     }
 
     /// Returns the given number of milliseconds as a [`TimeDelta`].
-    pub fn from_millis(millis: u64) -> Self {
+    pub const fn from_millis(millis: u64) -> Self {
         TimeDelta(millis.saturating_mul(1_000))
     }
 
     /// Returns the given number of seconds as a [`TimeDelta`].
-    pub fn from_secs(secs: u64) -> Self {
+    pub const fn from_secs(secs: u64) -> Self {
         TimeDelta(secs.saturating_mul(1_000_000))
     }
 
@@ -970,6 +970,108 @@ impl ApplicationPermissions {
     }
 }
 
+/// Rules on the incoming message bundles that the owners of a chain include in their blocks.
+///
+/// Validators don't enforce these: the chain owners' clients leave the bundles that don't
+/// satisfy them in the inbox, so that public chains can ignore unwanted messages.
+#[derive(Default, Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct InboxPolicy {
+    /// If this is `Some`, only bundles from the specified chains are accepted.
+    #[debug(skip_if = Option::is_none)]
+    pub allowed_senders: Option<Vec<ChainId>>,
+    /// If this is `Some`, only messages from the system and from the specified applications
+    /// are accepted.
+    #[debug(skip_if = Option::is_none)]
+    pub allowed_applications: Option<Vec<ApplicationId>>,
+    /// The native tokens that a bundle must credit to the chain or its owners to be accepted.
+    pub min_attached_value: Amount,
+    /// If this is `Some`, at most this many bundles are received in one block.
+    #[debug(skip_if = Option::is_none)]
+    pub max_bundles_per_block: Option<u32>,
+    /// If this is `Some`, the bundles received in one block have at most this many bytes in
+    /// total. A larger bundle stays in the inbox until the limit is raised.
+    #[debug(skip_if = Option::is_none)]
+    pub max_bundle_bytes_per_block: Option<u64>,
+}
+
+impl InboxPolicy {
+    /// Returns whether all bundles are accepted.
+    pub fn accepts_all(&self) -> bool {
+        *self == InboxPolicy::default()
+    }
+
+    /// Returns whether bundles from the given chain are accepted.
+    pub fn allows_sender(&self, chain_id: &ChainId) -> bool {
+        self.allowed_senders
+            .as_ref()
+            .is_none_or(|chain_ids| chain_ids.contains(chain_id))
+    }
+
+    /// Returns whether messages from the given application are accepted.
+    pub fn allows_application(&self, app_id: &GenericApplicationId) -> bool {
+        match (app_id, &self.allowed_applications) {
+            (_, None) | (GenericApplicationId::System, Some(_)) => true,
+            (GenericApplicationId::User(app_id), Some(app_ids)) => app_ids.contains(app_id),
+        }
+    }
+}
+
+/// Limits on the block proposals of a chain, set by its owners.
+///
+/// Validators reject proposals that exceed them, in addition to the committee's resource
+/// control policy, so that they can bound the worst-case processing of each block.
+#[derive(Default, Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockLimits {
+    /// If this is `Some`, the serialized size of a proposed block is at most this many bytes.
+    #[debug(skip_if = Option::is_none)]
+    pub max_block_bytes: Option<u64>,
+    /// If this is `Some`, a block has at most this many operations.
+    #[debug(skip_if = Option::is_none)]
+    pub max_operations_per_block: Option<u32>,
+    /// If this is `Some`, a block receives at most this many incoming bundles.
+    #[debug(skip_if = Option::is_none)]
+    pub max_incoming_bundles_per_block: Option<u32>,
+}
+
+impl BlockLimits {
+    /// Returns whether blocks are only limited by the committee's policy.
+    pub fn is_unlimited(&self) -> bool {
+        *self == BlockLimits::default()
+    }
+}
+
+/// How much of a chain's history its owners ask validators to keep.
+///
+/// This is a preference: validators may honor it when pruning block bodies from their
+/// storage, but archive nodes always keep the full history. The headers and certificates of
+/// all blocks are always kept, and so are the bodies of blocks whose messages are in flight.
+#[derive(Default, Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    /// The bodies of all blocks are kept.
+    #[default]
+    FullHistory,
+    /// Only the bodies of this many latest blocks are kept.
+    LastBlocks(u64),
+    /// Only the headers and certificates of the blocks are kept.
+    HeadersOnly,
+}
+
+impl RetentionPolicy {
+    /// Returns how many of the latest block bodies are kept, or `None` if all of them are.
+    pub fn block_body_retention(&self) -> Option<u64> {
+        match self {
+            RetentionPolicy::FullHistory => None,
+            RetentionPolicy::LastBlocks(count) => Some(*count),
+            RetentionPolicy::HeadersOnly => Some(0),
+        }
+    }
+
+    /// Returns whether the bodies of all blocks are kept.
+    pub fn is_full_history(&self) -> bool {
+        *self == RetentionPolicy::FullHistory
+    }
+}
+
 /// A record of a single oracle response.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum OracleResponse {
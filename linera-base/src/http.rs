@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use crate::hex_debug;
 
 /// An HTTP request.
-#[derive(Clone, Debug, Eq, PartialEq, WitLoad, WitStore, WitType)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, WitLoad, WitStore, WitType)]
 #[witty(name = "http-request")]
 pub struct Request {
     /// The [`Method`] used for the HTTP request.
@@ -24,6 +24,7 @@ pub struct Request {
 
     /// The body of the request.
     #[debug(with = "hex_debug")]
+    #[serde(with = "serde_bytes")]
     pub body: Vec<u8>,
 }
 
@@ -70,7 +71,7 @@ impl Request {
 }
 
 /// The method used in an HTTP request.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, WitLoad, WitStore, WitType)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, WitLoad, WitStore, WitType)]
 #[witty(name = "http-method")]
 pub enum Method {
     /// A GET request.
@@ -17,6 +17,7 @@ use tokio::signal::unix;
 #[cfg(not(target_arch = "wasm32"))]
 use {::tracing::debug, tokio_util::sync::CancellationToken};
 pub mod abi;
+pub mod appended;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod command;
 pub mod crypto;
@@ -2,17 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This module provides unified handling for tracing subscribers within Linera binaries.
+//!
+//! The binaries use the same names for the fields of their events, so that structured logs
+//! can be queried the same way across them: `chain_id` for the chain concerned,
+//! `message_type` for the kind of request or message handled, and `latency_ms` for how long
+//! handling it took, in milliseconds.
 
 use std::{
-    env,
+    env, fmt as std_fmt,
     fs::{File, OpenOptions},
     path::Path,
-    sync::Arc,
+    str::FromStr,
+    sync::{Arc, OnceLock},
 };
 
 use is_terminal::IsTerminal as _;
 use tracing::Subscriber;
 use tracing_subscriber::{
+    filter::{EnvFilter, ParseError},
     fmt::{
         self,
         format::{FmtSpan, Format, Full},
@@ -21,9 +28,69 @@ use tracing_subscriber::{
     },
     layer::{Layer, SubscriberExt as _},
     registry::LookupSpan,
+    reload,
     util::SubscriberInitExt,
+    Registry,
 };
 
+/// The handle to change the filter of the subscriber installed by [`init`].
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// An error when changing the log filter at runtime.
+#[derive(Debug, thiserror::Error)]
+pub enum LogFilterError {
+    /// The filter directives are invalid.
+    #[error("invalid log filter: {0}")]
+    Parse(#[from] ParseError),
+    /// Tracing was not initialized with [`init`].
+    #[error("tracing was not initialized with `linera_base::tracing::init`")]
+    NotInitialized,
+    /// The subscriber was dropped.
+    #[error("failed to change the log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+/// The format of the log output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One human-readable line per event.
+    #[default]
+    Plain,
+    /// Human-readable events over several lines.
+    Pretty,
+    /// One JSON object per event, with the event's fields at the top level, for ingestion
+    /// into log aggregators such as Loki or Elasticsearch.
+    Json,
+}
+
+/// An error when parsing a [`LogFormat`].
+#[derive(Debug, thiserror::Error)]
+#[error("invalid log format `{0}`: valid values are `plain`, `pretty` and `json`")]
+pub struct InvalidLogFormat(String);
+
+impl FromStr for LogFormat {
+    type Err = InvalidLogFormat;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "plain" => Ok(LogFormat::Plain),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(InvalidLogFormat(format.to_owned())),
+        }
+    }
+}
+
+impl std_fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std_fmt::Formatter<'_>) -> std_fmt::Result {
+        f.write_str(match self {
+            LogFormat::Plain => "plain",
+            LogFormat::Pretty => "pretty",
+            LogFormat::Json => "json",
+        })
+    }
+}
+
 /// Initializes tracing in a standard way.
 ///
 /// The environment variables `RUST_LOG`, `RUST_LOG_SPAN_EVENTS`, and `RUST_LOG_FORMAT`
@@ -34,22 +101,35 @@ use tracing_subscriber::{
 /// store log files. If it is set, a file named `log_name` with the `log` extension is
 /// created in the directory.
 pub fn init(log_name: &str) {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
+    let format = std::env::var("RUST_LOG_FORMAT")
+        .ok()
+        .map(|format| {
+            format
+                .parse()
+                .unwrap_or_else(|error| panic!("Invalid RUST_LOG_FORMAT: {error}"))
+        })
+        .unwrap_or_default();
+    init_with_format(log_name, format);
+}
+
+/// Initializes tracing like [`init`], with the given output format instead of the one in
+/// `RUST_LOG_FORMAT`.
+pub fn init_with_format(log_name: &str, format: LogFormat) {
+    let env_filter = EnvFilter::builder()
         .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
         .from_env_lossy();
+    let (env_filter, handle) = reload::Layer::new(env_filter);
 
     let span_events = std::env::var("RUST_LOG_SPAN_EVENTS")
         .ok()
         .map(|s| fmt_span_from_str(&s))
         .unwrap_or(FmtSpan::NONE);
 
-    let format = std::env::var("RUST_LOG_FORMAT").ok();
-
     let color_output =
         !std::env::var("NO_COLOR").is_ok_and(|x| !x.is_empty()) && std::io::stderr().is_terminal();
 
     let stderr_layer = prepare_formatted_layer(
-        format.as_deref(),
+        format,
         fmt::layer()
             .with_span_events(span_events.clone())
             .with_writer(std::io::stderr)
@@ -58,7 +138,7 @@ pub fn init(log_name: &str) {
 
     let maybe_log_file_layer = open_log_file(log_name).map(|file_writer| {
         prepare_formatted_layer(
-            format.as_deref(),
+            format,
             fmt::layer()
                 .with_span_events(span_events)
                 .with_writer(Arc::new(file_writer))
@@ -71,6 +151,23 @@ pub fn init(log_name: &str) {
         .with(maybe_log_file_layer)
         .with(stderr_layer)
         .init();
+    let _ = LOG_FILTER.set(handle);
+}
+
+/// Returns the filter directives of the subscriber installed by [`init`], e.g.
+/// `info,linera_core=debug`.
+pub fn log_filter() -> Result<String, LogFilterError> {
+    let handle = LOG_FILTER.get().ok_or(LogFilterError::NotInitialized)?;
+    Ok(handle.with_current(|filter| filter.to_string())?)
+}
+
+/// Replaces the filter directives of the subscriber installed by [`init`], using the same
+/// syntax as `RUST_LOG`, without restarting the process.
+pub fn set_log_filter(directives: &str) -> Result<(), LogFilterError> {
+    let filter = EnvFilter::try_new(directives)?;
+    let handle = LOG_FILTER.get().ok_or(LogFilterError::NotInitialized)?;
+    handle.reload(filter)?;
+    Ok(())
 }
 
 /// Opens a log file for writing.
@@ -93,11 +190,11 @@ fn open_log_file(log_name: &str) -> Option<File> {
     )
 }
 
-/// Applies a requested `formatting` to the log output of the provided `layer`.
+/// Applies a requested `format` to the log output of the provided `layer`.
 ///
 /// Returns a boxed [`Layer`] with the formatting applied to the original `layer`.
 fn prepare_formatted_layer<S, N, W, T>(
-    formatting: Option<&str>,
+    format: LogFormat,
     layer: fmt::Layer<S, N, Format<Full, T>, W>,
 ) -> Box<dyn Layer<S> + Send + Sync>
 where
@@ -106,13 +203,15 @@ where
     W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
     T: FormatTime + Send + Sync + 'static,
 {
-    match formatting.unwrap_or("plain") {
-        "json" => layer.json().boxed(),
-        "pretty" => layer.pretty().boxed(),
-        "plain" => layer.boxed(),
-        format => {
-            panic!("Invalid RUST_LOG_FORMAT: `{format}`.  Valid values are `json` or `pretty`.")
-        }
+    match format {
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Plain => layer.boxed(),
     }
 }
 
@@ -13,7 +13,7 @@ use linera_base::{
     crypto::{BcsHashable, CryptoHash},
     data_types::{Blob, BlockHeight, Epoch, Event, OracleResponse, Timestamp},
     hashed::Hashed,
-    identifiers::{AccountOwner, BlobId, BlobType, ChainId, MessageId},
+    identifiers::{AccountOwner, ApplicationId, BlobId, BlobType, ChainId, MessageId, StreamId},
 };
 use linera_execution::{BlobState, Operation, OutgoingMessage};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
@@ -560,6 +560,46 @@ impl Block {
         required_blob_ids
     }
 
+    /// Returns the IDs of the streams that this block emitted events to.
+    pub fn event_stream_ids(&self) -> BTreeSet<StreamId> {
+        self.body
+            .events
+            .iter()
+            .flatten()
+            .map(|event| event.stream_id.clone())
+            .collect()
+    }
+
+    /// Returns the IDs of the user applications with operations, incoming or outgoing
+    /// messages, or events in this block.
+    pub fn application_ids(&self) -> BTreeSet<ApplicationId> {
+        let operations = self.body.operations.iter().map(Operation::application_id);
+        let incoming_messages = self
+            .body
+            .incoming_bundles
+            .iter()
+            .flat_map(|incoming_bundle| &incoming_bundle.bundle.messages)
+            .map(|posted_message| posted_message.message.application_id());
+        let outgoing_messages = self
+            .body
+            .messages
+            .iter()
+            .flatten()
+            .map(|outgoing_message| outgoing_message.message.application_id());
+        let events = self
+            .body
+            .events
+            .iter()
+            .flatten()
+            .map(|event| event.stream_id.application_id);
+        operations
+            .chain(incoming_messages)
+            .chain(outgoing_messages)
+            .chain(events)
+            .filter_map(|application_id| application_id.user_application_id().copied())
+            .collect()
+    }
+
     /// Returns reference to the outgoing messages in the block.
     pub fn messages(&self) -> &Vec<Vec<OutgoingMessage>> {
         &self.body.messages
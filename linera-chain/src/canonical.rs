@@ -0,0 +1,219 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stable JSON and CBOR encodings of certificates, blocks and events, for consumers that
+//! don't want to decode BCS.
+//!
+//! Both encodings are derived from the human-readable serialization of the types:
+//!
+//! * Structs are objects with one entry per field, named as in the Rust code.
+//! * Enum values are objects with a single entry, named after the variant, whose value is
+//!   the variant's content. Variants without content are strings.
+//! * Options are `null` or their value. Sequences, tuples and fixed-size arrays are arrays.
+//! * Hashes, public keys, signatures, chain and application IDs are hexadecimal strings,
+//!   amounts are decimal strings, and timestamps and block heights are integers.
+//! * Byte vectors, e.g. the serialized operations and messages of user applications, are
+//!   arrays of integers.
+//!
+//! The canonical JSON encoding has no whitespace and sorts the entries of every object by
+//! key, so that equal values are always encoded to the same bytes. The canonical CBOR
+//! encoding ([RFC 8949](https://www.rfc-editor.org/rfc/rfc8949)) represents the same value,
+//! with the map keys in the deterministic order of section 4.2.1: shorter keys first, then
+//! bytewise.
+//!
+//! The fields and variants of every type are documented by the format registry that is
+//! generated from the types in `linera-rpc/tests/snapshots/format__format.yaml.snap`.
+//! [`CANONICAL_FORMAT_VERSION`] is increased whenever the encodings change in a way that is
+//! not backwards compatible.
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// The version of the canonical encodings.
+pub const CANONICAL_FORMAT_VERSION: u32 = 1;
+
+/// An error when encoding or decoding a value canonically.
+#[derive(Debug, Error)]
+pub enum CanonicalEncodingError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("CBOR serialization error: {0}")]
+    CborSerialization(#[from] ciborium::ser::Error<std::io::Error>),
+    #[error("CBOR deserialization error: {0}")]
+    CborDeserialization(#[from] ciborium::de::Error<std::io::Error>),
+    #[error("Unsupported CBOR value: {0}")]
+    UnsupportedCborValue(String),
+}
+
+/// Returns the canonical JSON encoding of the value.
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String, CanonicalEncodingError> {
+    // Without the `preserve_order` feature, the objects of a `serde_json::Value` are sorted
+    // by key.
+    let value = serde_json::to_value(value)?;
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Decodes a value from JSON. The entries of objects may be in any order.
+pub fn from_canonical_json<T: DeserializeOwned>(json: &str) -> Result<T, CanonicalEncodingError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+/// Returns the canonical CBOR encoding of the value.
+pub fn to_canonical_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, CanonicalEncodingError> {
+    let value = json_to_cbor(serde_json::to_value(value)?);
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decodes a value from CBOR. The entries of maps may be in any order.
+pub fn from_canonical_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CanonicalEncodingError> {
+    let value = ciborium::from_reader::<ciborium::Value, _>(bytes)?;
+    Ok(serde_json::from_value(cbor_to_json(value)?)?)
+}
+
+fn json_to_cbor(value: serde_json::Value) -> ciborium::Value {
+    use ciborium::Value as Cbor;
+    use serde_json::Value as Json;
+
+    match value {
+        Json::Null => Cbor::Null,
+        Json::Bool(value) => Cbor::Bool(value),
+        Json::Number(number) => {
+            if let Some(number) = number.as_u64() {
+                Cbor::Integer(number.into())
+            } else if let Some(number) = number.as_i64() {
+                Cbor::Integer(number.into())
+            } else {
+                Cbor::Float(number.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Json::String(string) => Cbor::Text(string),
+        Json::Array(values) => Cbor::Array(values.into_iter().map(json_to_cbor).collect()),
+        Json::Object(object) => {
+            let mut entries = object.into_iter().collect::<Vec<_>>();
+            // The encoded length of a text string only grows with the string's length.
+            entries.sort_by(|(key1, _), (key2, _)| {
+                (key1.len(), key1.as_bytes()).cmp(&(key2.len(), key2.as_bytes()))
+            });
+            Cbor::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (Cbor::Text(key), json_to_cbor(value)))
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Result<serde_json::Value, CanonicalEncodingError> {
+    use ciborium::Value as Cbor;
+    use serde_json::Value as Json;
+
+    Ok(match value {
+        Cbor::Null => Json::Null,
+        Cbor::Bool(value) => Json::Bool(value),
+        Cbor::Integer(integer) => {
+            let integer = i128::from(integer);
+            if let Ok(integer) = u64::try_from(integer) {
+                Json::from(integer)
+            } else if let Ok(integer) = i64::try_from(integer) {
+                Json::from(integer)
+            } else {
+                return Err(CanonicalEncodingError::UnsupportedCborValue(format!(
+                    "integer {integer}"
+                )));
+            }
+        }
+        Cbor::Float(float) => serde_json::Number::from_f64(float)
+            .map(Json::Number)
+            .ok_or_else(|| {
+                CanonicalEncodingError::UnsupportedCborValue(format!("float {float}"))
+            })?,
+        Cbor::Text(string) => Json::String(string),
+        Cbor::Array(values) => Json::Array(
+            values
+                .into_iter()
+                .map(cbor_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Cbor::Map(entries) => Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    Cbor::Text(key) => Ok((key, cbor_to_json(value)?)),
+                    key => Err(CanonicalEncodingError::UnsupportedCborValue(format!(
+                        "map key {key:?}"
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        value => {
+            return Err(CanonicalEncodingError::UnsupportedCborValue(format!(
+                "{value:?}"
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        crypto::CryptoHash,
+        data_types::Event,
+        identifiers::{ApplicationId, GenericApplicationId, StreamId, StreamName},
+    };
+
+    use super::*;
+
+    fn event() -> Event {
+        Event {
+            stream_id: StreamId {
+                application_id: GenericApplicationId::User(ApplicationId::new(
+                    CryptoHash::test_hash("application"),
+                )),
+                stream_name: StreamName(b"stream".to_vec()),
+            },
+            index: 3,
+            value: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let event = event();
+        let json = to_canonical_json(&event).unwrap();
+        assert!(!json.contains(char::is_whitespace));
+        let index = json.find("\"index\"").unwrap();
+        let stream_id = json.find("\"stream_id\"").unwrap();
+        let value = json.find("\"value\"").unwrap();
+        assert!(index < stream_id && stream_id < value);
+        assert_eq!(from_canonical_json::<Event>(&json).unwrap(), event);
+
+        // Re-encoding JSON with its keys in another order gives the same bytes.
+        let reordered = serde_json::to_string(&serde_json::json!({
+            "value": event.value,
+            "stream_id": event.stream_id,
+            "index": event.index,
+        }))
+        .unwrap();
+        let decoded = from_canonical_json::<Event>(&reordered).unwrap();
+        assert_eq!(to_canonical_json(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_canonical_cbor() {
+        let event = event();
+        let cbor = to_canonical_cbor(&event).unwrap();
+        assert_eq!(from_canonical_cbor::<Event>(&cbor).unwrap(), event);
+
+        let value = ciborium::from_reader::<ciborium::Value, _>(cbor.as_slice()).unwrap();
+        let keys = value
+            .as_map()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.as_text().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["index", "value", "stream_id"]);
+    }
+}
@@ -3,13 +3,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
+    appended,
     crypto::{ValidatorPublicKey, ValidatorSignature},
     data_types::{Epoch, Round},
     identifiers::{ChainId, MessageId},
 };
-use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use super::{generic::GenericCertificate, Certificate};
+use super::{generic::GenericCertificate, Certificate, CertificateAppended};
 use crate::{
     block::{Block, ConfirmedBlock, ConversionError},
     data_types::MessageBundle,
@@ -66,11 +67,21 @@ impl Serialize for GenericCertificate<ConfirmedBlock> {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("ConfirmedBlockCertificate", 3)?;
-        state.serialize_field("value", self.inner())?;
-        state.serialize_field("round", &self.round)?;
-        state.serialize_field("signatures", self.signatures())?;
-        state.end()
+        #[derive(Serialize)]
+        #[serde(rename = "ConfirmedBlockCertificate")]
+        struct Fields<'a> {
+            value: &'a ConfirmedBlock,
+            round: Round,
+            signatures: &'a [(ValidatorPublicKey, ValidatorSignature)],
+        }
+
+        let fields = Fields {
+            value: self.inner(),
+            round: self.round,
+            signatures: self.signatures(),
+        };
+        let appended = CertificateAppended::new(self.aggregate_signature());
+        appended::serialize(&fields, &appended, serializer)
     }
 }
 
@@ -87,11 +98,17 @@ impl<'de> Deserialize<'de> for GenericCertificate<ConfirmedBlock> {
             signatures: Vec<(ValidatorPublicKey, ValidatorSignature)>,
         }
 
-        let helper = Helper::deserialize(deserializer)?;
-        if !crate::data_types::is_strictly_ordered(&helper.signatures) {
+        let (helper, appended): (Helper, _) = appended::deserialize(deserializer)?;
+        let aggregate_signature = CertificateAppended::into_aggregate_signature(appended);
+        if !crate::data_types::is_strictly_ordered(&helper.signatures)
+            || aggregate_signature
+                .as_ref()
+                .is_some_and(|aggregate| !aggregate.has_ordered_signers())
+        {
             Err(serde::de::Error::custom("Vector is not strictly sorted"))
         } else {
-            Ok(Self::new(helper.value, helper.round, helper.signatures))
+            Ok(Self::new(helper.value, helper.round, helper.signatures)
+                .with_aggregate_signature(aggregate_signature))
         }
     }
 }
@@ -10,7 +10,10 @@ use linera_base::{
 use linera_execution::committee::Committee;
 
 use super::CertificateValue;
-use crate::{data_types::LiteValue, ChainError};
+use crate::{
+    data_types::{AggregateSignature, LiteValue},
+    ChainError,
+};
 
 /// Generic type representing a certificate for `value` of type `T`.
 #[derive(Debug)]
@@ -18,6 +21,9 @@ pub struct GenericCertificate<T: CertificateValue> {
     value: T,
     pub round: Round,
     signatures: Vec<(ValidatorPublicKey, ValidatorSignature)>,
+    /// The BLS12-381 signatures of the other signers, aggregated into one. It is boxed so
+    /// that certificates without one stay small.
+    aggregate_signature: Option<Box<AggregateSignature>>,
 }
 
 impl<T: CertificateValue> GenericCertificate<T> {
@@ -32,9 +38,16 @@ impl<T: CertificateValue> GenericCertificate<T> {
             value,
             round,
             signatures,
+            aggregate_signature: None,
         }
     }
 
+    /// Returns this certificate with the given aggregated signature of other validators.
+    pub fn with_aggregate_signature(mut self, aggregate: Option<AggregateSignature>) -> Self {
+        self.aggregate_signature = aggregate.map(Box::new);
+        self
+    }
+
     /// Returns a reference to the `Hashed` value contained in this certificate.
     pub fn value(&self) -> &T {
         &self.value
@@ -68,6 +81,23 @@ impl<T: CertificateValue> GenericCertificate<T> {
         &self.signatures
     }
 
+    /// Returns the aggregated BLS12-381 signature, if any.
+    pub fn aggregate_signature(&self) -> Option<&AggregateSignature> {
+        self.aggregate_signature.as_deref()
+    }
+
+    /// Returns all the validators that signed, individually or in the aggregate signature.
+    pub fn signers(&self) -> impl Iterator<Item = &ValidatorPublicKey> {
+        let aggregate_signers = self
+            .aggregate_signature
+            .iter()
+            .flat_map(|aggregate| &aggregate.signers);
+        self.signatures
+            .iter()
+            .map(|(public_key, _)| public_key)
+            .chain(aggregate_signers)
+    }
+
     #[cfg(with_testing)]
     pub fn signatures_mut(&mut self) -> &mut Vec<(ValidatorPublicKey, ValidatorSignature)> {
         &mut self.signatures
@@ -92,6 +122,10 @@ impl<T: CertificateValue> GenericCertificate<T> {
         self.signatures
             .binary_search_by(|(name, _)| name.cmp(validator_name))
             .is_ok()
+            || self
+                .aggregate_signature
+                .as_ref()
+                .is_some_and(|aggregate| aggregate.signers.binary_search(validator_name).is_ok())
     }
 
     /// Verifies the certificate.
@@ -104,6 +138,7 @@ impl<T: CertificateValue> GenericCertificate<T> {
             T::KIND,
             self.round,
             &self.signatures,
+            self.aggregate_signature.as_deref(),
             committee,
         )?;
         Ok(())
@@ -117,6 +152,7 @@ impl<T: CertificateValue> GenericCertificate<T> {
             value: LiteValue::new(&self.value),
             round: self.round,
             signatures: std::borrow::Cow::Borrowed(&self.signatures),
+            aggregate_signature: self.aggregate_signature.as_deref().cloned(),
         }
     }
 }
@@ -127,6 +163,7 @@ impl<T: CertificateValue> Clone for GenericCertificate<T> {
             value: self.value.clone(),
             round: self.round,
             signatures: self.signatures.clone(),
+            aggregate_signature: self.aggregate_signature.clone(),
         }
     }
 }
@@ -139,5 +176,6 @@ impl<T: CertificateValue + Eq + PartialEq> PartialEq for GenericCertificate<T> {
         self.hash() == other.hash()
             && self.round == other.round
             && self.signatures == other.signatures
+            && self.aggregate_signature == other.aggregate_signature
     }
 }
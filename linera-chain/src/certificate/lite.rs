@@ -5,20 +5,21 @@
 use std::borrow::Cow;
 
 use linera_base::{
+    appended,
     crypto::{ValidatorPublicKey, ValidatorSignature},
     data_types::Round,
 };
-use linera_execution::committee::Committee;
-use serde::{Deserialize, Serialize};
+use linera_execution::committee::{CertificateSignatureScheme, Committee};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{CertificateValue, GenericCertificate};
+use super::{CertificateAppended, CertificateValue, GenericCertificate};
 use crate::{
-    data_types::{check_signatures, LiteValue, LiteVote},
+    data_types::{check_signatures, AggregateSignature, LiteValue, LiteVote},
     ChainError,
 };
 
 /// A certified statement from the committee, without the value.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct LiteCertificate<'a> {
     /// Hash and chain ID of the certified value (used as key for storage).
@@ -27,6 +28,48 @@ pub struct LiteCertificate<'a> {
     pub round: Round,
     /// Signatures on the value.
     pub signatures: Cow<'a, [(ValidatorPublicKey, ValidatorSignature)]>,
+    /// The BLS12-381 signatures of the other signers, aggregated into one.
+    pub aggregate_signature: Option<AggregateSignature>,
+}
+
+impl Serialize for LiteCertificate<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(rename = "LiteCertificate")]
+        struct Fields<'a> {
+            value: &'a LiteValue,
+            round: Round,
+            signatures: &'a [(ValidatorPublicKey, ValidatorSignature)],
+        }
+
+        let fields = Fields {
+            value: &self.value,
+            round: self.round,
+            signatures: &self.signatures,
+        };
+        let appended = CertificateAppended::new(self.aggregate_signature.as_ref());
+        appended::serialize(&fields, &appended, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LiteCertificate<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "LiteCertificate")]
+        struct Fields {
+            value: LiteValue,
+            round: Round,
+            signatures: Vec<(ValidatorPublicKey, ValidatorSignature)>,
+        }
+
+        let (fields, appended): (Fields, _) = appended::deserialize(deserializer)?;
+        Ok(LiteCertificate {
+            value: fields.value,
+            round: fields.round,
+            signatures: Cow::Owned(fields.signatures),
+            aggregate_signature: CertificateAppended::into_aggregate_signature(appended),
+        })
+    }
 }
 
 impl LiteCertificate<'_> {
@@ -42,27 +85,46 @@ impl LiteCertificate<'_> {
             value,
             round,
             signatures,
+            aggregate_signature: None,
         }
     }
 
     /// Creates a [`LiteCertificate`] from a list of votes, without cryptographically checking the
-    /// signatures. Returns `None` if the votes are empty or don't have matching values and rounds.
-    pub fn try_from_votes(votes: impl IntoIterator<Item = LiteVote>) -> Option<Self> {
-        let mut votes = votes.into_iter();
-        let LiteVote {
-            value,
-            round,
-            public_key,
-            signature,
-        } = votes.next()?;
-        let mut signatures = vec![(public_key, signature)];
-        for vote in votes {
-            if vote.value.value_hash != value.value_hash || vote.round != round {
-                return None;
-            }
-            signatures.push((vote.public_key, vote.signature));
+    /// individual signatures. Returns `None` if the votes are empty or don't have matching values
+    /// and rounds.
+    ///
+    /// If the committee aggregates signatures, the valid BLS12-381 signatures are aggregated
+    /// into one, and only the votes without one keep their individual signature.
+    pub fn try_from_votes(
+        votes: impl IntoIterator<Item = LiteVote>,
+        committee: &Committee,
+    ) -> Option<Self> {
+        let votes = votes.into_iter().collect::<Vec<_>>();
+        let LiteVote { value, round, .. } = votes.first()?;
+        if votes
+            .iter()
+            .any(|vote| vote.value.value_hash != value.value_hash || vote.round != *round)
+        {
+            return None;
         }
-        Some(LiteCertificate::new(value, round, signatures))
+        let aggregate_signature = match committee.certificate_signature_scheme() {
+            CertificateSignatureScheme::Secp256k1 => None,
+            CertificateSignatureScheme::AggregatedBls12381 => {
+                AggregateSignature::from_votes(&votes, committee)
+            }
+        };
+        let signatures = votes
+            .iter()
+            .filter(|vote| {
+                aggregate_signature.as_ref().is_none_or(|aggregate| {
+                    aggregate.signers.binary_search(&vote.public_key).is_err()
+                })
+            })
+            .map(|vote| (vote.public_key, vote.signature))
+            .collect();
+        let mut certificate = LiteCertificate::new(value.clone(), *round, signatures);
+        certificate.aggregate_signature = aggregate_signature;
+        Some(certificate)
     }
 
     /// Verifies the certificate.
@@ -72,6 +134,7 @@ impl LiteCertificate<'_> {
             self.value.kind,
             self.round,
             &self.signatures,
+            self.aggregate_signature.as_ref(),
             committee,
         )?;
         Ok(&self.value)
@@ -92,11 +155,10 @@ impl LiteCertificate<'_> {
         {
             return None;
         }
-        Some(GenericCertificate::new(
-            value,
-            self.round,
-            self.signatures.into_owned(),
-        ))
+        Some(
+            GenericCertificate::new(value, self.round, self.signatures.into_owned())
+                .with_aggregate_signature(self.aggregate_signature),
+        )
     }
 
     /// Returns a [`LiteCertificate`] that owns the list of signatures.
@@ -105,6 +167,7 @@ impl LiteCertificate<'_> {
             value: self.value.clone(),
             round: self.round,
             signatures: Cow::Owned(self.signatures.clone().into_owned()),
+            aggregate_signature: self.aggregate_signature.clone(),
         }
     }
 }
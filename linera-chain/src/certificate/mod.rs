@@ -8,7 +8,7 @@ mod lite;
 mod timeout;
 mod validated;
 
-use std::collections::BTreeSet;
+use std::{borrow::Cow, collections::BTreeSet};
 
 pub use generic::GenericCertificate;
 use linera_base::{
@@ -19,7 +19,10 @@ use linera_base::{
 pub use lite::LiteCertificate;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{ConfirmedBlock, Timeout, ValidatedBlock};
+use crate::{
+    data_types::AggregateSignature,
+    types::{ConfirmedBlock, Timeout, ValidatedBlock},
+};
 
 /// Certificate for a [`ValidatedBlock`] instance.
 /// A validated block certificate means the block is valid (but not necessarily finalized yet).
@@ -36,6 +39,32 @@ pub type ConfirmedBlockCertificate = GenericCertificate<ConfirmedBlock>;
 /// A timeout certificate means that the next consensus round has begun.
 pub type TimeoutCertificate = GenericCertificate<Timeout>;
 
+/// The fields appended to the serialization of certificates, by version.
+#[derive(Serialize, Deserialize)]
+enum CertificateAppended<'a> {
+    /// The BLS12-381 signatures of the validators that aggregated theirs.
+    V1 {
+        aggregate_signature: Option<Cow<'a, AggregateSignature>>,
+    },
+}
+
+impl<'a> CertificateAppended<'a> {
+    fn new(aggregate_signature: Option<&'a AggregateSignature>) -> Self {
+        CertificateAppended::V1 {
+            aggregate_signature: aggregate_signature.map(Cow::Borrowed),
+        }
+    }
+
+    /// Returns the aggregated signature. Certificates serialized before it was appended have
+    /// none.
+    fn into_aggregate_signature(appended: Option<Self>) -> Option<AggregateSignature> {
+        let CertificateAppended::V1 {
+            aggregate_signature,
+        } = appended?;
+        aggregate_signature.map(Cow::into_owned)
+    }
+}
+
 /// Enum wrapping all types of certificates that can be created.
 /// A certified statement from the committee.
 /// Every certificate is a statement signed by the quorum of the committee.
@@ -82,6 +111,14 @@ impl Certificate {
             Certificate::Timeout(cert) => cert.signatures(),
         }
     }
+
+    pub fn aggregate_signature(&self) -> Option<&AggregateSignature> {
+        match self {
+            Certificate::Validated(cert) => cert.aggregate_signature(),
+            Certificate::Confirmed(cert) => cert.aggregate_signature(),
+            Certificate::Timeout(cert) => cert.aggregate_signature(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
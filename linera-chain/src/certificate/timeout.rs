@@ -3,15 +3,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
+    appended,
     crypto::{ValidatorPublicKey, ValidatorSignature},
     data_types::Round,
 };
-use serde::{
-    ser::{Serialize, SerializeStruct, Serializer},
-    Deserialize, Deserializer,
-};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{generic::GenericCertificate, Certificate};
+use super::{generic::GenericCertificate, Certificate, CertificateAppended};
 use crate::block::{ConversionError, Timeout};
 
 impl TryFrom<Certificate> for GenericCertificate<Timeout> {
@@ -33,11 +31,21 @@ impl From<GenericCertificate<Timeout>> for Certificate {
 
 impl Serialize for GenericCertificate<Timeout> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let mut state = serializer.serialize_struct("TimeoutCertificate", 4)?;
-        state.serialize_field("value", self.inner())?;
-        state.serialize_field("round", &self.round)?;
-        state.serialize_field("signatures", self.signatures())?;
-        state.end()
+        #[derive(Serialize)]
+        #[serde(rename = "TimeoutCertificate")]
+        struct Fields<'a> {
+            value: &'a Timeout,
+            round: Round,
+            signatures: &'a [(ValidatorPublicKey, ValidatorSignature)],
+        }
+
+        let fields = Fields {
+            value: self.inner(),
+            round: self.round,
+            signatures: self.signatures(),
+        };
+        let appended = CertificateAppended::new(self.aggregate_signature());
+        appended::serialize(&fields, &appended, serializer)
     }
 }
 
@@ -53,11 +61,17 @@ impl<'de> Deserialize<'de> for GenericCertificate<Timeout> {
             round: Round,
             signatures: Vec<(ValidatorPublicKey, ValidatorSignature)>,
         }
-        let inner = Inner::deserialize(deserializer)?;
-        if !crate::data_types::is_strictly_ordered(&inner.signatures) {
+        let (inner, appended): (Inner, _) = appended::deserialize(deserializer)?;
+        let aggregate_signature = CertificateAppended::into_aggregate_signature(appended);
+        if !crate::data_types::is_strictly_ordered(&inner.signatures)
+            || aggregate_signature
+                .as_ref()
+                .is_some_and(|aggregate| !aggregate.has_ordered_signers())
+        {
             Err(serde::de::Error::custom("Vector is not strictly sorted"))
         } else {
-            Ok(Self::new(inner.value, inner.round, inner.signatures))
+            Ok(Self::new(inner.value, inner.round, inner.signatures)
+                .with_aggregate_signature(aggregate_signature))
         }
     }
 }
@@ -21,9 +21,9 @@ use linera_base::{
     ownership::ChainOwnership,
 };
 use linera_execution::{
-    committee::Committee, ExecutionRuntimeContext, ExecutionStateView, Message, MessageContext,
-    Operation, OperationContext, OutgoingMessage, Query, QueryContext, QueryOutcome,
-    ResourceController, ResourceTracker, ServiceRuntimeEndpoint, TransactionTracker,
+    committee::Committee, ContractLog, ExecutionRuntimeContext, ExecutionStateView, Message,
+    MessageContext, Operation, OperationContext, OutgoingMessage, Query, QueryContext,
+    QueryOutcome, ResourceController, ResourceTracker, ServiceRuntimeEndpoint, TransactionTracker,
 };
 use linera_views::{
     bucket_queue_view::BucketQueueView,
@@ -40,8 +40,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     block::ConfirmedBlock,
     data_types::{
-        BlockExecutionOutcome, ChainAndHeight, IncomingBundle, MessageAction, MessageBundle,
-        OperationResult, PostedMessage, ProposedBlock, Transaction,
+        BlockExecutionOutcome, BlockExecutionResources, ChainAndHeight, IncomingBundle,
+        MessageAction, MessageBundle, OperationResult, PostedMessage, ProposedBlock, Transaction,
     },
     inbox::{Cursor, InboxError, InboxStateView},
     manager::ChainManager,
@@ -204,6 +204,9 @@ impl BundleInInbox {
 // of 100 seems reasonable for the storing of the data.
 const TIMESTAMPBUNDLE_BUCKET_SIZE: usize = 100;
 
+/// The number of recent blocks whose execution logs are kept.
+pub const EXECUTION_LOG_BLOCKS: u64 = 100;
+
 /// A view accessing the state of a chain.
 #[derive(Debug, RootView, ClonableView, SimpleObject)]
 #[graphql(cache_control(no_cache))]
@@ -249,6 +252,12 @@ where
     /// Number of outgoing messages in flight for each block height.
     /// We use a `RegisterView` to prioritize speed for small maps.
     pub outbox_counters: RegisterView<C, BTreeMap<BlockHeight, u32>>,
+    /// The blocks below this height have had their bodies pruned from storage.
+    pub pruned_height: RegisterView<C, BlockHeight>,
+    /// The messages that applications logged while this node executed the recent blocks, for
+    /// each transaction, by block height. They are not part of the blocks.
+    #[graphql(skip)]
+    pub execution_logs: MapView<C, BlockHeight, Vec<Vec<ContractLog>>>,
 }
 
 /// Block-chaining state.
@@ -420,6 +429,73 @@ where
         }
     }
 
+    /// Advances the pruned height so that only the last `retention` blocks, and the blocks
+    /// whose outgoing messages are still in flight, keep their bodies.
+    ///
+    /// Returns the hashes of the blocks whose bodies can now be pruned from storage.
+    pub async fn advance_pruned_height(
+        &mut self,
+        retention: u64,
+    ) -> Result<Vec<CryptoHash>, ChainError> {
+        let next_block_height = self.tip_state.get().next_block_height;
+        let mut target = BlockHeight(next_block_height.0.saturating_sub(retention));
+        if let Some(height) = self.outbox_counters.get().keys().next() {
+            // The bodies are still needed to deliver the messages.
+            target = target.min(*height);
+        }
+        let pruned_height = *self.pruned_height.get();
+        if target <= pruned_height {
+            return Ok(Vec::new());
+        }
+        let start = usize::try_from(pruned_height.0).map_err(|_| ArithmeticError::Overflow)?;
+        let end = usize::try_from(target.0).map_err(|_| ArithmeticError::Overflow)?;
+        let hashes = self.confirmed_log.read(start..end).await?;
+        self.pruned_height.set(target);
+        Ok(hashes)
+    }
+
+    /// Drops the messages of the bundles that the given pruned blocks removed from the inboxes
+    /// by anticipation. Only their cursors and certificate hashes are needed to reconcile them
+    /// with the bundles that are added later.
+    pub async fn prune_removed_bundles(
+        &mut self,
+        blocks: &[ConfirmedBlock],
+    ) -> Result<(), ChainError> {
+        let mut cursors_by_origin: BTreeMap<_, BTreeSet<Cursor>> = BTreeMap::new();
+        for block in blocks {
+            for IncomingBundle { origin, bundle, .. } in &block.block().body.incoming_bundles {
+                cursors_by_origin
+                    .entry(*origin)
+                    .or_default()
+                    .insert(Cursor::from(bundle));
+            }
+        }
+        let inboxes = self
+            .inboxes
+            .try_load_entries_mut(cursors_by_origin.keys())
+            .await?;
+        for (cursors, mut inbox) in cursors_by_origin.values().zip(inboxes) {
+            inbox.prune_removed_bundles(cursors).await?;
+        }
+        Ok(())
+    }
+
+    /// Stores the messages logged while executing the block at `height`, and forgets those
+    /// of the block [`EXECUTION_LOG_BLOCKS`] below it.
+    pub fn record_execution_logs(
+        &mut self,
+        height: BlockHeight,
+        logs: Vec<Vec<ContractLog>>,
+    ) -> Result<(), ChainError> {
+        if let Some(expired) = height.0.checked_sub(EXECUTION_LOG_BLOCKS) {
+            self.execution_logs.remove(&BlockHeight(expired))?;
+        }
+        if logs.iter().any(|txn_logs| !txn_logs.is_empty()) {
+            self.execution_logs.insert(&height, logs)?;
+        }
+        Ok(())
+    }
+
     /// Invariant for the states of active chains.
     pub fn is_active(&self) -> bool {
         self.execution_state.system.is_active()
@@ -683,7 +759,7 @@ where
         round: Option<u32>,
         published_blobs: &[Blob],
         replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
-    ) -> Result<BlockExecutionOutcome, ChainError> {
+    ) -> Result<(BlockExecutionOutcome, BlockExecutionResources), ChainError> {
         #[cfg(with_metrics)]
         let _execution_latency = BLOCK_EXECUTION_LATENCY.measure_latency();
 
@@ -711,6 +787,37 @@ where
                     .collect::<BTreeSet<_>>(),
             ChainError::InternalError("published_blobs mismatch".to_string())
         );
+        // If a sponsor pays for this block, set aside its funds before charging any fees.
+        for (txn_index, transaction) in block.transactions().skip(block.incoming_bundles.len() + 1)
+        {
+            if let Transaction::ExecuteOperation(operation) = transaction {
+                ensure!(
+                    operation.as_fee_sponsorship().is_none(),
+                    ChainError::ExecutionError(
+                        Box::new(ExecutionError::MisplacedFeeSponsorship),
+                        ChainExecutionContext::Operation(txn_index),
+                    )
+                );
+            }
+        }
+        let fee_sponsorship = block
+            .operations
+            .first()
+            .and_then(Operation::as_fee_sponsorship);
+        let reserved_sponsor_funds = match fee_sponsorship {
+            Some((sponsorship, public_key, signature)) => chain
+                .system
+                .reserve_sponsor_funds(
+                    sponsorship,
+                    public_key,
+                    signature,
+                    block.authenticated_signer,
+                    block.timestamp,
+                )
+                .await
+                .with_execution_context(ChainExecutionContext::Block)?,
+            None => Amount::ZERO,
+        };
         resource_controller
             .track_block_size(EMPTY_BLOCK_SIZE)
             .with_execution_context(ChainExecutionContext::Block)?;
@@ -749,7 +856,10 @@ where
         let mut blobs = Vec::new();
         let mut messages = Vec::new();
         let mut operation_results = Vec::new();
+        let mut transaction_fees = Vec::new();
+        let mut logs = Vec::new();
         for (txn_index, transaction) in block.transactions() {
+            let fees_before = resource_controller.tracker.fees;
             let chain_execution_context = match transaction {
                 Transaction::ReceiveMessages(_) => ChainExecutionContext::IncomingBundle(txn_index),
                 Transaction::ExecuteOperation(_) => ChainExecutionContext::Operation(txn_index),
@@ -861,6 +971,7 @@ where
             messages.push(txn_outcome.outgoing_messages);
             events.push(txn_outcome.events);
             blobs.push(txn_outcome.blobs);
+            logs.push(txn_outcome.logs);
 
             if let Transaction::ExecuteOperation(_) = transaction {
                 resource_controller
@@ -868,6 +979,7 @@ where
                     .with_execution_context(chain_execution_context)?;
                 operation_results.push(OperationResult(txn_outcome.operation_result));
             }
+            transaction_fees.push(resource_controller.tracker.fees.saturating_sub(fees_before));
         }
 
         // Finally, charge for the block fee, except if the chain is closed. Closed chains should
@@ -879,6 +991,14 @@ where
                 .track_block()
                 .with_execution_context(ChainExecutionContext::Block)?;
         }
+        // Return whatever the sponsor's funds did not pay for.
+        if let Some((sponsorship, _, _)) = fee_sponsorship {
+            chain
+                .system
+                .settle_sponsor_funds(sponsorship, reserved_sponsor_funds)
+                .await
+                .with_execution_context(ChainExecutionContext::Block)?;
+        }
 
         let recipients = messages
             .iter()
@@ -913,7 +1033,7 @@ where
             chain.crypto_hash().await?
         };
 
-        Ok(BlockExecutionOutcome {
+        let outcome = BlockExecutionOutcome {
             messages,
             previous_message_blocks,
             state_hash,
@@ -921,7 +1041,13 @@ where
             events,
             blobs,
             operation_results,
-        })
+        };
+        let resources = BlockExecutionResources {
+            tracker: resource_controller.tracker,
+            transaction_fees,
+            logs,
+        };
+        Ok((outcome, resources))
     }
 
     /// Executes a block: first the incoming messages, then the main operation.
@@ -934,6 +1060,28 @@ where
         published_blobs: &[Blob],
         replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
     ) -> Result<BlockExecutionOutcome, ChainError> {
+        let (outcome, _) = self
+            .execute_block_with_resources(
+                block,
+                local_time,
+                round,
+                published_blobs,
+                replaying_oracle_responses,
+            )
+            .await?;
+        Ok(outcome)
+    }
+
+    /// Executes a block like [`ChainStateView::execute_block`], and also returns the
+    /// resources it used and the fees it was charged for each transaction.
+    pub async fn execute_block_with_resources(
+        &mut self,
+        block: &ProposedBlock,
+        local_time: Timestamp,
+        round: Option<u32>,
+        published_blobs: &[Blob],
+        replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
+    ) -> Result<(BlockExecutionOutcome, BlockExecutionResources), ChainError> {
         assert_eq!(
             block.chain_id,
             self.execution_state.context().extra().chain_id()
@@ -5,28 +5,40 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
     error::Error,
+    fmt,
+    sync::Arc,
 };
 
 use async_graphql::SimpleObject;
+use async_trait::async_trait;
 use custom_debug_derive::Debug;
+#[cfg(not(target_arch = "wasm32"))]
+use linera_base::crypto::{BlsSecretKey, DecodedBlsPublicKey};
 use linera_base::{
-    bcs,
+    appended, bcs,
     crypto::{
-        AccountPublicKey, AccountSignature, BcsHashable, BcsSignable, CryptoError, CryptoHash,
-        Signer, ValidatorPublicKey, ValidatorSecretKey, ValidatorSignature,
+        AccountPublicKey, AccountSignature, BcsHashable, BcsSignable, BlsSignature, CryptoError,
+        CryptoHash, Signer, ValidatorPublicKey, ValidatorSecretKey, ValidatorSignature,
+    },
+    data_types::{
+        Amount, Blob, BlockHeight, BlockLimits, Epoch, Event, InboxPolicy, OracleResponse, Round,
+        Timestamp,
     },
-    data_types::{Amount, Blob, BlockHeight, Epoch, Event, OracleResponse, Round, Timestamp},
     doc_scalar, ensure, hex_debug,
     identifiers::{Account, AccountOwner, BlobId, ChainId, MessageId},
 };
-use linera_execution::{committee::Committee, Message, MessageKind, Operation, OutgoingMessage};
-use serde::{Deserialize, Serialize};
+use linera_execution::{
+    committee::{CertificateSignatureScheme, Committee},
+    system::SystemMessage,
+    ContractLog, Message, MessageKind, Operation, OutgoingMessage, ResourceTracker,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     block::{Block, ValidatedBlock},
     types::{
-        CertificateKind, CertificateValue, GenericCertificate, LiteCertificate,
-        ValidatedBlockCertificate,
+        CertificateKind, CertificateValue, ConfirmedBlockCertificate, GenericCertificate,
+        LiteCertificate, ValidatedBlockCertificate,
     },
     ChainError,
 };
@@ -124,6 +136,39 @@ impl ProposedBlock {
         Ok(())
     }
 
+    /// Checks the block against the limits set by the chain owners.
+    pub fn check_block_limits(&self, limits: &BlockLimits) -> Result<(), ChainError> {
+        if let Some(limit) = limits.max_operations_per_block {
+            ensure!(
+                self.operations.len() <= limit as usize,
+                ChainError::BlockLimitExceeded {
+                    what: "operations",
+                    limit: limit.into(),
+                }
+            );
+        }
+        if let Some(limit) = limits.max_incoming_bundles_per_block {
+            ensure!(
+                self.incoming_bundles.len() <= limit as usize,
+                ChainError::BlockLimitExceeded {
+                    what: "incoming bundles",
+                    limit: limit.into(),
+                }
+            );
+        }
+        if let Some(limit) = limits.max_block_bytes {
+            let size = bcs::serialized_size(self)?;
+            ensure!(
+                size <= usize::try_from(limit).unwrap_or(usize::MAX),
+                ChainError::BlockLimitExceeded {
+                    what: "bytes",
+                    limit,
+                }
+            );
+        }
+        Ok(())
+    }
+
     /// Returns the message ID belonging to the `index`th outgoing message in this block.
     pub fn message_id(&self, index: u32) -> MessageId {
         MessageId {
@@ -185,6 +230,17 @@ impl IncomingBundle {
             (message_id, posted_message)
         })
     }
+
+    /// Returns whether the owners' inbox policy accepts this bundle. Protected bundles are
+    /// always accepted.
+    pub fn is_accepted_by(&self, policy: &InboxPolicy) -> bool {
+        self.bundle.is_protected()
+            || (policy.allows_sender(&self.origin)
+                && self.bundle.messages.iter().all(|posted_message| {
+                    policy.allows_application(&posted_message.message.application_id())
+                })
+                && self.bundle.attached_value() >= policy.min_attached_value)
+    }
 }
 
 impl BcsHashable<'_> for IncomingBundle {}
@@ -226,6 +282,19 @@ pub struct BlockProposal {
     pub validated_block_certificate: Option<LiteCertificate<'static>>,
 }
 
+/// A block proposal that has not been signed by its owner yet.
+///
+/// This allows the proposal to be signed elsewhere, e.g. on an offline machine holding the
+/// owner's key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsignedBlockProposal {
+    pub content: ProposalContent,
+    /// The owner who has to sign the proposal.
+    pub owner: AccountOwner,
+    #[debug(skip_if = Option::is_none)]
+    pub validated_block_certificate: Option<LiteCertificate<'static>>,
+}
+
 /// A message together with kind, authentication and grant information.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct PostedMessage {
@@ -308,6 +377,20 @@ pub struct BlockExecutionOutcome {
     pub operation_results: Vec<OperationResult>,
 }
 
+/// The resources used by a [`ProposedBlock`]'s execution, and the fees it was charged.
+#[derive(Debug, Clone, Default)]
+pub struct BlockExecutionResources {
+    /// The resources used by the whole block, including the total fees.
+    pub tracker: ResourceTracker,
+    /// The fees charged for each transaction, i.e. for each incoming bundle, then for each
+    /// operation. The fees for the block itself and for the blobs it publishes are not
+    /// included.
+    pub transaction_fees: Vec<Amount>,
+    /// The messages that applications logged in each transaction, i.e. in each incoming
+    /// bundle, then in each operation.
+    pub logs: Vec<Vec<ContractLog>>,
+}
+
 /// The hash and chain ID of a `CertificateValue`.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct LiteValue {
@@ -316,6 +399,43 @@ pub struct LiteValue {
     pub kind: CertificateKind,
 }
 
+/// Evidence that a validator signed two different confirmed blocks at the same height of a
+/// chain.
+///
+/// The signed values don't include the height, so it has to be checked against the blocks
+/// with the hashes in the votes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct EquivocationEvidence {
+    /// The height of the conflicting blocks.
+    pub height: BlockHeight,
+    /// The validator that signed both blocks.
+    pub validator: ValidatorPublicKey,
+    /// The validator's vote for the first block.
+    pub first_vote: CertifiedVote,
+    /// The validator's vote for the second block.
+    pub second_vote: CertifiedVote,
+}
+
+/// A validator's vote on a statement, as it is included in a certificate.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct CertifiedVote {
+    pub value: LiteValue,
+    pub round: Round,
+    pub signature: CertifiedSignature,
+}
+
+/// The signature of a validator's vote in a certificate.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub enum CertifiedSignature {
+    /// The validator's own signature.
+    Individual(ValidatorSignature),
+    /// A BLS12-381 signature aggregating the validator's with other validators' signatures.
+    Aggregated(AggregateSignature),
+}
+
 impl LiteValue {
     pub fn new<T: CertificateValue>(value: &T) -> Self {
         LiteValue {
@@ -329,30 +449,158 @@ impl LiteValue {
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 struct VoteValue(CryptoHash, Round, CertificateKind);
 
+/// Signs with the key of a validator that is kept outside of the node, e.g. by an external
+/// program.
+#[async_trait]
+pub trait ValidatorSigner: Send + Sync {
+    /// Returns the public key of the validator.
+    fn public_key(&self) -> ValidatorPublicKey;
+
+    /// Signs the given hash of a value.
+    async fn sign(
+        &self,
+        prehash: CryptoHash,
+    ) -> Result<ValidatorSignature, Box<dyn Error + Send + Sync>>;
+}
+
+/// The key a validator signs with.
+enum ValidatorSecret {
+    Local(ValidatorSecretKey),
+    External(Arc<dyn ValidatorSigner>),
+}
+
+/// The keys of a validator: the key it signs its votes and chain information with, and the
+/// BLS12-381 key derived from it, if the secret key is available locally.
+pub struct ValidatorKeys {
+    secret: ValidatorSecret,
+    public_key: ValidatorPublicKey,
+    #[cfg(not(target_arch = "wasm32"))]
+    bls_secret_key: Option<BlsSecretKey>,
+}
+
+impl ValidatorKeys {
+    /// Derives the BLS12-381 key of the validator from its secret key.
+    pub fn new(secret_key: ValidatorSecretKey) -> Self {
+        ValidatorKeys {
+            public_key: secret_key.public(),
+            #[cfg(not(target_arch = "wasm32"))]
+            bls_secret_key: Some(BlsSecretKey::from_validator_secret(&secret_key)),
+            secret: ValidatorSecret::Local(secret_key),
+        }
+    }
+
+    /// Uses the given external signer. Since there is no BLS12-381 key, the validator can't
+    /// vote in epochs whose committee aggregates the signatures.
+    pub fn external(signer: Arc<dyn ValidatorSigner>) -> Self {
+        ValidatorKeys {
+            public_key: signer.public_key(),
+            #[cfg(not(target_arch = "wasm32"))]
+            bls_secret_key: None,
+            secret: ValidatorSecret::External(signer),
+        }
+    }
+
+    /// Returns the key the validator signs with, if it is available locally.
+    pub fn secret_key(&self) -> Option<&ValidatorSecretKey> {
+        match &self.secret {
+            ValidatorSecret::Local(secret_key) => Some(secret_key),
+            ValidatorSecret::External(_) => None,
+        }
+    }
+
+    /// Returns the public key of the validator.
+    pub fn public_key(&self) -> ValidatorPublicKey {
+        self.public_key
+    }
+
+    /// Signs the `value`. Signatures from an external signer are checked before they are
+    /// returned.
+    pub async fn sign<'de, T>(&self, value: &T) -> Result<ValidatorSignature, ChainError>
+    where
+        T: BcsSignable<'de> + fmt::Debug + Sync,
+    {
+        match &self.secret {
+            ValidatorSecret::Local(secret_key) => Ok(ValidatorSignature::new(value, secret_key)),
+            ValidatorSecret::External(signer) => {
+                let signature = signer
+                    .sign(CryptoHash::new(value))
+                    .await
+                    .map_err(|error| ChainError::ValidatorSignerError(error.to_string()))?;
+                signature.check(value, &self.public_key)?;
+                Ok(signature)
+            }
+        }
+    }
+
+    /// Returns the signer of the validator's votes in an epoch with the given committee.
+    /// The votes are only signed with the BLS12-381 key if the committee aggregates them.
+    pub fn vote_signer(&self, committee: Option<&Committee>) -> VoteSigner<'_> {
+        let aggregate = committee.is_some_and(|committee| {
+            committee.certificate_signature_scheme()
+                == CertificateSignatureScheme::AggregatedBls12381
+        });
+        VoteSigner {
+            keys: self,
+            aggregate,
+        }
+    }
+}
+
+/// Signs the votes of a validator in an epoch.
+#[derive(Clone, Copy)]
+pub struct VoteSigner<'a> {
+    keys: &'a ValidatorKeys,
+    /// Whether the votes are aggregated in certificates.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    aggregate: bool,
+}
+
+impl VoteSigner<'_> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bls_signature(&self, value: &VoteValue) -> Result<Option<BlsSignature>, ChainError> {
+        if !self.aggregate {
+            return Ok(None);
+        }
+        let bls_secret_key = self.keys.bls_secret_key.as_ref().ok_or_else(|| {
+            ChainError::ValidatorSignerError(
+                "the external signer can't sign with a BLS12-381 key".to_string(),
+            )
+        })?;
+        Ok(Some(BlsSignature::new(value, bls_secret_key)))
+    }
+
+    /// Validators don't run on Wasm, so there is no need to sign with BLS12-381 keys.
+    #[cfg(target_arch = "wasm32")]
+    fn bls_signature(&self, _value: &VoteValue) -> Result<Option<BlsSignature>, ChainError> {
+        Ok(None)
+    }
+}
+
 /// A vote on a statement from a validator.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+#[derive(Clone, Debug)]
 pub struct Vote<T> {
     pub value: T,
     pub round: Round,
     pub public_key: ValidatorPublicKey,
     pub signature: ValidatorSignature,
+    /// The BLS12-381 signature, to be aggregated with the other validators' votes.
+    pub bls_signature: Option<BlsSignature>,
 }
 
 impl<T> Vote<T> {
     /// Use signing key to create a signed object.
-    pub fn new(value: T, round: Round, key_pair: &ValidatorSecretKey) -> Self
+    pub async fn new(value: T, round: Round, signer: VoteSigner<'_>) -> Result<Self, ChainError>
     where
         T: CertificateValue,
     {
         let hash_and_round = VoteValue(value.hash(), round, T::KIND);
-        let signature = ValidatorSignature::new(&hash_and_round, key_pair);
-        Self {
+        Ok(Self {
+            public_key: signer.keys.public_key(),
+            signature: signer.keys.sign(&hash_and_round).await?,
+            bls_signature: signer.bls_signature(&hash_and_round)?,
             value,
             round,
-            public_key: key_pair.public(),
-            signature,
-        }
+        })
     }
 
     /// Returns the vote, with a `LiteValue` instead of the full value.
@@ -365,6 +613,7 @@ impl<T> Vote<T> {
             round: self.round,
             public_key: self.public_key,
             signature: self.signature,
+            bls_signature: self.bls_signature,
         }
     }
 
@@ -375,13 +624,15 @@ impl<T> Vote<T> {
 }
 
 /// A vote on a statement from a validator, represented as a `LiteValue`.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct LiteVote {
     pub value: LiteValue,
     pub round: Round,
     pub public_key: ValidatorPublicKey,
     pub signature: ValidatorSignature,
+    /// The BLS12-381 signature, to be aggregated with the other validators' votes.
+    pub bls_signature: Option<BlsSignature>,
 }
 
 impl LiteVote {
@@ -396,6 +647,7 @@ impl LiteVote {
             round: self.round,
             public_key: self.public_key,
             signature: self.signature,
+            bls_signature: self.bls_signature,
         })
     }
 
@@ -404,6 +656,113 @@ impl LiteVote {
     }
 }
 
+/// The fields appended to the serialization of votes, by version.
+#[derive(Serialize, Deserialize)]
+enum VoteAppended {
+    /// The BLS12-381 signature.
+    V1 { bls_signature: Option<BlsSignature> },
+}
+
+impl VoteAppended {
+    /// Returns the BLS12-381 signature. Votes serialized before it was appended have none.
+    fn into_bls_signature(appended: Option<Self>) -> Option<BlsSignature> {
+        let VoteAppended::V1 { bls_signature } = appended?;
+        bls_signature
+    }
+}
+
+impl<T: Serialize> Serialize for Vote<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(rename = "Vote")]
+        struct Fields<'a, T> {
+            value: &'a T,
+            round: Round,
+            public_key: ValidatorPublicKey,
+            signature: ValidatorSignature,
+        }
+
+        let fields = Fields {
+            value: &self.value,
+            round: self.round,
+            public_key: self.public_key,
+            signature: self.signature,
+        };
+        let appended = VoteAppended::V1 {
+            bls_signature: self.bls_signature,
+        };
+        appended::serialize(&fields, &appended, serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Vote<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Vote")]
+        struct Fields<T> {
+            value: T,
+            round: Round,
+            public_key: ValidatorPublicKey,
+            signature: ValidatorSignature,
+        }
+
+        let (fields, appended): (Fields<T>, _) = appended::deserialize(deserializer)?;
+        Ok(Vote {
+            value: fields.value,
+            round: fields.round,
+            public_key: fields.public_key,
+            signature: fields.signature,
+            bls_signature: VoteAppended::into_bls_signature(appended),
+        })
+    }
+}
+
+impl Serialize for LiteVote {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(rename = "LiteVote")]
+        struct Fields<'a> {
+            value: &'a LiteValue,
+            round: Round,
+            public_key: ValidatorPublicKey,
+            signature: ValidatorSignature,
+        }
+
+        let fields = Fields {
+            value: &self.value,
+            round: self.round,
+            public_key: self.public_key,
+            signature: self.signature,
+        };
+        let appended = VoteAppended::V1 {
+            bls_signature: self.bls_signature,
+        };
+        appended::serialize(&fields, &appended, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LiteVote {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "LiteVote")]
+        struct Fields {
+            value: LiteValue,
+            round: Round,
+            public_key: ValidatorPublicKey,
+            signature: ValidatorSignature,
+        }
+
+        let (fields, appended): (Fields, _) = appended::deserialize(deserializer)?;
+        Ok(LiteVote {
+            value: fields.value,
+            round: fields.round,
+            public_key: fields.public_key,
+            signature: fields.signature,
+            bls_signature: VoteAppended::into_bls_signature(appended),
+        })
+    }
+}
+
 impl MessageBundle {
     pub fn is_skippable(&self) -> bool {
         self.messages.iter().all(PostedMessage::is_skippable)
@@ -424,6 +783,17 @@ impl MessageBundle {
     pub fn is_protected(&self) -> bool {
         self.messages.iter().any(PostedMessage::is_protected)
     }
+
+    /// Returns the native tokens that the bundle credits to the receiving chain or its owners.
+    pub fn attached_value(&self) -> Amount {
+        self.messages
+            .iter()
+            .filter_map(|posted_message| match &posted_message.message {
+                Message::System(SystemMessage::Credit { amount, .. }) => Some(*amount),
+                _ => None,
+            })
+            .fold(Amount::ZERO, Amount::saturating_add)
+    }
 }
 
 impl PostedMessage {
@@ -492,6 +862,70 @@ pub struct ProposalContent {
     pub outcome: Option<BlockExecutionOutcome>,
 }
 
+impl UnsignedBlockProposal {
+    /// Creates a proposal for a new block.
+    pub fn new_initial(owner: AccountOwner, round: Round, block: ProposedBlock) -> Self {
+        Self {
+            content: ProposalContent {
+                round,
+                block,
+                outcome: None,
+            },
+            owner,
+            validated_block_certificate: None,
+        }
+    }
+
+    /// Creates a proposal to retry a validated block in a later round.
+    pub fn new_retry(
+        owner: AccountOwner,
+        round: Round,
+        validated_block_certificate: ValidatedBlockCertificate,
+    ) -> Self {
+        let lite_cert = validated_block_certificate.lite_certificate().cloned();
+        let block = validated_block_certificate.into_inner().into_inner();
+        let (block, outcome) = block.into_proposal();
+        Self {
+            content: ProposalContent {
+                block,
+                round,
+                outcome: Some(outcome),
+            },
+            owner,
+            validated_block_certificate: Some(lite_cert),
+        }
+    }
+
+    /// Returns the hash that the owner has to sign.
+    pub fn signing_hash(&self) -> CryptoHash {
+        CryptoHash::new(&self.content)
+    }
+
+    /// Signs the proposal with the owner's key.
+    pub async fn sign(
+        self,
+        signer: &(impl Signer + ?Sized),
+    ) -> Result<BlockProposal, Box<dyn Error>> {
+        let signature = signer.sign(&self.owner, &self.signing_hash()).await?;
+        let public_key = signer.get_public_key(&self.owner).await?;
+        Ok(self.with_signature(public_key, signature))
+    }
+
+    /// Returns the proposal with the given signature, which is not checked.
+    pub fn with_signature(
+        self,
+        public_key: AccountPublicKey,
+        signature: AccountSignature,
+    ) -> BlockProposal {
+        BlockProposal {
+            content: self.content,
+            public_key,
+            signature,
+            validated_block_certificate: self.validated_block_certificate,
+        }
+    }
+}
+
 impl BlockProposal {
     pub async fn new_initial(
         owner: AccountOwner,
@@ -499,20 +933,9 @@ impl BlockProposal {
         block: ProposedBlock,
         signer: &(impl Signer + ?Sized),
     ) -> Result<Self, Box<dyn Error>> {
-        let content = ProposalContent {
-            round,
-            block,
-            outcome: None,
-        };
-        let signature = signer.sign(&owner, &CryptoHash::new(&content)).await?;
-        let public_key = signer.get_public_key(&owner).await?;
-
-        Ok(Self {
-            content,
-            public_key,
-            signature,
-            validated_block_certificate: None,
-        })
+        UnsignedBlockProposal::new_initial(owner, round, block)
+            .sign(signer)
+            .await
     }
 
     pub async fn new_retry(
@@ -521,23 +944,9 @@ impl BlockProposal {
         validated_block_certificate: ValidatedBlockCertificate,
         signer: &(impl Signer + ?Sized),
     ) -> Result<Self, Box<dyn Error>> {
-        let lite_cert = validated_block_certificate.lite_certificate().cloned();
-        let block = validated_block_certificate.into_inner().into_inner();
-        let (block, outcome) = block.into_proposal();
-        let content = ProposalContent {
-            block,
-            round,
-            outcome: Some(outcome),
-        };
-        let signature = signer.sign(&owner, &CryptoHash::new(&content)).await?;
-
-        let public_key = signer.get_public_key(&owner).await?;
-        Ok(Self {
-            content,
-            public_key,
-            signature,
-            validated_block_certificate: Some(lite_cert),
-        })
+        UnsignedBlockProposal::new_retry(owner, round, validated_block_certificate)
+            .sign(signer)
+            .await
     }
 
     pub fn check_signature(&self) -> Result<(), CryptoError> {
@@ -587,7 +996,8 @@ impl BlockProposal {
 }
 
 impl LiteVote {
-    /// Uses the signing key to create a signed object.
+    /// Uses the signing key to create a signed object. The vote is not signed with a
+    /// BLS12-381 key, so it can't be aggregated.
     pub fn new(value: LiteValue, round: Round, secret_key: &ValidatorSecretKey) -> Self {
         let hash_and_round = VoteValue(value.value_hash, round, value.kind);
         let signature = ValidatorSignature::new(&hash_and_round, secret_key);
@@ -596,16 +1006,154 @@ impl LiteVote {
             round,
             public_key: secret_key.public(),
             signature,
+            bls_signature: None,
         }
     }
 
-    /// Verifies the signature in the vote.
+    /// Verifies the signature in the vote. The BLS12-381 signature is only checked when
+    /// it is aggregated, against the key in the committee.
     pub fn check(&self) -> Result<(), ChainError> {
         let hash_and_round = VoteValue(self.value.value_hash, self.round, self.value.kind);
         Ok(self.signature.check(&hash_and_round, &self.public_key)?)
     }
 }
 
+impl EquivocationEvidence {
+    /// Returns the evidence against every validator that signed both certificates, if they
+    /// confirm different blocks at the same height of the same chain. Validators whose
+    /// signatures are aggregated count too: the aggregate proves that each of its signers
+    /// signed.
+    pub fn from_certificates(
+        first: &ConfirmedBlockCertificate,
+        second: &ConfirmedBlockCertificate,
+    ) -> Vec<Self> {
+        let first_header = &first.block().header;
+        let second_header = &second.block().header;
+        if first_header.chain_id != second_header.chain_id
+            || first_header.height != second_header.height
+        {
+            return Vec::new();
+        }
+        Self::from_lite_certificates(
+            first_header.height,
+            &first.lite_certificate(),
+            &second.lite_certificate(),
+        )
+    }
+
+    /// Returns the evidence against every validator that signed both certificates, if they
+    /// confirm different blocks of the same chain. Both blocks must be at the given `height`,
+    /// e.g. according to their headers if their bodies were pruned.
+    pub fn from_lite_certificates(
+        height: BlockHeight,
+        first: &LiteCertificate,
+        second: &LiteCertificate,
+    ) -> Vec<Self> {
+        if first.value.value_hash == second.value.value_hash
+            || first.value.chain_id != second.value.chain_id
+        {
+            return Vec::new();
+        }
+        let mut second_votes = CertifiedVote::from_certificate(second);
+        CertifiedVote::from_certificate(first)
+            .into_iter()
+            .filter_map(|(validator, first_vote)| {
+                Some(EquivocationEvidence {
+                    height,
+                    validator,
+                    first_vote,
+                    second_vote: second_votes.remove(&validator)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the chain on which the validator equivocated.
+    pub fn chain_id(&self) -> ChainId {
+        self.first_vote.value.chain_id
+    }
+
+    /// Verifies that both votes are validly signed by the validator, and confirm different
+    /// blocks of the same chain. Aggregated signatures are checked against the keys of the
+    /// committees that the blocks were confirmed with.
+    pub fn check(
+        &self,
+        first_committee: &Committee,
+        second_committee: &Committee,
+    ) -> Result<(), ChainError> {
+        let (first, second) = (&self.first_vote, &self.second_vote);
+        ensure!(
+            first.value.chain_id == second.value.chain_id
+                && first.value.kind == CertificateKind::Confirmed
+                && second.value.kind == CertificateKind::Confirmed
+                && first.value.value_hash != second.value.value_hash,
+            ChainError::InvalidEquivocationEvidence
+        );
+        first.check(&self.validator, first_committee)?;
+        second.check(&self.validator, second_committee)
+    }
+}
+
+impl CertifiedVote {
+    /// Returns the votes of all the validators that signed the certificate.
+    fn from_certificate(certificate: &LiteCertificate) -> BTreeMap<ValidatorPublicKey, Self> {
+        let vote = |signature| CertifiedVote {
+            value: certificate.value.clone(),
+            round: certificate.round,
+            signature,
+        };
+        let aggregated_votes = certificate
+            .aggregate_signature
+            .iter()
+            .flat_map(|aggregate| {
+                aggregate.signers.iter().map(|validator| {
+                    let signature = CertifiedSignature::Aggregated(aggregate.clone());
+                    (*validator, vote(signature))
+                })
+            });
+        certificate
+            .signatures
+            .iter()
+            .map(|(validator, signature)| {
+                (*validator, vote(CertifiedSignature::Individual(*signature)))
+            })
+            .chain(aggregated_votes)
+            .collect()
+    }
+
+    /// Verifies that the vote is validly signed by the validator.
+    pub fn check(
+        &self,
+        validator: &ValidatorPublicKey,
+        committee: &Committee,
+    ) -> Result<(), ChainError> {
+        let hash_and_round = VoteValue(self.value.value_hash, self.round, self.value.kind);
+        match &self.signature {
+            CertifiedSignature::Individual(signature) => {
+                Ok(signature.check(&hash_and_round, validator)?)
+            }
+            CertifiedSignature::Aggregated(aggregate) => {
+                ensure!(
+                    aggregate.signers.binary_search(validator).is_ok(),
+                    ChainError::InvalidEquivocationEvidence
+                );
+                let bls_keys = aggregate
+                    .signers
+                    .iter()
+                    .map(|signer| {
+                        Ok(committee
+                            .decoded_bls_key(signer)
+                            .ok_or(ChainError::MissingBlsKey(*signer))??)
+                    })
+                    .collect::<Result<Vec<_>, ChainError>>()?;
+                Ok(aggregate
+                    .signature
+                    .check_aggregate(&hash_and_round, &bls_keys)?)
+            }
+        }
+    }
+}
+
 pub struct SignatureAggregator<'a, T: CertificateValue> {
     committee: &'a Committee,
     weight: u64,
@@ -659,6 +1207,78 @@ impl<'a, T: CertificateValue> SignatureAggregator<'a, T> {
     }
 }
 
+/// The BLS12-381 signatures of several validators on the same value, aggregated into one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    /// The validators whose signatures are aggregated, in increasing order.
+    pub signers: Vec<ValidatorPublicKey>,
+    /// The aggregated signature.
+    pub signature: BlsSignature,
+}
+
+impl AggregateSignature {
+    /// Aggregates the valid BLS12-381 signatures of the votes, which must all be for the same
+    /// value and round. Returns `None` if there are none.
+    ///
+    /// The aggregate is checked once; only if that fails are the signatures checked one by one,
+    /// to leave out the invalid ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_votes(votes: &[LiteVote], committee: &Committee) -> Option<Self> {
+        let first_vote = votes.first()?;
+        let hash_and_round = VoteValue(
+            first_vote.value.value_hash,
+            first_vote.round,
+            first_vote.value.kind,
+        );
+        let mut signed = votes
+            .iter()
+            .filter_map(|vote| {
+                let bls_key = committee.decoded_bls_key(&vote.public_key)?.ok()?;
+                Some((vote.public_key, vote.bls_signature?, bls_key))
+            })
+            .collect::<Vec<_>>();
+        signed.sort_by_key(|(public_key, _, _)| *public_key);
+        signed.dedup_by_key(|(public_key, _, _)| *public_key);
+        let aggregate = |signed: &[(ValidatorPublicKey, BlsSignature, DecodedBlsPublicKey)]| {
+            let signature =
+                BlsSignature::aggregate(signed.iter().map(|(_, signature, _)| signature)).ok()?;
+            signature
+                .check_aggregate(
+                    &hash_and_round,
+                    signed.iter().map(|(_, _, bls_key)| bls_key),
+                )
+                .ok()?;
+            Some(AggregateSignature {
+                signers: signed
+                    .iter()
+                    .map(|(public_key, _, _)| *public_key)
+                    .collect(),
+                signature,
+            })
+        };
+        if let Some(aggregate_signature) = aggregate(&signed) {
+            return Some(aggregate_signature);
+        }
+        signed.retain(|(_, signature, bls_key)| {
+            signature
+                .check_aggregate(&hash_and_round, [bls_key])
+                .is_ok()
+        });
+        aggregate(&signed)
+    }
+
+    /// Aggregating signatures is not supported on Wasm.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn from_votes(_votes: &[LiteVote], _committee: &Committee) -> Option<Self> {
+        None
+    }
+
+    /// Returns whether the signers are strictly ordered, i.e. sorted and without duplicates.
+    pub(crate) fn has_ordered_signers(&self) -> bool {
+        self.signers.windows(2).all(|pair| pair[0] < pair[1])
+    }
+}
+
 // Checks if the array slice is strictly ordered. That means that if the array
 // has duplicates, this will return False, even if the array is sorted
 pub(crate) fn is_strictly_ordered(values: &[(ValidatorPublicKey, ValidatorSignature)]) -> bool {
@@ -671,12 +1291,28 @@ pub(crate) fn check_signatures(
     certificate_kind: CertificateKind,
     round: Round,
     signatures: &[(ValidatorPublicKey, ValidatorSignature)],
+    aggregate_signature: Option<&AggregateSignature>,
     committee: &Committee,
 ) -> Result<(), ChainError> {
+    let aggregate_signers = match aggregate_signature {
+        None => &[][..],
+        Some(aggregate) => {
+            ensure!(
+                committee.certificate_signature_scheme()
+                    == CertificateSignatureScheme::AggregatedBls12381,
+                ChainError::UnexpectedAggregateSignature
+            );
+            &aggregate.signers[..]
+        }
+    };
     // Check the quorum.
     let mut weight = 0;
     let mut used_validators = HashSet::new();
-    for (validator, _) in signatures {
+    let signers = signatures
+        .iter()
+        .map(|(validator, _)| validator)
+        .chain(aggregate_signers);
+    for validator in signers {
         // Check that each validator only appears once.
         ensure!(
             !used_validators.contains(validator),
@@ -695,6 +1331,21 @@ pub(crate) fn check_signatures(
     // All that is left is checking signatures!
     let hash_and_round = VoteValue(value_hash, round, certificate_kind);
     ValidatorSignature::verify_batch(&hash_and_round, signatures.iter())?;
+    if let Some(aggregate) = aggregate_signature {
+        let bls_keys = aggregate
+            .signers
+            .iter()
+            .map(|validator| {
+                let bls_key = committee
+                    .decoded_bls_key(validator)
+                    .ok_or(ChainError::MissingBlsKey(*validator))??;
+                Ok(bls_key)
+            })
+            .collect::<Result<Vec<_>, ChainError>>()?;
+        aggregate
+            .signature
+            .check_aggregate(&hash_and_round, &bls_keys)?;
+    }
     Ok(())
 }
 
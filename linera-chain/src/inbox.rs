@@ -1,6 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeSet;
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 
@@ -78,6 +79,7 @@ where
     pub added_bundles: QueueView<C, MessageBundle>,
     /// These bundles have been removed by anticipation and are waiting to be added.
     /// At least one of `added_bundles` and `removed_bundles` should be empty.
+    /// Their messages are dropped when the bodies of the blocks that removed them are pruned.
     pub removed_bundles: QueueView<C, MessageBundle>,
 }
 
@@ -143,6 +145,18 @@ impl Cursor {
     }
 }
 
+/// Returns whether `bundle` is the bundle that was removed by anticipation as `removed`. If the
+/// messages of `removed` were pruned, the certificate hash stands for them.
+fn matches_removed_bundle(bundle: &MessageBundle, removed: &MessageBundle) -> bool {
+    if !removed.messages.is_empty() {
+        return bundle == removed;
+    }
+    bundle.height == removed.height
+        && bundle.timestamp == removed.timestamp
+        && bundle.certificate_hash == removed.certificate_hash
+        && bundle.transaction_index == removed.transaction_index
+}
+
 impl From<(ChainId, ChainId, InboxError)> for ChainError {
     fn from(value: (ChainId, ChainId, InboxError)) -> Self {
         let (chain_id, origin, error) = value;
@@ -284,7 +298,7 @@ where
                     // We already executed this bundle by anticipation. Remove it from
                     // the queue.
                     ensure!(
-                        bundle == previous_bundle,
+                        matches_removed_bundle(&bundle, &previous_bundle),
                         InboxError::UnexpectedBundle {
                             previous_bundle,
                             bundle,
@@ -321,6 +335,22 @@ where
         self.next_cursor_to_add.set(cursor.try_add_one()?);
         Ok(newly_added)
     }
+
+    /// Drops the messages of the bundles removed by anticipation with the given cursors.
+    pub(crate) async fn prune_removed_bundles(
+        &mut self,
+        cursors: &BTreeSet<Cursor>,
+    ) -> Result<(), ViewError> {
+        if self.removed_bundles.count() == 0 {
+            return Ok(());
+        }
+        for bundle in self.removed_bundles.iter_mut().await? {
+            if cursors.contains(&Cursor::from(&*bundle)) {
+                bundle.messages.clear();
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(with_testing)]
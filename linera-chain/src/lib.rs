@@ -6,6 +6,7 @@
 #![deny(clippy::large_futures)]
 
 pub mod block;
+pub mod canonical;
 mod certificate;
 
 pub mod types {
@@ -18,14 +19,15 @@ mod inbox;
 pub mod manager;
 mod outbox;
 mod pending_blobs;
+pub mod state_proof;
 #[cfg(with_testing)]
 pub mod test;
 
-pub use chain::ChainStateView;
+pub use chain::{ChainStateView, EXECUTION_LOG_BLOCKS};
 use data_types::{MessageBundle, PostedMessage};
 use linera_base::{
     bcs,
-    crypto::{CryptoError, CryptoHash},
+    crypto::{CryptoError, CryptoHash, ValidatorPublicKey},
     data_types::{ArithmeticError, BlockHeight, Round, Timestamp},
     identifiers::{ApplicationId, BlobId, ChainId},
 };
@@ -38,6 +40,8 @@ use thiserror::Error;
 pub enum ChainError {
     #[error("Cryptographic error: {0}")]
     CryptoError(#[from] CryptoError),
+    #[error("Failed to sign with the validator's key: {0}")]
+    ValidatorSignerError(String),
     #[error(transparent)]
     ArithmeticError(#[from] ArithmeticError),
     #[error(transparent)]
@@ -140,10 +144,18 @@ pub enum ChainError {
     CertificateRequiresQuorum,
     #[error("Certificate signature verification failed: {error}")]
     CertificateSignatureVerificationFailed { error: String },
+    #[error("The committee of this certificate does not aggregate signatures")]
+    UnexpectedAggregateSignature,
+    #[error("Validator {0} has no BLS key to check the aggregate signature with")]
+    MissingBlsKey(ValidatorPublicKey),
+    #[error("The votes are not conflicting votes from the same validator")]
+    InvalidEquivocationEvidence,
     #[error("Internal error {0}")]
     InternalError(String),
     #[error("Block proposal is too large")]
     BlockProposalTooLarge,
+    #[error("Block proposal exceeds the chain's limit of {limit} {what}")]
+    BlockLimitExceeded { what: &'static str, limit: u64 },
     #[error(transparent)]
     BcsError(#[from] bcs::Error),
     #[error("Insufficient balance to pay the fees")]
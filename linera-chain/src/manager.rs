@@ -74,7 +74,7 @@ use async_graphql::{ComplexObject, SimpleObject};
 use custom_debug_derive::Debug;
 use futures::future::Either;
 use linera_base::{
-    crypto::{AccountPublicKey, ValidatorSecretKey},
+    crypto::AccountPublicKey,
     data_types::{Blob, BlockHeight, Epoch, Round, Timestamp},
     ensure,
     identifiers::{AccountOwner, BlobId, ChainId},
@@ -93,7 +93,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     block::{Block, ConfirmedBlock, Timeout, ValidatedBlock},
-    data_types::{BlockProposal, LiteVote, ProposedBlock, Vote},
+    data_types::{BlockProposal, LiteVote, ProposedBlock, Vote, VoteSigner},
     types::{TimeoutCertificate, ValidatedBlockCertificate},
     ChainError,
 };
@@ -355,57 +355,57 @@ where
     }
 
     /// Checks if the current round has timed out, and signs a `Timeout`.
-    pub fn vote_timeout(
+    pub async fn vote_timeout(
         &mut self,
         chain_id: ChainId,
         height: BlockHeight,
         epoch: Epoch,
-        key_pair: Option<&ValidatorSecretKey>,
+        signer: Option<VoteSigner<'_>>,
         local_time: Timestamp,
-    ) -> bool {
-        let Some(key_pair) = key_pair else {
-            return false; // We are not a validator.
+    ) -> Result<bool, ChainError> {
+        let Some(signer) = signer else {
+            return Ok(false); // We are not a validator.
         };
         let Some(round_timeout) = *self.round_timeout.get() else {
-            return false; // The current round does not time out.
+            return Ok(false); // The current round does not time out.
         };
         if local_time < round_timeout || self.ownership.get().owners.is_empty() {
-            return false; // Round has not timed out yet, or there are no regular owners.
+            return Ok(false); // Round has not timed out yet, or there are no regular owners.
         }
         let current_round = self.current_round();
         if let Some(vote) = self.timeout_vote.get() {
             if vote.round == current_round {
-                return false; // We already signed this timeout.
+                return Ok(false); // We already signed this timeout.
             }
         }
         let value = Timeout::new(chain_id, height, epoch);
         self.timeout_vote
-            .set(Some(Vote::new(value, current_round, key_pair)));
-        true
+            .set(Some(Vote::new(value, current_round, signer).await?));
+        Ok(true)
     }
 
     /// Signs a `Timeout` certificate to switch to fallback mode.
     ///
     /// This must only be called after verifying that the condition for fallback mode is
     /// satisfied locally.
-    pub fn vote_fallback(
+    pub async fn vote_fallback(
         &mut self,
         chain_id: ChainId,
         height: BlockHeight,
         epoch: Epoch,
-        key_pair: Option<&ValidatorSecretKey>,
-    ) -> bool {
-        let Some(key_pair) = key_pair else {
-            return false; // We are not a validator.
+        signer: Option<VoteSigner<'_>>,
+    ) -> Result<bool, ChainError> {
+        let Some(signer) = signer else {
+            return Ok(false); // We are not a validator.
         };
         if self.fallback_vote.get().is_some() || self.current_round() >= Round::Validator(0) {
-            return false; // We already signed this or are already in fallback mode.
+            return Ok(false); // We already signed this or are already in fallback mode.
         }
         let value = Timeout::new(chain_id, height, epoch);
         let last_regular_round = Round::SingleLeader(u32::MAX);
         self.fallback_vote
-            .set(Some(Vote::new(value, last_regular_round, key_pair)));
-        true
+            .set(Some(Vote::new(value, last_regular_round, signer).await?));
+        Ok(true)
     }
 
     /// Verifies that a validated block is still relevant and should be handled.
@@ -441,11 +441,11 @@ where
     }
 
     /// Signs a vote to validate the proposed block.
-    pub fn create_vote(
+    pub async fn create_vote(
         &mut self,
         proposal: BlockProposal,
         block: Block,
-        key_pair: Option<&ValidatorSecretKey>,
+        signer: Option<VoteSigner<'_>>,
         local_time: Timestamp,
         blobs: BTreeMap<BlobId, Blob>,
     ) -> Result<Option<ValidatedOrConfirmedVote>, ChainError> {
@@ -473,7 +473,7 @@ where
         self.update_proposed(proposal.clone(), blobs)?;
         self.update_current_round(local_time);
 
-        let Some(key_pair) = key_pair else {
+        let Some(signer) = signer else {
             // Not a validator.
             return Ok(None);
         };
@@ -482,13 +482,13 @@ where
         if round.is_fast() {
             self.validated_vote.set(None);
             let value = ConfirmedBlock::new(block);
-            let vote = Vote::new(value, round, key_pair);
+            let vote = Vote::new(value, round, signer).await?;
             Ok(Some(Either::Right(
                 self.confirmed_vote.get_mut().insert(vote),
             )))
         } else {
             let value = ValidatedBlock::new(block);
-            let vote = Vote::new(value, round, key_pair);
+            let vote = Vote::new(value, round, signer).await?;
             Ok(Some(Either::Left(
                 self.validated_vote.get_mut().insert(vote),
             )))
@@ -496,23 +496,23 @@ where
     }
 
     /// Signs a vote to confirm the validated block.
-    pub fn create_final_vote(
+    pub async fn create_final_vote(
         &mut self,
         validated: ValidatedBlockCertificate,
-        key_pair: Option<&ValidatorSecretKey>,
+        signer: Option<VoteSigner<'_>>,
         local_time: Timestamp,
         blobs: BTreeMap<BlobId, Blob>,
-    ) -> Result<(), ViewError> {
+    ) -> Result<(), ChainError> {
         let round = validated.round;
         let confirmed_block = ConfirmedBlock::new(validated.inner().block().clone());
         self.update_locking(LockingBlock::Regular(validated), blobs)?;
         self.update_current_round(local_time);
-        if let Some(key_pair) = key_pair {
+        if let Some(signer) = signer {
             if self.current_round() != round {
                 return Ok(()); // We never vote in a past round.
             }
             // Vote to confirm.
-            let vote = Vote::new(confirmed_block, round, key_pair);
+            let vote = Vote::new(confirmed_block, round, signer).await?;
             // Ok to overwrite validation votes with confirmation votes at equal or higher round.
             self.confirmed_vote.set(Some(vote));
             self.validated_vote.set(None);
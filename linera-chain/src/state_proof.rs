@@ -0,0 +1,317 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proofs that entries are part of the execution state committed by a block.
+//!
+//! The hash of a view is computed from the hashes of its subviews, so an entry of a map-like
+//! view can be tied to the `state_hash` of a [`BlockHeader`] by revealing all the entries of
+//! that view, the hashes of the other views in the collections holding it, and the hashes of
+//! the other subviews on the path up to the root of the execution state. The views keep
+//! their existing hashes, so that proofs can be made for the state of any chain. This lets
+//! light clients check query responses against a certificate instead of trusting the node
+//! that answered them. The node service serves these proofs with its `balanceProof` and
+//! `applicationStateProof` queries.
+//!
+//! The hashes of the other subviews are written by the `write_field_hashes` method generated
+//! by the `HashableView` derive, which the view's own hash is computed from, so that the
+//! proofs stay in sync with the views' fields.
+//!
+//! Note that the hash of a map-like view does not delimit keys and values. A proof for an
+//! application's state is therefore only conclusive if the application's keys cannot be
+//! confused with their values, e.g. because they have a fixed length.
+
+use linera_base::{
+    bcs,
+    crypto::{BcsHashable, CryptoHash},
+    data_types::Amount,
+    doc_scalar,
+    identifiers::{AccountOwner, ApplicationId},
+};
+use linera_views::{
+    context::Context,
+    sha3::{Digest as _, Sha3_256},
+    views::{FieldHashSink, HashableView as _, ViewError},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{block::BlockHeader, ChainError, ChainStateView};
+
+/// The output of the hash function used by views.
+type ViewHash = [u8; 32];
+
+/// A step from the hash of a view to the hash of its parent view.
+///
+/// The parent's hash is the hash of `before`, followed by the child's hash and `after`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HashStep {
+    /// The bytes hashed before the child's hash.
+    #[serde(with = "serde_bytes")]
+    pub before: Vec<u8>,
+    /// The bytes hashed after the child's hash.
+    #[serde(with = "serde_bytes")]
+    pub after: Vec<u8>,
+}
+
+impl HashStep {
+    /// Returns the hash of the parent view, given the hash of the child.
+    fn apply(&self, child_hash: ViewHash) -> ViewHash {
+        let mut hasher = Sha3_256::new();
+        hasher.update(&self.before);
+        hasher.update(child_hash);
+        hasher.update(&self.after);
+        hasher.finalize().into()
+    }
+}
+
+/// Builds the [`HashStep`] for a field of a view, from the field hashes that the view
+/// writes with its `write_field_hashes` method.
+struct FieldStep {
+    name: &'static str,
+    found: bool,
+    step: HashStep,
+}
+
+impl FieldStep {
+    fn new(name: &'static str) -> Self {
+        FieldStep {
+            name,
+            found: false,
+            step: HashStep {
+                before: Vec::new(),
+                after: Vec::new(),
+            },
+        }
+    }
+
+    /// Returns the step, or an error if the view has no field with the expected name.
+    fn finish(self) -> Result<HashStep, ChainError> {
+        if !self.found {
+            return Err(ChainError::InternalError(format!(
+                "the view has no field `{}` to prove",
+                self.name
+            )));
+        }
+        Ok(self.step)
+    }
+}
+
+impl FieldHashSink for FieldStep {
+    fn write_field_hash(&mut self, name: &'static str, hash: &[u8]) -> Result<(), ViewError> {
+        if name == self.name {
+            self.found = true;
+        } else if self.found {
+            self.step.after.extend_from_slice(hash);
+        } else {
+            self.step.before.extend_from_slice(hash);
+        }
+        Ok(())
+    }
+}
+
+/// A proof that a map-like view with the given entries is part of a chain's execution state.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StateProof {
+    /// All the serialized entries of the view, in the order of their keys.
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The collections of views that hold the view, from the innermost. Each lists the
+    /// serialized keys of all its views in order, with their hashes, except for the view on
+    /// the path, whose hash is `None`.
+    pub collections: Vec<Vec<(Vec<u8>, Option<ViewHash>)>>,
+    /// The steps from the hash of the outermost view up to the hash of the execution state.
+    pub path: Vec<HashStep>,
+}
+
+doc_scalar!(
+    StateProof,
+    "A proof that a map-like view with the given entries is part of a chain's execution state"
+);
+
+/// Errors that can occur when verifying a [`StateProof`].
+#[derive(Debug, Error)]
+pub enum StateProofError {
+    #[error("The proof does not match the state hash of block {0:?}")]
+    StateHashMismatch(CryptoHash),
+    #[error("The keys of the entries in the proof are not strictly increasing")]
+    UnorderedEntries,
+    #[error("The proof is not for a view at the expected place in the execution state")]
+    UnexpectedView,
+    #[error("Failed to deserialize a proven value: {0}")]
+    Deserialization(#[from] bcs::Error),
+}
+
+/// The hash of an execution state view, as it is wrapped by `CryptoHashView`.
+#[derive(Serialize, Deserialize)]
+struct ExecutionStateViewHash(ViewHash);
+
+impl BcsHashable<'_> for ExecutionStateViewHash {}
+
+/// Returns the number of entries of a view, as it is hashed.
+fn hashed_count(len: usize) -> [u8; 4] {
+    u32::try_from(len).unwrap_or(u32::MAX).to_le_bytes()
+}
+
+impl StateProof {
+    /// Returns the hash of the map-like view with the entries of this proof, as computed by
+    /// `MapView` and `KeyValueStoreView`.
+    fn entries_hash(&self) -> Result<ViewHash, StateProofError> {
+        if !self.entries.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(StateProofError::UnorderedEntries);
+        }
+        let mut hasher = Sha3_256::new();
+        for (key, value) in &self.entries {
+            hasher.update(key);
+            hasher.update(value);
+        }
+        hasher.update(hashed_count(self.entries.len()));
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns the hash of a collection of views, as computed by `ReentrantCollectionView`,
+    /// given the hash of the view at `view_key`.
+    fn collection_hash(
+        collection: &[(Vec<u8>, Option<ViewHash>)],
+        view_key: &[u8],
+        view_hash: ViewHash,
+    ) -> Result<ViewHash, StateProofError> {
+        if !collection.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(StateProofError::UnorderedEntries);
+        }
+        let mut hasher = Sha3_256::new();
+        hasher.update(hashed_count(collection.len()));
+        let mut found = false;
+        for (key, hash) in collection {
+            hasher.update(key);
+            match hash {
+                Some(hash) if key.as_slice() != view_key => hasher.update(hash),
+                None if key.as_slice() == view_key => {
+                    found = true;
+                    hasher.update(view_hash);
+                }
+                _ => return Err(StateProofError::UnexpectedView),
+            }
+        }
+        if !found {
+            return Err(StateProofError::UnexpectedView);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Verifies this proof against the execution state committed by the block with the given
+    /// `header`, for the view at `view_keys` in the collections of views that hold it, from
+    /// the innermost. Returns the serialized value at `key`, if any.
+    fn verify(
+        &self,
+        header: &BlockHeader,
+        key: &[u8],
+        view_keys: &[&[u8]],
+    ) -> Result<Option<&[u8]>, StateProofError> {
+        if self.collections.len() != view_keys.len() {
+            return Err(StateProofError::UnexpectedView);
+        }
+        let mut hash = self.entries_hash()?;
+        for (collection, view_key) in self.collections.iter().zip(view_keys) {
+            hash = Self::collection_hash(collection, view_key, hash)?;
+        }
+        let view_hash = self.path.iter().fold(hash, |hash, step| step.apply(hash));
+        if CryptoHash::new(&ExecutionStateViewHash(view_hash)) != header.state_hash {
+            return Err(StateProofError::StateHashMismatch(header.state_hash));
+        }
+        Ok(self
+            .entries
+            .binary_search_by(|(entry_key, _)| entry_key.as_slice().cmp(key))
+            .ok()
+            .map(|index| self.entries[index].1.as_slice()))
+    }
+
+    /// Verifies this proof of the balances of a chain, obtained from
+    /// [`ChainStateView::prove_balances`], and returns the balance of `owner`.
+    pub fn verify_balance(
+        &self,
+        header: &BlockHeader,
+        owner: &AccountOwner,
+    ) -> Result<Amount, StateProofError> {
+        match self.verify(header, &bcs::to_bytes(owner)?, &[])? {
+            Some(bytes) => Ok(bcs::from_bytes(bytes)?),
+            None => Ok(Amount::ZERO),
+        }
+    }
+
+    /// Verifies this proof of an application's state, obtained from
+    /// [`ChainStateView::prove_application_state`], and returns the value at `key`, if any.
+    pub fn verify_application_state(
+        &self,
+        header: &BlockHeader,
+        application_id: &ApplicationId,
+        key: &[u8],
+    ) -> Result<Option<&[u8]>, StateProofError> {
+        let application_key = bcs::to_bytes(application_id)?;
+        self.verify(header, key, &[&application_key])
+    }
+}
+
+impl<C> ChainStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: linera_execution::ExecutionRuntimeContext,
+{
+    /// Returns a proof of the balances of all the owners on this chain, relative to the state
+    /// hash of the latest block.
+    pub async fn prove_balances(&self) -> Result<StateProof, ChainError> {
+        let system = &self.execution_state.system;
+        let entries = system
+            .balances
+            .index_values()
+            .await?
+            .into_iter()
+            .map(|(owner, amount)| Ok((bcs::to_bytes(&owner)?, bcs::to_bytes(&amount)?)))
+            .collect::<Result<Vec<_>, bcs::Error>>()
+            .map_err(ViewError::from)?;
+        let mut balances_step = FieldStep::new("balances");
+        system.write_field_hashes(&mut balances_step).await?;
+        let mut system_step = FieldStep::new("system");
+        self.execution_state
+            .write_field_hashes(&mut system_step)
+            .await?;
+        Ok(StateProof {
+            entries,
+            collections: Vec::new(),
+            path: vec![balances_step.finish()?, system_step.finish()?],
+        })
+    }
+
+    /// Returns a proof of the state of the given application on this chain, relative to the
+    /// state hash of the latest block.
+    pub async fn prove_application_state(
+        &self,
+        application_id: &ApplicationId,
+    ) -> Result<StateProof, ChainError> {
+        let users = &self.execution_state.users;
+        let application_ids = users.indices().await?;
+        let views = users.try_load_entries(&application_ids).await?;
+        let mut collection = Vec::new();
+        let mut entries = None;
+        for (id, view) in application_ids.iter().zip(views) {
+            let view = view.ok_or_else(|| ViewError::not_found("application state", id))?;
+            let key = bcs::to_bytes(id).map_err(ViewError::from)?;
+            if id == application_id {
+                entries = Some(view.index_values().await?);
+                collection.push((key, None));
+            } else {
+                collection.push((key, Some(view.hash().await?.into())));
+            }
+        }
+        let Some(entries) = entries else {
+            return Err(ViewError::not_found("application state", application_id).into());
+        };
+        let mut users_step = FieldStep::new("users");
+        self.execution_state
+            .write_field_hashes(&mut users_step)
+            .await?;
+        Ok(StateProof {
+            entries,
+            collections: vec![collection],
+            path: vec![users_step.finish()?],
+        })
+    }
+}
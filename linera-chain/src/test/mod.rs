@@ -157,6 +157,7 @@ impl<T: CertificateValue> VoteTestExt<T> for Vote<T> {
             network_address: "".to_string(),
             votes: 100,
             account_public_key: AccountPublicKey::test_key(1),
+            bls_key: None,
         };
         let committee = Committee::new(
             vec![(self.public_key, state)].into_iter().collect(),
@@ -12,10 +12,11 @@ use std::{
 use assert_matches::assert_matches;
 use axum::{routing::get, Router};
 use linera_base::{
-    crypto::{AccountPublicKey, ValidatorPublicKey},
+    crypto::{AccountPublicKey, AccountSecretKey, CryptoHash, ValidatorPublicKey},
     data_types::{
         Amount, ApplicationDescription, ApplicationPermissions, Blob, BlockHeight, Bytecode,
-        ChainDescription, ChainOrigin, Epoch, InitialChainConfig, Timestamp,
+        ChainDescription, ChainOrigin, Epoch, InitialChainConfig, Resources, SendMessageRequest,
+        Timestamp,
     },
     http,
     identifiers::{AccountOwner, ApplicationId, ChainId, ModuleId},
@@ -24,10 +25,11 @@ use linera_base::{
 };
 use linera_execution::{
     committee::{Committee, ValidatorState},
-    system::Recipient,
+    system::{FeeSponsorship, Recipient},
     test_utils::{ExpectedCall, MockApplication},
-    BaseRuntime, ContractRuntime, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext,
-    Operation, ResourceControlPolicy, ServiceRuntime, SystemOperation, TestExecutionRuntimeContext,
+    BaseRuntime, ContractLog, ContractRuntime, ExecutionError, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, LogLevel, Operation, ResourceControlPolicy, ServiceRuntime,
+    SystemOperation, TestExecutionRuntimeContext, MAX_LOG_MESSAGE_LEN, MAX_TRANSACTION_LOGS,
 };
 use linera_views::{
     context::{Context as _, MemoryContext, ViewContext},
@@ -39,8 +41,9 @@ use test_case::test_case;
 use crate::{
     block::{Block, ConfirmedBlock},
     data_types::{BlockExecutionOutcome, ProposedBlock},
+    state_proof::StateProofError,
     test::{make_child_block, make_first_block, BlockTestExt, HttpServer},
-    ChainError, ChainExecutionContext, ChainStateView,
+    ChainError, ChainExecutionContext, ChainStateView, EXECUTION_LOG_BLOCKS,
 };
 
 impl ChainStateView<MemoryContext<TestExecutionRuntimeContext>>
@@ -168,6 +171,7 @@ async fn test_block_size_limit() -> anyhow::Result<()> {
                     network_address: ValidatorPublicKey::test_key(1).to_string(),
                     votes: 1,
                     account_public_key: AccountPublicKey::test_key(1),
+                    bls_key: None,
                 },
             )]),
             ResourceControlPolicy {
@@ -243,6 +247,105 @@ async fn test_block_size_limit() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_advance_pruned_height() -> anyhow::Result<()> {
+    let mut chain = ChainStateView::new(ChainId(CryptoHash::test_hash("chain"))).await;
+    let hashes = (0..10)
+        .map(|index| CryptoHash::test_hash(format!("block {index}")))
+        .collect::<Vec<_>>();
+    for hash in &hashes {
+        chain.confirmed_log.push(*hash);
+    }
+    chain.tip_state.get_mut().next_block_height = BlockHeight(10);
+    // Block 5 still has an outgoing message in flight.
+    chain.outbox_counters.get_mut().insert(BlockHeight(5), 1);
+
+    assert_eq!(chain.advance_pruned_height(3).await?, hashes[..5]);
+    assert_eq!(*chain.pruned_height.get(), BlockHeight(5));
+    assert!(chain.advance_pruned_height(3).await?.is_empty());
+
+    chain.outbox_counters.get_mut().clear();
+    assert_eq!(chain.advance_pruned_height(3).await?, hashes[5..7]);
+    assert_eq!(*chain.pruned_height.get(), BlockHeight(7));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_state_proofs() -> anyhow::Result<()> {
+    let mut env = TestEnvironment::new();
+    let time = Timestamp::from(0);
+    let chain_desc = env.make_child_chain_description_with_config(3, env.make_open_chain_config());
+    let chain_id = chain_desc.id();
+    let (app_description, _, _) = env.make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let owner = AccountOwner::from(CryptoHash::test_hash("owner"));
+
+    let mut chain = ChainStateView::new(chain_id).await;
+    chain
+        .context()
+        .extra()
+        .add_blobs(env.description_blobs())
+        .await?;
+    chain.ensure_is_active(time).await?;
+    chain
+        .execution_state
+        .system
+        .balances
+        .insert(&owner, Amount::from_tokens(3))?;
+    chain
+        .execution_state
+        .users
+        .try_load_entry_mut(&application_id)
+        .await?
+        .insert(b"key".to_vec(), b"value".to_vec())
+        .await?;
+
+    let block = make_first_block(chain_id);
+    let outcome = chain.execute_block(&block, time, None, &[], None).await?;
+    let header = Block::new(block, outcome).header;
+
+    let balances = chain.prove_balances().await?;
+    assert_eq!(
+        balances.verify_balance(&header, &owner)?,
+        Amount::from_tokens(3)
+    );
+    let other_owner = AccountOwner::from(CryptoHash::test_hash("other owner"));
+    assert_eq!(
+        balances.verify_balance(&header, &other_owner)?,
+        Amount::ZERO
+    );
+
+    let application_state = chain.prove_application_state(&application_id).await?;
+    assert_eq!(
+        application_state.verify_application_state(&header, &application_id, b"key")?,
+        Some(&b"value"[..])
+    );
+    assert_eq!(
+        application_state.verify_application_state(&header, &application_id, b"other key")?,
+        None
+    );
+    assert_matches!(
+        application_state.verify_balance(&header, &owner),
+        Err(StateProofError::UnexpectedView)
+    );
+
+    let mut forged = application_state;
+    forged.entries[0].1 = b"forged".to_vec();
+    assert_matches!(
+        forged.verify_application_state(&header, &application_id, b"key"),
+        Err(StateProofError::StateHashMismatch(_))
+    );
+    let mut forged = balances;
+    forged.entries.push(forged.entries[0].clone());
+    assert_matches!(
+        forged.verify_balance(&header, &owner),
+        Err(StateProofError::UnorderedEntries)
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_application_permissions() -> anyhow::Result<()> {
     let mut env = TestEnvironment::new();
@@ -648,6 +751,134 @@ async fn test_service_http_response_size_limit(
     chain.execute_block(&block, time, None, &[], None).await
 }
 
+/// Tests that the messages logged by contracts are captured for each transaction, and only
+/// kept for the recent blocks.
+#[tokio::test]
+async fn test_execution_logs() -> anyhow::Result<()> {
+    let (application, application_id, mut chain, block, time) =
+        prepare_test_with_dummy_mock_application(ResourceControlPolicy::default()).await?;
+
+    application.expect_call(ExpectedCall::execute_operation(|runtime, _| {
+        runtime.capture_log(LogLevel::Info, "hello".to_string())?;
+        runtime.capture_log(
+            LogLevel::Error,
+            format!("a{}", "é".repeat(MAX_LOG_MESSAGE_LEN)),
+        )?;
+        for _ in 0..MAX_TRANSACTION_LOGS {
+            runtime.capture_log(LogLevel::Debug, "dropped".to_string())?;
+        }
+        Ok(vec![])
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let (_, resources) = chain
+        .execute_block_with_resources(&block, time, None, &[], None)
+        .await?;
+    let [logs] = resources.logs.as_slice() else {
+        panic!("expected the logs of one transaction");
+    };
+    assert_eq!(logs.len(), MAX_TRANSACTION_LOGS);
+    assert_eq!(
+        logs[0],
+        ContractLog {
+            application_id,
+            level: LogLevel::Info,
+            message: "hello".to_string(),
+        }
+    );
+    // Long messages are truncated at a character boundary.
+    assert_eq!(
+        logs[1].message,
+        format!("a{}", "é".repeat(MAX_LOG_MESSAGE_LEN / 2 - 1))
+    );
+
+    chain.record_execution_logs(BlockHeight(0), resources.logs.clone())?;
+    assert_eq!(
+        chain.execution_logs.get(&BlockHeight(0)).await?,
+        Some(resources.logs)
+    );
+    // Blocks without logs are not recorded, and old logs are forgotten.
+    for height in 1..=EXECUTION_LOG_BLOCKS {
+        chain.record_execution_logs(BlockHeight(height), vec![vec![]])?;
+    }
+    assert!(chain.execution_logs.indices().await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the funds of a block's sponsor only pay for the block's fees, and not for the
+/// grants of the messages it sends.
+#[tokio::test]
+async fn test_sponsor_funds_only_pay_fees() -> anyhow::Result<()> {
+    let (application, _, mut chain, block, time) =
+        prepare_test_with_dummy_mock_application(ResourceControlPolicy {
+            block: Amount::from_millis(1),
+            operation: Amount::from_millis(2),
+            message: Amount::from_millis(3),
+            ..ResourceControlPolicy::default()
+        })
+        .await?;
+    let chain_id = block.chain_id;
+    let secret_key = AccountSecretKey::generate();
+    let sponsor = AccountOwner::from(secret_key.public());
+    let sponsor_balance = Amount::from_tokens(5);
+    chain
+        .execution_state
+        .system
+        .balances
+        .insert(&sponsor, sponsor_balance)?;
+    let chain_balance = *chain.execution_state.system.balance.get();
+    let sponsorship = FeeSponsorship {
+        chain_id,
+        sponsor,
+        beneficiary: None,
+        allowance: sponsor_balance,
+        expiry: Timestamp::from(100),
+    };
+    let mut block = block;
+    block
+        .operations
+        .insert(0, Operation::system(sponsorship.sign(&secret_key)));
+
+    // The grant is worth more than all the fees of the block.
+    let grant = Resources {
+        messages: 1_000,
+        ..Resources::default()
+    };
+    application.expect_call(ExpectedCall::execute_operation(move |runtime, _| {
+        runtime.send_message(SendMessageRequest {
+            destination: ChainId(CryptoHash::test_hash("recipient")),
+            authenticated: false,
+            is_tracked: false,
+            grant,
+            message: vec![],
+        })?;
+        Ok(vec![])
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let (_, resources) = chain
+        .execute_block_with_resources(&block, time, None, &[], None)
+        .await?;
+    let fees = resources.tracker.fees;
+    let sponsor_debit = sponsor_balance.try_sub(
+        chain
+            .execution_state
+            .system
+            .balances
+            .get(&sponsor)
+            .await?
+            .unwrap_or_default(),
+    )?;
+    assert_eq!(sponsor_debit, fees);
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        chain_balance.try_sub(Amount::from_tokens(3))?
+    );
+
+    Ok(())
+}
+
 /// Sets up a test with a dummy [`MockApplication`].
 ///
 /// Creates and initializes a [`ChainStateView`] configured with the
@@ -675,6 +906,7 @@ async fn prepare_test_with_dummy_mock_application(
                     network_address: ValidatorPublicKey::test_key(1).to_string(),
                     votes: 1,
                     account_public_key: AccountPublicKey::test_key(1),
+                    bls_key: None,
                 },
             )]),
             policy,
@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
-    crypto::{AccountSecretKey, Ed25519SecretKey, Secp256k1SecretKey, ValidatorKeypair},
+    crypto::{
+        AccountSecretKey, BlsSecretKey, Ed25519SecretKey, Secp256k1SecretKey, ValidatorKeypair,
+    },
     data_types::Amount,
 };
 
@@ -140,3 +142,384 @@ fn test_certificates() {
         .is_none());
     assert!(builder.append(v3.public_key, v3.signature).is_err());
 }
+
+#[tokio::test]
+async fn test_aggregated_certificates() {
+    let key_pairs = (0..3)
+        .map(|_| ValidatorKeypair::generate())
+        .collect::<Vec<_>>();
+    let mut committee = Committee::make_simple(
+        key_pairs
+            .iter()
+            .map(|key_pair| (key_pair.public_key, AccountSecretKey::generate().public()))
+            .collect(),
+    );
+    let secp_committee = committee.clone();
+    for key_pair in &key_pairs {
+        let bls_key = BlsSecretKey::from_validator_secret(&key_pair.secret_key).validator_key();
+        committee
+            .validators
+            .get_mut(&key_pair.public_key)
+            .unwrap()
+            .bls_key = Some(bls_key);
+    }
+    let committee =
+        committee.with_certificate_signature_scheme(CertificateSignatureScheme::AggregatedBls12381);
+    committee.check_bls_keys().unwrap();
+
+    let block = BlockExecutionOutcome {
+        messages: vec![Vec::new()],
+        previous_message_blocks: BTreeMap::new(),
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new()],
+        events: vec![Vec::new()],
+        blobs: vec![Vec::new()],
+        operation_results: vec![OperationResult::default()],
+    }
+    .with(make_first_block(dummy_chain_id(1)).with_simple_transfer(dummy_chain_id(1), Amount::ONE));
+    let value = ConfirmedBlock::new(block);
+    let validator_keys = key_pairs
+        .iter()
+        .map(|key_pair| ValidatorKeys::new(key_pair.secret_key.copy()))
+        .collect::<Vec<_>>();
+    // Votes are only signed with the BLS12-381 keys if the committee aggregates them.
+    let mut votes = Vec::new();
+    for keys in &validator_keys {
+        let signer = keys.vote_signer(Some(&secp_committee));
+        let vote = Vote::new(value.clone(), Round::Fast, signer).await.unwrap();
+        assert!(vote.bls_signature.is_none());
+        let signer = keys.vote_signer(Some(&committee));
+        let vote = Vote::new(value.clone(), Round::Fast, signer).await.unwrap();
+        votes.push(vote.lite());
+    }
+
+    let lite_certificate = LiteCertificate::try_from_votes(votes.clone(), &committee).unwrap();
+    assert!(lite_certificate.signatures.is_empty());
+    assert_eq!(
+        lite_certificate
+            .aggregate_signature
+            .as_ref()
+            .unwrap()
+            .signers
+            .len(),
+        3
+    );
+    lite_certificate.check(&committee).unwrap();
+    let certificate = lite_certificate.with_value(value.clone()).unwrap();
+    certificate.check(&committee).unwrap();
+    assert!(key_pairs
+        .iter()
+        .all(|key_pair| certificate.is_signed_by(&key_pair.public_key)));
+    assert!(matches!(
+        certificate.check(&secp_committee),
+        Err(ChainError::UnexpectedAggregateSignature)
+    ));
+
+    // Without aggregation, each vote keeps its individual signature.
+    let lite_certificate = LiteCertificate::try_from_votes(votes.clone(), &secp_committee).unwrap();
+    assert!(lite_certificate.aggregate_signature.is_none());
+    lite_certificate.check(&secp_committee).unwrap();
+
+    // An invalid BLS signature is left out of the aggregate.
+    votes[2].bls_signature = votes[0].bls_signature;
+    let lite_certificate = LiteCertificate::try_from_votes(votes, &committee).unwrap();
+    assert_eq!(lite_certificate.signatures.len(), 1);
+    assert_eq!(
+        lite_certificate
+            .aggregate_signature
+            .as_ref()
+            .unwrap()
+            .signers
+            .len(),
+        2
+    );
+    lite_certificate.check(&committee).unwrap();
+}
+
+/// An external signer with the validator's key, or with another key if `key_pair` doesn't
+/// match `public_key`.
+struct TestValidatorSigner {
+    public_key: ValidatorPublicKey,
+    key_pair: ValidatorKeypair,
+}
+
+#[async_trait::async_trait]
+impl ValidatorSigner for TestValidatorSigner {
+    fn public_key(&self) -> ValidatorPublicKey {
+        self.public_key
+    }
+
+    async fn sign(
+        &self,
+        prehash: CryptoHash,
+    ) -> Result<ValidatorSignature, Box<dyn Error + Send + Sync>> {
+        Ok(ValidatorSignature::sign_prehash(
+            &self.key_pair.secret_key,
+            prehash,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_external_validator_signer() {
+    let key_pair = ValidatorKeypair::generate();
+    let public_key = key_pair.public_key;
+    let keys = ValidatorKeys::external(Arc::new(TestValidatorSigner {
+        public_key,
+        key_pair,
+    }));
+    assert!(keys.secret_key().is_none());
+    let committee =
+        Committee::make_simple(vec![(public_key, AccountSecretKey::generate().public())]);
+    let block = BlockExecutionOutcome::default().with(make_first_block(dummy_chain_id(1)));
+    let value = ConfirmedBlock::new(block);
+    let vote = Vote::new(
+        value.clone(),
+        Round::Fast,
+        keys.vote_signer(Some(&committee)),
+    )
+    .await
+    .unwrap();
+    assert_eq!(vote.public_key, public_key);
+    let certificate = ConfirmedBlockCertificate::new(
+        value.clone(),
+        Round::Fast,
+        vec![(vote.public_key, vote.signature)],
+    );
+    certificate.check(&committee).unwrap();
+
+    // Without a BLS12-381 key, the validator can't vote if the signatures are aggregated.
+    let aggregating_committee = committee
+        .clone()
+        .with_certificate_signature_scheme(CertificateSignatureScheme::AggregatedBls12381);
+    assert!(matches!(
+        Vote::new(
+            value.clone(),
+            Round::Fast,
+            keys.vote_signer(Some(&aggregating_committee))
+        )
+        .await,
+        Err(ChainError::ValidatorSignerError(_))
+    ));
+
+    // Signatures with the wrong key are rejected.
+    let keys = ValidatorKeys::external(Arc::new(TestValidatorSigner {
+        public_key,
+        key_pair: ValidatorKeypair::generate(),
+    }));
+    assert!(matches!(
+        Vote::new(value, Round::Fast, keys.vote_signer(Some(&committee))).await,
+        Err(ChainError::CryptoError(_))
+    ));
+}
+
+/// Returns a confirmed block at height zero of chain 1, transferring to `recipient`.
+fn make_confirmed_block(recipient: ChainId) -> ConfirmedBlock {
+    let block = BlockExecutionOutcome {
+        messages: vec![Vec::new()],
+        previous_message_blocks: BTreeMap::new(),
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new()],
+        events: vec![Vec::new()],
+        blobs: vec![Vec::new()],
+        operation_results: vec![OperationResult::default()],
+    }
+    .with(make_first_block(dummy_chain_id(1)).with_simple_transfer(recipient, Amount::ONE));
+    ConfirmedBlock::new(block)
+}
+
+#[test]
+fn test_equivocation_evidence() {
+    let validator1_key_pair = ValidatorKeypair::generate();
+    let validator2_key_pair = ValidatorKeypair::generate();
+    let committee = Committee::make_simple(
+        [&validator1_key_pair, &validator2_key_pair]
+            .iter()
+            .map(|key_pair| (key_pair.public_key, AccountSecretKey::generate().public()))
+            .collect(),
+    );
+
+    let make_certificate = |recipient, key_pairs: &[&ValidatorKeypair]| {
+        let value = make_confirmed_block(recipient);
+        let signatures = key_pairs
+            .iter()
+            .map(|key_pair| {
+                let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, &key_pair.secret_key);
+                (vote.public_key, vote.signature)
+            })
+            .collect();
+        ConfirmedBlockCertificate::new(value, Round::Fast, signatures)
+    };
+    let first = make_certificate(
+        dummy_chain_id(2),
+        &[&validator1_key_pair, &validator2_key_pair],
+    );
+    let second = make_certificate(dummy_chain_id(3), &[&validator2_key_pair]);
+
+    assert!(EquivocationEvidence::from_certificates(&first, &first).is_empty());
+    let evidence = EquivocationEvidence::from_certificates(&first, &second);
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence[0].validator, validator2_key_pair.public_key);
+    assert_eq!(evidence[0].chain_id(), dummy_chain_id(1));
+    assert_eq!(evidence[0].height, BlockHeight::ZERO);
+    assert!(evidence[0].check(&committee, &committee).is_ok());
+    // The same evidence is found without the blocks' bodies.
+    assert_eq!(
+        EquivocationEvidence::from_lite_certificates(
+            BlockHeight::ZERO,
+            &first.lite_certificate(),
+            &second.lite_certificate()
+        ),
+        evidence
+    );
+
+    let mut forged = evidence[0].clone();
+    forged.second_vote.signature = forged.first_vote.signature.clone();
+    assert!(forged.check(&committee, &committee).is_err());
+    let mut impersonated = evidence[0].clone();
+    impersonated.validator = validator1_key_pair.public_key;
+    assert!(impersonated.check(&committee, &committee).is_err());
+    let mut consistent = evidence[0].clone();
+    consistent.second_vote = consistent.first_vote.clone();
+    assert!(matches!(
+        consistent.check(&committee, &committee),
+        Err(ChainError::InvalidEquivocationEvidence)
+    ));
+}
+
+#[tokio::test]
+async fn test_aggregated_equivocation_evidence() {
+    let key_pairs = (0..3)
+        .map(|_| ValidatorKeypair::generate())
+        .collect::<Vec<_>>();
+    let mut committee = Committee::make_simple(
+        key_pairs
+            .iter()
+            .map(|key_pair| (key_pair.public_key, AccountSecretKey::generate().public()))
+            .collect(),
+    );
+    for key_pair in &key_pairs {
+        let bls_key = BlsSecretKey::from_validator_secret(&key_pair.secret_key).validator_key();
+        committee
+            .validators
+            .get_mut(&key_pair.public_key)
+            .unwrap()
+            .bls_key = Some(bls_key);
+    }
+    let committee =
+        committee.with_certificate_signature_scheme(CertificateSignatureScheme::AggregatedBls12381);
+
+    let make_certificate = |recipient, key_pairs: Vec<ValidatorKeypair>| {
+        let committee = &committee;
+        async move {
+            let value = make_confirmed_block(recipient);
+            let mut votes = Vec::new();
+            for key_pair in key_pairs {
+                let keys = ValidatorKeys::new(key_pair.secret_key);
+                let signer = keys.vote_signer(Some(committee));
+                let vote = Vote::new(value.clone(), Round::Fast, signer).await.unwrap();
+                votes.push(vote.lite());
+            }
+            LiteCertificate::try_from_votes(votes, committee).unwrap()
+        }
+    };
+    let copy_key_pairs = |range: std::ops::Range<usize>| {
+        key_pairs[range]
+            .iter()
+            .map(|key_pair| ValidatorKeypair {
+                secret_key: key_pair.secret_key.copy(),
+                public_key: key_pair.public_key,
+            })
+            .collect::<Vec<_>>()
+    };
+    let first = make_certificate(dummy_chain_id(2), copy_key_pairs(0..2)).await;
+    let second = make_certificate(dummy_chain_id(3), copy_key_pairs(1..3)).await;
+    assert!(first.signatures.is_empty() && second.signatures.is_empty());
+
+    // Only the validator that signed both aggregates equivocated.
+    let evidence = EquivocationEvidence::from_lite_certificates(BlockHeight::ZERO, &first, &second);
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence[0].validator, key_pairs[1].public_key);
+    assert!(matches!(
+        evidence[0].second_vote.signature,
+        CertifiedSignature::Aggregated(_)
+    ));
+    evidence[0].check(&committee, &committee).unwrap();
+
+    // The validator must be one of the aggregate's signers.
+    let mut impersonated = evidence[0].clone();
+    impersonated.validator = key_pairs[0].public_key;
+    assert!(matches!(
+        impersonated.check(&committee, &committee),
+        Err(ChainError::InvalidEquivocationEvidence)
+    ));
+    // The aggregate is checked against the committee's keys.
+    let mut forged = evidence[0].clone();
+    forged.second_vote.value = forged.first_vote.value.clone();
+    forged.second_vote.value.value_hash = CryptoHash::test_hash("other block");
+    assert!(forged.check(&committee, &committee).is_err());
+}
+
+#[test]
+fn test_read_votes_without_appended_fields() {
+    #[derive(Serialize)]
+    struct OldLiteVote {
+        value: LiteValue,
+        round: Round,
+        public_key: ValidatorPublicKey,
+        signature: ValidatorSignature,
+    }
+
+    let key_pair = ValidatorKeypair::generate();
+    let value = make_confirmed_block(dummy_chain_id(2));
+    let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, &key_pair.secret_key);
+    let old_vote = OldLiteVote {
+        value: vote.value.clone(),
+        round: vote.round,
+        public_key: vote.public_key,
+        signature: vote.signature,
+    };
+    let old_bytes = bcs::to_bytes(&old_vote).unwrap();
+    assert_eq!(bcs::from_bytes::<LiteVote>(&old_bytes).unwrap(), vote);
+    let bytes = bcs::to_bytes(&vote).unwrap();
+    assert!(bytes.starts_with(&old_bytes));
+    assert_eq!(bcs::from_bytes::<LiteVote>(&bytes).unwrap(), vote);
+
+    // Full votes and certificates serialized before the BLS12-381 fields can be read too.
+    let vote = Vote {
+        value: value.clone(),
+        round: Round::Fast,
+        public_key: vote.public_key,
+        signature: vote.signature,
+        bls_signature: None,
+    };
+    let old_bytes =
+        bcs::to_bytes(&(&vote.value, vote.round, vote.public_key, vote.signature)).unwrap();
+    let read_vote = bcs::from_bytes::<Vote<ConfirmedBlock>>(&old_bytes).unwrap();
+    assert_eq!(read_vote.value, vote.value);
+    assert!(read_vote.bls_signature.is_none());
+
+    let certificate =
+        ConfirmedBlockCertificate::new(value, Round::Fast, vec![(vote.public_key, vote.signature)]);
+    let lite_certificate = certificate.lite_certificate().cloned();
+    let old_bytes = bcs::to_bytes(&(
+        &lite_certificate.value,
+        lite_certificate.round,
+        &lite_certificate.signatures,
+    ))
+    .unwrap();
+    assert_eq!(
+        bcs::from_bytes::<LiteCertificate>(&old_bytes).unwrap(),
+        lite_certificate
+    );
+    let old_bytes = bcs::to_bytes(&(
+        certificate.value(),
+        certificate.round,
+        certificate.signatures(),
+    ))
+    .unwrap();
+    assert_eq!(
+        bcs::from_bytes::<ConfirmedBlockCertificate>(&old_bytes).unwrap(),
+        certificate
+    );
+}
@@ -145,6 +145,43 @@ async fn test_inbox_remove_then_add_skippable() {
     assert_eq!(view.removed_bundles.count(), 0);
 }
 
+#[tokio::test]
+async fn test_inbox_remove_then_prune_then_add() {
+    let hash = CryptoHash::test_hash("1");
+    let mut view = InboxStateView::new().await;
+    // Remove two bundles by anticipation.
+    assert!(!view
+        .remove_bundle(&make_bundle(hash, 0, 0, [0]))
+        .await
+        .unwrap());
+    assert!(!view
+        .remove_bundle(&make_bundle(hash, 0, 1, [1]))
+        .await
+        .unwrap());
+    // Prune the messages of the first one.
+    let cursors = BTreeSet::from([Cursor::from(&make_bundle(hash, 0, 0, [0]))]);
+    view.prune_removed_bundles(&cursors).await.unwrap();
+    let removed_bundles = view.removed_bundles.elements().await.unwrap();
+    assert!(removed_bundles[0].messages.is_empty());
+    assert_eq!(removed_bundles[1], make_bundle(hash, 0, 1, [1]));
+    // Fail to add a bundle from another certificate.
+    assert_matches!(
+        view.add_bundle(make_bundle(CryptoHash::test_hash("2"), 0, 0, [0]))
+            .await,
+        Err(InboxError::UnexpectedBundle { .. })
+    );
+    // The pruned bundle is matched by its certificate hash, the other one still by its messages.
+    assert!(!view.add_bundle(make_bundle(hash, 0, 0, [0])).await.unwrap());
+    assert_matches!(
+        view.add_bundle(make_bundle(hash, 0, 1, [0])).await,
+        Err(InboxError::UnexpectedBundle { .. })
+    );
+    assert!(!view.add_bundle(make_bundle(hash, 0, 1, [1])).await.unwrap());
+    // Inbox is empty again.
+    assert_eq!(view.added_bundles.count(), 0);
+    assert_eq!(view.removed_bundles.count(), 0);
+}
+
 #[tokio::test]
 async fn test_inbox_add_then_remove_unskippable() {
     let hash = CryptoHash::test_hash("1");
@@ -0,0 +1,348 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use clap::Parser as _;
+use futures::{Stream, StreamExt as _};
+use linera_base::{
+    crypto::InMemorySigner, data_types::ApplicationPermissions, identifiers::AccountOwner,
+    ownership::ChainOwnership,
+};
+use linera_client::{
+    client_context::ClientContext, client_options::ClientContextOptions, config::GenesisConfig,
+    persistent, util::wait_for_next_round, wallet::Wallet,
+};
+use linera_core::{
+    client::{AbortOnDrop, ChainClient, ChainClientError},
+    data_types::ClientOutcome,
+    environment,
+    node::NotificationStream,
+};
+use linera_execution::{
+    Operation, Query, QueryOutcome, QueryResponse, WasmRuntime, WithWasmDefault as _,
+};
+use linera_faucet_client::Faucet;
+use linera_rpc::NodeProvider;
+use linera_storage::{DbStorage, WallClock};
+use linera_views::memory::{MemoryStore, MemoryStoreConfig};
+use tokio::sync::Mutex;
+
+use crate::{
+    error::ParseError, Account, Amount, ApplicationId, BlockHash, ChainId, Error, Event, Owner,
+};
+
+type Storage = DbStorage<MemoryStore, WallClock>;
+type Environment = environment::Impl<Storage, NodeProvider>;
+
+/// The maximal number of stream queries to the in-memory storage.
+const MAX_STREAM_QUERIES: usize = 10;
+
+/// The number of clients created in this process, to give each one its own storage.
+static CLIENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A connection to a Linera network, with a key to own chains.
+///
+/// Cloning a client is cheap: the clones share their chains.
+#[derive(Clone)]
+pub struct Client {
+    context: Arc<Mutex<ClientContext<Environment, persistent::Memory<Wallet>>>>,
+    faucet: Option<Faucet>,
+    owner: AccountOwner,
+}
+
+impl Client {
+    /// Connects to the network of the faucet at the given URL, which can then give new
+    /// chains to this client.
+    pub async fn connect(faucet_url: impl Into<String>) -> Result<Self, Error> {
+        let faucet = Faucet::new(faucet_url.into());
+        let genesis_config = faucet.genesis_config().await.map_err(Error::faucet)?;
+        let mut client = Self::with_genesis_config(genesis_config).await?;
+        client.faucet = Some(faucet);
+        Ok(client)
+    }
+
+    /// Connects to the network described by the given genesis configuration, in JSON, as
+    /// created by `linera-server generate`. Such a client cannot claim chains from a faucet.
+    pub async fn connect_with_genesis_config(genesis_config: &str) -> Result<Self, Error> {
+        let genesis_config = serde_json::from_str(genesis_config)
+            .map_err(|error| ParseError::new("genesis configuration", error))?;
+        Self::with_genesis_config(genesis_config).await
+    }
+
+    async fn with_genesis_config(genesis_config: GenesisConfig) -> Result<Self, Error> {
+        let namespace = format!(
+            "linera_client_api_{}",
+            CLIENT_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut storage = Storage::maybe_create_and_connect(
+            &MemoryStoreConfig::new(MAX_STREAM_QUERIES),
+            &namespace,
+            None::<WasmRuntime>.with_wasm_default(),
+        )
+        .await
+        .map_err(Error::storage)?;
+        genesis_config
+            .initialize_storage(&mut storage)
+            .await
+            .map_err(Error::storage)?;
+        let mut signer = InMemorySigner::new(None);
+        let owner = signer.generate_new().into();
+        // The default values of all the options of the `linera` tool.
+        let options = ClientContextOptions::parse_from(["linera-client-api", "--quiet"]);
+        let wallet = persistent::Memory::new(Wallet::new(genesis_config));
+        let context = ClientContext::new(storage, options, wallet, Box::new(signer));
+        Ok(Client {
+            context: Arc::new(Mutex::new(context)),
+            faucet: None,
+            owner,
+        })
+    }
+
+    /// Returns the owner of the chains of this client, i.e. its public key.
+    pub fn owner(&self) -> Owner {
+        Owner(self.owner)
+    }
+
+    /// Returns the chains owned by this client.
+    pub async fn chains(&self) -> Vec<ChainId> {
+        let context = self.context.lock().await;
+        let chain_ids = context.wallet().owned_chain_ids();
+        chain_ids.into_iter().map(ChainId).collect()
+    }
+
+    /// Requests a new chain from the faucet, owned by this client.
+    pub async fn claim_chain(&self) -> Result<Chain, Error> {
+        let faucet = self.faucet.as_ref().ok_or(Error::NoFaucet)?;
+        let outcome = faucet.claim(&self.owner).await.map_err(Error::faucet)?;
+        let chain = self.assign_chain(outcome.chain_id).await?;
+        chain.synchronize().await?;
+        Ok(chain)
+    }
+
+    /// Returns the chain with the given ID. Only the chains owned by this client, e.g. the
+    /// ones from [`Client::claim_chain`] or [`Chain::open_chain`], can add blocks: the others
+    /// can only be queried.
+    pub async fn chain(&self, chain_id: ChainId) -> Result<Chain, Error> {
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id.0)
+            .await
+            .map_err(Error::chain)?;
+        Ok(self.make_chain(client))
+    }
+
+    /// Records that this client now owns the given chain.
+    async fn assign_chain(
+        &self,
+        chain_id: linera_base::identifiers::ChainId,
+    ) -> Result<Chain, Error> {
+        let mut context = self.context.lock().await;
+        context
+            .assign_new_chain_to_key(chain_id, self.owner)
+            .await
+            .map_err(Error::chain)?;
+        let client = context
+            .make_chain_client(chain_id)
+            .await
+            .map_err(Error::chain)?;
+        Ok(self.make_chain(client))
+    }
+
+    fn make_chain(&self, client: ChainClient<Environment>) -> Chain {
+        Chain {
+            client,
+            owner: self.owner,
+            parent: self.clone(),
+        }
+    }
+}
+
+/// A chain, to add blocks to it or to query its state.
+#[derive(Clone)]
+pub struct Chain {
+    client: ChainClient<Environment>,
+    owner: AccountOwner,
+    parent: Client,
+}
+
+impl Chain {
+    /// Returns the ID of the chain.
+    pub fn id(&self) -> ChainId {
+        ChainId(self.client.chain_id())
+    }
+
+    /// Downloads the blocks of the chain and of its incoming messages that the client does
+    /// not know yet from the validators.
+    pub async fn synchronize(&self) -> Result<(), Error> {
+        self.client
+            .synchronize_from_validators()
+            .await
+            .map_err(Error::chain)?;
+        Ok(())
+    }
+
+    /// Synchronizes the chain and returns its balance, including the incoming transfers
+    /// that no block received yet.
+    pub async fn balance(&self) -> Result<Amount, Error> {
+        self.synchronize().await?;
+        let balance = self.client.query_balance().await.map_err(Error::chain)?;
+        Ok(Amount(balance))
+    }
+
+    /// Transfers tokens from the chain's balance to an account, and returns the hash of the
+    /// block doing it.
+    pub async fn transfer(&self, amount: Amount, recipient: Account) -> Result<BlockHash, Error> {
+        let certificate = self
+            .commit(|client| async move {
+                client
+                    .transfer_to_account(AccountOwner::CHAIN, amount.0, recipient.0)
+                    .await
+            })
+            .await?;
+        Ok(BlockHash(certificate.hash()))
+    }
+
+    /// Opens a new chain owned by this client, with the given balance taken from this
+    /// chain's balance.
+    pub async fn open_chain(&self, balance: Amount) -> Result<Chain, Error> {
+        let ownership = ChainOwnership::single(self.owner);
+        let (chain_id, _) = self
+            .commit(|client| {
+                let ownership = ownership.clone();
+                async move {
+                    client
+                        .open_chain(ownership, ApplicationPermissions::default(), balance.0)
+                        .await
+                }
+            })
+            .await?;
+        self.parent.assign_chain(chain_id).await
+    }
+
+    /// Runs a GraphQL query against the application's service, on the client's current
+    /// state of the chain, and returns the `data` of the response.
+    pub async fn query_application(
+        &self,
+        application_id: ApplicationId,
+        query: &str,
+    ) -> Result<serde_json::Value, Error> {
+        let (data, _) = self.query_user_application(application_id, query).await?;
+        Ok(data)
+    }
+
+    /// Runs a GraphQL mutation against the application's service, executes the operations
+    /// that it schedules in a new block, and returns the hash of the block.
+    pub async fn call_application(
+        &self,
+        application_id: ApplicationId,
+        mutation: &str,
+    ) -> Result<BlockHash, Error> {
+        let (_, operations) = self
+            .query_user_application(application_id, mutation)
+            .await?;
+        if operations.is_empty() {
+            return Err(Error::NoOperations);
+        }
+        let certificate = self
+            .commit(|client| {
+                let operations = operations.clone();
+                async move { client.execute_operations(operations, vec![]).await }
+            })
+            .await?;
+        Ok(BlockHash(certificate.hash()))
+    }
+
+    /// Listens to the validators' notifications about the chain, keeping the chain up to
+    /// date until the subscription is dropped.
+    pub async fn subscribe(&self) -> Result<Subscription, Error> {
+        let (listener, abort_handle, notifications) =
+            self.client.listen().await.map_err(Error::chain)?;
+        tokio::spawn(listener);
+        Ok(Subscription {
+            notifications,
+            _abort_handle: abort_handle,
+        })
+    }
+
+    async fn query_user_application(
+        &self,
+        application_id: ApplicationId,
+        request: &str,
+    ) -> Result<(serde_json::Value, Vec<Operation>), Error> {
+        let query = Query::User {
+            application_id: application_id.0,
+            bytes: serde_json::to_vec(&serde_json::json!({ "query": request }))
+                .expect("a JSON value can be serialized"),
+        };
+        let QueryOutcome {
+            response,
+            operations,
+        } = self
+            .client
+            .query_application(query)
+            .await
+            .map_err(Error::chain)?;
+        let QueryResponse::User(bytes) = response else {
+            unreachable!("a user application query has a user application response");
+        };
+        let mut response: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(Error::chain)?;
+        if let Some(errors) = response
+            .get("errors")
+            .filter(|errors| errors.as_array().is_some_and(|errors| !errors.is_empty()))
+        {
+            return Err(Error::Application(errors.clone()));
+        }
+        Ok((response["data"].take(), operations))
+    }
+
+    /// Applies the given function to the chain client until it does not need to wait for
+    /// the next round anymore, and returns its result.
+    async fn commit<T, F, Fut>(&self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(ChainClient<Environment>) -> Fut,
+        Fut: Future<Output = Result<ClientOutcome<T>, ChainClientError>>,
+    {
+        let mut subscription = None;
+        loop {
+            self.client.prepare_chain().await.map_err(Error::chain)?;
+            let timeout = match f(self.client.clone()).await.map_err(Error::chain)? {
+                ClientOutcome::Committed(result) => return Ok(result),
+                ClientOutcome::WaitForTimeout(timeout) => timeout,
+            };
+            // Listen to the notifications, to learn about new rounds and blocks.
+            if subscription.is_none() {
+                subscription = Some(self.subscribe().await?);
+            }
+            let subscription = subscription.as_mut().expect("the subscription was created");
+            wait_for_next_round(&mut subscription.notifications, timeout).await;
+        }
+    }
+}
+
+/// A stream of the [`Event`]s of a chain, from [`Chain::subscribe`].
+pub struct Subscription {
+    notifications: NotificationStream,
+    _abort_handle: AbortOnDrop,
+}
+
+impl Stream for Subscription {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.notifications
+            .poll_next_unpin(cx)
+            .map(|notification| notification.map(Event::from_notification))
+    }
+}
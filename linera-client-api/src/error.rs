@@ -0,0 +1,63 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// The errors of the operations of a [`Client`](crate::Client) or a [`Chain`](crate::Chain).
+///
+/// The underlying errors are only available through [`std::error::Error::source`], since
+/// their types are not part of the stable API.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An argument is invalid.
+    #[error(transparent)]
+    InvalidInput(#[from] ParseError),
+    /// The faucet could not be reached, or refused the request.
+    #[error("faucet error")]
+    Faucet(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The client was connected without a faucet.
+    #[error("the client was not connected to a faucet")]
+    NoFaucet,
+    /// The local storage of the client failed.
+    #[error("storage error")]
+    Storage(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// Communicating with the validators or executing a block failed.
+    #[error("chain client error")]
+    Chain(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// An application query or mutation returned GraphQL errors.
+    #[error("the application returned errors: {0}")]
+    Application(serde_json::Value),
+    /// A mutation did not result in any operation to execute.
+    #[error("the mutation did not schedule any operation")]
+    NoOperations,
+}
+
+impl Error {
+    pub(crate) fn faucet(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Error::Faucet(Box::new(error))
+    }
+
+    pub(crate) fn storage(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Error::Storage(Box::new(error))
+    }
+
+    pub(crate) fn chain(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Error::Chain(Box::new(error))
+    }
+}
+
+/// An error parsing one of the [`types`](crate::types), or a genesis configuration.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid {kind}: {message}")]
+pub struct ParseError {
+    kind: &'static str,
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: &'static str, error: impl std::fmt::Display) -> Self {
+        ParseError {
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
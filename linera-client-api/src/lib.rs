@@ -0,0 +1,45 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A stable, high-level library for Rust programs using Linera chains.
+//!
+//! A [`Client`] connects to a Linera network, usually through a faucet, and holds a key
+//! to own chains. A [`Chain`] transfers tokens, opens new chains, calls and queries
+//! applications, and subscribes to the chain's new blocks and messages.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), linera_client_api::Error> {
+//! use linera_client_api::{Account, Amount, Client};
+//!
+//! let client = Client::connect("https://faucet.testnet.linera.net").await?;
+//! let chain = client.claim_chain().await?;
+//! let new_chain = chain.open_chain("1.5".parse()?).await?;
+//! chain
+//!     .transfer(Amount::from_tokens(1), Account::chain(new_chain.id()))
+//!     .await?;
+//! println!("The new chain has {}", new_chain.balance().await?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Stability
+//!
+//! This crate follows semantic versioning on its own, independently of the other Linera
+//! crates, which are internal and change in every release. Its public API only uses its
+//! own types and standard or widely used ones (`serde_json::Value`, `futures::Stream`), so
+//! that internal changes do not break the programs using it. In particular:
+//! * The text and serde formats of the [`types`] are stable, and match the ones of the
+//!   `linera` command-line tool.
+//! * Errors, events and other enums are `#[non_exhaustive]`, so that new cases can be added
+//!   in minor versions.
+//! * The client keeps its chains in memory: nothing is written to disk.
+
+#![deny(missing_docs)]
+
+mod client;
+mod error;
+pub mod types;
+
+pub use client::{Chain, Client, Subscription};
+pub use error::{Error, ParseError};
+pub use types::{Account, Amount, ApplicationId, BlockHash, ChainId, Event, Owner};
@@ -0,0 +1,223 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The identifiers and values used by the client.
+//!
+//! All of them are serialized as strings, in the same format as their [`Display`]
+//! implementation, which [`FromStr`] parses.
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use linera_base::{crypto::CryptoHash, data_types, identifiers};
+use linera_core::worker::{Notification, Reason};
+use serde::Serialize;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+use crate::error::ParseError;
+
+/// Implements `Display` and `FromStr` for a wrapper of an internal type, using the formats of
+/// the internal type.
+macro_rules! impl_text_format {
+    ($name:ident, $kind:literal) => {
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, ParseError> {
+                s.parse()
+                    .map($name)
+                    .map_err(|error| ParseError::new($kind, error))
+            }
+        }
+    };
+}
+
+/// The ID of a chain.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct ChainId(pub(crate) identifiers::ChainId);
+
+impl_text_format!(ChainId, "chain ID");
+
+/// The owner of an account: a public key, or an application.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct Owner(pub(crate) identifiers::AccountOwner);
+
+impl_text_format!(Owner, "owner");
+
+/// An account: the balance of a chain, or of an owner on a chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, SerializeDisplay, DeserializeFromStr)]
+pub struct Account(pub(crate) identifiers::Account);
+
+impl_text_format!(Account, "account");
+
+impl Account {
+    /// Returns the account of the chain's own balance.
+    pub fn chain(chain_id: ChainId) -> Self {
+        Account(identifiers::Account::chain(chain_id.0))
+    }
+
+    /// Returns the account of `owner` on the chain.
+    pub fn new(chain_id: ChainId, owner: Owner) -> Self {
+        Account(identifiers::Account::new(chain_id.0, owner.0))
+    }
+
+    /// Returns the chain of the account.
+    pub fn chain_id(&self) -> ChainId {
+        ChainId(self.0.chain_id)
+    }
+
+    /// Returns the owner of the account, or `None` for the chain's own balance.
+    pub fn owner(&self) -> Option<Owner> {
+        (self.0.owner != identifiers::AccountOwner::CHAIN).then_some(Owner(self.0.owner))
+    }
+}
+
+/// An amount of tokens, written in tokens with up to 18 decimals, e.g. `1.5`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    SerializeDisplay,
+    DeserializeFromStr,
+)]
+pub struct Amount(pub(crate) data_types::Amount);
+
+impl_text_format!(Amount, "amount");
+
+impl Amount {
+    /// No tokens.
+    pub const ZERO: Amount = Amount(data_types::Amount::ZERO);
+
+    /// Returns the amount of that many tokens, or the maximal amount if saturated.
+    pub fn from_tokens(tokens: u128) -> Self {
+        Amount(data_types::Amount::from_tokens(tokens))
+    }
+
+    /// Returns the amount of that many attotokens, i.e. 10^-18 tokens.
+    pub fn from_attos(attos: u128) -> Self {
+        Amount(data_types::Amount::from_attos(attos))
+    }
+
+    /// Returns the number of attotokens, i.e. 10^-18 tokens, of this amount.
+    pub fn attos(self) -> u128 {
+        u128::from(self.0)
+    }
+}
+
+/// The hash of a block.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct BlockHash(pub(crate) CryptoHash);
+
+impl_text_format!(BlockHash, "block hash");
+
+/// The ID of an application.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct ApplicationId(pub(crate) identifiers::ApplicationId);
+
+// Application IDs have no text format of their own, but are serialized as strings.
+impl Display for ApplicationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_value(self.0) {
+            Ok(serde_json::Value::String(string)) => f.write_str(&string),
+            _ => Err(fmt::Error),
+        }
+    }
+}
+
+impl FromStr for ApplicationId {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        serde_json::from_value(serde_json::Value::String(s.to_owned()))
+            .map(ApplicationId)
+            .map_err(|error| ParseError::new("application ID", error))
+    }
+}
+
+/// Something that happened on a chain, reported by a [`Subscription`](crate::Subscription).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Event {
+    /// A block was added to the chain.
+    NewBlock {
+        /// The height of the block.
+        height: u64,
+        /// The hash of the block.
+        hash: BlockHash,
+    },
+    /// Messages were sent to the chain, and are now in its inbox.
+    NewIncomingBundle {
+        /// The chain that sent the messages.
+        origin: ChainId,
+        /// The height of the block that sent the messages.
+        height: u64,
+    },
+    /// The validators started a new round for the next block of the chain.
+    NewRound {
+        /// The height of the next block.
+        height: u64,
+    },
+}
+
+impl Event {
+    pub(crate) fn from_notification(notification: Notification) -> Self {
+        match notification.reason {
+            Reason::NewBlock { height, hash, .. } => Event::NewBlock {
+                height: height.into(),
+                hash: BlockHash(hash),
+            },
+            Reason::NewIncomingBundle { origin, height } => Event::NewIncomingBundle {
+                origin: ChainId(origin),
+                height: height.into(),
+            },
+            Reason::NewRound { height, .. } => Event::NewRound {
+                height: height.into(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Account, Amount, ChainId};
+
+    #[test]
+    fn test_text_formats_round_trip() {
+        let amount: Amount = "1.5".parse().unwrap();
+        assert_eq!(amount.attos(), 1_500_000_000_000_000_000);
+        assert_eq!(amount.to_string(), "1.5");
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"1.5\"");
+        assert!("one".parse::<Amount>().is_err());
+
+        let chain_id_string = "e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65";
+        let chain_id: ChainId = chain_id_string.parse().unwrap();
+        assert_eq!(chain_id.to_string(), chain_id_string);
+        let account = Account::chain(chain_id);
+        assert_eq!(account.chain_id(), chain_id);
+        assert_eq!(account.owner(), None);
+        assert_eq!(account.to_string().parse::<Account>().unwrap(), account);
+    }
+}
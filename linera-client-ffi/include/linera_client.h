@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * C bindings of the Linera client library. See the documentation of the
+ * `linera-client-ffi` crate for the conventions followed by these functions.
+ */
+
+#ifndef LINERA_CLIENT_H
+#define LINERA_CLIENT_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The outcome of a call. Unless it is LINERA_STATUS_OK, `linera_last_error`
+ * describes the error. */
+typedef enum LineraStatus {
+    LINERA_STATUS_OK = 0,
+    LINERA_STATUS_INVALID_ARGUMENT = 1,
+    LINERA_STATUS_FAILED = 2,
+    LINERA_STATUS_PANIC = 3,
+} LineraStatus;
+
+/* A client connected to a Linera network. */
+typedef struct LineraClient LineraClient;
+
+/* Returns the message of the last error on this thread, or NULL if the last call
+ * succeeded. The message is valid until the next call on this thread. */
+const char *linera_last_error(void);
+
+/* Frees a string written by the library. */
+void linera_string_free(char *string);
+
+/* Connects a new client through the faucet at the given URL. */
+LineraStatus linera_client_connect(const char *faucet_url, LineraClient **out_client);
+
+/* Connects a new client to the network of a genesis configuration, in JSON. */
+LineraStatus linera_client_connect_with_genesis_config(const char *genesis_config,
+                                                       LineraClient **out_client);
+
+/* Frees a client. */
+void linera_client_free(LineraClient *client);
+
+/* Writes the owner of the client's chains. */
+LineraStatus linera_client_owner(const LineraClient *client, char **out_owner);
+
+/* Writes the IDs of the chains owned by the client, as a JSON array. */
+LineraStatus linera_client_chains(const LineraClient *client, char **out_chain_ids);
+
+/* Requests a new chain from the faucet, and writes its ID. */
+LineraStatus linera_client_claim_chain(const LineraClient *client, char **out_chain_id);
+
+/* Synchronizes the chain, and writes its balance. */
+LineraStatus linera_chain_balance(const LineraClient *client, const char *chain_id,
+                                  char **out_balance);
+
+/* Transfers tokens to an account, and writes the hash of the block. */
+LineraStatus linera_chain_transfer(const LineraClient *client, const char *chain_id,
+                                   const char *amount, const char *recipient,
+                                   char **out_block_hash);
+
+/* Opens a new chain owned by the client, and writes its ID. */
+LineraStatus linera_chain_open_chain(const LineraClient *client, const char *chain_id,
+                                     const char *balance, char **out_chain_id);
+
+/* Runs a GraphQL query against an application, and writes the data in JSON. */
+LineraStatus linera_chain_query_application(const LineraClient *client, const char *chain_id,
+                                            const char *application_id, const char *query,
+                                            char **out_json);
+
+/* Runs a GraphQL mutation against an application, executes its operations in a new
+ * block, and writes the hash of the block. */
+LineraStatus linera_chain_call_application(const LineraClient *client, const char *chain_id,
+                                           const char *application_id, const char *mutation,
+                                           char **out_block_hash);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LINERA_CLIENT_H */
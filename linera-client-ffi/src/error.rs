@@ -0,0 +1,114 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+/// The outcome of a call to one of the functions of the library.
+///
+/// Unless it is `LINERA_STATUS_OK`, `linera_last_error` describes the error.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineraStatus {
+    /// The call succeeded, and its outputs were written.
+    Ok = 0,
+    /// An argument is null, not valid UTF-8, or cannot be parsed.
+    InvalidArgument = 1,
+    /// The operation failed, e.g. because the validators could not be reached.
+    Failed = 2,
+    /// The library panicked: this is a bug.
+    Panic = 3,
+}
+
+/// An error of a call to the library.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum FfiError {
+    #[error("the argument `{0}` is null")]
+    NullPointer(&'static str),
+    #[error("the argument `{0}` is not valid UTF-8")]
+    InvalidUtf8(&'static str),
+    #[error("failed to start the client's runtime: {0}")]
+    Runtime(#[from] std::io::Error),
+    #[error(transparent)]
+    Client(#[from] linera_client_api::Error),
+}
+
+impl From<linera_client_api::ParseError> for FfiError {
+    fn from(error: linera_client_api::ParseError) -> Self {
+        FfiError::Client(error.into())
+    }
+}
+
+impl FfiError {
+    fn status(&self) -> LineraStatus {
+        match self {
+            FfiError::NullPointer(_)
+            | FfiError::InvalidUtf8(_)
+            | FfiError::Client(linera_client_api::Error::InvalidInput(_)) => {
+                LineraStatus::InvalidArgument
+            }
+            FfiError::Runtime(_) | FfiError::Client(_) => LineraStatus::Failed,
+        }
+    }
+
+    /// Returns the message of the error, followed by the ones of its sources.
+    fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            report.push_str(": ");
+            report.push_str(&error.to_string());
+            source = error.source();
+        }
+        report
+    }
+}
+
+thread_local! {
+    /// The message of the last error on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|message| {
+        CString::new(message.replace('\0', " ")).expect("the NUL characters were replaced")
+    });
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+/// Runs the body of a function of the library: catches its panics, so that they do not
+/// unwind into the caller, and records its error for `linera_last_error`.
+pub(crate) fn call(f: impl FnOnce() -> Result<(), FfiError>) -> LineraStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => {
+            set_last_error(None);
+            LineraStatus::Ok
+        }
+        Ok(Err(error)) => {
+            set_last_error(Some(error.report()));
+            error.status()
+        }
+        Err(_) => {
+            set_last_error(Some("the Linera client panicked".to_owned()));
+            LineraStatus::Panic
+        }
+    }
+}
+
+/// Returns the message of the last error of a call on this thread, or null if the last call
+/// succeeded.
+///
+/// The message is owned by the library, and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn linera_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
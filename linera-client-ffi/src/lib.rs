@@ -0,0 +1,414 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! C bindings of [`linera_client_api`], to embed Linera clients in Swift, Kotlin, Python
+//! and other programs that can call C functions.
+//!
+//! The functions are declared in `include/linera_client.h`. They follow these conventions:
+//! * They return a [`LineraStatus`], and on failure `linera_last_error` describes the error.
+//! * Identifiers, amounts and hashes are passed as UTF-8 strings, in the same formats as
+//!   the `linera` command-line tool. Application queries and their results are JSON.
+//! * Output strings are written to `out_*` arguments, and must be freed with
+//!   `linera_string_free`. Clients must be freed with `linera_client_free`.
+//! * The functions block until their operation completes. A client can be used from
+//!   several threads at once.
+
+mod error;
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    fmt::Display,
+    future::Future,
+    str::FromStr,
+};
+
+use linera_client_api::{Chain, ChainId, Client, ParseError};
+use tokio::runtime::Runtime;
+
+use crate::error::{call, FfiError};
+pub use crate::error::{linera_last_error, LineraStatus};
+
+/// A client connected to a Linera network, with the runtime that runs its tasks.
+pub struct LineraClient {
+    runtime: Runtime,
+    client: Client,
+}
+
+impl LineraClient {
+    /// Starts a runtime, connects a client with it, and returns the new client.
+    fn start<Fut>(connect: impl FnOnce() -> Fut) -> Result<*mut LineraClient, FfiError>
+    where
+        Fut: Future<Output = Result<Client, linera_client_api::Error>>,
+    {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let client = runtime.block_on(connect())?;
+        Ok(Box::into_raw(Box::new(LineraClient { runtime, client })))
+    }
+
+    /// Runs `f` on the given chain of the client, and waits for its result.
+    fn with_chain<T, F, Fut>(&self, chain_id: ChainId, f: F) -> Result<T, FfiError>
+    where
+        F: FnOnce(Chain) -> Fut,
+        Fut: Future<Output = Result<T, linera_client_api::Error>>,
+    {
+        let result = self.runtime.block_on(async {
+            let chain = self.client.chain(chain_id).await?;
+            f(chain).await
+        })?;
+        Ok(result)
+    }
+}
+
+/// Reads a string argument.
+///
+/// # Safety
+///
+/// The pointer must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn read_str<'a>(string: *const c_char, name: &'static str) -> Result<&'a str, FfiError> {
+    if string.is_null() {
+        return Err(FfiError::NullPointer(name));
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| FfiError::InvalidUtf8(name))
+}
+
+/// Reads and parses a string argument.
+///
+/// # Safety
+///
+/// The pointer must be null or point to a NUL-terminated string.
+unsafe fn parse_arg<T>(string: *const c_char, name: &'static str) -> Result<T, FfiError>
+where
+    T: FromStr<Err = ParseError>,
+{
+    Ok(read_str(string, name)?.parse()?)
+}
+
+/// Returns the location of an output.
+///
+/// # Safety
+///
+/// The pointer must be null or valid for writes during `'a`.
+unsafe fn output<'a, T>(location: *mut T, name: &'static str) -> Result<&'a mut T, FfiError> {
+    location.as_mut().ok_or(FfiError::NullPointer(name))
+}
+
+/// Returns a string to be freed by `linera_string_free`.
+fn into_c_string(value: impl Display) -> *mut c_char {
+    CString::new(value.to_string())
+        .expect("the text formats and JSON have no NUL characters")
+        .into_raw()
+}
+
+/// Returns the client at the given pointer.
+///
+/// # Safety
+///
+/// The pointer must be null or come from `linera_client_connect` or
+/// `linera_client_connect_with_genesis_config`, and not be freed yet.
+unsafe fn client_ref<'a>(client: *const LineraClient) -> Result<&'a LineraClient, FfiError> {
+    client.as_ref().ok_or(FfiError::NullPointer("client"))
+}
+
+/// Connects a new client to the network of the faucet at the given URL, which can then
+/// give new chains to the client, and writes it to `out_client`.
+///
+/// # Safety
+///
+/// `faucet_url` must be a NUL-terminated string and `out_client` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_connect(
+    faucet_url: *const c_char,
+    out_client: *mut *mut LineraClient,
+) -> LineraStatus {
+    call(|| {
+        let faucet_url = read_str(faucet_url, "faucet_url")?;
+        let out_client = output(out_client, "out_client")?;
+        *out_client = LineraClient::start(|| Client::connect(faucet_url))?;
+        Ok(())
+    })
+}
+
+/// Connects a new client to the network described by the given genesis configuration, in
+/// JSON, and writes it to `out_client`. Such a client cannot claim chains from a faucet.
+///
+/// # Safety
+///
+/// `genesis_config` must be a NUL-terminated string and `out_client` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_connect_with_genesis_config(
+    genesis_config: *const c_char,
+    out_client: *mut *mut LineraClient,
+) -> LineraStatus {
+    call(|| {
+        let genesis_config = read_str(genesis_config, "genesis_config")?;
+        let out_client = output(out_client, "out_client")?;
+        *out_client = LineraClient::start(|| Client::connect_with_genesis_config(genesis_config))?;
+        Ok(())
+    })
+}
+
+/// Frees a client, and its keys: the chains it owns can no longer add blocks. Does nothing
+/// if `client` is null.
+///
+/// # Safety
+///
+/// `client` must be null or a client that was not freed yet, and that no other thread uses.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_free(client: *mut LineraClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Frees a string written by the library. Does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string written by the library that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn linera_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Writes the owner of the client's chains, i.e. its public key, to `out_owner`.
+///
+/// # Safety
+///
+/// `client` must be a valid client and `out_owner` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_owner(
+    client: *const LineraClient,
+    out_owner: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        *output(out_owner, "out_owner")? = into_c_string(client.client.owner());
+        Ok(())
+    })
+}
+
+/// Writes the IDs of the chains owned by the client to `out_chain_ids`, as a JSON array of
+/// strings.
+///
+/// # Safety
+///
+/// `client` must be a valid client and `out_chain_ids` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_chains(
+    client: *const LineraClient,
+    out_chain_ids: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let out_chain_ids = output(out_chain_ids, "out_chain_ids")?;
+        let chain_ids = client.runtime.block_on(client.client.chains());
+        let json = serde_json::to_string(&chain_ids).expect("chain IDs can be serialized");
+        *out_chain_ids = into_c_string(json);
+        Ok(())
+    })
+}
+
+/// Requests a new chain from the faucet, owned by the client, and writes its ID to
+/// `out_chain_id`.
+///
+/// # Safety
+///
+/// `client` must be a valid client and `out_chain_id` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_client_claim_chain(
+    client: *const LineraClient,
+    out_chain_id: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let out_chain_id = output(out_chain_id, "out_chain_id")?;
+        let chain = client.runtime.block_on(client.client.claim_chain())?;
+        *out_chain_id = into_c_string(chain.id());
+        Ok(())
+    })
+}
+
+/// Synchronizes the chain with the validators, and writes its balance to `out_balance`.
+///
+/// # Safety
+///
+/// `client` must be a valid client, `chain_id` a NUL-terminated string, and `out_balance`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_chain_balance(
+    client: *const LineraClient,
+    chain_id: *const c_char,
+    out_balance: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let chain_id = parse_arg(chain_id, "chain_id")?;
+        let out_balance = output(out_balance, "out_balance")?;
+        let balance = client.with_chain(chain_id, |chain| async move { chain.balance().await })?;
+        *out_balance = into_c_string(balance);
+        Ok(())
+    })
+}
+
+/// Transfers tokens from the chain's balance to an account, and writes the hash of the
+/// block doing it to `out_block_hash`.
+///
+/// # Safety
+///
+/// `client` must be a valid client, `chain_id`, `amount` and `recipient` NUL-terminated
+/// strings, and `out_block_hash` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_chain_transfer(
+    client: *const LineraClient,
+    chain_id: *const c_char,
+    amount: *const c_char,
+    recipient: *const c_char,
+    out_block_hash: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let chain_id = parse_arg(chain_id, "chain_id")?;
+        let amount = parse_arg(amount, "amount")?;
+        let recipient = parse_arg(recipient, "recipient")?;
+        let out_block_hash = output(out_block_hash, "out_block_hash")?;
+        let hash = client.with_chain(chain_id, |chain| async move {
+            chain.transfer(amount, recipient).await
+        })?;
+        *out_block_hash = into_c_string(hash);
+        Ok(())
+    })
+}
+
+/// Opens a new chain owned by the client, with the given balance taken from the chain's
+/// balance, and writes its ID to `out_chain_id`.
+///
+/// # Safety
+///
+/// `client` must be a valid client, `chain_id` and `balance` NUL-terminated strings, and
+/// `out_chain_id` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_chain_open_chain(
+    client: *const LineraClient,
+    chain_id: *const c_char,
+    balance: *const c_char,
+    out_chain_id: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let chain_id = parse_arg(chain_id, "chain_id")?;
+        let balance = parse_arg(balance, "balance")?;
+        let out_chain_id = output(out_chain_id, "out_chain_id")?;
+        let new_chain =
+            client.with_chain(
+                chain_id,
+                |chain| async move { chain.open_chain(balance).await },
+            )?;
+        *out_chain_id = into_c_string(new_chain.id());
+        Ok(())
+    })
+}
+
+/// Runs a GraphQL query against an application's service on the chain, and writes the
+/// `data` of the response, in JSON, to `out_json`.
+///
+/// # Safety
+///
+/// `client` must be a valid client, `chain_id`, `application_id` and `query` NUL-terminated
+/// strings, and `out_json` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_chain_query_application(
+    client: *const LineraClient,
+    chain_id: *const c_char,
+    application_id: *const c_char,
+    query: *const c_char,
+    out_json: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let chain_id = parse_arg(chain_id, "chain_id")?;
+        let application_id = parse_arg(application_id, "application_id")?;
+        let query = read_str(query, "query")?;
+        let out_json = output(out_json, "out_json")?;
+        let data = client.with_chain(chain_id, |chain| async move {
+            chain.query_application(application_id, query).await
+        })?;
+        *out_json = into_c_string(data);
+        Ok(())
+    })
+}
+
+/// Runs a GraphQL mutation against an application's service on the chain, executes the
+/// operations that it schedules in a new block, and writes the hash of the block to
+/// `out_block_hash`.
+///
+/// # Safety
+///
+/// `client` must be a valid client, `chain_id`, `application_id` and `mutation`
+/// NUL-terminated strings, and `out_block_hash` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn linera_chain_call_application(
+    client: *const LineraClient,
+    chain_id: *const c_char,
+    application_id: *const c_char,
+    mutation: *const c_char,
+    out_block_hash: *mut *mut c_char,
+) -> LineraStatus {
+    call(|| {
+        let client = client_ref(client)?;
+        let chain_id = parse_arg(chain_id, "chain_id")?;
+        let application_id = parse_arg(application_id, "application_id")?;
+        let mutation = read_str(mutation, "mutation")?;
+        let out_block_hash = output(out_block_hash, "out_block_hash")?;
+        let hash = client.with_chain(chain_id, |chain| async move {
+            chain.call_application(application_id, mutation).await
+        })?;
+        *out_block_hash = into_c_string(hash);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CStr, ptr};
+
+    use super::{
+        linera_chain_balance, linera_client_connect, linera_client_connect_with_genesis_config,
+        linera_last_error, LineraStatus,
+    };
+
+    fn last_error() -> String {
+        let message = linera_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let mut client = ptr::null_mut();
+        let status =
+            unsafe { linera_client_connect_with_genesis_config(c"not JSON".as_ptr(), &mut client) };
+        assert_eq!(status, LineraStatus::InvalidArgument);
+        assert!(client.is_null());
+        assert!(last_error().starts_with("invalid genesis configuration"));
+
+        let status = unsafe { linera_client_connect(ptr::null(), &mut client) };
+        assert_eq!(status, LineraStatus::InvalidArgument);
+        assert_eq!(last_error(), "the argument `faucet_url` is null");
+
+        let mut balance = ptr::null_mut();
+        let status = unsafe { linera_chain_balance(ptr::null(), c"".as_ptr(), &mut balance) };
+        assert_eq!(status, LineraStatus::InvalidArgument);
+        assert_eq!(last_error(), "the argument `client` is null");
+        assert!(balance.is_null());
+    }
+}
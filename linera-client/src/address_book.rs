@@ -0,0 +1,163 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable aliases for the chains and accounts that a wallet sends to.
+//!
+//! The [`AddressBook`] is stored in the wallet. Commands that expect a chain or an account
+//! accept either its usual form, e.g. `CHAIN-ID:OWNER`, or the name of an alias, which is
+//! resolved with [`AddressBook::resolve_account`] or [`AddressBook::resolve_chain`].
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use linera_base::{
+    ensure,
+    identifiers::{Account, ChainId},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error, wallet::Wallet, Error};
+
+/// The aliases of a wallet, by name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AddressBook {
+    aliases: BTreeMap<String, Account>,
+}
+
+/// An account, given either directly or by its alias in the address book.
+#[derive(Clone, Debug)]
+pub enum AccountOrAlias {
+    Account(Account),
+    Alias(String),
+}
+
+/// A chain, given either by its ID or by its alias in the address book.
+#[derive(Clone, Debug)]
+pub enum ChainOrAlias {
+    Chain(ChainId),
+    Alias(String),
+}
+
+/// Returns whether `name` can be used as an alias: it starts with a letter, contains only
+/// ASCII letters, digits, `-` and `_`, and is not a chain ID.
+pub fn is_valid_alias(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && name.parse::<ChainId>().is_err()
+}
+
+impl AddressBook {
+    /// Adds an alias for an account. Unless `overwrite` is set, an existing alias with the
+    /// same name is an error.
+    pub fn add(&mut self, name: String, account: Account, overwrite: bool) -> Result<(), Error> {
+        ensure!(is_valid_alias(&name), error::Inner::InvalidAlias(name));
+        ensure!(
+            overwrite || !self.aliases.contains_key(&name),
+            error::Inner::AliasExists(name)
+        );
+        self.aliases.insert(name, account);
+        Ok(())
+    }
+
+    /// Removes an alias, and returns the account it stood for.
+    pub fn remove(&mut self, name: &str) -> Result<Account, Error> {
+        Ok(self
+            .aliases
+            .remove(name)
+            .ok_or_else(|| error::Inner::UnknownAlias(name.to_owned()))?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Account> {
+        self.aliases.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Account)> {
+        self.aliases.iter()
+    }
+
+    /// Returns whether some alias stands for the given chain, or for an account on it.
+    pub fn knows_chain(&self, chain_id: ChainId) -> bool {
+        self.aliases
+            .values()
+            .any(|account| account.chain_id == chain_id)
+    }
+
+    pub fn resolve_account(&self, account: &AccountOrAlias) -> Result<Account, Error> {
+        match account {
+            AccountOrAlias::Account(account) => Ok(*account),
+            AccountOrAlias::Alias(name) => Ok(*self
+                .get(name)
+                .ok_or_else(|| error::Inner::UnknownAlias(name.clone()))?),
+        }
+    }
+
+    /// Resolves a chain. An alias for an account stands for the account's chain.
+    pub fn resolve_chain(&self, chain: &ChainOrAlias) -> Result<ChainId, Error> {
+        match chain {
+            ChainOrAlias::Chain(chain_id) => Ok(*chain_id),
+            ChainOrAlias::Alias(name) => Ok(self
+                .get(name)
+                .ok_or_else(|| error::Inner::UnknownAlias(name.clone()))?
+                .chain_id),
+        }
+    }
+}
+
+impl AccountOrAlias {
+    /// Returns whether this is a raw account whose chain is neither in the wallet nor in its
+    /// address book.
+    pub fn is_unknown_to(&self, wallet: &Wallet) -> bool {
+        match self {
+            AccountOrAlias::Account(account) => {
+                wallet.get(account.chain_id).is_none()
+                    && !wallet.address_book.knows_chain(account.chain_id)
+            }
+            AccountOrAlias::Alias(_) => false,
+        }
+    }
+}
+
+impl FromStr for AccountOrAlias {
+    type Err = <Account as FromStr>::Err;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if is_valid_alias(string) {
+            return Ok(AccountOrAlias::Alias(string.to_owned()));
+        }
+        Ok(AccountOrAlias::Account(string.parse()?))
+    }
+}
+
+impl FromStr for ChainOrAlias {
+    type Err = <ChainId as FromStr>::Err;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if is_valid_alias(string) {
+            return Ok(ChainOrAlias::Alias(string.to_owned()));
+        }
+        Ok(ChainOrAlias::Chain(string.parse()?))
+    }
+}
+
+impl fmt::Display for AccountOrAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountOrAlias::Account(account) => write!(f, "{account}"),
+            AccountOrAlias::Alias(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl fmt::Display for ChainOrAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainOrAlias::Chain(chain_id) => write!(f, "{chain_id}"),
+            ChainOrAlias::Alias(name) => write!(f, "{name}"),
+        }
+    }
+}
@@ -13,7 +13,12 @@ use linera_chain::{
     data_types::{BlockProposal, ProposedBlock},
     types::ConfirmedBlock,
 };
-use linera_core::{client::ChainClient, local_node::LocalNodeClient, Environment};
+use linera_core::{
+    client::ChainClient,
+    local_node::LocalNodeClient,
+    progress::{Progress, ProgressReporter, ProgressTask, ProgressUnit},
+    Environment,
+};
 use linera_execution::{
     committee::Committee,
     system::{Recipient, SystemOperation},
@@ -100,6 +105,7 @@ impl<Env: Environment> Benchmark<Env> {
         committee: Committee,
         local_node: LocalNodeClient<Env::Storage>,
         health_check_endpoints: Option<String>,
+        progress_reporter: Arc<dyn ProgressReporter>,
     ) -> Result<(), BenchmarkError> {
         let shutdown_notifier = CancellationToken::new();
         tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
@@ -116,13 +122,25 @@ impl<Env: Environment> Benchmark<Env> {
         // the desired BPS, the tasks would continue sending block proposals until the channel's
         // buffer is filled, which would cause us to not properly control the BPS rate.
         let (sender, receiver) = crossbeam_channel::bounded(0);
+        let progress = Progress::start(
+            progress_reporter,
+            ProgressTask::Benchmark,
+            ProgressUnit::Blocks,
+            0,
+            None,
+        );
         let bps_control_task = task::spawn_blocking(move || {
             handle.block_on(async move {
                 let mut recv_count = 0;
+                let mut num_blocks = 0;
                 let mut start = time::Instant::now();
                 while let Ok(()) = receiver.recv() {
                     recv_count += 1;
                     if recv_count == num_chains {
+                        // Each task reports once per second at the target BPS, and after
+                        // each block otherwise.
+                        num_blocks += bps.unwrap_or(num_chains) as u64;
+                        progress.advance_to(num_blocks);
                         let elapsed = start.elapsed();
                         if let Some(bps) = bps {
                             let tps =
@@ -0,0 +1,235 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Summaries of what happens on a chain, to show it as it happens.
+//!
+//! The validators' notifications only say that a chain has a new block, message bundle or
+//! round. A [`ChainActivity`] adds the contents of new blocks, read from the local storage:
+//! the messages they received, the applications of their operations and the events they
+//! emitted.
+
+use std::fmt;
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{BlockHeight, Round, Timestamp},
+    identifiers::{AccountOwner, ChainId, GenericApplicationId, StreamId},
+};
+use linera_chain::{block::Block, data_types::MessageAction};
+use linera_core::worker::{Notification, Reason};
+use linera_execution::MessageKind;
+use linera_storage::Storage;
+use serde::Serialize;
+
+use crate::Error;
+
+/// Something that happened on a chain.
+#[derive(Clone, Debug, Serialize)]
+pub enum ChainActivity {
+    /// A new block was added to the chain.
+    NewBlock(BlockSummary),
+    /// A message bundle from another chain was added to the chain's inbox.
+    NewIncomingBundle {
+        chain_id: ChainId,
+        origin: ChainId,
+        height: BlockHeight,
+    },
+    /// The chain moved to a new round.
+    NewRound {
+        chain_id: ChainId,
+        height: BlockHeight,
+        round: Round,
+    },
+}
+
+/// The contents of a block.
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockSummary {
+    pub chain_id: ChainId,
+    pub height: BlockHeight,
+    pub hash: CryptoHash,
+    pub timestamp: Timestamp,
+    pub authenticated_signer: Option<AccountOwner>,
+    /// The messages from other chains that the block accepted or rejected.
+    pub incoming_messages: Vec<IncomingMessageSummary>,
+    /// The application of each operation.
+    pub operations: Vec<GenericApplicationId>,
+    /// The number of messages the block sent.
+    pub outgoing_messages: usize,
+    pub events: Vec<EventSummary>,
+}
+
+/// A message received by a block.
+#[derive(Clone, Debug, Serialize)]
+pub struct IncomingMessageSummary {
+    pub origin: ChainId,
+    /// The height of the block that sent the message.
+    pub height: BlockHeight,
+    pub application_id: GenericApplicationId,
+    pub kind: MessageKind,
+    pub action: MessageAction,
+}
+
+/// An event emitted by a block.
+#[derive(Clone, Debug, Serialize)]
+pub struct EventSummary {
+    pub stream_id: StreamId,
+    pub index: u32,
+    /// The size of the event's value, in bytes.
+    pub size: usize,
+}
+
+impl ChainActivity {
+    /// Creates the [`ChainActivity`] for a notification. The blocks are read from `storage`.
+    pub async fn from_notification<S: Storage>(
+        storage: &S,
+        notification: Notification,
+    ) -> Result<Self, Error> {
+        let chain_id = notification.chain_id;
+        Ok(match notification.reason {
+            Reason::NewBlock { hash, .. } => {
+                let block = storage.read_confirmed_block(hash).await?;
+                ChainActivity::NewBlock(BlockSummary::new(hash, block.block()))
+            }
+            Reason::NewIncomingBundle { origin, height } => ChainActivity::NewIncomingBundle {
+                chain_id,
+                origin,
+                height,
+            },
+            Reason::NewRound { height, round } => ChainActivity::NewRound {
+                chain_id,
+                height,
+                round,
+            },
+        })
+    }
+}
+
+impl BlockSummary {
+    /// Summarizes the block with the given hash.
+    pub fn new(hash: CryptoHash, block: &Block) -> Self {
+        let incoming_messages = block
+            .body
+            .incoming_bundles
+            .iter()
+            .flat_map(|bundle| {
+                bundle
+                    .bundle
+                    .messages
+                    .iter()
+                    .map(|message| IncomingMessageSummary {
+                        origin: bundle.origin,
+                        height: bundle.bundle.height,
+                        application_id: message.message.application_id(),
+                        kind: message.kind,
+                        action: bundle.action,
+                    })
+            })
+            .collect();
+        let events = block
+            .body
+            .events
+            .iter()
+            .flatten()
+            .map(|event| EventSummary {
+                stream_id: event.stream_id.clone(),
+                index: event.index,
+                size: event.value.len(),
+            })
+            .collect();
+        BlockSummary {
+            chain_id: block.header.chain_id,
+            height: block.header.height,
+            hash,
+            timestamp: block.header.timestamp,
+            authenticated_signer: block.header.authenticated_signer,
+            incoming_messages,
+            operations: block
+                .body
+                .operations
+                .iter()
+                .map(|operation| operation.application_id())
+                .collect(),
+            outgoing_messages: block.body.messages.iter().map(Vec::len).sum(),
+            events,
+        }
+    }
+}
+
+/// Formats the system application as `system`, and user applications by their ID.
+struct DisplayApplication<'a>(&'a GenericApplicationId);
+
+impl fmt::Display for DisplayApplication<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            GenericApplicationId::System => write!(f, "system"),
+            GenericApplicationId::User(application_id) => write!(f, "{application_id}"),
+        }
+    }
+}
+
+impl fmt::Display for ChainActivity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainActivity::NewBlock(block) => write!(f, "{block}"),
+            ChainActivity::NewIncomingBundle {
+                chain_id,
+                origin,
+                height,
+            } => writeln!(
+                f,
+                "Chain {chain_id}: new messages from chain {origin}, sent at height {height}"
+            ),
+            ChainActivity::NewRound {
+                chain_id,
+                height,
+                round,
+            } => writeln!(f, "Chain {chain_id}: now in the {round} at height {height}"),
+        }
+    }
+}
+
+impl fmt::Display for BlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Chain {}: block {} {} at {}",
+            self.chain_id, self.height, self.hash, self.timestamp
+        )?;
+        if let Some(signer) = self.authenticated_signer {
+            write!(f, ", signed by {signer}")?;
+        }
+        writeln!(f)?;
+        for message in &self.incoming_messages {
+            let action = match message.action {
+                MessageAction::Accept => "accepted",
+                MessageAction::Reject => "rejected",
+            };
+            writeln!(
+                f,
+                "  {action} {:?} message of {} from chain {}, sent at height {}",
+                message.kind,
+                DisplayApplication(&message.application_id),
+                message.origin,
+                message.height
+            )?;
+        }
+        for application_id in &self.operations {
+            writeln!(f, "  operation of {}", DisplayApplication(application_id))?;
+        }
+        if self.outgoing_messages > 0 {
+            writeln!(f, "  {} outgoing messages", self.outgoing_messages)?;
+        }
+        for event in &self.events {
+            writeln!(
+                f,
+                "  event {} in stream {} of {} ({} bytes)",
+                event.index,
+                String::from_utf8_lossy(&event.stream_id.stream_name.0),
+                DisplayApplication(&event.stream_id.application_id),
+                event.size
+            )?;
+        }
+        Ok(())
+    }
+}
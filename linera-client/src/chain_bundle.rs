@@ -0,0 +1,111 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Portable bundles of a wallet's chain, to use it from another machine.
+//!
+//! A [`ChainBundle`] contains the chain's entry in the wallet: its owner, its tip and its
+//! pending proposal, together with the blobs that proposal publishes. The owner's key is
+//! not included: it has to be in the keystore of the wallet the bundle is imported into.
+//! On import, the validators are asked for the chain, to make sure that they know the
+//! exported tip.
+
+use linera_base::{crypto::CryptoHash, ensure, identifiers::ChainId};
+use linera_core::{client::ChainClient, Environment};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    config::GenesisConfig,
+    error,
+    wallet::{UserChain, Wallet},
+    Error,
+};
+
+/// Everything needed to use a chain of a wallet from another wallet, except the owner's key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChainBundle {
+    /// The hash of the genesis configuration of the chain's network.
+    pub genesis_config_hash: CryptoHash,
+    /// The chain's entry in the wallet.
+    pub chain: UserChain,
+}
+
+impl ChainBundle {
+    /// Creates a bundle with the wallet's entry for the chain.
+    pub fn new(wallet: &Wallet, chain_id: ChainId) -> Result<Self, Error> {
+        let chain = wallet
+            .get(chain_id)
+            .ok_or(error::Inner::NonexistentChain(chain_id))?
+            .clone();
+        Ok(ChainBundle {
+            genesis_config_hash: wallet.genesis_config().hash(),
+            chain,
+        })
+    }
+
+    /// Returns an error if the bundle is for a different network.
+    pub fn check_network(&self, genesis_config: &GenesisConfig) -> Result<(), Error> {
+        ensure!(
+            self.genesis_config_hash == genesis_config.hash(),
+            error::Inner::WrongNetwork(self.genesis_config_hash)
+        );
+        Ok(())
+    }
+
+    /// Downloads the chain from the validators, with a client that starts from the genesis
+    /// state, and checks that its blocks include the exported tip.
+    ///
+    /// Returns the wallet entry to import. If the chain has moved on since it was exported,
+    /// the entry is updated to the latest block, and the pending proposal is dropped.
+    pub async fn verify_tip<Env: Environment>(
+        &self,
+        chain_client: &ChainClient<Env>,
+    ) -> Result<UserChain, Error> {
+        let chain = &self.chain;
+        // The client doesn't know the chain's blocks yet, so it has to ask the validators
+        // for all of them, not only up to its own tip.
+        let info = chain_client.synchronize_chain_state(chain.chain_id).await?;
+        if let Some(expected) = chain.block_hash {
+            let height = chain.next_block_height.try_sub_one()?;
+            ensure!(
+                info.next_block_height > height,
+                error::Inner::UnknownChainTip {
+                    chain_id: chain.chain_id,
+                    height,
+                }
+            );
+            let found = chain_client
+                .chain_state_view()
+                .await?
+                .confirmed_log
+                .get(height.try_into()?)
+                .await?;
+            ensure!(
+                found == Some(expected),
+                error::Inner::InconsistentChainTip {
+                    chain_id: chain.chain_id,
+                    height,
+                    expected,
+                    found,
+                }
+            );
+        }
+        if info.next_block_height == chain.next_block_height {
+            return Ok(chain.clone());
+        }
+        if chain.pending_proposal.is_some() {
+            warn!(
+                "Chain {} has new blocks since it was exported: dropping its pending proposal",
+                chain.chain_id
+            );
+        }
+        Ok(UserChain {
+            chain_id: chain.chain_id,
+            owner: chain.owner,
+            block_hash: info.block_hash,
+            timestamp: info.timestamp,
+            next_block_height: info.next_block_height,
+            pending_proposal: None,
+        })
+    }
+}
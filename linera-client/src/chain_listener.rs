@@ -3,22 +3,25 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
     sync::Arc,
     time::Duration,
 };
 
 use async_trait::async_trait;
 use futures::{
-    future::{join_all, select_all},
+    channel::mpsc,
+    future::{self, join_all, select_all},
     lock::Mutex,
     FutureExt as _, StreamExt,
 };
 use linera_base::{
-    crypto::{CryptoHash, Signer},
+    crypto::Signer,
     data_types::{ChainDescription, Timestamp},
     identifiers::{AccountOwner, BlobType, ChainId},
     task::NonBlockingFuture,
 };
+use linera_chain::block::Block;
 use linera_core::{
     client::{AbortOnDrop, ChainClient, ChainClientError},
     node::NotificationStream,
@@ -26,11 +29,37 @@ use linera_core::{
     Environment,
 };
 use linera_storage::{Clock as _, Storage as _};
+use lru::LruCache;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, instrument, warn, Instrument as _};
 
 use crate::{wallet::Wallet, Error};
 
+#[cfg(with_metrics)]
+mod metrics {
+    use std::sync::LazyLock;
+
+    use linera_base::prometheus_util::register_int_gauge_vec;
+    use prometheus::IntGaugeVec;
+
+    pub static CHAIN_SYNC_LAG_MS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+        register_int_gauge_vec(
+            "chain_listener_sync_lag_ms",
+            "The time between the creation of a chain's latest block and its processing by \
+            the chain listener, in milliseconds",
+            &["chain_id"],
+        )
+    });
+
+    pub static CHAIN_BLOCK_HEIGHT: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+        register_int_gauge_vec(
+            "chain_listener_block_height",
+            "The height of the latest block of a chain processed by the chain listener",
+            &["chain_id"],
+        )
+    });
+}
+
 #[derive(Debug, Default, Clone, clap::Args)]
 pub struct ChainListenerConfig {
     /// Do not create blocks automatically to receive incoming messages. Instead, wait for
@@ -56,6 +85,24 @@ pub struct ChainListenerConfig {
         env = "LINERA_LISTENER_DELAY_AFTER"
     )]
     pub delay_after_ms: u64,
+
+    /// Only create blocks automatically to receive the incoming messages of these chains.
+    /// By default, the inboxes of all the chains with a key are processed.
+    #[arg(
+        long = "listener-process-inbox-of",
+        env = "LINERA_LISTENER_PROCESS_INBOX_OF",
+        value_delimiter = ','
+    )]
+    pub process_inbox_of: Vec<ChainId>,
+
+    /// Only listen to this many chains of the wallet at a time, instead of all of them.
+    /// Chains are only listened to once they are used, e.g. queried in the node service, and
+    /// the least recently used ones stop being listened to if there are more.
+    #[arg(
+        long = "listener-max-active-chains",
+        env = "LINERA_LISTENER_MAX_ACTIVE_CHAINS"
+    )]
+    pub max_active_chains: Option<NonZeroUsize>,
 }
 
 type ContextChainClient<C> = ChainClient<<C as ClientContext>::Environment>;
@@ -135,6 +182,24 @@ impl<C: ClientContext> ListeningClient<C> {
     }
 }
 
+/// Requests a chain listener with a limited number of active chains to listen to a chain
+/// of the wallet, e.g. because it is about to be used.
+///
+/// The default activator does nothing, as does any activator if the listener listens to all
+/// chains anyway.
+#[derive(Clone, Debug, Default)]
+pub struct ChainActivator(Option<mpsc::UnboundedSender<ChainId>>);
+
+impl ChainActivator {
+    /// Marks the chain as used, starting to listen to it if necessary.
+    pub fn activate(&self, chain_id: ChainId) {
+        if let Some(sender) = &self.0 {
+            // The listener may have stopped already, in which case there is nothing to do.
+            let _ = sender.unbounded_send(chain_id);
+        }
+    }
+}
+
 /// A `ChainListener` is a process that listens to notifications from validators and reacts
 /// appropriately.
 pub struct ChainListener<C: ClientContext> {
@@ -145,6 +210,13 @@ pub struct ChainListener<C: ClientContext> {
     /// Map from publishing chain to subscriber chains.
     /// Events emitted on the _publishing chain_ are of interest to the _subscriber chains_.
     event_subscribers: BTreeMap<ChainId, BTreeSet<ChainId>>,
+    /// The chains we listen to, from the most to the least recently used, if their number is
+    /// limited by `max_active_chains`.
+    active_chains: Option<LruCache<ChainId, ()>>,
+    /// A sender for activation requests, handed out by [`ChainListener::activator`].
+    activation_sender: mpsc::UnboundedSender<ChainId>,
+    /// The requests to listen to a chain.
+    activations: mpsc::UnboundedReceiver<ChainId>,
     cancellation_token: CancellationToken,
 }
 
@@ -156,30 +228,48 @@ impl<C: ClientContext> ChainListener<C> {
         storage: <C::Environment as Environment>::Storage,
         cancellation_token: CancellationToken,
     ) -> Self {
+        let active_chains = config.max_active_chains.map(LruCache::new);
+        let (activation_sender, activations) = mpsc::unbounded();
         Self {
             storage,
             context,
             config: Arc::new(config),
             listening: Default::default(),
             event_subscribers: Default::default(),
+            active_chains,
+            activation_sender,
+            activations,
             cancellation_token,
         }
     }
 
+    /// Returns a handle to request listening to chains that are used, if the number of chains
+    /// we listen to is limited.
+    pub fn activator(&self) -> ChainActivator {
+        if self.active_chains.is_some() {
+            ChainActivator(Some(self.activation_sender.clone()))
+        } else {
+            ChainActivator::default()
+        }
+    }
+
     /// Runs the chain listener.
     #[instrument(skip(self))]
     pub async fn run(mut self) -> Result<(), Error> {
-        let chain_ids = {
-            let guard = self.context.lock().await;
-            BTreeSet::from_iter(guard.wallet().chain_ids())
-        };
-        self.listen_recursively(chain_ids).await?;
+        if self.active_chains.is_none() {
+            let chain_ids = {
+                let guard = self.context.lock().await;
+                BTreeSet::from_iter(guard.wallet().chain_ids())
+            };
+            self.listen_recursively(chain_ids).await?;
+        }
         loop {
             match self.next_action().await? {
                 Action::ProcessInbox(chain_id) => self.maybe_process_inbox(chain_id).await?,
                 Action::Notification(notification) => {
                     self.process_notification(notification).await?
                 }
+                Action::Activate(chain_id) => self.activate(chain_id).await,
                 Action::Stop => break,
             }
         }
@@ -187,6 +277,17 @@ impl<C: ClientContext> ChainListener<C> {
         Ok(())
     }
 
+    /// Starts listening to a chain of the wallet that is used, unless we already do.
+    async fn activate(&mut self, chain_id: ChainId) {
+        if self.context.lock().await.wallet().get(chain_id).is_none() {
+            debug!("Not listening to {chain_id:.8}, which is not in the wallet");
+            return;
+        }
+        if let Err(error) = self.listen_recursively(BTreeSet::from([chain_id])).await {
+            warn!(%error, "Failed to start listening to {chain_id:.8}");
+        }
+    }
+
     /// Processes a notification, updating local chains and validators as needed.
     async fn process_notification(&mut self, notification: Notification) -> Result<(), Error> {
         Self::sleep(self.config.delay_before_ms).await;
@@ -198,7 +299,10 @@ impl<C: ClientContext> ChainListener<C> {
             Reason::NewBlock { hash, .. } => {
                 self.update_validators(&notification).await?;
                 self.update_wallet(notification.chain_id).await?;
-                self.add_new_chains(*hash).await?;
+                let block = self.storage.read_confirmed_block(*hash).await?.into_block();
+                #[cfg(with_metrics)]
+                self.record_sync_lag(&block);
+                self.add_new_chains(&block).await?;
                 let publishers = self
                     .update_event_subscriptions(notification.chain_id)
                     .await?;
@@ -215,8 +319,7 @@ impl<C: ClientContext> ChainListener<C> {
 
     /// If any new chains were created by the given block, and we have a key pair for them,
     /// add them to the wallet and start listening for notifications.
-    async fn add_new_chains(&mut self, hash: CryptoHash) -> Result<(), Error> {
-        let block = self.storage.read_confirmed_block(hash).await?.into_block();
+    async fn add_new_chains(&mut self, block: &Block) -> Result<(), Error> {
         let blobs = block.created_blobs().into_iter();
         let new_chains = blobs
             .filter_map(|(blob_id, blob)| {
@@ -260,6 +363,23 @@ impl<C: ClientContext> ChainListener<C> {
         Ok(())
     }
 
+    /// Updates the metrics of how far behind the chain listener is on the block's chain.
+    #[cfg(with_metrics)]
+    fn record_sync_lag(&self, block: &Block) {
+        let chain_id = block.header.chain_id.to_string();
+        let lag = self
+            .storage
+            .clock()
+            .current_time()
+            .delta_since(block.header.timestamp);
+        metrics::CHAIN_SYNC_LAG_MS
+            .with_label_values(&[&chain_id])
+            .set(i64::try_from(lag.as_micros() / 1000).unwrap_or(i64::MAX));
+        metrics::CHAIN_BLOCK_HEIGHT
+            .with_label_values(&[&chain_id])
+            .set(i64::try_from(block.header.height.0).unwrap_or(i64::MAX));
+    }
+
     /// Processes the inboxes of all chains that are subscribed to `chain_id`.
     async fn process_new_events(&mut self, chain_id: ChainId) -> Result<(), Error> {
         let Some(subscribers) = self.event_subscribers.get(&chain_id).cloned() else {
@@ -285,6 +405,9 @@ impl<C: ClientContext> ChainListener<C> {
     /// Returns all publishing chains, that we also need to listen to.
     async fn listen(&mut self, chain_id: ChainId) -> Result<BTreeSet<ChainId>, Error> {
         if self.listening.contains_key(&chain_id) {
+            if let Some(active_chains) = &mut self.active_chains {
+                active_chains.promote(&chain_id);
+            }
             return Ok(BTreeSet::new());
         }
         let client = self
@@ -303,17 +426,42 @@ impl<C: ClientContext> ChainListener<C> {
         let listening_client =
             ListeningClient::new(client, abort_handle, join_handle, notification_stream);
         self.listening.insert(chain_id, listening_client);
+        self.deactivate_least_recently_used(chain_id).await;
         let publishing_chains = self.update_event_subscriptions(chain_id).await?;
         self.maybe_process_inbox(chain_id).await?;
         Ok(publishing_chains)
     }
 
+    /// Marks the chain as the most recently used one, and stops listening to the least
+    /// recently used one if there are too many.
+    async fn deactivate_least_recently_used(&mut self, chain_id: ChainId) {
+        let Some(active_chains) = &mut self.active_chains else {
+            return;
+        };
+        let Some((old_chain_id, ())) = active_chains.push(chain_id, ()) else {
+            return;
+        };
+        if old_chain_id == chain_id {
+            return;
+        }
+        debug!("Stopping to listen to the least recently used chain {old_chain_id:.8}");
+        for subscribers in self.event_subscribers.values_mut() {
+            subscribers.remove(&old_chain_id);
+        }
+        if let Some(listening_client) = self.listening.remove(&old_chain_id) {
+            listening_client.stop().await;
+        }
+    }
+
     /// Updates the event subscribers map, and returns all publishing chains we need to listen to.
     async fn update_event_subscriptions(
         &mut self,
         chain_id: ChainId,
     ) -> Result<BTreeSet<ChainId>, Error> {
-        let listening_client = self.listening.get_mut(&chain_id).expect("missing client");
+        // If the number of active chains is limited, the chain may not be listened to anymore.
+        let Some(listening_client) = self.listening.get_mut(&chain_id) else {
+            return Ok(BTreeSet::new());
+        };
         if !listening_client.client.is_tracked() {
             return Ok(BTreeSet::new());
         }
@@ -330,7 +478,8 @@ impl<C: ClientContext> ChainListener<C> {
     /// Returns the next notification or timeout to process.
     async fn next_action(&mut self) -> Result<Action, Error> {
         loop {
-            let (timeout_chain_id, timeout) = self.next_timeout()?;
+            let next_timeout = self.next_timeout();
+            let storage = &self.storage;
             let notification_futures = self
                 .listening
                 .values_mut()
@@ -339,17 +488,39 @@ impl<C: ClientContext> ChainListener<C> {
                     Box::pin(async move { stream.lock().await.next().await })
                 })
                 .collect::<Vec<_>>();
+            let timeout = async {
+                match next_timeout {
+                    Some((chain_id, timeout)) => {
+                        storage.clock().sleep_until(timeout).await;
+                        chain_id
+                    }
+                    None => future::pending().await,
+                }
+            };
+            // With a limited number of active chains, we may not listen to any chain yet.
+            let next_notification = async {
+                if notification_futures.is_empty() {
+                    return future::pending().await;
+                }
+                select_all(notification_futures).await
+            };
             futures::select! {
                 () = self.cancellation_token.cancelled().fuse() => {
                     return Ok(Action::Stop);
                 }
-                () = self.storage.clock().sleep_until(timeout).fuse() => {
+                timeout_chain_id = timeout.fuse() => {
                     return Ok(Action::ProcessInbox(timeout_chain_id));
                 }
-                (maybe_notification, index, _) = select_all(notification_futures).fuse() => {
+                chain_id = self.activations.select_next_some() => {
+                    return Ok(Action::Activate(chain_id));
+                }
+                (maybe_notification, index, _) = next_notification.fuse() => {
                     let Some(notification) = maybe_notification else {
                         let chain_id = *self.listening.keys().nth(index).unwrap();
                         self.listening.remove(&chain_id);
+                        if let Some(active_chains) = &mut self.active_chains {
+                            active_chains.pop(&chain_id);
+                        }
                         warn!("Notification stream for {chain_id} closed");
                         continue;
                     };
@@ -359,14 +530,14 @@ impl<C: ClientContext> ChainListener<C> {
         }
     }
 
-    /// Returns the next timeout to process, and the chain to which it applies.
-    fn next_timeout(&self) -> Result<(ChainId, Timestamp), Error> {
+    /// Returns the next timeout to process, and the chain to which it applies, or `None` if
+    /// we don't listen to any chain.
+    fn next_timeout(&self) -> Option<(ChainId, Timestamp)> {
         let (chain_id, client) = self
             .listening
             .iter()
-            .min_by_key(|(_, client)| client.timeout)
-            .expect("No chains left to listen to");
-        Ok((*chain_id, client.timeout))
+            .min_by_key(|(_, client)| client.timeout)?;
+        Some((*chain_id, client.timeout))
     }
 
     /// Updates the validators about the chain.
@@ -405,7 +576,16 @@ impl<C: ClientContext> ChainListener<C> {
             debug!("Not processing inbox for {chain_id:.8} due to listener configuration");
             return Ok(());
         }
-        let listening_client = self.listening.get_mut(&chain_id).expect("missing client");
+        if !self.config.process_inbox_of.is_empty()
+            && !self.config.process_inbox_of.contains(&chain_id)
+        {
+            debug!("Not processing inbox for {chain_id:.8} due to listener configuration");
+            return Ok(());
+        }
+        let Some(listening_client) = self.listening.get_mut(&chain_id) else {
+            debug!("Not processing inbox for {chain_id:.8}, which is not listened to anymore");
+            return Ok(());
+        };
         if !listening_client.client.is_tracked() {
             debug!("Not processing inbox for non-tracked chain {chain_id:.8}");
             return Ok(());
@@ -421,7 +601,15 @@ impl<C: ClientContext> ChainListener<C> {
                 debug!(%chain_id, "Cannot find key for chain");
             }
             Err(error) => warn!(%error, "Failed to process inbox."),
-            Ok((certs, None)) => info!("Done processing inbox. {} blocks created.", certs.len()),
+            Ok((certs, None)) => {
+                info!("Done processing inbox. {} blocks created.", certs.len());
+                // Bundles that wait for a larger batch are received at the batch timeout.
+                match listening_client.client.inbox_batch_deadline().await {
+                    Ok(Some(deadline)) => listening_client.timeout = deadline,
+                    Ok(None) => {}
+                    Err(error) => warn!(%error, "Failed to read the pending bundles."),
+                }
+            }
             Ok((certs, Some(new_timeout))) => {
                 info!(
                     "{} blocks created. Will try processing the inbox later based \
@@ -449,5 +637,6 @@ impl<C: ClientContext> ChainListener<C> {
 enum Action {
     ProcessInbox(ChainId),
     Notification(Notification),
+    Activate(ChainId),
     Stop,
 }
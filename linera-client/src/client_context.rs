@@ -3,25 +3,30 @@
 
 #[cfg(with_testing)]
 use std::num::NonZeroUsize;
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{hash_map, HashMap},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
-use futures::Future;
+use futures::{future, Future};
 use linera_base::{
     crypto::{CryptoHash, Signer},
-    data_types::{BlockHeight, ChainDescription, Timestamp},
+    data_types::{Amount, BlockHeight, ChainDescription, Timestamp},
     identifiers::{Account, AccountOwner, BlobId, BlobType, ChainId},
     ownership::ChainOwnership,
     time::{Duration, Instant},
 };
 use linera_chain::types::ConfirmedBlockCertificate;
 use linera_core::{
-    client::{BlanketMessagePolicy, ChainClient, Client, MessagePolicy, PendingProposal},
-    data_types::ClientOutcome,
+    client::{ChainClient, Client, MessagePolicy, PendingProposal},
+    data_types::{ChainInfoQuery, ClientOutcome},
     join_set_ext::JoinSet,
-    node::CrossChainMessageDelivery,
+    node::{CrossChainMessageDelivery, ValidatorNode as _, ValidatorNodeProvider as _},
     Environment, JoinSetExt,
 };
+#[cfg(any(feature = "benchmark", feature = "fs"))]
+use linera_execution::{system::SystemOperation, Operation};
 use linera_rpc::node_provider::{NodeOptions, NodeProvider};
 use linera_storage::Storage;
 use linera_views::views::ViewError;
@@ -31,28 +36,20 @@ use tracing::{debug, info};
 use {
     crate::benchmark::{Benchmark, BenchmarkError},
     futures::{stream, StreamExt, TryStreamExt},
-    linera_base::{
-        crypto::AccountPublicKey,
-        data_types::{Amount, Epoch},
-        identifiers::ApplicationId,
-    },
+    linera_base::{crypto::AccountPublicKey, data_types::Epoch, identifiers::ApplicationId},
     linera_core::client::ChainClientError,
-    linera_execution::{
-        committee::Committee,
-        system::{OpenChainConfig, SystemOperation},
-        Operation,
-    },
-    std::{collections::HashMap, iter},
+    linera_execution::{committee::Committee, system::OpenChainConfig},
+    std::iter,
     tokio::task,
 };
 #[cfg(feature = "fs")]
 use {
     linera_base::{
-        data_types::{BlobContent, Bytecode},
+        data_types::{Blob, BlobContent, Bytecode},
         identifiers::ModuleId,
         vm::VmRuntime,
     },
-    linera_core::client::create_bytecode_blobs,
+    linera_core::client::{create_bytecode_blobs, DryRunOutcome},
     std::{fs, path::PathBuf},
 };
 
@@ -61,10 +58,14 @@ use crate::persistent::{LocalPersist as Persist, LocalPersistExt as _};
 #[cfg(not(web))]
 use crate::persistent::{Persist, PersistExt as _};
 use crate::{
+    chain_bundle::ChainBundle,
     chain_listener,
     client_options::{ChainOwnershipConfig, ClientContextOptions},
     config::WalletState,
-    error, util,
+    error,
+    multi_owner::{RoundStatus, ValidatorRoundState, ValidatorStatus},
+    transfer_batch::{self, BatchTransfer, InvalidLine, TransferStatus},
+    util,
     wallet::{UserChain, Wallet},
     Error,
 };
@@ -77,8 +78,7 @@ pub struct ClientContext<Env: Environment, W> {
     pub retry_delay: Duration,
     pub max_retries: u32,
     pub chain_listeners: JoinSet,
-    pub blanket_message_policy: BlanketMessagePolicy,
-    pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    pub message_policy: MessagePolicy,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -144,6 +144,20 @@ where
             1 => format!("Client node for {:.8}", chain_ids[0]),
             n => format!("Client node for {:.8} and {} others", chain_ids[0], n - 1),
         };
+        #[cfg(not(target_arch = "wasm32"))]
+        let progress_reporter = options.progress_reporter();
+        // These borrow the options, before the external signer's are moved out of them.
+        let communication_policy = options.communication_policy();
+        let message_policy = options.message_policy();
+        #[cfg(not(target_arch = "wasm32"))]
+        let signer: Box<dyn Signer> = match options.external_signer {
+            Some(program) => Box::new(crate::external_signer::ExternalSigner::new(
+                program,
+                options.external_signer_args,
+                signer,
+            )),
+            None => signer,
+        };
         let client = Client::new(
             linera_core::environment::Impl {
                 network: node_provider,
@@ -158,7 +172,12 @@ where
             options.max_loaded_chains,
             options.grace_period,
             options.blob_download_timeout,
-        );
+            options.certificate_download_parallelism,
+            communication_policy,
+        )
+        .with_certificate_cache_size(options.certificate_cache_size);
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = client.with_progress_reporter(progress_reporter);
 
         ClientContext {
             client: Arc::new(client),
@@ -168,14 +187,16 @@ where
             retry_delay: options.retry_delay,
             max_retries: options.max_retries,
             chain_listeners: JoinSet::default(),
-            blanket_message_policy: options.blanket_message_policy,
-            restrict_chain_ids_to: options.restrict_chain_ids_to,
+            message_policy,
         }
     }
 
     #[cfg(with_testing)]
     pub fn new_test_client_context(storage: S, wallet: W, signer: Box<dyn Signer>) -> Self {
-        use linera_core::DEFAULT_GRACE_PERIOD;
+        use linera_core::{
+            client::BlanketMessagePolicy, validator_health::CommunicationPolicy,
+            DEFAULT_GRACE_PERIOD,
+        };
 
         let send_recv_timeout = Duration::from_millis(4000);
         let retry_delay = Duration::from_millis(1000);
@@ -208,6 +229,8 @@ where
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            NonZeroUsize::new(4).expect("Certificate download parallelism should not be zero"),
+            CommunicationPolicy::default(),
         );
 
         ClientContext {
@@ -218,8 +241,7 @@ where
             retry_delay,
             max_retries,
             chain_listeners: JoinSet::default(),
-            blanket_message_policy: BlanketMessagePolicy::Accept,
-            restrict_chain_ids_to: None,
+            message_policy: MessagePolicy::new(BlanketMessagePolicy::Accept, None),
         }
     }
 }
@@ -300,10 +322,7 @@ where
                 preferred_owner,
             )
             .await?;
-        chain_client.options_mut().message_policy = MessagePolicy::new(
-            self.blanket_message_policy,
-            self.restrict_chain_ids_to.clone(),
-        );
+        chain_client.options_mut().message_policy = self.message_policy.clone();
         Ok(chain_client)
     }
 
@@ -450,6 +469,29 @@ where
         Ok(())
     }
 
+    /// Imports a chain exported from another wallet, after checking with the validators that
+    /// the exported tip belongs to the chain. Returns the chain's new entry in the wallet.
+    pub async fn import_chain(&mut self, bundle: ChainBundle) -> Result<UserChain, Error> {
+        bundle.check_network(self.wallet.genesis_config())?;
+        let chain_id = bundle.chain.chain_id;
+        self.client.track_chain(chain_id);
+        // Start from the genesis state rather than the exported one, so that the tip is
+        // checked against the blocks downloaded from the validators.
+        let chain_client = self
+            .make_chain_client_internal(
+                chain_id,
+                None,
+                Timestamp::from(0),
+                BlockHeight::ZERO,
+                None,
+                bundle.chain.owner,
+            )
+            .await?;
+        let chain = bundle.verify_tip(&chain_client).await?;
+        self.mutate_wallet(|w| w.insert(chain.clone())).await?;
+        Ok(chain)
+    }
+
     /// Applies the given function to the chain client.
     ///
     /// Updates the wallet regardless of the outcome. As long as the function returns a round
@@ -523,6 +565,232 @@ where
         Ok(())
     }
 
+    /// Replaces the key of the chain's preferred owner with the one of `new_owner`, keeping
+    /// the rest of the chain's ownership configuration.
+    ///
+    /// The wallet is only updated once the block is confirmed and the new ownership has been
+    /// checked. If the block fails, the wallet keeps the old owner and the pending block is
+    /// discarded, unless the validators may already have locked it. In that case the block
+    /// must be committed with `linera retry-pending-block`, before setting the new preferred
+    /// owner.
+    pub async fn rotate_key(
+        &mut self,
+        chain_id: Option<ChainId>,
+        new_owner: AccountOwner,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        let chain_id = chain_id.unwrap_or_else(|| self.default_chain());
+        let mut chain_client = self.make_chain_client(chain_id).await?;
+        let old_owner = chain_client.identity().await?;
+        info!(%chain_id, %old_owner, %new_owner, "Rotating the key of a chain owner");
+        let time_start = Instant::now();
+        let mut ownership = chain_client.chain_info().await?.manager.ownership;
+        let is_super_owner = ownership.super_owners.remove(&old_owner);
+        if is_super_owner {
+            ownership.super_owners.insert(new_owner);
+        }
+        if let Some(weight) = ownership.owners.remove(&old_owner) {
+            ownership.owners.insert(new_owner, weight);
+        } else if !is_super_owner {
+            return Err(error::Inner::ChainOwnership.into());
+        }
+
+        let result = self
+            .apply_client_command(&chain_client, |chain_client| {
+                let ownership = ownership.clone();
+                let chain_client = chain_client.clone();
+                async move {
+                    chain_client
+                        .change_ownership(ownership)
+                        .await
+                        .map_err(Error::from)
+                        .context("Failed to rotate the key")
+                }
+            })
+            .await;
+        let certificate = match result {
+            Ok(certificate) => certificate,
+            Err(error) => {
+                // Only discard the block if we know that it can't be committed anymore.
+                if chain_client.synchronize_from_validators().await.is_ok() {
+                    let info = chain_client.chain_info_with_manager_values().await?;
+                    if info.manager.requested_locking.is_none()
+                        && !info.manager.ownership.verify_owner(&new_owner)
+                    {
+                        chain_client.clear_pending_proposal();
+                        self.update_wallet_from_client(&chain_client).await?;
+                    }
+                }
+                return Err(error);
+            }
+        };
+
+        let ownership = chain_client.chain_info().await?.manager.ownership;
+        if !ownership.verify_owner(&new_owner) || ownership.verify_owner(&old_owner) {
+            return Err(error::Inner::ChainOwnership.into());
+        }
+        chain_client.set_preferred_owner(new_owner);
+        self.update_wallet_from_client(&chain_client).await?;
+        let time_total = time_start.elapsed();
+        info!("Key rotated after {} ms", time_total.as_millis());
+        Ok(certificate)
+    }
+
+    /// Transfers native tokens from `sender` to all the recipients of `transfers`, in as few
+    /// blocks as possible, with at most `max_per_block` transfers per block.
+    ///
+    /// The recipients' chains and the sender's balance are checked before any block is
+    /// proposed. The blocks are committed one after the other; once one of them fails, the
+    /// remaining ones are not submitted.
+    pub async fn transfer_batch(
+        &mut self,
+        sender: Account,
+        transfers: Vec<BatchTransfer>,
+        max_per_block: usize,
+    ) -> Result<Vec<(BatchTransfer, TransferStatus)>, Error> {
+        let chain_client = self.make_chain_client(sender.chain_id).await?;
+        let admin_id = self.wallet.genesis_admin_chain();
+        let mut invalid_lines = Vec::new();
+        let mut checked_chains = HashMap::new();
+        for transfer in &transfers {
+            let chain_id = transfer.recipient.chain_id;
+            let exists = match checked_chains.entry(chain_id) {
+                hash_map::Entry::Occupied(entry) => *entry.get(),
+                hash_map::Entry::Vacant(entry) => *entry.insert(
+                    self.client
+                        .ensure_has_chain_description(chain_id, admin_id)
+                        .await
+                        .is_ok(),
+                ),
+            };
+            if !exists {
+                invalid_lines.push(InvalidLine {
+                    line: transfer.line,
+                    message: format!("unknown recipient chain {chain_id}"),
+                });
+            }
+        }
+        if !invalid_lines.is_empty() {
+            return Err(transfer_batch::Error::InvalidLines(invalid_lines).into());
+        }
+
+        chain_client.synchronize_from_validators().await?;
+        let needed = transfers.iter().try_fold(Amount::ZERO, |total, transfer| {
+            total.try_add(transfer.amount)
+        })?;
+        let available = chain_client.query_owner_balance(sender.owner).await?;
+        if needed > available {
+            return Err(transfer_batch::Error::InsufficientBalance { needed, available }.into());
+        }
+
+        let policy = chain_client.local_committee().await?.policy().clone();
+        // Leave room for the rest of the block, e.g. the outgoing messages.
+        let max_bytes = policy
+            .maximum_block_size
+            .min(policy.maximum_block_proposal_size)
+            / 2;
+        let operations = transfers
+            .iter()
+            .map(|transfer| transfer.operation(sender.owner))
+            .collect::<Vec<_>>();
+        let blocks = transfer_batch::plan_blocks(&operations, max_per_block, max_bytes);
+        info!(
+            transfers = transfers.len(),
+            blocks = blocks.len(),
+            %sender,
+            "Starting batch transfer"
+        );
+
+        let mut statuses = vec![TransferStatus::NotSubmitted; transfers.len()];
+        for range in blocks {
+            let block_operations = operations[range.clone()].to_vec();
+            let result = self
+                .apply_client_command(&chain_client, |chain_client| {
+                    let chain_client = chain_client.clone();
+                    let block_operations = block_operations.clone();
+                    async move {
+                        chain_client
+                            .execute_operations(block_operations, vec![])
+                            .await
+                    }
+                })
+                .await;
+            let status = match result {
+                Ok(certificate) => TransferStatus::Confirmed {
+                    height: certificate.block().header.height,
+                    hash: certificate.hash(),
+                },
+                Err(error) => {
+                    let mut message = error.to_string();
+                    // Only discard the block if we know that it can't be committed anymore.
+                    let info = match chain_client.synchronize_from_validators().await {
+                        Ok(_) => Some(chain_client.chain_info_with_manager_values().await?),
+                        Err(_) => None,
+                    };
+                    if info.is_some_and(|info| info.manager.requested_locking.is_none()) {
+                        chain_client.clear_pending_proposal();
+                        self.update_wallet_from_client(&chain_client).await?;
+                    } else {
+                        message.push_str(
+                            "; the block may still be committed by `linera retry-pending-block`",
+                        );
+                    }
+                    statuses[range].fill(TransferStatus::Failed(message));
+                    break;
+                }
+            };
+            statuses[range].fill(status);
+        }
+        Ok(transfers.into_iter().zip(statuses).collect())
+    }
+
+    /// Returns the state of the current round of the chain, as seen locally and by each
+    /// validator of the current committee.
+    pub async fn round_status(&mut self, chain_id: ChainId) -> Result<RoundStatus, Error> {
+        let chain_client = self.make_chain_client(chain_id).await?;
+        chain_client.synchronize_from_validators().await?;
+        let info = chain_client.chain_info_with_manager_values().await?;
+        self.update_wallet_from_client(&chain_client).await?;
+        let committee = chain_client.local_committee().await?;
+        let node_provider = self.make_node_provider();
+        let validators =
+            future::join_all(committee.validators().iter().map(|(public_key, state)| {
+                let node = node_provider.make_node(&state.network_address);
+                let weight = state.votes;
+                async move {
+                    let query = ChainInfoQuery::new(chain_id).with_manager_values();
+                    let state = match node {
+                        Ok(node) => match node.handle_chain_info_query(query).await {
+                            Ok(response) if response.check(public_key).is_ok() => {
+                                ValidatorRoundState::Reachable {
+                                    next_block_height: response.info.next_block_height,
+                                    current_round: response.info.manager.current_round,
+                                    vote: response.info.manager.pending.map(Box::new),
+                                }
+                            }
+                            Ok(_) => ValidatorRoundState::Unreachable(
+                                "invalid signature on the response".to_owned(),
+                            ),
+                            Err(error) => ValidatorRoundState::Unreachable(error.to_string()),
+                        },
+                        Err(error) => ValidatorRoundState::Unreachable(error.to_string()),
+                    };
+                    ValidatorStatus {
+                        public_key: *public_key,
+                        weight,
+                        state,
+                    }
+                }
+            }))
+            .await;
+        Ok(RoundStatus::new(
+            chain_id,
+            info.next_block_height,
+            &info.manager,
+            validators,
+            committee.quorum_threshold(),
+        ))
+    }
+
     pub async fn set_preferred_owner(
         &mut self,
         chain_id: Option<ChainId>,
@@ -551,17 +819,8 @@ where
         service: PathBuf,
         vm_runtime: VmRuntime,
     ) -> Result<ModuleId, Error> {
-        info!("Loading bytecode files");
-        let contract_bytecode = Bytecode::load_from_file(&contract)
-            .await
-            .with_context(|| format!("failed to load contract bytecode from {:?}", &contract))?;
-        let service_bytecode = Bytecode::load_from_file(&service)
-            .await
-            .with_context(|| format!("failed to load service bytecode from {:?}", &service))?;
-
+        let (blobs, module_id) = Self::load_module_blobs(contract, service, vm_runtime).await?;
         info!("Publishing module");
-        let (blobs, module_id) =
-            create_bytecode_blobs(contract_bytecode, service_bytecode, vm_runtime).await;
         let (module_id, _) = self
             .apply_client_command(chain_client, |chain_client| {
                 let blobs = blobs.clone();
@@ -582,6 +841,36 @@ where
         Ok(module_id)
     }
 
+    /// Executes the publication of a module locally, without submitting it to the
+    /// validators.
+    pub async fn dry_run_module_publication(
+        &self,
+        chain_client: &ChainClient<Env>,
+        contract: PathBuf,
+        service: PathBuf,
+        vm_runtime: VmRuntime,
+    ) -> Result<(ModuleId, DryRunOutcome), Error> {
+        let (blobs, module_id) = Self::load_module_blobs(contract, service, vm_runtime).await?;
+        let operation = Operation::system(SystemOperation::PublishModule { module_id });
+        let outcome = chain_client.dry_run(vec![operation], blobs).await?;
+        Ok((module_id, outcome))
+    }
+
+    async fn load_module_blobs(
+        contract: PathBuf,
+        service: PathBuf,
+        vm_runtime: VmRuntime,
+    ) -> Result<(Vec<Blob>, ModuleId), Error> {
+        info!("Loading bytecode files");
+        let contract_bytecode = Bytecode::load_from_file(&contract)
+            .await
+            .with_context(|| format!("failed to load contract bytecode from {:?}", &contract))?;
+        let service_bytecode = Bytecode::load_from_file(&service)
+            .await
+            .with_context(|| format!("failed to load service bytecode from {:?}", &service))?;
+        Ok(create_bytecode_blobs(contract_bytecode, service_bytecode, vm_runtime).await)
+    }
+
     pub async fn publish_data_blob(
         &mut self,
         chain_client: &ChainClient<Env>,
@@ -1,16 +1,30 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashSet, fmt, iter, num::NonZeroUsize, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt, iter,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::Arc,
+};
 
 use linera_base::{
-    data_types::{ApplicationPermissions, TimeDelta},
+    data_types::{Amount, ApplicationPermissions, TimeDelta},
     identifiers::{AccountOwner, ApplicationId, ChainId},
     ownership::{ChainOwnership, TimeoutConfig},
     time::Duration,
 };
-use linera_core::{client::BlanketMessagePolicy, DEFAULT_GRACE_PERIOD};
-use linera_execution::ResourceControlPolicy;
+#[cfg(not(target_arch = "wasm32"))]
+use linera_core::progress::{NoProgress, ProgressReporter};
+use linera_core::{
+    client::{BlanketMessagePolicy, MessagePolicy},
+    validator_health::CommunicationPolicy,
+    DEFAULT_GRACE_PERIOD,
+};
+use linera_execution::{
+    ExecutionRuntimeConfig, HttpRedirectPolicy, HttpRequestPolicy, ResourceControlPolicy,
+};
 
 #[cfg(any(with_indexed_db, not(with_persist)))]
 use crate::{config::WalletState, wallet::Wallet};
@@ -48,6 +62,34 @@ pub struct ClientContextOptions {
     #[arg(long = "keystore")]
     pub keystore_path: Option<PathBuf>,
 
+    /// How long the key unlocking an encrypted keystore is kept in memory before the
+    /// passphrase is requested again, in seconds.
+    #[arg(
+        long = "keystore-unlock-timeout-secs",
+        default_value = "300",
+        value_parser = util::parse_secs
+    )]
+    pub keystore_unlock_timeout: Duration,
+
+    /// How long to wait for other processes using the wallet or the keystore to finish,
+    /// in milliseconds, so that commands are queued instead of failing.
+    #[arg(
+        long = "wallet-lock-timeout-ms",
+        env = "LINERA_WALLET_LOCK_TIMEOUT_MS",
+        default_value = "0",
+        value_parser = util::parse_millis
+    )]
+    pub wallet_lock_timeout: Duration,
+
+    /// A program to request signatures from for the owners whose keys are not in the
+    /// keystore, e.g. to use a hardware wallet or a key management service.
+    #[arg(long)]
+    pub external_signer: Option<PathBuf>,
+
+    /// An argument to pass to the `--external-signer` program. Can be repeated.
+    #[arg(long = "external-signer-arg", requires = "external_signer")]
+    pub external_signer_args: Vec<String>,
+
     /// Given an ASCII alphanumeric parameter `X`, read the wallet state and the wallet
     /// storage config from the environment variables `LINERA_WALLET_{X}` and
     /// `LINERA_STORAGE_{X}` instead of `LINERA_WALLET` and
@@ -102,6 +144,21 @@ pub struct ClientContextOptions {
     #[arg(long, value_parser = util::parse_chain_set)]
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
 
+    /// Only receive incoming messages once this many bundles are pending, to include them in
+    /// fewer blocks. By default, they are received at once.
+    #[arg(long)]
+    pub message_batch_size: Option<usize>,
+
+    /// Receive pending incoming messages anyway once the oldest bundle is this old, in
+    /// milliseconds, even if there are fewer than `--message-batch-size`.
+    #[arg(long = "message-batch-timeout-ms", value_parser = util::parse_millis_delta)]
+    pub message_batch_timeout: Option<TimeDelta>,
+
+    /// Leave the incoming bundles that transfer more than this amount of native tokens in
+    /// the inbox until they are approved, e.g. with `linera process-inbox --approve`.
+    #[arg(long)]
+    pub message_approval_threshold: Option<Amount>,
+
     /// An additional delay, after reaching a quorum, to wait for additional validator signatures,
     /// as a fraction of time taken to reach quorum.
     #[arg(long, default_value_t = DEFAULT_GRACE_PERIOD)]
@@ -114,6 +171,108 @@ pub struct ClientContextOptions {
         value_parser = util::parse_millis
     )]
     pub blob_download_timeout: Duration,
+
+    /// The maximal number of batches of certificates downloaded in parallel when
+    /// synchronizing a chain.
+    #[arg(long, default_value = "4")]
+    pub certificate_download_parallelism: NonZeroUsize,
+
+    /// The maximal number of recently confirmed blocks kept in memory, so that they are not
+    /// downloaded from the validators again.
+    #[arg(long, default_value = "1000")]
+    pub certificate_cache_size: NonZeroUsize,
+
+    /// The maximal time to wait for a single validator when communicating with a quorum, in
+    /// milliseconds. By default, there is no limit.
+    #[arg(long = "validator-request-timeout-ms", value_parser = util::parse_millis)]
+    pub validator_request_timeout: Option<Duration>,
+
+    /// The number of consecutive failures after which a validator is temporarily left out of
+    /// requests, as long as the other validators can form a quorum. If zero, validators are
+    /// never left out.
+    #[arg(long, default_value = "3")]
+    pub max_validator_failures: u32,
+
+    /// How long a failing validator is left out the first time, in milliseconds. This doubles
+    /// with each further exclusion, up to `--max-validator-exclusion-ms`.
+    #[arg(
+        long = "validator-exclusion-ms",
+        default_value = "30000",
+        value_parser = util::parse_millis
+    )]
+    pub validator_exclusion: Duration,
+
+    /// The maximal time a failing validator is left out, in milliseconds.
+    #[arg(
+        long = "max-validator-exclusion-ms",
+        default_value = "600000",
+        value_parser = util::parse_millis
+    )]
+    pub max_validator_exclusion: Duration,
+
+    /// Upload the blobs published by a block to a single validator first, and let the other
+    /// validators download them from it. This requires validators running with
+    /// `--fetch-blobs-from-peers`.
+    #[arg(long)]
+    pub upload_blobs_to_one_validator: bool,
+
+    /// Only contact the fastest validators that form a quorum at first, and the others if
+    /// they don't reach a quorum within this time, in milliseconds. By default, all validators
+    /// are contacted at once.
+    #[arg(long = "fastest-quorum-delay-ms", value_parser = util::parse_millis)]
+    pub fastest_quorum_delay: Option<Duration>,
+
+    /// Do not report the progress of long-running operations.
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// How to report the progress of long-running operations on the standard error output:
+    /// as progress bars, only displayed on a terminal, or as JSON lines.
+    #[arg(long, default_value = "bars", conflicts_with = "quiet")]
+    pub progress_format: ProgressFormat,
+}
+
+impl ClientContextOptions {
+    /// Returns the policy for handling incoming messages.
+    pub fn message_policy(&self) -> MessagePolicy {
+        MessagePolicy::new(
+            self.blanket_message_policy,
+            self.restrict_chain_ids_to.clone(),
+        )
+        .with_batching(self.message_batch_size, self.message_batch_timeout)
+        .with_approval_threshold(self.message_approval_threshold)
+    }
+
+    /// Returns the policy for communicating with the validators.
+    pub fn communication_policy(&self) -> CommunicationPolicy {
+        CommunicationPolicy {
+            request_timeout: self.validator_request_timeout,
+            max_consecutive_failures: self.max_validator_failures,
+            exclusion_duration: self.validator_exclusion,
+            max_exclusion_duration: self.max_validator_exclusion,
+            upload_blobs_to_one_validator: self.upload_blobs_to_one_validator,
+            fastest_quorum_delay: self.fastest_quorum_delay,
+        }
+    }
+
+    /// Returns the reporter of the progress of long-running operations.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn progress_reporter(&self) -> Arc<dyn ProgressReporter> {
+        if self.quiet {
+            return Arc::new(NoProgress);
+        }
+        match self.progress_format {
+            ProgressFormat::Bars => Arc::new(crate::progress::ProgressBars::default()),
+            ProgressFormat::Json => Arc::new(crate::progress::JsonProgress),
+        }
+    }
+}
+
+/// How to report the progress of long-running operations.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressFormat {
+    Bars,
+    Json,
 }
 
 #[cfg(with_indexed_db)]
@@ -280,6 +439,47 @@ impl From<ApplicationPermissionsConfig> for ApplicationPermissions {
     }
 }
 
+/// Node-local restrictions on the HTTP requests performed by applications. These only tighten
+/// the limits set by the committee's resource control policy.
+#[derive(Debug, Clone, clap::Args)]
+pub struct HttpRequestPolicyConfig {
+    /// If present, applications can only send HTTP requests to these hosts.
+    #[arg(long)]
+    pub http_allowed_hosts: Option<Vec<String>>,
+    /// The maximum size in bytes of a received HTTP response.
+    #[arg(long)]
+    pub max_http_response_bytes: Option<u64>,
+    /// The maximum amount of time to wait for an HTTP response, in milliseconds.
+    #[arg(long = "http-request-timeout-ms", value_parser = util::parse_millis)]
+    pub http_request_timeout: Option<Duration>,
+    /// The maximum number of HTTP redirects to follow. Use 0 to never follow redirects.
+    #[arg(long, default_value = "10")]
+    pub max_http_redirects: usize,
+}
+
+impl From<HttpRequestPolicyConfig> for HttpRequestPolicy {
+    fn from(config: HttpRequestPolicyConfig) -> HttpRequestPolicy {
+        let redirect_policy = match config.max_http_redirects {
+            0 => HttpRedirectPolicy::None,
+            limit => HttpRedirectPolicy::Limited(limit),
+        };
+        HttpRequestPolicy {
+            allowed_hosts: config
+                .http_allowed_hosts
+                .map(|hosts| Arc::new(hosts.into_iter().collect::<BTreeSet<_>>())),
+            maximum_response_bytes: config.max_http_response_bytes,
+            timeout: config.http_request_timeout,
+            redirect_policy,
+        }
+    }
+}
+
+impl HttpRequestPolicyConfig {
+    pub fn into_execution_runtime_config(self) -> ExecutionRuntimeConfig {
+        HttpRequestPolicy::from(self).into()
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResourceControlPolicyConfig {
     NoFees,
@@ -10,15 +10,16 @@ use std::{
 
 use linera_base::{
     crypto::{
-        AccountPublicKey, BcsSignable, CryptoHash, InMemorySigner, ValidatorPublicKey,
-        ValidatorSecretKey,
+        AccountPublicKey, BcsSignable, BlsValidatorKey, CryptoHash, InMemorySigner,
+        ValidatorPublicKey, ValidatorSecretKey,
     },
     data_types::{Amount, ChainDescription, ChainOrigin, Epoch, InitialChainConfig, Timestamp},
-    identifiers::ChainId,
+    identifiers::{ApplicationId, ChainId, GenericApplicationId},
     ownership::ChainOwnership,
 };
+use linera_chain::block::Block;
 use linera_execution::{
-    committee::{Committee, ValidatorState},
+    committee::{CertificateSignatureScheme, Committee, ValidatorState},
     ResourceControlPolicy,
 };
 use linera_rpc::config::{
@@ -59,13 +60,19 @@ pub struct ValidatorConfig {
     pub account_key: AccountPublicKey,
     /// The network configuration for the validator.
     pub network: ValidatorPublicNetworkConfig,
+    /// The BLS key of the validator, to aggregate certificate signatures.
+    #[serde(default)]
+    pub bls_key: Option<BlsValidatorKey>,
 }
 
 /// The private configuration of a validator service.
 #[derive(Serialize, Deserialize)]
 pub struct ValidatorServerConfig {
     pub validator: ValidatorConfig,
-    pub validator_secret: ValidatorSecretKey,
+    /// The secret key of the validator. It can be left out if the server signs with an
+    /// external signer instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_secret: Option<ValidatorSecretKey>,
     pub internal_network: ValidatorInternalNetworkConfig,
 }
 
@@ -78,6 +85,9 @@ use crate::persistent::Persist;
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct CommitteeConfig {
     pub validators: Vec<ValidatorConfig>,
+    /// How the validators sign certificates.
+    #[serde(default)]
+    pub certificate_signature_scheme: CertificateSignatureScheme,
 }
 
 impl CommitteeConfig {
@@ -92,11 +102,13 @@ impl CommitteeConfig {
                         network_address: v.network.to_string(),
                         votes: 100,
                         account_public_key: v.account_key,
+                        bls_key: v.bls_key,
                     },
                 )
             })
             .collect();
         Committee::new(validators, policy)
+            .with_certificate_signature_scheme(self.certificate_signature_scheme)
     }
 }
 
@@ -293,28 +305,7 @@ impl GenesisConfig {
         {
             return Err(Error::StorageIsAlreadyInitialized(description));
         }
-        let committee = self.create_committee();
-        let committees: BTreeMap<_, _> = [(
-            Epoch::ZERO,
-            bcs::to_bytes(&committee).expect("serializing a committee should not fail"),
-        )]
-        .into_iter()
-        .collect();
-        for (chain_number, (public_key, balance)) in (0..).zip(&self.chains) {
-            let origin = ChainOrigin::Root(chain_number);
-            let config = InitialChainConfig {
-                admin_id: if chain_number == 0 {
-                    None
-                } else {
-                    Some(self.admin_id)
-                },
-                application_permissions: Default::default(),
-                balance: *balance,
-                committees: committees.clone(),
-                epoch: Epoch::ZERO,
-                ownership: ChainOwnership::single((*public_key).into()),
-            };
-            let description = ChainDescription::new(origin, config, self.timestamp);
+        for description in self.chain_descriptions() {
             storage.create_chain(description).await?;
         }
         let network_description = NetworkDescription {
@@ -329,6 +320,37 @@ impl GenesisConfig {
         Ok(())
     }
 
+    /// Returns the descriptions of the initial chains, in order: the first one is the admin
+    /// chain.
+    pub fn chain_descriptions(&self) -> Vec<ChainDescription> {
+        let committee = self.create_committee();
+        let committees: BTreeMap<_, _> = [(
+            Epoch::ZERO,
+            bcs::to_bytes(&committee).expect("serializing a committee should not fail"),
+        )]
+        .into_iter()
+        .collect();
+        (0..)
+            .zip(&self.chains)
+            .map(|(chain_number, (public_key, balance))| {
+                let origin = ChainOrigin::Root(chain_number);
+                let config = InitialChainConfig {
+                    admin_id: if chain_number == 0 {
+                        None
+                    } else {
+                        Some(self.admin_id)
+                    },
+                    application_permissions: Default::default(),
+                    balance: *balance,
+                    committees: committees.clone(),
+                    epoch: Epoch::ZERO,
+                    ownership: ChainOwnership::single((*public_key).into()),
+                };
+                ChainDescription::new(origin, config, self.timestamp)
+            })
+            .collect()
+    }
+
     pub fn create_committee(&self) -> Committee {
         self.committee.clone().into_committee(self.policy.clone())
     }
@@ -370,4 +392,96 @@ pub struct Destination {
     pub endpoint: String,
     /// The port number of the target destination.
     pub port: u16,
+    /// The kind of destination, and where the blocks are published to.
+    #[serde(default)]
+    pub kind: DestinationKind,
+    /// The blocks to publish to the destination.
+    #[serde(default)]
+    pub filter: DestinationFilter,
+    /// How the blocks are encoded.
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Selects the blocks to export to a destination. By default, all blocks are exported.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DestinationFilter {
+    /// If not empty, only the blocks of these chains are exported.
+    #[serde(default)]
+    pub chain_ids: Vec<ChainId>,
+    /// If not empty, only the blocks with events from these applications are exported.
+    #[serde(default)]
+    pub event_applications: Vec<ApplicationId>,
+}
+
+impl DestinationFilter {
+    /// Returns whether blocks of this chain can be exported.
+    pub fn accepts_chain(&self, chain_id: ChainId) -> bool {
+        self.chain_ids.is_empty() || self.chain_ids.contains(&chain_id)
+    }
+
+    /// Returns whether the block is exported.
+    pub fn accepts_block(&self, block: &Block) -> bool {
+        self.accepts_chain(block.header.chain_id)
+            && (self.event_applications.is_empty()
+                || block.body.events.iter().flatten().any(|event| {
+                    match event.stream_id.application_id {
+                        GenericApplicationId::System => false,
+                        GenericApplicationId::User(application_id) => {
+                            self.event_applications.contains(&application_id)
+                        }
+                    }
+                }))
+    }
+}
+
+/// The encodings of exported blocks.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// The BCS serialization of the confirmed block.
+    #[default]
+    Bcs,
+    /// The canonical JSON encoding of the confirmed block, with sorted object keys. See
+    /// [`linera_chain::canonical`].
+    Json,
+    /// The canonical CBOR encoding of the confirmed block. See [`linera_chain::canonical`].
+    Cbor,
+    /// The block's certificate, as the `Certificate` message of the validators' gRPC API.
+    Protobuf,
+}
+
+impl ExportFormat {
+    /// Returns the MIME type of the encoding.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Bcs => "application/x-bcs",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Cbor => "application/cbor",
+            ExportFormat::Protobuf => "application/x-protobuf",
+        }
+    }
+}
+
+/// The kinds of export destinations.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DestinationKind {
+    /// A service receiving the blocks over gRPC.
+    #[default]
+    Grpc,
+    /// A Kafka broker. Blocks are published to the topic's partitions by chain ID.
+    Kafka {
+        topic: String,
+        /// The number of partitions of the topic.
+        #[serde(default = "default_kafka_partitions")]
+        partitions: u32,
+    },
+    /// A NATS server with JetStream. The blocks of each chain are published to the
+    /// subject `<subject_prefix>.<chain ID>`, which must belong to a stream.
+    Nats { subject_prefix: String },
+}
+
+fn default_kafka_partitions() -> u32 {
+    1
 }
@@ -22,6 +22,17 @@ pub(crate) enum Inner {
     View(#[from] linera_views::views::ViewError),
     #[error("non-existent chain: {0:?}")]
     NonexistentChain(linera_base::identifiers::ChainId),
+    #[error("no chain specified and no default chain in the wallet")]
+    NoDefaultChain,
+    #[error("unknown alias: {0}")]
+    UnknownAlias(String),
+    #[error("the alias {0} already exists")]
+    AliasExists(String),
+    #[error(
+        "invalid alias {0:?}: aliases start with a letter and contain only letters, digits, `-` \
+         and `_`"
+    )]
+    InvalidAlias(String),
     #[error("no keypair found for chain: {0:?}")]
     NonexistentKeypair(linera_base::identifiers::ChainId),
     #[error("error on the local node: {0}")]
@@ -32,6 +43,25 @@ pub(crate) enum Inner {
     Arithmetic(#[from] linera_base::data_types::ArithmeticError),
     #[error("incorrect chain ownership")]
     ChainOwnership,
+    #[error("the chain belongs to a different network, with genesis configuration {0}")]
+    WrongNetwork(linera_base::crypto::CryptoHash),
+    #[error("the validators don't know block {height} of chain {chain_id}, the exported tip")]
+    UnknownChainTip {
+        chain_id: linera_base::identifiers::ChainId,
+        height: linera_base::data_types::BlockHeight,
+    },
+    #[error(
+        "the validators have {found:?} at height {height} of chain {chain_id}, \
+        instead of the exported block {expected}"
+    )]
+    InconsistentChainTip {
+        chain_id: linera_base::identifiers::ChainId,
+        height: linera_base::data_types::BlockHeight,
+        expected: linera_base::crypto::CryptoHash,
+        found: Option<linera_base::crypto::CryptoHash>,
+    },
+    #[error(transparent)]
+    TransferBatch(#[from] crate::transfer_batch::Error),
     #[cfg(feature = "benchmark")]
     #[error("Benchmark error: {0}")]
     Benchmark(#[from] BenchmarkError),
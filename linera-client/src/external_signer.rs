@@ -0,0 +1,227 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signers that request signatures from an external program.
+//!
+//! This allows chain owners and validators to keep their keys out of the keystore and the
+//! server configuration, by running a program that forwards the signing requests to wherever
+//! the keys are kept, e.g. a hardware wallet or a key management service. There is no
+//! built-in support for specific devices or services.
+//!
+//! The program is run once per request. It receives a [`Request`] as JSON on its standard
+//! input and must write its response as JSON on its standard output, then exit
+//! successfully:
+//!
+//! - `{"get_public_key": {"owner": ...}}` is answered with an [`AccountPublicKey`],
+//! - `{"sign": {"owner": ..., "value": ...}}` is answered with an [`AccountSignature`] of
+//!   the [`CryptoHash`] `value`,
+//! - `{"contains_key": {"owner": ...}}` is answered with `true` or `false`,
+//! - `{"validator_sign": {"public_key": ..., "value": ...}}` is answered with a
+//!   [`ValidatorSignature`] of the [`CryptoHash`] `value`.
+//!
+//! The signatures are checked against the expected public keys before they are used.
+
+use std::{
+    path::PathBuf,
+    process::{Output, Stdio},
+};
+
+use async_trait::async_trait;
+use linera_base::{
+    crypto::{
+        AccountPublicKey, AccountSignature, CryptoError, CryptoHash, Signer, ValidatorPublicKey,
+        ValidatorSignature,
+    },
+    identifiers::AccountOwner,
+};
+use linera_chain::data_types::ValidatorSigner;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::AsyncWriteExt as _;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to run the external signer: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid message exchanged with the external signer: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the external signer failed ({status}): {stderr}")]
+    Failed {
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    #[error("the external signer returned the key of {actual} instead of {expected}")]
+    UnexpectedPublicKey {
+        expected: AccountOwner,
+        actual: AccountOwner,
+    },
+    #[error("the external signer returned an invalid signature: {0}")]
+    InvalidSignature(CryptoError),
+}
+
+/// A request sent to the external signer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// Requests the public key of `owner`.
+    GetPublicKey { owner: AccountOwner },
+    /// Requests a signature of `value` by `owner`.
+    Sign {
+        owner: AccountOwner,
+        value: CryptoHash,
+    },
+    /// Asks whether the key of `owner` is available.
+    ContainsKey { owner: AccountOwner },
+    /// Requests a signature of `value` by the validator with the given `public_key`.
+    ValidatorSign {
+        public_key: ValidatorPublicKey,
+        value: CryptoHash,
+    },
+}
+
+/// An external program, with its arguments.
+struct ExternalProgram {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl ExternalProgram {
+    /// Runs the program to handle the `request`.
+    async fn request<T: DeserializeOwned>(&self, request: &Request) -> Result<T, Error> {
+        let mut child = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("standard input should be piped");
+        stdin.write_all(&serde_json::to_vec(request)?).await?;
+        drop(stdin);
+        Self::response(child.wait_with_output().await?)
+    }
+
+    fn response<T: DeserializeOwned>(output: Output) -> Result<T, Error> {
+        if !output.status.success() {
+            return Err(Error::Failed {
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            });
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// A [`Signer`] using the keys available locally, and requesting signatures for the other
+/// owners from an external program.
+pub struct ExternalSigner {
+    program: ExternalProgram,
+    local: Box<dyn Signer>,
+}
+
+impl ExternalSigner {
+    /// Creates a new [`ExternalSigner`] running `program` with `args`, and using `local` for
+    /// the keys it contains.
+    pub fn new(program: PathBuf, args: Vec<String>, local: Box<dyn Signer>) -> Self {
+        ExternalSigner {
+            program: ExternalProgram { program, args },
+            local,
+        }
+    }
+
+    /// Requests the public key of `owner` from the external program.
+    async fn external_public_key(&self, owner: &AccountOwner) -> Result<AccountPublicKey, Error> {
+        let request = Request::GetPublicKey { owner: *owner };
+        let public_key: AccountPublicKey = self.program.request(&request).await?;
+        let actual = AccountOwner::from(public_key);
+        if actual != *owner {
+            return Err(Error::UnexpectedPublicKey {
+                expected: *owner,
+                actual,
+            });
+        }
+        Ok(public_key)
+    }
+}
+
+#[async_trait]
+impl Signer for ExternalSigner {
+    async fn sign(
+        &self,
+        owner: &AccountOwner,
+        value: &CryptoHash,
+    ) -> Result<AccountSignature, Box<dyn std::error::Error>> {
+        let is_local = self.local.contains_key(owner).await?;
+        if is_local {
+            return self.local.sign(owner, value).await;
+        }
+        let public_key = self.external_public_key(owner).await?;
+        let request = Request::Sign {
+            owner: *owner,
+            value: *value,
+        };
+        let signature: AccountSignature = self.program.request(&request).await?;
+        signature
+            .verify_prehash(*value, public_key)
+            .map_err(Error::InvalidSignature)?;
+        Ok(signature)
+    }
+
+    async fn get_public_key(
+        &self,
+        owner: &AccountOwner,
+    ) -> Result<AccountPublicKey, Box<dyn std::error::Error>> {
+        let is_local = self.local.contains_key(owner).await?;
+        if is_local {
+            return self.local.get_public_key(owner).await;
+        }
+        Ok(self.external_public_key(owner).await?)
+    }
+
+    async fn contains_key(&self, owner: &AccountOwner) -> Result<bool, Box<dyn std::error::Error>> {
+        let is_local = self.local.contains_key(owner).await?;
+        if is_local {
+            return Ok(true);
+        }
+        let request = Request::ContainsKey { owner: *owner };
+        Ok(self.program.request(&request).await?)
+    }
+}
+
+/// A [`ValidatorSigner`] requesting the signatures of a validator from an external program.
+///
+/// The program is run for each signature, so this is only suitable for validators with a low
+/// load.
+pub struct ExternalValidatorSigner {
+    program: ExternalProgram,
+    public_key: ValidatorPublicKey,
+}
+
+impl ExternalValidatorSigner {
+    /// Creates a new [`ExternalValidatorSigner`] for the validator with the given
+    /// `public_key`, running `program` with `args`.
+    pub fn new(program: PathBuf, args: Vec<String>, public_key: ValidatorPublicKey) -> Self {
+        ExternalValidatorSigner {
+            program: ExternalProgram { program, args },
+            public_key,
+        }
+    }
+}
+
+#[async_trait]
+impl ValidatorSigner for ExternalValidatorSigner {
+    fn public_key(&self) -> ValidatorPublicKey {
+        self.public_key
+    }
+
+    async fn sign(
+        &self,
+        prehash: CryptoHash,
+    ) -> Result<ValidatorSignature, Box<dyn std::error::Error + Send + Sync>> {
+        let request = Request::ValidatorSign {
+            public_key: self.public_key,
+            value: prehash,
+        };
+        // The signature is checked by the validator's keys, like those of any signer.
+        Ok(self.program.request(&request).await?)
+    }
+}
@@ -6,12 +6,25 @@
 #![recursion_limit = "256"]
 #![deny(clippy::large_futures)]
 
+pub mod address_book;
+pub mod chain_activity;
+pub mod chain_bundle;
 pub mod chain_listener;
 pub mod client_context;
 pub mod client_options;
 pub mod config;
 mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod external_signer;
+#[cfg(feature = "fs")]
+pub mod memo;
+pub mod multi_owner;
 pub mod persistent;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod progress;
+#[cfg(target_family = "unix")]
+pub mod sync_daemon;
+pub mod transfer_batch;
 pub mod util;
 pub mod wallet;
 
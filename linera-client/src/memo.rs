@@ -0,0 +1,168 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encrypted memos on transfers, e.g. to tag exchange deposits or to attach payment
+//! references without revealing them publicly.
+//!
+//! A memo is sealed to one of the recipient's Ed25519 keys: the sender generates a one-time
+//! key pair, and encrypts the memo with XChaCha20-Poly1305, using a key derived from the
+//! X25519 secret shared between the one-time key and the recipient's key. The recipient's
+//! wallet computes the same secret from its own key and the one-time public key.
+
+use chacha20poly1305::{
+    aead::{Aead as _, KeyInit as _},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use linera_base::{
+    crypto::{
+        AccountPublicKey, BcsHashable, CryptoHash, CryptoRng, Ed25519PublicKey, Ed25519SecretKey,
+        InMemorySigner,
+    },
+    data_types::Amount,
+    identifiers::AccountOwner,
+};
+use linera_chain::block::Block;
+use linera_execution::{
+    system::{EncryptedMemo, Recipient, MAXIMUM_MEMO_LEN},
+    Operation, SystemOperation,
+};
+use serde::{Deserialize, Serialize};
+
+/// The length of the authentication tag that XChaCha20-Poly1305 appends to the ciphertext.
+const TAG_LENGTH: usize = 16;
+
+/// The maximum length of a memo's text, in bytes.
+pub const MAXIMUM_MEMO_TEXT_LEN: usize = MAXIMUM_MEMO_LEN - TAG_LENGTH;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("memos can only be encrypted to Ed25519 keys")]
+    UnsupportedKey,
+    #[error("the memo has {0} bytes, but at most {MAXIMUM_MEMO_TEXT_LEN} are allowed")]
+    TooLong(usize),
+    #[error("the memo was not encrypted to a valid key")]
+    InvalidKey,
+    #[error("the memo could not be decrypted")]
+    Decryption,
+    #[error("the decrypted memo is not valid UTF-8")]
+    InvalidText,
+    #[error("failed to generate random bytes: {0}")]
+    Random(#[from] getrandom::Error),
+}
+
+/// What the key of a memo is derived from.
+#[derive(Serialize, Deserialize)]
+struct MemoKey {
+    shared_secret: [u8; 32],
+    ephemeral_key: Ed25519PublicKey,
+    recipient_key: Ed25519PublicKey,
+}
+
+impl BcsHashable<'_> for MemoKey {}
+
+impl MemoKey {
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        let key = CryptoHash::new(self);
+        XChaCha20Poly1305::new(Key::from_slice(key.as_bytes().as_slice()))
+    }
+}
+
+/// Encrypts `text` so that only the owner of `recipient_key` can read it.
+pub fn seal(text: &str, recipient_key: &AccountPublicKey) -> Result<EncryptedMemo, Error> {
+    let AccountPublicKey::Ed25519(recipient_key) = *recipient_key else {
+        return Err(Error::UnsupportedKey);
+    };
+    if text.len() > MAXIMUM_MEMO_TEXT_LEN {
+        return Err(Error::TooLong(text.len()));
+    }
+    let mut rng: Box<dyn CryptoRng> = None.into();
+    let ephemeral_secret = Ed25519SecretKey::generate_from(&mut rng);
+    let shared_secret = ephemeral_secret
+        .shared_secret(&recipient_key)
+        .ok_or(Error::InvalidKey)?;
+    let ephemeral_key = ephemeral_secret.public();
+    let mut nonce = [0; 24];
+    getrandom::getrandom(&mut nonce)?;
+    let key = MemoKey {
+        shared_secret,
+        ephemeral_key,
+        recipient_key,
+    };
+    let ciphertext = key
+        .cipher()
+        .encrypt(XNonce::from_slice(&nonce), text.as_bytes())
+        .map_err(|_| Error::InvalidKey)?;
+    Ok(EncryptedMemo {
+        ephemeral_key,
+        recipient_key,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Decrypts the `memo`, given the secret shared between the recipient's key and the
+/// memo's one-time key.
+pub fn open(memo: &EncryptedMemo, shared_secret: [u8; 32]) -> Result<String, Error> {
+    let key = MemoKey {
+        shared_secret,
+        ephemeral_key: memo.ephemeral_key,
+        recipient_key: memo.recipient_key,
+    };
+    let plaintext = key
+        .cipher()
+        .decrypt(XNonce::from_slice(&memo.nonce), memo.ciphertext.as_slice())
+        .map_err(|_| Error::Decryption)?;
+    String::from_utf8(plaintext).map_err(|_| Error::InvalidText)
+}
+
+/// A memo in a block that was decrypted with one of the wallet's keys.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReceivedMemo {
+    /// The index of the transfer among the block's operations.
+    pub operation_index: usize,
+    pub owner: AccountOwner,
+    pub recipient: Recipient,
+    pub amount: Amount,
+    /// The owner whose key the memo was encrypted to.
+    pub recipient_owner: AccountOwner,
+    pub text: String,
+}
+
+/// Returns the memos of the transfers in `block` that are encrypted to one of the
+/// `signer`'s keys.
+pub fn read_memos(block: &Block, signer: &InMemorySigner) -> Vec<ReceivedMemo> {
+    block
+        .body
+        .operations
+        .iter()
+        .enumerate()
+        .filter_map(|(operation_index, operation)| {
+            let Operation::System(operation) = operation else {
+                return None;
+            };
+            let SystemOperation::TransferWithMemo {
+                owner,
+                recipient,
+                amount,
+                memo,
+            } = operation.as_ref()
+            else {
+                return None;
+            };
+            let recipient_owner = AccountOwner::from(AccountPublicKey::Ed25519(memo.recipient_key));
+            let shared_secret = signer.shared_secret(
+                &recipient_owner,
+                &AccountPublicKey::Ed25519(memo.ephemeral_key),
+            )?;
+            let text = open(memo, shared_secret).ok()?;
+            Some(ReceivedMemo {
+                operation_index,
+                owner: *owner,
+                recipient: *recipient,
+                amount: *amount,
+                recipient_owner,
+                text,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,291 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The round state of chains with several owners, to coordinate their block proposals.
+//!
+//! Only some owners may propose a block in each round: the super owners in the fast round,
+//! all owners in the multi-leader rounds and a single leader in the other rounds. A
+//! [`RoundStatus`] combines the local view of the chain's current round with the votes of
+//! the validators, and tells each owner what they can do next.
+//!
+//! A block is proposed by a single owner: a co-owner's proposal can be exported with
+//! `linera proposal export --owner`, signed on their machine with `linera proposal sign`,
+//! and then submitted by anyone with `linera proposal submit`, which collects the
+//! validators' votes.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use linera_base::{
+    crypto::ValidatorPublicKey,
+    data_types::{BlockHeight, Round, Timestamp},
+    identifiers::{AccountOwner, ChainId},
+};
+use linera_chain::{
+    data_types::{LiteValue, LiteVote},
+    manager::ChainManagerInfo,
+};
+
+/// What an owner can do in the current round.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OwnerAction {
+    /// The owner proposed a block in the current round, which is waiting for votes.
+    Proposed,
+    /// The owner may propose a block in the current round. If a block is locked, that block
+    /// has to be proposed again.
+    CanPropose,
+    /// Another owner proposed a block in the current round.
+    Wait,
+    /// The owner may not propose before the current round times out.
+    WaitForTimeout,
+}
+
+impl fmt::Display for OwnerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnerAction::Proposed => write!(f, "proposed, waiting for votes"),
+            OwnerAction::CanPropose => write!(f, "can propose now"),
+            OwnerAction::Wait => write!(f, "nothing to do"),
+            OwnerAction::WaitForTimeout => write!(f, "waiting for the round to time out"),
+        }
+    }
+}
+
+/// An owner of the chain, and what they can do in the current round.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwnerStatus {
+    pub owner: AccountOwner,
+    pub is_super_owner: bool,
+    /// The weight of a regular owner, which determines how often they are round leader.
+    pub weight: Option<u64>,
+    pub action: OwnerAction,
+}
+
+/// A validator's view of the chain.
+#[derive(Clone, Debug)]
+pub enum ValidatorRoundState {
+    /// The validator responded.
+    Reachable {
+        next_block_height: BlockHeight,
+        current_round: Round,
+        /// The latest vote the validator cast on the chain, if any.
+        vote: Option<Box<LiteVote>>,
+    },
+    /// The validator could not be queried.
+    Unreachable(String),
+}
+
+/// A validator of the chain's committee, and its view of the chain.
+#[derive(Clone, Debug)]
+pub struct ValidatorStatus {
+    pub public_key: ValidatorPublicKey,
+    pub weight: u64,
+    pub state: ValidatorRoundState,
+}
+
+impl ValidatorStatus {
+    /// Returns the validator's vote, if it is for the given block height and round.
+    fn vote_in(&self, height: BlockHeight, round: Round) -> Option<&LiteVote> {
+        match &self.state {
+            ValidatorRoundState::Reachable {
+                next_block_height,
+                vote: Some(vote),
+                ..
+            } if *next_block_height == height && vote.round == round => Some(vote.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The state of the current round of a chain.
+#[derive(Clone, Debug)]
+pub struct RoundStatus {
+    pub chain_id: ChainId,
+    pub next_block_height: BlockHeight,
+    pub current_round: Round,
+    /// When the current round times out, if it does.
+    pub round_timeout: Option<Timestamp>,
+    /// The owner who proposed a block in the current round, if any.
+    pub proposer: Option<AccountOwner>,
+    /// The round of the locked block, if any. Only that block can be proposed, until a
+    /// different block is validated in a later round.
+    pub locking_round: Option<Round>,
+    pub owners: Vec<OwnerStatus>,
+    pub validators: Vec<ValidatorStatus>,
+    /// The total weight of the votes needed for a certificate.
+    pub quorum_threshold: u64,
+}
+
+impl RoundStatus {
+    /// Creates the [`RoundStatus`] of the chain with the local chain `manager` information,
+    /// at the given height. The `validators` are the members of the current committee.
+    pub fn new(
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+        manager: &ChainManagerInfo,
+        validators: Vec<ValidatorStatus>,
+        quorum_threshold: u64,
+    ) -> Self {
+        let current_round = manager.current_round;
+        let proposer = manager
+            .requested_proposed
+            .as_ref()
+            .filter(|proposal| {
+                proposal.content.round == current_round
+                    && proposal.content.block.height == next_block_height
+            })
+            .map(|proposal| AccountOwner::from(proposal.public_key));
+        let ownership = &manager.ownership;
+        let mut owners = BTreeMap::new();
+        for owner in &ownership.super_owners {
+            owners.insert(*owner, (true, None));
+        }
+        for (owner, weight) in &ownership.owners {
+            owners.entry(*owner).or_insert((false, None)).1 = Some(*weight);
+        }
+        let owners = owners
+            .into_iter()
+            .map(|(owner, (is_super_owner, weight))| {
+                let action = if proposer == Some(owner) {
+                    OwnerAction::Proposed
+                } else if proposer.is_some() {
+                    OwnerAction::Wait
+                } else if manager.can_propose(&owner, current_round) {
+                    OwnerAction::CanPropose
+                } else {
+                    OwnerAction::WaitForTimeout
+                };
+                OwnerStatus {
+                    owner,
+                    is_super_owner,
+                    weight,
+                    action,
+                }
+            })
+            .collect();
+        RoundStatus {
+            chain_id,
+            next_block_height,
+            current_round,
+            round_timeout: manager.round_timeout,
+            proposer,
+            locking_round: manager
+                .requested_locking
+                .as_ref()
+                .map(|locking| locking.round()),
+            owners,
+            validators,
+            quorum_threshold,
+        }
+    }
+
+    /// Returns the owners that still need to act in the current round: those who may
+    /// propose a block, or whose proposal is waiting for votes.
+    pub fn owners_to_act(&self) -> impl Iterator<Item = &OwnerStatus> {
+        self.owners.iter().filter(|owner| {
+            matches!(
+                owner.action,
+                OwnerAction::Proposed | OwnerAction::CanPropose
+            )
+        })
+    }
+
+    /// Returns the value with the most votes in the current round, with the total weight of
+    /// these votes.
+    pub fn leading_votes(&self) -> Option<(LiteValue, u64)> {
+        let mut weights = HashMap::<LiteValue, u64>::new();
+        for validator in &self.validators {
+            if let Some(vote) = validator.vote_in(self.next_block_height, self.current_round) {
+                *weights.entry(vote.value.clone()).or_default() += validator.weight;
+            }
+        }
+        weights.into_iter().max_by_key(|(_, weight)| *weight)
+    }
+}
+
+impl fmt::Display for RoundStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Chain {} at height {}, in the {}",
+            self.chain_id, self.next_block_height, self.current_round
+        )?;
+        if let Some(timeout) = self.round_timeout {
+            write!(f, ", timing out at {timeout}")?;
+        }
+        writeln!(f)?;
+        match self.proposer {
+            Some(owner) => writeln!(f, "Proposal in the current round by {owner}")?,
+            None => writeln!(f, "No proposal in the current round")?,
+        }
+        if let Some(round) = self.locking_round {
+            writeln!(
+                f,
+                "A block is locked since the {round} and has to be proposed again"
+            )?;
+        }
+        writeln!(f, "Owners:")?;
+        for status in &self.owners {
+            let role = match (status.is_super_owner, status.weight) {
+                (true, _) => "super owner".to_owned(),
+                (false, Some(weight)) => format!("weight {weight}"),
+                (false, None) => "owner".to_owned(),
+            };
+            writeln!(f, "  {} ({role}): {}", status.owner, status.action)?;
+        }
+        let owners_to_act = self
+            .owners_to_act()
+            .map(|status| status.owner.to_string())
+            .collect::<Vec<_>>();
+        if owners_to_act.is_empty() {
+            writeln!(f, "No owner can act before the round times out")?;
+        } else {
+            writeln!(
+                f,
+                "Owners that still need to act: {}",
+                owners_to_act.join(", ")
+            )?;
+        }
+        match self.leading_votes() {
+            Some((value, weight)) => writeln!(
+                f,
+                "Votes: {weight} of {} needed, to {:?} {}",
+                self.quorum_threshold, value.kind, value.value_hash
+            )?,
+            None => writeln!(
+                f,
+                "Votes: 0 of {} needed, no votes in the current round",
+                self.quorum_threshold
+            )?,
+        }
+        writeln!(f, "Validators:")?;
+        for validator in &self.validators {
+            write!(
+                f,
+                "  {} (weight {}): ",
+                validator.public_key, validator.weight
+            )?;
+            match &validator.state {
+                ValidatorRoundState::Reachable {
+                    next_block_height,
+                    current_round,
+                    vote,
+                } => {
+                    write!(f, "at height {next_block_height}, in the {current_round}")?;
+                    if let Some(vote) = vote {
+                        write!(
+                            f,
+                            ", voted to {:?} {} in the {}",
+                            vote.value.kind, vote.value.value_hash, vote.round
+                        )?;
+                    }
+                    writeln!(f)?;
+                }
+                ValidatorRoundState::Unreachable(error) => writeln!(f, "unreachable: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encryption of persistent files with a passphrase.
+//!
+//! The contents are encrypted with XChaCha20-Poly1305, using a key derived from the
+//! passphrase with Argon2id. The parameters of the key derivation are stored next to the
+//! ciphertext, so that they can be strengthened later without breaking existing files.
+
+use std::{io, sync::Mutex};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead as _, KeyInit as _},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use linera_base::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// The length of the salt used to derive a key, in bytes.
+const SALT_LENGTH: usize = 16;
+/// The length of a nonce of XChaCha20-Poly1305, in bytes.
+const NONCE_LENGTH: usize = 24;
+/// The length of a key of XChaCha20-Poly1305, in bytes.
+const KEY_LENGTH: usize = 32;
+
+/// A key derived from a passphrase.
+type DerivedKey = Zeroizing<[u8; KEY_LENGTH]>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("the file is encrypted and no passphrase was provided to unlock it")]
+    Locked,
+    #[error("wrong passphrase, or corrupted encrypted file")]
+    WrongPassphrase,
+    #[error("failed to read the passphrase: {0}")]
+    Passphrase(#[from] io::Error),
+    #[error("failed to derive a key from the passphrase: {0}")]
+    KeyDerivation(String),
+    #[error("failed to generate random bytes: {0}")]
+    Random(#[from] getrandom::Error),
+}
+
+/// The parameters used to derive a key from a passphrase with Argon2id.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KeyDerivation {
+    #[serde(with = "hex")]
+    salt: Vec<u8>,
+    /// The memory cost, in KiB.
+    memory_cost: u32,
+    /// The number of iterations.
+    time_cost: u32,
+    /// The degree of parallelism.
+    parallelism: u32,
+}
+
+impl KeyDerivation {
+    /// Returns new parameters with a random salt and the default costs.
+    pub fn generate() -> Result<Self, Error> {
+        let mut salt = vec![0; SALT_LENGTH];
+        getrandom::getrandom(&mut salt)?;
+        Ok(KeyDerivation {
+            salt,
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        })
+    }
+
+    /// Derives the key for the given `passphrase`.
+    fn derive(&self, passphrase: &str) -> Result<DerivedKey, Error> {
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|error| Error::KeyDerivation(error.to_string()))?;
+        let mut key = Zeroizing::new([0; KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, key.as_mut_slice())
+            .map_err(|error| Error::KeyDerivation(error.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// The encrypted contents of a file.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Encrypted {
+    argon2id: KeyDerivation,
+    #[serde(with = "hex")]
+    nonce: Vec<u8>,
+    #[serde(with = "hex")]
+    ciphertext: Vec<u8>,
+}
+
+/// The format of an encrypted file.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Envelope {
+    encrypted: Encrypted,
+}
+
+impl Envelope {
+    /// Returns whether the JSON `value` read from a file is an encrypted envelope.
+    pub fn is_envelope(value: &serde_json::Value) -> bool {
+        value.get("encrypted").is_some()
+    }
+
+    /// Encrypts `plaintext` with the key derived with the parameters `kdf`.
+    pub fn seal(plaintext: &[u8], kdf: &KeyDerivation, keys: &KeyCache) -> Result<Self, Error> {
+        let key = keys.key(kdf)?;
+        let mut nonce = vec![0; NONCE_LENGTH];
+        getrandom::getrandom(&mut nonce)?;
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| Error::WrongPassphrase)?;
+        keys.remember(kdf, key);
+        Ok(Envelope {
+            encrypted: Encrypted {
+                argon2id: kdf.clone(),
+                nonce,
+                ciphertext,
+            },
+        })
+    }
+
+    /// Decrypts the contents, returning them with the parameters of the key derivation.
+    pub fn open(self, keys: &KeyCache) -> Result<(Zeroizing<Vec<u8>>, KeyDerivation), Error> {
+        let Encrypted {
+            argon2id: kdf,
+            nonce,
+            ciphertext,
+        } = self.encrypted;
+        if nonce.len() != NONCE_LENGTH {
+            return Err(Error::WrongPassphrase);
+        }
+        let key = keys.key(&kdf)?;
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::WrongPassphrase)?;
+        keys.remember(&kdf, key);
+        Ok((Zeroizing::new(plaintext), kdf))
+    }
+}
+
+/// A key that was recently derived from a passphrase.
+struct CachedKey {
+    kdf: KeyDerivation,
+    key: DerivedKey,
+    expires_at: Instant,
+}
+
+/// Keeps the key derived from a passphrase in memory for a limited time, so that the
+/// passphrase is only requested again once that time has elapsed.
+pub struct KeyCache {
+    passphrase: Box<dyn Fn() -> io::Result<String> + Send + Sync>,
+    timeout: Duration,
+    cached: Mutex<Option<CachedKey>>,
+}
+
+impl KeyCache {
+    /// Creates a new [`KeyCache`] that keeps keys for `timeout` and calls `passphrase` to
+    /// obtain the passphrase when needed, e.g. by asking the user.
+    pub fn new(
+        timeout: Duration,
+        passphrase: impl Fn() -> io::Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        KeyCache {
+            passphrase: Box::new(passphrase),
+            timeout,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Drops the cached key, if any, so that the passphrase is requested on the next use.
+    pub fn lock(&self) {
+        *self.cache() = None;
+    }
+
+    /// Returns the key derived with `kdf`, using the cached one if it hasn't expired.
+    fn key(&self, kdf: &KeyDerivation) -> Result<DerivedKey, Error> {
+        if let Some(cached) = &*self.cache() {
+            if cached.kdf == *kdf && cached.expires_at > Instant::now() {
+                return Ok(cached.key.clone());
+            }
+        }
+        let passphrase = Zeroizing::new((self.passphrase)()?);
+        kdf.derive(&passphrase)
+    }
+
+    /// Caches a `key` derived with `kdf`, once it is known to be correct.
+    fn remember(&self, kdf: &KeyDerivation, key: DerivedKey) {
+        let mut cache = self.cache();
+        if let Some(cached) = &*cache {
+            if cached.kdf == *kdf && cached.expires_at > Instant::now() {
+                return;
+            }
+        }
+        *cache = Some(CachedKey {
+            kdf: kdf.clone(),
+            key,
+            expires_at: Instant::now() + self.timeout,
+        });
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, Option<CachedKey>> {
+        self.cached
+            .lock()
+            .expect("Panics should not happen while holding a lock to the key cache")
+    }
+}
@@ -4,12 +4,21 @@
 use std::{
     io::{self, BufRead as _, Write as _},
     path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use fs4::FileExt;
 use thiserror_context::Context;
+use zeroize::Zeroizing;
 
-use super::{Dirty, Persist};
+use super::{
+    encryption::{self, Envelope, KeyCache, KeyDerivation},
+    Dirty, Persist,
+};
+
+/// How often to try again to lock a file that another process holds.
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
 
 /// A guard that keeps an exclusive lock on a file.
 struct Lock(fs_err::File);
@@ -20,6 +29,8 @@ enum ErrorInner {
     IoError(#[from] std::io::Error),
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("encryption error: {0}")]
+    Encryption(#[from] encryption::Error),
 }
 
 thiserror_context::impl_context!(Error(ErrorInner));
@@ -62,8 +73,38 @@ impl Lock {
     /// Acquires an exclusive lock on a provided `file`, returning a [`Lock`] which will
     /// release the lock when dropped.
     pub fn new(file: fs_err::File) -> std::io::Result<Self> {
-        file.file().try_lock_exclusive()?;
-        Ok(Lock(file))
+        Self::wait(file, Duration::ZERO)
+    }
+
+    /// Acquires an exclusive lock on a provided `file`, waiting up to `timeout` for other
+    /// processes to release it.
+    pub fn wait(file: fs_err::File, timeout: Duration) -> std::io::Result<Self> {
+        let deadline = Instant::now() + timeout;
+        let mut waiting = false;
+        loop {
+            let error = match file.file().try_lock_exclusive() {
+                Ok(()) => return Ok(Lock(file)),
+                Err(error) => error,
+            };
+            let now = Instant::now();
+            if error.raw_os_error() != fs4::lock_contended_error().raw_os_error() {
+                return Err(error);
+            }
+            if now >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("{} is in use by another process", file.path().display()),
+                ));
+            }
+            if !waiting {
+                tracing::info!(
+                    "Waiting for another process to release {}",
+                    file.path().display()
+                );
+                waiting = true;
+            }
+            std::thread::sleep(LOCK_RETRY_DELAY.min(deadline - now));
+        }
     }
 }
 
@@ -79,11 +120,21 @@ impl Drop for Lock {
 /// An exclusive lock is taken using `flock(2)` to ensure that concurrent updates cannot
 /// happen, and writes are saved to a staging file before being moved over the old file,
 /// an operation that is atomic on all Unixes.
+///
+/// The file can be encrypted with a passphrase, in which case the passphrase is obtained
+/// from a [`KeyCache`] whenever the file is read or written.
 pub struct File<T> {
     _lock: Lock,
     path: std::path::PathBuf,
     value: T,
     dirty: Dirty,
+    encryption: Option<Encryption>,
+}
+
+/// The key derivation and the passphrases used to encrypt a [`File`].
+struct Encryption {
+    kdf: KeyDerivation,
+    keys: Arc<KeyCache>,
 }
 
 impl<T> std::ops::Deref for File<T> {
@@ -127,18 +178,40 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
             path: path.into(),
             value,
             dirty: Dirty::new(true),
+            encryption: None,
         };
         Ok(this)
     }
 
-    /// Reads the value from a file at `path`, returning an error if it does not exist.
+    /// Reads the value from a file at `path`, returning an error if it does not exist or
+    /// if it is encrypted.
     pub fn read(path: &Path) -> Result<Self, Error> {
-        Self::read_or_create(path, || {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("file is empty or does not exist: {}", path.display()),
-            )
-            .into())
+        Self::read_or_create(path, || Err(Self::not_found(path)))
+    }
+
+    /// Reads the value from a file at `path` like [`File::read`], but if another process
+    /// holds the file, waits up to `lock_timeout` for it to be released instead of failing
+    /// right away.
+    pub fn read_waiting(path: &Path, lock_timeout: Duration) -> Result<Self, Error> {
+        Self::open(path, None, lock_timeout, || Err(Self::not_found(path)))
+    }
+
+    /// Reads the value from a file at `path`, returning an error if it does not exist. If
+    /// the file is encrypted, it is decrypted with a passphrase obtained from `keys`, and
+    /// it remains encrypted when its value is persisted.
+    pub fn read_encrypted(path: &Path, keys: Arc<KeyCache>) -> Result<Self, Error> {
+        Self::read_encrypted_waiting(path, keys, Duration::ZERO)
+    }
+
+    /// Reads the value from a file at `path` like [`File::read_encrypted`], but if another
+    /// process holds the file, waits up to `lock_timeout` for it to be released.
+    pub fn read_encrypted_waiting(
+        path: &Path,
+        keys: Arc<KeyCache>,
+        lock_timeout: Duration,
+    ) -> Result<Self, Error> {
+        Self::open(path, Some(keys), lock_timeout, || {
+            Err(Self::not_found(path))
         })
     }
 
@@ -148,23 +221,73 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
         path: &Path,
         value: impl FnOnce() -> Result<T, Error>,
     ) -> Result<Self, Error> {
-        let lock = Lock::new(open_options().read(true).open(path)?)?;
+        Self::open(path, None, Duration::ZERO, value)
+    }
+
+    /// Encrypts the file with a passphrase obtained from `keys`, using a new key
+    /// derivation. The encrypted file is written the next time the value is persisted.
+    pub fn encrypt(&mut self, keys: Arc<KeyCache>) -> Result<(), Error> {
+        let kdf = KeyDerivation::generate()?;
+        self.encryption = Some(Encryption { kdf, keys });
+        *self.dirty = true;
+        Ok(())
+    }
+
+    /// Returns whether the file is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    fn not_found(path: &Path) -> Error {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("file is empty or does not exist: {}", path.display()),
+        )
+        .into()
+    }
+
+    fn open(
+        path: &Path,
+        keys: Option<Arc<KeyCache>>,
+        lock_timeout: Duration,
+        value: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<Self, Error> {
+        let lock = Lock::wait(open_options().read(true).open(path)?, lock_timeout)?;
         let mut reader = io::BufReader::new(&lock.0);
         let file_is_empty = reader.fill_buf()?.is_empty();
 
-        Ok(Self {
-            value: if file_is_empty {
-                value()?
+        let (value, encryption) = if file_is_empty {
+            (value()?, None)
+        } else {
+            let contents: serde_json::Value = serde_json::from_reader(reader)?;
+            if Envelope::is_envelope(&contents) {
+                let keys = keys.ok_or(encryption::Error::Locked)?;
+                let envelope: Envelope = serde_json::from_value(contents)?;
+                let (plaintext, kdf) = envelope.open(&keys)?;
+                let value = serde_json::from_slice(&plaintext)?;
+                (value, Some(Encryption { kdf, keys }))
             } else {
-                serde_json::from_reader(reader)?
-            },
+                (serde_json::from_value(contents)?, None)
+            }
+        };
+
+        Ok(Self {
+            value,
             dirty: Dirty::new(file_is_empty),
             path: path.into(),
             _lock: lock,
+            encryption,
         })
     }
 
     fn save(&mut self) -> Result<(), Error> {
+        let envelope = match &self.encryption {
+            None => None,
+            Some(Encryption { kdf, keys }) => {
+                let plaintext = Zeroizing::new(serde_json::to_vec(&self.value)?);
+                Some(Envelope::seal(&plaintext, kdf, keys)?)
+            }
+        };
         let mut temp_file_path = self.path.clone();
         temp_file_path.set_extension("json.new");
         let temp_file = open_options().open(&temp_file_path)?;
@@ -172,9 +295,12 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
 
         let remove_temp_file = || fs_err::remove_file(&temp_file_path);
 
-        serde_json::to_writer_pretty(&mut temp_file_writer, &self.value)
-            .map_err(Error::from)
-            .or_cleanup(remove_temp_file)?;
+        match &envelope {
+            None => serde_json::to_writer_pretty(&mut temp_file_writer, &self.value),
+            Some(envelope) => serde_json::to_writer_pretty(&mut temp_file_writer, envelope),
+        }
+        .map_err(Error::from)
+        .or_cleanup(remove_temp_file)?;
         temp_file_writer
             .flush()
             .map_err(Error::from)
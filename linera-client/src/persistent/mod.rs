@@ -13,7 +13,9 @@ cfg_if::cfg_if! {
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "fs")] {
+        pub mod encryption;
         pub mod file;
+        pub use encryption::KeyCache;
         pub use file::File;
     }
 }
@@ -0,0 +1,93 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Displays the progress of long-running operations on the standard error output.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use linera_core::progress::{ProgressEvent, ProgressReporter, ProgressTask, ProgressUnit};
+
+/// Writes each progress event as a line of JSON, for other programs to parse.
+pub struct JsonProgress;
+
+impl ProgressReporter for JsonProgress {
+    fn report(&self, event: ProgressEvent) {
+        eprintln!(
+            "{}",
+            serde_json::to_string(&event).expect("progress events should serialize to JSON")
+        );
+    }
+}
+
+/// Displays a progress bar for each task in progress. Nothing is displayed if the
+/// standard error output is not a terminal.
+#[derive(Default)]
+pub struct ProgressBars {
+    bars: MultiProgress,
+    tasks: Mutex<HashMap<ProgressTask, ProgressBar>>,
+}
+
+impl ProgressReporter for ProgressBars {
+    fn report(&self, event: ProgressEvent) {
+        let mut tasks = self.tasks.lock().unwrap();
+        match event {
+            ProgressEvent::Started { task, total, unit } => {
+                let bar = match total {
+                    Some(total) => ProgressBar::new(total).with_style(bar_style(unit)),
+                    None => {
+                        let spinner = ProgressBar::new_spinner().with_style(spinner_style(unit));
+                        spinner.enable_steady_tick(Duration::from_millis(200));
+                        spinner
+                    }
+                };
+                let bar = self.bars.add(bar.with_message(description(&task)));
+                if let Some(previous_bar) = tasks.insert(task, bar) {
+                    previous_bar.finish_and_clear();
+                }
+            }
+            ProgressEvent::Advanced { task, done } => {
+                if let Some(bar) = tasks.get(&task) {
+                    bar.set_position(done);
+                }
+            }
+            ProgressEvent::Finished { task } => {
+                if let Some(bar) = tasks.remove(&task) {
+                    bar.finish_and_clear();
+                    self.bars.remove(&bar);
+                }
+            }
+        }
+    }
+}
+
+fn description(task: &ProgressTask) -> String {
+    match task {
+        ProgressTask::SynchronizeChain { chain_id } => format!("Synchronizing chain {chain_id:.8}"),
+        ProgressTask::PublishBlobs { chain_id } => {
+            format!("Publishing blobs on chain {chain_id:.8}")
+        }
+        ProgressTask::Benchmark => "Benchmark".to_string(),
+        ProgressTask::CopyNamespace { namespace } => format!("Copying namespace {namespace}"),
+    }
+}
+
+fn bar_style(unit: ProgressUnit) -> ProgressStyle {
+    let template = match unit {
+        ProgressUnit::Blocks => "{msg} [{bar:40}] {pos}/{len} blocks ({eta})",
+        ProgressUnit::Bytes => "{msg} [{bar:40}] {bytes}/{total_bytes} ({elapsed})",
+        ProgressUnit::Keys => "{msg} [{bar:40}] {pos}/{len} keys ({eta})",
+    };
+    ProgressStyle::with_template(template)
+        .expect("the progress bar template is valid")
+        .progress_chars("=> ")
+}
+
+fn spinner_style(unit: ProgressUnit) -> ProgressStyle {
+    let template = match unit {
+        ProgressUnit::Blocks => "{spinner} {msg}: {pos} blocks ({per_sec}, {elapsed})",
+        ProgressUnit::Bytes => "{spinner} {msg}: {bytes} ({elapsed})",
+        ProgressUnit::Keys => "{spinner} {msg}: {pos} keys ({per_sec}, {elapsed})",
+    };
+    ProgressStyle::with_template(template).expect("the spinner template is valid")
+}
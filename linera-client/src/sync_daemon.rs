@@ -0,0 +1,230 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A long-running client process that keeps the wallet's chains synchronized.
+//!
+//! The [`ChainListener`](crate::chain_listener::ChainListener) follows the wallet's chains
+//! and processes their inboxes. Since the daemon holds the wallet, other commands cannot
+//! use it while the daemon runs: instead, they send [`Request`]s to the daemon's control
+//! socket, one JSON object per line, and receive one [`Response`] per line, e.g.:
+//!
+//! - `"status"` is answered with the wallet's chains,
+//! - `{"balance": {"chain_id": ..., "owner": ...}}` with the balance of the chain or owner,
+//! - `{"synchronize": {"chain_id": ...}}` with the chain's height after synchronizing it,
+//! - `{"process_inbox": {"chain_id": ...}}` with the number of blocks created,
+//! - `"stop"` with `"stopped"`, before the daemon shuts down.
+//!
+//! The chain ID can be omitted to use the wallet's default chain.
+
+use std::{path::Path, sync::Arc};
+
+use futures::{lock::Mutex, FutureExt as _};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight},
+    identifiers::{AccountOwner, ChainId},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::{UnixListener, UnixStream},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{chain_listener::ClientContext, error};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("control socket error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid message on the control socket: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the sync daemon closed the connection without responding")]
+    NoResponse,
+}
+
+/// A request sent to the sync daemon.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// Requests the state of the wallet's chains.
+    Status,
+    /// Requests the balance of a chain, or of one of its owners.
+    Balance {
+        chain_id: Option<ChainId>,
+        owner: Option<AccountOwner>,
+    },
+    /// Synchronizes a chain with the validators.
+    Synchronize { chain_id: Option<ChainId> },
+    /// Creates blocks to receive a chain's incoming messages.
+    ProcessInbox { chain_id: Option<ChainId> },
+    /// Shuts the daemon down.
+    Stop,
+}
+
+/// The sync daemon's response to a [`Request`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Status(Vec<ChainStatus>),
+    Balance(Amount),
+    Synchronized { next_block_height: BlockHeight },
+    InboxProcessed { blocks: usize },
+    Stopped,
+    Error(String),
+}
+
+/// The wallet's view of a chain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainStatus {
+    pub chain_id: ChainId,
+    pub owner: Option<AccountOwner>,
+    pub next_block_height: BlockHeight,
+    pub block_hash: Option<CryptoHash>,
+    pub is_default: bool,
+}
+
+/// Answers the requests received on a control socket, using the chains of a
+/// [`ClientContext`] shared with a chain listener.
+pub struct SyncDaemon<C: ClientContext> {
+    context: Arc<Mutex<C>>,
+    listener: UnixListener,
+    cancellation_token: CancellationToken,
+}
+
+impl<C: ClientContext> SyncDaemon<C> {
+    /// Creates a daemon listening on the socket at `path`. The `cancellation_token` is
+    /// cancelled when a client requests the daemon to stop.
+    pub fn bind(
+        path: &Path,
+        context: Arc<Mutex<C>>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, Error> {
+        let listener = UnixListener::bind(path)?;
+        info!("Listening for requests on {}", path.display());
+        Ok(SyncDaemon {
+            context,
+            listener,
+            cancellation_token,
+        })
+    }
+
+    /// Handles the connections to the control socket, one at a time, until the
+    /// cancellation token is cancelled.
+    pub async fn run(self) -> Result<(), Error> {
+        loop {
+            let stream = futures::select! {
+                () = self.cancellation_token.cancelled().fuse() => return Ok(()),
+                result = self.listener.accept().fuse() => result?.0,
+            };
+            if let Err(error) = self.handle_connection(stream).await {
+                warn!(%error, "Failed to handle a connection to the control socket");
+            }
+        }
+    }
+
+    async fn handle_connection(&self, stream: UnixStream) -> Result<(), Error> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle_request(request).await,
+                Err(error) => Response::Error(format!("invalid request: {error}")),
+            };
+            let mut bytes = serde_json::to_vec(&response)?;
+            bytes.push(b'\n');
+            writer.write_all(&bytes).await?;
+            if matches!(response, Response::Stopped) {
+                self.cancellation_token.cancel();
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_request(&self, request: Request) -> Response {
+        match self.try_handle_request(request).await {
+            Ok(response) => response,
+            Err(error) => Response::Error(error.to_string()),
+        }
+    }
+
+    async fn try_handle_request(&self, request: Request) -> Result<Response, crate::Error> {
+        let chain_id = match &request {
+            Request::Status => return Ok(Response::Status(self.status().await)),
+            Request::Stop => return Ok(Response::Stopped),
+            Request::Balance { chain_id, .. }
+            | Request::Synchronize { chain_id }
+            | Request::ProcessInbox { chain_id } => *chain_id,
+        };
+        let client = {
+            let context = self.context.lock().await;
+            let chain_id = match chain_id {
+                Some(chain_id) => chain_id,
+                None => context
+                    .wallet()
+                    .default_chain()
+                    .ok_or(error::Inner::NoDefaultChain)?,
+            };
+            context.make_chain_client(chain_id).await?
+        };
+        let response = match request {
+            Request::Balance { owner: None, .. } => {
+                Response::Balance(client.query_balance().await?)
+            }
+            Request::Balance {
+                owner: Some(owner), ..
+            } => Response::Balance(client.query_owner_balance(owner).await?),
+            Request::Synchronize { .. } => {
+                let info = client.synchronize_from_validators().await?;
+                Response::Synchronized {
+                    next_block_height: info.next_block_height,
+                }
+            }
+            Request::ProcessInbox { .. } => {
+                let (certificates, _) = client.process_inbox().await?;
+                Response::InboxProcessed {
+                    blocks: certificates.len(),
+                }
+            }
+            Request::Status | Request::Stop => unreachable!("handled above"),
+        };
+        self.context.lock().await.update_wallet(&client).await?;
+        Ok(response)
+    }
+
+    async fn status(&self) -> Vec<ChainStatus> {
+        let context = self.context.lock().await;
+        let wallet = context.wallet();
+        let default_chain = wallet.default_chain();
+        wallet
+            .chain_ids()
+            .into_iter()
+            .filter_map(|chain_id| wallet.get(chain_id))
+            .map(|chain| ChainStatus {
+                chain_id: chain.chain_id,
+                owner: chain.owner,
+                next_block_height: chain.next_block_height,
+                block_hash: chain.block_hash,
+                is_default: default_chain == Some(chain.chain_id),
+            })
+            .collect()
+    }
+}
+
+/// Sends a request to the sync daemon listening on the socket at `path`, and returns its
+/// response.
+pub async fn request(path: &Path, request: &Request) -> Result<Response, Error> {
+    let stream = UnixStream::connect(path).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut bytes = serde_json::to_vec(request)?;
+    bytes.push(b'\n');
+    writer.write_all(&bytes).await?;
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or(Error::NoResponse)?;
+    Ok(serde_json::from_str(&line)?)
+}
@@ -0,0 +1,170 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Batches of native-token transfers read from a CSV file, e.g. for payrolls or airdrops.
+//!
+//! Each line of the input is `<recipient>,<amount>`, where the recipient is an account
+//! formatted as `chain-id` or `chain-id:owner-type:address`. Empty lines and lines starting
+//! with `#` are ignored, and so is a first line `recipient,amount`.
+
+use std::{fmt, ops::Range};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight},
+    identifiers::{Account, AccountOwner},
+};
+use linera_execution::{
+    system::{Recipient, SystemOperation},
+    Operation,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid transfers:\n{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    InvalidLines(Vec<InvalidLine>),
+    #[error("the transfers add up to {needed}, but the sender only has {available}")]
+    InsufficientBalance { needed: Amount, available: Amount },
+}
+
+/// A line of the input that is not a valid transfer.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct InvalidLine {
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+/// A transfer read from the input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchTransfer {
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub recipient: Account,
+    pub amount: Amount,
+}
+
+impl BatchTransfer {
+    /// Returns the operation transferring the tokens from `owner`.
+    pub fn operation(&self, owner: AccountOwner) -> Operation {
+        Operation::system(SystemOperation::Transfer {
+            owner,
+            recipient: Recipient::Account(self.recipient),
+            amount: self.amount,
+        })
+    }
+}
+
+/// What happened to a transfer of a batch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransferStatus {
+    /// The transfer was confirmed in the block at `height`, whose certificate has the
+    /// given `hash`.
+    Confirmed {
+        height: BlockHeight,
+        hash: CryptoHash,
+    },
+    /// The block containing the transfer failed with this error.
+    Failed(String),
+    /// The transfer was not submitted, because an earlier block failed.
+    NotSubmitted,
+}
+
+impl fmt::Display for TransferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferStatus::Confirmed { height, hash } => {
+                write!(f, "confirmed at height {height} in {hash}")
+            }
+            TransferStatus::Failed(error) => write!(f, "failed: {error}"),
+            TransferStatus::NotSubmitted => write!(f, "not submitted"),
+        }
+    }
+}
+
+/// Parses the CSV `input`. All the invalid lines are reported at once.
+pub fn parse_transfers(input: &str) -> Result<Vec<BatchTransfer>, Error> {
+    let mut transfers = Vec::new();
+    let mut invalid_lines = Vec::new();
+    let mut is_first = true;
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let fields = text.split(',').map(str::trim).collect::<Vec<_>>();
+        if std::mem::take(&mut is_first)
+            && fields.len() == 2
+            && fields[0].eq_ignore_ascii_case("recipient")
+            && fields[1].eq_ignore_ascii_case("amount")
+        {
+            continue;
+        }
+        match parse_fields(&fields) {
+            Ok((recipient, amount)) => transfers.push(BatchTransfer {
+                line,
+                recipient,
+                amount,
+            }),
+            Err(message) => invalid_lines.push(InvalidLine { line, message }),
+        }
+    }
+    if !invalid_lines.is_empty() {
+        return Err(Error::InvalidLines(invalid_lines));
+    }
+    if transfers.is_empty() {
+        return Err(Error::InvalidLines(vec![InvalidLine {
+            line: 0,
+            message: "no transfers found".to_owned(),
+        }]));
+    }
+    Ok(transfers)
+}
+
+fn parse_fields(fields: &[&str]) -> Result<(Account, Amount), String> {
+    let [recipient, amount] = fields else {
+        return Err(format!(
+            "expected `<recipient>,<amount>`, found {} fields",
+            fields.len()
+        ));
+    };
+    let recipient = recipient
+        .parse::<Account>()
+        .map_err(|error| format!("invalid recipient {recipient:?}: {error}"))?;
+    let amount = amount
+        .parse::<Amount>()
+        .map_err(|error| format!("invalid amount {amount:?}: {error}"))?;
+    if amount == Amount::ZERO {
+        return Err("the amount must be positive".to_owned());
+    }
+    Ok((recipient, amount))
+}
+
+/// Splits the `operations` into as few blocks as possible, with at most `max_per_block`
+/// operations per block, and operations of at most `max_bytes` in total. Returns the range
+/// of operations in each block.
+pub fn plan_blocks(
+    operations: &[Operation],
+    max_per_block: usize,
+    max_bytes: u64,
+) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0u64;
+    for (index, operation) in operations.iter().enumerate() {
+        let size = bcs::serialized_size(operation).map_or(u64::MAX, |size| size as u64);
+        let is_full = index - start >= max_per_block || bytes.saturating_add(size) > max_bytes;
+        if index > start && is_full {
+            blocks.push(start..index);
+            start = index;
+            bytes = 0;
+        }
+        bytes = bytes.saturating_add(size);
+    }
+    if start < operations.len() {
+        blocks.push(start..operations.len());
+    }
+    blocks
+}
@@ -0,0 +1,44 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{AccountPublicKey, CryptoHash},
+    identifiers::{Account, AccountOwner, ChainId},
+};
+
+use crate::address_book::{is_valid_alias, AccountOrAlias, AddressBook, ChainOrAlias};
+
+#[test]
+fn test_address_book_resolution() -> anyhow::Result<()> {
+    let chain_id = ChainId(CryptoHash::test_hash("chain 1"));
+    let chain_id2 = ChainId(CryptoHash::test_hash("chain 2"));
+    let owner = AccountOwner::from(AccountPublicKey::test_key(1));
+    let account = Account::new(chain_id, owner);
+    let mut address_book = AddressBook::default();
+    address_book.add("alice".to_string(), account, false)?;
+    address_book.add("bob".to_string(), Account::chain(chain_id2), false)?;
+    assert!(address_book
+        .add("alice".to_string(), Account::chain(chain_id), false)
+        .is_err());
+    assert!(address_book
+        .add("0alice".to_string(), Account::chain(chain_id), false)
+        .is_err());
+
+    let alice: AccountOrAlias = "alice".parse()?;
+    assert!(matches!(alice, AccountOrAlias::Alias(_)));
+    assert_eq!(address_book.resolve_account(&alice)?, account);
+    assert_eq!(address_book.resolve_chain(&"alice".parse()?)?, chain_id);
+    let raw: AccountOrAlias = account.to_string().parse()?;
+    assert_eq!(address_book.resolve_account(&raw)?, account);
+    let raw: ChainOrAlias = chain_id.to_string().parse()?;
+    assert_eq!(address_book.resolve_chain(&raw)?, chain_id);
+    assert!(address_book.resolve_chain(&"carol".parse()?).is_err());
+    assert!("not an alias".parse::<AccountOrAlias>().is_err());
+    assert!(!is_valid_alias(&chain_id.to_string()));
+
+    assert!(address_book.knows_chain(chain_id2));
+    address_book.remove("bob")?;
+    assert!(!address_book.knows_chain(chain_id2));
+    assert!(address_book.remove("bob").is_err());
+    Ok(())
+}
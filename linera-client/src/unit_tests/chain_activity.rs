@@ -0,0 +1,65 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::InMemorySigner,
+    data_types::Amount,
+    identifiers::{AccountOwner, GenericApplicationId},
+};
+use linera_chain::data_types::MessageAction;
+use linera_core::{
+    test_utils::{MemoryStorageBuilder, TestBuilder},
+    worker::{Notification, Reason},
+};
+use linera_execution::system::Recipient;
+
+use crate::chain_activity::ChainActivity;
+
+#[test_log::test(tokio::test)]
+async fn test_chain_activity_of_new_block() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(Some(42));
+    let mut builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1, &mut signer).await?;
+    let client0 = builder.add_root_chain(0, Amount::ONE).await?;
+    let client1 = builder.add_root_chain(1, Amount::ONE).await?;
+    let certificate = client1
+        .transfer(
+            AccountOwner::CHAIN,
+            Amount::ONE,
+            Recipient::chain(client0.chain_id()),
+        )
+        .await?
+        .unwrap();
+    Box::pin(client0.receive_certificate_and_update_validators(certificate)).await?;
+    let (certificates, _) = Box::pin(client0.process_inbox()).await?;
+    let [certificate] = &certificates[..] else {
+        panic!("Expected a single block");
+    };
+
+    let notification = Notification {
+        chain_id: client0.chain_id(),
+        reason: Reason::NewBlock {
+            height: certificate.block().header.height,
+            hash: certificate.hash(),
+            event_streams: Default::default(),
+            application_ids: Default::default(),
+        },
+    };
+    let activity = ChainActivity::from_notification(client0.storage_client(), notification).await?;
+    let ChainActivity::NewBlock(block) = &activity else {
+        panic!("Expected a new block");
+    };
+    assert_eq!(block.hash, certificate.hash());
+    assert!(block.operations.is_empty());
+    let [message] = &block.incoming_messages[..] else {
+        panic!("Expected a single incoming message");
+    };
+    assert_eq!(message.origin, client1.chain_id());
+    assert_eq!(message.application_id, GenericApplicationId::System);
+    assert_eq!(message.action, MessageAction::Accept);
+    assert!(activity.to_string().contains(&format!(
+        "accepted Tracked message of system from chain {}",
+        client1.chain_id()
+    )));
+    assert!(serde_json::to_string(&activity)?.contains("NewBlock"));
+    Ok(())
+}
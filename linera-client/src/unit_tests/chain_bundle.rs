@@ -0,0 +1,64 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{CryptoHash, InMemorySigner},
+    data_types::{Amount, BlockHeight},
+    identifiers::AccountOwner,
+};
+use linera_core::test_utils::{MemoryStorageBuilder, TestBuilder};
+
+use super::util::make_genesis_config;
+use crate::{chain_bundle::ChainBundle, wallet::Wallet};
+
+#[test_log::test(tokio::test)]
+async fn test_chain_bundle_verify_tip() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(Some(42));
+    let mut builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1, &mut signer).await?;
+    let client0 = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+    let chain_id = client0.chain_id();
+    client0.burn(AccountOwner::CHAIN, Amount::ONE).await?;
+
+    let genesis_config = make_genesis_config(&builder);
+    let mut wallet = Wallet::new(genesis_config.clone());
+    wallet.update_from_state(&client0);
+    let bundle = ChainBundle::new(&wallet, chain_id)?;
+    bundle.check_network(&genesis_config)?;
+    let mut other_network = genesis_config.clone();
+    other_network.network_name = "other network".to_string();
+    assert!(bundle.check_network(&other_network).is_err());
+
+    // A client starting from the genesis state finds the exported tip.
+    let client = builder
+        .make_client(chain_id, None, BlockHeight::ZERO)
+        .await?;
+    let chain = bundle.verify_tip(&client).await?;
+    assert_eq!(chain.next_block_height, BlockHeight::from(1));
+    assert_eq!(chain.block_hash, bundle.chain.block_hash);
+
+    // A bundle with a block the validators don't have is rejected.
+    let mut tampered = bundle.clone();
+    tampered.chain.block_hash = Some(CryptoHash::test_hash("tampered"));
+    let Err(error) = tampered.verify_tip(&client).await else {
+        panic!("A bundle with an unknown block should be rejected");
+    };
+    assert!(error.to_string().contains("instead of the exported block"));
+
+    // If the chain moved on, the entry is updated to the latest block.
+    client0.burn(AccountOwner::CHAIN, Amount::ONE).await?;
+    let client = builder
+        .make_client(chain_id, None, BlockHeight::ZERO)
+        .await?;
+    let chain = bundle.verify_tip(&client).await?;
+    assert_eq!(chain.next_block_height, BlockHeight::from(2));
+    assert_eq!(chain.block_hash, client0.state().block_hash());
+
+    // A bundle from a tip that the validators don't know yet is rejected.
+    let mut ahead = bundle.clone();
+    ahead.chain.next_block_height = BlockHeight::from(3);
+    let Err(error) = ahead.verify_tip(&client).await else {
+        panic!("A bundle ahead of the validators should be rejected");
+    };
+    assert!(error.to_string().contains("the exported tip"));
+    Ok(())
+}
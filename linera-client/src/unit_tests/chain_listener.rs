@@ -18,6 +18,7 @@ use linera_core::{
     environment,
     node::CrossChainMessageDelivery,
     test_utils::{MemoryStorageBuilder, StorageBuilder as _, TestBuilder},
+    validator_health::CommunicationPolicy,
     DEFAULT_GRACE_PERIOD,
 };
 use linera_execution::system::Recipient;
@@ -30,9 +31,9 @@ use crate::{
     Error,
 };
 
-struct ClientContext {
-    wallet: Wallet,
-    client: Arc<Client<environment::Test>>,
+pub(super) struct ClientContext {
+    pub(super) wallet: Wallet,
+    pub(super) client: Arc<Client<environment::Test>>,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -139,6 +140,8 @@ async fn test_chain_listener() -> anyhow::Result<()> {
             NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            NonZeroUsize::new(4).expect("Certificate download parallelism should not be zero"),
+            CommunicationPolicy::default(),
         )),
     };
     context
@@ -196,3 +199,85 @@ async fn test_chain_listener() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that a chain listener with a limited number of active chains only listens to a chain
+/// and processes its inbox once it is activated.
+#[test_log::test(tokio::test)]
+async fn test_chain_listener_activation() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(Some(42));
+    let key_pair = signer.generate_new();
+    let owner: AccountOwner = key_pair.into();
+    let config = ChainListenerConfig {
+        max_active_chains: NonZeroUsize::new(1),
+        ..ChainListenerConfig::default()
+    };
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let client0 = builder.add_root_chain(0, Amount::ONE).await?;
+    let chain_id0 = client0.chain_id();
+    let client1 = builder.add_root_chain(1, Amount::ONE).await?;
+    let genesis_config = make_genesis_config(&builder);
+    let storage = builder.make_storage().await?;
+    let delivery = CrossChainMessageDelivery::NonBlocking;
+
+    let mut context = ClientContext {
+        wallet: Wallet::new(genesis_config),
+        client: Arc::new(Client::new(
+            environment::Impl {
+                storage: storage.clone(),
+                network: builder.make_node_provider(),
+            },
+            Box::new(signer),
+            10,
+            delivery,
+            false,
+            [chain_id0],
+            format!("Client node for {:.8}", chain_id0),
+            NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
+            DEFAULT_GRACE_PERIOD,
+            Duration::from_secs(1),
+            NonZeroUsize::new(4).expect("Certificate download parallelism should not be zero"),
+            CommunicationPolicy::default(),
+        )),
+    };
+    context
+        .update_wallet_for_new_chain(chain_id0, Some(owner), clock.current_time())
+        .await?;
+    client0
+        .change_ownership(ChainOwnership::single(owner))
+        .await?;
+
+    let context = Arc::new(Mutex::new(context));
+    let cancellation_token = CancellationToken::new();
+    let listener = ChainListener::new(config, context, storage, cancellation_token.child_token());
+    let activator = listener.activator();
+    let handle = linera_base::task::spawn(async move { listener.run().await.unwrap() });
+
+    // Transfer one token to chain 0. The listener doesn't listen to it yet.
+    let recipient0 = Recipient::chain(chain_id0);
+    client1
+        .transfer(AccountOwner::CHAIN, Amount::ONE, recipient0)
+        .await?;
+    client0.synchronize_from_validators().boxed().await?;
+    assert_eq!(client0.local_balance().await?, Amount::ONE);
+
+    // Once chain 0 is used, the listener receives the message.
+    activator.activate(chain_id0);
+    for i in 0.. {
+        client0.synchronize_from_validators().boxed().await?;
+        let balance = client0.local_balance().await?;
+        if balance == Amount::from_tokens(2) {
+            break;
+        }
+        linera_base::time::timer::sleep(Duration::from_millis(100)).await;
+        if i == 30 {
+            panic!("Unexpected local balance: {}", balance);
+        }
+    }
+
+    cancellation_token.cancel();
+    handle.await?;
+
+    Ok(())
+}
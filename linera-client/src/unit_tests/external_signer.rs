@@ -0,0 +1,141 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use linera_base::{
+    crypto::{
+        AccountPublicKey, AccountSignature, CryptoHash, InMemorySigner, Signer as _, TestString,
+        ValidatorKeypair, ValidatorSignature,
+    },
+    identifiers::AccountOwner,
+};
+use linera_chain::{data_types::ValidatorKeys, ChainError};
+
+use crate::external_signer::{ExternalSigner, ExternalValidatorSigner};
+
+/// Returns an external signer running a shell `script`, without any local keys.
+fn shell_signer(script: String) -> ExternalSigner {
+    ExternalSigner::new(
+        "sh".into(),
+        vec!["-c".to_owned(), script],
+        Box::new(InMemorySigner::new(Some(0))),
+    )
+}
+
+/// Returns a shell script answering requests for a public key with `public_key`, and the
+/// other requests with `response`.
+fn shell_script(public_key: &AccountPublicKey, response: &str) -> String {
+    let public_key = serde_json::to_string(public_key).unwrap();
+    format!(
+        "case \"$(cat)\" in *get_public_key*) echo '{public_key}';; *) echo '{response}';; esac"
+    )
+}
+
+#[test_log::test(tokio::test)]
+async fn test_external_signer() -> anyhow::Result<()> {
+    let mut remote = InMemorySigner::new(Some(42));
+    let public_key = remote.generate_new();
+    let owner = AccountOwner::from(public_key);
+    let value = CryptoHash::new(&TestString::new("value"));
+    let signature = remote.sign(&owner, &value).await.unwrap();
+
+    let response = serde_json::to_string(&signature)?;
+    let signer = shell_signer(shell_script(&public_key, &response));
+    let received: AccountSignature = signer.sign(&owner, &value).await.unwrap();
+    assert_eq!(received, signature);
+    assert_eq!(signer.get_public_key(&owner).await.unwrap(), public_key);
+
+    let signer = shell_signer("echo 'unknown key' >&2; exit 1".to_owned());
+    let error = signer.sign(&owner, &value).await.unwrap_err();
+    assert!(error.to_string().contains("unknown key"));
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_external_signer_checks_keys_and_signatures() -> anyhow::Result<()> {
+    let mut remote = InMemorySigner::new(Some(42));
+    let public_key = remote.generate_new();
+    let owner = AccountOwner::from(public_key);
+    let other_public_key = remote.generate_new();
+    let other_owner = AccountOwner::from(other_public_key);
+    let value = CryptoHash::new(&TestString::new("value"));
+
+    // A signature of another value is rejected.
+    let other_value = CryptoHash::new(&TestString::new("other value"));
+    let signature = remote.sign(&owner, &other_value).await.unwrap();
+    let response = serde_json::to_string(&signature)?;
+    let signer = shell_signer(shell_script(&public_key, &response));
+    let error = signer.sign(&owner, &value).await.unwrap_err();
+    assert!(error.to_string().contains("invalid signature"));
+
+    // A signature by another owner is rejected, whether or not the signer claims that it is
+    // the owner's key.
+    let signature = remote.sign(&other_owner, &value).await.unwrap();
+    let response = serde_json::to_string(&signature)?;
+    let signer = shell_signer(shell_script(&public_key, &response));
+    let error = signer.sign(&owner, &value).await.unwrap_err();
+    assert!(error.to_string().contains("invalid signature"));
+    let signer = shell_signer(shell_script(&other_public_key, &response));
+    let error = signer.sign(&owner, &value).await.unwrap_err();
+    assert!(error.to_string().contains("instead of"));
+    assert!(signer.get_public_key(&owner).await.is_err());
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_external_signer_uses_local_keys() -> anyhow::Result<()> {
+    let mut local = InMemorySigner::new(Some(42));
+    let owner = AccountOwner::from(local.generate_new());
+    let signer = ExternalSigner::new("false".into(), Vec::new(), Box::new(local));
+    assert!(signer.contains_key(&owner).await.unwrap());
+    let value = CryptoHash::new(&TestString::new("value"));
+    signer.sign(&owner, &value).await.unwrap();
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_external_validator_signer() -> anyhow::Result<()> {
+    let key_pair = ValidatorKeypair::generate();
+    let value = TestString::new("value");
+    let signature = ValidatorSignature::new(&value, &key_pair.secret_key);
+    let response = serde_json::to_string(&signature)?;
+    let script = format!("cat > /dev/null; echo '{response}'");
+    let signer = ExternalValidatorSigner::new(
+        "sh".into(),
+        vec!["-c".to_owned(), script],
+        key_pair.public_key,
+    );
+    let keys = ValidatorKeys::external(Arc::new(signer));
+    assert_eq!(keys.sign(&value).await.unwrap(), signature);
+
+    // The signature is rejected if it doesn't match the value or the validator's key.
+    let other_value = TestString::new("other value");
+    assert!(matches!(
+        keys.sign(&other_value).await,
+        Err(ChainError::CryptoError(_))
+    ));
+    let script = format!("cat > /dev/null; echo '{response}'");
+    let signer = ExternalValidatorSigner::new(
+        "sh".into(),
+        vec!["-c".to_owned(), script],
+        ValidatorKeypair::generate().public_key,
+    );
+    let keys = ValidatorKeys::external(Arc::new(signer));
+    assert!(matches!(
+        keys.sign(&value).await,
+        Err(ChainError::CryptoError(_))
+    ));
+
+    let signer = ExternalValidatorSigner::new(
+        "sh".into(),
+        vec!["-c".to_owned(), "echo 'no key' >&2; exit 1".to_owned()],
+        key_pair.public_key,
+    );
+    let keys = ValidatorKeys::external(Arc::new(signer));
+    assert!(matches!(
+        keys.sign(&value).await,
+        Err(ChainError::ValidatorSignerError(error)) if error.contains("no key")
+    ));
+    Ok(())
+}
@@ -0,0 +1,39 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{AccountPublicKey, InMemorySigner},
+    identifiers::AccountOwner,
+};
+
+use crate::memo::{self, Error, MAXIMUM_MEMO_TEXT_LEN};
+
+#[test]
+fn test_memo_is_only_readable_by_the_recipient() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(Some(42));
+    let recipient_key = signer.generate_new();
+    let recipient = AccountOwner::from(recipient_key);
+    let stranger = AccountOwner::from(signer.generate_new());
+
+    let memo = memo::seal("deposit 12345", &recipient_key)?;
+    assert!(!memo
+        .ciphertext
+        .windows(b"12345".len())
+        .any(|window| window == b"12345"));
+    let ephemeral_key = AccountPublicKey::Ed25519(memo.ephemeral_key);
+    let shared_secret = signer.shared_secret(&recipient, &ephemeral_key).unwrap();
+    assert_eq!(memo::open(&memo, shared_secret)?, "deposit 12345");
+
+    let wrong_secret = signer.shared_secret(&stranger, &ephemeral_key).unwrap();
+    assert!(matches!(
+        memo::open(&memo, wrong_secret),
+        Err(Error::Decryption)
+    ));
+
+    let too_long = "x".repeat(MAXIMUM_MEMO_TEXT_LEN + 1);
+    assert!(matches!(
+        memo::seal(&too_long, &recipient_key),
+        Err(Error::TooLong(_))
+    ));
+    Ok(())
+}
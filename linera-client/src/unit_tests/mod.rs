@@ -1,7 +1,20 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod address_book;
+mod chain_activity;
+mod chain_bundle;
 mod chain_listener;
+#[cfg(target_family = "unix")]
+mod external_signer;
+#[cfg(feature = "fs")]
+mod memo;
+mod multi_owner;
+#[cfg(feature = "fs")]
+mod persistent;
+#[cfg(target_family = "unix")]
+mod sync_daemon;
+mod transfer_batch;
 mod util;
 #[cfg(feature = "fs")]
 mod wallet;
@@ -0,0 +1,105 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::{CryptoHash, InMemorySigner, ValidatorKeypair},
+    data_types::{BlockHeight, Round},
+    identifiers::{AccountOwner, ChainId},
+    ownership::{ChainOwnership, TimeoutConfig},
+};
+use linera_chain::{
+    data_types::{BlockProposal, LiteValue, LiteVote},
+    manager::ChainManagerInfo,
+    test::make_first_block,
+    types::CertificateKind,
+};
+
+use crate::multi_owner::{OwnerAction, RoundStatus, ValidatorRoundState, ValidatorStatus};
+
+fn validator(next_block_height: BlockHeight, vote: Option<(LiteValue, Round)>) -> ValidatorStatus {
+    let keypair = ValidatorKeypair::generate();
+    let vote =
+        vote.map(|(value, round)| Box::new(LiteVote::new(value, round, &keypair.secret_key)));
+    ValidatorStatus {
+        public_key: keypair.public_key,
+        weight: 1,
+        state: ValidatorRoundState::Reachable {
+            next_block_height,
+            current_round: Round::SingleLeader(0),
+            vote,
+        },
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn test_round_status() -> anyhow::Result<()> {
+    let chain_id = ChainId(CryptoHash::test_hash("chain"));
+    let mut signer = InMemorySigner::new(Some(42));
+    let leader = AccountOwner::from(signer.generate_new());
+    let other = AccountOwner::from(signer.generate_new());
+    let ownership =
+        ChainOwnership::multiple([(leader, 100), (other, 50)], 0, TimeoutConfig::default());
+    let mut manager = ChainManagerInfo {
+        ownership,
+        current_round: Round::SingleLeader(0),
+        leader: Some(leader),
+        ..ChainManagerInfo::default()
+    };
+
+    let status = RoundStatus::new(chain_id, BlockHeight::ZERO, &manager, Vec::new(), 3);
+    assert_eq!(status.proposer, None);
+    assert_eq!(status.owners.len(), 2);
+    let action_of = |status: &RoundStatus, owner| {
+        status
+            .owners
+            .iter()
+            .find(|status| status.owner == owner)
+            .unwrap()
+            .action
+    };
+    assert_eq!(action_of(&status, leader), OwnerAction::CanPropose);
+    assert_eq!(action_of(&status, other), OwnerAction::WaitForTimeout);
+    assert_eq!(
+        status
+            .owners_to_act()
+            .map(|status| status.owner)
+            .collect::<Vec<_>>(),
+        vec![leader]
+    );
+    assert!(status.leading_votes().is_none());
+
+    // Once the leader proposed, the validators' votes are counted.
+    let block = make_first_block(chain_id);
+    let proposal = BlockProposal::new_initial(leader, Round::SingleLeader(0), block, &signer)
+        .await
+        .unwrap();
+    manager.requested_proposed = Some(Box::new(proposal));
+    let value = LiteValue {
+        value_hash: CryptoHash::test_hash("block"),
+        chain_id,
+        kind: CertificateKind::Validated,
+    };
+    let validators = vec![
+        validator(
+            BlockHeight::ZERO,
+            Some((value.clone(), Round::SingleLeader(0))),
+        ),
+        validator(
+            BlockHeight::ZERO,
+            Some((value.clone(), Round::SingleLeader(0))),
+        ),
+        // Votes from earlier rounds don't count.
+        validator(
+            BlockHeight::ZERO,
+            Some((value.clone(), Round::MultiLeader(0))),
+        ),
+        validator(BlockHeight::ZERO, None),
+    ];
+    let status = RoundStatus::new(chain_id, BlockHeight::ZERO, &manager, validators, 3);
+    assert_eq!(status.proposer, Some(leader));
+    assert_eq!(action_of(&status, leader), OwnerAction::Proposed);
+    assert_eq!(action_of(&status, other), OwnerAction::Wait);
+    assert_eq!(status.leading_votes(), Some((value, 2)));
+    assert!(status.to_string().contains("Votes: 2 of 3 needed"));
+    Ok(())
+}
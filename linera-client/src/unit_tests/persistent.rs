@@ -0,0 +1,107 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use linera_base::{crypto::InMemorySigner, time::Duration};
+
+use crate::persistent::{self, KeyCache, Persist as _};
+
+/// Returns a key cache with the given `passphrase`, and the number of times it was requested.
+fn key_cache(passphrase: &'static str, timeout: Duration) -> (Arc<KeyCache>, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let keys = KeyCache::new(timeout, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(passphrase.to_owned())
+    });
+    (Arc::new(keys), requests)
+}
+
+/// Tests that a plaintext keystore can be encrypted and is only readable with the right
+/// passphrase.
+#[test_log::test(tokio::test)]
+async fn test_encrypted_keystore() -> anyhow::Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("keystore.json");
+    let mut signer = InMemorySigner::new(Some(42));
+    signer.generate_new();
+    let keys = signer.keys();
+
+    let mut file = persistent::File::new(&path, signer)?;
+    file.persist().await?;
+    drop(file);
+
+    // Plaintext keystores can still be read, with or without a passphrase.
+    let (plaintext_keys, _) = key_cache("unused", Duration::from_secs(60));
+    let mut file = persistent::File::<InMemorySigner>::read_encrypted(&path, plaintext_keys)?;
+    assert!(!file.is_encrypted());
+    assert_eq!(file.keys(), keys);
+
+    let (cache, requests) = key_cache("correct horse", Duration::from_secs(60));
+    file.encrypt(cache.clone())?;
+    file.persist().await?;
+    file.persist().await?;
+    // The derived key is cached after the first use.
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    drop(file);
+
+    assert!(!fs_err::read_to_string(&path)?.contains("keys"));
+    assert!(persistent::File::<InMemorySigner>::read(&path).is_err());
+    let (wrong_keys, _) = key_cache("wrong", Duration::from_secs(60));
+    assert!(persistent::File::<InMemorySigner>::read_encrypted(&path, wrong_keys).is_err());
+
+    let file = persistent::File::<InMemorySigner>::read_encrypted(&path, cache.clone())?;
+    assert!(file.is_encrypted());
+    assert_eq!(file.keys(), keys);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    drop(file);
+
+    // Once locked, the passphrase is requested again.
+    cache.lock();
+    persistent::File::<InMemorySigner>::read_encrypted(&path, cache)?;
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+/// Tests that the passphrase is requested again once the cached key has expired.
+#[test_log::test(tokio::test)]
+async fn test_key_cache_timeout() -> anyhow::Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("keystore.json");
+    let (cache, requests) = key_cache("passphrase", Duration::ZERO);
+
+    let mut file = persistent::File::new(&path, InMemorySigner::new(Some(42)))?;
+    file.encrypt(cache)?;
+    file.persist().await?;
+    file.persist().await?;
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+/// Tests that a file held by another user can be waited for, instead of failing right away.
+#[test_log::test(tokio::test)]
+async fn test_wait_for_lock() -> anyhow::Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("wallet.json");
+    let mut file = persistent::File::new(&path, 42u64)?;
+    file.persist().await?;
+    drop(file);
+    // Persisting replaces the file, so the lock is taken again on the new one.
+    let file = persistent::File::<u64>::read(&path)?;
+
+    assert!(persistent::File::<u64>::read(&path).is_err());
+    assert!(persistent::File::<u64>::read_waiting(&path, Duration::from_millis(10)).is_err());
+
+    let holder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        drop(file);
+    });
+    let file = persistent::File::<u64>::read_waiting(&path, Duration::from_secs(10))?;
+    assert_eq!(*file, 42);
+    holder.join().unwrap();
+    Ok(())
+}
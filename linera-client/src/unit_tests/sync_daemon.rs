@@ -0,0 +1,86 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+use futures::lock::Mutex;
+use linera_base::{crypto::InMemorySigner, data_types::Amount};
+use linera_core::{
+    client::Client,
+    environment,
+    node::CrossChainMessageDelivery,
+    test_utils::{MemoryStorageBuilder, TestBuilder},
+    validator_health::CommunicationPolicy,
+    DEFAULT_GRACE_PERIOD,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::{chain_listener::ClientContext, util::make_genesis_config};
+use crate::{
+    sync_daemon::{self, Request, Response, SyncDaemon},
+    wallet::Wallet,
+};
+
+#[test_log::test(tokio::test)]
+async fn test_sync_daemon_requests() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(Some(42));
+    let mut builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1, &mut signer).await?;
+    let client0 = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+    let chain_id = client0.chain_id();
+    let mut wallet = Wallet::new(make_genesis_config(&builder));
+    wallet.update_from_state(&client0);
+    wallet.set_default_chain(chain_id)?;
+    let storage = builder.make_storage().await?;
+    let context = ClientContext {
+        wallet,
+        client: Arc::new(Client::new(
+            environment::Impl {
+                storage,
+                network: builder.make_node_provider(),
+            },
+            Box::new(signer),
+            10,
+            CrossChainMessageDelivery::NonBlocking,
+            false,
+            [chain_id],
+            format!("Client node for {:.8}", chain_id),
+            NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
+            DEFAULT_GRACE_PERIOD,
+            Duration::from_secs(1),
+            NonZeroUsize::new(4).expect("Certificate download parallelism should not be zero"),
+            CommunicationPolicy::default(),
+        )),
+    };
+
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("wallet.sock");
+    let cancellation_token = CancellationToken::new();
+    let daemon = SyncDaemon::bind(
+        &path,
+        Arc::new(Mutex::new(context)),
+        cancellation_token.clone(),
+    )?;
+    let handle = tokio::spawn(daemon.run());
+
+    let Response::Status(chains) = sync_daemon::request(&path, &Request::Status).await? else {
+        panic!("Expected the wallet's chains");
+    };
+    let [chain] = &chains[..] else {
+        panic!("Expected a single chain");
+    };
+    assert_eq!(chain.chain_id, chain_id);
+    assert!(chain.is_default);
+
+    let request = Request::Balance {
+        chain_id: None,
+        owner: None,
+    };
+    let response = sync_daemon::request(&path, &request).await?;
+    assert!(matches!(response, Response::Balance(balance) if balance == Amount::from_tokens(3)));
+
+    let response = sync_daemon::request(&path, &Request::Stop).await?;
+    assert!(matches!(response, Response::Stopped));
+    handle.await??;
+    assert!(cancellation_token.is_cancelled());
+    Ok(())
+}
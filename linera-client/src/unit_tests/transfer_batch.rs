@@ -0,0 +1,75 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::Amount,
+    identifiers::{Account, AccountOwner, ChainId},
+};
+
+use crate::transfer_batch::{parse_transfers, plan_blocks, Error, InvalidLine};
+
+#[test]
+fn test_parse_transfers() {
+    let chain_id = ChainId(CryptoHash::test_hash("recipient"));
+    let owner = AccountOwner::from(CryptoHash::test_hash("owner"));
+    let input = format!(
+        "recipient,amount\n\
+         {chain_id},1.5\n\
+         \n\
+         # A comment.\n\
+         {chain_id}:{owner}, 2\n"
+    );
+    let transfers = parse_transfers(&input).unwrap();
+    assert_eq!(transfers.len(), 2);
+    assert_eq!(transfers[0].line, 2);
+    assert_eq!(transfers[0].recipient, Account::chain(chain_id));
+    assert_eq!(transfers[0].amount, Amount::from_millis(1500));
+    assert_eq!(transfers[1].line, 5);
+    assert_eq!(transfers[1].recipient, Account::new(chain_id, owner));
+    assert_eq!(transfers[1].amount, Amount::from_tokens(2));
+}
+
+#[test]
+fn test_parse_transfers_reports_all_invalid_lines() {
+    let chain_id = ChainId(CryptoHash::test_hash("recipient"));
+    let input = format!("{chain_id},1\nnot-a-chain,1\n{chain_id},0\n{chain_id}\n");
+    let Err(Error::InvalidLines(lines)) = parse_transfers(&input) else {
+        panic!("Expected invalid lines");
+    };
+    let numbers = lines.iter().map(|line| line.line).collect::<Vec<_>>();
+    assert_eq!(numbers, vec![2, 3, 4]);
+
+    let Err(Error::InvalidLines(lines)) = parse_transfers("recipient,amount\n") else {
+        panic!("Expected an error for an empty batch");
+    };
+    assert_eq!(
+        lines,
+        vec![InvalidLine {
+            line: 0,
+            message: "no transfers found".to_owned()
+        }]
+    );
+}
+
+#[test]
+fn test_plan_blocks() {
+    let chain_id = ChainId(CryptoHash::test_hash("recipient"));
+    let input = format!("{chain_id},1\n").repeat(5);
+    let owner = AccountOwner::CHAIN;
+    let operations = parse_transfers(&input)
+        .unwrap()
+        .iter()
+        .map(|transfer| transfer.operation(owner))
+        .collect::<Vec<_>>();
+    assert_eq!(plan_blocks(&operations, 100, u64::MAX), vec![0..5]);
+    assert_eq!(
+        plan_blocks(&operations, 2, u64::MAX),
+        vec![0..2, 2..4, 4..5]
+    );
+
+    let size = bcs::serialized_size(&operations[0]).unwrap() as u64;
+    assert_eq!(plan_blocks(&operations, 100, 3 * size), vec![0..3, 3..5]);
+    // Operations that are too large on their own still get a block each.
+    assert_eq!(plan_blocks(&operations, 100, 0).len(), 5);
+}
@@ -24,10 +24,14 @@ pub fn make_genesis_config(builder: &TestBuilder<MemoryStorageBuilder>) -> Genes
             public_key: *public_key,
             network: network.clone(),
             account_key: state.account_public_key,
+            bls_key: state.bls_key,
         })
         .collect();
     let mut genesis_config = GenesisConfig::new(
-        CommitteeConfig { validators },
+        CommitteeConfig {
+            validators,
+            certificate_signature_scheme: builder.initial_committee.certificate_signature_scheme(),
+        },
         builder.admin_id(),
         Timestamp::from(0),
         builder.initial_committee.policy().clone(),
@@ -15,13 +15,15 @@ use linera_core::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{config::GenesisConfig, error, Error};
+use crate::{address_book::AddressBook, config::GenesisConfig, error, Error};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Wallet {
     pub chains: BTreeMap<ChainId, UserChain>,
     pub default: Option<ChainId>,
     pub genesis_config: GenesisConfig,
+    #[serde(default, skip_serializing_if = "AddressBook::is_empty")]
+    pub address_book: AddressBook,
 }
 
 impl Extend<UserChain> for Wallet {
@@ -38,6 +40,7 @@ impl Wallet {
             chains: BTreeMap::new(),
             default: None,
             genesis_config,
+            address_book: AddressBook::default(),
         }
     }
 
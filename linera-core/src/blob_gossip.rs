@@ -0,0 +1,153 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fetching the blobs of block proposals from other validators.
+//!
+//! A block proposal that publishes blobs is only accepted by a validator once it has all of
+//! them. Instead of expecting the client to upload the blobs to every validator, a validator
+//! that is missing some asks the other validators of the chain's committee for them: it is
+//! enough for the client to upload the blobs to a single validator.
+
+use std::collections::BTreeSet;
+
+use futures::future;
+use linera_base::{
+    crypto::ValidatorPublicKey,
+    data_types::Blob,
+    identifiers::{BlobId, ChainId},
+};
+use linera_chain::data_types::BlockProposal;
+use linera_execution::committee::Committee;
+use linera_storage::Storage;
+use tracing::{debug, warn};
+
+use crate::{
+    data_types::ChainInfoResponse,
+    node::{ValidatorNode as _, ValidatorNodeProvider},
+    worker::{NetworkActions, WorkerError, WorkerState},
+};
+
+/// Downloads missing proposal blobs from the other validators.
+pub struct BlobGossip<P> {
+    node_provider: P,
+    /// The public key of this validator, which is not asked for blobs.
+    public_key: ValidatorPublicKey,
+}
+
+impl<P: ValidatorNodeProvider> BlobGossip<P> {
+    /// Creates a new [`BlobGossip`] for the validator with the given public key, connecting
+    /// to the other validators with `node_provider`.
+    pub fn new(node_provider: P, public_key: ValidatorPublicKey) -> Self {
+        BlobGossip {
+            node_provider,
+            public_key,
+        }
+    }
+
+    /// Handles a block proposal. If the worker is missing some of the blobs published by the
+    /// proposal, they are downloaded from the other validators and the proposal is handled
+    /// again.
+    pub async fn handle_block_proposal<S>(
+        &self,
+        worker: &WorkerState<S>,
+        proposal: BlockProposal,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let result = Box::pin(worker.handle_block_proposal(proposal.clone())).await;
+        let Err(WorkerError::BlobsNotFound(missing_blob_ids)) = &result else {
+            return result;
+        };
+        // Only the published blobs are pending: the others must already be confirmed, and
+        // the client is expected to send the validator the corresponding blocks.
+        let published_blob_ids = proposal.content.block.published_blob_ids();
+        let blob_ids = missing_blob_ids
+            .iter()
+            .filter(|blob_id| published_blob_ids.contains(blob_id))
+            .copied()
+            .collect::<Vec<_>>();
+        if blob_ids.is_empty() {
+            return result;
+        }
+        let chain_id = proposal.content.block.chain_id;
+        let committee = {
+            let chain = worker.chain_state_view(chain_id).await?;
+            match chain.execution_state.system.current_committee() {
+                Some((_, committee)) => committee.clone(),
+                None => return result,
+            }
+        };
+        let blobs = self
+            .download_pending_blobs(&committee, chain_id, &blob_ids)
+            .await;
+        if blobs.is_empty() {
+            return result;
+        }
+        for blob in blobs {
+            worker.handle_pending_blob(chain_id, blob).await?;
+        }
+        Box::pin(worker.handle_block_proposal(proposal)).await
+    }
+
+    /// Downloads the pending blobs of a chain from the other validators of the committee.
+    /// Returns the ones that could be found, after checking that they have the requested IDs.
+    pub async fn download_pending_blobs(
+        &self,
+        committee: &Committee,
+        chain_id: ChainId,
+        blob_ids: &[BlobId],
+    ) -> Vec<Blob> {
+        let mut nodes = match self.node_provider.make_nodes(committee) {
+            Ok(nodes) => nodes.collect::<Vec<_>>(),
+            Err(error) => {
+                warn!(%error, "Failed to connect to the other validators");
+                return Vec::new();
+            }
+        };
+        // Start with the validator after this one, so that not every validator asks the
+        // same one first.
+        if let Some(index) = nodes
+            .iter()
+            .position(|(public_key, _)| *public_key == self.public_key)
+        {
+            nodes.remove(index);
+            let len = nodes.len().max(1);
+            nodes.rotate_left(index % len);
+        }
+        let mut missing_blob_ids = blob_ids.iter().copied().collect::<BTreeSet<_>>();
+        let mut blobs = Vec::new();
+        for (public_key, node) in nodes {
+            if missing_blob_ids.is_empty() {
+                break;
+            }
+            let results = future::join_all(missing_blob_ids.iter().map(|blob_id| {
+                let node = &node;
+                async move {
+                    (
+                        *blob_id,
+                        node.download_pending_blob(chain_id, *blob_id).await,
+                    )
+                }
+            }))
+            .await;
+            for (blob_id, result) in results {
+                match result {
+                    Ok(content) => {
+                        let blob = Blob::new(content);
+                        if blob.id() == blob_id {
+                            missing_blob_ids.remove(&blob_id);
+                            blobs.push(blob);
+                        } else {
+                            warn!(%public_key, %blob_id, "Validator sent a blob with the wrong ID");
+                        }
+                    }
+                    Err(error) => {
+                        debug!(%public_key, %blob_id, %error, "Validator sent no pending blob");
+                    }
+                }
+            }
+        }
+        blobs
+    }
+}
@@ -11,25 +11,29 @@ use std::{
 
 use custom_debug_derive::Debug;
 use linera_base::{
+    bcs,
     crypto::{CryptoHash, ValidatorPublicKey},
     data_types::{ApplicationDescription, Blob, BlockHeight, Epoch, Timestamp},
     hashed::Hashed,
     identifiers::{ApplicationId, BlobId, ChainId},
 };
 use linera_chain::{
-    data_types::{BlockProposal, MessageBundle, ProposedBlock},
+    data_types::{BlockExecutionResources, BlockProposal, MessageBundle, ProposedBlock},
     types::{Block, ConfirmedBlockCertificate, TimeoutCertificate, ValidatedBlockCertificate},
     ChainStateView,
 };
 use linera_execution::{
-    ExecutionStateView, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint,
-    ServiceSyncRuntime,
+    Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint, ServiceSyncRuntime,
 };
 use linera_storage::Storage;
 use tokio::sync::{mpsc, oneshot, OwnedRwLockReadGuard};
 use tracing::{debug, instrument, trace, warn, Instrument as _};
 
-use super::{config::ChainWorkerConfig, state::ChainWorkerState, DeliveryNotifier};
+use super::{
+    config::ChainWorkerConfig,
+    state::{ChainWorkerState, ExecutionStateCache},
+    DeliveryNotifier,
+};
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
     value_cache::ValueCache,
@@ -88,7 +92,9 @@ where
         round: Option<u32>,
         published_blobs: Vec<Blob>,
         #[debug(skip)]
-        callback: oneshot::Sender<Result<(Block, ChainInfoResponse), WorkerError>>,
+        callback: oneshot::Sender<
+            Result<(Block, ChainInfoResponse, BlockExecutionResources), WorkerError>,
+        >,
     },
 
     /// Process a leader timeout issued for this multi-owner chain.
@@ -188,9 +194,7 @@ where
         config: ChainWorkerConfig,
         storage: StorageClient,
         block_cache: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
-        execution_state_cache: Arc<
-            ValueCache<CryptoHash, ExecutionStateView<StorageClient::Context>>,
-        >,
+        execution_state_cache: Arc<ExecutionStateCache<StorageClient::Context>>,
         tracked_chains: Option<Arc<RwLock<HashSet<ChainId>>>>,
         delivery_notifier: DeliveryNotifier,
         chain_id: ChainId,
@@ -230,9 +234,7 @@ where
         config: ChainWorkerConfig,
         storage: StorageClient,
         block_cache: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
-        execution_state_cache: Arc<
-            ValueCache<CryptoHash, ExecutionStateView<StorageClient::Context>>,
-        >,
+        execution_state_cache: Arc<ExecutionStateCache<StorageClient::Context>>,
         tracked_chains: Option<Arc<RwLock<HashSet<ChainId>>>>,
         delivery_notifier: DeliveryNotifier,
         chain_id: ChainId,
@@ -373,7 +375,7 @@ where
                 .send(self.worker.process_timeout(certificate).await)
                 .is_ok(),
             ChainWorkerRequest::HandleBlockProposal { proposal, callback } => callback
-                .send(self.worker.handle_block_proposal(proposal).await)
+                .send(Box::pin(self.worker.handle_block_proposal(proposal)).await)
                 .is_ok(),
             ChainWorkerRequest::ProcessValidatedBlock {
                 certificate,
@@ -445,6 +447,35 @@ impl<Context> ChainWorkerRequest<Context>
 where
     Context: linera_views::context::Context + Clone + Send + Sync + 'static,
 {
+    /// Returns an estimate of the memory held by this request's payload, in bytes.
+    pub fn estimated_size(&self) -> usize {
+        let size = match self {
+            ChainWorkerRequest::StageBlockExecution {
+                block,
+                published_blobs,
+                ..
+            } => bcs::serialized_size(&(block, published_blobs)),
+            ChainWorkerRequest::ProcessTimeout { certificate, .. } => {
+                bcs::serialized_size(certificate)
+            }
+            ChainWorkerRequest::HandleBlockProposal { proposal, .. } => {
+                bcs::serialized_size(proposal)
+            }
+            ChainWorkerRequest::ProcessValidatedBlock { certificate, .. } => {
+                bcs::serialized_size(certificate)
+            }
+            ChainWorkerRequest::ProcessConfirmedBlock { certificate, .. } => {
+                bcs::serialized_size(certificate)
+            }
+            ChainWorkerRequest::ProcessCrossChainUpdate { bundles, .. } => {
+                bcs::serialized_size(bundles)
+            }
+            ChainWorkerRequest::HandlePendingBlob { blob, .. } => Ok(blob.bytes().len()),
+            _ => Ok(0),
+        };
+        size.unwrap_or(0)
+    }
+
     /// Responds to this request with an `error`.
     pub fn send_error(self, error: WorkerError) {
         debug!("Immediately sending error to chain worker request {self:?}");
@@ -5,14 +5,27 @@
 
 use std::sync::Arc;
 
-use linera_base::{crypto::ValidatorSecretKey, time::Duration};
+use linera_base::{
+    crypto::ValidatorSecretKey,
+    data_types::RetentionPolicy,
+    identifiers::{AccountOwner, ChainId},
+    time::Duration,
+};
+use linera_chain::data_types::{ValidatorKeys, ValidatorSigner, VoteSigner};
+use linera_execution::committee::Committee;
+
+use crate::rate_limiter::RateLimiter;
+
+/// The key under which block proposals are rate-limited: the chain, and the proposer if they
+/// own it.
+pub type ProposerKey = (ChainId, Option<AccountOwner>);
 
 /// Configuration parameters for the [`ChainWorkerState`][`super::state::ChainWorkerState`].
 #[derive(Clone, Default)]
 pub struct ChainWorkerConfig {
-    /// The signature key pair of the validator. The key may be missing for replicas
+    /// The signature keys of the validator. The keys may be missing for replicas
     /// without voting rights (possibly with a partial view of chains).
-    pub key_pair: Option<Arc<ValidatorSecretKey>>,
+    pub key_pair: Option<Arc<ValidatorKeys>>,
     /// Whether inactive chains are allowed in storage.
     pub allow_inactive_chains: bool,
     /// Whether new messages from deprecated epochs are allowed.
@@ -22,6 +35,16 @@ pub struct ChainWorkerConfig {
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
     /// will wait until that timestamp before voting.
     pub grace_period: Duration,
+    /// If set, only the bodies of this many latest blocks are kept in storage, together with
+    /// those of blocks whose outgoing messages are still in flight. Older blocks keep their
+    /// header and certificate.
+    pub block_body_retention: Option<u64>,
+    /// Whether the retention policies that the chain owners set are honored, to prune the
+    /// block bodies of their chains further than `block_body_retention`.
+    pub honor_retention_policies: bool,
+    /// The limit on the rate of block proposals by each owner of a chain, if any. The
+    /// proposers of an open chain who don't own it are limited together, under `None`.
+    pub proposal_rate_limiter: Option<Arc<RateLimiter<ProposerKey>>>,
 }
 
 impl ChainWorkerConfig {
@@ -29,7 +52,7 @@ impl ChainWorkerConfig {
     pub fn with_key_pair(mut self, key_pair: Option<ValidatorSecretKey>) -> Self {
         match key_pair {
             Some(validator_secret) => {
-                self.key_pair = Some(Arc::new(validator_secret));
+                self.key_pair = Some(Arc::new(ValidatorKeys::new(validator_secret)));
             }
             None => {
                 self.key_pair = None;
@@ -38,8 +61,37 @@ impl ChainWorkerConfig {
         self
     }
 
-    /// Gets a reference to the [`ValidatorSecretKey`], if available.
+    /// Configures the validator to sign with an external signer instead of a local key.
+    pub fn with_external_signer(mut self, signer: Arc<dyn ValidatorSigner>) -> Self {
+        self.key_pair = Some(Arc::new(ValidatorKeys::external(signer)));
+        self
+    }
+
+    /// Returns how many of the latest block bodies of a chain with the given retention
+    /// policy are kept in storage, or `None` if all of them are.
+    pub fn block_body_retention(&self, retention_policy: &RetentionPolicy) -> Option<u64> {
+        let requested = retention_policy
+            .block_body_retention()
+            .filter(|_| self.honor_retention_policies);
+        match (self.block_body_retention, requested) {
+            (Some(retention), Some(requested)) => Some(retention.min(requested)),
+            (retention, requested) => retention.or(requested),
+        }
+    }
+
+    /// Gets a reference to the [`ValidatorSecretKey`], if available locally.
     pub fn key_pair(&self) -> Option<&ValidatorSecretKey> {
-        self.key_pair.as_ref().map(Arc::as_ref)
+        self.key_pair.as_deref()?.secret_key()
+    }
+
+    /// Returns the keys of the validator, if this is a validator.
+    pub fn validator_keys(&self) -> Option<&ValidatorKeys> {
+        self.key_pair.as_deref()
+    }
+
+    /// Returns the signer of the votes in an epoch with the given committee, if this is a
+    /// validator.
+    pub fn vote_signer(&self, committee: Option<&Committee>) -> Option<VoteSigner<'_>> {
+        Some(self.key_pair.as_deref()?.vote_signer(committee))
     }
 }
@@ -0,0 +1,94 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A budget for the memory held by the requests in flight to the chain workers.
+
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The granularity of the budget, in bytes.
+const UNIT_BYTES: u64 = 1024;
+
+/// A budget limiting the total estimated size of the requests being handled by chain workers.
+///
+/// Reserving memory waits until enough of the budget is available, which slows down the callers
+/// instead of letting the chain workers accumulate an unbounded amount of state.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    semaphore: Arc<Semaphore>,
+    total_units: u32,
+}
+
+/// A reservation of part of a [`MemoryBudget`], released when dropped.
+#[derive(Debug)]
+pub struct MemoryReservation {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `total_bytes`.
+    pub fn new(total_bytes: u64) -> Self {
+        let total_units = total_bytes
+            .div_ceil(UNIT_BYTES)
+            .clamp(1, Semaphore::MAX_PERMITS.min(u32::MAX as usize) as u64)
+            as u32;
+        MemoryBudget {
+            semaphore: Arc::new(Semaphore::new(total_units as usize)),
+            total_units,
+        }
+    }
+
+    /// Waits until `bytes` are available and reserves them.
+    ///
+    /// Requests larger than the whole budget reserve all of it, so that they can still be
+    /// handled, one at a time.
+    pub async fn reserve(&self, bytes: usize) -> MemoryReservation {
+        let units = (bytes as u64)
+            .div_ceil(UNIT_BYTES)
+            .clamp(1, u64::from(self.total_units)) as u32;
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_many_owned(units)
+            .await
+            .expect("the semaphore of a `MemoryBudget` is never closed");
+        MemoryReservation { _permit: permit }
+    }
+
+    /// Returns the number of bytes currently reserved.
+    #[cfg(any(test, with_metrics))]
+    pub fn reserved_bytes(&self) -> u64 {
+        let available = self.semaphore.available_permits() as u64;
+        (u64::from(self.total_units) - available) * UNIT_BYTES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::MemoryBudget;
+
+    #[tokio::test]
+    async fn test_memory_budget_applies_backpressure() {
+        let budget = MemoryBudget::new(4 * 1024);
+
+        let first = budget.reserve(3 * 1024).await;
+        // Small reservations take at least one unit.
+        let second = budget.reserve(1).await;
+        assert_eq!(budget.reserved_bytes(), 4 * 1024);
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), budget.reserve(1))
+                .await
+                .is_err()
+        );
+
+        drop(first);
+        drop(second);
+        // Oversized requests reserve the whole budget once it's free.
+        let _all = budget.reserve(1_000_000).await;
+        assert_eq!(budget.reserved_bytes(), 4 * 1024);
+    }
+}
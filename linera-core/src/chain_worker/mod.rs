@@ -6,13 +6,17 @@
 mod actor;
 mod config;
 mod delivery_notifier;
+mod memory_budget;
 mod state;
 
-pub(super) use self::delivery_notifier::DeliveryNotifier;
 #[cfg(test)]
 pub(crate) use self::state::CrossChainUpdateHelper;
 pub use self::{
     actor::{ChainWorkerActor, ChainWorkerRequest},
     config::ChainWorkerConfig,
-    state::ChainWorkerState,
+    state::{ChainWorkerState, ExecutionStateCache},
+};
+pub(super) use self::{
+    delivery_notifier::DeliveryNotifier,
+    memory_budget::{MemoryBudget, MemoryReservation},
 };
@@ -22,7 +22,7 @@ use linera_execution::committee::Committee;
 use linera_storage::{Clock as _, Storage};
 use linera_views::{
     context::Context,
-    views::{RootView, View},
+    views::{RootView, View, ViewError},
 };
 use tokio::sync::oneshot;
 use tracing::{debug, instrument, trace, warn};
@@ -88,7 +88,8 @@ where
             .already_validated_block(certificate.inner().height())?
         {
             return Ok((
-                ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair()),
+                ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+                    .await?,
                 actions,
             ));
         }
@@ -109,7 +110,8 @@ where
                 },
             })
         }
-        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        let info = ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+            .await?;
         self.save().await?;
         Ok((info, actions))
     }
@@ -177,9 +179,19 @@ where
             .state
             .get_required_blobs(proposal.expected_blob_ids(), &created_blobs)
             .await?;
-        let key_pair = self.state.config.key_pair();
-        let manager = &mut self.state.chain.manager;
-        match manager.create_vote(proposal, block, key_pair, local_time, blobs)? {
+        let chain = &mut self.state.chain;
+        let committee = chain
+            .execution_state
+            .system
+            .committees
+            .get()
+            .get(&proposal.content.block.epoch);
+        let signer = self.state.config.vote_signer(committee);
+        match chain
+            .manager
+            .create_vote(proposal, block, signer, local_time, blobs)
+            .await?
+        {
             // Cache the value we voted on, so the client doesn't have to send it again.
             Some(Either::Left(vote)) => {
                 self.state
@@ -229,7 +241,8 @@ where
         if already_committed_block || should_skip_validated_block()? {
             // If we just processed the same pending block, return the chain info unchanged.
             return Ok((
-                ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair()),
+                ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+                    .await?,
                 actions,
                 true,
             ));
@@ -258,13 +271,24 @@ where
             .filter_map(|(blob_id, maybe_blob)| Some((blob_id, maybe_blob?)))
             .collect();
         let old_round = self.state.chain.manager.current_round();
-        self.state.chain.manager.create_final_vote(
-            certificate,
-            self.state.config.key_pair(),
-            self.state.storage.clock().current_time(),
-            blobs,
-        )?;
-        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        let chain = &mut self.state.chain;
+        let committee = chain
+            .execution_state
+            .system
+            .committees
+            .get()
+            .get(&certificate.block().header.epoch);
+        chain
+            .manager
+            .create_final_vote(
+                certificate,
+                self.state.config.vote_signer(committee),
+                self.state.storage.clock().current_time(),
+                blobs,
+            )
+            .await?;
+        let info = ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+            .await?;
         self.save().await?;
         let round = self.state.chain.manager.current_round();
         if round > old_round {
@@ -294,11 +318,16 @@ where
             });
         }
         if tip.next_block_height > height {
-            // We already processed this block.
+            // We already processed this block, or the certificate conflicts with it.
+            self.state
+                .record_equivocation_evidence(&certificate)
+                .await?;
             let actions = self.state.create_network_actions().await?;
             self.register_delivery_notifier(height, &actions, notify_when_messages_are_delivered)
                 .await;
-            let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+            let info =
+                ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+                    .await?;
             return Ok((info, actions));
         }
         let local_time = self.state.storage.clock().current_time();
@@ -361,21 +390,22 @@ where
             .await?;
         let oracle_responses = Some(block.body.oracle_responses.clone());
         let (proposed_block, outcome) = block.clone().into_proposal();
-        let verified_outcome = if let Some(execution_state) =
+        let (verified_outcome, logs) = if let Some((execution_state, logs)) =
             self.state.execution_state_cache.remove(&outcome.state_hash)
         {
             chain.execution_state = execution_state;
-            outcome.clone()
+            (outcome.clone(), logs)
         } else {
-            chain
-                .execute_block(
+            let (verified_outcome, resources) = chain
+                .execute_block_with_resources(
                     &proposed_block,
                     local_time,
                     None,
                     &published_blobs,
                     oracle_responses,
                 )
-                .await?
+                .await?;
+            (verified_outcome, resources.logs)
         };
         // We should always agree on the messages and state hash.
         ensure!(
@@ -389,17 +419,58 @@ where
         chain
             .apply_confirmed_block(certificate.value(), local_time)
             .await?;
+        chain.record_execution_logs(height, logs)?;
         self.state
             .track_newly_created_chains(&proposed_block, &outcome);
         let mut actions = self.state.create_network_actions().await?;
+        // Record the outgoing messages before saving the chain, so that they are never lost.
+        self.state.record_pending_delivery().await?;
         trace!("Processed confirmed block {height} on chain {chain_id:.8}");
         let hash = certificate.hash();
         actions.notifications.push(Notification {
             chain_id,
-            reason: Reason::NewBlock { height, hash },
+            reason: Reason::NewBlock {
+                height,
+                hash,
+                event_streams: block.event_stream_ids(),
+                application_ids: block.application_ids(),
+            },
         });
+        let retention_policy = *self
+            .state
+            .chain
+            .execution_state
+            .system
+            .retention_policy
+            .get();
+        let mut pruned_hashes = Vec::new();
+        if let Some(retention) = self.state.config.block_body_retention(&retention_policy) {
+            pruned_hashes = self.state.chain.advance_pruned_height(retention).await?;
+            let mut pruned_blocks = Vec::new();
+            for hash in &pruned_hashes {
+                match self.state.storage.read_confirmed_block(*hash).await {
+                    Ok(block) => pruned_blocks.push(block),
+                    Err(ViewError::BlockBodyPruned(_)) => {}
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            self.state
+                .chain
+                .prune_removed_bundles(&pruned_blocks)
+                .await?;
+        }
         // Persist chain.
         self.save().await?;
+        // The bodies are only removed once the chain no longer needs them. If this fails, they
+        // merely take up space.
+        if let Err(error) = self
+            .state
+            .storage
+            .prune_confirmed_blocks(&pruned_hashes)
+            .await
+        {
+            warn!("Failed to prune the bodies of blocks on chain {chain_id:.8}: {error}");
+        }
 
         self.state
             .block_values
@@ -407,7 +478,8 @@ where
 
         self.register_delivery_notifier(height, &actions, notify_when_messages_are_delivered)
             .await;
-        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        let info = ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+            .await?;
 
         Ok((info, actions))
     }
@@ -500,11 +572,12 @@ where
         recipient: ChainId,
         latest_height: BlockHeight,
     ) -> Result<(), WorkerError> {
-        let fully_delivered = self
+        let marked_as_received = self
             .state
             .chain
             .mark_messages_as_received(&recipient, latest_height)
-            .await?
+            .await?;
+        let fully_delivered = marked_as_received
             && self
                 .state
                 .all_messages_to_tracked_chains_delivered_up_to(latest_height)
@@ -512,6 +585,10 @@ where
 
         self.save().await?;
 
+        if marked_as_received {
+            self.state.clear_pending_delivery().await?;
+        }
+
         if fully_delivered {
             self.state.delivery_notifier.notify(latest_height);
         }
@@ -536,11 +613,13 @@ where
         if let Some(epoch) = chain.execution_state.system.epoch.get() {
             let chain_id = chain.chain_id();
             let height = chain.tip_state.get().next_block_height;
-            let key_pair = self.state.config.key_pair();
+            let committee = chain.execution_state.system.committees.get().get(epoch);
+            let signer = self.state.config.vote_signer(committee);
             let local_time = self.state.storage.clock().current_time();
             if chain
                 .manager
-                .vote_timeout(chain_id, height, *epoch, key_pair, local_time)
+                .vote_timeout(chain_id, height, *epoch, signer, local_time)
+                .await?
             {
                 self.save().await?;
             }
@@ -564,10 +643,12 @@ where
             if elapsed >= chain.ownership().timeout_config.fallback_duration {
                 let chain_id = chain.chain_id();
                 let height = chain.tip_state.get().next_block_height;
-                let key_pair = self.state.config.key_pair();
+                let committee = chain.execution_state.system.committees.get().get(epoch);
+                let signer = self.state.config.vote_signer(committee);
                 if chain
                     .manager
-                    .vote_fallback(chain_id, height, *epoch, key_pair)
+                    .vote_fallback(chain_id, height, *epoch, signer)
+                    .await?
                 {
                     self.save().await?;
                 }
@@ -609,10 +690,10 @@ where
         }
         ensure!(was_expected, WorkerError::UnexpectedBlob);
         self.save().await?;
-        Ok(ChainInfoResponse::new(
-            &self.state.chain,
-            self.state.config.key_pair(),
-        ))
+        Ok(
+            ChainInfoResponse::signed(&self.state.chain, self.state.config.validator_keys())
+                .await?,
+        )
     }
 
     /// Stores the chain state in persistent storage.
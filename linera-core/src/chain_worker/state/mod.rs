@@ -17,16 +17,19 @@ use linera_base::{
     data_types::{ApplicationDescription, Blob, BlockHeight, Epoch},
     ensure,
     hashed::Hashed,
-    identifiers::{ApplicationId, BlobId, BlobType, ChainId},
+    identifiers::{AccountOwner, ApplicationId, BlobId, BlobType, ChainId},
 };
 use linera_chain::{
-    data_types::{BlockExecutionOutcome, BlockProposal, MessageBundle, ProposedBlock},
+    data_types::{
+        BlockExecutionOutcome, BlockExecutionResources, BlockProposal, EquivocationEvidence,
+        MessageBundle, ProposedBlock,
+    },
     manager,
     types::{Block, ConfirmedBlockCertificate, TimeoutCertificate, ValidatedBlockCertificate},
     ChainError, ChainStateView,
 };
 use linera_execution::{
-    ExecutionStateView, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint,
+    ContractLog, ExecutionStateView, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint,
 };
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::{ClonableView, ViewError};
@@ -38,13 +41,18 @@ use self::{
     attempted_changes::ChainWorkerStateWithAttemptedChanges,
     temporary_changes::ChainWorkerStateWithTemporaryChanges,
 };
-use super::{ChainWorkerConfig, DeliveryNotifier};
+use super::{config::ProposerKey, ChainWorkerConfig, DeliveryNotifier};
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     value_cache::ValueCache,
     worker::{NetworkActions, WorkerError},
 };
 
+/// The execution states of the blocks that were executed but not confirmed yet, by state
+/// hash, with the messages that applications logged while executing them.
+pub type ExecutionStateCache<C> =
+    ValueCache<CryptoHash, (ExecutionStateView<C>, Vec<Vec<ContractLog>>)>;
+
 /// The state of the chain worker.
 pub struct ChainWorkerState<StorageClient>
 where
@@ -56,10 +64,11 @@ where
     shared_chain_view: Option<Arc<RwLock<ChainStateView<StorageClient::Context>>>>,
     service_runtime_endpoint: Option<ServiceRuntimeEndpoint>,
     block_values: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
-    execution_state_cache: Arc<ValueCache<CryptoHash, ExecutionStateView<StorageClient::Context>>>,
+    execution_state_cache: Arc<ExecutionStateCache<StorageClient::Context>>,
     tracked_chains: Option<Arc<sync::RwLock<HashSet<ChainId>>>>,
     delivery_notifier: DeliveryNotifier,
     knows_chain_is_active: bool,
+    knows_pending_delivery_is_recorded: bool,
 }
 
 impl<StorageClient> ChainWorkerState<StorageClient>
@@ -73,9 +82,7 @@ where
         storage: StorageClient,
         block_values: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
 
-        execution_state_cache: Arc<
-            ValueCache<CryptoHash, ExecutionStateView<StorageClient::Context>>,
-        >,
+        execution_state_cache: Arc<ExecutionStateCache<StorageClient::Context>>,
         tracked_chains: Option<Arc<sync::RwLock<HashSet<ChainId>>>>,
         delivery_notifier: DeliveryNotifier,
         chain_id: ChainId,
@@ -94,6 +101,7 @@ where
             tracked_chains,
             delivery_notifier,
             knows_chain_is_active: false,
+            knows_pending_delivery_is_recorded: false,
         })
     }
 
@@ -186,12 +194,11 @@ where
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: &[Blob],
-    ) -> Result<(Block, ChainInfoResponse), WorkerError> {
-        let (block, response) = ChainWorkerStateWithTemporaryChanges::new(self)
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), WorkerError> {
+        ChainWorkerStateWithTemporaryChanges::new(self)
             .await
             .stage_block_execution(block, round, published_blobs)
-            .await?;
-        Ok((block, response))
+            .await
     }
 
     /// Processes a leader timeout issued for this multi-owner chain.
@@ -212,14 +219,16 @@ where
         proposal: BlockProposal,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         self.ensure_is_active().await?;
-        if ChainWorkerStateWithTemporaryChanges::new(&mut *self)
+        let outcome = ChainWorkerStateWithTemporaryChanges::new(&mut *self)
             .await
             .check_proposed_block(&proposal)
-            .await?
-            == manager::Outcome::Skip
-        {
+            .await?;
+        // The proposer is only charged once the proposal is known to be signed by them, and
+        // to be allowed on this chain.
+        let rate_limit_key = self.check_proposal_rate_limit(&proposal)?;
+        if outcome == manager::Outcome::Skip {
             // Skipping: We already voted for this block.
-            let info = ChainInfoResponse::new(&self.chain, self.config.key_pair());
+            let info = ChainInfoResponse::signed(&self.chain, self.config.validator_keys()).await?;
             return Ok((info, NetworkActions::default()));
         };
         let published_blobs = ChainWorkerStateWithAttemptedChanges::new(&mut *self)
@@ -242,11 +251,52 @@ where
             // If we just processed the same pending block, return the chain info unchanged.
             NetworkActions::default()
         };
+        if let Some((rate_limiter, key)) = self
+            .config
+            .proposal_rate_limiter
+            .as_ref()
+            .zip(rate_limit_key)
+        {
+            rate_limiter.record(key, self.storage.clock().current_time());
+        }
 
-        let info = ChainInfoResponse::new(&self.chain, self.config.key_pair());
+        let info = ChainInfoResponse::signed(&self.chain, self.config.validator_keys()).await?;
         Ok((info, actions))
     }
 
+    /// Returns an error if the proposer of a checked proposal exceeded their rate limit.
+    /// Otherwise returns the key to charge once the proposal is accepted, if any.
+    ///
+    /// Each owner of the chain has their own limit, and the other proposers of an open
+    /// chain share one, so that they can't bypass it with fresh keys.
+    fn check_proposal_rate_limit(
+        &self,
+        proposal: &BlockProposal,
+    ) -> Result<Option<ProposerKey>, WorkerError> {
+        let Some(rate_limiter) = &self.config.proposal_rate_limiter else {
+            return Ok(None);
+        };
+        let chain_id = self.chain_id();
+        let owner = AccountOwner::from(proposal.public_key);
+        let ownership = self.chain.manager.ownership.get();
+        let is_owner =
+            ownership.super_owners.contains(&owner) || ownership.owners.contains_key(&owner);
+        let key = (chain_id, is_owner.then_some(owner));
+        let now = self.storage.clock().current_time();
+        if let Some(retry_after) = rate_limiter.retry_after(&key, now) {
+            #[cfg(with_metrics)]
+            crate::worker::RATE_LIMITED_PROPOSALS
+                .with_label_values(&[])
+                .inc();
+            return Err(WorkerError::ProposalRateLimited {
+                chain_id,
+                owner,
+                retry_after,
+            });
+        }
+        Ok(Some(key))
+    }
+
     /// Processes a validated block issued for this multi-owner chain.
     #[tracing::instrument(level = "debug", skip(self))]
     pub(super) async fn process_validated_block(
@@ -443,6 +493,87 @@ where
         }
     }
 
+    /// Records evidence against the validators that signed both `certificate` and the
+    /// different block we confirmed at the same height, if any.
+    async fn record_equivocation_evidence(
+        &self,
+        certificate: &ConfirmedBlockCertificate,
+    ) -> Result<(), WorkerError> {
+        let height = certificate.block().header.height;
+        let Some(hash) = self
+            .chain
+            .confirmed_log
+            .get(usize::try_from(height)?)
+            .await?
+        else {
+            return Ok(());
+        };
+        if hash == certificate.hash() {
+            return Ok(());
+        }
+        // If the body of our block was pruned, its header and signatures are still kept.
+        let header = self.storage.read_block_header(hash).await?;
+        if header.chain_id != certificate.block().header.chain_id || header.height != height {
+            return Ok(());
+        }
+        let confirmed = self.storage.read_lite_certificate(hash).await?;
+        let evidences = EquivocationEvidence::from_lite_certificates(
+            height,
+            &confirmed,
+            &certificate.lite_certificate(),
+        );
+        let committees = self.chain.execution_state.system.committees.get();
+        let (Some(first_committee), Some(second_committee)) = (
+            committees.get(&header.epoch),
+            committees.get(&certificate.block().header.epoch),
+        ) else {
+            return Ok(());
+        };
+        // The incoming certificate hasn't been checked, so only keep correctly signed votes.
+        for evidence in evidences {
+            if evidence.check(first_committee, second_committee).is_err() {
+                continue;
+            }
+            tracing::warn!(
+                validator = %evidence.validator,
+                chain_id = %evidence.chain_id(),
+                %height,
+                "Validator signed conflicting blocks",
+            );
+            self.storage.write_equivocation_evidence(&evidence).await?;
+        }
+        Ok(())
+    }
+
+    /// Records in storage that this chain has outgoing messages that haven't been delivered,
+    /// so that their delivery resumes if the validator restarts.
+    ///
+    /// Clients only deliver messages to the chains they track, so they don't record them.
+    async fn record_pending_delivery(&mut self) -> Result<(), WorkerError> {
+        if self.tracked_chains.is_some()
+            || self.knows_pending_delivery_is_recorded
+            || self.chain.outbox_counters.get().is_empty()
+        {
+            return Ok(());
+        }
+        self.storage.write_pending_delivery(self.chain_id()).await?;
+        self.knows_pending_delivery_is_recorded = true;
+        Ok(())
+    }
+
+    /// Removes the record of pending deliveries from storage if all outgoing messages of this
+    /// chain have been delivered.
+    async fn clear_pending_delivery(&mut self) -> Result<(), WorkerError> {
+        if self.tracked_chains.is_some() || !self.chain.outbox_counters.get().is_empty() {
+            return Ok(());
+        }
+        self.storage
+            .remove_pending_delivery(self.chain_id())
+            .await?;
+        self.knows_pending_delivery_is_recorded = false;
+        Ok(())
+    }
+
     /// Loads pending cross-chain requests.
     async fn create_network_actions(&self) -> Result<NetworkActions, WorkerError> {
         let mut heights_by_recipient = BTreeMap::<_, Vec<_>>::new();
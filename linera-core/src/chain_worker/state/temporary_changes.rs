@@ -10,8 +10,8 @@ use linera_base::{
 };
 use linera_chain::{
     data_types::{
-        BlockExecutionOutcome, BlockProposal, IncomingBundle, MessageAction, ProposalContent,
-        ProposedBlock,
+        BlockExecutionOutcome, BlockExecutionResources, BlockProposal, IncomingBundle,
+        MessageAction, ProposalContent, ProposedBlock,
     },
     manager,
     types::Block,
@@ -116,22 +116,36 @@ where
         Ok(response)
     }
 
-    /// Executes a block without persisting any changes to the state.
+    /// Executes a block without persisting any changes to the state. Also returns the
+    /// resources the block used and the fees it was charged.
     pub(super) async fn stage_block_execution(
         &mut self,
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: &[Blob],
-    ) -> Result<(Block, ChainInfoResponse), WorkerError> {
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), WorkerError> {
         self.0.ensure_is_active().await?;
         let local_time = self.0.storage.clock().current_time();
         let signer = block.authenticated_signer;
         let (_, committee) = self.0.chain.current_committee()?;
         block.check_proposal_size(committee.policy().maximum_block_proposal_size)?;
+        block.check_block_limits(self.0.chain.execution_state.system.block_limits.get())?;
 
-        let outcome = self
-            .execute_block(&block, local_time, round, published_blobs)
-            .await?;
+        let (outcome, resources) = Box::pin(self.0.chain.execute_block_with_resources(
+            &block,
+            local_time,
+            round,
+            published_blobs,
+            None,
+        ))
+        .await?;
+        self.0.execution_state_cache.insert_owned(
+            &outcome.state_hash,
+            (
+                self.0.chain.execution_state.clone_unchecked()?,
+                resources.logs.clone(),
+            ),
+        );
 
         let mut response = ChainInfoResponse::new(&self.0.chain, None);
         if let Some(signer) = signer {
@@ -145,7 +159,7 @@ where
                 .await?;
         }
 
-        Ok((outcome.with(block), response))
+        Ok((outcome.with(block), response, resources))
     }
 
     /// Validates a proposal's signatures; returns `manager::Outcome::Skip` if we already voted
@@ -173,6 +187,7 @@ where
         super::check_block_epoch(epoch, block.chain_id, block.epoch)?;
         let policy = committee.policy().clone();
         block.check_proposal_size(policy.maximum_block_proposal_size)?;
+        block.check_block_limits(chain.execution_state.system.block_limits.get())?;
         // Check the authentication of the block.
         ensure!(
             chain.manager.verify_owner(proposal),
@@ -246,7 +261,8 @@ where
         let chain = &self.0.chain;
         let mut info = ChainInfo::from(chain);
         if query.request_committees {
-            info.requested_committees = Some(chain.execution_state.system.committees.get().clone());
+            info.requested_committees =
+                Some(chain.execution_state.system.committees.get().0.clone());
         }
         if query.request_owner_balance == AccountOwner::CHAIN {
             info.requested_owner_balance = Some(*chain.execution_state.system.balance.get());
@@ -306,7 +322,14 @@ where
         if query.request_manager_values {
             info.manager.add_values(&chain.manager);
         }
-        Ok(ChainInfoResponse::new(info, self.0.config.key_pair()))
+        if query.request_equivocation_evidence {
+            info.requested_equivocation_evidence = self
+                .0
+                .storage
+                .read_equivocation_evidence(chain.chain_id())
+                .await?;
+        }
+        Ok(ChainInfoResponse::signed(info, self.0.config.validator_keys()).await?)
     }
 
     /// Executes a block, caches the result, and returns the outcome.
@@ -317,16 +340,20 @@ where
         round: Option<u32>,
         published_blobs: &[Blob],
     ) -> Result<BlockExecutionOutcome, WorkerError> {
-        let outcome =
-            Box::pin(
-                self.0
-                    .chain
-                    .execute_block(block, local_time, round, published_blobs, None),
-            )
-            .await?;
+        let (outcome, resources) = Box::pin(self.0.chain.execute_block_with_resources(
+            block,
+            local_time,
+            round,
+            published_blobs,
+            None,
+        ))
+        .await?;
         self.0.execution_state_cache.insert_owned(
             &outcome.state_hash,
-            self.0.chain.execution_state.clone_unchecked()?,
+            (
+                self.0.chain.execution_state.clone_unchecked()?,
+                resources.logs,
+            ),
         );
         Ok(outcome)
     }
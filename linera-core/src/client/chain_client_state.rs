@@ -2,13 +2,16 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeSet, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::Arc,
+};
 
 use linera_base::{
     crypto::CryptoHash,
     data_types::{Blob, BlockHeight, Timestamp},
     ensure,
-    identifiers::AccountOwner,
+    identifiers::{AccountOwner, ChainId},
     ownership::ChainOwnership,
 };
 use linera_chain::data_types::ProposedBlock;
@@ -31,6 +34,9 @@ pub struct ChainClientState {
     ///
     /// This is always at the same height as `next_block_height`.
     pending_proposal: Option<PendingProposal>,
+    /// The incoming bundles, by origin and height, that were approved to be received even
+    /// though they exceed the message policy's approval threshold.
+    approved_bundles: HashSet<(ChainId, BlockHeight)>,
 
     /// A mutex that is held whilst we are performing operations that should not be
     /// attempted by multiple clients at the same time.
@@ -49,6 +55,7 @@ impl ChainClientState {
             timestamp,
             next_block_height,
             pending_proposal,
+            approved_bundles: HashSet::new(),
             client_mutex: Arc::default(),
         }
     }
@@ -86,6 +93,22 @@ impl ChainClientState {
         }
     }
 
+    /// Returns whether the incoming bundle from the given origin and height was approved.
+    pub fn is_approved_bundle(&self, origin: ChainId, height: BlockHeight) -> bool {
+        self.approved_bundles.contains(&(origin, height))
+    }
+
+    pub(super) fn approve_bundle(&mut self, origin: ChainId, height: BlockHeight) {
+        self.approved_bundles.insert((origin, height));
+    }
+
+    pub(super) fn retain_approved_bundles(
+        &mut self,
+        f: impl FnMut(&(ChainId, BlockHeight)) -> bool,
+    ) {
+        self.approved_bundles.retain(f);
+    }
+
     /// Returns whether the given ownership includes anyone whose secret key we don't have.
     pub fn has_other_owners(
         &self,
@@ -19,8 +19,10 @@ use dashmap::{
     DashMap,
 };
 use futures::{
+    channel::mpsc,
     future::{self, try_join_all, Either, FusedFuture, Future},
     stream::{self, AbortHandle, FusedStream, FuturesUnordered, StreamExt},
+    SinkExt as _,
 };
 #[cfg(with_metrics)]
 use linera_base::prometheus_util::MeasureLatency as _;
@@ -28,8 +30,9 @@ use linera_base::{
     abi::Abi,
     crypto::{AccountPublicKey, CryptoHash, Signer, ValidatorPublicKey},
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, Blob, BlobContent, BlockHeight, Epoch,
-        Round, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, Blob, BlobContent, BlockHeight,
+        BlockLimits, ChainDescription, Epoch, InboxPolicy, RetentionPolicy, Round, TimeDelta,
+        Timestamp,
     },
     ensure,
     identifiers::{
@@ -37,12 +40,14 @@ use linera_base::{
         StreamId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
+    time::Instant,
 };
 #[cfg(not(target_arch = "wasm32"))]
 use linera_base::{data_types::Bytecode, vm::VmRuntime};
 use linera_chain::{
     data_types::{
-        BlockProposal, ChainAndHeight, IncomingBundle, LiteVote, MessageAction, ProposedBlock,
+        BlockExecutionResources, BlockProposal, ChainAndHeight, IncomingBundle, LiteVote,
+        MessageAction, ProposedBlock, UnsignedBlockProposal,
     },
     manager::LockingBlock,
     types::{
@@ -54,8 +59,8 @@ use linera_chain::{
 use linera_execution::{
     committee::Committee,
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemOperation, EPOCH_STREAM_NAME,
-        REMOVED_EPOCH_STREAM_NAME,
+        AdminOperation, EncryptedMemo, NameTarget, OpenChainConfig, Recipient, SystemOperation,
+        EPOCH_STREAM_NAME, REMOVED_EPOCH_STREAM_NAME,
     },
     ExecutionError, Operation, Query, QueryOutcome, QueryResponse, SystemQuery, SystemResponse,
 };
@@ -75,12 +80,15 @@ use crate::{
     environment::Environment,
     local_node::{LocalNodeClient, LocalNodeError},
     node::{
-        CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
+        CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode,
         ValidatorNodeProvider as _,
     },
     notifier::ChannelNotifier,
+    progress::{NoProgress, Progress, ProgressReporter, ProgressTask, ProgressUnit},
     remote_node::RemoteNode,
     updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
+    validator_health::{CommunicationPolicy, ValidatorHealth},
+    value_cache::ValueCache,
     worker::{Notification, ProcessableCertificate, Reason, WorkerError, WorkerState},
 };
 
@@ -143,6 +151,38 @@ mod metrics {
     });
 }
 
+/// The number of certificates requested at a time when synchronizing a chain in parallel.
+const CERTIFICATE_DOWNLOAD_BATCH_SIZE: u64 = 100;
+
+/// The default number of recently confirmed block certificates a client keeps in memory.
+pub const DEFAULT_CERTIFICATE_CACHE_SIZE: usize = 1_000;
+
+/// Checks the signatures of the `certificates` whose epoch has a known committee, off the
+/// async runtime where possible.
+async fn check_certificate_signatures(
+    certificates: Vec<ConfirmedBlockCertificate>,
+    committees: Arc<BTreeMap<Epoch, Committee>>,
+) -> Result<Vec<ConfirmedBlockCertificate>, ChainError> {
+    let check = move || {
+        for certificate in &certificates {
+            if let Some(committee) = committees.get(&certificate.block().header.epoch) {
+                certificate.check(committee)?;
+            }
+        }
+        Ok(certificates)
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        tokio::task::spawn_blocking(check)
+            .await
+            .expect("Checking certificate signatures should not panic")
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        check()
+    }
+}
+
 /// A builder that creates [`ChainClient`]s which share the cache and notifiers.
 pub struct Client<Env: Environment> {
     environment: Env,
@@ -171,6 +211,18 @@ pub struct Client<Env: Environment> {
     max_loaded_chains: NonZeroUsize,
     /// The delay when downloading a blob, after which we try a second validator.
     blob_download_timeout: Duration,
+    /// The maximum number of batches of certificates downloaded concurrently when
+    /// synchronizing a chain.
+    certificate_download_parallelism: NonZeroUsize,
+    /// The optional features advertised by the validators, once known.
+    node_features: DashMap<ValidatorPublicKey, NodeFeatures>,
+    /// The health of the validators, used to leave out unreachable ones.
+    validator_health: ValidatorHealth,
+    /// Receives the progress of long-running operations.
+    progress_reporter: Arc<dyn ProgressReporter>,
+    /// The block certificates that were recently confirmed or processed, so that they are
+    /// not downloaded from the validators again.
+    certificate_cache: ValueCache<CryptoHash, ConfirmedBlockCertificate>,
 }
 
 impl<Env: Environment> Client<Env> {
@@ -188,6 +240,8 @@ impl<Env: Environment> Client<Env> {
         max_loaded_chains: NonZeroUsize,
         grace_period: f64,
         blob_download_timeout: Duration,
+        certificate_download_parallelism: NonZeroUsize,
+        communication_policy: CommunicationPolicy,
     ) -> Self {
         let tracked_chains = Arc::new(RwLock::new(tracked_chains.into_iter().collect()));
         let state = WorkerState::new_for_client(
@@ -214,9 +268,35 @@ impl<Env: Environment> Client<Env> {
             signer,
             max_loaded_chains,
             blob_download_timeout,
+            certificate_download_parallelism,
+            node_features: DashMap::new(),
+            validator_health: ValidatorHealth::new(communication_policy),
+            progress_reporter: Arc::new(NoProgress),
+            certificate_cache: ValueCache::new(
+                NonZeroUsize::new(DEFAULT_CERTIFICATE_CACHE_SIZE)
+                    .expect("the default cache size is not zero"),
+            ),
         }
     }
 
+    /// Reports the progress of long-running operations to the given reporter.
+    pub fn with_progress_reporter(mut self, progress_reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.progress_reporter = progress_reporter;
+        self
+    }
+
+    /// Keeps up to `size` recently confirmed block certificates in memory, instead of
+    /// [`DEFAULT_CERTIFICATE_CACHE_SIZE`].
+    pub fn with_certificate_cache_size(mut self, size: NonZeroUsize) -> Self {
+        self.certificate_cache = ValueCache::new(size);
+        self
+    }
+
+    /// Returns the reporter of the progress of long-running operations.
+    pub fn progress_reporter(&self) -> &Arc<dyn ProgressReporter> {
+        &self.progress_reporter
+    }
+
     /// Returns the storage client used by this client's local node.
     pub fn storage_client(&self) -> &Env::Storage {
         self.environment.storage()
@@ -232,6 +312,11 @@ impl<Env: Environment> Client<Env> {
         &self.local_node
     }
 
+    /// Returns the health of the validators the client communicated with.
+    pub fn validator_health(&self) -> &ValidatorHealth {
+        &self.validator_health
+    }
+
     /// Returns a reference to the [`Signer`] of the client.
     #[instrument(level = "trace", skip(self))]
     pub fn signer(&self) -> &impl Signer {
@@ -312,15 +397,91 @@ impl<Env: Environment> Client<Env> {
 
     /// Downloads and processes all certificates up to (excluding) the specified height.
     #[instrument(level = "trace", skip(self, validators))]
-    pub async fn download_certificates(
+    pub async fn download_certificates<N: ValidatorNode>(
         &self,
-        validators: &[RemoteNode<impl ValidatorNode>],
+        validators: &[RemoteNode<N>],
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
-        // Sequentially try each validator in random order.
+        // Try the fastest validators first, in random order if they are equally fast.
         let mut validators_vec = validators.iter().collect::<Vec<_>>();
         validators_vec.shuffle(&mut rand::thread_rng());
+        self.validator_health
+            .sort_by_expected_latency(&mut validators_vec, |remote_node| &remote_node.public_key);
+        let info = self.chain_info(chain_id, validators).await?;
+        if target_next_block_height <= info.next_block_height {
+            return Ok(info);
+        }
+        let progress = Progress::start(
+            self.progress_reporter.clone(),
+            ProgressTask::SynchronizeChain { chain_id },
+            ProgressUnit::Blocks,
+            info.next_block_height.0,
+            Some(target_next_block_height.0),
+        );
+        // Validators that prune block bodies may not have the oldest blocks anymore. These are
+        // downloaded from the archive nodes first, which keep the full history of the chain.
+        let features =
+            future::join_all(validators_vec.iter().map(|node| self.node_features(node))).await;
+        let pruned_below = features
+            .iter()
+            .filter(|features| !features.archive)
+            .filter_map(|features| features.block_body_retention)
+            .map(|retention| BlockHeight(target_next_block_height.0.saturating_sub(retention)))
+            .min();
+        if let Some(pruned_below) = pruned_below.filter(|height| info.next_block_height < *height) {
+            let mut archive_first = validators_vec
+                .iter()
+                .copied()
+                .zip(&features)
+                .collect::<Vec<_>>();
+            archive_first.sort_by_key(|(_, features)| !features.archive);
+            let archive_first = archive_first
+                .into_iter()
+                .map(|(remote_node, _)| remote_node)
+                .collect::<Vec<_>>();
+            self.download_certificates_in_order(
+                &archive_first,
+                validators,
+                chain_id,
+                pruned_below,
+                &progress,
+            )
+            .await?;
+        }
+        self.download_certificates_in_order(
+            &validators_vec,
+            validators,
+            chain_id,
+            target_next_block_height,
+            &progress,
+        )
+        .await
+    }
+
+    /// Downloads and processes all certificates up to (excluding) the specified height, trying
+    /// the validators in the given order. `validators` are used to download missing blobs.
+    async fn download_certificates_in_order<N: ValidatorNode>(
+        &self,
+        validators_vec: &[&RemoteNode<N>],
+        validators: &[RemoteNode<N>],
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+        progress: &Progress,
+    ) -> Result<Box<ChainInfo>, ChainClientError> {
+        let info = self.chain_info(chain_id, validators).await?;
+        if target_next_block_height <= info.next_block_height {
+            return Ok(info);
+        }
+        self.try_download_certificates_pipelined(
+            validators_vec,
+            chain_id,
+            info.next_block_height,
+            target_next_block_height,
+            progress,
+        )
+        .await?;
+        // Sequentially fill in whatever the pipeline failed to download.
         for remote_node in validators_vec {
             let info = self.chain_info(chain_id, validators).await?;
             if target_next_block_height <= info.next_block_height {
@@ -331,6 +492,7 @@ impl<Env: Environment> Client<Env> {
                 chain_id,
                 info.next_block_height,
                 target_next_block_height,
+                progress,
             )
             .await?;
         }
@@ -345,6 +507,125 @@ impl<Env: Environment> Client<Env> {
         }
     }
 
+    /// Downloads and processes the certificates from `start` up to (excluding) `stop`, in
+    /// batches spread over the given validators.
+    ///
+    /// Up to `certificate_download_parallelism` batches are downloaded and have their signatures
+    /// checked concurrently, while the batches that are ready are processed in order. This
+    /// stops at the first batch that cannot be downloaded or processed.
+    #[instrument(level = "trace", skip_all)]
+    async fn try_download_certificates_pipelined(
+        &self,
+        validators: &[&RemoteNode<impl ValidatorNode>],
+        chain_id: ChainId,
+        start: BlockHeight,
+        stop: BlockHeight,
+        progress: &Progress,
+    ) -> Result<(), ChainClientError> {
+        if validators.is_empty() {
+            return Ok(());
+        }
+        // Committees we don't know yet are left to the local node to check.
+        let committees = Arc::new(
+            self.epoch_and_committees(chain_id)
+                .await
+                .map(|(_, committees)| committees)
+                .unwrap_or_default(),
+        );
+        let mut batches = Vec::new();
+        let mut batch_start = start;
+        while batch_start < stop {
+            let limit = u64::from(stop)
+                .checked_sub(u64::from(batch_start))
+                .ok_or(ArithmeticError::Overflow)?
+                .min(CERTIFICATE_DOWNLOAD_BATCH_SIZE);
+            batches.push((batch_start, limit));
+            batch_start = batch_start.try_add(BlockHeight(limit))?;
+        }
+        let mut downloads = stream::iter(batches.into_iter().enumerate())
+            .map(|(index, (batch_start, limit))| {
+                let committees = committees.clone();
+                async move {
+                    // Start with a different validator for each batch, and fall back to the
+                    // others if it doesn't have them.
+                    for offset in 0..validators.len() {
+                        let remote_node = validators[(index + offset) % validators.len()];
+                        let certificates = match self
+                            .try_query_certificates_from(remote_node, chain_id, batch_start, limit)
+                            .await
+                        {
+                            Ok(Some(certificates)) => certificates,
+                            Ok(None) => continue,
+                            Err(error) => {
+                                debug!(
+                                    "Failed to download certificates from validator {}: {error}",
+                                    remote_node.public_key
+                                );
+                                continue;
+                            }
+                        };
+                        match check_certificate_signatures(certificates, committees.clone()).await {
+                            Ok(certificates) => return Some((remote_node, certificates)),
+                            Err(error) => warn!(
+                                "Invalid certificates from validator {}: {error}",
+                                remote_node.public_key
+                            ),
+                        }
+                    }
+                    None
+                }
+            })
+            .buffered(self.certificate_download_parallelism.get());
+        let (mut sender, mut receiver) = mpsc::channel(self.certificate_download_parallelism.get());
+        // Keep downloading while the batches that are ready are being processed.
+        let download = async move {
+            while let Some(batch) = downloads.next().await {
+                if sender.send(batch).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let process = async move {
+            while let Some(Some((remote_node, certificates))) = receiver.next().await {
+                let Some(info) = self
+                    .try_process_certificates(remote_node, chain_id, certificates)
+                    .await
+                else {
+                    break;
+                };
+                progress.advance_to(info.next_block_height.0);
+            }
+            // Dropping the receiver stops the remaining downloads.
+        };
+        future::join(download, process).await;
+        Ok(())
+    }
+
+    /// Returns the features the given validator advertises, or the default ones if it
+    /// doesn't respond.
+    async fn node_features(&self, remote_node: &RemoteNode<impl ValidatorNode>) -> NodeFeatures {
+        let known_features = self
+            .node_features
+            .get(&remote_node.public_key)
+            .map(|features| *features);
+        if let Some(features) = known_features {
+            return features;
+        }
+        match remote_node.node.get_node_features().await {
+            Ok(features) => {
+                self.node_features.insert(remote_node.public_key, features);
+                features
+            }
+            Err(error) => {
+                debug!(
+                    "Failed to get the features of validator {}: {error}",
+                    remote_node.public_key
+                );
+                NodeFeatures::default()
+            }
+        }
+    }
+
     /// Downloads and processes all certificates up to (excluding) the specified height from the
     /// given validator.
     #[instrument(level = "trace", skip_all)]
@@ -354,6 +635,7 @@ impl<Env: Environment> Client<Env> {
         chain_id: ChainId,
         mut start: BlockHeight,
         stop: BlockHeight,
+        progress: &Progress,
     ) -> Result<(), ChainClientError> {
         while start < stop {
             // TODO(#2045): Analyze network errors instead of guessing the batch size.
@@ -361,8 +643,8 @@ impl<Env: Environment> Client<Env> {
                 .checked_sub(u64::from(start))
                 .ok_or(ArithmeticError::Overflow)?
                 .min(1000);
-            let Some(certificates) = remote_node
-                .try_query_certificates_from(chain_id, start, limit)
+            let Some(certificates) = self
+                .try_query_certificates_from(remote_node, chain_id, start, limit)
                 .await?
             else {
                 break;
@@ -375,6 +657,7 @@ impl<Env: Environment> Client<Env> {
             };
             assert!(info.next_block_height > start);
             start = info.next_block_height;
+            progress.advance_to(start.0);
         }
         Ok(())
     }
@@ -406,7 +689,10 @@ impl<Env: Environment> Client<Env> {
             }
 
             match result {
-                Ok(response) => info = Some(response.info),
+                Ok(response) => {
+                    self.cache_certificate(&certificate);
+                    info = Some(response.info);
+                }
                 Err(error) => {
                     // The certificate is not as expected. Give up.
                     warn!("Failed to process network certificate {}: {}", hash, error);
@@ -418,6 +704,91 @@ impl<Env: Environment> Client<Env> {
         info
     }
 
+    /// Queries the validator for the hashes of the certificates of the chain from `start`,
+    /// and returns these certificates. Returns `None` if the query failed.
+    #[instrument(level = "trace", skip_all)]
+    async fn try_query_certificates_from(
+        &self,
+        remote_node: &RemoteNode<impl ValidatorNode>,
+        chain_id: ChainId,
+        start: BlockHeight,
+        limit: u64,
+    ) -> Result<Option<Vec<ConfirmedBlockCertificate>>, NodeError> {
+        debug!(name = ?remote_node.public_key, ?chain_id, ?start, ?limit, "Querying certificates");
+        let range = BlockHeightRange {
+            start,
+            limit: Some(limit),
+        };
+        let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
+        match remote_node.handle_chain_info_query(query).await {
+            Ok(info) => {
+                let hashes = info.requested_sent_certificate_hashes;
+                Ok(Some(
+                    self.download_certificates_by_hash(remote_node, hashes)
+                        .await?,
+                ))
+            }
+            Err(error) => {
+                warn!("Failed to query certificates: {error}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the certificates with the given hashes, in the same order. The ones that were
+    /// recently confirmed are taken from the cache, and the others downloaded from the
+    /// validator.
+    async fn download_certificates_by_hash(
+        &self,
+        remote_node: &RemoteNode<impl ValidatorNode>,
+        hashes: Vec<CryptoHash>,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
+        let (mut certificates, missing_hashes): (HashMap<_, _>, Vec<_>) =
+            self.certificate_cache.try_get_many(hashes.clone());
+        if !certificates.is_empty() {
+            debug!(
+                "Reusing {} cached certificates out of {}",
+                certificates.len(),
+                hashes.len()
+            );
+        }
+        let downloaded = if missing_hashes.is_empty() {
+            Vec::new()
+        } else {
+            let start_time = Instant::now();
+            match remote_node.download_certificates(missing_hashes).await {
+                Ok(downloaded) => {
+                    self.validator_health
+                        .record_success(remote_node.public_key, start_time.elapsed());
+                    downloaded
+                }
+                Err(error) => {
+                    if error.is_connectivity_error() {
+                        self.validator_health
+                            .record_failure(remote_node.public_key, Instant::now());
+                    }
+                    return Err(error);
+                }
+            }
+        };
+        certificates.extend(
+            downloaded
+                .into_iter()
+                .map(|certificate| (certificate.hash(), certificate)),
+        );
+        Ok(hashes
+            .iter()
+            .filter_map(|hash| certificates.remove(hash))
+            .collect())
+    }
+
+    /// Remembers a block certificate that was confirmed or processed locally, so that it is
+    /// not downloaded again.
+    fn cache_certificate(&self, certificate: &ConfirmedBlockCertificate) {
+        self.certificate_cache
+            .insert_owned(&certificate.hash(), certificate.clone());
+    }
+
     async fn handle_certificate<T: ProcessableCertificate>(
         &self,
         certificate: GenericCertificate<T>,
@@ -496,6 +867,14 @@ pub struct MessagePolicy {
     /// accepted. `Option::None` means that messages from all chains are accepted. An empty
     /// `HashSet` denotes that messages from no chains are accepted.
     restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    /// If this is `Some`, incoming bundles are only received once there are this many.
+    batch_size: Option<usize>,
+    /// If this is `Some`, incoming bundles that don't make a full batch are received anyway
+    /// once the oldest one is this old.
+    batch_timeout: Option<TimeDelta>,
+    /// If this is `Some`, bundles that credit more than this amount of native tokens stay in
+    /// the inbox until they are approved with [`ChainClient::approve_incoming_bundle`].
+    approval_threshold: Option<Amount>,
 }
 
 #[derive(Copy, Clone, Debug, clap::ValueEnum)]
@@ -518,7 +897,63 @@ impl MessagePolicy {
         Self {
             blanket,
             restrict_chain_ids_to,
+            batch_size: None,
+            batch_timeout: None,
+            approval_threshold: None,
+        }
+    }
+
+    /// Receives incoming bundles in batches: only once there are `batch_size` of them, or
+    /// the oldest one is `batch_timeout` old. Without either, bundles are received at once.
+    pub fn with_batching(
+        mut self,
+        batch_size: Option<usize>,
+        batch_timeout: Option<TimeDelta>,
+    ) -> Self {
+        self.batch_size = batch_size;
+        self.batch_timeout = batch_timeout;
+        self
+    }
+
+    /// Leaves the bundles that credit more than `approval_threshold` in the inbox until they
+    /// are approved.
+    pub fn with_approval_threshold(mut self, approval_threshold: Option<Amount>) -> Self {
+        self.approval_threshold = approval_threshold;
+        self
+    }
+
+    /// Returns whether the bundle must be approved before it is received. Protected bundles
+    /// can't be held back.
+    fn requires_approval(&self, bundle: &IncomingBundle) -> bool {
+        !bundle.bundle.is_protected()
+            && self
+                .approval_threshold
+                .is_some_and(|threshold| bundle.bundle.attached_value() > threshold)
+    }
+
+    /// Returns when the bundles will be received if they don't make a full batch, or `None`
+    /// if there is no batch timeout.
+    fn batch_deadline(&self, bundles: &[IncomingBundle]) -> Option<Timestamp> {
+        let oldest = bundles.iter().map(|bundle| bundle.bundle.timestamp).min()?;
+        Some(oldest.saturating_add(self.batch_timeout?))
+    }
+
+    /// Returns whether the bundles can be received now, given that at most `max_bundles` fit
+    /// in one block.
+    fn is_batch_ready(
+        &self,
+        bundles: &[IncomingBundle],
+        max_bundles: usize,
+        now: Timestamp,
+    ) -> bool {
+        if self.batch_size.is_none() && self.batch_timeout.is_none() {
+            return true;
         }
+        self.batch_size
+            .is_some_and(|batch_size| bundles.len() >= batch_size.min(max_bundles))
+            || self
+                .batch_deadline(bundles)
+                .is_some_and(|deadline| deadline <= now)
     }
 
     #[instrument(level = "trace", skip(self))]
@@ -547,6 +982,16 @@ impl MessagePolicy {
     }
 }
 
+/// The pending message bundles that the policies allow in the next block.
+#[derive(Default)]
+struct PendingBundles {
+    bundles: Vec<IncomingBundle>,
+    /// The bundles that stay in the inbox until they are approved.
+    awaiting_approval: Vec<IncomingBundle>,
+    /// The maximal number of bundles in one block.
+    max_bundles: usize,
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct ChainClientOptions {
@@ -722,6 +1167,9 @@ pub type ChainGuard<'a, T> = Unsend<DashMapRef<'a, ChainId, T>>;
 pub type ChainGuardMut<'a, T> = Unsend<DashMapRefMut<'a, ChainId, T>>;
 pub type ChainGuardMapped<'a, T> = Unsend<DashMapMappedRef<'a, ChainId, ChainClientState, T>>;
 
+/// A block proposal to be signed offline, with the blobs that it publishes.
+pub type UnsignedProposalWithBlobs = (UnsignedBlockProposal, Vec<Blob>);
+
 impl<Env: Environment> ChainClient<Env> {
     /// Gets a shared reference to the chain's state.
     #[instrument(level = "trace", skip(self))]
@@ -941,12 +1389,61 @@ impl<Env: Environment> ChainClient<Env> {
     }
 
     /// Obtains up to `self.options.max_pending_message_bundles` pending message bundles for the
-    /// local chain.
+    /// local chain, unless the message policy waits for a larger batch.
     #[instrument(level = "trace")]
     async fn pending_message_bundles(&self) -> Result<Vec<IncomingBundle>, ChainClientError> {
+        let PendingBundles {
+            bundles,
+            max_bundles,
+            ..
+        } = self.select_pending_message_bundles().await?;
+        let now = self.storage_client().clock().current_time();
+        if !self
+            .options
+            .message_policy
+            .is_batch_ready(&bundles, max_bundles, now)
+        {
+            debug!(
+                count = bundles.len(),
+                "Waiting for a larger batch of bundles"
+            );
+            return Ok(Vec::new());
+        }
+        Ok(bundles)
+    }
+
+    /// Returns when the pending message bundles that wait for a larger batch will be received,
+    /// if the message policy has a batch timeout.
+    #[instrument(level = "trace")]
+    pub async fn inbox_batch_deadline(&self) -> Result<Option<Timestamp>, ChainClientError> {
+        let PendingBundles { bundles, .. } = self.select_pending_message_bundles().await?;
+        Ok(self.options.message_policy.batch_deadline(&bundles))
+    }
+
+    /// Returns the pending message bundles that stay in the inbox until they are approved,
+    /// because they exceed the message policy's approval threshold.
+    #[instrument(level = "trace")]
+    pub async fn incoming_bundles_awaiting_approval(
+        &self,
+    ) -> Result<Vec<IncomingBundle>, ChainClientError> {
+        Ok(self
+            .select_pending_message_bundles()
+            .await?
+            .awaiting_approval)
+    }
+
+    /// Approves the incoming bundles from the given origin and block height, so that they are
+    /// received even if they exceed the message policy's approval threshold.
+    #[instrument(level = "trace")]
+    pub fn approve_incoming_bundle(&self, origin: ChainId, height: BlockHeight) {
+        self.state_mut().approve_bundle(origin, height);
+    }
+
+    /// Selects the pending message bundles that the policies allow in the next block.
+    async fn select_pending_message_bundles(&self) -> Result<PendingBundles, ChainClientError> {
         if self.options.message_policy.is_ignore() {
             // Ignore all messages.
-            return Ok(Vec::new());
+            return Ok(PendingBundles::default());
         }
 
         let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
@@ -965,18 +1462,86 @@ impl<Env: Environment> ChainClient<Env> {
             );
         }
 
-        let pending_message_bundles = info.requested_pending_message_bundles;
+        let ChainInfo {
+            requested_pending_message_bundles: pending_message_bundles,
+            inbox_policy,
+            block_limits,
+            ..
+        } = *info;
+        // Validators reject blocks with more incoming bundles than the chain's limit.
+        let max_bundles = block_limits
+            .max_incoming_bundles_per_block
+            .map_or(usize::MAX, |limit| limit as usize)
+            .min(self.options.max_pending_message_bundles);
+        // Bundles that the owners' inbox policy doesn't accept stay in the inbox. Unless they
+        // can be skipped, the later bundles from the same origin have to wait for them.
+        let mut blocked_origins = HashSet::new();
+        // The bundles and bytes received so far, limited by the inbox policy.
+        let mut bundle_count = 0u32;
+        let mut bundle_bytes = 0u64;
+        // Approvals are only kept for the bundles that are still in the inbox.
+        let pending_keys = pending_message_bundles
+            .iter()
+            .map(|bundle| (bundle.origin, bundle.bundle.height))
+            .collect::<HashSet<_>>();
+        self.state_mut()
+            .retain_approved_bundles(|key| pending_keys.contains(key));
+        let mut awaiting_approval = Vec::new();
 
-        Ok(pending_message_bundles
+        let bundles = pending_message_bundles
             .into_iter()
             .filter_map(|mut bundle| {
-                self.options
-                    .message_policy
-                    .must_handle(&mut bundle)
-                    .then_some(bundle)
+                if blocked_origins.contains(&bundle.origin) {
+                    return None;
+                }
+                if !bundle.is_accepted_by(&inbox_policy) {
+                    debug!(origin = %bundle.origin, "Bundle not accepted by the inbox policy");
+                    if !bundle.bundle.is_skippable() {
+                        blocked_origins.insert(bundle.origin);
+                    }
+                    return None;
+                }
+                if !self.options.message_policy.must_handle(&mut bundle) {
+                    return None;
+                }
+                if self.options.message_policy.requires_approval(&bundle)
+                    && !self
+                        .state()
+                        .is_approved_bundle(bundle.origin, bundle.bundle.height)
+                {
+                    debug!(origin = %bundle.origin, "Bundle awaiting approval");
+                    blocked_origins.insert(bundle.origin);
+                    awaiting_approval.push(bundle);
+                    return None;
+                }
+                // Bundles over the limits are only postponed, so they always block their
+                // origin. Protected bundles are received regardless.
+                let bytes = bcs::serialized_size(&bundle).unwrap_or(0) as u64;
+                if !bundle.bundle.is_protected()
+                    && (inbox_policy
+                        .max_bundles_per_block
+                        .is_some_and(|max_count| bundle_count >= max_count)
+                        || inbox_policy
+                            .max_bundle_bytes_per_block
+                            .is_some_and(|max_bytes| {
+                                bundle_bytes.saturating_add(bytes) > max_bytes
+                            }))
+                {
+                    debug!(origin = %bundle.origin, "Bundle over the inbox policy's block limits");
+                    blocked_origins.insert(bundle.origin);
+                    return None;
+                }
+                bundle_count = bundle_count.saturating_add(1);
+                bundle_bytes = bundle_bytes.saturating_add(bytes);
+                Some(bundle)
             })
-            .take(self.options.max_pending_message_bundles)
-            .collect())
+            .take(max_bundles)
+            .collect();
+        Ok(PendingBundles {
+            bundles,
+            awaiting_approval,
+            max_bundles,
+        })
     }
 
     /// Returns an `UpdateStreams` operation that updates this client's chain about new events
@@ -1105,6 +1670,27 @@ impl<Env: Environment> ChainClient<Env> {
     /// Returns an error if we don't have the private key for the identity.
     #[instrument(level = "trace")]
     pub async fn identity(&self) -> Result<AccountOwner, ChainClientError> {
+        let preferred_owner = self.owner_identity().await?;
+        let has_signer = self
+            .signer()
+            .contains_key(&preferred_owner)
+            .await
+            .map_err(ChainClientError::signer_failure)?;
+
+        if !has_signer {
+            warn!(%self.chain_id, ?preferred_owner,
+                "Chain is one of the owners but its Signer instance doesn't contain the key",
+            );
+            return Err(ChainClientError::CannotFindKeyForChain(self.chain_id));
+        }
+
+        Ok(preferred_owner)
+    }
+
+    /// Obtains the identity of the current owner of the chain, without checking that we have
+    /// its private key, e.g. because proposals are signed offline.
+    #[instrument(level = "trace")]
+    async fn owner_identity(&self) -> Result<AccountOwner, ChainClientError> {
         let Some(preferred_owner) = self.preferred_owner else {
             return Err(ChainClientError::NoAccountKeyConfigured(self.chain_id));
         };
@@ -1132,19 +1718,6 @@ impl<Env: Environment> ChainClient<Env> {
             return Err(ChainClientError::NotAnOwner(self.chain_id));
         }
 
-        let has_signer = self
-            .signer()
-            .contains_key(&preferred_owner)
-            .await
-            .map_err(ChainClientError::signer_failure)?;
-
-        if !has_signer {
-            warn!(%self.chain_id, ?preferred_owner,
-                "Chain is one of the owners but its Signer instance doesn't contain the key",
-            );
-            return Err(ChainClientError::CannotFindKeyForChain(self.chain_id));
-        }
-
         Ok(preferred_owner)
     }
 
@@ -1324,6 +1897,7 @@ impl<Env: Environment> ChainClient<Env> {
                 })
             },
             self.options.grace_period,
+            &self.client.validator_health,
         )
         .await?;
         Ok(())
@@ -1346,6 +1920,33 @@ impl<Env: Environment> ChainClient<Env> {
         let n_validators = nodes.len();
         let chain_worker_count =
             std::cmp::max(1, self.client.max_loaded_chains.get() / n_validators);
+        if let CommunicateAction::SubmitBlock { proposal, .. } = &action {
+            let health = &self.client.validator_health;
+            if health.policy().upload_blobs_to_one_validator
+                && n_validators > 1
+                && !proposal.content.block.published_blob_ids().is_empty()
+            {
+                // Upload the published blobs to a single validator first, so that the
+                // others can download them from it instead of from us.
+                let now = Instant::now();
+                if let Some(remote_node) = nodes
+                    .iter()
+                    .find(|remote_node| !health.is_excluded(&remote_node.public_key, now))
+                {
+                    let mut updater = ValidatorUpdater {
+                        chain_worker_count,
+                        remote_node: remote_node.clone(),
+                        local_node: local_node.clone(),
+                    };
+                    if let Err(error) = Box::pin(updater.send_chain_update(action.clone())).await {
+                        warn!(
+                            validator = %remote_node.public_key, %error,
+                            "Failed to upload the published blobs to a single validator"
+                        );
+                    }
+                }
+            }
+        }
         let ((votes_hash, votes_round), votes) = communicate_with_quorum(
             &nodes,
             committee,
@@ -1360,6 +1961,7 @@ impl<Env: Environment> ChainClient<Env> {
                 Box::pin(async move { updater.send_chain_update(action).await })
             },
             self.options.grace_period,
+            &self.client.validator_health,
         )
         .await?;
         ensure!(
@@ -1375,7 +1977,7 @@ impl<Env: Environment> ChainClient<Env> {
         // * `communicate_with_quorum` ensured a sufficient "weight" of
         // (non-error) answers were returned by validators.
         // * each answer is a vote signed by the expected validator.
-        let certificate = LiteCertificate::try_from_votes(votes)
+        let certificate = LiteCertificate::try_from_votes(votes, committee)
             .ok_or_else(|| {
                 ChainClientError::InternalError("Vote values or rounds don't match; this is a bug")
             })?
@@ -1459,7 +2061,7 @@ impl<Env: Environment> ChainClient<Env> {
                     .await
                     .ok_or(err)?;
                     self.client.local_node.store_blobs(&blobs).await?;
-                    self.process_certificate(certificate).await?;
+                    self.process_certificate(certificate.clone()).await?;
                 }
                 _ => {
                     // The certificate is not as expected. Give up.
@@ -1468,6 +2070,7 @@ impl<Env: Environment> ChainClient<Env> {
                 }
             }
         }
+        self.client.cache_certificate(&certificate);
 
         Ok(())
     }
@@ -1542,8 +2145,9 @@ impl<Env: Environment> ChainClient<Env> {
         .collect();
 
         // Download the block certificates.
-        let remote_certificates = remote_node
-            .download_certificates(certificate_hashes)
+        let remote_certificates = self
+            .client
+            .download_certificates_by_hash(remote_node, certificate_hashes)
             .await?;
 
         // Check the signatures and keep only the ones that are valid.
@@ -1772,6 +2376,7 @@ impl<Env: Environment> ChainClient<Env> {
                 })
             },
             self.options.grace_period,
+            &self.client.validator_health,
         )
         .await;
         let received_certificate_batches = match result {
@@ -1927,6 +2532,7 @@ impl<Env: Environment> ChainClient<Env> {
                 }
             },
             self.options.grace_period,
+            &self.client.validator_health,
         )
         .await?;
 
@@ -1958,8 +2564,9 @@ impl<Env: Environment> ChainClient<Env> {
             return Ok(());
         }
 
-        let certificates: Vec<ConfirmedBlockCertificate> = remote_node
-            .download_certificates(info.requested_sent_certificate_hashes)
+        let certificates = self
+            .client
+            .download_certificates_by_hash(remote_node, info.requested_sent_certificate_hashes)
             .await?;
 
         if !certificates.is_empty()
@@ -2172,7 +2779,7 @@ impl<Env: Environment> ChainClient<Env> {
         mut block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
-    ) -> Result<(Block, ChainInfoResponse), ChainClientError> {
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), ChainClientError> {
         loop {
             let result = self
                 .stage_block_execution(block.clone(), round, published_blobs.clone())
@@ -2217,12 +2824,12 @@ impl<Env: Environment> ChainClient<Env> {
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
-    ) -> Result<(Block, ChainInfoResponse), ChainClientError> {
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), ChainClientError> {
         loop {
             let result = self
                 .client
                 .local_node
-                .stage_block_execution(block.clone(), round, published_blobs.clone())
+                .stage_block_execution_with_resources(block.clone(), round, published_blobs.clone())
                 .await;
             if let Err(LocalNodeError::BlobsNotFound(blob_ids)) = &result {
                 self.receive_certificates_for_blobs(blob_ids.clone())
@@ -2268,6 +2875,86 @@ impl<Env: Environment> ChainClient<Env> {
             .await
     }
 
+    /// Executes a block with the given operations locally, against the current state of the
+    /// chain, without proposing it to the validators or persisting any changes.
+    ///
+    /// Like [`ChainClient::execute_operations`], the block also accepts the pending incoming
+    /// message bundles, and rejects those that fail. A pending proposal is ignored.
+    #[instrument(level = "trace", skip(operations, blobs))]
+    pub async fn dry_run(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+    ) -> Result<DryRunOutcome, ChainClientError> {
+        self.prepare_chain().await?;
+        let identity = self.owner_identity().await?;
+        let incoming_bundles = self.pending_message_bundles().await?;
+        let query = ChainInfoQuery::new(self.chain_id).with_owner_balance(identity);
+        let info = self
+            .client
+            .local_node
+            .handle_chain_info_query(query)
+            .await?
+            .info;
+        let (previous_block_hash, height, timestamp) = {
+            let state = self.state();
+            (
+                state.block_hash(),
+                state.next_block_height(),
+                self.next_timestamp(&incoming_bundles, state.timestamp()),
+            )
+        };
+        let proposed_block = ProposedBlock {
+            epoch: self.epoch().await?,
+            chain_id: self.chain_id,
+            incoming_bundles,
+            operations,
+            previous_block_hash,
+            height,
+            authenticated_signer: Some(identity),
+            timestamp,
+        };
+        let round = info.manager.current_round.multi_leader();
+        let (block, response, resources) = self
+            .stage_block_execution_and_discard_failing_messages(proposed_block, round, blobs)
+            .await?;
+        Ok(DryRunOutcome {
+            block,
+            resources,
+            chain_balance_before: info.chain_balance,
+            chain_balance_after: response.info.chain_balance,
+            owner_balance_before: info.requested_owner_balance,
+            owner_balance_after: response.info.requested_owner_balance,
+        })
+    }
+
+    /// Estimates the fees that a block with the given operations would be charged, by
+    /// executing it locally like [`ChainClient::dry_run`].
+    ///
+    /// This fails like the actual execution would, e.g. if the chain cannot pay the fees.
+    #[instrument(level = "trace", skip(operations))]
+    pub async fn estimate_fees(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<FeeEstimate, ChainClientError> {
+        let outcome = self.dry_run(operations, Vec::new()).await?;
+        let resources = outcome.resources;
+        let bundle_count = outcome.block.body.incoming_bundles.len();
+        let (bundle_fees, operation_fees) = resources.transaction_fees.split_at(bundle_count);
+        let incoming_bundles = bundle_fees
+            .iter()
+            .try_fold(Amount::ZERO, |sum, fees| sum.try_add(*fees))?;
+        let transactions = operation_fees
+            .iter()
+            .try_fold(incoming_bundles, |sum, fees| sum.try_add(*fees))?;
+        Ok(FeeEstimate {
+            operations: operation_fees.to_vec(),
+            incoming_bundles,
+            block: resources.tracker.fees.try_sub(transactions)?,
+            total: resources.tracker.fees,
+        })
+    }
+
     /// Executes a new block.
     ///
     /// This must be preceded by a call to `prepare_chain()`.
@@ -2365,7 +3052,7 @@ impl<Env: Environment> ChainClient<Env> {
             Either::Left(round) => round.multi_leader(),
             Either::Right(_) => None,
         };
-        let (block, _) = self
+        let (block, _, _) = self
             .stage_block_execution_and_discard_failing_messages(
                 proposed_block,
                 round,
@@ -2541,7 +3228,7 @@ impl<Env: Environment> ChainClient<Env> {
             .stage_block_execution_and_discard_failing_messages(block, None, Vec::new())
             .await
         {
-            Ok((_, response)) => Ok((
+            Ok((_, response, _)) => Ok((
                 response.info.chain_balance,
                 response.info.requested_owner_balance,
             )),
@@ -2631,6 +3318,24 @@ impl<Env: Environment> ChainClient<Env> {
             .await
     }
 
+    /// Sends tokens to an account, with a memo encrypted to the recipient.
+    #[instrument(level = "trace", skip(memo))]
+    pub async fn transfer_with_memo(
+        &self,
+        owner: AccountOwner,
+        amount: Amount,
+        account: Account,
+        memo: EncryptedMemo,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::TransferWithMemo {
+            owner,
+            recipient: Recipient::Account(account),
+            amount,
+            memo,
+        })
+        .await
+    }
+
     /// Burns tokens.
     #[instrument(level = "trace")]
     pub async fn burn(
@@ -2678,6 +3383,31 @@ impl<Env: Environment> ChainClient<Env> {
         }
         let owner = self.identity().await?;
 
+        let prepared = match self.prepare_proposal(&info, owner).await? {
+            ClientOutcome::Committed(Some(prepared)) => prepared,
+            ClientOutcome::Committed(None) => return Ok(ClientOutcome::Committed(None)),
+            ClientOutcome::WaitForTimeout(timeout) => {
+                return Ok(ClientOutcome::WaitForTimeout(timeout))
+            }
+        };
+        let proposal = prepared
+            .proposal
+            .sign(self.signer())
+            .await
+            .map_err(ChainClientError::signer_failure)?;
+        let certificate = self
+            .submit_proposal(&info, Box::new(proposal), prepared.block, prepared.blobs)
+            .await?;
+        Ok(ClientOutcome::Committed(Some(certificate)))
+    }
+
+    /// Returns the unsigned proposal for the block we have to propose next: the highest
+    /// validated block, if there is one, or else our own pending block.
+    async fn prepare_proposal(
+        &self,
+        info: &ChainInfo,
+        owner: AccountOwner,
+    ) -> Result<ClientOutcome<Option<PreparedProposal>>, ChainClientError> {
         let local_node = &self.client.local_node;
         // Otherwise we have to re-propose the highest validated block, if there is one.
         let pending_proposal = self.state().pending_proposal().clone();
@@ -2716,11 +3446,11 @@ impl<Env: Environment> ChainClient<Env> {
             // Use the round number assuming there are oracle responses.
             // Using the round number during execution counts as an oracle.
             let proposed_block = pending_proposal.block;
-            let round = match Self::round_for_new_proposal(&info, &owner, &proposed_block, true)? {
+            let round = match Self::round_for_new_proposal(info, &owner, &proposed_block, true)? {
                 Either::Left(round) => round.multi_leader(),
                 Either::Right(_) => None,
             };
-            let (block, _) = self
+            let (block, _, _) = self
                 .stage_block_execution(proposed_block, round, pending_proposal.blobs.clone())
                 .await?;
             debug!("Proposing the local pending block.");
@@ -2732,7 +3462,7 @@ impl<Env: Environment> ChainClient<Env> {
         let has_oracle_responses = block.has_oracle_responses();
         let (proposed_block, outcome) = block.into_proposal();
         let round = match Self::round_for_new_proposal(
-            &info,
+            info,
             &owner,
             &proposed_block,
             has_oracle_responses,
@@ -2742,30 +3472,37 @@ impl<Env: Environment> ChainClient<Env> {
         };
         debug!("Proposing block for round {}", round);
 
-        let already_handled_locally = info
-            .manager
-            .already_handled_proposal(round, &proposed_block);
         // Create the final block proposal.
-        let proposal = if let Some(locking) = info.manager.requested_locking {
-            Box::new(match *locking {
-                LockingBlock::Regular(cert) => {
-                    BlockProposal::new_retry(owner, round, cert, self.signer())
-                        .await
-                        .map_err(ChainClientError::signer_failure)?
-                }
-                LockingBlock::Fast(proposal) => {
-                    BlockProposal::new_initial(owner, round, proposal.content.block, self.signer())
-                        .await
-                        .map_err(ChainClientError::signer_failure)?
-                }
-            })
-        } else {
-            Box::new(
-                BlockProposal::new_initial(owner, round, proposed_block.clone(), self.signer())
-                    .await
-                    .map_err(ChainClientError::signer_failure)?,
-            )
+        let proposal = match info.manager.requested_locking.as_deref().cloned() {
+            Some(LockingBlock::Regular(cert)) => {
+                UnsignedBlockProposal::new_retry(owner, round, cert)
+            }
+            Some(LockingBlock::Fast(proposal)) => {
+                UnsignedBlockProposal::new_initial(owner, round, proposal.content.block)
+            }
+            None => UnsignedBlockProposal::new_initial(owner, round, proposed_block.clone()),
         };
+        Ok(ClientOutcome::Committed(Some(PreparedProposal {
+            proposal,
+            block: Block::new(proposed_block, outcome),
+            blobs,
+        })))
+    }
+
+    /// Checks a signed block proposal locally, then sends it to the validators and returns
+    /// the resulting confirmed block certificate.
+    async fn submit_proposal(
+        &self,
+        info: &ChainInfo,
+        proposal: Box<BlockProposal>,
+        block: Block,
+        blobs: Vec<Blob>,
+    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
+        let local_node = &self.client.local_node;
+        let round = proposal.content.round;
+        let already_handled_locally = info
+            .manager
+            .already_handled_proposal(round, &proposal.content.block);
         if !already_handled_locally {
             // Check the final block proposal. This will be cheaper after #1401.
             if let Err(err) = local_node.handle_block_proposal(*proposal.clone()).await {
@@ -2781,7 +3518,6 @@ impl<Env: Environment> ChainClient<Env> {
             }
         }
         let committee = self.local_committee().await?;
-        let block = Block::new(proposed_block, outcome);
         // Send the query to validators.
         let certificate = if round.is_fast() {
             let hashed_value = ConfirmedBlock::new(block);
@@ -2796,7 +3532,81 @@ impl<Env: Environment> ChainClient<Env> {
         };
         debug!(round = %certificate.round, "Sending confirmed block to validators");
         self.update_validators(Some(&committee)).await?;
-        Ok(ClientOutcome::Committed(Some(certificate)))
+        Ok(certificate)
+    }
+
+    /// Returns an unsigned proposal for the next block, so that it can be signed offline by
+    /// the chain owner and submitted with [`ChainClient::submit_signed_proposal`].
+    ///
+    /// If a block is pending or locked on the chain, that block has to be proposed first.
+    /// Otherwise a new pending block is created with the incoming messages, the `operations`
+    /// and the `blobs`. Returns `None` if there is nothing to propose. If a validated block
+    /// needs to be finalized first, which doesn't require a signature, that is done and `None`
+    /// is returned, too.
+    #[instrument(level = "trace", skip(operations, blobs))]
+    pub async fn unsigned_proposal(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+    ) -> Result<ClientOutcome<Option<UnsignedProposalWithBlobs>>, ChainClientError> {
+        self.synchronize_from_validators().await?;
+        let mutex = self.state().client_mutex();
+        let _guard = mutex.lock_owned().await;
+        let mut info = self.request_leader_timeout_if_needed().await?;
+        if info.manager.has_locking_block_in_current_round()
+            && !info.manager.current_round.is_fast()
+        {
+            return Ok(self.finalize_locking_block(info).await?.map(|_| None));
+        }
+        let owner = self.owner_identity().await?;
+        if info.manager.requested_locking.is_none() && self.state().pending_proposal().is_none() {
+            let incoming_bundles = self.pending_message_bundles().await?;
+            if incoming_bundles.is_empty() && operations.is_empty() {
+                return Ok(ClientOutcome::Committed(None));
+            }
+            self.new_pending_block(incoming_bundles, operations, blobs, owner)
+                .await?;
+            info = self.chain_info_with_manager_values().await?;
+        }
+        Ok(self
+            .prepare_proposal(&info, owner)
+            .await?
+            .map(|prepared| prepared.map(|prepared| (prepared.proposal, prepared.blobs))))
+    }
+
+    /// Submits a block proposal that was signed offline to the validators.
+    #[instrument(level = "trace", skip(proposal, blobs))]
+    pub async fn submit_signed_proposal(
+        &self,
+        proposal: BlockProposal,
+        blobs: Vec<Blob>,
+    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
+        ensure!(
+            proposal.content.block.chain_id == self.chain_id,
+            ChainClientError::BlockProposalError("The proposal is for a different chain")
+        );
+        proposal.check_signature().map_err(NodeError::from)?;
+        self.synchronize_from_validators().await?;
+        let mutex = self.state().client_mutex();
+        let _guard = mutex.lock_owned().await;
+        let info = self.chain_info_with_manager_values().await?;
+        ensure!(
+            proposal.content.block.height == info.next_block_height,
+            ChainClientError::BlockProposalError(
+                "The proposal is not for the next block height of the chain"
+            )
+        );
+        let block = match &proposal.content.outcome {
+            Some(outcome) => Block::new(proposal.content.block.clone(), outcome.clone()),
+            None => {
+                let round = proposal.content.round.multi_leader();
+                self.stage_block_execution(proposal.content.block.clone(), round, blobs.clone())
+                    .await?
+                    .0
+            }
+        };
+        self.submit_proposal(&info, Box::new(proposal), block, blobs)
+            .await
     }
 
     /// Checks that the current height and hash match the `ChainClientState`. Then requests a
@@ -3024,6 +3834,96 @@ impl<Env: Environment> ChainClient<Env> {
         .await
     }
 
+    /// Registers or renews a name on the admin chain, owned by this client's signer.
+    #[instrument(level = "trace")]
+    pub async fn register_name(
+        &self,
+        name: String,
+        target: NameTarget,
+        expiry: Timestamp,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::RegisterName {
+            name,
+            target,
+            expiry,
+        })
+        .await
+    }
+
+    /// Transfers a name owned by this client's signer to a new owner.
+    #[instrument(level = "trace")]
+    pub async fn transfer_name(
+        &self,
+        name: String,
+        new_owner: AccountOwner,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::TransferName { name, new_owner })
+            .await
+    }
+
+    /// Resolves a name registered on the admin chain, after synchronizing the admin chain from
+    /// the validators.
+    #[instrument(level = "trace")]
+    pub async fn resolve_name(&self, name: &str) -> Result<Option<NameTarget>, ChainClientError> {
+        self.synchronize_chain_state(self.admin_id).await?;
+        let admin_chain = self
+            .client
+            .local_node
+            .chain_state_view(self.admin_id)
+            .await?;
+        let system = &admin_chain.execution_state.system;
+        Ok(system.resolve_name(name, *system.timestamp.get()).await?)
+    }
+
+    /// Sets or, if `value` is `None`, removes a configuration value of this chain, readable by
+    /// all its applications.
+    #[instrument(level = "trace", skip(value))]
+    pub async fn set_config_value(
+        &self,
+        key: String,
+        value: Option<Vec<u8>>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::SetConfig { key, value })
+            .await
+    }
+
+    /// Changes the rules on the incoming message bundles that the owners of this chain accept.
+    #[instrument(level = "trace")]
+    pub async fn change_inbox_policy(
+        &self,
+        inbox_policy: InboxPolicy,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::ChangeInboxPolicy(inbox_policy))
+            .await
+    }
+
+    /// Changes the limits on the block proposals of this chain.
+    #[instrument(level = "trace")]
+    pub async fn change_block_limits(
+        &self,
+        block_limits: BlockLimits,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::ChangeBlockLimits(block_limits))
+            .await
+    }
+
+    /// Changes how much of this chain's history its owners ask validators to keep.
+    #[instrument(level = "trace")]
+    pub async fn change_retention_policy(
+        &self,
+        retention_policy: RetentionPolicy,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::ChangeRetentionPolicy(retention_policy))
+            .await
+    }
+
+    /// Returns a configuration value of this chain from the local state.
+    #[instrument(level = "trace")]
+    pub async fn config_value(&self, key: &str) -> Result<Option<Vec<u8>>, ChainClientError> {
+        let chain = self.chain_state_view().await?;
+        Ok(chain.execution_state.system.config.get(key).await?)
+    }
+
     /// Opens a new chain with a derived UID.
     #[instrument(level = "trace", skip(self))]
     pub async fn open_chain(
@@ -3064,6 +3964,54 @@ impl<Env: Environment> ChainClient<Env> {
         }
     }
 
+    /// Opens several new chains in a single block, one for each configuration.
+    ///
+    /// Returns the descriptions of the new chains, in the order of the configurations.
+    #[instrument(level = "trace", skip(self, configs), fields(count = configs.len()))]
+    pub async fn open_chains(
+        &self,
+        configs: Vec<OpenChainConfig>,
+    ) -> Result<ClientOutcome<(Vec<ChainDescription>, ConfirmedBlockCertificate)>, ChainClientError>
+    {
+        loop {
+            let operations = configs
+                .iter()
+                .cloned()
+                .map(|config| Operation::system(SystemOperation::OpenChain(config)))
+                .collect();
+            let certificate = match self.execute_block(operations, vec![]).await? {
+                ExecuteBlockOutcome::Executed(certificate) => certificate,
+                ExecuteBlockOutcome::Conflict(_) => continue,
+                ExecuteBlockOutcome::WaitForTimeout(timeout) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+            };
+            // Each operation created one chain description blob, in order.
+            let descriptions = certificate
+                .block()
+                .body
+                .blobs
+                .iter()
+                .flatten()
+                .filter(|blob| blob.content().blob_type() == BlobType::ChainDescription)
+                .map(|blob| bcs::from_bytes::<ChainDescription>(blob.bytes()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if descriptions.len() != configs.len() {
+                return Err(ChainClientError::InternalError(
+                    "Failed to create the new chains",
+                ));
+            }
+            for description in &descriptions {
+                self.client.track_chain(description.id());
+            }
+            self.client
+                .local_node
+                .retry_pending_cross_chain_requests(self.chain_id)
+                .await?;
+            return Ok(ClientOutcome::Committed((descriptions, certificate)));
+        }
+    }
+
     /// Closes the chain (and loses everything in it!!).
     /// Returns `None` if the chain was already closed.
     #[instrument(level = "trace")]
@@ -3102,14 +4050,17 @@ impl<Env: Environment> ChainClient<Env> {
         blobs: Vec<Blob>,
         module_id: ModuleId,
     ) -> Result<ClientOutcome<(ModuleId, ConfirmedBlockCertificate)>, ChainClientError> {
-        self.execute_operations(
-            vec![Operation::system(SystemOperation::PublishModule {
-                module_id,
-            })],
-            blobs,
-        )
-        .await?
-        .try_map(|certificate| Ok((module_id, certificate)))
+        let progress = self.publish_blobs_progress(&blobs);
+        let outcome = self
+            .execute_operations(
+                vec![Operation::system(SystemOperation::PublishModule {
+                    module_id,
+                })],
+                blobs,
+            )
+            .await?;
+        progress.complete();
+        outcome.try_map(|certificate| Ok((module_id, certificate)))
     }
 
     /// Publishes some data blobs.
@@ -3127,8 +4078,28 @@ impl<Env: Environment> ChainClient<Env> {
                 })
             })
             .collect();
-        self.execute_operations(publish_blob_operations, blobs.collect())
-            .await
+        let blobs = blobs.collect::<Vec<_>>();
+        let progress = self.publish_blobs_progress(&blobs);
+        let outcome = self
+            .execute_operations(publish_blob_operations, blobs)
+            .await?;
+        progress.complete();
+        Ok(outcome)
+    }
+
+    /// Reports the publication of the given blobs until the returned [`Progress`] is
+    /// dropped.
+    fn publish_blobs_progress(&self, blobs: &[Blob]) -> Progress {
+        let size = blobs.iter().map(|blob| blob.bytes().len() as u64).sum();
+        Progress::start(
+            self.client.progress_reporter.clone(),
+            ProgressTask::PublishBlobs {
+                chain_id: self.chain_id,
+            },
+            ProgressUnit::Bytes,
+            0,
+            Some(size),
+        )
     }
 
     /// Publishes some data blob.
@@ -3813,6 +4784,38 @@ enum ExecuteBlockOutcome {
     WaitForTimeout(RoundTimeout),
 }
 
+/// The result of [`ChainClient::dry_run`]: a block that was executed locally, but neither
+/// proposed nor persisted.
+#[derive(Clone, Debug)]
+pub struct DryRunOutcome {
+    /// The block, including its execution outcome, i.e. the messages, events and blobs it
+    /// would create, and the resulting state hash.
+    pub block: Block,
+    /// The resources the block would use, including the fees it would be charged.
+    pub resources: BlockExecutionResources,
+    /// The chain's balance before the block.
+    pub chain_balance_before: Amount,
+    /// The chain's balance after the block, including fees and incoming transfers.
+    pub chain_balance_after: Amount,
+    /// The balance of the block's signer before the block, if the account exists.
+    pub owner_balance_before: Option<Amount>,
+    /// The balance of the block's signer after the block, if the account exists.
+    pub owner_balance_after: Option<Amount>,
+}
+
+/// The fees of a block, as estimated by [`ChainClient::estimate_fees`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// The fees for each operation, in order.
+    pub operations: Vec<Amount>,
+    /// The fees for the pending incoming messages that the block would accept.
+    pub incoming_bundles: Amount,
+    /// The fees for the block itself and for the blobs it publishes.
+    pub block: Amount,
+    /// The total fees of the block.
+    pub total: Amount,
+}
+
 /// Wrapper for `AbortHandle` that aborts when its dropped.
 #[must_use]
 pub struct AbortOnDrop(pub AbortHandle);
@@ -3838,6 +4841,13 @@ struct ReceivedCertificatesFromValidator {
     other_sender_chains: Vec<ChainId>,
 }
 
+/// A block proposal that is ready to be signed, together with the block and published blobs.
+struct PreparedProposal {
+    proposal: UnsignedBlockProposal,
+    block: Block,
+    blobs: Vec<Blob>,
+}
+
 /// A pending proposed block, together with its published blobs.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PendingProposal {
@@ -10,13 +10,18 @@ use linera_base::{
         BcsSignable, CryptoError, CryptoHash, ValidatorPublicKey, ValidatorSecretKey,
         ValidatorSignature,
     },
-    data_types::{Amount, BlockHeight, ChainDescription, Epoch, Round, Timestamp},
+    data_types::{
+        Amount, BlockHeight, BlockLimits, ChainDescription, Epoch, InboxPolicy, RetentionPolicy,
+        Round, Timestamp,
+    },
     identifiers::{AccountOwner, ChainId},
 };
 use linera_chain::{
-    data_types::{ChainAndHeight, IncomingBundle, MessageBundle},
+    data_types::{
+        ChainAndHeight, EquivocationEvidence, IncomingBundle, MessageBundle, ValidatorKeys,
+    },
     manager::ChainManagerInfo,
-    ChainStateView,
+    ChainError, ChainStateView,
 };
 use linera_execution::{committee::Committee, ExecutionRuntimeContext};
 use linera_storage::ChainRuntimeContext;
@@ -90,6 +95,9 @@ pub struct ChainInfoQuery {
     /// Include a vote to switch to fallback mode, if appropriate.
     #[debug(skip_if = Not::not)]
     pub request_fallback: bool,
+    /// Query the recorded evidence of validators signing conflicting blocks of the chain.
+    #[debug(skip_if = Not::not)]
+    pub request_equivocation_evidence: bool,
 }
 
 impl ChainInfoQuery {
@@ -105,6 +113,7 @@ impl ChainInfoQuery {
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: false,
+            request_equivocation_evidence: false,
         }
     }
 
@@ -152,6 +161,11 @@ impl ChainInfoQuery {
         self.request_fallback = true;
         self
     }
+
+    pub fn with_equivocation_evidence(mut self) -> Self {
+        self.request_equivocation_evidence = true;
+        self
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -196,6 +210,18 @@ pub struct ChainInfo {
     /// The response to `request_received_certificates_excluding_first_n`
     #[debug(skip_if = Vec::is_empty)]
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The recorded evidence of validators signing conflicting blocks (if requested).
+    #[debug(skip_if = Vec::is_empty)]
+    pub requested_equivocation_evidence: Vec<EquivocationEvidence>,
+    /// The rules on the incoming message bundles that the chain owners accept.
+    #[debug(skip_if = InboxPolicy::accepts_all)]
+    pub inbox_policy: InboxPolicy,
+    /// The limits on the block proposals of the chain, enforced by validators.
+    #[debug(skip_if = BlockLimits::is_unlimited)]
+    pub block_limits: BlockLimits,
+    /// How much of the chain's history its owners ask validators to keep.
+    #[debug(skip_if = RetentionPolicy::is_full_history)]
+    pub retention_policy: RetentionPolicy,
 }
 
 impl ChainInfo {
@@ -284,6 +310,10 @@ where
             requested_sent_certificate_hashes: Vec::new(),
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            requested_equivocation_evidence: Vec::new(),
+            inbox_policy: system_state.inbox_policy.get().clone(),
+            block_limits: *system_state.block_limits.get(),
+            retention_policy: *system_state.retention_policy.get(),
         }
     }
 }
@@ -295,6 +325,19 @@ impl ChainInfoResponse {
         Self { info, signature }
     }
 
+    /// Creates a response signed with the validator's keys, if any.
+    pub async fn signed(
+        info: impl Into<ChainInfo>,
+        keys: Option<&ValidatorKeys>,
+    ) -> Result<Self, ChainError> {
+        let info = Box::new(info.into());
+        let signature = match keys {
+            Some(keys) => Some(keys.sign(&*info).await?),
+            None => None,
+        };
+        Ok(Self { info, signature })
+    }
+
     /// Signs the [`ChainInfo`] stored inside this [`ChainInfoResponse`] with the provided
     /// [`ValidatorSecretKey`].
     pub fn sign(&mut self, key_pair: &ValidatorSecretKey) {
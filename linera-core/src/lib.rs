@@ -7,6 +7,7 @@
 #![recursion_limit = "256"]
 #![deny(clippy::large_futures)]
 
+pub mod blob_gossip;
 pub mod chain_worker;
 pub mod client;
 pub mod data_types;
@@ -14,10 +15,14 @@ pub mod join_set_ext;
 pub mod local_node;
 pub mod node;
 pub mod notifier;
+pub mod progress;
+pub mod rate_limiter;
 pub mod remote_node;
+pub mod replay;
 #[cfg(with_testing)]
 #[path = "unit_tests/test_utils.rs"]
 pub mod test_utils;
+pub mod validator_health;
 pub mod worker;
 
 pub(crate) mod updater;
@@ -14,7 +14,7 @@ use linera_base::{
     identifiers::{ApplicationId, BlobId, ChainId},
 };
 use linera_chain::{
-    data_types::{BlockProposal, ProposedBlock},
+    data_types::{BlockExecutionResources, BlockProposal, ProposedBlock},
     types::{Block, ConfirmedBlockCertificate, GenericCertificate, LiteCertificate},
     ChainStateView,
 };
@@ -185,6 +185,22 @@ where
             .await?)
     }
 
+    /// Like [`LocalNodeClient::stage_block_execution`], but also returns the resources used
+    /// by the block and the fees it was charged.
+    #[instrument(level = "trace", skip_all)]
+    pub async fn stage_block_execution_with_resources(
+        &self,
+        block: ProposedBlock,
+        round: Option<u32>,
+        published_blobs: Vec<Blob>,
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), LocalNodeError> {
+        Ok(self
+            .node
+            .state
+            .stage_block_execution_with_resources(block, round, published_blobs)
+            .await?)
+    }
+
     /// Reads blobs from storage.
     pub async fn read_blobs_from_storage(
         &self,
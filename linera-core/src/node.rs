@@ -9,7 +9,7 @@ use futures::stream::LocalBoxStream as BoxStream;
 use futures::stream::Stream;
 use linera_base::{
     crypto::{CryptoError, CryptoHash, ValidatorPublicKey},
-    data_types::{ArithmeticError, BlobContent, BlockHeight},
+    data_types::{ArithmeticError, BlobContent, BlockHeight, TimeDelta},
     identifiers::{BlobId, ChainId},
 };
 use linera_chain::{
@@ -29,6 +29,7 @@ use thiserror::Error;
 
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
+    notifier::NotificationFilter,
     worker::{Notification, WorkerError},
 };
 
@@ -43,6 +44,16 @@ pub enum CrossChainMessageDelivery {
     Blocking,
 }
 
+/// The optional features supported by a validator node.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeFeatures {
+    /// Whether the node is an archive node, which keeps the full history of every chain and
+    /// never prunes block bodies.
+    pub archive: bool,
+    /// If set, the node only keeps the bodies of this many latest blocks of each chain.
+    pub block_body_retention: Option<u64>,
+}
+
 /// How to communicate with a validator node.
 #[allow(async_fn_in_trait)]
 #[cfg_attr(not(web), trait_variant::make(Send))]
@@ -96,9 +107,20 @@ pub trait ValidatorNode {
     /// Gets the network's description.
     async fn get_network_description(&self) -> Result<NetworkDescription, NodeError>;
 
+    /// Gets the optional features supported by this validator node.
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError>;
+
     /// Subscribes to receiving notifications for a collection of chains.
     async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError>;
 
+    /// Subscribes to receiving the notifications accepted by `filter` for a collection of
+    /// chains.
+    async fn subscribe_with_filter(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError>;
+
     // Uploads a blob. Returns an error if the validator has not seen a
     // certificate using this blob.
     async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError>;
@@ -106,6 +128,10 @@ pub trait ValidatorNode {
     /// Downloads a blob. Returns an error if the validator does not have the blob.
     async fn download_blob(&self, blob_id: BlobId) -> Result<BlobContent, NodeError>;
 
+    /// Downloads a batch of blobs, in the same order. Returns an error if the validator does
+    /// not have one of them.
+    async fn download_blobs(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError>;
+
     /// Downloads a blob that belongs to a pending proposal or the locking block on a chain.
     async fn download_pending_blob(
         &self,
@@ -260,6 +286,28 @@ pub enum NodeError {
     EmptyBlobsNotFound,
     #[error("Local error handling validator response")]
     ResponseHandlingError { error: String },
+
+    #[error(
+        "The validator is throttling block proposals on chain {chain_id}; retry in {} ms",
+        retry_after.as_duration().as_millis()
+    )]
+    ProposalThrottled {
+        chain_id: ChainId,
+        retry_after: TimeDelta,
+    },
+}
+
+impl NodeError {
+    /// Returns whether the error means that the validator could not be reached, rather than
+    /// that it rejected the request.
+    pub fn is_connectivity_error(&self) -> bool {
+        matches!(
+            self,
+            NodeError::GrpcError { .. }
+                | NodeError::ClientIoError { .. }
+                | NodeError::CannotResolveValidatorAddress { .. }
+        )
+    }
 }
 
 impl From<tonic::Status> for NodeError {
@@ -350,6 +398,18 @@ impl From<WorkerError> for NodeError {
             WorkerError::ChainError(error) => (*error).into(),
             WorkerError::MissingCertificateValue => Self::MissingCertificateValue,
             WorkerError::BlobsNotFound(blob_ids) => Self::BlobsNotFound(blob_ids),
+            WorkerError::ChainProposalsThrottled {
+                chain_id,
+                retry_after,
+            }
+            | WorkerError::ProposalRateLimited {
+                chain_id,
+                retry_after,
+                ..
+            } => Self::ProposalThrottled {
+                chain_id,
+                retry_after,
+            },
             error => Self::WorkerError {
                 error: error.to_string(),
             },
@@ -1,14 +1,53 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
 use dashmap::DashMap;
-use linera_base::identifiers::ChainId;
+use linera_base::identifiers::{ApplicationId, ChainId, StreamId};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tracing::trace;
 
-use crate::worker;
+use crate::worker::{self, Reason};
+
+/// Restricts the notifications that a client receives about the chains it subscribed to.
+///
+/// An empty filter accepts all the notifications. Otherwise, only the notifications about new
+/// blocks that emitted events to one of the `event_streams`, or that involve one of the
+/// `application_ids`, are accepted.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NotificationFilter {
+    /// The event streams of interest.
+    pub event_streams: BTreeSet<StreamId>,
+    /// The applications of interest.
+    pub application_ids: BTreeSet<ApplicationId>,
+}
+
+impl NotificationFilter {
+    /// Returns whether this filter accepts all the notifications.
+    pub fn is_empty(&self) -> bool {
+        self.event_streams.is_empty() && self.application_ids.is_empty()
+    }
+
+    /// Returns whether a notification with the given `reason` is accepted by this filter.
+    pub fn matches(&self, reason: &Reason) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        match reason {
+            Reason::NewBlock {
+                event_streams,
+                application_ids,
+                ..
+            } => {
+                !self.event_streams.is_disjoint(event_streams)
+                    || !self.application_ids.is_disjoint(application_ids)
+            }
+            Reason::NewIncomingBundle { .. } | Reason::NewRound { .. } => false,
+        }
+    }
+}
 
 // TODO(#2171): replace this with a Tokio broadcast channel
 
@@ -16,7 +55,13 @@ use crate::worker;
 /// from the validator.
 /// Clients will be evicted if their connections are terminated.
 pub struct ChannelNotifier<N> {
-    inner: DashMap<ChainId, Vec<UnboundedSender<N>>>,
+    inner: DashMap<ChainId, Vec<Subscriber<N>>>,
+}
+
+/// A client waiting to receive notifications, with the filter it subscribed with.
+struct Subscriber<N> {
+    sender: UnboundedSender<N>,
+    filter: Arc<NotificationFilter>,
 }
 
 impl<N> Default for ChannelNotifier<N> {
@@ -28,25 +73,50 @@ impl<N> Default for ChannelNotifier<N> {
 }
 
 impl<N> ChannelNotifier<N> {
-    fn add_sender(&self, chain_ids: Vec<ChainId>, sender: &UnboundedSender<N>) {
+    fn add_sender(
+        &self,
+        chain_ids: Vec<ChainId>,
+        sender: &UnboundedSender<N>,
+        filter: NotificationFilter,
+    ) {
+        let filter = Arc::new(filter);
         for id in chain_ids {
-            let mut senders = self.inner.entry(id).or_default();
-            senders.push(sender.clone());
+            let mut subscribers = self.inner.entry(id).or_default();
+            subscribers.push(Subscriber {
+                sender: sender.clone(),
+                filter: filter.clone(),
+            });
         }
     }
 
     /// Creates a subscription given a collection of chain IDs and a sender to the client.
     pub fn subscribe(&self, chain_ids: Vec<ChainId>) -> UnboundedReceiver<N> {
+        self.subscribe_with_filter(chain_ids, NotificationFilter::default())
+    }
+
+    /// Creates a subscription to the notifications accepted by `filter`, given a collection
+    /// of chain IDs and a sender to the client.
+    pub fn subscribe_with_filter(
+        &self,
+        chain_ids: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        self.add_sender(chain_ids, &tx);
+        self.add_sender(chain_ids, &tx, filter);
         rx
     }
 
-    /// Creates a subscription given a collection of chain IDs and a sender to the client.
+    /// Creates a subscription to the notifications accepted by `filter`, given a collection
+    /// of chain IDs and a sender to the client.
     /// Immediately posts a first notification as an ACK.
-    pub fn subscribe_with_ack(&self, chain_ids: Vec<ChainId>, ack: N) -> UnboundedReceiver<N> {
+    pub fn subscribe_with_ack(
+        &self,
+        chain_ids: Vec<ChainId>,
+        filter: NotificationFilter,
+        ack: N,
+    ) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        self.add_sender(chain_ids, &tx);
+        self.add_sender(chain_ids, &tx, filter);
         tx.send(ack)
             .expect("pushing to a new channel should succeed");
         rx
@@ -57,31 +127,35 @@ impl<N> ChannelNotifier<N>
 where
     N: Clone,
 {
-    /// Notifies all the clients waiting for a notification from a given chain.
-    pub fn notify_chain(&self, chain_id: &ChainId, notification: &N) {
-        let senders_is_empty = {
-            let Some(mut senders) = self.inner.get_mut(chain_id) else {
+    /// Notifies all the clients waiting for a notification from a given chain, if their
+    /// filter accepts the `reason` of the notification.
+    pub fn notify_chain(&self, chain_id: &ChainId, reason: &Reason, notification: &N) {
+        let subscribers_is_empty = {
+            let Some(mut subscribers) = self.inner.get_mut(chain_id) else {
                 trace!("Chain {chain_id:?} has no subscribers.");
                 return;
             };
-            let mut dead_senders = vec![];
-            let senders = senders.value_mut();
-
-            for (index, sender) in senders.iter_mut().enumerate() {
-                if sender.send(notification.clone()).is_err() {
-                    dead_senders.push(index);
+            let mut dead_subscribers = vec![];
+            let subscribers = subscribers.value_mut();
+
+            for (index, subscriber) in subscribers.iter_mut().enumerate() {
+                if subscriber.sender.is_closed()
+                    || (subscriber.filter.matches(reason)
+                        && subscriber.sender.send(notification.clone()).is_err())
+                {
+                    dead_subscribers.push(index);
                 }
             }
 
-            for index in dead_senders.into_iter().rev() {
+            for index in dead_subscribers.into_iter().rev() {
                 trace!("Removed dead subscriber for chain {chain_id:?}.");
-                senders.remove(index);
+                subscribers.remove(index);
             }
 
-            senders.is_empty()
+            subscribers.is_empty()
         };
 
-        if senders_is_empty {
+        if subscribers_is_empty {
             trace!("No more subscribers for chain {chain_id:?}. Removing entry.");
             self.inner.remove(chain_id);
         }
@@ -95,7 +169,7 @@ pub trait Notifier: Clone + Send + 'static {
 impl Notifier for Arc<ChannelNotifier<worker::Notification>> {
     fn notify(&self, notifications: &[worker::Notification]) {
         for notification in notifications {
-            self.notify_chain(&notification.chain_id, notification);
+            self.notify_chain(&notification.chain_id, &notification.reason, notification);
         }
     }
 }
@@ -119,10 +193,22 @@ pub mod tests {
         time::Duration,
     };
 
+    use linera_base::{
+        crypto::CryptoHash,
+        data_types::{BlockHeight, Round},
+        identifiers::StreamName,
+    };
     use linera_execution::test_utils::dummy_chain_description;
 
     use super::*;
 
+    fn new_round() -> Reason {
+        Reason::NewRound {
+            height: BlockHeight::ZERO,
+            round: Round::Fast,
+        }
+    }
+
     #[test]
     fn test_concurrent() {
         let notifier = ChannelNotifier::default();
@@ -168,13 +254,13 @@ pub mod tests {
         let a_notifier = notifier.clone();
         let handle_a = std::thread::spawn(move || {
             for _ in 0..NOTIFICATIONS_A {
-                a_notifier.notify_chain(&chain_a, &());
+                a_notifier.notify_chain(&chain_a, &new_round(), &());
             }
         });
 
         let handle_b = std::thread::spawn(move || {
             for _ in 0..NOTIFICATIONS_B {
-                notifier.notify_chain(&chain_b, &());
+                notifier.notify_chain(&chain_b, &new_round(), &());
             }
         });
 
@@ -215,22 +301,79 @@ pub mod tests {
         assert_eq!(notifier.inner.len(), 4);
 
         rx_c.close();
-        notifier.notify_chain(&chain_c, &());
+        notifier.notify_chain(&chain_c, &new_round(), &());
         assert_eq!(notifier.inner.len(), 3);
 
         rx_a.close();
-        notifier.notify_chain(&chain_a, &());
+        notifier.notify_chain(&chain_a, &new_round(), &());
         assert_eq!(notifier.inner.len(), 3);
 
         rx_b.close();
-        notifier.notify_chain(&chain_b, &());
+        notifier.notify_chain(&chain_b, &new_round(), &());
         assert_eq!(notifier.inner.len(), 2);
 
-        notifier.notify_chain(&chain_a, &());
+        notifier.notify_chain(&chain_a, &new_round(), &());
         assert_eq!(notifier.inner.len(), 1);
 
         rx_d.close();
-        notifier.notify_chain(&chain_d, &());
+        notifier.notify_chain(&chain_d, &new_round(), &());
         assert_eq!(notifier.inner.len(), 0);
     }
+
+    #[test]
+    fn test_filters() {
+        let notifier = ChannelNotifier::default();
+        let chain_id = dummy_chain_description(0).id();
+        let application_id = ApplicationId::new(CryptoHash::test_hash("application"));
+        let stream_id = StreamId {
+            application_id: application_id.into(),
+            stream_name: StreamName(b"stream".to_vec()),
+        };
+        let new_block =
+            |event_streams: &[StreamId], application_ids: &[ApplicationId]| Reason::NewBlock {
+                height: BlockHeight::ZERO,
+                hash: CryptoHash::test_hash("block"),
+                event_streams: event_streams.iter().cloned().collect(),
+                application_ids: application_ids.iter().copied().collect(),
+            };
+
+        let mut rx_all = notifier.subscribe(vec![chain_id]);
+        let mut rx_stream = notifier.subscribe_with_filter(
+            vec![chain_id],
+            NotificationFilter {
+                event_streams: [stream_id.clone()].into(),
+                ..NotificationFilter::default()
+            },
+        );
+        let mut rx_application = notifier.subscribe_with_filter(
+            vec![chain_id],
+            NotificationFilter {
+                application_ids: [application_id].into(),
+                ..NotificationFilter::default()
+            },
+        );
+
+        notifier.notify_chain(&chain_id, &new_round(), &0);
+        notifier.notify_chain(&chain_id, &new_block(&[], &[]), &1);
+        notifier.notify_chain(&chain_id, &new_block(&[], &[application_id]), &2);
+        notifier.notify_chain(
+            &chain_id,
+            &new_block(&[stream_id.clone()], &[application_id]),
+            &3,
+        );
+
+        let received = |rx: &mut UnboundedReceiver<u8>| {
+            std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>()
+        };
+        assert_eq!(received(&mut rx_all), vec![0, 1, 2, 3]);
+        assert_eq!(received(&mut rx_stream), vec![3]);
+        assert_eq!(received(&mut rx_application), vec![2, 3]);
+
+        // Subscribers that filter out a notification are still evicted once they are gone.
+        rx_all.close();
+        rx_stream.close();
+        rx_application.close();
+        notifier.notify_chain(&chain_id, &new_round(), &4);
+        assert!(notifier.inner.is_empty());
+    }
 }
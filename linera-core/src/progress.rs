@@ -0,0 +1,119 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Progress reports of the long-running operations of a client, e.g. to display progress
+//! bars instead of waiting silently.
+
+use std::sync::Arc;
+
+use linera_base::identifiers::ChainId;
+use serde::Serialize;
+
+/// A long-running operation of a client.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressTask {
+    /// Downloading and processing the missing blocks of a chain.
+    SynchronizeChain { chain_id: ChainId },
+    /// Publishing blobs, e.g. the bytecode of a module, in a block of a chain.
+    PublishBlobs { chain_id: ChainId },
+    /// Proposing blocks as part of a benchmark.
+    Benchmark,
+    /// Copying the keys of a storage namespace to another storage.
+    CopyNamespace { namespace: String },
+}
+
+/// What the progress of a [`ProgressTask`] is measured in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressUnit {
+    Blocks,
+    Bytes,
+    Keys,
+}
+
+/// A change in the progress of a [`ProgressTask`].
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// The task started. The total amount of work is `None` if it is unknown.
+    Started {
+        task: ProgressTask,
+        total: Option<u64>,
+        unit: ProgressUnit,
+    },
+    /// The given amount of work of the task is done.
+    Advanced { task: ProgressTask, done: u64 },
+    /// The task finished, successfully or not.
+    Finished { task: ProgressTask },
+}
+
+/// Receives the progress of the long-running operations of a client.
+pub trait ProgressReporter: Send + Sync {
+    /// Handles a change in the progress of a task. This must not block.
+    fn report(&self, event: ProgressEvent);
+}
+
+/// A [`ProgressReporter`] ignoring all events.
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+/// A task in progress, reported as finished when dropped.
+pub struct Progress {
+    reporter: Arc<dyn ProgressReporter>,
+    task: ProgressTask,
+    /// The position the task started from, subtracted from the positions it advances to.
+    start: u64,
+    /// The position the task stops at, if known.
+    stop: Option<u64>,
+}
+
+impl Progress {
+    /// Reports the start of a task advancing from `start` to `stop`, or indefinitely if
+    /// `stop` is `None`.
+    pub fn start(
+        reporter: Arc<dyn ProgressReporter>,
+        task: ProgressTask,
+        unit: ProgressUnit,
+        start: u64,
+        stop: Option<u64>,
+    ) -> Self {
+        reporter.report(ProgressEvent::Started {
+            task: task.clone(),
+            total: stop.map(|stop| stop.saturating_sub(start)),
+            unit,
+        });
+        Progress {
+            reporter,
+            task,
+            start,
+            stop,
+        }
+    }
+
+    /// Reports that the task advanced to the given position.
+    pub fn advance_to(&self, position: u64) {
+        self.reporter.report(ProgressEvent::Advanced {
+            task: self.task.clone(),
+            done: position.saturating_sub(self.start),
+        });
+    }
+
+    /// Reports that the task advanced to its stop position, if known, and finished.
+    pub fn complete(self) {
+        if let Some(stop) = self.stop {
+            self.advance_to(stop);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.reporter.report(ProgressEvent::Finished {
+            task: self.task.clone(),
+        });
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limits for the requests handled by a worker or a service.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    num::{NonZeroU32, NonZeroUsize},
+    sync::{Arc, Mutex},
+};
+
+use linera_base::data_types::{TimeDelta, Timestamp};
+use lru::LruCache;
+
+/// The maximal rate of events attributed to a single key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// The number of events allowed per second, on average.
+    pub events_per_second: NonZeroU32,
+    /// The number of events allowed in a burst, before the average rate applies.
+    pub burst: NonZeroU32,
+}
+
+impl RateLimit {
+    /// Returns the time between two events at the average rate.
+    fn interval(&self) -> TimeDelta {
+        TimeDelta::from_micros(1_000_000 / u64::from(self.events_per_second.get()))
+    }
+}
+
+/// Limits the rate of events per key, using the generic cell rate algorithm.
+///
+/// Only the most recently used keys are tracked: the limit of a key that is evicted starts
+/// over.
+pub struct RateLimiter<K: Hash + Eq> {
+    limit: RateLimit,
+    /// For each key, the time at which the next event would arrive at the average rate.
+    arrival_times: Mutex<LruCache<K, Timestamp>>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    /// Creates a new [`RateLimiter`] tracking up to `capacity` keys.
+    pub fn new(limit: RateLimit, capacity: NonZeroUsize) -> Self {
+        RateLimiter {
+            limit,
+            arrival_times: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Records an event for `key` at time `now`, unless that would exceed the limit. In that
+    /// case, returns how long to wait before the event would be allowed.
+    pub fn check(&self, key: K, now: Timestamp) -> Result<(), TimeDelta> {
+        let mut arrival_times = self.arrival_times();
+        let arrival_time = self.allowed_arrival_time(&mut arrival_times, &key, now)?;
+        arrival_times.put(key, arrival_time.saturating_add(self.limit.interval()));
+        Ok(())
+    }
+
+    /// Records an event for `key` at time `now`, even if it exceeds the limit, e.g. once the
+    /// event was allowed by [`Self::retry_after`] and turned out to be legitimate.
+    pub fn record(&self, key: K, now: Timestamp) {
+        let mut arrival_times = self.arrival_times();
+        let arrival_time = arrival_times.get(&key).copied().unwrap_or(now).max(now);
+        arrival_times.put(key, arrival_time.saturating_add(self.limit.interval()));
+    }
+
+    /// Returns how long to wait before an event for `key` would be allowed at time `now`, if
+    /// it isn't allowed right away, without recording an event.
+    pub fn retry_after(&self, key: &K, now: Timestamp) -> Option<TimeDelta> {
+        self.allowed_arrival_time(&mut self.arrival_times(), key, now)
+            .err()
+    }
+
+    /// Returns the arrival time of an event for `key` at time `now` at the average rate, or
+    /// how long to wait if the event would exceed the limit.
+    fn allowed_arrival_time(
+        &self,
+        arrival_times: &mut LruCache<K, Timestamp>,
+        key: &K,
+        now: Timestamp,
+    ) -> Result<Timestamp, TimeDelta> {
+        let tolerance = TimeDelta::from_micros(
+            self.limit
+                .interval()
+                .as_micros()
+                .saturating_mul(u64::from(self.limit.burst.get() - 1)),
+        );
+        let arrival_time = arrival_times.get(key).copied().unwrap_or(now).max(now);
+        let earliest_time = arrival_time.saturating_sub(tolerance);
+        if earliest_time > now {
+            return Err(earliest_time.delta_since(now));
+        }
+        Ok(arrival_time)
+    }
+
+    fn arrival_times(&self) -> std::sync::MutexGuard<'_, LruCache<K, Timestamp>> {
+        self.arrival_times
+            .lock()
+            .expect("Panics should not happen while holding a lock to the arrival times")
+    }
+}
+
+/// Limits the number of events in progress per key.
+pub(crate) struct ConcurrencyLimiter<K: Hash + Eq> {
+    limit: NonZeroUsize,
+    /// The number of events in progress for each key that has any.
+    counts: Mutex<HashMap<K, usize>>,
+}
+
+/// A permit for an event in progress, released when dropped.
+pub(crate) struct ConcurrencyPermit<K: Hash + Eq + Clone> {
+    limiter: Arc<ConcurrencyLimiter<K>>,
+    key: K,
+}
+
+impl<K: Hash + Eq + Clone> ConcurrencyLimiter<K> {
+    /// Creates a new [`ConcurrencyLimiter`] allowing `limit` events in progress per key.
+    pub(crate) fn new(limit: NonZeroUsize) -> Self {
+        ConcurrencyLimiter {
+            limit,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Returns a permit for a new event for `key`, unless the limit of events in progress
+    /// would be exceeded.
+    pub(crate) fn try_acquire(self: &Arc<Self>, key: K) -> Option<ConcurrencyPermit<K>> {
+        let mut counts = self.counts();
+        let count = counts.entry(key.clone()).or_default();
+        if *count >= self.limit.get() {
+            return None;
+        }
+        *count += 1;
+        Some(ConcurrencyPermit {
+            limiter: self.clone(),
+            key,
+        })
+    }
+
+    fn counts(&self) -> std::sync::MutexGuard<'_, HashMap<K, usize>> {
+        self.counts
+            .lock()
+            .expect("Panics should not happen while holding a lock to the counts")
+    }
+}
+
+impl<K: Hash + Eq + Clone> Drop for ConcurrencyPermit<K> {
+    fn drop(&mut self) {
+        let mut counts = self.limiter.counts();
+        if let Some(count) = counts.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::{NonZeroU32, NonZeroUsize},
+        sync::Arc,
+    };
+
+    use linera_base::data_types::{TimeDelta, Timestamp};
+
+    use super::{ConcurrencyLimiter, RateLimit, RateLimiter};
+
+    fn limiter(events_per_second: u32, burst: u32) -> RateLimiter<u8> {
+        let limit = RateLimit {
+            events_per_second: NonZeroU32::new(events_per_second).unwrap(),
+            burst: NonZeroU32::new(burst).unwrap(),
+        };
+        RateLimiter::new(limit, NonZeroUsize::new(2).unwrap())
+    }
+
+    #[test]
+    fn test_burst_then_average_rate() {
+        let limiter = limiter(10, 3);
+        let now = Timestamp::from(1_000_000);
+        // Asking when an event would be allowed doesn't record one.
+        for _ in 0..5 {
+            assert_eq!(limiter.retry_after(&0, now), None);
+        }
+        for _ in 0..3 {
+            assert_eq!(limiter.check(0, now), Ok(()));
+        }
+        assert_eq!(limiter.check(0, now), Err(TimeDelta::from_micros(100_000)));
+        assert_eq!(
+            limiter.retry_after(&0, now),
+            Some(TimeDelta::from_micros(100_000))
+        );
+        // Other keys are not affected.
+        assert_eq!(limiter.check(1, now), Ok(()));
+        // Recorded events count even beyond the limit.
+        limiter.record(1, now);
+        limiter.record(1, now);
+        assert_eq!(
+            limiter.retry_after(&1, now),
+            Some(TimeDelta::from_micros(100_000))
+        );
+
+        let later = now.saturating_add(TimeDelta::from_micros(100_000));
+        assert_eq!(limiter.check(0, later), Ok(()));
+        assert!(limiter.check(0, later).is_err());
+    }
+
+    #[test]
+    fn test_evicted_keys_start_over() {
+        let limiter = limiter(1, 1);
+        let now = Timestamp::from(0);
+        assert_eq!(limiter.check(0, now), Ok(()));
+        assert!(limiter.check(0, now).is_err());
+        assert_eq!(limiter.check(1, now), Ok(()));
+        assert_eq!(limiter.check(2, now), Ok(()));
+        assert_eq!(limiter.check(0, now), Ok(()));
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(NonZeroUsize::new(2).unwrap()));
+        let first = limiter.try_acquire(0).unwrap();
+        let _second = limiter.try_acquire(0).unwrap();
+        assert!(limiter.try_acquire(0).is_none());
+        // Other keys are not affected.
+        assert!(limiter.try_acquire(1).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(0).is_some());
+    }
+}
@@ -7,7 +7,7 @@ use custom_debug_derive::Debug;
 use futures::{future::try_join_all, stream::FuturesUnordered, StreamExt};
 use linera_base::{
     crypto::{CryptoHash, ValidatorPublicKey},
-    data_types::{Blob, BlockHeight},
+    data_types::Blob,
     ensure,
     identifiers::{BlobId, ChainId},
 };
@@ -161,40 +161,6 @@ impl<N: ValidatorNode> RemoteNode<N> {
         Ok(response.info)
     }
 
-    #[instrument(level = "trace", skip_all)]
-    pub(crate) async fn try_query_certificates_from(
-        &self,
-        chain_id: ChainId,
-        start: BlockHeight,
-        limit: u64,
-    ) -> Result<Option<Vec<ConfirmedBlockCertificate>>, NodeError> {
-        tracing::debug!(name = ?self.public_key, ?chain_id, ?start, ?limit, "Querying certificates");
-        let range = BlockHeightRange {
-            start,
-            limit: Some(limit),
-        };
-        let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
-        match self.handle_chain_info_query(query).await {
-            Ok(info) => {
-                let certificates = self
-                    .node
-                    .download_certificates(info.requested_sent_certificate_hashes)
-                    .await?
-                    .into_iter()
-                    .map(|c| {
-                        ConfirmedBlockCertificate::try_from(c)
-                            .map_err(|_| NodeError::InvalidChainInfoResponse)
-                    })
-                    .collect::<Result<_, _>>()?;
-                Ok(Some(certificates))
-            }
-            Err(error) => {
-                tracing::warn!("Failed to query certificates: {error}");
-                Ok(None)
-            }
-        }
-    }
-
     #[instrument(level = "trace")]
     pub(crate) async fn download_certificate_for_blob(
         &self,
@@ -239,13 +205,39 @@ impl<N: ValidatorNode> RemoteNode<N> {
     /// Tries to download the given blobs from this node. Returns `None` if not all could be found.
     #[instrument(level = "trace")]
     pub(crate) async fn try_download_blobs(&self, blob_ids: &[BlobId]) -> Option<Vec<Blob>> {
-        let mut stream = blob_ids
-            .iter()
-            .map(|blob_id| self.try_download_blob(*blob_id))
-            .collect::<FuturesUnordered<_>>();
-        let mut blobs = Vec::new();
-        while let Some(maybe_blob) = stream.next().await {
-            blobs.push(maybe_blob?);
+        if blob_ids.is_empty() {
+            return Some(Vec::new());
+        }
+        let contents = match self.node.download_blobs(blob_ids.to_vec()).await {
+            Ok(contents) => contents,
+            Err(error) => {
+                tracing::debug!(
+                    "Failed to fetch blobs {blob_ids:?} from validator {}: {error}",
+                    self.public_key
+                );
+                return None;
+            }
+        };
+        if contents.len() != blob_ids.len() {
+            tracing::info!(
+                "Validator {} sent {} blobs instead of {}.",
+                self.public_key,
+                contents.len(),
+                blob_ids.len()
+            );
+            return None;
+        }
+        let mut blobs = Vec::with_capacity(contents.len());
+        for (blob_id, content) in blob_ids.iter().zip(contents) {
+            let blob = Blob::new(content);
+            if blob.id() != *blob_id {
+                tracing::info!(
+                    "Validator {} sent an invalid blob {blob_id}.",
+                    self.public_key
+                );
+                return None;
+            }
+            blobs.push(blob);
         }
         Some(blobs)
     }
@@ -0,0 +1,342 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Re-executing the confirmed blocks of a chain, to find nondeterministic executions.
+//!
+//! The certificates of a chain are read from a validator's storage and executed again, in
+//! order, against a separate scratch storage that starts from the chain's description. The
+//! outcome of each block, and in particular its execution state hash, is compared to the
+//! one that the validators signed. This shows whether an upgrade of the validator changed
+//! the execution of past blocks, or whether some contract executes nondeterministically.
+//!
+//! The replay stops at the first block whose outcome differs, since the later blocks would
+//! be executed on top of a diverged state.
+
+use std::{collections::BTreeMap, fmt};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{BlockHeight, OracleResponse},
+    identifiers::{BlobId, BlobType, ChainId},
+};
+use linera_chain::{
+    data_types::BlockExecutionOutcome, types::ConfirmedBlockCertificate, ChainError, ChainStateView,
+};
+use linera_storage::{Clock as _, Storage};
+use linera_views::views::{RootView as _, View as _, ViewError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// An error when replaying the blocks of a chain.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
+    #[error(transparent)]
+    ChainError(#[from] Box<ChainError>),
+    #[error("Chain {chain_id} only has {next_block_height} blocks, cannot replay it from {from}")]
+    HeightNotFound {
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+        from: BlockHeight,
+    },
+    #[error("The scratch storage already has blocks of chain {0}")]
+    ScratchNotEmpty(ChainId),
+    #[error("The blobs required by block {height} are missing: {blob_ids:?}")]
+    BlobsNotFound {
+        height: BlockHeight,
+        blob_ids: Vec<BlobId>,
+    },
+    #[error("Failed to execute block {height} again: {error}")]
+    Execution {
+        height: BlockHeight,
+        error: Box<ChainError>,
+    },
+}
+
+impl From<ChainError> for ReplayError {
+    fn from(error: ChainError) -> Self {
+        ReplayError::ChainError(Box::new(error))
+    }
+}
+
+/// A part of a block's execution outcome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomePart {
+    StateHash,
+    Messages,
+    PreviousMessageBlocks,
+    OracleResponses,
+    Events,
+    Blobs,
+    OperationResults,
+}
+
+impl fmt::Display for OutcomePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutcomePart::StateHash => "state hash",
+            OutcomePart::Messages => "messages",
+            OutcomePart::PreviousMessageBlocks => "previous message blocks",
+            OutcomePart::OracleResponses => "oracle responses",
+            OutcomePart::Events => "events",
+            OutcomePart::Blobs => "blobs",
+            OutcomePart::OperationResults => "operation results",
+        };
+        f.write_str(name)
+    }
+}
+
+impl OutcomePart {
+    /// Returns the parts in which the two outcomes differ.
+    fn differences(
+        stored: &BlockExecutionOutcome,
+        replayed: &BlockExecutionOutcome,
+    ) -> Vec<OutcomePart> {
+        [
+            (
+                OutcomePart::StateHash,
+                stored.state_hash == replayed.state_hash,
+            ),
+            (OutcomePart::Messages, stored.messages == replayed.messages),
+            (
+                OutcomePart::PreviousMessageBlocks,
+                stored.previous_message_blocks == replayed.previous_message_blocks,
+            ),
+            (
+                OutcomePart::OracleResponses,
+                stored.oracle_responses == replayed.oracle_responses,
+            ),
+            (OutcomePart::Events, stored.events == replayed.events),
+            (OutcomePart::Blobs, stored.blobs == replayed.blobs),
+            (
+                OutcomePart::OperationResults,
+                stored.operation_results == replayed.operation_results,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(part, equal)| (!equal).then_some(part))
+        .collect()
+    }
+}
+
+/// The result of executing a confirmed block again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayedBlock {
+    pub height: BlockHeight,
+    /// The hash of the block's certificate.
+    pub hash: CryptoHash,
+    /// The execution state hash that the validators signed.
+    pub state_hash: CryptoHash,
+    /// The execution state hash after executing the block again.
+    pub replayed_state_hash: CryptoHash,
+    /// The parts of the outcome that differ from the signed one.
+    pub differences: Vec<OutcomePart>,
+}
+
+impl ReplayedBlock {
+    /// Returns whether executing the block again had the same outcome.
+    pub fn matches(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// The result of replaying the blocks of a chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub chain_id: ChainId,
+    /// The replayed blocks from the requested height, and any earlier block whose outcome
+    /// differs. If a block differs, it is the last one.
+    pub blocks: Vec<ReplayedBlock>,
+}
+
+impl ReplayReport {
+    /// Returns the first block whose outcome differs, if any.
+    pub fn first_difference(&self) -> Option<&ReplayedBlock> {
+        self.blocks.iter().find(|block| !block.matches())
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Replayed {} blocks of chain {}",
+            self.blocks.len(),
+            self.chain_id
+        )?;
+        for block in &self.blocks {
+            if block.matches() {
+                writeln!(f, "  {} {}: ok", block.height, block.hash)?;
+            } else {
+                let differences = block
+                    .differences
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(
+                    f,
+                    "  {} {}: differs in {differences} (signed state hash {}, replayed {})",
+                    block.height, block.hash, block.state_hash, block.replayed_state_hash
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Executes the confirmed blocks of the chain from `storage` again in `scratch`, and
+/// compares their outcomes from height `from` on.
+///
+/// The earlier blocks are executed too, to recreate the chain's state. The scratch storage
+/// must not have any blocks of the chain yet. The bodies of all the chain's blocks must
+/// still be in `storage`.
+pub async fn replay_chain<S, T>(
+    storage: &S,
+    scratch: &T,
+    chain_id: ChainId,
+    from: BlockHeight,
+) -> Result<ReplayReport, ReplayError>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    T: Storage + Clone + Send + Sync + 'static,
+{
+    let hashes = storage
+        .load_chain(chain_id)
+        .await?
+        .confirmed_log
+        .read(..)
+        .await?;
+    let next_block_height = BlockHeight(hashes.len() as u64);
+    if from > next_block_height {
+        return Err(ReplayError::HeightNotFound {
+            chain_id,
+            next_block_height,
+            from,
+        });
+    }
+
+    let description_id = BlobId::new(chain_id.0, BlobType::ChainDescription);
+    let description = storage.read_blob(description_id).await?;
+    scratch.maybe_write_blobs(&[description]).await?;
+    let mut chain = scratch.load_chain(chain_id).await?;
+    if chain.tip_state.get().next_block_height > BlockHeight::ZERO {
+        return Err(ReplayError::ScratchNotEmpty(chain_id));
+    }
+    chain
+        .ensure_is_active(scratch.clock().current_time())
+        .await?;
+
+    let mut report = ReplayReport {
+        chain_id,
+        blocks: Vec::new(),
+    };
+    for hash in hashes {
+        let certificate = storage.read_certificate(hash).await?;
+        let replayed = Box::pin(replay_block(storage, scratch, &mut chain, &certificate)).await?;
+        let matches = replayed.matches();
+        if replayed.height >= from || !matches {
+            report.blocks.push(replayed);
+        }
+        if !matches {
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// Executes a single confirmed block in the scratch chain state, and applies it if the
+/// outcome is the same.
+async fn replay_block<S, T>(
+    storage: &S,
+    scratch: &T,
+    chain: &mut ChainStateView<T::Context>,
+    certificate: &ConfirmedBlockCertificate,
+) -> Result<ReplayedBlock, ReplayError>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    T: Storage + Clone + Send + Sync + 'static,
+{
+    let block = certificate.block();
+    let height = block.header.height;
+
+    // Copy the blobs and events that the block reads from the validator's storage.
+    let mut blobs = block.iter_created_blobs().collect::<BTreeMap<_, _>>();
+    let blob_ids = block
+        .required_blob_ids()
+        .into_iter()
+        .filter(|blob_id| !blobs.contains_key(blob_id))
+        .collect::<Vec<_>>();
+    let mut missing_blob_ids = Vec::new();
+    for (blob_id, maybe_blob) in blob_ids.iter().zip(storage.read_blobs(&blob_ids).await?) {
+        match maybe_blob {
+            Some(blob) => {
+                blobs.insert(*blob_id, blob);
+            }
+            None => missing_blob_ids.push(*blob_id),
+        }
+    }
+    if !missing_blob_ids.is_empty() {
+        return Err(ReplayError::BlobsNotFound {
+            height,
+            blob_ids: missing_blob_ids,
+        });
+    }
+    scratch
+        .maybe_write_blobs(&blobs.values().cloned().collect::<Vec<_>>())
+        .await?;
+    let events = block
+        .body
+        .oracle_responses
+        .iter()
+        .flatten()
+        .filter_map(|response| match response {
+            OracleResponse::Event(event_id, bytes) => Some((event_id.clone(), bytes.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    scratch.write_events(events).await?;
+    let published_blobs = block
+        .published_blob_ids()
+        .iter()
+        .filter_map(|blob_id| blobs.remove(blob_id))
+        .collect::<Vec<_>>();
+
+    // Execute the block the way the worker does for confirmed blocks.
+    let local_time = scratch.clock().current_time();
+    chain
+        .remove_bundles_from_inboxes(block.header.timestamp, &block.body.incoming_bundles)
+        .await?;
+    let oracle_responses = Some(block.body.oracle_responses.clone());
+    let (proposed_block, outcome) = block.clone().into_proposal();
+    let replayed_outcome = Box::pin(chain.execute_block(
+        &proposed_block,
+        local_time,
+        None,
+        &published_blobs,
+        oracle_responses,
+    ))
+    .await
+    .map_err(|error| ReplayError::Execution {
+        height,
+        error: Box::new(error),
+    })?;
+    let differences = OutcomePart::differences(&outcome, &replayed_outcome);
+    if differences.is_empty() {
+        chain
+            .apply_confirmed_block(certificate.value(), local_time)
+            .await?;
+        chain.save().await?;
+    } else {
+        chain.rollback();
+    }
+    Ok(ReplayedBlock {
+        height,
+        hash: certificate.hash(),
+        state_hash: outcome.state_hash,
+        replayed_state_hash: replayed_outcome.state_hash,
+        differences,
+    })
+}
@@ -13,6 +13,7 @@ use linera_base::{
     data_types::*,
     identifiers::{Account, AccountOwner, ApplicationId},
     ownership::{ChainOwnership, TimeoutConfig},
+    time::{Duration, Instant},
 };
 use linera_chain::{
     data_types::{IncomingBundle, MessageBundle, PostedMessage},
@@ -22,7 +23,7 @@ use linera_chain::{
 };
 use linera_execution::{
     committee::Committee,
-    system::{Recipient, SystemOperation},
+    system::{OpenChainConfig, Recipient, SystemOperation},
     ExecutionError, Message, MessageKind, Operation, QueryOutcome, ResourceControlPolicy,
     SystemMessage, SystemQuery, SystemResponse,
 };
@@ -42,6 +43,7 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 #[cfg(feature = "storage-service")]
 use crate::test_utils::ServiceStorageBuilder;
 use crate::{
+    blob_gossip::BlobGossip,
     client::{
         BlanketMessagePolicy, ChainClient, ChainClientError, ClientOutcome, MessageAction,
         MessagePolicy,
@@ -52,8 +54,10 @@ use crate::{
         NodeError::{self, ClientIoError},
         ValidatorNode,
     },
+    replay::{replay_chain, ReplayError},
     test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder},
     updater::CommunicationError,
+    validator_health::CommunicationPolicy,
     worker::{Notification, Reason, WorkerError},
     Environment,
 };
@@ -301,6 +305,146 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_offline_signed_proposal<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer)
+        .await?
+        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let chain_2 = builder.add_root_chain(2, Amount::ZERO).await?;
+    let operation = SystemOperation::Transfer {
+        owner: AccountOwner::CHAIN,
+        recipient: Recipient::chain(chain_2.chain_id()),
+        amount: Amount::from_tokens(3),
+    };
+    let (proposal, blobs) = sender
+        .unsigned_proposal(vec![operation.into()], vec![])
+        .await?
+        .unwrap()
+        .expect("there should be a block to propose");
+    assert_eq!(proposal.content.block.height, BlockHeight::ZERO);
+    assert!(sender.pending_proposal().is_some());
+
+    // Proposals with an invalid signature are rejected before contacting the validators.
+    let other_key = AccountSecretKey::generate();
+    let forged = proposal.clone().with_signature(
+        other_key.public(),
+        other_key.sign_prehash(proposal.signing_hash()),
+    );
+    assert!(sender
+        .submit_signed_proposal(forged, blobs.clone())
+        .await
+        .is_err());
+
+    let signed = proposal.sign(sender.signer()).await.unwrap();
+    let certificate = sender.submit_signed_proposal(signed, blobs).await?;
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.local_balance().await?, Amount::from_millis(999));
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_dry_run<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer)
+        .await?
+        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let chain_2 = builder.add_root_chain(2, Amount::ZERO).await?;
+    let operation = SystemOperation::Transfer {
+        owner: AccountOwner::CHAIN,
+        recipient: Recipient::chain(chain_2.chain_id()),
+        amount: Amount::from_tokens(3),
+    };
+    let outcome = sender
+        .dry_run(vec![operation.clone().into()], vec![])
+        .await?;
+    assert_eq!(outcome.block.header.height, BlockHeight::ZERO);
+    assert_eq!(outcome.resources.tracker.fees, Amount::from_millis(1));
+    assert_eq!(outcome.resources.transaction_fees, vec![Amount::ZERO]);
+    assert_eq!(outcome.chain_balance_before, Amount::from_tokens(4));
+    assert_eq!(outcome.chain_balance_after, Amount::from_millis(999));
+    assert_eq!(outcome.block.body.messages[0].len(), 1);
+
+    // Nothing was proposed or persisted.
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+
+    // A dry run fails like the actual execution would.
+    let operation = SystemOperation::Transfer {
+        owner: AccountOwner::CHAIN,
+        recipient: Recipient::chain(chain_2.chain_id()),
+        amount: Amount::from_tokens(5),
+    };
+    assert!(sender
+        .dry_run(vec![operation.into()], vec![])
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_estimate_fees<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer)
+        .await?
+        .with_policy(ResourceControlPolicy::all_categories());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let chain_2 = builder.add_root_chain(2, Amount::ZERO).await?;
+    let transfer = |amount| {
+        Operation::system(SystemOperation::Transfer {
+            owner: AccountOwner::CHAIN,
+            recipient: Recipient::chain(chain_2.chain_id()),
+            amount,
+        })
+    };
+    let operations = vec![transfer(Amount::from_tokens(1)), transfer(Amount::ONE)];
+    let estimate = sender.estimate_fees(operations.clone()).await?;
+    assert_eq!(estimate.operations.len(), 2);
+    // Both operations have the same size and send one message each.
+    assert_eq!(estimate.operations[0], estimate.operations[1]);
+    assert!(estimate.operations[0] > Amount::ZERO);
+    assert_eq!(estimate.incoming_bundles, Amount::ZERO);
+    let operation_fees = estimate.operations[0].try_mul(2)?;
+    assert_eq!(estimate.total, operation_fees.try_add(estimate.block)?);
+
+    // The estimate matches what is actually charged.
+    sender.execute_operations(operations, vec![]).await?;
+    assert_eq!(
+        sender.local_balance().await?,
+        Amount::from_tokens(2).try_sub(estimate.total)?
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -503,6 +647,50 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chains_in_one_block<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let public_keys = (0..3).map(|_| signer.generate_new()).collect::<Vec<_>>();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let configs = public_keys
+        .iter()
+        .map(|public_key| OpenChainConfig {
+            ownership: ChainOwnership::single((*public_key).into()),
+            balance: Amount::ONE,
+            application_permissions: ApplicationPermissions::default(),
+        })
+        .collect();
+    let (descriptions, certificate) = sender.open_chains(configs).await?.unwrap();
+
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert_eq!(sender.local_balance().await?, Amount::ONE);
+    assert_eq!(descriptions.len(), 3);
+    // The descriptions are in the order of the configurations.
+    for (description, public_key) in descriptions.iter().zip(&public_keys) {
+        let ownership = ChainOwnership::single((*public_key).into());
+        assert_eq!(description.config().ownership, ownership);
+        assert_eq!(description.config().balance, Amount::ONE);
+    }
+    let new_id = descriptions[2].id();
+    let mut client = builder.make_client(new_id, None, BlockHeight::ZERO).await?;
+    client.set_preferred_owner(public_keys[2].into());
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await?;
+    assert_eq!(client.query_balance().await?, Amount::ONE);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -2225,6 +2413,299 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_message_batching_and_approval<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let mut receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+
+    // Bundles are only received in batches of two.
+    receiver.options_mut().message_policy =
+        MessagePolicy::new(BlanketMessagePolicy::Accept, None).with_batching(Some(2), None);
+    let cert = sender
+        .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert!(receiver.process_inbox().await?.0.is_empty());
+    assert_eq!(receiver.inbox_batch_deadline().await?, None);
+    let cert = sender
+        .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(certificates[0].block().body.incoming_bundles.len(), 2);
+
+    // Transfers of more than one token wait for an approval.
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None)
+        .with_approval_threshold(Some(Amount::ONE));
+    let cert = sender
+        .transfer(AccountOwner::CHAIN, Amount::from_tokens(2), recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert!(receiver.process_inbox().await?.0.is_empty());
+    let awaiting_approval = receiver.incoming_bundles_awaiting_approval().await?;
+    assert_eq!(awaiting_approval.len(), 1);
+    let bundle = &awaiting_approval[0];
+    assert_eq!(bundle.origin, sender.chain_id());
+    receiver.approve_incoming_bundle(bundle.origin, bundle.bundle.height);
+    assert_eq!(receiver.process_inbox().await?.0.len(), 1);
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(4));
+    assert!(receiver
+        .incoming_bundles_awaiting_approval()
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_inbox_policy<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let sender1 = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let sender2 = builder.add_root_chain(2, Amount::from_tokens(10)).await?;
+    let receiver = builder.add_root_chain(3, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+
+    // Only accept bundles from chain 1 that credit at least 2 tokens.
+    let inbox_policy = InboxPolicy {
+        allowed_senders: Some(vec![sender1.chain_id()]),
+        allowed_applications: None,
+        min_attached_value: Amount::from_tokens(2),
+        ..InboxPolicy::default()
+    };
+    receiver
+        .change_inbox_policy(inbox_policy.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    let info = receiver.chain_info().await?;
+    assert_eq!(info.inbox_policy, inbox_policy);
+
+    for (sender, tokens) in [(&sender2, 3), (&sender1, 2)] {
+        let cert = sender
+            .transfer(AccountOwner::CHAIN, Amount::from_tokens(tokens), recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    // Only the transfer of 2 tokens from chain 1 is received.
+    assert_eq!(receiver.process_inbox().await?.0.len(), 1);
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
+
+    // A transfer of 1 token from chain 1 is not accepted, and holds back the next one.
+    for tokens in [1, 5] {
+        let cert = sender1
+            .transfer(AccountOwner::CHAIN, Amount::from_tokens(tokens), recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    assert!(receiver.process_inbox().await?.0.is_empty());
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
+
+    // Once all bundles are accepted, the remaining ones are received.
+    receiver
+        .change_inbox_policy(InboxPolicy::default())
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(11));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_inbox_policy_block_limits<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+
+    // Receive at most two bundles per block.
+    let inbox_policy = InboxPolicy {
+        max_bundles_per_block: Some(2),
+        ..InboxPolicy::default()
+    };
+    receiver
+        .change_inbox_policy(inbox_policy.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(receiver.chain_info().await?.inbox_policy, inbox_policy);
+
+    for _ in 0..3 {
+        let cert = sender
+            .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    // The three bundles are received in two blocks.
+    let (certificates, _) = receiver.process_inbox().await?;
+    let bundle_counts = certificates
+        .iter()
+        .map(|certificate| certificate.block().body.incoming_bundles.len())
+        .collect::<Vec<_>>();
+    assert_eq!(bundle_counts, vec![2, 1]);
+
+    // A limit of one byte per block leaves all bundles in the inbox.
+    receiver
+        .change_inbox_policy(InboxPolicy {
+            max_bundle_bytes_per_block: Some(1),
+            ..InboxPolicy::default()
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let cert = sender
+        .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert!(receiver.process_inbox().await?.0.is_empty());
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_block_limits<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+
+    let block_limits = BlockLimits {
+        max_operations_per_block: Some(1),
+        max_incoming_bundles_per_block: Some(2),
+        ..BlockLimits::default()
+    };
+    receiver
+        .change_block_limits(block_limits)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(receiver.chain_info().await?.block_limits, block_limits);
+
+    for _ in 0..3 {
+        let cert = sender
+            .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    // The client receives the three bundles in two blocks, within the limit.
+    let (certificates, _) = receiver.process_inbox().await?;
+    let bundle_counts = certificates
+        .iter()
+        .map(|certificate| certificate.block().body.incoming_bundles.len())
+        .collect::<Vec<_>>();
+    assert_eq!(bundle_counts, vec![2, 1]);
+
+    // Blocks with more operations are rejected.
+    let transfer = Operation::system(SystemOperation::Transfer {
+        owner: AccountOwner::CHAIN,
+        recipient: Recipient::chain(sender.chain_id()),
+        amount: Amount::ONE,
+    });
+    assert_matches!(
+        receiver
+            .execute_operations(vec![transfer.clone(), transfer], vec![])
+            .await,
+        Err(ChainClientError::LocalNodeError(
+            LocalNodeError::WorkerError(WorkerError::ChainError(chain_error))
+        )) if matches!(
+            *chain_error,
+            ChainError::BlockLimitExceeded { what: "operations", limit: 1 }
+        )
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_retention_policy<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let client = builder.add_root_chain(1, Amount::ONE).await?;
+    assert_eq!(
+        client.chain_info().await?.retention_policy,
+        RetentionPolicy::FullHistory
+    );
+
+    for retention_policy in [
+        RetentionPolicy::LastBlocks(2),
+        RetentionPolicy::HeadersOnly,
+        RetentionPolicy::FullHistory,
+    ] {
+        client
+            .change_retention_policy(retention_policy)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            client.chain_info().await?.retention_policy,
+            retention_policy
+        );
+    }
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -2366,3 +2847,197 @@ where
     assert_eq!(client.local_balance().await.unwrap(), expected_balance);
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_unreachable_validator_is_excluded<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 0, &mut signer)
+        .await?
+        .with_communication_policy(CommunicationPolicy {
+            max_consecutive_failures: 2,
+            ..CommunicationPolicy::default()
+        });
+    let client = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let validator = builder.node(3).name();
+    let health = client.client.validator_health();
+
+    // Validator 3 fails to respond to the first operations, and is then excluded.
+    builder.set_fault_type([3], FaultType::Offline).await;
+    for _ in 0..3 {
+        client
+            .burn(AccountOwner::CHAIN, Amount::ONE)
+            .await?
+            .unwrap();
+    }
+    assert!(health.is_excluded(&validator, Instant::now()));
+
+    // If another validator goes offline, the excluded one is needed for a quorum again.
+    builder.set_fault_type([3], FaultType::Honest).await;
+    builder.set_fault_type([2], FaultType::Offline).await;
+    client
+        .burn(AccountOwner::CHAIN, Amount::ONE)
+        .await?
+        .unwrap();
+    assert!(!health.is_excluded(&validator, Instant::now()));
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(6));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_fastest_quorum_falls_back_to_other_validators<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 0, &mut signer)
+        .await?
+        .with_communication_policy(CommunicationPolicy {
+            max_consecutive_failures: 0,
+            fastest_quorum_delay: Some(Duration::from_secs(60)),
+            ..CommunicationPolicy::default()
+        });
+    let client = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let validator = builder.node(2).name();
+    let health = client.client.validator_health();
+
+    // Validators without any recorded responses are contacted first, so validator 2 is
+    // contacted in one of the operations. It fails, so the deferred validator is contacted
+    // right away instead of after the delay.
+    builder.set_fault_type([2], FaultType::Offline).await;
+    for _ in 0..2 {
+        client
+            .burn(AccountOwner::CHAIN, Amount::ONE)
+            .await?
+            .unwrap();
+    }
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(8));
+
+    // It is now ranked last.
+    let mut reports = health.reports();
+    health.sort_by_expected_latency(&mut reports, |(public_key, _)| public_key);
+    let (last, report) = reports.last().unwrap();
+    assert_eq!(*last, validator);
+    assert!(report.consecutive_failures > 0);
+    assert_eq!(report.expected_latency(), None);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_download_pending_blobs_from_peers<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 0, &mut signer).await?;
+    let client = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let chain_id = client.chain_id();
+    let blob_bytes = b"blob".to_vec();
+    let blob_id = Blob::new(BlobContent::new_data(blob_bytes.clone())).id();
+    client.publish_data_blob(blob_bytes).await?.unwrap();
+
+    let gossip = BlobGossip::new(builder.make_node_provider(), builder.node(0).name());
+    let committee = builder.initial_committee.clone();
+    let unknown_blob_id = Blob::new(BlobContent::new_data(b"unknown".to_vec())).id();
+    // At least one of the validators 2 and 3 has the blob.
+    builder.set_fault_type([1], FaultType::Offline).await;
+    let blobs = gossip
+        .download_pending_blobs(&committee, chain_id, &[blob_id, unknown_blob_id])
+        .await;
+    assert_eq!(
+        blobs.iter().map(Blob::id).collect::<Vec<_>>(),
+        vec![blob_id]
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[test_log::test(tokio::test)]
+async fn test_replay_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut signer).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    for _ in 0..2 {
+        let certificate = sender
+            .transfer_to_account(
+                AccountOwner::CHAIN,
+                Amount::ONE,
+                Account::chain(receiver.chain_id()),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(certificate)
+            .await?;
+    }
+    sender.publish_data_blob(b"blob".to_vec()).await?.unwrap();
+    assert_eq!(receiver.process_inbox().await?.0.len(), 1);
+
+    // The earlier blocks are executed, but only the requested ones are reported.
+    let scratch = builder.make_storage().await?;
+    let report = Box::pin(replay_chain(
+        sender.storage_client(),
+        &scratch,
+        sender.chain_id(),
+        BlockHeight(1),
+    ))
+    .await?;
+    assert_eq!(
+        report
+            .blocks
+            .iter()
+            .map(|block| block.height)
+            .collect::<Vec<_>>(),
+        vec![BlockHeight(1), BlockHeight(2)]
+    );
+    assert!(report.first_difference().is_none());
+    assert_matches!(
+        Box::pin(replay_chain(
+            sender.storage_client(),
+            &scratch,
+            sender.chain_id(),
+            BlockHeight(0),
+        ))
+        .await,
+        Err(ReplayError::ScratchNotEmpty(_))
+    );
+
+    // Blocks with incoming messages are replayed without the sender's chain.
+    let report = Box::pin(replay_chain(
+        receiver.storage_client(),
+        &scratch,
+        receiver.chain_id(),
+        BlockHeight(0),
+    ))
+    .await?;
+    assert_eq!(report.blocks.len(), 1);
+    assert!(report.blocks[0].matches());
+    assert_matches!(
+        Box::pin(replay_chain(
+            receiver.storage_client(),
+            &builder.make_storage().await?,
+            receiver.chain_id(),
+            BlockHeight(2),
+        ))
+        .await,
+        Err(ReplayError::HeightNotFound { .. })
+    );
+    Ok(())
+}
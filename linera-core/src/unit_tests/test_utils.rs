@@ -11,7 +11,7 @@ use std::{
 
 use async_trait::async_trait;
 use futures::{
-    future::Either,
+    future::{self, Either},
     lock::{Mutex, MutexGuard},
     Future,
 };
@@ -52,11 +52,12 @@ use crate::{
     client::Client,
     data_types::*,
     node::{
-        CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
+        CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
-    notifier::ChannelNotifier,
+    notifier::{ChannelNotifier, NotificationFilter},
     updater::DEFAULT_GRACE_PERIOD,
+    validator_health::CommunicationPolicy,
     worker::{NetworkActions, Notification, ProcessableCertificate, WorkerState},
 };
 
@@ -165,10 +166,21 @@ where
     }
 
     async fn subscribe(&self, chains: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
-        self.spawn_and_receive(move |validator, sender| validator.do_subscribe(chains, sender))
+        self.subscribe_with_filter(chains, NotificationFilter::default())
             .await
     }
 
+    async fn subscribe_with_filter(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<NotificationStream, NodeError> {
+        self.spawn_and_receive(move |validator, sender| {
+            validator.do_subscribe(chains, filter, sender)
+        })
+        .await
+    }
+
     async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
         Ok(Default::default())
     }
@@ -181,6 +193,10 @@ where
         })
     }
 
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError> {
+        Ok(NodeFeatures::default())
+    }
+
     async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
         self.spawn_and_receive(move |validator, sender| validator.do_upload_blob(content, sender))
             .await
@@ -191,6 +207,15 @@ where
             .await
     }
 
+    async fn download_blobs(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError> {
+        future::try_join_all(
+            blob_ids
+                .into_iter()
+                .map(|blob_id| self.download_blob(blob_id)),
+        )
+        .await
+    }
+
     async fn download_pending_blob(
         &self,
         chain_id: ChainId,
@@ -465,10 +490,11 @@ where
     async fn do_subscribe(
         self,
         chains: Vec<ChainId>,
+        filter: NotificationFilter,
         sender: oneshot::Sender<Result<NotificationStream, NodeError>>,
     ) -> Result<(), Result<NotificationStream, NodeError>> {
         let validator = self.client.lock().await;
-        let rx = validator.notifier.subscribe(chains);
+        let rx = validator.notifier.subscribe_with_filter(chains, filter);
         let stream: NotificationStream = Box::pin(UnboundedReceiverStream::new(rx));
         sender.send(Ok(stream))
     }
@@ -669,6 +695,7 @@ pub struct TestBuilder<'a, B: StorageBuilder> {
     chain_client_storages: Vec<B::Storage>,
     pub chain_owners: BTreeMap<ChainId, AccountOwner>,
     pub signer: &'a mut InMemorySigner,
+    communication_policy: CommunicationPolicy,
 }
 
 #[async_trait]
@@ -771,6 +798,11 @@ where
             chain_client_storages: Vec::new(),
             chain_owners: BTreeMap::new(),
             signer,
+            // Most tests expect all validators to be contacted again once they are back.
+            communication_policy: CommunicationPolicy {
+                max_consecutive_failures: 0,
+                ..CommunicationPolicy::default()
+            },
         })
     }
 
@@ -780,6 +812,11 @@ where
         self
     }
 
+    pub fn with_communication_policy(mut self, policy: CommunicationPolicy) -> Self {
+        self.communication_policy = policy;
+        self
+    }
+
     pub async fn set_fault_type(&mut self, indexes: impl AsRef<[usize]>, fault_type: FaultType) {
         let mut faulty_validators = vec![];
         for index in indexes.as_ref() {
@@ -926,6 +963,8 @@ where
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            NonZeroUsize::new(4).expect("Certificate download parallelism should not be zero"),
+            self.communication_policy,
         ));
         Ok(builder
             .create_chain_client(
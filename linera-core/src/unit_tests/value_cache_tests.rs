@@ -1,7 +1,11 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{borrow::Cow, collections::BTreeSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    num::NonZeroUsize,
+};
 
 use linera_base::{
     crypto::CryptoHash,
@@ -264,6 +268,30 @@ fn test_filtering_out_cached_items() {
     );
 }
 
+/// Tests that a cache created with a custom size keeps at most that many values, and
+/// retrieves the cached ones among many keys.
+#[test]
+fn test_custom_size_and_retrieval_of_many_values() {
+    let cache = ValueCache::<CryptoHash, Hashed<Timeout>>::new(NonZeroUsize::new(3).unwrap());
+    let values = create_dummy_certificate_values(0..5).collect::<Vec<_>>();
+    for value in &values {
+        cache.insert_owned(&value.hash(), value.clone());
+    }
+    assert_eq!(cache.len(), 3);
+
+    let hashes = values.iter().map(|value| value.hash()).collect::<Vec<_>>();
+    let (found, missing): (HashMap<_, _>, Vec<_>) = cache.try_get_many(hashes.clone());
+    assert_eq!(missing, hashes[..2]);
+    assert_eq!(
+        found,
+        HashMap::from_iter(
+            values[2..]
+                .iter()
+                .map(|value| (value.hash(), value.clone()))
+        )
+    );
+}
+
 /// Creates multiple dummy [`Hashed<Timeout>`]s to use in the tests.
 fn create_dummy_certificate_values<Heights>(
     heights: Heights,
@@ -10,7 +10,7 @@ mod wasm;
 use std::{
     collections::{BTreeMap, BTreeSet},
     iter,
-    num::NonZeroUsize,
+    num::{NonZeroU32, NonZeroUsize},
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -50,12 +50,12 @@ use linera_execution::{
     ExecutionError, Message, MessageKind, OutgoingMessage, Query, QueryContext, QueryOutcome,
     QueryResponse, SystemQuery, SystemResponse,
 };
-use linera_storage::{DbStorage, Storage, TestClock};
+use linera_storage::{DbStorage, MemoryArchiveStore, Storage, TestClock, WritePressureConfig};
 use linera_views::{
     memory::MemoryStore,
     random::generate_test_namespace,
     store::TestKeyValueStore as _,
-    views::{CryptoHashView, RootView},
+    views::{CryptoHashView, RootView, ViewError},
 };
 use test_case::test_case;
 use test_log::test;
@@ -69,9 +69,11 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 use crate::{
     chain_worker::CrossChainUpdateHelper,
     data_types::*,
+    node::NodeError,
+    rate_limiter::RateLimit,
     test_utils::{MemoryStorageBuilder, StorageBuilder},
     worker::{
-        Notification,
+        ChainArchival, Notification,
         Reason::{self, NewBlock, NewIncomingBundle},
         WorkerError, WorkerState,
     },
@@ -528,6 +530,221 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_owner_rate_limit<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let sender_owner = signer.generate_new().into();
+    let mut env = TestEnvironment::new(storage_builder.build().await?, false, false).await;
+    env.worker = env.worker.clone().with_proposal_rate_limit(Some(RateLimit {
+        events_per_second: NonZeroU32::new(1).unwrap(),
+        burst: NonZeroU32::new(1).unwrap(),
+    }));
+    let chain_1 = env
+        .add_root_chain(1, sender_owner, Amount::from_tokens(5))
+        .await
+        .id();
+    let block_proposal = make_first_block(chain_1)
+        .into_first_proposal(sender_owner, &signer)
+        .await
+        .unwrap();
+
+    // Proposals forged in the owner's name don't use up the owner's rate limit.
+    let mut forged_proposal = block_proposal.clone();
+    forged_proposal.signature = AccountSecretKey::generate().sign(&block_proposal.content);
+    for _ in 0..3 {
+        assert_matches!(
+            env.worker()
+                .handle_block_proposal(forged_proposal.clone())
+                .await,
+            Err(WorkerError::CryptoError(_))
+        );
+    }
+    env.worker()
+        .handle_block_proposal(block_proposal.clone())
+        .await?;
+
+    // The owner's next proposal is rejected until the clock advances, and clients are told
+    // when to retry.
+    let error = env
+        .worker()
+        .handle_block_proposal(block_proposal)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        &error,
+        WorkerError::ProposalRateLimited { chain_id, owner, .. }
+            if *chain_id == chain_1 && *owner == sender_owner
+    );
+    assert_matches!(
+        NodeError::from(error),
+        NodeError::ProposalThrottled { chain_id, .. } if chain_id == chain_1
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_chain_rate_limit<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let sender_owner = signer.generate_new().into();
+    let mut env = TestEnvironment::new(storage_builder.build().await?, false, false).await;
+    env.worker = env
+        .worker
+        .clone()
+        .with_chain_proposal_rate_limit(Some(RateLimit {
+            events_per_second: NonZeroU32::new(1).unwrap(),
+            burst: NonZeroU32::new(1).unwrap(),
+        }));
+    let chain_1 = env
+        .add_root_chain(1, sender_owner, Amount::from_tokens(5))
+        .await
+        .id();
+    let block_proposal = make_first_block(chain_1)
+        .into_first_proposal(sender_owner, &signer)
+        .await
+        .unwrap();
+
+    // Proposals from keys that may not propose don't use up the chain's rate limit.
+    let stranger = signer.generate_new().into();
+    let unauthorized_proposal = make_first_block(chain_1)
+        .into_first_proposal(stranger, &signer)
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        assert_matches!(
+            env.worker()
+                .handle_block_proposal(unauthorized_proposal.clone())
+                .await,
+            Err(WorkerError::InvalidOwner)
+        );
+    }
+    env.worker()
+        .handle_block_proposal(block_proposal.clone())
+        .await?;
+
+    let error = env
+        .worker()
+        .handle_block_proposal(block_proposal)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        &error,
+        WorkerError::ChainProposalsThrottled { chain_id, .. } if *chain_id == chain_1
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_open_chain_rate_limit<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let owner = signer.generate_new().into();
+    let mut env = TestEnvironment::new(storage_builder.build().await?, false, false).await;
+    env.worker = env.worker.clone().with_proposal_rate_limit(Some(RateLimit {
+        events_per_second: NonZeroU32::new(1).unwrap(),
+        burst: NonZeroU32::new(1).unwrap(),
+    }));
+    let chain_id = env
+        .add_root_chain(1, owner, Amount::from_tokens(2))
+        .await
+        .id();
+    let change_ownership_block =
+        make_first_block(chain_id).with_operation(SystemOperation::ChangeOwnership {
+            super_owners: vec![],
+            owners: vec![(owner, 100)],
+            multi_leader_rounds: 2,
+            open_multi_leader_rounds: true,
+            timeout_config: TimeoutConfig::default(),
+        });
+    let (change_ownership_block, _) = env
+        .worker()
+        .stage_block_execution(change_ownership_block, None, vec![])
+        .await?;
+    let change_ownership_value = ConfirmedBlock::new(change_ownership_block);
+    let change_ownership_certificate = env.make_certificate(change_ownership_value.clone());
+    env.worker()
+        .fully_handle_certificate_with_notifications(change_ownership_certificate, &())
+        .await?;
+
+    // Proposers who don't own the chain share a limit, so fresh keys don't bypass it.
+    let stranger = signer.generate_new().into();
+    let proposal = make_child_block(&change_ownership_value)
+        .into_proposal_with_round(stranger, &signer, Round::MultiLeader(0))
+        .await
+        .unwrap();
+    env.worker().handle_block_proposal(proposal).await?;
+    let other_stranger = signer.generate_new().into();
+    let proposal = make_child_block(&change_ownership_value)
+        .into_proposal_with_round(other_stranger, &signer, Round::MultiLeader(0))
+        .await
+        .unwrap();
+    assert_matches!(
+        env.worker().handle_block_proposal(proposal).await,
+        Err(WorkerError::ProposalRateLimited { chain_id: id, owner, .. })
+            if id == chain_id && owner == other_stranger
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_write_pressure() -> anyhow::Result<()> {
+    let mut signer = InMemorySigner::new(None);
+    let sender_owner = signer.generate_new().into();
+    // Any completed write saturates the storage.
+    let storage = MemoryStorageBuilder::default()
+        .build()
+        .await?
+        .with_write_pressure_config(WritePressureConfig {
+            max_pending_writes: 256,
+            saturated_write_latency: Duration::from_nanos(1),
+        });
+    let mut env = TestEnvironment::new(storage, false, false).await;
+    let chain_1 = env
+        .add_root_chain(1, sender_owner, Amount::from_tokens(5))
+        .await
+        .id();
+    let block_proposal = make_first_block(chain_1)
+        .into_first_proposal(sender_owner, &signer)
+        .await
+        .unwrap();
+
+    env.worker = env.worker.clone().with_max_write_pressure(Some(0.5));
+    let error = env
+        .worker()
+        .handle_block_proposal(block_proposal.clone())
+        .await
+        .unwrap_err();
+    assert_matches!(
+        &error,
+        WorkerError::ChainProposalsThrottled { chain_id, .. } if *chain_id == chain_1
+    );
+    assert_matches!(
+        NodeError::from(error),
+        NodeError::ProposalThrottled { chain_id, .. } if chain_id == chain_1
+    );
+
+    // Without a limit, the same proposal is accepted.
+    env.worker = env.worker.clone().with_max_write_pressure(None);
+    env.worker().handle_block_proposal(block_proposal).await?;
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -900,6 +1117,8 @@ where
                 reason: NewBlock {
                     height: BlockHeight(0),
                     hash: certificate0.hash(),
+                    event_streams: BTreeSet::new(),
+                    application_ids: BTreeSet::new(),
                 }
             },
             Notification {
@@ -914,6 +1133,8 @@ where
                 reason: NewBlock {
                     height: BlockHeight(1),
                     hash: certificate1.hash(),
+                    event_streams: BTreeSet::new(),
+                    application_ids: BTreeSet::new(),
                 }
             },
             Notification {
@@ -2008,6 +2229,69 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_pending_cross_chain_requests_are_resumed<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let sender_key_pair = AccountSecretKey::generate();
+    let storage = storage_builder.build().await?;
+    let mut env = TestEnvironment::new(storage.clone(), false, false).await;
+    let chain_1_desc = env
+        .add_root_chain(1, sender_key_pair.public().into(), Amount::from_tokens(5))
+        .await;
+    let chain_2_desc = env
+        .add_root_chain(2, AccountPublicKey::test_key(2).into(), Amount::ZERO)
+        .await;
+    let chain_1 = chain_1_desc.id();
+    let chain_2 = chain_2_desc.id();
+
+    let certificate = env
+        .make_simple_transfer_certificate(
+            chain_1_desc,
+            sender_key_pair.public(),
+            chain_2,
+            Amount::from_tokens(5),
+            Vec::new(),
+            Amount::ZERO,
+            None,
+        )
+        .await;
+    // Process the block but drop its cross-chain requests, as if the validator had stopped.
+    let (_, actions) = env
+        .worker()
+        .handle_confirmed_certificate(certificate, None)
+        .await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+    assert_eq!(storage.read_pending_deliveries().await?, vec![chain_1]);
+
+    let actions = env
+        .worker()
+        .pending_cross_chain_requests(|chain_id| chain_id != chain_1)
+        .await?;
+    assert!(actions.cross_chain_requests.is_empty());
+    let actions = env.worker().pending_cross_chain_requests(|_| true).await?;
+    assert_eq!(actions.cross_chain_requests.len(), 1);
+
+    // Once the messages are delivered and confirmed, the chain is no longer pending.
+    for request in actions.cross_chain_requests {
+        let actions = env.worker().handle_cross_chain_request(request).await?;
+        for request in actions.cross_chain_requests {
+            env.worker().handle_cross_chain_request(request).await?;
+        }
+    }
+    assert!(storage.read_pending_deliveries().await?.is_empty());
+    let chain = env.worker().chain_state_view(chain_2).await?;
+    assert_eq!(chain.received_log.count(), 1);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -3344,6 +3628,67 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_read_pruned_block<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut signer = InMemorySigner::new(None);
+    let owner = signer.generate_new().into();
+    let mut env = TestEnvironment::new(storage_builder.build().await?, false, false).await;
+    env.worker = env.worker.clone().with_block_body_retention(Some(1));
+    let chain_id = env
+        .add_root_chain(1, owner, Amount::from_tokens(2))
+        .await
+        .id();
+    let (block, _) = env
+        .worker()
+        .stage_block_execution(make_first_block(chain_id), None, vec![])
+        .await?;
+    let first_value = ConfirmedBlock::new(block);
+    let first_certificate = env.make_certificate(first_value.clone());
+    env.worker()
+        .fully_handle_certificate_with_notifications(first_certificate.clone(), &())
+        .await?;
+    let (block, _) = env
+        .worker()
+        .stage_block_execution(make_child_block(&first_value), None, vec![])
+        .await?;
+    let second_certificate = env.make_certificate(ConfirmedBlock::new(block));
+    env.worker()
+        .fully_handle_certificate_with_notifications(second_certificate.clone(), &())
+        .await?;
+
+    // Only the latest block keeps its body. The first one keeps its header, and reading the
+    // whole block says that it was pruned.
+    let storage = env.worker().storage_client().clone();
+    let hash = first_certificate.hash();
+    assert_eq!(
+        storage.read_block_header(hash).await?,
+        first_certificate.block().header
+    );
+    assert_matches!(
+        storage.read_confirmed_block(hash).await,
+        Err(ViewError::BlockBodyPruned(pruned)) if pruned == hash
+    );
+    assert_matches!(
+        storage.read_certificate(hash).await,
+        Err(ViewError::BlockBodyPruned(pruned)) if pruned == hash
+    );
+    assert_matches!(
+        storage
+            .read_certificates([second_certificate.hash(), hash])
+            .await,
+        Err(ViewError::BlockBodyPruned(pruned)) if pruned == hash
+    );
+    assert_eq!(
+        storage.read_certificate(second_certificate.hash()).await?,
+        second_certificate
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -3868,3 +4213,48 @@ where
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn test_archive_chain() -> anyhow::Result<()> {
+    let storage = MemoryStorageBuilder::default()
+        .build()
+        .await?
+        .with_archive_store(Some(Arc::new(MemoryArchiveStore::default())));
+    let mut env = TestEnvironment::new(storage, false, false).await;
+    let owner = AccountSecretKey::generate().public().into();
+    let chain_id = env
+        .add_root_chain(1, owner, Amount::from_tokens(5))
+        .await
+        .id();
+
+    // A loaded chain can't be archived.
+    let query = ChainInfoQuery::new(chain_id);
+    env.worker().handle_chain_info_query(query.clone()).await?;
+    assert!(env.worker().is_chain_loaded(chain_id));
+    assert_matches!(
+        env.worker().archive_chain(chain_id).await,
+        Err(WorkerError::ChainLoaded(id)) if id == chain_id
+    );
+
+    env.worker().flush_caches();
+    env.worker().archive_chain(chain_id).await?;
+    let usage = env
+        .worker()
+        .storage_client()
+        .chain_storage_usage(chain_id)
+        .await?;
+    assert_eq!(usage.state_bytes, 0);
+
+    // The chain can't be loaded while it is being archived.
+    let archival = ChainArchival::start(env.worker().chains_being_archived.clone(), chain_id)?;
+    assert_matches!(
+        env.worker().handle_chain_info_query(query.clone()).await,
+        Err(WorkerError::ChainBeingArchived(id)) if id == chain_id
+    );
+    drop(archival);
+
+    // Loading the chain afterwards restores it.
+    let (response, _actions) = env.worker().handle_chain_info_query(query).await?;
+    assert_eq!(response.info.chain_balance, Amount::from_tokens(5));
+    Ok(())
+}
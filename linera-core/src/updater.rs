@@ -14,7 +14,10 @@ use futures::{stream, stream::TryStreamExt, Future, StreamExt};
 use linera_base::{
     data_types::{BlockHeight, Round},
     identifiers::{BlobId, ChainId},
-    time::{timer::timeout, Duration, Instant},
+    time::{
+        timer::{sleep, timeout},
+        Duration, Instant,
+    },
 };
 use linera_chain::{
     data_types::{BlockProposal, LiteVote},
@@ -30,6 +33,7 @@ use crate::{
     local_node::LocalNodeClient,
     node::{CrossChainMessageDelivery, NodeError, ValidatorNode},
     remote_node::RemoteNode,
+    validator_health::ValidatorHealth,
 };
 
 /// The default amount of time we wait for additional validators to contribute
@@ -38,6 +42,9 @@ pub const DEFAULT_GRACE_PERIOD: f64 = 0.2;
 /// The maximum timeout for requests to a stake-weighted quorum if no quorum is reached.
 const MAX_TIMEOUT: Duration = Duration::from_secs(60 * 60 * 24); // 1 day.
 
+/// The maximal number of times a block proposal is retried after a validator throttled it.
+const MAX_THROTTLED_PROPOSAL_RETRIES: u32 = 5;
+
 /// Used for `communicate_chain_action`
 #[derive(Clone)]
 pub enum CommunicateAction {
@@ -101,6 +108,10 @@ pub enum CommunicationError<E: fmt::Debug> {
 /// Tries to stop early when a quorum is reached. If `grace_period` is specified, other validators
 /// are given additional time to contribute to the result. The grace period is calculated as a fraction
 /// (defaulting to `DEFAULT_GRACE_PERIOD`) of the time taken to reach quorum.
+///
+/// The responses are recorded in `health`. Validators it currently excludes are only contacted
+/// if no quorum can be reached without them. If the policy sets a `fastest_quorum_delay`, only
+/// the fastest validators that form a quorum are contacted at first.
 pub async fn communicate_with_quorum<'a, A, V, K, F, R, G>(
     validator_clients: &'a [RemoteNode<A>],
     committee: &Committee,
@@ -108,6 +119,7 @@ pub async fn communicate_with_quorum<'a, A, V, K, F, R, G>(
     execute: F,
     // Grace period as a fraction of time taken to reach quorum
     grace_period: f64,
+    health: &ValidatorHealth,
 ) -> Result<(K, Vec<V>), CommunicationError<NodeError>>
 where
     A: ValidatorNode + Clone + 'static,
@@ -117,36 +129,100 @@ where
     K: Hash + PartialEq + Eq + Clone + 'static,
     V: 'static,
 {
-    let mut responses: futures::stream::FuturesUnordered<_> = validator_clients
+    let request_timeout = health.policy().request_timeout;
+    let send_request = |remote_node: &RemoteNode<A>| {
+        let execute = execute.clone();
+        let remote_node = remote_node.clone();
+        async move {
+            let public_key = remote_node.public_key;
+            let start_time = Instant::now();
+            let result = match request_timeout {
+                None => execute(remote_node).await,
+                Some(duration) => timeout(duration, execute(remote_node))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(ChainClientError::RemoteNodeError(
+                            NodeError::ClientIoError {
+                                error: format!("no response within {} ms", duration.as_millis()),
+                            },
+                        ))
+                    }),
+            };
+            (public_key, start_time.elapsed(), result)
+        }
+    };
+    let now = Instant::now();
+    let (excluded, included): (Vec<_>, Vec<_>) = validator_clients
         .iter()
-        .filter_map(|remote_node| {
-            if committee.weight(&remote_node.public_key) == 0 {
-                // This should not happen but better prevent it because certificates
-                // are not allowed to include votes with weight 0.
-                return None;
-            }
-            let execute = execute.clone();
-            let remote_node = remote_node.clone();
-            Some(async move { (remote_node.public_key, execute(remote_node).await) })
-        })
-        .collect();
+        // This should not happen but better prevent it because certificates
+        // are not allowed to include votes with weight 0.
+        .filter(|remote_node| committee.weight(&remote_node.public_key) > 0)
+        .partition(|remote_node| health.is_excluded(&remote_node.public_key, now));
+    let included_votes = included
+        .iter()
+        .map(|remote_node| committee.weight(&remote_node.public_key))
+        .sum::<u64>();
+    // Only leave out the excluded validators if the others can form a quorum.
+    let (mut excluded, mut included) = if included_votes >= committee.quorum_threshold() {
+        (excluded, included)
+    } else {
+        (Vec::new(), included.into_iter().chain(excluded).collect())
+    };
+    // If configured, defer the validators that aren't needed for the fastest quorum.
+    let fastest_quorum_delay = health.policy().fastest_quorum_delay;
+    let mut deferred = Vec::new();
+    if fastest_quorum_delay.is_some() {
+        health.sort_by_expected_latency(&mut included, |remote_node| &remote_node.public_key);
+        let mut votes = 0;
+        let fastest = included
+            .iter()
+            .take_while(|remote_node| {
+                let needed = votes < committee.quorum_threshold();
+                votes += committee.weight(&remote_node.public_key);
+                needed
+            })
+            .count();
+        deferred = included.split_off(fastest);
+    }
+    let mut responses: futures::stream::FuturesUnordered<_> =
+        included.into_iter().map(&send_request).collect();
 
     let start_time = Instant::now();
+    let hedge_time = fastest_quorum_delay.map(|delay| start_time + delay);
     let mut end_time: Option<Instant> = None;
-    let mut remaining_votes = committee.total_votes();
+    let mut remaining_votes = committee.total_votes()
+        - excluded
+            .iter()
+            .chain(&deferred)
+            .map(|remote_node| committee.weight(&remote_node.public_key))
+            .sum::<u64>();
     let mut highest_key_score = 0;
     let mut value_scores = HashMap::new();
     let mut error_scores = HashMap::new();
 
-    'vote_wait: while let Ok(Some((name, result))) = timeout(
-        end_time.map_or(MAX_TIMEOUT, |t| t.saturating_duration_since(Instant::now())),
-        responses.next(),
-    )
-    .await
-    {
+    'vote_wait: loop {
+        let deadline = end_time.or(hedge_time.filter(|_| !deferred.is_empty()));
+        let response = timeout(
+            deadline.map_or(MAX_TIMEOUT, |t| t.saturating_duration_since(Instant::now())),
+            responses.next(),
+        )
+        .await;
+        let (name, latency, result) = match response {
+            Ok(Some(response)) => response,
+            // The fastest validators didn't reach a quorum in time: contact the others too.
+            Err(_) if end_time.is_none() && !deferred.is_empty() => {
+                for remote_node in deferred.drain(..) {
+                    remaining_votes += committee.weight(&remote_node.public_key);
+                    responses.push(send_request(remote_node));
+                }
+                continue;
+            }
+            Ok(None) | Err(_) => break 'vote_wait,
+        };
         remaining_votes -= committee.weight(&name);
         match result {
             Ok(value) => {
+                health.record_success(name, latency);
                 let key = group_by(&value);
                 let entry = value_scores.entry(key.clone()).or_insert((0, Vec::new()));
                 entry.0 += committee.weight(&name);
@@ -161,6 +237,12 @@ where
                         error: err.to_string(),
                     },
                 };
+                if err.is_connectivity_error() {
+                    health.record_failure(name, Instant::now());
+                } else {
+                    // The validator responded, even if it rejected the request.
+                    health.record_success(name, latency);
+                }
                 let entry = error_scores.entry(err.clone()).or_insert(0);
                 *entry += committee.weight(&name);
                 if *entry >= committee.validity_threshold() {
@@ -170,6 +252,17 @@ where
                 }
             }
         }
+        // If it becomes clear that no key can reach a quorum without the deferred validators,
+        // fall back to them, and then to the excluded ones.
+        for fallback in [&mut deferred, &mut excluded] {
+            if highest_key_score + remaining_votes >= committee.quorum_threshold() {
+                break;
+            }
+            for remote_node in fallback.drain(..) {
+                remaining_votes += committee.weight(&remote_node.public_key);
+                responses.push(send_request(remote_node));
+            }
+        }
         // If it becomes clear that no key can reach a quorum, break early.
         if highest_key_score + remaining_votes < committee.quorum_threshold() {
             break 'vote_wait;
@@ -277,6 +370,7 @@ where
     ) -> Result<Box<ChainInfo>, ChainClientError> {
         let chain_id = proposal.content.block.chain_id;
         let mut sent_cross_chain_updates = false;
+        let mut throttled_retries = 0;
         loop {
             match self
                 .remote_node
@@ -284,6 +378,14 @@ where
                 .await
             {
                 Ok(info) => return Ok(info),
+                Err(NodeError::ProposalThrottled { retry_after, .. })
+                    if throttled_retries < MAX_THROTTLED_PROPOSAL_RETRIES =>
+                {
+                    // The validator is busy with other proposals for this chain, or from this
+                    // owner, so we wait as long as it asked us to and retry.
+                    throttled_retries += 1;
+                    sleep(retry_after.as_duration()).await;
+                }
                 Err(NodeError::MissingCrossChainUpdate { .. })
                 | Err(NodeError::InactiveChain(_))
                     if !sent_cross_chain_updates =>
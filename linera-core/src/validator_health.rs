@@ -0,0 +1,282 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The health of the validators, as seen by a client.
+//!
+//! Each validator gets a score from its recent responses. Validators that fail to respond
+//! several times in a row are excluded from requests to a quorum for a while, as long as the
+//! other validators can still form a quorum. Once the exclusion expires, the validator is
+//! included again; if it fails again right away, it is excluded for twice as long.
+//!
+//! The validators are also ranked by their expected latency, so that the client can try the
+//! fastest ones first when downloading blocks, and optionally only contact the fastest
+//! validators that form a quorum when communicating with a quorum.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use linera_base::{
+    crypto::ValidatorPublicKey,
+    time::{Duration, Instant},
+};
+
+/// The weight of the latest response in a validator's score and average latency.
+const SMOOTHING_FACTOR: f64 = 0.2;
+
+/// The lowest score used to compute the expected latency, so that validators that failed
+/// many times are still ranked by their latency.
+const MIN_RANKING_SCORE: f64 = 0.05;
+
+/// How the client communicates with the validators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommunicationPolicy {
+    /// The maximal time to wait for a single validator when communicating with a quorum. If
+    /// `None`, the client waits until the validator responds or a quorum is reached.
+    pub request_timeout: Option<Duration>,
+    /// The number of consecutive failures after which a validator is excluded. If zero,
+    /// validators are never excluded.
+    pub max_consecutive_failures: u32,
+    /// How long a validator is excluded the first time.
+    pub exclusion_duration: Duration,
+    /// The maximal time a validator is excluded, after repeated failures.
+    pub max_exclusion_duration: Duration,
+    /// Whether to upload the blobs published by a block proposal to a single validator
+    /// first. The other validators can then download them from it, if they support it.
+    pub upload_blobs_to_one_validator: bool,
+    /// If set, only the fastest validators that form a quorum are contacted at first when
+    /// communicating with a quorum. The others are contacted too if no quorum is reached
+    /// within this time, or as soon as the first ones can't form one anymore.
+    pub fastest_quorum_delay: Option<Duration>,
+}
+
+impl Default for CommunicationPolicy {
+    fn default() -> Self {
+        CommunicationPolicy {
+            request_timeout: None,
+            max_consecutive_failures: 3,
+            exclusion_duration: Duration::from_secs(30),
+            max_exclusion_duration: Duration::from_secs(10 * 60),
+            upload_blobs_to_one_validator: false,
+            fastest_quorum_delay: None,
+        }
+    }
+}
+
+/// The health of a single validator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidatorHealthReport {
+    /// The smoothed fraction of successful responses, between 0 and 1.
+    pub score: f64,
+    /// The smoothed response time of the successful responses.
+    pub average_latency: Option<Duration>,
+    /// The number of failures since the last successful response.
+    pub consecutive_failures: u32,
+    /// Until when the validator is excluded, if it is.
+    pub excluded_until: Option<Instant>,
+}
+
+impl Default for ValidatorHealthReport {
+    fn default() -> Self {
+        ValidatorHealthReport {
+            score: 1.0,
+            average_latency: None,
+            consecutive_failures: 0,
+            excluded_until: None,
+        }
+    }
+}
+
+impl ValidatorHealthReport {
+    /// Returns the expected time until the validator responds successfully: its average
+    /// latency divided by its score. This is `None` if it never responded.
+    pub fn expected_latency(&self) -> Option<Duration> {
+        let latency = self.average_latency?;
+        Some(latency.div_f64(self.score.max(MIN_RANKING_SCORE)))
+    }
+}
+
+#[derive(Default)]
+struct ValidatorState {
+    report: ValidatorHealthReport,
+    /// The number of times the validator was excluded since its last successful response.
+    exclusions: u32,
+}
+
+/// Tracks the health of the validators, following a [`CommunicationPolicy`].
+pub struct ValidatorHealth {
+    policy: CommunicationPolicy,
+    states: Mutex<HashMap<ValidatorPublicKey, ValidatorState>>,
+}
+
+impl ValidatorHealth {
+    /// Creates a new [`ValidatorHealth`] tracker, considering all validators healthy.
+    pub fn new(policy: CommunicationPolicy) -> Self {
+        ValidatorHealth {
+            policy,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the policy used to communicate with the validators.
+    pub fn policy(&self) -> &CommunicationPolicy {
+        &self.policy
+    }
+
+    /// Returns whether the validator should be left out of requests at time `now`.
+    pub fn is_excluded(&self, public_key: &ValidatorPublicKey, now: Instant) -> bool {
+        self.states()
+            .get(public_key)
+            .and_then(|state| state.report.excluded_until)
+            .is_some_and(|until| until > now)
+    }
+
+    /// Records that the validator responded after `latency`.
+    pub fn record_success(&self, public_key: ValidatorPublicKey, latency: Duration) {
+        let mut states = self.states();
+        let state = states.entry(public_key).or_default();
+        let report = &mut state.report;
+        report.score += SMOOTHING_FACTOR * (1.0 - report.score);
+        report.average_latency = Some(match report.average_latency {
+            Some(average) => {
+                average.mul_f64(1.0 - SMOOTHING_FACTOR) + latency.mul_f64(SMOOTHING_FACTOR)
+            }
+            None => latency,
+        });
+        report.consecutive_failures = 0;
+        report.excluded_until = None;
+        state.exclusions = 0;
+    }
+
+    /// Records that the validator failed to respond at time `now`, and excludes it if it
+    /// failed too many times in a row.
+    pub fn record_failure(&self, public_key: ValidatorPublicKey, now: Instant) {
+        let mut states = self.states();
+        let state = states.entry(public_key).or_default();
+        let report = &mut state.report;
+        report.score -= SMOOTHING_FACTOR * report.score;
+        report.consecutive_failures = report.consecutive_failures.saturating_add(1);
+        if self.policy.max_consecutive_failures == 0
+            || report.consecutive_failures < self.policy.max_consecutive_failures
+        {
+            return;
+        }
+        let duration = self
+            .policy
+            .exclusion_duration
+            .saturating_mul(1 << state.exclusions.min(16))
+            .min(self.policy.max_exclusion_duration);
+        report.excluded_until = Some(now + duration);
+        state.exclusions = state.exclusions.saturating_add(1);
+    }
+
+    /// Sorts the items by the expected latency of their validators, fastest first.
+    ///
+    /// Validators that are unknown so far come first, so that their latency gets measured,
+    /// and validators that failed without ever responding come last. The sort is stable, so
+    /// the order of validators with the same expected latency is kept.
+    pub fn sort_by_expected_latency<T>(
+        &self,
+        items: &mut [T],
+        public_key: impl Fn(&T) -> &ValidatorPublicKey,
+    ) {
+        let states = self.states();
+        items.sort_by_key(|item| match states.get(public_key(item)) {
+            None => Duration::ZERO,
+            Some(state) => state.report.expected_latency().unwrap_or(Duration::MAX),
+        });
+    }
+
+    /// Returns the health of the validators that responded or failed so far.
+    pub fn reports(&self) -> Vec<(ValidatorPublicKey, ValidatorHealthReport)> {
+        self.states()
+            .iter()
+            .map(|(public_key, state)| (*public_key, state.report))
+            .collect()
+    }
+
+    fn states(&self) -> MutexGuard<'_, HashMap<ValidatorPublicKey, ValidatorState>> {
+        self.states
+            .lock()
+            .expect("Panics should not happen while holding a lock to the validator health")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        crypto::ValidatorKeypair,
+        time::{Duration, Instant},
+    };
+
+    use super::{CommunicationPolicy, ValidatorHealth};
+
+    #[test]
+    fn test_exclusion_and_reinclusion() {
+        let health = ValidatorHealth::new(CommunicationPolicy {
+            max_consecutive_failures: 2,
+            exclusion_duration: Duration::from_secs(10),
+            max_exclusion_duration: Duration::from_secs(15),
+            ..CommunicationPolicy::default()
+        });
+        let validator = ValidatorKeypair::generate().public_key;
+        let start = Instant::now();
+
+        health.record_failure(validator, start);
+        assert!(!health.is_excluded(&validator, start));
+        health.record_failure(validator, start);
+        assert!(health.is_excluded(&validator, start));
+        assert!(health.is_excluded(&validator, start + Duration::from_secs(9)));
+
+        // After the exclusion expires, a new failure excludes it for longer, up to the maximum.
+        let later = start + Duration::from_secs(10);
+        assert!(!health.is_excluded(&validator, later));
+        health.record_failure(validator, later);
+        assert!(health.is_excluded(&validator, later + Duration::from_secs(14)));
+        assert!(!health.is_excluded(&validator, later + Duration::from_secs(15)));
+
+        // A successful response includes it again right away.
+        health.record_success(validator, Duration::from_millis(100));
+        assert!(!health.is_excluded(&validator, later));
+        let [(_, report)] = health.reports()[..] else {
+            panic!("Expected a single report");
+        };
+        assert_eq!(report.consecutive_failures, 0);
+        assert_eq!(report.average_latency, Some(Duration::from_millis(100)));
+        assert!(report.score < 1.0);
+    }
+
+    #[test]
+    fn test_no_exclusion_if_disabled() {
+        let health = ValidatorHealth::new(CommunicationPolicy {
+            max_consecutive_failures: 0,
+            ..CommunicationPolicy::default()
+        });
+        let validator = ValidatorKeypair::generate().public_key;
+        let now = Instant::now();
+        for _ in 0..10 {
+            health.record_failure(validator, now);
+        }
+        assert!(!health.is_excluded(&validator, now));
+    }
+
+    #[test]
+    fn test_sort_by_expected_latency() {
+        let health = ValidatorHealth::new(CommunicationPolicy::default());
+        let [fast, slow, flaky, failing, unknown] =
+            [(); 5].map(|()| ValidatorKeypair::generate().public_key);
+        health.record_success(fast, Duration::from_millis(10));
+        health.record_success(slow, Duration::from_millis(100));
+        // A fast validator that often fails is expected to take longer than a reliable one.
+        health.record_success(flaky, Duration::from_millis(20));
+        for _ in 0..5 {
+            health.record_failure(flaky, Instant::now());
+        }
+        health.record_failure(failing, Instant::now());
+
+        let mut validators = vec![failing, slow, flaky, fast, unknown];
+        health.sort_by_expected_latency(&mut validators, |public_key| public_key);
+        assert_eq!(validators, vec![unknown, fast, flaky, slow, failing]);
+    }
+}
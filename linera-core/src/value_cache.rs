@@ -55,9 +55,7 @@ where
         let size = NonZeroUsize::try_from(DEFAULT_VALUE_CACHE_SIZE)
             .expect("Default cache size is larger than zero");
 
-        ValueCache {
-            cache: Mutex::new(LruCache::new(size)),
-        }
+        ValueCache::new(size)
     }
 }
 
@@ -65,6 +63,13 @@ impl<K, V> ValueCache<K, V>
 where
     K: Hash + Eq + PartialEq + Copy,
 {
+    /// Creates a cache of at most `size` values.
+    pub fn new(size: NonZeroUsize) -> Self {
+        ValueCache {
+            cache: Mutex::new(LruCache::new(size)),
+        }
+    }
+
     /// Returns a `Collection` of the hashes in the cache.
     pub fn keys<Collection>(&self) -> Collection
     where
@@ -78,6 +83,21 @@ where
             .collect()
     }
 
+    /// Returns the number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Returns [`true`] if the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.cache.lock().unwrap().is_empty()
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
     /// Returns [`true`] if the cache contains the `V` with the
     /// requested `K`.
     pub fn contains(&self, key: &K) -> bool {
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     num::NonZeroUsize,
     sync::{Arc, Mutex, RwLock},
     time::Duration,
@@ -14,25 +14,28 @@ use linera_base::{
     crypto::{CryptoError, CryptoHash, ValidatorPublicKey, ValidatorSecretKey},
     data_types::{
         ApplicationDescription, ArithmeticError, Blob, BlockHeight, DecompressionError, Epoch,
-        Round,
+        Round, TimeDelta,
     },
-    doc_scalar,
+    doc_scalar, ensure,
     hashed::Hashed,
-    identifiers::{AccountOwner, ApplicationId, BlobId, ChainId},
+    identifiers::{AccountOwner, ApplicationId, BlobId, ChainId, StreamId},
     time::timer::{sleep, timeout},
 };
 #[cfg(with_testing)]
 use linera_chain::ChainExecutionContext;
 use linera_chain::{
-    data_types::{BlockExecutionOutcome, BlockProposal, MessageBundle, ProposedBlock},
+    data_types::{
+        BlockExecutionOutcome, BlockExecutionResources, BlockProposal, MessageBundle,
+        ProposedBlock, ValidatorSigner,
+    },
     types::{
         Block, CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate,
         LiteCertificate, Timeout, TimeoutCertificate, ValidatedBlock, ValidatedBlockCertificate,
     },
     ChainError, ChainStateView,
 };
-use linera_execution::{ExecutionError, ExecutionStateView, Query, QueryOutcome};
-use linera_storage::Storage;
+use linera_execution::{ExecutionError, Query, QueryOutcome};
+use linera_storage::{Clock as _, Storage};
 use linera_views::views::ViewError;
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
@@ -42,17 +45,22 @@ use tracing::{error, instrument, trace, warn};
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{
-        exponential_bucket_interval, register_histogram_vec, register_int_counter_vec,
+        exponential_bucket_interval, exponential_bucket_latencies, register_histogram_vec,
+        register_int_counter_vec, register_int_gauge_vec, MeasureLatency as _,
     },
-    prometheus::{HistogramVec, IntCounterVec},
+    prometheus::{HistogramVec, IntCounterVec, IntGaugeVec},
     std::sync::LazyLock,
 };
 
 use crate::{
-    chain_worker::{ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, DeliveryNotifier},
+    chain_worker::{
+        ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, DeliveryNotifier,
+        ExecutionStateCache, MemoryBudget, MemoryReservation,
+    },
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     join_set_ext::{JoinSet, JoinSetExt},
     notifier::Notifier,
+    rate_limiter::{ConcurrencyLimiter, RateLimit, RateLimiter},
     value_cache::ValueCache,
 };
 
@@ -98,6 +106,77 @@ static CERTIFICATES_SIGNED: LazyLock<IntCounterVec> = LazyLock::new(|| {
     )
 });
 
+#[cfg(with_metrics)]
+static LOADED_CHAIN_WORKERS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "loaded_chain_workers",
+        "Number of chain workers currently loaded in memory",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static CHAIN_WORKER_EVICTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "chain_worker_evictions",
+        "Number of chain workers evicted to load other chains",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static CHAIN_WORKER_RESERVED_MEMORY: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "chain_worker_reserved_memory",
+        "Estimated memory in bytes held by the requests in flight to chain workers",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static CHAIN_WORKER_MEMORY_WAIT_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "chain_worker_memory_wait_latency",
+        "Time spent waiting for the chain worker memory budget (ms)",
+        &[],
+        exponential_bucket_latencies(10_000.0),
+    )
+});
+
+#[cfg(with_metrics)]
+pub(crate) static RATE_LIMITED_PROPOSALS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "rate_limited_proposals",
+        "Number of block proposals rejected because their owner exceeded the rate limit",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static THROTTLED_CHAIN_PROPOSALS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "throttled_chain_proposals",
+        "Number of block proposals rejected because their chain exceeded a proposal limit, or \
+         because the storage was saturated",
+        &["reason"],
+    )
+});
+
+/// The number of chain owners whose proposal rate is tracked at a time, including the
+/// shared limits of the other proposers of open chains.
+const PROPOSAL_RATE_LIMITER_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+/// The number of chains whose proposal rate is tracked at a time.
+const CHAIN_PROPOSAL_RATE_LIMITER_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+/// The delay after which clients are told to retry a proposal that was rejected because too
+/// many proposals for the same chain were being validated.
+const PENDING_PROPOSALS_RETRY_DELAY: TimeDelta = TimeDelta::from_millis(100);
+
+/// The delay after which clients are told to retry a proposal that was rejected because the
+/// storage was saturated by writes.
+const STORAGE_PRESSURE_RETRY_DELAY: TimeDelta = TimeDelta::from_millis(500);
+
 /// Instruct the networking layer to send cross-chain requests and/or push notifications.
 #[derive(Default, Debug)]
 pub struct NetworkActions {
@@ -132,6 +211,10 @@ pub enum Reason {
     NewBlock {
         height: BlockHeight,
         hash: CryptoHash,
+        /// The streams that the block emitted events to.
+        event_streams: BTreeSet<StreamId>,
+        /// The user applications with operations, messages or events in the block.
+        application_ids: BTreeSet<ApplicationId>,
     },
     NewIncomingBundle {
         origin: ChainId,
@@ -210,6 +293,29 @@ pub enum WorkerError {
     InvalidBlockProposal(String),
     #[error("The worker is too busy to handle new chains")]
     FullChainWorkerCache,
+    #[error("Chain {0} is loaded in the worker; it can only be archived once it is unloaded")]
+    ChainLoaded(ChainId),
+    #[error("Chain {0} is being archived")]
+    ChainBeingArchived(ChainId),
+    #[error("The worker is using its whole memory budget for other requests")]
+    MemoryBudgetExhausted,
+    #[error(
+        "Too many block proposals from {owner} on chain {chain_id}; retry in {} ms",
+        retry_after.as_duration().as_millis()
+    )]
+    ProposalRateLimited {
+        chain_id: ChainId,
+        owner: AccountOwner,
+        retry_after: TimeDelta,
+    },
+    #[error(
+        "Too many block proposals on chain {chain_id}; retry in {} ms",
+        retry_after.as_duration().as_millis()
+    )]
+    ChainProposalsThrottled {
+        chain_id: ChainId,
+        retry_after: TimeDelta,
+    },
     #[error("Failed to join spawned worker task")]
     JoinError,
     #[error("Blob was not required by any pending block")]
@@ -271,6 +377,19 @@ impl WorkerError {
     }
 }
 
+/// Statistics about the loaded chains and the caches of a [`WorkerState`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// The number of chain workers loaded in memory.
+    pub loaded_chains: usize,
+    /// The maximal number of chain workers loaded in memory at the same time.
+    pub max_loaded_chains: usize,
+    /// The number of blocks in the block cache.
+    pub cached_blocks: usize,
+    /// The number of execution states in the execution state cache.
+    pub cached_execution_states: usize,
+}
+
 /// State of a worker in a validator or a local node.
 pub struct WorkerState<StorageClient>
 where
@@ -283,7 +402,7 @@ where
     /// Configuration options for the [`ChainWorker`]s.
     chain_worker_config: ChainWorkerConfig,
     block_cache: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
-    execution_state_cache: Arc<ValueCache<CryptoHash, ExecutionStateView<StorageClient::Context>>>,
+    execution_state_cache: Arc<ExecutionStateCache<StorageClient::Context>>,
     /// Chain IDs that should be tracked by a worker.
     tracked_chains: Option<Arc<RwLock<HashSet<ChainId>>>>,
     /// One-shot channels to notify callers when messages of a particular chain have been
@@ -293,6 +412,16 @@ where
     chain_worker_tasks: Arc<Mutex<JoinSet>>,
     /// The cache of running [`ChainWorkerActor`]s.
     chain_workers: Arc<Mutex<LruCache<ChainId, ChainActorEndpoint<StorageClient>>>>,
+    /// The chains being moved to the archive store, which can't be loaded meanwhile.
+    chains_being_archived: Arc<Mutex<HashSet<ChainId>>>,
+    /// The budget for the memory held by requests to the [`ChainWorkerActor`]s, if any.
+    memory_budget: Option<Arc<MemoryBudget>>,
+    /// The limit on the rate of block proposals for each chain, if any.
+    chain_proposal_rate_limiter: Option<Arc<RateLimiter<ChainId>>>,
+    /// The limit on the number of block proposals being validated for each chain, if any.
+    pending_proposals_limiter: Option<Arc<ConcurrencyLimiter<ChainId>>>,
+    /// The storage write pressure above which block proposals are rejected, if any.
+    max_write_pressure: Option<f64>,
 }
 
 impl<StorageClient> Clone for WorkerState<StorageClient>
@@ -310,6 +439,11 @@ where
             delivery_notifiers: self.delivery_notifiers.clone(),
             chain_worker_tasks: self.chain_worker_tasks.clone(),
             chain_workers: self.chain_workers.clone(),
+            chains_being_archived: self.chains_being_archived.clone(),
+            memory_budget: self.memory_budget.clone(),
+            chain_proposal_rate_limiter: self.chain_proposal_rate_limiter.clone(),
+            pending_proposals_limiter: self.pending_proposals_limiter.clone(),
+            max_write_pressure: self.max_write_pressure,
         }
     }
 }
@@ -322,6 +456,37 @@ type ChainActorEndpoint<StorageClient> = mpsc::UnboundedSender<(
 
 pub(crate) type DeliveryNotifiers = HashMap<ChainId, DeliveryNotifier>;
 
+/// Marks a chain as being archived, until it is dropped.
+struct ChainArchival {
+    chains_being_archived: Arc<Mutex<HashSet<ChainId>>>,
+    chain_id: ChainId,
+}
+
+impl ChainArchival {
+    fn start(
+        chains_being_archived: Arc<Mutex<HashSet<ChainId>>>,
+        chain_id: ChainId,
+    ) -> Result<Self, WorkerError> {
+        ensure!(
+            chains_being_archived.lock().unwrap().insert(chain_id),
+            WorkerError::ChainBeingArchived(chain_id)
+        );
+        Ok(ChainArchival {
+            chains_being_archived,
+            chain_id,
+        })
+    }
+}
+
+impl Drop for ChainArchival {
+    fn drop(&mut self) {
+        self.chains_being_archived
+            .lock()
+            .unwrap()
+            .remove(&self.chain_id);
+    }
+}
+
 impl<StorageClient> WorkerState<StorageClient>
 where
     StorageClient: Storage,
@@ -343,6 +508,11 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chains_being_archived: Arc::default(),
+            memory_budget: None,
+            chain_proposal_rate_limiter: None,
+            pending_proposals_limiter: None,
+            max_write_pressure: None,
         }
     }
 
@@ -363,6 +533,11 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chains_being_archived: Arc::default(),
+            memory_budget: None,
+            chain_proposal_rate_limiter: None,
+            pending_proposals_limiter: None,
+            max_write_pressure: None,
         }
     }
 
@@ -405,11 +580,188 @@ where
         self
     }
 
+    /// Returns an instance that only keeps the bodies of the latest `block_body_retention`
+    /// blocks of each chain in storage, if set.
+    #[instrument(level = "trace", skip(self, block_body_retention))]
+    pub fn with_block_body_retention(mut self, block_body_retention: Option<u64>) -> Self {
+        self.chain_worker_config.block_body_retention = block_body_retention;
+        self
+    }
+
+    /// Returns an instance that prunes the block bodies of chains further than the block body
+    /// retention if their owners' retention policy asks for it.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_honor_retention_policies(mut self, honor_retention_policies: bool) -> Self {
+        self.chain_worker_config.honor_retention_policies = honor_retention_policies;
+        self
+    }
+
+    /// Returns an instance that limits the estimated memory held by the requests in flight to
+    /// its chain workers to `memory_budget` bytes, if set.
+    ///
+    /// Requests wait for enough of the budget to be available before being handled.
+    #[instrument(level = "trace", skip(self, memory_budget))]
+    pub fn with_chain_worker_memory_budget(mut self, memory_budget: Option<u64>) -> Self {
+        self.memory_budget = memory_budget.map(|bytes| Arc::new(MemoryBudget::new(bytes)));
+        self
+    }
+
+    /// Returns an instance that limits the rate at which each owner of a chain can submit
+    /// block proposals for it, if set. The proposers of an open chain who don't own it share
+    /// a single limit.
+    #[instrument(level = "trace", skip(self, limit))]
+    pub fn with_proposal_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+        self.chain_worker_config.proposal_rate_limiter =
+            limit.map(|limit| Arc::new(RateLimiter::new(limit, PROPOSAL_RATE_LIMITER_CAPACITY)));
+        self
+    }
+
+    /// Returns an instance that limits the rate at which block proposals are accepted for
+    /// each chain, regardless of their owner, if set.
+    #[instrument(level = "trace", skip(self, limit))]
+    pub fn with_chain_proposal_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+        self.chain_proposal_rate_limiter = limit.map(|limit| {
+            Arc::new(RateLimiter::new(
+                limit,
+                CHAIN_PROPOSAL_RATE_LIMITER_CAPACITY,
+            ))
+        });
+        self
+    }
+
+    /// Returns an instance that rejects block proposals for a chain while
+    /// `max_pending_proposals` other proposals for it are being validated, if set.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_max_pending_proposals_per_chain(
+        mut self,
+        max_pending_proposals: Option<NonZeroUsize>,
+    ) -> Self {
+        self.pending_proposals_limiter =
+            max_pending_proposals.map(|limit| Arc::new(ConcurrencyLimiter::new(limit)));
+        self
+    }
+
+    /// Returns an instance that rejects block proposals while the storage's
+    /// [write pressure](Storage::write_pressure) is above `max_write_pressure`, if set.
+    ///
+    /// Certificates are still handled, so that the chains already being extended make
+    /// progress and the pressure can go down.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_max_write_pressure(mut self, max_write_pressure: Option<f64>) -> Self {
+        self.max_write_pressure = max_write_pressure;
+        self
+    }
+
+    /// Returns an instance that signs its votes and chain information with the given external
+    /// signer instead of a local key.
+    #[instrument(level = "trace", skip(self, signer))]
+    pub fn with_external_validator_signer(mut self, signer: Arc<dyn ValidatorSigner>) -> Self {
+        self.chain_worker_config = self.chain_worker_config.with_external_signer(signer);
+        self
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn nickname(&self) -> &str {
         &self.nickname
     }
 
+    /// Returns statistics about the loaded chains and the caches.
+    pub fn stats(&self) -> WorkerStats {
+        let chain_workers = self.chain_workers.lock().unwrap();
+        WorkerStats {
+            loaded_chains: chain_workers.len(),
+            max_loaded_chains: usize::from(chain_workers.cap()),
+            cached_blocks: self.block_cache.len(),
+            cached_execution_states: self.execution_state_cache.len(),
+        }
+    }
+
+    /// Returns whether the worker of the chain is loaded in memory.
+    pub fn is_chain_loaded(&self, chain_id: ChainId) -> bool {
+        self.chain_workers.lock().unwrap().contains(&chain_id)
+    }
+
+    /// Empties the block and execution state caches and unloads the chain workers that
+    /// aren't handling a request. Returns the number of unloaded chain workers.
+    pub fn flush_caches(&self) -> usize {
+        self.block_cache.clear();
+        self.execution_state_cache.clear();
+        let mut chain_workers = self.chain_workers.lock().unwrap();
+        let idle_chains = chain_workers
+            .iter()
+            .filter(|(_, endpoint)| endpoint.strong_count() <= 1)
+            .map(|(chain_id, _)| *chain_id)
+            .collect::<Vec<_>>();
+        for chain_id in &idle_chains {
+            chain_workers.pop(chain_id);
+        }
+        self.clean_up_finished_chain_workers(&chain_workers);
+        #[cfg(with_metrics)]
+        LOADED_CHAIN_WORKERS
+            .with_label_values(&[])
+            .set(chain_workers.len() as i64);
+        idle_chains.len()
+    }
+
+    /// Unloads the workers of the chains for which `predicate` returns `true`, e.g. because
+    /// another shard takes over these chains. Returns the number of unloaded chain workers.
+    ///
+    /// The caller must make sure that no request for these chains is being handled.
+    pub fn unload_chains(&self, predicate: impl Fn(ChainId) -> bool) -> usize {
+        let mut chain_workers = self.chain_workers.lock().unwrap();
+        let chains = chain_workers
+            .iter()
+            .map(|(chain_id, _)| *chain_id)
+            .filter(|chain_id| predicate(*chain_id))
+            .collect::<Vec<_>>();
+        for chain_id in &chains {
+            chain_workers.pop(chain_id);
+        }
+        self.clean_up_finished_chain_workers(&chain_workers);
+        #[cfg(with_metrics)]
+        LOADED_CHAIN_WORKERS
+            .with_label_values(&[])
+            .set(chain_workers.len() as i64);
+        chains.len()
+    }
+
+    /// Cleans up any finished chain workers and their delivery notifiers.
+    fn clean_up_finished_chain_workers(
+        &self,
+        active_chain_workers: &LruCache<ChainId, ChainActorEndpoint<StorageClient>>,
+    ) {
+        self.chain_worker_tasks
+            .lock()
+            .unwrap()
+            .reap_finished_tasks();
+
+        self.delivery_notifiers
+            .lock()
+            .unwrap()
+            .retain(|chain_id, notifier| {
+                !notifier.is_empty() || active_chain_workers.contains(chain_id)
+            });
+    }
+
+    /// Moves the state, certificates and blobs of a chain to the storage's archive store.
+    ///
+    /// This fails if the chain is loaded, e.g. because it is still in use: it must be
+    /// unloaded first, which [`WorkerState::flush_caches`] does for idle chains. Requests for
+    /// the chain fail until the archival is complete. The chain is restored the next time it
+    /// is loaded, or one of its certificates or blobs is read.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn archive_chain(&self, chain_id: ChainId) -> Result<(), WorkerError> {
+        let _archival = {
+            let chain_workers = self.chain_workers.lock().unwrap();
+            ensure!(
+                !chain_workers.contains(&chain_id),
+                WorkerError::ChainLoaded(chain_id)
+            );
+            ChainArchival::start(self.chains_being_archived.clone(), chain_id)?
+        };
+        Ok(self.storage.archive_chain(chain_id).await?)
+    }
+
     /// Returns the storage client so that it can be manipulated or queried.
     #[instrument(level = "trace", skip(self))]
     #[cfg(not(feature = "test"))]
@@ -427,7 +779,10 @@ where
 
     #[instrument(level = "trace", skip(self, key_pair))]
     #[cfg(test)]
-    pub(crate) async fn with_key_pair(mut self, key_pair: Option<Arc<ValidatorSecretKey>>) -> Self {
+    pub(crate) async fn with_key_pair(
+        mut self,
+        key_pair: Option<Arc<linera_chain::data_types::ValidatorKeys>>,
+    ) -> Self {
         self.chain_worker_config.key_pair = key_pair;
         self.chain_workers.lock().unwrap().clear();
         self
@@ -541,6 +896,21 @@ where
         round: Option<u32>,
         published_blobs: Vec<Blob>,
     ) -> Result<(Block, ChainInfoResponse), WorkerError> {
+        let (block, response, _) = self
+            .stage_block_execution_with_resources(block, round, published_blobs)
+            .await?;
+        Ok((block, response))
+    }
+
+    /// Like [`WorkerState::stage_block_execution`], but also returns the resources used by
+    /// the block and the fees it was charged.
+    #[instrument(level = "trace", skip(self, block))]
+    pub async fn stage_block_execution_with_resources(
+        &self,
+        block: ProposedBlock,
+        round: Option<u32>,
+        published_blobs: Vec<Blob>,
+    ) -> Result<(Block, ChainInfoResponse, BlockExecutionResources), WorkerError> {
         self.query_chain_worker(block.chain_id, move |callback| {
             ChainWorkerRequest::StageBlockExecution {
                 block,
@@ -699,14 +1069,46 @@ where
     ) -> Result<Response, WorkerError> {
         let chain_actor = self.get_chain_worker_endpoint(chain_id).await?;
         let (callback, response) = oneshot::channel();
+        let request = request_builder(callback);
+        let reservation = match &self.memory_budget {
+            Some(memory_budget) => {
+                Some(Self::reserve_memory(memory_budget, request.estimated_size()).await?)
+            }
+            None => None,
+        };
 
         chain_actor
-            .send((request_builder(callback), tracing::Span::current()))
+            .send((request, tracing::Span::current()))
             .expect("`ChainWorkerActor` stopped executing unexpectedly");
 
+        let response = response
+            .await
+            .expect("`ChainWorkerActor` stopped executing without responding");
+        drop(reservation);
+        #[cfg(with_metrics)]
+        if let Some(memory_budget) = &self.memory_budget {
+            CHAIN_WORKER_RESERVED_MEMORY
+                .with_label_values(&[])
+                .set(memory_budget.reserved_bytes() as i64);
+        }
         response
+    }
+
+    /// Reserves `bytes` of the `memory_budget`, waiting for them to become available.
+    async fn reserve_memory(
+        memory_budget: &MemoryBudget,
+        bytes: usize,
+    ) -> Result<MemoryReservation, WorkerError> {
+        #[cfg(with_metrics)]
+        let _latency = CHAIN_WORKER_MEMORY_WAIT_LATENCY.measure_latency();
+        let reservation = timeout(Duration::from_secs(3), memory_budget.reserve(bytes))
             .await
-            .expect("`ChainWorkerActor` stopped executing without responding")
+            .map_err(|_| WorkerError::MemoryBudgetExhausted)?;
+        #[cfg(with_metrics)]
+        CHAIN_WORKER_RESERVED_MEMORY
+            .with_label_values(&[])
+            .set(memory_budget.reserved_bytes() as i64);
+        Ok(reservation)
     }
 
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, creating one and adding it
@@ -718,15 +1120,15 @@ where
     ) -> Result<ChainActorEndpoint<StorageClient>, WorkerError> {
         let (sender, new_receiver) = timeout(Duration::from_secs(3), async move {
             loop {
-                match self.try_get_chain_worker_endpoint(chain_id) {
-                    Some(endpoint) => break endpoint,
+                match self.try_get_chain_worker_endpoint(chain_id)? {
+                    Some(endpoint) => break Ok::<_, WorkerError>(endpoint),
                     None => sleep(Duration::from_millis(250)).await,
                 }
                 warn!("No chain worker candidates found for eviction, retrying...");
             }
         })
         .await
-        .map_err(|_| WorkerError::FullChainWorkerCache)?;
+        .map_err(|_| WorkerError::FullChainWorkerCache)??;
 
         if let Some(receiver) = new_receiver {
             let delivery_notifier = self
@@ -760,59 +1162,65 @@ where
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, attempting to create one
     /// and add it to the cache if needed.
     ///
-    /// Returns [`None`] if the cache is full and no candidate for eviction was found.
+    /// Returns [`None`] if the cache is full and no candidate for eviction was found, and an
+    /// error if the chain is being archived.
     #[instrument(level = "trace", skip(self))]
     #[expect(clippy::type_complexity)]
     fn try_get_chain_worker_endpoint(
         &self,
         chain_id: ChainId,
-    ) -> Option<(
-        ChainActorEndpoint<StorageClient>,
-        Option<
-            mpsc::UnboundedReceiver<(ChainWorkerRequest<StorageClient::Context>, tracing::Span)>,
-        >,
-    )> {
+    ) -> Result<
+        Option<(
+            ChainActorEndpoint<StorageClient>,
+            Option<
+                mpsc::UnboundedReceiver<(
+                    ChainWorkerRequest<StorageClient::Context>,
+                    tracing::Span,
+                )>,
+            >,
+        )>,
+        WorkerError,
+    > {
         let mut chain_workers = self.chain_workers.lock().unwrap();
 
         if let Some(endpoint) = chain_workers.get(&chain_id) {
-            Some((endpoint.clone(), None))
+            Ok(Some((endpoint.clone(), None)))
         } else {
+            ensure!(
+                !self
+                    .chains_being_archived
+                    .lock()
+                    .unwrap()
+                    .contains(&chain_id),
+                WorkerError::ChainBeingArchived(chain_id)
+            );
             if chain_workers.len() >= usize::from(chain_workers.cap()) {
-                let (chain_to_evict, _) = chain_workers
+                let Some((chain_to_evict, _)) = chain_workers
                     .iter()
                     .rev()
-                    .find(|(_, candidate_endpoint)| candidate_endpoint.strong_count() <= 1)?;
+                    .find(|(_, candidate_endpoint)| candidate_endpoint.strong_count() <= 1)
+                else {
+                    return Ok(None);
+                };
                 let chain_to_evict = *chain_to_evict;
 
                 chain_workers.pop(&chain_to_evict);
                 self.clean_up_finished_chain_workers(&chain_workers);
+                #[cfg(with_metrics)]
+                CHAIN_WORKER_EVICTIONS.with_label_values(&[]).inc();
             }
 
             let (sender, receiver) = mpsc::unbounded_channel();
             chain_workers.push(chain_id, sender.clone());
+            #[cfg(with_metrics)]
+            LOADED_CHAIN_WORKERS
+                .with_label_values(&[])
+                .set(chain_workers.len() as i64);
 
-            Some((sender, Some(receiver)))
+            Ok(Some((sender, Some(receiver))))
         }
     }
 
-    /// Cleans up any finished chain workers and their delivery notifiers.
-    fn clean_up_finished_chain_workers(
-        &self,
-        active_chain_workers: &LruCache<ChainId, ChainActorEndpoint<StorageClient>>,
-    ) {
-        self.chain_worker_tasks
-            .lock()
-            .unwrap()
-            .reap_finished_tasks();
-
-        self.delivery_notifiers
-            .lock()
-            .unwrap()
-            .retain(|chain_id, notifier| {
-                !notifier.is_empty() || active_chain_workers.contains(chain_id)
-            });
-    }
-
     #[instrument(skip_all, fields(
         nick = self.nickname,
         chain_id = format!("{:.8}", proposal.content.block.chain_id),
@@ -823,13 +1231,58 @@ where
         proposal: BlockProposal,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         trace!("{} <-- {:?}", self.nickname, proposal);
+        let chain_id = proposal.content.block.chain_id;
+        if let Some(max_write_pressure) = self.max_write_pressure {
+            if self.storage.write_pressure() > max_write_pressure {
+                #[cfg(with_metrics)]
+                THROTTLED_CHAIN_PROPOSALS
+                    .with_label_values(&["storage"])
+                    .inc();
+                return Err(WorkerError::ChainProposalsThrottled {
+                    chain_id,
+                    retry_after: STORAGE_PRESSURE_RETRY_DELAY,
+                });
+            }
+        }
+        // The chain's token is only spent once the chain worker accepted the proposal, i.e.
+        // from a proposer who is allowed to propose. A pending proposal only holds its permit
+        // while it is being handled.
+        if let Some(rate_limiter) = &self.chain_proposal_rate_limiter {
+            let now = self.storage.clock().current_time();
+            if let Some(retry_after) = rate_limiter.retry_after(&chain_id, now) {
+                #[cfg(with_metrics)]
+                THROTTLED_CHAIN_PROPOSALS.with_label_values(&["rate"]).inc();
+                return Err(WorkerError::ChainProposalsThrottled {
+                    chain_id,
+                    retry_after,
+                });
+            }
+        }
+        let _permit = match &self.pending_proposals_limiter {
+            None => None,
+            Some(limiter) => {
+                let Some(permit) = limiter.try_acquire(chain_id) else {
+                    #[cfg(with_metrics)]
+                    THROTTLED_CHAIN_PROPOSALS
+                        .with_label_values(&["pending"])
+                        .inc();
+                    return Err(WorkerError::ChainProposalsThrottled {
+                        chain_id,
+                        retry_after: PENDING_PROPOSALS_RETRY_DELAY,
+                    });
+                };
+                Some(permit)
+            }
+        };
         #[cfg(with_metrics)]
         let round = proposal.content.round;
-        let response = self
-            .query_chain_worker(proposal.content.block.chain_id, move |callback| {
-                ChainWorkerRequest::HandleBlockProposal { proposal, callback }
-            })
-            .await?;
+        let response = Box::pin(self.query_chain_worker(chain_id, move |callback| {
+            ChainWorkerRequest::HandleBlockProposal { proposal, callback }
+        }))
+        .await?;
+        if let Some(rate_limiter) = &self.chain_proposal_rate_limiter {
+            rate_limiter.record(chain_id, self.storage.clock().current_time());
+        }
         #[cfg(with_metrics)]
         NUM_ROUNDS_IN_BLOCK_PROPOSAL
             .with_label_values(&[round.type_name()])
@@ -892,7 +1345,7 @@ where
                     .inc_by(confirmed_transactions);
             }
 
-            for (validator_name, _) in certificate.signatures() {
+            for validator_name in certificate.signers() {
                 CERTIFICATES_SIGNED
                     .with_label_values(&[&validator_name.to_string()])
                     .inc();
@@ -964,6 +1417,32 @@ where
         result
     }
 
+    /// Returns the cross-chain requests for all outgoing messages that may not have been
+    /// delivered yet, e.g. because the validator was restarted before they were confirmed.
+    ///
+    /// Only the chains for which `is_handled` returns `true` are included, so that each shard
+    /// can resume the deliveries of its own chains.
+    #[instrument(skip_all, fields(nick = self.nickname))]
+    pub async fn pending_cross_chain_requests(
+        &self,
+        is_handled: impl Fn(ChainId) -> bool,
+    ) -> Result<NetworkActions, WorkerError> {
+        let mut actions = NetworkActions::default();
+        for chain_id in self.storage.read_pending_deliveries().await? {
+            if !is_handled(chain_id) {
+                continue;
+            }
+            // Querying a chain triggers the cross-chain requests for its outgoing messages.
+            let (_, chain_actions) = self
+                .handle_chain_info_query(ChainInfoQuery::new(chain_id))
+                .await?;
+            actions
+                .cross_chain_requests
+                .extend(chain_actions.cross_chain_requests);
+        }
+        Ok(actions)
+    }
+
     #[instrument(skip_all, fields(
         nick = self.nickname,
         chain_id = format!("{:.8}", chain_id)
@@ -1100,11 +1579,11 @@ where
     #[instrument(level = "trace", skip(self))]
     pub fn public_key(&self) -> ValidatorPublicKey {
         self.chain_worker_config
-            .key_pair()
+            .validator_keys()
             .expect(
                 "Test validator should have a key pair assigned to it \
                 in order to obtain it's public key",
             )
-            .public()
+            .public_key()
     }
 }
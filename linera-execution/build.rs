@@ -7,6 +7,7 @@ fn main() {
 
         with_fs: { all(not(target_arch = "wasm32"), feature = "fs") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
+        with_query_sandbox: { all(not(target_arch = "wasm32"), feature = "query-sandbox") },
         with_testing: { any(test, feature = "test") },
         with_tokio_multi_thread: { not(target_arch = "wasm32") },
         with_wasmer: { feature = "wasmer" },
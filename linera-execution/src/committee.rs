@@ -2,13 +2,28 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{borrow::Cow, collections::BTreeMap, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    hash::Hasher,
+    ops::{Deref, DerefMut},
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
 
 use async_graphql::InputObject;
-use linera_base::crypto::{AccountPublicKey, CryptoError, ValidatorPublicKey};
+use linera_base::{
+    appended,
+    crypto::{
+        AccountPublicKey, BlsPublicKey, BlsValidatorKey, CryptoError, DecodedBlsPublicKey,
+        ValidatorPublicKey,
+    },
+    data_types::Epoch,
+    doc_scalar,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::policy::ResourceControlPolicy;
+use crate::policy::{PolicyMinimal, ResourceControlPolicy, ResourceControlPolicyAppended};
 
 /// The identity of a validator.
 #[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Debug)]
@@ -48,7 +63,7 @@ impl<'de> Deserialize<'de> for ValidatorName {
 }
 
 /// Public state of a validator.
-#[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub struct ValidatorState {
     /// The network address (in a string format understood by the networking layer).
     pub network_address: String,
@@ -56,6 +71,109 @@ pub struct ValidatorState {
     pub votes: u64,
     /// The public key of the account associated with the validator.
     pub account_public_key: AccountPublicKey,
+    /// The BLS12-381 key the validator aggregates its votes with, if any.
+    pub bls_key: Option<BlsValidatorKey>,
+}
+
+impl Serialize for ValidatorState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            ValidatorStateFull::from(self).serialize(serializer)
+        } else {
+            ValidatorStateMinimal::from(self).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValidatorState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let ValidatorStateFull {
+                network_address,
+                votes,
+                account_public_key,
+                bls_key,
+            } = ValidatorStateFull::deserialize(deserializer)?;
+            Ok(ValidatorState {
+                network_address: network_address.into_owned(),
+                votes,
+                account_public_key,
+                bls_key,
+            })
+        } else {
+            let ValidatorStateMinimal {
+                network_address,
+                votes,
+                account_public_key,
+            } = ValidatorStateMinimal::deserialize(deserializer)?;
+            Ok(ValidatorState {
+                network_address: network_address.into_owned(),
+                votes,
+                account_public_key,
+                bls_key: None,
+            })
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ValidatorState")]
+struct ValidatorStateFull<'a> {
+    network_address: Cow<'a, str>,
+    votes: u64,
+    account_public_key: AccountPublicKey,
+    #[serde(default)]
+    bls_key: Option<BlsValidatorKey>,
+}
+
+/// The BCS serialization of a validator's state. The BLS12-381 key is appended to the
+/// committee instead.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ValidatorState")]
+struct ValidatorStateMinimal<'a> {
+    network_address: Cow<'a, str>,
+    votes: u64,
+    account_public_key: AccountPublicKey,
+}
+
+impl<'a> From<&'a ValidatorState> for ValidatorStateFull<'a> {
+    fn from(state: &'a ValidatorState) -> Self {
+        ValidatorStateFull {
+            network_address: Cow::Borrowed(&state.network_address),
+            votes: state.votes,
+            account_public_key: state.account_public_key,
+            bls_key: state.bls_key,
+        }
+    }
+}
+
+impl<'a> From<&'a ValidatorState> for ValidatorStateMinimal<'a> {
+    fn from(state: &'a ValidatorState) -> Self {
+        ValidatorStateMinimal {
+            network_address: Cow::Borrowed(&state.network_address),
+            votes: state.votes,
+            account_public_key: state.account_public_key,
+        }
+    }
+}
+
+/// How the validators of a committee sign their certificates.
+#[derive(
+    Eq, PartialEq, Hash, Copy, Clone, Debug, Default, Serialize, Deserialize, clap::ValueEnum,
+)]
+pub enum CertificateSignatureScheme {
+    /// Certificates contain the secp256k1 signature of each validator.
+    #[default]
+    Secp256k1,
+    /// Certificates contain a single BLS12-381 signature aggregating the votes. Every
+    /// validator must have a BLS key.
+    AggregatedBls12381,
 }
 
 /// A set of validators (identified by their public keys) and their voting rights.
@@ -71,6 +189,36 @@ pub struct Committee {
     validity_threshold: u64,
     /// The policy agreed on for this epoch.
     policy: ResourceControlPolicy,
+    /// How the validators sign certificates in this epoch.
+    certificate_signature_scheme: CertificateSignatureScheme,
+    /// The validators' BLS12-381 keys, decoded when first needed.
+    #[graphql(skip)]
+    decoded_bls_keys: DecodedBlsKeys,
+}
+
+/// The decoded BLS12-381 keys of a committee's validators, with the keys they were decoded
+/// from. They are derived from the validators, so they are ignored when comparing committees.
+#[derive(Clone, Default)]
+struct DecodedBlsKeys(
+    Arc<OnceLock<BTreeMap<ValidatorPublicKey, (BlsPublicKey, DecodedBlsPublicKey)>>>,
+);
+
+impl PartialEq for DecodedBlsKeys {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for DecodedBlsKeys {}
+
+impl std::hash::Hash for DecodedBlsKeys {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl std::fmt::Debug for DecodedBlsKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecodedBlsKeys").finish_non_exhaustive()
+    }
 }
 
 impl Serialize for Committee {
@@ -81,7 +229,11 @@ impl Serialize for Committee {
         if serializer.is_human_readable() {
             CommitteeFull::from(self).serialize(serializer)
         } else {
-            CommitteeMinimal::from(self).serialize(serializer)
+            appended::serialize(
+                &CommitteeMinimal::from(self),
+                &CommitteeAppended::from(self),
+                serializer,
+            )
         }
     }
 }
@@ -95,8 +247,8 @@ impl<'de> Deserialize<'de> for Committee {
             let committee_full = CommitteeFull::deserialize(deserializer)?;
             Committee::try_from(committee_full).map_err(serde::de::Error::custom)
         } else {
-            let committee_minimal = CommitteeMinimal::deserialize(deserializer)?;
-            Ok(Committee::from(committee_minimal))
+            let (committee_minimal, appended) = appended::deserialize(deserializer)?;
+            Committee::from_minimal(committee_minimal, appended).map_err(serde::de::Error::custom)
         }
     }
 }
@@ -109,13 +261,32 @@ struct CommitteeFull<'a> {
     quorum_threshold: u64,
     validity_threshold: u64,
     policy: Cow<'a, ResourceControlPolicy>,
+    #[serde(default)]
+    certificate_signature_scheme: CertificateSignatureScheme,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename = "Committee")]
 struct CommitteeMinimal<'a> {
     validators: Cow<'a, BTreeMap<ValidatorPublicKey, ValidatorState>>,
-    policy: Cow<'a, ResourceControlPolicy>,
+    policy: PolicyMinimal<'a>,
+}
+
+/// The fields appended to the BCS serialization of committees, by version.
+#[derive(Serialize, Deserialize)]
+enum CommitteeAppended {
+    /// The validators' BLS12-381 keys, and how they sign certificates.
+    V1 {
+        certificate_signature_scheme: CertificateSignatureScheme,
+        bls_keys: BTreeMap<ValidatorPublicKey, BlsValidatorKey>,
+    },
+    /// The fields of `V1`, and those appended to the policy, which is not the last part of
+    /// the committee's serialization.
+    V2 {
+        certificate_signature_scheme: CertificateSignatureScheme,
+        bls_keys: BTreeMap<ValidatorPublicKey, BlsValidatorKey>,
+        policy: ResourceControlPolicyAppended,
+    },
 }
 
 impl TryFrom<CommitteeFull<'static>> for Committee {
@@ -128,8 +299,10 @@ impl TryFrom<CommitteeFull<'static>> for Committee {
             quorum_threshold,
             validity_threshold,
             policy,
+            certificate_signature_scheme,
         } = committee_full;
-        let committee = Committee::new(validators.into_owned(), policy.into_owned());
+        let committee = Committee::new(validators.into_owned(), policy.into_owned())
+            .with_certificate_signature_scheme(certificate_signature_scheme);
         if total_votes != committee.total_votes {
             Err(format!(
                 "invalid committee: total_votes is {}; should be {}",
@@ -159,6 +332,8 @@ impl<'a> From<&'a Committee> for CommitteeFull<'a> {
             quorum_threshold,
             validity_threshold,
             policy,
+            certificate_signature_scheme,
+            decoded_bls_keys: _,
         } = committee;
         CommitteeFull {
             validators: Cow::Borrowed(validators),
@@ -166,33 +341,135 @@ impl<'a> From<&'a Committee> for CommitteeFull<'a> {
             quorum_threshold: *quorum_threshold,
             validity_threshold: *validity_threshold,
             policy: Cow::Borrowed(policy),
+            certificate_signature_scheme: *certificate_signature_scheme,
         }
     }
 }
 
-impl From<CommitteeMinimal<'static>> for Committee {
-    fn from(committee_min: CommitteeMinimal) -> Committee {
+impl Committee {
+    /// Returns the committee with the given BCS fields. Committees serialized before fields
+    /// were appended have none.
+    fn from_minimal(
+        committee_min: CommitteeMinimal,
+        appended: Option<CommitteeAppended>,
+    ) -> Result<Committee, String> {
         let CommitteeMinimal { validators, policy } = committee_min;
-        Committee::new(validators.into_owned(), policy.into_owned())
+        let mut validators = validators.into_owned();
+        let (certificate_signature_scheme, bls_keys, policy_appended) = match appended {
+            None => Default::default(),
+            Some(CommitteeAppended::V1 {
+                certificate_signature_scheme,
+                bls_keys,
+            }) => (certificate_signature_scheme, bls_keys, None),
+            Some(CommitteeAppended::V2 {
+                certificate_signature_scheme,
+                bls_keys,
+                policy,
+            }) => (certificate_signature_scheme, bls_keys, Some(policy)),
+        };
+        for (validator, bls_key) in bls_keys {
+            let state = validators.get_mut(&validator).ok_or_else(|| {
+                format!("invalid committee: {validator} has a BLS key but is not a validator")
+            })?;
+            state.bls_key = Some(bls_key);
+        }
+        let policy = ResourceControlPolicy::from_fields(policy, policy_appended);
+        Ok(Committee::new(validators, policy)
+            .with_certificate_signature_scheme(certificate_signature_scheme))
     }
 }
 
 impl<'a> From<&'a Committee> for CommitteeMinimal<'a> {
     fn from(committee: &'a Committee) -> CommitteeMinimal<'a> {
-        let Committee {
-            validators,
-            total_votes: _,
-            quorum_threshold: _,
-            validity_threshold: _,
-            policy,
-        } = committee;
         CommitteeMinimal {
-            validators: Cow::Borrowed(validators),
-            policy: Cow::Borrowed(policy),
+            validators: Cow::Borrowed(&committee.validators),
+            policy: committee.policy.split().0,
         }
     }
 }
 
+impl From<&Committee> for CommitteeAppended {
+    fn from(committee: &Committee) -> CommitteeAppended {
+        CommitteeAppended::V2 {
+            certificate_signature_scheme: committee.certificate_signature_scheme,
+            bls_keys: committee
+                .validators
+                .iter()
+                .filter_map(|(validator, state)| Some((*validator, state.bls_key?)))
+                .collect(),
+            policy: committee.policy.split().1,
+        }
+    }
+}
+
+/// The committees that a chain trusts, indexed by epoch, as stored in its state.
+///
+/// The committees stored before fields were appended to them are followed by the next
+/// committee, so the appended fields of all committees come after the last one.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct Committees(pub BTreeMap<Epoch, Committee>);
+
+impl Serialize for Committees {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        let committees = self
+            .0
+            .iter()
+            .map(|(epoch, committee)| (*epoch, CommitteeMinimal::from(committee)))
+            .collect::<BTreeMap<_, _>>();
+        let appended = self
+            .0
+            .iter()
+            .map(|(epoch, committee)| (*epoch, CommitteeAppended::from(committee)))
+            .collect::<BTreeMap<_, _>>();
+        appended::serialize(&committees, &appended, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Committees {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let (committees, appended): (
+            BTreeMap<Epoch, CommitteeMinimal>,
+            Option<BTreeMap<Epoch, CommitteeAppended>>,
+        ) = appended::deserialize(deserializer)?;
+        let mut appended = appended.unwrap_or_default();
+        committees
+            .into_iter()
+            .map(|(epoch, committee)| {
+                let committee = Committee::from_minimal(committee, appended.remove(&epoch))
+                    .map_err(serde::de::Error::custom)?;
+                Ok((epoch, committee))
+            })
+            .collect::<Result<_, _>>()
+            .map(Committees)
+    }
+}
+
+impl From<BTreeMap<Epoch, Committee>> for Committees {
+    fn from(committees: BTreeMap<Epoch, Committee>) -> Self {
+        Committees(committees)
+    }
+}
+
+impl Deref for Committees {
+    type Target = BTreeMap<Epoch, Committee>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Committees {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl std::fmt::Display for ValidatorName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         self.0.fmt(f)
@@ -232,9 +509,17 @@ impl Committee {
             quorum_threshold,
             validity_threshold,
             policy,
+            certificate_signature_scheme: CertificateSignatureScheme::default(),
+            decoded_bls_keys: DecodedBlsKeys::default(),
         }
     }
 
+    /// Returns this committee, signing certificates with the given scheme.
+    pub fn with_certificate_signature_scheme(mut self, scheme: CertificateSignatureScheme) -> Self {
+        self.certificate_signature_scheme = scheme;
+        self
+    }
+
     #[cfg(with_testing)]
     pub fn make_simple(keys: Vec<(ValidatorPublicKey, AccountPublicKey)>) -> Self {
         let map = keys
@@ -246,6 +531,7 @@ impl Committee {
                         network_address: validator_key.to_string(),
                         votes: 1,
                         account_public_key: account_key,
+                        bls_key: None,
                     },
                 )
             })
@@ -308,4 +594,62 @@ impl Committee {
     pub fn policy_mut(&mut self) -> &mut ResourceControlPolicy {
         &mut self.policy
     }
+
+    pub fn certificate_signature_scheme(&self) -> CertificateSignatureScheme {
+        self.certificate_signature_scheme
+    }
+
+    /// Returns the BLS key of the given validator, if it is in the committee and has one.
+    pub fn bls_key(&self, author: &ValidatorPublicKey) -> Option<&BlsValidatorKey> {
+        self.validators.get(author)?.bls_key.as_ref()
+    }
+
+    /// Returns the decoded BLS key of the given validator, if it is in the committee and has
+    /// one. The keys are only decoded once.
+    pub fn decoded_bls_key(
+        &self,
+        author: &ValidatorPublicKey,
+    ) -> Option<Result<DecodedBlsPublicKey, CryptoError>> {
+        let public_key = self.bls_key(author)?.public_key;
+        let decoded_keys = self.decoded_bls_keys.0.get_or_init(|| {
+            self.validators
+                .iter()
+                .filter_map(|(validator, state)| {
+                    let public_key = state.bls_key?.public_key;
+                    Some((*validator, (public_key, public_key.decode().ok()?)))
+                })
+                .collect()
+        });
+        match decoded_keys.get(author) {
+            Some((decoded_from, decoded_key)) if *decoded_from == public_key => {
+                Some(Ok(*decoded_key))
+            }
+            // The key is invalid, or the validators were changed since the keys were decoded.
+            _ => Some(public_key.decode()),
+        }
+    }
+
+    /// Checks the proofs of possession of the validators' BLS keys, and that every validator
+    /// has one if the certificates of this committee are aggregated.
+    pub fn check_bls_keys(&self) -> Result<(), CryptoError> {
+        let aggregated =
+            self.certificate_signature_scheme == CertificateSignatureScheme::AggregatedBls12381;
+        for (validator, state) in &self.validators {
+            match &state.bls_key {
+                Some(bls_key) => bls_key.check()?,
+                None if aggregated => {
+                    return Err(CryptoError::InvalidBlsKey(format!(
+                        "validator {validator} has no BLS key"
+                    )))
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
 }
+
+doc_scalar!(
+    CertificateSignatureScheme,
+    "How the validators of a committee sign their certificates"
+);
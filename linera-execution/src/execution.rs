@@ -32,10 +32,10 @@ use {
 use super::{runtime::ServiceRuntimeRequest, ExecutionRequest};
 use crate::{
     resources::ResourceController, system::SystemExecutionStateView, ApplicationDescription,
-    ApplicationId, ContractSyncRuntime, ExecutionError, ExecutionRuntimeConfig,
-    ExecutionRuntimeContext, Message, MessageContext, MessageKind, Operation, OperationContext,
-    OutgoingMessage, ProcessStreamsContext, Query, QueryContext, QueryOutcome, ServiceSyncRuntime,
-    SystemMessage, Timestamp, TransactionTracker,
+    ApplicationId, ContractSyncRuntime, ExecutionError, ExecutionRuntimeContext, Message,
+    MessageContext, MessageKind, Operation, OperationContext, OutgoingMessage,
+    ProcessStreamsContext, Query, QueryContext, QueryOutcome, ServiceSyncRuntime, SystemMessage,
+    Timestamp, TransactionTracker,
 };
 
 /// A view accessing the execution state of a chain.
@@ -198,7 +198,6 @@ where
         txn_tracker: &mut TransactionTracker,
         resource_controller: &mut ResourceController<Option<AccountOwner>>,
     ) -> Result<(), ExecutionError> {
-        let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
         self.run_user_action_with_runtime(
             application_id,
             action,
@@ -263,10 +262,14 @@ where
         *txn_tracker = txn_tracker_moved;
         txn_tracker.add_operation_result(result);
 
+        let fees = controller
+            .tracker
+            .fees
+            .try_sub(resource_controller.tracker.fees)?;
         resource_controller
             .with_state_and_grant(&mut self.system, grant)
             .await?
-            .merge_balance(initial_balance, controller.balance()?)?;
+            .merge_balance(initial_balance, controller.balance()?, fees)?;
         resource_controller.tracker = controller.tracker;
 
         Ok(())
@@ -330,7 +333,10 @@ where
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match message {
             Message::System(message) => {
-                let outcome = self.system.execute_message(context, message).await?;
+                let outcome = self
+                    .system
+                    .execute_message(context, message, grant, resource_controller)
+                    .await?;
                 txn_tracker.add_outgoing_messages(outcome)?;
             }
             Message::User {
@@ -414,7 +420,6 @@ where
                 application_id,
                 bytes,
             } => {
-                let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
                 let outcome = match endpoint {
                     Some(endpoint) => {
                         self.query_user_application_with_long_lived_service(
@@ -442,6 +447,17 @@ where
         context: QueryContext,
         query: Vec<u8>,
     ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
+        #[cfg(with_query_sandbox)]
+        if let Some(pool) = self
+            .context()
+            .extra()
+            .execution_runtime_config()
+            .query_sandbox
+        {
+            return self
+                .query_user_application_in_sandbox(&pool, application_id, context, query)
+                .await;
+        }
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
         let (code, description) = self.load_service(application_id, None).await?;
@@ -3,10 +3,11 @@
 
 //! Handle requests from the synchronous execution thread of user applications.
 
+#[cfg(not(web))]
+use std::error::Error as _;
+use std::ops::Range;
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
-#[cfg(not(web))]
-use std::time::Duration;
 
 use custom_debug_derive::Debug;
 use futures::{channel::mpsc, StreamExt as _};
@@ -35,6 +36,8 @@ use crate::{
     ExecutionStateView, ModuleId, OutgoingMessage, ResourceController, TransactionTracker,
     UserContractCode, UserServiceCode,
 };
+#[cfg(not(web))]
+use crate::{HttpRedirectPolicy, HttpRequestPolicy, ResourceControlPolicy};
 
 #[cfg(with_metrics)]
 /// Histogram of the latency to load a contract bytecode.
@@ -165,6 +168,11 @@ where
                 callback.respond(owners);
             }
 
+            ConfigValue { key, callback } => {
+                let value = self.system.config.get(&key).await?;
+                callback.respond(value);
+            }
+
             Transfer {
                 source,
                 destination,
@@ -279,10 +287,23 @@ where
             WriteBatch {
                 id,
                 batch,
+                maximum_storage_bytes,
                 callback,
             } => {
                 let mut view = self.users.try_load_entry_mut(&id).await?;
+                let previous_size = u64::from(view.total_size().sum());
                 view.write_batch(batch).await?;
+                let size = u64::from(view.total_size().sum());
+                // An application already over its quota, e.g. after the policy changed, may
+                // still shrink its state.
+                ensure!(
+                    size <= maximum_storage_bytes || size <= previous_size,
+                    ExecutionError::ApplicationStorageQuotaExceeded {
+                        application_id: id,
+                        limit: maximum_storage_bytes,
+                        size,
+                    }
+                );
                 callback.respond(());
             }
 
@@ -380,32 +401,59 @@ where
                     .system
                     .current_committee()
                     .ok_or_else(|| ExecutionError::UnauthorizedHttpRequest(url.clone()))?;
-                let allowed_hosts = &committee.policy().http_request_allow_list;
+                let committee_policy = committee.policy();
+                let local_policy = self
+                    .context()
+                    .extra()
+                    .execution_runtime_config()
+                    .http_request_policy;
 
                 ensure!(
-                    allowed_hosts.contains(host),
+                    local_policy.allows_host(committee_policy, host),
                     ExecutionError::UnauthorizedHttpRequest(url)
                 );
 
                 #[cfg_attr(web, allow(unused_mut))]
-                let mut request = Client::new()
+                let mut client = Client::builder();
+                #[cfg(not(web))]
+                {
+                    client = client.redirect(redirect_policy(&local_policy, committee_policy));
+                }
+                #[cfg_attr(web, allow(unused_mut))]
+                let mut request = client
+                    .build()?
                     .request(request.method.into(), url)
                     .body(request.body)
                     .headers(headers);
                 #[cfg(not(web))]
+                let timeout = local_policy.timeout(committee_policy);
+                #[cfg(not(web))]
                 {
-                    request = request.timeout(Duration::from_millis(
-                        committee.policy().http_request_timeout_ms,
-                    ));
+                    request = request.timeout(timeout);
                 }
 
-                let response = request.send().await?;
-
-                let mut response_size_limit = committee.policy().maximum_http_response_bytes;
+                let response = request.send().await.map_err(|error| {
+                    #[cfg(not(web))]
+                    if error.is_timeout() {
+                        return ExecutionError::HttpRequestTimedOut {
+                            timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+                        };
+                    }
+                    #[cfg(not(web))]
+                    if let Some(error) = error
+                        .source()
+                        .and_then(|source| source.downcast_ref::<HttpRedirectError>())
+                    {
+                        return error.clone().into();
+                    }
+                    ExecutionError::from(error)
+                })?;
+
+                let mut response_size_limit = local_policy.maximum_response_bytes(committee_policy);
 
                 if http_responses_are_oracle_responses {
                     response_size_limit =
-                        response_size_limit.min(committee.policy().maximum_oracle_response_bytes);
+                        response_size_limit.min(committee_policy.maximum_oracle_response_bytes);
                 }
 
                 callback.respond(
@@ -456,6 +504,36 @@ where
                 callback.respond(event_value);
             }
 
+            CheckSubscribedEvents {
+                chain_id,
+                stream_id,
+                subscriber_app_id,
+                indices,
+                callback,
+            } => {
+                let subscriptions = self
+                    .system
+                    .event_subscriptions
+                    .get(&(chain_id, stream_id.clone()))
+                    .await?
+                    .unwrap_or_default();
+                ensure!(
+                    subscriptions.applications.contains(&subscriber_app_id),
+                    ExecutionError::NotSubscribedToEventStream(chain_id, stream_id)
+                );
+                // Only events that this chain has already processed may be read, so the result
+                // does not depend on which events happen to be in the validator's storage.
+                ensure!(
+                    indices.start <= indices.end && indices.end <= subscriptions.next_index,
+                    ExecutionError::EventRangeNotAvailable {
+                        start: indices.start,
+                        end: indices.end,
+                        next_index: subscriptions.next_index,
+                    }
+                );
+                callback.respond(());
+            }
+
             SubscribeToEvents {
                 chain_id,
                 stream_id,
@@ -569,6 +647,60 @@ where
     }
 }
 
+/// The reason why the runtime refused to follow an HTTP redirect.
+#[cfg(not(web))]
+#[derive(Clone, Debug, thiserror::Error)]
+enum HttpRedirectError {
+    #[error("HTTP request was redirected more than {0} times")]
+    TooManyRedirects(usize),
+    #[error("HTTP request was redirected to an unauthorized host: {0}")]
+    UnauthorizedHost(Url),
+}
+
+#[cfg(not(web))]
+impl From<HttpRedirectError> for ExecutionError {
+    fn from(error: HttpRedirectError) -> Self {
+        match error {
+            HttpRedirectError::TooManyRedirects(limit) => {
+                ExecutionError::TooManyHttpRedirects { limit }
+            }
+            HttpRedirectError::UnauthorizedHost(url) => {
+                ExecutionError::UnauthorizedHttpRequest(url)
+            }
+        }
+    }
+}
+
+/// Returns the [`reqwest::redirect::Policy`] enforcing the node's [`HttpRedirectPolicy`], and
+/// ensuring that redirect targets are allowed hosts.
+#[cfg(not(web))]
+fn redirect_policy(
+    local_policy: &HttpRequestPolicy,
+    committee_policy: &ResourceControlPolicy,
+) -> reqwest::redirect::Policy {
+    let limit = match local_policy.redirect_policy {
+        HttpRedirectPolicy::None => return reqwest::redirect::Policy::none(),
+        HttpRedirectPolicy::Limited(limit) => limit,
+    };
+    let local_policy = local_policy.clone();
+    let committee_policy = committee_policy.clone();
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > limit {
+            return attempt.error(HttpRedirectError::TooManyRedirects(limit));
+        }
+        let is_allowed = attempt
+            .url()
+            .host_str()
+            .is_some_and(|host| local_policy.allows_host(&committee_policy, host));
+        if is_allowed {
+            attempt.follow()
+        } else {
+            let url = attempt.url().clone();
+            attempt.error(HttpRedirectError::UnauthorizedHost(url))
+        }
+    })
+}
+
 /// Requests to the execution state.
 #[derive(Debug)]
 pub enum ExecutionRequest {
@@ -611,6 +743,12 @@ pub enum ExecutionRequest {
         callback: Sender<Vec<AccountOwner>>,
     },
 
+    ConfigValue {
+        key: String,
+        #[debug(skip)]
+        callback: Sender<Option<Vec<u8>>>,
+    },
+
     Transfer {
         source: AccountOwner,
         destination: Account,
@@ -692,6 +830,7 @@ pub enum ExecutionRequest {
     WriteBatch {
         id: ApplicationId,
         batch: Batch,
+        maximum_storage_bytes: u64,
         #[debug(skip)]
         callback: Sender<()>,
     },
@@ -765,6 +904,15 @@ pub enum ExecutionRequest {
         callback: oneshot::Sender<Vec<u8>>,
     },
 
+    CheckSubscribedEvents {
+        chain_id: ChainId,
+        stream_id: StreamId,
+        subscriber_app_id: ApplicationId,
+        indices: Range<u32>,
+        #[debug(skip)]
+        callback: Sender<()>,
+    },
+
     SubscribeToEvents {
         chain_id: ChainId,
         stream_id: StreamId,
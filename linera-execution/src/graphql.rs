@@ -14,12 +14,13 @@ use linera_views::{context::Context, map_view::MapView};
 
 use crate::{
     committee::{Committee, ValidatorState},
-    system::{Recipient, UserData},
+    system::{NameTarget, Recipient, UserData},
     ExecutionStateView, SystemExecutionStateView,
 };
 
 doc_scalar!(Recipient, "The recipient of a transfer");
 doc_scalar!(UserData, "Optional user message attached to a transfer");
+doc_scalar!(NameTarget, "What a registered name resolves to");
 
 #[async_graphql::Object(cache_control(no_cache))]
 impl Committee {
@@ -93,4 +94,16 @@ impl<C: Send + Sync + Context> SystemExecutionStateView<C> {
     async fn _timestamp(&self) -> &Timestamp {
         self.timestamp.get()
     }
+
+    /// Returns what the given name currently resolves to, if it is registered on this chain.
+    #[graphql(name = "resolveName")]
+    async fn _resolve_name(&self, name: String) -> async_graphql::Result<Option<NameTarget>> {
+        Ok(self.resolve_name(&name, *self.timestamp.get()).await?)
+    }
+
+    /// Returns the configuration value set by the chain owners for the given key, if any.
+    #[graphql(name = "configValue")]
+    async fn _config_value(&self, key: String) -> async_graphql::Result<Option<Vec<u8>>> {
+        Ok(self.config.get(&key).await?)
+    }
 }
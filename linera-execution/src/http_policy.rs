@@ -0,0 +1,116 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Node-local restrictions on the HTTP requests performed by applications.
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use crate::ResourceControlPolicy;
+
+/// The maximum number of redirects followed by default, matching `reqwest`'s default.
+const DEFAULT_MAXIMUM_HTTP_REDIRECTS: usize = 10;
+
+/// How HTTP redirects received by the runtime are handled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HttpRedirectPolicy {
+    /// Redirects are never followed: the redirect response is returned to the application.
+    None,
+    /// At most this many redirects are followed. Every redirect target must be an allowed host.
+    Limited(usize),
+}
+
+impl Default for HttpRedirectPolicy {
+    fn default() -> Self {
+        HttpRedirectPolicy::Limited(DEFAULT_MAXIMUM_HTTP_REDIRECTS)
+    }
+}
+
+/// The node-local policy for HTTP requests performed by applications.
+///
+/// This is applied on top of the committee's [`ResourceControlPolicy`]: a request must satisfy
+/// both, so a node operator may only tighten the limits agreed on by the committee.
+#[derive(Clone, Debug, Default)]
+pub struct HttpRequestPolicy {
+    /// If set, applications may only contact these hosts.
+    pub allowed_hosts: Option<Arc<BTreeSet<String>>>,
+    /// If set, the maximum size in bytes of a received HTTP response.
+    pub maximum_response_bytes: Option<u64>,
+    /// If set, the maximum amount of time allowed to wait for an HTTP response.
+    pub timeout: Option<Duration>,
+    /// How redirects are handled.
+    pub redirect_policy: HttpRedirectPolicy,
+}
+
+impl HttpRequestPolicy {
+    /// Returns whether `host` may be contacted, according to this policy and the committee's.
+    pub fn allows_host(&self, committee_policy: &ResourceControlPolicy, host: &str) -> bool {
+        committee_policy.http_request_allow_list.contains(host)
+            && self
+                .allowed_hosts
+                .as_ref()
+                .is_none_or(|allowed_hosts| allowed_hosts.contains(host))
+    }
+
+    /// Returns the maximum size in bytes of an HTTP response.
+    pub fn maximum_response_bytes(&self, committee_policy: &ResourceControlPolicy) -> u64 {
+        let limit = committee_policy.maximum_http_response_bytes;
+        self.maximum_response_bytes
+            .map_or(limit, |local_limit| local_limit.min(limit))
+    }
+
+    /// Returns the maximum amount of time to wait for an HTTP response.
+    pub fn timeout(&self, committee_policy: &ResourceControlPolicy) -> Duration {
+        let timeout = Duration::from_millis(committee_policy.http_request_timeout_ms);
+        self.timeout
+            .map_or(timeout, |local_timeout| local_timeout.min(timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+    use super::HttpRequestPolicy;
+    use crate::ResourceControlPolicy;
+
+    fn committee_policy() -> ResourceControlPolicy {
+        ResourceControlPolicy {
+            maximum_http_response_bytes: 1_000,
+            http_request_timeout_ms: 2_000,
+            http_request_allow_list: BTreeSet::from(["a.com".to_owned(), "b.com".to_owned()]),
+            ..ResourceControlPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_default_policy_follows_committee() {
+        let committee_policy = committee_policy();
+        let policy = HttpRequestPolicy::default();
+        assert!(policy.allows_host(&committee_policy, "a.com"));
+        assert!(!policy.allows_host(&committee_policy, "c.com"));
+        assert_eq!(policy.maximum_response_bytes(&committee_policy), 1_000);
+        assert_eq!(policy.timeout(&committee_policy), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_local_policy_can_only_tighten_committee_policy() {
+        let committee_policy = committee_policy();
+        let policy = HttpRequestPolicy {
+            allowed_hosts: Some(Arc::new(BTreeSet::from([
+                "b.com".to_owned(),
+                "c.com".to_owned(),
+            ]))),
+            maximum_response_bytes: Some(10_000),
+            timeout: Some(Duration::from_millis(500)),
+            ..HttpRequestPolicy::default()
+        };
+        assert!(!policy.allows_host(&committee_policy, "a.com"));
+        assert!(policy.allows_host(&committee_policy, "b.com"));
+        assert!(!policy.allows_host(&committee_policy, "c.com"));
+        assert_eq!(policy.maximum_response_bytes(&committee_policy), 1_000);
+        assert_eq!(
+            policy.timeout(&committee_policy),
+            Duration::from_millis(500)
+        );
+    }
+}
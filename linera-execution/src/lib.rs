@@ -11,9 +11,12 @@ pub mod evm;
 mod execution;
 mod execution_state_actor;
 mod graphql;
+mod http_policy;
 mod policy;
 mod resources;
 mod runtime;
+#[cfg(with_query_sandbox)]
+pub mod sandbox;
 pub mod system;
 #[cfg(with_testing)]
 pub mod test_utils;
@@ -21,7 +24,7 @@ mod transaction_tracker;
 mod util;
 mod wasm;
 
-use std::{any::Any, fmt, str::FromStr, sync::Arc};
+use std::{any::Any, fmt, ops::Range, str::FromStr, sync::Arc};
 
 use async_graphql::SimpleObject;
 use async_trait::async_trait;
@@ -32,7 +35,7 @@ use derive_more::Display;
 use js_sys::wasm_bindgen::JsValue;
 use linera_base::{
     abi::Abi,
-    crypto::{BcsHashable, CryptoHash},
+    crypto::{AccountPublicKey, AccountSignature, BcsHashable, CryptoError, CryptoHash},
     data_types::{
         Amount, ApplicationDescription, ApplicationPermissions, ArithmeticError, Blob, BlockHeight,
         DecompressionError, Epoch, SendMessageRequest, StreamUpdate, Timestamp,
@@ -40,29 +43,35 @@ use linera_base::{
     doc_scalar, hex_debug, http,
     identifiers::{
         Account, AccountOwner, ApplicationId, BlobId, BlobType, ChainId, EventId,
-        GenericApplicationId, MessageId, ModuleId, StreamName,
+        GenericApplicationId, MessageId, ModuleId, StreamId, StreamName,
     },
     ownership::ChainOwnership,
     task,
 };
 use linera_views::{batch::Batch, views::ViewError};
 use serde::{Deserialize, Serialize};
-use system::AdminOperation;
+use system::{
+    AdminOperation, FeeSponsorship, MAXIMUM_CONFIG_KEY_LEN, MAXIMUM_CONFIG_VALUE_LEN,
+    MAXIMUM_MEMO_LEN,
+};
 use thiserror::Error;
 
 #[cfg(with_revm)]
 use crate::evm::EvmExecutionError;
 use crate::runtime::ContractSyncRuntime;
+#[cfg(with_query_sandbox)]
+use crate::sandbox::QuerySandboxPool;
 #[cfg(all(with_testing, with_wasm_runtime))]
 pub use crate::wasm::test as wasm_test;
 #[cfg(with_wasm_runtime)]
 pub use crate::wasm::{
     BaseRuntimeApi, ContractEntrypoints, ContractRuntimeApi, RuntimeApiData, ServiceEntrypoints,
-    ServiceRuntimeApi, WasmContractModule, WasmExecutionError, WasmServiceModule,
+    ServiceRuntimeApi, WasmBinaryKind, WasmContractModule, WasmExecutionError, WasmServiceModule,
 };
 pub use crate::{
     execution::{ExecutionStateView, ServiceRuntimeEndpoint},
     execution_state_actor::ExecutionRequest,
+    http_policy::{HttpRedirectPolicy, HttpRequestPolicy},
     policy::ResourceControlPolicy,
     resources::{ResourceController, ResourceTracker},
     runtime::{
@@ -72,7 +81,10 @@ pub use crate::{
     system::{
         SystemExecutionStateView, SystemMessage, SystemOperation, SystemQuery, SystemResponse,
     },
-    transaction_tracker::{TransactionOutcome, TransactionTracker},
+    transaction_tracker::{
+        ContractLog, LogLevel, TransactionOutcome, TransactionTracker, MAX_LOG_MESSAGE_LEN,
+        MAX_TRANSACTION_LOGS,
+    },
 };
 
 /// The `linera.sol` library code to be included in solidity smart
@@ -236,6 +248,15 @@ pub enum ExecutionError {
     BlockTooLarge,
     #[error("HTTP response exceeds the size limit of {limit} bytes, having at least {size} bytes")]
     HttpResponseSizeLimitExceeded { limit: u64, size: u64 },
+    #[error(
+        "Application {application_id} exceeds its storage quota of {limit} bytes, \
+        having {size} bytes"
+    )]
+    ApplicationStorageQuotaExceeded {
+        application_id: ApplicationId,
+        limit: u64,
+        size: u64,
+    },
     #[error("Runtime failed to respond to application")]
     MissingRuntimeResponse,
     #[error("Module ID {0:?} is invalid")]
@@ -254,6 +275,10 @@ pub enum ExecutionError {
     JsonError(#[from] serde_json::Error),
     #[error(transparent)]
     BcsError(#[from] bcs::Error),
+    #[error("Failed to communicate with the query sandbox: {0}")]
+    QuerySandbox(String),
+    #[error("Query failed in the sandbox: {0}")]
+    SandboxedQueryFailed(String),
     #[error("Recorded response for oracle query has the wrong type")]
     OracleResponseMismatch,
     #[error("Assertion failed: local time {local_time} is not earlier than {timestamp}")]
@@ -272,6 +297,10 @@ pub enum ExecutionError {
     UnauthorizedHttpRequest(reqwest::Url),
     #[error("Attempt to perform an HTTP request to an invalid URL")]
     InvalidUrlForHttpRequest(#[from] url::ParseError),
+    #[error("HTTP request timed out after {timeout_ms} ms")]
+    HttpRequestTimedOut { timeout_ms: u64 },
+    #[error("HTTP request was redirected more than {limit} times")]
+    TooManyHttpRedirects { limit: usize },
     #[error("Failed to send contract code to worker thread: {0:?}")]
     ContractModuleSend(#[from] linera_base::task::SendError<UserContractCode>),
     #[error("Failed to send service code to worker thread: {0:?}")]
@@ -311,6 +340,8 @@ pub enum ExecutionError {
     InvalidCommitteeEpoch { expected: Epoch, provided: Epoch },
     #[error("Failed to remove committee")]
     InvalidCommitteeRemoval,
+    #[error("Invalid BLS keys in the committee: {0}")]
+    InvalidCommitteeBlsKeys(CryptoError),
     #[error("Amount overflow")]
     AmountOverflow,
     #[error("Amount underflow")]
@@ -335,6 +366,49 @@ pub enum ExecutionError {
     EventNotFound(EventId),
     #[error("UpdateStreams is outdated")]
     OutdatedUpdateStreams,
+    #[error("Application is not subscribed to event stream {1:?} on chain {0}")]
+    NotSubscribedToEventStream(ChainId, StreamId),
+    #[error(
+        "Events {start}..{end} are not available: only {next_index} events of the stream \
+        have been processed"
+    )]
+    EventRangeNotAvailable {
+        start: u32,
+        end: u32,
+        next_index: u32,
+    },
+    #[error("Invalid fee sponsorship")]
+    InvalidFeeSponsorship,
+    #[error("Fee sponsorship expired at {0}")]
+    ExpiredFeeSponsorship(Timestamp),
+    #[error("SponsorFees is only allowed as the first operation of a block")]
+    MisplacedFeeSponsorship,
+    #[error("Invalid name {0:?}")]
+    InvalidName(String),
+    #[error("Name {0:?} is already registered")]
+    NameAlreadyRegistered(String),
+    #[error("Name {0:?} is not registered")]
+    NameNotFound(String),
+    #[error("Name registrations and transfers must be authenticated by the name's owner")]
+    UnauthenticatedNameOwner,
+    #[error("Configuration changes must be authenticated by a chain owner")]
+    UnauthenticatedConfigChange,
+    #[error(
+        "Invalid configuration key {0:?}: keys must be non-empty and at most \
+        {MAXIMUM_CONFIG_KEY_LEN} bytes"
+    )]
+    InvalidConfigKey(String),
+    #[error(
+        "The configuration value for {key:?} has {size} bytes, but at most \
+        {MAXIMUM_CONFIG_VALUE_LEN} are allowed"
+    )]
+    ConfigValueTooLarge { key: String, size: usize },
+    #[error("Invalid name registration expiry: {0}")]
+    InvalidNameExpiry(Timestamp),
+    #[error("Names can only be registered on the admin chain")]
+    NameServiceOnNonAdminChain,
+    #[error("The transfer's memo has {0} bytes, but at most {MAXIMUM_MEMO_LEN} are allowed")]
+    MemoTooLarge(usize),
 }
 
 impl From<ViewError> for ExecutionError {
@@ -371,8 +445,25 @@ pub trait UserService {
 }
 
 /// Configuration options for the execution runtime available to applications.
-#[derive(Clone, Copy, Default)]
-pub struct ExecutionRuntimeConfig {}
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionRuntimeConfig {
+    /// The node-local policy for HTTP requests performed by applications.
+    pub http_request_policy: HttpRequestPolicy,
+    /// The pool of sandboxed processes to run service queries in, if any. Otherwise
+    /// queries run in the current process.
+    #[cfg(with_query_sandbox)]
+    pub query_sandbox: Option<Arc<QuerySandboxPool>>,
+}
+
+impl From<HttpRequestPolicy> for ExecutionRuntimeConfig {
+    fn from(http_request_policy: HttpRequestPolicy) -> Self {
+        ExecutionRuntimeConfig {
+            http_request_policy,
+            #[cfg(with_query_sandbox)]
+            query_sandbox: None,
+        }
+    }
+}
 
 /// Requirements for the `extra` field in our state views (and notably the
 /// [`ExecutionStateView`]).
@@ -507,7 +598,7 @@ pub struct FinalizeContext {
     pub round: Option<u32>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct QueryContext {
     /// The current chain ID.
     pub chain_id: ChainId,
@@ -556,6 +647,9 @@ pub trait BaseRuntime {
     /// Reads balance owners.
     fn read_balance_owners(&mut self) -> Result<Vec<AccountOwner>, ExecutionError>;
 
+    /// Reads a configuration value set by the chain owners.
+    fn read_config_value(&mut self, key: String) -> Result<Option<Vec<u8>>, ExecutionError>;
+
     /// Reads the current ownership configuration for this chain.
     fn chain_ownership(&mut self) -> Result<ChainOwnership, ExecutionError>;
 
@@ -686,6 +780,9 @@ pub trait BaseRuntime {
 
     /// Asserts the existence of a data blob with the given hash.
     fn assert_data_blob_exists(&mut self, hash: &CryptoHash) -> Result<(), ExecutionError>;
+
+    /// Captures a message logged by the current application in the transaction's outcome.
+    fn capture_log(&mut self, level: LogLevel, message: String) -> Result<(), ExecutionError>;
 }
 
 pub trait ServiceRuntime: BaseRuntime {
@@ -765,6 +862,18 @@ pub trait ContractRuntime: BaseRuntime {
         index: u32,
     ) -> Result<Vec<u8>, ExecutionError>;
 
+    /// Reads the events with the given indices from a stream this application is subscribed
+    /// to. Returns the events' values.
+    ///
+    /// Returns an error if the range goes beyond the events this chain has already processed.
+    fn read_subscribed_events(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        stream_name: StreamName,
+        indices: Range<u32>,
+    ) -> Result<Vec<Vec<u8>>, ExecutionError>;
+
     /// Subscribes this application to an event stream.
     fn subscribe_to_events(
         &mut self,
@@ -1021,7 +1130,7 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
     }
 
     fn execution_runtime_config(&self) -> ExecutionRuntimeConfig {
-        self.execution_runtime_config
+        self.execution_runtime_config.clone()
     }
 
     fn user_contracts(&self) -> &Arc<DashMap<ApplicationId, UserContractCode>> {
@@ -1172,6 +1281,20 @@ impl Operation {
         }
     }
 
+    /// Returns the fee sponsorship in this operation, if any.
+    pub fn as_fee_sponsorship(
+        &self,
+    ) -> Option<(&FeeSponsorship, AccountPublicKey, &AccountSignature)> {
+        match self.as_system_operation() {
+            Some(SystemOperation::SponsorFees {
+                sponsorship,
+                public_key,
+                signature,
+            }) => Some((sponsorship, *public_key, signature)),
+            _ => None,
+        }
+    }
+
     /// Returns whether this operation is allowed regardless of application permissions.
     pub fn is_exempt_from_permissions(&self) -> bool {
         let Operation::System(system_op) = self else {
@@ -1182,6 +1305,7 @@ impl Operation {
             SystemOperation::ProcessNewEpoch(_)
                 | SystemOperation::ProcessRemovedEpoch(_)
                 | SystemOperation::UpdateStreams(_)
+                | SystemOperation::SponsorFees { .. }
         )
     }
 }
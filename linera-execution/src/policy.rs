@@ -3,10 +3,11 @@
 
 //! This module contains types related to fees and pricing.
 
-use std::{collections::BTreeSet, fmt};
+use std::{borrow::Cow, collections::BTreeSet, fmt};
 
 use async_graphql::InputObject;
 use linera_base::{
+    appended,
     data_types::{Amount, ArithmeticError, BlobContent, CompressedBytecode, Resources},
     ensure,
     identifiers::BlobType,
@@ -16,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use crate::ExecutionError;
 
 /// A collection of prices and limits associated with block execution.
-#[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject)]
+#[derive(Eq, PartialEq, Hash, Clone, Debug, InputObject)]
 pub struct ResourceControlPolicy {
     /// The base price for creating a new block.
     pub block: Amount,
@@ -83,6 +84,245 @@ pub struct ResourceControlPolicy {
     pub http_request_timeout_ms: u64,
     /// The list of hosts that contracts and services can send HTTP requests to.
     pub http_request_allow_list: BTreeSet<String>,
+    /// The price of registering or renewing a name on the admin chain.
+    pub name_registration: Amount,
+    /// The maximum size in bytes of the state of a single application on a chain.
+    pub maximum_application_storage_bytes: u64,
+}
+
+impl Serialize for ResourceControlPolicy {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        let (fields, appended) = self.split();
+        if serializer.is_human_readable() {
+            let ResourceControlPolicyAppended::V1 {
+                name_registration,
+                maximum_application_storage_bytes,
+            } = appended;
+            PolicyFull {
+                fields,
+                name_registration,
+                maximum_application_storage_bytes,
+            }
+            .serialize(serializer)
+        } else {
+            appended::serialize(&fields, &appended, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceControlPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let PolicyFull {
+                fields,
+                name_registration,
+                maximum_application_storage_bytes,
+            } = PolicyFull::deserialize(deserializer)?;
+            let appended = ResourceControlPolicyAppended::V1 {
+                name_registration,
+                maximum_application_storage_bytes,
+            };
+            Ok(ResourceControlPolicy::from_fields(fields, Some(appended)))
+        } else {
+            let (fields, appended) = appended::deserialize(deserializer)?;
+            Ok(ResourceControlPolicy::from_fields(fields, appended))
+        }
+    }
+}
+
+/// The fields of a [`ResourceControlPolicy`] that were serialized before others were
+/// appended.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ResourceControlPolicy")]
+pub(crate) struct PolicyMinimal<'a> {
+    block: Amount,
+    fuel_unit: Amount,
+    read_operation: Amount,
+    write_operation: Amount,
+    byte_read: Amount,
+    byte_written: Amount,
+    blob_read: Amount,
+    blob_published: Amount,
+    blob_byte_read: Amount,
+    blob_byte_published: Amount,
+    byte_stored: Amount,
+    operation: Amount,
+    operation_byte: Amount,
+    message: Amount,
+    message_byte: Amount,
+    service_as_oracle_query: Amount,
+    http_request: Amount,
+    maximum_fuel_per_block: u64,
+    maximum_service_oracle_execution_ms: u64,
+    maximum_block_size: u64,
+    maximum_bytecode_size: u64,
+    maximum_blob_size: u64,
+    maximum_published_blobs: u64,
+    maximum_block_proposal_size: u64,
+    maximum_bytes_read_per_block: u64,
+    maximum_bytes_written_per_block: u64,
+    maximum_oracle_response_bytes: u64,
+    maximum_http_response_bytes: u64,
+    http_request_timeout_ms: u64,
+    http_request_allow_list: Cow<'a, BTreeSet<String>>,
+}
+
+/// The human-readable serialization of a [`ResourceControlPolicy`], where the appended
+/// fields have defaults.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ResourceControlPolicy")]
+struct PolicyFull<'a> {
+    #[serde(flatten)]
+    fields: PolicyMinimal<'a>,
+    #[serde(default)]
+    name_registration: Amount,
+    #[serde(default = "unlimited")]
+    maximum_application_storage_bytes: u64,
+}
+
+/// Returns the default of the limits appended to policies.
+fn unlimited() -> u64 {
+    u64::MAX
+}
+
+/// The fields appended to the BCS serialization of policies, by version.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum ResourceControlPolicyAppended {
+    /// The price of names on the admin chain, and the limit on the storage of applications.
+    V1 {
+        name_registration: Amount,
+        maximum_application_storage_bytes: u64,
+    },
+}
+
+impl ResourceControlPolicy {
+    /// Returns the original and the appended fields of this policy, to be serialized.
+    pub(crate) fn split(&self) -> (PolicyMinimal<'_>, ResourceControlPolicyAppended) {
+        let fields = PolicyMinimal {
+            block: self.block,
+            fuel_unit: self.fuel_unit,
+            read_operation: self.read_operation,
+            write_operation: self.write_operation,
+            byte_read: self.byte_read,
+            byte_written: self.byte_written,
+            blob_read: self.blob_read,
+            blob_published: self.blob_published,
+            blob_byte_read: self.blob_byte_read,
+            blob_byte_published: self.blob_byte_published,
+            byte_stored: self.byte_stored,
+            operation: self.operation,
+            operation_byte: self.operation_byte,
+            message: self.message,
+            message_byte: self.message_byte,
+            service_as_oracle_query: self.service_as_oracle_query,
+            http_request: self.http_request,
+            maximum_fuel_per_block: self.maximum_fuel_per_block,
+            maximum_service_oracle_execution_ms: self.maximum_service_oracle_execution_ms,
+            maximum_block_size: self.maximum_block_size,
+            maximum_bytecode_size: self.maximum_bytecode_size,
+            maximum_blob_size: self.maximum_blob_size,
+            maximum_published_blobs: self.maximum_published_blobs,
+            maximum_block_proposal_size: self.maximum_block_proposal_size,
+            maximum_bytes_read_per_block: self.maximum_bytes_read_per_block,
+            maximum_bytes_written_per_block: self.maximum_bytes_written_per_block,
+            maximum_oracle_response_bytes: self.maximum_oracle_response_bytes,
+            maximum_http_response_bytes: self.maximum_http_response_bytes,
+            http_request_timeout_ms: self.http_request_timeout_ms,
+            http_request_allow_list: Cow::Borrowed(&self.http_request_allow_list),
+        };
+        let appended = ResourceControlPolicyAppended::V1 {
+            name_registration: self.name_registration,
+            maximum_application_storage_bytes: self.maximum_application_storage_bytes,
+        };
+        (fields, appended)
+    }
+
+    /// Creates a policy from its original fields and the appended ones, if any. Names are
+    /// free and the storage of applications is unlimited in policies without appended fields.
+    pub(crate) fn from_fields(
+        fields: PolicyMinimal<'_>,
+        appended: Option<ResourceControlPolicyAppended>,
+    ) -> Self {
+        let PolicyMinimal {
+            block,
+            fuel_unit,
+            read_operation,
+            write_operation,
+            byte_read,
+            byte_written,
+            blob_read,
+            blob_published,
+            blob_byte_read,
+            blob_byte_published,
+            byte_stored,
+            operation,
+            operation_byte,
+            message,
+            message_byte,
+            service_as_oracle_query,
+            http_request,
+            maximum_fuel_per_block,
+            maximum_service_oracle_execution_ms,
+            maximum_block_size,
+            maximum_bytecode_size,
+            maximum_blob_size,
+            maximum_published_blobs,
+            maximum_block_proposal_size,
+            maximum_bytes_read_per_block,
+            maximum_bytes_written_per_block,
+            maximum_oracle_response_bytes,
+            maximum_http_response_bytes,
+            http_request_timeout_ms,
+            http_request_allow_list,
+        } = fields;
+        let ResourceControlPolicyAppended::V1 {
+            name_registration,
+            maximum_application_storage_bytes,
+        } = appended.unwrap_or(ResourceControlPolicyAppended::V1 {
+            name_registration: Amount::ZERO,
+            maximum_application_storage_bytes: unlimited(),
+        });
+        ResourceControlPolicy {
+            block,
+            fuel_unit,
+            read_operation,
+            write_operation,
+            byte_read,
+            byte_written,
+            blob_read,
+            blob_published,
+            blob_byte_read,
+            blob_byte_published,
+            byte_stored,
+            operation,
+            operation_byte,
+            message,
+            message_byte,
+            service_as_oracle_query,
+            http_request,
+            maximum_fuel_per_block,
+            maximum_service_oracle_execution_ms,
+            maximum_block_size,
+            maximum_bytecode_size,
+            maximum_blob_size,
+            maximum_published_blobs,
+            maximum_block_proposal_size,
+            maximum_bytes_read_per_block,
+            maximum_bytes_written_per_block,
+            maximum_oracle_response_bytes,
+            maximum_http_response_bytes,
+            http_request_timeout_ms,
+            http_request_allow_list: http_request_allow_list.into_owned(),
+            name_registration,
+            maximum_application_storage_bytes,
+        }
+    }
 }
 
 impl fmt::Display for ResourceControlPolicy {
@@ -105,6 +345,7 @@ impl fmt::Display for ResourceControlPolicy {
             message_byte,
             service_as_oracle_query,
             http_request,
+            name_registration,
             maximum_fuel_per_block,
             maximum_service_oracle_execution_ms,
             maximum_block_size,
@@ -116,6 +357,7 @@ impl fmt::Display for ResourceControlPolicy {
             maximum_bytes_written_per_block,
             maximum_oracle_response_bytes,
             maximum_http_response_bytes,
+            maximum_application_storage_bytes,
             http_request_allow_list,
             http_request_timeout_ms,
         } = self;
@@ -139,6 +381,7 @@ impl fmt::Display for ResourceControlPolicy {
             {message:.2} per outgoing messages\n\
             {message_byte:.2} per byte in the argument of an outgoing messages\n\
             {http_request:.2} per HTTP request performed\n\
+            {name_registration:.2} per name registered or renewed\n\
             {maximum_fuel_per_block} maximum fuel per block\n\
             {maximum_service_oracle_execution_ms} ms maximum service-as-oracle execution time per \
                 block\n\
@@ -151,6 +394,7 @@ impl fmt::Display for ResourceControlPolicy {
             {maximum_bytes_written_per_block} maximum number of bytes written per block\n\
             {maximum_oracle_response_bytes} maximum number of bytes of an oracle response\n\
             {maximum_http_response_bytes} maximum number of bytes of an HTTP response\n\
+            {maximum_application_storage_bytes} maximum number of bytes stored per application\n\
             {http_request_timeout_ms} ms timeout for HTTP requests\n\
             HTTP hosts allowed for contracts and services: {http_request_allow_list:#?}\n",
         )?;
@@ -200,6 +444,8 @@ impl ResourceControlPolicy {
             maximum_http_response_bytes: u64::MAX,
             http_request_timeout_ms: u64::MAX,
             http_request_allow_list: BTreeSet::new(),
+            name_registration: Amount::ZERO,
+            maximum_application_storage_bytes: u64::MAX,
         }
     }
 
@@ -243,6 +489,7 @@ impl ResourceControlPolicy {
             message: Amount::from_attos(10),
             message_byte: Amount::from_attos(1),
             http_request: Amount::from_micros(1),
+            name_registration: Amount::from_micros(1),
             ..Self::no_fees()
         }
     }
@@ -280,6 +527,8 @@ impl ResourceControlPolicy {
             maximum_http_response_bytes: 10_000,
             http_request_timeout_ms: 20_000,
             http_request_allow_list: BTreeSet::new(),
+            name_registration: Amount::from_tokens(1),
+            maximum_application_storage_bytes: 100_000_000,
         }
     }
 
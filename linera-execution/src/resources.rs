@@ -69,6 +69,8 @@ pub struct ResourceTracker {
     pub service_oracle_execution: Duration,
     /// The amount allocated to message grants.
     pub grants: Amount,
+    /// The fees charged so far, not including the grants.
+    pub fees: Amount,
 }
 
 /// How to access the balance of an account.
@@ -78,6 +80,11 @@ pub trait BalanceHolder {
     fn try_add_assign(&mut self, other: Amount) -> Result<(), ArithmeticError>;
 
     fn try_sub_assign(&mut self, other: Amount) -> Result<(), ArithmeticError>;
+
+    /// Subtracts fees, which may also be paid with funds that are reserved for fees.
+    fn try_sub_assign_fees(&mut self, other: Amount) -> Result<(), ArithmeticError> {
+        self.try_sub_assign(other)
+    }
 }
 
 // The main accounting functions for a ResourceController.
@@ -93,11 +100,20 @@ where
     }
 
     /// Operates a 3-way merge by transferring the difference between `initial`
-    /// and `other` to `self`.
-    pub fn merge_balance(&mut self, initial: Amount, other: Amount) -> Result<(), ExecutionError> {
+    /// and `other` to `self`. Up to `fees` of that difference are charged as fees.
+    pub fn merge_balance(
+        &mut self,
+        initial: Amount,
+        other: Amount,
+        fees: Amount,
+    ) -> Result<(), ExecutionError> {
         if other <= initial {
+            let spent = initial.try_sub(other).expect("other <= initial");
+            let fees = fees.min(spent);
+            let other_costs = spent.try_sub(fees).expect("fees <= spent");
             self.account
-                .try_sub_assign(initial.try_sub(other).expect("other <= initial"))
+                .try_sub_assign_fees(fees)
+                .and_then(|()| self.account.try_sub_assign(other_costs))
                 .map_err(|_| ExecutionError::InsufficientFundingForFees {
                     balance: self.balance().unwrap_or(Amount::MAX),
                 })?;
@@ -108,9 +124,20 @@ where
         Ok(())
     }
 
-    /// Subtracts an amount from a balance and reports an error if that is impossible.
+    /// Charges the given fees.
     fn update_balance(&mut self, fees: Amount) -> Result<(), ExecutionError> {
-        self.account.try_sub_assign(fees).map_err(|_| {
+        self.account.try_sub_assign_fees(fees).map_err(|_| {
+            ExecutionError::InsufficientFundingForFees {
+                balance: self.balance().unwrap_or(Amount::MAX),
+            }
+        })?;
+        self.tracker.as_mut().fees.try_add_assign(fees)?;
+        Ok(())
+    }
+
+    /// Subtracts an amount from a balance and reports an error if that is impossible.
+    fn subtract_from_balance(&mut self, amount: Amount) -> Result<(), ExecutionError> {
+        self.account.try_sub_assign(amount).map_err(|_| {
             ExecutionError::InsufficientFundingForFees {
                 balance: self.balance().unwrap_or(Amount::MAX),
             }
@@ -132,7 +159,7 @@ where
     /// Tracks the allocation of a grant.
     pub fn track_grant(&mut self, grant: Amount) -> Result<(), ExecutionError> {
         self.tracker.as_mut().grants.try_add_assign(grant)?;
-        self.update_balance(grant)
+        self.subtract_from_balance(grant)
     }
 
     /// Tracks the creation of a block.
@@ -336,6 +363,11 @@ where
             .ok_or(ExecutionError::MaximumServiceOracleExecutionTimeExceeded)
     }
 
+    /// Tracks the registration or renewal of a name on the admin chain.
+    pub fn track_name_registration(&mut self) -> Result<(), ExecutionError> {
+        self.update_balance(self.policy.name_registration)
+    }
+
     /// Tracks a call to a service to run as an oracle.
     pub(crate) fn track_service_oracle_call(&mut self) -> Result<(), ExecutionError> {
         self.tracker.as_mut().service_oracle_queries = self
@@ -436,6 +468,32 @@ impl AsRef<ResourceTracker> for ResourceTracker {
 /// A temporary object holding a number of references to funding sources.
 pub struct Sources<'a> {
     sources: Vec<&'a mut Amount>,
+    /// The index of the funds set aside by the block's sponsor, which only pay for fees.
+    sponsor_funds: usize,
+}
+
+impl Sources<'_> {
+    fn subtract(
+        &mut self,
+        mut other: Amount,
+        include_sponsor_funds: bool,
+    ) -> Result<(), ArithmeticError> {
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            if !include_sponsor_funds && index == self.sponsor_funds {
+                continue;
+            }
+            if source.try_sub_assign(other).is_ok() {
+                return Ok(());
+            }
+            other.try_sub_assign(**source).expect("*source < other");
+            **source = Amount::ZERO;
+        }
+        if other > Amount::ZERO {
+            Err(ArithmeticError::Underflow)
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl BalanceHolder for Sources<'_> {
@@ -454,19 +512,12 @@ impl BalanceHolder for Sources<'_> {
         source.try_add_assign(other)
     }
 
-    fn try_sub_assign(&mut self, mut other: Amount) -> Result<(), ArithmeticError> {
-        for source in self.sources.iter_mut() {
-            if source.try_sub_assign(other).is_ok() {
-                return Ok(());
-            }
-            other.try_sub_assign(**source).expect("*source < other");
-            **source = Amount::ZERO;
-        }
-        if other > Amount::ZERO {
-            Err(ArithmeticError::Underflow)
-        } else {
-            Ok(())
-        }
+    fn try_sub_assign(&mut self, other: Amount) -> Result<(), ArithmeticError> {
+        self.subtract(other, false)
+    }
+
+    fn try_sub_assign_fees(&mut self, other: Amount) -> Result<(), ArithmeticError> {
+        self.subtract(other, true)
     }
 }
 
@@ -495,11 +546,16 @@ impl ResourceController<Option<AccountOwner>, ResourceTracker> {
         C: Context + Clone + Send + Sync + 'static,
     {
         let mut sources = Vec::new();
-        // First, use the grant (e.g. for messages) and otherwise use the chain account
-        // (e.g. for blocks and operations).
+        // First, use the grant (e.g. for messages), then the funds set aside by the block's
+        // sponsor, if any, and otherwise use the chain account (e.g. for blocks and operations).
+        // The sponsor's funds only pay for fees, not for the grants of outgoing messages.
+        let has_grant = grant.is_some();
         if let Some(grant) = grant {
             sources.push(grant);
-        } else {
+        }
+        let sponsor_funds = sources.len();
+        sources.push(view.sponsor_funds.get_mut());
+        if !has_grant {
             sources.push(view.balance.get_mut());
         }
         // Then the local account, if any. Currently, any negative fee (e.g. storage
@@ -513,7 +569,10 @@ impl ResourceController<Option<AccountOwner>, ResourceTracker> {
         Ok(ResourceController {
             policy: self.policy.clone(),
             tracker: &mut self.tracker,
-            account: Sources { sources },
+            account: Sources {
+                sources,
+                sponsor_funds,
+            },
         })
     }
 }
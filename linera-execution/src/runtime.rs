@@ -4,7 +4,7 @@
 use std::{
     collections::{hash_map, BTreeMap, HashMap, HashSet},
     mem,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
     sync::{Arc, Mutex},
     time::Instant,
 };
@@ -33,10 +33,10 @@ use crate::{
     system::CreateApplicationResult,
     util::{ReceiverExt, UnboundedSenderExt},
     ApplicationDescription, ApplicationId, BaseRuntime, ContractRuntime, ExecutionError,
-    FinalizeContext, Message, MessageContext, MessageKind, ModuleId, Operation, OperationContext,
-    OutgoingMessage, QueryContext, QueryOutcome, ServiceRuntime, TransactionTracker,
-    UserContractCode, UserContractInstance, UserServiceCode, UserServiceInstance,
-    MAX_STREAM_NAME_LEN,
+    FinalizeContext, LogLevel, Message, MessageContext, MessageKind, ModuleId, Operation,
+    OperationContext, OutgoingMessage, QueryContext, QueryOutcome, ServiceRuntime,
+    TransactionTracker, UserContractCode, UserContractInstance, UserServiceCode,
+    UserServiceInstance, MAX_STREAM_NAME_LEN,
 };
 
 #[cfg(test)]
@@ -677,6 +677,13 @@ where
             .recv_response()
     }
 
+    fn read_config_value(&mut self, key: String) -> Result<Option<Vec<u8>>, ExecutionError> {
+        self.inner()
+            .execution_state_sender
+            .send_request(|callback| ExecutionRequest::ConfigValue { key, callback })?
+            .recv_response()
+    }
+
     fn chain_ownership(&mut self) -> Result<ChainOwnership, ExecutionError> {
         self.inner()
             .execution_state_sender
@@ -947,6 +954,14 @@ where
         }
         Ok(())
     }
+
+    fn capture_log(&mut self, level: LogLevel, message: String) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application_id = this.current_application().id;
+        this.transaction_tracker
+            .add_log(application_id, level, message);
+        Ok(())
+    }
 }
 
 /// An extension trait to determine in compile time the different behaviors between contract and
@@ -1355,6 +1370,59 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         Ok(event)
     }
 
+    fn read_subscribed_events(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        stream_name: StreamName,
+        indices: Range<u32>,
+    ) -> Result<Vec<Vec<u8>>, ExecutionError> {
+        let mut this = self.inner();
+        ensure!(
+            stream_name.0.len() <= MAX_STREAM_NAME_LEN,
+            ExecutionError::StreamNameTooLong
+        );
+        let stream_id = StreamId {
+            stream_name,
+            application_id: application_id.into(),
+        };
+        let subscriber_app_id = this.current_application().id;
+        this.execution_state_sender
+            .send_request(|callback| ExecutionRequest::CheckSubscribedEvents {
+                chain_id,
+                stream_id: stream_id.clone(),
+                subscriber_app_id,
+                indices: indices.clone(),
+                callback,
+            })?
+            .recv_response()?;
+        // Each event is paid for as soon as it is loaded, so that the range is bounded by the
+        // block's limits rather than read into memory at once.
+        let mut events = Vec::new();
+        for index in indices {
+            let event_id = EventId {
+                chain_id,
+                stream_id: stream_id.clone(),
+                index,
+            };
+            let event = this
+                .execution_state_sender
+                .send_request(|callback| ExecutionRequest::ReadEvent {
+                    event_id: event_id.clone(),
+                    callback,
+                })?
+                .recv_response()?;
+            // TODO(#365): Consider separate event fee categories.
+            this.resource_controller.track_read_operations(1)?;
+            this.resource_controller
+                .track_bytes_read(event.len() as u64)?;
+            this.transaction_tracker
+                .replay_oracle_response(OracleResponse::Event(event_id, event.clone()))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
     fn subscribe_to_events(
         &mut self,
         chain_id: ChainId,
@@ -1584,10 +1652,15 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         )?;
         this.resource_controller
             .track_bytes_written(batch.size() as u64)?;
+        let maximum_storage_bytes = this
+            .resource_controller
+            .policy
+            .maximum_application_storage_bytes;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::WriteBatch {
                 id,
                 batch,
+                maximum_storage_bytes,
                 callback,
             })?
             .recv_response()?;
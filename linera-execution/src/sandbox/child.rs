@@ -0,0 +1,440 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The side of the protocol running in a sandbox process.
+
+use std::collections::HashMap;
+
+use futures::{channel::mpsc, StreamExt as _};
+use linera_base::{
+    data_types::{ApplicationDescription, Bytecode, CompressedBytecode},
+    identifiers::ApplicationId,
+    vm::VmRuntime,
+};
+use tokio::io::{BufReader, Stdin, Stdout};
+
+use super::protocol::{
+    read_message, write_message, SandboxInput, SandboxOutput, SandboxRequest, SandboxResponse,
+};
+#[cfg(with_wasm_runtime)]
+use crate::WasmServiceModule;
+#[cfg(with_revm)]
+use crate::{evm::revm::EvmServiceModule, EvmRuntime};
+use crate::{
+    util::RespondExt as _, ExecutionError, ExecutionRequest, QueryContext, QueryOutcome,
+    ServiceSyncRuntime, UserServiceCode, WasmRuntime,
+};
+
+/// Serves queries over the standard input and output until the input is closed.
+///
+/// This must be called at the start of a dedicated process: the system calls a service has
+/// no use for are denied to the whole process first.
+pub async fn run_query_sandbox(wasm_runtime: Option<WasmRuntime>) -> Result<(), ExecutionError> {
+    restrict_system_calls()?;
+    let mut sandbox = Sandbox {
+        wasm_runtime,
+        services: HashMap::new(),
+        input: BufReader::new(tokio::io::stdin()),
+        output: tokio::io::stdout(),
+    };
+    while let Some(message) = read_message(&mut sandbox.input).await? {
+        let SandboxInput::Query {
+            application_id,
+            context,
+            query,
+        } = message
+        else {
+            return Err(ExecutionError::QuerySandbox(
+                "received a response while no query was running".to_string(),
+            ));
+        };
+        let outcome = sandbox
+            .run_query(application_id, context, query)
+            .await
+            .map_err(|error| error.to_string());
+        write_message(&mut sandbox.output, &SandboxOutput::Outcome(outcome)).await?;
+    }
+    Ok(())
+}
+
+/// The state of a sandbox process.
+struct Sandbox {
+    #[cfg_attr(not(with_wasm_runtime), allow(dead_code))]
+    wasm_runtime: Option<WasmRuntime>,
+    /// The services compiled by this process.
+    services: HashMap<ApplicationId, (UserServiceCode, ApplicationDescription)>,
+    input: BufReader<Stdin>,
+    output: Stdout,
+}
+
+impl Sandbox {
+    async fn run_query(
+        &mut self,
+        application_id: ApplicationId,
+        context: QueryContext,
+        query: Vec<u8>,
+    ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
+        let (execution_state_sender, mut execution_state_receiver) = mpsc::unbounded();
+        let (code, description) = self.load_service(application_id).await?;
+
+        let service_runtime_task = linera_base::task::Blocking::spawn(move |mut codes| {
+            let mut runtime = ServiceSyncRuntime::new(execution_state_sender, context);
+
+            async move {
+                let code = codes.next().await.expect("we send this immediately below");
+                runtime.preload_service(application_id, code, description)?;
+                runtime.run_query(application_id, query)
+            }
+        })
+        .await;
+
+        service_runtime_task.send(code)?;
+
+        while let Some(request) = execution_state_receiver.next().await {
+            self.handle_request(request).await?;
+        }
+
+        service_runtime_task.join().await
+    }
+
+    /// Answers a request of the runtime by asking the validator.
+    async fn handle_request(&mut self, request: ExecutionRequest) -> Result<(), ExecutionError> {
+        macro_rules! forward {
+            ($request:expr, $callback:ident, $variant:ident) => {
+                match self.request($request).await? {
+                    SandboxResponse::$variant(value) => $callback.respond(value),
+                    response => return Err(unexpected_response(&response)),
+                }
+            };
+        }
+
+        use ExecutionRequest::*;
+        match request {
+            LoadService {
+                id,
+                callback,
+                txn_tracker,
+            } => {
+                let (code, description) = self.load_service(id).await?;
+                callback.respond((code, description, txn_tracker));
+            }
+            ChainBalance { callback } => forward!(SandboxRequest::ChainBalance, callback, Amount),
+            OwnerBalance { owner, callback } => {
+                forward!(SandboxRequest::OwnerBalance { owner }, callback, Amount)
+            }
+            OwnerBalances { callback } => {
+                forward!(SandboxRequest::OwnerBalances, callback, Balances)
+            }
+            BalanceOwners { callback } => forward!(SandboxRequest::BalanceOwners, callback, Owners),
+            SystemTimestamp { callback } => {
+                forward!(SandboxRequest::SystemTimestamp, callback, Timestamp)
+            }
+            ChainOwnership { callback } => {
+                forward!(SandboxRequest::ChainOwnership, callback, ChainOwnership)
+            }
+            GetApplicationPermissions { callback } => forward!(
+                SandboxRequest::ApplicationPermissions,
+                callback,
+                ApplicationPermissions
+            ),
+            ReadValueBytes { id, key, callback } => {
+                forward!(SandboxRequest::ReadValueBytes { id, key }, callback, Value)
+            }
+            ContainsKey { id, key, callback } => {
+                forward!(SandboxRequest::ContainsKey { id, key }, callback, Bool)
+            }
+            ContainsKeys { id, keys, callback } => {
+                forward!(SandboxRequest::ContainsKeys { id, keys }, callback, Bools)
+            }
+            ReadMultiValuesBytes { id, keys, callback } => forward!(
+                SandboxRequest::ReadMultiValuesBytes { id, keys },
+                callback,
+                Values
+            ),
+            FindKeysByPrefix {
+                id,
+                key_prefix,
+                callback,
+            } => forward!(
+                SandboxRequest::FindKeysByPrefix { id, key_prefix },
+                callback,
+                Keys
+            ),
+            FindKeyValuesByPrefix {
+                id,
+                key_prefix,
+                callback,
+            } => forward!(
+                SandboxRequest::FindKeyValuesByPrefix { id, key_prefix },
+                callback,
+                KeyValues
+            ),
+            PerformHttpRequest {
+                request,
+                http_responses_are_oracle_responses,
+                callback,
+            } => forward!(
+                SandboxRequest::PerformHttpRequest {
+                    request,
+                    http_responses_are_oracle_responses,
+                },
+                callback,
+                HttpResponse
+            ),
+            ReadBlobContent { blob_id, callback } => {
+                forward!(
+                    SandboxRequest::ReadBlobContent { blob_id },
+                    callback,
+                    BlobContent
+                )
+            }
+            AssertBlobExists { blob_id, callback } => {
+                forward!(SandboxRequest::AssertBlobExists { blob_id }, callback, Bool)
+            }
+            ReadEvent { event_id, callback } => {
+                forward!(SandboxRequest::ReadEvent { event_id }, callback, Event)
+            }
+            request => {
+                // Dropping the callback makes the runtime fail with
+                // `MissingRuntimeResponse`.
+                tracing::warn!(?request, "Unsupported request in a query sandbox");
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends a request to the validator and waits for the response.
+    async fn request(
+        &mut self,
+        request: SandboxRequest,
+    ) -> Result<SandboxResponse, ExecutionError> {
+        write_message(&mut self.output, &SandboxOutput::Request(request)).await?;
+        match read_message(&mut self.input).await? {
+            Some(SandboxInput::Response(response)) => Ok(response),
+            Some(SandboxInput::Query { .. }) => Err(ExecutionError::QuerySandbox(
+                "received a query while another one was running".to_string(),
+            )),
+            None => Err(ExecutionError::QuerySandbox(
+                "the input was closed".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the compiled service of an application, requesting and compiling its
+    /// bytecode the first time.
+    async fn load_service(
+        &mut self,
+        id: ApplicationId,
+    ) -> Result<(UserServiceCode, ApplicationDescription), ExecutionError> {
+        if let Some(service) = self.services.get(&id) {
+            return Ok(service.clone());
+        }
+        let (description, compressed_bytecode) =
+            match self.request(SandboxRequest::LoadService { id }).await? {
+                SandboxResponse::Service {
+                    description,
+                    compressed_bytecode,
+                } => (*description, compressed_bytecode),
+                response => return Err(unexpected_response(&response)),
+            };
+        let bytecode = CompressedBytecode {
+            compressed_bytes: compressed_bytecode,
+        }
+        .decompress()?;
+        let code = self.compile_service(&description, bytecode).await?;
+        self.services
+            .insert(id, (code.clone(), description.clone()));
+        Ok((code, description))
+    }
+
+    #[cfg_attr(not(any(with_wasm_runtime, with_revm)), allow(unused_variables))]
+    async fn compile_service(
+        &self,
+        description: &ApplicationDescription,
+        bytecode: Bytecode,
+    ) -> Result<UserServiceCode, ExecutionError> {
+        match description.module_id.vm_runtime {
+            VmRuntime::Wasm => {
+                cfg_if::cfg_if! {
+                    if #[cfg(with_wasm_runtime)] {
+                        let wasm_runtime = self.wasm_runtime.ok_or_else(|| {
+                            ExecutionError::QuerySandbox("no Wasm runtime was selected".to_string())
+                        })?;
+                        Ok(WasmServiceModule::new(bytecode, wasm_runtime).await?.into())
+                    } else {
+                        Err(ExecutionError::QuerySandbox(
+                            "this binary was compiled without a Wasm runtime".to_string(),
+                        ))
+                    }
+                }
+            }
+            VmRuntime::Evm => {
+                cfg_if::cfg_if! {
+                    if #[cfg(with_revm)] {
+                        Ok(EvmServiceModule::new(bytecode, EvmRuntime::Revm).await?.into())
+                    } else {
+                        Err(ExecutionError::QuerySandbox(
+                            "this binary was compiled without an EVM runtime".to_string(),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn unexpected_response(response: &SandboxResponse) -> ExecutionError {
+    ExecutionError::QuerySandbox(format!("unexpected response {response:?}"))
+}
+
+/// Only allows the system calls needed by the Tokio and Wasm runtimes to manage memory,
+/// threads, signals and time, and to use the file descriptors the process already has. All
+/// other calls fail with `ENOSYS`, including the ones to open files or network connections,
+/// to start programs and to inspect other processes.
+#[cfg(target_os = "linux")]
+fn restrict_system_calls() -> Result<(), ExecutionError> {
+    use seccompiler::{
+        BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
+        SeccompRule, TargetArch,
+    };
+
+    let sandbox_error = |error: &dyn std::fmt::Display| {
+        ExecutionError::QuerySandbox(format!("failed to restrict system calls: {error}"))
+    };
+    #[cfg_attr(not(target_arch = "x86_64"), allow(unused_mut))]
+    let mut allowed_system_calls = vec![
+        // Using the standard streams and the runtimes' own file descriptors.
+        libc::SYS_read,
+        libc::SYS_readv,
+        libc::SYS_write,
+        libc::SYS_writev,
+        libc::SYS_close,
+        libc::SYS_fstat,
+        libc::SYS_fcntl,
+        libc::SYS_epoll_create1,
+        libc::SYS_epoll_ctl,
+        libc::SYS_epoll_pwait,
+        libc::SYS_epoll_pwait2,
+        libc::SYS_eventfd2,
+        libc::SYS_ppoll,
+        // Memory.
+        libc::SYS_brk,
+        libc::SYS_mmap,
+        libc::SYS_munmap,
+        libc::SYS_mremap,
+        libc::SYS_mprotect,
+        libc::SYS_madvise,
+        libc::SYS_membarrier,
+        // Threads, which are further restricted below.
+        libc::SYS_futex,
+        libc::SYS_set_robust_list,
+        libc::SYS_rseq,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_getpid,
+        libc::SYS_gettid,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        // Signals, used by the Wasm runtimes to catch traps.
+        libc::SYS_rt_sigaction,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        // Time and randomness.
+        libc::SYS_clock_gettime,
+        libc::SYS_clock_getres,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_nanosleep,
+        libc::SYS_getrandom,
+    ];
+    #[cfg(target_arch = "x86_64")]
+    allowed_system_calls.extend([libc::SYS_epoll_wait, libc::SYS_poll]);
+    // An empty list of rules matches the system call regardless of its arguments.
+    let mut rules = allowed_system_calls
+        .into_iter()
+        .map(|system_call| (system_call, vec![]))
+        .collect::<std::collections::BTreeMap<_, _>>();
+    // `clone` may create threads, but not processes. Its flags are the first argument on
+    // all supported architectures.
+    let thread_flag = libc::CLONE_THREAD as u64;
+    let creates_thread = SeccompCondition::new(
+        0,
+        SeccompCmpArgLen::Qword,
+        SeccompCmpOp::MaskedEq(thread_flag),
+        thread_flag,
+    )
+    .map_err(|error| sandbox_error(&error))?;
+    rules.insert(
+        libc::SYS_clone,
+        vec![SeccompRule::new(vec![creates_thread]).map_err(|error| sandbox_error(&error))?],
+    );
+    let target_arch =
+        TargetArch::try_from(std::env::consts::ARCH).map_err(|error| sandbox_error(&error))?;
+    // Other system calls fail as if the kernel didn't implement them. This also makes the C
+    // library fall back from `clone3`, whose arguments can't be checked, to `clone`.
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Errno(libc::ENOSYS as u32),
+        SeccompAction::Allow,
+        target_arch,
+    )
+    .map_err(|error| sandbox_error(&error))?;
+    let program = BpfProgram::try_from(filter).map_err(|error| sandbox_error(&error))?;
+    seccompiler::apply_filter_all_threads(&program).map_err(|error| sandbox_error(&error))?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn restrict_system_calls() -> Result<(), ExecutionError> {
+    tracing::warn!("System calls of query sandboxes are only restricted on Linux");
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    /// The environment variable set in the process started by the test.
+    const RESTRICTED_CHILD: &str = "LINERA_TEST_RESTRICTED_CHILD";
+
+    /// Runs again in a new process, which restricts its system calls and checks that it can
+    /// still run tasks and compile services, but not open files or start programs.
+    #[test]
+    fn test_system_calls_are_restricted() {
+        if std::env::var_os(RESTRICTED_CHILD).is_none() {
+            let status = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "sandbox::child::tests::test_system_calls_are_restricted",
+                    "--nocapture",
+                ])
+                .env(RESTRICTED_CHILD, "1")
+                .status()
+                .unwrap();
+            assert!(status.success(), "the restricted process failed: {status}");
+            return;
+        }
+        #[cfg(with_wasm_runtime)]
+        let service_bytecode = linera_base::data_types::Bytecode::new(
+            std::fs::read("tests/fixtures/counter_service.wasm").unwrap(),
+        );
+
+        super::restrict_system_calls().unwrap();
+
+        let error = std::fs::File::open("Cargo.toml").unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ENOSYS));
+        assert!(std::process::Command::new("true").status().is_err());
+        assert!(std::net::TcpListener::bind("127.0.0.1:0").is_err());
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let result = tokio::task::spawn_blocking(|| 6 * 7).await.unwrap();
+            assert_eq!(result, 42);
+            #[cfg(with_wasm_runtime)]
+            crate::WasmServiceModule::new(service_bytecode, crate::WasmRuntime::default())
+                .await
+                .unwrap();
+        });
+    }
+}
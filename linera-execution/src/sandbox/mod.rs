@@ -0,0 +1,360 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs service queries in a pool of separate processes.
+//!
+//! Queries handle untrusted input with untrusted code. A sandbox process receives a query
+//! over its standard input, compiles and runs the service, and asks the validator over its
+//! standard output for every piece of chain state the service reads. It never has access
+//! to the storage or the keys of the validator: it doesn't inherit the validator's open files,
+//! can run as a separate user, and on Linux it can only make the few system calls needed to
+//! run services, so it cannot open files or network connections or start other programs.
+
+mod child;
+mod protocol;
+
+use std::{path::PathBuf, process::Stdio, sync::Mutex};
+
+use linera_base::identifiers::ApplicationId;
+use linera_views::{context::Context, views::View};
+use tokio::{
+    io::BufReader,
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::{Semaphore, SemaphorePermit},
+};
+
+pub use self::child::run_query_sandbox;
+use self::protocol::{
+    read_message, write_message, SandboxInput, SandboxOutput, SandboxRequest, SandboxResponse,
+};
+use crate::{
+    util::ReceiverExt as _, ExecutionError, ExecutionRequest, ExecutionRuntimeContext,
+    ExecutionStateView, QueryContext, QueryOutcome, ResourceController,
+};
+
+/// The configuration of a [`QuerySandboxPool`].
+#[derive(Clone, Debug)]
+pub struct QuerySandboxConfig {
+    /// The program to start for each sandbox.
+    pub program: PathBuf,
+    /// The arguments that make the program call [`run_query_sandbox`].
+    pub args: Vec<String>,
+    /// The maximal number of sandboxes. Further queries wait for a sandbox to be free.
+    pub max_processes: usize,
+    /// The user to run the sandboxes as, if not the validator's. Changing it requires the
+    /// validator to run with the privileges to do so.
+    pub uid: Option<u32>,
+    /// The group to run the sandboxes as, if not the validator's.
+    pub gid: Option<u32>,
+}
+
+/// A pool of processes running service queries.
+///
+/// Processes are started on demand and reused after a successful query. A process is
+/// killed if a query fails in a way that may have left it in an inconsistent state.
+#[derive(Debug)]
+pub struct QuerySandboxPool {
+    config: QuerySandboxConfig,
+    idle_processes: Mutex<Vec<SandboxProcess>>,
+    permits: Semaphore,
+}
+
+impl QuerySandboxPool {
+    /// Creates a pool. No process is started until the first query.
+    pub fn new(config: QuerySandboxConfig) -> Self {
+        let permits = Semaphore::new(config.max_processes);
+        QuerySandboxPool {
+            config,
+            idle_processes: Mutex::new(Vec::new()),
+            permits,
+        }
+    }
+
+    /// Waits for a free sandbox, starting a new process if no idle one is left.
+    async fn acquire(&self) -> Result<SandboxLease<'_>, ExecutionError> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        let idle_process = self.idle_processes.lock().unwrap().pop();
+        let process = match idle_process {
+            Some(process) => process,
+            None => SandboxProcess::spawn(&self.config)?,
+        };
+        Ok(SandboxLease {
+            pool: self,
+            process: Some(process),
+            _permit: permit,
+        })
+    }
+}
+
+/// A running sandbox.
+#[derive(Debug)]
+struct SandboxProcess {
+    /// The process, killed when this is dropped.
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl SandboxProcess {
+    fn spawn(config: &QuerySandboxConfig) -> Result<Self, ExecutionError> {
+        let mut command = Command::new(&config.program);
+        command
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        {
+            if let Some(gid) = config.gid {
+                command.gid(gid);
+            }
+            if let Some(uid) = config.uid {
+                command.uid(uid);
+            }
+        }
+        #[cfg(target_os = "linux")]
+        // SAFETY: The closure only makes system calls that are safe to make between `fork`
+        // and `exec`.
+        unsafe {
+            command.pre_exec(close_inherited_files_on_exec);
+        }
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(SandboxProcess {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    async fn send(&mut self, message: &SandboxInput) -> Result<(), ExecutionError> {
+        write_message(&mut self.stdin, message).await
+    }
+
+    async fn receive(&mut self) -> Result<SandboxOutput, ExecutionError> {
+        read_message(&mut self.stdout)
+            .await?
+            .ok_or_else(|| ExecutionError::QuerySandbox("the sandbox exited".to_string()))
+    }
+}
+
+/// Marks all file descriptors except the standard streams to be closed when the sandbox
+/// program starts, so that it doesn't inherit the validator's files and connections.
+#[cfg(target_os = "linux")]
+fn close_inherited_files_on_exec() -> std::io::Result<()> {
+    const FIRST_INHERITED: libc::c_uint = 3;
+    // SAFETY: This only changes flags of the process's own file descriptors.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_close_range,
+            FIRST_INHERITED,
+            libc::c_uint::MAX,
+            libc::CLOSE_RANGE_CLOEXEC,
+        )
+    };
+    if result == 0 {
+        return Ok(());
+    }
+    // Kernels before 5.11 don't support this, so the descriptors are marked one by one.
+    // SAFETY: `sysconf` has no preconditions.
+    let max_descriptors = unsafe { libc::sysconf(libc::_SC_OPEN_MAX) };
+    let max_descriptors = libc::c_int::try_from(max_descriptors).unwrap_or(libc::c_int::MAX);
+    for descriptor in FIRST_INHERITED as libc::c_int..max_descriptors {
+        // SAFETY: Unused descriptors only make this fail with `EBADF`.
+        unsafe {
+            libc::fcntl(descriptor, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    Ok(())
+}
+
+/// A sandbox used by one query. It is dropped, and the process killed, unless it is
+/// explicitly released.
+struct SandboxLease<'a> {
+    pool: &'a QuerySandboxPool,
+    process: Option<SandboxProcess>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl SandboxLease<'_> {
+    fn process(&mut self) -> &mut SandboxProcess {
+        self.process
+            .as_mut()
+            .expect("the process is only taken on release")
+    }
+
+    /// Returns the process to the pool, to be reused by later queries.
+    fn release(mut self) {
+        let process = self
+            .process
+            .take()
+            .expect("the process is only taken on release");
+        self.pool.idle_processes.lock().unwrap().push(process);
+    }
+}
+
+impl<C> ExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Runs a query on a user application in one of the sandboxes of the `pool`.
+    pub(crate) async fn query_user_application_in_sandbox(
+        &mut self,
+        pool: &QuerySandboxPool,
+        application_id: ApplicationId,
+        context: QueryContext,
+        query: Vec<u8>,
+    ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
+        let mut lease = pool.acquire().await?;
+        let process = lease.process();
+        process
+            .send(&SandboxInput::Query {
+                application_id,
+                context,
+                query,
+            })
+            .await?;
+        loop {
+            match process.receive().await? {
+                SandboxOutput::Request(request) => {
+                    let response = self.handle_sandbox_request(request).await?;
+                    process.send(&SandboxInput::Response(response)).await?;
+                }
+                SandboxOutput::Outcome(outcome) => {
+                    lease.release();
+                    return outcome.map_err(ExecutionError::SandboxedQueryFailed);
+                }
+            }
+        }
+    }
+
+    async fn handle_sandbox_request(
+        &mut self,
+        request: SandboxRequest,
+    ) -> Result<SandboxResponse, ExecutionError> {
+        use SandboxResponse as Response;
+        let response = match request {
+            SandboxRequest::LoadService { id } => {
+                let description = self.system.describe_application(id, None).await?;
+                let blob = self
+                    .context()
+                    .extra()
+                    .get_blob(description.service_bytecode_blob_id())
+                    .await?;
+                Response::Service {
+                    description: Box::new(description),
+                    compressed_bytecode: blob.into_bytes().to_vec(),
+                }
+            }
+            SandboxRequest::ChainBalance => Response::Amount(
+                self.forward(|callback| ExecutionRequest::ChainBalance { callback })
+                    .await?,
+            ),
+            SandboxRequest::OwnerBalance { owner } => Response::Amount(
+                self.forward(|callback| ExecutionRequest::OwnerBalance { owner, callback })
+                    .await?,
+            ),
+            SandboxRequest::OwnerBalances => Response::Balances(
+                self.forward(|callback| ExecutionRequest::OwnerBalances { callback })
+                    .await?,
+            ),
+            SandboxRequest::BalanceOwners => Response::Owners(
+                self.forward(|callback| ExecutionRequest::BalanceOwners { callback })
+                    .await?,
+            ),
+            SandboxRequest::SystemTimestamp => Response::Timestamp(
+                self.forward(|callback| ExecutionRequest::SystemTimestamp { callback })
+                    .await?,
+            ),
+            SandboxRequest::ChainOwnership => Response::ChainOwnership(
+                self.forward(|callback| ExecutionRequest::ChainOwnership { callback })
+                    .await?,
+            ),
+            SandboxRequest::ApplicationPermissions => Response::ApplicationPermissions(
+                self.forward(|callback| ExecutionRequest::GetApplicationPermissions { callback })
+                    .await?,
+            ),
+            SandboxRequest::ReadValueBytes { id, key } => Response::Value(
+                self.forward(|callback| ExecutionRequest::ReadValueBytes { id, key, callback })
+                    .await?,
+            ),
+            SandboxRequest::ContainsKey { id, key } => Response::Bool(
+                self.forward(|callback| ExecutionRequest::ContainsKey { id, key, callback })
+                    .await?,
+            ),
+            SandboxRequest::ContainsKeys { id, keys } => Response::Bools(
+                self.forward(|callback| ExecutionRequest::ContainsKeys { id, keys, callback })
+                    .await?,
+            ),
+            SandboxRequest::ReadMultiValuesBytes { id, keys } => Response::Values(
+                self.forward(|callback| ExecutionRequest::ReadMultiValuesBytes {
+                    id,
+                    keys,
+                    callback,
+                })
+                .await?,
+            ),
+            SandboxRequest::FindKeysByPrefix { id, key_prefix } => Response::Keys(
+                self.forward(|callback| ExecutionRequest::FindKeysByPrefix {
+                    id,
+                    key_prefix,
+                    callback,
+                })
+                .await?,
+            ),
+            SandboxRequest::FindKeyValuesByPrefix { id, key_prefix } => Response::KeyValues(
+                self.forward(|callback| ExecutionRequest::FindKeyValuesByPrefix {
+                    id,
+                    key_prefix,
+                    callback,
+                })
+                .await?,
+            ),
+            SandboxRequest::PerformHttpRequest {
+                request,
+                http_responses_are_oracle_responses,
+            } => Response::HttpResponse(
+                self.forward(|callback| ExecutionRequest::PerformHttpRequest {
+                    request,
+                    http_responses_are_oracle_responses,
+                    callback,
+                })
+                .await?,
+            ),
+            SandboxRequest::ReadBlobContent { blob_id } => Response::BlobContent(
+                self.forward(|callback| ExecutionRequest::ReadBlobContent { blob_id, callback })
+                    .await?,
+            ),
+            SandboxRequest::AssertBlobExists { blob_id } => Response::Bool(
+                self.forward(|callback| ExecutionRequest::AssertBlobExists { blob_id, callback })
+                    .await?,
+            ),
+            SandboxRequest::ReadEvent { event_id } => Response::Event(
+                self.forward(|callback| ExecutionRequest::ReadEvent { event_id, callback })
+                    .await?,
+            ),
+        };
+        Ok(response)
+    }
+
+    /// Handles an [`ExecutionRequest`] as if it came from a runtime in this process.
+    async fn forward<T>(
+        &mut self,
+        request: impl FnOnce(oneshot::Sender<T>) -> ExecutionRequest,
+    ) -> Result<T, ExecutionError>
+    where
+        T: Send,
+    {
+        let (callback, receiver) = oneshot::channel();
+        self.handle_request(request(callback), &mut ResourceController::default())
+            .await?;
+        receiver.recv_response()
+    }
+}
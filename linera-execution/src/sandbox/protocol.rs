@@ -0,0 +1,203 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The messages exchanged between a validator and its query sandboxes.
+//!
+//! Each message is BCS-serialized and preceded by its length, as a little-endian `u32`.
+
+use linera_base::{
+    data_types::{Amount, ApplicationDescription, ApplicationPermissions, BlobContent, Timestamp},
+    http,
+    identifiers::{AccountOwner, ApplicationId, BlobId, EventId},
+    ownership::ChainOwnership,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+
+use crate::{ExecutionError, QueryContext, QueryOutcome};
+
+/// The maximal size of a message, in bytes.
+const MAXIMUM_MESSAGE_SIZE: u32 = 256 << 20;
+
+/// A message sent to a sandbox.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SandboxInput {
+    /// Runs a query on a user application.
+    Query {
+        application_id: ApplicationId,
+        context: QueryContext,
+        #[serde(with = "serde_bytes")]
+        query: Vec<u8>,
+    },
+    /// Answers the last [`SandboxOutput::Request`].
+    Response(SandboxResponse),
+}
+
+/// A message sent by a sandbox.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SandboxOutput {
+    /// Requests data needed by the running query.
+    Request(SandboxRequest),
+    /// Finishes the running query. Errors are only forwarded as messages.
+    Outcome(Result<QueryOutcome<Vec<u8>>, String>),
+}
+
+/// The requests a service can make while handling a query. These only read the chain
+/// state.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SandboxRequest {
+    LoadService {
+        id: ApplicationId,
+    },
+    ChainBalance,
+    OwnerBalance {
+        owner: AccountOwner,
+    },
+    OwnerBalances,
+    BalanceOwners,
+    SystemTimestamp,
+    ChainOwnership,
+    ApplicationPermissions,
+    ReadValueBytes {
+        id: ApplicationId,
+        key: Vec<u8>,
+    },
+    ContainsKey {
+        id: ApplicationId,
+        key: Vec<u8>,
+    },
+    ContainsKeys {
+        id: ApplicationId,
+        keys: Vec<Vec<u8>>,
+    },
+    ReadMultiValuesBytes {
+        id: ApplicationId,
+        keys: Vec<Vec<u8>>,
+    },
+    FindKeysByPrefix {
+        id: ApplicationId,
+        key_prefix: Vec<u8>,
+    },
+    FindKeyValuesByPrefix {
+        id: ApplicationId,
+        key_prefix: Vec<u8>,
+    },
+    PerformHttpRequest {
+        request: http::Request,
+        http_responses_are_oracle_responses: bool,
+    },
+    ReadBlobContent {
+        blob_id: BlobId,
+    },
+    AssertBlobExists {
+        blob_id: BlobId,
+    },
+    ReadEvent {
+        event_id: EventId,
+    },
+}
+
+/// The answers to [`SandboxRequest`]s.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SandboxResponse {
+    /// The description of a service and its compressed bytecode, to be compiled in the
+    /// sandbox.
+    Service {
+        description: Box<ApplicationDescription>,
+        #[serde(with = "serde_bytes")]
+        compressed_bytecode: Vec<u8>,
+    },
+    Amount(Amount),
+    Balances(Vec<(AccountOwner, Amount)>),
+    Owners(Vec<AccountOwner>),
+    Timestamp(Timestamp),
+    ChainOwnership(ChainOwnership),
+    ApplicationPermissions(ApplicationPermissions),
+    Value(Option<Vec<u8>>),
+    Bool(bool),
+    Bools(Vec<bool>),
+    Values(Vec<Option<Vec<u8>>>),
+    Keys(Vec<Vec<u8>>),
+    KeyValues(Vec<(Vec<u8>, Vec<u8>)>),
+    HttpResponse(http::Response),
+    BlobContent((BlobContent, bool)),
+    Event(Vec<u8>),
+}
+
+/// Writes a message and flushes the writer.
+pub async fn write_message<T: Serialize>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> Result<(), ExecutionError> {
+    let bytes = bcs::to_bytes(message)?;
+    let size = u32::try_from(bytes.len())
+        .ok()
+        .filter(|size| *size <= MAXIMUM_MESSAGE_SIZE)
+        .ok_or_else(|| {
+            ExecutionError::QuerySandbox(format!("message of {} bytes is too large", bytes.len()))
+        })?;
+    writer.write_all(&size.to_le_bytes()).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads a message, or returns `None` if the other side closed the stream before
+/// starting a new message.
+pub async fn read_message<T: DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<T>, ExecutionError> {
+    let mut size = [0; 4];
+    match reader.read_exact(&mut size).await {
+        Ok(_) => {}
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let size = u32::from_le_bytes(size);
+    if size > MAXIMUM_MESSAGE_SIZE {
+        return Err(ExecutionError::QuerySandbox(format!(
+            "message of {size} bytes is too large"
+        )));
+    }
+    let mut bytes = vec![0; size as usize];
+    reader.read_exact(&mut bytes).await?;
+    Ok(Some(bcs::from_bytes(&bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{data_types::Amount, identifiers::AccountOwner};
+
+    use super::{read_message, write_message, SandboxRequest, SandboxResponse};
+
+    #[tokio::test]
+    async fn test_message_round_trip() {
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        let task = tokio::spawn(async move {
+            let request = SandboxRequest::OwnerBalance {
+                owner: AccountOwner::CHAIN,
+            };
+            write_message(&mut writer, &request).await.unwrap();
+            write_message(&mut writer, &SandboxResponse::Amount(Amount::ONE))
+                .await
+                .unwrap();
+        });
+
+        let request = read_message::<SandboxRequest>(&mut reader).await.unwrap();
+        assert!(matches!(
+            request,
+            Some(SandboxRequest::OwnerBalance { owner }) if owner == AccountOwner::CHAIN
+        ));
+        let response = read_message::<SandboxResponse>(&mut reader).await.unwrap();
+        assert!(matches!(
+            response,
+            Some(SandboxResponse::Amount(Amount::ONE))
+        ));
+        task.await.unwrap();
+        // The writer was dropped.
+        assert!(read_message::<SandboxResponse>(&mut reader)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
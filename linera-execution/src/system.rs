@@ -10,25 +10,31 @@ mod tests;
 use std::sync::LazyLock;
 use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
-    mem,
+    fmt, mem,
+    str::FromStr,
 };
 
 use custom_debug_derive::Debug;
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{
+        AccountPublicKey, AccountSecretKey, AccountSignature, BcsSignable, CryptoHash,
+        Ed25519PublicKey,
+    },
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, Blob, BlobContent, BlockHeight,
-        ChainDescription, ChainOrigin, Epoch, InitialChainConfig, OracleResponse, Timestamp,
+        BlockLimits, ChainDescription, ChainOrigin, Epoch, InboxPolicy, InitialChainConfig,
+        OracleResponse, RetentionPolicy, TimeDelta, Timestamp,
     },
     ensure, hex_debug,
     identifiers::{Account, AccountOwner, BlobId, BlobType, ChainId, EventId, ModuleId, StreamId},
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_views::{
+    common::CustomSerialize,
     context::Context,
     map_view::{HashedMapView, MapView},
     register_view::HashedRegisterView,
-    set_view::HashedSetView,
+    set_view::{HashedCustomSetView, HashedSetView},
     views::{ClonableView, HashableView, View, ViewError},
 };
 use serde::{Deserialize, Serialize};
@@ -38,9 +44,10 @@ use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCoun
 #[cfg(test)]
 use crate::test_utils::SystemExecutionState;
 use crate::{
-    committee::Committee, ApplicationDescription, ApplicationId, ExecutionError,
-    ExecutionRuntimeContext, MessageContext, MessageKind, OperationContext, OutgoingMessage,
-    QueryContext, QueryOutcome, ResourceController, TransactionTracker,
+    committee::{Committee, Committees},
+    ApplicationDescription, ApplicationId, ExecutionError, ExecutionRuntimeContext, MessageContext,
+    MessageKind, OperationContext, OutgoingMessage, QueryContext, QueryOutcome, ResourceController,
+    TransactionTracker,
 };
 
 /// The event stream name for new epochs and committees.
@@ -48,6 +55,24 @@ pub static EPOCH_STREAM_NAME: &[u8] = &[0];
 /// The event stream name for removed epochs.
 pub static REMOVED_EPOCH_STREAM_NAME: &[u8] = &[1];
 
+/// The maximum number of expired sponsorships removed each time a block is sponsored.
+const MAXIMUM_PRUNED_SPONSORSHIPS: usize = 16;
+
+/// The maximum length of a registered name, in bytes.
+pub const MAXIMUM_NAME_LEN: usize = 64;
+/// The maximum time until a name registration expires. Registrations can be renewed.
+pub const MAXIMUM_NAME_REGISTRATION_DURATION: TimeDelta = TimeDelta::from_secs(366 * 24 * 60 * 60);
+/// The maximum number of expired names removed each time a name is registered.
+const MAXIMUM_PRUNED_NAMES: usize = 16;
+
+/// The maximum length of a configuration key, in bytes.
+pub const MAXIMUM_CONFIG_KEY_LEN: usize = 64;
+/// The maximum length of a configuration value, in bytes.
+pub const MAXIMUM_CONFIG_VALUE_LEN: usize = 1024;
+
+/// The maximum length of the ciphertext of a transfer's memo, in bytes.
+pub const MAXIMUM_MEMO_LEN: usize = 512;
+
 /// The number of times the [`SystemOperation::OpenChain`] was executed.
 #[cfg(with_metrics)]
 static OPEN_CHAIN_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
@@ -71,7 +96,7 @@ pub struct SystemExecutionStateView<C> {
     // Not using a `MapView` because the set active of committees is supposed to be
     // small. Plus, currently, we would create the `BTreeMap` anyway in various places
     // (e.g. the `OpenChain` operation).
-    pub committees: HashedRegisterView<C, BTreeMap<Epoch, Committee>>,
+    pub committees: HashedRegisterView<C, Committees>,
     /// Ownership of the chain.
     pub ownership: HashedRegisterView<C, ChainOwnership>,
     /// Balance of the chain. (Available to any user able to create blocks in the chain.)
@@ -88,6 +113,44 @@ pub struct SystemExecutionStateView<C> {
     pub used_blobs: HashedSetView<C, BlobId>,
     /// The event stream subscriptions of applications on this chain.
     pub event_subscriptions: MapView<C, (ChainId, StreamId), EventSubscriptions>,
+    /// The total fees paid so far under each unexpired [`FeeSponsorship`], indexed by its hash.
+    /// This is only part of the state hash while it is not empty, so that the hashes of
+    /// existing chains don't change.
+    #[view(hash_unless_default)]
+    pub sponsored_fees: HashedMapView<C, CryptoHash, SponsoredFees>,
+    /// The sponsorships in `sponsored_fees`, ordered by expiry, so that the expired ones can
+    /// be removed. This is only part of the state hash while it is not empty.
+    #[view(hash_unless_default)]
+    pub sponsorship_expiries: HashedCustomSetView<C, SponsorshipExpiry>,
+    /// The funds set aside from the sponsor's account on this chain for the block being
+    /// executed, if any. This is always zero between blocks, and only part of the state hash
+    /// while it isn't.
+    #[view(hash_unless_default)]
+    pub sponsor_funds: HashedRegisterView<C, Amount>,
+    /// The registered names. This is only used on the admin chain, and only part of the state
+    /// hash while it is not empty.
+    #[view(hash_unless_default)]
+    pub names: HashedMapView<C, String, NameRecord>,
+    /// The registered names, ordered by expiry, so that the expired ones can be removed. This
+    /// is only part of the state hash while it is not empty.
+    #[view(hash_unless_default)]
+    pub name_expiries: HashedCustomSetView<C, NameExpiry>,
+    /// The configuration values set by the chain owners, readable by all applications. This is
+    /// only part of the state hash while it is not empty.
+    #[view(hash_unless_default)]
+    pub config: HashedMapView<C, String, Vec<u8>>,
+    /// The rules on the incoming message bundles that the chain owners accept. This is only
+    /// part of the state hash once set, so that the hashes of existing chains don't change.
+    #[view(hash_unless_default)]
+    pub inbox_policy: HashedRegisterView<C, InboxPolicy>,
+    /// The limits on the block proposals of this chain, enforced by validators. This is only
+    /// part of the state hash once set.
+    #[view(hash_unless_default)]
+    pub block_limits: HashedRegisterView<C, BlockLimits>,
+    /// How much of this chain's history its owners ask validators to keep. This is only part
+    /// of the state hash once set.
+    #[view(hash_unless_default)]
+    pub retention_policy: HashedRegisterView<C, RetentionPolicy>,
 }
 
 /// The applications subscribing to a particular stream, and the next event index.
@@ -201,6 +264,262 @@ pub enum SystemOperation {
     ProcessRemovedEpoch(Epoch),
     /// Updates the event stream trackers.
     UpdateStreams(Vec<(ChainId, StreamId, u32)>),
+    /// Has a sponsor pay for the fees of this block. This is only allowed as the first operation
+    /// of a block. The fees are paid from the sponsor's account on this chain, which the
+    /// sponsor has to fund beforehand, e.g. with a transfer: see [`FeeSponsorship`].
+    SponsorFees {
+        sponsorship: FeeSponsorship,
+        public_key: AccountPublicKey,
+        signature: AccountSignature,
+    },
+    /// Registers or renews a name on the admin chain, owned by the authenticated signer.
+    RegisterName {
+        name: String,
+        target: NameTarget,
+        expiry: Timestamp,
+    },
+    /// Transfers a name owned by the authenticated signer to a new owner.
+    TransferName {
+        name: String,
+        new_owner: AccountOwner,
+    },
+    /// Sets or, if `value` is `None`, removes a configuration value of this chain. This must
+    /// be authenticated by a chain owner.
+    SetConfig { key: String, value: Option<Vec<u8>> },
+    /// Changes the rules on the incoming message bundles that the chain owners accept.
+    ChangeInboxPolicy(InboxPolicy),
+    /// Transfers `amount` units of value like [`SystemOperation::Transfer`], with a memo that
+    /// only the recipient can decrypt, e.g. a payment reference.
+    TransferWithMemo {
+        owner: AccountOwner,
+        recipient: Recipient,
+        amount: Amount,
+        memo: EncryptedMemo,
+    },
+    /// Changes the limits on the block proposals of this chain. They apply from the next
+    /// block on.
+    ChangeBlockLimits(BlockLimits),
+    /// Changes how much of this chain's history its owners ask validators to keep.
+    ChangeRetentionPolicy(RetentionPolicy),
+}
+
+/// A memo encrypted to the recipient of a transfer.
+///
+/// The memo is encrypted with XChaCha20-Poly1305, using a key derived from the X25519 secret
+/// shared between the `ephemeral_key` and the recipient's Ed25519 key. Only the contents are
+/// hidden: the memo's length is public.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct EncryptedMemo {
+    /// The public key of the one-time key pair that the memo was encrypted with.
+    pub ephemeral_key: Ed25519PublicKey,
+    /// The public key of the recipient that the memo was encrypted to.
+    pub recipient_key: Ed25519PublicKey,
+    #[debug(with = "hex_debug")]
+    pub nonce: [u8; 24],
+    #[serde(with = "serde_bytes")]
+    #[debug(with = "hex_debug")]
+    pub ciphertext: Vec<u8>,
+}
+
+/// What a registered name resolves to.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+pub enum NameTarget {
+    /// A chain.
+    Chain(ChainId),
+    /// An account owner.
+    Owner(AccountOwner),
+    /// An application.
+    Application(ApplicationId),
+}
+
+impl fmt::Display for NameTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameTarget::Chain(chain_id) => write!(f, "chain:{chain_id}"),
+            NameTarget::Owner(owner) => write!(f, "owner:{owner}"),
+            NameTarget::Application(application_id) => write!(f, "application:{application_id}"),
+        }
+    }
+}
+
+impl FromStr for NameTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let Some((kind, value)) = string.split_once(':') else {
+            anyhow::bail!(
+                "Expecting a name target formatted as `chain:CHAIN-ID`, `owner:OWNER` or \
+                `application:APPLICATION-ID`"
+            );
+        };
+        match kind {
+            "chain" => Ok(NameTarget::Chain(value.parse()?)),
+            "owner" => Ok(NameTarget::Owner(value.parse()?)),
+            "application" => Ok(NameTarget::Application(value.parse()?)),
+            _ => anyhow::bail!("Unknown name target kind {kind:?}"),
+        }
+    }
+}
+
+/// A name registered on the admin chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct NameRecord {
+    /// What the name resolves to.
+    pub target: NameTarget,
+    /// The owner who may renew or transfer the name.
+    pub owner: AccountOwner,
+    /// The name can be registered by anyone at or after this time.
+    pub expiry: Timestamp,
+}
+
+/// Checks that a name is non-empty, not too long, consists of lowercase ASCII letters, digits
+/// and hyphens, and does not start or end with a hyphen.
+pub fn check_name(name: &str) -> Result<(), ExecutionError> {
+    ensure!(
+        !name.is_empty()
+            && name.len() <= MAXIMUM_NAME_LEN
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+            && !name.starts_with('-')
+            && !name.ends_with('-'),
+        ExecutionError::InvalidName(name.to_owned())
+    );
+    Ok(())
+}
+
+/// Checks that a name registration made at `timestamp` expires after it, but not later than
+/// [`MAXIMUM_NAME_REGISTRATION_DURATION`] after it.
+fn check_name_expiry(expiry: Timestamp, timestamp: Timestamp) -> Result<(), ExecutionError> {
+    ensure!(
+        timestamp < expiry
+            && expiry <= timestamp.saturating_add(MAXIMUM_NAME_REGISTRATION_DURATION),
+        ExecutionError::InvalidNameExpiry(expiry)
+    );
+    Ok(())
+}
+
+/// Checks that a configuration key is non-empty and not too long, and that the value, if any,
+/// is not too long.
+pub fn check_config_entry(key: &str, value: Option<&[u8]>) -> Result<(), ExecutionError> {
+    ensure!(
+        !key.is_empty() && key.len() <= MAXIMUM_CONFIG_KEY_LEN,
+        ExecutionError::InvalidConfigKey(key.to_owned())
+    );
+    if let Some(value) = value {
+        ensure!(
+            value.len() <= MAXIMUM_CONFIG_VALUE_LEN,
+            ExecutionError::ConfigValueTooLarge {
+                key: key.to_owned(),
+                size: value.len(),
+            }
+        );
+    }
+    Ok(())
+}
+
+impl NameRecord {
+    /// Returns whether the registration has expired at the given time.
+    pub fn is_expired(&self, timestamp: Timestamp) -> bool {
+        timestamp >= self.expiry
+    }
+}
+
+/// The expiry of a registered name, used to index the names by expiry.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct NameExpiry {
+    /// When the registration expires.
+    pub expiry: Timestamp,
+    /// The registered name.
+    pub name: String,
+}
+
+// Serialize the expiry first, in big-endian order, so that the lexicographic order of the
+// serialized keys is chronological.
+impl CustomSerialize for NameExpiry {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        let data = (self.expiry.micros().to_be_bytes(), &self.name);
+        Ok(bcs::to_bytes(&data)?)
+    }
+
+    fn from_custom_bytes(short_key: &[u8]) -> Result<Self, ViewError> {
+        let (expiry_bytes, name) = bcs::from_bytes(short_key)?;
+        Ok(Self {
+            expiry: Timestamp::from(u64::from_be_bytes(expiry_bytes)),
+            name,
+        })
+    }
+}
+
+/// A sponsor's consent to pay, up to a limit, for the fees of blocks on another chain.
+///
+/// The fees are paid from the sponsor's account on the sponsored chain: nothing is moved from the
+/// sponsor's own chain when a sponsored block is executed. A sponsor of several chains therefore
+/// funds each of them separately, by transferring tokens to its account on that chain, and may
+/// withdraw what is left with a claim. Blocks are only sponsored up to that account's balance.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct FeeSponsorship {
+    /// The chain whose blocks are sponsored.
+    pub chain_id: ChainId,
+    /// The owner of the account paying for the fees.
+    pub sponsor: AccountOwner,
+    /// If set, only blocks signed by this owner are sponsored.
+    #[debug(skip_if = Option::is_none)]
+    pub beneficiary: Option<AccountOwner>,
+    /// The maximum total amount of fees paid under this sponsorship.
+    pub allowance: Amount,
+    /// Blocks with a timestamp at or after this one are not sponsored.
+    pub expiry: Timestamp,
+}
+
+impl BcsSignable<'_> for FeeSponsorship {}
+
+/// The fees paid so far under a [`FeeSponsorship`].
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct SponsoredFees {
+    /// The total amount paid by the sponsor.
+    pub paid: Amount,
+    /// When the sponsorship expires. The record is removed after that.
+    pub expiry: Timestamp,
+}
+
+/// The expiry of a [`FeeSponsorship`], used to index the sponsorships by expiry.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct SponsorshipExpiry {
+    /// When the sponsorship expires.
+    pub expiry: Timestamp,
+    /// The hash of the sponsorship.
+    pub hash: CryptoHash,
+}
+
+// Serialize the expiry first, in big-endian order, so that the lexicographic order of the
+// serialized keys is chronological.
+impl CustomSerialize for SponsorshipExpiry {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        let data = (self.expiry.micros().to_be_bytes(), &self.hash);
+        Ok(bcs::to_bytes(&data)?)
+    }
+
+    fn from_custom_bytes(short_key: &[u8]) -> Result<Self, ViewError> {
+        let (expiry_bytes, hash) = bcs::from_bytes(short_key)?;
+        Ok(Self {
+            expiry: Timestamp::from(u64::from_be_bytes(expiry_bytes)),
+            hash,
+        })
+    }
+}
+
+impl FeeSponsorship {
+    /// Signs the sponsorship with the sponsor's key, and returns the operation to include first
+    /// in each sponsored block.
+    pub fn sign(self, secret_key: &AccountSecretKey) -> SystemOperation {
+        let signature = secret_key.sign(&self);
+        SystemOperation::SponsorFees {
+            sponsorship: self,
+            public_key: secret_key.public(),
+            signature,
+        }
+    }
 }
 
 /// Operations that are only allowed on the admin chain.
@@ -238,6 +557,17 @@ pub enum SystemMessage {
     },
     /// Notifies that a new application was created.
     ApplicationCreated,
+    /// Registers or renews a name on the admin chain, for the message's authenticated signer.
+    RegisterName {
+        name: String,
+        target: NameTarget,
+        expiry: Timestamp,
+    },
+    /// Transfers a name registered on the admin chain to a new owner.
+    TransferName {
+        name: String,
+        new_owner: AccountOwner,
+    },
 }
 
 /// A query to the system state.
@@ -305,6 +635,20 @@ pub struct CreateApplicationResult {
     pub txn_tracker: TransactionTracker,
 }
 
+impl<C: Context + Send + Sync> SystemExecutionStateView<C> {
+    /// Returns what `name` resolves to at the given time, if it is registered on this chain.
+    pub async fn resolve_name(
+        &self,
+        name: &str,
+        timestamp: Timestamp,
+    ) -> Result<Option<NameTarget>, ViewError> {
+        let record = self.names.get(name).await?;
+        Ok(record
+            .filter(|record| !record.is_expired(timestamp))
+            .map(|record| record.target))
+    }
+}
+
 impl<C> SystemExecutionStateView<C>
 where
     C: Context + Clone + Send + Sync + 'static,
@@ -381,6 +725,15 @@ where
             ChangeApplicationPermissions(application_permissions) => {
                 self.application_permissions.set(application_permissions);
             }
+            ChangeInboxPolicy(inbox_policy) => {
+                self.inbox_policy.set(inbox_policy);
+            }
+            ChangeBlockLimits(block_limits) => {
+                self.block_limits.set(block_limits);
+            }
+            ChangeRetentionPolicy(retention_policy) => {
+                self.retention_policy.set(retention_policy);
+            }
             CloseChain => self.close_chain().await?,
             Transfer {
                 owner,
@@ -392,6 +745,21 @@ where
                     .await?;
                 txn_tracker.add_outgoing_messages(maybe_message)?;
             }
+            TransferWithMemo {
+                owner,
+                amount,
+                recipient,
+                memo,
+            } => {
+                ensure!(
+                    memo.ciphertext.len() <= MAXIMUM_MEMO_LEN,
+                    ExecutionError::MemoTooLarge(memo.ciphertext.len())
+                );
+                let maybe_message = self
+                    .transfer(context.authenticated_signer, None, owner, recipient, amount)
+                    .await?;
+                txn_tracker.add_outgoing_messages(maybe_message)?;
+            }
             Claim {
                 owner,
                 target_id,
@@ -422,8 +790,13 @@ where
                     AdminOperation::CreateCommittee { epoch, blob_hash } => {
                         self.check_next_epoch(epoch)?;
                         let blob_id = BlobId::new(blob_hash, BlobType::Committee);
-                        let committee =
+                        let committee: Committee =
                             bcs::from_bytes(self.read_blob_content(blob_id).await?.bytes())?;
+                        // Validators don't run on Wasm, so they have checked the keys.
+                        #[cfg(not(target_arch = "wasm32"))]
+                        committee
+                            .check_bls_keys()
+                            .map_err(ExecutionError::InvalidCommitteeBlsKeys)?;
                         self.blob_used(Some(txn_tracker), blob_id).await?;
                         self.committees.get_mut().insert(epoch, committee);
                         self.epoch.set(Some(epoch));
@@ -509,7 +882,12 @@ where
                 };
                 let blob_id = BlobId::new(bcs::from_bytes(&bytes)?, BlobType::Committee);
                 txn_tracker.add_oracle_response(OracleResponse::Event(event_id, bytes));
-                let committee = bcs::from_bytes(self.read_blob_content(blob_id).await?.bytes())?;
+                let committee: Committee =
+                    bcs::from_bytes(self.read_blob_content(blob_id).await?.bytes())?;
+                #[cfg(not(target_arch = "wasm32"))]
+                committee
+                    .check_bls_keys()
+                    .map_err(ExecutionError::InvalidCommitteeBlsKeys)?;
                 self.blob_used(Some(txn_tracker), blob_id).await?;
                 self.committees.get_mut().insert(epoch, committee);
                 self.epoch.set(Some(epoch));
@@ -576,6 +954,53 @@ where
                     );
                 }
             }
+            // This is checked and applied by the chain, around the execution of the whole block.
+            SponsorFees { .. } => {}
+            RegisterName {
+                name,
+                target,
+                expiry,
+            } => {
+                check_name(&name)?;
+                check_name_expiry(expiry, context.timestamp)?;
+                // The fee is sent along as the message's grant and only charged on the admin
+                // chain, so that it is refunded if the registration fails and the message
+                // bounces.
+                let fee = resource_controller.policy.name_registration;
+                resource_controller
+                    .with_state(self)
+                    .await?
+                    .track_grant(fee)?;
+                let message = SystemMessage::RegisterName {
+                    name,
+                    target,
+                    expiry,
+                };
+                let mut message = self.name_service_message(context, message)?;
+                if !fee.is_zero() {
+                    message.grant = fee;
+                    message.refund_grant_to = context.refund_grant_to();
+                }
+                txn_tracker.add_outgoing_message(message)?;
+            }
+            TransferName { name, new_owner } => {
+                check_name(&name)?;
+                let message = SystemMessage::TransferName { name, new_owner };
+                txn_tracker.add_outgoing_message(self.name_service_message(context, message)?)?;
+            }
+            SetConfig { key, value } => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    ExecutionError::UnauthenticatedConfigChange
+                );
+                check_config_entry(&key, value.as_deref())?;
+                match value {
+                    Some(value) => self.config.insert(&key, value)?,
+                    None => self.config.remove(&key)?,
+                }
+            }
         }
 
         Ok(new_application)
@@ -663,6 +1088,104 @@ where
         )
     }
 
+    /// Checks a sponsor's consent to pay for the fees of a block signed by `signer` at
+    /// `timestamp`, and sets aside the funds that can be spent on them. Returns the reserved
+    /// amount.
+    ///
+    /// This also removes the records of the sponsorships that have expired: block timestamps
+    /// never decrease, so these can't be used again.
+    pub async fn reserve_sponsor_funds(
+        &mut self,
+        sponsorship: &FeeSponsorship,
+        public_key: AccountPublicKey,
+        signature: &AccountSignature,
+        signer: Option<AccountOwner>,
+        timestamp: Timestamp,
+    ) -> Result<Amount, ExecutionError> {
+        ensure!(
+            sponsorship.chain_id == self.context().extra().chain_id()
+                && sponsorship.sponsor == AccountOwner::from(public_key)
+                && sponsorship
+                    .beneficiary
+                    .is_none_or(|beneficiary| signer == Some(beneficiary)),
+            ExecutionError::InvalidFeeSponsorship
+        );
+        signature
+            .verify(sponsorship, public_key)
+            .map_err(|_| ExecutionError::InvalidFeeSponsorship)?;
+        ensure!(
+            timestamp < sponsorship.expiry,
+            ExecutionError::ExpiredFeeSponsorship(sponsorship.expiry)
+        );
+        self.prune_sponsored_fees(timestamp).await?;
+        let paid = self
+            .sponsored_fees
+            .get(&CryptoHash::new(sponsorship))
+            .await?
+            .map_or(Amount::ZERO, |fees| fees.paid);
+        let remaining = sponsorship.allowance.saturating_sub(paid);
+        let balance = self
+            .balances
+            .get(&sponsorship.sponsor)
+            .await?
+            .unwrap_or_default();
+        let reserved = remaining.min(balance);
+        if reserved > Amount::ZERO {
+            self.debit(&sponsorship.sponsor, reserved).await?;
+        }
+        self.sponsor_funds.set(reserved);
+        Ok(reserved)
+    }
+
+    /// Returns the unspent sponsor funds to the sponsor, and records the fees paid under the
+    /// sponsorship. Returns the amount of fees paid by the sponsor.
+    pub async fn settle_sponsor_funds(
+        &mut self,
+        sponsorship: &FeeSponsorship,
+        reserved: Amount,
+    ) -> Result<Amount, ExecutionError> {
+        let unspent = mem::take(self.sponsor_funds.get_mut());
+        if unspent > Amount::ZERO {
+            let balance = self
+                .balances
+                .get_mut_or_default(&sponsorship.sponsor)
+                .await?;
+            balance.try_add_assign(unspent)?;
+        }
+        let paid = reserved.try_sub(unspent)?;
+        let hash = CryptoHash::new(sponsorship);
+        let fees = self.sponsored_fees.get_mut_or_default(&hash).await?;
+        fees.paid.try_add_assign(paid)?;
+        fees.expiry = sponsorship.expiry;
+        self.sponsorship_expiries.insert(&SponsorshipExpiry {
+            expiry: sponsorship.expiry,
+            hash,
+        })?;
+        Ok(paid)
+    }
+
+    /// Removes up to [`MAXIMUM_PRUNED_SPONSORSHIPS`] records of sponsorships that have expired
+    /// at `timestamp`, oldest first.
+    async fn prune_sponsored_fees(&mut self, timestamp: Timestamp) -> Result<(), ExecutionError> {
+        let mut expired = Vec::new();
+        self.sponsorship_expiries
+            .for_each_index_while(|sponsorship_expiry| {
+                if timestamp < sponsorship_expiry.expiry
+                    || expired.len() == MAXIMUM_PRUNED_SPONSORSHIPS
+                {
+                    return Ok(false);
+                }
+                expired.push(sponsorship_expiry);
+                Ok(true)
+            })
+            .await?;
+        for sponsorship_expiry in expired {
+            self.sponsorship_expiries.remove(&sponsorship_expiry)?;
+            self.sponsored_fees.remove(&sponsorship_expiry.hash)?;
+        }
+        Ok(())
+    }
+
     /// Debits an [`Amount`] of tokens from an account's balance.
     async fn debit(
         &mut self,
@@ -699,6 +1222,8 @@ where
         &mut self,
         context: MessageContext,
         message: SystemMessage,
+        grant: Option<&mut Amount>,
+        resource_controller: &mut ResourceController<Option<AccountOwner>>,
     ) -> Result<Vec<OutgoingMessage>, ExecutionError> {
         let mut outcome = Vec::new();
         use SystemMessage::*;
@@ -740,10 +1265,139 @@ where
             }
             // This message is only a placeholder: Its ID is part of the application ID.
             ApplicationCreated => {}
+            // A failed name registration or transfer has no effect on the sender. The grant
+            // paying for the registration is refunded by the chain.
+            RegisterName { .. } | TransferName { .. } if context.is_bouncing => {}
+            RegisterName {
+                name,
+                target,
+                expiry,
+            } => {
+                let fee = resource_controller.policy.name_registration;
+                let granted = grant.as_deref().copied().unwrap_or_default();
+                ensure!(
+                    granted >= fee,
+                    ExecutionError::InsufficientFundingForFees { balance: granted }
+                );
+                self.register_name(context, name, target, expiry).await?;
+                resource_controller
+                    .with_state_and_grant(self, grant)
+                    .await?
+                    .track_name_registration()?;
+            }
+            TransferName { name, new_owner } => {
+                self.transfer_name(context, name, new_owner).await?;
+            }
         }
         Ok(outcome)
     }
 
+    /// Returns the message for the admin chain's name service, authenticated by the operation's
+    /// signer.
+    fn name_service_message(
+        &self,
+        context: OperationContext,
+        message: SystemMessage,
+    ) -> Result<OutgoingMessage, ExecutionError> {
+        ensure!(
+            context.authenticated_signer.is_some(),
+            ExecutionError::UnauthenticatedNameOwner
+        );
+        let admin_id = (*self.admin_id.get()).ok_or(ExecutionError::InactiveChain)?;
+        Ok(OutgoingMessage::new(admin_id, message)
+            .with_kind(MessageKind::Tracked)
+            .with_authenticated_signer(context.authenticated_signer))
+    }
+
+    /// Registers `name` for the message's signer, or renews it if they already own it.
+    ///
+    /// This also removes some of the names that have expired.
+    async fn register_name(
+        &mut self,
+        context: MessageContext,
+        name: String,
+        target: NameTarget,
+        expiry: Timestamp,
+    ) -> Result<(), ExecutionError> {
+        ensure!(
+            *self.admin_id.get() == Some(context.chain_id),
+            ExecutionError::NameServiceOnNonAdminChain
+        );
+        check_name(&name)?;
+        let owner = context
+            .authenticated_signer
+            .ok_or(ExecutionError::UnauthenticatedNameOwner)?;
+        check_name_expiry(expiry, context.timestamp)?;
+        self.prune_names(context.timestamp).await?;
+        if let Some(record) = self.names.get(&name).await? {
+            ensure!(
+                record.owner == owner || record.is_expired(context.timestamp),
+                ExecutionError::NameAlreadyRegistered(name)
+            );
+            self.name_expiries.remove(&NameExpiry {
+                expiry: record.expiry,
+                name: name.clone(),
+            })?;
+        }
+        self.name_expiries.insert(&NameExpiry {
+            expiry,
+            name: name.clone(),
+        })?;
+        let record = NameRecord {
+            target,
+            owner,
+            expiry,
+        };
+        self.names.insert(&name, record)?;
+        Ok(())
+    }
+
+    /// Removes up to [`MAXIMUM_PRUNED_NAMES`] names that have expired at `timestamp`, oldest
+    /// first.
+    async fn prune_names(&mut self, timestamp: Timestamp) -> Result<(), ExecutionError> {
+        let mut expired = Vec::new();
+        self.name_expiries
+            .for_each_index_while(|name_expiry| {
+                if timestamp < name_expiry.expiry || expired.len() == MAXIMUM_PRUNED_NAMES {
+                    return Ok(false);
+                }
+                expired.push(name_expiry);
+                Ok(true)
+            })
+            .await?;
+        for name_expiry in expired {
+            self.name_expiries.remove(&name_expiry)?;
+            self.names.remove(&name_expiry.name)?;
+        }
+        Ok(())
+    }
+
+    /// Transfers `name`, owned by the message's signer, to `new_owner`.
+    async fn transfer_name(
+        &mut self,
+        context: MessageContext,
+        name: String,
+        new_owner: AccountOwner,
+    ) -> Result<(), ExecutionError> {
+        ensure!(
+            *self.admin_id.get() == Some(context.chain_id),
+            ExecutionError::NameServiceOnNonAdminChain
+        );
+        let Some(record) = self.names.get_mut(&name).await? else {
+            return Err(ExecutionError::NameNotFound(name));
+        };
+        ensure!(
+            !record.is_expired(context.timestamp),
+            ExecutionError::NameNotFound(name)
+        );
+        ensure!(
+            context.authenticated_signer == Some(record.owner),
+            ExecutionError::UnauthenticatedNameOwner
+        );
+        record.owner = new_owner;
+        Ok(())
+    }
+
     /// Initializes the system application state on a newly opened chain.
     /// Returns `Ok(true)` if the chain was already initialized, `Ok(false)` if it wasn't.
     pub async fn initialize_chain(&mut self, chain_id: ChainId) -> Result<bool, ExecutionError> {
@@ -763,9 +1417,6 @@ where
             balance,
             application_permissions,
         } = description.config().clone();
-        self.timestamp.set(description.timestamp());
-        self.description.set(Some(description));
-        self.epoch.set(Some(epoch));
         let committees = committees
             .into_iter()
             .map(|(epoch, serialized_committee)| {
@@ -773,8 +1424,18 @@ where
                     .expect("Deserializing a committee shouldn't fail");
                 (epoch, committee)
             })
-            .collect();
-        self.committees.set(committees);
+            .collect::<BTreeMap<Epoch, Committee>>();
+        // Validators don't run on Wasm, so they have checked the keys.
+        #[cfg(not(target_arch = "wasm32"))]
+        for committee in committees.values() {
+            committee
+                .check_bls_keys()
+                .map_err(ExecutionError::InvalidCommitteeBlsKeys)?;
+        }
+        self.timestamp.set(description.timestamp());
+        self.description.set(Some(description));
+        self.epoch.set(Some(epoch));
+        self.committees.set(committees.into());
         // If `admin_id` is `None`, this chain is its own admin chain.
         self.admin_id.set(admin_id.or(Some(chain_id)));
         self.ownership.set(ownership);
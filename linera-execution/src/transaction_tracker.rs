@@ -10,10 +10,49 @@ use linera_base::{
     identifiers::{ApplicationId, BlobId, ChainId, StreamId},
 };
 
+use async_graphql::{Enum, SimpleObject};
+use serde::{Deserialize, Serialize};
+
 use crate::{ExecutionError, OutgoingMessage};
 
 type AppStreamUpdates = BTreeMap<(ChainId, StreamId), (u32, u32)>;
 
+/// The maximal number of log messages captured in each transaction. Later ones are dropped.
+pub const MAX_TRANSACTION_LOGS: usize = 100;
+/// The maximal length in bytes of a captured log message. Longer ones are truncated.
+pub const MAX_LOG_MESSAGE_LEN: usize = 1024;
+
+/// The level of a message logged by an application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<tracing::log::Level> for LogLevel {
+    fn from(level: tracing::log::Level) -> Self {
+        match level {
+            tracing::log::Level::Trace => LogLevel::Trace,
+            tracing::log::Level::Debug => LogLevel::Debug,
+            tracing::log::Level::Info => LogLevel::Info,
+            tracing::log::Level::Warn => LogLevel::Warn,
+            tracing::log::Level::Error => LogLevel::Error,
+        }
+    }
+}
+
+/// A message that an application logged while executing a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct ContractLog {
+    /// The application that logged the message.
+    pub application_id: ApplicationId,
+    pub level: LogLevel,
+    pub message: String,
+}
+
 /// Tracks oracle responses and execution outcomes of an ongoing transaction execution, as well
 /// as replayed oracle responses.
 #[derive(Debug, Default)]
@@ -39,6 +78,9 @@ pub struct TransactionTracker {
     operation_result: Option<Vec<u8>>,
     /// Streams that have been updated but not yet processed during this transaction.
     streams_to_process: BTreeMap<ApplicationId, AppStreamUpdates>,
+    /// Messages logged by the applications, up to [`MAX_TRANSACTION_LOGS`].
+    #[debug(skip_if = Vec::is_empty)]
+    logs: Vec<ContractLog>,
 }
 
 /// The [`TransactionTracker`] contents after a transaction has finished.
@@ -57,6 +99,9 @@ pub struct TransactionOutcome {
     pub blobs: Vec<Blob>,
     /// Operation result.
     pub operation_result: Vec<u8>,
+    /// Messages logged by the applications. These are not part of the block.
+    #[debug(skip_if = Vec::is_empty)]
+    pub logs: Vec<ContractLog>,
 }
 
 impl TransactionTracker {
@@ -154,6 +199,25 @@ impl TransactionTracker {
         self.oracle_responses.push(oracle_response);
     }
 
+    /// Captures a message logged by an application, unless too many were logged already.
+    pub fn add_log(&mut self, application_id: ApplicationId, level: LogLevel, mut message: String) {
+        if self.logs.len() >= MAX_TRANSACTION_LOGS {
+            return;
+        }
+        if message.len() > MAX_LOG_MESSAGE_LEN {
+            let mut end = MAX_LOG_MESSAGE_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+        self.logs.push(ContractLog {
+            application_id,
+            level,
+            message,
+        });
+    }
+
     pub fn add_operation_result(&mut self, result: Option<Vec<u8>>) {
         self.operation_result = result
     }
@@ -266,6 +330,7 @@ impl TransactionTracker {
             blobs,
             operation_result,
             streams_to_process,
+            logs,
         } = self;
         ensure!(
             streams_to_process.is_empty(),
@@ -286,6 +351,7 @@ impl TransactionTracker {
             events,
             blobs: blobs.into_values().collect(),
             operation_result: operation_result.unwrap_or_default(),
+            logs,
         })
     }
 }
@@ -119,6 +119,7 @@ async fn test_write_batch() {
             id,
             batch,
             callback,
+            ..
         } = request
         else {
             panic!("Expected a `ExecutionRequest::WriteBatch` but got {request:?} instead");
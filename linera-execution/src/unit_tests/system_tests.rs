@@ -1,13 +1,23 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::data_types::{Blob, BlockHeight, Bytecode};
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
 #[cfg(with_testing)]
 use linera_base::vm::VmRuntime;
+use linera_base::{
+    crypto::{BlsSecretKey, ValidatorKeypair},
+    data_types::{Blob, BlockHeight, Bytecode},
+};
 use linera_views::context::MemoryContext;
 
 use super::*;
-use crate::{test_utils::dummy_chain_description, ExecutionStateView, TestExecutionRuntimeContext};
+use crate::{
+    committee::CertificateSignatureScheme, test_utils::dummy_chain_description,
+    ExecutionRuntimeConfig, ExecutionStateView, Message, ResourceControlPolicy,
+    TestExecutionRuntimeContext,
+};
 
 /// Returns an execution state view and a matching operation context, for epoch 1, with root
 /// chain 0 as the admin ID and one empty committee.
@@ -138,3 +148,775 @@ async fn empty_accounts_are_removed() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that sponsor funds are reserved up to the allowance, and that unspent funds are
+/// returned to the sponsor.
+#[tokio::test]
+async fn sponsor_funds_are_reserved_and_settled() -> anyhow::Result<()> {
+    let description = dummy_chain_description(0);
+    let secret_key = AccountSecretKey::generate();
+    let sponsor = AccountOwner::from(secret_key.public());
+    let mut view = SystemExecutionState {
+        description: Some(description.clone()),
+        balances: BTreeMap::from([(sponsor, Amount::from_tokens(3))]),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+    let sponsorship = FeeSponsorship {
+        chain_id: ChainId::from(&description),
+        sponsor,
+        beneficiary: None,
+        allowance: Amount::from_tokens(5),
+        expiry: Timestamp::from(100),
+    };
+    let SystemOperation::SponsorFees {
+        sponsorship,
+        public_key,
+        signature,
+    } = sponsorship.sign(&secret_key)
+    else {
+        panic!("unexpected operation");
+    };
+
+    let reserved = view
+        .system
+        .reserve_sponsor_funds(
+            &sponsorship,
+            public_key,
+            &signature,
+            None,
+            Timestamp::from(0),
+        )
+        .await?;
+    assert_eq!(reserved, Amount::from_tokens(3));
+    assert_eq!(view.system.balances.get(&sponsor).await?, None);
+    view.system
+        .sponsor_funds
+        .get_mut()
+        .try_sub_assign(Amount::ONE)?;
+    let paid = view
+        .system
+        .settle_sponsor_funds(&sponsorship, reserved)
+        .await?;
+    assert_eq!(paid, Amount::ONE);
+    assert_eq!(*view.system.sponsor_funds.get(), Amount::ZERO);
+    assert_eq!(
+        view.system.balances.get(&sponsor).await?,
+        Some(Amount::from_tokens(2))
+    );
+
+    // Only the remaining allowance can be spent, and only before the expiry.
+    view.system
+        .balances
+        .insert(&sponsor, Amount::from_tokens(10))?;
+    let reserved = view
+        .system
+        .reserve_sponsor_funds(
+            &sponsorship,
+            public_key,
+            &signature,
+            None,
+            Timestamp::from(0),
+        )
+        .await?;
+    assert_eq!(reserved, Amount::from_tokens(4));
+    view.system
+        .settle_sponsor_funds(&sponsorship, reserved)
+        .await?;
+    assert_matches!(
+        view.system
+            .reserve_sponsor_funds(
+                &sponsorship,
+                public_key,
+                &signature,
+                None,
+                Timestamp::from(100)
+            )
+            .await,
+        Err(ExecutionError::ExpiredFeeSponsorship(_))
+    );
+
+    // The records of expired sponsorships are removed when another one is used.
+    let hash = CryptoHash::new(&sponsorship);
+    assert_eq!(
+        view.system.sponsored_fees.get(&hash).await?.unwrap().paid,
+        Amount::ONE
+    );
+    let SystemOperation::SponsorFees {
+        sponsorship: next_sponsorship,
+        public_key,
+        signature,
+    } = FeeSponsorship {
+        expiry: Timestamp::from(200),
+        ..sponsorship
+    }
+    .sign(&secret_key)
+    else {
+        panic!("unexpected operation");
+    };
+    view.system
+        .reserve_sponsor_funds(
+            &next_sponsorship,
+            public_key,
+            &signature,
+            None,
+            Timestamp::from(150),
+        )
+        .await?;
+    assert_eq!(view.system.sponsored_fees.get(&hash).await?, None);
+    assert_eq!(view.system.sponsorship_expiries.count().await?, 0);
+
+    Ok(())
+}
+
+/// Tests that names are registered, renewed, transferred and released on the admin chain.
+#[tokio::test]
+async fn names_are_registered_on_the_admin_chain() -> anyhow::Result<()> {
+    let description = dummy_chain_description(0);
+    let admin_id = ChainId::from(&description);
+    let mut view = SystemExecutionState {
+        description: Some(description),
+        admin_id: Some(admin_id),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+    let alice = AccountOwner::from(CryptoHash::test_hash("alice"));
+    let bob = AccountOwner::from(CryptoHash::test_hash("bob"));
+    let target = NameTarget::Chain(dummy_chain_description(1).id());
+    let register = |expiry: u64| SystemMessage::RegisterName {
+        name: "alice".to_owned(),
+        target,
+        expiry: Timestamp::from(expiry),
+    };
+    let context_at = |signer: AccountOwner, timestamp: u64| MessageContext {
+        timestamp: Timestamp::from(timestamp),
+        ..crate::test_utils::create_dummy_message_context(admin_id, Some(signer))
+    };
+
+    view.system
+        .execute_message(
+            context_at(alice, 0),
+            register(100),
+            None,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(
+        view.system
+            .resolve_name("alice", Timestamp::from(0))
+            .await?,
+        Some(target)
+    );
+
+    // Only the owner can renew the name before it expires.
+    assert_matches!(
+        view.system
+            .execute_message(
+                context_at(bob, 50),
+                register(200),
+                None,
+                &mut ResourceController::default()
+            )
+            .await,
+        Err(ExecutionError::NameAlreadyRegistered(_))
+    );
+    view.system
+        .execute_message(
+            context_at(alice, 50),
+            register(200),
+            None,
+            &mut ResourceController::default(),
+        )
+        .await?;
+
+    // Only the owner can transfer the name.
+    let transfer = |new_owner| SystemMessage::TransferName {
+        name: "alice".to_owned(),
+        new_owner,
+    };
+    assert_matches!(
+        view.system
+            .execute_message(
+                context_at(bob, 60),
+                transfer(bob),
+                None,
+                &mut ResourceController::default()
+            )
+            .await,
+        Err(ExecutionError::UnauthenticatedNameOwner)
+    );
+    view.system
+        .execute_message(
+            context_at(alice, 60),
+            transfer(bob),
+            None,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(view.system.names.get("alice").await?.unwrap().owner, bob);
+
+    // Once expired, the name no longer resolves and anyone can register it.
+    assert_eq!(
+        view.system
+            .resolve_name("alice", Timestamp::from(200))
+            .await?,
+        None
+    );
+    view.system
+        .execute_message(
+            context_at(alice, 200),
+            register(300),
+            None,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(view.system.names.get("alice").await?.unwrap().owner, alice);
+
+    assert_matches!(
+        view.system
+            .execute_message(
+                context_at(alice, 0),
+                SystemMessage::RegisterName {
+                    name: "Alice".to_owned(),
+                    target,
+                    expiry: Timestamp::from(100),
+                },
+                None,
+                &mut ResourceController::default(),
+            )
+            .await,
+        Err(ExecutionError::InvalidName(_))
+    );
+
+    Ok(())
+}
+
+/// Tests that name registrations are charged, and that expired names are removed.
+#[tokio::test]
+async fn name_registrations_are_charged_and_pruned() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let alice = AccountOwner::from(CryptoHash::test_hash("alice"));
+    view.system.balance.set(Amount::from_tokens(3));
+    let target = NameTarget::Chain(context.chain_id);
+    let operation = SystemOperation::RegisterName {
+        name: "alice".to_owned(),
+        target,
+        expiry: Timestamp::from(100),
+    };
+    let context = OperationContext {
+        authenticated_signer: Some(alice),
+        ..context
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            name_registration: Amount::from_tokens(2),
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    let mut txn_tracker = TransactionTracker::default();
+    view.system
+        .execute_operation(
+            context,
+            operation.clone(),
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await?;
+    assert_eq!(*view.system.balance.get(), Amount::ONE);
+    let outgoing_messages = txn_tracker.into_outcome()?.outgoing_messages;
+    let [message] = outgoing_messages.as_slice() else {
+        panic!("expected one message to the admin chain");
+    };
+    assert_eq!(message.grant, Amount::from_tokens(2));
+    assert_eq!(
+        message.refund_grant_to,
+        Some(Account {
+            chain_id: context.chain_id,
+            owner: alice,
+        })
+    );
+    assert_matches!(
+        view.system
+            .execute_operation(
+                context,
+                operation,
+                &mut TransactionTracker::default(),
+                &mut controller
+            )
+            .await,
+        Err(ExecutionError::InsufficientFundingForFees { .. })
+    );
+
+    // On the admin chain, registering a name removes the ones that have expired.
+    let description = dummy_chain_description(0);
+    let admin_id = ChainId::from(&description);
+    let mut view = SystemExecutionState {
+        description: Some(description),
+        admin_id: Some(admin_id),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+    let register = |name: &str, timestamp: u64, expiry: u64| {
+        let context = MessageContext {
+            timestamp: Timestamp::from(timestamp),
+            ..crate::test_utils::create_dummy_message_context(admin_id, Some(alice))
+        };
+        let message = SystemMessage::RegisterName {
+            name: name.to_owned(),
+            target,
+            expiry: Timestamp::from(expiry),
+        };
+        (context, message)
+    };
+    for (name, expiry) in [("old", 100), ("renewed", 100), ("recent", 300)] {
+        let (context, message) = register(name, 0, expiry);
+        view.system
+            .execute_message(context, message, None, &mut ResourceController::default())
+            .await?;
+    }
+    let (context, message) = register("renewed", 50, 300);
+    view.system
+        .execute_message(context, message, None, &mut ResourceController::default())
+        .await?;
+    let (context, message) = register("new", 200, 400);
+    view.system
+        .execute_message(context, message, None, &mut ResourceController::default())
+        .await?;
+    let mut names = view.system.names.indices().await?;
+    names.sort();
+    assert_eq!(names, ["new", "recent", "renewed"]);
+    assert_eq!(view.system.name_expiries.indices().await?.len(), 3);
+
+    Ok(())
+}
+
+/// Tests that registrations with an invalid expiry are not charged, and that the fee of
+/// registrations rejected by the admin chain is refunded.
+#[tokio::test]
+async fn rejected_name_registrations_are_refunded() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let alice = AccountOwner::from(CryptoHash::test_hash("alice"));
+    let bob = AccountOwner::from(CryptoHash::test_hash("bob"));
+    let fee = Amount::from_tokens(2);
+    view.system.balance.set(Amount::from_tokens(3));
+    let policy = Arc::new(ResourceControlPolicy {
+        name_registration: fee,
+        ..ResourceControlPolicy::default()
+    });
+    let mut controller = ResourceController {
+        policy: policy.clone(),
+        ..ResourceController::default()
+    };
+    let target = NameTarget::Chain(context.chain_id);
+    let register = |expiry: u64| SystemMessage::RegisterName {
+        name: "alice".to_owned(),
+        target,
+        expiry: Timestamp::from(expiry),
+    };
+    let context = OperationContext {
+        authenticated_signer: Some(alice),
+        timestamp: Timestamp::from(100),
+        ..context
+    };
+
+    // Registrations that expire in the past or too far in the future are not charged.
+    for expiry in [
+        100,
+        100 + MAXIMUM_NAME_REGISTRATION_DURATION.as_micros() + 1,
+    ] {
+        let SystemMessage::RegisterName {
+            name,
+            target,
+            expiry,
+        } = register(expiry)
+        else {
+            unreachable!();
+        };
+        assert_matches!(
+            view.system
+                .execute_operation(
+                    context,
+                    SystemOperation::RegisterName {
+                        name,
+                        target,
+                        expiry
+                    },
+                    &mut TransactionTracker::default(),
+                    &mut controller,
+                )
+                .await,
+            Err(ExecutionError::InvalidNameExpiry(_))
+        );
+    }
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(3));
+
+    // On the admin chain, the fee is paid with the grant, but only if the registration
+    // succeeds.
+    let description = dummy_chain_description(0);
+    let admin_id = ChainId::from(&description);
+    let mut admin_view = SystemExecutionState {
+        description: Some(description),
+        admin_id: Some(admin_id),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+    let refund_grant_to = Some(Account {
+        chain_id: context.chain_id,
+        owner: alice,
+    });
+    let context_of = |signer| MessageContext {
+        refund_grant_to,
+        ..crate::test_utils::create_dummy_message_context(admin_id, Some(signer))
+    };
+    let mut admin_controller = ResourceController {
+        policy: policy.clone(),
+        ..ResourceController::default()
+    };
+    let mut grant = fee;
+    admin_view
+        .system
+        .execute_message(
+            context_of(bob),
+            register(200),
+            Some(&mut grant),
+            &mut admin_controller,
+        )
+        .await?;
+    assert_eq!(grant, Amount::ZERO);
+    assert_eq!(admin_controller.tracker.fees, fee);
+    let mut grant = fee;
+    assert_matches!(
+        admin_view
+            .system
+            .execute_message(
+                context_of(alice),
+                register(200),
+                Some(&mut grant),
+                &mut admin_controller,
+            )
+            .await,
+        Err(ExecutionError::NameAlreadyRegistered(_))
+    );
+    assert_eq!(grant, fee);
+    let mut insufficient_grant = Amount::ONE;
+    assert_matches!(
+        admin_view
+            .system
+            .execute_message(
+                context_of(alice),
+                SystemMessage::RegisterName {
+                    name: "other".to_owned(),
+                    target,
+                    expiry: Timestamp::from(200),
+                },
+                Some(&mut insufficient_grant),
+                &mut admin_controller,
+            )
+            .await,
+        Err(ExecutionError::InsufficientFundingForFees { .. })
+    );
+
+    // The rejected message bounces back with its grant, which is refunded to the signer.
+    let bouncing_context = MessageContext {
+        chain_id: context.chain_id,
+        is_bouncing: true,
+        ..context_of(alice)
+    };
+    let mut txn_tracker = TransactionTracker::default();
+    view.execute_message(
+        bouncing_context,
+        Message::System(register(200)),
+        Some(&mut grant),
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+    assert_eq!(grant, fee);
+    view.send_refund(bouncing_context, grant, &mut txn_tracker)
+        .await?;
+    let outgoing_messages = txn_tracker.into_outcome()?.outgoing_messages;
+    let [refund] = outgoing_messages.as_slice() else {
+        panic!("expected one refund");
+    };
+    assert_eq!(refund.destination, context.chain_id);
+    assert_eq!(
+        refund.message,
+        Message::System(SystemMessage::Credit {
+            amount: fee,
+            source: alice,
+            target: alice,
+        })
+    );
+
+    Ok(())
+}
+
+/// Tests that only chain owners can set and remove configuration values.
+#[tokio::test]
+async fn config_values_are_set_by_owners() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let owner = AccountOwner::from(CryptoHash::test_hash("owner"));
+    let stranger = AccountOwner::from(CryptoHash::test_hash("stranger"));
+    view.system.ownership.set(ChainOwnership::single(owner));
+    let set_config = |key: &str, value: Option<&[u8]>| SystemOperation::SetConfig {
+        key: key.to_owned(),
+        value: value.map(<[u8]>::to_vec),
+    };
+    let context_of = |signer| OperationContext {
+        authenticated_signer: Some(signer),
+        ..context
+    };
+
+    view.system
+        .execute_operation(
+            context_of(owner),
+            set_config("fee-bps", Some(b"30")),
+            &mut TransactionTracker::default(),
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(
+        view.system.config.get("fee-bps").await?,
+        Some(b"30".to_vec())
+    );
+
+    assert_matches!(
+        view.system
+            .execute_operation(
+                context_of(stranger),
+                set_config("fee-bps", Some(b"0")),
+                &mut TransactionTracker::default(),
+                &mut ResourceController::default(),
+            )
+            .await,
+        Err(ExecutionError::UnauthenticatedConfigChange)
+    );
+    let too_large = vec![0; MAXIMUM_CONFIG_VALUE_LEN + 1];
+    assert_matches!(
+        view.system
+            .execute_operation(
+                context_of(owner),
+                set_config("fee-bps", Some(&too_large)),
+                &mut TransactionTracker::default(),
+                &mut ResourceController::default(),
+            )
+            .await,
+        Err(ExecutionError::ConfigValueTooLarge { .. })
+    );
+    assert_matches!(
+        view.system
+            .execute_operation(
+                context_of(owner),
+                set_config("", Some(b"30")),
+                &mut TransactionTracker::default(),
+                &mut ResourceController::default(),
+            )
+            .await,
+        Err(ExecutionError::InvalidConfigKey(_))
+    );
+
+    view.system
+        .execute_operation(
+            context_of(owner),
+            set_config("fee-bps", None),
+            &mut TransactionTracker::default(),
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(view.system.config.get("fee-bps").await?, None);
+
+    Ok(())
+}
+
+/// Tests that a transfer with a memo moves the tokens like a plain transfer, and that
+/// oversized memos are rejected.
+#[tokio::test]
+async fn transfers_with_memos() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let owner = AccountOwner::from(CryptoHash::test_hash("owner"));
+    view.system
+        .balances
+        .insert(&owner, Amount::from_tokens(10))?;
+    let context = OperationContext {
+        authenticated_signer: Some(owner),
+        ..context
+    };
+    let recipient = Recipient::chain(dummy_chain_description(1).id());
+    let transfer = |ciphertext: Vec<u8>| SystemOperation::TransferWithMemo {
+        owner,
+        recipient,
+        amount: Amount::from_tokens(3),
+        memo: EncryptedMemo {
+            ephemeral_key: Ed25519PublicKey::test_key(1),
+            recipient_key: Ed25519PublicKey::test_key(2),
+            nonce: [0; 24],
+            ciphertext,
+        },
+    };
+
+    let mut txn_tracker = TransactionTracker::default();
+    view.system
+        .execute_operation(
+            context,
+            transfer(vec![0; MAXIMUM_MEMO_LEN]),
+            &mut txn_tracker,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(txn_tracker.into_outcome()?.outgoing_messages.len(), 1);
+    assert_eq!(
+        view.system.balances.get(&owner).await?,
+        Some(Amount::from_tokens(7))
+    );
+
+    assert_matches!(
+        view.system
+            .execute_operation(
+                context,
+                transfer(vec![0; MAXIMUM_MEMO_LEN + 1]),
+                &mut TransactionTracker::default(),
+                &mut ResourceController::default(),
+            )
+            .await,
+        Err(ExecutionError::MemoTooLarge(_))
+    );
+
+    Ok(())
+}
+
+/// Tests that the committees of every chain are checked when it is initialized: they need
+/// valid proofs of possession, and BLS keys if they aggregate the validators' signatures.
+#[tokio::test]
+async fn initial_committees_need_valid_bls_keys() -> anyhow::Result<()> {
+    let key_pair = ValidatorKeypair::generate();
+    let committee =
+        Committee::make_simple(vec![(key_pair.public_key, AccountPublicKey::test_key(0))]);
+    let aggregating_committee = committee
+        .clone()
+        .with_certificate_signature_scheme(CertificateSignatureScheme::AggregatedBls12381);
+    let mut forged_committee = committee.clone();
+    let mut bls_key = BlsSecretKey::from_validator_secret(&key_pair.secret_key).validator_key();
+    bls_key.proof_of_possession =
+        BlsSecretKey::from_validator_secret(&ValidatorKeypair::generate().secret_key)
+            .validator_key()
+            .proof_of_possession;
+    forged_committee
+        .validators
+        .get_mut(&key_pair.public_key)
+        .unwrap()
+        .bls_key = Some(bls_key);
+    let origins = [
+        ChainOrigin::Root(0),
+        ChainOrigin::Child {
+            parent: dummy_chain_description(0).id(),
+            block_height: BlockHeight::ZERO,
+            chain_index: 0,
+        },
+    ];
+
+    for origin in origins {
+        for (committee, is_valid) in [
+            (&committee, true),
+            (&aggregating_committee, false),
+            (&forged_committee, false),
+        ] {
+            let config = InitialChainConfig {
+                admin_id: None,
+                application_permissions: Default::default(),
+                balance: Amount::ZERO,
+                committees: BTreeMap::from([(Epoch::ZERO, bcs::to_bytes(committee)?)]),
+                epoch: Epoch::ZERO,
+                ownership: ChainOwnership::default(),
+            };
+            let description = ChainDescription::new(origin, config, Timestamp::from(0));
+            let chain_id = description.id();
+            let mut view = SystemExecutionState::default()
+                .into_view_with(chain_id, ExecutionRuntimeConfig::default())
+                .await;
+            view.context()
+                .extra()
+                .add_blobs([Blob::new_chain_description(&description)])
+                .await?;
+
+            let result = view.system.initialize_chain(chain_id).await;
+            if is_valid {
+                assert_matches!(result, Ok(false));
+            } else {
+                assert_matches!(result, Err(ExecutionError::InvalidCommitteeBlsKeys(_)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tests that committees serialized before their BLS keys and the policy's name price and
+/// storage limit were appended can still be read, on their own and in a chain's state.
+#[test]
+fn committees_without_appended_fields_can_be_read() -> anyhow::Result<()> {
+    let key_pairs = [ValidatorKeypair::generate(), ValidatorKeypair::generate()];
+    let legacy_validators = key_pairs
+        .iter()
+        .map(|key_pair| {
+            let state = (
+                key_pair.public_key.to_string(),
+                1u64,
+                AccountPublicKey::test_key(0),
+            );
+            (key_pair.public_key, state)
+        })
+        .collect::<BTreeMap<_, _>>();
+    // The prices and the limits of a policy without fees, and its empty HTTP allow list.
+    let legacy_policy = ([Amount::ZERO; 17], [u64::MAX; 12], Vec::<String>::new());
+    let legacy_bytes = bcs::to_bytes(&(&legacy_validators, &legacy_policy))?;
+    let committee = Committee::make_simple(
+        key_pairs
+            .iter()
+            .map(|key_pair| (key_pair.public_key, AccountPublicKey::test_key(0)))
+            .collect(),
+    );
+    assert_eq!(bcs::from_bytes::<Committee>(&legacy_bytes)?, committee);
+
+    let legacy_committees = BTreeMap::from([
+        (Epoch(0), &legacy_validators),
+        (Epoch(1), &legacy_validators),
+    ])
+    .into_iter()
+    .map(|(epoch, validators)| (epoch, (validators, &legacy_policy)))
+    .collect::<BTreeMap<_, _>>();
+    let committees = bcs::from_bytes::<Committees>(&bcs::to_bytes(&legacy_committees)?)?;
+    assert_eq!(
+        committees.0,
+        BTreeMap::from([(Epoch(0), committee.clone()), (Epoch(1), committee.clone())])
+    );
+
+    // New committees keep their BLS keys.
+    let mut committee =
+        committee.with_certificate_signature_scheme(CertificateSignatureScheme::AggregatedBls12381);
+    for key_pair in &key_pairs {
+        committee
+            .validators
+            .get_mut(&key_pair.public_key)
+            .unwrap()
+            .bls_key =
+            Some(BlsSecretKey::from_validator_secret(&key_pair.secret_key).validator_key());
+    }
+    assert!(bcs::to_bytes(&committee)?.starts_with(&legacy_bytes));
+    assert_eq!(
+        bcs::from_bytes::<Committee>(&bcs::to_bytes(&committee)?)?,
+        committee
+    );
+    let committees = Committees(BTreeMap::from([
+        (Epoch(0), committee.clone()),
+        (Epoch(1), committee),
+    ]));
+    assert_eq!(
+        bcs::from_bytes::<Committees>(&bcs::to_bytes(&committees)?)?,
+        committees
+    );
+    Ok(())
+}
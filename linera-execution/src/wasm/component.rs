@@ -0,0 +1,647 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Support for applications compiled to the WebAssembly component model.
+//!
+//! Linera's runtime API is described in WIT and implemented using the canonical ABI over core
+//! Wasm modules. A component targeting the `linera:app` worlds, as produced by `cargo component`
+//! or `wasm-tools component new` for `wasm32-unknown-unknown`, therefore has a main core module
+//! that already uses the ABI expected by the runtime. The other core modules in the component,
+//! like the shims generated to lower the imports, and its nested components, which describe
+//! the types of its exports, only wire the component together, so the component is adapted by
+//! extracting its main module. This is only possible if the component
+//! passes the main module's memory and allocator to the canonical ABI, uses UTF-8 strings and
+//! doesn't need imports other than Linera's, such as WASI.
+
+use linera_base::data_types::Bytecode;
+
+use super::WasmExecutionError;
+
+/// The magic bytes at the start of every WebAssembly binary.
+const MAGIC: [u8; 4] = *b"\0asm";
+/// The version and layer fields of a core module.
+const CORE_MODULE_VERSION: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
+/// The layer field of a component, following its two version bytes.
+const COMPONENT_LAYER: [u8; 2] = [0x01, 0x00];
+/// The module that the Linera runtime API is imported from.
+const RUNTIME_API_PREFIX: &str = "linera:app/";
+/// The prefixes of the exported entrypoints of contracts and services.
+const ENTRYPOINT_PREFIXES: [&str; 2] = [
+    "linera:app/contract-entrypoints#",
+    "linera:app/service-entrypoints#",
+];
+
+/// The identifier of a section containing a core module in a component.
+const CORE_MODULE_SECTION_ID: u8 = 1;
+/// The identifier of a section containing core instances in a component.
+const CORE_INSTANCE_SECTION_ID: u8 = 2;
+/// The identifier of a section containing a nested component.
+const COMPONENT_SECTION_ID: u8 = 4;
+/// The identifier of a section containing aliases in a component.
+const ALIAS_SECTION_ID: u8 = 6;
+/// The identifier of a section containing canonical functions in a component.
+const CANONICAL_SECTION_ID: u8 = 8;
+
+/// The identifier of the import section of a core module.
+const IMPORT_SECTION_ID: u8 = 2;
+/// The identifier of the export section of a core module.
+const EXPORT_SECTION_ID: u8 = 7;
+
+/// The sort of the core items referred to by an alias.
+const CORE_SORT: u8 = 0x00;
+/// The sort of core functions.
+const CORE_FUNCTION_SORT: u8 = 0x00;
+/// The sort of core memories.
+const CORE_MEMORY_SORT: u8 = 0x02;
+/// The sort of core modules.
+const CORE_MODULE_SORT: u8 = 0x11;
+/// The sort of core instances.
+const CORE_INSTANCE_SORT: u8 = 0x12;
+
+/// The target of an alias to an export of a component instance.
+const INSTANCE_EXPORT_TARGET: u8 = 0x00;
+/// The target of an alias to an export of a core instance.
+const CORE_INSTANCE_EXPORT_TARGET: u8 = 0x01;
+/// The target of an alias to an item of an enclosing component.
+const OUTER_TARGET: u8 = 0x02;
+
+/// The canonical function lifting a core function.
+const CANONICAL_LIFT: u8 = 0x00;
+/// The canonical function lowering a component function.
+const CANONICAL_LOWER: u8 = 0x01;
+/// The canonical functions operating on resources.
+const CANONICAL_RESOURCE_NEW: u8 = 0x02;
+const CANONICAL_RESOURCE_DROP: u8 = 0x03;
+const CANONICAL_RESOURCE_REP: u8 = 0x04;
+
+/// The kind of a function import in a core module.
+const FUNCTION_IMPORT: u8 = 0x00;
+
+/// The kind of a WebAssembly binary.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WasmBinaryKind {
+    /// A core WebAssembly module.
+    CoreModule,
+    /// A WebAssembly component.
+    Component,
+}
+
+impl WasmBinaryKind {
+    /// Detects the kind of the WebAssembly binary in `bytes` from its preamble.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        let (magic, rest) = bytes.split_first_chunk::<4>()?;
+        let (version, _) = rest.split_first_chunk::<4>()?;
+        if *magic != MAGIC {
+            return None;
+        }
+        if *version == CORE_MODULE_VERSION {
+            Some(WasmBinaryKind::CoreModule)
+        } else if version[2..] == COMPONENT_LAYER {
+            Some(WasmBinaryKind::Component)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the core module to execute for the `bytecode` of an application.
+///
+/// Core modules are returned unchanged, while components are replaced by their main core
+/// module.
+pub fn adapt_to_core_module(bytecode: Bytecode) -> Result<Bytecode, WasmExecutionError> {
+    match WasmBinaryKind::detect(&bytecode.bytes) {
+        Some(WasmBinaryKind::Component) => Ok(Bytecode::new(
+            extract_core_module(&bytecode.bytes)?.to_vec(),
+        )),
+        // Anything else is left for the module parser to accept or reject.
+        Some(WasmBinaryKind::CoreModule) | None => Ok(bytecode),
+    }
+}
+
+/// Returns the main core module of the component in `bytes`, after checking that it can be
+/// executed on its own.
+fn extract_core_module(bytes: &[u8]) -> Result<&[u8], WasmExecutionError> {
+    let layout = ComponentLayout::parse(bytes)?;
+    let mut main_modules = Vec::new();
+    for (index, module) in layout.modules.iter().enumerate() {
+        if exports_entrypoints(module)? {
+            main_modules.push((index, *module));
+        }
+    }
+    let (main_index, main_module) = match main_modules[..] {
+        [main_module] => main_module,
+        [] => {
+            return Err(WasmExecutionError::UnsupportedComponent(
+                "no core module exporting the Linera entrypoints",
+            ));
+        }
+        _ => {
+            return Err(WasmExecutionError::UnsupportedComponent(
+                "more than one core module exporting the Linera entrypoints",
+            ));
+        }
+    };
+    if !imports_only_runtime_api(main_module)? {
+        return Err(WasmExecutionError::UnsupportedComponent(
+            "imports other than the Linera runtime API, such as WASI",
+        ));
+    }
+    let main_index = u32::try_from(main_index).map_err(|_| WasmExecutionError::InvalidComponent)?;
+    let mut main_instances = layout
+        .core_instances
+        .iter()
+        .enumerate()
+        .filter(|(_, module)| **module == Some(main_index));
+    let (Some((main_instance, _)), None) = (main_instances.next(), main_instances.next()) else {
+        return Err(WasmExecutionError::UnsupportedComponent(
+            "not exactly one instance of its main core module",
+        ));
+    };
+    let main_instance =
+        u32::try_from(main_instance).map_err(|_| WasmExecutionError::InvalidComponent)?;
+    for function in &layout.lifted_functions {
+        if layout.core_functions.get(*function as usize) != Some(&Some(main_instance)) {
+            return Err(WasmExecutionError::UnsupportedComponent(
+                "exports that are not implemented by its main core module",
+            ));
+        }
+    }
+    for options in &layout.canonical_options {
+        layout.check_canonical_options(options, main_instance)?;
+    }
+    Ok(main_module)
+}
+
+/// An option of a function lifted or lowered with the canonical ABI.
+enum CanonicalOption {
+    Utf8,
+    Utf16,
+    CompactUtf16,
+    Memory(u32),
+    Realloc(u32),
+    PostReturn(u32),
+}
+
+/// The parts of a component's index spaces needed to find out how its core modules are used.
+#[derive(Default)]
+struct ComponentLayout<'a> {
+    /// The core modules.
+    modules: Vec<&'a [u8]>,
+    /// The module that each core instance instantiates, if any.
+    core_instances: Vec<Option<u32>>,
+    /// The core instance exporting each core function, if it is aliased from one.
+    core_functions: Vec<Option<u32>>,
+    /// The core instance exporting each core memory.
+    core_memories: Vec<u32>,
+    /// The core functions lifted to component functions.
+    lifted_functions: Vec<u32>,
+    /// The options of each function lifted or lowered with the canonical ABI.
+    canonical_options: Vec<Vec<CanonicalOption>>,
+}
+
+impl<'a> ComponentLayout<'a> {
+    /// Parses the top level of the component in `bytes`.
+    fn parse(bytes: &'a [u8]) -> Result<Self, WasmExecutionError> {
+        let mut layout = ComponentLayout::default();
+        for section in Sections::new(bytes) {
+            let (section_id, mut reader) = section?;
+            match section_id {
+                CORE_MODULE_SECTION_ID => layout.modules.push(reader.bytes),
+                COMPONENT_SECTION_ID => check_nested_component(reader.bytes)?,
+                CORE_INSTANCE_SECTION_ID => {
+                    for _ in 0..reader.read_u32()? {
+                        let module = match reader.read_u8()? {
+                            0x00 => {
+                                let module_index = reader.read_u32()?;
+                                for _ in 0..reader.read_u32()? {
+                                    reader.read_name()?;
+                                    reader.read_u8()?;
+                                    reader.read_u32()?;
+                                }
+                                Some(module_index)
+                            }
+                            0x01 => {
+                                for _ in 0..reader.read_u32()? {
+                                    reader.read_name()?;
+                                    reader.read_u8()?;
+                                    reader.read_u32()?;
+                                }
+                                None
+                            }
+                            _ => return Err(WasmExecutionError::InvalidComponent),
+                        };
+                        layout.core_instances.push(module);
+                    }
+                }
+                ALIAS_SECTION_ID => {
+                    for _ in 0..reader.read_u32()? {
+                        layout.parse_alias(&mut reader)?;
+                    }
+                }
+                CANONICAL_SECTION_ID => {
+                    for _ in 0..reader.read_u32()? {
+                        layout.parse_canonical_function(&mut reader)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Parses an alias, adding the core function or memory it refers to.
+    fn parse_alias(&mut self, reader: &mut Reader<'a>) -> Result<(), WasmExecutionError> {
+        let core_sort = match reader.read_u8()? {
+            CORE_SORT => Some(reader.read_u8()?),
+            _ => None,
+        };
+        let core_instance_index = match reader.read_u8()? {
+            INSTANCE_EXPORT_TARGET => {
+                reader.read_u32()?;
+                reader.read_name()?;
+                None
+            }
+            CORE_INSTANCE_EXPORT_TARGET => {
+                let instance_index = reader.read_u32()?;
+                reader.read_name()?;
+                Some(instance_index)
+            }
+            OUTER_TARGET => {
+                reader.read_u32()?;
+                reader.read_u32()?;
+                None
+            }
+            _ => return Err(WasmExecutionError::InvalidComponent),
+        };
+        match (core_sort, core_instance_index) {
+            (Some(CORE_MODULE_SORT), _) => {
+                return Err(WasmExecutionError::UnsupportedComponent(
+                    "aliased core modules",
+                ));
+            }
+            (Some(CORE_FUNCTION_SORT), Some(instance_index)) => {
+                self.core_functions.push(Some(instance_index));
+            }
+            (Some(CORE_MEMORY_SORT), Some(instance_index)) => {
+                self.core_memories.push(instance_index);
+            }
+            // Core functions and memories can only be aliased from the exports of core
+            // instances, and core instances not at all.
+            (Some(CORE_FUNCTION_SORT | CORE_MEMORY_SORT | CORE_INSTANCE_SORT), _) => {
+                return Err(WasmExecutionError::InvalidComponent);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parses a canonical function, adding the core function it lifts or lowers.
+    fn parse_canonical_function(
+        &mut self,
+        reader: &mut Reader<'a>,
+    ) -> Result<(), WasmExecutionError> {
+        match reader.read_u8()? {
+            CANONICAL_LIFT => {
+                if reader.read_u8()? != CORE_FUNCTION_SORT {
+                    return Err(WasmExecutionError::InvalidComponent);
+                }
+                let core_function_index = reader.read_u32()?;
+                let options = Self::parse_canonical_options(reader)?;
+                reader.read_u32()?;
+                self.lifted_functions.push(core_function_index);
+                self.canonical_options.push(options);
+            }
+            CANONICAL_LOWER => {
+                if reader.read_u8()? != CORE_FUNCTION_SORT {
+                    return Err(WasmExecutionError::InvalidComponent);
+                }
+                reader.read_u32()?;
+                let options = Self::parse_canonical_options(reader)?;
+                self.canonical_options.push(options);
+                self.core_functions.push(None);
+            }
+            CANONICAL_RESOURCE_NEW | CANONICAL_RESOURCE_DROP | CANONICAL_RESOURCE_REP => {
+                return Err(WasmExecutionError::UnsupportedComponent("resources"));
+            }
+            _ => return Err(WasmExecutionError::InvalidComponent),
+        }
+        Ok(())
+    }
+
+    /// Parses the options of a function lifted or lowered with the canonical ABI.
+    fn parse_canonical_options(
+        reader: &mut Reader<'a>,
+    ) -> Result<Vec<CanonicalOption>, WasmExecutionError> {
+        (0..reader.read_u32()?)
+            .map(|_| {
+                Ok(match reader.read_u8()? {
+                    0x00 => CanonicalOption::Utf8,
+                    0x01 => CanonicalOption::Utf16,
+                    0x02 => CanonicalOption::CompactUtf16,
+                    0x03 => CanonicalOption::Memory(reader.read_u32()?),
+                    0x04 => CanonicalOption::Realloc(reader.read_u32()?),
+                    0x05 => CanonicalOption::PostReturn(reader.read_u32()?),
+                    _ => return Err(WasmExecutionError::InvalidComponent),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that a function lifted or lowered with these canonical ABI `options` behaves as
+    /// the runtime expects when calling into or from the given instance of the main module.
+    fn check_canonical_options(
+        &self,
+        options: &[CanonicalOption],
+        main_instance: u32,
+    ) -> Result<(), WasmExecutionError> {
+        for option in options {
+            match option {
+                CanonicalOption::Utf8 => {}
+                CanonicalOption::Utf16 | CanonicalOption::CompactUtf16 => {
+                    return Err(WasmExecutionError::UnsupportedComponent(
+                        "strings that are not encoded in UTF-8",
+                    ));
+                }
+                CanonicalOption::Memory(index) => {
+                    if self.core_memories.get(*index as usize) != Some(&main_instance) {
+                        return Err(WasmExecutionError::UnsupportedComponent(
+                            "functions using the memory of another core module",
+                        ));
+                    }
+                }
+                CanonicalOption::Realloc(index) | CanonicalOption::PostReturn(index) => {
+                    if self.core_functions.get(*index as usize) != Some(&Some(main_instance)) {
+                        return Err(WasmExecutionError::UnsupportedComponent(
+                            "functions using the allocator of another core module",
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a component nested in another one only describes the types of its exports.
+fn check_nested_component(bytes: &[u8]) -> Result<(), WasmExecutionError> {
+    if WasmBinaryKind::detect(bytes) != Some(WasmBinaryKind::Component) {
+        return Err(WasmExecutionError::InvalidComponent);
+    }
+    for section in Sections::new(bytes) {
+        match section?.0 {
+            CORE_MODULE_SECTION_ID | CORE_INSTANCE_SECTION_ID => {
+                return Err(WasmExecutionError::UnsupportedComponent(
+                    "core modules in nested components",
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the core module in `bytes` exports Linera contract or service entrypoints.
+fn exports_entrypoints(bytes: &[u8]) -> Result<bool, WasmExecutionError> {
+    if WasmBinaryKind::detect(bytes) != Some(WasmBinaryKind::CoreModule) {
+        return Err(WasmExecutionError::InvalidComponent);
+    }
+    for section in Sections::new(bytes) {
+        let (section_id, mut reader) = section?;
+        if section_id != EXPORT_SECTION_ID {
+            continue;
+        }
+        for _ in 0..reader.read_u32()? {
+            let name = reader.read_name()?;
+            reader.read_u8()?;
+            reader.read_u32()?;
+            if ENTRYPOINT_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Returns whether the core module in `bytes` only imports functions of the Linera runtime API.
+fn imports_only_runtime_api(bytes: &[u8]) -> Result<bool, WasmExecutionError> {
+    for section in Sections::new(bytes) {
+        let (section_id, mut reader) = section?;
+        if section_id != IMPORT_SECTION_ID {
+            continue;
+        }
+        for _ in 0..reader.read_u32()? {
+            let module = reader.read_name()?;
+            reader.read_name()?;
+            if !module.starts_with(RUNTIME_API_PREFIX) || reader.read_u8()? != FUNCTION_IMPORT {
+                return Ok(false);
+            }
+            reader.read_u32()?;
+        }
+    }
+    Ok(true)
+}
+
+/// An iterator over the sections of a core module or a component, with their identifiers.
+struct Sections<'a> {
+    reader: Reader<'a>,
+}
+
+impl<'a> Sections<'a> {
+    /// Iterates over the sections of the WebAssembly binary in `bytes`, after its preamble.
+    fn new(bytes: &'a [u8]) -> Self {
+        let preamble_length = MAGIC.len() + CORE_MODULE_VERSION.len();
+        Sections {
+            reader: Reader {
+                bytes: bytes.get(preamble_length..).unwrap_or_default(),
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = Result<(u8, Reader<'a>), WasmExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.bytes.is_empty() {
+            return None;
+        }
+        let section = (|| {
+            let section_id = self.reader.read_u8()?;
+            let size = self.reader.read_u32()?;
+            let contents = self.reader.read_bytes(size)?;
+            Ok((section_id, Reader { bytes: contents }))
+        })();
+        if section.is_err() {
+            self.reader.bytes = &[];
+        }
+        Some(section)
+    }
+}
+
+/// A reader of the values encoded in the sections of a WebAssembly binary.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Reads a single byte.
+    fn read_u8(&mut self) -> Result<u8, WasmExecutionError> {
+        let (&byte, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(WasmExecutionError::InvalidComponent)?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    /// Reads an unsigned LEB128-encoded 32-bit integer.
+    fn read_u32(&mut self) -> Result<u32, WasmExecutionError> {
+        let mut value = 0u32;
+        for index in 0..5 {
+            let byte = self.read_u8()?;
+            let bits = u32::from(byte & 0x7f);
+            if index == 4 && bits > 0x0f {
+                return Err(WasmExecutionError::InvalidComponent);
+            }
+            value |= bits << (7 * index);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WasmExecutionError::InvalidComponent)
+    }
+
+    /// Reads the given number of bytes.
+    fn read_bytes(&mut self, length: u32) -> Result<&'a [u8], WasmExecutionError> {
+        let length = usize::try_from(length).map_err(|_| WasmExecutionError::InvalidComponent)?;
+        if self.bytes.len() < length {
+            return Err(WasmExecutionError::InvalidComponent);
+        }
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// Reads a name, encoded as its length followed by its UTF-8 bytes.
+    fn read_name(&mut self) -> Result<&'a str, WasmExecutionError> {
+        let length = self.read_u32()?;
+        std::str::from_utf8(self.read_bytes(length)?)
+            .map_err(|_| WasmExecutionError::InvalidComponent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::data_types::Bytecode;
+
+    use super::{adapt_to_core_module, WasmBinaryKind};
+    use crate::wasm::WasmExecutionError;
+
+    /// Returns the binary encoding of a module or component in the text format.
+    fn wat2wasm(text: &str) -> Vec<u8> {
+        wasmer::wat2wasm(text.as_bytes()).unwrap().into_owned()
+    }
+
+    /// A core module exporting a contract entrypoint.
+    const MAIN_MODULE: &str = r#"
+        (module $main
+            (import "linera:app/base-runtime-api" "log" (func (param i32 i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "linera:app/contract-entrypoints#instantiate") (param i32 i32))
+            (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32) i32.const 0)
+        )
+    "#;
+
+    /// Builds a component that instantiates `main_module` and lifts its entrypoint with the
+    /// given string encoding.
+    fn component(main_module: &str, encoding: &str) -> Vec<u8> {
+        wat2wasm(&format!(
+            r#"
+            (component
+                (core module $shim
+                    (func (export "log") (param i32 i32 i32))
+                    (func (export "fd_write") (param i32 i32 i32 i32) (result i32) i32.const 0)
+                )
+                (core instance $shim (instantiate $shim))
+                (core module {main_module_fields})
+                (core instance $main (instantiate $main
+                    (with "linera:app/base-runtime-api" (instance $shim))
+                    (with "wasi_snapshot_preview1" (instance $shim))
+                ))
+                (alias core export $main "memory" (core memory $memory))
+                (alias core export $main "cabi_realloc" (core func $realloc))
+                (func (export "instantiate") (param "argument" string)
+                    (canon lift
+                        (core func $main "linera:app/contract-entrypoints#instantiate")
+                        {encoding} (memory $memory) (realloc $realloc)
+                    )
+                )
+            )
+            "#,
+            main_module_fields = main_module
+                .trim()
+                .strip_prefix("(module")
+                .and_then(|fields| fields.strip_suffix(')'))
+                .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_detect_binary_kind() {
+        assert_eq!(
+            WasmBinaryKind::detect(&wat2wasm("(module)")),
+            Some(WasmBinaryKind::CoreModule)
+        );
+        assert_eq!(
+            WasmBinaryKind::detect(&wat2wasm("(component)")),
+            Some(WasmBinaryKind::Component)
+        );
+        assert_eq!(WasmBinaryKind::detect(b"\0asm"), None);
+        assert_eq!(WasmBinaryKind::detect(b"not wasm"), None);
+    }
+
+    #[test]
+    fn test_core_module_is_unchanged() {
+        let bytecode = Bytecode::new(wat2wasm(MAIN_MODULE));
+        assert_eq!(adapt_to_core_module(bytecode.clone()).unwrap(), bytecode);
+    }
+
+    #[test]
+    fn test_main_module_is_extracted_from_component() {
+        let bytecode = Bytecode::new(component(MAIN_MODULE, "string-encoding=utf8"));
+        assert_eq!(
+            adapt_to_core_module(bytecode).unwrap(),
+            Bytecode::new(wat2wasm(MAIN_MODULE))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_components_are_rejected() {
+        let wasi_module = MAIN_MODULE.replace(
+            r#"(memory"#,
+            r#"(import "wasi_snapshot_preview1" "fd_write"
+                (func (param i32 i32 i32 i32) (result i32)))
+            (memory"#,
+        );
+        for bytes in [
+            wat2wasm("(component)"),
+            wat2wasm("(component (component (core module)))"),
+            component(&wasi_module, "string-encoding=utf8"),
+            component(MAIN_MODULE, "string-encoding=utf16"),
+        ] {
+            assert!(matches!(
+                adapt_to_core_module(Bytecode::new(bytes)),
+                Err(WasmExecutionError::UnsupportedComponent(_))
+            ));
+        }
+        let mut truncated = component(MAIN_MODULE, "string-encoding=utf8");
+        truncated.pop();
+        assert!(matches!(
+            adapt_to_core_module(Bytecode::new(truncated)),
+            Err(WasmExecutionError::InvalidComponent)
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Canonicalization of contract bytecode so that it executes deterministically.
+//!
+//! The only source of nondeterminism in the WebAssembly instructions accepted by the module
+//! parser is the bit pattern of the NaN values produced by floating-point operations: the
+//! specification allows any NaN to be returned, and different runtimes and architectures
+//! return different ones. Proposals with further nondeterministic semantics, such as threads
+//! and SIMD, are not supported by the parser, so modules using them are rejected.
+//!
+//! The runtimes' own NaN canonicalization isn't available for every runtime and target, so
+//! every floating-point instruction that may produce a NaN is followed by instructions
+//! replacing the result with the canonical NaN if it is a NaN.
+
+use linera_base::data_types::Bytecode;
+use wasm_instrument::parity_wasm::{
+    self,
+    elements::{Instruction, Local, Module, Type, ValueType},
+};
+
+use super::WasmExecutionError;
+
+/// The bit pattern of the canonical 32-bit NaN.
+const CANONICAL_F32_NAN: u32 = 0x7fc0_0000;
+/// The bit pattern of the canonical 64-bit NaN.
+const CANONICAL_F64_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+/// Rewrites the contract `bytecode` so that all NaN values it produces are canonical.
+pub fn canonicalize_nans(bytecode: Bytecode) -> Result<Bytecode, WasmExecutionError> {
+    let mut module: Module = parity_wasm::deserialize_buffer(&bytecode.bytes)?;
+    let parameter_counts = function_parameter_counts(&module)?;
+
+    if let Some(code_section) = module.code_section_mut() {
+        for (body, parameter_count) in code_section.bodies_mut().iter_mut().zip(parameter_counts) {
+            let produces_nans = |value_type| {
+                body.code()
+                    .elements()
+                    .iter()
+                    .any(|instruction| nan_result_type(instruction) == Some(value_type))
+            };
+            if !produces_nans(ValueType::F32) && !produces_nans(ValueType::F64) {
+                continue;
+            }
+
+            // Add two scratch locals to hold the results being checked.
+            let local_count = body
+                .locals()
+                .iter()
+                .try_fold(parameter_count, |count, local| {
+                    count.checked_add(local.count())
+                })
+                .ok_or(WasmExecutionError::InstrumentModule)?;
+            let f32_scratch = local_count;
+            let f64_scratch = local_count
+                .checked_add(1)
+                .ok_or(WasmExecutionError::InstrumentModule)?;
+            body.locals_mut().push(Local::new(1, ValueType::F32));
+            body.locals_mut().push(Local::new(1, ValueType::F64));
+
+            let instructions = body.code_mut().elements_mut();
+            let mut canonicalized = Vec::with_capacity(instructions.len());
+            for instruction in instructions.drain(..) {
+                let result_type = nan_result_type(&instruction);
+                canonicalized.push(instruction);
+                match result_type {
+                    Some(ValueType::F32) => canonicalized.extend([
+                        Instruction::TeeLocal(f32_scratch),
+                        Instruction::F32Const(CANONICAL_F32_NAN),
+                        Instruction::GetLocal(f32_scratch),
+                        Instruction::GetLocal(f32_scratch),
+                        Instruction::F32Eq,
+                        Instruction::Select,
+                    ]),
+                    Some(ValueType::F64) => canonicalized.extend([
+                        Instruction::TeeLocal(f64_scratch),
+                        Instruction::F64Const(CANONICAL_F64_NAN),
+                        Instruction::GetLocal(f64_scratch),
+                        Instruction::GetLocal(f64_scratch),
+                        Instruction::F64Eq,
+                        Instruction::Select,
+                    ]),
+                    _ => {}
+                }
+            }
+            *instructions = canonicalized;
+        }
+    }
+
+    Ok(Bytecode::new(module.into_bytes()?))
+}
+
+/// Returns the number of parameters of each function defined in the `module`.
+fn function_parameter_counts(module: &Module) -> Result<Vec<u32>, WasmExecutionError> {
+    let types = module
+        .type_section()
+        .map(|section| section.types())
+        .unwrap_or_default();
+    let Some(function_section) = module.function_section() else {
+        return Ok(Vec::new());
+    };
+    function_section
+        .entries()
+        .iter()
+        .map(|function| {
+            let Some(Type::Function(function_type)) = types.get(function.type_ref() as usize)
+            else {
+                return Err(WasmExecutionError::InstrumentModule);
+            };
+            u32::try_from(function_type.params().len())
+                .map_err(|_| WasmExecutionError::InstrumentModule)
+        })
+        .collect()
+}
+
+/// Returns the type of the result of `instruction` if it may be a NaN with an unspecified bit
+/// pattern.
+///
+/// Instructions that only manipulate the sign bit, like `abs`, `neg` and `copysign`, or that
+/// reinterpret bits, preserve the bit pattern of their input and are therefore deterministic.
+fn nan_result_type(instruction: &Instruction) -> Option<ValueType> {
+    use Instruction::*;
+
+    match instruction {
+        F32Add | F32Sub | F32Mul | F32Div | F32Min | F32Max | F32Sqrt | F32Ceil | F32Floor
+        | F32Trunc | F32Nearest | F32DemoteF64 => Some(ValueType::F32),
+        F64Add | F64Sub | F64Mul | F64Div | F64Min | F64Max | F64Sqrt | F64Ceil | F64Floor
+        | F64Trunc | F64Nearest | F64PromoteF32 => Some(ValueType::F64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::data_types::Bytecode;
+    use wasm_instrument::parity_wasm::{
+        self, builder,
+        elements::{Instruction, Instructions, Module, ValueType},
+    };
+
+    use super::{canonicalize_nans, CANONICAL_F32_NAN};
+
+    /// Builds a module with a single function taking two `f32` parameters.
+    fn module_with_function(instructions: Vec<Instruction>) -> Bytecode {
+        let module = builder::module()
+            .function()
+            .signature()
+            .with_params(vec![ValueType::F32, ValueType::F32])
+            .with_result(ValueType::F32)
+            .build()
+            .body()
+            .with_instructions(Instructions::new(instructions))
+            .build()
+            .build()
+            .build();
+        Bytecode::new(module.into_bytes().unwrap())
+    }
+
+    fn function_body(bytecode: &Bytecode) -> (usize, Vec<Instruction>) {
+        let module: Module = parity_wasm::deserialize_buffer(&bytecode.bytes).unwrap();
+        let body = &module.code_section().unwrap().bodies()[0];
+        (body.locals().len(), body.code().elements().to_vec())
+    }
+
+    #[test]
+    fn test_nan_producing_instructions_are_canonicalized() {
+        let bytecode = module_with_function(vec![
+            Instruction::GetLocal(0),
+            Instruction::GetLocal(1),
+            Instruction::F32Add,
+            Instruction::F32Neg,
+            Instruction::End,
+        ]);
+
+        let (local_count, instructions) = function_body(&canonicalize_nans(bytecode).unwrap());
+
+        assert_eq!(local_count, 2);
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::GetLocal(0),
+                Instruction::GetLocal(1),
+                Instruction::F32Add,
+                Instruction::TeeLocal(2),
+                Instruction::F32Const(CANONICAL_F32_NAN),
+                Instruction::GetLocal(2),
+                Instruction::GetLocal(2),
+                Instruction::F32Eq,
+                Instruction::Select,
+                Instruction::F32Neg,
+                Instruction::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_functions_without_nan_producing_instructions_are_unchanged() {
+        let instructions = vec![
+            Instruction::GetLocal(0),
+            Instruction::F32Abs,
+            Instruction::End,
+        ];
+        let bytecode = module_with_function(instructions.clone());
+
+        assert_eq!(
+            function_body(&canonicalize_nans(bytecode).unwrap()),
+            (0, instructions)
+        );
+    }
+}
@@ -7,9 +7,14 @@
 //!
 //! - `wasmer` enables the [Wasmer](https://wasmer.io/) runtime
 //! - `wasmtime` enables the [Wasmtime](https://wasmtime.dev/) runtime
+//!
+//! Applications may be compiled either to core Wasm modules or to Wasm components wrapping a
+//! core module that targets the `linera:app` worlds.
 
 #![cfg(with_wasm_runtime)]
 
+mod component;
+mod determinism;
 mod entrypoints;
 mod module_cache;
 #[macro_use]
@@ -36,6 +41,7 @@ use {
 };
 
 pub use self::{
+    component::WasmBinaryKind,
     entrypoints::{ContractEntrypoints, ServiceEntrypoints},
     runtime_api::{BaseRuntimeApi, ContractRuntimeApi, RuntimeApiData, ServiceRuntimeApi},
 };
@@ -82,7 +88,9 @@ impl WasmContractModule {
         contract_bytecode: Bytecode,
         runtime: WasmRuntime,
     ) -> Result<Self, WasmExecutionError> {
-        let contract_bytecode = add_metering(contract_bytecode)?;
+        let contract_bytecode = add_metering(component::adapt_to_core_module(contract_bytecode)?)?;
+        // Canonicalize after metering, so that the added instructions don't consume fuel.
+        let contract_bytecode = determinism::canonicalize_nans(contract_bytecode)?;
         match runtime {
             #[cfg(with_wasmer)]
             WasmRuntime::Wasmer => Self::from_wasmer(contract_bytecode).await,
@@ -146,6 +154,7 @@ impl WasmServiceModule {
         service_bytecode: Bytecode,
         runtime: WasmRuntime,
     ) -> Result<Self, WasmExecutionError> {
+        let service_bytecode = component::adapt_to_core_module(service_bytecode)?;
         match runtime {
             #[cfg(with_wasmer)]
             WasmRuntime::Wasmer => Self::from_wasmer(service_bytecode).await,
@@ -307,6 +316,10 @@ pub enum WasmExecutionError {
     LoadServiceModule(#[source] anyhow::Error),
     #[error("Failed to instrument Wasm module to add fuel metering")]
     InstrumentModule,
+    #[error("Invalid Wasm component")]
+    InvalidComponent,
+    #[error("Unsupported Wasm component: it has {0}")]
+    UnsupportedComponent(&'static str),
     #[error("Invalid Wasm module")]
     InvalidBytecode(#[from] wasm_instrument::parity_wasm::SerializationError),
     #[cfg(with_wasmer)]
@@ -185,6 +185,18 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Returns the configuration value set by the chain owners for the `key`, if any.
+    fn read_config_value(
+        caller: &mut Caller,
+        key: String,
+    ) -> Result<Option<Vec<u8>>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_config_value(key)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Makes an HTTP request to the given URL and returns the response body.
     fn perform_http_request(
         caller: &mut Caller,
@@ -226,8 +238,9 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
-    /// Logs a `message` with the provided information `level`.
-    fn log(_caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
+    /// Logs a `message` with the provided information `level`, and captures it in the
+    /// transaction's outcome.
+    fn log(caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
         match level {
             log::Level::Trace => tracing::trace!("{message}"),
             log::Level::Debug => tracing::debug!("{message}"),
@@ -235,7 +248,11 @@ where
             log::Level::Warn => tracing::warn!("{message}"),
             log::Level::Error => tracing::error!("{message}"),
         }
-        Ok(())
+        caller
+            .user_data_mut()
+            .runtime
+            .capture_log(level.into(), message)
+            .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
     /// Creates a new promise to check if the `key` is in storage.
@@ -569,6 +586,25 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Reads the events with indices from `start` to `end` (exclusive) from a stream this
+    /// application is subscribed to. Returns the events' values.
+    ///
+    /// Returns an error if the range goes beyond the events this chain has already processed.
+    fn read_subscribed_events(
+        caller: &mut Caller,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        name: StreamName,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Vec<u8>>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_subscribed_events(chain_id, application_id, name, start..end)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Subscribes this application to an event stream.
     fn subscribe_to_events(
         caller: &mut Caller,
@@ -218,6 +218,7 @@ async fn test_fee_consumption(
         message_byte: Amount::from_tokens(31),
         service_as_oracle_query: Amount::from_millis(37),
         http_request: Amount::from_tokens(41),
+        name_registration: Amount::from_tokens(43),
         maximum_fuel_per_block: 4_868_145_137,
         maximum_block_size: 43,
         maximum_service_oracle_execution_ms: 47,
@@ -229,6 +230,7 @@ async fn test_fee_consumption(
         maximum_bytes_written_per_block: 73,
         maximum_oracle_response_bytes: 79,
         maximum_http_response_bytes: 83,
+        maximum_application_storage_bytes: 109,
         http_request_timeout_ms: 89,
         blob_read: Amount::from_tokens(97),
         blob_published: Amount::from_tokens(101),
@@ -163,3 +163,38 @@ async fn test_read_balance_owners_system_api(
 
     view.query_application(context, query, None).await.unwrap();
 }
+
+/// Tests the service system API to read the chain's configuration values.
+#[tokio::test]
+async fn test_read_config_value_system_api() {
+    let mut view = SystemExecutionState::new(dummy_chain_description(0))
+        .into_view()
+        .await;
+    view.system
+        .config
+        .insert("fee-bps", b"30".to_vec())
+        .unwrap();
+
+    let (application_id, application, _) = view.register_mock_application(0).await.unwrap();
+
+    application.expect_call(ExpectedCall::handle_query(move |runtime, _query| {
+        assert_eq!(
+            runtime.read_config_value("fee-bps".to_owned()).unwrap(),
+            Some(b"30".to_vec())
+        );
+        assert_eq!(
+            runtime.read_config_value("missing".to_owned()).unwrap(),
+            None
+        );
+        Ok(vec![])
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = create_dummy_query_context();
+    let query = Query::User {
+        application_id,
+        bytes: vec![],
+    };
+
+    view.query_application(context, query, None).await.unwrap();
+}
@@ -3,20 +3,25 @@
 
 #![allow(clippy::field_reassign_with_default)]
 
-use std::{collections::BTreeMap, vec};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    vec,
+};
 
 use assert_matches::assert_matches;
 use linera_base::{
-    crypto::{AccountPublicKey, ValidatorPublicKey},
+    crypto::{AccountPublicKey, CryptoHash, ValidatorPublicKey},
     data_types::{
         Amount, ApplicationPermissions, Blob, BlockHeight, ChainDescription, ChainOrigin, Epoch,
-        InitialChainConfig, Resources, SendMessageRequest, Timestamp,
+        InitialChainConfig, OracleResponse, Resources, SendMessageRequest, Timestamp,
     },
-    identifiers::{Account, AccountOwner, BlobType},
+    identifiers::{Account, AccountOwner, ApplicationId, BlobType, EventId, StreamId, StreamName},
     ownership::ChainOwnership,
 };
 use linera_execution::{
     committee::Committee,
+    system::EventSubscriptions,
     test_utils::{
         blob_oracle_responses, create_dummy_message_context, create_dummy_operation_context,
         create_dummy_user_application_registrations, dummy_chain_description,
@@ -25,7 +30,7 @@ use linera_execution::{
     },
     BaseRuntime, ContractRuntime, ExecutionError, ExecutionRuntimeContext, Message, Operation,
     OperationContext, OutgoingMessage, Query, QueryContext, QueryOutcome, QueryResponse,
-    ResourceController, SystemOperation, TransactionTracker,
+    ResourceControlPolicy, ResourceController, SystemOperation, TransactionTracker,
 };
 use linera_views::{batch::Batch, context::Context, views::View};
 use test_case::test_case;
@@ -1256,7 +1261,7 @@ async fn test_open_chain() -> anyhow::Result<()> {
     assert_eq!(*child_view.system.balance.get(), Amount::ONE);
     assert_eq!(*child_view.system.ownership.get(), child_ownership);
     assert_eq!(
-        *child_view.system.committees.get(),
+        **child_view.system.committees.get(),
         committees
             .into_iter()
             .map(|(epoch, serialized_committee)| (
@@ -1349,6 +1354,171 @@ async fn test_close_chain() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Tests the contract API call `read_subscribed_events`.
+#[tokio::test]
+async fn test_read_subscribed_events() -> anyhow::Result<()> {
+    let (state, chain_id) = SystemExecutionState::dummy_chain_state(0);
+    let mut view = state.into_view().await;
+    let (application_id, application, blobs) = view.register_mock_application(0).await?;
+
+    let publisher_id = ApplicationId::new(CryptoHash::test_hash("publisher"));
+    let publisher_chain_id = dummy_chain_description(1).id();
+    let stream_name = StreamName(b"updates".to_vec());
+    let stream_id = StreamId {
+        application_id: publisher_id.into(),
+        stream_name: stream_name.clone(),
+    };
+    let events = (0..3)
+        .map(|index| {
+            let event_id = EventId {
+                chain_id: publisher_chain_id,
+                stream_id: stream_id.clone(),
+                index,
+            };
+            (event_id, vec![index as u8])
+        })
+        .collect::<Vec<_>>();
+    view.context().extra().add_events(events.clone()).await?;
+    // Only the first two events have been processed by this chain so far.
+    view.system.event_subscriptions.insert(
+        &(publisher_chain_id, stream_id),
+        EventSubscriptions {
+            next_index: 2,
+            applications: BTreeSet::from([application_id]),
+        },
+    )?;
+
+    application.expect_call(ExpectedCall::execute_operation({
+        let stream_name = stream_name.clone();
+        move |runtime, _operation| {
+            let values = runtime.read_subscribed_events(
+                publisher_chain_id,
+                publisher_id,
+                stream_name,
+                0..2,
+            )?;
+            assert_eq!(values, vec![vec![0], vec![1]]);
+            Ok(vec![])
+        }
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+    let mut oracle_responses = blob_oracle_responses(blobs.iter());
+    oracle_responses.extend(
+        events
+            .into_iter()
+            .take(2)
+            .map(|(event_id, value)| OracleResponse::Event(event_id, value)),
+    );
+    view.execute_operation(
+        create_dummy_operation_context(chain_id),
+        operation.clone(),
+        &mut TransactionTracker::new_replaying(oracle_responses),
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    // Reading beyond the processed events fails, even though they are in storage.
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _operation| {
+            runtime.read_subscribed_events(publisher_chain_id, publisher_id, stream_name, 1..3)?;
+            Ok(vec![])
+        },
+    ));
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(chain_id),
+            operation,
+            &mut TransactionTracker::new_replaying_blobs(blobs),
+            &mut ResourceController::default(),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::EventRangeNotAvailable {
+            start: 1,
+            end: 3,
+            next_index: 2,
+        })
+    );
+
+    Ok(())
+}
+
+/// Tests that an application cannot grow its state beyond the storage quota.
+#[tokio::test]
+async fn test_application_storage_quota() -> anyhow::Result<()> {
+    let (state, chain_id) = SystemExecutionState::dummy_chain_state(0);
+    let mut view = state.into_view().await;
+    let (application_id, application, blobs) = view.register_mock_application(0).await?;
+    let policy = Arc::new(ResourceControlPolicy {
+        maximum_application_storage_bytes: 100,
+        ..ResourceControlPolicy::default()
+    });
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+
+    // Writing within the quota succeeds.
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _operation| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0], vec![0; 50]);
+            runtime.write_batch(batch)?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+    let mut controller = ResourceController {
+        policy: policy.clone(),
+        ..ResourceController::default()
+    };
+    view.execute_operation(
+        create_dummy_operation_context(chain_id),
+        operation.clone(),
+        &mut TransactionTracker::new_replaying_blobs(blobs.iter()),
+        &mut controller,
+    )
+    .await?;
+
+    // Growing the state beyond the quota fails.
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _operation| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![1], vec![0; 50]);
+            runtime.write_batch(batch)?;
+            Ok(vec![])
+        },
+    ));
+    let mut controller = ResourceController {
+        policy,
+        ..ResourceController::default()
+    };
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(chain_id),
+            operation,
+            &mut TransactionTracker::new_replaying_blobs(blobs),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::ApplicationStorageQuotaExceeded {
+            application_id: id,
+            limit: 100,
+            size: 102,
+        }) if id == application_id
+    );
+
+    Ok(())
+}
+
 /// Tests an application attempting to transfer the tokens in the chain's balance while executing
 /// messages.
 #[test_case(
@@ -7,7 +7,7 @@
 
 use linera_base::crypto::ValidatorPublicKey;
 use linera_client::config::GenesisConfig;
-use linera_faucet::ClaimOutcome;
+use linera_faucet::{ClaimBatchOutcome, ClaimOutcome, ProvisionOutcome};
 use linera_version::VersionInfo;
 use thiserror_context::Context;
 
@@ -42,6 +42,15 @@ impl Faucet {
     async fn query<Response: serde::de::DeserializeOwned>(
         &self,
         query: &str,
+    ) -> Result<Response, Error> {
+        self.query_with_token(query, None).await
+    }
+
+    /// Sends the query, with `token` as the bearer token if present.
+    async fn query_with_token<Response: serde::de::DeserializeOwned>(
+        &self,
+        query: &str,
+        token: Option<&str>,
     ) -> Result<Response, Error> {
         #[derive(serde::Deserialize)]
         struct GraphQlResponse<T> {
@@ -54,10 +63,11 @@ impl Faucet {
         #[cfg(not(target_arch = "wasm32"))]
         let builder = builder.timeout(std::time::Duration::from_secs(30));
 
-        let response: GraphQlResponse<Response> = builder
-            .build()
-            .unwrap()
-            .post(&self.url)
+        let mut request = builder.build().unwrap().post(&self.url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response: GraphQlResponse<Response> = request
             .json(&serde_json::json!({
                 "query": query,
             }))
@@ -117,6 +127,54 @@ impl Faucet {
         Ok(self.query::<Response>(&query).await?.claim)
     }
 
+    pub async fn claim_batch(
+        &self,
+        owners: &[linera_base::identifiers::AccountOwner],
+    ) -> Result<ClaimBatchOutcome, Error> {
+        let owners = owners
+            .iter()
+            .map(|owner| format!("\"{owner}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "mutation {{ claimBatch(owners: [{owners}]) {{ \
+                chains certificateHashes \
+            }} }}"
+        );
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            claim_batch: ClaimBatchOutcome,
+        }
+
+        Ok(self.query::<Response>(&query).await?.claim_batch)
+    }
+
+    /// Creates `count` chains with new keys, using the faucet's provisioning `token`.
+    pub async fn provision_chains(
+        &self,
+        count: u32,
+        token: &str,
+    ) -> Result<ProvisionOutcome, Error> {
+        let query = format!(
+            "mutation {{ provisionChains(count: {count}) {{ \
+                chains {{ description owner secretKey }} certificateHashes \
+            }} }}"
+        );
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            provision_chains: ProvisionOutcome,
+        }
+
+        Ok(self
+            .query_with_token::<Response>(&query, Some(token))
+            .await?
+            .provision_chains)
+    }
+
     pub async fn current_validators(&self) -> Result<Vec<(ValidatorPublicKey, String)>, Error> {
         #[derive(serde::Deserialize)]
         #[serde(rename_all = "camelCase")]
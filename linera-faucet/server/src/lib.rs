@@ -3,29 +3,45 @@
 
 //! The server component of the Linera faucet.
 
-use std::{future::IntoFuture, net::SocketAddr, num::NonZeroU16, sync::Arc};
+mod limits;
 
-use async_graphql::{EmptySubscription, Error, Schema, SimpleObject};
+use std::{
+    future::IntoFuture,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU16,
+    sync::Arc,
+};
+
+use async_graphql::{Context, EmptySubscription, Error, Schema, SimpleObject};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{Extension, Router};
+use axum::{
+    extract::ConnectInfo,
+    http::{header::AUTHORIZATION, HeaderMap},
+    Extension, Router,
+};
 use futures::{lock::Mutex, FutureExt as _};
 use linera_base::{
-    crypto::{CryptoHash, ValidatorPublicKey},
-    data_types::{Amount, ApplicationPermissions, BlockHeight, Timestamp},
+    crypto::{AccountSecretKey, CryptoHash, CryptoRng, ValidatorPublicKey},
+    data_types::{Amount, ApplicationPermissions, BlockHeight, ChainDescription, Timestamp},
     identifiers::{AccountOwner, ChainId},
     ownership::ChainOwnership,
+    time::Instant,
 };
+use linera_chain::types::ConfirmedBlockCertificate;
 use linera_client::{
     chain_listener::{ChainListener, ChainListenerConfig, ClientContext},
     config::GenesisConfig,
 };
-use linera_core::data_types::ClientOutcome;
+use linera_core::{client::ChainClient, data_types::ClientOutcome};
+use linera_execution::system::OpenChainConfig;
 use linera_storage::{Clock as _, Storage};
 use serde::Deserialize;
 use tokio_util::sync::CancellationToken;
 use tower_http::cors::CorsLayer;
 use tracing::info;
 
+pub use crate::limits::{ClaimLimiter, ClaimLimitsConfig};
+
 /// Returns an HTML response constructing the GraphiQL web page for the given URI.
 pub(crate) async fn graphiql(uri: axum::http::Uri) -> impl axum::response::IntoResponse {
     axum::response::Html(
@@ -55,8 +71,17 @@ pub struct MutationRoot<C> {
     end_timestamp: Timestamp,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    limiter: Arc<ClaimLimiter>,
 }
 
+/// The IP address that a request came from, if known.
+#[derive(Clone, Copy, Debug)]
+struct ClientAddress(Option<IpAddr>);
+
+/// The bearer token that a request presented, if any.
+#[derive(Clone, Debug)]
+struct BearerToken(Option<String>);
+
 /// The result of a successful `claim` mutation.
 #[derive(SimpleObject)]
 pub struct ClaimOutcome {
@@ -66,6 +91,38 @@ pub struct ClaimOutcome {
     pub certificate_hash: CryptoHash,
 }
 
+/// The result of a successful `claimBatch` mutation.
+#[derive(SimpleObject)]
+pub struct ClaimBatchOutcome {
+    /// The descriptions of the new chains, in the order of the requested owners.
+    pub chains: Vec<ChainDescription>,
+    /// The hashes of the parent chain's certificates containing the `OpenChain` operations.
+    pub certificate_hashes: Vec<CryptoHash>,
+}
+
+/// A chain created by a `provisionChains` mutation, with the key of its owner.
+#[derive(SimpleObject)]
+pub struct ProvisionedChain {
+    /// The description of the new chain.
+    pub description: ChainDescription,
+    /// The owner of the new chain.
+    pub owner: AccountOwner,
+    /// The owner's secret key, in the format of the wallet's keystore.
+    pub secret_key: serde_json::Value,
+}
+
+/// The result of a successful `provisionChains` mutation.
+#[derive(SimpleObject)]
+pub struct ProvisionOutcome {
+    /// The new chains.
+    pub chains: Vec<ProvisionedChain>,
+    /// The hashes of the parent chain's certificates containing the `OpenChain` operations.
+    pub certificate_hashes: Vec<CryptoHash>,
+}
+
+/// The maximum number of chains that a batch claim creates in a single block.
+const MAX_CHAINS_PER_BLOCK: usize = 100;
+
 #[derive(Debug, Deserialize, SimpleObject)]
 pub struct Validator {
     pub public_key: ValidatorPublicKey,
@@ -114,8 +171,54 @@ where
     C: ClientContext,
 {
     /// Creates a new chain with the given authentication key, and transfers tokens to it.
-    async fn claim(&self, owner: AccountOwner) -> Result<ClaimOutcome, Error> {
-        self.do_claim(owner).await
+    ///
+    /// If the faucet requires it, `verification` is a token proving that the caller is a
+    /// person, e.g. a captcha response.
+    async fn claim(
+        &self,
+        ctx: &Context<'_>,
+        owner: AccountOwner,
+        verification: Option<String>,
+    ) -> Result<ClaimOutcome, Error> {
+        let ip = ctx
+            .data_opt::<ClientAddress>()
+            .and_then(|address| address.0);
+        self.do_claim(owner, ip, verification.as_deref()).await
+    }
+
+    /// Creates a new chain for each of the given authentication keys, and transfers tokens
+    /// to each of them. The chains are created in as few blocks as possible, e.g. to
+    /// provision many chains for a benchmark.
+    ///
+    /// This is only available if the faucet allows batch claims.
+    async fn claim_batch(
+        &self,
+        ctx: &Context<'_>,
+        owners: Vec<AccountOwner>,
+        verification: Option<String>,
+    ) -> Result<ClaimBatchOutcome, Error> {
+        let ip = ctx
+            .data_opt::<ClientAddress>()
+            .and_then(|address| address.0);
+        self.do_claim_batch(owners, ip, verification.as_deref())
+            .await
+    }
+
+    /// Creates `count` chains with new keys, and transfers tokens to each of them, e.g. for
+    /// CI or workshops. The response contains the secret keys, so the faucet should only
+    /// be reached over HTTPS.
+    ///
+    /// The request must present the faucet's provisioning token in an
+    /// `Authorization: Bearer` header. These chains don't count against the claim limits.
+    async fn provision_chains(
+        &self,
+        ctx: &Context<'_>,
+        count: u32,
+    ) -> Result<ProvisionOutcome, Error> {
+        let token = ctx
+            .data_opt::<BearerToken>()
+            .and_then(|token| token.0.as_deref());
+        self.do_provision_chains(count as usize, token).await
     }
 }
 
@@ -123,7 +226,13 @@ impl<C> MutationRoot<C>
 where
     C: ClientContext,
 {
-    async fn do_claim(&self, owner: AccountOwner) -> Result<ClaimOutcome, Error> {
+    async fn do_claim(
+        &self,
+        owner: AccountOwner,
+        ip: Option<IpAddr>,
+        verification: Option<&str>,
+    ) -> Result<ClaimOutcome, Error> {
+        self.limiter.verify(owner, ip, verification).await?;
         let chain_id = *self.chain_id.lock().await;
         let client = self
             .context
@@ -131,7 +240,153 @@ where
             .await
             .make_chain_client(chain_id)
             .await?;
+        let local_time = client.storage_client().clock().current_time();
+        self.limiter
+            .record(owner, ip, self.amount, local_time)
+            .await?;
+        let start = Instant::now();
+        let result = self.open_chains(&client, &[owner]).await;
+        if result.is_err() {
+            self.limiter.refund(owner, ip, self.amount).await?;
+        }
+        let (descriptions, certificate) = result?;
+        let outcome = ClaimOutcome {
+            chain_id: descriptions[0].id(),
+            certificate_hash: certificate.hash(),
+        };
+        info!(
+            chain_id = %outcome.chain_id,
+            %owner,
+            message_type = "claim",
+            latency_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "Opened a chain"
+        );
+        Ok(outcome)
+    }
 
+    async fn do_claim_batch(
+        &self,
+        owners: Vec<AccountOwner>,
+        ip: Option<IpAddr>,
+        verification: Option<&str>,
+    ) -> Result<ClaimBatchOutcome, Error> {
+        let max_batch_claims = self.limiter.max_batch_claims();
+        if max_batch_claims == 0 {
+            return Err(Error::new("This faucet doesn't allow batch claims."));
+        }
+        if owners.len() > max_batch_claims {
+            return Err(Error::new(format!(
+                "At most {max_batch_claims} chains can be claimed at once."
+            )));
+        }
+        for owner in &owners {
+            self.limiter.verify(*owner, ip, verification).await?;
+        }
+        let storage = self.context.lock().await.storage().clone();
+        let local_time = storage.clock().current_time();
+        for (index, owner) in owners.iter().enumerate() {
+            if let Err(error) = self
+                .limiter
+                .record(*owner, ip, self.amount, local_time)
+                .await
+            {
+                for owner in &owners[..index] {
+                    self.limiter.refund(*owner, ip, self.amount).await?;
+                }
+                return Err(error);
+            }
+        }
+
+        match self.open_chains_in_blocks(&owners).await {
+            Ok(outcome) => Ok(outcome),
+            Err((created, error)) => {
+                for owner in &owners[created..] {
+                    self.limiter.refund(*owner, ip, self.amount).await?;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    async fn do_provision_chains(
+        &self,
+        count: usize,
+        token: Option<&str>,
+    ) -> Result<ProvisionOutcome, Error> {
+        self.limiter.check_provisioning_token(token)?;
+        let max_provisioned_chains = self.limiter.max_provisioned_chains();
+        if count > max_provisioned_chains {
+            return Err(Error::new(format!(
+                "At most {max_provisioned_chains} chains can be provisioned at once."
+            )));
+        }
+        let mut rng: Box<dyn CryptoRng> = None.into();
+        let secret_keys = (0..count)
+            .map(|_| AccountSecretKey::generate_from(&mut rng))
+            .collect::<Vec<_>>();
+        let owners = secret_keys
+            .iter()
+            .map(|secret_key| AccountOwner::from(secret_key.public()))
+            .collect::<Vec<_>>();
+        let outcome = self
+            .open_chains_in_blocks(&owners)
+            .await
+            .map_err(|(_, error)| error)?;
+        let chains = outcome
+            .chains
+            .into_iter()
+            .zip(owners)
+            .zip(&secret_keys)
+            .map(|((description, owner), secret_key)| {
+                Ok(ProvisionedChain {
+                    description,
+                    owner,
+                    secret_key: serde_json::to_value(secret_key)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(ProvisionOutcome {
+            chains,
+            certificate_hashes: outcome.certificate_hashes,
+        })
+    }
+
+    /// Creates a new chain for each owner, in blocks of at most [`MAX_CHAINS_PER_BLOCK`]
+    /// chains. On failure, also returns the number of chains that were created.
+    async fn open_chains_in_blocks(
+        &self,
+        owners: &[AccountOwner],
+    ) -> Result<ClaimBatchOutcome, (usize, Error)> {
+        let mut outcome = ClaimBatchOutcome {
+            chains: Vec::with_capacity(owners.len()),
+            certificate_hashes: Vec::new(),
+        };
+        for batch in owners.chunks(MAX_CHAINS_PER_BLOCK) {
+            // The faucet may have switched to a new chain after the previous block.
+            let chain_id = *self.chain_id.lock().await;
+            let client = self.context.lock().await.make_chain_client(chain_id).await;
+            let result = match client {
+                Ok(client) => self.open_chains(&client, batch).await,
+                Err(error) => Err(error.into()),
+            };
+            match result {
+                Ok((descriptions, certificate)) => {
+                    outcome.chains.extend(descriptions);
+                    outcome.certificate_hashes.push(certificate.hash());
+                }
+                Err(error) => return Err((outcome.chains.len(), error)),
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Creates a new chain for each owner in a single block.
+    async fn open_chains(
+        &self,
+        client: &ChainClient<C::Environment>,
+        owners: &[AccountOwner],
+    ) -> Result<(Vec<ChainDescription>, ConfirmedBlockCertificate), Error> {
+        let amount = self.amount.try_mul(owners.len() as u128)?;
         if self.start_timestamp < self.end_timestamp {
             let local_time = client.storage_client().clock().current_time();
             if local_time < self.end_timestamp {
@@ -141,7 +396,7 @@ where
                     .as_micros();
                 let remaining_duration = self.end_timestamp.delta_since(local_time).as_micros();
                 let balance = client.local_balance().await?;
-                let Ok(remaining_balance) = balance.try_sub(self.amount) else {
+                let Ok(remaining_balance) = balance.try_sub(amount) else {
                     return Err(Error::new("The faucet is empty."));
                 };
                 // The tokens unlock linearly, e.g. if 1/3 of the time is left, then 1/3 of the
@@ -156,12 +411,17 @@ where
             }
         }
 
-        let ownership = ChainOwnership::single(owner);
-        let result = client
-            .open_chain(ownership, ApplicationPermissions::default(), self.amount)
-            .await;
-        self.context.lock().await.update_wallet(&client).await?;
-        let (chain_id, certificate) = match result? {
+        let configs = owners
+            .iter()
+            .map(|owner| OpenChainConfig {
+                ownership: ChainOwnership::single(*owner),
+                balance: self.amount,
+                application_permissions: ApplicationPermissions::default(),
+            })
+            .collect();
+        let result = client.open_chains(configs).await;
+        self.context.lock().await.update_wallet(client).await?;
+        let (descriptions, certificate) = match result? {
             ClientOutcome::Committed(result) => result,
             ClientOutcome::WaitForTimeout(timeout) => {
                 return Err(Error::new(format!(
@@ -195,10 +455,7 @@ where
             *self.chain_id.lock().await = chain_id;
         }
 
-        Ok(ClaimOutcome {
-            chain_id,
-            certificate_hash: certificate.hash(),
-        })
+        Ok((descriptions, certificate))
     }
 }
 
@@ -230,6 +487,7 @@ where
     end_block_height: BlockHeight,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    limiter: Arc<ClaimLimiter>,
 }
 
 impl<C> Clone for FaucetService<C>
@@ -249,6 +507,7 @@ where
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
+            limiter: Arc::clone(&self.limiter),
         }
     }
 }
@@ -268,8 +527,10 @@ where
         end_timestamp: Timestamp,
         genesis_config: Arc<GenesisConfig>,
         config: ChainListenerConfig,
+        limits: ClaimLimitsConfig,
         storage: <C::Environment as linera_core::Environment>::Storage,
     ) -> anyhow::Result<Self> {
+        let limiter = Arc::new(ClaimLimiter::new(limits)?);
         let client = context.make_chain_client(chain_id).await?;
         let context = Arc::new(Mutex::new(context));
         let start_timestamp = client.storage_client().clock().current_time();
@@ -287,6 +548,7 @@ where
             end_timestamp,
             start_timestamp,
             start_balance,
+            limiter,
         })
     }
 
@@ -299,6 +561,7 @@ where
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
+            limiter: Arc::clone(&self.limiter),
         };
         let query_root = QueryRoot {
             genesis_config: Arc::clone(&self.genesis_config),
@@ -327,7 +590,11 @@ where
             ChainListener::new(self.config, self.context, self.storage, cancellation_token).run();
         let tcp_listener =
             tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?;
-        let server = axum::serve(tcp_listener, app).into_future();
+        let server = axum::serve(
+            tcp_listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .into_future();
         futures::select! {
             result = Box::pin(chain_listener).fuse() => result?,
             result = Box::pin(server).fuse() => result?,
@@ -337,9 +604,30 @@ where
     }
 
     /// Executes a GraphQL query and generates a response for our `Schema`.
-    async fn index_handler(service: Extension<Self>, request: GraphQLRequest) -> GraphQLResponse {
+    async fn index_handler(
+        service: Extension<Self>,
+        ConnectInfo(address): ConnectInfo<SocketAddr>,
+        headers: HeaderMap,
+        request: GraphQLRequest,
+    ) -> GraphQLResponse {
+        let forwarded_ip = service
+            .limiter
+            .trust_forwarded_for()
+            .then(|| headers.get("x-forwarded-for")?.to_str().ok())
+            .flatten()
+            .and_then(|value| value.split(',').next()?.trim().parse().ok());
+        let ip = forwarded_ip.unwrap_or(address.ip());
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_owned);
         let schema = service.0.schema();
-        schema.execute(request.into_inner()).await.into()
+        let request = request
+            .into_inner()
+            .data(ClientAddress(Some(ip)))
+            .data(BearerToken(token));
+        schema.execute(request).await.into()
     }
 }
 
@@ -0,0 +1,280 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits on the chains that the faucet gives away.
+//!
+//! Claims are counted per IP address, per owner and in total over fixed time windows. The
+//! counters can be kept in a file, so that restarting the faucet doesn't reset them. An
+//! external service, e.g. one checking captcha responses or OAuth tokens, can be asked to
+//! approve each claim.
+
+use std::{collections::BTreeMap, net::IpAddr, path::PathBuf};
+
+use async_graphql::Error;
+use futures::lock::Mutex;
+use linera_base::{
+    data_types::{Amount, TimeDelta, Timestamp},
+    identifiers::AccountOwner,
+};
+use linera_client::persistent::{self, PersistExt as _};
+use serde::{Deserialize, Serialize};
+
+/// The configuration of the faucet's rate limits.
+#[derive(Clone, Debug, clap::Args)]
+pub struct ClaimLimitsConfig {
+    /// The length of the time windows over which claims are counted, in seconds.
+    #[arg(long = "rate-limit-window", default_value = "86400")]
+    pub window_secs: u64,
+
+    /// The maximum number of chains that one IP address can claim per time window.
+    #[arg(long)]
+    pub max_claims_per_ip: Option<u32>,
+
+    /// The maximum number of chains that one owner can claim per time window.
+    #[arg(long)]
+    pub max_claims_per_owner: Option<u32>,
+
+    /// The maximum number of tokens that the faucet gives away per time window.
+    #[arg(long)]
+    pub max_tokens_per_window: Option<Amount>,
+
+    /// The file in which the claim counters are kept across restarts.
+    #[arg(long)]
+    pub rate_limit_state: Option<PathBuf>,
+
+    /// Use the first address of the `X-Forwarded-For` header as the client's IP address,
+    /// e.g. if the faucet runs behind a reverse proxy.
+    #[arg(long)]
+    pub trust_forwarded_for: bool,
+
+    /// A URL that must approve each claim. The faucet posts the claim's `owner`, `ip` and
+    /// `verification` token as a JSON object, and only accepts the claim if the response
+    /// is successful.
+    #[arg(long)]
+    pub verification_webhook: Option<String>,
+
+    /// The maximum number of chains that one `claimBatch` request can create, e.g. to
+    /// provision chains for benchmarks. Batch claims are disabled if this is zero.
+    #[arg(long, default_value = "0")]
+    pub max_batch_claims: usize,
+
+    /// The token that `provisionChains` requests must present as a bearer token. These
+    /// requests create and fund chains with new keys, without counting them against the
+    /// limits above, e.g. for CI or workshops. Provisioning is disabled if this is not set.
+    #[arg(long, env = "LINERA_FAUCET_PROVISIONING_TOKEN", hide_env_values = true)]
+    pub provisioning_token: Option<String>,
+
+    /// The maximum number of chains that one `provisionChains` request can create.
+    #[arg(long, default_value = "1000")]
+    pub max_provisioned_chains: usize,
+}
+
+impl Default for ClaimLimitsConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 86400,
+            max_claims_per_ip: None,
+            max_claims_per_owner: None,
+            max_tokens_per_window: None,
+            rate_limit_state: None,
+            trust_forwarded_for: false,
+            verification_webhook: None,
+            max_batch_claims: 0,
+            provisioning_token: None,
+            max_provisioned_chains: 1000,
+        }
+    }
+}
+
+/// The claims of the current time window.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ClaimCounters {
+    window_start: Timestamp,
+    claims_per_ip: BTreeMap<IpAddr, u32>,
+    claims_per_owner: BTreeMap<AccountOwner, u32>,
+    tokens: Amount,
+}
+
+enum Counters {
+    File(persistent::File<ClaimCounters>),
+    Memory(persistent::Memory<ClaimCounters>),
+}
+
+impl Counters {
+    async fn mutate<R: Send>(
+        &mut self,
+        mutation: impl FnOnce(&mut ClaimCounters) -> R + Send,
+    ) -> Result<R, Error> {
+        Ok(match self {
+            Counters::File(file) => file.mutate(mutation).await?,
+            Counters::Memory(memory) => memory.mutate(mutation).await?,
+        })
+    }
+}
+
+/// The body of a request to the verification webhook.
+#[derive(Serialize)]
+struct VerificationRequest<'a> {
+    owner: AccountOwner,
+    ip: Option<IpAddr>,
+    verification: Option<&'a str>,
+}
+
+/// Checks and counts the claims.
+pub struct ClaimLimiter {
+    config: ClaimLimitsConfig,
+    counters: Mutex<Counters>,
+    http: reqwest::Client,
+}
+
+impl ClaimLimiter {
+    /// Creates a limiter with the given configuration, reading the counters from the
+    /// configured file if it exists.
+    pub fn new(config: ClaimLimitsConfig) -> anyhow::Result<Self> {
+        let counters = match &config.rate_limit_state {
+            Some(path) => Counters::File(persistent::File::read_or_create(path, || {
+                Ok(ClaimCounters::default())
+            })?),
+            None => Counters::Memory(persistent::Memory::new(ClaimCounters::default())),
+        };
+        Ok(Self {
+            config,
+            counters: Mutex::new(counters),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Returns whether the `X-Forwarded-For` header should be used as the client address.
+    pub fn trust_forwarded_for(&self) -> bool {
+        self.config.trust_forwarded_for
+    }
+
+    /// Returns the maximum number of chains that one batch claim can create.
+    pub fn max_batch_claims(&self) -> usize {
+        self.config.max_batch_claims
+    }
+
+    /// Returns the maximum number of chains that one provisioning request can create.
+    pub fn max_provisioned_chains(&self) -> usize {
+        self.config.max_provisioned_chains
+    }
+
+    /// Returns an error unless provisioning is enabled and `token` is the provisioning
+    /// token.
+    pub fn check_provisioning_token(&self, token: Option<&str>) -> Result<(), Error> {
+        let Some(expected) = self
+            .config
+            .provisioning_token
+            .as_deref()
+            .filter(|expected| !expected.is_empty())
+        else {
+            return Err(Error::new("This faucet doesn't allow provisioning chains."));
+        };
+        let Some(token) = token else {
+            return Err(Error::new("Provisioning chains requires a bearer token."));
+        };
+        // Compare in constant time, so that the response time doesn't reveal the token.
+        let difference = token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        if token.len() != expected.len() || difference != 0 {
+            return Err(Error::new("Invalid provisioning token."));
+        }
+        Ok(())
+    }
+
+    /// Asks the verification webhook, if any, to approve the claim.
+    pub async fn verify(
+        &self,
+        owner: AccountOwner,
+        ip: Option<IpAddr>,
+        verification: Option<&str>,
+    ) -> Result<(), Error> {
+        let Some(url) = &self.config.verification_webhook else {
+            return Ok(());
+        };
+        let request = VerificationRequest {
+            owner,
+            ip,
+            verification,
+        };
+        let response = self.http.post(url).json(&request).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::new("The claim was not verified."));
+        }
+        Ok(())
+    }
+
+    /// Counts a claim of `amount` tokens at time `now`, or returns an error if that
+    /// exceeds a limit. A claim that then fails must be [`refund`](Self::refund)ed.
+    pub async fn record(
+        &self,
+        owner: AccountOwner,
+        ip: Option<IpAddr>,
+        amount: Amount,
+        now: Timestamp,
+    ) -> Result<(), Error> {
+        let window = TimeDelta::from_secs(self.config.window_secs);
+        let config = &self.config;
+        self.counters
+            .lock()
+            .await
+            .mutate(|counters| {
+                if now.delta_since(counters.window_start) >= window {
+                    *counters = ClaimCounters {
+                        window_start: now,
+                        ..ClaimCounters::default()
+                    };
+                }
+                let tokens = counters.tokens.try_add(amount)?;
+                if config.max_tokens_per_window.is_some_and(|max| tokens > max) {
+                    return Err(Error::new(
+                        "The faucet gave away all tokens for now; try again later.",
+                    ));
+                }
+                let ip_claims = ip.map(|ip| counters.claims_per_ip.get(&ip).map_or(1, |n| n + 1));
+                if let (Some(max), Some(claims)) = (config.max_claims_per_ip, ip_claims) {
+                    if claims > max {
+                        return Err(Error::new("Too many claims from this address."));
+                    }
+                }
+                let owner_claims = counters.claims_per_owner.get(&owner).map_or(1, |n| n + 1);
+                if config
+                    .max_claims_per_owner
+                    .is_some_and(|max| owner_claims > max)
+                {
+                    return Err(Error::new("Too many claims for this owner."));
+                }
+                counters.tokens = tokens;
+                if let (Some(ip), Some(claims)) = (ip, ip_claims) {
+                    counters.claims_per_ip.insert(ip, claims);
+                }
+                counters.claims_per_owner.insert(owner, owner_claims);
+                Ok(())
+            })
+            .await?
+    }
+
+    /// Reverts a claim counted by [`record`](Self::record) that didn't succeed.
+    pub async fn refund(
+        &self,
+        owner: AccountOwner,
+        ip: Option<IpAddr>,
+        amount: Amount,
+    ) -> Result<(), Error> {
+        self.counters
+            .lock()
+            .await
+            .mutate(|counters| {
+                counters.tokens = counters.tokens.saturating_sub(amount);
+                if let Some(claims) = ip.and_then(|ip| counters.claims_per_ip.get_mut(&ip)) {
+                    *claims = claims.saturating_sub(1);
+                }
+                if let Some(claims) = counters.claims_per_owner.get_mut(&owner) {
+                    *claims = claims.saturating_sub(1);
+                }
+            })
+            .await
+    }
+}
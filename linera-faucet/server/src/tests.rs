@@ -8,9 +8,10 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use futures::lock::Mutex;
 use linera_base::{
-    crypto::{AccountPublicKey, InMemorySigner},
+    crypto::{AccountPublicKey, AccountSecretKey, InMemorySigner},
     data_types::{Amount, BlockHeight, Timestamp},
     identifiers::{AccountOwner, ChainId},
+    ownership::ChainOwnership,
 };
 use linera_client::{chain_listener, wallet::Wallet};
 use linera_core::{
@@ -19,7 +20,7 @@ use linera_core::{
     test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder as _, TestBuilder},
 };
 
-use super::MutationRoot;
+use super::{ClaimLimiter, ClaimLimitsConfig, MutationRoot};
 
 struct ClientContext {
     client: ChainClient<environment::Test>,
@@ -96,46 +97,209 @@ async fn test_faucet_rate_limiting() {
         end_timestamp: Timestamp::from(6000),
         start_timestamp: Timestamp::from(0),
         start_balance: Amount::from_tokens(6),
+        limiter: Arc::new(ClaimLimiter::new(ClaimLimitsConfig::default()).unwrap()),
     };
     // The faucet is releasing one token every 1000 microseconds. So at 1000 one claim should
     // succeed. At 3000, two more should have been unlocked.
     clock.set(Timestamp::from(999));
     assert!(root
-        .do_claim(AccountPublicKey::test_key(0).into())
+        .do_claim(AccountPublicKey::test_key(0).into(), None, None)
         .await
         .is_err());
     clock.set(Timestamp::from(1000));
     assert!(root
-        .do_claim(AccountPublicKey::test_key(1).into())
+        .do_claim(AccountPublicKey::test_key(1).into(), None, None)
         .await
         .is_ok());
     assert!(root
-        .do_claim(AccountPublicKey::test_key(2).into())
+        .do_claim(AccountPublicKey::test_key(2).into(), None, None)
         .await
         .is_err());
     clock.set(Timestamp::from(3000));
     assert!(root
-        .do_claim(AccountPublicKey::test_key(3).into())
+        .do_claim(AccountPublicKey::test_key(3).into(), None, None)
         .await
         .is_ok());
     assert!(root
-        .do_claim(AccountPublicKey::test_key(4).into())
+        .do_claim(AccountPublicKey::test_key(4).into(), None, None)
         .await
         .is_ok());
     assert!(root
-        .do_claim(AccountPublicKey::test_key(5).into())
+        .do_claim(AccountPublicKey::test_key(5).into(), None, None)
         .await
         .is_err());
     // If a validator is offline, it will create a pending block and then fail.
     clock.set(Timestamp::from(6000));
     builder.set_fault_type([0, 1], FaultType::Offline).await;
     assert!(root
-        .do_claim(AccountPublicKey::test_key(6).into())
+        .do_claim(AccountPublicKey::test_key(6).into(), None, None)
         .await
         .is_err());
     assert_eq!(context.lock().await.update_calls, 4); // Also called in the last error case.
 }
 
+#[tokio::test]
+async fn test_faucet_batch_claim() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let mut keys = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut keys)
+        .await
+        .unwrap();
+    let client = builder
+        .add_root_chain(1, Amount::from_tokens(10))
+        .await
+        .unwrap();
+    let chain_id = client.chain_id();
+    let context = ClientContext {
+        client,
+        update_calls: 0,
+    };
+    let context = Arc::new(Mutex::new(context));
+    let limits = ClaimLimitsConfig {
+        max_batch_claims: 3,
+        ..ClaimLimitsConfig::default()
+    };
+    let root = MutationRoot {
+        chain_id: Arc::new(Mutex::new(chain_id)),
+        context: context.clone(),
+        amount: Amount::from_tokens(1),
+        end_block_height: BlockHeight::from(10),
+        end_timestamp: Timestamp::from(0),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(10),
+        limiter: Arc::new(ClaimLimiter::new(limits).unwrap()),
+    };
+    let owners = (0..4)
+        .map(|i| AccountPublicKey::test_key(i).into())
+        .collect::<Vec<AccountOwner>>();
+    // The batch is too large.
+    assert!(root
+        .do_claim_batch(owners.clone(), None, None)
+        .await
+        .is_err());
+    let outcome = root
+        .do_claim_batch(owners[..3].to_vec(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(outcome.chains.len(), 3);
+    assert_eq!(outcome.certificate_hashes.len(), 1);
+    for (description, owner) in outcome.chains.iter().zip(&owners) {
+        assert_eq!(
+            description.config().ownership,
+            ChainOwnership::single(*owner)
+        );
+    }
+    let client = context.lock().await.client.clone();
+    assert_eq!(client.next_block_height(), BlockHeight::from(1));
+    assert_eq!(
+        client.local_balance().await.unwrap(),
+        Amount::from_tokens(7)
+    );
+    assert_eq!(context.lock().await.update_calls, 1);
+}
+
+#[tokio::test]
+async fn test_faucet_provisioning() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let mut keys = InMemorySigner::new(None);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1, &mut keys)
+        .await
+        .unwrap();
+    let client = builder
+        .add_root_chain(1, Amount::from_tokens(10))
+        .await
+        .unwrap();
+    let chain_id = client.chain_id();
+    let context = ClientContext {
+        client,
+        update_calls: 0,
+    };
+    let context = Arc::new(Mutex::new(context));
+    let limits = ClaimLimitsConfig {
+        max_claims_per_owner: Some(0),
+        provisioning_token: Some("secret".to_string()),
+        max_provisioned_chains: 3,
+        ..ClaimLimitsConfig::default()
+    };
+    let root = MutationRoot {
+        chain_id: Arc::new(Mutex::new(chain_id)),
+        context: context.clone(),
+        amount: Amount::from_tokens(1),
+        end_block_height: BlockHeight::from(10),
+        end_timestamp: Timestamp::from(0),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(10),
+        limiter: Arc::new(ClaimLimiter::new(limits).unwrap()),
+    };
+    // The token is missing or wrong, or too many chains are requested.
+    assert!(root.do_provision_chains(2, None).await.is_err());
+    assert!(root.do_provision_chains(2, Some("secreT")).await.is_err());
+    assert!(root.do_provision_chains(4, Some("secret")).await.is_err());
+    // The claim limits don't apply.
+    let outcome = root.do_provision_chains(3, Some("secret")).await.unwrap();
+    assert_eq!(outcome.chains.len(), 3);
+    assert_eq!(outcome.certificate_hashes.len(), 1);
+    for chain in &outcome.chains {
+        let secret_key: AccountSecretKey =
+            serde_json::from_value(chain.secret_key.clone()).unwrap();
+        assert_eq!(AccountOwner::from(secret_key.public()), chain.owner);
+        assert_eq!(
+            chain.description.config().ownership,
+            ChainOwnership::single(chain.owner)
+        );
+    }
+    let client = context.lock().await.client.clone();
+    assert_eq!(
+        client.local_balance().await.unwrap(),
+        Amount::from_tokens(7)
+    );
+}
+
+#[tokio::test]
+async fn test_claim_limits() {
+    let limiter = ClaimLimiter::new(ClaimLimitsConfig {
+        window_secs: 10,
+        max_claims_per_ip: Some(2),
+        max_claims_per_owner: Some(1),
+        max_tokens_per_window: Some(Amount::from_tokens(3)),
+        ..ClaimLimitsConfig::default()
+    })
+    .unwrap();
+    let ip = Some("192.0.2.1".parse().unwrap());
+    let other_ip = Some("192.0.2.2".parse().unwrap());
+    let owner = |i| AccountPublicKey::test_key(i).into();
+    let amount = Amount::ONE;
+    let start = Timestamp::from(1_000_000);
+
+    assert!(limiter.record(owner(0), ip, amount, start).await.is_ok());
+    // The same owner can't claim twice, even from another address.
+    assert!(limiter
+        .record(owner(0), other_ip, amount, start)
+        .await
+        .is_err());
+    assert!(limiter.record(owner(1), ip, amount, start).await.is_ok());
+    // The address made two claims already.
+    assert!(limiter.record(owner(2), ip, amount, start).await.is_err());
+    // A failed claim doesn't count.
+    limiter.refund(owner(1), ip, amount).await.unwrap();
+    assert!(limiter.record(owner(2), ip, amount, start).await.is_ok());
+    assert!(limiter
+        .record(owner(3), other_ip, amount, start)
+        .await
+        .is_ok());
+    // Three tokens were given away in this window.
+    assert!(limiter
+        .record(owner(4), other_ip, amount, start)
+        .await
+        .is_err());
+    // The counters are reset in the next window.
+    let next_window = Timestamp::from(11_000_000);
+    assert!(limiter
+        .record(owner(0), ip, amount, next_window)
+        .await
+        .is_ok());
+}
+
 #[test]
 fn test_multiply() {
     let mul = MutationRoot::<()>::multiply;
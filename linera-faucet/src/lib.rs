@@ -5,7 +5,11 @@
 Common definitions for the Linera faucet.
 */
 
-use linera_base::{crypto::CryptoHash, identifiers::ChainId};
+use linera_base::{
+    crypto::{AccountSecretKey, CryptoHash},
+    data_types::ChainDescription,
+    identifiers::{AccountOwner, ChainId},
+};
 
 /// The result of a successful `claim` mutation.
 #[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
@@ -19,3 +23,36 @@ pub struct ClaimOutcome {
     /// The hash of the parent chain's certificate containing the `OpenChain` operation.
     pub certificate_hash: CryptoHash,
 }
+
+/// The result of a successful `claimBatch` mutation.
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimBatchOutcome {
+    /// The descriptions of the new chains, in the order of the requested owners.
+    pub chains: Vec<ChainDescription>,
+    /// The hashes of the parent chain's certificates containing the `OpenChain` operations.
+    pub certificate_hashes: Vec<CryptoHash>,
+}
+
+/// A chain created by a `provisionChains` mutation, with the key of its owner.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisionedChain {
+    /// The description of the new chain.
+    pub description: ChainDescription,
+    /// The owner of the new chain.
+    pub owner: AccountOwner,
+    /// The owner's secret key.
+    pub secret_key: AccountSecretKey,
+}
+
+/// The result of a successful `provisionChains` mutation.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisionOutcome {
+    /// The new chains.
+    pub chains: Vec<ProvisionedChain>,
+    /// The hashes of the parent chain's certificates containing the `OpenChain` operations.
+    pub certificate_hashes: Vec<CryptoHash>,
+}
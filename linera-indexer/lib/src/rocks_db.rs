@@ -44,6 +44,11 @@ pub struct RocksDbConfig {
     /// The maximal number of entries in the storage cache.
     #[arg(long, default_value = "1000")]
     pub max_cache_entries: usize,
+
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    pub negative_cache_ttl_ms: u64,
 }
 
 pub type RocksDbRunner = Runner<RocksDbStore, RocksDbConfig>;
@@ -55,6 +60,7 @@ impl RocksDbRunner {
             max_cache_size: config.client.max_cache_size,
             max_entry_size: config.client.max_entry_size,
             max_cache_entries: config.client.max_cache_entries,
+            negative_cache_ttl_ms: config.client.negative_cache_ttl_ms,
         };
         let common_config = CommonStoreConfig {
             max_concurrent_queries: config.client.max_concurrent_queries,
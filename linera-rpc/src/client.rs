@@ -14,7 +14,8 @@ use linera_chain::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    node::{CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
 };
 
 use crate::grpc::GrpcClient;
@@ -151,6 +152,23 @@ impl ValidatorNode for Client {
         })
     }
 
+    async fn subscribe_with_filter(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError> {
+        Ok(match self {
+            Client::Grpc(grpc_client) => {
+                Box::pin(grpc_client.subscribe_with_filter(chains, filter).await?)
+            }
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => {
+                Box::pin(simple_client.subscribe_with_filter(chains, filter).await?)
+            }
+        })
+    }
+
     async fn get_version_info(&self) -> Result<linera_version::VersionInfo, NodeError> {
         Ok(match self {
             Client::Grpc(grpc_client) => grpc_client.get_version_info().await?,
@@ -171,6 +189,15 @@ impl ValidatorNode for Client {
         })
     }
 
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError> {
+        Ok(match self {
+            Client::Grpc(grpc_client) => grpc_client.get_node_features().await?,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.get_node_features().await?,
+        })
+    }
+
     async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
         Ok(match self {
             Client::Grpc(grpc_client) => grpc_client.upload_blob(content).await?,
@@ -189,6 +216,15 @@ impl ValidatorNode for Client {
         })
     }
 
+    async fn download_blobs(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError> {
+        Ok(match self {
+            Client::Grpc(grpc_client) => grpc_client.download_blobs(blob_ids).await?,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.download_blobs(blob_ids).await?,
+        })
+    }
+
     async fn download_pending_blob(
         &self,
         chain_id: ChainId,
@@ -91,6 +91,14 @@ impl ShardConfig {
     pub fn http_address(&self) -> String {
         format!("http://{}:{}", self.host, self.port)
     }
+
+    /// Returns the address to connect to the shard with the given TLS configuration.
+    pub fn address_with_tls(&self, tls: TlsConfig) -> String {
+        match tls {
+            TlsConfig::ClearText => self.http_address(),
+            TlsConfig::Tls => format!("https://{}:{}", self.host, self.port),
+        }
+    }
 }
 
 /// The network protocol.
@@ -146,6 +154,14 @@ pub struct ValidatorInternalNetworkPreConfig<P> {
     pub block_exporters: Vec<ExporterServiceConfig>,
     /// The port of the proxy's metrics endpoint.
     pub metrics_port: u16,
+    /// Whether this validator is an archive node, which never prunes block bodies. This is
+    /// advertised to clients, which prefer archive nodes when downloading a chain's history.
+    #[serde(default)]
+    pub archive: bool,
+    /// If set, the shards only keep the bodies of this many latest blocks of each chain. This
+    /// is advertised to clients, which only prefer archive nodes for older blocks.
+    #[serde(default)]
+    pub block_body_retention: Option<u64>,
 }
 
 impl<P> ValidatorInternalNetworkPreConfig<P> {
@@ -158,6 +174,8 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
             port: self.port,
             block_exporters: self.block_exporters.clone(),
             metrics_port: self.metrics_port,
+            archive: self.archive,
+            block_body_retention: self.block_body_retention,
         }
     }
 }
@@ -266,15 +284,21 @@ impl std::str::FromStr for NetworkProtocol {
     }
 }
 
+/// Returns the shard that `chain_id` is assigned to, among `num_shards` shards of the
+/// validator with the given public key.
+fn shard_id_for(public_key: &ValidatorPublicKey, chain_id: ChainId, num_shards: usize) -> ShardId {
+    use std::hash::{Hash, Hasher};
+    let mut s = std::collections::hash_map::DefaultHasher::new();
+    // Use the validator public key to randomise shard assignment.
+    public_key.hash(&mut s);
+    chain_id.hash(&mut s);
+    (s.finish() as ShardId) % num_shards
+}
+
 impl<P> ValidatorInternalNetworkPreConfig<P> {
     /// Static shard assignment
     pub fn get_shard_id(&self, chain_id: ChainId) -> ShardId {
-        use std::hash::{Hash, Hasher};
-        let mut s = std::collections::hash_map::DefaultHasher::new();
-        // Use the validator public key to randomise shard assignment.
-        self.public_key.hash(&mut s);
-        chain_id.hash(&mut s);
-        (s.finish() as ShardId) % self.shards.len()
+        shard_id_for(&self.public_key, chain_id, self.shards.len())
     }
 
     pub fn shard(&self, shard_id: ShardId) -> &ShardConfig {
@@ -287,6 +311,88 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
     }
 }
 
+/// The assignment of chains to a validator's shards, which can change while the validator
+/// is running.
+///
+/// While the chains are handed off to a new list of shards, the chains that move to a
+/// different shard are not assigned to any shard: their previous shards have to release
+/// them before the new ones take over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardAssignment {
+    public_key: ValidatorPublicKey,
+    shards: Vec<ShardConfig>,
+    next_shards: Option<Vec<ShardConfig>>,
+}
+
+impl ShardAssignment {
+    /// Creates the assignment of the validator's configured shards.
+    pub fn new<P>(network: &ValidatorInternalNetworkPreConfig<P>) -> Self {
+        Self {
+            public_key: network.public_key,
+            shards: network.shards.clone(),
+            next_shards: None,
+        }
+    }
+
+    /// Returns the current shards.
+    pub fn shards(&self) -> &[ShardConfig] {
+        &self.shards
+    }
+
+    /// Returns the shards that the chains are being handed off to, if any.
+    pub fn next_shards(&self) -> Option<&[ShardConfig]> {
+        self.next_shards.as_deref()
+    }
+
+    /// Returns the index of the current shard of `chain_id`, even if the chain is being
+    /// handed off to a different shard.
+    pub fn shard_id(&self, chain_id: ChainId) -> ShardId {
+        shard_id_for(&self.public_key, chain_id, self.shards.len())
+    }
+
+    /// Returns the shard that `chain_id` is assigned to, or `None` if the chain is being
+    /// handed off to a different shard.
+    pub fn shard_for(&self, chain_id: ChainId) -> Option<&ShardConfig> {
+        let shard = &self.shards[self.shard_id(chain_id)];
+        match &self.next_shards {
+            None => Some(shard),
+            Some(next_shards) => {
+                let next_shard =
+                    &next_shards[shard_id_for(&self.public_key, chain_id, next_shards.len())];
+                (next_shard.address() == shard.address()).then_some(shard)
+            }
+        }
+    }
+
+    /// Returns whether `chain_id` is assigned to `shard`.
+    pub fn is_assigned_to(&self, chain_id: ChainId, shard: &ShardConfig) -> bool {
+        self.shard_for(chain_id)
+            .is_some_and(|assigned| assigned.address() == shard.address())
+    }
+
+    /// Starts handing off the chains to `next_shards`.
+    pub fn begin_handoff(&mut self, next_shards: Vec<ShardConfig>) {
+        self.next_shards = Some(next_shards);
+    }
+
+    /// Assigns the chains to `shards`, completing or reverting a handoff.
+    pub fn complete_handoff(&mut self, shards: Vec<ShardConfig>) {
+        self.shards = shards;
+        self.next_shards = None;
+    }
+}
+
+/// A step of handing off the chains to a new list of shards, sent by the proxy to every
+/// shard.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardHandoff {
+    /// The new list of shards.
+    pub shards: Vec<ShardConfig>,
+    /// Whether the previous shards released the chains that move, so that the new ones
+    /// can take over. Otherwise, the shards release the chains that move.
+    pub complete: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 /// The server configuration for the linera-exporter.
 pub struct ExporterServiceConfig {
@@ -307,3 +413,62 @@ fn cross_chain_config_to_args() {
     assert_eq!(config, config2);
     assert_eq!(args, args2);
 }
+
+#[test]
+fn shard_assignment_handoff() {
+    use linera_base::crypto::{CryptoHash, ValidatorKeypair};
+
+    let shard = |port| ShardConfig {
+        host: "localhost".to_string(),
+        port,
+        metrics_port: None,
+    };
+    let network = ValidatorInternalNetworkPreConfig {
+        public_key: ValidatorKeypair::generate().public_key,
+        protocol: (),
+        shards: vec![shard(9100), shard(9101)],
+        host: "localhost".to_string(),
+        port: 10000,
+        block_exporters: Vec::new(),
+        metrics_port: 10001,
+        archive: false,
+        block_body_retention: None,
+    };
+    let mut assignment = ShardAssignment::new(&network);
+    let chain_ids = (0..100)
+        .map(|i| ChainId(CryptoHash::test_hash(format!("chain {i}"))))
+        .collect::<Vec<_>>();
+    for chain_id in &chain_ids {
+        assert_eq!(
+            assignment.shard_for(*chain_id),
+            Some(network.get_shard_for(*chain_id))
+        );
+    }
+
+    let next_shards = vec![shard(9100), shard(9101), shard(9102)];
+    let next_network = ValidatorInternalNetworkPreConfig {
+        shards: next_shards.clone(),
+        ..network.clone()
+    };
+    assignment.begin_handoff(next_shards.clone());
+    let mut moving = 0;
+    for chain_id in &chain_ids {
+        let shard = network.get_shard_for(*chain_id);
+        if shard == next_network.get_shard_for(*chain_id) {
+            assert!(assignment.is_assigned_to(*chain_id, shard));
+        } else {
+            moving += 1;
+            assert_eq!(assignment.shard_for(*chain_id), None);
+            assert!(!assignment.is_assigned_to(*chain_id, shard));
+        }
+    }
+    assert!(moving > 0);
+
+    assignment.complete_handoff(next_shards);
+    for chain_id in &chain_ids {
+        assert_eq!(
+            assignment.shard_for(*chain_id),
+            Some(next_network.get_shard_for(*chain_id))
+        );
+    }
+}
@@ -20,7 +20,8 @@ use linera_chain::{
 };
 use linera_core::{
     data_types::ChainInfoResponse,
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    node::{CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
     worker::Notification,
 };
 use linera_storage::NetworkDescription;
@@ -30,7 +31,7 @@ use tracing::{debug, error, info, instrument, warn};
 
 use super::{
     api::{self, validator_node_client::ValidatorNodeClient, SubscriptionRequest},
-    transport, GRPC_MAX_MESSAGE_SIZE,
+    transport, GRPC_BATCH_RESPONSE_BUDGET, GRPC_MAX_MESSAGE_SIZE,
 };
 use crate::{
     HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
@@ -259,13 +260,28 @@ impl ValidatorNode for GrpcClient {
         GrpcClient::try_into_chain_info(client_delegate!(self, handle_chain_info_query, query)?)
     }
 
-    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
     async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError> {
+        self.subscribe_with_filter(chains, NotificationFilter::default())
+            .await
+    }
+
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn subscribe_with_filter(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError> {
         let retry_delay = self.retry_delay;
         let max_retries = self.max_retries;
         let mut retry_count = 0;
+        let filter = if filter.is_empty() {
+            None
+        } else {
+            Some(filter.try_into()?)
+        };
         let subscription_request = SubscriptionRequest {
             chain_ids: chains.into_iter().map(|chain| chain.into()).collect(),
+            filter,
         };
         let mut client = self.client.clone();
 
@@ -352,6 +368,12 @@ impl ValidatorNode for GrpcClient {
         Ok(client_delegate!(self, get_network_description, req)?.try_into()?)
     }
 
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError> {
+        let req = ();
+        Ok(client_delegate!(self, get_node_features, req)?.into())
+    }
+
     #[instrument(target = "grpc_client", skip(self), err, fields(address = self.address))]
     async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
         Ok(client_delegate!(self, upload_blob, content)?.try_into()?)
@@ -362,6 +384,49 @@ impl ValidatorNode for GrpcClient {
         Ok(client_delegate!(self, download_blob, blob_id)?.try_into()?)
     }
 
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn download_blobs(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError> {
+        let mut blobs = Vec::with_capacity(blob_ids.len());
+        let mut request = api::BlobsBatchRequest::try_from(blob_ids.clone())?;
+        request.max_response_bytes = Some(GRPC_BATCH_RESPONSE_BUDGET as u64);
+        while blobs.len() < blob_ids.len() {
+            let req = request.clone();
+            let response = match client_delegate!(self, download_blobs, req) {
+                Ok(response) => response,
+                Err(error) => {
+                    // The node may not support batch requests yet.
+                    debug!("Failed to download a batch of blobs: {error}; trying one by one");
+                    let remaining = blob_ids[blobs.len()..]
+                        .iter()
+                        .map(|blob_id| self.download_blob(*blob_id));
+                    blobs.extend(future::try_join_all(remaining).await?);
+                    break;
+                }
+            };
+            let continuation = response.continuation;
+            let mut received = Vec::<BlobContent>::try_from(response)?;
+            if received.is_empty() {
+                break;
+            }
+            blobs.append(&mut received);
+            request.continuation = continuation;
+            if request.continuation.is_none() {
+                let remaining = blob_ids.get(blobs.len()..).unwrap_or_default();
+                request.blob_ids = remaining
+                    .iter()
+                    .copied()
+                    .map(api::BlobId::try_from)
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        let missing_blob_ids = blob_ids.get(blobs.len()..).unwrap_or_default();
+        ensure!(
+            missing_blob_ids.is_empty(),
+            NodeError::BlobsNotFound(missing_blob_ids.to_vec())
+        );
+        Ok(blobs)
+    }
+
     #[instrument(target = "grpc_client", skip(self), err, fields(address = self.address))]
     async fn download_pending_blob(
         &self,
@@ -400,20 +465,22 @@ impl ValidatorNode for GrpcClient {
         &self,
         hashes: Vec<CryptoHash>,
     ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
-        let mut missing_hashes = hashes;
-        let mut certs_collected = Vec::with_capacity(missing_hashes.len());
-        loop {
-            // Macro doesn't compile if we pass `missing_hashes.clone()` directly to `client_delegate!`.
-            let missing = missing_hashes.clone();
-            let mut received: Vec<ConfirmedBlockCertificate> = Vec::<Certificate>::try_from(
-                client_delegate!(self, download_certificates, missing)?,
-            )?
-            .into_iter()
-            .map(|cert| {
-                ConfirmedBlockCertificate::try_from(cert)
-                    .map_err(|_| NodeError::UnexpectedCertificateValue)
-            })
-            .collect::<Result<_, _>>()?;
+        let mut certs_collected = Vec::with_capacity(hashes.len());
+        let mut request = api::CertificatesBatchRequest::from(hashes.clone());
+        request.max_response_bytes = Some(GRPC_BATCH_RESPONSE_BUDGET as u64);
+        while certs_collected.len() < hashes.len() {
+            // Macro doesn't compile if we pass `request.clone()` directly to `client_delegate!`.
+            let req = request.clone();
+            let response = client_delegate!(self, download_certificates, req)?;
+            let continuation = response.continuation;
+            let mut received: Vec<ConfirmedBlockCertificate> =
+                Vec::<Certificate>::try_from(response)?
+                    .into_iter()
+                    .map(|cert| {
+                        ConfirmedBlockCertificate::try_from(cert)
+                            .map_err(|_| NodeError::UnexpectedCertificateValue)
+                    })
+                    .collect::<Result<_, _>>()?;
 
             // In the case of the server not returning any certificates, we break the loop.
             if received.is_empty() {
@@ -421,12 +488,19 @@ impl ValidatorNode for GrpcClient {
             }
 
             // Honest validator should return certificates in the same order as the requested hashes.
-            missing_hashes = missing_hashes[received.len()..].to_vec();
             certs_collected.append(&mut received);
+            request.continuation = continuation;
+            if request.continuation.is_none() {
+                // Nodes that don't support continuation tokens truncate their responses
+                // without returning one: request the remaining certificates instead.
+                let remaining = hashes.get(certs_collected.len()..).unwrap_or_default();
+                request.hashes = remaining.iter().copied().map(Into::into).collect();
+            }
         }
+        let missing_hashes = hashes.get(certs_collected.len()..).unwrap_or_default();
         ensure!(
             missing_hashes.is_empty(),
-            NodeError::MissingCertificates(missing_hashes)
+            NodeError::MissingCertificates(missing_hashes.to_vec())
         );
         Ok(certs_collected)
     }
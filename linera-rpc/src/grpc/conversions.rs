@@ -19,7 +19,8 @@ use linera_chain::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
-    node::NodeError,
+    node::{NodeError, NodeFeatures},
+    notifier::NotificationFilter,
     worker::Notification,
 };
 use thiserror::Error;
@@ -27,8 +28,8 @@ use tonic::{Code, Status};
 
 use super::api::{self, PendingBlobRequest};
 use crate::{
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    config::ShardHandoff, HandleConfirmedCertificateRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest,
 };
 
 #[derive(Error, Debug)]
@@ -142,6 +143,34 @@ impl From<api::VersionInfo> for linera_version::VersionInfo {
     }
 }
 
+impl From<NodeFeatures> for api::NodeFeatures {
+    fn from(
+        NodeFeatures {
+            archive,
+            block_body_retention,
+        }: NodeFeatures,
+    ) -> Self {
+        Self {
+            archive,
+            block_body_retention,
+        }
+    }
+}
+
+impl From<api::NodeFeatures> for NodeFeatures {
+    fn from(
+        api::NodeFeatures {
+            archive,
+            block_body_retention,
+        }: api::NodeFeatures,
+    ) -> Self {
+        Self {
+            archive,
+            block_body_retention,
+        }
+    }
+}
+
 impl From<linera_storage::NetworkDescription> for api::NetworkDescription {
     fn from(
         linera_storage::NetworkDescription {
@@ -202,6 +231,46 @@ impl TryFrom<api::Notification> for Option<Notification> {
     }
 }
 
+impl TryFrom<NotificationFilter> for api::NotificationFilter {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(filter: NotificationFilter) -> Result<Self, Self::Error> {
+        Ok(Self {
+            bytes: bincode::serialize(&filter)?,
+        })
+    }
+}
+
+impl TryFrom<api::NotificationFilter> for NotificationFilter {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(filter: api::NotificationFilter) -> Result<Self, Self::Error> {
+        Ok(bincode::deserialize(&filter.bytes)?)
+    }
+}
+
+impl TryFrom<ShardHandoff> for api::ShardHandoff {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(handoff: ShardHandoff) -> Result<Self, Self::Error> {
+        Ok(Self {
+            shards: bincode::serialize(&handoff.shards)?,
+            complete: handoff.complete,
+        })
+    }
+}
+
+impl TryFrom<api::ShardHandoff> for ShardHandoff {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(handoff: api::ShardHandoff) -> Result<Self, Self::Error> {
+        Ok(Self {
+            shards: bincode::deserialize(&handoff.shards)?,
+            complete: handoff.complete,
+        })
+    }
+}
+
 impl TryFrom<ChainInfoResponse> for api::ChainInfoResult {
     type Error = GrpcProtoConversionError;
 
@@ -349,8 +418,13 @@ impl TryFrom<api::LiteCertificate> for HandleLiteCertRequest<'_> {
         };
         let signatures = bincode::deserialize(&certificate.signatures)?;
         let round = bincode::deserialize(&certificate.round)?;
+        let mut lite_certificate = LiteCertificate::new(value, round, signatures);
+        lite_certificate.aggregate_signature = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         Ok(Self {
-            certificate: LiteCertificate::new(value, round, signatures),
+            certificate: lite_certificate,
             wait_for_outgoing_messages: certificate.wait_for_outgoing_messages,
         })
     }
@@ -367,6 +441,12 @@ impl TryFrom<HandleLiteCertRequest<'_>> for api::LiteCertificate {
             signatures: bincode::serialize(&request.certificate.signatures)?,
             wait_for_outgoing_messages: request.wait_for_outgoing_messages,
             kind: request.certificate.value.kind as i32,
+            aggregate_signature: request
+                .certificate
+                .aggregate_signature
+                .as_ref()
+                .map(bincode::serialize)
+                .transpose()?,
         })
     }
 }
@@ -480,11 +560,16 @@ impl TryFrom<api::Certificate> for TimeoutCertificate {
     fn try_from(certificate: api::Certificate) -> Result<Self, Self::Error> {
         let round = bincode::deserialize(&certificate.round)?;
         let signatures = bincode::deserialize(&certificate.signatures)?;
+        let aggregate_signature = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         let cert_type = certificate.kind;
 
         if cert_type == api::CertificateKind::Timeout as i32 {
             let value: Timeout = bincode::deserialize(&certificate.value)?;
-            Ok(TimeoutCertificate::new(value, round, signatures))
+            Ok(TimeoutCertificate::new(value, round, signatures)
+                .with_aggregate_signature(aggregate_signature))
         } else {
             Err(GrpcProtoConversionError::InvalidCertificateType)
         }
@@ -497,11 +582,16 @@ impl TryFrom<api::Certificate> for ValidatedBlockCertificate {
     fn try_from(certificate: api::Certificate) -> Result<Self, Self::Error> {
         let round = bincode::deserialize(&certificate.round)?;
         let signatures = bincode::deserialize(&certificate.signatures)?;
+        let aggregate_signature = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         let cert_type = certificate.kind;
 
         if cert_type == api::CertificateKind::Validated as i32 {
             let value: ValidatedBlock = bincode::deserialize(&certificate.value)?;
-            Ok(ValidatedBlockCertificate::new(value, round, signatures))
+            Ok(ValidatedBlockCertificate::new(value, round, signatures)
+                .with_aggregate_signature(aggregate_signature))
         } else {
             Err(GrpcProtoConversionError::InvalidCertificateType)
         }
@@ -514,11 +604,16 @@ impl TryFrom<api::Certificate> for ConfirmedBlockCertificate {
     fn try_from(certificate: api::Certificate) -> Result<Self, Self::Error> {
         let round = bincode::deserialize(&certificate.round)?;
         let signatures = bincode::deserialize(&certificate.signatures)?;
+        let aggregate_signature = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         let cert_type = certificate.kind;
 
         if cert_type == api::CertificateKind::Confirmed as i32 {
             let value: ConfirmedBlock = bincode::deserialize(&certificate.value)?;
-            Ok(ConfirmedBlockCertificate::new(value, round, signatures))
+            Ok(ConfirmedBlockCertificate::new(value, round, signatures)
+                .with_aggregate_signature(aggregate_signature))
         } else {
             Err(GrpcProtoConversionError::InvalidCertificateType)
         }
@@ -531,6 +626,10 @@ impl TryFrom<TimeoutCertificate> for api::Certificate {
     fn try_from(certificate: TimeoutCertificate) -> Result<Self, Self::Error> {
         let round = bincode::serialize(&certificate.round)?;
         let signatures = bincode::serialize(certificate.signatures())?;
+        let aggregate_signature = certificate
+            .aggregate_signature()
+            .map(bincode::serialize)
+            .transpose()?;
 
         let value = bincode::serialize(certificate.value())?;
 
@@ -538,6 +637,7 @@ impl TryFrom<TimeoutCertificate> for api::Certificate {
             value,
             round,
             signatures,
+            aggregate_signature,
             kind: api::CertificateKind::Timeout as i32,
         })
     }
@@ -549,6 +649,10 @@ impl TryFrom<ConfirmedBlockCertificate> for api::Certificate {
     fn try_from(certificate: ConfirmedBlockCertificate) -> Result<Self, Self::Error> {
         let round = bincode::serialize(&certificate.round)?;
         let signatures = bincode::serialize(certificate.signatures())?;
+        let aggregate_signature = certificate
+            .aggregate_signature()
+            .map(bincode::serialize)
+            .transpose()?;
 
         let value = bincode::serialize(certificate.value())?;
 
@@ -556,6 +660,7 @@ impl TryFrom<ConfirmedBlockCertificate> for api::Certificate {
             value,
             round,
             signatures,
+            aggregate_signature,
             kind: api::CertificateKind::Confirmed as i32,
         })
     }
@@ -567,6 +672,10 @@ impl TryFrom<ValidatedBlockCertificate> for api::Certificate {
     fn try_from(certificate: ValidatedBlockCertificate) -> Result<Self, Self::Error> {
         let round = bincode::serialize(&certificate.round)?;
         let signatures = bincode::serialize(certificate.signatures())?;
+        let aggregate_signature = certificate
+            .aggregate_signature()
+            .map(bincode::serialize)
+            .transpose()?;
 
         let value = bincode::serialize(certificate.value())?;
 
@@ -574,6 +683,7 @@ impl TryFrom<ValidatedBlockCertificate> for api::Certificate {
             value,
             round,
             signatures,
+            aggregate_signature,
             kind: api::CertificateKind::Validated as i32,
         })
     }
@@ -600,6 +710,7 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_equivocation_evidence: chain_info_query.request_equivocation_evidence,
         })
     }
 }
@@ -626,6 +737,7 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_equivocation_evidence: chain_info_query.request_equivocation_evidence,
         })
     }
 }
@@ -911,16 +1023,63 @@ impl From<Vec<CryptoHash>> for api::CertificatesBatchRequest {
     fn from(certs: Vec<CryptoHash>) -> Self {
         Self {
             hashes: certs.into_iter().map(Into::into).collect(),
+            max_response_bytes: None,
+            continuation: None,
         }
     }
 }
 
+impl TryFrom<Vec<BlobId>> for api::BlobsBatchRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(blob_ids: Vec<BlobId>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            blob_ids: blob_ids
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            max_response_bytes: None,
+            continuation: None,
+        })
+    }
+}
+
+impl TryFrom<Vec<BlobContent>> for api::BlobsBatchResponse {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(blobs: Vec<BlobContent>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            blobs: blobs
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            continuation: None,
+        })
+    }
+}
+
+impl TryFrom<api::BlobsBatchResponse> for Vec<BlobContent> {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(response: api::BlobsBatchResponse) -> Result<Self, Self::Error> {
+        response
+            .blobs
+            .into_iter()
+            .map(BlobContent::try_from)
+            .collect()
+    }
+}
+
 impl TryFrom<Certificate> for api::Certificate {
     type Error = GrpcProtoConversionError;
 
     fn try_from(certificate: Certificate) -> Result<Self, Self::Error> {
         let round = bincode::serialize(&certificate.round())?;
         let signatures = bincode::serialize(certificate.signatures())?;
+        let aggregate_signature = certificate
+            .aggregate_signature()
+            .map(bincode::serialize)
+            .transpose()?;
 
         let (kind, value) = match certificate {
             Certificate::Confirmed(confirmed) => (
@@ -942,6 +1101,7 @@ impl TryFrom<Certificate> for api::Certificate {
             round,
             signatures,
             kind: kind as i32,
+            aggregate_signature,
         })
     }
 }
@@ -952,16 +1112,29 @@ impl TryFrom<api::Certificate> for Certificate {
     fn try_from(certificate: api::Certificate) -> Result<Self, Self::Error> {
         let round = bincode::deserialize(&certificate.round)?;
         let signatures = bincode::deserialize(&certificate.signatures)?;
+        let aggregate_signature = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
 
         let value = if certificate.kind == api::CertificateKind::Confirmed as i32 {
             let value: ConfirmedBlock = bincode::deserialize(&certificate.value)?;
-            Certificate::Confirmed(ConfirmedBlockCertificate::new(value, round, signatures))
+            Certificate::Confirmed(
+                ConfirmedBlockCertificate::new(value, round, signatures)
+                    .with_aggregate_signature(aggregate_signature),
+            )
         } else if certificate.kind == api::CertificateKind::Validated as i32 {
             let value: ValidatedBlock = bincode::deserialize(&certificate.value)?;
-            Certificate::Validated(ValidatedBlockCertificate::new(value, round, signatures))
+            Certificate::Validated(
+                ValidatedBlockCertificate::new(value, round, signatures)
+                    .with_aggregate_signature(aggregate_signature),
+            )
         } else if certificate.kind == api::CertificateKind::Timeout as i32 {
             let value: Timeout = bincode::deserialize(&certificate.value)?;
-            Certificate::Timeout(TimeoutCertificate::new(value, round, signatures))
+            Certificate::Timeout(
+                TimeoutCertificate::new(value, round, signatures)
+                    .with_aggregate_signature(aggregate_signature),
+            )
         } else {
             return Err(GrpcProtoConversionError::InvalidCertificateType);
         };
@@ -979,6 +1152,7 @@ impl TryFrom<Vec<Certificate>> for api::CertificatesBatchResponse {
                 .into_iter()
                 .map(TryInto::try_into)
                 .collect::<Result<_, _>>()?,
+            continuation: None,
         })
     }
 }
@@ -997,11 +1171,12 @@ impl TryFrom<api::CertificatesBatchResponse> for Vec<Certificate> {
 
 #[cfg(test)]
 pub mod tests {
-    use std::{borrow::Cow, fmt::Debug};
+    use std::{borrow::Cow, collections::BTreeSet, fmt::Debug};
 
     use linera_base::{
         crypto::{AccountSecretKey, BcsSignable, CryptoHash, Secp256k1SecretKey, ValidatorKeypair},
-        data_types::{Amount, Blob, Round, Timestamp},
+        data_types::{Amount, Blob, BlockLimits, InboxPolicy, RetentionPolicy, Round, Timestamp},
+        identifiers::{ApplicationId, StreamId, StreamName},
     };
     use linera_chain::{
         data_types::{BlockExecutionOutcome, ProposedBlock},
@@ -1079,6 +1254,31 @@ pub mod tests {
         round_trip_check::<_, api::ChainId>(chain_id);
     }
 
+    #[test]
+    pub fn test_node_features() {
+        round_trip_check::<_, api::NodeFeatures>(NodeFeatures {
+            archive: true,
+            block_body_retention: None,
+        });
+        round_trip_check::<_, api::NodeFeatures>(NodeFeatures {
+            archive: false,
+            block_body_retention: Some(1000),
+        });
+    }
+
+    #[test]
+    pub fn test_shard_handoff() {
+        let handoff = ShardHandoff {
+            shards: vec![crate::config::ShardConfig {
+                host: "localhost".to_string(),
+                port: 9100,
+                metrics_port: Some(21100),
+            }],
+            complete: true,
+        };
+        round_trip_check::<_, api::ShardHandoff>(handoff);
+    }
+
     #[test]
     pub fn test_chain_info_response() {
         let chain_info = Box::new(ChainInfo {
@@ -1097,6 +1297,10 @@ pub mod tests {
             requested_sent_certificate_hashes: vec![],
             count_received_log: 0,
             requested_received_log: vec![],
+            requested_equivocation_evidence: vec![],
+            inbox_policy: InboxPolicy::default(),
+            block_limits: BlockLimits::default(),
+            retention_policy: RetentionPolicy::default(),
         });
 
         let chain_info_response_none = ChainInfoResponse {
@@ -1138,6 +1342,7 @@ pub mod tests {
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: true,
+            request_equivocation_evidence: true,
         };
         round_trip_check::<_, api::ChainInfoQuery>(chain_info_query_some);
     }
@@ -1164,6 +1369,15 @@ pub mod tests {
         round_trip_check::<_, api::HandlePendingBlobRequest>(pending_blob_request);
     }
 
+    #[test]
+    pub fn test_blobs_batch_response() {
+        let blobs = vec![
+            BlobContent::new_data(*b"foo"),
+            BlobContent::new_data(*b"bar"),
+        ];
+        round_trip_check::<_, api::BlobsBatchResponse>(blobs);
+    }
+
     #[test]
     pub fn test_lite_certificate() {
         let key_pair = ValidatorKeypair::generate();
@@ -1178,6 +1392,7 @@ pub mod tests {
                 key_pair.public_key,
                 ValidatorSignature::new(&Foo("test".into()), &key_pair.secret_key),
             )]),
+            aggregate_signature: None,
         };
         let request = HandleLiteCertRequest {
             certificate,
@@ -1268,6 +1483,8 @@ pub mod tests {
             reason: linera_core::worker::Reason::NewBlock {
                 height: BlockHeight(0),
                 hash: CryptoHash::new(&Foo("".into())),
+                event_streams: BTreeSet::new(),
+                application_ids: BTreeSet::new(),
             },
         };
         let message = api::Notification::try_from(notification.clone()).unwrap();
@@ -1279,4 +1496,17 @@ pub mod tests {
         let ack = api::Notification::default();
         assert_eq!(None, Option::<Notification>::try_from(ack).unwrap());
     }
+
+    #[test]
+    pub fn test_notification_filter() {
+        let application_id = ApplicationId::new(CryptoHash::new(&Foo("app".into())));
+        let filter = NotificationFilter {
+            event_streams: BTreeSet::from([StreamId {
+                application_id: application_id.into(),
+                stream_name: StreamName(b"stream".to_vec()),
+            }]),
+            application_ids: BTreeSet::from([application_id]),
+        };
+        round_trip_check::<_, api::NotificationFilter>(filter);
+    }
 }
@@ -0,0 +1,576 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Certificates for mutual TLS between the proxy and the shards of a validator.
+//!
+//! The certificates are kept in a directory shared by the processes of the validator. They
+//! are signed by a self-signed certificate authority, so that operators don't need their own
+//! PKI. [`InternalTlsConfig::rotate`] issues the missing certificates and renews the ones that
+//! are about to expire, and should be run regularly, e.g. with `linera-server
+//! rotate-internal-tls`. The processes load their certificate when they start.
+//!
+//! Rotations overlap, so that the processes can be restarted one at a time:
+//! * A certificate is renewed `overlap` before it expires, and the previous one remains
+//!   valid in the meantime.
+//! * A new authority is created once the newest one would expire less than
+//!   `certificate_validity + overlap` from now. It is trusted right away, but only signs
+//!   certificates once the previous authority can't sign certificates for a whole
+//!   `certificate_validity` anymore, i.e. `overlap` later.
+//! * An authority is trusted until it expires, which is after all the certificates it
+//!   signed.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose,
+};
+use serde::{Deserialize, Serialize};
+use tonic::transport::{ClientTlsConfig, Identity, ServerTlsConfig};
+use tracing::warn;
+
+use crate::config::{ShardId, ValidatorInternalNetworkConfig};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The file listing the authorities and the certificates of the directory.
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// The name of the proxy's certificate.
+pub const PROXY_CERTIFICATE_NAME: &str = "proxy";
+
+/// Returns the name of the certificate of a shard.
+pub fn shard_certificate_name(shard_id: ShardId) -> String {
+    format!("shard-{shard_id}")
+}
+
+/// An error related to the internal TLS certificates.
+#[derive(Debug, thiserror::Error)]
+pub enum InternalTlsError {
+    #[error("I/O error in the internal TLS directory: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid internal TLS manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("failed to generate a certificate: {0}")]
+    Generation(#[from] rcgen::Error),
+
+    #[error("the internal TLS validity periods are inconsistent: {0}")]
+    InvalidValidity(String),
+
+    #[error("no internal TLS directory was given, with `--internal-tls-dir`")]
+    MissingDirectory,
+
+    #[error("invalid date for a certificate: {0}")]
+    InvalidDate(#[from] time::error::ComponentRange),
+
+    #[error(
+        "there is no internal TLS certificate named {0:?}; run `linera-server \
+         rotate-internal-tls` first"
+    )]
+    MissingCertificate(String),
+
+    #[error("the internal TLS certificate {0:?} has expired")]
+    ExpiredCertificate(String),
+}
+
+/// Where the internal TLS certificates are kept, and how long they are valid for.
+#[derive(Clone, Debug, Parser)]
+pub struct InternalTlsConfig {
+    /// The directory of the certificates for TLS between the proxy and the shards. It is
+    /// needed if the internal network protocol uses TLS.
+    #[arg(long = "internal-tls-dir", env = "LINERA_INTERNAL_TLS_DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// For how many days the certificates of the proxy and the shards are valid.
+    #[arg(long = "internal-tls-certificate-validity-days", default_value = "30")]
+    pub certificate_validity_days: u64,
+
+    /// For how many days the internal certificate authorities are valid.
+    #[arg(long = "internal-tls-authority-validity-days", default_value = "365")]
+    pub authority_validity_days: u64,
+
+    /// For how many days the previous certificate or authority remains usable after a
+    /// rotation. All the processes must be restarted within this time to load the new
+    /// certificates.
+    #[arg(long = "internal-tls-overlap-days", default_value = "7")]
+    pub overlap_days: u64,
+}
+
+impl Default for InternalTlsConfig {
+    fn default() -> Self {
+        InternalTlsConfig::parse_from::<[OsString; 1], OsString>(["".into()])
+    }
+}
+
+/// The certificate authorities and certificates of the directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// The authorities that are trusted, from the oldest to the newest.
+    authorities: Vec<AuthorityRecord>,
+    /// The certificates, by name.
+    certificates: BTreeMap<String, CertificateRecord>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct AuthorityRecord {
+    /// A unique ID, used in the file names.
+    id: u64,
+    /// The start of the validity, in seconds since the Unix epoch.
+    not_before: u64,
+    /// The end of the validity, in seconds since the Unix epoch.
+    not_after: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CertificateRecord {
+    /// The ID of the authority that signed the certificate.
+    authority: u64,
+    /// The host names and IP addresses the certificate is valid for.
+    hosts: Vec<String>,
+    not_before: u64,
+    not_after: u64,
+}
+
+/// What a rotation changed.
+#[derive(Debug, Default)]
+pub struct RotationReport {
+    /// The ID of the authority that was created, if any.
+    pub created_authority: Option<u64>,
+    /// The IDs of the authorities that expired and were removed.
+    pub removed_authorities: Vec<u64>,
+    /// The names of the certificates that were issued or renewed.
+    pub issued_certificates: Vec<String>,
+    /// The names of the certificates that are not needed anymore and were removed.
+    pub removed_certificates: Vec<String>,
+}
+
+impl RotationReport {
+    /// Returns whether the processes must be restarted to use the new certificates.
+    pub fn has_changes(&self) -> bool {
+        self.created_authority.is_some() || !self.issued_certificates.is_empty()
+    }
+}
+
+/// The TLS configurations of a process of the validator.
+#[derive(Clone, Debug)]
+pub struct InternalTlsIdentity {
+    /// The configuration to accept connections from the other processes.
+    pub server: ServerTlsConfig,
+    /// The configuration to connect to the other processes.
+    pub client: ClientTlsConfig,
+}
+
+impl InternalTlsConfig {
+    fn certificate_validity(&self) -> u64 {
+        self.certificate_validity_days * SECONDS_PER_DAY
+    }
+
+    fn authority_validity(&self) -> u64 {
+        self.authority_validity_days * SECONDS_PER_DAY
+    }
+
+    fn overlap(&self) -> u64 {
+        self.overlap_days * SECONDS_PER_DAY
+    }
+
+    /// Checks that the certificates can be rotated with overlaps.
+    pub fn check(&self) -> Result<(), InternalTlsError> {
+        if self.overlap_days == 0 || self.certificate_validity_days <= self.overlap_days {
+            return Err(InternalTlsError::InvalidValidity(format!(
+                "the certificates must be valid for longer than the overlap of {} days, \
+                 which must not be zero",
+                self.overlap_days
+            )));
+        }
+        if self.authority_validity_days <= self.certificate_validity_days + 2 * self.overlap_days {
+            return Err(InternalTlsError::InvalidValidity(format!(
+                "the authorities must be valid for longer than the certificates plus twice \
+                 the overlap, i.e. {} days",
+                self.certificate_validity_days + 2 * self.overlap_days
+            )));
+        }
+        Ok(())
+    }
+
+    fn dir(&self) -> Result<&Path, InternalTlsError> {
+        self.dir
+            .as_deref()
+            .ok_or(InternalTlsError::MissingDirectory)
+    }
+
+    /// Creates the missing authority and certificates for the proxy and the shards of the
+    /// `network`, and renews the ones that are about to expire.
+    pub fn rotate(
+        &self,
+        network: &ValidatorInternalNetworkConfig,
+        now: SystemTime,
+    ) -> Result<RotationReport, InternalTlsError> {
+        self.check()?;
+        let dir = self.dir()?;
+        fs::create_dir_all(dir)?;
+        let now = unix_seconds(now);
+        let mut manifest = read_manifest(dir)?;
+        let mut report = RotationReport::default();
+
+        let (expired, authorities) = manifest
+            .authorities
+            .into_iter()
+            .partition::<Vec<_>, _>(|authority| authority.not_after <= now);
+        manifest.authorities = authorities;
+        for authority in expired {
+            remove_files(dir, &authority_file_name(authority.id))?;
+            report.removed_authorities.push(authority.id);
+        }
+
+        let needs_authority = manifest.authorities.last().is_none_or(|newest| {
+            newest.not_after < now + self.certificate_validity() + self.overlap()
+        });
+        if needs_authority {
+            let id = manifest
+                .authorities
+                .last()
+                .map_or(now, |newest| now.max(newest.id + 1));
+            let record = AuthorityRecord {
+                id,
+                not_before: now,
+                not_after: now + self.authority_validity(),
+            };
+            let authority = Certificate::from_params(authority_params(&record, None)?)?;
+            let file_name = authority_file_name(id);
+            write_file(
+                dir,
+                &format!("{file_name}.pem"),
+                &authority.serialize_pem()?,
+            )?;
+            write_file(
+                dir,
+                &format!("{file_name}.key"),
+                &authority.serialize_private_key_pem(),
+            )?;
+            manifest.authorities.push(record);
+            report.created_authority = Some(id);
+        }
+
+        // The oldest authority that outlives the certificates it signs now.
+        let signer_record = manifest
+            .authorities
+            .iter()
+            .find(|authority| authority.not_after >= now + self.certificate_validity())
+            .expect("the newest authority is valid long enough")
+            .clone();
+        let mut signer = None;
+
+        let mut expected = vec![(
+            PROXY_CERTIFICATE_NAME.to_string(),
+            vec![network.host.clone()],
+        )];
+        expected.extend(
+            network.shards.iter().enumerate().map(|(shard_id, shard)| {
+                (shard_certificate_name(shard_id), vec![shard.host.clone()])
+            }),
+        );
+
+        let mut certificates = BTreeMap::new();
+        for (name, hosts) in expected {
+            let current = manifest.certificates.remove(&name);
+            if let Some(record) = current.filter(|record| {
+                record.authority == signer_record.id
+                    && record.hosts == hosts
+                    && record.not_after >= now + self.overlap()
+            }) {
+                certificates.insert(name, record);
+                continue;
+            }
+            let signer = match &mut signer {
+                Some(signer) => signer,
+                None => signer.insert(load_authority(dir, &signer_record)?),
+            };
+            let record = CertificateRecord {
+                authority: signer_record.id,
+                hosts,
+                not_before: now,
+                not_after: now + self.certificate_validity(),
+            };
+            let certificate = Certificate::from_params(certificate_params(&record)?)?;
+            write_file(
+                dir,
+                &format!("{name}.pem"),
+                &certificate.serialize_pem_with_signer(signer)?,
+            )?;
+            write_file(
+                dir,
+                &format!("{name}.key"),
+                &certificate.serialize_private_key_pem(),
+            )?;
+            report.issued_certificates.push(name.clone());
+            certificates.insert(name, record);
+        }
+        for name in manifest.certificates.into_keys() {
+            remove_files(dir, &name)?;
+            report.removed_certificates.push(name);
+        }
+        manifest.certificates = certificates;
+
+        write_file(
+            dir,
+            MANIFEST_FILE,
+            &serde_json::to_string_pretty(&manifest)?,
+        )?;
+        Ok(report)
+    }
+
+    /// Loads the certificate with the given name, and the authorities to trust.
+    pub fn load(
+        &self,
+        name: &str,
+        now: SystemTime,
+    ) -> Result<InternalTlsIdentity, InternalTlsError> {
+        let dir = self.dir()?;
+        let now = unix_seconds(now);
+        let manifest = read_manifest(dir)?;
+        let record = manifest
+            .certificates
+            .get(name)
+            .ok_or_else(|| InternalTlsError::MissingCertificate(name.to_string()))?;
+        if record.not_after <= now {
+            return Err(InternalTlsError::ExpiredCertificate(name.to_string()));
+        }
+        if record.not_after < now + self.overlap() {
+            warn!(
+                name,
+                "The internal TLS certificate expires in less than {} days; run `linera-server \
+                 rotate-internal-tls` and restart this process",
+                self.overlap_days
+            );
+        }
+        let mut trusted_authorities = String::new();
+        for authority in &manifest.authorities {
+            if authority.not_after > now {
+                let file_name = authority_file_name(authority.id);
+                trusted_authorities.push_str(&read_file(dir, &format!("{file_name}.pem"))?);
+            }
+        }
+        let identity = Identity::from_pem(
+            read_file(dir, &format!("{name}.pem"))?,
+            read_file(dir, &format!("{name}.key"))?,
+        );
+        let trusted_authorities = tonic::transport::Certificate::from_pem(trusted_authorities);
+        Ok(InternalTlsIdentity {
+            server: ServerTlsConfig::new()
+                .identity(identity.clone())
+                .client_ca_root(trusted_authorities.clone()),
+            client: ClientTlsConfig::new()
+                .ca_certificate(trusted_authorities)
+                .identity(identity),
+        })
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+fn to_date(unix_seconds: u64) -> Result<time::OffsetDateTime, InternalTlsError> {
+    let seconds = i64::try_from(unix_seconds)
+        .map_err(|_| InternalTlsError::InvalidValidity(format!("{unix_seconds} is too large")))?;
+    Ok(time::OffsetDateTime::from_unix_timestamp(seconds)?)
+}
+
+fn authority_file_name(id: u64) -> String {
+    format!("authority-{id}")
+}
+
+/// Returns the parameters of an authority. Signing only uses its name and key, so an
+/// authority is loaded by generating it again with the same parameters and its saved key.
+fn authority_params(
+    record: &AuthorityRecord,
+    key_pair: Option<KeyPair>,
+) -> Result<CertificateParams, InternalTlsError> {
+    let mut params = CertificateParams::default();
+    let mut name = DistinguishedName::new();
+    name.push(
+        DnType::CommonName,
+        format!("Linera internal authority {}", record.id),
+    );
+    params.distinguished_name = name;
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    params.not_before = to_date(record.not_before)?;
+    params.not_after = to_date(record.not_after)?;
+    params.key_pair = key_pair;
+    Ok(params)
+}
+
+fn load_authority(dir: &Path, record: &AuthorityRecord) -> Result<Certificate, InternalTlsError> {
+    let key = read_file(dir, &format!("{}.key", authority_file_name(record.id)))?;
+    let params = authority_params(record, Some(KeyPair::from_pem(&key)?))?;
+    Ok(Certificate::from_params(params)?)
+}
+
+/// Returns the parameters of a certificate, used both to accept and to open connections.
+fn certificate_params(record: &CertificateRecord) -> Result<CertificateParams, InternalTlsError> {
+    let mut params = CertificateParams::new(record.hosts.clone());
+    let mut name = DistinguishedName::new();
+    name.push(DnType::CommonName, record.hosts.join(","));
+    params.distinguished_name = name;
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![
+        ExtendedKeyUsagePurpose::ServerAuth,
+        ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+    params.not_before = to_date(record.not_before)?;
+    params.not_after = to_date(record.not_after)?;
+    Ok(params)
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, InternalTlsError> {
+    match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        Ok(manifest) => Ok(serde_json::from_str(&manifest)?),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn read_file(dir: &Path, file_name: &str) -> Result<String, InternalTlsError> {
+    Ok(fs::read_to_string(dir.join(file_name))?)
+}
+
+/// Writes a file atomically, so that a process starting at the same time never reads a
+/// partial file.
+fn write_file(dir: &Path, file_name: &str, contents: &str) -> Result<(), InternalTlsError> {
+    let path = dir.join(file_name);
+    let temporary_path = dir.join(format!(".{file_name}.tmp"));
+    fs::write(&temporary_path, contents)?;
+    fs::rename(temporary_path, path)?;
+    Ok(())
+}
+
+fn remove_files(dir: &Path, stem: &str) -> Result<(), InternalTlsError> {
+    for extension in ["pem", "key"] {
+        match fs::remove_file(dir.join(format!("{stem}.{extension}"))) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use linera_base::crypto::ValidatorPublicKey;
+
+    use super::{read_manifest, InternalTlsConfig, PROXY_CERTIFICATE_NAME, SECONDS_PER_DAY};
+    use crate::config::{NetworkProtocol, ShardConfig, TlsConfig, ValidatorInternalNetworkConfig};
+
+    fn network(num_shards: usize) -> ValidatorInternalNetworkConfig {
+        ValidatorInternalNetworkConfig {
+            public_key: ValidatorPublicKey::test_key(0),
+            protocol: NetworkProtocol::Grpc(TlsConfig::Tls),
+            shards: (0..num_shards)
+                .map(|i| ShardConfig {
+                    host: format!("shard{i}"),
+                    port: 19100,
+                    metrics_port: None,
+                })
+                .collect(),
+            host: "127.0.0.1".to_string(),
+            port: 20100,
+            block_exporters: vec![],
+            metrics_port: 21100,
+            archive: false,
+            block_body_retention: None,
+        }
+    }
+
+    fn day(days: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + days * SECONDS_PER_DAY)
+    }
+
+    #[test]
+    fn test_internal_tls_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = InternalTlsConfig {
+            dir: Some(dir.path().to_path_buf()),
+            certificate_validity_days: 30,
+            authority_validity_days: 100,
+            overlap_days: 7,
+        };
+        let two_shards = network(2);
+        let one_shard = network(1);
+
+        let report = config.rotate(&two_shards, day(0)).unwrap();
+        assert!(report.created_authority.is_some());
+        assert_eq!(
+            report.issued_certificates,
+            [PROXY_CERTIFICATE_NAME, "shard-0", "shard-1"]
+        );
+        config.load("shard-1", day(0)).unwrap();
+
+        // Nothing is due yet.
+        let report = config.rotate(&two_shards, day(20)).unwrap();
+        assert!(!report.has_changes());
+
+        // The certificates expire within the overlap, and a shard was removed.
+        let report = config.rotate(&one_shard, day(24)).unwrap();
+        assert!(report.created_authority.is_none());
+        assert_eq!(report.issued_certificates.len(), 2);
+        assert_eq!(report.removed_certificates, ["shard-1"]);
+        assert!(config.load("shard-1", day(24)).is_err());
+
+        let report = config.rotate(&one_shard, day(50)).unwrap();
+        assert_eq!(report.issued_certificates.len(), 2);
+
+        // The authority expires on day 100: a new one is created after day 63, but only
+        // used after day 70.
+        let first_authority = read_manifest(dir.path()).unwrap().authorities[0].id;
+        let report = config.rotate(&one_shard, day(64)).unwrap();
+        let second_authority = report.created_authority.unwrap();
+        assert!(report.issued_certificates.is_empty());
+        let manifest = read_manifest(dir.path()).unwrap();
+        assert_eq!(manifest.authorities.len(), 2);
+        assert!(manifest
+            .certificates
+            .values()
+            .all(|record| record.authority == first_authority));
+
+        let report = config.rotate(&one_shard, day(71)).unwrap();
+        assert!(report.created_authority.is_none());
+        assert_eq!(report.issued_certificates.len(), 2);
+        let manifest = read_manifest(dir.path()).unwrap();
+        assert!(manifest
+            .certificates
+            .values()
+            .all(|record| record.authority == second_authority));
+
+        // The first authority is trusted until it expires.
+        let report = config.rotate(&one_shard, day(101)).unwrap();
+        assert_eq!(report.removed_authorities, [first_authority]);
+        config.load(PROXY_CERTIFICATE_NAME, day(101)).unwrap();
+    }
+
+    #[test]
+    fn test_internal_tls_validity_checks() {
+        let config = InternalTlsConfig {
+            dir: None,
+            certificate_validity_days: 30,
+            authority_validity_days: 40,
+            overlap_days: 7,
+        };
+        assert!(config.check().is_err());
+        assert!(InternalTlsConfig::default().check().is_ok());
+    }
+}
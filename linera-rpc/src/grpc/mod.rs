@@ -3,6 +3,8 @@
 
 mod client;
 mod conversions;
+#[cfg(with_server)]
+pub mod internal_tls;
 mod node_provider;
 pub mod pool;
 #[cfg(with_server)]
@@ -38,9 +40,25 @@ pub enum GrpcError {
     Reflection(#[from] tonic_reflection::server::Error),
 }
 
+/// Returns a builder of the gRPC reflection services, describing the Linera services and the
+/// standard health-checking service, so that tools like `grpcurl` can discover them.
+///
+/// Both the `v1` and the older `v1alpha` versions of the reflection protocol should be served,
+/// since many tools only support the latter.
+#[cfg(with_server)]
+pub fn reflection_builder() -> tonic_reflection::server::Builder<'static> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(crate::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+}
+
 const MEBIBYTE: usize = 1024 * 1024;
 pub const GRPC_MAX_MESSAGE_SIZE: usize = 16 * MEBIBYTE;
 
 /// Limit of gRPC message size up to which we will try to populate with data when estimating.
 /// We leave 30% of buffer for the rest of the message and potential underestimation.
 pub const GRPC_CHUNKED_MESSAGE_FILL_LIMIT: usize = GRPC_MAX_MESSAGE_SIZE * 7 / 10;
+
+/// The size up to which clients ask for the responses to batch requests to be filled. Larger
+/// batches are received in several responses, using continuation tokens.
+pub const GRPC_BATCH_RESPONSE_BUDGET: usize = 4 * MEBIBYTE;
@@ -31,6 +31,13 @@ impl GrpcConnectionPool {
         self
     }
 
+    /// Uses the given TLS configuration instead of the Web PKI roots for `https` addresses.
+    #[cfg(not(web))]
+    pub fn with_tls(mut self, tls: impl Into<Option<tonic::transport::ClientTlsConfig>>) -> Self {
+        self.options.tls = tls.into();
+        self
+    }
+
     /// Obtains a channel for the current address. Either clones an existing one (thereby
     /// reusing the connection), or creates one if needed. New channels do not create a
     /// connection immediately.
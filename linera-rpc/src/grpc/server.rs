@@ -6,6 +6,7 @@ use std::sync::LazyLock;
 use std::{
     net::{IpAddr, SocketAddr},
     str::FromStr,
+    sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
@@ -13,21 +14,28 @@ use std::{
 use futures::{
     channel::mpsc::{self, Receiver},
     future::BoxFuture,
-    FutureExt as _, StreamExt,
+    FutureExt as _, SinkExt as _, StreamExt,
 };
 use linera_base::{data_types::Blob, identifiers::ChainId};
 use linera_core::{
+    blob_gossip::BlobGossip,
     node::NodeError,
     worker::{NetworkActions, Notification, Reason, WorkerError, WorkerState},
     JoinSetExt as _, TaskHandle,
 };
 use linera_storage::Storage;
 use rand::Rng;
-use tokio::{sync::oneshot, task::JoinSet};
+use tokio::{
+    sync::{oneshot, RwLockReadGuard},
+    task::JoinSet,
+};
 use tokio_util::sync::CancellationToken;
-use tonic::{transport::Channel, Request, Response, Status};
+use tonic::{
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Request, Response, Status,
+};
 use tower::{builder::ServiceBuilder, Layer, Service};
-use tracing::{debug, error, info, instrument, trace, warn};
+use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{
@@ -44,17 +52,28 @@ use super::{
         validator_worker_server::{ValidatorWorker as ValidatorWorkerRpc, ValidatorWorkerServer},
         BlockProposal, ChainInfoQuery, ChainInfoResult, CrossChainRequest,
         HandlePendingBlobRequest, LiteCertificate, PendingBlobRequest, PendingBlobResult,
+        ShardHandoffResult,
     },
+    internal_tls::InternalTlsIdentity,
     pool::GrpcConnectionPool,
     GrpcError, GRPC_MAX_MESSAGE_SIZE,
 };
 use crate::{
-    config::{CrossChainConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig},
+    config::{
+        CrossChainConfig, NotificationConfig, ShardAssignment, ShardConfig, ShardHandoff, ShardId,
+        TlsConfig, ValidatorInternalNetworkConfig,
+    },
+    request_id::RequestIdLayer,
+    shard_map::ShardMap,
     HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    HandleValidatedCertificateRequest, NodeProvider, RequestId,
 };
 
-type CrossChainSender = mpsc::Sender<(linera_core::data_types::CrossChainRequest, ShardId)>;
+/// The cross-chain requests to send, with the ID of the request they result from, if any.
+type CrossChainSender = mpsc::Sender<(
+    linera_core::data_types::CrossChainRequest,
+    Option<RequestId>,
+)>;
 type NotificationSender = mpsc::Sender<Notification>;
 
 #[cfg(with_metrics)]
@@ -124,9 +143,14 @@ where
 {
     state: WorkerState<S>,
     shard_id: ShardId,
-    network: ValidatorInternalNetworkConfig,
+    /// The configuration of this shard.
+    shard: ShardConfig,
+    /// The assignment of chains to shards, shared by the shards running in this process.
+    shard_map: Arc<ShardMap>,
     cross_chain_sender: CrossChainSender,
     notification_sender: NotificationSender,
+    /// If set, missing blobs of block proposals are downloaded from the other validators.
+    blob_gossip: Option<Arc<BlobGossip<NodeProvider>>>,
 }
 
 pub struct GrpcServerHandle {
@@ -198,8 +222,11 @@ where
         state: WorkerState<S>,
         shard_id: ShardId,
         internal_network: ValidatorInternalNetworkConfig,
+        shard_map: Arc<ShardMap>,
+        internal_tls: Option<InternalTlsIdentity>,
         cross_chain_config: CrossChainConfig,
         notification_config: NotificationConfig,
+        blob_gossip: Option<BlobGossip<NodeProvider>>,
         shutdown_signal: CancellationToken,
         join_set: &mut JoinSet<()>,
     ) -> GrpcServerHandle {
@@ -221,13 +248,14 @@ where
             );
             Self::forward_cross_chain_queries(
                 state.nickname().to_string(),
-                internal_network.clone(),
+                shard_map.clone(),
                 cross_chain_config.max_retries,
                 Duration::from_millis(cross_chain_config.retry_delay_ms),
                 Duration::from_millis(cross_chain_config.sender_delay_ms),
                 cross_chain_config.sender_failure_rate,
                 cross_chain_config.max_concurrent_tasks,
                 shard_id,
+                internal_tls.as_ref().map(|tls| tls.client.clone()),
                 cross_chain_receiver,
             )
         });
@@ -241,6 +269,7 @@ where
                 state.nickname().to_string(),
                 internal_network.proxy_address(),
                 internal_network.exporter_addresses(),
+                internal_tls.as_ref().map(|tls| tls.client.clone()),
                 notification_receiver,
             )
         });
@@ -250,11 +279,15 @@ where
         let grpc_server = GrpcServer {
             state,
             shard_id,
-            network: internal_network,
+            shard: internal_network.shard(shard_id).clone(),
+            shard_map,
             cross_chain_sender,
             notification_sender,
+            blob_gossip: blob_gossip.map(Arc::new),
         };
 
+        join_set.spawn_task(grpc_server.clone().resume_pending_deliveries());
+
         let worker_node = ValidatorWorkerServer::new(grpc_server)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
             .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
@@ -262,24 +295,36 @@ where
         let handle = join_set.spawn_task(async move {
             let server_address = SocketAddr::from((IpAddr::from_str(&host)?, port));
 
-            let reflection_service = tonic_reflection::server::Builder::configure()
-                .register_encoded_file_descriptor_set(crate::FILE_DESCRIPTOR_SET)
-                .build_v1()?;
+            let reflection_service = super::reflection_builder().build_v1()?;
+            let reflection_service_v1alpha = super::reflection_builder().build_v1alpha()?;
 
             health_reporter
                 .set_serving::<ValidatorWorkerServer<Self>>()
                 .await;
+            let shutdown = async move {
+                shutdown_signal.cancelled().await;
+                // Tell the load balancers to stop sending requests while shutting down.
+                health_reporter
+                    .set_not_serving::<ValidatorWorkerServer<Self>>()
+                    .await;
+            };
 
-            tonic::transport::Server::builder()
+            let mut server = tonic::transport::Server::builder();
+            if let Some(tls) = internal_tls {
+                server = server.tls_config(tls.server)?;
+            }
+            server
                 .layer(
                     ServiceBuilder::new()
                         .layer(GrpcPrometheusMetricsMiddlewareLayer)
+                        .layer(RequestIdLayer::propagating())
                         .into_inner(),
                 )
                 .add_service(health_service)
                 .add_service(reflection_service)
+                .add_service(reflection_service_v1alpha)
                 .add_service(worker_node)
-                .serve_with_shutdown(server_address, shutdown_signal.cancelled_owned())
+                .serve_with_shutdown(server_address, shutdown)
                 .await?;
 
             Ok(())
@@ -290,16 +335,23 @@ where
 
     /// Continuously waits for receiver to receive a notification which is then sent to
     /// the proxy.
-    #[instrument(skip(receiver))]
+    #[instrument(skip(tls, receiver))]
     async fn forward_notifications(
         nickname: String,
         proxy_address: String,
         exporter_addresses: Vec<String>,
+        tls: Option<ClientTlsConfig>,
         mut receiver: Receiver<Notification>,
     ) {
-        let channel = tonic::transport::Channel::from_shared(proxy_address.clone())
-            .expect("Proxy URI should be valid")
-            .connect_lazy();
+        let connect = |address: &str| {
+            let endpoint = Endpoint::from_shared(address.to_string())?;
+            let endpoint = match &tls {
+                Some(tls) => endpoint.tls_config(tls.clone())?,
+                None => endpoint,
+            };
+            Ok::<_, tonic::transport::Error>(endpoint.connect_lazy())
+        };
+        let channel = connect(&proxy_address).expect("Proxy URI should be valid");
         let mut client = NotifierServiceClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
             .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
@@ -307,9 +359,7 @@ where
         let mut exporter_clients: Vec<NotifierServiceClient<Channel>> = exporter_addresses
             .iter()
             .map(|address| {
-                let channel = tonic::transport::Channel::from_shared(address.clone())
-                    .expect("Exporter URI should be valid")
-                    .connect_lazy();
+                let channel = connect(address).expect("Exporter URI should be valid");
                 NotifierServiceClient::new(channel)
                     .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
                     .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -335,7 +385,7 @@ where
                 )
             }
 
-            if let Reason::NewBlock { height: _, hash: _ } = reason {
+            if let Reason::NewBlock { .. } = reason {
                 for exporter_client in &mut exporter_clients {
                     let request = tonic::Request::new(notification.clone());
                     if let Err(error) = exporter_client.notify(request).await {
@@ -351,19 +401,49 @@ where
         }
     }
 
+    /// Sends the cross-chain requests for the outgoing messages of this shard's chains that
+    /// were not confirmed before the server was last stopped.
+    async fn resume_pending_deliveries(mut self) {
+        let assignment = self.shard_map.read().await.clone();
+        let actions = match self
+            .state
+            .pending_cross_chain_requests(|chain_id| {
+                assignment.is_assigned_to(chain_id, &self.shard)
+            })
+            .await
+        {
+            Ok(actions) => actions,
+            Err(error) => {
+                error!(%error, "could not resume pending cross-chain deliveries");
+                return;
+            }
+        };
+        info!(
+            shard_id = self.shard_id,
+            "resuming {} pending cross-chain deliveries",
+            actions.cross_chain_requests.len()
+        );
+        for request in actions.cross_chain_requests {
+            // Unlike new requests, wait for room in the queue instead of dropping them.
+            if self.cross_chain_sender.send((request, None)).await.is_err() {
+                break;
+            }
+        }
+    }
+
     fn handle_network_actions(&self, actions: NetworkActions) {
         let mut cross_chain_sender = self.cross_chain_sender.clone();
         let mut notification_sender = self.notification_sender.clone();
+        // The cross-chain requests are part of the request being handled.
+        let request_id = RequestId::current();
 
         for request in actions.cross_chain_requests {
-            let shard_id = self.network.get_shard_id(request.target_chain_id());
             trace!(
                 source_shard_id = self.shard_id,
-                target_shard_id = shard_id,
-                "Scheduling cross-chain query",
+                "Scheduling cross-chain query"
             );
 
-            if let Err(error) = cross_chain_sender.try_send((request, shard_id)) {
+            if let Err(error) = cross_chain_sender.try_send((request, request_id)) {
                 error!(%error, "dropping cross-chain request");
                 #[cfg(with_metrics)]
                 if error.is_full() {
@@ -392,23 +472,30 @@ where
     #[expect(clippy::too_many_arguments)]
     async fn forward_cross_chain_queries(
         nickname: String,
-        network: ValidatorInternalNetworkConfig,
+        shard_map: Arc<ShardMap>,
         cross_chain_max_retries: u32,
         cross_chain_retry_delay: Duration,
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
         cross_chain_max_concurrent_tasks: usize,
         this_shard: ShardId,
-        receiver: mpsc::Receiver<(linera_core::data_types::CrossChainRequest, ShardId)>,
+        tls: Option<ClientTlsConfig>,
+        receiver: mpsc::Receiver<(
+            linera_core::data_types::CrossChainRequest,
+            Option<RequestId>,
+        )>,
     ) {
-        let pool = GrpcConnectionPool::default();
+        let tls_config = if tls.is_some() {
+            TlsConfig::Tls
+        } else {
+            TlsConfig::ClearText
+        };
+        let pool = GrpcConnectionPool::default().with_tls(tls);
         let max_concurrent_tasks = Some(cross_chain_max_concurrent_tasks);
 
         receiver
-            .for_each_concurrent(max_concurrent_tasks, |(cross_chain_request, shard_id)| {
-                let shard = network.shard(shard_id);
-                let remote_address = shard.http_address();
-
+            .for_each_concurrent(max_concurrent_tasks, |(cross_chain_request, request_id)| {
+                let shard_map = shard_map.clone();
                 let pool = pool.clone();
                 let nickname = nickname.clone();
 
@@ -428,9 +515,21 @@ where
                         )
                         .await;
 
+                        // Look up the target shard before each attempt, in case the chain
+                        // was handed off to another shard in the meantime. Waiting for a
+                        // handoff to complete does not count as an attempt.
+                        let shard = shard_map
+                            .wait_for_shard(cross_chain_request.target_chain_id())
+                            .await;
+                        let to_shard = shard.address();
+                        let remote_address = shard.address_with_tls(tls_config);
+
                         let result = || async {
                             let cross_chain_request = cross_chain_request.clone().try_into()?;
-                            let request = Request::new(cross_chain_request);
+                            let mut request = Request::new(cross_chain_request);
+                            if let Some(request_id) = request_id {
+                                request_id.add_to_metadata(request.metadata_mut());
+                            }
                             let mut client =
                                 ValidatorWorkerClient::new(pool.channel(remote_address.clone())?)
                                     .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -445,14 +544,14 @@ where
                                     %error,
                                     i,
                                     from_shard = this_shard,
-                                    to_shard = shard_id,
+                                    to_shard,
                                     "Failed to send cross-chain query",
                                 );
                             }
                             _ => {
                                 trace!(
                                     from_shard = this_shard,
-                                    to_shard = shard_id,
+                                    to_shard,
                                     "Sent cross-chain query",
                                 );
                                 break;
@@ -461,22 +560,49 @@ where
                         error!(
                             nickname,
                             from_shard = this_shard,
-                            to_shard = shard_id,
+                            to_shard,
                             "Dropping cross-chain query",
                         );
                     }
                 }
+                .instrument(tracing::info_span!(
+                    "cross_chain_request",
+                    request_id = request_id.map(tracing::field::display)
+                ))
             })
             .await;
     }
 
+    /// Waits until no chains are being handed off, and checks that the chain is assigned to
+    /// this shard. The chains are not handed off while the returned guard is held.
+    #[allow(clippy::result_large_err)]
+    async fn assigned_chain(
+        &self,
+        chain_id: Option<ChainId>,
+    ) -> Result<RwLockReadGuard<'_, ShardAssignment>, Status> {
+        let assignment = self.shard_map.read().await;
+        if let Some(chain_id) = chain_id {
+            if !assignment.is_assigned_to(chain_id, &self.shard) {
+                return Err(Status::unavailable(format!(
+                    "chain {chain_id} is not assigned to shard {}",
+                    self.shard_id
+                )));
+            }
+        }
+        Ok(assignment)
+    }
+
     fn log_request_outcome_and_latency(start: Instant, success: bool, method_name: &str) {
-        #![allow(unused_variables)]
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        debug!(
+            message_type = method_name,
+            latency_ms, success, "Handled request"
+        );
         #[cfg(with_metrics)]
         {
             SERVER_REQUEST_LATENCY_PER_REQUEST_TYPE
                 .with_label_values(&[method_name])
-                .observe(start.elapsed().as_secs_f64() * 1000.0);
+                .observe(latency_ms);
             if success {
                 SERVER_REQUEST_SUCCESS
                     .with_label_values(&[method_name])
@@ -506,24 +632,31 @@ where
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let proposal = request.into_inner().try_into()?;
         trace!(?proposal, "Handling block proposal");
-        Ok(Response::new(
-            match self.state.clone().handle_block_proposal(proposal).await {
-                Ok((info, actions)) => {
-                    Self::log_request_outcome_and_latency(start, true, "handle_block_proposal");
-                    self.handle_network_actions(actions);
-                    info.try_into()?
-                }
-                Err(error) => {
-                    Self::log_request_outcome_and_latency(start, false, "handle_block_proposal");
-                    let nickname = self.state.nickname();
-                    warn!(nickname, %error, "Failed to handle block proposal");
-                    NodeError::from(error).try_into()?
-                }
-            },
-        ))
+        let result = match &self.blob_gossip {
+            Some(blob_gossip) => {
+                blob_gossip
+                    .handle_block_proposal(&self.state, proposal)
+                    .await
+            }
+            None => self.state.clone().handle_block_proposal(proposal).await,
+        };
+        Ok(Response::new(match result {
+            Ok((info, actions)) => {
+                Self::log_request_outcome_and_latency(start, true, "handle_block_proposal");
+                self.handle_network_actions(actions);
+                info.try_into()?
+            }
+            Err(error) => {
+                Self::log_request_outcome_and_latency(start, false, "handle_block_proposal");
+                let nickname = self.state.nickname();
+                warn!(nickname, %error, "Failed to handle block proposal");
+                NodeError::from(error).try_into()?
+            }
+        }))
     }
 
     #[instrument(
@@ -539,6 +672,7 @@ where
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let HandleLiteCertRequest {
             certificate,
@@ -556,6 +690,8 @@ where
             Ok((info, actions)) => {
                 Self::log_request_outcome_and_latency(start, true, "handle_lite_certificate");
                 self.handle_network_actions(actions);
+                // The deliveries may need this shard to handle other requests.
+                drop(assignment);
                 if let Some(receiver) = receiver {
                     if let Err(e) = receiver.await {
                         error!("Failed to wait for message delivery: {e}");
@@ -589,6 +725,7 @@ where
         &self,
         request: Request<api::HandleConfirmedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let HandleConfirmedCertificateRequest {
             certificate,
@@ -605,6 +742,8 @@ where
             Ok((info, actions)) => {
                 Self::log_request_outcome_and_latency(start, true, "handle_confirmed_certificate");
                 self.handle_network_actions(actions);
+                // The deliveries may need this shard to handle other requests.
+                drop(assignment);
                 if let Some(receiver) = receiver {
                     if let Err(e) = receiver.await {
                         error!("Failed to wait for message delivery: {e}");
@@ -634,6 +773,7 @@ where
         &self,
         request: Request<api::HandleValidatedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let HandleValidatedCertificateRequest { certificate } = request.into_inner().try_into()?;
         trace!(?certificate, "Handling certificate");
@@ -670,6 +810,7 @@ where
         &self,
         request: Request<api::HandleTimeoutCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let HandleTimeoutCertificateRequest { certificate } = request.into_inner().try_into()?;
         trace!(?certificate, "Handling Timeout certificate");
@@ -705,6 +846,7 @@ where
         &self,
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let query = request.into_inner().try_into()?;
         trace!(?query, "Handling chain info query");
@@ -736,6 +878,7 @@ where
         &self,
         request: Request<PendingBlobRequest>,
     ) -> Result<Response<PendingBlobResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let (chain_id, blob_id) = request.into_inner().try_into()?;
         trace!(?chain_id, ?blob_id, "Download pending blob");
@@ -771,6 +914,7 @@ where
         &self,
         request: Request<HandlePendingBlobRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _assignment = self.assigned_chain(request.get_ref().chain_id()).await?;
         let start = Instant::now();
         let (chain_id, blob_content) = request.into_inner().try_into()?;
         let blob = Blob::new(blob_content);
@@ -804,7 +948,19 @@ where
         request: Request<CrossChainRequest>,
     ) -> Result<Response<()>, Status> {
         let start = Instant::now();
-        let request = request.into_inner().try_into()?;
+        let assignment = self.shard_map.read().await;
+        let request: linera_core::data_types::CrossChainRequest =
+            request.into_inner().try_into()?;
+        if !assignment.is_assigned_to(request.target_chain_id(), &self.shard) {
+            // The chain is being handed off, or was handed off to another shard.
+            drop(assignment);
+            trace!(?request, "Forwarding cross-chain request");
+            self.handle_network_actions(NetworkActions {
+                cross_chain_requests: vec![request],
+                ..NetworkActions::default()
+            });
+            return Ok(Response::new(()));
+        }
         trace!(?request, "Handling cross-chain request");
         match self.state.clone().handle_cross_chain_request(request).await {
             Ok(actions) => {
@@ -819,6 +975,34 @@ where
         }
         Ok(Response::new(()))
     }
+
+    #[instrument(
+        target = "grpc_server",
+        skip_all,
+        err,
+        fields(nickname = self.state.nickname(), shard_id = self.shard_id)
+    )]
+    async fn handle_shard_handoff(
+        &self,
+        request: Request<api::ShardHandoff>,
+    ) -> Result<Response<ShardHandoffResult>, Status> {
+        let handoff: ShardHandoff = request.into_inner().try_into()?;
+        if handoff.shards.is_empty() {
+            return Err(Status::invalid_argument("the list of shards is empty"));
+        }
+        let complete = handoff.complete;
+        let unloaded_chains = self
+            .shard_map
+            .hand_off(handoff, &self.shard, &self.state)
+            .await;
+        info!(
+            nickname = self.state.nickname(),
+            complete, unloaded_chains, "Handed off chains to the new shards"
+        );
+        Ok(Response::new(ShardHandoffResult {
+            unloaded_chains: unloaded_chains as u64,
+        }))
+    }
 }
 
 /// Types which are proxyable and expose the appropriate methods to be handled
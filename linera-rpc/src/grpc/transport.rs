@@ -7,6 +7,10 @@ use crate::NodeOptions;
 pub struct Options {
     pub connect_timeout: Option<linera_base::time::Duration>,
     pub timeout: Option<linera_base::time::Duration>,
+    /// The TLS configuration for `https` addresses. By default, the servers are
+    /// authenticated with the Web PKI roots.
+    #[cfg(not(web))]
+    pub tls: Option<tonic::transport::ClientTlsConfig>,
 }
 
 impl From<&'_ NodeOptions> for Options {
@@ -14,6 +18,8 @@ impl From<&'_ NodeOptions> for Options {
         Self {
             connect_timeout: Some(node_options.send_timeout),
             timeout: Some(node_options.recv_timeout),
+            #[cfg(not(web))]
+            tls: None,
         }
     }
 }
@@ -33,8 +39,11 @@ cfg_if::cfg_if! {
             address: String,
             options: &Options,
         ) -> Result<Channel, Error> {
-            let mut endpoint = tonic::transport::Endpoint::from_shared(address)?
-                .tls_config(tonic::transport::channel::ClientTlsConfig::default().with_webpki_roots())?;
+            let tls_config = options.tls.clone().unwrap_or_else(|| {
+                tonic::transport::channel::ClientTlsConfig::default().with_webpki_roots()
+            });
+            let mut endpoint =
+                tonic::transport::Endpoint::from_shared(address)?.tls_config(tls_config)?;
 
             if let Some(timeout) = options.connect_timeout {
                 endpoint = endpoint.connect_timeout(timeout);
@@ -14,14 +14,18 @@ pub mod node_provider;
 pub mod client;
 
 mod message;
+pub mod request_id;
+#[cfg(any(with_server, with_simple_network))]
+pub mod shard_map;
 #[cfg(with_simple_network)]
 pub mod simple;
 
 pub mod grpc;
 
 pub use client::Client;
-pub use message::RpcMessage;
+pub use message::{RpcMessage, TracedMessage};
 pub use node_provider::{NodeOptions, NodeProvider};
+pub use request_id::RequestId;
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
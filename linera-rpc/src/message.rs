@@ -13,15 +13,15 @@ use linera_chain::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
-    node::NodeError,
+    node::{NodeError, NodeFeatures},
 };
 use linera_storage::NetworkDescription;
 use linera_version::VersionInfo;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    config::ShardHandoff, HandleConfirmedCertificateRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest, RequestId,
 };
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -61,6 +61,22 @@ pub enum RpcMessage {
 
     // Internal to a validator
     CrossChainRequest(Box<CrossChainRequest>),
+    Traced(Box<TracedMessage>),
+    ShardHandoff(Box<ShardHandoff>),
+    ShardHandoffResponse(u64),
+
+    // Appended to keep the indices of the variants above
+    NodeFeaturesQuery,
+    NodeFeaturesResponse(Box<NodeFeatures>),
+}
+
+/// A message sent from one component of a validator to another, as part of the handling of
+/// a request.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct TracedMessage {
+    pub request_id: RequestId,
+    pub message: RpcMessage,
 }
 
 impl RpcMessage {
@@ -80,6 +96,7 @@ impl RpcMessage {
             CrossChainRequest(request) => request.target_chain_id(),
             DownloadPendingBlob(request) => request.0,
             HandlePendingBlob(request) => request.0,
+            Traced(traced) => return traced.message.target_chain_id(),
             Vote(_)
             | Error(_)
             | ChainInfoResponse(_)
@@ -87,6 +104,8 @@ impl RpcMessage {
             | VersionInfoResponse(_)
             | NetworkDescriptionQuery
             | NetworkDescriptionResponse(_)
+            | NodeFeaturesQuery
+            | NodeFeaturesResponse(_)
             | UploadBlob(_)
             | UploadBlobResponse(_)
             | DownloadBlob(_)
@@ -99,7 +118,9 @@ impl RpcMessage {
             | BlobLastUsedByResponse(_)
             | MissingBlobIds(_)
             | MissingBlobIdsResponse(_)
-            | DownloadCertificatesResponse(_) => {
+            | DownloadCertificatesResponse(_)
+            | ShardHandoff(_)
+            | ShardHandoffResponse(_) => {
                 return None;
             }
         };
@@ -115,6 +136,7 @@ impl RpcMessage {
         match self {
             VersionInfoQuery
             | NetworkDescriptionQuery
+            | NodeFeaturesQuery
             | UploadBlob(_)
             | DownloadBlob(_)
             | DownloadConfirmedBlock(_)
@@ -128,11 +150,15 @@ impl RpcMessage {
             | ConfirmedCertificate(_)
             | ChainInfoQuery(_)
             | CrossChainRequest(_)
+            | Traced(_)
+            | ShardHandoff(_)
+            | ShardHandoffResponse(_)
             | Vote(_)
             | Error(_)
             | ChainInfoResponse(_)
             | VersionInfoResponse(_)
             | NetworkDescriptionResponse(_)
+            | NodeFeaturesResponse(_)
             | UploadBlobResponse(_)
             | DownloadPendingBlob(_)
             | DownloadPendingBlobResponse(_)
@@ -144,6 +170,17 @@ impl RpcMessage {
             | DownloadCertificatesResponse(_) => false,
         }
     }
+
+    /// Wraps this message with the ID of the request it is part of, if any.
+    pub fn with_request_id(self, request_id: Option<RequestId>) -> Self {
+        match request_id {
+            Some(request_id) => RpcMessage::Traced(Box::new(TracedMessage {
+                request_id,
+                message: self,
+            })),
+            None => self,
+        }
+    }
 }
 
 impl TryFrom<RpcMessage> for ChainInfoResponse {
@@ -223,6 +260,17 @@ impl TryFrom<RpcMessage> for NetworkDescription {
     }
 }
 
+impl TryFrom<RpcMessage> for NodeFeatures {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        match message {
+            RpcMessage::NodeFeaturesResponse(features) => Ok(*features),
+            RpcMessage::Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
 impl TryFrom<RpcMessage> for Vec<BlobId> {
     type Error = NodeError;
     fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
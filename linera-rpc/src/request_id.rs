@@ -0,0 +1,181 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Identifiers of the requests received by a validator, to correlate the logs of all the
+//! components handling them.
+
+#[cfg(not(web))]
+use std::future::Future;
+#[cfg(with_server)]
+use std::task::{Context, Poll};
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+#[cfg(with_server)]
+use futures::{future::BoxFuture, FutureExt as _};
+use serde::{Deserialize, Serialize};
+use tonic::metadata::{MetadataMap, MetadataValue};
+#[cfg(with_server)]
+use tower::{Layer, Service};
+#[cfg(with_server)]
+use tracing::Instrument as _;
+
+/// The gRPC metadata key of the request ID.
+pub const REQUEST_ID_METADATA_KEY: &str = "linera-request-id";
+
+/// The ID of a request received by a validator's proxy.
+///
+/// The proxy passes it on to the shard handling the request, which passes it on to the
+/// cross-chain requests it sends as a result. Each component records it as the
+/// `request_id` field of the tracing span of the request, so that the logs of the worker
+/// and of the storage share it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestId(u64);
+
+#[cfg(not(web))]
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+impl RequestId {
+    /// Returns a new random request ID.
+    pub fn generate() -> Self {
+        RequestId(rand::random())
+    }
+
+    /// Returns the ID of the request being handled by the current task, if any.
+    #[cfg(not(web))]
+    pub fn current() -> Option<Self> {
+        CURRENT_REQUEST_ID.try_with(|request_id| *request_id).ok()
+    }
+
+    /// Runs `future` as the handling of this request, i.e. with this as the current ID.
+    #[cfg(not(web))]
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT_REQUEST_ID.scope(self, future).await
+    }
+
+    /// Reads the request ID from the metadata of a gRPC request, if any.
+    pub fn from_metadata(metadata: &MetadataMap) -> Option<Self> {
+        metadata
+            .get(REQUEST_ID_METADATA_KEY)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Adds this request ID to the metadata of a gRPC request.
+    pub fn add_to_metadata(self, metadata: &mut MetadataMap) {
+        let value = MetadataValue::try_from(self.to_string())
+            .expect("hexadecimal numbers are valid metadata values");
+        metadata.insert(REQUEST_ID_METADATA_KEY, value);
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for RequestId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(RequestId(u64::from_str_radix(s, 16)?))
+    }
+}
+
+/// A layer handling each gRPC request in a tracing span with its request ID, and with the
+/// ID as the current one.
+#[cfg(with_server)]
+#[derive(Clone, Copy)]
+pub struct RequestIdLayer {
+    /// Whether to generate a new ID for each request, instead of using the one in the
+    /// request's metadata.
+    generate: bool,
+}
+
+#[cfg(with_server)]
+impl RequestIdLayer {
+    /// Returns a layer generating a new ID for each request, for the requests from clients.
+    pub fn generating() -> Self {
+        RequestIdLayer { generate: true }
+    }
+
+    /// Returns a layer using the ID in the metadata of each request, if any, for the
+    /// requests from the other components of the validator.
+    pub fn propagating() -> Self {
+        RequestIdLayer { generate: false }
+    }
+}
+
+#[cfg(with_server)]
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RequestIdService {
+            service,
+            generate: self.generate,
+        }
+    }
+}
+
+#[cfg(with_server)]
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    service: S,
+    generate: bool,
+}
+
+#[cfg(with_server)]
+impl<S, B> Service<tonic::codegen::http::Request<B>> for RequestIdService<S>
+where
+    S: Service<tonic::codegen::http::Request<B>> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: tonic::codegen::http::Request<B>) -> Self::Future {
+        let request_id = if self.generate {
+            Some(RequestId::generate())
+        } else {
+            request
+                .headers()
+                .get(REQUEST_ID_METADATA_KEY)
+                .and_then(|value| value.to_str().ok()?.parse().ok())
+        };
+        let future = self.service.call(request);
+        match request_id {
+            Some(request_id) => {
+                let span = tracing::info_span!("request", %request_id);
+                request_id.scope(future).instrument(span).boxed()
+            }
+            None => future.boxed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::metadata::MetadataMap;
+
+    use super::RequestId;
+
+    #[test]
+    fn test_request_id_metadata_round_trip() {
+        let request_id = RequestId::generate();
+        let mut metadata = MetadataMap::new();
+        assert_eq!(RequestId::from_metadata(&metadata), None);
+        request_id.add_to_metadata(&mut metadata);
+        assert_eq!(RequestId::from_metadata(&metadata), Some(request_id));
+        assert_eq!(request_id.to_string().parse(), Ok(request_id));
+    }
+}
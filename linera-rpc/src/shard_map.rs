@@ -0,0 +1,130 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The assignment of chains to shards that the shards running in one process share.
+
+use linera_base::identifiers::ChainId;
+use linera_core::worker::WorkerState;
+use linera_storage::Storage;
+use tokio::sync::{watch, RwLock, RwLockReadGuard};
+
+use crate::config::{ShardAssignment, ShardConfig, ShardHandoff};
+
+/// The assignment of chains to a validator's shards, which changes when the chains are
+/// handed off to a new list of shards.
+///
+/// Requests hold a read lock while they are handled, so that chains are only handed off to
+/// other shards between requests.
+pub struct ShardMap {
+    assignment: RwLock<ShardAssignment>,
+    /// Notified whenever the assignment changes.
+    changed: watch::Sender<()>,
+}
+
+impl ShardMap {
+    /// Creates a shard map with the given assignment.
+    pub fn new(assignment: ShardAssignment) -> Self {
+        Self {
+            assignment: RwLock::new(assignment),
+            changed: watch::Sender::new(()),
+        }
+    }
+
+    /// Waits until no chains are being handed off, and returns the assignment. The chains
+    /// are not handed off while the returned guard is held.
+    pub async fn read(&self) -> RwLockReadGuard<'_, ShardAssignment> {
+        self.assignment.read().await
+    }
+
+    /// Returns the shard that `chain_id` is assigned to, waiting until the chain is handed
+    /// off if it is moving to another shard.
+    pub async fn wait_for_shard(&self, chain_id: ChainId) -> ShardConfig {
+        // Subscribe first, so that a handoff completing in the meantime is not missed.
+        let mut changed = self.changed.subscribe();
+        loop {
+            if let Some(shard) = self.assignment.read().await.shard_for(chain_id) {
+                return shard.clone();
+            }
+            changed
+                .changed()
+                .await
+                .expect("the shard map keeps the sender alive");
+        }
+    }
+
+    /// Applies a step of handing off the chains to a new list of shards, once the requests
+    /// that are being handled are done, and unloads the chains of `state` that are no
+    /// longer assigned to `shard`. Returns the number of unloaded chain workers.
+    ///
+    /// The shards running in the same process each apply the same step, which only changes
+    /// the assignment once.
+    pub async fn hand_off<S: Storage>(
+        &self,
+        handoff: ShardHandoff,
+        shard: &ShardConfig,
+        state: &WorkerState<S>,
+    ) -> usize {
+        let ShardHandoff { shards, complete } = handoff;
+        let mut assignment = self.assignment.write().await;
+        if complete {
+            assignment.complete_handoff(shards);
+        } else {
+            assignment.begin_handoff(shards);
+        }
+        self.changed.send_replace(());
+        state.unload_chains(|chain_id| !assignment.is_assigned_to(chain_id, shard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use linera_base::{
+        crypto::{CryptoHash, ValidatorKeypair},
+        identifiers::ChainId,
+    };
+
+    use super::ShardMap;
+    use crate::config::{ShardAssignment, ShardConfig, ValidatorInternalNetworkPreConfig};
+
+    fn shard(port: u16) -> ShardConfig {
+        ShardConfig {
+            host: "localhost".to_string(),
+            port,
+            metrics_port: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_moving_chain() {
+        let network = ValidatorInternalNetworkPreConfig {
+            public_key: ValidatorKeypair::generate().public_key,
+            protocol: (),
+            shards: vec![shard(9100)],
+            host: "localhost".to_string(),
+            port: 10000,
+            block_exporters: Vec::new(),
+            metrics_port: 10001,
+            archive: false,
+            block_body_retention: None,
+        };
+        let next_shards = vec![shard(9100), shard(9101)];
+        let mut assignment = ShardAssignment::new(&network);
+        assignment.begin_handoff(next_shards.clone());
+        let chain_id = (0..)
+            .map(|i| ChainId(CryptoHash::test_hash(format!("chain {i}"))))
+            .find(|chain_id| assignment.shard_for(*chain_id).is_none())
+            .unwrap();
+        let map = ShardMap::new(assignment);
+
+        let wait = map.wait_for_shard(chain_id);
+        tokio::pin!(wait);
+        assert!(tokio::time::timeout(Duration::from_millis(10), &mut wait)
+            .await
+            .is_err());
+        map.assignment.write().await.complete_handoff(next_shards);
+        map.changed.send_replace(());
+        assert_eq!(wait.await, shard(9101));
+    }
+}
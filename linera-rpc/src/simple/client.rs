@@ -4,7 +4,7 @@
 
 use std::future::Future;
 
-use futures::{sink::SinkExt, stream::StreamExt};
+use futures::{future, sink::SinkExt, stream::StreamExt};
 use linera_base::{
     crypto::CryptoHash,
     data_types::BlobContent,
@@ -19,7 +19,8 @@ use linera_chain::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    node::{CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
 };
 use linera_storage::NetworkDescription;
 use linera_version::VersionInfo;
@@ -153,6 +154,15 @@ impl ValidatorNode for SimpleClient {
         async { Err(NodeError::SubscriptionError { transport }) }
     }
 
+    fn subscribe_with_filter(
+        &self,
+        _chains: Vec<ChainId>,
+        _filter: NotificationFilter,
+    ) -> impl Future<Output = Result<NotificationStream, NodeError>> + Send {
+        let transport = self.network.protocol.to_string();
+        async { Err(NodeError::SubscriptionError { transport }) }
+    }
+
     async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
         self.query(RpcMessage::VersionInfoQuery).await
     }
@@ -161,6 +171,10 @@ impl ValidatorNode for SimpleClient {
         self.query(RpcMessage::NetworkDescriptionQuery).await
     }
 
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError> {
+        self.query(RpcMessage::NodeFeaturesQuery).await
+    }
+
     async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
         self.query(RpcMessage::UploadBlob(Box::new(content))).await
     }
@@ -170,6 +184,15 @@ impl ValidatorNode for SimpleClient {
             .await
     }
 
+    async fn download_blobs(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError> {
+        future::try_join_all(
+            blob_ids
+                .into_iter()
+                .map(|blob_id| self.download_blob(blob_id)),
+        )
+        .await
+    }
+
     async fn download_pending_blob(
         &self,
         chain_id: ChainId,
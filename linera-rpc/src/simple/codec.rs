@@ -10,13 +10,22 @@ use tokio_util::codec::{Decoder, Encoder};
 
 use crate::RpcMessage;
 
-/// The size of the frame prefix that contains the payload size.
-const PREFIX_SIZE: u8 = mem::size_of::<u32>() as u8;
+/// The bytes at the start of every frame, to recognize the traffic of this protocol.
+const MAGIC: [u8; 4] = *b"LNRA";
+
+/// The version of the frame format.
+const VERSION: u8 = 1;
+
+/// The size of the frame header: the magic bytes, the version, the payload size and the
+/// CRC32 checksum of the payload.
+const HEADER_SIZE: usize = MAGIC.len() + mem::size_of::<u8>() + 2 * mem::size_of::<u32>();
 
 /// An encoder/decoder of [`RpcMessage`]s for the RPC protocol.
 ///
-/// The frames are length-delimited by a [`u32`] prefix, and the payload is deserialized by
-/// [`bincode`].
+/// Each frame starts with a header containing [`MAGIC`], the [`VERSION`] of the format, the
+/// payload size as a [`u32`] and the CRC32 checksum of the payload. The payload is
+/// serialized by [`bincode`]. Frames from another protocol, another version or with a
+/// corrupted payload are rejected before deserializing them.
 #[derive(Clone, Copy, Debug)]
 pub struct Codec;
 
@@ -26,7 +35,7 @@ impl Encoder<RpcMessage> for Codec {
     fn encode(&mut self, message: RpcMessage, buffer: &mut BytesMut) -> Result<(), Self::Error> {
         let mut frame_buffer = buffer.split_off(buffer.len());
 
-        frame_buffer.put_u32_le(0);
+        frame_buffer.put_bytes(0, HEADER_SIZE);
 
         let mut frame_writer = frame_buffer.writer();
 
@@ -35,15 +44,19 @@ impl Encoder<RpcMessage> for Codec {
 
         let mut frame_buffer = frame_writer.into_inner();
         let frame_size = frame_buffer.len();
-        let payload_size = frame_size - PREFIX_SIZE as usize;
+        let payload_size = frame_size - HEADER_SIZE;
+        let checksum = crc32fast::hash(&frame_buffer[HEADER_SIZE..]);
 
         let mut start_of_frame = frame_buffer.deref_mut();
 
+        start_of_frame.put_slice(&MAGIC);
+        start_of_frame.put_u8(VERSION);
         start_of_frame.put_u32_le(
             payload_size
                 .try_into()
                 .map_err(|_| Error::MessageTooBig { size: payload_size })?,
         );
+        start_of_frame.put_u32_le(checksum);
 
         buffer.unsplit(frame_buffer);
 
@@ -56,26 +69,50 @@ impl Decoder for Codec {
     type Error = Error;
 
     fn decode(&mut self, buffer: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if buffer.len() < PREFIX_SIZE.into() {
+        if buffer.len() < HEADER_SIZE {
             return Ok(None);
         }
 
         let mut start_of_buffer: &[u8] = &*buffer;
+        let mut magic = [0; MAGIC.len()];
+        start_of_buffer.copy_to_slice(&mut magic);
+        let version = start_of_buffer.get_u8();
+        // The rest of the stream or datagram can't be framed without a valid header.
+        if magic != MAGIC {
+            buffer.clear();
+            return Err(Error::InvalidMagic { found: magic });
+        }
+        if version != VERSION {
+            buffer.clear();
+            return Err(Error::UnsupportedVersion {
+                found: version,
+                expected: VERSION,
+            });
+        }
         let payload_size = start_of_buffer
             .get_u32_le()
             .try_into()
             .expect("u32 should fit in a usize");
+        let expected_checksum = start_of_buffer.get_u32_le();
 
-        let frame_size = PREFIX_SIZE as usize + payload_size;
+        let frame_size = HEADER_SIZE + payload_size;
 
         if buffer.len() < frame_size {
             buffer.reserve(frame_size);
             return Ok(None);
         }
 
-        let _prefix = buffer.split_to(PREFIX_SIZE.into());
+        let _header = buffer.split_to(HEADER_SIZE);
         let payload = buffer.split_to(payload_size);
 
+        let checksum = crc32fast::hash(&payload);
+        if checksum != expected_checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: expected_checksum,
+                actual: checksum,
+            });
+        }
+
         let message =
             bincode::deserialize(&payload).map_err(|error| Error::Deserialization(*error))?;
 
@@ -99,6 +136,24 @@ pub enum Error {
         message is {size} bytes but can't be larger than {max} bytes.",
         max = u32::MAX)]
     MessageTooBig { size: usize },
+
+    #[error(
+        "Received a frame without the protocol's magic bytes (found {found:02x?}): \
+         the peer may be using another protocol or port"
+    )]
+    InvalidMagic { found: [u8; 4] },
+
+    #[error(
+        "Received a frame of version {found} of the protocol, but only version {expected} is \
+         supported"
+    )]
+    UnsupportedVersion { found: u8, expected: u8 },
+
+    #[error(
+        "Received a corrupted frame: the checksum of its payload is {actual:#010x} instead of \
+         {expected:#010x}"
+    )]
+    ChecksumMismatch { expected: u32, actual: u32 },
 }
 
 impl From<Error> for NodeError {
@@ -122,12 +177,20 @@ mod tests {
     use test_strategy::proptest;
     use tokio_util::codec::{Decoder, Encoder};
 
-    use super::{Codec, RpcMessage, PREFIX_SIZE};
+    use super::{Codec, Error, RpcMessage, HEADER_SIZE, MAGIC, VERSION};
+
+    /// Writes the header of a frame with the given payload to the buffer.
+    fn put_header(buffer: &mut BytesMut, payload: &[u8]) {
+        buffer.put_slice(&MAGIC);
+        buffer.put_u8(VERSION);
+        buffer.put_u32_le(payload.len() as u32);
+        buffer.put_u32_le(crc32fast::hash(payload));
+    }
 
     /// Test decoding of a frame from a buffer.
     ///
     /// The buffer may contain leading or trailing bytes around the frame. The frame contains the
-    /// size of the payload, and the payload is a serialized dummy [`RpcMessage`].
+    /// header and the payload, which is a serialized dummy [`RpcMessage`].
     ///
     /// The decoder should produce the exact same message as used as the test input, and it should
     /// ignore the leading and trailing bytes.
@@ -141,14 +204,14 @@ mod tests {
         let payload = bincode::serialize(&message).expect("RpcMessage is serializable");
 
         let mut buffer = BytesMut::with_capacity(
-            leading_bytes.len() + PREFIX_SIZE as usize + payload.len() + trailing_bytes.len(),
+            leading_bytes.len() + HEADER_SIZE + payload.len() + trailing_bytes.len(),
         );
 
         buffer.extend_from_slice(&leading_bytes);
 
         let start_of_buffer = buffer.split();
 
-        buffer.put_u32_le(payload.len() as u32);
+        put_header(&mut buffer, &payload);
         buffer.extend_from_slice(&payload);
         buffer.extend_from_slice(&trailing_bytes);
 
@@ -166,9 +229,9 @@ mod tests {
     /// The buffer may already contain some leading bytes, but the cursor is set to where the frame
     /// should start.
     ///
-    /// The encoder should write a prefix with the size of the serialized message, followed by the
-    /// serialized message bytes. It should not touch the leading bytes nor append any trailing
-    /// bytes.
+    /// The encoder should write a header with the size and checksum of the serialized message,
+    /// followed by the serialized message bytes. It should not touch the leading bytes nor
+    /// append any trailing bytes.
     #[proptest]
     fn encodes_at_the_correct_buffer_offset(
         leading_bytes: Vec<u8>,
@@ -183,25 +246,57 @@ mod tests {
         buffer.extend_from_slice(&leading_bytes);
 
         let frame_start = buffer.len();
-        let prefix_end = frame_start + PREFIX_SIZE as usize;
+        let header_end = frame_start + HEADER_SIZE;
 
         let result = Codec.encode(message, &mut buffer);
 
         assert!(matches!(result, Ok(())));
         assert_eq!(&buffer[..frame_start], &leading_bytes);
 
-        let prefix = u32::from_le_bytes(
-            buffer[frame_start..prefix_end]
-                .try_into()
-                .expect("Incorrect prefix slice indices"),
-        );
+        let mut expected_header = BytesMut::new();
+        put_header(&mut expected_header, &serialized_message);
 
-        assert_eq!(prefix as usize, serialized_message.len());
+        assert_eq!(&buffer[frame_start..header_end], &expected_header);
         assert_eq!(
             buffer.len(),
-            leading_bytes.len() + PREFIX_SIZE as usize + prefix as usize
+            leading_bytes.len() + HEADER_SIZE + serialized_message.len()
         );
 
-        assert_eq!(&buffer[prefix_end..], &serialized_message);
+        assert_eq!(&buffer[header_end..], &serialized_message);
+    }
+
+    /// Test that frames from another protocol or version, or with a corrupted payload, are
+    /// rejected.
+    #[proptest]
+    fn rejects_invalid_frames(message_contents: ChainInfoQuery) {
+        let message = RpcMessage::ChainInfoQuery(Box::new(message_contents));
+        let mut frame = BytesMut::new();
+        Codec
+            .encode(message, &mut frame)
+            .expect("Encoding should succeed");
+
+        let mut buffer = frame.clone();
+        buffer[0] ^= 0xff;
+        assert!(matches!(
+            Codec.decode(&mut buffer),
+            Err(Error::InvalidMagic { .. })
+        ));
+        assert!(buffer.is_empty());
+
+        let mut buffer = frame.clone();
+        buffer[MAGIC.len()] = VERSION + 1;
+        assert!(matches!(
+            Codec.decode(&mut buffer),
+            Err(Error::UnsupportedVersion { found, expected: VERSION }) if found == VERSION + 1
+        ));
+
+        let mut buffer = frame.clone();
+        let last = buffer.len() - 1;
+        buffer[last] ^= 0x01;
+        assert!(matches!(
+            Codec.decode(&mut buffer),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert!(buffer.is_empty());
     }
 }
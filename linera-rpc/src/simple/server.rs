@@ -1,10 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use futures::{channel::mpsc, stream::StreamExt};
+use futures::{channel::mpsc, stream::StreamExt, SinkExt as _};
 use linera_base::{data_types::Blob, time::Duration};
 use linera_core::{
+    blob_gossip::BlobGossip,
     node::NodeError,
     worker::{NetworkActions, WorkerError, WorkerState},
     JoinSetExt as _,
@@ -17,8 +20,9 @@ use tracing::{debug, error, info, instrument, warn};
 
 use super::transport::{MessageHandler, ServerHandle, TransportProtocol};
 use crate::{
-    config::{CrossChainConfig, ShardId, ValidatorInternalNetworkPreConfig},
-    RpcMessage,
+    config::{CrossChainConfig, ShardConfig, ShardId, ValidatorInternalNetworkPreConfig},
+    shard_map::ShardMap,
+    NodeProvider, RequestId, RpcMessage, TracedMessage,
 };
 
 #[derive(Clone)]
@@ -31,7 +35,13 @@ where
     port: u16,
     state: WorkerState<S>,
     shard_id: ShardId,
+    /// The configuration of this shard.
+    shard: ShardConfig,
+    /// The assignment of chains to shards, shared by the shards running in this process.
+    shard_map: Arc<ShardMap>,
     cross_chain_config: CrossChainConfig,
+    /// If set, missing blobs of block proposals are downloaded from the other validators.
+    blob_gossip: Option<Arc<BlobGossip<NodeProvider>>>,
     // Stats
     packets_processed: u64,
     user_errors: u64,
@@ -47,20 +57,32 @@ where
         port: u16,
         state: WorkerState<S>,
         shard_id: ShardId,
+        shard_map: Arc<ShardMap>,
         cross_chain_config: CrossChainConfig,
     ) -> Self {
+        let shard = network.shard(shard_id).clone();
         Self {
             network,
             host,
             port,
             state,
             shard_id,
+            shard,
+            shard_map,
             cross_chain_config,
+            blob_gossip: None,
             packets_processed: 0,
             user_errors: 0,
         }
     }
 
+    /// Returns an instance that downloads the missing blobs of block proposals from the
+    /// other validators, if set.
+    pub fn with_blob_gossip(mut self, blob_gossip: Option<BlobGossip<NodeProvider>>) -> Self {
+        self.blob_gossip = blob_gossip.map(Arc::new);
+        self
+    }
+
     pub fn packets_processed(&self) -> u64 {
         self.packets_processed
     }
@@ -78,12 +100,13 @@ where
     async fn forward_cross_chain_queries(
         nickname: String,
         network: ValidatorInternalNetworkPreConfig<TransportProtocol>,
+        shard_map: Arc<ShardMap>,
         cross_chain_max_retries: u32,
         cross_chain_retry_delay: Duration,
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
         this_shard: ShardId,
-        mut receiver: mpsc::Receiver<(RpcMessage, ShardId)>,
+        mut receiver: mpsc::Receiver<RpcMessage>,
     ) {
         let mut pool = network
             .protocol
@@ -91,16 +114,20 @@ where
             .await
             .expect("Initialization should not fail");
 
-        while let Some((message, shard_id)) = receiver.next().await {
+        while let Some(message) = receiver.next().await {
             if cross_chain_sender_failure_rate > 0.0
                 && rand::thread_rng().gen::<f32>() < cross_chain_sender_failure_rate
             {
                 warn!("Dropped 1 cross-message intentionally.");
                 continue;
             }
-
-            let shard = network.shard(shard_id);
-            let remote_address = format!("{}:{}", shard.host, shard.port);
+            let Some(target_chain_id) = message.target_chain_id() else {
+                error!(
+                    nickname,
+                    "Dropping cross-chain query without a target chain"
+                );
+                continue;
+            };
 
             // Send the cross-chain query and retry if needed.
             for i in 0..cross_chain_max_retries {
@@ -110,6 +137,10 @@ where
                 )
                 .await;
 
+                // Look up the target shard before each attempt, in case the chain was
+                // handed off to another shard in the meantime. Waiting for a handoff to
+                // complete does not count as an attempt.
+                let remote_address = shard_map.wait_for_shard(target_chain_id).await.address();
                 let status = pool.send_message_to(message.clone(), &remote_address).await;
                 match status {
                     Err(error) => {
@@ -118,14 +149,14 @@ where
                             %error,
                             i,
                             from_shard = this_shard,
-                            to_shard = shard_id,
+                            to_shard = %remote_address,
                             "Failed to send cross-chain query",
                         );
                     }
                     _ => {
                         debug!(
                             from_shard = this_shard,
-                            to_shard = shard_id,
+                            to_shard = %remote_address,
                             "Sent cross-chain query",
                         );
                         break;
@@ -134,7 +165,7 @@ where
                 error!(
                     nickname,
                     from_shard = this_shard,
-                    to_shard = shard_id,
+                    %target_chain_id,
                     "Dropping cross-chain query",
                 );
             }
@@ -158,6 +189,7 @@ where
         join_set.spawn_task(Self::forward_cross_chain_queries(
             self.state.nickname().to_string(),
             self.network.clone(),
+            self.shard_map.clone(),
             self.cross_chain_config.max_retries,
             Duration::from_millis(self.cross_chain_config.retry_delay_ms),
             Duration::from_millis(self.cross_chain_config.sender_delay_ms),
@@ -171,6 +203,7 @@ where
             server: self,
             cross_chain_sender,
         };
+        join_set.spawn_task(state.clone().resume_pending_deliveries());
         // Launch server for the appropriate protocol.
         protocol.spawn_server(address, state, shutdown_signal, join_set)
     }
@@ -182,7 +215,7 @@ where
     S: Storage,
 {
     server: Server<S>,
-    cross_chain_sender: mpsc::Sender<(RpcMessage, ShardId)>,
+    cross_chain_sender: mpsc::Sender<RpcMessage>,
 }
 
 #[async_trait]
@@ -195,13 +228,63 @@ where
         skip_all,
         fields(
             nickname = self.server.state.nickname(),
-            chain_id = ?message.target_chain_id()
+            chain_id = ?message.target_chain_id(),
+            request_id = tracing::field::Empty,
         )
     )]
     async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+        match message {
+            RpcMessage::Traced(traced) => {
+                let TracedMessage {
+                    request_id,
+                    message,
+                } = *traced;
+                tracing::Span::current().record("request_id", tracing::field::display(request_id));
+                request_id
+                    .scope(Box::pin(self.handle_untraced_message(message)))
+                    .await
+            }
+            message => Box::pin(self.handle_untraced_message(message)).await,
+        }
+    }
+}
+
+impl<S> RunningServerState<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    /// Handles a message, unwrapped from its request ID if it had one.
+    async fn handle_untraced_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+        // Wait until no chains are being handed off, and check that the chain is assigned to
+        // this shard. The chains are not handed off while the guard is held.
+        let shard_map = self.server.shard_map.clone();
+        let assignment = match message.target_chain_id() {
+            Some(chain_id) => {
+                let assignment = shard_map.read().await;
+                if !assignment.is_assigned_to(chain_id, &self.server.shard) {
+                    let error = NodeError::WorkerError {
+                        error: format!(
+                            "chain {chain_id} is not assigned to shard {}",
+                            self.server.shard_id
+                        ),
+                    };
+                    return Some(error.into());
+                }
+                Some(assignment)
+            }
+            None => None,
+        };
         let reply = match message {
             RpcMessage::BlockProposal(message) => {
-                match self.server.state.handle_block_proposal(*message).await {
+                let result = match &self.server.blob_gossip {
+                    Some(blob_gossip) => {
+                        blob_gossip
+                            .handle_block_proposal(&self.server.state, *message)
+                            .await
+                    }
+                    None => self.server.state.handle_block_proposal(*message).await,
+                };
+                match result {
                     Ok((info, actions)) => {
                         // Cross-shard requests
                         self.handle_network_actions(actions);
@@ -230,6 +313,8 @@ where
                     Ok((info, actions)) => {
                         // Cross-shard requests
                         self.handle_network_actions(actions);
+                        // The messages may be for chains that are being handed off.
+                        drop(assignment);
                         if let Some(receiver) = receiver {
                             if let Err(e) = receiver.await {
                                 error!("Failed to wait for message delivery: {e}");
@@ -305,6 +390,8 @@ where
                     Ok((info, actions)) => {
                         // Cross-shard requests
                         self.handle_network_actions(actions);
+                        // The messages may be for chains that are being handed off.
+                        drop(assignment);
                         if let Some(receiver) = receiver {
                             if let Err(e) = receiver.await {
                                 error!("Failed to wait for message delivery: {e}");
@@ -387,12 +474,36 @@ where
                 Ok(Some(RpcMessage::VersionInfoResponse(Box::default())))
             }
 
+            RpcMessage::ShardHandoff(handoff) => {
+                if handoff.shards.is_empty() {
+                    Err(NodeError::WorkerError {
+                        error: "the list of shards is empty".to_string(),
+                    })
+                } else {
+                    let complete = handoff.complete;
+                    let unloaded_chains = self
+                        .server
+                        .shard_map
+                        .hand_off(*handoff, &self.server.shard, &self.server.state)
+                        .await;
+                    info!(
+                        nickname = self.server.state.nickname(),
+                        complete, unloaded_chains, "Handed off chains to the new shards"
+                    );
+                    Ok(Some(RpcMessage::ShardHandoffResponse(
+                        unloaded_chains as u64,
+                    )))
+                }
+            }
+
             RpcMessage::Vote(_)
             | RpcMessage::Error(_)
             | RpcMessage::ChainInfoResponse(_)
             | RpcMessage::VersionInfoResponse(_)
             | RpcMessage::NetworkDescriptionQuery
             | RpcMessage::NetworkDescriptionResponse(_)
+            | RpcMessage::NodeFeaturesQuery
+            | RpcMessage::NodeFeaturesResponse(_)
             | RpcMessage::DownloadBlob(_)
             | RpcMessage::DownloadBlobResponse(_)
             | RpcMessage::DownloadPendingBlobResponse(_)
@@ -405,7 +516,9 @@ where
             | RpcMessage::DownloadCertificates(_)
             | RpcMessage::DownloadCertificatesResponse(_)
             | RpcMessage::UploadBlob(_)
-            | RpcMessage::UploadBlobResponse(_) => Err(NodeError::UnexpectedMessage),
+            | RpcMessage::UploadBlobResponse(_)
+            | RpcMessage::ShardHandoffResponse(_)
+            | RpcMessage::Traced(_) => Err(NodeError::UnexpectedMessage),
         };
 
         self.server.packets_processed += 1;
@@ -434,23 +547,57 @@ where
             }
         }
     }
+
+    /// Sends the cross-chain requests for the outgoing messages of this shard's chains that
+    /// were not confirmed before the server was last stopped.
+    async fn resume_pending_deliveries(mut self) {
+        let assignment = self.server.shard_map.read().await.clone();
+        let shard = &self.server.shard;
+        let actions = match self
+            .server
+            .state
+            .pending_cross_chain_requests(|chain_id| assignment.is_assigned_to(chain_id, shard))
+            .await
+        {
+            Ok(actions) => actions,
+            Err(error) => {
+                error!(%error, "could not resume pending cross-chain deliveries");
+                return;
+            }
+        };
+        info!(
+            "[{}] Resuming {} pending cross-chain deliveries",
+            self.server.state.nickname(),
+            actions.cross_chain_requests.len()
+        );
+        for request in actions.cross_chain_requests {
+            let request = RpcMessage::CrossChainRequest(Box::new(request));
+            // Unlike new requests, wait for room in the queue instead of dropping them.
+            if self.cross_chain_sender.send(request).await.is_err() {
+                break;
+            }
+        }
+    }
 }
 
 impl<S> RunningServerState<S>
 where
     S: Storage + Send,
 {
+    /// Schedules the cross-chain requests resulting from the request being handled, as part
+    /// of the same request.
     fn handle_network_actions(&mut self, actions: NetworkActions) {
+        let request_id = RequestId::current();
         for request in actions.cross_chain_requests {
-            let shard_id = self.server.network.get_shard_id(request.target_chain_id());
             debug!(
-                "[{}] Scheduling cross-chain query: {} -> {}",
+                "[{}] Scheduling cross-chain query: shard {} -> chain {}",
                 self.server.state.nickname(),
                 self.server.shard_id,
-                shard_id
+                request.target_chain_id()
             );
-            let request = RpcMessage::CrossChainRequest(Box::new(request));
-            if let Err(error) = self.cross_chain_sender.try_send((request, shard_id)) {
+            let request =
+                RpcMessage::CrossChainRequest(Box::new(request)).with_request_id(request_id);
+            if let Err(error) = self.cross_chain_sender.try_send(request) {
                 error!(%error, "dropping cross-chain request");
                 break;
             }
@@ -4,19 +4,22 @@
 
 use linera_base::{
     crypto::{AccountPublicKey, AccountSignature, TestString},
-    data_types::{BlobContent, ChainDescription, ChainOrigin, OracleResponse, Round},
+    data_types::{
+        BlobContent, ChainDescription, ChainOrigin, OracleResponse, RetentionPolicy, Round,
+    },
     identifiers::{AccountOwner, BlobType, GenericApplicationId},
     ownership::ChainOwnership,
     vm::VmRuntime,
 };
 use linera_chain::{
-    data_types::MessageAction,
+    data_types::{CertifiedSignature, MessageAction},
     manager::{ChainManagerInfo, LockingBlock},
     types::{Certificate, CertificateKind, ConfirmedBlock, Timeout, ValidatedBlock},
 };
 use linera_core::{data_types::CrossChainRequest, node::NodeError};
 use linera_execution::{
-    system::{AdminOperation, Recipient, SystemMessage, SystemOperation},
+    committee::CertificateSignatureScheme,
+    system::{AdminOperation, NameTarget, Recipient, SystemMessage, SystemOperation},
     Message, MessageKind, Operation,
 };
 use linera_rpc::RpcMessage;
@@ -56,8 +59,10 @@ fn get_registry() -> Result<Registry> {
     tracer.trace_type::<AccountPublicKey>(&samples)?;
     tracer.trace_type::<AccountSignature>(&samples)?;
     tracer.trace_type::<Round>(&samples)?;
+    tracer.trace_type::<RetentionPolicy>(&samples)?;
     tracer.trace_type::<OracleResponse>(&samples)?;
     tracer.trace_type::<Recipient>(&samples)?;
+    tracer.trace_type::<NameTarget>(&samples)?;
     tracer.trace_type::<SystemOperation>(&samples)?;
     tracer.trace_type::<AdminOperation>(&samples)?;
     tracer.trace_type::<SystemMessage>(&samples)?;
@@ -65,8 +70,10 @@ fn get_registry() -> Result<Registry> {
     tracer.trace_type::<Message>(&samples)?;
     tracer.trace_type::<VmRuntime>(&samples)?;
     tracer.trace_type::<MessageAction>(&samples)?;
+    tracer.trace_type::<CertifiedSignature>(&samples)?;
     tracer.trace_type::<MessageKind>(&samples)?;
     tracer.trace_type::<CertificateKind>(&samples)?;
+    tracer.trace_type::<CertificateSignatureScheme>(&samples)?;
     tracer.trace_type::<Certificate>(&samples)?;
     tracer.trace_type::<ConfirmedBlock>(&samples)?;
     tracer.trace_type::<ValidatedBlock>(&samples)?;
@@ -22,6 +22,8 @@ async fn client() {
     let options = Options {
         connect_timeout: Some(Duration::from_millis(100)),
         timeout: Some(Duration::from_millis(100)),
+        #[cfg(not(web))]
+        tls: None,
     };
     let channel = create_channel(address.clone(), &options).unwrap();
     let _ = GrpcClient::new(address, channel, retry_delay, max_retries)
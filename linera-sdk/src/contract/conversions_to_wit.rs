@@ -254,6 +254,9 @@ impl From<WriteOperation> for wit_contract_api::WriteOperation {
             WriteOperation::Put { key, value } => {
                 wit_contract_api::WriteOperation::Put((key, value))
             }
+            WriteOperation::DeleteRange { start, end } => {
+                wit_contract_api::WriteOperation::DeleteRange((start, end))
+            }
         }
     }
 }
@@ -3,6 +3,8 @@
 
 //! Runtime types to interface with the host executing the contract.
 
+use std::ops::Range;
+
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
     data_types::{
@@ -14,7 +16,7 @@ use linera_base::{
         AccountPermissionError, ChainOwnership, ChangeApplicationPermissionsError, CloseChainError,
     },
 };
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
 use super::wit::{base_runtime_api as base_wit, contract_runtime_api as contract_wit};
 use crate::{Contract, DataBlobHash, KeyValueStore, ViewStorageContext};
@@ -135,6 +137,11 @@ where
         base_wit::get_chain_ownership().into()
     }
 
+    /// Returns the configuration value set by the chain owners for the `key`, if any.
+    pub fn config_value(&mut self, key: &str) -> Option<Vec<u8>> {
+        base_wit::read_config_value(key)
+    }
+
     /// Makes an HTTP `request` as an oracle and returns the HTTP response.
     ///
     /// Should only be used with queries where it is very likely that all validators will receive
@@ -279,6 +286,29 @@ where
         bcs::from_bytes(&event).expect("Failed to deserialize event")
     }
 
+    /// Reads the events with the given indices from a stream this application is subscribed to.
+    /// Returns the events' values.
+    ///
+    /// Fails the block if the range goes beyond the events this chain has already processed.
+    pub fn read_subscribed_events<Value: DeserializeOwned>(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        name: StreamName,
+        indices: Range<u32>,
+    ) -> Vec<Value> {
+        contract_wit::read_subscribed_events(
+            chain_id.into(),
+            application_id.into(),
+            &name.into(),
+            indices.start,
+            indices.end,
+        )
+        .iter()
+        .map(|event| bcs::from_bytes(event).expect("Failed to deserialize event"))
+        .collect()
+    }
+
     /// Subscribes this application to an event stream.
     pub fn subscribe_to_events(
         &mut self,
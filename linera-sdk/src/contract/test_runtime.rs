@@ -5,6 +5,7 @@
 
 use std::{
     collections::{BTreeMap, HashMap, VecDeque},
+    ops::Range,
     sync::{Arc, Mutex, MutexGuard},
 };
 
@@ -19,7 +20,7 @@ use linera_base::{
         AccountPermissionError, ChainOwnership, ChangeApplicationPermissionsError, CloseChainError,
     },
 };
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{Contract, DataBlobHash, KeyValueStore, ViewStorageContext};
 
@@ -50,6 +51,7 @@ where
     chain_balance: Option<Amount>,
     owner_balances: Option<HashMap<AccountOwner, Amount>>,
     chain_ownership: Option<ChainOwnership>,
+    config: BTreeMap<String, Vec<u8>>,
     can_close_chain: Option<bool>,
     can_change_application_permissions: Option<bool>,
     call_application_handler: Option<CallApplicationHandler>,
@@ -57,6 +59,7 @@ where
     outgoing_transfers: HashMap<Account, Amount>,
     created_events: BTreeMap<StreamName, Vec<Vec<u8>>>,
     events: BTreeMap<(ChainId, StreamName, u32), Vec<u8>>,
+    subscribed_events: BTreeMap<(ChainId, ApplicationId, StreamName, u32), Vec<u8>>,
     claim_requests: Vec<ClaimRequest>,
     expected_service_queries: VecDeque<(ApplicationId, String, String)>,
     expected_http_requests: VecDeque<(http::Request, http::Response)>,
@@ -97,6 +100,7 @@ where
             chain_balance: None,
             owner_balances: None,
             chain_ownership: None,
+            config: BTreeMap::new(),
             can_close_chain: None,
             can_change_application_permissions: None,
             call_application_handler: None,
@@ -104,6 +108,7 @@ where
             outgoing_transfers: HashMap::new(),
             created_events: BTreeMap::new(),
             events: BTreeMap::new(),
+            subscribed_events: BTreeMap::new(),
             claim_requests: Vec::new(),
             expected_service_queries: VecDeque::new(),
             expected_http_requests: VecDeque::new(),
@@ -572,6 +577,23 @@ where
         )
     }
 
+    /// Configures a configuration value of the chain to return during the test.
+    pub fn with_config_value(mut self, key: impl Into<String>, value: Vec<u8>) -> Self {
+        self.config.insert(key.into(), value);
+        self
+    }
+
+    /// Configures a configuration value of the chain to return during the test.
+    pub fn set_config_value(&mut self, key: impl Into<String>, value: Vec<u8>) -> &mut Self {
+        self.config.insert(key.into(), value);
+        self
+    }
+
+    /// Returns the configuration value set by the chain owners for the `key`, if any.
+    pub fn config_value(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.config.get(key).cloned()
+    }
+
     /// Configures if the application being tested is allowed to close the chain its in.
     pub fn with_can_close_chain(mut self, can_close_chain: bool) -> Self {
         self.can_close_chain = Some(can_close_chain);
@@ -807,6 +829,42 @@ where
         bcs::from_bytes(value).expect("Failed to deserialize event value")
     }
 
+    /// Adds an event to a stream of another application, so that it can be read using
+    /// `read_subscribed_events`.
+    pub fn add_subscribed_event(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        name: StreamName,
+        index: u32,
+        value: &[u8],
+    ) {
+        self.subscribed_events
+            .insert((chain_id, application_id, name, index), value.to_vec());
+    }
+
+    /// Reads the events with the given indices from a stream this application is subscribed to.
+    /// Returns the events' values.
+    ///
+    /// Panics if any of the events doesn't exist.
+    pub fn read_subscribed_events<Value: DeserializeOwned>(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        name: StreamName,
+        indices: Range<u32>,
+    ) -> Vec<Value> {
+        indices
+            .map(|index| {
+                let value = self
+                    .subscribed_events
+                    .get(&(chain_id, application_id, name.clone(), index))
+                    .expect("Event not found");
+                bcs::from_bytes(value).expect("Failed to deserialize event value")
+            })
+            .collect()
+    }
+
     /// Subscribes this application to an event stream.
     pub fn subscribe_to_events(
         &mut self,
@@ -0,0 +1,196 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed GraphQL requests to application services.
+
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// A GraphQL request to an application service, that selects a single field of the query
+/// or mutation root, whose value has the type `T`.
+///
+/// The functions generated by [`codegen`](super::codegen) return these for each field of
+/// the service's roots. Other applications can send the request with
+/// [`ServiceRuntime::query_application`](crate::ServiceRuntime::query_application) or
+/// [`ContractRuntime::query_service`](crate::ContractRuntime::query_service), and frontends
+/// as JSON to the application's endpoint of the node service.
+#[derive(Debug)]
+pub struct ServiceRequest<T> {
+    query: String,
+    variables: Map<String, Value>,
+    field: String,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ServiceRequest<T> {
+    fn clone(&self) -> Self {
+        ServiceRequest {
+            query: self.query.clone(),
+            variables: self.variables.clone(),
+            field: self.field.clone(),
+            response: PhantomData,
+        }
+    }
+}
+
+/// An error when reading the response to a [`ServiceRequest`].
+#[derive(Debug, Error)]
+pub enum GraphQLResponseError {
+    /// The service returned errors.
+    #[error("The service returned errors: {}", .0.join("; "))]
+    Service(Vec<String>),
+    /// The response doesn't contain the requested field.
+    #[error("The response has no field {0:?}")]
+    MissingField(String),
+    /// The field's value doesn't have the expected type.
+    #[error("Failed to deserialize the response: {0}")]
+    Deserialization(#[from] serde_json::Error),
+}
+
+impl<T> ServiceRequest<T> {
+    /// Creates a request with the given query document, whose response is the value of the
+    /// root `field`.
+    pub fn new(query: impl Into<String>, field: impl Into<String>) -> Self {
+        ServiceRequest {
+            query: query.into(),
+            variables: Map::new(),
+            field: field.into(),
+            response: PhantomData,
+        }
+    }
+
+    /// Sets the value of a variable of the query.
+    ///
+    /// # Panics
+    ///
+    /// If the value can't be represented as JSON, e.g. a map with non-string keys.
+    pub fn with_variable(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("GraphQL variables must be valid JSON");
+        self.variables.insert(name.into(), value);
+        self
+    }
+
+    /// Returns the query document.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the values of the query's variables.
+    pub fn variables(&self) -> &Map<String, Value> {
+        &self.variables
+    }
+
+    /// Returns the request to pass to the service.
+    pub fn to_request(&self) -> async_graphql::Request {
+        async_graphql::Request::new(self.query.clone()).variables(
+            async_graphql::Variables::from_json(Value::Object(self.variables.clone())),
+        )
+    }
+
+    /// Returns the request as the JSON body of an HTTP request to the node service.
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "query": self.query,
+            "variables": self.variables,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> ServiceRequest<T> {
+    /// Returns the value of the requested field from the service's response.
+    pub fn parse_response(
+        &self,
+        response: async_graphql::Response,
+    ) -> Result<T, GraphQLResponseError> {
+        if !response.errors.is_empty() {
+            let messages = response
+                .errors
+                .into_iter()
+                .map(|error| error.message)
+                .collect();
+            return Err(GraphQLResponseError::Service(messages));
+        }
+        self.parse_data(response.data.into_json()?)
+    }
+
+    /// Returns the value of the requested field from the JSON body of the node service's
+    /// HTTP response.
+    pub fn parse_json_response(&self, mut response: Value) -> Result<T, GraphQLResponseError> {
+        if let Some(Value::Array(errors)) = response.get("errors") {
+            if !errors.is_empty() {
+                let messages = errors
+                    .iter()
+                    .map(|error| match error.get("message") {
+                        Some(Value::String(message)) => message.clone(),
+                        _ => error.to_string(),
+                    })
+                    .collect();
+                return Err(GraphQLResponseError::Service(messages));
+            }
+        }
+        let data = response.get_mut("data").map(Value::take);
+        self.parse_data(data.unwrap_or_default())
+    }
+
+    fn parse_data(&self, mut data: Value) -> Result<T, GraphQLResponseError> {
+        let value = data
+            .get_mut(&self.field)
+            .ok_or_else(|| GraphQLResponseError::MissingField(self.field.clone()))?
+            .take();
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl<T> From<ServiceRequest<T>> for async_graphql::Request {
+    fn from(request: ServiceRequest<T>) -> Self {
+        request.to_request()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{GraphQLResponseError, ServiceRequest};
+
+    #[test]
+    fn test_service_request() {
+        let request =
+            ServiceRequest::<Option<u64>>::new("query($a: Int!) { value(a: $a) }", "value")
+                .with_variable("a", 3);
+        assert_eq!(
+            request.to_json(),
+            json!({
+                "query": "query($a: Int!) { value(a: $a) }",
+                "variables": { "a": 3 },
+            })
+        );
+        assert_eq!(
+            request
+                .parse_json_response(json!({ "data": { "value": 5 } }))
+                .unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            request
+                .parse_json_response(json!({ "data": { "value": null } }))
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            request.parse_json_response(json!({ "data": null, "errors": [{ "message": "boom" }] })),
+            Err(GraphQLResponseError::Service(messages)) if messages == ["boom"]
+        ));
+        assert!(matches!(
+            request.parse_json_response(json!({ "data": {} })),
+            Err(GraphQLResponseError::MissingField(field)) if field == "value"
+        ));
+
+        let data = async_graphql::Value::from_json(json!({ "value": 7 })).unwrap();
+        let response = async_graphql::Response::new(data);
+        assert_eq!(request.parse_response(response).unwrap(), Some(7));
+    }
+}
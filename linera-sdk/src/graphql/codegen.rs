@@ -0,0 +1,596 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of Rust types and typed requests from the GraphQL schema of a service.
+//!
+//! The generated code contains:
+//!
+//! * a struct for each object and interface type, with an optional field per GraphQL field,
+//!   since a query only selects some of them;
+//! * a struct for each input object type, and an enum for each enum type;
+//! * a type alias for each custom scalar and union type, to [`serde_json::Value`] unless
+//!   another Rust type is given with [`Codegen::with_scalar`];
+//! * a function returning a [`ServiceRequest`](super::ServiceRequest) for each field of the
+//!   query root in a `queries` module, and of the mutation root in a `mutations` module. If
+//!   the field returns objects, the function takes the selection set to request from them.
+//!
+//! The schema of a service is printed by [`async_graphql::Schema::sdl`], e.g. in a test of
+//! the service. The code is typically generated by a build script:
+//!
+//! ```ignore
+//! let schema = std::fs::read_to_string("service.graphql")?;
+//! let code = linera_sdk::graphql::codegen::Codegen::new(&schema)
+//!     .with_scalar("Amount", "linera_sdk::linera_base_types::Amount")
+//!     .generate()?;
+//! let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+//! std::fs::write(out_dir.join("service_client.rs"), code)?;
+//! ```
+//!
+//! and included in a module with
+//! `include!(concat!(env!("OUT_DIR"), "/service_client.rs"));`. The generated types derive
+//! the `serde` traits, so the crate must depend on `serde` with the `derive` feature.
+
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use async_graphql::parser::{
+    parse_schema,
+    types::{
+        BaseType, FieldDefinition, InputValueDefinition, Type, TypeKind, TypeSystemDefinition,
+    },
+    Positioned,
+};
+use thiserror::Error;
+
+/// The Rust keywords that can't be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// An error when generating code from a GraphQL schema.
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    /// The schema couldn't be parsed.
+    #[error("Invalid GraphQL schema: {0}")]
+    Parse(#[from] async_graphql::parser::Error),
+    /// The schema refers to a type that it doesn't define.
+    #[error("Unknown GraphQL type {0:?}")]
+    UnknownType(String),
+}
+
+/// Generates Rust code from the GraphQL schema of a service.
+pub struct Codegen<'a> {
+    schema: &'a str,
+    scalars: BTreeMap<String, String>,
+}
+
+/// The kinds of named GraphQL types.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    BuiltinScalar,
+    Scalar,
+    Object,
+    Union,
+    Enum,
+    InputObject,
+}
+
+impl<'a> Codegen<'a> {
+    /// Creates a generator for the schema, in the GraphQL schema definition language.
+    pub fn new(schema: &'a str) -> Self {
+        Codegen {
+            schema,
+            scalars: BTreeMap::new(),
+        }
+    }
+
+    /// Represents the custom scalar `name` with the Rust type `rust_type`, given by its
+    /// path, instead of [`serde_json::Value`].
+    pub fn with_scalar(mut self, name: impl Into<String>, rust_type: impl Into<String>) -> Self {
+        self.scalars.insert(name.into(), rust_type.into());
+        self
+    }
+
+    /// Returns the generated Rust code.
+    pub fn generate(&self) -> Result<String, CodegenError> {
+        let document = parse_schema(self.schema)?;
+        let mut roots = [
+            ("query", "queries", "Query".to_string()),
+            ("mutation", "mutations", "Mutation".to_string()),
+        ];
+        let mut subscription_root = "Subscription".to_string();
+        let mut types = Vec::new();
+        for definition in document.definitions {
+            match definition {
+                TypeSystemDefinition::Schema(schema) => {
+                    let schema = schema.node;
+                    if let Some(name) = schema.query {
+                        roots[0].2 = name.node.to_string();
+                    }
+                    if let Some(name) = schema.mutation {
+                        roots[1].2 = name.node.to_string();
+                    }
+                    if let Some(name) = schema.subscription {
+                        subscription_root = name.node.to_string();
+                    }
+                }
+                TypeSystemDefinition::Type(definition) => {
+                    if !definition.node.name.node.starts_with("__") {
+                        types.push(definition.node);
+                    }
+                }
+                TypeSystemDefinition::Directive(_) => {}
+            }
+        }
+
+        let mut kinds = ["Int", "Float", "String", "Boolean", "ID"]
+            .into_iter()
+            .map(|name| (name.to_string(), Kind::BuiltinScalar))
+            .collect::<BTreeMap<_, _>>();
+        for definition in &types {
+            let kind = match &definition.kind {
+                TypeKind::Scalar => Kind::Scalar,
+                TypeKind::Object(_) | TypeKind::Interface(_) => Kind::Object,
+                TypeKind::Union(_) => Kind::Union,
+                TypeKind::Enum(_) => Kind::Enum,
+                TypeKind::InputObject(_) => Kind::InputObject,
+            };
+            kinds
+                .entry(definition.name.node.to_string())
+                .or_insert(kind);
+        }
+
+        let mut generator = Generator {
+            kinds,
+            code: String::new(),
+        };
+        generator.line(
+            "// This file is generated by `linera_sdk::graphql::codegen` from the GraphQL schema",
+        );
+        generator.line("// of an application service.");
+        for definition in &types {
+            let name = definition.name.node.as_str();
+            if roots.iter().any(|(_, _, root)| root == name)
+                || name == subscription_root
+                || generator.kinds.get(name) == Some(&Kind::BuiltinScalar)
+            {
+                continue;
+            }
+            generator.line("");
+            generator.description(&definition.description, "");
+            match &definition.kind {
+                TypeKind::Scalar => {
+                    let rust_type = self
+                        .scalars
+                        .get(name)
+                        .map_or("::linera_sdk::serde_json::Value", String::as_str);
+                    generator.line(&format!("pub type {name} = {rust_type};"));
+                }
+                TypeKind::Union(_) => {
+                    generator.line(&format!(
+                        "pub type {name} = ::linera_sdk::serde_json::Value;"
+                    ));
+                }
+                TypeKind::Enum(enum_type) => {
+                    generator.line(
+                        "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, \
+                         ::serde::Serialize, ::serde::Deserialize)]",
+                    );
+                    generator.line(&format!("pub enum {name} {{"));
+                    for value in &enum_type.values {
+                        let value = &value.node;
+                        generator.description(&value.description, "    ");
+                        generator.line(&format!(
+                            "    #[serde(rename = {:?})]",
+                            value.value.node.as_str()
+                        ));
+                        generator.line(&format!("    {},", variant_name(&value.value.node)));
+                    }
+                    generator.line("}");
+                }
+                TypeKind::InputObject(input) => {
+                    generator.line(
+                        "#[derive(Clone, Debug, PartialEq, ::serde::Serialize, \
+                         ::serde::Deserialize)]",
+                    );
+                    generator.line(&format!("pub struct {name} {{"));
+                    for field in &input.fields {
+                        let field = &field.node;
+                        let rust_type = generator.rust_type(&field.ty.node, "")?;
+                        generator.field(
+                            &field.description,
+                            &field.name.node,
+                            &rust_type,
+                            field.ty.node.nullable,
+                        );
+                    }
+                    generator.line("}");
+                }
+                TypeKind::Object(object) => generator.object(name, &object.fields)?,
+                TypeKind::Interface(interface) => generator.object(name, &interface.fields)?,
+            }
+        }
+
+        for (operation, module, root) in &roots {
+            let fields = types.iter().find_map(|definition| {
+                if definition.name.node.as_str() != root {
+                    return None;
+                }
+                match &definition.kind {
+                    TypeKind::Object(object) => Some(&object.fields),
+                    _ => None,
+                }
+            });
+            if let Some(fields) = fields {
+                generator.root(operation, module, fields)?;
+            }
+        }
+        Ok(generator.code)
+    }
+}
+
+struct Generator {
+    kinds: BTreeMap<String, Kind>,
+    code: String,
+}
+
+impl Generator {
+    fn line(&mut self, line: &str) {
+        self.code.push_str(line);
+        self.code.push('\n');
+    }
+
+    fn description(&mut self, description: &Option<Positioned<String>>, indent: &str) {
+        if let Some(description) = description {
+            for line in description.node.lines() {
+                let line = line.trim_end();
+                if line.is_empty() {
+                    self.line(&format!("{indent}///"));
+                } else {
+                    self.line(&format!("{indent}/// {line}"));
+                }
+            }
+        }
+    }
+
+    fn kind(&self, name: &str) -> Result<Kind, CodegenError> {
+        self.kinds
+            .get(name)
+            .copied()
+            .ok_or_else(|| CodegenError::UnknownType(name.to_string()))
+    }
+
+    /// Returns the Rust type of a named GraphQL type, in the module `path`.
+    fn named_type(&self, name: &str, path: &str) -> Result<String, CodegenError> {
+        let kind = self.kind(name)?;
+        Ok(match name {
+            "Int" if kind == Kind::BuiltinScalar => "i32".to_string(),
+            "Float" if kind == Kind::BuiltinScalar => "f64".to_string(),
+            "String" | "ID" if kind == Kind::BuiltinScalar => "String".to_string(),
+            "Boolean" if kind == Kind::BuiltinScalar => "bool".to_string(),
+            _ => format!("{path}{name}"),
+        })
+    }
+
+    /// Returns the Rust type of a GraphQL type, in the module `path`.
+    fn rust_type(&self, ty: &Type, path: &str) -> Result<String, CodegenError> {
+        let rust_type = match &ty.base {
+            BaseType::Named(name) => self.named_type(name, path)?,
+            BaseType::List(item) => format!("Vec<{}>", self.rust_type(item, path)?),
+        };
+        if ty.nullable {
+            Ok(format!("Option<{rust_type}>"))
+        } else {
+            Ok(rust_type)
+        }
+    }
+
+    /// Returns the name of the type inside the lists.
+    fn innermost_name(ty: &Type) -> &str {
+        match &ty.base {
+            BaseType::Named(name) => name.as_str(),
+            BaseType::List(item) => Self::innermost_name(item),
+        }
+    }
+
+    fn field(
+        &mut self,
+        description: &Option<Positioned<String>>,
+        name: &str,
+        rust_type: &str,
+        optional: bool,
+    ) {
+        self.description(description, "    ");
+        let field_name = field_name(name);
+        let mut attributes = Vec::new();
+        if field_name.trim_start_matches("r#") != name {
+            attributes.push(format!("rename = {name:?}"));
+        }
+        if optional {
+            attributes.push("default".to_string());
+            attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+        }
+        if !attributes.is_empty() {
+            self.line(&format!("    #[serde({})]", attributes.join(", ")));
+        }
+        self.line(&format!("    pub {field_name}: {rust_type},"));
+    }
+
+    fn object(
+        &mut self,
+        name: &str,
+        fields: &[Positioned<FieldDefinition>],
+    ) -> Result<(), CodegenError> {
+        self.line(
+            "#[derive(Clone, Debug, Default, PartialEq, ::serde::Serialize, \
+             ::serde::Deserialize)]",
+        );
+        self.line(&format!("pub struct {name} {{"));
+        for field in fields {
+            let field = &field.node;
+            let ty = &field.ty.node;
+            // A query may not select the field, so it is always optional. Objects are boxed,
+            // since they may contain themselves.
+            let rust_type = match &ty.base {
+                BaseType::Named(field_type) if self.kind(field_type)? == Kind::Object => {
+                    format!("Box<{field_type}>")
+                }
+                BaseType::Named(field_type) => self.named_type(field_type, "")?,
+                BaseType::List(item) => format!("Vec<{}>", self.rust_type(item, "")?),
+            };
+            self.field(
+                &field.description,
+                &field.name.node,
+                &format!("Option<{rust_type}>"),
+                true,
+            );
+        }
+        self.line("}");
+        Ok(())
+    }
+
+    /// Generates the module with a request function for each field of a root type.
+    fn root(
+        &mut self,
+        operation: &str,
+        module: &str,
+        fields: &[Positioned<FieldDefinition>],
+    ) -> Result<(), CodegenError> {
+        self.line("");
+        self.line(&format!(
+            "/// The requests for the fields of the service's {operation} root."
+        ));
+        self.line(&format!("pub mod {module} {{"));
+        for (index, field) in fields.iter().enumerate() {
+            let field = &field.node;
+            let name = field.name.node.as_str();
+            let response_type = self.rust_type(&field.ty.node, "super::")?;
+            let needs_selection = matches!(
+                self.kind(Self::innermost_name(&field.ty.node))?,
+                Kind::Object | Kind::Union
+            );
+            let arguments = field
+                .arguments
+                .iter()
+                .map(|argument| &argument.node)
+                .collect::<Vec<&InputValueDefinition>>();
+            let selection = if arguments
+                .iter()
+                .any(|argument| field_name(&argument.name.node) == "selection")
+            {
+                "selection_set"
+            } else {
+                "selection"
+            };
+
+            let mut parameters = Vec::new();
+            for argument in &arguments {
+                let rust_type = self.rust_type(&argument.ty.node, "super::")?;
+                parameters.push(format!("{}: {rust_type}", field_name(&argument.name.node)));
+            }
+            if needs_selection {
+                parameters.push(format!("{selection}: &str"));
+            }
+
+            let mut document = operation.to_string();
+            let mut call = name.to_string();
+            if !arguments.is_empty() {
+                let variables = arguments
+                    .iter()
+                    .map(|argument| format!("${}: {}", argument.name.node, argument.ty.node))
+                    .collect::<Vec<_>>();
+                let values = arguments
+                    .iter()
+                    .map(|argument| format!("{0}: ${0}", argument.name.node))
+                    .collect::<Vec<_>>();
+                write!(document, "({})", variables.join(", ")).unwrap();
+                write!(call, "({})", values.join(", ")).unwrap();
+            }
+
+            if index > 0 {
+                self.line("");
+            }
+            self.description(&field.description, "    ");
+            if needs_selection {
+                if field.description.is_some() {
+                    self.line("    ///");
+                }
+                self.line(&format!(
+                    "    /// `{selection}` is the selection set of the returned objects, \
+                     e.g. `\"id name\"`."
+                ));
+            }
+            self.line(&format!(
+                "    pub fn {}({}) -> ::linera_sdk::graphql::ServiceRequest<{response_type}> {{",
+                field_name(name),
+                parameters.join(", ")
+            ));
+            let document = if needs_selection {
+                let template = format!("{document} {{{{ {call} {{{{ {{}} }}}} }}}}");
+                format!("format!({template:?}, {selection})")
+            } else {
+                format!("{:?}", format!("{document} {{ {call} }}"))
+            };
+            self.line(&format!(
+                "        ::linera_sdk::graphql::ServiceRequest::new({document}, {name:?})"
+            ));
+            for argument in &arguments {
+                self.line(&format!(
+                    "            .with_variable({:?}, {})",
+                    argument.name.node.as_str(),
+                    field_name(&argument.name.node)
+                ));
+            }
+            self.line("    }");
+        }
+        self.line("}");
+        Ok(())
+    }
+}
+
+/// Returns the Rust name of a GraphQL field or argument.
+fn field_name(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake_case = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            let starts_word = previous.is_some_and(|previous| {
+                previous.is_ascii_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_ascii_uppercase()
+                        && next.is_some_and(|next| next.is_ascii_lowercase()))
+            });
+            if starts_word && !snake_case.ends_with('_') {
+                snake_case.push('_');
+            }
+            snake_case.push(c.to_ascii_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+    match snake_case.as_str() {
+        "self" | "super" | "crate" => format!("{snake_case}_"),
+        _ if KEYWORDS.contains(&snake_case.as_str()) => format!("r#{snake_case}"),
+        _ => snake_case,
+    }
+}
+
+/// Returns the Rust name of a GraphQL enum value, in upper camel case.
+fn variant_name(value: &str) -> String {
+    let all_caps = !value.chars().any(|c| c.is_ascii_lowercase());
+    let mut name = String::new();
+    for word in value.split('_').filter(|word| !word.is_empty()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            if all_caps {
+                name.extend(chars.map(|c| c.to_ascii_lowercase()));
+            } else {
+                name.extend(chars);
+            }
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'V');
+    }
+    if name == "Self" {
+        name.push('_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_name, variant_name, Codegen, CodegenError};
+
+    const SCHEMA: &str = r#"
+        """A user of the application."""
+        type Account {
+            owner: AccountOwner!
+            balance: Amount
+            friends: [Account!]!
+            bestFriend: Account
+        }
+
+        enum Status {
+            IN_PROGRESS
+            DONE
+        }
+
+        input TransferInput {
+            recipient: AccountOwner!
+            amount: Amount!
+            memo: String
+        }
+
+        scalar AccountOwner
+        scalar Amount
+
+        type QueryRoot {
+            """The current value."""
+            value: Int!
+            account(owner: AccountOwner!): Account
+            statuses: [Status!]!
+        }
+
+        type MutationRoot {
+            transfer(input: TransferInput!): [Int!]!
+        }
+
+        schema {
+            query: QueryRoot
+            mutation: MutationRoot
+        }
+    "#;
+
+    #[test]
+    fn test_generate() {
+        let code = Codegen::new(SCHEMA)
+            .with_scalar("Amount", "linera_sdk::linera_base_types::Amount")
+            .generate()
+            .unwrap();
+        for expected in [
+            "/// A user of the application.\n",
+            "pub struct Account {\n",
+            "    pub owner: Option<AccountOwner>,\n",
+            "    pub friends: Option<Vec<Account>>,\n",
+            "    #[serde(rename = \"bestFriend\", default, skip_serializing_if = \"Option::is_none\")]\n    pub best_friend: Option<Box<Account>>,\n",
+            "    #[serde(rename = \"IN_PROGRESS\")]\n    InProgress,\n",
+            "pub struct TransferInput {\n    pub recipient: AccountOwner,\n    pub amount: Amount,\n",
+            "    pub memo: Option<String>,\n",
+            "pub type AccountOwner = ::linera_sdk::serde_json::Value;\n",
+            "pub type Amount = linera_sdk::linera_base_types::Amount;\n",
+            "pub mod queries {\n",
+            "    /// The current value.\n    pub fn value() -> ::linera_sdk::graphql::ServiceRequest<i32> {\n        ::linera_sdk::graphql::ServiceRequest::new(\"query { value }\", \"value\")\n",
+            "    pub fn account(owner: super::AccountOwner, selection: &str) -> ::linera_sdk::graphql::ServiceRequest<Option<super::Account>> {\n",
+            "format!(\"query($owner: AccountOwner!) {{ account(owner: $owner) {{ {} }} }}\", selection)",
+            "            .with_variable(\"owner\", owner)\n",
+            "    pub fn statuses() -> ::linera_sdk::graphql::ServiceRequest<Vec<super::Status>> {\n",
+            "pub mod mutations {\n",
+            "ServiceRequest::new(\"mutation($input: TransferInput!) { transfer(input: $input) }\", \"transfer\")\n",
+        ] {
+            assert!(code.contains(expected), "{expected:?} not in:\n{code}");
+        }
+        assert!(!code.contains("pub struct QueryRoot"));
+
+        assert!(matches!(
+            Codegen::new("type QueryRoot { value: Counter }").generate(),
+            Err(CodegenError::UnknownType(name)) if name == "Counter"
+        ));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(field_name("chainId"), "chain_id");
+        assert_eq!(field_name("URLValue"), "url_value");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(variant_name("IN_PROGRESS"), "InProgress");
+        assert_eq!(variant_name("camelCase"), "CamelCase");
+    }
+}
@@ -1,13 +1,19 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! GraphQL traits for generating interfaces into applications.
+//! GraphQL traits for generating interfaces into applications, and typed requests to their
+//! services.
+
+mod client;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod codegen;
 
 use std::sync::Arc;
 
 /// Re-exports the derive macro for [`GraphQLMutationRoot`].
 pub use linera_sdk_derive::GraphQLMutationRoot;
 
+pub use self::client::{GraphQLResponseError, ServiceRequest};
 use crate::{Service, ServiceRuntime};
 
 /// An object associated with a GraphQL mutation root. Those are typically used to build
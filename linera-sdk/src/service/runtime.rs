@@ -130,6 +130,11 @@ where
         })
     }
 
+    /// Returns the configuration value set by the chain owners for the `key`, if any.
+    pub fn config_value(&self, key: &str) -> Option<Vec<u8>> {
+        base_wit::read_config_value(key)
+    }
+
     /// Makes an HTTP request to the given URL as an oracle and returns the answer, if any.
     ///
     /// Should only be used with queries where it is very likely that all validators will receive
@@ -4,7 +4,7 @@
 //! Runtime types to simulate interfacing with the host executing the service.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     mem,
     sync::Mutex,
 };
@@ -31,6 +31,7 @@ where
     timestamp: Mutex<Option<Timestamp>>,
     chain_balance: Mutex<Option<Amount>>,
     owner_balances: Mutex<Option<HashMap<AccountOwner, Amount>>>,
+    config: Mutex<BTreeMap<String, Vec<u8>>>,
     query_application_handler: Mutex<Option<QueryApplicationHandler>>,
     expected_http_requests: Mutex<VecDeque<(http::Request, http::Response)>>,
     blobs: Mutex<Option<HashMap<DataBlobHash, Vec<u8>>>>,
@@ -61,6 +62,7 @@ where
             timestamp: Mutex::new(None),
             chain_balance: Mutex::new(None),
             owner_balances: Mutex::new(None),
+            config: Mutex::new(BTreeMap::new()),
             query_application_handler: Mutex::new(None),
             expected_http_requests: Mutex::new(VecDeque::new()),
             blobs: Mutex::new(None),
@@ -291,6 +293,23 @@ where
             .collect()
     }
 
+    /// Configures a configuration value of the chain to return during the test.
+    pub fn with_config_value(self, key: impl Into<String>, value: Vec<u8>) -> Self {
+        self.set_config_value(key, value);
+        self
+    }
+
+    /// Configures a configuration value of the chain to return during the test.
+    pub fn set_config_value(&self, key: impl Into<String>, value: Vec<u8>) -> &Self {
+        self.config.lock().unwrap().insert(key.into(), value);
+        self
+    }
+
+    /// Returns the configuration value set by the chain owners for the `key`, if any.
+    pub fn config_value(&self, key: &str) -> Option<Vec<u8>> {
+        self.config.lock().unwrap().get(key).cloned()
+    }
+
     /// Schedules an operation to be included in the block being built.
     ///
     /// The operation is specified as an opaque blob of bytes.
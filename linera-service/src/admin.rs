@@ -0,0 +1,439 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An HTTP API for validator operators, to inspect and tune a running server or proxy
+//! without restarting it.
+//!
+//! Every request must present the configured token in an `Authorization: Bearer <token>`
+//! header. The endpoints are:
+//!
+//! * `GET /shards`: the status of the validator's shards.
+//! * `PUT /shards`: hands off the chains to a new list of shards, given as a JSON array of
+//!   shard configurations. Only the proxy supports this.
+//! * `GET /chains/:chain_id`: the state of a chain and of its worker.
+//! * `POST /chains/:chain_id/archive`: moves the state, certificates and blobs of a chain to
+//!   the archive store, if `--chain-archive` is set. The chain must not be loaded: flushing
+//!   the caches unloads it if it is idle. Only the servers running the chain's shard support this.
+//! * `GET /storage`: whether the storage responds, and how fast.
+//! * `POST /caches/flush`: empties the in-memory caches.
+//! * `GET /log-filter` and `PUT /log-filter`: the log filter, in the syntax of `RUST_LOG`.
+//! * `GET /metrics/history`: the recent values of key metrics, if `--metrics-history` is
+//!   set. See the `metrics_history` module.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::{ensure, Context as _};
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Epoch},
+    identifiers::ChainId,
+    time::Instant,
+    tracing::LogFilterError,
+};
+use linera_core::worker::{WorkerError, WorkerStats};
+use linera_rpc::config::{ShardConfig, ShardId};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+#[cfg(with_metrics)]
+use crate::metrics_history::{self, MetricsHistory};
+use crate::{
+    proxy::{HandoffError, HandoffOutcome},
+    util,
+};
+
+/// The configuration of the admin API.
+#[derive(Clone, Debug, clap::Args)]
+pub struct AdminConfig {
+    /// The port of the admin API for operators. The API is disabled if this is not set.
+    #[arg(long)]
+    pub admin_port: Option<u16>,
+
+    /// The address that the admin API listens on.
+    #[arg(long, default_value = "127.0.0.1")]
+    pub admin_host: String,
+
+    /// The token that requests to the admin API must present as a bearer token.
+    #[arg(long, env = "LINERA_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+
+    /// Keeps a history of key metrics in memory, and serves it from the admin API.
+    #[arg(long)]
+    pub metrics_history: bool,
+
+    /// The interval between two samples of the metrics history, in milliseconds.
+    #[arg(
+        long = "metrics-history-interval-ms",
+        default_value = "15000",
+        value_parser = util::parse_millis
+    )]
+    pub metrics_history_interval: Duration,
+
+    /// For how long the samples of the metrics history are kept, in milliseconds.
+    #[arg(
+        long = "metrics-history-retention-ms",
+        default_value = "86400000",
+        value_parser = util::parse_millis
+    )]
+    pub metrics_history_retention: Duration,
+
+    /// A metric family to record in the history, e.g. `linera_num_blocks`. May be given
+    /// several times. By default, the main block, request and worker metrics are recorded.
+    #[arg(long = "metrics-history-metric")]
+    pub metrics_history_metrics: Vec<String>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            admin_port: None,
+            admin_host: "127.0.0.1".to_string(),
+            admin_token: None,
+            metrics_history: false,
+            metrics_history_interval: Duration::from_secs(15),
+            metrics_history_retention: Duration::from_secs(24 * 60 * 60),
+            metrics_history_metrics: Vec::new(),
+        }
+    }
+}
+
+/// The status of one of the validator's shards.
+#[derive(Clone, Debug, Serialize)]
+pub struct ShardStatus {
+    pub shard_id: ShardId,
+    pub host: String,
+    pub port: u16,
+    /// Whether the shard is running in this process, or accepts connections.
+    pub reachable: bool,
+    /// The statistics of the shard's worker, if it is running in this process.
+    pub worker: Option<WorkerStats>,
+}
+
+/// The state of a chain and of its worker.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChainStatus {
+    pub chain_id: ChainId,
+    /// The shard that the chain is assigned to.
+    pub shard_id: ShardId,
+    /// Whether the chain's worker is loaded in memory, if its shard runs in this process.
+    pub loaded: Option<bool>,
+    pub next_block_height: BlockHeight,
+    pub block_hash: Option<CryptoHash>,
+    pub epoch: Option<Epoch>,
+    pub balance: Amount,
+    /// The number of chains that this chain has an inbox for.
+    pub inboxes: usize,
+    /// The number of chains that this chain has an outbox for.
+    pub outboxes: usize,
+}
+
+/// Whether the storage responds, and how fast.
+#[derive(Clone, Debug, Serialize)]
+pub struct StorageHealth {
+    pub healthy: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// What was removed from the caches.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CacheFlush {
+    /// The number of chain workers that were unloaded.
+    pub unloaded_chains: usize,
+    /// The number of cached entries that were dropped.
+    pub cleared_entries: usize,
+}
+
+/// The log filter, in the syntax of `RUST_LOG`.
+#[derive(Clone, Debug, Serialize, serde::Deserialize)]
+pub struct LogFilter {
+    pub filter: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminError {
+    #[error("chain {0} does not exist")]
+    UnknownChain(ChainId),
+
+    #[error("{0}")]
+    Unsupported(&'static str),
+
+    #[error(transparent)]
+    Handoff(#[from] HandoffError),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] ViewError),
+
+    #[error(transparent)]
+    Worker(#[from] WorkerError),
+
+    #[error(transparent)]
+    LogFilter(#[from] LogFilterError),
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            AdminError::UnknownChain(_) => StatusCode::NOT_FOUND,
+            AdminError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+            AdminError::Handoff(HandoffError::InProgress)
+            | AdminError::Worker(
+                WorkerError::ChainLoaded(_) | WorkerError::ChainBeingArchived(_),
+            ) => StatusCode::CONFLICT,
+            AdminError::Handoff(HandoffError::NoShards)
+            | AdminError::LogFilter(LogFilterError::Parse(_)) => StatusCode::BAD_REQUEST,
+            AdminError::Handoff(HandoffError::Shard { .. }) => StatusCode::BAD_GATEWAY,
+            AdminError::Handoff(HandoffError::Storage(_))
+            | AdminError::Storage(_)
+            | AdminError::Worker(_)
+            | AdminError::LogFilter(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// The process that the admin API inspects, i.e. a server or a proxy.
+#[async_trait]
+pub trait AdminBackend: Send + Sync + 'static {
+    /// Returns the status of the validator's shards.
+    async fn shards(&self) -> Vec<ShardStatus>;
+
+    /// Returns the state of a chain and of its worker.
+    async fn chain(&self, chain_id: ChainId) -> Result<ChainStatus, AdminError>;
+
+    /// Moves the state, certificates and blobs of a chain, which must not be loaded, to the
+    /// archive store.
+    async fn archive_chain(&self, chain_id: ChainId) -> Result<(), AdminError>;
+
+    /// Checks that the storage responds.
+    async fn storage_health(&self) -> StorageHealth;
+
+    /// Empties the in-memory caches.
+    async fn flush_caches(&self) -> CacheFlush;
+
+    /// Hands off the chains to a new list of shards.
+    async fn hand_off(&self, shards: Vec<ShardConfig>) -> Result<HandoffOutcome, AdminError>;
+}
+
+/// Starts serving the admin API in the background, if it is enabled.
+pub fn start_admin_api<B: AdminBackend>(
+    config: &AdminConfig,
+    backend: B,
+    shutdown_signal: CancellationToken,
+) -> anyhow::Result<()> {
+    let Some(port) = config.admin_port else {
+        return Ok(());
+    };
+    let token = config
+        .admin_token
+        .clone()
+        .context("the admin API requires a token: set `--admin-token` or `LINERA_ADMIN_TOKEN`")?;
+    ensure!(!token.is_empty(), "the admin token must not be empty");
+    let address: SocketAddr = format!("{}:{}", config.admin_host, port)
+        .parse()
+        .context("invalid admin API address")?;
+    #[cfg(not(with_metrics))]
+    ensure!(
+        !config.metrics_history,
+        "the metrics history requires the `metrics` feature"
+    );
+    info!("Starting to serve the admin API on {address}");
+    #[cfg_attr(not(with_metrics), allow(unused_mut))]
+    let mut router = router(Arc::new(backend));
+    #[cfg(with_metrics)]
+    if config.metrics_history {
+        let history = Arc::new(MetricsHistory::new(
+            config.metrics_history_interval,
+            config.metrics_history_retention,
+            config.metrics_history_metrics.clone(),
+        ));
+        metrics_history::start_sampling(history.clone(), shutdown_signal.clone());
+        router = router.merge(metrics_history::router(history));
+    }
+    let router = router.layer(middleware::from_fn_with_state(
+        Arc::<str>::from(token),
+        check_token,
+    ));
+    tokio::spawn(async move {
+        let listener = tokio::net::TcpListener::bind(address)
+            .await
+            .expect("failed to bind the admin API's address");
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown_signal.cancelled_owned())
+            .await
+        {
+            panic!("Error serving the admin API: {e}");
+        }
+    });
+    Ok(())
+}
+
+fn router<B: AdminBackend>(backend: Arc<B>) -> Router {
+    Router::new()
+        .route("/shards", get(shards::<B>).put(hand_off::<B>))
+        .route("/chains/:chain_id", get(chain::<B>))
+        .route("/chains/:chain_id/archive", post(archive_chain::<B>))
+        .route("/storage", get(storage_health::<B>))
+        .route("/caches/flush", post(flush_caches::<B>))
+        .route("/log-filter", get(log_filter).put(set_log_filter))
+        .with_state(backend)
+}
+
+async fn check_token(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    if !is_authorized(request.headers(), &token) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+/// Returns whether the request's headers contain the bearer token.
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(presented) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    // Compare in constant time, so that the response time doesn't reveal the token.
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+async fn shards<B: AdminBackend>(State(backend): State<Arc<B>>) -> Json<Vec<ShardStatus>> {
+    Json(backend.shards().await)
+}
+
+async fn hand_off<B: AdminBackend>(
+    State(backend): State<Arc<B>>,
+    Json(shards): Json<Vec<ShardConfig>>,
+) -> Result<Json<HandoffOutcome>, AdminError> {
+    Ok(Json(backend.hand_off(shards).await?))
+}
+
+async fn chain<B: AdminBackend>(
+    State(backend): State<Arc<B>>,
+    Path(chain_id): Path<ChainId>,
+) -> Result<Json<ChainStatus>, AdminError> {
+    Ok(Json(backend.chain(chain_id).await?))
+}
+
+async fn archive_chain<B: AdminBackend>(
+    State(backend): State<Arc<B>>,
+    Path(chain_id): Path<ChainId>,
+) -> Result<StatusCode, AdminError> {
+    backend.archive_chain(chain_id).await?;
+    info!("Archived chain {chain_id}");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn storage_health<B: AdminBackend>(State(backend): State<Arc<B>>) -> Response {
+    let health = backend.storage_health().await;
+    let status = if health.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(health)).into_response()
+}
+
+async fn flush_caches<B: AdminBackend>(State(backend): State<Arc<B>>) -> Json<CacheFlush> {
+    let flush = backend.flush_caches().await;
+    info!(
+        "Flushed the caches: unloaded {} chains and dropped {} entries",
+        flush.unloaded_chains, flush.cleared_entries
+    );
+    Json(flush)
+}
+
+async fn log_filter() -> Result<Json<LogFilter>, AdminError> {
+    let filter = linera_base::tracing::log_filter()?;
+    Ok(Json(LogFilter { filter }))
+}
+
+async fn set_log_filter(
+    Json(LogFilter { filter }): Json<LogFilter>,
+) -> Result<Json<LogFilter>, AdminError> {
+    linera_base::tracing::set_log_filter(&filter)?;
+    info!("Changed the log filter to {filter}");
+    Ok(Json(LogFilter { filter }))
+}
+
+/// Reads the state of a chain from storage.
+pub async fn chain_status<S: Storage>(
+    storage: &S,
+    chain_id: ChainId,
+    shard_id: ShardId,
+    loaded: Option<bool>,
+) -> Result<ChainStatus, AdminError> {
+    let chain = storage.load_chain(chain_id).await?;
+    let system = &chain.execution_state.system;
+    if system.description.get().is_none() {
+        return Err(AdminError::UnknownChain(chain_id));
+    }
+    let tip = chain.tip_state.get();
+    Ok(ChainStatus {
+        chain_id,
+        shard_id,
+        loaded,
+        next_block_height: tip.next_block_height,
+        block_hash: tip.block_hash,
+        epoch: *system.epoch.get(),
+        balance: *system.balance.get(),
+        inboxes: chain.inboxes.count().await?,
+        outboxes: chain.outboxes.count().await?,
+    })
+}
+
+/// Checks that the storage responds, by reading the network description.
+pub async fn check_storage<S: Storage>(storage: &S) -> StorageHealth {
+    let start = Instant::now();
+    let result = storage.read_network_description().await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    StorageHealth {
+        healthy: result.is_ok(),
+        latency_ms,
+        error: result.err().map(|error| error.to_string()),
+    }
+}
+
+/// Returns whether a TCP connection to the address can be opened within the timeout.
+pub async fn is_reachable(host: &str, port: u16, timeout: Duration) -> bool {
+    let connect = tokio::net::TcpStream::connect((host, port));
+    matches!(tokio::time::timeout(timeout, connect).await, Ok(Ok(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_authorized(&headers, "secret"));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        assert!(is_authorized(&headers, "secret"));
+        assert!(!is_authorized(&headers, "secret2"));
+        assert!(!is_authorized(&headers, "secreT"));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("secret"));
+        assert!(!is_authorized(&headers, "secret"));
+    }
+}
@@ -24,10 +24,14 @@ use linera_core::node::ValidatorNodeProvider;
 use linera_rpc::config::CrossChainConfig;
 #[cfg(all(feature = "storage-service", with_testing))]
 use linera_storage_service::common::storage_service_test_endpoint;
+#[cfg(all(feature = "redis", with_testing))]
+use linera_views::redis_db::RedisStore;
 #[cfg(all(feature = "rocksdb", feature = "scylladb", with_testing))]
 use linera_views::rocks_db::{RocksDbSpawnMode, RocksDbStore};
 #[cfg(all(feature = "scylladb", with_testing))]
-use linera_views::{scylla_db::ScyllaDbStore, store::TestKeyValueStore as _};
+use linera_views::scylla_db::ScyllaDbStore;
+#[cfg(all(any(feature = "scylladb", feature = "redis"), with_testing))]
+use linera_views::store::TestKeyValueStore as _;
 use tempfile::{tempdir, TempDir};
 use tokio::process::{Child, Command};
 use tonic::transport::{channel::ClientTlsConfig, Endpoint};
@@ -96,6 +100,18 @@ async fn make_testing_config(database: Database) -> Result<StorageConfig> {
             #[cfg(not(feature = "scylladb"))]
             panic!("Database::ScyllaDb is selected without the feature scylladb");
         }
+        Database::Redis => {
+            #[cfg(feature = "redis")]
+            {
+                let config = RedisStore::new_test_config().await?;
+                Ok(StorageConfig::Redis {
+                    uri: config.inner_config.uri,
+                    persistent: false,
+                })
+            }
+            #[cfg(not(feature = "redis"))]
+            panic!("Database::Redis is selected without the feature redis");
+        }
         Database::DualRocksDbScyllaDb => {
             #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
             {
@@ -213,6 +229,7 @@ pub enum Database {
     Service,
     DynamoDb,
     ScyllaDb,
+    Redis,
     DualRocksDbScyllaDb,
 }
 
@@ -0,0 +1,323 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the configuration of a validator's proxy and shards, to find mistakes
+//! before starting them.
+
+use std::{collections::BTreeSet, fmt, path::Path};
+
+use linera_client::config::{GenesisConfig, ValidatorServerConfig};
+use linera_rpc::{
+    config::{NetworkProtocol, ValidatorInternalNetworkConfig},
+    simple::TransportProtocol,
+};
+use serde::Serialize;
+
+use crate::{
+    storage::{NamespaceStatus, StorageConfigNamespace, StoreConfig},
+    util,
+};
+
+/// The outcome of a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    /// The configuration may work, but probably not as intended.
+    Warning,
+    Failed,
+}
+
+/// The result of checking one aspect of the configuration.
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    /// What was checked, e.g. `port 19100`.
+    pub check: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+/// The results of all the checks of a configuration.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ConfigReport {
+    pub checks: Vec<CheckResult>,
+}
+
+/// The part of a validator whose configuration is checked.
+#[derive(Clone, Copy, Debug)]
+pub enum Component {
+    Proxy,
+    /// The given shard, or all the shards if `None`.
+    Shards(Option<usize>),
+}
+
+impl ConfigReport {
+    /// Returns whether any check failed.
+    pub fn has_failures(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|result| result.status == CheckStatus::Failed)
+    }
+
+    fn add(&mut self, check: impl Into<String>, status: CheckStatus, message: impl Into<String>) {
+        self.checks.push(CheckResult {
+            check: check.into(),
+            status,
+            message: message.into(),
+        });
+    }
+
+    /// Checks the server configuration, genesis configuration and storage of the given
+    /// component of a validator.
+    pub async fn check(
+        component: Component,
+        server_config_path: &Path,
+        genesis_config_path: &Path,
+        storage_config: &StorageConfigNamespace,
+    ) -> Self {
+        let mut report = ConfigReport::default();
+        let server_config =
+            report.read_json::<ValidatorServerConfig>("server config", server_config_path);
+        let genesis_config =
+            report.read_json::<GenesisConfig>("genesis config", genesis_config_path);
+        if let Some(server_config) = &server_config {
+            report.check_server_config(component, server_config).await;
+            if let Some(genesis_config) = &genesis_config {
+                report.check_committee(server_config, genesis_config);
+            }
+        }
+        if let Some(genesis_config) = &genesis_config {
+            report.check_storage(storage_config, genesis_config).await;
+        }
+        report
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned>(&mut self, name: &str, path: &Path) -> Option<T> {
+        let check = format!("{name} {}", path.display());
+        match util::read_json(path) {
+            Ok(config) => {
+                self.add(check, CheckStatus::Passed, "valid");
+                Some(config)
+            }
+            Err(error) => {
+                self.add(check, CheckStatus::Failed, format!("{error:#}"));
+                None
+            }
+        }
+    }
+
+    async fn check_server_config(
+        &mut self,
+        component: Component,
+        server_config: &ValidatorServerConfig,
+    ) {
+        let network = &server_config.internal_network;
+        if network.shards.is_empty() {
+            self.add("shards", CheckStatus::Failed, "no shards are configured");
+        }
+        let mut ports = Vec::new();
+        match component {
+            Component::Proxy => {
+                let public_network = &server_config.validator.network;
+                ports.push(("public port", public_network.protocol, public_network.port));
+                ports.push(("internal port", network.protocol, network.port));
+                ports.push(("metrics port", network.protocol, network.metrics_port));
+            }
+            Component::Shards(shard) => {
+                for (shard_id, shard_config) in network.shards.iter().enumerate() {
+                    if shard.is_some_and(|shard| shard != shard_id) {
+                        continue;
+                    }
+                    ports.push(("shard port", network.protocol, shard_config.port));
+                    if let Some(metrics_port) = shard_config.metrics_port {
+                        ports.push(("shard metrics port", network.protocol, metrics_port));
+                    }
+                }
+                if let Some(shard) = shard.filter(|shard| *shard >= network.shards.len()) {
+                    self.add(
+                        format!("shard {shard}"),
+                        CheckStatus::Failed,
+                        format!("only {} shards are configured", network.shards.len()),
+                    );
+                }
+            }
+        }
+        let mut seen_ports = BTreeSet::new();
+        for (name, protocol, port) in ports {
+            let check = format!("{name} {port}");
+            if !seen_ports.insert(port) {
+                self.add(check, CheckStatus::Failed, "used more than once");
+                continue;
+            }
+            match bind(protocol, port).await {
+                Ok(()) => self.add(check, CheckStatus::Passed, "free"),
+                Err(error) => self.add(check, CheckStatus::Failed, error.to_string()),
+            }
+        }
+        for host in hosts(network) {
+            let check = format!("host {host}");
+            // The port is irrelevant for the resolution of the name.
+            let lookup = tokio::net::lookup_host((host.as_str(), 0)).await;
+            match lookup.map(|mut addresses| addresses.next()) {
+                Ok(Some(_)) => self.add(check, CheckStatus::Passed, "resolvable"),
+                Ok(None) => self.add(check, CheckStatus::Failed, "resolves to no address"),
+                Err(error) => self.add(check, CheckStatus::Failed, error.to_string()),
+            }
+        }
+    }
+
+    fn check_committee(
+        &mut self,
+        server_config: &ValidatorServerConfig,
+        genesis_config: &GenesisConfig,
+    ) {
+        let public_key = server_config.validator.public_key;
+        let validator = genesis_config
+            .committee
+            .validators
+            .iter()
+            .find(|validator| validator.public_key == public_key);
+        let check = format!("validator {public_key}");
+        match validator {
+            None => self.add(
+                check,
+                CheckStatus::Warning,
+                "not in the genesis committee; it must be added to the committee later",
+            ),
+            Some(validator) if validator.network != server_config.validator.network => self.add(
+                check,
+                CheckStatus::Warning,
+                "the public network in the genesis committee differs from the server config",
+            ),
+            Some(_) => self.add(check, CheckStatus::Passed, "in the genesis committee"),
+        }
+    }
+
+    async fn check_storage(
+        &mut self,
+        storage_config: &StorageConfigNamespace,
+        genesis_config: &GenesisConfig,
+    ) {
+        let check = format!("storage {storage_config}");
+        let store_config = match storage_config.add_common_config(Default::default()).await {
+            Ok(store_config) => store_config,
+            Err(error) => return self.add(check, CheckStatus::Failed, format!("{error:#}")),
+        };
+        if let StoreConfig::Memory { .. } = store_config {
+            return self.add(
+                check,
+                CheckStatus::Warning,
+                "the memory storage is lost when the process stops",
+            );
+        }
+        match store_config.namespace_status().await {
+            Err(error) => self.add(check, CheckStatus::Failed, format!("{error:#}")),
+            Ok(NamespaceStatus::Missing) => self.add(
+                check,
+                CheckStatus::Failed,
+                "the namespace does not exist; run `linera-server initialize` first",
+            ),
+            Ok(NamespaceStatus::Uninitialized) => self.add(
+                check,
+                CheckStatus::Failed,
+                "the namespace exists but was not initialized with a genesis config",
+            ),
+            Ok(NamespaceStatus::Initialized(description))
+                if description.genesis_config_hash != genesis_config.hash() =>
+            {
+                self.add(
+                    check,
+                    CheckStatus::Failed,
+                    format!(
+                        "the namespace was initialized with another genesis config, for network {:?}",
+                        description.name
+                    ),
+                )
+            }
+            Ok(NamespaceStatus::Initialized(description)) => self.add(
+                check,
+                CheckStatus::Passed,
+                format!("initialized for network {:?}", description.name),
+            ),
+        }
+    }
+}
+
+/// Returns the hosts the components of the validator connect to.
+fn hosts(network: &ValidatorInternalNetworkConfig) -> BTreeSet<String> {
+    network
+        .shards
+        .iter()
+        .map(|shard| shard.host.clone())
+        .chain(Some(network.host.clone()))
+        .chain(
+            network
+                .block_exporters
+                .iter()
+                .map(|exporter| exporter.host.clone()),
+        )
+        .collect()
+}
+
+/// Tries to listen on the given port, as the proxy or a shard would.
+async fn bind(protocol: NetworkProtocol, port: u16) -> std::io::Result<()> {
+    let address = ("0.0.0.0", port);
+    match protocol {
+        NetworkProtocol::Simple(TransportProtocol::Udp) => {
+            tokio::net::UdpSocket::bind(address).await?;
+        }
+        NetworkProtocol::Simple(TransportProtocol::Tcp) | NetworkProtocol::Grpc(_) => {
+            tokio::net::TcpListener::bind(address).await?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.checks {
+            let status = match result.status {
+                CheckStatus::Passed => "OK",
+                CheckStatus::Warning => "WARN",
+                CheckStatus::Failed => "FAIL",
+            };
+            writeln!(f, "[{status:>4}] {}: {}", result.check, result.message)?;
+        }
+        let count = |status| {
+            self.checks
+                .iter()
+                .filter(|result| result.status == status)
+                .count()
+        };
+        write!(
+            f,
+            "{} passed, {} warnings, {} failed",
+            count(CheckStatus::Passed),
+            count(CheckStatus::Warning),
+            count(CheckStatus::Failed)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_rpc::config::{NetworkProtocol, TlsConfig};
+
+    use super::{CheckStatus, ConfigReport};
+
+    #[tokio::test]
+    async fn test_port_in_use_fails() {
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let protocol = NetworkProtocol::Grpc(TlsConfig::ClearText);
+        assert!(super::bind(protocol, port).await.is_err());
+        drop(listener);
+        assert!(super::bind(protocol, port).await.is_ok());
+
+        let mut report = ConfigReport::default();
+        report.add("port", CheckStatus::Warning, "in use");
+        assert!(!report.has_failures());
+        report.add("port", CheckStatus::Failed, "in use");
+        assert!(report.has_failures());
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Kafka and NATS destinations of the block exporter.
+//!
+//! Each block is published in the destination's [`ExportFormat`], keyed by its chain ID and
+//! with its height, hash and content type in the record's headers. A block is only marked
+//! as exported once the destination acknowledged it, so that blocks are delivered at least
+//! once: after a crash, the last blocks may be published again.
+
+use linera_base::{crypto::CryptoHash, identifiers::ChainId};
+use linera_chain::{
+    canonical,
+    types::{ConfirmedBlock, ConfirmedBlockCertificate},
+};
+use linera_client::config::{Destination, DestinationFilter, DestinationKind, ExportFormat};
+use linera_rpc::grpc::api;
+use prost::Message as _;
+
+use super::ExporterError;
+
+/// A destination that the exporter publishes blocks to.
+pub(crate) struct DestinationClient {
+    filter: DestinationFilter,
+    format: ExportFormat,
+    connection: Connection,
+}
+
+enum Connection {
+    #[cfg(feature = "kafka")]
+    Kafka(kafka::KafkaClient),
+    #[cfg(feature = "nats")]
+    Nats(nats::NatsClient),
+}
+
+impl DestinationClient {
+    /// Connects to the destination, or returns `None` if the exporter doesn't publish
+    /// blocks to that kind of destination yet.
+    #[cfg_attr(
+        not(any(feature = "kafka", feature = "nats")),
+        allow(unreachable_code, unused_variables)
+    )]
+    pub async fn connect(destination: &Destination) -> Result<Option<Self>, ExporterError> {
+        let connection = match &destination.kind {
+            DestinationKind::Grpc => return Ok(None),
+            #[cfg(feature = "kafka")]
+            DestinationKind::Kafka { topic, partitions } => Connection::Kafka(
+                kafka::KafkaClient::connect(destination, topic, *partitions).await?,
+            ),
+            #[cfg(feature = "nats")]
+            DestinationKind::Nats { subject_prefix } => {
+                Connection::Nats(nats::NatsClient::connect(destination, subject_prefix).await?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(ExporterError::UnsupportedDestination(
+                    destination.kind.clone(),
+                ))
+            }
+        };
+        Ok(Some(Self {
+            filter: destination.filter.clone(),
+            format: destination.format,
+            connection,
+        }))
+    }
+
+    /// Returns whether blocks of this chain can be published to the destination.
+    pub fn accepts_chain(&self, chain_id: ChainId) -> bool {
+        self.filter.accepts_chain(chain_id)
+    }
+
+    /// Publishes the certificate's block, if the destination's filter accepts it. Returns
+    /// the offset or sequence number that the destination acknowledged it with.
+    pub async fn publish(
+        &self,
+        certificate: ConfirmedBlockCertificate,
+    ) -> Result<Option<u64>, ExporterError> {
+        if !self.filter.accepts_block(certificate.block()) {
+            return Ok(None);
+        }
+        let payload = encode(self.format, &certificate)?;
+        let content_type = self.format.content_type();
+        self.connection
+            .publish(
+                certificate.hash(),
+                certificate.inner(),
+                content_type,
+                payload,
+            )
+            .await
+            .map(Some)
+    }
+}
+
+impl Connection {
+    #[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(unused_variables))]
+    async fn publish(
+        &self,
+        hash: CryptoHash,
+        block: &ConfirmedBlock,
+        content_type: &str,
+        payload: Vec<u8>,
+    ) -> Result<u64, ExporterError> {
+        match *self {
+            #[cfg(feature = "kafka")]
+            Self::Kafka(ref client) => client.publish(hash, block, content_type, payload).await,
+            #[cfg(feature = "nats")]
+            Self::Nats(ref client) => client.publish(hash, block, content_type, payload).await,
+        }
+    }
+}
+
+/// Encodes a certificate's block in the given format.
+fn encode(
+    format: ExportFormat,
+    certificate: &ConfirmedBlockCertificate,
+) -> Result<Vec<u8>, ExporterError> {
+    match format {
+        ExportFormat::Bcs => bcs::to_bytes(certificate.inner()).map_err(ExporterError::destination),
+        ExportFormat::Json => canonical::to_canonical_json(certificate.inner())
+            .map(String::into_bytes)
+            .map_err(ExporterError::destination),
+        ExportFormat::Cbor => {
+            canonical::to_canonical_cbor(certificate.inner()).map_err(ExporterError::destination)
+        }
+        ExportFormat::Protobuf => {
+            let certificate = api::Certificate::try_from(certificate.clone())
+                .map_err(ExporterError::destination)?;
+            Ok(certificate.encode_to_vec())
+        }
+    }
+}
+
+/// Returns the partition of a chain's blocks, among `partitions`.
+#[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+fn partition_of(chain_id: ChainId, partitions: u32) -> u32 {
+    let bytes = chain_id.0.as_bytes();
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % partitions.max(1)
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use std::collections::BTreeMap;
+
+    use linera_base::crypto::CryptoHash;
+    use linera_chain::types::ConfirmedBlock;
+    use linera_client::config::Destination;
+    use rskafka::{
+        client::{
+            partition::{Compression, PartitionClient, UnknownTopicHandling},
+            ClientBuilder,
+        },
+        record::Record,
+    };
+
+    use super::{partition_of, ExporterError};
+
+    pub(crate) struct KafkaClient {
+        partitions: Vec<PartitionClient>,
+    }
+
+    impl KafkaClient {
+        pub async fn connect(
+            destination: &Destination,
+            topic: &str,
+            partitions: u32,
+        ) -> Result<Self, ExporterError> {
+            let broker = format!("{}:{}", destination.endpoint, destination.port);
+            let client = ClientBuilder::new(vec![broker])
+                .build()
+                .await
+                .map_err(ExporterError::destination)?;
+            let mut partition_clients = Vec::new();
+            for partition in 0..partitions.max(1) {
+                let partition = i32::try_from(partition).map_err(ExporterError::destination)?;
+                partition_clients.push(
+                    client
+                        .partition_client(topic, partition, UnknownTopicHandling::Retry)
+                        .await
+                        .map_err(ExporterError::destination)?,
+                );
+            }
+            Ok(Self {
+                partitions: partition_clients,
+            })
+        }
+
+        pub async fn publish(
+            &self,
+            hash: CryptoHash,
+            block: &ConfirmedBlock,
+            content_type: &str,
+            payload: Vec<u8>,
+        ) -> Result<u64, ExporterError> {
+            let chain_id = block.chain_id();
+            let partition = partition_of(chain_id, self.partitions.len() as u32);
+            let record = Record {
+                key: Some(chain_id.to_string().into_bytes()),
+                value: Some(payload),
+                headers: BTreeMap::from([
+                    (
+                        "height".to_string(),
+                        block.height().to_string().into_bytes(),
+                    ),
+                    ("hash".to_string(), hash.to_string().into_bytes()),
+                    ("content-type".to_string(), content_type.as_bytes().to_vec()),
+                ]),
+                timestamp: chrono::Utc::now(),
+            };
+            let offsets = self.partitions[partition as usize]
+                .produce(vec![record], Compression::NoCompression)
+                .await
+                .map_err(ExporterError::destination)?;
+            let offset = offsets.first().copied().unwrap_or_default();
+            u64::try_from(offset).map_err(ExporterError::destination)
+        }
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use async_nats::{jetstream, HeaderMap};
+    use linera_base::crypto::CryptoHash;
+    use linera_chain::types::ConfirmedBlock;
+    use linera_client::config::Destination;
+
+    use super::ExporterError;
+
+    pub(crate) struct NatsClient {
+        jetstream: jetstream::Context,
+        subject_prefix: String,
+    }
+
+    impl NatsClient {
+        pub async fn connect(
+            destination: &Destination,
+            subject_prefix: &str,
+        ) -> Result<Self, ExporterError> {
+            let url = format!("nats://{}:{}", destination.endpoint, destination.port);
+            let client = async_nats::connect(url)
+                .await
+                .map_err(ExporterError::destination)?;
+            Ok(Self {
+                jetstream: jetstream::new(client),
+                subject_prefix: subject_prefix.to_string(),
+            })
+        }
+
+        pub async fn publish(
+            &self,
+            hash: CryptoHash,
+            block: &ConfirmedBlock,
+            content_type: &str,
+            payload: Vec<u8>,
+        ) -> Result<u64, ExporterError> {
+            let subject = format!("{}.{}", self.subject_prefix, block.chain_id());
+            let mut headers = HeaderMap::new();
+            // JetStream drops the blocks that are published again after a crash.
+            headers.insert("Nats-Msg-Id", hash.to_string().as_str());
+            headers.insert("Linera-Block-Height", block.height().to_string().as_str());
+            headers.insert("Content-Type", content_type);
+            let ack = self
+                .jetstream
+                .publish_with_headers(subject, headers, payload.into())
+                .await
+                .map_err(ExporterError::destination)?
+                .await
+                .map_err(ExporterError::destination)?;
+            Ok(ack.sequence)
+        }
+    }
+}
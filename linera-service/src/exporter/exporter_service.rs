@@ -1,13 +1,14 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use async_trait::async_trait;
+use futures::FutureExt as _;
 use linera_base::{
     crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId, listen_for_shutdown_signals,
 };
-use linera_client::config::DestinationConfig;
+use linera_client::config::{DestinationConfig, DestinationId};
 use linera_core::worker::Reason;
 use linera_rpc::{
     config::ExporterServiceConfig,
@@ -16,20 +17,22 @@ use linera_rpc::{
         Notification,
     },
 };
-use linera_sdk::views::{RootView, View};
-use linera_service::storage::Runnable;
 use linera_storage::Storage;
-#[cfg(test)]
-use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::Mutex;
+use linera_views::views::{RootView, View, ViewError};
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Mutex,
+};
 use tokio_util::sync::CancellationToken;
 use tonic::{transport::Server, Request, Response, Status};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::{state::BlockExporterStateView, ExporterError};
+use super::{destinations::DestinationClient, state::BlockExporterStateView, ExporterError};
+use crate::storage::Runnable;
 
+/// The configuration of a block exporter.
 #[derive(Debug)]
-pub(super) struct ExporterContext {
+pub struct ExporterContext {
     id: u32,
     service_config: ExporterServiceConfig,
     destination_config: DestinationConfig,
@@ -39,9 +42,12 @@ pub(crate) struct ExporterService<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
-    state: Mutex<BlockExporterStateView<S::BlockExporterContext>>,
+    state: Arc<Mutex<BlockExporterStateView<S::BlockExporterContext>>>,
     destination_config: DestinationConfig,
     storage: S,
+    /// The chains with new blocks, for the task publishing them to the destinations.
+    new_blocks: UnboundedSender<ChainId>,
+    new_blocks_receiver: Option<UnboundedReceiver<ChainId>>,
     #[cfg(test)]
     debug_destination: Option<UnboundedSender<Summary>>,
 }
@@ -67,6 +73,8 @@ where
                 .await
                 .map_err(|e| Status::from_error(e.into()))?;
         }
+        // The receiver is only dropped if the exporter is shutting down.
+        let _ = self.new_blocks.send(chain_id);
 
         // after implementation of future destinations
         // this will be offloaded to a seperate thread.
@@ -113,21 +121,40 @@ where
             .map_err(ExporterError::StateError)?;
         let state = BlockExporterStateView::load(storage_context).await?;
         let destination_config = exporter_context.destination_config.clone();
+        let (new_blocks, new_blocks_receiver) = mpsc::unbounded_channel();
         Ok(Self {
-            state: Mutex::new(state),
+            state: Arc::new(Mutex::new(state)),
             destination_config,
             storage,
+            new_blocks,
+            new_blocks_receiver: Some(new_blocks_receiver),
             #[cfg(test)]
             debug_destination: None,
         })
     }
 
     pub async fn run(
-        self,
+        mut self,
         cancellation_token: CancellationToken,
         port: u16,
     ) -> core::result::Result<(), ExporterError> {
         info!("Linera exporter is running.");
+        let mut clients = Vec::new();
+        for (id, destination) in (0..).zip(&self.destination_config.destinations) {
+            if let Some(client) = DestinationClient::connect(destination).await? {
+                clients.push((id, client));
+            }
+        }
+        if let Some(new_blocks) = self.new_blocks_receiver.take() {
+            if !clients.is_empty() {
+                let publisher = BlockPublisher {
+                    storage: self.storage.clone(),
+                    state: self.state.clone(),
+                    clients,
+                };
+                tokio::spawn(publisher.run(new_blocks, cancellation_token.clone()));
+            }
+        }
         self.start_notification_server(port, cancellation_token)
             .await
     }
@@ -139,6 +166,99 @@ where
     }
 }
 
+/// Publishes the blocks that the exporter is notified about to the Kafka and NATS
+/// destinations that accept them, in order, recording the progress of each destination in the exporter's
+/// state.
+struct BlockPublisher<S>
+where
+    S: Storage,
+{
+    storage: S,
+    state: Arc<Mutex<BlockExporterStateView<S::BlockExporterContext>>>,
+    clients: Vec<(DestinationId, DestinationClient)>,
+}
+
+impl<S> BlockPublisher<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    async fn run(
+        self,
+        mut new_blocks: UnboundedReceiver<ChainId>,
+        cancellation_token: CancellationToken,
+    ) {
+        // Catch up with the blocks the destinations didn't acknowledge before a restart.
+        match self.state.lock().await.chain_ids().await {
+            Ok(chain_ids) => {
+                for chain_id in chain_ids {
+                    self.publish_chain(chain_id).await;
+                }
+            }
+            Err(error) => warn!(%error, "Failed to read the chains of the exporter state"),
+        }
+        loop {
+            let chain_id = futures::select! {
+                () = cancellation_token.cancelled().fuse() => return,
+                chain_id = new_blocks.recv().fuse() => match chain_id {
+                    Some(chain_id) => chain_id,
+                    None => return,
+                },
+            };
+            self.publish_chain(chain_id).await;
+        }
+    }
+
+    /// Publishes the chain's blocks that each destination didn't acknowledge yet. A
+    /// destination that fails is retried on the chain's next notification.
+    async fn publish_chain(&self, chain_id: ChainId) {
+        for (destination, client) in &self.clients {
+            if let Err(error) = self.publish_to(chain_id, *destination, client).await {
+                warn!(
+                    %chain_id, %destination, %error,
+                    "Failed to publish blocks to the destination"
+                );
+            }
+        }
+    }
+
+    async fn publish_to(
+        &self,
+        chain_id: ChainId,
+        destination: DestinationId,
+        client: &DestinationClient,
+    ) -> Result<(), ExporterError> {
+        if !client.accepts_chain(chain_id) {
+            return Ok(());
+        }
+        let Some((next_height, (tip_height, _))) = self
+            .state
+            .lock()
+            .await
+            .pending_blocks(&chain_id, destination)
+            .await?
+        else {
+            return Ok(());
+        };
+        let start = usize::try_from(next_height).map_err(ViewError::ArithmeticError)?;
+        let end = usize::try_from(tip_height).map_err(ViewError::ArithmeticError)?;
+        let hashes = self
+            .storage
+            .load_chain(chain_id)
+            .await?
+            .confirmed_log
+            .read(start..=end)
+            .await?;
+        for hash in hashes {
+            let certificate = self.storage.read_certificate(hash).await?;
+            let offset = client.publish(certificate).await?;
+            let mut state = self.state.lock().await;
+            state.commit_block(&chain_id, destination, offset).await?;
+            state.save().await?;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Runnable for ExporterContext {
     type Output = Result<(), ExporterError>;
@@ -149,14 +269,12 @@ impl Runnable for ExporterContext {
     {
         let shutdown_notifier = CancellationToken::new();
         tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
-        let port = self.service_config.port;
-        let service = ExporterService::from_context(&self, storage).await?;
-        service.run(shutdown_notifier, port).await
+        self.run_until(storage, shutdown_notifier).await
     }
 }
 
 impl ExporterContext {
-    pub(super) fn new(
+    pub fn new(
         id: u32,
         service_config: ExporterServiceConfig,
         destination_config: DestinationConfig,
@@ -167,6 +285,20 @@ impl ExporterContext {
             destination_config,
         }
     }
+
+    /// Runs the exporter until `shutdown_signal` is cancelled.
+    pub async fn run_until<S>(
+        self,
+        storage: S,
+        shutdown_signal: CancellationToken,
+    ) -> Result<(), ExporterError>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let port = self.service_config.port;
+        let service = ExporterService::from_context(&self, storage).await?;
+        service.run(shutdown_signal, port).await
+    }
 }
 
 fn parse_notification(
@@ -177,7 +309,7 @@ fn parse_notification(
         .ok_or(ExporterError::BadNotification)?;
     let reason = bincode::deserialize::<Reason>(&notification.reason)
         .map_err(|_| ExporterError::BadNotification)?;
-    if let Reason::NewBlock { height, hash } = reason {
+    if let Reason::NewBlock { height, hash, .. } = reason {
         return Ok((
             chain_id
                 .try_into()
@@ -240,7 +372,7 @@ pub struct Summary {
     byte_size: usize,
 }
 
-#[cfg(with_testing)]
+#[cfg(test)]
 impl Summary {
     pub fn new(
         chain_id: ChainId,
@@ -259,11 +391,13 @@ impl Summary {
 
 #[cfg(test)]
 mod test {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
 
+    use crate::cli_wrappers::local_net::LocalNet;
     use linera_base::{
         crypto::CryptoHash,
-        data_types::{Amount, Round},
+        data_types::{Amount, Event, Round},
+        identifiers::{ApplicationId, StreamId, StreamName},
         port::get_free_port,
     };
     use linera_chain::{
@@ -271,8 +405,8 @@ mod test {
         test::{make_first_block, BlockTestExt},
         types::{ConfirmedBlock, ConfirmedBlockCertificate},
     };
+    use linera_client::config::{DestinationFilter, DestinationKind, ExportFormat};
     use linera_rpc::grpc::api::notifier_service_client::NotifierServiceClient;
-    use linera_service::cli_wrappers::local_net::LocalNet;
     use linera_storage::DbStorage;
     use linera_views::memory::MemoryStore;
 
@@ -327,6 +461,8 @@ mod test {
         let reason = Reason::NewBlock {
             height: certificate.inner().height(),
             hash: certificate.hash(),
+            event_streams: BTreeSet::new(),
+            application_ids: BTreeSet::new(),
         };
         let request = tonic::Request::new(Notification {
             chain_id: Some(certificate.inner().chain_id().into()),
@@ -352,4 +488,95 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_destination_progress() -> anyhow::Result<()> {
+        let storage = DbStorage::<MemoryStore, _>::make_test_storage(None).await;
+        let context = storage.block_exporter_context(0).await?;
+        let mut state = BlockExporterStateView::load(context).await?;
+        let chain_id = ChainId(CryptoHash::test_hash("root1"));
+        assert_eq!(state.pending_blocks(&chain_id, 0).await?, None);
+
+        let tip = (BlockHeight::from(1), CryptoHash::test_hash("block1"));
+        state.initialize_chain(&chain_id, tip).await?;
+        assert_eq!(
+            state.pending_blocks(&chain_id, 0).await?,
+            Some((BlockHeight::ZERO, tip))
+        );
+        state.commit_block(&chain_id, 0, Some(8)).await?;
+        state.commit_block(&chain_id, 0, None).await?;
+        assert_eq!(state.pending_blocks(&chain_id, 0).await?, None);
+        assert_eq!(
+            state.pending_blocks(&chain_id, 1).await?,
+            Some((BlockHeight::ZERO, tip))
+        );
+        state.save().await?;
+
+        // The progress of each destination survives a restart.
+        let context = storage.block_exporter_context(0).await?;
+        let state = BlockExporterStateView::load(context).await?;
+        assert_eq!(state.chain_ids().await?, vec![chain_id]);
+        assert_eq!(state.committed_offset(&chain_id, 0).await?, Some(8));
+        assert_eq!(state.committed_offset(&chain_id, 1).await?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_destination_filter() -> anyhow::Result<()> {
+        let application_id = ApplicationId::new(CryptoHash::test_hash("app"));
+        let config: DestinationConfig = toml::from_str(&format!(
+            r#"
+            [[destinations]]
+            endpoint = "localhost"
+            port = 4222
+            kind = {{ type = "nats", subject_prefix = "linera" }}
+            format = "json"
+            filter = {{ event_applications = ["{application_id}"] }}
+            "#
+        ))?;
+        let [destination] = &config.destinations[..] else {
+            panic!("Expected a single destination");
+        };
+        assert_eq!(destination.format, ExportFormat::Json);
+        assert_eq!(
+            destination.kind,
+            DestinationKind::Nats {
+                subject_prefix: "linera".to_string()
+            }
+        );
+
+        let chain_id = ChainId(CryptoHash::test_hash("root1"));
+        let event = Event {
+            stream_id: StreamId {
+                application_id: application_id.into(),
+                stream_name: StreamName(b"stream".to_vec()),
+            },
+            index: 0,
+            value: Vec::new(),
+        };
+        let make_block = |events| {
+            BlockExecutionOutcome {
+                messages: vec![Vec::new()],
+                previous_message_blocks: BTreeMap::new(),
+                state_hash: CryptoHash::test_hash("state"),
+                oracle_responses: vec![Vec::new()],
+                events: vec![events],
+                blobs: vec![Vec::new()],
+                operation_results: vec![OperationResult::default()],
+            }
+            .with(make_first_block(chain_id).with_simple_transfer(chain_id, Amount::ONE))
+        };
+        let filter = &destination.filter;
+        assert!(filter.accepts_chain(chain_id));
+        assert!(filter.accepts_block(&make_block(vec![event])));
+        assert!(!filter.accepts_block(&make_block(Vec::new())));
+
+        let filter = DestinationFilter {
+            chain_ids: vec![ChainId(CryptoHash::test_hash("root2"))],
+            event_applications: Vec::new(),
+        };
+        assert!(!filter.accepts_chain(chain_id));
+        assert!(!filter.accepts_block(&make_block(Vec::new())));
+        Ok(())
+    }
 }
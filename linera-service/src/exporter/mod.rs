@@ -0,0 +1,40 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The block exporter of a validator, which receives the shards' notifications of new blocks.
+
+use linera_client::config::DestinationKind;
+use linera_views::views::ViewError;
+
+mod destinations;
+mod exporter_service;
+mod state;
+
+pub use exporter_service::ExporterContext;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExporterError {
+    #[error("received an invalid notification.")]
+    BadNotification,
+
+    #[error("unable to load the exporter state")]
+    StateError(ViewError),
+
+    #[error("generic storage error: {0}")]
+    StorageError(#[from] ViewError),
+
+    #[error("generic error: {0}")]
+    GenericError(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("the exporter was built without support for destinations of kind {0:?}")]
+    UnsupportedDestination(DestinationKind),
+
+    #[error("destination error: {0}")]
+    DestinationError(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl ExporterError {
+    fn destination(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        ExporterError::DestinationError(Box::new(error))
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_client::config::DestinationId;
+use linera_views::{
+    context::Context,
+    map_view::MapView,
+    reentrant_collection_view::ReentrantCollectionView,
+    register_view::RegisterView,
+    views::{ClonableView, RootView, View, ViewError},
+};
+
+use super::ExporterError;
+
+/// State of the linera exporter as a view.
+#[derive(Debug, RootView, ClonableView)]
+pub struct BlockExporterStateView<C> {
+    /// The chain status, by chain ID.
+    state: ReentrantCollectionView<C, ChainId, ChainStatusView<C>>,
+}
+
+/// State of the linera exporter for a particular chain as a view.
+#[derive(Debug, View, ClonableView)]
+struct ChainStatusView<C> {
+    /// Tracks the highest block known to be in storage with its hash.
+    /// Hash is needed to retrieve the blocks themselves as a block is stored
+    /// in the shared database by its hash as the key.
+    known_height: RegisterView<C, Option<(BlockHeight, CryptoHash)>>,
+    /// Tracks the highest block already processed (plus one)
+    /// for every destination.
+    next_heights_to_process: MapView<C, DestinationId, BlockHeight>,
+    /// The offset or sequence number that each destination acknowledged the last
+    /// published block with.
+    committed_offsets: MapView<C, DestinationId, u64>,
+}
+
+impl<C> ChainStatusView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+{
+    // Update with the latest [`BlockHeight`] rather than incrementing by one.
+    // As in cases notifications are lost, exporter is lagging behind, crashes etc.
+    pub fn update_block_height(&mut self, height: BlockHeight, hash: CryptoHash) {
+        self.known_height.set(Some((height, hash)));
+    }
+
+    pub async fn increment_destination_height(
+        &mut self,
+        destination: DestinationId,
+    ) -> Result<(), ExporterError> {
+        let height = self
+            .next_heights_to_process
+            .get_mut_or_default(&destination)
+            .await?;
+        *height = height.try_add_one().map_err(ViewError::ArithmeticError)?;
+        Ok(())
+    }
+
+    /// Records that the next block to process was acknowledged by the destination at
+    /// `offset`, or filtered out if `offset` is `None`.
+    pub async fn commit_block(
+        &mut self,
+        destination: DestinationId,
+        offset: Option<u64>,
+    ) -> Result<(), ExporterError> {
+        self.increment_destination_height(destination).await?;
+        if let Some(offset) = offset {
+            self.committed_offsets.insert(&destination, offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C> BlockExporterStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+{
+    pub async fn initialize_chain(
+        &mut self,
+        chain_id: &ChainId,
+        (height, hash): (BlockHeight, CryptoHash),
+    ) -> Result<(), ExporterError> {
+        let mut guard = self.state.try_load_entry_mut(chain_id).await?;
+        guard.update_block_height(height, hash);
+        Ok(())
+    }
+
+    /// Returns the IDs of the chains that the exporter was notified about.
+    pub async fn chain_ids(&self) -> Result<Vec<ChainId>, ExporterError> {
+        Ok(self.state.indices().await?)
+    }
+
+    /// Returns the next height to export to the destination, and the height and hash of
+    /// the chain's highest block in storage, if there are blocks left to export.
+    pub async fn pending_blocks(
+        &self,
+        chain_id: &ChainId,
+        destination: DestinationId,
+    ) -> Result<Option<(BlockHeight, (BlockHeight, CryptoHash))>, ExporterError> {
+        let Some(guard) = self.state.try_load_entry(chain_id).await? else {
+            return Ok(None);
+        };
+        let Some(tip) = *guard.known_height.get() else {
+            return Ok(None);
+        };
+        let next_height = guard
+            .next_heights_to_process
+            .get(&destination)
+            .await?
+            .unwrap_or_default();
+        Ok((next_height <= tip.0).then_some((next_height, tip)))
+    }
+
+    /// Records that the chain's next block was acknowledged by the destination at
+    /// `offset`, or filtered out if `offset` is `None`.
+    pub async fn commit_block(
+        &mut self,
+        chain_id: &ChainId,
+        destination: DestinationId,
+        offset: Option<u64>,
+    ) -> Result<(), ExporterError> {
+        let mut guard = self.state.try_load_entry_mut(chain_id).await?;
+        guard.commit_block(destination, offset).await
+    }
+
+    /// Returns the offset or sequence number of the last block of the chain that the
+    /// destination acknowledged.
+    #[cfg(test)]
+    pub async fn committed_offset(
+        &self,
+        chain_id: &ChainId,
+        destination: DestinationId,
+    ) -> Result<Option<u64>, ExporterError> {
+        let Some(guard) = self.state.try_load_entry(chain_id).await? else {
+            return Ok(None);
+        };
+        Ok(guard.committed_offsets.get(&destination).await?)
+    }
+}
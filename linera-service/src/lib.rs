@@ -6,11 +6,20 @@
 
 #![deny(clippy::large_futures)]
 
+pub mod admin;
 pub mod cli_wrappers;
+pub mod config_check;
+pub mod exporter;
+#[cfg(with_metrics)]
+pub mod metrics_history;
 pub mod node_service;
 pub mod project;
 #[cfg(with_metrics)]
 pub mod prometheus_server;
+pub mod proxy;
+#[cfg(feature = "sql-indexer")]
+pub mod sql_indexer;
 pub mod storage;
 pub mod util;
 pub mod wallet;
+pub mod worker_benchmark;
@@ -4,33 +4,12 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use exporter_service::ExporterContext;
 use futures::FutureExt;
 use linera_client::config::{BlockExporterConfig, GenesisConfig};
-use linera_sdk::views::ViewError;
-use linera_service::{storage::StorageConfigNamespace, util};
+use linera_execution::ExecutionRuntimeConfig;
+use linera_service::{exporter::ExporterContext, storage::StorageConfigNamespace, util};
 use linera_views::{lru_caching::StorageCacheConfig, store::CommonStoreConfig};
 
-#[allow(dead_code)]
-mod exporter_service;
-#[allow(dead_code)]
-mod state;
-
-#[derive(thiserror::Error, Debug)]
-pub(crate) enum ExporterError {
-    #[error("received an invalid notification.")]
-    BadNotification,
-
-    #[error("unable to load the exporter state")]
-    StateError(ViewError),
-
-    #[error("generic storage error: {0}")]
-    StorageError(#[from] ViewError),
-
-    #[error("generic error: {0}")]
-    GenericError(Box<dyn std::error::Error + Send + Sync + 'static>),
-}
-
 /// Options for running the linera block exporter.
 #[derive(clap::Parser, Debug, Clone)]
 #[command(
@@ -69,6 +48,11 @@ struct ExporterOptions {
     #[arg(long, default_value = "1000")]
     max_cache_entries: usize,
 
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    negative_cache_ttl_ms: u64,
+
     /// Path to the file describing the initial user chains (aka genesis state)
     #[arg(long = "genesis")]
     genesis_config_path: PathBuf,
@@ -91,6 +75,7 @@ impl ExporterOptions {
             max_cache_size: self.max_cache_size,
             max_entry_size: self.max_entry_size,
             max_cache_entries: self.max_cache_entries,
+            negative_cache_ttl_ms: self.negative_cache_ttl_ms,
         };
         let common_config = CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -118,7 +103,13 @@ impl ExporterOptions {
                 .await
                 .unwrap();
             storage_config
-                .run_with_storage(&genesis_config, None, context)
+                .run_with_storage(
+                    &genesis_config,
+                    None,
+                    ExecutionRuntimeConfig::default(),
+                    None,
+                    context,
+                )
                 .boxed()
                 .await
         };
@@ -0,0 +1,132 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use futures::FutureExt;
+use linera_base::identifiers::ChainId;
+use linera_client::config::GenesisConfig;
+use linera_execution::ExecutionRuntimeConfig;
+use linera_service::{sql_indexer::IndexerContext, storage::StorageConfigNamespace, util};
+use linera_views::{lru_caching::StorageCacheConfig, store::CommonStoreConfig};
+
+/// Options for running the Linera SQL indexer.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(
+    name = "Linera SQL Indexer",
+    version = linera_version::VersionInfo::default_clap_str(),
+)]
+struct IndexerOptions {
+    /// The URL of the PostgreSQL or SQLite database to write to, e.g.
+    /// `postgres://user@localhost/linera` or `sqlite://indexer.db?mode=rwc`.
+    #[arg(long)]
+    database_url: String,
+
+    /// A chain to index, in addition to the admin chain. Can be repeated. The chains that
+    /// the indexed blocks send messages to are indexed too.
+    #[arg(long = "chain-id")]
+    chain_ids: Vec<ChainId>,
+
+    /// How often to look for new blocks in storage, in milliseconds.
+    #[arg(long, default_value = "1000")]
+    poll_interval_ms: u64,
+
+    /// Storage configuration for the blockchain history, chain states and binary blobs.
+    #[arg(long = "storage")]
+    storage_config: StorageConfigNamespace,
+
+    /// The number of Tokio worker threads to use.
+    #[arg(long)]
+    tokio_threads: Option<usize>,
+
+    /// The maximal number of simultaneous queries to the database
+    #[arg(long)]
+    max_concurrent_queries: Option<usize>,
+
+    /// The maximal number of stream queries to the database
+    #[arg(long, default_value = "10")]
+    max_stream_queries: usize,
+
+    /// The maximal memory used in the storage cache.
+    #[arg(long, default_value = "10000000")]
+    max_cache_size: usize,
+
+    /// The maximal size of an entry in the storage cache.
+    #[arg(long, default_value = "1000000")]
+    max_entry_size: usize,
+
+    /// The maximal number of entries in the storage cache.
+    #[arg(long, default_value = "1000")]
+    max_cache_entries: usize,
+
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    negative_cache_ttl_ms: u64,
+
+    /// Path to the file describing the initial user chains (aka genesis state)
+    #[arg(long = "genesis")]
+    genesis_config_path: PathBuf,
+}
+
+fn main() -> Result<()> {
+    linera_base::tracing::init("linera-sql-indexer");
+    let options = <IndexerOptions as clap::Parser>::parse();
+    options.run()
+}
+
+impl IndexerOptions {
+    fn run(&self) -> anyhow::Result<()> {
+        let storage_cache_config = StorageCacheConfig {
+            max_cache_size: self.max_cache_size,
+            max_entry_size: self.max_entry_size,
+            max_cache_entries: self.max_cache_entries,
+            negative_cache_ttl_ms: self.negative_cache_ttl_ms,
+        };
+        let common_config = CommonStoreConfig {
+            max_concurrent_queries: self.max_concurrent_queries,
+            max_stream_queries: self.max_stream_queries,
+            storage_cache_config,
+        };
+
+        let mut runtime_builder = match self.tokio_threads {
+            None | Some(1) => tokio::runtime::Builder::new_current_thread(),
+            Some(worker_threads) => {
+                let mut builder = tokio::runtime::Builder::new_multi_thread();
+                builder.worker_threads(worker_threads);
+                builder
+            }
+        };
+
+        let genesis_config: GenesisConfig = util::read_json(&self.genesis_config_path)?;
+        let mut chain_ids = vec![genesis_config.admin_id];
+        chain_ids.extend(&self.chain_ids);
+
+        let future = async {
+            let context = IndexerContext::new(
+                self.database_url.clone(),
+                chain_ids,
+                Duration::from_millis(self.poll_interval_ms),
+            );
+            let storage_config = self
+                .storage_config
+                .add_common_config(common_config)
+                .await
+                .unwrap();
+            storage_config
+                .run_with_storage(
+                    &genesis_config,
+                    None,
+                    ExecutionRuntimeConfig::default(),
+                    None,
+                    context,
+                )
+                .boxed()
+                .await
+        };
+
+        let runtime = runtime_builder.enable_all().build()?;
+        runtime.block_on(future)?.map_err(|e| e.into())
+    }
+}
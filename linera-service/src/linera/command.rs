@@ -5,40 +5,89 @@ use std::{borrow::Cow, num::NonZeroU16, path::PathBuf};
 
 use chrono::{DateTime, Utc};
 use linera_base::{
-    crypto::{AccountPublicKey, CryptoHash, ValidatorPublicKey},
+    crypto::{AccountPublicKey, BlsValidatorKey, CryptoHash, ValidatorPublicKey},
     data_types::Amount,
     identifiers::{Account, AccountOwner, ApplicationId, ChainId, ModuleId},
     time::Duration,
     vm::VmRuntime,
 };
 use linera_client::{
+    address_book::{AccountOrAlias, ChainOrAlias},
     chain_listener::ChainListenerConfig,
     client_options::{
         ApplicationPermissionsConfig, ChainOwnershipConfig, ResourceControlPolicyConfig,
     },
     util,
 };
+use linera_execution::{committee::CertificateSignatureScheme, system::NameTarget};
+use linera_faucet_server::ClaimLimitsConfig;
 use linera_rpc::config::CrossChainConfig;
 #[cfg(feature = "kubernetes")]
 use linera_service::cli_wrappers::local_kubernetes_net::BuildMode;
-use linera_service::util::{
-    DEFAULT_PAUSE_AFTER_GQL_MUTATIONS_SECS, DEFAULT_PAUSE_AFTER_LINERA_SERVICE_SECS,
+use linera_service::{
+    node_service::{AccessConfig, QueryCacheConfig},
+    storage::StorageConfigNamespace,
+    util::{DEFAULT_PAUSE_AFTER_GQL_MUTATIONS_SECS, DEFAULT_PAUSE_AFTER_LINERA_SERVICE_SECS},
 };
+use linera_storage::ArchiveStoreConfig;
 
 #[derive(Clone, clap::Subcommand)]
 pub enum ClientCommand {
     /// Transfer funds
     Transfer {
-        /// Sending chain ID (must be one of our chains)
+        /// Sending chain ID (must be one of our chains), or its alias
         #[arg(long = "from")]
-        sender: Account,
+        sender: AccountOrAlias,
 
-        /// Recipient account
+        /// Recipient account, or its alias
         #[arg(long = "to")]
-        recipient: Account,
+        recipient: AccountOrAlias,
 
         /// Amount to transfer
         amount: Amount,
+
+        /// Only execute the transfer locally, against the current state of the chain, and
+        /// print the resulting fees and balances, without submitting it to the validators.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// A memo, e.g. a deposit tag or a payment reference, encrypted so that only the
+        /// recipient can read it.
+        #[arg(long, requires = "memo_key")]
+        memo: Option<String>,
+
+        /// The recipient's public key to encrypt the memo to. It must be the key of the
+        /// recipient account's owner, unless the recipient is a chain's account.
+        #[arg(long, requires = "memo")]
+        memo_key: Option<AccountPublicKey>,
+    },
+
+    /// Transfer native tokens to many recipients, in as few blocks as possible.
+    ///
+    /// Each line of the input file is `<recipient>,<amount>`. The recipients and the
+    /// sender's balance are checked before any block is proposed, and the result of each
+    /// transfer is printed as `<line>,<recipient>,<amount>,<result>`.
+    TransferBatch {
+        /// Sending chain ID (must be one of our chains), or its alias
+        #[arg(long = "from")]
+        sender: AccountOrAlias,
+
+        /// The CSV file listing the transfers
+        #[arg(long)]
+        input: PathBuf,
+
+        /// The maximal number of transfers in a single block
+        #[arg(long, default_value = "100", value_parser = clap::value_parser!(u64).range(1..))]
+        max_transfers_per_block: u64,
+    },
+
+    /// Print the memos of the transfers in a block that are encrypted to one of the wallet's
+    /// keys.
+    ///
+    /// The block must be in the local storage, e.g. after synchronizing its chain.
+    ReadMemos {
+        /// The hash of the block.
+        block_hash: CryptoHash,
     },
 
     /// Open (i.e. activate) a new chain deriving the UID from an existing one.
@@ -86,6 +135,27 @@ pub enum ClientCommand {
 
         #[clap(flatten)]
         ownership_config: ChainOwnershipConfig,
+
+        /// Instead of setting new owners, replace the key of the chain's preferred owner with
+        /// a newly generated one, keeping the rest of the ownership configuration.
+        ///
+        /// The new key is added to the keystore before the block is proposed, and the wallet
+        /// is updated once the block is confirmed.
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "super_owners",
+                "owners",
+                "owner_weights",
+                "multi_leader_rounds",
+                "open_multi_leader_rounds",
+                "fast_round_duration",
+                "base_timeout",
+                "timeout_increment",
+                "fallback_duration",
+            ]
+        )]
+        rotate_key: bool,
     },
 
     /// Change the preferred owner of a chain.
@@ -118,6 +188,153 @@ pub enum ClientCommand {
         chain_id: ChainId,
     },
 
+    /// Register or renew a name on the admin chain.
+    ///
+    /// The name is owned by the signer of the chain that registers it. Registration succeeds
+    /// once the admin chain processes it, unless someone else owns the name. The chain that
+    /// registers it pays the name registration fee of the resource control policy.
+    RegisterName {
+        /// The name to register, made of lowercase ASCII letters, digits and hyphens.
+        name: String,
+
+        /// What the name resolves to, written as `chain:CHAIN-ID`, `owner:OWNER` or
+        /// `application:APPLICATION-ID`.
+        target: NameTarget,
+
+        /// The number of days until the registration expires.
+        #[arg(long, default_value = "365")]
+        days: u64,
+
+        /// The chain that sends the registration. If omitted, uses the default chain of the
+        /// wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
+    /// Transfer a name registered on the admin chain to a new owner.
+    TransferName {
+        /// The name to transfer.
+        name: String,
+
+        /// The new owner of the name.
+        #[arg(long)]
+        new_owner: AccountOwner,
+
+        /// The chain that sends the transfer. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
+    /// Resolve a name registered on the admin chain.
+    ResolveName {
+        /// The name to resolve.
+        name: String,
+    },
+
+    /// Set or remove a configuration value of a chain, readable by all its applications.
+    ///
+    /// Only the owners of the chain can change its configuration.
+    SetConfig {
+        /// The configuration key.
+        key: String,
+
+        /// The new value. If omitted, the key is removed.
+        value: Option<String>,
+
+        /// Interpret the value as hexadecimal bytes instead of a UTF-8 string.
+        #[arg(long)]
+        hex: bool,
+
+        /// The chain to configure. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
+    /// Change the rules on the incoming message bundles that the owners of a chain include in
+    /// their blocks.
+    ///
+    /// The bundles that are not accepted stay in the inbox. Without any option, all bundles
+    /// are accepted.
+    ChangeInboxPolicy {
+        /// The chain to configure. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// Only accept bundles from these chains.
+        #[arg(long, value_delimiter = ',')]
+        allow_senders: Option<Vec<ChainId>>,
+
+        /// Only accept messages from the system and from these applications.
+        #[arg(long, value_delimiter = ',')]
+        allow_applications: Option<Vec<ApplicationId>>,
+
+        /// Only accept bundles that credit at least this amount of native tokens.
+        #[arg(long, default_value = "0")]
+        min_attached_value: Amount,
+
+        /// Receive at most this many bundles in one block.
+        #[arg(long)]
+        max_bundles_per_block: Option<u32>,
+
+        /// Receive bundles of at most this many bytes in total in one block. Larger bundles
+        /// stay in the inbox.
+        #[arg(long)]
+        max_bundle_bytes_per_block: Option<u64>,
+    },
+
+    /// Change the limits on the block proposals of a chain, which validators enforce.
+    ///
+    /// Without any option, blocks are only limited by the committee's resource control policy.
+    ChangeBlockLimits {
+        /// The chain to configure. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// The maximal serialized size of a proposed block, in bytes.
+        #[arg(long)]
+        max_block_bytes: Option<u64>,
+
+        /// The maximal number of operations in a block.
+        #[arg(long)]
+        max_operations_per_block: Option<u32>,
+
+        /// The maximal number of incoming bundles received in a block.
+        #[arg(long)]
+        max_incoming_bundles_per_block: Option<u32>,
+    },
+
+    /// Change how much of a chain's history its owners ask validators to keep.
+    ///
+    /// Validators may honor this when pruning block bodies, but archive nodes keep the full
+    /// history. Without any option, validators are asked to keep the full history.
+    ChangeRetentionPolicy {
+        /// The chain to configure. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// Only keep the bodies of this many latest blocks.
+        #[arg(long, conflicts_with = "headers_only")]
+        last_blocks: Option<u64>,
+
+        /// Only keep the headers and certificates of the blocks.
+        #[arg(long)]
+        headers_only: bool,
+    },
+
+    /// Read a configuration value of a chain from the local state.
+    ReadConfig {
+        /// The configuration key.
+        key: String,
+
+        /// Print the value as hexadecimal bytes instead of a UTF-8 string.
+        #[arg(long)]
+        hex: bool,
+
+        /// The chain to read from. If omitted, uses the default chain of the wallet.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
     /// Read the current native-token balance of the given account directly from the local
     /// state.
     ///
@@ -127,9 +344,9 @@ pub enum ClientCommand {
     /// linera local-balance` for a consolidated balance.
     LocalBalance {
         /// The account to read, written as `CHAIN-ID:OWNER` or simply `CHAIN-ID` for the
-        /// chain balance. By default, we read the chain balance of the default chain in
-        /// the wallet.
-        account: Option<Account>,
+        /// chain balance, or an alias. By default, we read the chain balance of the
+        /// default chain in the wallet.
+        account: Option<AccountOrAlias>,
     },
 
     /// Simulate the execution of one block made of pending messages from the local inbox,
@@ -139,9 +356,9 @@ pub enum ClientCommand {
     /// validators yet. Call `linera sync` first to do so.
     QueryBalance {
         /// The account to query, written as `CHAIN-ID:OWNER` or simply `CHAIN-ID` for the
-        /// chain balance. By default, we read the chain balance of the default chain in
-        /// the wallet.
-        account: Option<Account>,
+        /// chain balance, or an alias. By default, we read the chain balance of the
+        /// default chain in the wallet.
+        account: Option<AccountOrAlias>,
     },
 
     /// (DEPRECATED) Synchronize the local state of the chain with a quorum validators, then query the
@@ -150,24 +367,29 @@ pub enum ClientCommand {
     /// This command is deprecated. Use `linera sync && linera query-balance` instead.
     SyncBalance {
         /// The account to query, written as `CHAIN-ID:OWNER` or simply `CHAIN-ID` for the
-        /// chain balance. By default, we read the chain balance of the default chain in
-        /// the wallet.
-        account: Option<Account>,
+        /// chain balance, or an alias. By default, we read the chain balance of the
+        /// default chain in the wallet.
+        account: Option<AccountOrAlias>,
     },
 
     /// Synchronize the local state of the chain with a quorum validators.
     Sync {
-        /// The chain to synchronize with validators. If omitted, synchronizes the
-        /// default chain of the wallet.
-        chain_id: Option<ChainId>,
+        /// The chain to synchronize with validators, or its alias. If omitted,
+        /// synchronizes the default chain of the wallet.
+        chain_id: Option<ChainOrAlias>,
     },
 
     /// Process all pending incoming messages from the inbox of the given chain by creating as many
     /// blocks as needed to execute all (non-failing) messages. Failing messages will be
     /// marked as rejected and may bounce to their sender depending on their configuration.
     ProcessInbox {
-        /// The chain to process. If omitted, uses the default chain of the wallet.
-        chain_id: Option<ChainId>,
+        /// The chain to process, or its alias. If omitted, uses the default chain of the
+        /// wallet.
+        chain_id: Option<ChainOrAlias>,
+
+        /// Also receive the bundles that exceed `--message-approval-threshold`.
+        #[arg(long)]
+        approve: bool,
     },
 
     /// Show the version and genesis config hash of a new validator, and print a warning if it is
@@ -218,6 +440,11 @@ pub enum ClientCommand {
         #[arg(long, default_value = "1")]
         votes: u64,
 
+        /// The BLS key of the validator and its proof of possession, separated by a colon.
+        /// This is required if the committee aggregates certificate signatures.
+        #[arg(long)]
+        bls_key: Option<BlsValidatorKey>,
+
         /// Skip the version and genesis config checks.
         #[arg(long)]
         skip_online_check: bool,
@@ -230,6 +457,13 @@ pub enum ClientCommand {
         public_key: ValidatorPublicKey,
     },
 
+    /// Change how the validators sign certificates in the next epoch (admin only)
+    SetCertificateSignatureScheme {
+        /// The signature scheme of the certificates.
+        #[arg(value_enum)]
+        scheme: CertificateSignatureScheme,
+    },
+
     /// Deprecates all committees except the last one.
     FinalizeCommittee,
 
@@ -303,6 +537,10 @@ pub enum ClientCommand {
         #[arg(long)]
         http_request: Option<Amount>,
 
+        /// Set the price of registering or renewing a name on the admin chain.
+        #[arg(long)]
+        name_registration: Option<Amount>,
+
         /// Set the maximum amount of fuel per block.
         #[arg(long)]
         maximum_fuel_per_block: Option<u64>,
@@ -348,6 +586,10 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_http_response_bytes: Option<u64>,
 
+        /// Set the maximum size in bytes of the state of a single application on a chain.
+        #[arg(long)]
+        maximum_application_storage_bytes: Option<u64>,
+
         /// Set the maximum amount of time allowed to wait for an HTTP response.
         #[arg(long)]
         http_request_timeout_ms: Option<u64>,
@@ -520,6 +762,10 @@ pub enum ClientCommand {
         #[arg(long)]
         http_request_price: Option<Amount>,
 
+        /// Set the price of registering or renewing a name on the admin chain.
+        #[arg(long)]
+        name_registration_price: Option<Amount>,
+
         /// Set the maximum amount of fuel per block.
         /// (This will overwrite value from `--policy-config`)
         #[arg(long)]
@@ -574,6 +820,10 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_http_response_bytes: Option<u64>,
 
+        /// Set the maximum size in bytes of the state of a single application on a chain.
+        #[arg(long)]
+        maximum_application_storage_bytes: Option<u64>,
+
         /// Set the maximum amount of time allowed to wait for an HTTP response.
         #[arg(long)]
         http_request_timeout_ms: Option<u64>,
@@ -592,7 +842,8 @@ pub enum ClientCommand {
         network_name: Option<String>,
     },
 
-    /// Watch the network for notifications.
+    /// Watch the network for notifications, and print the new blocks, incoming messages and
+    /// events of a chain as they happen.
     Watch {
         /// The chain ID to watch.
         chain_id: Option<ChainId>,
@@ -600,6 +851,10 @@ pub enum ClientCommand {
         /// Show all notifications from all validators.
         #[arg(long)]
         raw: bool,
+
+        /// Print the chain's activity as JSON, one line per notification.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Run a GraphQL service to explore and extend the chains of the wallet.
@@ -610,6 +865,24 @@ pub enum ClientCommand {
         /// The port on which to run the server
         #[arg(long)]
         port: NonZeroU16,
+
+        /// The port on which to serve Prometheus metrics, if the binary was built with the
+        /// `metrics` feature.
+        #[arg(long)]
+        metrics_port: Option<NonZeroU16>,
+
+        /// Restrictions on the access to the service, e.g. to expose it publicly.
+        #[command(flatten)]
+        access: AccessConfig,
+
+        /// The cache of application query responses.
+        #[command(flatten)]
+        query_cache: QueryCacheConfig,
+
+        /// A JSON file listing other wallets to serve, each under `/wallets/<name>` and with
+        /// its own API token.
+        #[arg(long)]
+        sessions: Option<PathBuf>,
     },
 
     /// Run a GraphQL service that exposes a faucet where users can claim tokens.
@@ -638,6 +911,36 @@ pub enum ClientCommand {
         /// Configuration for the faucet chain listener.
         #[command(flatten)]
         config: ChainListenerConfig,
+
+        /// Limits on the claims from each client.
+        #[command(flatten)]
+        limits: ClaimLimitsConfig,
+    },
+
+    /// Keep the chains of the wallet synchronized and process their inboxes, until
+    /// stopped.
+    ///
+    /// While the daemon runs, it holds the wallet: use `linera daemon` to query and update
+    /// the chains through the daemon's control socket.
+    SyncDaemon {
+        #[command(flatten)]
+        config: ChainListenerConfig,
+
+        /// The path of the control socket. Defaults to the wallet's path, with the
+        /// extension `sock`.
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+
+    /// Send a request to a running `linera sync-daemon`.
+    Daemon {
+        /// The path of the daemon's control socket. Defaults to the wallet's path, with the
+        /// extension `sock`.
+        #[arg(long)]
+        socket: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: DaemonCommand,
     },
 
     /// Publish module.
@@ -741,6 +1044,12 @@ pub enum ClientCommand {
         /// The list of required dependencies of application, if any.
         #[arg(long, num_args(0..))]
         required_application_ids: Option<Vec<ApplicationId>>,
+
+        /// Only execute the publication of the module locally and print the resulting fees
+        /// and balances, without submitting it to the validators. The creation of the
+        /// application is not simulated, since it requires the module to be published.
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Create an unassigned key pair.
@@ -772,6 +1081,18 @@ pub enum ClientCommand {
     #[command(subcommand)]
     Wallet(WalletCommand),
 
+    /// Manage the aliases of the wallet's address book.
+    #[command(subcommand)]
+    Alias(AliasCommand),
+
+    /// Create block proposals to be signed offline, sign them and submit them.
+    #[command(subcommand)]
+    Proposal(ProposalCommand),
+
+    /// Coordinate the block proposals of the owners of a multi-owner chain.
+    #[command(subcommand)]
+    MultiOwner(MultiOwnerCommand),
+
     /// Manage Linera projects.
     #[command(subcommand)]
     Project(ProjectCommand),
@@ -810,12 +1131,22 @@ impl ClientCommand {
     pub fn log_file_name(&self) -> Cow<'static, str> {
         match self {
             ClientCommand::Transfer { .. }
+            | ClientCommand::TransferBatch { .. }
+            | ClientCommand::ReadMemos { .. }
             | ClientCommand::OpenChain { .. }
             | ClientCommand::OpenMultiOwnerChain { .. }
             | ClientCommand::ChangeOwnership { .. }
             | ClientCommand::SetPreferredOwner { .. }
             | ClientCommand::ChangeApplicationPermissions { .. }
             | ClientCommand::CloseChain { .. }
+            | ClientCommand::RegisterName { .. }
+            | ClientCommand::TransferName { .. }
+            | ClientCommand::ResolveName { .. }
+            | ClientCommand::SetConfig { .. }
+            | ClientCommand::ChangeInboxPolicy { .. }
+            | ClientCommand::ChangeBlockLimits { .. }
+            | ClientCommand::ChangeRetentionPolicy { .. }
+            | ClientCommand::ReadConfig { .. }
             | ClientCommand::LocalBalance { .. }
             | ClientCommand::QueryBalance { .. }
             | ClientCommand::SyncBalance { .. }
@@ -826,6 +1157,7 @@ impl ClientCommand {
             | ClientCommand::SyncValidator { .. }
             | ClientCommand::SetValidator { .. }
             | ClientCommand::RemoveValidator { .. }
+            | ClientCommand::SetCertificateSignatureScheme { .. }
             | ClientCommand::ResourceControlPolicy { .. }
             | ClientCommand::FinalizeCommittee
             | ClientCommand::CreateGenesisConfig { .. }
@@ -837,6 +1169,9 @@ impl ClientCommand {
             | ClientCommand::Keygen
             | ClientCommand::Assign { .. }
             | ClientCommand::Wallet { .. }
+            | ClientCommand::Alias { .. }
+            | ClientCommand::Proposal { .. }
+            | ClientCommand::MultiOwner { .. }
             | ClientCommand::RetryPendingBlock { .. } => "client".into(),
             #[cfg(feature = "benchmark")]
             ClientCommand::Benchmark { .. } => "benchmark".into(),
@@ -846,6 +1181,8 @@ impl ClientCommand {
             ClientCommand::Storage { .. } => "storage".into(),
             ClientCommand::Service { port, .. } => format!("service-{port}").into(),
             ClientCommand::Faucet { .. } => "faucet".into(),
+            ClientCommand::SyncDaemon { .. } => "sync-daemon".into(),
+            ClientCommand::Daemon { .. } => "client".into(),
             ClientCommand::HelpMarkdown | ClientCommand::ExtractScriptFromMarkdown { .. } => {
                 "tool".into()
             }
@@ -853,6 +1190,37 @@ impl ClientCommand {
     }
 }
 
+#[derive(Clone, clap::Subcommand)]
+pub enum DaemonCommand {
+    /// Show the chains of the daemon's wallet.
+    Status,
+
+    /// Show the balance of a chain, or of one of its owners.
+    Balance {
+        /// The chain. The default chain of the wallet is used otherwise.
+        chain_id: Option<ChainId>,
+
+        /// The owner whose balance to show, instead of the chain's.
+        #[arg(long)]
+        owner: Option<AccountOwner>,
+    },
+
+    /// Synchronize a chain with the validators.
+    Sync {
+        /// The chain. The default chain of the wallet is used otherwise.
+        chain_id: Option<ChainId>,
+    },
+
+    /// Process the inbox of a chain.
+    ProcessInbox {
+        /// The chain. The default chain of the wallet is used otherwise.
+        chain_id: Option<ChainId>,
+    },
+
+    /// Stop the daemon.
+    Stop,
+}
+
 #[derive(Clone, clap::Parser)]
 pub enum DatabaseToolCommand {
     /// Delete all the namespaces in the database
@@ -878,6 +1246,101 @@ pub enum DatabaseToolCommand {
 
     /// List the chain IDs in the database
     ListChainIds,
+
+    /// Move a chain, with its certificates and blobs, to an archive store, while no validator
+    /// uses the database. Running validators archive chains with their admin API instead.
+    /// The chain is restored automatically when a validator using the archive store loads it
+    /// or reads one of its certificates or blobs again
+    ArchiveChain {
+        /// The chain to archive.
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// The archive store: the path of a directory, or `s3://BUCKET/PREFIX` for a bucket
+        /// of an S3-compatible service configured by the `AWS_*` environment variables.
+        #[arg(long)]
+        archive: ArchiveStoreConfig,
+    },
+
+    /// Move an archived chain back from an archive store
+    RestoreChain {
+        /// The chain to restore.
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// The archive store: the path of a directory, or `s3://BUCKET/PREFIX` for a bucket
+        /// of an S3-compatible service configured by the `AWS_*` environment variables.
+        #[arg(long)]
+        archive: ArchiveStoreConfig,
+    },
+
+    /// Back up a RocksDB namespace incrementally, while it remains in use
+    Backup {
+        /// The directory of the backups.
+        #[arg(long)]
+        backup_dir: PathBuf,
+
+        /// The number of backups to keep in the directory, deleting the oldest ones.
+        #[arg(long)]
+        max_backups: Option<usize>,
+    },
+
+    /// Restore a RocksDB namespace, which must not exist, from a backup
+    RestoreBackup {
+        /// The directory of the backups.
+        #[arg(long)]
+        backup_dir: PathBuf,
+
+        /// The ID of the backup to restore. Defaults to the latest one.
+        #[arg(long)]
+        backup_id: Option<u32>,
+    },
+
+    /// List the RocksDB backups in a directory
+    ListBackups {
+        /// The directory of the backups.
+        #[arg(long)]
+        backup_dir: PathBuf,
+    },
+    /// Copy all the keys of a namespace, which must not be in use, to a new namespace,
+    /// e.g. of another storage backend
+    ///
+    /// An interrupted copy resumes where it stopped if it is run again with the same
+    /// `--checkpoint` file.
+    Copy {
+        /// The source storage and namespace, e.g. `rocksdb:db.rocksdb:linera`.
+        #[arg(long)]
+        from: StorageConfigNamespace,
+
+        /// The target storage and namespace, e.g. `scylladb:tcp:localhost:9042:linera`.
+        #[arg(long)]
+        to: StorageConfigNamespace,
+
+        /// The file where the progress of the copy is saved, to resume it if interrupted.
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+
+        /// The number of keys read and written at once.
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+    },
+
+    /// Copy the data of the old store of a `migratingrocksdbscylladb` storage to its new
+    /// store, where it differs, and verify it. Until then, the storage cannot be used in
+    /// `read_new` mode
+    BackfillMigration {
+        /// The number of keys read and written at once.
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+    },
+
+    /// Compare the old and the new store of a `migratingrocksdbscylladb` storage. If they
+    /// hold the same data, the storage can then be used in `read_new` mode
+    VerifyMigration {
+        /// The number of keys read at once.
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+    },
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -984,6 +1447,32 @@ pub enum NetCommand {
     Helper,
 }
 
+#[derive(Clone, clap::Subcommand)]
+pub enum AliasCommand {
+    /// Add an alias for a chain or an account. The alias can then be used instead of the
+    /// chain or account in the other commands.
+    Add {
+        /// The name of the alias.
+        name: String,
+
+        /// The chain or account, written as `CHAIN-ID:OWNER` or simply `CHAIN-ID`.
+        account: Account,
+
+        /// Replace the alias if it already exists.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove an alias.
+    Remove {
+        /// The name of the alias.
+        name: String,
+    },
+
+    /// Show the aliases of the address book.
+    List,
+}
+
 #[derive(Clone, clap::Subcommand)]
 pub enum WalletCommand {
     /// Show the contents of the wallet.
@@ -1001,6 +1490,13 @@ pub enum WalletCommand {
     /// Change the wallet default chain.
     SetDefault { chain_id: ChainId },
 
+    /// Encrypt the keystore with a passphrase.
+    ///
+    /// The passphrase is then requested whenever the private keys are used, unless it is set
+    /// in the environment variable `LINERA_KEYSTORE_PASSPHRASE`. Plaintext keystores remain
+    /// supported.
+    Encrypt,
+
     /// Initialize a wallet from the genesis configuration.
     Init {
         /// The path to the genesis configuration for a Linera deployment. Either this or `--faucet`
@@ -1024,6 +1520,20 @@ pub enum WalletCommand {
         /// TESTING ONLY.
         #[arg(long)]
         testing_prng_seed: Option<u64>,
+
+        /// Derive the keys of this wallet from a new mnemonic phrase, which is printed once.
+        /// All the keys can then be recovered from the phrase with `--recover-mnemonic`.
+        #[arg(long, conflicts_with_all = ["testing_prng_seed", "recover_mnemonic"])]
+        mnemonic: bool,
+
+        /// Derive the keys of this wallet from an existing mnemonic phrase, read from the
+        /// environment variable `LINERA_MNEMONIC` or requested interactively.
+        #[arg(long, conflicts_with = "testing_prng_seed")]
+        recover_mnemonic: bool,
+
+        /// The number of keys to recover from the mnemonic phrase.
+        #[arg(long, default_value = "1", requires = "recover_mnemonic")]
+        recover_keys: u32,
     },
 
     /// Request a new chain from a faucet and add it to the wallet.
@@ -1037,6 +1547,18 @@ pub enum WalletCommand {
         set_default: bool,
     },
 
+    /// Request several new chains at once from a faucet and add them to the wallet, e.g.
+    /// to provision chains for a benchmark. The faucet must allow batch claims.
+    RequestChains {
+        /// The address of a faucet.
+        #[arg(long)]
+        faucet: String,
+
+        /// The number of chains to request.
+        #[arg(long)]
+        count: usize,
+    },
+
     /// Add a new followed chain (i.e. a chain without keypair) to the wallet.
     FollowChain {
         /// The chain ID.
@@ -1049,6 +1571,91 @@ pub enum WalletCommand {
 
     /// Forgets the specified chain, including the associated key pair.
     ForgetChain { chain_id: ChainId },
+
+    /// Write everything needed to use a chain from another wallet to a file.
+    ///
+    /// The file contains the chain's tip and pending proposal, and the owner the chain is
+    /// used with, but not the owner's key: that has to be in the other wallet's keystore.
+    ExportChain {
+        /// The chain to export. If not specified, the wallet's default chain is used.
+        chain_id: Option<ChainId>,
+
+        /// The file to write the chain to.
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Add a chain exported from another wallet with `linera wallet export-chain`.
+    ///
+    /// The chain is downloaded from the validators, to check that they know the exported
+    /// tip. If the chain has new blocks since it was exported, the wallet is updated to the
+    /// latest one.
+    ImportChain {
+        /// The file containing the exported chain.
+        input: PathBuf,
+
+        /// Whether this chain should become the default chain.
+        #[arg(long)]
+        set_default: bool,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+pub enum ProposalCommand {
+    /// Write an unsigned proposal for the next block of a chain to a file.
+    ///
+    /// The proposal can then be signed with `linera proposal sign` on a machine holding
+    /// the owner's key, which doesn't need network access, and submitted with `linera
+    /// proposal submit`. If the chain has a pending or locked block, that block is
+    /// proposed; otherwise a new block with the incoming messages and the given operations
+    /// is created.
+    Export {
+        /// The chain to propose a block for. If not specified, the wallet's default chain is
+        /// used.
+        chain_id: Option<ChainId>,
+
+        /// The file to write the unsigned proposal to.
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Path to a JSON file containing the list of operations to include in a new block.
+        #[arg(long)]
+        operations_path: Option<PathBuf>,
+
+        /// The owner to propose the block as, e.g. a co-owner of a multi-owner chain whose
+        /// key is not in the keystore. If not specified, the wallet's owner of the chain is
+        /// used.
+        #[arg(long)]
+        owner: Option<AccountOwner>,
+    },
+
+    /// Sign an unsigned proposal with a key from the keystore.
+    ///
+    /// This only requires the keystore, and no wallet, storage or network access.
+    Sign {
+        /// The file containing the unsigned proposal.
+        input: PathBuf,
+
+        /// The file to write the signed proposal to.
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Submit a signed proposal to the validators.
+    Submit {
+        /// The file containing the signed proposal.
+        input: PathBuf,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+pub enum MultiOwnerCommand {
+    /// Show the current round of a chain, which owners still need to act, and the
+    /// validators' votes.
+    Status {
+        /// The chain to show. If not specified, the wallet's default chain is used.
+        chain_id: Option<ChainId>,
+    },
 }
 
 #[derive(Clone, clap::Parser)]
@@ -7,11 +7,11 @@
 
 use std::{
     collections::{BTreeSet, HashMap},
-    env,
+    env, io,
     ops::Deref,
     path::PathBuf,
     process,
-    sync::Arc,
+    sync::{Arc, OnceLock},
     time::Instant,
 };
 
@@ -19,39 +19,55 @@ use anyhow::{anyhow, bail, ensure, Context, Error};
 use async_trait::async_trait;
 use chrono::Utc;
 use colored::Colorize;
-use command::{ClientCommand, DatabaseToolCommand, NetCommand, ProjectCommand, WalletCommand};
+use command::{
+    AliasCommand, ClientCommand, DaemonCommand, DatabaseToolCommand, MultiOwnerCommand, NetCommand,
+    ProjectCommand, ProposalCommand, WalletCommand,
+};
 use futures::{lock::Mutex, FutureExt as _, StreamExt};
 use linera_base::{
     bcs,
-    crypto::{CryptoHash, InMemorySigner, Signer},
+    crypto::{generate_mnemonic, CryptoHash, InMemorySigner, Signer},
     data_types::{
-        ApplicationPermissions, BlockHeight, ChainDescription, ChainOrigin, Epoch,
-        InitialChainConfig, Timestamp,
+        ApplicationPermissions, Blob, BlockHeight, BlockLimits, ChainDescription, ChainOrigin,
+        Epoch, InboxPolicy, InitialChainConfig, RetentionPolicy, TimeDelta, Timestamp,
     },
+    hex,
     identifiers::{AccountOwner, ChainId},
     listen_for_shutdown_signals,
     ownership::ChainOwnership,
+    tracing::LogFormat,
 };
+use linera_chain::data_types::{BlockProposal, MessageAction, UnsignedBlockProposal};
 use linera_client::{
+    chain_activity::ChainActivity,
+    chain_bundle::ChainBundle,
+    chain_listener::ChainListener,
     client_context::ClientContext,
-    client_options::ClientContextOptions,
+    client_options::{ClientContextOptions, HttpRequestPolicyConfig},
     config::{CommitteeConfig, GenesisConfig, SignerState, WalletState},
-    persistent::{self, Persist},
+    memo,
+    persistent::{self, KeyCache, Persist},
+    sync_daemon,
+    transfer_batch::{self, TransferStatus},
     wallet::{UserChain, Wallet},
 };
 use linera_core::{
-    data_types::ClientOutcome, node::ValidatorNodeProvider, worker::Reason, JoinSetExt as _,
+    client::DryRunOutcome, data_types::ClientOutcome, node::ValidatorNodeProvider, worker::Reason,
+    JoinSetExt as _,
 };
 use linera_execution::{
     committee::{Committee, ValidatorState},
-    WasmRuntime, WithWasmDefault as _,
+    system::{Recipient, SystemOperation},
+    Operation, WasmRuntime, WithWasmDefault as _,
 };
 use linera_faucet_server::FaucetService;
+#[cfg(with_metrics)]
+use linera_service::prometheus_server;
 use linera_service::{
     cli_wrappers,
-    node_service::NodeService,
+    node_service::{AccessConfig, NodeService, Session, SessionConfig},
     project::{self, Project},
-    storage::{Runnable, RunnableWithStore, StorageConfigNamespace},
+    storage::{CopyOptions, Runnable, RunnableWithStore, StorageConfigNamespace},
     util, wallet,
 };
 use linera_storage::{DbStorage, Storage};
@@ -59,6 +75,7 @@ use linera_views::{
     lru_caching::StorageCacheConfig,
     store::{CommonStoreConfig, KeyValueStore},
 };
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
@@ -71,6 +88,20 @@ use crate::persistent::PersistExt as _;
 
 struct Job(ClientOptions);
 
+/// The wallet of the node service's session, saved in its own file.
+type SessionWallet = WalletState<persistent::File<Wallet>>;
+
+/// The keystore of the node service's session, saved in its own file.
+type SessionSigner = SignerState<persistent::File<InMemorySigner>>;
+
+/// The contents of a file with a block proposal that is signed offline, together with the
+/// blobs it publishes.
+#[derive(Serialize, Deserialize)]
+struct ProposalFile<P> {
+    proposal: P,
+    blobs: Vec<Blob>,
+}
+
 fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Vec<u8>> {
     let value = match (string, path) {
         (Some(_), Some(_)) => bail!("cannot have both a json string and file"),
@@ -84,6 +115,73 @@ fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Ve
     Ok(serde_json::to_vec(&value)?)
 }
 
+/// Prints the fees, resources and effects of a block that was executed locally.
+fn print_dry_run(outcome: &DryRunOutcome) {
+    let block = &outcome.block;
+    let resources = &outcome.resources.tracker;
+    println!(
+        "Dry run of block {} on chain {}. Nothing was submitted to the validators.",
+        block.header.height, block.header.chain_id
+    );
+    println!("Fees: {}", resources.fees);
+    let transaction_fees = outcome
+        .resources
+        .transaction_fees
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    println!("Fees per transaction: {}", transaction_fees.join(", "));
+    println!("Fuel: {}", resources.fuel);
+    println!(
+        "Storage: {} read operations ({} bytes), {} write operations ({} bytes)",
+        resources.read_operations,
+        resources.bytes_read,
+        resources.write_operations,
+        resources.bytes_written
+    );
+    println!("Block size: {} bytes", resources.block_size);
+    println!(
+        "Chain balance: {} -> {}",
+        outcome.chain_balance_before, outcome.chain_balance_after
+    );
+    if let Some(balance) = outcome.owner_balance_after {
+        println!(
+            "Owner balance: {} -> {}",
+            outcome.owner_balance_before.unwrap_or_default(),
+            balance
+        );
+    }
+    let rejected = block
+        .body
+        .incoming_bundles
+        .iter()
+        .filter(|bundle| bundle.action == MessageAction::Reject)
+        .count();
+    println!(
+        "Incoming bundles: {} ({} rejected)",
+        block.body.incoming_bundles.len(),
+        rejected
+    );
+    for message in block.body.messages.iter().flatten() {
+        println!(
+            "Outgoing message to {} ({:?})",
+            message.destination, message.kind
+        );
+    }
+    println!(
+        "Events: {}, blobs: {}",
+        block.body.events.iter().flatten().count(),
+        block.body.blobs.iter().flatten().count()
+    );
+    for log in outcome.resources.logs.iter().flatten() {
+        println!(
+            "Log of {} ({:?}): {}",
+            log.application_id, log.level, log.message
+        );
+    }
+    println!("State hash: {}", block.header.state_hash);
+}
+
 #[async_trait]
 impl Runnable for Job {
     type Output = anyhow::Result<()>;
@@ -96,7 +194,8 @@ impl Runnable for Job {
         let wallet = options.wallet().await?;
         let mut signer = options.signer().await?;
 
-        let command = options.command;
+        // Some commands still need the options, e.g. to find the wallet's path.
+        let command = options.command.clone();
 
         use ClientCommand::*;
         match command {
@@ -104,6 +203,9 @@ impl Runnable for Job {
                 sender,
                 recipient,
                 amount,
+                dry_run,
+                memo,
+                memo_key,
             } => {
                 let mut context = ClientContext::new(
                     storage.clone(),
@@ -111,7 +213,46 @@ impl Runnable for Job {
                     wallet,
                     Box::new(signer.into_value()),
                 );
+                let address_book = &context.wallet().address_book;
+                let sender = address_book.resolve_account(&sender)?;
+                if recipient.is_unknown_to(context.wallet()) {
+                    warn!(
+                        "The recipient {recipient} is neither in the wallet nor in the address \
+                        book: make sure that it is correct"
+                    );
+                }
+                let recipient = address_book.resolve_account(&recipient)?;
+                let memo = match (memo, memo_key) {
+                    (Some(text), Some(key)) => {
+                        ensure!(
+                            recipient.owner == AccountOwner::CHAIN
+                                || recipient.owner == AccountOwner::from(key),
+                            "The memo key {key} is not the key of the recipient {}",
+                            recipient.owner
+                        );
+                        Some(memo::seal(&text, &key)?)
+                    }
+                    _ => None,
+                };
                 let chain_client = context.make_chain_client(sender.chain_id).await?;
+                if dry_run {
+                    let operation = Operation::system(match memo {
+                        Some(memo) => SystemOperation::TransferWithMemo {
+                            owner: sender.owner,
+                            recipient: Recipient::Account(recipient),
+                            amount,
+                            memo,
+                        },
+                        None => SystemOperation::Transfer {
+                            owner: sender.owner,
+                            recipient: Recipient::Account(recipient),
+                            amount,
+                        },
+                    });
+                    let outcome = chain_client.dry_run(vec![operation], Vec::new()).await?;
+                    print_dry_run(&outcome);
+                    return Ok(());
+                }
                 info!(
                     "Starting transfer of {} native tokens from {} to {}",
                     amount, sender, recipient
@@ -120,10 +261,20 @@ impl Runnable for Job {
                 let certificate = context
                     .apply_client_command(&chain_client, |chain_client| {
                         let chain_client = chain_client.clone();
+                        let memo = memo.clone();
                         async move {
-                            chain_client
-                                .transfer_to_account(sender.owner, amount, recipient)
-                                .await
+                            match memo {
+                                Some(memo) => {
+                                    chain_client
+                                        .transfer_with_memo(sender.owner, amount, recipient, memo)
+                                        .await
+                                }
+                                None => {
+                                    chain_client
+                                        .transfer_to_account(sender.owner, amount, recipient)
+                                        .await
+                                }
+                            }
                         }
                     })
                     .await
@@ -133,6 +284,56 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            TransferBatch {
+                sender,
+                input,
+                max_transfers_per_block,
+            } => {
+                let transfers = transfer_batch::parse_transfers(&fs_err::read_to_string(&input)?)?;
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let sender = context.wallet().address_book.resolve_account(&sender)?;
+                let time_start = Instant::now();
+                let results = context
+                    .transfer_batch(sender, transfers, usize::try_from(max_transfers_per_block)?)
+                    .await
+                    .context("Failed to make batch transfer")?;
+                let time_total = time_start.elapsed();
+                let failed = results
+                    .iter()
+                    .filter(|(_, status)| !matches!(status, TransferStatus::Confirmed { .. }))
+                    .count();
+                for (transfer, status) in &results {
+                    println!(
+                        "{},{},{},{status}",
+                        transfer.line, transfer.recipient, transfer.amount
+                    );
+                }
+                info!(
+                    "{} of {} transfers confirmed after {} ms",
+                    results.len() - failed,
+                    results.len(),
+                    time_total.as_millis()
+                );
+                if failed > 0 {
+                    bail!("{failed} transfers were not confirmed");
+                }
+            }
+
+            ReadMemos { block_hash } => {
+                let block = storage
+                    .read_confirmed_block(block_hash)
+                    .await
+                    .with_context(|| format!("Block {block_hash} is not in the local storage"))?;
+                for memo in memo::read_memos(block.block(), &signer.into_value()) {
+                    println!("{}", serde_json::to_string(&memo)?);
+                }
+            }
+
             OpenChain {
                 chain_id,
                 owner,
@@ -228,9 +429,29 @@ impl Runnable for Job {
                 println!("{}", id);
             }
 
+            ChangeOwnership {
+                chain_id,
+                rotate_key: true,
+                ..
+            } => {
+                let new_public_key = signer.mutate(|s| s.generate_new()).await?;
+                let new_owner = AccountOwner::from(new_public_key);
+                info!("New key for {} added to the keystore", new_owner);
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let certificate = context.rotate_key(chain_id, new_owner).await?;
+                debug!("{:?}", certificate);
+                println!("{}", new_owner);
+            }
+
             ChangeOwnership {
                 chain_id,
                 ownership_config,
+                rotate_key: false,
             } => {
                 let mut context = ClientContext::new(
                     storage.clone(),
@@ -319,6 +540,254 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            RegisterName {
+                name,
+                target,
+                days,
+                chain_id,
+            } => {
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Registering name {name:?} for {target}");
+                let time_start = Instant::now();
+                let expiry = Timestamp::now()
+                    .saturating_add(TimeDelta::from_secs(days.saturating_mul(24 * 60 * 60)));
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let name = name.clone();
+                        async move { chain_client.register_name(name, target, expiry).await }
+                    })
+                    .await
+                    .context("Failed to register name")?;
+                let time_total = time_start.elapsed();
+                info!(
+                    "Name registration sent to the admin chain after {} ms",
+                    time_total.as_millis()
+                );
+                debug!("{:?}", certificate);
+            }
+
+            TransferName {
+                name,
+                new_owner,
+                chain_id,
+            } => {
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Transferring name {name:?} to {new_owner}");
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let name = name.clone();
+                        async move { chain_client.transfer_name(name, new_owner).await }
+                    })
+                    .await
+                    .context("Failed to transfer name")?;
+                let time_total = time_start.elapsed();
+                info!(
+                    "Name transfer sent to the admin chain after {} ms",
+                    time_total.as_millis()
+                );
+                debug!("{:?}", certificate);
+            }
+
+            SetConfig {
+                key,
+                value,
+                hex: is_hex,
+                chain_id,
+            } => {
+                let value = match value {
+                    Some(value) if is_hex => Some(hex::decode(value)?),
+                    Some(value) => Some(value.into_bytes()),
+                    None => None,
+                };
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Setting configuration key {key:?} of chain {chain_id}");
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let key = key.clone();
+                        let value = value.clone();
+                        async move { chain_client.set_config_value(key, value).await }
+                    })
+                    .await
+                    .context("Failed to set the configuration value")?;
+                let time_total = time_start.elapsed();
+                info!(
+                    "Configuration value set after {} ms",
+                    time_total.as_millis()
+                );
+                debug!("{:?}", certificate);
+            }
+
+            ChangeInboxPolicy {
+                chain_id,
+                allow_senders,
+                allow_applications,
+                min_attached_value,
+                max_bundles_per_block,
+                max_bundle_bytes_per_block,
+            } => {
+                let inbox_policy = InboxPolicy {
+                    allowed_senders: allow_senders,
+                    allowed_applications: allow_applications,
+                    min_attached_value,
+                    max_bundles_per_block,
+                    max_bundle_bytes_per_block,
+                };
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Changing the inbox policy of chain {chain_id}");
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let inbox_policy = inbox_policy.clone();
+                        async move { chain_client.change_inbox_policy(inbox_policy).await }
+                    })
+                    .await
+                    .context("Failed to change the inbox policy")?;
+                let time_total = time_start.elapsed();
+                info!("Inbox policy changed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            ChangeBlockLimits {
+                chain_id,
+                max_block_bytes,
+                max_operations_per_block,
+                max_incoming_bundles_per_block,
+            } => {
+                let block_limits = BlockLimits {
+                    max_block_bytes,
+                    max_operations_per_block,
+                    max_incoming_bundles_per_block,
+                };
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Changing the block limits of chain {chain_id}");
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.change_block_limits(block_limits).await }
+                    })
+                    .await
+                    .context("Failed to change the block limits")?;
+                let time_total = time_start.elapsed();
+                info!("Block limits changed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            ChangeRetentionPolicy {
+                chain_id,
+                last_blocks,
+                headers_only,
+            } => {
+                let retention_policy = match (last_blocks, headers_only) {
+                    (_, true) => RetentionPolicy::HeadersOnly,
+                    (Some(count), false) => RetentionPolicy::LastBlocks(count),
+                    (None, false) => RetentionPolicy::FullHistory,
+                };
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                info!("Changing the retention policy of chain {chain_id} to {retention_policy:?}");
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.change_retention_policy(retention_policy).await }
+                    })
+                    .await
+                    .context("Failed to change the retention policy")?;
+                let time_total = time_start.elapsed();
+                info!(
+                    "Retention policy changed after {} ms",
+                    time_total.as_millis()
+                );
+                debug!("{:?}", certificate);
+            }
+
+            ReadConfig {
+                key,
+                hex: is_hex,
+                chain_id,
+            } => {
+                let context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id).await?;
+                match chain_client.config_value(&key).await? {
+                    Some(value) if is_hex => println!("{}", hex::encode(value)),
+                    Some(value) => println!("{}", String::from_utf8(value)?),
+                    None => bail!("Configuration key {key:?} is not set on chain {chain_id}"),
+                }
+            }
+
+            ResolveName { name } => {
+                let context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_client = context.make_chain_client(context.default_chain()).await?;
+                info!("Resolving name {name:?}");
+                let time_start = Instant::now();
+                let target = chain_client.resolve_name(&name).await?;
+                let time_total = time_start.elapsed();
+                info!("Name resolved after {} ms", time_total.as_millis());
+                match target {
+                    Some(target) => println!("{target}"),
+                    None => bail!("Name {name:?} is not registered"),
+                }
+            }
+
             LocalBalance { account } => {
                 let context = ClientContext::new(
                     storage.clone(),
@@ -326,7 +795,10 @@ impl Runnable for Job {
                     wallet,
                     Box::new(signer.into_value()),
                 );
-                let account = account.unwrap_or_else(|| context.default_account());
+                let account = match account {
+                    Some(account) => context.wallet().address_book.resolve_account(&account)?,
+                    None => context.default_account(),
+                };
                 let chain_client = context.make_chain_client(account.chain_id).await?;
                 info!("Reading the balance of {} from the local state", account);
                 let time_start = Instant::now();
@@ -343,7 +815,10 @@ impl Runnable for Job {
                     wallet,
                     Box::new(signer.into_value()),
                 );
-                let account = account.unwrap_or_else(|| context.default_account());
+                let account = match account {
+                    Some(account) => context.wallet().address_book.resolve_account(&account)?,
+                    None => context.default_account(),
+                };
                 let chain_client = context.make_chain_client(account.chain_id).await?;
                 info!(
                     "Evaluating the local balance of {account} by staging execution of known \
@@ -363,7 +838,10 @@ impl Runnable for Job {
                     wallet,
                     Box::new(signer.into_value()),
                 );
-                let account = account.unwrap_or_else(|| context.default_account());
+                let account = match account {
+                    Some(account) => context.wallet().address_book.resolve_account(&account)?,
+                    None => context.default_account(),
+                };
                 let chain_client = context.make_chain_client(account.chain_id).await?;
                 info!("Synchronizing chain information and querying the local balance");
                 warn!("This command is deprecated. Use `linera sync && linera query-balance` instead.");
@@ -387,7 +865,10 @@ impl Runnable for Job {
                     wallet,
                     Box::new(signer.into_value()),
                 );
-                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_id = match chain_id {
+                    Some(chain_id) => context.wallet().address_book.resolve_chain(&chain_id)?,
+                    None => context.default_chain(),
+                };
                 let chain_client = context.make_chain_client(chain_id).await?;
                 info!("Synchronizing chain information");
                 let time_start = Instant::now();
@@ -400,15 +881,28 @@ impl Runnable for Job {
                 );
             }
 
-            ProcessInbox { chain_id } => {
+            ProcessInbox { chain_id, approve } => {
                 let mut context = ClientContext::new(
                     storage.clone(),
                     options.inner.clone(),
                     wallet,
                     Box::new(signer.into_value()),
                 );
-                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_id = match chain_id {
+                    Some(chain_id) => context.wallet().address_book.resolve_chain(&chain_id)?,
+                    None => context.default_chain(),
+                };
                 let chain_client = context.make_chain_client(chain_id).await?;
+                if approve {
+                    chain_client.synchronize_from_validators().await?;
+                    for bundle in chain_client.incoming_bundles_awaiting_approval().await? {
+                        info!(
+                            "Approving the bundle from chain {} at height {}",
+                            bundle.origin, bundle.bundle.height
+                        );
+                        chain_client.approve_incoming_bundle(bundle.origin, bundle.bundle.height);
+                    }
+                }
                 info!("Processing the inbox of chain {}", chain_id);
                 let time_start = Instant::now();
                 let certificates = context.process_inbox(&chain_client).await?;
@@ -418,6 +912,14 @@ impl Runnable for Job {
                     certificates.len(),
                     time_total.as_millis()
                 );
+                let awaiting_approval = chain_client.incoming_bundles_awaiting_approval().await?;
+                if !awaiting_approval.is_empty() {
+                    warn!(
+                        "{} incoming bundles exceed the approval threshold and stay in the \
+                        inbox; use --approve to receive them",
+                        awaiting_approval.len()
+                    );
+                }
             }
 
             QueryValidator {
@@ -598,6 +1100,7 @@ impl Runnable for Job {
 
             command @ (SetValidator { .. }
             | RemoveValidator { .. }
+            | SetCertificateSignatureScheme { .. }
             | ResourceControlPolicy { .. }) => {
                 use linera_core::node::ValidatorNode as _;
 
@@ -617,6 +1120,7 @@ impl Runnable for Job {
                     account_key: _,
                     address,
                     votes: _,
+                    bls_key: _,
                     skip_online_check: false,
                 } = &command
                 {
@@ -670,20 +1174,27 @@ impl Runnable for Job {
                             let mut committee = chain_client.local_committee().await.unwrap();
                             let mut policy = committee.policy().clone();
                             let mut validators = committee.validators().clone();
+                            let mut scheme = committee.certificate_signature_scheme();
                             match command {
                                 SetValidator {
                                     public_key,
                                     account_key,
                                     address,
                                     votes,
+                                    bls_key,
                                     skip_online_check: _,
                                 } => {
+                                    // Keep the BLS key of an existing validator by default.
+                                    let bls_key = bls_key.or_else(|| {
+                                        validators.get(&public_key).and_then(|state| state.bls_key)
+                                    });
                                     validators.insert(
                                         public_key,
                                         ValidatorState {
                                             network_address: address,
                                             votes,
                                             account_public_key: account_key,
+                                            bls_key,
                                         },
                                     );
                                 }
@@ -693,6 +1204,12 @@ impl Runnable for Job {
                                         return Ok(ClientOutcome::Committed(None));
                                     }
                                 }
+                                SetCertificateSignatureScheme { scheme: new_scheme } => {
+                                    if scheme == new_scheme {
+                                        return Ok(ClientOutcome::Committed(None));
+                                    }
+                                    scheme = new_scheme;
+                                }
                                 ResourceControlPolicy {
                                     block,
                                     fuel_unit,
@@ -711,6 +1228,7 @@ impl Runnable for Job {
                                     message_byte,
                                     service_as_oracle_query,
                                     http_request,
+                                    name_registration,
                                     maximum_fuel_per_block,
                                     maximum_service_oracle_execution_ms,
                                     maximum_block_size,
@@ -722,6 +1240,7 @@ impl Runnable for Job {
                                     maximum_bytes_written_per_block,
                                     maximum_oracle_response_bytes,
                                     maximum_http_response_bytes,
+                                    maximum_application_storage_bytes,
                                     http_request_timeout_ms,
                                     http_request_allow_list,
                                 } => {
@@ -755,6 +1274,8 @@ impl Runnable for Job {
                                             .unwrap_or(existing_policy.service_as_oracle_query),
                                         http_request: http_request
                                             .unwrap_or(existing_policy.http_request),
+                                        name_registration: name_registration
+                                            .unwrap_or(existing_policy.name_registration),
                                         maximum_fuel_per_block: maximum_fuel_per_block
                                             .unwrap_or(existing_policy.maximum_fuel_per_block),
                                         maximum_service_oracle_execution_ms:
@@ -785,6 +1306,10 @@ impl Runnable for Job {
                                             ),
                                         maximum_http_response_bytes: maximum_http_response_bytes
                                             .unwrap_or(existing_policy.maximum_http_response_bytes),
+                                        maximum_application_storage_bytes:
+                                            maximum_application_storage_bytes.unwrap_or(
+                                                existing_policy.maximum_application_storage_bytes,
+                                            ),
                                         http_request_timeout_ms: http_request_timeout_ms
                                             .unwrap_or(existing_policy.http_request_timeout_ms),
                                         http_request_allow_list: http_request_allow_list
@@ -798,7 +1323,8 @@ impl Runnable for Job {
                                 }
                                 _ => unreachable!(),
                             }
-                            committee = Committee::new(validators, policy);
+                            committee = Committee::new(validators, policy)
+                                .with_certificate_signature_scheme(scheme);
                             chain_client
                                 .stage_new_committee(committee)
                                 .await
@@ -920,6 +1446,7 @@ impl Runnable for Job {
                     committee,
                     context.client.local_node().clone(),
                     health_check_endpoints,
+                    context.client.progress_reporter().clone(),
                 )
                 .await?;
 
@@ -928,7 +1455,11 @@ impl Runnable for Job {
                     .await?;
             }
 
-            Watch { chain_id, raw } => {
+            Watch {
+                chain_id,
+                raw,
+                json,
+            } => {
                 let mut context = ClientContext::new(
                     storage.clone(),
                     options.inner.clone(),
@@ -948,12 +1479,65 @@ impl Runnable for Job {
                     }
                     if raw {
                         println!("{}", serde_json::to_string(&notification)?);
+                        continue;
+                    }
+                    let activity = ChainActivity::from_notification(&storage, notification).await?;
+                    if json {
+                        println!("{}", serde_json::to_string(&activity)?);
+                    } else {
+                        print!("{activity}");
                     }
                 }
                 info!("Notification stream ended.");
             }
 
-            Service { config, port } => {
+            Service {
+                config,
+                port,
+                metrics_port,
+                access,
+                query_cache,
+                sessions,
+            } => {
+                let genesis_hash = wallet.genesis_config().hash();
+                let mut tokens = BTreeSet::from_iter(access.api_tokens.iter().cloned());
+                let mut session_services = Vec::new();
+                for session in sessions
+                    .map(|path| SessionConfig::read_all(&path))
+                    .transpose()?
+                    .unwrap_or_default()
+                {
+                    let token = session.token()?;
+                    ensure!(
+                        tokens.insert(token.clone()),
+                        "the API token of session {:?} is already used",
+                        session.name
+                    );
+                    let (session_wallet, session_signer) = options.session_wallet(&session)?;
+                    ensure!(
+                        session_wallet.genesis_config().hash() == genesis_hash,
+                        "the wallet of session {:?} belongs to another network",
+                        session.name
+                    );
+                    let context = ClientContext::new(
+                        storage.clone(),
+                        options.inner.clone(),
+                        session_wallet,
+                        Box::new(session_signer.into_value()),
+                    );
+                    let default_chain = context.wallet().default_chain();
+                    let session_access = AccessConfig {
+                        api_tokens: vec![token],
+                        private: true,
+                        ..access.clone()
+                    };
+                    let service = NodeService::new(config.clone(), port, default_chain, context)
+                        .await
+                        .with_access(session_access)?
+                        .with_query_cache(query_cache.clone());
+                    session_services.push(Session::new(session.name, service));
+                }
+
                 let context = ClientContext::new(
                     storage.clone(),
                     options.inner.clone(),
@@ -962,11 +1546,28 @@ impl Runnable for Job {
                 );
 
                 let default_chain = context.wallet().default_chain();
-                let service = NodeService::new(config, port, default_chain, context).await;
+                let service = NodeService::new(config, port, default_chain, context)
+                    .await
+                    .with_access(access)?
+                    .with_query_cache(query_cache);
                 let cancellation_token = CancellationToken::new();
                 let child_token = cancellation_token.child_token();
+                if let Some(metrics_port) = metrics_port {
+                    #[cfg(with_metrics)]
+                    prometheus_server::start_metrics(
+                        ("0.0.0.0", metrics_port.get()),
+                        cancellation_token.child_token(),
+                    );
+                    #[cfg(not(with_metrics))]
+                    warn!(
+                        "Not serving metrics on port {metrics_port}: this binary was built \
+                        without the `metrics` feature"
+                    );
+                }
                 tokio::spawn(listen_for_shutdown_signals(cancellation_token));
-                service.run(child_token).await?;
+                service
+                    .run_with_sessions(session_services, child_token)
+                    .await?;
             }
 
             Faucet {
@@ -976,6 +1577,7 @@ impl Runnable for Job {
                 limit_rate_until,
                 max_chain_length,
                 config,
+                limits,
             } => {
                 let context = ClientContext::new(
                     storage.clone(),
@@ -1003,6 +1605,7 @@ impl Runnable for Job {
                     end_timestamp,
                     genesis_config,
                     config,
+                    limits,
                     storage,
                 )
                 .await?;
@@ -1012,6 +1615,38 @@ impl Runnable for Job {
                 faucet.run(child_token).await?;
             }
 
+            SyncDaemon { config, socket } => {
+                let context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let socket = options.sync_daemon_socket_path(socket)?;
+                if socket.exists() {
+                    warn!("Removing the stale control socket {}", socket.display());
+                    fs_err::remove_file(&socket)?;
+                }
+                let context = Arc::new(Mutex::new(context));
+                let cancellation_token = CancellationToken::new();
+                let daemon = sync_daemon::SyncDaemon::bind(
+                    &socket,
+                    context.clone(),
+                    cancellation_token.clone(),
+                )?;
+                let chain_listener =
+                    ChainListener::new(config, context, storage, cancellation_token.child_token())
+                        .run();
+                tokio::spawn(listen_for_shutdown_signals(cancellation_token.clone()));
+                let result = futures::select! {
+                    result = Box::pin(chain_listener).fuse() => result.map_err(Error::from),
+                    result = Box::pin(daemon.run()).fuse() => result.map_err(Error::from),
+                };
+                cancellation_token.cancel();
+                fs_err::remove_file(&socket)?;
+                result?;
+            }
+
             PublishModule {
                 contract,
                 service,
@@ -1143,6 +1778,7 @@ impl Runnable for Job {
                 json_argument,
                 json_argument_path,
                 required_application_ids,
+                dry_run,
             } => {
                 let mut context = ClientContext::new(
                     storage.clone(),
@@ -1153,8 +1789,17 @@ impl Runnable for Job {
 
                 let start_time = Instant::now();
                 let publisher = publisher.unwrap_or_else(|| context.default_chain());
-                info!("Publishing and creating application on chain {}", publisher);
                 let chain_client = context.make_chain_client(publisher).await?;
+                if dry_run {
+                    let (module_id, outcome) = context
+                        .dry_run_module_publication(&chain_client, contract, service, vm_runtime)
+                        .await?;
+                    println!("Module ID: {module_id}");
+                    print_dry_run(&outcome);
+                    println!("The creation of the application was not simulated.");
+                    return Ok(());
+                }
+                info!("Publishing and creating application on chain {}", publisher);
                 let parameters = read_json(json_parameters, json_parameters_path)?;
                 let argument = read_json(json_argument, json_argument_path)?;
                 let module_id = context
@@ -1299,6 +1944,102 @@ impl Runnable for Job {
                 );
             }
 
+            Proposal(ProposalCommand::Export {
+                chain_id,
+                output,
+                operations_path,
+                owner,
+            }) => {
+                let operations: Vec<Operation> = match operations_path {
+                    Some(path) => serde_json::from_str(&fs_err::read_to_string(path)?)?,
+                    None => Vec::new(),
+                };
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let start_time = Instant::now();
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                info!(
+                    "Exporting an unsigned block proposal for chain {}",
+                    chain_id
+                );
+                let mut chain_client = context.make_chain_client(chain_id).await?;
+                let wallet_owner = chain_client.preferred_owner();
+                if let Some(owner) = owner {
+                    chain_client.set_preferred_owner(owner);
+                }
+                let outcome = chain_client.unsigned_proposal(operations, Vec::new()).await;
+                match wallet_owner {
+                    Some(owner) => chain_client.set_preferred_owner(owner),
+                    None => chain_client.unset_preferred_owner(),
+                }
+                match outcome? {
+                    ClientOutcome::Committed(Some((proposal, blobs))) => {
+                        let block = &proposal.content.block;
+                        info!(
+                            "Proposal for block {} with {} operations and {} incoming bundles \
+                            must be signed by {}",
+                            block.height,
+                            block.operations.len(),
+                            block.incoming_bundles.len(),
+                            proposal.owner
+                        );
+                        let file = ProposalFile { proposal, blobs };
+                        fs_err::write(&output, serde_json::to_vec_pretty(&file)?)?;
+                        println!("{}", output.display());
+                    }
+                    ClientOutcome::Committed(None) => info!("There is no block to propose."),
+                    ClientOutcome::WaitForTimeout(timeout) => {
+                        info!("Please try again at {}", timeout.timestamp)
+                    }
+                }
+                context.update_wallet_from_client(&chain_client).await?;
+                info!(
+                    "Proposal exported in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+            }
+
+            Proposal(ProposalCommand::Submit { input }) => {
+                let file: ProposalFile<BlockProposal> =
+                    serde_json::from_str(&fs_err::read_to_string(&input)?)?;
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let start_time = Instant::now();
+                let chain_id = file.proposal.content.block.chain_id;
+                info!("Submitting a signed block proposal for chain {}", chain_id);
+                let chain_client = context.make_chain_client(chain_id).await?;
+                let certificate = chain_client
+                    .submit_signed_proposal(file.proposal, file.blobs)
+                    .await?;
+                context.update_wallet_from_client(&chain_client).await?;
+                println!("{}", certificate.hash());
+                info!(
+                    "Proposal submitted in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+            }
+
+            MultiOwner(MultiOwnerCommand::Status { chain_id }) => {
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                info!("Querying the round state of chain {}", chain_id);
+                let status = context.round_status(chain_id).await?;
+                print!("{status}");
+            }
+
             Wallet(WalletCommand::Init {
                 faucet: Some(faucet_url),
                 with_new_chain: true,
@@ -1378,11 +2119,74 @@ impl Runnable for Job {
                 );
             }
 
+            Wallet(WalletCommand::RequestChains {
+                faucet: faucet_url,
+                count,
+            }) => {
+                let start_time = Instant::now();
+                let mut owners = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let public_key = signer.mutate(|s| s.generate_new()).await?;
+                    owners.push(AccountOwner::from(public_key));
+                }
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                info!("Requesting {count} new chains using the faucet at address {faucet_url}");
+                let faucet = cli_wrappers::Faucet::new(faucet_url);
+                let outcome = faucet.claim_batch(&owners).await?;
+                for (description, owner) in outcome.chains.iter().zip(owners) {
+                    let chain_id = description.id();
+                    println!("{chain_id}");
+                    context.assign_new_chain_to_key(chain_id, owner).await?;
+                }
+                info!(
+                    "{} new chains requested and added in {} ms",
+                    outcome.chains.len(),
+                    start_time.elapsed().as_millis()
+                );
+            }
+
+            Wallet(WalletCommand::ImportChain { input, set_default }) => {
+                let start_time = Instant::now();
+                let bundle: ChainBundle = serde_json::from_str(&fs_err::read_to_string(input)?)?;
+                if let Some(owner) = bundle.chain.owner {
+                    if !signer.contains_key(&owner).await.expect("Signer error") {
+                        warn!("no keypair found in keystore for owner {owner}");
+                    }
+                }
+                let mut context = ClientContext::new(
+                    storage.clone(),
+                    options.inner.clone(),
+                    wallet,
+                    Box::new(signer.into_value()),
+                );
+                let chain = context.import_chain(bundle).await?;
+                if set_default {
+                    context
+                        .wallet_mut()
+                        .mutate(|w| w.set_default_chain(chain.chain_id))
+                        .await??;
+                }
+                println!("{}", chain.chain_id);
+                info!(
+                    "Chain imported at height {} in {} ms",
+                    chain.next_block_height,
+                    start_time.elapsed().as_millis()
+                );
+            }
+
             CreateGenesisConfig { .. }
             | Keygen
+            | Alias(_)
+            | Daemon { .. }
             | Net(_)
             | Storage { .. }
             | Wallet(_)
+            | Proposal(ProposalCommand::Sign { .. })
             | ExtractScriptFromMarkdown { .. }
             | HelpMarkdown => {
                 unreachable!()
@@ -1488,10 +2292,19 @@ struct ClientOptions {
     #[arg(long, default_value = "1000")]
     max_cache_entries: usize,
 
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    negative_cache_ttl_ms: u64,
+
     /// The WebAssembly runtime to use.
     #[arg(long)]
     wasm_runtime: Option<WasmRuntime>,
 
+    /// Restrictions on the HTTP requests performed by applications.
+    #[command(flatten)]
+    http_request_policy_config: HttpRequestPolicyConfig,
+
     /// The number of Tokio worker threads to use.
     #[arg(long, env = "LINERA_CLIENT_TOKIO_THREADS")]
     tokio_threads: Option<usize>,
@@ -1500,6 +2313,14 @@ struct ClientOptions {
     #[arg(long, env = "LINERA_CLIENT_TOKIO_BLOCKING_THREADS")]
     tokio_blocking_threads: Option<usize>,
 
+    /// The format of the log output: `plain`, `pretty`, or `json` for structured logs.
+    #[arg(long, env = "RUST_LOG_FORMAT", default_value = "plain")]
+    log_format: LogFormat,
+
+    /// The cache of the key unlocking the keystore, if it is encrypted.
+    #[arg(skip)]
+    key_cache: OnceLock<Arc<KeyCache>>,
+
     /// Subcommand.
     #[command(subcommand)]
     command: ClientCommand,
@@ -1515,6 +2336,7 @@ impl ClientOptions {
             max_cache_size: self.max_cache_size,
             max_entry_size: self.max_entry_size,
             max_cache_entries: self.max_cache_entries,
+            negative_cache_ttl_ms: self.negative_cache_ttl_ms,
         };
         CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -1530,11 +2352,17 @@ impl ClientOptions {
             .add_common_config(self.common_config())
             .await?;
         let genesis_config = self.wallet().await?.genesis_config().clone();
-        let output = Box::pin(store_config.run_with_storage(
-            &genesis_config,
-            self.wasm_runtime.with_wasm_default(),
-            job,
-        ))
+        let output = Box::pin(
+            store_config.run_with_storage(
+                &genesis_config,
+                self.wasm_runtime.with_wasm_default(),
+                self.http_request_policy_config
+                    .clone()
+                    .into_execution_runtime_config(),
+                None,
+                job,
+            ),
+        )
         .await?;
         Ok(output)
     }
@@ -1549,6 +2377,101 @@ impl ClientOptions {
         Ok(output)
     }
 
+    async fn run_backup_command(&self, command: &DatabaseToolCommand) -> Result<i32, Error> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "rocksdb")] {
+                let start_time = Instant::now();
+                let storage_config = self.storage_config()?;
+                match command {
+                    DatabaseToolCommand::Backup {
+                        backup_dir,
+                        max_backups,
+                    } => {
+                        let info = storage_config
+                            .add_common_config(self.common_config())
+                            .await?
+                            .create_backup(backup_dir.clone(), *max_backups)
+                            .await?;
+                        info!(
+                            "Backup {} of {} files and {} bytes created in {} ms",
+                            info.backup_id,
+                            info.num_files,
+                            info.size,
+                            start_time.elapsed().as_millis()
+                        );
+                    }
+                    DatabaseToolCommand::RestoreBackup {
+                        backup_dir,
+                        backup_id,
+                    } => {
+                        storage_config
+                            .add_common_config(self.common_config())
+                            .await?
+                            .restore_backup(backup_dir.clone(), *backup_id)
+                            .await?;
+                        info!("Backup restored in {} ms", start_time.elapsed().as_millis());
+                    }
+                    DatabaseToolCommand::ListBackups { backup_dir } => {
+                        let backups =
+                            linera_service::storage::StoreConfig::list_backups(backup_dir)?;
+                        info!("The list of backups is:");
+                        for info in backups {
+                            let time = chrono::DateTime::from_timestamp(info.timestamp, 0)
+                                .unwrap_or_default();
+                            println!(
+                                "{}\t{}\t{} files\t{} bytes",
+                                info.backup_id, time, info.num_files, info.size
+                            );
+                        }
+                    }
+                    _ => unreachable!("not a backup command"),
+                }
+                Ok(0)
+            } else {
+                let _ = command;
+                bail!("Backups require the feature 'rocksdb'");
+            }
+        }
+    }
+
+    async fn run_migration_command(&self, command: &DatabaseToolCommand) -> Result<i32, Error> {
+        cfg_if::cfg_if! {
+            if #[cfg(all(feature = "rocksdb", feature = "scylladb"))] {
+                let start_time = Instant::now();
+                let store_config = self
+                    .storage_config()?
+                    .add_common_config(self.common_config())
+                    .await?;
+                match command {
+                    DatabaseToolCommand::BackfillMigration { batch_size } => {
+                        let keys_copied = store_config.backfill_migration(*batch_size).await?;
+                        info!(
+                            "{keys_copied} keys copied to the new store in {} ms",
+                            start_time.elapsed().as_millis()
+                        );
+                    }
+                    DatabaseToolCommand::VerifyMigration { batch_size } => {
+                        let divergences = store_config.verify_migration(*batch_size).await?;
+                        info!(
+                            "The stores were compared in {} ms",
+                            start_time.elapsed().as_millis()
+                        );
+                        if divergences > 0 {
+                            info!("{divergences} keys differ between the stores");
+                            return Ok(1);
+                        }
+                        info!("The new store holds the same data as the old store");
+                    }
+                    _ => unreachable!("not a migration command"),
+                }
+                Ok(0)
+            } else {
+                let _ = command;
+                bail!("Migrations require the features 'rocksdb' and 'scylladb'");
+            }
+        }
+    }
+
     async fn initialize_storage(&self) -> Result<(), Error> {
         let storage_config = self.storage_config()?;
         debug!("Initializing storage using configuration: {storage_config}");
@@ -1561,15 +2484,86 @@ impl ClientOptions {
     }
 
     async fn wallet(&self) -> Result<WalletState<persistent::File<Wallet>>, Error> {
-        let wallet = persistent::File::read(&self.wallet_path()?)?;
+        let wallet =
+            persistent::File::read_waiting(&self.wallet_path()?, self.inner.wallet_lock_timeout)?;
         Ok(WalletState::new(wallet))
     }
 
     async fn signer(&self) -> Result<SignerState<persistent::File<InMemorySigner>>, Error> {
-        let signer = persistent::File::read(&self.keystore_path()?)?;
+        let signer = persistent::File::read_encrypted_waiting(
+            &self.keystore_path()?,
+            self.key_cache(),
+            self.inner.wallet_lock_timeout,
+        )?;
         Ok(SignerState::new(signer))
     }
 
+    /// Reads the wallet and the keystore of a session of the node service.
+    fn session_wallet(
+        &self,
+        session: &SessionConfig,
+    ) -> Result<(SessionWallet, SessionSigner), Error> {
+        let wallet =
+            persistent::File::read_waiting(&session.wallet, self.inner.wallet_lock_timeout)?;
+        let name = session.name.clone();
+        let passphrase_env = session.passphrase_env.clone();
+        let key_cache = Arc::new(KeyCache::new(
+            self.inner.keystore_unlock_timeout,
+            move || {
+                let variable = passphrase_env.as_ref().ok_or_else(|| {
+                    io::Error::other(format!(
+                        "the keystore of session {name:?} is encrypted: set its `passphrase_env`"
+                    ))
+                })?;
+                env::var(variable).map_err(|_| {
+                    io::Error::other(format!(
+                        "the keystore passphrase of session {name:?} must be set in `{variable}`"
+                    ))
+                })
+            },
+        ));
+        let signer = persistent::File::read_encrypted_waiting(
+            &session.keystore,
+            key_cache,
+            self.inner.wallet_lock_timeout,
+        )?;
+        Ok((WalletState::new(wallet), SignerState::new(signer)))
+    }
+
+    /// Returns the cache of the key unlocking the keystore. The passphrase is read from
+    /// the environment variable `LINERA_KEYSTORE_PASSPHRASE` if it is set, and requested
+    /// interactively otherwise.
+    fn key_cache(&self) -> Arc<KeyCache> {
+        self.key_cache
+            .get_or_init(|| {
+                let passphrase_env_var = self.passphrase_env_var();
+                Arc::new(KeyCache::new(
+                    self.inner.keystore_unlock_timeout,
+                    move || match env::var(&passphrase_env_var) {
+                        Ok(passphrase) => Ok(passphrase),
+                        Err(_) => rpassword::prompt_password("Keystore passphrase: "),
+                    },
+                ))
+            })
+            .clone()
+    }
+
+    /// Returns a new passphrase to encrypt the keystore, asking for it twice if it is not
+    /// set in the environment.
+    fn new_passphrase(&self) -> Result<String, Error> {
+        if let Ok(passphrase) = env::var(self.passphrase_env_var()) {
+            return Ok(passphrase);
+        }
+        let passphrase = rpassword::prompt_password("New keystore passphrase: ")?;
+        let confirmation = rpassword::prompt_password("Confirm keystore passphrase: ")?;
+        ensure!(passphrase == confirmation, "The passphrases do not match");
+        Ok(passphrase)
+    }
+
+    fn passphrase_env_var(&self) -> String {
+        format!("LINERA_KEYSTORE_PASSPHRASE{}", self.suffix())
+    }
+
     fn suffix(&self) -> String {
         self.inner
             .with_wallet
@@ -1632,6 +2626,13 @@ impl ClientOptions {
         Ok(config_path.join("wallet.json"))
     }
 
+    fn sync_daemon_socket_path(&self, socket: Option<PathBuf>) -> Result<PathBuf, Error> {
+        match socket {
+            Some(path) => Ok(path),
+            None => Ok(self.wallet_path()?.with_extension("sock")),
+        }
+    }
+
     fn keystore_path(&self) -> Result<PathBuf, Error> {
         if let Some(path) = &self.inner.keystore_path {
             return Ok(path.clone());
@@ -1659,16 +2660,47 @@ impl ClientOptions {
 
     pub fn create_keystore(
         &self,
-        testing_prng_seed: Option<u64>,
+        signer: InMemorySigner,
     ) -> Result<SignerState<persistent::File<InMemorySigner>>, Error> {
         let keystore_path = self.keystore_path()?;
         if keystore_path.exists() {
             bail!("Keystore already exists: {}", keystore_path.display());
         }
-        let signer_state =
-            SignerState::read_or_create(&keystore_path, InMemorySigner::new(testing_prng_seed))?;
+        let signer_state = SignerState::read_or_create(&keystore_path, signer)?;
         Ok(signer_state)
     }
+
+    /// Returns the signer of a new keystore: derived from a new or an existing mnemonic
+    /// phrase if requested, and otherwise generating random keys.
+    fn new_signer(
+        &self,
+        testing_prng_seed: Option<u64>,
+        mnemonic: bool,
+        recover_mnemonic: bool,
+        recover_keys: u32,
+    ) -> Result<InMemorySigner, Error> {
+        if mnemonic {
+            let phrase = generate_mnemonic(&mut rand::rngs::OsRng);
+            println!(
+                "Write down this mnemonic phrase and keep it secret. \
+                 It is the only way to recover the keys of this wallet:\n\n{phrase}\n"
+            );
+            return Ok(InMemorySigner::from_mnemonic(&phrase, "")?);
+        }
+        if !recover_mnemonic {
+            return Ok(InMemorySigner::new(testing_prng_seed));
+        }
+        let phrase = match env::var("LINERA_MNEMONIC") {
+            Ok(phrase) => phrase,
+            Err(_) => rpassword::prompt_password("Mnemonic phrase: ")?,
+        };
+        let mut signer = InMemorySigner::from_mnemonic(&phrase, "")?;
+        for _ in 0..recover_keys {
+            let public_key = signer.derive_next_key()?;
+            println!("Recovered key of owner {}", AccountOwner::from(public_key));
+        }
+        Ok(signer)
+    }
 }
 
 struct DatabaseToolJob<'a>(&'a DatabaseToolCommand);
@@ -1758,6 +2790,43 @@ impl RunnableWithStore for DatabaseToolJob<'_> {
                     println!("{}", id);
                 }
             }
+            DatabaseToolCommand::ArchiveChain { chain_id, archive } => {
+                let archive_store = archive.connect().await?;
+                let storage = DbStorage::<S, _>::connect(&config, &namespace, None)
+                    .await?
+                    .with_archive_store(Some(archive_store));
+                storage.archive_chain(*chain_id).await?;
+                info!(
+                    "Chain {chain_id} archived in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+            }
+            DatabaseToolCommand::RestoreChain { chain_id, archive } => {
+                let archive_store = archive.connect().await?;
+                let storage = DbStorage::<S, _>::connect(&config, &namespace, None)
+                    .await?
+                    .with_archive_store(Some(archive_store));
+                if storage.restore_chain(*chain_id).await? {
+                    info!(
+                        "Chain {chain_id} restored in {} ms",
+                        start_time.elapsed().as_millis()
+                    );
+                } else {
+                    info!("Chain {chain_id} is not archived");
+                }
+            }
+            DatabaseToolCommand::Backup { .. }
+            | DatabaseToolCommand::RestoreBackup { .. }
+            | DatabaseToolCommand::ListBackups { .. } => {
+                unreachable!("backups do not run with a generic store")
+            }
+            DatabaseToolCommand::Copy { .. } => {
+                unreachable!("copies run with two stores")
+            }
+            DatabaseToolCommand::BackfillMigration { .. }
+            | DatabaseToolCommand::VerifyMigration { .. } => {
+                unreachable!("migrations do not run with a generic store")
+            }
         }
         Ok(0)
     }
@@ -1766,7 +2835,7 @@ impl RunnableWithStore for DatabaseToolJob<'_> {
 fn main() -> anyhow::Result<()> {
     let options = ClientOptions::init();
 
-    linera_base::tracing::init(&options.command.log_file_name());
+    linera_base::tracing::init_with_format(&options.command.log_file_name(), options.log_format);
 
     let mut runtime = if options.tokio_threads == Some(1) {
         tokio::runtime::Builder::new_current_thread()
@@ -1855,6 +2924,7 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             message_byte_price,
             service_as_oracle_query_price,
             http_request_price,
+            name_registration_price,
             maximum_fuel_per_block,
             maximum_service_oracle_execution_ms,
             maximum_block_size,
@@ -1866,6 +2936,7 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             maximum_bytes_written_per_block,
             maximum_oracle_response_bytes,
             maximum_http_response_bytes,
+            maximum_application_storage_bytes,
             http_request_timeout_ms,
             http_request_allow_list,
             testing_prng_seed,
@@ -1895,6 +2966,8 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 service_as_oracle_query: service_as_oracle_query_price
                     .unwrap_or(existing_policy.service_as_oracle_query),
                 http_request: http_request_price.unwrap_or(existing_policy.http_request),
+                name_registration: name_registration_price
+                    .unwrap_or(existing_policy.name_registration),
                 maximum_fuel_per_block: maximum_fuel_per_block
                     .unwrap_or(existing_policy.maximum_fuel_per_block),
                 maximum_service_oracle_execution_ms: maximum_service_oracle_execution_ms
@@ -1916,6 +2989,8 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                     .unwrap_or(existing_policy.maximum_oracle_response_bytes),
                 maximum_http_response_bytes: maximum_http_response_bytes
                     .unwrap_or(existing_policy.maximum_http_response_bytes),
+                maximum_application_storage_bytes: maximum_application_storage_bytes
+                    .unwrap_or(existing_policy.maximum_application_storage_bytes),
                 http_request_timeout_ms: http_request_timeout_ms
                     .unwrap_or(existing_policy.http_request_timeout_ms),
                 http_request_allow_list: http_request_allow_list
@@ -1932,9 +3007,12 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 .unwrap_or_else(Timestamp::now);
 
             let origin: ChainOrigin = ChainOrigin::Root(*admin_root);
-            let mut signer = options.create_keystore(*testing_prng_seed)?;
+            let mut signer = options.create_keystore(InMemorySigner::new(*testing_prng_seed))?;
             let admin_public_key = signer.mutate(|s| s.generate_new()).await?;
             let committee = committee_config.clone().into_committee(policy.clone());
+            committee
+                .check_bls_keys()
+                .context("invalid BLS keys in the committee")?;
             let committees = [(
                 Epoch::ZERO,
                 bcs::to_bytes(&committee).expect("serializing a committee should not fail"),
@@ -2041,6 +3119,36 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             Ok(0)
         }
 
+        ClientCommand::Proposal(ProposalCommand::Sign { input, output }) => {
+            let start_time = Instant::now();
+            let file: ProposalFile<UnsignedBlockProposal> =
+                serde_json::from_str(&fs_err::read_to_string(input)?)?;
+            let block = &file.proposal.content.block;
+            info!(
+                "Signing block {} of chain {} in round {} as {}, with {} operations and {} \
+                incoming bundles",
+                block.height,
+                block.chain_id,
+                file.proposal.content.round,
+                file.proposal.owner,
+                block.operations.len(),
+                block.incoming_bundles.len()
+            );
+            let signer = options.signer().await?.into_value();
+            let proposal = file
+                .proposal
+                .sign(&signer)
+                .await
+                .map_err(|error| anyhow!("Failed to sign the proposal: {error}"))?;
+            let file = ProposalFile {
+                proposal,
+                blobs: file.blobs,
+            };
+            fs_err::write(output, serde_json::to_vec_pretty(&file)?)?;
+            info!("Proposal signed in {} ms", start_time.elapsed().as_millis());
+            Ok(0)
+        }
+
         ClientCommand::Net(net_command) => match net_command {
             #[cfg(feature = "kubernetes")]
             NetCommand::Up {
@@ -2133,10 +3241,69 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             }
         },
 
+        ClientCommand::Storage(
+            command @ (DatabaseToolCommand::Backup { .. }
+            | DatabaseToolCommand::RestoreBackup { .. }
+            | DatabaseToolCommand::ListBackups { .. }),
+        ) => options.run_backup_command(command).await,
+
+        ClientCommand::Storage(
+            command @ (DatabaseToolCommand::BackfillMigration { .. }
+            | DatabaseToolCommand::VerifyMigration { .. }),
+        ) => options.run_migration_command(command).await,
+
+        ClientCommand::Storage(DatabaseToolCommand::Copy {
+            from,
+            to,
+            checkpoint,
+            batch_size,
+        }) => {
+            let start_time = Instant::now();
+            let source = from.add_common_config(options.common_config()).await?;
+            let target = to.add_common_config(options.common_config()).await?;
+            let copy_options = CopyOptions {
+                batch_size: *batch_size,
+                checkpoint_path: checkpoint.clone(),
+                progress_reporter: options.inner.progress_reporter(),
+            };
+            let keys_copied = source.copy_namespace(target, copy_options).await?;
+            info!(
+                "{keys_copied} keys copied from {from} to {to} in {} ms",
+                start_time.elapsed().as_millis()
+            );
+            Ok(0)
+        }
+
         ClientCommand::Storage(command) => {
             Ok(options.run_with_store(DatabaseToolJob(command)).await?)
         }
 
+        ClientCommand::Alias(alias_command) => {
+            let mut wallet = options.wallet().await?;
+            match alias_command {
+                AliasCommand::Add {
+                    name,
+                    account,
+                    force,
+                } => {
+                    wallet
+                        .mutate(|w| w.address_book.add(name.clone(), *account, *force))
+                        .await??;
+                    info!("Added the alias {name} for {account}");
+                }
+                AliasCommand::Remove { name } => {
+                    let account = wallet.mutate(|w| w.address_book.remove(name)).await??;
+                    info!("Removed the alias {name} of {account}");
+                }
+                AliasCommand::List => {
+                    for (name, account) in wallet.address_book.iter() {
+                        println!("{name}: {account}");
+                    }
+                }
+            }
+            Ok(0)
+        }
+
         ClientCommand::Wallet(wallet_command) => match wallet_command {
             WalletCommand::Show {
                 chain_id,
@@ -2168,6 +3335,32 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 Ok(0)
             }
 
+            WalletCommand::Encrypt => {
+                let start_time = Instant::now();
+                let keystore_path = options.keystore_path()?;
+                let mut keystore = persistent::File::<InMemorySigner>::read_encrypted(
+                    &keystore_path,
+                    options.key_cache(),
+                )?;
+                ensure!(
+                    !keystore.is_encrypted(),
+                    "Keystore is already encrypted: {}",
+                    keystore_path.display()
+                );
+                let passphrase = options.new_passphrase()?;
+                ensure!(!passphrase.is_empty(), "The passphrase must not be empty");
+                keystore.encrypt(Arc::new(KeyCache::new(
+                    options.inner.keystore_unlock_timeout,
+                    move || Ok(passphrase.clone()),
+                )))?;
+                keystore.persist().await?;
+                info!(
+                    "Keystore encrypted in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                Ok(0)
+            }
+
             WalletCommand::SetDefault { chain_id } => {
                 let start_time = Instant::now();
                 options
@@ -2232,7 +3425,30 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 Ok(0)
             }
 
-            WalletCommand::RequestChain { .. } => {
+            WalletCommand::ExportChain { chain_id, output } => {
+                let wallet = options.wallet().await?;
+                let chain_id = match chain_id {
+                    Some(chain_id) => *chain_id,
+                    None => wallet
+                        .default_chain()
+                        .context("No chain specified and no default chain in the wallet")?,
+                };
+                let bundle = ChainBundle::new(&wallet, chain_id)?;
+                fs_err::write(output, serde_json::to_vec_pretty(&bundle)?)?;
+                info!(
+                    "Chain {chain_id} exported at height {}",
+                    bundle.chain.next_block_height
+                );
+                if let Some(owner) = bundle.chain.owner {
+                    info!("The importing wallet's keystore needs the key of {owner}");
+                }
+                println!("{}", output.display());
+                Ok(0)
+            }
+
+            WalletCommand::RequestChain { .. }
+            | WalletCommand::RequestChains { .. }
+            | WalletCommand::ImportChain { .. } => {
                 options.run_with_storage(Job(options.clone())).await??;
                 Ok(0)
             }
@@ -2243,6 +3459,9 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 with_new_chain,
                 with_other_chains,
                 testing_prng_seed,
+                mnemonic,
+                recover_mnemonic,
+                recover_keys,
             } => {
                 let start_time = Instant::now();
                 let genesis_config: GenesisConfig = match (genesis_config_path, faucet) {
@@ -2275,7 +3494,13 @@ Make sure to use a Linera client compatible with this network.
                     (_, _) => bail!("Either --faucet or --genesis must be specified, but not both"),
                 };
                 let timestamp = genesis_config.timestamp;
-                let mut keystore = options.create_keystore(*testing_prng_seed)?;
+                let signer = options.new_signer(
+                    *testing_prng_seed,
+                    *mnemonic,
+                    *recover_mnemonic,
+                    *recover_keys,
+                )?;
+                let mut keystore = options.create_keystore(signer)?;
                 keystore.persist().await?;
                 options
                     .create_wallet(genesis_config)?
@@ -2303,6 +3528,48 @@ Make sure to use a Linera client compatible with this network.
             }
         },
 
+        ClientCommand::Daemon { socket, command } => {
+            let socket = options.sync_daemon_socket_path(socket.clone())?;
+            let request = match command {
+                DaemonCommand::Status => sync_daemon::Request::Status,
+                DaemonCommand::Balance { chain_id, owner } => sync_daemon::Request::Balance {
+                    chain_id: *chain_id,
+                    owner: *owner,
+                },
+                DaemonCommand::Sync { chain_id } => sync_daemon::Request::Synchronize {
+                    chain_id: *chain_id,
+                },
+                DaemonCommand::ProcessInbox { chain_id } => sync_daemon::Request::ProcessInbox {
+                    chain_id: *chain_id,
+                },
+                DaemonCommand::Stop => sync_daemon::Request::Stop,
+            };
+            let response = sync_daemon::request(&socket, &request)
+                .await
+                .with_context(|| format!("no sync daemon listening on {}", socket.display()))?;
+            match response {
+                sync_daemon::Response::Error(error) => bail!("the sync daemon failed: {error}"),
+                sync_daemon::Response::Status(chains) => {
+                    for chain in chains {
+                        let default = if chain.is_default { " (default)" } else { "" };
+                        println!(
+                            "{}{default}: next block height {}",
+                            chain.chain_id, chain.next_block_height
+                        );
+                    }
+                }
+                sync_daemon::Response::Balance(balance) => println!("{balance}"),
+                sync_daemon::Response::Synchronized { next_block_height } => {
+                    println!("Synchronized up to block height {next_block_height}")
+                }
+                sync_daemon::Response::InboxProcessed { blocks } => {
+                    println!("Processed the inbox in {blocks} blocks")
+                }
+                sync_daemon::Response::Stopped => println!("The sync daemon is stopping"),
+            }
+            Ok(0)
+        }
+
         _ => {
             options.run_with_storage(Job(options.clone())).await??;
             Ok(0)
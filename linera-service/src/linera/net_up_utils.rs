@@ -96,8 +96,22 @@ impl StorageConfigProvider {
             StorageConfig::DynamoDb { .. } => Ok(Database::DynamoDb),
             #[cfg(feature = "scylladb")]
             StorageConfig::ScyllaDb { .. } => Ok(Database::ScyllaDb),
+            #[cfg(feature = "postgres")]
+            StorageConfig::Postgres { .. } => {
+                anyhow::bail!("Not possible to work with PostgreSQL")
+            }
+            #[cfg(feature = "foundationdb")]
+            StorageConfig::FoundationDb { .. } => {
+                anyhow::bail!("Not possible to work with FoundationDB")
+            }
+            #[cfg(feature = "redis")]
+            StorageConfig::Redis { .. } => Ok(Database::Redis),
             #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
             StorageConfig::DualRocksDbScyllaDb { .. } => Ok(Database::DualRocksDbScyllaDb),
+            #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+            StorageConfig::MigratingRocksDbScyllaDb { .. } => {
+                anyhow::bail!("Not possible to work with a migration")
+            }
         }
     }
 }
@@ -0,0 +1,347 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A history of key metrics kept in memory, so that operators without a Prometheus server
+//! can still see how a validator behaved before an incident.
+//!
+//! The metrics registered with Prometheus are sampled at a fixed interval, and the samples
+//! of the retention period are kept in a ring buffer. Each metric family gives one series
+//! per sample, summed over all label values: counters and gauges give their value, and
+//! histograms and summaries give a `<name>_count` and a `<name>_sum` series. As with
+//! Prometheus, counters are cumulative, so the rate of a counter is the difference between
+//! two samples.
+//!
+//! The history is served by the admin API:
+//!
+//! * `GET /metrics/history`: the samples, as JSON. The optional `metrics` parameter selects
+//!   a comma-separated list of series, and `since` and `until` select a time range, in
+//!   milliseconds since the Unix epoch.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use prometheus::proto::{MetricFamily, MetricType};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// The metric families that are recorded if none are configured.
+pub const DEFAULT_METRICS: &[&str] = &[
+    "linera_num_blocks",
+    "linera_transaction_count",
+    "linera_certificates_signed",
+    "linera_block_execution_latency",
+    "linera_server_request_count",
+    "linera_server_request_error",
+    "linera_server_request_latency",
+    "linera_proxy_request_count",
+    "linera_proxy_request_error",
+    "linera_proxy_request_latency",
+    "linera_loaded_chain_workers",
+    "linera_chain_worker_reserved_memory",
+];
+
+/// The values of the recorded series at one point in time.
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    /// The time of the sample, in milliseconds since the Unix epoch.
+    timestamp_ms: u64,
+    values: BTreeMap<String, f64>,
+}
+
+/// The recent samples of a set of metric families.
+#[derive(Debug)]
+pub struct MetricsHistory {
+    interval: Duration,
+    capacity: usize,
+    metrics: Vec<String>,
+    samples: Mutex<VecDeque<Sample>>,
+}
+
+/// The values of one series over time.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Series {
+    pub name: String,
+    /// The samples, as pairs of a time in milliseconds since the Unix epoch and a value.
+    pub points: Vec<(u64, f64)>,
+}
+
+/// The recorded history, as returned by the admin API.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HistoryResponse {
+    pub interval_ms: u64,
+    pub series: Vec<Series>,
+}
+
+/// The parameters of a history request.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// A comma-separated list of series. All series are returned if this is not set.
+    pub metrics: Option<String>,
+    /// The earliest sample to return, in milliseconds since the Unix epoch.
+    pub since: Option<u64>,
+    /// The latest sample to return, in milliseconds since the Unix epoch.
+    pub until: Option<u64>,
+}
+
+impl MetricsHistory {
+    /// Creates an empty history that keeps the samples of the given metric families for the
+    /// retention period. If `metrics` is empty, [`DEFAULT_METRICS`] are recorded.
+    pub fn new(interval: Duration, retention: Duration, metrics: Vec<String>) -> Self {
+        let interval = interval.max(Duration::from_millis(1));
+        let capacity = (retention.as_millis() / interval.as_millis()).max(1) as usize;
+        let metrics = if metrics.is_empty() {
+            DEFAULT_METRICS.iter().map(ToString::to_string).collect()
+        } else {
+            metrics
+        };
+        Self {
+            interval,
+            capacity,
+            metrics,
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns the interval between two samples.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Records the current values of the metrics registered with Prometheus.
+    pub fn sample(&self) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        let values = self.series_values(&prometheus::gather());
+        self.record(timestamp_ms, values);
+    }
+
+    fn series_values(&self, families: &[MetricFamily]) -> BTreeMap<String, f64> {
+        let mut values = BTreeMap::new();
+        for family in families {
+            let name = family.get_name();
+            if !self.metrics.iter().any(|metric| metric == name) {
+                continue;
+            }
+            let metrics = family.get_metric();
+            match family.get_field_type() {
+                MetricType::COUNTER => {
+                    let value = metrics.iter().map(|m| m.get_counter().get_value()).sum();
+                    values.insert(name.to_string(), value);
+                }
+                MetricType::GAUGE => {
+                    let value = metrics.iter().map(|m| m.get_gauge().get_value()).sum();
+                    values.insert(name.to_string(), value);
+                }
+                MetricType::UNTYPED => {
+                    let value = metrics.iter().map(|m| m.get_untyped().get_value()).sum();
+                    values.insert(name.to_string(), value);
+                }
+                MetricType::HISTOGRAM => {
+                    let count = metrics
+                        .iter()
+                        .map(|m| m.get_histogram().get_sample_count() as f64)
+                        .sum();
+                    let sum = metrics
+                        .iter()
+                        .map(|m| m.get_histogram().get_sample_sum())
+                        .sum();
+                    values.insert(format!("{name}_count"), count);
+                    values.insert(format!("{name}_sum"), sum);
+                }
+                MetricType::SUMMARY => {
+                    let count = metrics
+                        .iter()
+                        .map(|m| m.get_summary().get_sample_count() as f64)
+                        .sum();
+                    let sum = metrics
+                        .iter()
+                        .map(|m| m.get_summary().get_sample_sum())
+                        .sum();
+                    values.insert(format!("{name}_count"), count);
+                    values.insert(format!("{name}_sum"), sum);
+                }
+            }
+        }
+        values
+    }
+
+    fn record(&self, timestamp_ms: u64, values: BTreeMap<String, f64>) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(Sample {
+            timestamp_ms,
+            values,
+        });
+    }
+
+    /// Returns the recorded series that match the query.
+    pub fn query(&self, query: &HistoryQuery) -> HistoryResponse {
+        let selected = query.metrics.as_ref().map(|metrics| {
+            metrics
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<_>>()
+        });
+        let since = query.since.unwrap_or(0);
+        let until = query.until.unwrap_or(u64::MAX);
+        let mut series = BTreeMap::<&str, Vec<(u64, f64)>>::new();
+        let samples = self.samples.lock().unwrap();
+        for sample in samples
+            .iter()
+            .filter(|sample| (since..=until).contains(&sample.timestamp_ms))
+        {
+            for (name, value) in &sample.values {
+                if selected
+                    .as_ref()
+                    .is_some_and(|selected| !selected.contains(&name.as_str()))
+                {
+                    continue;
+                }
+                series
+                    .entry(name)
+                    .or_default()
+                    .push((sample.timestamp_ms, *value));
+            }
+        }
+        HistoryResponse {
+            interval_ms: self.interval.as_millis() as u64,
+            series: series
+                .into_iter()
+                .map(|(name, points)| Series {
+                    name: name.to_string(),
+                    points,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Samples the metrics in the background until the shutdown signal.
+pub fn start_sampling(history: Arc<MetricsHistory>, shutdown_signal: CancellationToken) {
+    info!(
+        "Recording {} metrics every {} ms",
+        history.metrics.len(),
+        history.interval.as_millis()
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(history.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => history.sample(),
+                () = shutdown_signal.cancelled() => break,
+            }
+        }
+    });
+}
+
+/// Returns the routes of the admin API that serve the history.
+pub fn router(history: Arc<MetricsHistory>) -> Router {
+    Router::new()
+        .route("/metrics/history", get(metrics_history))
+        .with_state(history)
+}
+
+async fn metrics_history(
+    State(history): State<Arc<MetricsHistory>>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<HistoryResponse> {
+    Json(history.query(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+
+    use super::*;
+
+    fn values(pairs: &[(&str, f64)]) -> BTreeMap<String, f64> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_history_retention_and_query() {
+        let history = MetricsHistory::new(
+            Duration::from_secs(15),
+            Duration::from_secs(45),
+            vec!["a".to_string(), "b".to_string()],
+        );
+        for (i, timestamp_ms) in [0, 15_000, 30_000, 45_000].into_iter().enumerate() {
+            history.record(timestamp_ms, values(&[("a", i as f64), ("b", 10.0)]));
+        }
+
+        // Only the samples of the last 45 seconds are kept.
+        let response = history.query(&HistoryQuery::default());
+        assert_eq!(response.interval_ms, 15_000);
+        assert_eq!(
+            response.series,
+            vec![
+                Series {
+                    name: "a".to_string(),
+                    points: vec![(15_000, 1.0), (30_000, 2.0), (45_000, 3.0)],
+                },
+                Series {
+                    name: "b".to_string(),
+                    points: vec![(15_000, 10.0), (30_000, 10.0), (45_000, 10.0)],
+                },
+            ]
+        );
+
+        let response = history.query(&HistoryQuery {
+            metrics: Some("a".to_string()),
+            since: Some(20_000),
+            until: Some(40_000),
+        });
+        assert_eq!(
+            response.series,
+            vec![Series {
+                name: "a".to_string(),
+                points: vec![(30_000, 2.0)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_series_values() {
+        let registry = Registry::new();
+        let counter = IntCounterVec::new(Opts::new("requests", "Requests"), &["method"]).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        let histogram =
+            HistogramVec::new(HistogramOpts::new("latency", "Latency"), &["method"]).unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        counter.with_label_values(&["a"]).inc_by(2);
+        counter.with_label_values(&["b"]).inc_by(3);
+        histogram.with_label_values(&["a"]).observe(1.5);
+        histogram.with_label_values(&["b"]).observe(2.5);
+
+        let history = MetricsHistory::new(
+            Duration::from_secs(15),
+            Duration::from_secs(60),
+            vec!["requests".to_string(), "latency".to_string()],
+        );
+        assert_eq!(
+            history.series_values(&registry.gather()),
+            values(&[
+                ("latency_count", 2.0),
+                ("latency_sum", 4.0),
+                ("requests", 5.0)
+            ])
+        );
+    }
+}
@@ -8,65 +8,308 @@ use async_graphql::{
     ScalarType, Schema, SimpleObject, Subscription,
 };
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{extract::Path, http::StatusCode, response, response::IntoResponse, Extension, Router};
-use futures::{lock::Mutex, Future, FutureExt as _};
+use axum::{
+    extract::Path, http::StatusCode, middleware, response, response::IntoResponse, Extension,
+    Router,
+};
+use futures::{lock::Mutex, stream, Future, FutureExt as _, StreamExt as _};
 use linera_base::{
-    crypto::{CryptoError, CryptoHash},
+    crypto::{CryptoError, CryptoHash, ValidatorPublicKey},
     data_types::{
-        Amount, ApplicationDescription, ApplicationPermissions, Bytecode, Epoch, TimeDelta,
+        Amount, ApplicationDescription, ApplicationPermissions, BlobContent, BlockHeight, Bytecode,
+        Epoch, Event, TimeDelta, Timestamp,
     },
-    identifiers::{AccountOwner, ApplicationId, ChainId, ModuleId},
+    identifiers::{AccountOwner, ApplicationId, ChainId, ModuleId, StreamId, StreamName},
     ownership::{ChainOwnership, TimeoutConfig},
+    time::Instant,
     vm::VmRuntime,
     BcsHexParseError,
 };
 use linera_chain::{
+    state_proof::StateProof,
     types::{ConfirmedBlock, GenericCertificate},
     ChainStateView,
 };
-use linera_client::chain_listener::{ChainListener, ChainListenerConfig, ClientContext};
+use linera_client::chain_listener::{
+    ChainActivator, ChainListener, ChainListenerConfig, ClientContext,
+};
 use linera_core::{
     client::{ChainClient, ChainClientError},
     data_types::ClientOutcome,
-    worker::Notification,
+    worker::{Notification, Reason},
 };
 use linera_execution::{
     committee::Committee,
-    system::{AdminOperation, Recipient},
-    Operation, Query, QueryOutcome, QueryResponse, SystemOperation,
+    system::{AdminOperation, NameTarget, Recipient},
+    ContractLog, Operation, Query, QueryOutcome, QueryResponse, SystemOperation,
 };
-use linera_sdk::linera_base_types::BlobContent;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error as ThisError;
 use tokio::sync::OwnedRwLockReadGuard;
 use tokio_util::sync::CancellationToken;
-use tower_http::cors::CorsLayer;
 use tracing::{debug, error, info, instrument, trace};
 
+pub use self::{
+    access::AccessConfig,
+    query_cache::QueryCacheConfig,
+    sessions::{Session, SessionConfig},
+};
+use self::{
+    access::{AccessControl, Authorized},
+    query_cache::{QueryCache, QueryKey},
+};
 use crate::util;
 
+mod access;
+mod query_cache;
+mod rest;
+mod sessions;
+
+#[cfg(with_metrics)]
+mod metrics {
+    use std::sync::{Arc, LazyLock, Mutex};
+
+    use async_graphql::{
+        extensions::{
+            Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+        },
+        parser::types::{ExecutableDocument, OperationType, Selection},
+        Response, ServerResult, Variables,
+    };
+    use linera_base::{
+        prometheus_util::{
+            exponential_bucket_latencies, register_histogram_vec, register_int_counter_vec,
+            register_int_gauge_vec,
+        },
+        time::Instant,
+    };
+    use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec};
+
+    pub static QUERY_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec(
+            "node_service_query_latency",
+            "Node service request latency, by operation type and root field",
+            &["operation_type", "field"],
+            exponential_bucket_latencies(10_000.0),
+        )
+    });
+
+    pub static QUERY_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "node_service_query_errors",
+            "Node service requests that returned errors, by operation type and root field",
+            &["operation_type", "field"],
+        )
+    });
+
+    pub static QUERY_CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "node_service_query_cache_lookups",
+            "Application queries looked up in the node service's cache, by result",
+            &["result"],
+        )
+    });
+
+    pub static ACTIVE_SUBSCRIPTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+        register_int_gauge_vec(
+            "node_service_active_subscriptions",
+            "Active node service subscriptions, by subscription",
+            &["subscription"],
+        )
+    });
+
+    /// Counts a subscription among the active ones until it is dropped.
+    pub struct SubscriptionGuard(&'static str);
+
+    impl SubscriptionGuard {
+        pub fn new(name: &'static str) -> Self {
+            ACTIVE_SUBSCRIPTIONS.with_label_values(&[name]).inc();
+            Self(name)
+        }
+    }
+
+    impl Drop for SubscriptionGuard {
+        fn drop(&mut self) {
+            ACTIVE_SUBSCRIPTIONS.with_label_values(&[self.0]).dec();
+        }
+    }
+
+    /// A GraphQL extension that records the latency and errors of each request.
+    pub struct QueryMetrics;
+
+    impl ExtensionFactory for QueryMetrics {
+        fn create(&self) -> Arc<dyn Extension> {
+            Arc::new(QueryMetricsExtension::default())
+        }
+    }
+
+    /// The labels of an operation: its type and its first root field.
+    type Labels = (&'static str, String);
+
+    #[derive(Default)]
+    struct QueryMetricsExtension {
+        /// The labels of the document's operations, by operation name.
+        operations: Mutex<Vec<(Option<String>, Labels)>>,
+        /// The labels of the operation that was executed.
+        executed: Mutex<Option<Labels>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Extension for QueryMetricsExtension {
+        async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+            let start = Instant::now();
+            let response = next.run(ctx).await;
+            // Requests that fail to parse or to validate are never executed.
+            let (operation_type, field) = self
+                .executed
+                .lock()
+                .unwrap()
+                .take()
+                .unwrap_or(("invalid", String::new()));
+            let labels = [operation_type, field.as_str()];
+            QUERY_LATENCY
+                .with_label_values(&labels)
+                .observe(start.elapsed().as_secs_f64() * 1000.0);
+            if response.is_err() {
+                QUERY_ERRORS.with_label_values(&labels).inc();
+            }
+            response
+        }
+
+        async fn parse_query(
+            &self,
+            ctx: &ExtensionContext<'_>,
+            query: &str,
+            variables: &Variables,
+            next: NextParseQuery<'_>,
+        ) -> ServerResult<ExecutableDocument> {
+            let document = next.run(ctx, query, variables).await?;
+            *self.operations.lock().unwrap() = document
+                .operations
+                .iter()
+                .map(|(name, operation)| {
+                    let operation_type = match operation.node.ty {
+                        OperationType::Query => "query",
+                        OperationType::Mutation => "mutation",
+                        OperationType::Subscription => "subscription",
+                    };
+                    let field = operation
+                        .node
+                        .selection_set
+                        .node
+                        .items
+                        .iter()
+                        .find_map(|selection| match &selection.node {
+                            Selection::Field(field) => Some(field.node.name.node.to_string()),
+                            _ => None,
+                        })
+                        .unwrap_or_default();
+                    (name.map(|name| name.to_string()), (operation_type, field))
+                })
+                .collect();
+            Ok(document)
+        }
+
+        async fn execute(
+            &self,
+            ctx: &ExtensionContext<'_>,
+            operation_name: Option<&str>,
+            next: NextExecute<'_>,
+        ) -> Response {
+            let mut operations = std::mem::take(&mut *self.operations.lock().unwrap());
+            let index = match operation_name {
+                Some(operation_name) => operations
+                    .iter()
+                    .position(|(name, _)| name.as_deref() == Some(operation_name)),
+                None => (operations.len() == 1).then_some(0),
+            };
+            *self.executed.lock().unwrap() = index.map(|index| operations.swap_remove(index).1);
+            next.run(ctx, operation_name).await
+        }
+    }
+}
+
+/// Counts `stream` among the active subscriptions called `name` until it is dropped.
+fn track_subscription<S: Stream>(name: &'static str, stream: S) -> impl Stream<Item = S::Item> {
+    #[cfg(with_metrics)]
+    {
+        let guard = metrics::SubscriptionGuard::new(name);
+        stream.map(move |item| {
+            let _guard = &guard;
+            item
+        })
+    }
+    #[cfg(not(with_metrics))]
+    {
+        let _ = name;
+        stream
+    }
+}
+
 #[derive(SimpleObject, Serialize, Deserialize, Clone)]
 pub struct Chains {
     pub list: Vec<ChainId>,
     pub default: Option<ChainId>,
 }
 
+/// A proof of a part of a chain's execution state, relative to the chain's latest block.
+#[derive(SimpleObject)]
+pub struct ChainStateProof {
+    /// The hash of the block whose state hash the proof leads to, if the chain has any blocks.
+    /// Light clients check the proof against this block's certificate.
+    pub block_hash: Option<CryptoHash>,
+    /// The proof.
+    pub proof: StateProof,
+}
+
+/// The health of a validator, as seen by the node service's client.
+#[derive(SimpleObject)]
+pub struct ValidatorScore {
+    /// The public key of the validator.
+    pub public_key: ValidatorPublicKey,
+    /// The smoothed fraction of successful responses, between 0 and 1.
+    pub score: f64,
+    /// The smoothed response time of the successful responses, in milliseconds.
+    pub average_latency_ms: Option<f64>,
+    /// The expected time until the validator responds successfully, in milliseconds. The
+    /// validators expected to be the fastest are tried first.
+    pub expected_latency_ms: Option<f64>,
+    /// The number of failures since the last successful response.
+    pub consecutive_failures: u32,
+    /// Whether the validator is currently left out of requests after repeated failures.
+    pub excluded: bool,
+}
+
 /// Our root GraphQL query type.
 pub struct QueryRoot<C> {
     context: Arc<Mutex<C>>,
+    activator: ChainActivator,
     port: NonZeroU16,
+    /// The path under which the service's endpoints are served, e.g. `/wallets/alice`.
+    path_prefix: String,
     default_chain: Option<ChainId>,
 }
 
+/// A message bundle from another chain, added to a chain's inbox.
+#[derive(SimpleObject, Clone)]
+pub struct NewIncomingBundle {
+    /// The chain that sent the bundle.
+    pub origin: ChainId,
+    /// The height of the block that sent the bundle.
+    pub height: BlockHeight,
+}
+
 /// Our root GraphQL subscription type.
 pub struct SubscriptionRoot<C> {
     context: Arc<Mutex<C>>,
+    activator: ChainActivator,
 }
 
 /// Our root GraphQL mutation type.
 pub struct MutationRoot<C> {
     context: Arc<Mutex<C>>,
+    activator: ChainActivator,
 }
 
 #[derive(Debug, ThisError)]
@@ -81,6 +324,16 @@ enum NodeServiceError {
     UnknownChainId { chain_id: String },
     #[error("malformed chain ID: {0}")]
     InvalidChainId(CryptoError),
+    #[error(transparent)]
+    LocalNodeError(#[from] linera_core::local_node::LocalNodeError),
+    #[error(transparent)]
+    ViewError(#[from] linera_views::views::ViewError),
+    #[error(transparent)]
+    ExecutionError(#[from] Box<linera_execution::ExecutionError>),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("creating blocks requires an API token")]
+    Unauthorized,
 }
 
 impl IntoResponse for NodeServiceError {
@@ -101,6 +354,22 @@ impl IntoResponse for NodeServiceError {
                 StatusCode::BAD_REQUEST,
                 vec!["invalid chain ID".to_string()],
             ),
+            NodeServiceError::LocalNodeError(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
+            }
+            NodeServiceError::ViewError(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
+            }
+            NodeServiceError::ExecutionError(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
+            }
+            NodeServiceError::NotFound(what) => {
+                (StatusCode::NOT_FOUND, vec![format!("not found: {what}")])
+            }
+            NodeServiceError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                vec!["creating blocks requires an API token".to_string()],
+            ),
         };
         let tuple = (tuple.0, json!({"error": tuple.1}).to_string());
         tuple.into_response()
@@ -117,13 +386,120 @@ where
         &self,
         chain_id: ChainId,
     ) -> Result<impl Stream<Item = Notification>, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        Ok(track_subscription(
+            "notifications",
+            client.subscribe().await?,
+        ))
+    }
+
+    /// Subscribes to the new blocks of the specified chain.
+    async fn new_blocks(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<impl Stream<Item = Result<ConfirmedBlock, Error>>, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let notifications = client.subscribe().await?;
+        let blocks = notifications.filter_map(move |notification| {
+            let client = client.clone();
+            async move {
+                let Reason::NewBlock { hash, .. } = notification.reason else {
+                    return None;
+                };
+                Some(client.read_confirmed_block(hash).await.map_err(Error::from))
+            }
+        });
+        Ok(track_subscription("newBlocks", blocks))
+    }
+
+    /// Subscribes to the message bundles that other chains send to the specified chain.
+    async fn incoming_bundles(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<impl Stream<Item = NewIncomingBundle>, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let notifications = client.subscribe().await?;
+        let bundles = notifications.filter_map(|notification| async move {
+            match notification.reason {
+                Reason::NewIncomingBundle { origin, height } => {
+                    Some(NewIncomingBundle { origin, height })
+                }
+                _ => None,
+            }
+        });
+        Ok(track_subscription("incomingBundles", bundles))
+    }
+
+    /// Subscribes to the events that the specified chain's blocks add to an application's
+    /// stream.
+    async fn events(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        stream_name: StreamName,
+    ) -> Result<impl Stream<Item = Result<Event, Error>>, Error> {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
             .await
             .make_chain_client(chain_id)
             .await?;
-        Ok(client.subscribe().await?)
+        let stream_id = StreamId {
+            application_id: application_id.into(),
+            stream_name,
+        };
+        let notifications = client.subscribe().await?;
+        let events = notifications.filter_map(move |notification| {
+            let client = client.clone();
+            let stream_id = stream_id.clone();
+            async move {
+                let Reason::NewBlock {
+                    hash,
+                    event_streams,
+                    ..
+                } = notification.reason
+                else {
+                    return None;
+                };
+                if !event_streams.contains(&stream_id) {
+                    return None;
+                }
+                let events = match client.read_confirmed_block(hash).await {
+                    Ok(block) => block
+                        .block()
+                        .body
+                        .events
+                        .iter()
+                        .flatten()
+                        .filter(|event| event.stream_id == stream_id)
+                        .cloned()
+                        .map(Ok)
+                        .collect(),
+                    Err(error) => vec![Err(Error::from(error))],
+                };
+                Some(stream::iter(events))
+            }
+        });
+        Ok(track_subscription("events", events.flatten()))
     }
 }
 
@@ -164,6 +540,7 @@ where
         Fut: Future<Output = (Result<ClientOutcome<T>, Error>, ChainClient<C::Environment>)>,
     {
         loop {
+            self.activator.activate(*chain_id);
             let client = self
                 .context
                 .lock()
@@ -192,6 +569,7 @@ where
     async fn process_inbox(&self, chain_id: ChainId) -> Result<Vec<CryptoHash>, Error> {
         let mut hashes = Vec::new();
         loop {
+            self.activator.activate(chain_id);
             let client = self
                 .context
                 .lock()
@@ -216,6 +594,7 @@ where
 
     /// Retries the pending block that was unsuccessfully proposed earlier.
     async fn retry_pending_block(&self, chain_id: ChainId) -> Result<Option<CryptoHash>, Error> {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -486,6 +865,34 @@ where
         self.execute_system_operation(operation, chain_id).await
     }
 
+    /// Registers or renews a name on the admin chain, owned by the chain's signer. The name can
+    /// be registered by anyone else at or after the expiry time.
+    async fn register_name(
+        &self,
+        chain_id: ChainId,
+        name: String,
+        target: NameTarget,
+        expiry: Timestamp,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::RegisterName {
+            name,
+            target,
+            expiry,
+        };
+        self.execute_system_operation(operation, chain_id).await
+    }
+
+    /// Transfers a name owned by the chain's signer to a new owner.
+    async fn transfer_name(
+        &self,
+        chain_id: ChainId,
+        name: String,
+        new_owner: AccountOwner,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::TransferName { name, new_owner };
+        self.execute_system_operation(operation, chain_id).await
+    }
+
     /// (admin chain only) Registers a new committee. This will notify the subscribers of
     /// the admin chain so that they can migrate to the new epoch (by accepting the
     /// notification as an "incoming message" in a next block).
@@ -494,8 +901,8 @@ where
         chain_id: ChainId,
         committee: Committee,
     ) -> Result<CryptoHash, Error> {
-        Ok(self
-            .apply_client_command(&chain_id, move |client| {
+        Ok(
+            Box::pin(self.apply_client_command(&chain_id, move |client| {
                 let committee = committee.clone();
                 async move {
                     let result = client
@@ -504,9 +911,10 @@ where
                         .map_err(Error::from);
                     (result, client)
                 }
-            })
+            }))
             .await?
-            .hash())
+            .hash(),
+        )
     }
 
     /// (admin chain only) Removes a committee. Once this message is accepted by a chain,
@@ -600,6 +1008,7 @@ where
         ChainStateExtendedView<<C::Environment as linera_core::Environment>::StorageContext>,
         Error,
     > {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -611,6 +1020,7 @@ where
     }
 
     async fn applications(&self, chain_id: ChainId) -> Result<Vec<ApplicationOverview>, Error> {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -626,12 +1036,30 @@ where
 
         let overviews = applications
             .into_iter()
-            .map(|(id, description)| ApplicationOverview::new(id, description, self.port, chain_id))
+            .map(|(id, description)| {
+                ApplicationOverview::new(id, description, self.port, &self.path_prefix, chain_id)
+            })
             .collect();
 
         Ok(overviews)
     }
 
+    /// Resolves a name registered on the admin chain of the given chain.
+    async fn resolve_name(
+        &self,
+        chain_id: ChainId,
+        name: String,
+    ) -> Result<Option<NameTarget>, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        Ok(client.resolve_name(&name).await?)
+    }
+
     async fn chains(&self) -> Result<Chains, Error> {
         Ok(Chains {
             list: self.context.lock().await.wallet().chain_ids(),
@@ -644,6 +1072,7 @@ where
         hash: Option<CryptoHash>,
         chain_id: ChainId,
     ) -> Result<Option<ConfirmedBlock>, Error> {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -671,6 +1100,7 @@ where
         chain_id: ChainId,
         limit: Option<u32>,
     ) -> Result<Vec<ConfirmedBlock>, Error> {
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -693,6 +1123,88 @@ where
         }
     }
 
+    /// Returns the messages that applications logged while this node executed the block at
+    /// the given height, for each incoming bundle and then each operation. Only the logs of
+    /// recent blocks are kept.
+    async fn execution_logs(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<Vec<Vec<ContractLog>>, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let view = client.chain_state_view().await?;
+        Ok(view.execution_logs.get(&height).await?.unwrap_or_default())
+    }
+
+    /// Returns a proof of the balances on the given chain, that light clients can check
+    /// against the certificate of the chain's latest block.
+    async fn balance_proof(&self, chain_id: ChainId) -> Result<ChainStateProof, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let view = client.chain_state_view().await?;
+        Ok(ChainStateProof {
+            block_hash: view.tip_state.get().block_hash,
+            proof: view.prove_balances().await?,
+        })
+    }
+
+    /// Returns a proof of the state of an application on the given chain, that light clients
+    /// can check against the certificate of the chain's latest block.
+    async fn application_state_proof(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+    ) -> Result<ChainStateProof, Error> {
+        self.activator.activate(chain_id);
+        let client = self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?;
+        let view = client.chain_state_view().await?;
+        Ok(ChainStateProof {
+            block_hash: view.tip_state.get().block_hash,
+            proof: view.prove_application_state(&application_id).await?,
+        })
+    }
+
+    /// Returns the health of the validators that the client communicated with so far,
+    /// fastest first.
+    async fn validator_scores(&self) -> Vec<ValidatorScore> {
+        let context = self.context.lock().await;
+        let health = context.client().validator_health();
+        let mut reports = health.reports();
+        health.sort_by_expected_latency(&mut reports, |(public_key, _)| public_key);
+        let now = Instant::now();
+        reports
+            .into_iter()
+            .map(|(public_key, report)| ValidatorScore {
+                public_key,
+                score: report.score,
+                average_latency_ms: report
+                    .average_latency
+                    .map(|latency| latency.as_secs_f64() * 1000.0),
+                expected_latency_ms: report
+                    .expected_latency()
+                    .map(|latency| latency.as_secs_f64() * 1000.0),
+                consecutive_failures: report.consecutive_failures,
+                excluded: report.excluded_until.is_some_and(|until| until > now),
+            })
+            .collect()
+    }
+
     /// Returns the version information on this node service.
     async fn version(&self) -> linera_version::VersionInfo {
         linera_version::VersionInfo::default()
@@ -781,14 +1293,16 @@ impl ApplicationOverview {
         id: ApplicationId,
         description: ApplicationDescription,
         port: NonZeroU16,
+        path_prefix: &str,
         chain_id: ChainId,
     ) -> Self {
         Self {
             id,
             description,
             link: format!(
-                "http://localhost:{}/chains/{}/applications/{}",
+                "http://localhost:{}{}/chains/{}/applications/{}",
                 port.get(),
+                path_prefix,
                 chain_id,
                 id
             ),
@@ -797,15 +1311,24 @@ impl ApplicationOverview {
 }
 
 /// The `NodeService` is a server that exposes a web-server to the client.
-/// The node service is primarily used to explore the state of a chain in GraphQL.
+/// The node service is primarily used to explore the state of a chain in GraphQL, or with
+/// the read-only REST API under `/rest`.
+///
+/// Besides its own wallet, the service may serve the wallets of [`Session`]s, each under
+/// `/wallets/<name>`.
 pub struct NodeService<C>
 where
     C: ClientContext,
 {
     config: ChainListenerConfig,
     port: NonZeroU16,
+    path_prefix: String,
     default_chain: Option<ChainId>,
     context: Arc<Mutex<C>>,
+    access: Arc<AccessControl>,
+    query_cache: Arc<QueryCache>,
+    /// Requests the chain listener to listen to the chains that are used.
+    activator: ChainActivator,
 }
 
 impl<C> Clone for NodeService<C>
@@ -816,8 +1339,12 @@ where
         Self {
             config: self.config.clone(),
             port: self.port,
+            path_prefix: self.path_prefix.clone(),
             default_chain: self.default_chain,
             context: Arc::clone(&self.context),
+            access: Arc::clone(&self.access),
+            query_cache: Arc::clone(&self.query_cache),
+            activator: self.activator.clone(),
         }
     }
 }
@@ -836,37 +1363,123 @@ where
         Self {
             config,
             port,
+            path_prefix: String::new(),
             default_chain,
             context: Arc::new(Mutex::new(context)),
+            access: Arc::new(
+                AccessControl::new(AccessConfig::default())
+                    .expect("the default access configuration should be valid"),
+            ),
+            query_cache: Arc::new(QueryCache::new(&QueryCacheConfig::default())),
+            activator: ChainActivator::default(),
         }
     }
 
+    /// Restricts the access to the node service, e.g. to expose it publicly.
+    pub fn with_access(mut self, config: AccessConfig) -> anyhow::Result<Self> {
+        self.access = Arc::new(AccessControl::new(config)?);
+        Ok(self)
+    }
+
+    /// Configures the cache of application query responses.
+    pub fn with_query_cache(mut self, config: QueryCacheConfig) -> Self {
+        self.query_cache = Arc::new(QueryCache::new(&config));
+        self
+    }
+
     pub fn schema(&self) -> Schema<QueryRoot<C>, MutationRoot<C>, SubscriptionRoot<C>> {
-        Schema::build(
+        let builder = Schema::build(
             QueryRoot {
                 context: Arc::clone(&self.context),
                 port: self.port,
+                path_prefix: self.path_prefix.clone(),
                 default_chain: self.default_chain,
+                activator: self.activator.clone(),
             },
             MutationRoot {
                 context: Arc::clone(&self.context),
+                activator: self.activator.clone(),
             },
             SubscriptionRoot {
                 context: Arc::clone(&self.context),
+                activator: self.activator.clone(),
             },
-        )
-        .finish()
+        );
+        #[cfg(with_metrics)]
+        let builder = builder.extension(metrics::QueryMetrics);
+        if self.access.requires_tokens() {
+            builder.extension(access::AuthorizedMutations).finish()
+        } else {
+            builder.finish()
+        }
     }
 
     /// Runs the node service.
-    #[instrument(name = "node_service", level = "info", skip_all, fields(port = ?self.port))]
     pub async fn run(self, cancellation_token: CancellationToken) -> Result<(), anyhow::Error> {
+        self.run_with_sessions(Vec::new(), cancellation_token).await
+    }
+
+    /// Runs the node service, and serves the wallets of the sessions too.
+    #[instrument(name = "node_service", level = "info", skip_all, fields(port = ?self.port))]
+    pub async fn run_with_sessions(
+        self,
+        sessions: Vec<Session<C>>,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), anyhow::Error> {
         let port = self.port.get();
+        let cors_layer = self.access.cors_layer();
+        let (chain_listener, mut app) = self.listen_and_route(cancellation_token.clone()).await;
+        let mut chain_listeners = vec![Box::pin(chain_listener.run())];
+        for session in sessions {
+            let path = format!("/wallets/{}", session.name());
+            let service = session.into_service().with_path_prefix(path.clone());
+            let (chain_listener, router) =
+                service.listen_and_route(cancellation_token.clone()).await;
+            chain_listeners.push(Box::pin(chain_listener.run()));
+            app = app.nest(&path, router);
+            info!("GraphiQL IDE: http://localhost:{port}{path}");
+        }
+        let app = app.layer(cors_layer);
+
+        info!("GraphiQL IDE: http://localhost:{}", port);
+
+        let mut chain_listeners = futures::future::try_join_all(chain_listeners).fuse();
+        let tcp_listener =
+            tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?;
+        let server = axum::serve(tcp_listener, app).into_future();
+        futures::select! {
+            result = chain_listeners => { result?; }
+            result = Box::pin(server).fuse() => result?,
+        };
+
+        Ok(())
+    }
+
+    /// Serves the endpoints under the given path instead of the root.
+    fn with_path_prefix(mut self, path_prefix: String) -> Self {
+        self.path_prefix = path_prefix;
+        self
+    }
+
+    /// Creates the chain listener of the service's wallet, and the routes of its
+    /// endpoints, with access control but without CORS headers.
+    async fn listen_and_route(
+        mut self,
+        cancellation_token: CancellationToken,
+    ) -> (ChainListener<C>, Router) {
+        let storage = self.context.lock().await.storage().clone();
+        let chain_listener = ChainListener::new(
+            self.config.clone(),
+            Arc::clone(&self.context),
+            storage,
+            cancellation_token,
+        );
+        self.activator = chain_listener.activator();
         let index_handler = axum::routing::get(util::graphiql).post(Self::index_handler);
         let application_handler =
             axum::routing::get(util::graphiql).post(Self::application_handler);
 
-        let app = Router::new()
+        let router = Router::new()
             .route("/", index_handler)
             .route(
                 "/chains/:chain_id/applications/:application_id",
@@ -874,26 +1487,13 @@ where
             )
             .route("/ready", axum::routing::get(|| async { "ready!" }))
             .route_service("/ws", GraphQLSubscription::new(self.schema()))
+            .nest("/rest", rest::router::<C>())
             .layer(Extension(self.clone()))
-            // TODO(#551): Provide application authentication.
-            .layer(CorsLayer::permissive());
-
-        info!("GraphiQL IDE: http://localhost:{}", port);
-
-        let storage = self.context.lock().await.storage().clone();
-
-        let chain_listener =
-            ChainListener::new(self.config, self.context, storage, cancellation_token).run();
-        let mut chain_listener = Box::pin(chain_listener).fuse();
-        let tcp_listener =
-            tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?;
-        let server = axum::serve(tcp_listener, app).into_future();
-        futures::select! {
-            result = chain_listener => result?,
-            result = Box::pin(server).fuse() => result?,
-        };
-
-        Ok(())
+            .layer(middleware::from_fn_with_state(
+                self.access.clone(),
+                access::check_access,
+            ));
+        (chain_listener, router)
     }
 
     /// Handles service queries for user applications (including mutations).
@@ -902,6 +1502,7 @@ where
         application_id: ApplicationId,
         request: Vec<u8>,
         chain_id: ChainId,
+        authorized: Authorized,
     ) -> Result<Vec<u8>, NodeServiceError> {
         let QueryOutcome {
             response,
@@ -912,8 +1513,12 @@ where
         if operations.is_empty() {
             return Ok(response);
         }
+        if !authorized.0 {
+            return Err(NodeServiceError::Unauthorized);
+        }
 
         trace!("Query requested a new block with operations: {operations:?}");
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -940,17 +1545,15 @@ where
         Ok(serde_json::to_vec(&response)?)
     }
 
-    /// Queries a user application, returning the raw [`QueryOutcome`].
+    /// Queries a user application, returning the raw [`QueryOutcome`]. Responses to
+    /// queries that schedule no operations are cached until the chain has a new block.
     async fn query_user_application(
         &self,
         application_id: ApplicationId,
         bytes: Vec<u8>,
         chain_id: ChainId,
     ) -> Result<QueryOutcome<Vec<u8>>, NodeServiceError> {
-        let query = Query::User {
-            application_id,
-            bytes,
-        };
+        self.activator.activate(chain_id);
         let client = self
             .context
             .lock()
@@ -960,27 +1563,71 @@ where
             .map_err(|_| NodeServiceError::UnknownChainId {
                 chain_id: chain_id.to_string(),
             })?;
+        let mut key = None;
+        if self.query_cache.is_enabled() {
+            let cache_key = QueryKey {
+                chain_id,
+                application_id,
+                block_hash: Self::block_hash(&client).await?,
+                bytes: bytes.clone(),
+            };
+            if let Some(response) = self.query_cache.get(&cache_key) {
+                #[cfg(with_metrics)]
+                metrics::QUERY_CACHE_LOOKUPS
+                    .with_label_values(&["hit"])
+                    .inc();
+                return Ok(QueryOutcome {
+                    response,
+                    operations: Vec::new(),
+                });
+            }
+            #[cfg(with_metrics)]
+            metrics::QUERY_CACHE_LOOKUPS
+                .with_label_values(&["miss"])
+                .inc();
+            key = Some(cache_key);
+        }
+        let query = Query::User {
+            application_id,
+            bytes,
+        };
         let QueryOutcome {
             response,
             operations,
         } = client.query_application(query).await?;
-        match response {
-            QueryResponse::System(_) => {
-                unreachable!("cannot get a system response for a user query")
+        let QueryResponse::User(response) = response else {
+            unreachable!("cannot get a system response for a user query")
+        };
+        if let Some(key) = key.filter(|_| operations.is_empty()) {
+            // Only cache the response if no block was added during the query.
+            if key.block_hash == Self::block_hash(&client).await? {
+                self.query_cache.insert(key, response.clone());
             }
-            QueryResponse::User(user_response_bytes) => Ok(QueryOutcome {
-                response: user_response_bytes,
-                operations,
-            }),
         }
+        Ok(QueryOutcome {
+            response,
+            operations,
+        })
+    }
+
+    /// Returns the hash of the chain's latest block in the local node, if any.
+    async fn block_hash(
+        client: &ChainClient<C::Environment>,
+    ) -> Result<Option<CryptoHash>, NodeServiceError> {
+        let info = client.chain_info().await.map_err(ChainClientError::from)?;
+        Ok(info.block_hash)
     }
 
     /// Executes a GraphQL query and generates a response for our `Schema`.
-    async fn index_handler(service: Extension<Self>, request: GraphQLRequest) -> GraphQLResponse {
+    async fn index_handler(
+        service: Extension<Self>,
+        Extension(authorized): Extension<Authorized>,
+        request: GraphQLRequest,
+    ) -> GraphQLResponse {
         service
             .0
             .schema()
-            .execute(request.into_inner())
+            .execute(request.into_inner().data(authorized))
             .await
             .into()
     }
@@ -991,6 +1638,7 @@ where
     async fn application_handler(
         Path((chain_id, application_id)): Path<(String, String)>,
         service: Extension<Self>,
+        Extension(authorized): Extension<Authorized>,
         request: String,
     ) -> Result<Vec<u8>, NodeServiceError> {
         let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
@@ -1000,11 +1648,31 @@ where
             "Processing request for application {application_id} on chain {chain_id}:\n{:?}",
             &request
         );
+        let start = linera_base::time::Instant::now();
         let response = service
             .0
-            .handle_service_request(application_id, request.into_bytes(), chain_id)
-            .await?;
+            .handle_service_request(application_id, request.into_bytes(), chain_id, authorized)
+            .await;
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        debug!(
+            %chain_id,
+            %application_id,
+            message_type = "application_query",
+            latency_ms,
+            success = response.is_ok(),
+            "Handled application query"
+        );
+        #[cfg(with_metrics)]
+        {
+            let labels = ["application", ""];
+            metrics::QUERY_LATENCY
+                .with_label_values(&labels)
+                .observe(latency_ms);
+            if response.is_err() {
+                metrics::QUERY_ERRORS.with_label_values(&labels).inc();
+            }
+        }
 
-        Ok(response)
+        response
     }
 }
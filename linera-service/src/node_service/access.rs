@@ -0,0 +1,288 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Access control for the node service, so that it can be exposed publicly.
+//!
+//! Browsers may be restricted to a list of origins. If API tokens are configured, only
+//! requests presenting one of them, as a bearer token or in an `X-Api-Key` header, may
+//! submit mutations or application queries that create blocks, and each token's request
+//! rate may be limited. Queries remain open to everyone, unless the service is private:
+//! then every request must present a token.
+
+use std::{
+    num::{NonZeroU32, NonZeroUsize},
+    sync::Arc,
+};
+
+use anyhow::{ensure, Context as _};
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery},
+    parser::types::{ExecutableDocument, OperationType},
+    ServerError, ServerResult, Variables,
+};
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use linera_base::data_types::Timestamp;
+use linera_core::rate_limiter::{RateLimit, RateLimiter};
+use tower_http::cors::CorsLayer;
+
+/// The header in which API tokens may be presented instead of `Authorization`.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// The configuration of the node service's access control.
+#[derive(Clone, Debug, clap::Args)]
+pub struct AccessConfig {
+    /// An origin from which browsers may send requests, e.g. `https://app.example.com`.
+    /// Can be given several times. Requests from all origins are allowed if this is not set.
+    #[arg(long = "allowed-origin")]
+    pub allowed_origins: Vec<String>,
+
+    /// A token that authorizes mutations, presented as a bearer token or in an `X-Api-Key`
+    /// header. Can be given several times. Anyone can submit mutations if this is not set.
+    #[arg(
+        long = "api-token",
+        env = "LINERA_SERVICE_API_TOKENS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    pub api_tokens: Vec<String>,
+
+    /// If set, the maximal number of requests per second that a single API token can make,
+    /// on average.
+    #[arg(long)]
+    pub requests_per_token_per_second: Option<NonZeroU32>,
+
+    /// The number of requests that a single API token can make at once, if
+    /// `--requests-per-token-per-second` is set.
+    #[arg(long, default_value = "10")]
+    pub request_burst_per_token: NonZeroU32,
+
+    /// Requires an API token for all requests, including queries and subscriptions.
+    #[arg(long, requires = "api_tokens")]
+    pub private: bool,
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            api_tokens: Vec::new(),
+            requests_per_token_per_second: None,
+            request_burst_per_token: NonZeroU32::new(10).unwrap(),
+            private: false,
+        }
+    }
+}
+
+/// Whether a request may submit mutations.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Authorized(pub bool);
+
+/// Checks the API tokens of the requests, and limits their rates.
+pub(super) struct AccessControl {
+    tokens: Vec<String>,
+    /// The rate limits, by token index.
+    rate_limiter: Option<RateLimiter<usize>>,
+    /// Whether queries require a token too.
+    private: bool,
+    cors: CorsLayer,
+}
+
+impl AccessControl {
+    pub(super) fn new(config: AccessConfig) -> anyhow::Result<Self> {
+        let tokens = config
+            .api_tokens
+            .into_iter()
+            .filter(|token| !token.is_empty())
+            .collect::<Vec<_>>();
+        ensure!(
+            !config.private || !tokens.is_empty(),
+            "a private node service requires API tokens"
+        );
+        let rate_limiter = config
+            .requests_per_token_per_second
+            .map(|events_per_second| {
+                let limit = RateLimit {
+                    events_per_second,
+                    burst: config.request_burst_per_token,
+                };
+                let capacity = NonZeroUsize::new(tokens.len()).unwrap_or(NonZeroUsize::MIN);
+                RateLimiter::new(limit, capacity)
+            });
+        let cors = if config.allowed_origins.is_empty() {
+            CorsLayer::permissive()
+        } else {
+            let origins = config
+                .allowed_origins
+                .iter()
+                .map(|origin| {
+                    HeaderValue::from_str(origin)
+                        .with_context(|| format!("invalid allowed origin {origin:?}"))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([Method::GET, Method::POST])
+                .allow_headers([
+                    AUTHORIZATION,
+                    CONTENT_TYPE,
+                    HeaderName::from_static(API_KEY_HEADER),
+                ])
+        };
+        Ok(Self {
+            tokens,
+            rate_limiter,
+            private: config.private,
+            cors,
+        })
+    }
+
+    /// Returns whether API tokens are required for mutations.
+    pub(super) fn requires_tokens(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    pub(super) fn cors_layer(&self) -> CorsLayer {
+        self.cors.clone()
+    }
+
+    /// Returns the index of the given token, if it is one of the API tokens.
+    fn token_index(&self, presented: &str) -> Option<usize> {
+        // Compare in constant time, so that the response time doesn't reveal the tokens.
+        self.tokens.iter().position(|token| {
+            presented.len() == token.len()
+                && presented
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |difference, (a, b)| difference | (a ^ b))
+                    == 0
+        })
+    }
+}
+
+/// Returns the token presented in the request's headers, if any.
+fn presented_token(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| headers.get(API_KEY_HEADER)?.to_str().ok())
+}
+
+/// A middleware that rejects requests with unknown tokens or above their token's rate
+/// limit, or without a token if the service is private, and records whether the others
+/// may submit mutations.
+pub(super) async fn check_access(
+    State(access): State<Arc<AccessControl>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authorized = if !access.requires_tokens() {
+        true
+    } else if let Some(token) = presented_token(request.headers()) {
+        let Some(index) = access.token_index(token) else {
+            return (StatusCode::UNAUTHORIZED, "Invalid API token").into_response();
+        };
+        if let Some(rate_limiter) = &access.rate_limiter {
+            if let Err(delay) = rate_limiter.check(index, Timestamp::now()) {
+                let seconds = delay.as_micros().div_ceil(1_000_000);
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, seconds.to_string())],
+                    "Too many requests for this API token",
+                )
+                    .into_response();
+            }
+        }
+        true
+    } else if access.private {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "This service requires an API token",
+        )
+            .into_response();
+    } else {
+        false
+    };
+    request.extensions_mut().insert(Authorized(authorized));
+    next.run(request).await
+}
+
+/// A GraphQL extension that rejects documents with mutations, unless the request is
+/// [`Authorized`]. Mutations sent over WebSocket connections are always rejected.
+pub(super) struct AuthorizedMutations;
+
+impl ExtensionFactory for AuthorizedMutations {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(AuthorizedMutations)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for AuthorizedMutations {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let authorized = ctx
+            .data_opt::<Authorized>()
+            .is_some_and(|authorized| authorized.0);
+        let has_mutations = document
+            .operations
+            .iter()
+            .any(|(_, operation)| operation.node.ty == OperationType::Mutation);
+        if has_mutations && !authorized {
+            return Err(ServerError::new("Mutations require an API token", None));
+        }
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderMap, HeaderValue};
+
+    use super::{presented_token, AccessConfig, AccessControl};
+
+    #[test]
+    fn test_tokens() {
+        let access = AccessControl::new(AccessConfig {
+            api_tokens: vec!["first".to_string(), "second".to_string(), String::new()],
+            ..AccessConfig::default()
+        })
+        .unwrap();
+        assert!(access.requires_tokens());
+        assert_eq!(access.token_index("second"), Some(1));
+        assert_eq!(access.token_index("secont"), None);
+        assert_eq!(access.token_index(""), None);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(presented_token(&headers), None);
+        headers.insert("x-api-key", HeaderValue::from_static("first"));
+        assert_eq!(presented_token(&headers), Some("first"));
+        headers.insert("authorization", HeaderValue::from_static("Bearer second"));
+        assert_eq!(presented_token(&headers), Some("second"));
+
+        assert!(AccessControl::new(AccessConfig {
+            allowed_origins: vec!["https://example.com\n".to_string()],
+            ..AccessConfig::default()
+        })
+        .is_err());
+        assert!(AccessControl::new(AccessConfig {
+            private: true,
+            ..AccessConfig::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of the responses to application queries.
+//!
+//! A service query only reads the state of its chain, which only changes with new blocks.
+//! The responses to queries that schedule no operations are therefore cached by
+//! application, query and latest block of the chain, so that identical queries are not
+//! executed again. Services may also read the current time or make HTTP requests: cached
+//! responses expire after a configurable delay to bound how long such changes go unseen.
+
+use std::{num::NonZeroUsize, sync::Mutex, time::Duration};
+
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{ApplicationId, ChainId},
+    time::Instant,
+};
+use lru::LruCache;
+
+use crate::util;
+
+/// The configuration of the node service's query cache.
+#[derive(Clone, Debug, clap::Args)]
+pub struct QueryCacheConfig {
+    /// The maximal number of application query responses to cache, to answer identical
+    /// queries against the same block without executing them again. `0` disables the cache.
+    #[arg(long, default_value = "1000")]
+    pub query_cache_size: usize,
+
+    /// How long a cached application query response is used, in milliseconds.
+    #[arg(
+        long = "query-cache-ttl-ms",
+        default_value = "1000",
+        value_parser = util::parse_millis
+    )]
+    pub query_cache_ttl: Duration,
+}
+
+impl Default for QueryCacheConfig {
+    fn default() -> Self {
+        Self {
+            query_cache_size: 1000,
+            query_cache_ttl: Duration::from_secs(1),
+        }
+    }
+}
+
+/// What determines the response to an application query.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) struct QueryKey {
+    pub chain_id: ChainId,
+    pub application_id: ApplicationId,
+    /// The hash of the chain's latest block, if any.
+    pub block_hash: Option<CryptoHash>,
+    pub bytes: Vec<u8>,
+}
+
+/// The cached query responses, with the time they were cached.
+type Responses = LruCache<QueryKey, (Instant, Vec<u8>)>;
+
+/// A least-recently used cache of application query responses, which expire after a delay.
+pub(super) struct QueryCache {
+    /// The responses, or `None` if the cache is disabled.
+    responses: Option<Mutex<Responses>>,
+    ttl: Duration,
+}
+
+impl QueryCache {
+    pub(super) fn new(config: &QueryCacheConfig) -> Self {
+        let responses = NonZeroUsize::new(config.query_cache_size)
+            .filter(|_| !config.query_cache_ttl.is_zero())
+            .map(|size| Mutex::new(LruCache::new(size)));
+        Self {
+            responses,
+            ttl: config.query_cache_ttl,
+        }
+    }
+
+    /// Returns whether responses are cached at all.
+    pub(super) fn is_enabled(&self) -> bool {
+        self.responses.is_some()
+    }
+
+    /// Returns the cached response to the query, unless it expired.
+    pub(super) fn get(&self, key: &QueryKey) -> Option<Vec<u8>> {
+        let mut responses = self.responses.as_ref()?.lock().unwrap();
+        let (cached_at, response) = responses.get(key)?;
+        if cached_at.elapsed() < self.ttl {
+            return Some(response.clone());
+        }
+        responses.pop(key);
+        None
+    }
+
+    /// Caches the response to the query.
+    pub(super) fn insert(&self, key: QueryKey, response: Vec<u8>) {
+        if let Some(responses) = &self.responses {
+            responses
+                .lock()
+                .unwrap()
+                .put(key, (Instant::now(), response));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use linera_base::{
+        crypto::CryptoHash,
+        identifiers::{ApplicationId, ChainId},
+    };
+
+    use super::{QueryCache, QueryCacheConfig, QueryKey};
+
+    fn key(block_hash: Option<CryptoHash>, query: &str) -> QueryKey {
+        QueryKey {
+            chain_id: ChainId(CryptoHash::test_hash("chain")),
+            application_id: ApplicationId::new(CryptoHash::test_hash("application")),
+            block_hash,
+            bytes: query.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_query_cache() {
+        let cache = QueryCache::new(&QueryCacheConfig {
+            query_cache_size: 2,
+            query_cache_ttl: Duration::from_secs(3600),
+        });
+        let block_hash = Some(CryptoHash::test_hash("block"));
+        cache.insert(key(None, "{ value }"), b"0".to_vec());
+        cache.insert(key(block_hash, "{ value }"), b"1".to_vec());
+        assert_eq!(cache.get(&key(None, "{ value }")), Some(b"0".to_vec()));
+        assert_eq!(
+            cache.get(&key(block_hash, "{ value }")),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(cache.get(&key(block_hash, "{ other }")), None);
+
+        // The least recently used response is evicted.
+        cache.insert(key(block_hash, "{ other }"), b"2".to_vec());
+        assert_eq!(cache.get(&key(None, "{ value }")), None);
+
+        let expired = QueryCache::new(&QueryCacheConfig {
+            query_cache_size: 2,
+            query_cache_ttl: Duration::from_nanos(1),
+        });
+        expired.insert(key(None, "{ value }"), b"0".to_vec());
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(expired.get(&key(None, "{ value }")), None);
+
+        let disabled = QueryCache::new(&QueryCacheConfig {
+            query_cache_size: 0,
+            ..QueryCacheConfig::default()
+        });
+        assert!(!disabled.is_enabled());
+        disabled.insert(key(None, "{ value }"), b"0".to_vec());
+        assert_eq!(disabled.get(&key(None, "{ value }")), None);
+    }
+}
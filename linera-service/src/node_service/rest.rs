@@ -0,0 +1,368 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A read-only REST API to explore the chains of the node service, for clients that can't
+//! use GraphQL.
+//!
+//! Lists are paginated: each page contains at most `limit` items, and the `next` cursor to
+//! request the following page, if any.
+
+use axum::{
+    extract::{Path, Query},
+    routing::get,
+    Extension, Json, Router,
+};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, ApplicationDescription, BlockHeight, Epoch, Timestamp},
+    identifiers::{AccountOwner, ApplicationId, ChainId, EventId, StreamId, StreamName},
+};
+use linera_chain::{block::Block, data_types::IncomingBundle};
+use linera_client::chain_listener::ClientContext;
+use linera_core::client::ChainClient;
+use linera_execution::{Operation, OutgoingMessage};
+use linera_storage::Storage as _;
+use serde::{Deserialize, Serialize};
+
+use super::{NodeService, NodeServiceError};
+
+/// The number of items in a page if the request doesn't specify a limit.
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// The maximum number of items in a page.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// The routes of the REST API.
+pub(super) fn router<C: ClientContext>() -> Router {
+    Router::new()
+        .route("/chains", get(chains::<C>))
+        .route("/chains/:chain_id", get(chain::<C>))
+        .route("/chains/:chain_id/balance", get(balance::<C>))
+        .route("/chains/:chain_id/blocks", get(blocks::<C>))
+        .route("/chains/:chain_id/blocks/:height", get(block::<C>))
+        .route(
+            "/chains/:chain_id/blocks/:height/transactions",
+            get(transactions::<C>),
+        )
+        .route("/chains/:chain_id/applications", get(applications::<C>))
+        .route(
+            "/chains/:chain_id/events/:application_id/:stream_name",
+            get(events::<C>),
+        )
+}
+
+/// A page of a list.
+#[derive(Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The cursor to pass to get the next page, if there is one.
+    pub next: Option<u64>,
+}
+
+/// The state of a chain.
+#[derive(Serialize)]
+pub struct ChainSummary {
+    pub chain_id: ChainId,
+    pub next_block_height: BlockHeight,
+    pub tip_hash: Option<CryptoHash>,
+    pub timestamp: Timestamp,
+    pub epoch: Option<Epoch>,
+    pub balance: Amount,
+    pub closed: bool,
+}
+
+/// An overview of a block.
+#[derive(Serialize)]
+pub struct BlockSummary {
+    pub height: BlockHeight,
+    pub hash: CryptoHash,
+    pub timestamp: Timestamp,
+    pub authenticated_signer: Option<AccountOwner>,
+    pub incoming_bundles: usize,
+    pub operations: usize,
+}
+
+/// A transaction of a block, with its outcome.
+#[derive(Serialize)]
+pub struct Transaction {
+    pub index: usize,
+    #[serde(flatten)]
+    pub kind: TransactionKind,
+    pub messages: Vec<OutgoingMessage>,
+    /// The events that the transaction emitted, with hex-encoded values.
+    pub events: Vec<EventSummary>,
+}
+
+/// What a transaction executes.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    IncomingBundle(IncomingBundle),
+    Operation(Operation),
+}
+
+/// An event of a stream.
+#[derive(Serialize)]
+pub struct EventSummary {
+    pub stream_id: StreamId,
+    pub index: u32,
+    /// The hex-encoded value of the event.
+    pub value: String,
+}
+
+/// An application registered on a chain.
+#[derive(Serialize)]
+pub struct ApplicationSummary {
+    pub id: ApplicationId,
+    pub description: ApplicationDescription,
+}
+
+#[derive(Serialize)]
+pub struct Balance {
+    pub owner: AccountOwner,
+    pub balance: Amount,
+}
+
+#[derive(Deserialize)]
+pub struct BalanceQuery {
+    /// The owner whose balance to return; the chain's balance if this is not set.
+    owner: Option<AccountOwner>,
+}
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    /// The cursor returned with the previous page.
+    cursor: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl PageQuery {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
+
+async fn chain_client<C: ClientContext>(
+    service: &NodeService<C>,
+    chain_id: ChainId,
+) -> Result<ChainClient<C::Environment>, NodeServiceError> {
+    service.activator.activate(chain_id);
+    service
+        .context
+        .lock()
+        .await
+        .make_chain_client(chain_id)
+        .await
+        .map_err(|_| NodeServiceError::UnknownChainId {
+            chain_id: chain_id.to_string(),
+        })
+}
+
+/// Reads the block of the chain at the given height.
+async fn read_block<C: ClientContext>(
+    service: &NodeService<C>,
+    chain_id: ChainId,
+    height: u64,
+) -> Result<Block, NodeServiceError> {
+    let client = chain_client(service, chain_id).await?;
+    let not_found = || NodeServiceError::NotFound(format!("block {height} of chain {chain_id}"));
+    let index = usize::try_from(height).map_err(|_| not_found())?;
+    let hash = client
+        .chain_state_view()
+        .await?
+        .confirmed_log
+        .get(index)
+        .await?
+        .ok_or_else(not_found)?;
+    Ok(client.read_confirmed_block(hash).await?.into_block())
+}
+
+/// Returns the chains of the wallet.
+async fn chains<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+) -> Json<super::Chains> {
+    Json(super::Chains {
+        list: service.context.lock().await.wallet().chain_ids(),
+        default: service.default_chain,
+    })
+}
+
+/// Returns the state of a chain.
+async fn chain<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path(chain_id): Path<ChainId>,
+) -> Result<Json<ChainSummary>, NodeServiceError> {
+    let client = chain_client(&service, chain_id).await?;
+    let view = client.chain_state_view().await?;
+    let tip = view.tip_state.get();
+    let system = &view.execution_state.system;
+    Ok(Json(ChainSummary {
+        chain_id,
+        next_block_height: tip.next_block_height,
+        tip_hash: tip.block_hash,
+        timestamp: *system.timestamp.get(),
+        epoch: *system.epoch.get(),
+        balance: *system.balance.get(),
+        closed: *system.closed.get(),
+    }))
+}
+
+/// Returns the balance of a chain, or of one of its owners.
+async fn balance<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path(chain_id): Path<ChainId>,
+    Query(query): Query<BalanceQuery>,
+) -> Result<Json<Balance>, NodeServiceError> {
+    let client = chain_client(&service, chain_id).await?;
+    let view = client.chain_state_view().await?;
+    let system = &view.execution_state.system;
+    let owner = query.owner.unwrap_or(AccountOwner::CHAIN);
+    let balance = if owner.is_chain() {
+        *system.balance.get()
+    } else {
+        system.balances.get(&owner).await?.unwrap_or_default()
+    };
+    Ok(Json(Balance { owner, balance }))
+}
+
+/// Returns the blocks of a chain, from the latest one down. The cursor is the height
+/// below which to list the blocks.
+async fn blocks<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path(chain_id): Path<ChainId>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Page<BlockSummary>>, NodeServiceError> {
+    let client = chain_client(&service, chain_id).await?;
+    let view = client.chain_state_view().await?;
+    let count = view.confirmed_log.count();
+    let end = query
+        .cursor
+        .and_then(|cursor| usize::try_from(cursor).ok())
+        .map_or(count, |cursor| cursor.min(count));
+    let start = end.saturating_sub(query.limit());
+    let hashes = view.confirmed_log.read(start..end).await?;
+    drop(view);
+    let mut items = Vec::with_capacity(hashes.len());
+    for hash in hashes.into_iter().rev() {
+        let block = client.read_confirmed_block(hash).await?.into_block();
+        items.push(BlockSummary {
+            height: block.header.height,
+            hash,
+            timestamp: block.header.timestamp,
+            authenticated_signer: block.header.authenticated_signer,
+            incoming_bundles: block.body.incoming_bundles.len(),
+            operations: block.body.operations.len(),
+        });
+    }
+    let next = (start > 0).then_some(start as u64);
+    Ok(Json(Page { items, next }))
+}
+
+/// Returns the block of a chain at the given height.
+async fn block<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path((chain_id, height)): Path<(ChainId, u64)>,
+) -> Result<Json<Block>, NodeServiceError> {
+    Ok(Json(read_block(&service, chain_id, height).await?))
+}
+
+/// Returns the transactions of the block of a chain at the given height: first the
+/// incoming message bundles, then the operations.
+async fn transactions<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path((chain_id, height)): Path<(ChainId, u64)>,
+) -> Result<Json<Vec<Transaction>>, NodeServiceError> {
+    let block = read_block(&service, chain_id, height).await?;
+    let body = block.body;
+    let kinds = body
+        .incoming_bundles
+        .into_iter()
+        .map(TransactionKind::IncomingBundle)
+        .chain(body.operations.into_iter().map(TransactionKind::Operation));
+    let transactions = kinds
+        .zip(body.messages)
+        .zip(body.events)
+        .enumerate()
+        .map(|(index, ((kind, messages), events))| Transaction {
+            index,
+            kind,
+            messages,
+            events: events
+                .into_iter()
+                .map(|event| EventSummary {
+                    stream_id: event.stream_id,
+                    index: event.index,
+                    value: hex::encode(event.value),
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(Json(transactions))
+}
+
+/// Returns the applications registered on a chain.
+async fn applications<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path(chain_id): Path<ChainId>,
+) -> Result<Json<Vec<ApplicationSummary>>, NodeServiceError> {
+    let client = chain_client(&service, chain_id).await?;
+    let applications = client
+        .chain_state_view()
+        .await?
+        .execution_state
+        .list_applications()
+        .await
+        .map_err(Box::new)?;
+    Ok(Json(
+        applications
+            .into_iter()
+            .map(|(id, description)| ApplicationSummary { id, description })
+            .collect(),
+    ))
+}
+
+/// Returns the events of an application's stream on a chain, from the oldest one up. The
+/// stream name is the path segment's UTF-8 bytes, and the cursor is the index of the
+/// first event to list.
+async fn events<C: ClientContext>(
+    Extension(service): Extension<NodeService<C>>,
+    Path((chain_id, application_id, stream_name)): Path<(ChainId, ApplicationId, String)>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Page<EventSummary>>, NodeServiceError> {
+    let stream_id = StreamId {
+        application_id: application_id.into(),
+        stream_name: StreamName::from(stream_name),
+    };
+    let client = chain_client(&service, chain_id).await?;
+    let count = client
+        .chain_state_view()
+        .await?
+        .execution_state
+        .stream_event_counts
+        .get(&stream_id)
+        .await?
+        .unwrap_or_default();
+    let start = query
+        .cursor
+        .map_or(0, |cursor| u32::try_from(cursor).unwrap_or(u32::MAX))
+        .min(count);
+    let limit = u32::try_from(query.limit()).unwrap_or(u32::MAX);
+    let end = start.saturating_add(limit).min(count);
+    let storage = client.storage_client().clone();
+    let mut items = Vec::with_capacity((end - start) as usize);
+    for index in start..end {
+        let event_id = EventId {
+            chain_id,
+            stream_id: stream_id.clone(),
+            index,
+        };
+        let value = storage.read_event(event_id).await?;
+        items.push(EventSummary {
+            stream_id: stream_id.clone(),
+            index,
+            value: hex::encode(value),
+        });
+    }
+    let next = (end < count).then_some(u64::from(end));
+    Ok(Json(Page { items, next }))
+}
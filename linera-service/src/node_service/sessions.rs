@@ -0,0 +1,149 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serving several wallets from a single node service, e.g. to host the wallet backends
+//! of several users.
+//!
+//! Each session has its own wallet and keystore, and its own client and chain listener, so
+//! that the sessions don't share any in-memory state except the storage. The endpoints of a
+//! session are served under `/wallets/<name>`, and every request to them has to present the
+//! session's API token.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{ensure, Context as _};
+use linera_client::chain_listener::ClientContext;
+use serde::{Deserialize, Serialize};
+
+use super::NodeService;
+
+/// A wallet that the node service serves, besides its own. The sessions are read from a
+/// JSON file containing a list of them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionConfig {
+    /// The name of the session. Its endpoints are served under `/wallets/<name>`.
+    pub name: String,
+    /// The path of the session's wallet.
+    pub wallet: PathBuf,
+    /// The path of the session's keystore.
+    pub keystore: PathBuf,
+    /// The environment variable that contains the session's API token.
+    pub token_env: String,
+    /// The environment variable that contains the passphrase of the keystore, if it is
+    /// encrypted.
+    #[serde(default)]
+    pub passphrase_env: Option<String>,
+}
+
+impl SessionConfig {
+    /// Reads the list of sessions from a JSON file, and checks that their names are valid
+    /// and distinct.
+    pub fn read_all(path: &Path) -> anyhow::Result<Vec<SessionConfig>> {
+        let file = fs_err::File::open(path)?;
+        let sessions: Vec<SessionConfig> = serde_json::from_reader(file)
+            .with_context(|| format!("invalid sessions file {}", path.display()))?;
+        let mut names = BTreeSet::new();
+        for session in &sessions {
+            ensure!(
+                !session.name.is_empty()
+                    && session
+                        .name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                "invalid session name {:?}: only letters, digits, `-` and `_` are allowed",
+                session.name
+            );
+            ensure!(
+                names.insert(&session.name),
+                "duplicate session name {:?}",
+                session.name
+            );
+        }
+        Ok(sessions)
+    }
+
+    /// Reads the session's API token from its environment variable.
+    pub fn token(&self) -> anyhow::Result<String> {
+        let token = std::env::var(&self.token_env).with_context(|| {
+            format!(
+                "the API token of session {:?} must be set in `{}`",
+                self.name, self.token_env
+            )
+        })?;
+        ensure!(
+            !token.is_empty(),
+            "the API token of session {:?} must not be empty",
+            self.name
+        );
+        Ok(token)
+    }
+}
+
+/// The node service of a session's wallet, to be served under `/wallets/<name>`.
+pub struct Session<C>
+where
+    C: ClientContext,
+{
+    name: String,
+    service: NodeService<C>,
+}
+
+impl<C> Session<C>
+where
+    C: ClientContext,
+{
+    /// Creates a session. The service's access configuration should be private, with the
+    /// session's token.
+    pub fn new(name: String, service: NodeService<C>) -> Self {
+        Self { name, service }
+    }
+
+    /// Returns the name of the session.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(super) fn into_service(self) -> NodeService<C> {
+        self.service
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::SessionConfig;
+
+    fn read(json: &str) -> anyhow::Result<Vec<SessionConfig>> {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(json.as_bytes()).unwrap();
+        SessionConfig::read_all(file.path())
+    }
+
+    #[test]
+    fn test_read_sessions() {
+        let sessions = read(
+            r#"[
+                {"name": "alice", "wallet": "a.json", "keystore": "ka.json", "token_env": "A"},
+                {"name": "bob-2", "wallet": "b.json", "keystore": "kb.json", "token_env": "B",
+                 "passphrase_env": "B_PASSPHRASE"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1].passphrase_env.as_deref(), Some("B_PASSPHRASE"));
+
+        let duplicate = r#"[
+            {"name": "alice", "wallet": "a.json", "keystore": "ka.json", "token_env": "A"},
+            {"name": "alice", "wallet": "b.json", "keystore": "kb.json", "token_env": "B"}
+        ]"#;
+        assert!(read(duplicate).is_err());
+        let invalid_name =
+            r#"[{"name": "a/b", "wallet": "a.json", "keystore": "ka.json", "token_env": "A"}]"#;
+        assert!(read(invalid_name).is_err());
+    }
+}
@@ -0,0 +1,485 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capturing the read-only requests that a proxy receives, and replaying them against
+//! another validator.
+//!
+//! With `--access-log`, the gRPC proxy appends every read-only request to a file, one JSON
+//! object per line, with the time at which it was received. Requests that change a chain's
+//! state, or that make the shards sign anything, are not captured.
+//!
+//! `linera-proxy replay-access-log` sends the captured requests again to a target
+//! validator, e.g. a staging deployment, with the same intervals between them, optionally
+//! sped up. This reproduces the traffic of the production validator for load testing. If a
+//! reference validator is given too, each request is sent to both, and the responses are
+//! compared, e.g. to check that a new release answers like the current one.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{ensure, Context as _};
+use futures::{stream::FuturesUnordered, StreamExt as _};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::Timestamp,
+    identifiers::{BlobId, ChainId},
+    time::Instant,
+};
+use linera_core::{
+    data_types::ChainInfoQuery,
+    node::{NodeError, ValidatorNode, ValidatorNodeProvider as _},
+};
+use linera_rpc::{Client, NodeOptions, NodeProvider};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader, BufWriter},
+    sync::{mpsc, Semaphore},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use super::LatencySummary;
+
+/// The number of captured requests that may wait to be written before new ones are dropped.
+const ACCESS_LOG_CAPACITY: usize = 10_000;
+
+/// The configuration of the proxy's access log.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct AccessLogConfig {
+    /// A file to append the read-only requests to, to replay them later with
+    /// `replay-access-log`. Only supported with gRPC.
+    #[arg(long)]
+    pub access_log: Option<PathBuf>,
+}
+
+/// A read-only request to a validator.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum ReadRequest {
+    ChainInfoQuery { query: ChainInfoQuery },
+    GetVersionInfo,
+    GetNetworkDescription,
+    GetNodeFeatures,
+    DownloadBlob { blob_id: BlobId },
+    DownloadBlobs { blob_ids: Vec<BlobId> },
+    DownloadPendingBlob { chain_id: ChainId, blob_id: BlobId },
+    DownloadCertificate { hash: CryptoHash },
+    DownloadCertificates { hashes: Vec<CryptoHash> },
+    BlobLastUsedBy { blob_id: BlobId },
+    MissingBlobIds { blob_ids: Vec<BlobId> },
+}
+
+/// A captured request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+    /// When the proxy received the request.
+    pub timestamp: Timestamp,
+    pub request: ReadRequest,
+}
+
+impl ReadRequest {
+    /// Returns the name of the gRPC method.
+    pub fn method(&self) -> &'static str {
+        match self {
+            ReadRequest::ChainInfoQuery { .. } => "handle_chain_info_query",
+            ReadRequest::GetVersionInfo => "get_version_info",
+            ReadRequest::GetNetworkDescription => "get_network_description",
+            ReadRequest::GetNodeFeatures => "get_node_features",
+            ReadRequest::DownloadBlob { .. } => "download_blob",
+            ReadRequest::DownloadBlobs { .. } => "download_blobs",
+            ReadRequest::DownloadPendingBlob { .. } => "download_pending_blob",
+            ReadRequest::DownloadCertificate { .. } => "download_certificate",
+            ReadRequest::DownloadCertificates { .. } => "download_certificates",
+            ReadRequest::BlobLastUsedBy { .. } => "blob_last_used_by",
+            ReadRequest::MissingBlobIds { .. } => "missing_blob_ids",
+        }
+    }
+
+    /// Sends the request to a validator, and returns the serialized response, without
+    /// anything that differs between validators, like signatures.
+    async fn send(&self, node: &impl ValidatorNode) -> Result<Vec<u8>, NodeError> {
+        let bytes = match self {
+            ReadRequest::ChainInfoQuery { query } => {
+                let response = node.handle_chain_info_query(query.clone()).await?;
+                bcs::to_bytes(&response.info)
+            }
+            ReadRequest::GetVersionInfo => bcs::to_bytes(&node.get_version_info().await?),
+            ReadRequest::GetNetworkDescription => {
+                bcs::to_bytes(&node.get_network_description().await?)
+            }
+            ReadRequest::GetNodeFeatures => bcs::to_bytes(&node.get_node_features().await?),
+            ReadRequest::DownloadBlob { blob_id } => {
+                bcs::to_bytes(&node.download_blob(*blob_id).await?)
+            }
+            ReadRequest::DownloadBlobs { blob_ids } => {
+                bcs::to_bytes(&node.download_blobs(blob_ids.clone()).await?)
+            }
+            ReadRequest::DownloadPendingBlob { chain_id, blob_id } => {
+                bcs::to_bytes(&node.download_pending_blob(*chain_id, *blob_id).await?)
+            }
+            ReadRequest::DownloadCertificate { hash } => {
+                bcs::to_bytes(&node.download_certificate(*hash).await?)
+            }
+            ReadRequest::DownloadCertificates { hashes } => {
+                bcs::to_bytes(&node.download_certificates(hashes.clone()).await?)
+            }
+            ReadRequest::BlobLastUsedBy { blob_id } => {
+                bcs::to_bytes(&node.blob_last_used_by(*blob_id).await?)
+            }
+            ReadRequest::MissingBlobIds { blob_ids } => {
+                bcs::to_bytes(&node.missing_blob_ids(blob_ids.clone()).await?)
+            }
+        };
+        Ok(bytes.expect("responses should be serializable"))
+    }
+}
+
+/// Appends the captured requests to the access log in the background.
+#[derive(Clone, Debug)]
+pub(super) struct AccessLog {
+    sender: mpsc::Sender<AccessLogEntry>,
+}
+
+impl AccessLog {
+    /// Opens the access log, if it is configured, and starts writing to it.
+    pub(super) async fn start(
+        config: &AccessLogConfig,
+        shutdown_signal: CancellationToken,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(path) = &config.access_log else {
+            return Ok(None);
+        };
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("failed to open the access log {}", path.display()))?;
+        info!("Capturing the read-only requests in {}", path.display());
+        let (sender, receiver) = mpsc::channel(ACCESS_LOG_CAPACITY);
+        tokio::spawn(Self::write_entries(
+            BufWriter::new(file),
+            receiver,
+            shutdown_signal,
+        ));
+        Ok(Some(Self { sender }))
+    }
+
+    /// Captures a request. If the log can't keep up, the request is not captured, rather
+    /// than delaying the response.
+    pub(super) fn record(&self, request: ReadRequest) {
+        let entry = AccessLogEntry {
+            timestamp: Timestamp::now(),
+            request,
+        };
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.sender.try_send(entry) {
+            warn!("The access log is full: dropping a request");
+        }
+    }
+
+    async fn write_entries(
+        mut writer: BufWriter<tokio::fs::File>,
+        mut receiver: mpsc::Receiver<AccessLogEntry>,
+        shutdown_signal: CancellationToken,
+    ) {
+        let mut flush_interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            let result = tokio::select! {
+                entry = receiver.recv() => match entry {
+                    Some(entry) => {
+                        let mut line =
+                            serde_json::to_vec(&entry).expect("entries should be serializable");
+                        line.push(b'\n');
+                        writer.write_all(&line).await
+                    }
+                    None => break,
+                },
+                _ = flush_interval.tick() => writer.flush().await,
+                () = shutdown_signal.cancelled() => break,
+            };
+            if let Err(error) = result {
+                error!("Failed to write to the access log: {error}");
+                return;
+            }
+        }
+        if let Err(error) = writer.flush().await {
+            error!("Failed to write to the access log: {error}");
+        }
+    }
+}
+
+/// Reads the captured requests from an access log.
+pub async fn read_access_log(path: &Path) -> anyhow::Result<Vec<AccessLogEntry>> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("failed to open the access log {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut entries = Vec::new();
+    let mut line_number = 0;
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).with_context(|| {
+            format!("invalid entry on line {line_number} of {}", path.display())
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// How to replay an access log.
+#[derive(Clone, Debug, clap::Args)]
+pub struct ReplayOptions {
+    /// The address of the validator to send the requests to, e.g. `grpc://localhost:19100`.
+    #[arg(long)]
+    pub target: String,
+
+    /// The address of a validator to send the same requests to, and whose responses the
+    /// target's are compared with.
+    #[arg(long)]
+    pub reference: Option<String>,
+
+    /// How much faster than captured the requests are sent, e.g. `2` for twice as fast.
+    /// With `0`, the requests are sent as fast as possible.
+    #[arg(long, default_value = "1")]
+    pub speed: f64,
+
+    /// The maximal number of requests waiting for a response at once.
+    #[arg(long, default_value = "100")]
+    pub max_in_flight: usize,
+
+    /// The timeout of each request, in milliseconds.
+    #[arg(long = "timeout-ms", default_value = "10000", value_parser = crate::util::parse_millis)]
+    pub timeout: Duration,
+}
+
+/// The results of the replayed requests of one method.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MethodReport {
+    pub requests: usize,
+    /// The requests that the target answered with an error.
+    pub errors: usize,
+    /// The requests to which the target and the reference answered differently. An error
+    /// from both counts as the same answer.
+    pub mismatches: usize,
+    /// The latency of the target's responses.
+    pub latency: LatencySummary,
+}
+
+/// The results of replaying an access log.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReplayReport {
+    pub requests: usize,
+    /// How long the replay took, in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the responses were compared with a reference validator.
+    pub compared: bool,
+    pub methods: BTreeMap<String, MethodReport>,
+}
+
+impl ReplayReport {
+    /// Returns whether the target answered a request differently from the reference.
+    pub fn has_mismatches(&self) -> bool {
+        self.methods.values().any(|method| method.mismatches > 0)
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Replayed {} requests in {} ms",
+            self.requests, self.duration_ms
+        )?;
+        for (method, report) in &self.methods {
+            write!(
+                f,
+                "  {method}: {} requests, {} errors",
+                report.requests, report.errors
+            )?;
+            if self.compared {
+                write!(f, ", {} mismatches", report.mismatches)?;
+            }
+            writeln!(
+                f,
+                ", latency p50 {:.1} ms, p90 {:.1} ms, p99 {:.1} ms",
+                report.latency.p50_ms, report.latency.p90_ms, report.latency.p99_ms
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of one replayed request.
+struct Replayed {
+    method: &'static str,
+    latency: Duration,
+    failed: bool,
+    mismatch: bool,
+}
+
+/// Sends the captured requests to the target validator, and compares its responses with
+/// the reference validator's, if any.
+pub async fn replay_access_log(
+    entries: Vec<AccessLogEntry>,
+    options: &ReplayOptions,
+) -> anyhow::Result<ReplayReport> {
+    ensure!(
+        options.speed >= 0.0 && options.speed.is_finite(),
+        "the speed must be a non-negative number"
+    );
+    ensure!(
+        options.max_in_flight > 0,
+        "at least one request must be in flight"
+    );
+    let provider = NodeProvider::new(NodeOptions {
+        send_timeout: options.timeout,
+        recv_timeout: options.timeout,
+        retry_delay: Duration::ZERO,
+        max_retries: 0,
+    });
+    let target = Arc::new(provider.make_node(&options.target)?);
+    let reference = match &options.reference {
+        Some(address) => Some(Arc::new(provider.make_node(address)?)),
+        None => None,
+    };
+    let compared = reference.is_some();
+    let in_flight = Arc::new(Semaphore::new(options.max_in_flight));
+    let first_timestamp = entries.first().map(|entry| entry.timestamp);
+    let start = Instant::now();
+    let mut pending = FuturesUnordered::new();
+    let mut results = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        if let Some(first_timestamp) = first_timestamp.filter(|_| options.speed > 0.0) {
+            let offset = entry.timestamp.delta_since(first_timestamp).as_duration();
+            let due = start + offset.div_f64(options.speed);
+            // Collect the responses while waiting for the next request to be due.
+            while Instant::now() < due {
+                tokio::select! {
+                    Some(result) = pending.next(), if !pending.is_empty() => results.push(result),
+                    () = tokio::time::sleep(due.saturating_duration_since(Instant::now())) => {}
+                }
+            }
+        }
+        let permit = Arc::clone(&in_flight)
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let target = Arc::clone(&target);
+        let reference = reference.clone();
+        pending.push(tokio::spawn(async move {
+            let _permit = permit;
+            replay_request(&entry.request, &target, reference.as_deref()).await
+        }));
+    }
+    while let Some(result) = pending.next().await {
+        results.push(result);
+    }
+
+    let mut latencies = BTreeMap::<&str, Vec<Duration>>::new();
+    let mut report = ReplayReport {
+        requests: results.len(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        compared,
+        methods: BTreeMap::new(),
+    };
+    for result in results {
+        let replayed = result.context("a replayed request panicked")?;
+        let method = report
+            .methods
+            .entry(replayed.method.to_string())
+            .or_default();
+        method.requests += 1;
+        method.errors += usize::from(replayed.failed);
+        method.mismatches += usize::from(replayed.mismatch);
+        latencies
+            .entry(replayed.method)
+            .or_default()
+            .push(replayed.latency);
+    }
+    for (method, latencies) in latencies {
+        if let Some(report) = report.methods.get_mut(method) {
+            report.latency = LatencySummary::from_latencies(latencies);
+        }
+    }
+    Ok(report)
+}
+
+async fn replay_request(
+    request: &ReadRequest,
+    target: &Client,
+    reference: Option<&Client>,
+) -> Replayed {
+    let start = Instant::now();
+    let (response, expected) = match reference {
+        Some(reference) => {
+            let (response, expected) = futures::join!(
+                async {
+                    let response = request.send(target).await;
+                    (response, start.elapsed())
+                },
+                request.send(reference)
+            );
+            (response, Some(expected))
+        }
+        None => ((request.send(target).await, start.elapsed()), None),
+    };
+    let (response, latency) = response;
+    let mismatch = match &expected {
+        Some(Ok(expected)) => response.as_ref().ok() != Some(expected),
+        Some(Err(_)) => response.is_ok(),
+        None => false,
+    };
+    if mismatch {
+        warn!(
+            method = request.method(),
+            "The target and the reference answered differently to {request:?}"
+        );
+    }
+    Replayed {
+        method: request.method(),
+        latency,
+        failed: response.is_err(),
+        mismatch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{crypto::CryptoHash, data_types::Timestamp, identifiers::ChainId};
+    use linera_core::data_types::ChainInfoQuery;
+
+    use super::{AccessLogEntry, ReadRequest};
+
+    #[test]
+    fn test_entry_serialization() {
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        let entry = AccessLogEntry {
+            timestamp: Timestamp::from(1_000),
+            request: ReadRequest::ChainInfoQuery {
+                query: ChainInfoQuery::new(chain_id).with_pending_message_bundles(),
+            },
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""method":"chain_info_query""#));
+        let decoded = serde_json::from_str::<AccessLogEntry>(&json).unwrap();
+        assert_eq!(decoded.timestamp, entry.timestamp);
+        let ReadRequest::ChainInfoQuery { query } = decoded.request else {
+            panic!("unexpected request {:?}", decoded.request);
+        };
+        assert_eq!(query.chain_id, chain_id);
+        assert!(query.request_pending_message_bundles);
+
+        let json = r#"{"timestamp":2000,"request":{"method":"get_version_info"}}"#;
+        let decoded = serde_json::from_str::<AccessLogEntry>(json).unwrap();
+        assert_eq!(decoded.request.method(), "get_version_info");
+    }
+}
@@ -10,6 +10,7 @@ use std::{
     fmt::Debug,
     marker::PhantomData,
     net::SocketAddr,
+    num::NonZeroUsize,
     sync::Arc,
     task::{Context, Poll},
     time::Duration,
@@ -18,8 +19,16 @@ use std::{
 use anyhow::Result;
 use async_trait::async_trait;
 use futures::{future::BoxFuture, FutureExt as _};
-use linera_base::identifiers::ChainId;
-use linera_core::{notifier::ChannelNotifier, JoinSetExt as _};
+use linera_base::{
+    data_types::{Blob, BlockHeight},
+    identifiers::ChainId,
+    time::Instant,
+};
+use linera_core::{
+    data_types::ChainInfoResponse,
+    notifier::{ChannelNotifier, NotificationFilter},
+    worker, JoinSetExt as _,
+};
 use linera_rpc::{
     config::{
         ShardConfig, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
@@ -30,18 +39,22 @@ use linera_rpc::{
             notifier_service_server::{NotifierService, NotifierServiceServer},
             validator_node_server::{ValidatorNode, ValidatorNodeServer},
             validator_worker_client::ValidatorWorkerClient,
-            BlobContent, BlobId, BlobIds, BlockProposal, Certificate, CertificatesBatchRequest,
-            CertificatesBatchResponse, ChainInfoQuery, ChainInfoResult, CryptoHash,
-            HandlePendingBlobRequest, LiteCertificate, NetworkDescription, Notification,
-            PendingBlobRequest, PendingBlobResult, SubscriptionRequest, VersionInfo,
+            BlobContent, BlobId, BlobIds, BlobsBatchRequest, BlobsBatchResponse, BlockProposal,
+            Certificate, CertificatesBatchRequest, CertificatesBatchResponse, ChainInfoQuery,
+            ChainInfoResult, ContinuationToken, CryptoHash, HandlePendingBlobRequest,
+            LiteCertificate, NetworkDescription, NodeFeatures, Notification, PendingBlobRequest,
+            PendingBlobResult, SubscriptionRequest, VersionInfo,
         },
+        internal_tls::InternalTlsIdentity,
         pool::GrpcConnectionPool,
-        GrpcProtoConversionError, GrpcProxyable, GRPC_CHUNKED_MESSAGE_FILL_LIMIT,
-        GRPC_MAX_MESSAGE_SIZE,
+        reflection_builder, GrpcProtoConversionError, GrpcProxyable,
+        GRPC_CHUNKED_MESSAGE_FILL_LIMIT, GRPC_MAX_MESSAGE_SIZE,
     },
+    request_id::RequestIdLayer,
+    RequestId,
 };
-use linera_sdk::{linera_base_types::Blob, views::ViewError};
 use linera_storage::Storage;
+use linera_views::views::ViewError;
 use prost::Message;
 use tokio::{select, task::JoinSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -60,6 +73,12 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
+use super::{
+    access_log::{AccessLog, ReadRequest},
+    handoff::{HandoffError, HandoffOutcome, Route, ShardRouter},
+    status::RecentLatencies,
+    tip_cache::{ChainTipCache, Lookup},
+};
 #[cfg(with_metrics)]
 use crate::prometheus_server;
 
@@ -95,19 +114,44 @@ static PROXY_REQUEST_ERROR: LazyLock<IntCounterVec> = LazyLock::new(|| {
     )
 });
 
+#[cfg(with_metrics)]
+static PROXY_CHAIN_INFO_CACHE_HITS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "proxy_chain_info_cache_hits",
+        "Number of chain info queries answered from the proxy's cache",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static PROXY_CHAIN_INFO_CACHE_MISSES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "proxy_chain_info_cache_misses",
+        "Number of chain info queries for recently confirmed chains forwarded to a shard",
+        &[],
+    )
+});
+
+/// Measures the latency of the requests, for the metrics and the status page.
 #[derive(Clone)]
-pub struct PrometheusMetricsMiddlewareLayer;
+pub struct PrometheusMetricsMiddlewareLayer {
+    latencies: Arc<RecentLatencies>,
+}
 
 #[derive(Clone)]
 pub struct PrometheusMetricsMiddlewareService<T> {
     service: T,
+    latencies: Arc<RecentLatencies>,
 }
 
 impl<S> Layer<S> for PrometheusMetricsMiddlewareLayer {
     type Service = PrometheusMetricsMiddlewareService<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        PrometheusMetricsMiddlewareService { service }
+        PrometheusMetricsMiddlewareService {
+            service,
+            latencies: self.latencies.clone(),
+        }
     }
 }
 
@@ -125,16 +169,18 @@ where
     }
 
     fn call(&mut self, request: Req) -> Self::Future {
-        #[cfg(with_metrics)]
-        let start = linera_base::time::Instant::now();
+        let start = Instant::now();
         let future = self.service.call(request);
+        let latencies = self.latencies.clone();
         async move {
             let response = future.await?;
+            let latency = start.elapsed();
+            latencies.record(latency);
             #[cfg(with_metrics)]
             {
                 PROXY_REQUEST_LATENCY
                     .with_label_values(&[])
-                    .observe(start.elapsed().as_secs_f64() * 1000.0);
+                    .observe(latency.as_secs_f64() * 1000.0);
                 PROXY_REQUEST_COUNT.with_label_values(&[]).inc();
             }
             Ok(response)
@@ -149,9 +195,23 @@ pub struct GrpcProxy<S>(Arc<GrpcProxyInner<S>>);
 struct GrpcProxyInner<S> {
     public_config: ValidatorPublicNetworkConfig,
     internal_config: ValidatorInternalNetworkConfig,
+    /// The shards that the requests for each chain are forwarded to.
+    router: ShardRouter,
     worker_connection_pool: GrpcConnectionPool,
     notifier: ChannelNotifier<Result<Notification, Status>>,
+    /// The cached chain info of recently confirmed chains, if enabled.
+    chain_info_cache: Option<Arc<ChainTipCache<ChainInfoResult>>>,
     tls: TlsConfig,
+    /// The TLS configuration to accept the connections of the shards, if the internal
+    /// network uses TLS.
+    internal_server_tls: Option<ServerTlsConfig>,
+    /// The latencies of the recent requests, for the status page.
+    latencies: Arc<RecentLatencies>,
+    /// Where the read-only requests are captured, if anywhere.
+    access_log: Option<AccessLog>,
+    /// The storage write pressure above which block proposals and blob uploads are
+    /// rejected, if any.
+    max_write_pressure: Option<f64>,
     storage: S,
 }
 
@@ -159,26 +219,72 @@ impl<S> GrpcProxy<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
         public_config: ValidatorPublicNetworkConfig,
         internal_config: ValidatorInternalNetworkConfig,
         connect_timeout: Duration,
         timeout: Duration,
+        chain_info_cache_size: usize,
+        chain_info_cache_max_age: Duration,
         tls: TlsConfig,
+        internal_tls: Option<InternalTlsIdentity>,
+        access_log: Option<AccessLog>,
+        max_write_pressure: Option<f64>,
         storage: S,
     ) -> Self {
+        let (internal_server_tls, internal_client_tls) = internal_tls
+            .map(|identity| (identity.server, identity.client))
+            .unzip();
         Self(Arc::new(GrpcProxyInner {
             public_config,
+            router: ShardRouter::new(&internal_config),
             internal_config,
             worker_connection_pool: GrpcConnectionPool::default()
                 .with_connect_timeout(connect_timeout)
-                .with_timeout(timeout),
+                .with_timeout(timeout)
+                .with_tls(internal_client_tls),
             notifier: ChannelNotifier::default(),
+            chain_info_cache: NonZeroUsize::new(chain_info_cache_size)
+                .map(|capacity| Arc::new(ChainTipCache::new(capacity, chain_info_cache_max_age))),
             tls,
+            internal_server_tls,
+            latencies: Arc::default(),
+            access_log,
+            max_write_pressure,
             storage,
         }))
     }
 
+    /// Returns the latencies of the recent requests that the proxy handled.
+    pub(super) fn recent_latencies(&self) -> Arc<RecentLatencies> {
+        self.0.latencies.clone()
+    }
+
+    /// Returns the cached chain info of recently confirmed chains, if enabled.
+    pub fn chain_info_cache(&self) -> Option<Arc<ChainTipCache<ChainInfoResult>>> {
+        self.0.chain_info_cache.clone()
+    }
+
+    /// Returns the shards that the requests for each chain are forwarded to.
+    pub(super) fn router(&self) -> &ShardRouter {
+        &self.0.router
+    }
+
+    /// Hands off the chains to the new list of `shards`. See the
+    /// [`handoff`](super::handoff) module for how the chains are handed off.
+    pub async fn hand_off(&self, shards: Vec<ShardConfig>) -> Result<HandoffOutcome, HandoffError> {
+        self.0
+            .router
+            .hand_off(&self.0.storage, shards, |shard, handoff| async move {
+                let mut client = self.worker_client_for_shard(&shard)?;
+                let request = api::ShardHandoff::try_from(handoff)?;
+                let result = client.handle_shard_handoff(request).await?.into_inner();
+                Ok::<_, anyhow::Error>(result.unloaded_chains as usize)
+            })
+            .await
+    }
+
     fn as_validator_node(&self) -> ValidatorNodeServer<Self> {
         ValidatorNodeServer::new(self.clone())
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -201,20 +307,20 @@ where
         SocketAddr::from(([0, 0, 0, 0], self.0.internal_config.port))
     }
 
-    fn shard_for(&self, proxyable: &impl GrpcProxyable) -> Option<ShardConfig> {
-        Some(
-            self.0
-                .internal_config
-                .get_shard_for(proxyable.chain_id()?)
-                .clone(),
-        )
+    async fn shard_for(&self, proxyable: &impl GrpcProxyable) -> Option<Route> {
+        Some(self.0.router.route(proxyable.chain_id()?).await)
     }
 
     fn worker_client_for_shard(
         &self,
         shard: &ShardConfig,
     ) -> Result<ValidatorWorkerClient<Channel>> {
-        let address = shard.http_address();
+        let tls = if self.0.internal_server_tls.is_some() {
+            TlsConfig::Tls
+        } else {
+            TlsConfig::ClearText
+        };
+        let address = shard.address_with_tls(tls);
         let channel = self.0.worker_connection_pool.channel(address)?;
         let client = ValidatorWorkerClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -246,27 +352,50 @@ where
         health_reporter
             .set_serving::<ValidatorNodeServer<GrpcProxy<S>>>()
             .await;
+        health_reporter
+            .set_serving::<NotifierServiceServer<GrpcProxy<S>>>()
+            .await;
+        let mut internal_server = Server::builder();
+        if let Some(tls_config) = &self.0.internal_server_tls {
+            internal_server = internal_server.tls_config(tls_config.clone())?;
+        }
         let internal_server = join_set.spawn_task(
-            Server::builder()
+            internal_server
+                .add_service(health_service.clone())
+                .add_service(reflection_builder().build_v1()?)
+                .add_service(reflection_builder().build_v1alpha()?)
                 .add_service(self.as_notifier_service())
                 .serve(self.internal_address())
                 .in_current_span(),
         );
-        let reflection_service = tonic_reflection::server::Builder::configure()
-            .register_encoded_file_descriptor_set(linera_rpc::FILE_DESCRIPTOR_SET)
-            .build_v1()?;
+        let reflection_service = reflection_builder().build_v1()?;
+        let reflection_service_v1alpha = reflection_builder().build_v1alpha()?;
+        let shutdown = async move {
+            shutdown_signal.cancelled().await;
+            // Tell the load balancers to stop sending requests while shutting down.
+            health_reporter
+                .set_not_serving::<ValidatorNodeServer<GrpcProxy<S>>>()
+                .await;
+            health_reporter
+                .set_not_serving::<NotifierServiceServer<GrpcProxy<S>>>()
+                .await;
+        };
         let public_server = join_set.spawn_task(
             self.public_server()?
                 .layer(
                     ServiceBuilder::new()
-                        .layer(PrometheusMetricsMiddlewareLayer)
+                        .layer(PrometheusMetricsMiddlewareLayer {
+                            latencies: self.0.latencies.clone(),
+                        })
+                        .layer(RequestIdLayer::generating())
                         .into_inner(),
                 )
                 .accept_http1(true)
                 .add_service(health_service)
                 .add_service(tonic_web::enable(self.as_validator_node()))
                 .add_service(tonic_web::enable(reflection_service))
-                .serve_with_shutdown(self.public_address(), shutdown_signal.cancelled_owned())
+                .add_service(tonic_web::enable(reflection_service_v1alpha))
+                .serve_with_shutdown(self.public_address(), shutdown)
                 .in_current_span(),
         );
 
@@ -291,30 +420,81 @@ where
         }
     }
 
+    /// Returns a client for the shard of the request's chain, and the route, which must be
+    /// held until the request is handled.
     async fn worker_client<R>(
         &self,
         request: Request<R>,
-    ) -> Result<(ValidatorWorkerClient<Channel>, R), Status>
+    ) -> Result<(ValidatorWorkerClient<Channel>, R, Route), Status>
     where
         R: Debug + GrpcProxyable,
     {
         debug!("proxying request from {:?}", request.remote_addr());
         let inner = request.into_inner();
-        let shard = self
+        let route = self
             .shard_for(&inner)
+            .await
             .ok_or_else(|| Status::not_found("could not find shard for message"))?;
         let client = self
-            .worker_client_for_shard(&shard)
+            .worker_client_for_shard(&route.shard)
             .map_err(|_| Status::internal("could not connect to shard"))?;
-        Ok((client, inner))
+        Ok((client, inner, route))
+    }
+
+    /// Captures a read-only request in the access log, if it is enabled. The request is
+    /// only created in that case.
+    fn capture(&self, request: impl FnOnce() -> Option<ReadRequest>) {
+        if let Some(access_log) = &self.0.access_log {
+            if let Some(request) = request() {
+                access_log.record(request);
+            }
+        }
+    }
+
+    /// Rejects a request that would write to the storage, if it is saturated.
+    #[allow(clippy::result_large_err)]
+    fn check_write_pressure(&self) -> Result<(), Status> {
+        if let Some(max_write_pressure) = self.0.max_write_pressure {
+            let pressure = self.0.storage.write_pressure();
+            if pressure > max_write_pressure {
+                return Err(Status::resource_exhausted(format!(
+                    "The validator's storage is saturated (write pressure {pressure:.2}); \
+                     retry later"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the cached chain info of `chain_id`, after forwarding a request that may have
+    /// changed the chain's state.
+    fn invalidate_chain_info(&self, chain_id: Option<ChainId>) {
+        if let (Some(cache), Some(chain_id)) = (&self.0.chain_info_cache, chain_id) {
+            cache.invalidate(chain_id);
+        }
+    }
+
+    /// Returns the next block height in a successful chain info result.
+    fn next_block_height(result: &ChainInfoResult) -> Option<BlockHeight> {
+        let Some(api::chain_info_result::Inner::ChainInfoResponse(response)) = &result.inner else {
+            return None;
+        };
+        let response = ChainInfoResponse::try_from(response.clone()).ok()?;
+        Some(response.info.next_block_height)
     }
 
     #[allow(clippy::result_large_err)]
     fn log_and_return_proxy_request_outcome(
         result: Result<Response<ChainInfoResult>, Status>,
         method_name: &str,
+        start: Instant,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        #![allow(unused_variables)]
+        debug!(
+            message_type = method_name,
+            latency_ms = start.elapsed().as_secs_f64() * 1000.0,
+            success = result.is_ok(),
+            "Proxied request"
+        );
         match result {
             Ok(chain_info_result) => {
                 #[cfg(with_metrics)]
@@ -349,6 +529,7 @@ where
             ViewError::NotFound(_)
             | ViewError::BlobsNotFound(_)
             | ViewError::EventsNotFound(_)
+            | ViewError::BlockBodyPruned(_)
             | ViewError::CannotAcquireCollectionEntry
             | ViewError::MissingEntries => Status::not_found(err.to_string()),
         };
@@ -364,76 +545,132 @@ where
 {
     type SubscribeStream = UnboundedReceiverStream<Result<Notification, Status>>;
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_block_proposal(
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_block_proposal(inner).await,
-            "handle_block_proposal",
-        )
+        self.check_write_pressure()?;
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_block_proposal(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
+        Self::log_and_return_proxy_request_outcome(result, "handle_block_proposal", start)
     }
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_lite_certificate(
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_lite_certificate(inner).await,
-            "handle_lite_certificate",
-        )
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_lite_certificate(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
+        Self::log_and_return_proxy_request_outcome(result, "handle_lite_certificate", start)
     }
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_confirmed_certificate(
         &self,
         request: Request<api::HandleConfirmedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_confirmed_certificate(inner).await,
-            "handle_confirmed_certificate",
-        )
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_confirmed_certificate(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
+        Self::log_and_return_proxy_request_outcome(result, "handle_confirmed_certificate", start)
     }
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_validated_certificate(
         &self,
         request: Request<api::HandleValidatedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_validated_certificate(inner).await,
-            "handle_validated_certificate",
-        )
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_validated_certificate(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
+        Self::log_and_return_proxy_request_outcome(result, "handle_validated_certificate", start)
     }
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_timeout_certificate(
         &self,
         request: Request<api::HandleTimeoutCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_timeout_certificate(inner).await,
-            "handle_timeout_certificate",
-        )
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_timeout_certificate(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
+        Self::log_and_return_proxy_request_outcome(result, "handle_timeout_certificate", start)
     }
 
-    #[instrument(skip_all, err(Display))]
+    #[instrument(skip_all, err(Display), fields(chain_id = ?request.get_ref().chain_id()))]
     async fn handle_chain_info_query(
         &self,
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
-        Self::log_and_return_proxy_request_outcome(
-            client.handle_chain_info_query(inner).await,
-            "handle_chain_info_query",
-        )
+        let start = Instant::now();
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        // Votes for timeouts and fallback mode are signed by the shard: they aren't captured.
+        self.capture(|| {
+            let query = linera_core::data_types::ChainInfoQuery::try_from(inner.clone()).ok()?;
+            let read_only = !query.request_leader_timeout && !query.request_fallback;
+            read_only.then_some(ReadRequest::ChainInfoQuery { query })
+        });
+        let Some(cache) = &self.0.chain_info_cache else {
+            return Self::log_and_return_proxy_request_outcome(
+                client.handle_chain_info_query(forwarded(inner)).await,
+                "handle_chain_info_query",
+                start,
+            );
+        };
+        let query = linera_core::data_types::ChainInfoQuery::try_from(inner.clone())?;
+        // Votes for timeouts and fallback mode must be requested from the shard.
+        if query.request_leader_timeout || query.request_fallback {
+            return Self::log_and_return_proxy_request_outcome(
+                client.handle_chain_info_query(forwarded(inner)).await,
+                "handle_chain_info_query",
+                start,
+            );
+        }
+        let key = inner.encode_to_vec();
+        let generation = match cache.get(query.chain_id, &key) {
+            Lookup::Hit(result) => {
+                #[cfg(with_metrics)]
+                PROXY_CHAIN_INFO_CACHE_HITS.with_label_values(&[]).inc();
+                return Self::log_and_return_proxy_request_outcome(
+                    Ok(Response::new(result)),
+                    "handle_chain_info_query",
+                    start,
+                );
+            }
+            Lookup::Miss(generation) => {
+                #[cfg(with_metrics)]
+                PROXY_CHAIN_INFO_CACHE_MISSES.with_label_values(&[]).inc();
+                Some(generation)
+            }
+            Lookup::Untracked => None,
+        };
+        let result = client.handle_chain_info_query(forwarded(inner)).await;
+        if let (Some(generation), Ok(response)) = (generation, &result) {
+            if let Some(next_block_height) = Self::next_block_height(response.get_ref()) {
+                cache.insert(
+                    query.chain_id,
+                    key,
+                    generation,
+                    next_block_height,
+                    response.get_ref().clone(),
+                );
+            }
+        }
+        Self::log_and_return_proxy_request_outcome(result, "handle_chain_info_query", start)
     }
 
     #[instrument(skip_all, err(Display))]
@@ -447,12 +684,17 @@ where
             .into_iter()
             .map(ChainId::try_from)
             .collect::<Result<Vec<ChainId>, _>>()?;
+        let filter = subscription_request
+            .filter
+            .map(NotificationFilter::try_from)
+            .transpose()?
+            .unwrap_or_default();
         // The empty notification seems to be needed in some cases to force
         // completion of HTTP2 headers.
         let rx = self
             .0
             .notifier
-            .subscribe_with_ack(chain_ids, Ok(Notification::default()));
+            .subscribe_with_ack(chain_ids, filter, Ok(Notification::default()));
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
 
@@ -461,6 +703,7 @@ where
         &self,
         _request: Request<()>,
     ) -> Result<Response<VersionInfo>, Status> {
+        self.capture(|| Some(ReadRequest::GetVersionInfo));
         // We assume each shard is running the same version as the proxy
         Ok(Response::new(linera_version::VersionInfo::default().into()))
     }
@@ -470,6 +713,7 @@ where
         &self,
         _request: Request<()>,
     ) -> Result<Response<NetworkDescription>, Status> {
+        self.capture(|| Some(ReadRequest::GetNetworkDescription));
         let description = self
             .0
             .storage
@@ -482,10 +726,23 @@ where
         Ok(Response::new(description.into()))
     }
 
+    #[instrument(skip_all, err(Display))]
+    async fn get_node_features(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<NodeFeatures>, Status> {
+        self.capture(|| Some(ReadRequest::GetNodeFeatures));
+        let features = linera_core::node::NodeFeatures {
+            archive: self.0.internal_config.archive,
+            block_body_retention: self.0.internal_config.block_body_retention,
+        };
+        Ok(Response::new(features.into()))
+    }
+
     #[instrument(skip_all, err(Display))]
     async fn upload_blob(&self, request: Request<BlobContent>) -> Result<Response<BlobId>, Status> {
-        let content: linera_sdk::linera_base_types::BlobContent =
-            request.into_inner().try_into()?;
+        self.check_write_pressure()?;
+        let content: linera_base::data_types::BlobContent = request.into_inner().try_into()?;
         let blob = Blob::new(content);
         let id = blob.id();
         let result = self.0.storage.maybe_write_blobs(&[blob]).await;
@@ -501,6 +758,7 @@ where
         request: Request<BlobId>,
     ) -> Result<Response<BlobContent>, Status> {
         let blob_id = request.into_inner().try_into()?;
+        self.capture(|| Some(ReadRequest::DownloadBlob { blob_id }));
         let blob = self
             .0
             .storage
@@ -515,9 +773,13 @@ where
         &self,
         request: Request<PendingBlobRequest>,
     ) -> Result<Response<PendingBlobResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        self.capture(|| {
+            let (chain_id, blob_id) = inner.clone().try_into().ok()?;
+            Some(ReadRequest::DownloadPendingBlob { chain_id, blob_id })
+        });
         #[cfg_attr(not(with_metrics), expect(clippy::needless_match))]
-        match client.download_pending_blob(inner).await {
+        match client.download_pending_blob(forwarded(inner)).await {
             Ok(blob_result) => {
                 #[cfg(with_metrics)]
                 PROXY_REQUEST_SUCCESS
@@ -540,9 +802,12 @@ where
         &self,
         request: Request<HandlePendingBlobRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.worker_client(request).await?;
+        let (mut client, inner, _route) = self.worker_client(request).await?;
+        let chain_id = inner.chain_id();
+        let result = client.handle_pending_blob(forwarded(inner)).await;
+        self.invalidate_chain_info(chain_id);
         #[cfg_attr(not(with_metrics), expect(clippy::needless_match))]
-        match client.handle_pending_blob(inner).await {
+        match result {
             Ok(blob_result) => {
                 #[cfg(with_metrics)]
                 PROXY_REQUEST_SUCCESS
@@ -566,6 +831,7 @@ where
         request: Request<CryptoHash>,
     ) -> Result<Response<Certificate>, Status> {
         let hash = request.into_inner().try_into()?;
+        self.capture(|| Some(ReadRequest::DownloadCertificate { hash }));
         let certificate: linera_chain::types::Certificate = self
             .0
             .storage
@@ -581,21 +847,24 @@ where
         &self,
         request: Request<CertificatesBatchRequest>,
     ) -> Result<Response<CertificatesBatchResponse>, Status> {
+        let request = request.into_inner();
         let hashes: Vec<linera_base::crypto::CryptoHash> = request
-            .into_inner()
             .hashes
             .into_iter()
             .map(linera_base::crypto::CryptoHash::try_from)
             .collect::<Result<Vec<linera_base::crypto::CryptoHash>, _>>()?;
-
-        // Use 70% of the max message size as a buffer capacity.
-        // Leave 30% as overhead.
+        self.capture(|| {
+            Some(ReadRequest::DownloadCertificates {
+                hashes: hashes.clone(),
+            })
+        });
+        let offset = continuation_offset(request.continuation);
         let mut grpc_message_limiter: GrpcMessageLimiter<linera_chain::types::Certificate> =
-            GrpcMessageLimiter::new(GRPC_CHUNKED_MESSAGE_FILL_LIMIT);
+            GrpcMessageLimiter::with_budget(request.max_response_bytes);
 
         let mut certificates = vec![];
 
-        'outer: for batch in hashes.chunks(100) {
+        'outer: for batch in hashes.get(offset..).unwrap_or_default().chunks(100) {
             for certificate in self
                 .0
                 .storage
@@ -611,9 +880,60 @@ where
             }
         }
 
-        Ok(Response::new(CertificatesBatchResponse::try_from(
-            certificates,
-        )?))
+        let continuation = continuation_token(offset, certificates.len(), hashes.len());
+        let mut response = CertificatesBatchResponse::try_from(certificates)?;
+        response.continuation = continuation;
+        Ok(Response::new(response))
+    }
+
+    #[instrument(skip_all, err(Display))]
+    async fn download_blobs(
+        &self,
+        request: Request<BlobsBatchRequest>,
+    ) -> Result<Response<BlobsBatchResponse>, Status> {
+        let request = request.into_inner();
+        let blob_ids = request
+            .blob_ids
+            .into_iter()
+            .map(linera_base::identifiers::BlobId::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.capture(|| {
+            Some(ReadRequest::DownloadBlobs {
+                blob_ids: blob_ids.clone(),
+            })
+        });
+        let offset = continuation_offset(request.continuation);
+        let mut grpc_message_limiter: GrpcMessageLimiter<linera_base::data_types::BlobContent> =
+            GrpcMessageLimiter::with_budget(request.max_response_bytes);
+
+        let mut blobs = vec![];
+
+        'outer: for batch in blob_ids.get(offset..).unwrap_or_default().chunks(100) {
+            let maybe_blobs = self
+                .0
+                .storage
+                .read_blobs(batch)
+                .await
+                .map_err(Self::error_to_status)?;
+            for (blob_id, maybe_blob) in batch.iter().zip(maybe_blobs) {
+                let Some(blob) = maybe_blob else {
+                    return Err(Self::error_to_status(ViewError::BlobsNotFound(vec![
+                        *blob_id,
+                    ])));
+                };
+                let content = blob.into_content();
+                if grpc_message_limiter.fits::<BlobContent>(content.clone())? {
+                    blobs.push(content);
+                } else {
+                    break 'outer;
+                }
+            }
+        }
+
+        let continuation = continuation_token(offset, blobs.len(), blob_ids.len());
+        let mut response = BlobsBatchResponse::try_from(blobs)?;
+        response.continuation = continuation;
+        Ok(Response::new(response))
     }
 
     #[instrument(skip_all, err(level = Level::WARN))]
@@ -622,6 +942,7 @@ where
         request: Request<BlobId>,
     ) -> Result<Response<CryptoHash>, Status> {
         let blob_id = request.into_inner().try_into()?;
+        self.capture(|| Some(ReadRequest::BlobLastUsedBy { blob_id }));
         let blob_state = self
             .0
             .storage
@@ -637,6 +958,11 @@ where
         request: Request<BlobIds>,
     ) -> Result<Response<BlobIds>, Status> {
         let blob_ids: Vec<linera_base::identifiers::BlobId> = request.into_inner().try_into()?;
+        self.capture(|| {
+            Some(ReadRequest::MissingBlobIds {
+                blob_ids: blob_ids.clone(),
+            })
+        });
         let missing_blob_ids = self
             .0
             .storage
@@ -655,26 +981,83 @@ where
     #[instrument(skip_all, err(Display))]
     async fn notify(&self, request: Request<Notification>) -> Result<Response<()>, Status> {
         let notification = request.into_inner();
-        let chain_id = notification
-            .chain_id
-            .clone()
-            .ok_or_else(|| Status::invalid_argument("Missing field: chain_id."))?
-            .try_into()?;
-        self.0.notifier.notify_chain(&chain_id, &Ok(notification));
+        let Some(worker_notification) =
+            Option::<worker::Notification>::try_from(notification.clone())?
+        else {
+            return Err(Status::invalid_argument("Missing field: chain_id."));
+        };
+        if let Some(cache) = &self.0.chain_info_cache {
+            cache.handle_notification(&worker_notification);
+        }
+        let worker::Notification { chain_id, reason } = worker_notification;
+        self.0
+            .notifier
+            .notify_chain(&chain_id, &reason, &Ok(notification));
         Ok(Response::new(()))
     }
 }
 
+/// Returns the number of items of a batch request that were already returned, according to
+/// the request's continuation token.
+/// Returns a request to forward to a shard, with the ID of the request being handled.
+fn forwarded<R>(inner: R) -> Request<R> {
+    let mut request = Request::new(inner);
+    if let Some(request_id) = RequestId::current() {
+        request_id.add_to_metadata(request.metadata_mut());
+    }
+    request
+}
+
+fn continuation_offset(continuation: Option<ContinuationToken>) -> usize {
+    continuation.map_or(0, |token| {
+        usize::try_from(token.offset).unwrap_or(usize::MAX)
+    })
+}
+
+/// Returns the token to resume a batch request of `total` items, if the response with
+/// `returned` items starting at `offset` did not complete it.
+///
+/// No token is returned if the response is empty, i.e. if the next item does not fit in a
+/// message at all: the client would otherwise keep requesting it.
+fn continuation_token(offset: usize, returned: usize, total: usize) -> Option<ContinuationToken> {
+    let next = offset.saturating_add(returned);
+    (returned > 0 && next < total).then_some(ContinuationToken {
+        offset: next as u64,
+    })
+}
+
 /// A message limiter that keeps track of the remaining capacity in bytes.
 struct GrpcMessageLimiter<T> {
     remaining: usize,
+    /// The capacity available to the first element, if it is larger than `remaining`.
+    first: Option<usize>,
     _phantom: PhantomData<T>,
 }
 
 impl<T> GrpcMessageLimiter<T> {
+    #[cfg(test)]
     fn new(limit: usize) -> Self {
         Self {
             remaining: limit,
+            first: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a limiter for a response of at most `max_response_bytes`, as requested by the
+    /// client, and never more than [`GRPC_CHUNKED_MESSAGE_FILL_LIMIT`].
+    ///
+    /// The first element is accepted even if it exceeds the client's budget, as long as it
+    /// fits in a message, so that every response makes progress.
+    fn with_budget(max_response_bytes: Option<u64>) -> Self {
+        let budget = max_response_bytes
+            .and_then(|max| usize::try_from(max).ok())
+            .map_or(GRPC_CHUNKED_MESSAGE_FILL_LIMIT, |max| {
+                max.min(GRPC_CHUNKED_MESSAGE_FILL_LIMIT)
+            });
+        Self {
+            remaining: budget,
+            first: Some(GRPC_CHUNKED_MESSAGE_FILL_LIMIT),
             _phantom: PhantomData,
         }
     }
@@ -690,26 +1073,34 @@ impl<T> GrpcMessageLimiter<T> {
         U: TryFrom<T, Error = GrpcProtoConversionError> + Message,
     {
         let required = U::try_from(el).map(|proto| proto.encoded_len())?;
-        if required > self.remaining {
-            return Ok(false);
+        let first = self.first.take();
+        if required <= self.remaining {
+            self.remaining -= required;
+            return Ok(true);
+        }
+        if first.is_some_and(|limit| required <= limit) {
+            self.remaining = 0;
+            return Ok(true);
         }
-        self.remaining -= required;
-        Ok(true)
+        Ok(false)
     }
 }
 
 #[cfg(test)]
 mod proto_message_cap {
-    use linera_base::crypto::CryptoHash;
+    use linera_base::{
+        crypto::{CryptoHash, TestString, ValidatorKeypair, ValidatorSignature},
+        identifiers::ChainId,
+    };
     use linera_chain::{
         data_types::BlockExecutionOutcome,
         types::{Block, Certificate, ConfirmedBlock, ConfirmedBlockCertificate},
     };
-    use linera_sdk::linera_base_types::{
-        ChainId, TestString, ValidatorKeypair, ValidatorSignature,
-    };
 
-    use super::{CertificatesBatchResponse, GrpcMessageLimiter};
+    use super::{
+        continuation_offset, continuation_token, CertificatesBatchResponse, ContinuationToken,
+        GrpcMessageLimiter,
+    };
 
     fn test_certificate() -> Certificate {
         let keypair = ValidatorKeypair::generate();
@@ -763,4 +1154,25 @@ mod proto_message_cap {
             vec![certificate.clone(), certificate.clone()]
         );
     }
+
+    #[test]
+    fn takes_first_beyond_budget() {
+        let certificate = test_certificate();
+        let mut limiter = GrpcMessageLimiter::with_budget(Some(1));
+        assert!(limiter
+            .fits::<super::Certificate>(certificate.clone())
+            .unwrap());
+        assert!(!limiter.fits::<super::Certificate>(certificate).unwrap());
+    }
+
+    #[test]
+    fn continuation_tokens() {
+        assert_eq!(continuation_token(0, 2, 2), None);
+        // An empty response must not be resumed.
+        assert_eq!(continuation_token(0, 0, 2), None);
+        let token = continuation_token(1, 2, 5);
+        assert_eq!(token, Some(ContinuationToken { offset: 3 }));
+        assert_eq!(continuation_offset(token), 3);
+        assert_eq!(continuation_offset(None), 0);
+    }
 }
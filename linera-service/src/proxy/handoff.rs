@@ -0,0 +1,415 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Handing off chains to a new list of shards, e.g. when shards are added to or removed
+//! from a running validator.
+//!
+//! Chains are assigned to shards by hashing, so a new list of shards moves chains from one
+//! shard to another. All shards share the same storage, so a chain only has to be released
+//! by the worker of its previous shard before its new shard takes over:
+//!
+//! 1. The proxy holds back the requests for the chains that move, and waits until the
+//!    requests that it already forwarded are handled.
+//! 2. Every shard waits until the requests that it is handling are done, unloads the chains
+//!    that move away from it, and holds back the cross-chain requests for the chains that
+//!    move.
+//! 3. Once every shard has released its chains, every shard adopts the new list of shards,
+//!    and the proxy forwards the held requests to the chains' new shards.
+//!
+//! If a shard fails, the previous list of shards is restored. The shards that are added
+//! must already be running, and the shards that are removed can only be stopped after the
+//! handoff.
+//!
+//! Once the previous shards have released the chains, the new list of shards is recorded in
+//! the storage. A server that starts with a different list of shards in its configuration
+//! assigns the chains to the recorded shards instead, e.g. a shard that is started before
+//! being added only takes over chains once they are handed off to it. The proxy refuses to
+//! start with a different list of shards: its configuration must be updated after a
+//! handoff, and changing the configuration of a stopped validator requires a handoff too.
+
+use std::{future::Future, sync::Arc};
+
+use futures::future;
+use linera_base::identifiers::ChainId;
+use linera_rpc::config::{
+    ShardAssignment, ShardConfig, ShardHandoff, ValidatorInternalNetworkConfig,
+};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use tokio::sync::{watch, Mutex, OwnedRwLockReadGuard, RwLock};
+use tracing::{info, warn};
+
+/// An error handing off the chains to a new list of shards.
+#[derive(Debug, thiserror::Error)]
+pub enum HandoffError {
+    #[error("the chains are already being handed off to new shards")]
+    InProgress,
+
+    #[error("the list of shards is empty")]
+    NoShards,
+
+    #[error("shard {address} failed to hand off its chains: {error}")]
+    Shard { address: String, error: String },
+
+    #[error("failed to record the new list of shards: {0}")]
+    Storage(#[from] ViewError),
+}
+
+/// The result of handing off the chains to a new list of shards.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct HandoffOutcome {
+    pub previous_shards: usize,
+    pub shards: usize,
+    /// The number of chain workers that the shards unloaded.
+    pub unloaded_chains: usize,
+}
+
+/// Returns the validator's shards recorded in the storage, and records the configured ones if
+/// there are none yet. They differ from the configured shards if the chains were handed off
+/// to other shards since the configuration was written.
+pub async fn load_shards<S: Storage>(
+    storage: &S,
+    network: &ValidatorInternalNetworkConfig,
+) -> Result<Vec<ShardConfig>, ViewError> {
+    let Some(addresses) = storage.read_shard_addresses().await? else {
+        storage
+            .write_shard_addresses(&shard_addresses(&network.shards))
+            .await?;
+        return Ok(network.shards.clone());
+    };
+    let shards = addresses
+        .into_iter()
+        .map(|(host, port)| {
+            let metrics_port = network
+                .shards
+                .iter()
+                .find(|shard| shard.host == host && shard.port == port)
+                .and_then(|shard| shard.metrics_port);
+            ShardConfig {
+                host,
+                port,
+                metrics_port,
+            }
+        })
+        .collect();
+    Ok(shards)
+}
+
+fn shard_addresses(shards: &[ShardConfig]) -> Vec<(String, u16)> {
+    shards
+        .iter()
+        .map(|shard| (shard.host.clone(), shard.port))
+        .collect()
+}
+
+/// The shards that the proxy forwards the requests for each chain to.
+pub(crate) struct ShardRouter {
+    routes: watch::Sender<Routes>,
+    /// Held while the chains are being handed off, so that there is one handoff at a time.
+    handoff: Mutex<()>,
+}
+
+#[derive(Clone)]
+struct Routes {
+    assignment: Arc<ShardAssignment>,
+    /// Read-locked by the requests that were forwarded with these routes, until they are
+    /// handled.
+    in_flight: Arc<RwLock<()>>,
+}
+
+/// The shard to forward a request to. The chains are not handed off until the request
+/// is handled and the route is dropped.
+pub(crate) struct Route {
+    pub shard: ShardConfig,
+    _in_flight: OwnedRwLockReadGuard<()>,
+}
+
+impl ShardRouter {
+    pub fn new(network: &ValidatorInternalNetworkConfig) -> Self {
+        let routes = Routes {
+            assignment: Arc::new(ShardAssignment::new(network)),
+            in_flight: Arc::default(),
+        };
+        Self {
+            routes: watch::Sender::new(routes),
+            handoff: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current assignment of chains to shards.
+    pub fn assignment(&self) -> Arc<ShardAssignment> {
+        self.routes.borrow().assignment.clone()
+    }
+
+    /// Returns the shard that `chain_id` is assigned to, waiting until the chain is handed
+    /// off if it is moving to another shard.
+    pub async fn route(&self, chain_id: ChainId) -> Route {
+        let mut receiver = self.routes.subscribe();
+        loop {
+            let routes = receiver.borrow_and_update().clone();
+            if let Some(shard) = routes.assignment.shard_for(chain_id) {
+                let in_flight = routes.in_flight.clone().read_owned().await;
+                // If a handoff started in the meantime, the chain may be moving.
+                if Arc::ptr_eq(&routes.in_flight, &self.routes.borrow().in_flight) {
+                    return Route {
+                        shard: shard.clone(),
+                        _in_flight: in_flight,
+                    };
+                }
+                continue;
+            }
+            receiver
+                .changed()
+                .await
+                .expect("the router keeps the sender alive");
+        }
+    }
+
+    /// Hands off the chains to the new list of `shards`, using `send` to send each step of
+    /// the handoff to a shard, and records the new list of shards in `storage`. `send`
+    /// returns the number of chain workers that the shard unloaded.
+    pub async fn hand_off<S, F, Fut, E>(
+        &self,
+        storage: &S,
+        shards: Vec<ShardConfig>,
+        send: F,
+    ) -> Result<HandoffOutcome, HandoffError>
+    where
+        S: Storage,
+        F: Fn(ShardConfig, ShardHandoff) -> Fut,
+        Fut: Future<Output = Result<usize, E>>,
+        E: std::fmt::Display,
+    {
+        let _handoff = self
+            .handoff
+            .try_lock()
+            .map_err(|_| HandoffError::InProgress)?;
+        if shards.is_empty() {
+            return Err(HandoffError::NoShards);
+        }
+        let previous = self.assignment();
+        let previous_shards = previous.shards().to_vec();
+        info!(
+            "Handing off the chains from {} to {} shards",
+            previous_shards.len(),
+            shards.len()
+        );
+
+        let mut assignment = (*previous).clone();
+        assignment.begin_handoff(shards.clone());
+        let previous_routes = self.routes.send_replace(Routes {
+            assignment: Arc::new(assignment),
+            in_flight: Arc::default(),
+        });
+        // Wait until the requests that may be for the moving chains are handled.
+        drop(previous_routes.in_flight.write().await);
+
+        let mut targets = previous_shards.clone();
+        for shard in &shards {
+            if !targets
+                .iter()
+                .any(|target| target.address() == shard.address())
+            {
+                targets.push(shard.clone());
+            }
+        }
+        let send_to_targets = |handoff: ShardHandoff| {
+            future::try_join_all(targets.iter().map(|shard| {
+                let address = shard.address();
+                let result = send(shard.clone(), handoff.clone());
+                async move {
+                    result.await.map_err(|error| HandoffError::Shard {
+                        address,
+                        error: error.to_string(),
+                    })
+                }
+            }))
+        };
+        let result = async {
+            let released = send_to_targets(ShardHandoff {
+                shards: shards.clone(),
+                complete: false,
+            })
+            .await?;
+            storage
+                .write_shard_addresses(&shard_addresses(&shards))
+                .await?;
+            let completed = send_to_targets(ShardHandoff {
+                shards: shards.clone(),
+                complete: true,
+            })
+            .await?;
+            Ok::<_, HandoffError>(released.into_iter().chain(completed).sum())
+        }
+        .await;
+
+        let mut assignment = (*previous).clone();
+        match result {
+            Ok(unloaded_chains) => {
+                assignment.complete_handoff(shards.clone());
+                self.routes
+                    .send_modify(|routes| routes.assignment = Arc::new(assignment));
+                info!("Handed off the chains to {} shards", shards.len());
+                Ok(HandoffOutcome {
+                    previous_shards: previous_shards.len(),
+                    shards: shards.len(),
+                    unloaded_chains,
+                })
+            }
+            Err(error) => {
+                warn!(%error, "Failed to hand off the chains; restoring the previous shards");
+                if let Err(error) = storage
+                    .write_shard_addresses(&shard_addresses(&previous_shards))
+                    .await
+                {
+                    warn!(%error, "Failed to record the previous shards");
+                }
+                let restored = send_to_targets(ShardHandoff {
+                    shards: previous_shards,
+                    complete: true,
+                })
+                .await;
+                if let Err(error) = restored {
+                    warn!(%error, "Failed to restore the previous shards");
+                }
+                self.routes
+                    .send_modify(|routes| routes.assignment = Arc::new(assignment));
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use linera_base::{
+        crypto::{CryptoHash, ValidatorPublicKey},
+        identifiers::ChainId,
+    };
+    use linera_rpc::config::{
+        NetworkProtocol, ShardConfig, ShardHandoff, TlsConfig, ValidatorInternalNetworkConfig,
+    };
+    use linera_storage::{DbStorage, TestClock};
+    use linera_views::memory::MemoryStore;
+
+    use super::{load_shards, HandoffError, ShardRouter};
+
+    type TestStorage = DbStorage<MemoryStore, TestClock>;
+
+    fn shard(port: u16) -> ShardConfig {
+        ShardConfig {
+            host: "localhost".to_string(),
+            port,
+            metrics_port: None,
+        }
+    }
+
+    fn network(shards: Vec<ShardConfig>) -> ValidatorInternalNetworkConfig {
+        ValidatorInternalNetworkConfig {
+            public_key: ValidatorPublicKey::test_key(0),
+            protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+            shards,
+            host: "localhost".to_string(),
+            port: 10000,
+            block_exporters: Vec::new(),
+            metrics_port: 10001,
+            archive: false,
+            block_body_retention: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hand_off_to_more_shards() {
+        let previous = network(vec![shard(9100), shard(9101)]);
+        let next = network(vec![shard(9100), shard(9101), shard(9102)]);
+        let storage = TestStorage::make_test_storage(None).await;
+        assert_eq!(
+            load_shards(&storage, &previous).await.unwrap(),
+            previous.shards
+        );
+        let router = ShardRouter::new(&previous);
+        let chain_id = (0..)
+            .map(|i| ChainId(CryptoHash::test_hash(format!("chain {i}"))))
+            .find(|chain_id| previous.get_shard_for(*chain_id) != next.get_shard_for(*chain_id))
+            .unwrap();
+        assert_eq!(
+            &router.route(chain_id).await.shard,
+            previous.get_shard_for(chain_id)
+        );
+
+        let steps = Mutex::new(Vec::new());
+        let handoff = router.hand_off(
+            &storage,
+            next.shards.clone(),
+            |shard, handoff: ShardHandoff| {
+                steps.lock().unwrap().push((shard.port, handoff.complete));
+                async {
+                    tokio::task::yield_now().await;
+                    Ok::<_, HandoffError>(1)
+                }
+            },
+        );
+        let route = router.route(chain_id);
+        let (outcome, route) = tokio::join!(handoff, route);
+        let outcome = outcome.unwrap();
+        assert_eq!(outcome.previous_shards, 2);
+        assert_eq!(outcome.shards, 3);
+        assert_eq!(outcome.unloaded_chains, 6);
+        // The request for the moving chain was held back until the handoff completed.
+        assert_eq!(&route.shard, next.get_shard_for(chain_id));
+        // A restarted server assigns the chains to the new shards, even with the previous
+        // configuration.
+        assert_eq!(load_shards(&storage, &previous).await.unwrap(), next.shards);
+        let mut steps = steps.into_inner().unwrap();
+        steps.sort();
+        assert_eq!(
+            steps,
+            [
+                (9100, false),
+                (9100, true),
+                (9101, false),
+                (9101, true),
+                (9102, false),
+                (9102, true)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_hand_off_restores_the_shards() {
+        let previous = network(vec![shard(9100), shard(9101)]);
+        let storage = TestStorage::make_test_storage(None).await;
+        let router = ShardRouter::new(&previous);
+        let error = router
+            .hand_off(
+                &storage,
+                vec![shard(9100)],
+                |shard, handoff: ShardHandoff| async move {
+                    if shard.port == 9101 && !handoff.complete {
+                        Err("unreachable")
+                    } else {
+                        Ok(0)
+                    }
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HandoffError::Shard { .. }));
+        assert_eq!(router.assignment().shards(), previous.shards.as_slice());
+        assert_eq!(router.assignment().next_shards(), None);
+        assert_eq!(
+            load_shards(&storage, &previous).await.unwrap(),
+            previous.shards
+        );
+
+        let route = tokio::time::timeout(
+            Duration::from_secs(1),
+            router.route(ChainId(CryptoHash::test_hash("chain"))),
+        )
+        .await
+        .unwrap();
+        assert!(previous.shards.contains(&route.shard));
+    }
+}
@@ -3,37 +3,25 @@
 
 #![deny(clippy::large_futures)]
 
-use std::{net::SocketAddr, path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use anyhow::{anyhow, bail, ensure, Result};
-use async_trait::async_trait;
-use futures::{FutureExt as _, SinkExt, StreamExt};
-use linera_base::listen_for_shutdown_signals;
+use anyhow::Result;
+use futures::FutureExt as _;
+use linera_base::tracing::LogFormat;
 use linera_client::config::{GenesisConfig, ValidatorServerConfig};
-use linera_core::{node::NodeError, JoinSetExt as _};
-use linera_rpc::{
-    config::{
-        NetworkProtocol, ShardConfig, ValidatorInternalNetworkPreConfig,
-        ValidatorPublicNetworkPreConfig,
-    },
-    simple::{MessageHandler, TransportProtocol},
-    RpcMessage,
-};
-use linera_sdk::linera_base_types::Blob;
-#[cfg(with_metrics)]
-use linera_service::prometheus_server;
+use linera_execution::ExecutionRuntimeConfig;
+use linera_rpc::grpc::internal_tls::InternalTlsConfig;
 use linera_service::{
-    storage::{Runnable, StorageConfigNamespace},
+    admin::AdminConfig,
+    config_check::{Component, ConfigReport},
+    proxy::{self, AccessLogConfig, ProxyContext, ReplayOptions, StatusPageConfig},
+    storage::StorageConfigNamespace,
     util,
 };
-use linera_storage::Storage;
 use linera_views::{lru_caching::StorageCacheConfig, store::CommonStoreConfig};
-use tokio::task::JoinSet;
-use tokio_util::sync::CancellationToken;
-use tracing::{error, info, instrument};
-
-mod grpc;
-use grpc::GrpcProxy;
 
 /// Options for running the proxy.
 #[derive(clap::Parser, Debug, Clone)]
@@ -41,10 +29,13 @@ use grpc::GrpcProxy;
     name = "Linera Proxy",
     about = "A proxy to redirect incoming requests to Linera Server shards",
     version = linera_version::VersionInfo::default_clap_str(),
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
 )]
 pub struct ProxyOptions {
     /// Path to server configuration.
-    config_path: PathBuf,
+    #[arg(required = true)]
+    config_path: Option<PathBuf>,
 
     /// Timeout for sending queries (ms)
     #[arg(long = "send-timeout-ms",
@@ -60,17 +51,62 @@ pub struct ProxyOptions {
           env = "LINERA_PROXY_RECV_TIMEOUT")]
     recv_timeout: Duration,
 
+    /// The maximal number of recently confirmed chains whose chain info is cached by the
+    /// proxy, using the shards' notifications of new blocks. Set to 0 to disable the cache.
+    /// Only supported with gRPC.
+    #[arg(
+        long,
+        default_value = "1000",
+        env = "LINERA_PROXY_CHAIN_INFO_CACHE_SIZE"
+    )]
+    chain_info_cache_size: usize,
+
+    /// The maximal time a cached chain info is used for, even if the shard sent no
+    /// notification about its chain (ms)
+    #[arg(long = "chain-info-cache-max-age-ms",
+          default_value = "1000",
+          value_parser = util::parse_millis,
+          env = "LINERA_PROXY_CHAIN_INFO_CACHE_MAX_AGE")]
+    chain_info_cache_max_age: Duration,
+
+    /// Configuration for the admin API
+    #[command(flatten)]
+    admin_config: AdminConfig,
+
+    /// Configuration for the public status page
+    #[command(flatten)]
+    status_config: StatusPageConfig,
+
+    /// Configuration for the capture of read-only requests
+    #[command(flatten)]
+    access_log_config: AccessLogConfig,
+
+    /// If set, reject block proposals and blob uploads while the storage's write pressure,
+    /// between 0 (idle) and 1 (saturated), is above this value. Only supported with gRPC.
+    #[arg(long,
+          value_parser = util::parse_write_pressure,
+          env = "LINERA_PROXY_MAX_WRITE_PRESSURE")]
+    max_write_pressure: Option<f64>,
+
+    /// The certificates for TLS with the shards
+    #[command(flatten)]
+    internal_tls: InternalTlsConfig,
+
     /// The number of Tokio worker threads to use.
     #[arg(long, env = "LINERA_PROXY_TOKIO_THREADS")]
     tokio_threads: Option<usize>,
 
+    /// The format of the log output: `plain`, `pretty`, or `json` for structured logs.
+    #[arg(long, env = "RUST_LOG_FORMAT", default_value = "plain")]
+    log_format: LogFormat,
+
     /// The number of Tokio blocking threads to use.
     #[arg(long, env = "LINERA_PROXY_TOKIO_BLOCKING_THREADS")]
     tokio_blocking_threads: Option<usize>,
 
     /// Storage configuration for the blockchain history, chain states and binary blobs.
-    #[arg(long = "storage")]
-    storage_config: StorageConfigNamespace,
+    #[arg(long = "storage", required = true)]
+    storage_config: Option<StorageConfigNamespace>,
 
     /// The maximal number of simultaneous queries to the database
     #[arg(long)]
@@ -92,292 +128,75 @@ pub struct ProxyOptions {
     #[arg(long, default_value = "1000")]
     pub max_cache_entries: usize,
 
-    /// Path to the file describing the initial user chains (aka genesis state)
-    #[arg(long = "genesis")]
-    genesis_config_path: PathBuf,
-}
-
-/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
-/// The proxy can be configured to have a gRPC ingress and egress, or a combination
-/// of TCP / UDP ingress and egress.
-enum Proxy<S>
-where
-    S: Storage + Clone + Send + Sync + 'static,
-{
-    Simple(Box<SimpleProxy<S>>),
-    Grpc(GrpcProxy<S>),
-}
-
-struct ProxyContext {
-    config: ValidatorServerConfig,
-    send_timeout: Duration,
-    recv_timeout: Duration,
-}
-
-impl ProxyContext {
-    pub fn from_options(options: &ProxyOptions) -> Result<Self> {
-        let config = util::read_json(&options.config_path)?;
-        Ok(Self {
-            config,
-            send_timeout: options.send_timeout,
-            recv_timeout: options.recv_timeout,
-        })
-    }
-}
-
-#[async_trait]
-impl Runnable for ProxyContext {
-    type Output = Result<(), anyhow::Error>;
-
-    async fn run<S>(self, storage: S) -> Result<(), anyhow::Error>
-    where
-        S: Storage + Clone + Send + Sync + 'static,
-    {
-        let shutdown_notifier = CancellationToken::new();
-        tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
-        let proxy = Proxy::from_context(self, storage)?;
-        match proxy {
-            Proxy::Simple(simple_proxy) => simple_proxy.run(shutdown_notifier).await,
-            Proxy::Grpc(grpc_proxy) => grpc_proxy.run(shutdown_notifier).await,
-        }
-    }
-}
-
-impl<S> Proxy<S>
-where
-    S: Storage + Clone + Send + Sync + 'static,
-{
-    /// Constructs and configures the [`Proxy`] given [`ProxyContext`].
-    fn from_context(context: ProxyContext, storage: S) -> Result<Self> {
-        let internal_protocol = context.config.internal_network.protocol;
-        let external_protocol = context.config.validator.network.protocol;
-        let proxy = match (internal_protocol, external_protocol) {
-            (NetworkProtocol::Grpc { .. }, NetworkProtocol::Grpc(tls)) => {
-                Self::Grpc(GrpcProxy::new(
-                    context.config.validator.network,
-                    context.config.internal_network,
-                    context.send_timeout,
-                    context.recv_timeout,
-                    tls,
-                    storage,
-                ))
-            }
-            (
-                NetworkProtocol::Simple(internal_transport),
-                NetworkProtocol::Simple(public_transport),
-            ) => Self::Simple(Box::new(SimpleProxy {
-                internal_config: context
-                    .config
-                    .internal_network
-                    .clone_with_protocol(internal_transport),
-                public_config: context
-                    .config
-                    .validator
-                    .network
-                    .clone_with_protocol(public_transport),
-                send_timeout: context.send_timeout,
-                recv_timeout: context.recv_timeout,
-                storage,
-            })),
-            _ => {
-                bail!(
-                    "network protocol mismatch: cannot have {} and {} ",
-                    internal_protocol,
-                    external_protocol,
-                );
-            }
-        };
-
-        Ok(proxy)
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct SimpleProxy<S>
-where
-    S: Storage + Clone + Send + Sync + 'static,
-{
-    public_config: ValidatorPublicNetworkPreConfig<TransportProtocol>,
-    internal_config: ValidatorInternalNetworkPreConfig<TransportProtocol>,
-    send_timeout: Duration,
-    recv_timeout: Duration,
-    storage: S,
-}
-
-#[async_trait]
-impl<S> MessageHandler for SimpleProxy<S>
-where
-    S: Storage + Clone + Send + Sync + 'static,
-{
-    #[instrument(skip_all, fields(chain_id = ?message.target_chain_id()))]
-    async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
-        if message.is_local_message() {
-            match self.try_local_message(message).await {
-                Ok(maybe_response) => {
-                    return maybe_response;
-                }
-                Err(error) => {
-                    error!(error = %error, "Failed to handle local message");
-                    return None;
-                }
-            }
-        }
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    pub negative_cache_ttl_ms: u64,
 
-        let Some(chain_id) = message.target_chain_id() else {
-            error!("Can't proxy message without chain ID");
-            return None;
-        };
+    /// Path to the file describing the initial user chains (aka genesis state)
+    #[arg(long = "genesis", required = true)]
+    genesis_config_path: Option<PathBuf>,
 
-        let shard = self.internal_config.get_shard_for(chain_id).clone();
-        let protocol = self.internal_config.protocol;
-
-        match Self::try_proxy_message(
-            message,
-            shard.clone(),
-            protocol,
-            self.send_timeout,
-            self.recv_timeout,
-        )
-        .await
-        {
-            Ok(maybe_response) => maybe_response,
-            Err(error) => {
-                error!(error = %error, "Failed to proxy message to {}", shard.address());
-                None
-            }
-        }
-    }
+    #[command(subcommand)]
+    command: Option<ProxyCommand>,
 }
 
-impl<S> SimpleProxy<S>
-where
-    S: Storage + Clone + Send + Sync + 'static,
-{
-    #[instrument(name = "SimpleProxy::run", skip_all, fields(port = self.public_config.port, metrics_port = self.internal_config.metrics_port), err)]
-    async fn run(self, shutdown_signal: CancellationToken) -> Result<()> {
-        info!("Starting simple server");
-        let mut join_set = JoinSet::new();
-        let address = self.get_listen_address(self.public_config.port);
-
-        #[cfg(with_metrics)]
-        Self::start_metrics(
-            self.get_listen_address(self.internal_config.metrics_port),
-            shutdown_signal.clone(),
-        );
-
-        self.public_config
-            .protocol
-            .spawn_server(address, self, shutdown_signal, &mut join_set)
-            .join()
-            .await?;
-
-        join_set.await_all_tasks().await;
-
-        Ok(())
-    }
-
-    #[cfg(with_metrics)]
-    pub fn start_metrics(address: SocketAddr, shutdown_signal: CancellationToken) {
-        prometheus_server::start_metrics(address, shutdown_signal)
-    }
-
-    fn get_listen_address(&self, port: u16) -> SocketAddr {
-        SocketAddr::from(([0, 0, 0, 0], port))
-    }
-
-    async fn try_proxy_message(
-        message: RpcMessage,
-        shard: ShardConfig,
-        protocol: TransportProtocol,
-        send_timeout: Duration,
-        recv_timeout: Duration,
-    ) -> Result<Option<RpcMessage>> {
-        let mut connection = protocol.connect((shard.host, shard.port)).await?;
-        linera_base::time::timer::timeout(send_timeout, connection.send(message)).await??;
-        let message = linera_base::time::timer::timeout(recv_timeout, connection.next())
-            .await?
-            .transpose()?;
-        Ok(message)
-    }
-
-    async fn try_local_message(&self, message: RpcMessage) -> Result<Option<RpcMessage>> {
-        use RpcMessage::*;
+#[derive(clap::Subcommand, Debug, Clone)]
+enum ProxyCommand {
+    /// Checks the server configuration, the genesis configuration and the storage of the
+    /// proxy, without running it: that its ports are free, that the hosts of the shards are
+    /// resolvable and that the storage namespace was initialized with this genesis
+    /// configuration. Exits with an error if any check fails.
+    #[command(name = "check-config")]
+    CheckConfig {
+        /// Path to server configuration.
+        config_path: PathBuf,
+
+        /// Storage configuration for the blockchain history, chain states and binary blobs.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// Prints the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
 
-        match message {
-            VersionInfoQuery => {
-                // We assume each shard is running the same version as the proxy
-                Ok(Some(RpcMessage::VersionInfoResponse(
-                    linera_version::VersionInfo::default().into(),
-                )))
-            }
-            NetworkDescriptionQuery => {
-                let description = self
-                    .storage
-                    .read_network_description()
-                    .await?
-                    .ok_or(anyhow!("Cannot find network description in the database"))?;
-                Ok(Some(RpcMessage::NetworkDescriptionResponse(Box::new(
-                    description,
-                ))))
-            }
-            UploadBlob(content) => {
-                let blob = Blob::new(*content);
-                let id = blob.id();
-                ensure!(
-                    self.storage.maybe_write_blobs(&[blob]).await?[0],
-                    "Blob not found"
-                );
-                Ok(Some(RpcMessage::UploadBlobResponse(Box::new(id))))
-            }
-            DownloadBlob(blob_id) => {
-                let content = self.storage.read_blob(*blob_id).await?.into_content();
-                Ok(Some(RpcMessage::DownloadBlobResponse(Box::new(content))))
-            }
-            DownloadConfirmedBlock(hash) => Ok(Some(RpcMessage::DownloadConfirmedBlockResponse(
-                Box::new(self.storage.read_confirmed_block(*hash).await?),
-            ))),
-            DownloadCertificates(hashes) => {
-                let certificates = self.storage.read_certificates(hashes).await?;
-                Ok(Some(RpcMessage::DownloadCertificatesResponse(certificates)))
-            }
-            BlobLastUsedBy(blob_id) => Ok(Some(RpcMessage::BlobLastUsedByResponse(Box::new(
-                self.storage.read_blob_state(*blob_id).await?.last_used_by,
-            )))),
-            MissingBlobIds(blob_ids) => Ok(Some(RpcMessage::MissingBlobIdsResponse(
-                self.storage.missing_blobs(&blob_ids).await?,
-            ))),
-            BlockProposal(_)
-            | LiteCertificate(_)
-            | TimeoutCertificate(_)
-            | ConfirmedCertificate(_)
-            | ValidatedCertificate(_)
-            | ChainInfoQuery(_)
-            | CrossChainRequest(_)
-            | Vote(_)
-            | Error(_)
-            | ChainInfoResponse(_)
-            | VersionInfoResponse(_)
-            | NetworkDescriptionResponse(_)
-            | DownloadBlobResponse(_)
-            | DownloadPendingBlob(_)
-            | DownloadPendingBlobResponse(_)
-            | HandlePendingBlob(_)
-            | BlobLastUsedByResponse(_)
-            | MissingBlobIdsResponse(_)
-            | DownloadConfirmedBlockResponse(_)
-            | DownloadCertificatesResponse(_)
-            | UploadBlobResponse(_) => Err(anyhow::Error::from(NodeError::UnexpectedMessage)),
-        }
-    }
+    /// Sends the read-only requests captured with `--access-log` to a validator again, with
+    /// the same intervals between them, e.g. to load-test a staging deployment. If a
+    /// reference validator is given, its responses are compared with the target's, and the
+    /// command exits with an error if any differ.
+    #[command(name = "replay-access-log")]
+    ReplayAccessLog {
+        /// The access log to replay.
+        access_log: PathBuf,
+
+        #[command(flatten)]
+        options: ReplayOptions,
+
+        /// Prints the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 fn main() -> Result<()> {
     let options = <ProxyOptions as clap::Parser>::parse();
+    if let Some(command) = options.command {
+        linera_base::tracing::init_with_format("proxy", options.log_format);
+        return tokio::runtime::Runtime::new()?.block_on(command.run());
+    }
     let server_config: ValidatorServerConfig =
-        util::read_json(&options.config_path).expect("Fail to read server config");
+        util::read_json(options.config_path()).expect("Fail to read server config");
     let public_key = &server_config.validator.public_key;
 
-    linera_base::tracing::init(&format!("validator-{public_key}-proxy"));
+    linera_base::tracing::init_with_format(
+        &format!("validator-{public_key}-proxy"),
+        options.log_format,
+    );
 
     let mut runtime = if options.tokio_threads == Some(1) {
         tokio::runtime::Builder::new_current_thread()
@@ -398,22 +217,107 @@ fn main() -> Result<()> {
     runtime.enable_all().build()?.block_on(options.run())
 }
 
+impl ProxyCommand {
+    async fn run(self) -> Result<()> {
+        match self {
+            ProxyCommand::CheckConfig {
+                config_path,
+                storage_config,
+                genesis_config_path,
+                json,
+            } => {
+                let report = ConfigReport::check(
+                    Component::Proxy,
+                    &config_path,
+                    &genesis_config_path,
+                    &storage_config,
+                )
+                .await;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    println!("{report}");
+                }
+                if report.has_failures() {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            ProxyCommand::ReplayAccessLog {
+                access_log,
+                options,
+                json,
+            } => {
+                let entries = proxy::read_access_log(&access_log).await?;
+                let report = proxy::replay_access_log(entries, &options).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print!("{report}");
+                }
+                if report.has_mismatches() {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 impl ProxyOptions {
+    fn config_path(&self) -> &Path {
+        self.config_path
+            .as_deref()
+            .expect("the config path is required without a subcommand")
+    }
+
+    fn context(&self, genesis_config: &GenesisConfig) -> Result<ProxyContext> {
+        Ok(ProxyContext {
+            config: util::read_json(self.config_path())?,
+            send_timeout: self.send_timeout,
+            recv_timeout: self.recv_timeout,
+            chain_info_cache_size: self.chain_info_cache_size,
+            chain_info_cache_max_age: self.chain_info_cache_max_age,
+            admin_config: self.admin_config.clone(),
+            internal_tls: self.internal_tls.clone(),
+            status_config: self.status_config.clone(),
+            access_log_config: self.access_log_config.clone(),
+            max_write_pressure: self.max_write_pressure,
+            admin_chain_id: Some(genesis_config.admin_id),
+        })
+    }
+
     async fn run(&self) -> Result<()> {
         let storage_cache_config = StorageCacheConfig {
             max_cache_size: self.max_cache_size,
             max_entry_size: self.max_entry_size,
             max_cache_entries: self.max_cache_entries,
+            negative_cache_ttl_ms: self.negative_cache_ttl_ms,
         };
         let common_config = CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
             max_stream_queries: self.max_stream_queries,
             storage_cache_config,
         };
-        let genesis_config: GenesisConfig = util::read_json(&self.genesis_config_path)?;
-        let store_config = self.storage_config.add_common_config(common_config).await?;
+        let genesis_config_path = self
+            .genesis_config_path
+            .as_ref()
+            .expect("the genesis config is required without a subcommand");
+        let genesis_config: GenesisConfig = util::read_json(genesis_config_path)?;
+        let store_config = self
+            .storage_config
+            .as_ref()
+            .expect("the storage config is required without a subcommand")
+            .add_common_config(common_config)
+            .await?;
         store_config
-            .run_with_storage(&genesis_config, None, ProxyContext::from_options(self)?)
+            .run_with_storage(
+                &genesis_config,
+                None,
+                ExecutionRuntimeConfig::default(),
+                None,
+                self.context(&genesis_config)?,
+            )
             .boxed()
             .await?
     }
@@ -0,0 +1,575 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The proxy of a validator, which redirects incoming requests to the validator's shards.
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, ensure, Result};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use linera_base::{
+    data_types::Blob, identifiers::ChainId, listen_for_shutdown_signals, time::Instant,
+};
+use linera_client::config::ValidatorServerConfig;
+use linera_core::{
+    node::{NodeError, NodeFeatures},
+    JoinSetExt as _,
+};
+use linera_rpc::{
+    config::{
+        NetworkProtocol, ShardConfig, TlsConfig, ValidatorInternalNetworkPreConfig,
+        ValidatorPublicNetworkPreConfig,
+    },
+    grpc::internal_tls::{InternalTlsConfig, PROXY_CERTIFICATE_NAME},
+    simple::{MessageHandler, TransportProtocol},
+    RequestId, RpcMessage,
+};
+use linera_storage::Storage;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument};
+
+#[cfg(with_metrics)]
+use crate::prometheus_server;
+use crate::{
+    admin::{
+        self, AdminBackend, AdminConfig, AdminError, CacheFlush, ChainStatus, ShardStatus,
+        StorageHealth,
+    },
+    storage::Runnable,
+};
+
+mod access_log;
+mod grpc;
+mod handoff;
+mod status;
+mod tip_cache;
+use access_log::AccessLog;
+pub use access_log::{
+    read_access_log, replay_access_log, AccessLogConfig, AccessLogEntry, MethodReport, ReadRequest,
+    ReplayOptions, ReplayReport,
+};
+use grpc::GrpcProxy;
+use handoff::ShardRouter;
+pub use handoff::{load_shards, HandoffError, HandoffOutcome};
+pub use status::{LatencySummary, ShardHealth, StatusPageConfig, SyncStatus, ValidatorStatus};
+use status::{RecentLatencies, StatusPage};
+
+/// The default timeout for sending queries to the shards and receiving their responses.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(4);
+/// The default number of chains whose chain info is cached.
+pub const DEFAULT_CHAIN_INFO_CACHE_SIZE: usize = 1000;
+/// The default time a cached chain info is used for.
+pub const DEFAULT_CHAIN_INFO_CACHE_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
+/// The proxy can be configured to have a gRPC ingress and egress, or a combination
+/// of TCP / UDP ingress and egress.
+#[derive(Clone)]
+enum Proxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    Simple(Box<SimpleProxy<S>>),
+    Grpc(GrpcProxy<S>),
+}
+
+/// The configuration of a proxy.
+pub struct ProxyContext {
+    pub config: ValidatorServerConfig,
+    /// Timeout for sending queries to the shards.
+    pub send_timeout: Duration,
+    /// Timeout for receiving responses from the shards.
+    pub recv_timeout: Duration,
+    /// The maximal number of recently confirmed chains whose chain info is cached. Only
+    /// supported with gRPC.
+    pub chain_info_cache_size: usize,
+    /// The maximal time a cached chain info is used for.
+    pub chain_info_cache_max_age: Duration,
+    /// The configuration of the admin API.
+    pub admin_config: AdminConfig,
+    /// The certificates for TLS with the shards, if the internal network uses it.
+    pub internal_tls: InternalTlsConfig,
+    /// The configuration of the public status page.
+    pub status_config: StatusPageConfig,
+    /// The configuration of the capture of read-only requests.
+    pub access_log_config: AccessLogConfig,
+    /// The storage write pressure above which block proposals and blob uploads are
+    /// rejected, if any. Only supported with gRPC.
+    pub max_write_pressure: Option<f64>,
+    /// The admin chain of the network, whose epoch and height the status page shows.
+    pub admin_chain_id: Option<ChainId>,
+}
+
+impl ProxyContext {
+    /// Creates the configuration of a proxy for the given validator, with the default
+    /// timeouts and cache settings.
+    pub fn new(config: ValidatorServerConfig) -> Self {
+        Self {
+            config,
+            send_timeout: DEFAULT_TIMEOUT,
+            recv_timeout: DEFAULT_TIMEOUT,
+            chain_info_cache_size: DEFAULT_CHAIN_INFO_CACHE_SIZE,
+            chain_info_cache_max_age: DEFAULT_CHAIN_INFO_CACHE_MAX_AGE,
+            admin_config: AdminConfig::default(),
+            internal_tls: InternalTlsConfig::default(),
+            status_config: StatusPageConfig::default(),
+            access_log_config: AccessLogConfig::default(),
+            max_write_pressure: None,
+            admin_chain_id: None,
+        }
+    }
+
+    /// Runs the proxy until `shutdown_signal` is cancelled.
+    pub async fn run_until<S>(self, storage: S, shutdown_signal: CancellationToken) -> Result<()>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let admin_config = self.admin_config.clone();
+        let status_config = self.status_config.clone();
+        let admin_chain_id = self.admin_chain_id;
+        let shards = load_shards(&storage, &self.config.internal_network).await?;
+        ensure!(
+            shards == self.config.internal_network.shards,
+            "the chains were handed off to the shards {:?}, but the configuration lists {:?}; \
+             update the configuration to list the same shards",
+            shards.iter().map(ShardConfig::address).collect::<Vec<_>>(),
+            self.config
+                .internal_network
+                .shards
+                .iter()
+                .map(ShardConfig::address)
+                .collect::<Vec<_>>(),
+        );
+        let access_log = AccessLog::start(&self.access_log_config, shutdown_signal.clone()).await?;
+        let proxy = Proxy::from_context(self, storage.clone(), access_log)?;
+        let proxy_admin = ProxyAdmin {
+            storage,
+            proxy: proxy.clone(),
+        };
+        let status_page = StatusPage::new(
+            proxy_admin.clone(),
+            admin_chain_id,
+            proxy.recent_latencies(),
+            status_config.status_cache_duration,
+        );
+        status::start_status_page(&status_config, status_page, shutdown_signal.clone())?;
+        admin::start_admin_api(&admin_config, proxy_admin, shutdown_signal.clone())?;
+        match proxy {
+            Proxy::Simple(simple_proxy) => simple_proxy.run(shutdown_signal).await,
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.run(shutdown_signal).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Runnable for ProxyContext {
+    type Output = Result<(), anyhow::Error>;
+
+    async fn run<S>(self, storage: S) -> Result<(), anyhow::Error>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let shutdown_notifier = CancellationToken::new();
+        tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
+        self.run_until(storage, shutdown_notifier).await
+    }
+}
+
+impl<S> Proxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    /// Constructs and configures the [`Proxy`] given [`ProxyContext`].
+    fn from_context(
+        context: ProxyContext,
+        storage: S,
+        access_log: Option<AccessLog>,
+    ) -> Result<Self> {
+        let internal_protocol = context.config.internal_network.protocol;
+        let external_protocol = context.config.validator.network.protocol;
+        let proxy = match (internal_protocol, external_protocol) {
+            (NetworkProtocol::Grpc(internal_tls), NetworkProtocol::Grpc(tls)) => {
+                let internal_tls = match internal_tls {
+                    TlsConfig::ClearText => None,
+                    TlsConfig::Tls => Some(
+                        context
+                            .internal_tls
+                            .load(PROXY_CERTIFICATE_NAME, SystemTime::now())?,
+                    ),
+                };
+                Self::Grpc(GrpcProxy::new(
+                    context.config.validator.network,
+                    context.config.internal_network,
+                    context.send_timeout,
+                    context.recv_timeout,
+                    context.chain_info_cache_size,
+                    context.chain_info_cache_max_age,
+                    tls,
+                    internal_tls,
+                    access_log,
+                    context.max_write_pressure,
+                    storage,
+                ))
+            }
+            (
+                NetworkProtocol::Simple(internal_transport),
+                NetworkProtocol::Simple(public_transport),
+            ) => {
+                ensure!(
+                    access_log.is_none(),
+                    "the access log is only supported with gRPC"
+                );
+                ensure!(
+                    context.max_write_pressure.is_none(),
+                    "the storage write throttling is only supported with gRPC"
+                );
+                Self::Simple(Box::new(SimpleProxy {
+                    router: Arc::new(ShardRouter::new(&context.config.internal_network)),
+                    internal_config: context
+                        .config
+                        .internal_network
+                        .clone_with_protocol(internal_transport),
+                    public_config: context
+                        .config
+                        .validator
+                        .network
+                        .clone_with_protocol(public_transport),
+                    send_timeout: context.send_timeout,
+                    recv_timeout: context.recv_timeout,
+                    storage,
+                    latencies: Arc::default(),
+                }))
+            }
+            _ => {
+                bail!(
+                    "network protocol mismatch: cannot have {} and {} ",
+                    internal_protocol,
+                    external_protocol,
+                );
+            }
+        };
+
+        Ok(proxy)
+    }
+
+    /// Returns the latencies of the recent requests that the proxy handled.
+    fn recent_latencies(&self) -> Arc<RecentLatencies> {
+        match self {
+            Proxy::Simple(simple_proxy) => simple_proxy.latencies.clone(),
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.recent_latencies(),
+        }
+    }
+
+    /// Returns the shards that the proxy forwards the requests for each chain to.
+    fn router(&self) -> &ShardRouter {
+        match self {
+            Proxy::Simple(simple_proxy) => &simple_proxy.router,
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.router(),
+        }
+    }
+
+    /// Hands off the chains to the new list of `shards`.
+    async fn hand_off(&self, shards: Vec<ShardConfig>) -> Result<HandoffOutcome, HandoffError> {
+        match self {
+            Proxy::Simple(simple_proxy) => simple_proxy.hand_off(shards).await,
+            Proxy::Grpc(grpc_proxy) => grpc_proxy.hand_off(shards).await,
+        }
+    }
+}
+
+/// The admin API of a proxy.
+#[derive(Clone)]
+struct ProxyAdmin<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    storage: S,
+    proxy: Proxy<S>,
+}
+
+#[async_trait]
+impl<S> AdminBackend for ProxyAdmin<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    async fn shards(&self) -> Vec<ShardStatus> {
+        let mut shards = Vec::new();
+        let assignment = self.proxy.router().assignment();
+        for (shard_id, shard) in assignment.shards().iter().enumerate() {
+            shards.push(ShardStatus {
+                shard_id,
+                host: shard.host.clone(),
+                port: shard.port,
+                reachable: admin::is_reachable(&shard.host, shard.port, Duration::from_secs(1))
+                    .await,
+                worker: None,
+            });
+        }
+        shards
+    }
+
+    async fn chain(&self, chain_id: ChainId) -> Result<ChainStatus, AdminError> {
+        let shard_id = self.proxy.router().assignment().shard_id(chain_id);
+        admin::chain_status(&self.storage, chain_id, shard_id, None).await
+    }
+
+    async fn archive_chain(&self, _chain_id: ChainId) -> Result<(), AdminError> {
+        Err(AdminError::Unsupported(
+            "the chains are archived by the admin API of the servers running their shards",
+        ))
+    }
+
+    async fn storage_health(&self) -> StorageHealth {
+        admin::check_storage(&self.storage).await
+    }
+
+    async fn flush_caches(&self) -> CacheFlush {
+        CacheFlush {
+            unloaded_chains: 0,
+            cleared_entries: match &self.proxy {
+                Proxy::Simple(_) => None,
+                Proxy::Grpc(grpc_proxy) => grpc_proxy.chain_info_cache(),
+            }
+            .map_or(0, |cache| cache.clear()),
+        }
+    }
+
+    async fn hand_off(&self, shards: Vec<ShardConfig>) -> Result<HandoffOutcome, AdminError> {
+        Ok(self.proxy.hand_off(shards).await?)
+    }
+}
+
+#[derive(Clone)]
+pub struct SimpleProxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    /// The shards that the requests for each chain are forwarded to.
+    router: Arc<ShardRouter>,
+    public_config: ValidatorPublicNetworkPreConfig<TransportProtocol>,
+    internal_config: ValidatorInternalNetworkPreConfig<TransportProtocol>,
+    send_timeout: Duration,
+    recv_timeout: Duration,
+    storage: S,
+    /// The latencies of the recent requests, for the status page.
+    latencies: Arc<RecentLatencies>,
+}
+
+#[async_trait]
+impl<S> MessageHandler for SimpleProxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    #[instrument(
+        skip_all,
+        fields(chain_id = ?message.target_chain_id(), request_id = tracing::field::Empty)
+    )]
+    async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+        let request_id = RequestId::generate();
+        tracing::Span::current().record("request_id", tracing::field::display(request_id));
+        if message.is_local_message() {
+            match self.try_local_message(message).await {
+                Ok(maybe_response) => {
+                    return maybe_response;
+                }
+                Err(error) => {
+                    error!(error = %error, "Failed to handle local message");
+                    return None;
+                }
+            }
+        }
+
+        let Some(chain_id) = message.target_chain_id() else {
+            error!("Can't proxy message without chain ID");
+            return None;
+        };
+
+        // The chain is not handed off to another shard until the route is dropped.
+        let route = self.router.route(chain_id).await;
+        let shard = route.shard.clone();
+        let protocol = self.internal_config.protocol;
+
+        let start = Instant::now();
+        let result = Self::try_proxy_message(
+            message.with_request_id(Some(request_id)),
+            shard.clone(),
+            protocol,
+            self.send_timeout,
+            self.recv_timeout,
+        )
+        .await;
+        drop(route);
+        self.latencies.record(start.elapsed());
+        match result {
+            Ok(maybe_response) => maybe_response,
+            Err(error) => {
+                error!(error = %error, "Failed to proxy message to {}", shard.address());
+                None
+            }
+        }
+    }
+}
+
+impl<S> SimpleProxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    #[instrument(name = "SimpleProxy::run", skip_all, fields(port = self.public_config.port, metrics_port = self.internal_config.metrics_port), err)]
+    async fn run(self, shutdown_signal: CancellationToken) -> Result<()> {
+        info!("Starting simple server");
+        let mut join_set = JoinSet::new();
+        let address = self.get_listen_address(self.public_config.port);
+
+        #[cfg(with_metrics)]
+        Self::start_metrics(
+            self.get_listen_address(self.internal_config.metrics_port),
+            shutdown_signal.clone(),
+        );
+
+        self.public_config
+            .protocol
+            .spawn_server(address, self, shutdown_signal, &mut join_set)
+            .join()
+            .await?;
+
+        join_set.await_all_tasks().await;
+
+        Ok(())
+    }
+
+    #[cfg(with_metrics)]
+    fn start_metrics(address: SocketAddr, shutdown_signal: CancellationToken) {
+        prometheus_server::start_metrics(address, shutdown_signal)
+    }
+
+    fn get_listen_address(&self, port: u16) -> SocketAddr {
+        SocketAddr::from(([0, 0, 0, 0], port))
+    }
+
+    /// Hands off the chains to the new list of `shards`. See the [`handoff`] module for how
+    /// the chains are handed off.
+    async fn hand_off(&self, shards: Vec<ShardConfig>) -> Result<HandoffOutcome, HandoffError> {
+        let protocol = self.internal_config.protocol;
+        self.router
+            .hand_off(&self.storage, shards, |shard, handoff| async move {
+                let message = RpcMessage::ShardHandoff(Box::new(handoff));
+                let response = Self::try_proxy_message(
+                    message,
+                    shard,
+                    protocol,
+                    self.send_timeout,
+                    self.recv_timeout,
+                )
+                .await?;
+                match response {
+                    Some(RpcMessage::ShardHandoffResponse(unloaded_chains)) => {
+                        Ok(unloaded_chains as usize)
+                    }
+                    Some(RpcMessage::Error(error)) => Err(anyhow::Error::from(*error)),
+                    _ => Err(anyhow::Error::from(NodeError::UnexpectedMessage)),
+                }
+            })
+            .await
+    }
+
+    async fn try_proxy_message(
+        message: RpcMessage,
+        shard: ShardConfig,
+        protocol: TransportProtocol,
+        send_timeout: Duration,
+        recv_timeout: Duration,
+    ) -> Result<Option<RpcMessage>> {
+        let mut connection = protocol.connect((shard.host, shard.port)).await?;
+        linera_base::time::timer::timeout(send_timeout, connection.send(message)).await??;
+        let message = linera_base::time::timer::timeout(recv_timeout, connection.next())
+            .await?
+            .transpose()?;
+        Ok(message)
+    }
+
+    async fn try_local_message(&self, message: RpcMessage) -> Result<Option<RpcMessage>> {
+        use RpcMessage::*;
+
+        match message {
+            VersionInfoQuery => {
+                // We assume each shard is running the same version as the proxy
+                Ok(Some(RpcMessage::VersionInfoResponse(
+                    linera_version::VersionInfo::default().into(),
+                )))
+            }
+            NetworkDescriptionQuery => {
+                let description = self
+                    .storage
+                    .read_network_description()
+                    .await?
+                    .ok_or(anyhow!("Cannot find network description in the database"))?;
+                Ok(Some(RpcMessage::NetworkDescriptionResponse(Box::new(
+                    description,
+                ))))
+            }
+            NodeFeaturesQuery => Ok(Some(RpcMessage::NodeFeaturesResponse(Box::new(
+                NodeFeatures {
+                    archive: self.internal_config.archive,
+                    block_body_retention: self.internal_config.block_body_retention,
+                },
+            )))),
+            UploadBlob(content) => {
+                let blob = Blob::new(*content);
+                let id = blob.id();
+                ensure!(
+                    self.storage.maybe_write_blobs(&[blob]).await?[0],
+                    "Blob not found"
+                );
+                Ok(Some(RpcMessage::UploadBlobResponse(Box::new(id))))
+            }
+            DownloadBlob(blob_id) => {
+                let content = self.storage.read_blob(*blob_id).await?.into_content();
+                Ok(Some(RpcMessage::DownloadBlobResponse(Box::new(content))))
+            }
+            DownloadConfirmedBlock(hash) => Ok(Some(RpcMessage::DownloadConfirmedBlockResponse(
+                Box::new(self.storage.read_confirmed_block(*hash).await?),
+            ))),
+            DownloadCertificates(hashes) => {
+                let certificates = self.storage.read_certificates(hashes).await?;
+                Ok(Some(RpcMessage::DownloadCertificatesResponse(certificates)))
+            }
+            BlobLastUsedBy(blob_id) => Ok(Some(RpcMessage::BlobLastUsedByResponse(Box::new(
+                self.storage.read_blob_state(*blob_id).await?.last_used_by,
+            )))),
+            MissingBlobIds(blob_ids) => Ok(Some(RpcMessage::MissingBlobIdsResponse(
+                self.storage.missing_blobs(&blob_ids).await?,
+            ))),
+            BlockProposal(_)
+            | LiteCertificate(_)
+            | TimeoutCertificate(_)
+            | ConfirmedCertificate(_)
+            | ValidatedCertificate(_)
+            | ChainInfoQuery(_)
+            | CrossChainRequest(_)
+            | Traced(_)
+            | ShardHandoff(_)
+            | ShardHandoffResponse(_)
+            | Vote(_)
+            | Error(_)
+            | ChainInfoResponse(_)
+            | VersionInfoResponse(_)
+            | NetworkDescriptionResponse(_)
+            | NodeFeaturesResponse(_)
+            | DownloadBlobResponse(_)
+            | DownloadPendingBlob(_)
+            | DownloadPendingBlobResponse(_)
+            | HandlePendingBlob(_)
+            | BlobLastUsedByResponse(_)
+            | MissingBlobIdsResponse(_)
+            | DownloadConfirmedBlockResponse(_)
+            | DownloadCertificatesResponse(_)
+            | UploadBlobResponse(_) => Err(anyhow::Error::from(NodeError::UnexpectedMessage)),
+        }
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A public status page of the proxy, so that anyone can check how a validator is doing
+//! without gRPC tooling.
+//!
+//! Unlike the admin API, the page requires no authentication, so it only shows what the
+//! validator's users can already observe, and nothing about its internal network:
+//!
+//! * `GET /status`: the status as JSON. The response is `503 Service Unavailable` if a
+//!   shard or the storage doesn't respond.
+//! * `GET /`: the same status as a small HTML page.
+//!
+//! The status is computed at most once per cache duration, however many requests arrive,
+//! and the responses carry a matching `Cache-Control` header so that browsers and HTTP
+//! caches may keep them for as long.
+
+use std::{collections::VecDeque, fmt::Write as _, net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Context as _;
+use axum::{
+    extract::State,
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use linera_base::{
+    data_types::{BlockHeight, Epoch, Timestamp},
+    identifiers::ChainId,
+    time::Instant,
+};
+use linera_storage::Storage;
+use linera_version::VersionInfo;
+use linera_views::views::ViewError;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use super::ProxyAdmin;
+use crate::{
+    admin::{self, AdminBackend as _},
+    util,
+};
+
+/// The number of recent requests whose latency is summarized on the status page.
+const RECENT_LATENCIES: usize = 1024;
+
+/// The configuration of the public status page.
+#[derive(Clone, Debug, clap::Args)]
+pub struct StatusPageConfig {
+    /// The port of the public status page. The page is disabled if this is not set.
+    #[arg(long)]
+    pub status_port: Option<u16>,
+
+    /// The address that the status page listens on.
+    #[arg(long, default_value = "0.0.0.0")]
+    pub status_host: String,
+
+    /// For how long the status is reused before being computed again, in milliseconds.
+    /// Responses may be cached by HTTP caches for as long.
+    #[arg(
+        long = "status-cache-ms",
+        default_value = "5000",
+        value_parser = util::parse_millis
+    )]
+    pub status_cache_duration: Duration,
+}
+
+impl Default for StatusPageConfig {
+    fn default() -> Self {
+        Self {
+            status_port: None,
+            status_host: "0.0.0.0".to_string(),
+            status_cache_duration: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The public status of a validator.
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidatorStatus {
+    /// Whether all the shards and the storage respond.
+    pub healthy: bool,
+    pub version: VersionInfo,
+    /// The name of the network, if the storage was initialized.
+    pub network: Option<String>,
+    /// The current epoch, according to the validator's admin chain.
+    pub epoch: Option<Epoch>,
+    /// How far the validator's admin chain is, if it is known.
+    pub admin_chain: Option<SyncStatus>,
+    pub storage_healthy: bool,
+    pub shards: Vec<ShardHealth>,
+    /// The latency of the recent requests handled by the proxy.
+    pub latency: LatencySummary,
+}
+
+/// How far a validator is in a chain.
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncStatus {
+    pub chain_id: ChainId,
+    pub next_block_height: BlockHeight,
+    /// The timestamp of the latest block.
+    pub timestamp: Timestamp,
+}
+
+/// Whether one of the validator's shards responds.
+#[derive(Clone, Debug, Serialize)]
+pub struct ShardHealth {
+    pub shard_id: usize,
+    pub reachable: bool,
+}
+
+/// Percentiles of the latency of recent requests, in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LatencySummary {
+    /// The number of requests that the percentiles are computed from.
+    pub requests: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// The latencies of the most recent requests handled by the proxy.
+#[derive(Debug, Default)]
+pub(super) struct RecentLatencies(std::sync::Mutex<VecDeque<Duration>>);
+
+impl RecentLatencies {
+    /// Records the latency of a request, forgetting the oldest one if there are too many.
+    pub(super) fn record(&self, latency: Duration) {
+        let mut latencies = self.0.lock().unwrap();
+        if latencies.len() == RECENT_LATENCIES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Returns the percentiles of the recorded latencies.
+    pub(super) fn summary(&self) -> LatencySummary {
+        let samples = self.0.lock().unwrap().iter().copied().collect();
+        LatencySummary::from_latencies(samples)
+    }
+}
+
+impl LatencySummary {
+    /// Returns the percentiles of the given latencies.
+    pub(super) fn from_latencies(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let percentile = |percent: usize| {
+            let index = (samples.len() * percent).div_ceil(100).saturating_sub(1);
+            samples
+                .get(index)
+                .map_or(0.0, |latency| latency.as_micros() as f64 / 1000.0)
+        };
+        LatencySummary {
+            requests: samples.len(),
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+        }
+    }
+}
+
+/// What the status page reports on, and the last status it computed.
+pub(super) struct StatusPage<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    admin: ProxyAdmin<S>,
+    admin_chain_id: Option<ChainId>,
+    latencies: Arc<RecentLatencies>,
+    cache_duration: Duration,
+    cached: tokio::sync::Mutex<Option<(Instant, ValidatorStatus)>>,
+}
+
+impl<S> StatusPage<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    pub(super) fn new(
+        admin: ProxyAdmin<S>,
+        admin_chain_id: Option<ChainId>,
+        latencies: Arc<RecentLatencies>,
+        cache_duration: Duration,
+    ) -> Self {
+        Self {
+            admin,
+            admin_chain_id,
+            latencies,
+            cache_duration,
+            cached: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Returns the status, computing it again if the cached one is too old.
+    async fn status(&self) -> ValidatorStatus {
+        // Holding the lock while computing makes concurrent requests wait for one result.
+        let mut cached = self.cached.lock().await;
+        if let Some((computed_at, status)) = &*cached {
+            if computed_at.elapsed() < self.cache_duration {
+                return status.clone();
+            }
+        }
+        let status = self.compute_status().await;
+        *cached = Some((Instant::now(), status.clone()));
+        status
+    }
+
+    async fn compute_status(&self) -> ValidatorStatus {
+        let storage = &self.admin.storage;
+        let storage_healthy = admin::check_storage(storage).await.healthy;
+        let network = storage
+            .read_network_description()
+            .await
+            .ok()
+            .flatten()
+            .map(|description| description.name);
+        let (epoch, admin_chain) = match self.admin_chain_id {
+            Some(chain_id) => match self.sync_status(chain_id).await {
+                Ok(Some((epoch, sync_status))) => (epoch, Some(sync_status)),
+                Ok(None) | Err(_) => (None, None),
+            },
+            None => (None, None),
+        };
+        let shards = self
+            .admin
+            .shards()
+            .await
+            .into_iter()
+            .map(|shard| ShardHealth {
+                shard_id: shard.shard_id,
+                reachable: shard.reachable,
+            })
+            .collect::<Vec<_>>();
+        ValidatorStatus {
+            healthy: storage_healthy && shards.iter().all(|shard| shard.reachable),
+            version: VersionInfo::default(),
+            network,
+            epoch,
+            admin_chain,
+            storage_healthy,
+            shards,
+            latency: self.latencies.summary(),
+        }
+    }
+
+    /// Reads the epoch and the tip of a chain, if it exists in storage.
+    async fn sync_status(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Option<(Option<Epoch>, SyncStatus)>, ViewError> {
+        let chain = self.admin.storage.load_chain(chain_id).await?;
+        let system = &chain.execution_state.system;
+        if system.description.get().is_none() {
+            return Ok(None);
+        }
+        let sync_status = SyncStatus {
+            chain_id,
+            next_block_height: chain.tip_state.get().next_block_height,
+            timestamp: *system.timestamp.get(),
+        };
+        Ok(Some((*system.epoch.get(), sync_status)))
+    }
+}
+
+/// Starts serving the status page in the background, if it is enabled.
+pub(super) fn start_status_page<S>(
+    config: &StatusPageConfig,
+    page: StatusPage<S>,
+    shutdown_signal: CancellationToken,
+) -> anyhow::Result<()>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let Some(port) = config.status_port else {
+        return Ok(());
+    };
+    let address: SocketAddr = format!("{}:{}", config.status_host, port)
+        .parse()
+        .context("invalid status page address")?;
+    info!("Starting to serve the status page on {address}");
+    let router = Router::new()
+        .route("/", get(status_html::<S>))
+        .route("/status", get(status_json::<S>))
+        .with_state(Arc::new(page));
+    tokio::spawn(async move {
+        let listener = tokio::net::TcpListener::bind(address)
+            .await
+            .expect("failed to bind the status page's address");
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown_signal.cancelled_owned())
+            .await
+        {
+            panic!("Error serving the status page: {e}");
+        }
+    });
+    Ok(())
+}
+
+async fn status_json<S>(State(page): State<Arc<StatusPage<S>>>) -> Response
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let status = page.status().await;
+    let code = if status.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, [cache_control(&page)], Json(status)).into_response()
+}
+
+async fn status_html<S>(State(page): State<Arc<StatusPage<S>>>) -> Response
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let status = page.status().await;
+    ([cache_control(&page)], Html(render_html(&status))).into_response()
+}
+
+fn cache_control<S>(page: &StatusPage<S>) -> (axum::http::HeaderName, String)
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let max_age = page.cache_duration.as_secs();
+    (CACHE_CONTROL, format!("public, max-age={max_age}"))
+}
+
+/// Renders the status as a self-contained HTML page.
+fn render_html(status: &ValidatorStatus) -> String {
+    let mut rows = Vec::new();
+    let health = if status.healthy {
+        "healthy"
+    } else {
+        "degraded"
+    };
+    rows.push(("Status", health.to_string()));
+    rows.push(("Version", status.version.crate_version.to_string()));
+    rows.push(("Commit", status.version.git_commit.to_string()));
+    if let Some(network) = &status.network {
+        rows.push(("Network", network.clone()));
+    }
+    if let Some(epoch) = status.epoch {
+        rows.push(("Epoch", epoch.to_string()));
+    }
+    if let Some(admin_chain) = &status.admin_chain {
+        rows.push((
+            "Admin chain height",
+            admin_chain.next_block_height.to_string(),
+        ));
+        rows.push(("Latest admin block", admin_chain.timestamp.to_string()));
+    }
+    let storage = if status.storage_healthy { "up" } else { "down" };
+    rows.push(("Storage", storage.to_string()));
+    let reachable = status.shards.iter().filter(|shard| shard.reachable).count();
+    rows.push((
+        "Shards",
+        format!("{reachable} of {} reachable", status.shards.len()),
+    ));
+    let latency = &status.latency;
+    rows.push((
+        "Latency",
+        format!(
+            "p50 {:.1} ms, p90 {:.1} ms, p99 {:.1} ms over {} requests",
+            latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.requests
+        ),
+    ));
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Linera validator</title>\
+         </head><body><h1>Linera validator</h1><table>\n",
+    );
+    for (name, value) in rows {
+        writeln!(
+            html,
+            "<tr><th>{name}</th><td>{}</td></tr>",
+            escape_html(&value)
+        )
+        .expect("writing to a string cannot fail");
+    }
+    html.push_str("</table><p><a href=\"/status\">JSON</a></p></body></html>\n");
+    html
+}
+
+/// Escapes the characters that have a meaning in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{escape_html, LatencySummary, RecentLatencies, RECENT_LATENCIES};
+
+    #[test]
+    fn test_recent_latencies() {
+        let latencies = RecentLatencies::default();
+        assert_eq!(latencies.summary(), LatencySummary::default());
+
+        for millis in 1..=100 {
+            latencies.record(Duration::from_millis(millis));
+        }
+        let summary = latencies.summary();
+        assert_eq!(summary.requests, 100);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p90_ms, 90.0);
+        assert_eq!(summary.p99_ms, 99.0);
+
+        // Only the most recent latencies are kept.
+        for _ in 0..RECENT_LATENCIES {
+            latencies.record(Duration::from_millis(7));
+        }
+        let summary = latencies.summary();
+        assert_eq!(summary.requests, RECENT_LATENCIES);
+        assert_eq!(summary.p99_ms, 7.0);
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<b>\"Tom\" & 'Jerry'</b>"),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+    }
+}
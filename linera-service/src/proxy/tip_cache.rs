@@ -0,0 +1,266 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of the chain information of recently confirmed chains.
+//!
+//! The shards notify the proxy of every new block, new round and new incoming bundle. For
+//! the chains with a recently confirmed block, the proxy keeps the responses of the shard
+//! to chain info queries, so that repeated queries don't have to be forwarded. A cached
+//! response is dropped as soon as a notification for its chain arrives, or a request that
+//! may change the chain's state is forwarded. Notifications can be dropped by the shards
+//! under load, so responses are also dropped after a maximum age.
+
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+use linera_core::worker::{Notification, Reason};
+use lru::LruCache;
+
+/// The maximal number of different queries cached for a single chain.
+const MAX_RESPONSES_PER_CHAIN: usize = 16;
+
+/// The latest confirmed block of a chain, and the cached responses for that state.
+struct ChainTip<R> {
+    height: BlockHeight,
+    /// Incremented whenever the chain's state may have changed.
+    generation: u64,
+    /// The cached responses, by encoded query, with the time they were received.
+    responses: HashMap<Vec<u8>, (R, Instant)>,
+}
+
+impl<R> ChainTip<R> {
+    fn invalidate(&mut self) {
+        self.generation += 1;
+        self.responses.clear();
+    }
+}
+
+/// A snapshot of a chain's cache entry, taken before forwarding a query to the shard.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TipGeneration {
+    height: BlockHeight,
+    generation: u64,
+}
+
+/// The result of looking up a query in the [`ChainTipCache`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum Lookup<R> {
+    /// The cached response.
+    Hit(R),
+    /// No response is cached yet. The generation is to be passed to
+    /// [`ChainTipCache::insert`] once the shard has responded.
+    Miss(TipGeneration),
+    /// The chain had no recently confirmed block.
+    Untracked,
+}
+
+/// Caches the responses to chain info queries for recently confirmed chains.
+pub struct ChainTipCache<R> {
+    max_age: Duration,
+    tips: Mutex<LruCache<ChainId, ChainTip<R>>>,
+}
+
+impl<R: Clone> ChainTipCache<R> {
+    /// Creates a new [`ChainTipCache`] tracking up to `capacity` chains and keeping responses
+    /// for at most `max_age`.
+    pub fn new(capacity: NonZeroUsize, max_age: Duration) -> Self {
+        ChainTipCache {
+            max_age,
+            tips: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Updates the cache with a notification from a shard.
+    pub fn handle_notification(&self, notification: &Notification) {
+        let mut tips = self.tips();
+        match &notification.reason {
+            Reason::NewBlock { height, .. } => match tips.get_mut(&notification.chain_id) {
+                Some(tip) => {
+                    tip.invalidate();
+                    tip.height = tip.height.max(*height);
+                }
+                None => {
+                    tips.put(
+                        notification.chain_id,
+                        ChainTip {
+                            height: *height,
+                            generation: 0,
+                            responses: HashMap::new(),
+                        },
+                    );
+                }
+            },
+            Reason::NewIncomingBundle { .. } | Reason::NewRound { .. } => {
+                self.invalidate_locked(&mut tips, notification.chain_id)
+            }
+        }
+    }
+
+    /// Drops the cached responses for `chain_id`, e.g. because a request that may change its
+    /// state was forwarded to the shard.
+    pub fn invalidate(&self, chain_id: ChainId) {
+        let mut tips = self.tips();
+        self.invalidate_locked(&mut tips, chain_id);
+    }
+
+    /// Looks up the cached response to the encoded `query`.
+    pub fn get(&self, chain_id: ChainId, query: &[u8]) -> Lookup<R> {
+        let mut tips = self.tips();
+        let Some(tip) = tips.get_mut(&chain_id) else {
+            return Lookup::Untracked;
+        };
+        if let Some((response, received_at)) = tip.responses.get(query) {
+            if received_at.elapsed() <= self.max_age {
+                return Lookup::Hit(response.clone());
+            }
+            tip.responses.remove(query);
+        }
+        Lookup::Miss(TipGeneration {
+            height: tip.height,
+            generation: tip.generation,
+        })
+    }
+
+    /// Caches the shard's `response` to the encoded `query`, unless the chain's state may
+    /// have changed since `generation` was obtained, or the response was not for the latest
+    /// confirmed block, i.e. `next_block_height` is not the following height.
+    pub fn insert(
+        &self,
+        chain_id: ChainId,
+        query: Vec<u8>,
+        generation: TipGeneration,
+        next_block_height: BlockHeight,
+        response: R,
+    ) {
+        let mut tips = self.tips();
+        let Some(tip) = tips.get_mut(&chain_id) else {
+            return;
+        };
+        if tip.height != generation.height
+            || tip.generation != generation.generation
+            || tip.height.try_add_one().ok() != Some(next_block_height)
+        {
+            return;
+        }
+        if tip.responses.len() >= MAX_RESPONSES_PER_CHAIN && !tip.responses.contains_key(&query) {
+            return;
+        }
+        tip.responses.insert(query, (response, Instant::now()));
+    }
+
+    /// Drops all cached responses and stops tracking all chains until their next block.
+    /// Returns the number of dropped responses.
+    pub fn clear(&self) -> usize {
+        let mut tips = self.tips();
+        let responses = tips.iter().map(|(_, tip)| tip.responses.len()).sum();
+        tips.clear();
+        responses
+    }
+
+    fn invalidate_locked(&self, tips: &mut LruCache<ChainId, ChainTip<R>>, chain_id: ChainId) {
+        if let Some(tip) = tips.peek_mut(&chain_id) {
+            tip.invalidate();
+        }
+    }
+
+    fn tips(&self) -> MutexGuard<'_, LruCache<ChainId, ChainTip<R>>> {
+        self.tips
+            .lock()
+            .expect("Panics should not happen while holding a lock to the chain tip cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, num::NonZeroUsize, time::Duration};
+
+    use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+    use linera_core::worker::{Notification, Reason};
+
+    use super::{ChainTipCache, Lookup, TipGeneration};
+
+    fn new_block(chain_id: ChainId, height: u64) -> Notification {
+        Notification {
+            chain_id,
+            reason: Reason::NewBlock {
+                height: BlockHeight(height),
+                hash: CryptoHash::test_hash(format!("block {height}")),
+                event_streams: BTreeSet::new(),
+                application_ids: BTreeSet::new(),
+            },
+        }
+    }
+
+    /// Returns the generation if the lookup was a miss, and panics otherwise.
+    fn miss<R: std::fmt::Debug>(lookup: Lookup<R>) -> TipGeneration {
+        match lookup {
+            Lookup::Miss(generation) => generation,
+            lookup => panic!("Expected a cache miss, got {lookup:?}"),
+        }
+    }
+
+    #[test]
+    fn test_chain_tip_cache() {
+        let cache = ChainTipCache::new(NonZeroUsize::new(10).unwrap(), Duration::from_secs(60));
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        let query = b"query".to_vec();
+
+        // Chains without a recently confirmed block are not cached.
+        assert_eq!(cache.get(chain_id, &query), Lookup::Untracked);
+
+        cache.handle_notification(&new_block(chain_id, 3));
+        let generation = miss(cache.get(chain_id, &query));
+        // Responses that are not for the latest block are not cached.
+        cache.insert(chain_id, query.clone(), generation, BlockHeight(3), "old");
+        assert_eq!(miss(cache.get(chain_id, &query)), generation);
+        cache.insert(chain_id, query.clone(), generation, BlockHeight(4), "info");
+        assert_eq!(cache.get(chain_id, &query), Lookup::Hit("info"));
+
+        // Changes of the chain's state drop the response.
+        cache.invalidate(chain_id);
+        let new_generation = miss(cache.get(chain_id, &query));
+        // A response obtained before that change is not cached.
+        cache.insert(chain_id, query.clone(), generation, BlockHeight(4), "stale");
+        miss(cache.get(chain_id, &query));
+
+        cache.insert(
+            chain_id,
+            query.clone(),
+            new_generation,
+            BlockHeight(4),
+            "info",
+        );
+        cache.handle_notification(&new_block(chain_id, 4));
+        miss(cache.get(chain_id, &query));
+    }
+
+    #[test]
+    fn test_chain_tip_cache_clear() {
+        let cache = ChainTipCache::new(NonZeroUsize::new(10).unwrap(), Duration::from_secs(60));
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        cache.handle_notification(&new_block(chain_id, 0));
+        let generation = miss(cache.get(chain_id, b"query"));
+        cache.insert(chain_id, b"query".to_vec(), generation, BlockHeight(1), ());
+        assert_eq!(cache.clear(), 1);
+        assert_eq!(cache.get(chain_id, b"query"), Lookup::Untracked);
+        // A response obtained before the cache was cleared is not cached.
+        cache.insert(chain_id, b"query".to_vec(), generation, BlockHeight(1), ());
+        assert_eq!(cache.get(chain_id, b"query"), Lookup::Untracked);
+    }
+
+    #[test]
+    fn test_chain_tip_cache_max_age() {
+        let cache = ChainTipCache::new(NonZeroUsize::new(10).unwrap(), Duration::ZERO);
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        cache.handle_notification(&new_block(chain_id, 0));
+        let generation = miss(cache.get(chain_id, b"query"));
+        cache.insert(chain_id, b"query".to_vec(), generation, BlockHeight(1), ());
+        std::thread::sleep(Duration::from_millis(1));
+        miss(cache.get(chain_id, b"query"));
+    }
+}
@@ -3,7 +3,7 @@
 
 use async_trait::async_trait;
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{CryptoHash, ValidatorPublicKey},
     data_types::{BlobContent, Timestamp},
     identifiers::{AccountOwner, BlobId, ChainId},
 };
@@ -23,12 +23,12 @@ use linera_core::{
     client::ChainClient,
     data_types::{ChainInfoQuery, ChainInfoResponse},
     node::{
-        CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
+        CrossChainMessageDelivery, NodeError, NodeFeatures, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
+    notifier::NotificationFilter,
 };
 use linera_execution::committee::Committee;
-use linera_sdk::linera_base_types::ValidatorPublicKey;
 use linera_service::node_service::NodeService;
 use linera_storage::{DbStorage, NetworkDescription, Storage};
 use linera_version::VersionInfo;
@@ -100,6 +100,14 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn subscribe_with_filter(
+        &self,
+        _: Vec<ChainId>,
+        _: NotificationFilter,
+    ) -> Result<NotificationStream, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
@@ -108,6 +116,10 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn get_node_features(&self) -> Result<NodeFeatures, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn upload_blob(&self, _: BlobContent) -> Result<BlobId, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
@@ -116,6 +128,10 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn download_blobs(&self, _: Vec<BlobId>) -> Result<Vec<BlobContent>, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn download_certificate(
         &self,
         _: CryptoHash,
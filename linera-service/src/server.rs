@@ -6,44 +6,81 @@
 
 use std::{
     borrow::Cow,
-    num::{NonZeroU16, NonZeroUsize},
+    num::{NonZeroU16, NonZeroU32, NonZeroUsize},
     path::{Path, PathBuf},
-    time::Duration,
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
 use linera_base::{
-    crypto::{CryptoRng, Ed25519SecretKey},
+    crypto::{
+        AccountSecretKey, BlsSecretKey, CryptoRng, Ed25519SecretKey, ValidatorKeypair,
+        ValidatorSecretKey,
+    },
+    data_types::BlockHeight,
+    identifiers::ChainId,
     listen_for_shutdown_signals,
+    tracing::LogFormat,
 };
+use linera_chain::data_types::ValidatorSigner;
 use linera_client::{
-    config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
+    client_options::HttpRequestPolicyConfig,
+    config::{
+        BlockExporterConfig, CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig,
+    },
+    external_signer::ExternalValidatorSigner,
     persistent::{self, Persist},
 };
-use linera_core::{worker::WorkerState, JoinSetExt as _};
-use linera_execution::{WasmRuntime, WithWasmDefault};
+use linera_core::{
+    blob_gossip::BlobGossip,
+    rate_limiter::RateLimit,
+    replay::{replay_chain, ReplayReport},
+    worker::WorkerState,
+    JoinSetExt as _,
+};
+#[cfg(feature = "query-sandbox")]
+use linera_execution::sandbox::{QuerySandboxConfig, QuerySandboxPool};
+use linera_execution::{committee::CertificateSignatureScheme, WasmRuntime, WithWasmDefault};
 use linera_rpc::{
     config::{
-        CrossChainConfig, ExporterServiceConfig, NetworkProtocol, NotificationConfig, ShardConfig,
-        ShardId, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        CrossChainConfig, ExporterServiceConfig, NetworkProtocol, NotificationConfig,
+        ShardAssignment, ShardConfig, ShardId, TlsConfig, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig,
+    },
+    grpc::{
+        self,
+        internal_tls::{self, InternalTlsConfig},
     },
-    grpc, simple,
+    shard_map::ShardMap,
+    simple, NodeOptions, NodeProvider,
 };
-use linera_sdk::linera_base_types::{AccountSecretKey, ValidatorKeypair};
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
 use linera_service::{
+    admin::{
+        self, AdminBackend, AdminConfig, AdminError, CacheFlush, ChainStatus, ShardStatus,
+        StorageHealth,
+    },
+    config_check::{Component, ConfigReport},
+    exporter::ExporterContext,
+    proxy::{load_shards, HandoffOutcome, ProxyContext},
     storage::{Runnable, StorageConfigNamespace},
     util,
+    worker_benchmark::{WorkerBenchmark, WorkerBenchmarkOptions},
+};
+use linera_storage::{ArchiveStoreConfig, DbStorage, Storage};
+use linera_views::{
+    lru_caching::StorageCacheConfig,
+    memory::{MemoryStore, MemoryStoreConfig},
+    store::CommonStoreConfig,
 };
-use linera_storage::Storage;
-use linera_views::{lru_caching::StorageCacheConfig, store::CommonStoreConfig};
 use serde::Deserialize;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 struct ServerContext {
     server_config: ValidatorServerConfig,
@@ -52,6 +89,114 @@ struct ServerContext {
     shard: Option<usize>,
     grace_period: Duration,
     max_loaded_chains: NonZeroUsize,
+    chain_worker_memory_budget: Option<u64>,
+    block_body_retention: Option<u64>,
+    honor_retention_policies: bool,
+    proposal_rate_limit: Option<RateLimit>,
+    chain_proposal_rate_limit: Option<RateLimit>,
+    max_pending_proposals_per_chain: Option<NonZeroUsize>,
+    max_write_pressure: Option<f64>,
+    /// The signer of the validator's votes, if not the secret key in the server configuration.
+    external_signer: Option<Arc<dyn ValidatorSigner>>,
+    /// The options to connect to the other validators to download blobs, if enabled.
+    blob_gossip_options: Option<NodeOptions>,
+    /// Whether to run the proxy in this process too.
+    all_in_one: bool,
+    /// The block exporters to run in this process, with `all_in_one`.
+    exporters: Vec<BlockExporterConfig>,
+    /// The configuration of the admin API.
+    admin_config: AdminConfig,
+    /// The certificates for TLS between the proxy and the shards.
+    internal_tls: InternalTlsConfig,
+}
+
+/// The admin API of a server, with the shards that run in this process.
+struct ServerAdmin<S>
+where
+    S: Storage,
+{
+    shard_map: Arc<ShardMap>,
+    states: Vec<(ShardConfig, WorkerState<S>)>,
+    storage: S,
+}
+
+impl<S> ServerAdmin<S>
+where
+    S: Storage,
+{
+    fn state(&self, shard: &ShardConfig) -> Option<&WorkerState<S>> {
+        self.states
+            .iter()
+            .find(|(config, _)| config.address() == shard.address())
+            .map(|(_, state)| state)
+    }
+
+    /// Returns the current shard of `chain_id`, and its index.
+    async fn shard_for(&self, chain_id: ChainId) -> (ShardId, ShardConfig) {
+        let assignment = self.shard_map.read().await;
+        let shard_id = assignment.shard_id(chain_id);
+        (shard_id, assignment.shards()[shard_id].clone())
+    }
+}
+
+#[async_trait]
+impl<S> AdminBackend for ServerAdmin<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    async fn shards(&self) -> Vec<ShardStatus> {
+        let mut shards = Vec::new();
+        let configs = self.shard_map.read().await.shards().to_vec();
+        for (shard_id, shard) in configs.iter().enumerate() {
+            let worker = self.state(shard).map(WorkerState::stats);
+            let reachable = worker.is_some()
+                || admin::is_reachable(&shard.host, shard.port, Duration::from_secs(1)).await;
+            shards.push(ShardStatus {
+                shard_id,
+                host: shard.host.clone(),
+                port: shard.port,
+                reachable,
+                worker,
+            });
+        }
+        shards
+    }
+
+    async fn chain(&self, chain_id: ChainId) -> Result<ChainStatus, AdminError> {
+        let (shard_id, shard) = self.shard_for(chain_id).await;
+        let loaded = self
+            .state(&shard)
+            .map(|state| state.is_chain_loaded(chain_id));
+        admin::chain_status(&self.storage, chain_id, shard_id, loaded).await
+    }
+
+    async fn archive_chain(&self, chain_id: ChainId) -> Result<(), AdminError> {
+        let (_, shard) = self.shard_for(chain_id).await;
+        let state = self.state(&shard).ok_or(AdminError::Unsupported(
+            "the chain is archived by the server running its shard",
+        ))?;
+        Ok(state.archive_chain(chain_id).await?)
+    }
+
+    async fn storage_health(&self) -> StorageHealth {
+        admin::check_storage(&self.storage).await
+    }
+
+    async fn flush_caches(&self) -> CacheFlush {
+        let mut flush = CacheFlush::default();
+        for (_, state) in &self.states {
+            let stats = state.stats();
+            flush.cleared_entries += stats.cached_blocks + stats.cached_execution_states;
+            flush.unloaded_chains += state.flush_caches();
+        }
+        flush
+    }
+
+    async fn hand_off(&self, _shards: Vec<ShardConfig>) -> Result<HandoffOutcome, AdminError> {
+        Err(AdminError::Unsupported(
+            "the chains are handed off to new shards by the proxy's admin API",
+        ))
+    }
 }
 
 impl ServerContext {
@@ -66,26 +211,45 @@ impl ServerContext {
     {
         let shard = self.server_config.internal_network.shard(shard_id);
         info!("Shard booted on {}", shard.host);
-        info!(
-            "Public key: {}",
-            self.server_config.validator_secret.public()
-        );
-        let state = WorkerState::new(
+        info!("Public key: {}", self.server_config.validator.public_key);
+        let mut state = WorkerState::new(
             format!("Shard {} @ {}:{}", shard_id, local_ip_addr, shard.port),
-            Some(self.server_config.validator_secret.copy()),
+            self.server_config
+                .validator_secret
+                .as_ref()
+                .map(ValidatorSecretKey::copy),
             storage,
             self.max_loaded_chains,
         )
         .with_allow_inactive_chains(false)
         .with_allow_messages_from_deprecated_epochs(false)
-        .with_grace_period(self.grace_period);
+        .with_grace_period(self.grace_period)
+        .with_chain_worker_memory_budget(self.chain_worker_memory_budget)
+        .with_block_body_retention(self.block_body_retention)
+        .with_honor_retention_policies(self.honor_retention_policies)
+        .with_proposal_rate_limit(self.proposal_rate_limit)
+        .with_chain_proposal_rate_limit(self.chain_proposal_rate_limit)
+        .with_max_pending_proposals_per_chain(self.max_pending_proposals_per_chain)
+        .with_max_write_pressure(self.max_write_pressure);
+        if let Some(signer) = &self.external_signer {
+            state = state.with_external_validator_signer(signer.clone());
+        }
         (state, shard_id, shard.clone())
     }
 
+    fn make_blob_gossip(&self) -> Option<BlobGossip<NodeProvider>> {
+        let options = self.blob_gossip_options?;
+        Some(BlobGossip::new(
+            NodeProvider::new(options),
+            self.server_config.validator.public_key,
+        ))
+    }
+
     fn spawn_simple<S>(
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        shard_map: Arc<ShardMap>,
         protocol: simple::TransportProtocol,
         shutdown_signal: CancellationToken,
     ) -> JoinSet<()>
@@ -116,8 +280,10 @@ impl ServerContext {
                 shard.port,
                 state,
                 shard_id,
+                shard_map.clone(),
                 cross_chain_config,
             )
+            .with_blob_gossip(self.make_blob_gossip())
             .spawn(shutdown_signal.clone(), &mut join_set);
 
             handles.push(
@@ -139,8 +305,10 @@ impl ServerContext {
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        shard_map: Arc<ShardMap>,
+        tls_config: TlsConfig,
         shutdown_signal: CancellationToken,
-    ) -> JoinSet<()>
+    ) -> anyhow::Result<JoinSet<()>>
     where
         S: Storage + Clone + Send + Sync + 'static,
     {
@@ -153,14 +321,24 @@ impl ServerContext {
                 Self::start_metrics(listen_address, port, shutdown_signal.clone());
             }
 
+            let internal_tls = match tls_config {
+                TlsConfig::ClearText => None,
+                TlsConfig::Tls => Some(self.internal_tls.load(
+                    &internal_tls::shard_certificate_name(shard_id),
+                    SystemTime::now(),
+                )?),
+            };
             let server_handle = grpc::GrpcServer::spawn(
                 listen_address.to_string(),
                 shard.port,
                 state,
                 shard_id,
                 self.server_config.internal_network.clone(),
+                shard_map.clone(),
+                internal_tls,
                 self.cross_chain_config.clone(),
                 self.notification_config.clone(),
+                self.make_blob_gossip(),
                 shutdown_signal.clone(),
                 &mut join_set,
             );
@@ -177,7 +355,58 @@ impl ServerContext {
 
         join_set.spawn_task(handles.collect::<()>());
 
-        join_set
+        Ok(join_set)
+    }
+
+    /// Runs the proxy and the block exporters in this process, sharing the shards' storage.
+    /// If one of them stops, the whole validator is shut down.
+    fn spawn_bundled_services<S>(
+        &self,
+        storage: S,
+        shutdown_signal: CancellationToken,
+        join_set: &mut JoinSet<()>,
+    ) where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let config = ValidatorServerConfig {
+            validator: self.server_config.validator.clone(),
+            validator_secret: self
+                .server_config
+                .validator_secret
+                .as_ref()
+                .map(ValidatorSecretKey::copy),
+            internal_network: self.server_config.internal_network.clone(),
+        };
+        let proxy = ProxyContext {
+            internal_tls: self.internal_tls.clone(),
+            ..ProxyContext::new(config)
+        }
+        .run_until(storage.clone(), shutdown_signal.clone());
+        let signal = shutdown_signal.clone();
+        join_set.spawn_task(async move {
+            if let Err(error) = proxy.boxed().await {
+                error!("Error running the proxy: {error:?}");
+            }
+            signal.cancel();
+        });
+        for config in &self.exporters {
+            let id = config.id;
+            let exporter = ExporterContext::new(
+                id,
+                config.service_config.clone(),
+                config.destination_config.clone(),
+            )
+            .run_until(storage.clone(), shutdown_signal.clone());
+            let signal = shutdown_signal.clone();
+            join_set.spawn_task(async move {
+                if let Err(error) = exporter.boxed().await {
+                    if !signal.is_cancelled() {
+                        error!("Error running block exporter {id}: {error:?}");
+                    }
+                }
+                signal.cancel();
+            });
+        }
     }
 
     #[cfg(with_metrics)]
@@ -203,6 +432,21 @@ impl Runnable for ServerContext {
         let listen_address = self.get_listen_address();
 
         tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
+        let bundled_storage = self.all_in_one.then(|| storage.clone());
+        let admin_storage = storage.clone();
+
+        // After a handoff, the chains are assigned to the recorded shards.
+        let internal_network = &self.server_config.internal_network;
+        let shards = load_shards(&storage, internal_network).await?;
+        if shards != internal_network.shards {
+            warn!(
+                "The chains were handed off to other shards than the configured ones: {:?}",
+                shards.iter().map(ShardConfig::address).collect::<Vec<_>>()
+            );
+        }
+        let mut assignment = ShardAssignment::new(internal_network);
+        assignment.complete_handoff(shards);
+        let shard_map = Arc::new(ShardMap::new(assignment));
 
         // Run the server
         let states = match self.shard {
@@ -219,22 +463,71 @@ impl Runnable for ServerContext {
             }
         };
 
+        let server_admin = ServerAdmin {
+            shard_map: shard_map.clone(),
+            states: states
+                .iter()
+                .map(|(state, _, shard)| (shard.clone(), state.clone()))
+                .collect(),
+            storage: admin_storage,
+        };
+        admin::start_admin_api(&self.admin_config, server_admin, shutdown_notifier.clone())?;
+
         let mut join_set = match self.server_config.internal_network.protocol {
-            NetworkProtocol::Simple(protocol) => {
-                self.spawn_simple(&listen_address, states, protocol, shutdown_notifier)
-            }
-            NetworkProtocol::Grpc(tls_config) => match tls_config {
-                TlsConfig::ClearText => self.spawn_grpc(&listen_address, states, shutdown_notifier),
-                TlsConfig::Tls => bail!("TLS not supported between proxy and shards."),
-            },
+            NetworkProtocol::Simple(protocol) => self.spawn_simple(
+                &listen_address,
+                states,
+                shard_map,
+                protocol,
+                shutdown_notifier.clone(),
+            ),
+            NetworkProtocol::Grpc(tls_config) => self.spawn_grpc(
+                &listen_address,
+                states,
+                shard_map,
+                tls_config,
+                shutdown_notifier.clone(),
+            )?,
         };
 
+        if let Some(storage) = bundled_storage {
+            info!(
+                "Running the proxy and {} block exporters",
+                self.exporters.len()
+            );
+            self.spawn_bundled_services(storage, shutdown_notifier, &mut join_set);
+        }
+
         join_set.await_all_tasks().await;
 
         Ok(())
     }
 }
 
+/// Replays the blocks of a chain in a temporary in-memory storage.
+struct ReplayContext {
+    chain_id: ChainId,
+    from: BlockHeight,
+}
+
+#[async_trait]
+impl Runnable for ReplayContext {
+    type Output = anyhow::Result<ReplayReport>;
+
+    async fn run<S>(self, storage: S) -> anyhow::Result<ReplayReport>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let scratch = DbStorage::<MemoryStore, _>::maybe_create_and_connect(
+            &MemoryStoreConfig::new(10),
+            "replay",
+            storage.wasm_runtime(),
+        )
+        .await?;
+        Ok(replay_chain(&storage, &scratch, self.chain_id, self.from).await?)
+    }
+}
+
 #[derive(clap::Parser)]
 #[command(
     name = "linera-server",
@@ -250,6 +543,10 @@ struct ServerOptions {
     #[arg(long, env = "LINERA_SERVER_TOKIO_THREADS")]
     tokio_threads: Option<usize>,
 
+    /// The format of the log output: `plain`, `pretty`, or `json` for structured logs.
+    #[arg(long, env = "RUST_LOG_FORMAT", default_value = "plain")]
+    log_format: LogFormat,
+
     /// The number of Tokio blocking threads to use.
     #[arg(long, env = "LINERA_SERVER_TOKIO_BLOCKING_THREADS")]
     tokio_blocking_threads: Option<usize>,
@@ -273,6 +570,15 @@ struct ValidatorOptions {
     /// The port for the metrics endpoint
     metrics_port: u16,
 
+    /// Whether the validator is an archive node, which keeps the full history of every chain.
+    #[serde(default)]
+    archive: bool,
+
+    /// If set, the shards only keep the bodies of this many latest blocks of each chain.
+    /// This is the default for `--block-body-retention`, and is advertised to clients.
+    #[serde(default)]
+    block_body_retention: Option<u64>,
+
     /// The host of the proxy in the internal network.
     internal_host: String,
 
@@ -310,17 +616,22 @@ fn make_server_config<R: CryptoRng>(
         port: options.internal_port,
         block_exporters: options.block_exporters,
         metrics_port: options.metrics_port,
+        archive: options.archive,
+        block_body_retention: options.block_body_retention,
     };
+    let bls_key =
+        BlsSecretKey::from_validator_secret(&validator_keypair.secret_key).validator_key();
     let validator = ValidatorConfig {
         network,
         public_key,
         account_key: account_secret.public(),
+        bls_key: Some(bls_key),
     };
     Ok(persistent::File::new(
         path,
         ValidatorServerConfig {
             validator,
-            validator_secret: validator_keypair.secret_key,
+            validator_secret: Some(validator_keypair.secret_key),
             internal_network,
         },
     )?)
@@ -330,64 +641,7 @@ fn make_server_config<R: CryptoRng>(
 enum ServerCommand {
     /// Runs a service for each shard of the Linera validator")
     #[command(name = "run")]
-    Run {
-        /// Path to the file containing the server configuration of this Linera validator (including its secret key)
-        #[arg(long = "server")]
-        server_config_path: PathBuf,
-
-        /// Storage configuration for the blockchain history, chain states and binary blobs.
-        #[arg(long = "storage")]
-        storage_config: StorageConfigNamespace,
-
-        /// Configuration for cross-chain requests
-        #[command(flatten)]
-        cross_chain_config: CrossChainConfig,
-
-        /// Configuration for notifications
-        #[command(flatten)]
-        notification_config: NotificationConfig,
-
-        /// Path to the file describing the initial user chains (aka genesis state)
-        #[arg(long = "genesis")]
-        genesis_config_path: PathBuf,
-
-        /// Runs a specific shard (from 0 to shards-1)
-        #[arg(long)]
-        shard: Option<usize>,
-
-        /// Blocks with a timestamp this far in the future will still be accepted, but the validator
-        /// will wait until that timestamp before voting.
-        #[arg(long = "grace-period-ms", default_value = "500", value_parser = util::parse_millis)]
-        grace_period: Duration,
-
-        /// The WebAssembly runtime to use.
-        #[arg(long)]
-        wasm_runtime: Option<WasmRuntime>,
-
-        /// The maximal number of chains loaded in memory at a given time.
-        #[arg(long, default_value = "400")]
-        max_loaded_chains: NonZeroUsize,
-
-        /// The maximal number of simultaneous queries to the database
-        #[arg(long)]
-        max_concurrent_queries: Option<usize>,
-
-        /// The maximal number of stream queries to the database
-        #[arg(long, default_value = "10")]
-        max_stream_queries: usize,
-
-        /// The maximal memory used in the storage cache.
-        #[arg(long, default_value = "10000000")]
-        max_cache_size: usize,
-
-        /// The maximal size of an entry in the storage cache.
-        #[arg(long, default_value = "1000000")]
-        max_entry_size: usize,
-
-        /// The maximal number of entries in the storage cache.
-        #[arg(long, default_value = "1000")]
-        max_cache_entries: usize,
-    },
+    Run(Box<RunOptions>),
 
     /// Act as a trusted third-party and generate all server configurations
     #[command(name = "generate")]
@@ -400,6 +654,10 @@ enum ServerCommand {
         #[arg(long)]
         committee: Option<PathBuf>,
 
+        /// How the validators of the committee sign certificates
+        #[arg(long, value_enum, default_value_t)]
+        certificate_signature_scheme: CertificateSignatureScheme,
+
         /// Force this command to generate keys using a PRNG and a given seed. USE FOR
         /// TESTING ONLY.
         #[arg(long)]
@@ -436,6 +694,89 @@ enum ServerCommand {
         /// The maximal number of entries in the storage cache.
         #[arg(long, default_value = "1000")]
         max_cache_entries: usize,
+
+        /// For how long, in milliseconds, the storage cache may remember that a key shared with
+        /// other processes is missing. Zero disables this.
+        #[arg(long, default_value = "0")]
+        negative_cache_ttl_ms: u64,
+    },
+
+    /// Checks the server configuration, the genesis configuration and the storage of the
+    /// shards, without running them: that their ports are free, that the hosts of the
+    /// validator are resolvable and that the storage namespace was initialized with this
+    /// genesis configuration. Exits with an error if any check fails.
+    #[command(name = "check-config")]
+    CheckConfig {
+        /// Path to the file containing the server configuration of this Linera validator
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+
+        /// Storage configuration for the blockchain history, chain states and binary blobs.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// Only checks a specific shard (from 0 to shards-1)
+        #[arg(long)]
+        shard: Option<usize>,
+
+        /// Prints the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generates a synthetic multi-chain workload directly against a validator's internal
+    /// handlers, and reports the throughput and latencies, for capacity planning.
+    ///
+    /// The benchmark creates its own chains and committee in the given storage, which should
+    /// use a new namespace.
+    #[command(name = "benchmark")]
+    Benchmark {
+        /// Storage configuration for the blockchain history, chain states and binary blobs.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        #[command(flatten)]
+        options: WorkerBenchmarkOptions,
+
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Executes the confirmed blocks of a chain again in a temporary in-memory storage, and
+    /// compares their outcomes with the ones the validators signed, e.g. to check that a new
+    /// version executes past blocks the same way. Stops at the first block that differs, and
+    /// exits with an error if there is one.
+    #[command(name = "replay")]
+    Replay {
+        /// Storage configuration for the blockchain history, chain states and binary blobs.
+        #[arg(long = "storage")]
+        storage_config: StorageConfigNamespace,
+
+        /// Path to the file describing the initial user chains (aka genesis state)
+        #[arg(long = "genesis")]
+        genesis_config_path: PathBuf,
+
+        /// The chain to replay.
+        #[arg(long = "chain")]
+        chain_id: ChainId,
+
+        /// The height of the first block to compare. The earlier blocks are executed too, to
+        /// recreate the chain's state.
+        #[arg(long, default_value = "0")]
+        from: BlockHeight,
+
+        /// The WebAssembly runtime to use.
+        #[arg(long)]
+        wasm_runtime: Option<WasmRuntime>,
+
+        /// Prints the report as JSON.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Replaces the configurations of the shards by following the given template.
@@ -466,12 +807,218 @@ enum ServerCommand {
         #[arg(long)]
         metrics_port: Option<String>,
     },
+
+    /// Issues the certificates for TLS between the proxy and the shards, and renews the
+    /// ones that are about to expire. The processes must be restarted to use the new
+    /// certificates, within the overlap.
+    #[command(name = "rotate-internal-tls")]
+    RotateInternalTls {
+        /// Path to the file containing the server configuration of this Linera validator.
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+
+        #[command(flatten)]
+        internal_tls: InternalTlsConfig,
+    },
+
+    /// Serves queries to user applications over the standard input and output. This is
+    /// started by validators run with `--query-sandbox-processes`.
+    #[cfg(feature = "query-sandbox")]
+    #[command(name = "query-sandbox", hide = true)]
+    QuerySandbox {
+        /// The WebAssembly runtime to use.
+        #[arg(long)]
+        wasm_runtime: Option<WasmRuntime>,
+    },
 }
 
-fn main() {
+/// The options of the `run` command.
+#[derive(clap::Args)]
+struct RunOptions {
+    /// Path to the file containing the server configuration of this Linera validator (including its secret key)
+    #[arg(long = "server")]
+    server_config_path: PathBuf,
+
+    /// Storage configuration for the blockchain history, chain states and binary blobs.
+    #[arg(long = "storage")]
+    storage_config: StorageConfigNamespace,
+
+    /// Configuration for cross-chain requests
+    #[command(flatten)]
+    cross_chain_config: CrossChainConfig,
+
+    /// Configuration for notifications
+    #[command(flatten)]
+    notification_config: NotificationConfig,
+
+    /// The certificates for TLS between the proxy and the shards
+    #[command(flatten)]
+    internal_tls: InternalTlsConfig,
+
+    /// Path to the file describing the initial user chains (aka genesis state)
+    #[arg(long = "genesis")]
+    genesis_config_path: PathBuf,
+
+    /// Runs a specific shard (from 0 to shards-1)
+    #[arg(long)]
+    shard: Option<usize>,
+
+    /// Blocks with a timestamp this far in the future will still be accepted, but the validator
+    /// will wait until that timestamp before voting.
+    #[arg(long = "grace-period-ms", default_value = "500", value_parser = util::parse_millis)]
+    grace_period: Duration,
+
+    /// The WebAssembly runtime to use.
+    #[arg(long)]
+    wasm_runtime: Option<WasmRuntime>,
+
+    /// Restrictions on the HTTP requests performed by applications.
+    #[command(flatten)]
+    http_request_policy_config: HttpRequestPolicyConfig,
+
+    /// If set, run the queries to user applications in up to this many separate
+    /// processes, which cannot open network connections or start programs, instead of
+    /// in the validator process.
+    #[cfg(feature = "query-sandbox")]
+    #[arg(long)]
+    query_sandbox_processes: Option<NonZeroUsize>,
+
+    /// The user ID to run the query sandboxes as. It should not own any of the
+    /// validator's files. This requires the server to run with the privileges to
+    /// change users.
+    #[cfg(feature = "query-sandbox")]
+    #[arg(long, requires = "query_sandbox_processes")]
+    query_sandbox_uid: Option<u32>,
+
+    /// The group ID to run the query sandboxes as.
+    #[cfg(feature = "query-sandbox")]
+    #[arg(long, requires = "query_sandbox_processes")]
+    query_sandbox_gid: Option<u32>,
+
+    /// The maximal number of chains loaded in memory at a given time.
+    #[arg(long, default_value = "400")]
+    max_loaded_chains: NonZeroUsize,
+
+    /// If set, the maximal estimated memory in bytes held by the requests being handled by
+    /// the loaded chains. Further requests wait until enough memory is released.
+    #[arg(long)]
+    chain_worker_memory_budget: Option<u64>,
+
+    /// If set, only keep the bodies of this many latest blocks of each chain in storage.
+    /// Older blocks keep their header and certificate, unless their outgoing messages are
+    /// still in flight. Defaults to the `block_body_retention` of the server
+    /// configuration, which is what clients are told.
+    #[arg(long)]
+    block_body_retention: Option<u64>,
+
+    /// Whether to also prune the block bodies of the chains whose owners ask for a shorter
+    /// history with `linera change-retention-policy`. Archive nodes ignore this.
+    #[arg(long)]
+    honor_retention_policies: bool,
+
+    /// The archive store where chains archived with the admin API or with
+    /// `linera storage archive-chain` are kept: the path of a directory, or
+    /// `s3://BUCKET/PREFIX` for a bucket of an S3-compatible service configured by the
+    /// `AWS_*` environment variables. Chains are restored from there when they, or their
+    /// certificates or blobs, are used again.
+    #[arg(long)]
+    chain_archive: Option<ArchiveStoreConfig>,
+
+    /// If set, the maximal number of block proposals per second that a single owner can
+    /// submit for a chain, on average.
+    #[arg(long)]
+    proposals_per_owner_per_second: Option<NonZeroU32>,
+
+    /// The number of block proposals that a single owner can submit for a chain at once,
+    /// if `--proposals-per-owner-per-second` is set.
+    #[arg(long, default_value = "10")]
+    proposal_burst_per_owner: NonZeroU32,
+
+    /// If set, the maximal number of block proposals per second that are accepted for a
+    /// chain, on average, regardless of their owner.
+    #[arg(long)]
+    proposals_per_chain_per_second: Option<NonZeroU32>,
+
+    /// The number of block proposals that are accepted for a chain at once, if
+    /// `--proposals-per-chain-per-second` is set.
+    #[arg(long, default_value = "20")]
+    proposal_burst_per_chain: NonZeroU32,
+
+    /// If set, the maximal number of block proposals for a chain that are validated at the
+    /// same time. Further proposals are rejected until one of them is handled.
+    #[arg(long)]
+    max_pending_proposals_per_chain: Option<NonZeroUsize>,
+
+    /// If set, reject block proposals while the storage's write pressure, between 0
+    /// (idle) and 1 (saturated), is above this value. Certificates are still handled.
+    #[arg(long, value_parser = util::parse_write_pressure)]
+    max_write_pressure: Option<f64>,
+
+    /// A program to request the signatures of the validator's votes from, instead of
+    /// using the secret key in the server configuration, which can then be left out.
+    #[arg(long)]
+    external_signer: Option<PathBuf>,
+
+    /// An argument to pass to the `--external-signer` program. Can be repeated.
+    #[arg(long = "external-signer-arg", requires = "external_signer")]
+    external_signer_args: Vec<String>,
+
+    /// Download the missing blobs of block proposals from the other validators, so that
+    /// clients only need to upload them to one validator.
+    #[arg(long)]
+    fetch_blobs_from_peers: bool,
+
+    /// The timeout for requests to the other validators, e.g. to download blobs.
+    #[arg(long = "peer-timeout-ms", default_value = "4000", value_parser = util::parse_millis)]
+    peer_timeout: Duration,
+
+    /// Also run the proxy in this process, sharing the storage with the shards, so that a
+    /// small validator only needs a single process. All shards are run.
+    #[arg(long, conflicts_with = "shard")]
+    all_in_one: bool,
+
+    /// With `--all-in-one`, the configuration file of a block exporter to run in this
+    /// process. The shards only notify the exporters listed in the server configuration.
+    #[arg(long = "exporter-config", requires = "all_in_one")]
+    exporter_config_paths: Vec<PathBuf>,
+
+    /// Configuration for the admin API
+    #[command(flatten)]
+    admin_config: AdminConfig,
+
+    /// The maximal number of simultaneous queries to the database
+    #[arg(long)]
+    max_concurrent_queries: Option<usize>,
+
+    /// The maximal number of stream queries to the database
+    #[arg(long, default_value = "10")]
+    max_stream_queries: usize,
+
+    /// The maximal memory used in the storage cache.
+    #[arg(long, default_value = "10000000")]
+    max_cache_size: usize,
+
+    /// The maximal size of an entry in the storage cache.
+    #[arg(long, default_value = "1000000")]
+    max_entry_size: usize,
+
+    /// The maximal number of entries in the storage cache.
+    #[arg(long, default_value = "1000")]
+    max_cache_entries: usize,
+
+    /// For how long, in milliseconds, the storage cache may remember that a key shared with
+    /// other processes is missing. Zero disables this.
+    #[arg(long, default_value = "0")]
+    negative_cache_ttl_ms: u64,
+}
+
+fn main() -> anyhow::Result<()> {
     let options = <ServerOptions as clap::Parser>::parse();
 
-    linera_base::tracing::init(&log_file_name_for(&options.command));
+    linera_base::tracing::init_with_format(
+        &log_file_name_for(&options.command),
+        options.log_format,
+    );
 
     let mut runtime = if options.tokio_threads == Some(1) {
         tokio::runtime::Builder::new_current_thread()
@@ -499,16 +1046,12 @@ fn main() {
 /// Returns the log file name to use based on the [`ServerCommand`] that will run.
 fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
     match command {
-        ServerCommand::Run {
-            shard,
-            server_config_path,
-            ..
-        } => {
+        ServerCommand::Run(options) => {
             let server_config: ValidatorServerConfig =
-                util::read_json(server_config_path).expect("Failed to read server config");
+                util::read_json(&options.server_config_path).expect("Failed to read server config");
             let public_key = &server_config.validator.public_key;
 
-            if let Some(shard) = shard {
+            if let Some(shard) = options.shard {
                 format!("validator-{public_key}-shard-{shard}")
             } else {
                 format!("validator-{public_key}")
@@ -517,34 +1060,104 @@ fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
         }
         ServerCommand::Generate { .. }
         | ServerCommand::Initialize { .. }
-        | ServerCommand::EditShards { .. } => "server".into(),
+        | ServerCommand::CheckConfig { .. }
+        | ServerCommand::Benchmark { .. }
+        | ServerCommand::Replay { .. }
+        | ServerCommand::EditShards { .. }
+        | ServerCommand::RotateInternalTls { .. } => "server".into(),
+        #[cfg(feature = "query-sandbox")]
+        ServerCommand::QuerySandbox { .. } => "query-sandbox".into(),
     }
 }
 
-async fn run(options: ServerOptions) {
+async fn run(options: ServerOptions) -> anyhow::Result<()> {
     match options.command {
-        ServerCommand::Run {
-            server_config_path,
-            storage_config,
-            cross_chain_config,
-            notification_config,
-            genesis_config_path,
-            shard,
-            grace_period,
-            wasm_runtime,
-            max_loaded_chains,
-            max_concurrent_queries,
-            max_stream_queries,
-            max_cache_size,
-            max_entry_size,
-            max_cache_entries,
-        } => {
+        ServerCommand::Run(options) => {
+            let RunOptions {
+                server_config_path,
+                storage_config,
+                cross_chain_config,
+                notification_config,
+                internal_tls,
+                genesis_config_path,
+                shard,
+                grace_period,
+                wasm_runtime,
+                http_request_policy_config,
+                #[cfg(feature = "query-sandbox")]
+                query_sandbox_processes,
+                #[cfg(feature = "query-sandbox")]
+                query_sandbox_uid,
+                #[cfg(feature = "query-sandbox")]
+                query_sandbox_gid,
+                max_loaded_chains,
+                chain_worker_memory_budget,
+                block_body_retention,
+                honor_retention_policies,
+                chain_archive,
+                proposals_per_owner_per_second,
+                proposal_burst_per_owner,
+                proposals_per_chain_per_second,
+                proposal_burst_per_chain,
+                max_pending_proposals_per_chain,
+                max_write_pressure,
+                external_signer,
+                external_signer_args,
+                fetch_blobs_from_peers,
+                peer_timeout,
+                all_in_one,
+                exporter_config_paths,
+                admin_config,
+                max_concurrent_queries,
+                max_stream_queries,
+                max_cache_size,
+                max_entry_size,
+                max_cache_entries,
+                negative_cache_ttl_ms,
+            } = *options;
             linera_version::VERSION_INFO.log();
 
             let genesis_config: GenesisConfig =
                 util::read_json(&genesis_config_path).expect("Failed to read initial chain config");
             let server_config: ValidatorServerConfig =
                 util::read_json(&server_config_path).expect("Failed to read server config");
+            let block_body_retention =
+                block_body_retention.or(server_config.internal_network.block_body_retention);
+            assert!(
+                !server_config.internal_network.archive || block_body_retention.is_none(),
+                "Archive nodes keep all block bodies: `--block-body-retention` must not be set"
+            );
+            let external_signer = external_signer.map(|program| {
+                let signer = ExternalValidatorSigner::new(
+                    program,
+                    external_signer_args,
+                    server_config.validator.public_key,
+                );
+                Arc::new(signer) as Arc<dyn ValidatorSigner>
+            });
+            assert!(
+                server_config.validator_secret.is_some() || external_signer.is_some(),
+                "The server configuration has no validator secret: `--external-signer` must be set"
+            );
+            // External signers have no BLS12-381 key to sign the aggregated votes with.
+            assert!(
+                external_signer.is_none()
+                    || genesis_config.committee.certificate_signature_scheme
+                        != CertificateSignatureScheme::AggregatedBls12381,
+                "The genesis committee aggregates the votes with BLS12-381 keys, which \
+                 `--external-signer` doesn't support"
+            );
+            // Archive nodes keep the full history of every chain.
+            let honor_retention_policies =
+                honor_retention_policies && !server_config.internal_network.archive;
+            let exporters = exporter_config_paths
+                .iter()
+                .map(|path| {
+                    let config = fs_err::read_to_string(path)
+                        .expect("Unable to read the block exporter configuration file");
+                    toml::from_str(&config).expect("Invalid block exporter configuration file")
+                })
+                .collect();
 
             let job = ServerContext {
                 server_config,
@@ -553,24 +1166,87 @@ async fn run(options: ServerOptions) {
                 shard,
                 grace_period,
                 max_loaded_chains,
+                chain_worker_memory_budget,
+                block_body_retention,
+                honor_retention_policies,
+                proposal_rate_limit: proposals_per_owner_per_second.map(|events_per_second| {
+                    RateLimit {
+                        events_per_second,
+                        burst: proposal_burst_per_owner,
+                    }
+                }),
+                chain_proposal_rate_limit: proposals_per_chain_per_second.map(
+                    |events_per_second| RateLimit {
+                        events_per_second,
+                        burst: proposal_burst_per_chain,
+                    },
+                ),
+                max_pending_proposals_per_chain,
+                max_write_pressure,
+                external_signer,
+                blob_gossip_options: fetch_blobs_from_peers.then_some(NodeOptions {
+                    send_timeout: peer_timeout,
+                    recv_timeout: peer_timeout,
+                    retry_delay: Duration::from_millis(100),
+                    max_retries: 1,
+                }),
+                all_in_one,
+                exporters,
+                admin_config,
+                internal_tls,
             };
             let wasm_runtime = wasm_runtime.with_wasm_default();
             let storage_cache_config = StorageCacheConfig {
                 max_cache_size,
                 max_entry_size,
                 max_cache_entries,
+                negative_cache_ttl_ms,
             };
             let common_config = CommonStoreConfig {
                 max_concurrent_queries,
                 max_stream_queries,
                 storage_cache_config,
             };
+            #[cfg_attr(not(feature = "query-sandbox"), allow(unused_mut))]
+            let mut execution_runtime_config =
+                http_request_policy_config.into_execution_runtime_config();
+            #[cfg(feature = "query-sandbox")]
+            if let Some(query_sandbox_processes) = query_sandbox_processes {
+                let mut args = vec!["query-sandbox".to_string()];
+                if let Some(wasm_runtime) = wasm_runtime {
+                    args.extend(["--wasm-runtime".to_string(), wasm_runtime.to_string()]);
+                }
+                let config = QuerySandboxConfig {
+                    program: std::env::current_exe().expect("Failed to find the server binary"),
+                    args,
+                    max_processes: query_sandbox_processes.get(),
+                    uid: query_sandbox_uid,
+                    gid: query_sandbox_gid,
+                };
+                execution_runtime_config.query_sandbox =
+                    Some(Arc::new(QuerySandboxPool::new(config)));
+            }
             let store_config = storage_config
                 .add_common_config(common_config)
                 .await
                 .unwrap();
+            let archive_store = match chain_archive {
+                Some(archive) => Some(
+                    archive
+                        .connect()
+                        .await
+                        .expect("Failed to connect to the chain archive store"),
+                ),
+                None => None,
+            };
             store_config
-                .run_with_storage(&genesis_config, wasm_runtime, job)
+                .run_with_storage(
+                    &genesis_config,
+                    wasm_runtime,
+                    execution_runtime_config,
+                    archive_store,
+                    job,
+                )
                 .boxed()
                 .await
                 .unwrap()
@@ -580,6 +1256,7 @@ async fn run(options: ServerOptions) {
         ServerCommand::Generate {
             validators,
             committee,
+            certificate_signature_scheme,
             testing_prng_seed,
         } => {
             let mut config_validators = Vec::new();
@@ -608,6 +1285,7 @@ async fn run(options: ServerOptions) {
                     &committee,
                     CommitteeConfig {
                         validators: config_validators,
+                        certificate_signature_scheme,
                     },
                 )
                 .expect("Unable to open committee configuration");
@@ -626,6 +1304,7 @@ async fn run(options: ServerOptions) {
             max_cache_size,
             max_entry_size,
             max_cache_entries,
+            negative_cache_ttl_ms,
         } => {
             let genesis_config: GenesisConfig =
                 util::read_json(&genesis_config_path).expect("Failed to read initial chain config");
@@ -633,6 +1312,7 @@ async fn run(options: ServerOptions) {
                 max_cache_size,
                 max_entry_size,
                 max_cache_entries,
+                negative_cache_ttl_ms,
             };
             let common_config = CommonStoreConfig {
                 max_concurrent_queries,
@@ -650,6 +1330,92 @@ async fn run(options: ServerOptions) {
             store_config.initialize(&genesis_config).await.unwrap();
         }
 
+        ServerCommand::CheckConfig {
+            server_config_path,
+            storage_config,
+            genesis_config_path,
+            shard,
+            json,
+        } => {
+            let report = ConfigReport::check(
+                Component::Shards(shard),
+                &server_config_path,
+                &genesis_config_path,
+                &storage_config,
+            )
+            .await;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{report}");
+            }
+            if report.has_failures() {
+                std::process::exit(1);
+            }
+        }
+
+        ServerCommand::Benchmark {
+            storage_config,
+            options,
+            json,
+        } => {
+            options.check().expect("Invalid benchmark options");
+            let benchmark = WorkerBenchmark::new(options);
+            let genesis_config = benchmark.genesis_config().clone();
+            let store_config = storage_config
+                .add_common_config(CommonStoreConfig::default())
+                .await
+                .unwrap();
+            let report = store_config
+                .run_with_storage(&genesis_config, None, Default::default(), None, benchmark)
+                .boxed()
+                .await
+                .unwrap()
+                .expect("Benchmark failed");
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{report}");
+            }
+        }
+
+        ServerCommand::Replay {
+            storage_config,
+            genesis_config_path,
+            chain_id,
+            from,
+            wasm_runtime,
+            json,
+        } => {
+            let genesis_config: GenesisConfig =
+                util::read_json(&genesis_config_path).expect("Failed to read initial chain config");
+            let store_config = storage_config
+                .add_common_config(CommonStoreConfig::default())
+                .await
+                .unwrap();
+            let job = ReplayContext { chain_id, from };
+            let report = store_config
+                .run_with_storage(
+                    &genesis_config,
+                    wasm_runtime.with_wasm_default(),
+                    Default::default(),
+                    None,
+                    job,
+                )
+                .boxed()
+                .await
+                .unwrap()
+                .expect("Replay failed");
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{report}");
+            }
+            if report.first_difference().is_some() {
+                std::process::exit(1);
+            }
+        }
+
         ServerCommand::EditShards {
             server_config_path,
             num_shards,
@@ -667,7 +1433,43 @@ async fn run(options: ServerOptions) {
                 .await
                 .expect("Failed to write updated server config");
         }
+
+        ServerCommand::RotateInternalTls {
+            server_config_path,
+            internal_tls,
+        } => {
+            let server_config: ValidatorServerConfig =
+                util::read_json(&server_config_path).expect("Failed to read server config");
+            let report = internal_tls
+                .rotate(&server_config.internal_network, SystemTime::now())
+                .expect("Failed to rotate the internal TLS certificates");
+            if let Some(id) = report.created_authority {
+                info!("Created the certificate authority {id}");
+            }
+            for id in report.removed_authorities {
+                info!("Removed the expired certificate authority {id}");
+            }
+            for name in report.removed_certificates {
+                info!("Removed the certificate {name}");
+            }
+            if report.issued_certificates.is_empty() {
+                info!("No certificate needed to be renewed");
+            } else {
+                info!(
+                    "Issued the certificates {}; restart the proxy and the shards to use them",
+                    report.issued_certificates.join(", ")
+                );
+            }
+        }
+
+        #[cfg(feature = "query-sandbox")]
+        ServerCommand::QuerySandbox { wasm_runtime } => {
+            linera_execution::sandbox::run_query_sandbox(wasm_runtime.with_wasm_default())
+                .await
+                .expect("Failed to serve queries");
+        }
     }
+    Ok(())
 }
 
 fn generate_shard_configs(
@@ -756,6 +1558,8 @@ mod test {
                 internal_host: "internal_host".into(),
                 internal_port: 10000,
                 metrics_port: 5000,
+                archive: false,
+                block_body_retention: None,
                 shards: vec![
                     ShardConfig {
                         host: "host1".into(),
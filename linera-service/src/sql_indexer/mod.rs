@@ -0,0 +1,217 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An indexer writing the confirmed blocks in storage to a PostgreSQL or SQLite database.
+//!
+//! The indexer polls the local storage, e.g. that of a validator or of its block exporter,
+//! for new blocks of the chains it follows, and writes each block together with its
+//! operations, its incoming and outgoing messages and its events to the tables described
+//! in `schema.sql` (see [`SCHEMA`]). Explorers and analytics can then query the database
+//! directly.
+//!
+//! Besides the chains it is configured with, the indexer follows every chain that the
+//! indexed blocks sent messages to. Since the database records the last indexed height of
+//! each chain, an interrupted indexer resumes where it stopped.
+
+mod sink;
+
+use std::{collections::BTreeSet, num::TryFromIntError, time::Duration};
+
+use async_trait::async_trait;
+use futures::FutureExt as _;
+use linera_base::{
+    crypto::CryptoError, data_types::ArithmeticError, identifiers::ChainId,
+    listen_for_shutdown_signals,
+};
+use linera_storage::Storage;
+use linera_views::views::ViewError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+pub use self::sink::{SqlSink, SCHEMA};
+use crate::storage::Runnable;
+
+#[derive(thiserror::Error, Debug)]
+pub enum IndexerError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] ViewError),
+
+    #[error("failed to serialize a system operation or message: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid chain ID in the database: {0}")]
+    InvalidChainId(#[from] CryptoError),
+
+    #[error("value out of range for the database: {0}")]
+    OutOfRange(#[from] TryFromIntError),
+
+    #[error(transparent)]
+    Arithmetic(#[from] ArithmeticError),
+}
+
+/// The configuration of the SQL indexer.
+#[derive(Debug, Clone)]
+pub struct IndexerContext {
+    database_url: String,
+    chain_ids: Vec<ChainId>,
+    poll_interval: Duration,
+}
+
+#[async_trait]
+impl Runnable for IndexerContext {
+    type Output = Result<(), IndexerError>;
+
+    async fn run<S>(self, storage: S) -> Self::Output
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let shutdown_notifier = CancellationToken::new();
+        tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
+        self.run_until(storage, shutdown_notifier).await
+    }
+}
+
+impl IndexerContext {
+    pub fn new(database_url: String, chain_ids: Vec<ChainId>, poll_interval: Duration) -> Self {
+        Self {
+            database_url,
+            chain_ids,
+            poll_interval,
+        }
+    }
+
+    /// Indexes new blocks every poll interval, until `shutdown_signal` is cancelled.
+    pub async fn run_until<S>(
+        self,
+        storage: S,
+        shutdown_signal: CancellationToken,
+    ) -> Result<(), IndexerError>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let sink = SqlSink::connect(&self.database_url).await?;
+        info!("Linera SQL indexer is running.");
+        loop {
+            let count = self.index_all(&storage, &sink).await?;
+            if count > 0 {
+                info!("Indexed {count} new blocks");
+            }
+            futures::select! {
+                () = shutdown_signal.cancelled().fuse() => return Ok(()),
+                () = tokio::time::sleep(self.poll_interval).fuse() => {}
+            }
+        }
+    }
+
+    /// Indexes the new blocks of all followed chains, and returns how many there were.
+    pub async fn index_all<S: Storage>(
+        &self,
+        storage: &S,
+        sink: &SqlSink,
+    ) -> Result<usize, IndexerError> {
+        let mut count = 0;
+        let mut indexed = BTreeSet::new();
+        // Indexing a chain can reveal new destinations, so repeat until there are none.
+        loop {
+            let chain_ids = self
+                .chain_ids
+                .iter()
+                .copied()
+                .chain(sink.destinations().await?)
+                .filter(|chain_id| !indexed.contains(chain_id))
+                .collect::<BTreeSet<_>>();
+            if chain_ids.is_empty() {
+                return Ok(count);
+            }
+            for chain_id in chain_ids {
+                count += index_chain(storage, sink, chain_id).await?;
+                indexed.insert(chain_id);
+            }
+        }
+    }
+}
+
+/// Indexes the blocks of the chain that are in storage but not in the database yet, and
+/// returns how many there were.
+pub async fn index_chain<S: Storage>(
+    storage: &S,
+    sink: &SqlSink,
+    chain_id: ChainId,
+) -> Result<usize, IndexerError> {
+    let start = usize::try_from(sink.next_block_height(chain_id).await?)?;
+    let hashes = {
+        let chain = storage.load_chain(chain_id).await?;
+        let end = chain.confirmed_log.count();
+        if start >= end {
+            return Ok(0);
+        }
+        chain.confirmed_log.read(start..end).await?
+    };
+    for hash in &hashes {
+        let certificate = storage.read_confirmed_block(*hash).await?;
+        let block = certificate.block();
+        debug!(%chain_id, height = %block.header.height, "Indexing block {hash}");
+        sink.insert_block(*hash, block).await?;
+    }
+    Ok(hashes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use linera_base::{crypto::CryptoHash, data_types::Amount, identifiers::AccountOwner};
+    use linera_chain::{
+        data_types::{BlockExecutionOutcome, OperationResult},
+        test::{make_first_block, BlockTestExt},
+    };
+    use linera_execution::{OutgoingMessage, SystemMessage};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_insert_block() -> anyhow::Result<()> {
+        let sink = SqlSink::connect("sqlite::memory:").await?;
+        let chain_id = ChainId(CryptoHash::test_hash("root1"));
+        let recipient = ChainId(CryptoHash::test_hash("root2"));
+        assert_eq!(sink.next_block_height(chain_id).await?, 0.into());
+
+        let proposal = make_first_block(chain_id).with_simple_transfer(recipient, Amount::ONE);
+        let message = OutgoingMessage::new(
+            recipient,
+            SystemMessage::Credit {
+                target: AccountOwner::CHAIN,
+                amount: Amount::ONE,
+                source: AccountOwner::CHAIN,
+            },
+        );
+        let block = BlockExecutionOutcome {
+            messages: vec![vec![message]],
+            previous_message_blocks: BTreeMap::new(),
+            state_hash: CryptoHash::test_hash("state"),
+            oracle_responses: vec![Vec::new()],
+            events: vec![Vec::new()],
+            blobs: vec![Vec::new()],
+            operation_results: vec![OperationResult::default()],
+        }
+        .with(proposal);
+        sink.insert_block(CryptoHash::test_hash("block"), &block)
+            .await?;
+
+        assert_eq!(sink.next_block_height(chain_id).await?, 1.into());
+        assert_eq!(sink.destinations().await?, vec![recipient]);
+        assert_eq!(sink.count("blocks").await?, 1);
+        assert_eq!(sink.count("operations").await?, 1);
+        assert_eq!(sink.count("outgoing_messages").await?, 1);
+        assert_eq!(sink.count("incoming_messages").await?, 0);
+        // A block can't be indexed twice.
+        assert!(sink
+            .insert_block(CryptoHash::test_hash("block"), &block)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
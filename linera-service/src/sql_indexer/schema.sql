@@ -0,0 +1,84 @@
+-- The tables written by the SQL indexer. The statements are valid for both PostgreSQL and
+-- SQLite, and are applied each time the indexer starts.
+--
+-- Hashes, chain IDs, owners and application IDs are in their usual textual form, amounts
+-- are decimal strings, and timestamps are in microseconds since the Unix epoch. System
+-- operations and messages are stored as JSON, and the bytes of user operations, messages
+-- and events as hexadecimal strings, since only the application can decode them.
+
+-- One row per confirmed block.
+CREATE TABLE IF NOT EXISTS blocks (
+    hash TEXT PRIMARY KEY,
+    chain_id TEXT NOT NULL,
+    height BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    authenticated_signer TEXT,
+    previous_block_hash TEXT,
+    state_hash TEXT NOT NULL,
+    UNIQUE (chain_id, height)
+);
+
+-- The operations of each block. Exactly one of `system_operation` and `user_bytes` is set.
+CREATE TABLE IF NOT EXISTS operations (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash),
+    operation_index BIGINT NOT NULL,
+    -- NULL for system operations.
+    application_id TEXT,
+    system_operation TEXT,
+    user_bytes TEXT,
+    PRIMARY KEY (block_hash, operation_index)
+);
+
+-- The messages that each block received from other chains, with the bundle they came in.
+CREATE TABLE IF NOT EXISTS incoming_messages (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash),
+    bundle_index BIGINT NOT NULL,
+    message_index BIGINT NOT NULL,
+    origin TEXT NOT NULL,
+    -- The sending block, and the index of the transaction that sent the message.
+    origin_height BIGINT NOT NULL,
+    origin_block_hash TEXT NOT NULL,
+    origin_transaction_index BIGINT NOT NULL,
+    -- `Accept` or `Reject`.
+    action TEXT NOT NULL,
+    -- `Simple`, `Protected`, `Tracked` or `Bouncing`.
+    kind TEXT NOT NULL,
+    authenticated_signer TEXT,
+    grant_amount TEXT NOT NULL,
+    application_id TEXT,
+    system_message TEXT,
+    user_bytes TEXT,
+    PRIMARY KEY (block_hash, bundle_index, message_index)
+);
+
+-- The messages that each block sent, per transaction.
+CREATE TABLE IF NOT EXISTS outgoing_messages (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash),
+    transaction_index BIGINT NOT NULL,
+    message_index BIGINT NOT NULL,
+    destination TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    authenticated_signer TEXT,
+    grant_amount TEXT NOT NULL,
+    application_id TEXT,
+    system_message TEXT,
+    user_bytes TEXT,
+    PRIMARY KEY (block_hash, transaction_index, message_index)
+);
+
+-- The events that each block emitted, per transaction.
+CREATE TABLE IF NOT EXISTS events (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash),
+    transaction_index BIGINT NOT NULL,
+    position BIGINT NOT NULL,
+    -- NULL for system streams.
+    application_id TEXT,
+    stream_name TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (block_hash, transaction_index, position)
+);
+
+CREATE INDEX IF NOT EXISTS outgoing_messages_by_destination ON outgoing_messages (destination);
+CREATE INDEX IF NOT EXISTS events_by_stream ON events (application_id, stream_name, event_index);
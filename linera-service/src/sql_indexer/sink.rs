@@ -0,0 +1,217 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::BlockHeight,
+    identifiers::{ChainId, GenericApplicationId},
+};
+use linera_chain::block::Block;
+use linera_execution::{Message, Operation};
+use sqlx::{any::AnyPoolOptions, AnyPool};
+
+use super::IndexerError;
+
+/// The SQL statements creating the indexer's tables.
+pub const SCHEMA: &str = include_str!("schema.sql");
+
+/// A PostgreSQL or SQLite database with the [`SCHEMA`] of the indexer.
+#[derive(Clone)]
+pub struct SqlSink {
+    pool: AnyPool,
+}
+
+impl SqlSink {
+    /// Connects to the database at `url`, e.g. `postgres://localhost/linera` or
+    /// `sqlite://indexer.db?mode=rwc`, and creates the tables that are missing.
+    pub async fn connect(url: &str) -> Result<Self, IndexerError> {
+        sqlx::any::install_default_drivers();
+        // Each connection to an in-memory SQLite database has its own database.
+        let max_connections = if url.starts_with("sqlite::memory:") {
+            1
+        } else {
+            4
+        };
+        let pool = AnyPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await?;
+        sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+        Ok(SqlSink { pool })
+    }
+
+    /// Returns the height of the next block of the chain to index.
+    pub async fn next_block_height(&self, chain_id: ChainId) -> Result<BlockHeight, IndexerError> {
+        let height: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(height) FROM blocks WHERE chain_id = $1")
+                .bind(chain_id.to_string())
+                .fetch_one(&self.pool)
+                .await?;
+        match height {
+            None => Ok(BlockHeight::ZERO),
+            Some(height) => Ok(BlockHeight(u64::try_from(height)?).try_add_one()?),
+        }
+    }
+
+    /// Returns the chains that indexed blocks sent messages to.
+    pub async fn destinations(&self) -> Result<Vec<ChainId>, IndexerError> {
+        let destinations: Vec<String> =
+            sqlx::query_scalar("SELECT DISTINCT destination FROM outgoing_messages")
+                .fetch_all(&self.pool)
+                .await?;
+        destinations
+            .iter()
+            .map(|destination| Ok(destination.parse()?))
+            .collect()
+    }
+
+    /// Writes a block, with its operations, messages and events, in a single transaction.
+    pub async fn insert_block(&self, hash: CryptoHash, block: &Block) -> Result<(), IndexerError> {
+        let mut transaction = self.pool.begin().await?;
+        let header = &block.header;
+        let block_hash = hash.to_string();
+        sqlx::query(
+            "INSERT INTO blocks (hash, chain_id, height, timestamp, epoch, \
+             authenticated_signer, previous_block_hash, state_hash) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(&block_hash)
+        .bind(header.chain_id.to_string())
+        .bind(i64::try_from(header.height.0)?)
+        .bind(i64::try_from(header.timestamp.micros())?)
+        .bind(i64::from(header.epoch.0))
+        .bind(header.authenticated_signer.map(|owner| owner.to_string()))
+        .bind(header.previous_block_hash.map(|hash| hash.to_string()))
+        .bind(header.state_hash.to_string())
+        .execute(&mut *transaction)
+        .await?;
+
+        for (index, operation) in block.body.operations.iter().enumerate() {
+            let (system_operation, user_bytes) = match operation {
+                Operation::System(operation) => (Some(serde_json::to_string(operation)?), None),
+                Operation::User { bytes, .. } => (None, Some(hex::encode(bytes))),
+            };
+            sqlx::query(
+                "INSERT INTO operations (block_hash, operation_index, application_id, \
+                 system_operation, user_bytes) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(&block_hash)
+            .bind(i64::try_from(index)?)
+            .bind(user_application_id(operation.application_id()))
+            .bind(system_operation)
+            .bind(user_bytes)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for (bundle_index, bundle) in block.body.incoming_bundles.iter().enumerate() {
+            for (message_index, posted) in bundle.bundle.messages.iter().enumerate() {
+                let (application_id, system_message, user_bytes) =
+                    message_columns(&posted.message)?;
+                sqlx::query(
+                    "INSERT INTO incoming_messages (block_hash, bundle_index, message_index, \
+                     origin, origin_height, origin_block_hash, origin_transaction_index, action, \
+                     kind, authenticated_signer, grant_amount, application_id, system_message, \
+                     user_bytes) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+                )
+                .bind(&block_hash)
+                .bind(i64::try_from(bundle_index)?)
+                .bind(i64::try_from(message_index)?)
+                .bind(bundle.origin.to_string())
+                .bind(i64::try_from(bundle.bundle.height.0)?)
+                .bind(bundle.bundle.certificate_hash.to_string())
+                .bind(i64::from(bundle.bundle.transaction_index))
+                .bind(format!("{:?}", bundle.action))
+                .bind(format!("{:?}", posted.kind))
+                .bind(posted.authenticated_signer.map(|owner| owner.to_string()))
+                .bind(posted.grant.to_string())
+                .bind(application_id)
+                .bind(system_message)
+                .bind(user_bytes)
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        for (transaction_index, messages) in block.body.messages.iter().enumerate() {
+            for (message_index, outgoing) in messages.iter().enumerate() {
+                let (application_id, system_message, user_bytes) =
+                    message_columns(&outgoing.message)?;
+                sqlx::query(
+                    "INSERT INTO outgoing_messages (block_hash, transaction_index, \
+                     message_index, destination, kind, authenticated_signer, grant_amount, \
+                     application_id, system_message, user_bytes) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                )
+                .bind(&block_hash)
+                .bind(i64::try_from(transaction_index)?)
+                .bind(i64::try_from(message_index)?)
+                .bind(outgoing.destination.to_string())
+                .bind(format!("{:?}", outgoing.kind))
+                .bind(outgoing.authenticated_signer.map(|owner| owner.to_string()))
+                .bind(outgoing.grant.to_string())
+                .bind(application_id)
+                .bind(system_message)
+                .bind(user_bytes)
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        for (transaction_index, events) in block.body.events.iter().enumerate() {
+            for (position, event) in events.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO events (block_hash, transaction_index, position, \
+                     application_id, stream_name, event_index, value) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                )
+                .bind(&block_hash)
+                .bind(i64::try_from(transaction_index)?)
+                .bind(i64::try_from(position)?)
+                .bind(user_application_id(event.stream_id.application_id))
+                .bind(hex::encode(&event.stream_id.stream_name.0))
+                .bind(i64::from(event.index))
+                .bind(hex::encode(&event.value))
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Returns the number of rows in a table.
+    #[cfg(test)]
+    pub(crate) async fn count(&self, table: &str) -> Result<i64, IndexerError> {
+        Ok(sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(&self.pool)
+            .await?)
+    }
+}
+
+/// Returns the ID of a user application, or `None` for the system application.
+fn user_application_id(application_id: GenericApplicationId) -> Option<String> {
+    match application_id {
+        GenericApplicationId::System => None,
+        GenericApplicationId::User(application_id) => Some(application_id.to_string()),
+    }
+}
+
+/// Returns the `application_id`, `system_message` and `user_bytes` columns of a message.
+fn message_columns(
+    message: &Message,
+) -> Result<(Option<String>, Option<String>, Option<String>), IndexerError> {
+    Ok(match message {
+        Message::System(message) => (None, Some(serde_json::to_string(message)?), None),
+        Message::User {
+            application_id,
+            bytes,
+        } => (
+            Some(application_id.to_string()),
+            None,
+            Some(hex::encode(bytes)),
+        ),
+    })
+}
@@ -10,7 +10,10 @@ use std::{
 
 use anyhow::{bail, Context as _, Result};
 use async_graphql::http::GraphiQLSource;
-use axum::response::{self, IntoResponse};
+use axum::{
+    extract::OriginalUri,
+    response::{self, IntoResponse},
+};
 use http::Uri;
 #[cfg(test)]
 use linera_base::command::parse_version_message;
@@ -145,10 +148,19 @@ where
 }
 
 /// Returns an HTML response constructing the GraphiQL web page for the given URI.
-pub(crate) async fn graphiql(uri: Uri) -> impl IntoResponse {
+pub(crate) async fn graphiql(
+    uri: Uri,
+    OriginalUri(original_uri): OriginalUri,
+) -> impl IntoResponse {
+    // In a nested router, `uri` is relative to the path that the router is nested under.
+    let prefix = original_uri
+        .path()
+        .strip_suffix(uri.path().trim_end_matches('/'))
+        .unwrap_or_default()
+        .trim_end_matches('/');
     let source = GraphiQLSource::build()
-        .endpoint(uri.path())
-        .subscription_endpoint("/ws")
+        .endpoint(original_uri.path())
+        .subscription_endpoint(&format!("{prefix}/ws"))
         .finish();
     response::Html(source)
 }
@@ -161,6 +173,18 @@ pub fn parse_millis_delta(s: &str) -> Result<TimeDelta, ParseIntError> {
     Ok(TimeDelta::from_millis(s.parse()?))
 }
 
+/// Parses a storage write pressure threshold, between 0 and 1.
+pub fn parse_write_pressure(s: &str) -> Result<f64, String> {
+    let pressure = s.parse::<f64>().map_err(|error| error.to_string())?;
+    if (0.0..=1.0).contains(&pressure) {
+        Ok(pressure)
+    } else {
+        Err(format!(
+            "the write pressure must be between 0 and 1, not {pressure}"
+        ))
+    }
+}
+
 #[test]
 fn test_parse_version_message() {
     let s = "something\n . . . version12\nother things";
@@ -0,0 +1,483 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A load generator for a validator's internal handlers, for capacity planning.
+//!
+//! The benchmark creates its own genesis in the given storage, with a committee of one
+//! validator and chains whose keys it holds. Each chain then proposes blocks of transfers at
+//! the configured rate, directly to a [`WorkerState`], the way the proxy forwards them to a
+//! shard: the worker executes and votes on each proposal, the votes are turned into
+//! certificates, and the resulting cross-chain requests are handled by the same worker. The
+//! messages that a chain receives are included in its next block.
+//!
+//! No network is involved, so the results are an upper bound on what one shard can handle
+//! with the given storage.
+
+use std::{collections::VecDeque, fmt, num::NonZeroUsize, time::Duration};
+
+use anyhow::{anyhow, bail, ensure, Context as _};
+use async_trait::async_trait;
+use futures::future;
+use linera_base::{
+    crypto::{
+        BlsSecretKey, CryptoHash, CryptoRng, Ed25519PublicKey, InMemorySigner, ValidatorKeypair,
+    },
+    data_types::{Amount, Epoch, Timestamp},
+    identifiers::{Account, AccountOwner, ChainId},
+};
+use linera_chain::{
+    data_types::{BlockProposal, ProposedBlock},
+    types::{CertificateKind, LiteCertificate},
+};
+use linera_client::config::{CommitteeConfig, GenesisConfig, ValidatorConfig};
+use linera_core::{data_types::ChainInfoQuery, worker::WorkerState};
+use linera_execution::{
+    system::{EncryptedMemo, Recipient, MAXIMUM_MEMO_LEN},
+    Operation, ResourceControlPolicy, SystemOperation,
+};
+use linera_rpc::config::{NetworkProtocol, TlsConfig, ValidatorPublicNetworkConfig};
+use linera_storage::Storage;
+use serde::Serialize;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::info;
+
+use crate::storage::Runnable;
+
+/// The options of a benchmark of a validator's internal handlers.
+#[derive(Clone, Debug, clap::Args)]
+pub struct WorkerBenchmarkOptions {
+    /// The number of chains proposing blocks.
+    #[arg(long, default_value = "16")]
+    pub chains: NonZeroUsize,
+
+    /// The target number of transfers per second, over all chains.
+    #[arg(long, default_value = "1000")]
+    pub tps: NonZeroUsize,
+
+    /// The number of transfers in each block.
+    #[arg(long, default_value = "1")]
+    pub transactions_per_block: NonZeroUsize,
+
+    /// The size of the payload of each transfer, in bytes. The payload is sent as the
+    /// transfer's memo, so it can be at most the maximum size of a memo.
+    #[arg(long, default_value = "0")]
+    pub payload_size: usize,
+
+    /// The fraction of the transfers that go to another chain, between 0 and 1. The other
+    /// transfers don't send any message.
+    #[arg(long, default_value = "0.5")]
+    pub cross_chain_ratio: f64,
+
+    /// How long to generate load for, in seconds.
+    #[arg(long = "duration-secs", default_value = "10", value_parser = crate::util::parse_secs)]
+    pub duration: Duration,
+
+    /// The maximal number of chains loaded in memory at a given time.
+    #[arg(long, default_value = "400")]
+    pub max_loaded_chains: NonZeroUsize,
+}
+
+impl WorkerBenchmarkOptions {
+    /// Returns an error if the options are inconsistent.
+    pub fn check(&self) -> anyhow::Result<()> {
+        ensure!(
+            (0.0..=1.0).contains(&self.cross_chain_ratio),
+            "The cross-chain ratio must be between 0 and 1"
+        );
+        ensure!(
+            self.payload_size <= MAXIMUM_MEMO_LEN,
+            "The payload size must be at most {MAXIMUM_MEMO_LEN} bytes"
+        );
+        Ok(())
+    }
+
+    /// Returns how often each chain proposes a block.
+    fn block_period(&self) -> Duration {
+        let blocks_per_second = self.tps.get() as f64 / self.transactions_per_block.get() as f64;
+        Duration::from_secs_f64(self.chains.get() as f64 / blocks_per_second)
+    }
+}
+
+/// A benchmark of a validator's internal handlers, run with [`Runnable`] on the storage
+/// under test.
+pub struct WorkerBenchmark {
+    options: WorkerBenchmarkOptions,
+    genesis_config: GenesisConfig,
+    validator: ValidatorKeypair,
+    signer: InMemorySigner,
+}
+
+impl WorkerBenchmark {
+    /// Creates new keys for the validator and the chains, and the genesis configuration
+    /// that the benchmark runs with.
+    pub fn new(options: WorkerBenchmarkOptions) -> Self {
+        let mut rng = Box::<dyn CryptoRng>::from(None);
+        let validator = ValidatorKeypair::generate_from(&mut rng);
+        let mut signer = InMemorySigner::new(None);
+        let committee = CommitteeConfig {
+            validators: vec![ValidatorConfig {
+                public_key: validator.public_key,
+                account_key: signer.generate_new(),
+                network: ValidatorPublicNetworkConfig {
+                    protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+                    host: "localhost".to_string(),
+                    port: 0,
+                },
+                bls_key: Some(
+                    BlsSecretKey::from_validator_secret(&validator.secret_key).validator_key(),
+                ),
+            }],
+            certificate_signature_scheme: Default::default(),
+        };
+        let mut genesis_config = GenesisConfig::new(
+            committee,
+            // A placeholder, until the admin chain's ID is known.
+            ChainId(CryptoHash::from([0; 4])),
+            Timestamp::now(),
+            ResourceControlPolicy::no_fees(),
+            "benchmark".to_string(),
+        );
+        genesis_config.chains = (0..options.chains.get())
+            .map(|_| (signer.generate_new(), Amount::from_tokens(1_000_000)))
+            .collect();
+        // The admin chain's description doesn't depend on the admin chain ID.
+        genesis_config.admin_id = genesis_config.chain_descriptions()[0].id();
+        Self {
+            options,
+            genesis_config,
+            validator,
+            signer,
+        }
+    }
+
+    /// Returns the genesis configuration that the benchmark runs with.
+    pub fn genesis_config(&self) -> &GenesisConfig {
+        &self.genesis_config
+    }
+}
+
+#[async_trait]
+impl Runnable for WorkerBenchmark {
+    type Output = anyhow::Result<BenchmarkReport>;
+
+    async fn run<S>(self, mut storage: S) -> anyhow::Result<BenchmarkReport>
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        match storage.read_network_description().await? {
+            None => self.genesis_config.initialize_storage(&mut storage).await?,
+            Some(description) if description.genesis_config_hash == self.genesis_config.hash() => {}
+            Some(_) => bail!("The storage is already initialized: use a new namespace"),
+        }
+        let worker = WorkerState::new(
+            "benchmark".to_string(),
+            Some(self.validator.secret_key),
+            storage,
+            self.options.max_loaded_chains,
+        )
+        .with_allow_inactive_chains(false)
+        .with_allow_messages_from_deprecated_epochs(false);
+
+        let chains = self
+            .genesis_config
+            .chain_descriptions()
+            .iter()
+            .zip(&self.genesis_config.chains)
+            .map(|(description, (public_key, _))| (description.id(), (*public_key).into()))
+            .collect::<Vec<(ChainId, AccountOwner)>>();
+        let chain_ids = chains
+            .iter()
+            .map(|(chain_id, _)| *chain_id)
+            .collect::<Vec<_>>();
+        info!(
+            "Proposing blocks on {} chains for {} s",
+            chains.len(),
+            self.options.duration.as_secs()
+        );
+        let start = Instant::now();
+        let deadline = start + self.options.duration;
+        let stats =
+            future::try_join_all(chains.iter().enumerate().map(|(index, (chain_id, owner))| {
+                let load = ChainLoad {
+                    worker: &worker,
+                    signer: &self.signer,
+                    options: &self.options,
+                    chain_id: *chain_id,
+                    owner: *owner,
+                    recipients: chain_ids
+                        .iter()
+                        .cycle()
+                        .skip(index + 1)
+                        .take(chain_ids.len() - 1)
+                        .copied()
+                        .collect(),
+                };
+                load.run(deadline)
+            }))
+            .await?;
+        Ok(BenchmarkReport::new(stats, start.elapsed()))
+    }
+}
+
+/// The blocks proposed by one chain.
+struct ChainLoad<'a, S: Storage> {
+    worker: &'a WorkerState<S>,
+    signer: &'a InMemorySigner,
+    options: &'a WorkerBenchmarkOptions,
+    chain_id: ChainId,
+    owner: AccountOwner,
+    /// The other chains, to send the cross-chain transfers to.
+    recipients: Vec<ChainId>,
+}
+
+/// What one chain measured.
+#[derive(Default)]
+struct ChainStats {
+    blocks: usize,
+    transactions: usize,
+    cross_chain_transfers: usize,
+    /// The time from proposing a block until its confirmed certificate was handled.
+    block_latencies: Vec<Duration>,
+    /// The time to handle the cross-chain requests of a confirmed block.
+    delivery_latencies: Vec<Duration>,
+}
+
+impl<S> ChainLoad<'_, S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    async fn run(self, deadline: Instant) -> anyhow::Result<ChainStats> {
+        let mut stats = ChainStats::default();
+        let mut interval = tokio::time::interval(self.options.block_period());
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut transfers = 0;
+        while interval.tick().await < deadline {
+            let operations = (0..self.options.transactions_per_block.get())
+                .map(|_| {
+                    let operation = self.transfer(transfers, &mut stats);
+                    transfers += 1;
+                    operation
+                })
+                .collect();
+            Box::pin(self.propose(operations, &mut stats)).await?;
+        }
+        Ok(stats)
+    }
+
+    /// Returns the transfer number `index`, to another chain or not, as set by the
+    /// cross-chain ratio.
+    fn transfer(&self, index: usize, stats: &mut ChainStats) -> Operation {
+        let ratio = self.options.cross_chain_ratio;
+        let is_cross_chain = ((index + 1) as f64 * ratio).floor() > (index as f64 * ratio).floor();
+        let recipient = if is_cross_chain && !self.recipients.is_empty() {
+            stats.cross_chain_transfers += 1;
+            let chain_id = self.recipients[stats.cross_chain_transfers % self.recipients.len()];
+            Recipient::Account(Account::chain(chain_id))
+        } else {
+            Recipient::Burn
+        };
+        let owner = AccountOwner::CHAIN;
+        let amount = Amount::from_attos(1);
+        let operation = if self.options.payload_size == 0 {
+            SystemOperation::Transfer {
+                owner,
+                recipient,
+                amount,
+            }
+        } else {
+            let key = Ed25519PublicKey([0; 32]);
+            SystemOperation::TransferWithMemo {
+                owner,
+                recipient,
+                amount,
+                memo: EncryptedMemo {
+                    ephemeral_key: key,
+                    recipient_key: key,
+                    nonce: [0; 24],
+                    ciphertext: vec![0; self.options.payload_size],
+                },
+            }
+        };
+        Operation::system(operation)
+    }
+
+    /// Proposes and confirms a block with the `operations` and the pending incoming
+    /// messages, and handles the cross-chain requests.
+    async fn propose(
+        &self,
+        operations: Vec<Operation>,
+        stats: &mut ChainStats,
+    ) -> anyhow::Result<()> {
+        let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
+        let (response, _) = self.worker.handle_chain_info_query(query).await?;
+        let info = response.info;
+        let transactions = operations.len() + info.requested_pending_message_bundles.len();
+        let block = ProposedBlock {
+            chain_id: self.chain_id,
+            epoch: info.epoch.unwrap_or(Epoch::ZERO),
+            incoming_bundles: info.requested_pending_message_bundles,
+            operations,
+            height: info.next_block_height,
+            timestamp: info.timestamp.max(Timestamp::now()),
+            authenticated_signer: Some(self.owner),
+            previous_block_hash: info.block_hash,
+        };
+        let round = info.manager.ownership.first_round();
+
+        let start = Instant::now();
+        let proposal = BlockProposal::new_initial(self.owner, round, block, self.signer)
+            .await
+            .map_err(|error| anyhow!("Failed to sign the block proposal: {error}"))?;
+        let (response, _) = self.worker.handle_block_proposal(proposal).await?;
+        let mut vote = response
+            .info
+            .manager
+            .pending
+            .context("The validator didn't vote for the proposal")?;
+        let actions = loop {
+            let kind = vote.kind();
+            let certificate = LiteCertificate::new(
+                vote.value,
+                vote.round,
+                vec![(vote.public_key, vote.signature)],
+            );
+            let (response, actions) =
+                Box::pin(self.worker.handle_lite_certificate(certificate, None)).await?;
+            if kind == CertificateKind::Confirmed {
+                break actions;
+            }
+            vote = response
+                .info
+                .manager
+                .pending
+                .context("The validator didn't vote to confirm the block")?;
+        };
+        stats.block_latencies.push(start.elapsed());
+
+        let start = Instant::now();
+        let mut requests = VecDeque::from(actions.cross_chain_requests);
+        while let Some(request) = requests.pop_front() {
+            let actions = self.worker.handle_cross_chain_request(request).await?;
+            requests.extend(actions.cross_chain_requests);
+        }
+        stats.delivery_latencies.push(start.elapsed());
+        stats.blocks += 1;
+        stats.transactions += transactions;
+        Ok(())
+    }
+}
+
+/// The throughput and latencies measured by a benchmark.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkReport {
+    pub elapsed: Duration,
+    pub blocks: usize,
+    /// The number of operations and incoming message bundles in the blocks.
+    pub transactions: usize,
+    pub cross_chain_transfers: usize,
+    /// The time from proposing a block until its confirmed certificate was handled.
+    pub block_latency: Latencies,
+    /// The time to handle the cross-chain requests of a confirmed block.
+    pub delivery_latency: Latencies,
+}
+
+/// Percentiles of a latency.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Latencies {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Latencies {
+    fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let percentile = |percent: usize| {
+            let index = (samples.len() * percent).div_ceil(100).saturating_sub(1);
+            samples.get(index).copied().unwrap_or_default()
+        };
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+impl BenchmarkReport {
+    fn new(stats: Vec<ChainStats>, elapsed: Duration) -> Self {
+        let mut block_latencies = Vec::new();
+        let mut delivery_latencies = Vec::new();
+        let (mut blocks, mut transactions, mut cross_chain_transfers) = (0, 0, 0);
+        for chain in stats {
+            blocks += chain.blocks;
+            transactions += chain.transactions;
+            cross_chain_transfers += chain.cross_chain_transfers;
+            block_latencies.extend(chain.block_latencies);
+            delivery_latencies.extend(chain.delivery_latencies);
+        }
+        Self {
+            elapsed,
+            blocks,
+            transactions,
+            cross_chain_transfers,
+            block_latency: Latencies::new(block_latencies),
+            delivery_latency: Latencies::new(delivery_latencies),
+        }
+    }
+
+    /// Returns the number of transactions per second.
+    pub fn transactions_per_second(&self) -> f64 {
+        self.transactions as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Latencies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            self.p50, self.p90, self.p99, self.max
+        )
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "{} blocks and {} transactions in {seconds:.1} s",
+            self.blocks, self.transactions
+        )?;
+        writeln!(
+            f,
+            "Throughput: {:.1} transactions/s, {:.1} blocks/s, {:.1} cross-chain transfers/s",
+            self.transactions_per_second(),
+            self.blocks as f64 / seconds,
+            self.cross_chain_transfers as f64 / seconds
+        )?;
+        writeln!(f, "Block latency: {}", self.block_latency)?;
+        write!(f, "Cross-chain delivery latency: {}", self.delivery_latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Latencies;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let samples = (1..=200).rev().map(Duration::from_millis).collect();
+        let latencies = Latencies::new(samples);
+        assert_eq!(latencies.p50, Duration::from_millis(100));
+        assert_eq!(latencies.p90, Duration::from_millis(180));
+        assert_eq!(latencies.p99, Duration::from_millis(198));
+        assert_eq!(latencies.max, Duration::from_millis(200));
+        assert_eq!(Latencies::new(Vec::new()).max, Duration::ZERO);
+    }
+}
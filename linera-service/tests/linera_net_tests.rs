@@ -3560,7 +3560,7 @@ async fn test_end_to_end_repeated_transfers(config: impl LineraNetConfig) -> Res
                         message_duration += start_time.elapsed();
                     }
                 }
-                Reason::NewBlock { height, hash } => {
+                Reason::NewBlock { height, hash, .. } => {
                     assert_eq!(height, next_height2);
                     assert!(
                         got_message,
@@ -32,7 +32,7 @@ use crate::common::storage_service_test_endpoint;
 use crate::{
     common::{KeyPrefix, ServiceStoreError, ServiceStoreInternalConfig, MAX_PAYLOAD_SIZE},
     key_value_store::{
-        statement::Operation, store_processor_client::StoreProcessorClient, KeyValue,
+        statement::Operation, store_processor_client::StoreProcessorClient, KeyRange, KeyValue,
         KeyValueAppend, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
         ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyListRootKeys,
         ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk, RequestContainsKey,
@@ -273,6 +273,11 @@ impl WritableKeyValueStore for ServiceStoreClientInternal {
                 WriteOperation::Delete { key } => (key.len(), 0),
                 WriteOperation::Put { key, value } => (key.len(), value.len()),
                 WriteOperation::DeletePrefix { key_prefix } => (key_prefix.len(), 0),
+                WriteOperation::DeleteRange { start, end } => {
+                    // The end of the range is sized like a value, with its own root key.
+                    ensure!(end.len() <= MAX_KEY_SIZE, ServiceStoreError::KeyTooLong);
+                    (start.len(), end.len() + root_key_len)
+                }
             };
             let operation_size = key_len + value_len + root_key_len;
             ensure!(key_len <= MAX_KEY_SIZE, ServiceStoreError::KeyTooLong);
@@ -365,6 +370,16 @@ impl ServiceStoreClientInternal {
                 full_key_prefix.extend(key_prefix);
                 Operation::DeletePrefix(full_key_prefix)
             }
+            WriteOperation::DeleteRange { start, end } => {
+                let mut full_start = self.start_key.clone();
+                full_start.extend(start);
+                let mut full_end = self.start_key.clone();
+                full_end.extend(end);
+                Operation::DeleteRange(KeyRange {
+                    start: full_start,
+                    end: full_end,
+                })
+            }
         };
         Statement {
             operation: Some(operation),
@@ -23,7 +23,7 @@ use tracing_subscriber::fmt::format::FmtSpan;
 use crate::key_value_store::{
     statement::Operation,
     store_processor_server::{StoreProcessor, StoreProcessorServer},
-    KeyValue, OptValue, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
+    KeyRange, KeyValue, OptValue, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
     ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyListRootKeys,
     ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk, RequestContainsKey,
     RequestContainsKeys, RequestCreateNamespace, RequestDeleteNamespace, RequestExistsNamespace,
@@ -453,6 +453,9 @@ impl StoreProcessor for ServiceStoreServer {
                 Operation::DeletePrefix(key_prefix) => {
                     batch.delete_key_prefix(key_prefix);
                 }
+                Operation::DeleteRange(KeyRange { start, end }) => {
+                    batch.delete_key_range(start, Some(end));
+                }
             }
         }
         if !batch.is_empty() {
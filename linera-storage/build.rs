@@ -9,6 +9,7 @@ fn main() {
         with_wasmtime: { all(not(target_arch = "wasm32"), feature = "wasmtime") },
         with_wasm_runtime: { any(with_wasmer, with_wasmtime) },
         with_revm: { feature = "revm" },
+        with_s3: { all(not(target_arch = "wasm32"), feature = "s3") },
         web: { all(target_arch = "wasm32", feature = "web") },
     };
 }
@@ -0,0 +1,308 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Object stores for the archives of inactive chains, moved out of the main storage.
+
+#[cfg(with_s3)]
+mod s3;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+#[cfg(with_testing)]
+use std::{collections::BTreeMap, sync::Mutex};
+use std::{str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+#[cfg(with_s3)]
+use futures::FutureExt as _;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::StreamExt as _;
+use linera_views::views::ViewError;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+#[cfg(with_s3)]
+pub use self::s3::S3ArchiveStore;
+
+/// The contents of an object, read in chunks.
+pub type ObjectStream = BoxStream<'static, Result<Vec<u8>, ViewError>>;
+
+/// A store of objects, e.g. a bucket of an object storage service, where the archives of
+/// chains are kept.
+///
+/// Objects are written and read in chunks, so that they don't have to fit in memory.
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+pub trait ArchiveStore: Send + Sync {
+    /// Starts writing an object. It replaces any object with the same key once the writer is
+    /// finished; if the writer is dropped instead, the object is left unchanged.
+    async fn create_object(&self, key: &str) -> Result<Box<dyn ObjectWriter>, ViewError>;
+
+    /// Reads an object, if it exists.
+    async fn read_object(&self, key: &str) -> Result<Option<ObjectStream>, ViewError>;
+
+    /// Deletes an object, if it exists.
+    async fn delete_object(&self, key: &str) -> Result<(), ViewError>;
+}
+
+/// The writer of an object of an [`ArchiveStore`].
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+pub trait ObjectWriter: Send {
+    /// Appends a chunk to the object.
+    async fn write(&mut self, chunk: Vec<u8>) -> Result<(), ViewError>;
+
+    /// Completes the object, making it visible to readers.
+    async fn finish(self: Box<Self>) -> Result<(), ViewError>;
+}
+
+/// The location of an archive store, as given on the command line: either `s3://BUCKET`
+/// or `s3://BUCKET/PREFIX` for a bucket of an S3-compatible service, or the path of a
+/// directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveStoreConfig {
+    /// A local directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    Directory(PathBuf),
+    /// A bucket of an S3-compatible object storage service, and the prefix of the keys
+    /// of the objects in it.
+    S3 { bucket: String, prefix: String },
+}
+
+impl FromStr for ArchiveStoreConfig {
+    type Err = ViewError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(location) = input.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            if bucket.is_empty() {
+                return Err(archive_error(format!("missing bucket in {input}")));
+            }
+            return Ok(Self::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_end_matches('/').to_string(),
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        return Ok(Self::Directory(PathBuf::from(input)));
+        #[cfg(target_arch = "wasm32")]
+        Err(archive_error(format!("unsupported archive store {input}")))
+    }
+}
+
+impl ArchiveStoreConfig {
+    /// Connects to the archive store. S3 buckets are accessed with the AWS configuration of
+    /// the environment, e.g. `AWS_REGION`, `AWS_ENDPOINT_URL` and the credentials.
+    pub async fn connect(&self) -> Result<Arc<dyn ArchiveStore>, ViewError> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Directory(directory) => {
+                Ok(Arc::new(DirectoryArchiveStore::new(directory.clone())?))
+            }
+            #[cfg(with_s3)]
+            Self::S3 { bucket, prefix } => Ok(Arc::new(
+                S3ArchiveStore::from_env(bucket.clone(), prefix.clone())
+                    .boxed()
+                    .await?,
+            )),
+            #[cfg(not(with_s3))]
+            Self::S3 { .. } => Err(archive_error(
+                "this binary was built without the `s3` feature".to_string(),
+            )),
+        }
+    }
+}
+
+/// Returns an error of the archive store.
+fn archive_error(error: String) -> ViewError {
+    ViewError::StoreError {
+        backend: "archive".to_string(),
+        error,
+    }
+}
+
+/// An [`ArchiveStore`] keeping each object in a file of a local directory, e.g. a mounted
+/// object storage bucket.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DirectoryArchiveStore {
+    directory: PathBuf,
+}
+
+/// The number of bytes read from a file at once by a [`DirectoryArchiveStore`].
+#[cfg(not(target_arch = "wasm32"))]
+const FILE_CHUNK_SIZE: usize = 1 << 20;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DirectoryArchiveStore {
+    /// Creates a store in the given directory, which is created if necessary.
+    pub fn new(directory: PathBuf) -> Result<Self, ViewError> {
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(key)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl ArchiveStore for DirectoryArchiveStore {
+    async fn create_object(&self, key: &str) -> Result<Box<dyn ObjectWriter>, ViewError> {
+        // Write to a temporary file first, so that a partial object is never visible.
+        let path = self.path(key);
+        let temporary_path = path.with_extension("partial");
+        let file = tokio::fs::File::create(&temporary_path).await?;
+        Ok(Box::new(FileWriter {
+            file,
+            temporary_path,
+            path,
+        }))
+    }
+
+    async fn read_object(&self, key: &str) -> Result<Option<ObjectStream>, ViewError> {
+        let file = match tokio::fs::File::open(self.path(key)).await {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let chunks = futures::stream::try_unfold(file, |mut file| async move {
+            let mut chunk = Vec::with_capacity(FILE_CHUNK_SIZE);
+            (&mut file)
+                .take(FILE_CHUNK_SIZE as u64)
+                .read_to_end(&mut chunk)
+                .await?;
+            Ok((!chunk.is_empty()).then_some((chunk, file)))
+        });
+        Ok(Some(chunks.boxed()))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ViewError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The writer of an object of a [`DirectoryArchiveStore`].
+#[cfg(not(target_arch = "wasm32"))]
+struct FileWriter {
+    file: tokio::fs::File,
+    temporary_path: PathBuf,
+    path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl ObjectWriter for FileWriter {
+    async fn write(&mut self, chunk: Vec<u8>) -> Result<(), ViewError> {
+        self.file.write_all(&chunk).await?;
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> Result<(), ViewError> {
+        self.file.sync_all().await?;
+        tokio::fs::rename(&self.temporary_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+/// An [`ArchiveStore`] keeping the objects in memory, for tests.
+#[cfg(with_testing)]
+#[derive(Default)]
+pub struct MemoryArchiveStore {
+    objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+
+#[cfg(with_testing)]
+impl MemoryArchiveStore {
+    /// Returns whether the store has an object with the given key.
+    pub fn contains_object(&self, key: &str) -> bool {
+        self.objects.lock().unwrap().contains_key(key)
+    }
+}
+
+#[cfg(with_testing)]
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl ArchiveStore for MemoryArchiveStore {
+    async fn create_object(&self, key: &str) -> Result<Box<dyn ObjectWriter>, ViewError> {
+        Ok(Box::new(MemoryWriter {
+            objects: self.objects.clone(),
+            key: key.to_string(),
+            bytes: Vec::new(),
+        }))
+    }
+
+    async fn read_object(&self, key: &str) -> Result<Option<ObjectStream>, ViewError> {
+        let Some(bytes) = self.objects.lock().unwrap().get(key).cloned() else {
+            return Ok(None);
+        };
+        // Split the object, so that readers see more than one chunk.
+        let chunks = bytes
+            .chunks(7)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        Ok(Some(Box::pin(futures::stream::iter(chunks))))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ViewError> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// The writer of an object of a [`MemoryArchiveStore`].
+#[cfg(with_testing)]
+struct MemoryWriter {
+    objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    key: String,
+    bytes: Vec<u8>,
+}
+
+#[cfg(with_testing)]
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl ObjectWriter for MemoryWriter {
+    async fn write(&mut self, chunk: Vec<u8>) -> Result<(), ViewError> {
+        self.bytes.extend(chunk);
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> Result<(), ViewError> {
+        self.objects.lock().unwrap().insert(self.key, self.bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveStoreConfig;
+
+    #[test]
+    fn test_parse_archive_store_config() {
+        assert_eq!(
+            "s3://bucket/chains/".parse::<ArchiveStoreConfig>().unwrap(),
+            ArchiveStoreConfig::S3 {
+                bucket: "bucket".to_string(),
+                prefix: "chains".to_string(),
+            }
+        );
+        assert_eq!(
+            "s3://bucket".parse::<ArchiveStoreConfig>().unwrap(),
+            ArchiveStoreConfig::S3 {
+                bucket: "bucket".to_string(),
+                prefix: String::new(),
+            }
+        );
+        assert!("s3:///chains".parse::<ArchiveStoreConfig>().is_err());
+        assert_eq!(
+            "/var/archive".parse::<ArchiveStoreConfig>().unwrap(),
+            ArchiveStoreConfig::Directory("/var/archive".into())
+        );
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An [`ArchiveStore`] in a bucket of an S3-compatible object storage service.
+
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use aws_credential_types::provider::{ProvideCredentials as _, SharedCredentialsProvider};
+use aws_sigv4::{
+    http_request::{
+        sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest,
+        SigningSettings, UriPathNormalizationMode,
+    },
+    sign::v4,
+};
+use futures::{FutureExt as _, StreamExt as _, TryStreamExt as _};
+use linera_views::views::ViewError;
+use reqwest::{Method, Response, StatusCode};
+
+use super::{archive_error, ArchiveStore, ObjectStream, ObjectWriter};
+
+/// The size of the parts of multipart uploads. S3 requires at least 5 MiB for all the parts
+/// but the last one.
+const PART_SIZE: usize = 8 << 20;
+
+/// The region used if the environment doesn't configure one.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// An [`ArchiveStore`] keeping the objects in a bucket of S3, or of a compatible service.
+///
+/// The objects are uploaded in parts, so that only one part at a time is held in memory.
+/// Uploads of dropped writers are left incomplete: the bucket should have a lifecycle rule
+/// that aborts incomplete multipart uploads.
+#[derive(Clone)]
+pub struct S3ArchiveStore {
+    client: reqwest::Client,
+    /// The URL of the bucket, in path style so that it works with any endpoint.
+    bucket_url: String,
+    /// The prefix of the keys of the objects, if any, ending with a `/`.
+    prefix: String,
+    region: String,
+    credentials: SharedCredentialsProvider,
+}
+
+impl S3ArchiveStore {
+    /// Creates a store in the given bucket of the endpoint, with keys starting with
+    /// `prefix`.
+    pub fn new(
+        endpoint: &str,
+        region: String,
+        credentials: SharedCredentialsProvider,
+        bucket: &str,
+        prefix: &str,
+    ) -> Self {
+        let prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{prefix}/")
+        };
+        Self {
+            client: reqwest::Client::new(),
+            bucket_url: format!("{}/{bucket}", endpoint.trim_end_matches('/')),
+            prefix,
+            region,
+            credentials,
+        }
+    }
+
+    /// Creates a store in the given bucket, with the endpoint, region and credentials
+    /// configured by the environment, like the AWS command-line tools.
+    pub async fn from_env(bucket: String, prefix: String) -> Result<Self, ViewError> {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest())
+            .boxed()
+            .await;
+        let region = config
+            .region()
+            .map_or_else(|| DEFAULT_REGION.to_string(), ToString::to_string);
+        let endpoint = config.endpoint_url().map_or_else(
+            || format!("https://s3.{region}.amazonaws.com"),
+            str::to_string,
+        );
+        let credentials = config
+            .credentials_provider()
+            .ok_or_else(|| archive_error("no AWS credentials are configured".to_string()))?;
+        Ok(Self::new(&endpoint, region, credentials, &bucket, &prefix))
+    }
+
+    fn object_url(&self, key: &str) -> String {
+        format!("{}/{}{key}", self.bucket_url, self.prefix)
+    }
+
+    /// Signs and sends a request.
+    async fn send(&self, method: Method, url: &str, body: Vec<u8>) -> Result<Response, ViewError> {
+        let identity = self
+            .credentials
+            .provide_credentials()
+            .await
+            .map_err(|error| archive_error(format!("failed to get AWS credentials: {error}")))?
+            .into();
+        let mut settings = SigningSettings::default();
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name("s3")
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()
+            .map_err(|error| archive_error(error.to_string()))?
+            .into();
+        let signable = SignableRequest::new(
+            method.as_str(),
+            url,
+            std::iter::empty(),
+            SignableBody::UnsignedPayload,
+        )
+        .map_err(|error| archive_error(error.to_string()))?;
+        let (instructions, _) = sign(signable, &params)
+            .map_err(|error| archive_error(error.to_string()))?
+            .into_parts();
+        let mut request = self.client.request(method, url).body(body);
+        for (name, value) in instructions.headers() {
+            request = request.header(name, value);
+        }
+        request.send().await.map_err(request_error)
+    }
+
+    /// Sends a request, and returns the response if it succeeded.
+    async fn send_successfully(
+        &self,
+        method: Method,
+        url: &str,
+        body: Vec<u8>,
+    ) -> Result<Response, ViewError> {
+        let response = self.send(method, url, body).await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        Err(response_error(response).await)
+    }
+
+    /// Aborts a multipart upload, so that its parts are not kept.
+    async fn abort_upload(&self, url: &str, upload_id: &str) -> Result<(), ViewError> {
+        let url = format!("{url}?uploadId={}", uri_encode(upload_id));
+        self.send_successfully(Method::DELETE, &url, Vec::new())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ArchiveStore for S3ArchiveStore {
+    async fn create_object(&self, key: &str) -> Result<Box<dyn ObjectWriter>, ViewError> {
+        Ok(Box::new(S3ObjectWriter {
+            store: self.clone(),
+            url: self.object_url(key),
+            upload_id: None,
+            part_tags: Vec::new(),
+            buffer: Vec::new(),
+        }))
+    }
+
+    async fn read_object(&self, key: &str) -> Result<Option<ObjectStream>, ViewError> {
+        let response = self
+            .send(Method::GET, &self.object_url(key), Vec::new())
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+        let chunks = response
+            .bytes_stream()
+            .map_ok(Vec::from)
+            .map_err(request_error);
+        Ok(Some(chunks.boxed()))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ViewError> {
+        let response = self
+            .send(Method::DELETE, &self.object_url(key), Vec::new())
+            .await?;
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        Err(response_error(response).await)
+    }
+}
+
+/// The writer of an object of an [`S3ArchiveStore`]. Objects of at most one part are
+/// uploaded at once, and longer ones with a multipart upload.
+struct S3ObjectWriter {
+    store: S3ArchiveStore,
+    url: String,
+    /// The ID of the multipart upload, once the first part is uploaded.
+    upload_id: Option<String>,
+    /// The entity tags of the uploaded parts.
+    part_tags: Vec<String>,
+    /// The bytes of the next part.
+    buffer: Vec<u8>,
+}
+
+impl S3ObjectWriter {
+    /// Uploads the buffer as the next part, starting the multipart upload if necessary.
+    async fn upload_part(&mut self) -> Result<(), ViewError> {
+        let upload_id = match &self.upload_id {
+            Some(upload_id) => upload_id,
+            None => {
+                let url = format!("{}?uploads", self.url);
+                let response = self
+                    .store
+                    .send_successfully(Method::POST, &url, Vec::new())
+                    .await?;
+                let body = response.text().await.map_err(request_error)?;
+                let upload_id = xml_element(&body, "UploadId").ok_or_else(|| {
+                    archive_error(format!("unexpected response to a new upload: {body}"))
+                })?;
+                self.upload_id.insert(upload_id.to_string())
+            }
+        };
+        let url = format!(
+            "{}?partNumber={}&uploadId={}",
+            self.url,
+            self.part_tags.len() + 1,
+            uri_encode(upload_id)
+        );
+        let part = std::mem::take(&mut self.buffer);
+        let response = self
+            .store
+            .send_successfully(Method::PUT, &url, part)
+            .await?;
+        let tag = response
+            .headers()
+            .get("ETag")
+            .and_then(|tag| tag.to_str().ok())
+            .ok_or_else(|| archive_error("missing entity tag of an uploaded part".to_string()))?;
+        self.part_tags.push(tag.to_string());
+        Ok(())
+    }
+
+    /// Uploads the last part and completes the multipart upload.
+    async fn complete_upload(&mut self) -> Result<(), ViewError> {
+        if !self.buffer.is_empty() {
+            self.upload_part().await?;
+        }
+        let upload_id = self.upload_id.as_deref().expect("the upload was started");
+        let mut body = "<CompleteMultipartUpload>".to_string();
+        for (index, tag) in self.part_tags.iter().enumerate() {
+            body.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{tag}</ETag></Part>",
+                index + 1
+            ));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+        let url = format!("{}?uploadId={}", self.url, uri_encode(upload_id));
+        let response = self
+            .store
+            .send_successfully(Method::POST, &url, body.into_bytes())
+            .await?;
+        // The upload can still fail after the response started, with an error in its body.
+        let body = response.text().await.map_err(request_error)?;
+        if xml_element(&body, "Code").is_some() {
+            return Err(archive_error(format!(
+                "failed to complete an upload: {body}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ObjectWriter for S3ObjectWriter {
+    async fn write(&mut self, chunk: Vec<u8>) -> Result<(), ViewError> {
+        self.buffer.extend(chunk);
+        if self.buffer.len() >= PART_SIZE {
+            self.upload_part().await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> Result<(), ViewError> {
+        if self.upload_id.is_none() {
+            let part = std::mem::take(&mut self.buffer);
+            self.store
+                .send_successfully(Method::PUT, &self.url, part)
+                .await?;
+            return Ok(());
+        }
+        let result = self.complete_upload().await;
+        if result.is_err() {
+            let upload_id = self.upload_id.as_deref().expect("the upload was started");
+            if let Err(error) = self.store.abort_upload(&self.url, upload_id).await {
+                tracing::warn!(%error, url = %self.url, "Failed to abort an upload");
+            }
+        }
+        result
+    }
+}
+
+/// Returns the text of the first element with the given name in an XML document.
+fn xml_element<'a>(document: &'a str, name: &str) -> Option<&'a str> {
+    let start = document.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + document[start..].find(&format!("</{name}>"))?;
+    Some(&document[start..end])
+}
+
+/// Encodes a value of a query parameter, as S3 expects in the signed URL.
+fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn request_error(error: reqwest::Error) -> ViewError {
+    archive_error(format!("request to S3 failed: {error}"))
+}
+
+/// Returns the error for an unsuccessful response.
+async fn response_error(response: Response) -> ViewError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    archive_error(format!("request to S3 failed with {status}: {body}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{uri_encode, xml_element};
+
+    #[test]
+    fn test_xml_element() {
+        let body = "<?xml version=\"1.0\"?><InitiateMultipartUploadResult><Bucket>b</Bucket>\
+                    <UploadId>VXBsb2FkIElE</UploadId></InitiateMultipartUploadResult>";
+        assert_eq!(xml_element(body, "UploadId"), Some("VXBsb2FkIElE"));
+        assert_eq!(xml_element(body, "Code"), None);
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("a+b/c=~_-."), "a%2Bb%2Fc%3D~_-.");
+    }
+}
@@ -3,17 +3,26 @@
 
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
+use futures::{lock::Mutex, TryStreamExt as _};
 use linera_base::{
-    crypto::CryptoHash,
-    data_types::{Blob, Epoch, TimeDelta, Timestamp},
-    identifiers::{ApplicationId, BlobId, ChainId, EventId},
+    crypto::{CryptoHash, ValidatorPublicKey},
+    data_types::{Blob, BlockHeight, Epoch, TimeDelta, Timestamp},
+    ensure,
+    identifiers::{ApplicationId, BlobId, ChainId, EventId, StreamId, StreamName},
 };
 use linera_chain::{
-    types::{CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, LiteCertificate},
+    data_types::EquivocationEvidence,
+    types::{
+        BlockHeader, CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, LiteCertificate,
+    },
     ChainStateView,
 };
 use linera_execution::{
@@ -23,7 +32,10 @@ use linera_views::{
     backends::dual::{DualStoreRootKeyAssignment, StoreInUse},
     batch::Batch,
     context::ViewContext,
-    store::{AdminKeyValueStore, KeyIterable as _, KeyValueStore},
+    store::{
+        AdminKeyValueStore, KeyIterable as _, KeyValueIterable as _, KeyValueStore,
+        ReadableKeyValueStore as _, WritableKeyValueStore as _,
+    },
     views::{View, ViewError},
 };
 use serde::{Deserialize, Serialize};
@@ -31,7 +43,7 @@ use serde::{Deserialize, Serialize};
 use {
     futures::channel::oneshot::{self, Receiver},
     linera_views::{random::generate_test_namespace, store::TestKeyValueStore},
-    std::{cmp::Reverse, collections::BTreeMap},
+    std::cmp::Reverse,
 };
 #[cfg(with_metrics)]
 use {
@@ -42,7 +54,11 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::{ChainRuntimeContext, Clock, NetworkDescription, Storage};
+use crate::{
+    write_pressure::{WritePressure, WritePressureStore},
+    ArchiveStore, ChainRuntimeContext, ChainStorageUsage, Clock, NetworkDescription, ObjectStream,
+    ObjectWriter, Storage, WritePressureConfig,
+};
 
 /// The metric counting how often a blob is tested for existence from storage
 #[cfg(with_metrics)]
@@ -95,6 +111,17 @@ pub static READ_CONFIRMED_BLOCK_COUNTER: LazyLock<IntCounterVec> = LazyLock::new
     )
 });
 
+/// The metric counting how often a confirmed block's body is pruned from storage.
+#[cfg(with_metrics)]
+#[doc(hidden)]
+pub static PRUNE_CONFIRMED_BLOCK_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "prune_confirmed_block",
+        "The metric counting how often a confirmed block's body is pruned from storage",
+        &[],
+    )
+});
+
 /// The metric counting how often a blob is read from storage.
 #[cfg(with_metrics)]
 #[doc(hidden)]
@@ -238,6 +265,34 @@ pub static WRITE_NETWORK_DESCRIPTION: LazyLock<IntCounterVec> = LazyLock::new(||
     )
 });
 
+/// The metric counting the bytes of certificates and blobs attributed to chains.
+#[cfg(with_metrics)]
+static CHAIN_STORED_BYTES_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "chain_stored_bytes",
+        "The metric counting the bytes of certificates and blobs stored on behalf of chains",
+        &["kind"],
+    )
+});
+
+#[cfg(with_metrics)]
+static ARCHIVE_CHAIN_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "archive_chain",
+        "The metric counting how often a chain is moved to the archive store",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static RESTORE_CHAIN_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "restore_chain",
+        "The metric counting how often a chain is restored from the archive store",
+        &[],
+    )
+});
+
 trait BatchExt {
     fn add_blob(&mut self, blob: &Blob) -> Result<(), ViewError>;
 
@@ -286,6 +341,15 @@ impl BatchExt for Batch {
     fn add_event(&mut self, event_id: EventId, value: Vec<u8>) -> Result<(), ViewError> {
         #[cfg(with_metrics)]
         WRITE_EVENT_COUNTER.with_label_values(&[]).inc();
+        if let Some(application_id) = event_id.stream_id.application_id.user_application_id() {
+            let index_key = bcs::to_bytes(&BaseKey::ApplicationEvent(
+                *application_id,
+                event_id.chain_id,
+                event_id.stream_id.stream_name.clone(),
+                event_id.index,
+            ))?;
+            self.put_key_value_bytes(index_key, Vec::new());
+        }
         let event_key = bcs::to_bytes(&BaseKey::Event(event_id))?;
         self.put_key_value_bytes(event_key.to_vec(), value);
         Ok(())
@@ -312,6 +376,103 @@ pub struct DbStorage<Store, Clock = WallClock> {
     user_contracts: Arc<DashMap<ApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<ApplicationId, UserServiceCode>>,
     execution_runtime_config: ExecutionRuntimeConfig,
+    archive_store: Option<Arc<dyn ArchiveStore>>,
+    /// Held while chains are archived or restored, shared by all the clones.
+    archive_lock: Arc<Mutex<()>>,
+    /// The writes in flight and their latency, shared by all the clones.
+    write_pressure: Arc<WritePressure>,
+}
+
+/// The counters of the bytes stored on behalf of a chain, maintained as they are written.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+struct StorageUsageCounters {
+    certificate_bytes: u64,
+    blob_bytes: u64,
+}
+
+/// An entry of a chain's archive. An archive is a sequence of entries, each one serialized
+/// after its length as a little-endian `u32`, so that it can be read in chunks.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum ArchiveEntry {
+    /// A key-value pair of the chain's state.
+    State(Vec<u8>, Vec<u8>),
+    /// A key-value pair outside of the chain's state: a certificate, block or header of the
+    /// chain, or a blob and its state.
+    Base(Vec<u8>, Vec<u8>),
+}
+
+/// Writes the entries of an archive to an object, in chunks.
+struct ArchiveWriter {
+    writer: Box<dyn ObjectWriter>,
+    chunk: Vec<u8>,
+}
+
+impl ArchiveWriter {
+    fn new(writer: Box<dyn ObjectWriter>) -> Self {
+        Self {
+            writer,
+            chunk: Vec::new(),
+        }
+    }
+
+    async fn write(&mut self, entry: &ArchiveEntry) -> Result<(), ViewError> {
+        let bytes = bcs::to_bytes(entry)?;
+        let length = u32::try_from(bytes.len()).map_err(|_| ViewError::TooLargeValue)?;
+        self.chunk.extend(length.to_le_bytes());
+        self.chunk.extend(bytes);
+        if self.chunk.len() >= ARCHIVE_CHUNK_SIZE {
+            self.writer.write(std::mem::take(&mut self.chunk)).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<(), ViewError> {
+        if !self.chunk.is_empty() {
+            self.writer.write(self.chunk).await?;
+        }
+        self.writer.finish().await
+    }
+}
+
+/// Reads the entries of an archive from the chunks of an object.
+struct ArchiveReader {
+    chunks: ObjectStream,
+    buffer: Vec<u8>,
+    /// The position of the next entry in the buffer.
+    position: usize,
+}
+
+impl ArchiveReader {
+    fn new(chunks: ObjectStream) -> Self {
+        Self {
+            chunks,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Returns the next entry, or `None` at the end of the archive.
+    async fn next(&mut self) -> Result<Option<ArchiveEntry>, ViewError> {
+        let length = loop {
+            let remaining = &self.buffer[self.position..];
+            if let Some(length) = remaining.get(..4) {
+                let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+                if remaining.len() >= 4 + length {
+                    break length;
+                }
+            }
+            let Some(chunk) = self.chunks.try_next().await? else {
+                ensure!(remaining.is_empty(), ViewError::MissingEntries);
+                return Ok(None);
+            };
+            self.buffer.drain(..self.position);
+            self.position = 0;
+            self.buffer.extend(chunk);
+        };
+        let start = self.position + 4;
+        self.position = start + length;
+        Ok(Some(bcs::from_bytes(&self.buffer[start..self.position])?))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -324,24 +485,85 @@ enum BaseKey {
     Event(EventId),
     BlockExporterState(u32),
     NetworkDescription,
+    BlockHeader(CryptoHash),
+    PendingDelivery(ChainId),
+    EquivocationEvidence(ChainId, BlockHeight, ValidatorPublicKey),
+    /// The index of the events emitted by an application on a chain. The values are empty.
+    ApplicationEvent(ApplicationId, ChainId, StreamName, u32),
+    /// The marker of a chain moved to the archive store. The values are empty.
+    ArchivedChain(ChainId),
+    /// The marker of a certificate moved to the archive store, with its block and header.
+    /// The values are the IDs of the chains whose archive has them.
+    ArchivedCertificate(CryptoHash),
+    /// The marker of a blob moved to the archive store, with its state. The values are the
+    /// IDs of the chains whose archive has them.
+    ArchivedBlob(BlobId),
+    /// The counters of the bytes stored on behalf of a chain.
+    StorageUsage(ChainId),
+    /// The addresses of the validator's shards.
+    ShardAddresses,
 }
 
 const INDEX_CHAIN_ID: u8 = 0;
 const INDEX_BLOB_ID: u8 = 3;
+const INDEX_PENDING_DELIVERY: u8 = 9;
+const INDEX_EQUIVOCATION_EVIDENCE: u8 = 10;
+const INDEX_APPLICATION_EVENT: u8 = 11;
+#[cfg(test)]
+const INDEX_ARCHIVED_CHAIN: u8 = 12;
+#[cfg(test)]
+const INDEX_STORAGE_USAGE: u8 = 15;
 const CHAIN_ID_LENGTH: usize = std::mem::size_of::<ChainId>();
 const BLOB_ID_LENGTH: usize = std::mem::size_of::<BlobId>();
+/// The number of keys read or written at once when a chain is archived or restored.
+const ARCHIVE_BATCH_SIZE: usize = 1000;
+/// The number of bytes of a chain's archive written to the archive store at once.
+const ARCHIVE_CHUNK_SIZE: usize = 1 << 20;
 
 #[cfg(test)]
 mod tests {
     use linera_base::{
         crypto::CryptoHash,
-        identifiers::{BlobId, BlobType, ChainId},
+        data_types::{Amount, Blob, BlockHeight, Epoch, Round, Timestamp},
+        identifiers::{ApplicationId, BlobId, BlobType, ChainId, StreamName},
+        time::Duration,
     };
+    use linera_chain::{
+        data_types::{BlockExecutionOutcome, ProposedBlock},
+        types::{ConfirmedBlock, ConfirmedBlockCertificate},
+    };
+    use std::sync::Arc;
 
-    use crate::db_storage::{
-        BaseKey, BLOB_ID_LENGTH, CHAIN_ID_LENGTH, INDEX_BLOB_ID, INDEX_CHAIN_ID,
+    use linera_execution::{test_utils::dummy_chain_description, BlobState};
+    use linera_views::{
+        memory::MemoryStore, store::ReadableKeyValueStore as _, views::RootView as _,
     };
 
+    use crate::{
+        db_storage::{
+            ArchiveEntry, ArchiveReader, ArchiveWriter, BaseKey, DbStorage, TestClock,
+            BLOB_ID_LENGTH, CHAIN_ID_LENGTH, INDEX_APPLICATION_EVENT, INDEX_ARCHIVED_CHAIN,
+            INDEX_BLOB_ID, INDEX_CHAIN_ID, INDEX_PENDING_DELIVERY, INDEX_STORAGE_USAGE,
+        },
+        ArchiveStore, MemoryArchiveStore, Storage, WritePressureConfig,
+    };
+
+    /// Returns a certificate for the first block of the chain.
+    fn make_certificate(chain_id: ChainId) -> ConfirmedBlockCertificate {
+        let block = ProposedBlock {
+            epoch: Epoch::ZERO,
+            chain_id,
+            incoming_bundles: vec![],
+            operations: vec![],
+            previous_block_hash: None,
+            height: BlockHeight::ZERO,
+            authenticated_signer: None,
+            timestamp: Timestamp::default(),
+        };
+        let value = ConfirmedBlock::new(BlockExecutionOutcome::default().with(block));
+        ConfirmedBlockCertificate::new(value, Round::Fast, vec![])
+    }
+
     #[test]
     fn test_base_key_serialization() {
         let hash = CryptoHash::default();
@@ -362,6 +584,233 @@ mod tests {
         assert_eq!(key[0], INDEX_CHAIN_ID);
         assert_eq!(key.len(), 1 + CHAIN_ID_LENGTH);
     }
+
+    #[test]
+    fn test_pending_delivery_serialization() {
+        let chain_id = ChainId(CryptoHash::default());
+        let base_key = BaseKey::PendingDelivery(chain_id);
+        let key = bcs::to_bytes(&base_key).expect("a key");
+        assert_eq!(key[0], INDEX_PENDING_DELIVERY);
+        assert_eq!(key.len(), 1 + CHAIN_ID_LENGTH);
+    }
+
+    #[test]
+    fn test_application_event_serialization() {
+        let application_id = ApplicationId::new(CryptoHash::default());
+        let chain_id = ChainId(CryptoHash::default());
+        let stream_name = StreamName(b"stream".to_vec());
+        let base_key = BaseKey::ApplicationEvent(application_id, chain_id, stream_name.clone(), 7);
+        let key = bcs::to_bytes(&base_key).expect("a key");
+        let mut prefix = vec![INDEX_APPLICATION_EVENT];
+        prefix.extend(bcs::to_bytes(&application_id).unwrap());
+        prefix.extend(bcs::to_bytes(&chain_id).unwrap());
+        assert!(key.starts_with(&prefix));
+        let suffix = bcs::from_bytes::<(StreamName, u32)>(&key[prefix.len()..]).unwrap();
+        assert_eq!(suffix, (stream_name, 7));
+    }
+
+    #[test]
+    fn test_archived_chain_serialization() {
+        let chain_id = ChainId(CryptoHash::default());
+        let base_key = BaseKey::ArchivedChain(chain_id);
+        let key = bcs::to_bytes(&base_key).expect("a key");
+        assert_eq!(key[0], INDEX_ARCHIVED_CHAIN);
+        assert_eq!(key.len(), 1 + CHAIN_ID_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_archive_entries_round_trip() -> anyhow::Result<()> {
+        let archive_store = MemoryArchiveStore::default();
+        let entries = vec![
+            ArchiveEntry::State(vec![1, 2], vec![3; 20]),
+            ArchiveEntry::Base(vec![4], Vec::new()),
+            ArchiveEntry::State(Vec::new(), vec![5]),
+        ];
+        let mut writer = ArchiveWriter::new(archive_store.create_object("archive").await?);
+        for entry in &entries {
+            writer.write(entry).await?;
+        }
+        writer.finish().await?;
+
+        // The memory store returns the object in small chunks, which split the entries.
+        let chunks = archive_store.read_object("archive").await?.unwrap();
+        let mut reader = ArchiveReader::new(chunks);
+        let mut read_entries = Vec::new();
+        while let Some(entry) = reader.next().await? {
+            read_entries.push(entry);
+        }
+        assert_eq!(read_entries, entries);
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_usage_serialization() {
+        let chain_id = ChainId(CryptoHash::default());
+        let base_key = BaseKey::StorageUsage(chain_id);
+        let key = bcs::to_bytes(&base_key).expect("a key");
+        assert_eq!(key[0], INDEX_STORAGE_USAGE);
+        assert_eq!(key.len(), 1 + CHAIN_ID_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_chain_storage_usage() -> anyhow::Result<()> {
+        let storage = DbStorage::<MemoryStore, TestClock>::make_test_storage(None).await;
+        let description = dummy_chain_description(0);
+        let chain_id = description.id();
+        storage.create_chain(description).await?;
+        let usage = storage.chain_storage_usage(chain_id).await?;
+        assert!(usage.state_bytes > 0);
+        assert_eq!((usage.certificate_bytes, usage.blob_bytes), (0, 0));
+
+        let certificate = make_certificate(chain_id);
+        let blob = Blob::new_data(b"blob".to_vec());
+        storage
+            .write_blobs_and_certificate(&[blob.clone()], &certificate)
+            .await?;
+        let usage = storage.chain_storage_usage(chain_id).await?;
+        assert!(usage.certificate_bytes > 0);
+        assert_eq!(usage.blob_bytes, 4);
+
+        // Writing the same data again doesn't count it twice.
+        storage
+            .write_blobs_and_certificate(&[blob], &certificate)
+            .await?;
+        assert_eq!(storage.chain_storage_usage(chain_id).await?, usage);
+
+        // Pruned blocks only count their header, which is stored in full.
+        storage
+            .prune_confirmed_blocks(&[certificate.hash()])
+            .await?;
+        let pruned_usage = storage.chain_storage_usage(chain_id).await?;
+        let header = &certificate.block().header;
+        assert_eq!(
+            pruned_usage.certificate_bytes,
+            usage.certificate_bytes - bcs::serialized_size(certificate.value())? as u64
+                + bcs::serialized_size(header)? as u64
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_restore_chain() -> anyhow::Result<()> {
+        let archive_store = Arc::new(MemoryArchiveStore::default());
+        let storage = DbStorage::<MemoryStore, TestClock>::make_test_storage(None)
+            .await
+            .with_archive_store(Some(archive_store.clone()));
+        let description = dummy_chain_description(0);
+        let chain_id = description.id();
+        storage.create_chain(description).await?;
+        let certificate = make_certificate(chain_id);
+        let hash = certificate.hash();
+        storage
+            .write_blobs_and_certificate(&[], &certificate)
+            .await?;
+        // The first block uses the chain's description blob.
+        let blob_id = BlobId::new(chain_id.0, BlobType::ChainDescription);
+        let blob_state = |chain_id| BlobState {
+            last_used_by: hash,
+            chain_id,
+            block_height: BlockHeight::ZERO,
+            epoch: Epoch::ZERO,
+        };
+        storage
+            .write_blob_state(blob_id, &blob_state(chain_id))
+            .await?;
+        let blob = storage.read_blob(blob_id).await?;
+        let mut chain = storage.load_chain(chain_id).await?;
+        chain.confirmed_log.push(hash);
+        chain.save().await?;
+        let balance = *chain.execution_state.system.balance.get();
+        drop(chain);
+        let marker_key = bcs::to_bytes(&BaseKey::ArchivedChain(chain_id))?;
+        let archive_key = DbStorage::<MemoryStore, TestClock>::archive_key(chain_id);
+        let archived_keys = [
+            BaseKey::Certificate(hash),
+            BaseKey::ConfirmedBlock(hash),
+            BaseKey::Blob(blob_id),
+            BaseKey::BlobState(blob_id),
+        ]
+        .iter()
+        .map(bcs::to_bytes)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        // Archiving leaves a marker, and removes the state, the certificate and the blob.
+        storage.archive_chain(chain_id).await?;
+        assert!(storage.store.contains_key(&marker_key).await?);
+        assert!(archive_store.contains_object(&archive_key));
+        assert!(!storage.load_chain_state(chain_id).await?.is_active());
+        for key in &archived_keys {
+            assert!(!storage.store.contains_key(key).await?);
+        }
+        // An archived chain can't be archived again.
+        assert!(storage.archive_chain(chain_id).await.is_err());
+
+        // Reading an archived certificate restores the chain.
+        assert_eq!(storage.read_certificate(hash).await?, certificate);
+        assert!(!storage.store.contains_key(&marker_key).await?);
+        assert!(!archive_store.contains_object(&archive_key));
+        let chain = storage.load_chain_state(chain_id).await?;
+        assert_eq!(*chain.execution_state.system.balance.get(), balance);
+        assert_eq!(storage.read_blob(blob_id).await?, blob);
+        assert_eq!(
+            storage.read_blob_state(blob_id).await?,
+            blob_state(chain_id)
+        );
+        assert!(!storage.restore_chain(chain_id).await?);
+
+        // Blobs that other chains used since are kept. Reading an archived blob also
+        // restores the chain.
+        let other_chain_id = dummy_chain_description(1).id();
+        storage
+            .write_blob_state(blob_id, &blob_state(other_chain_id))
+            .await?;
+        storage.archive_chain(chain_id).await?;
+        assert!(storage.store.contains_key(&archived_keys[2]).await?);
+        storage
+            .write_blob_state(blob_id, &blob_state(chain_id))
+            .await?;
+        storage.restore_chain(chain_id).await?;
+        storage.archive_chain(chain_id).await?;
+        assert!(!storage.store.contains_key(&archived_keys[2]).await?);
+        assert_eq!(storage.read_blob(blob_id).await?, blob);
+        assert!(!storage.store.contains_key(&marker_key).await?);
+
+        // If the archival was interrupted while the entries were removed, or they were
+        // written again since, loading the chain restores the others.
+        storage.archive_chain(chain_id).await?;
+        storage
+            .write_blobs_and_certificate(&[], &certificate)
+            .await?;
+        let chain = storage.load_chain(chain_id).await?;
+        assert_eq!(*chain.execution_state.system.balance.get(), balance);
+        assert_eq!(storage.read_certificate(hash).await?, certificate);
+        assert_eq!(storage.read_blob(blob_id).await?, blob);
+        assert!(!storage.store.contains_key(&marker_key).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_state_writes_count_towards_write_pressure() -> anyhow::Result<()> {
+        let storage = DbStorage::<MemoryStore, TestClock>::make_test_storage(None).await;
+        let description = dummy_chain_description(0);
+        let chain_id = description.id();
+        storage.create_chain(description).await?;
+        let storage = storage.with_write_pressure_config(WritePressureConfig {
+            max_pending_writes: 256,
+            saturated_write_latency: Duration::from_nanos(1),
+        });
+        assert_eq!(storage.write_pressure(), 0.0);
+
+        let mut chain = storage.load_chain(chain_id).await?;
+        chain
+            .execution_state
+            .system
+            .balance
+            .set(Amount::from_tokens(1));
+        chain.save().await?;
+        assert_eq!(storage.write_pressure(), 1.0);
+        Ok(())
+    }
 }
 
 /// An implementation of [`DualStoreRootKeyAssignment`] that stores the
@@ -502,31 +951,30 @@ where
     C: Clock + Clone + Send + Sync + 'static,
     Store::Error: Send + Sync,
 {
-    type Context = ViewContext<ChainRuntimeContext<Self>, Store>;
+    type Context = ViewContext<ChainRuntimeContext<Self>, WritePressureStore<Store>>;
     type Clock = C;
-    type BlockExporterContext = ViewContext<u32, Store>;
+    type BlockExporterContext = ViewContext<u32, WritePressureStore<Store>>;
 
     fn clock(&self) -> &C {
         &self.clock
     }
 
+    /// The score is based on the writes of certificates, blobs, events and the other
+    /// entries outside of chain states, which every block makes.
+    fn write_pressure(&self) -> f64 {
+        self.write_pressure.score()
+    }
+
     async fn load_chain(
         &self,
         chain_id: ChainId,
     ) -> Result<ChainStateView<Self::Context>, ViewError> {
         #[cfg(with_metrics)]
         let _metric = LOAD_CHAIN_LATENCY.measure_latency();
-        let runtime_context = ChainRuntimeContext {
-            storage: self.clone(),
-            chain_id,
-            execution_runtime_config: self.execution_runtime_config,
-            user_contracts: self.user_contracts.clone(),
-            user_services: self.user_services.clone(),
-        };
-        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
-        let store = self.store.clone_with_root_key(&root_key)?;
-        let context = ViewContext::create_root_context(store, runtime_context).await?;
-        ChainStateView::load(context).await
+        // The state of an archived chain may still be partly in this storage, if the
+        // archival was interrupted, so the chain is restored first.
+        self.restore_chain(chain_id).await?;
+        self.load_chain_state(chain_id).await
     }
 
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError> {
@@ -534,6 +982,9 @@ where
         let test = self.store.contains_key(&blob_key).await?;
         #[cfg(with_metrics)]
         CONTAINS_BLOB_COUNTER.with_label_values(&[]).inc();
+        if !test && self.restore_blobs([&blob_id]).await? {
+            return self.contains_blob(blob_id).await;
+        }
         Ok(test)
     }
 
@@ -552,6 +1003,9 @@ where
         }
         #[cfg(with_metrics)]
         CONTAINS_BLOBS_COUNTER.with_label_values(&[]).inc();
+        if self.restore_blobs(&missing_blobs).await? {
+            return self.missing_blobs(&missing_blobs).await;
+        }
         Ok(missing_blobs)
     }
 
@@ -560,6 +1014,9 @@ where
         let test = self.store.contains_key(&blob_key).await?;
         #[cfg(with_metrics)]
         CONTAINS_BLOB_STATE_COUNTER.with_label_values(&[]).inc();
+        if !test && self.restore_blobs([&blob_id]).await? {
+            return self.contains_blob_state(blob_id).await;
+        }
         Ok(test)
     }
 
@@ -568,15 +1025,37 @@ where
         let maybe_value = self.store.read_value::<ConfirmedBlock>(&block_key).await?;
         #[cfg(with_metrics)]
         READ_CONFIRMED_BLOCK_COUNTER.with_label_values(&[]).inc();
-        let value = maybe_value.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
+        let Some(value) = maybe_value else {
+            if self.restore_certificates([&hash]).await? {
+                return self.read_confirmed_block(hash).await;
+            }
+            self.check_not_pruned(hash).await?;
+            return Err(ViewError::not_found("value for hash", hash));
+        };
         Ok(value)
     }
 
+    async fn read_block_header(&self, hash: CryptoHash) -> Result<BlockHeader, ViewError> {
+        let block_key = bcs::to_bytes(&BaseKey::ConfirmedBlock(hash))?;
+        if let Some(block) = self.store.read_value::<ConfirmedBlock>(&block_key).await? {
+            return Ok(block.into_block().header);
+        }
+        let header_key = bcs::to_bytes(&BaseKey::BlockHeader(hash))?;
+        let maybe_header = self.store.read_value::<BlockHeader>(&header_key).await?;
+        if maybe_header.is_none() && self.restore_certificates([&hash]).await? {
+            return self.read_block_header(hash).await;
+        }
+        maybe_header.ok_or_else(|| ViewError::not_found("block header for hash", hash))
+    }
+
     async fn read_blob(&self, blob_id: BlobId) -> Result<Blob, ViewError> {
         let blob_key = bcs::to_bytes(&BaseKey::Blob(blob_id))?;
         let maybe_blob_bytes = self.store.read_value_bytes(&blob_key).await?;
         #[cfg(with_metrics)]
         READ_BLOB_COUNTER.with_label_values(&[]).inc();
+        if maybe_blob_bytes.is_none() && self.restore_blobs([&blob_id]).await? {
+            return self.read_blob(blob_id).await;
+        }
         let blob_bytes = maybe_blob_bytes.ok_or_else(|| ViewError::BlobsNotFound(vec![blob_id]))?;
         Ok(Blob::new_with_id_unchecked(blob_id, blob_bytes))
     }
@@ -594,6 +1073,13 @@ where
         READ_BLOB_COUNTER
             .with_label_values(&[])
             .inc_by(blob_ids.len() as u64);
+        let missing = blob_ids
+            .iter()
+            .zip(&maybe_blob_bytes)
+            .filter_map(|(blob_id, bytes)| bytes.is_none().then_some(blob_id));
+        if self.restore_blobs(missing).await? {
+            return self.read_blobs(blob_ids).await;
+        }
 
         Ok(blob_ids
             .iter()
@@ -609,6 +1095,9 @@ where
         let maybe_blob_state = self.store.read_value::<BlobState>(&blob_state_key).await?;
         #[cfg(with_metrics)]
         READ_BLOB_STATE_COUNTER.with_label_values(&[]).inc();
+        if maybe_blob_state.is_none() && self.restore_blobs([&blob_id]).await? {
+            return self.read_blob_state(blob_id).await;
+        }
         let blob_state = maybe_blob_state
             .ok_or_else(|| ViewError::not_found("blob state for blob ID", blob_id))?;
         Ok(blob_state)
@@ -628,6 +1117,13 @@ where
             .await?;
         #[cfg(with_metrics)]
         READ_BLOB_STATES_COUNTER.with_label_values(&[]).inc();
+        let missing = blob_ids
+            .iter()
+            .zip(&maybe_blob_states)
+            .filter_map(|(blob_id, state)| state.is_none().then_some(blob_id));
+        if self.restore_blobs(missing).await? {
+            return self.read_blob_states(blob_ids).await;
+        }
         let blob_states = maybe_blob_states
             .into_iter()
             .zip(blob_ids)
@@ -702,6 +1198,15 @@ where
             .store
             .read_multi_values::<BlobState>(blob_state_keys)
             .await?;
+        let missing = blob_ids
+            .iter()
+            .zip(&maybe_blob_states)
+            .filter_map(|(blob_id, state)| state.is_none().then_some(blob_id));
+        if self.restore_blobs(missing).await? {
+            return self
+                .maybe_write_blob_states(blob_ids, blob_state, overwrite)
+                .await;
+        }
         let mut latest_epochs = Vec::new();
         let mut batch = Batch::new();
         let mut need_write = false;
@@ -745,6 +1250,15 @@ where
             .map(|blob| bcs::to_bytes(&BaseKey::BlobState(blob.id())))
             .collect::<Result<_, _>>()?;
         let blob_states = self.store.contains_keys(blob_state_keys).await?;
+        let missing = blobs
+            .iter()
+            .zip(&blob_states)
+            .filter(|(_, has_state)| !**has_state)
+            .map(|(blob, _)| blob.id())
+            .collect::<Vec<_>>();
+        if self.restore_blobs(&missing).await? {
+            return self.maybe_write_blobs(blobs).await;
+        }
         let mut batch = Batch::new();
         for (blob, has_state) in blobs.iter().zip(&blob_states) {
             if *has_state {
@@ -766,16 +1280,94 @@ where
         self.write_batch(batch).await
     }
 
+    async fn prune_confirmed_blocks(&self, hashes: &[CryptoHash]) -> Result<(), ViewError> {
+        let block_keys = hashes
+            .iter()
+            .map(|hash| bcs::to_bytes(&BaseKey::ConfirmedBlock(*hash)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let blocks = self
+            .store
+            .read_multi_values::<ConfirmedBlock>(block_keys.clone())
+            .await?;
+        let mut batch = Batch::new();
+        // The bytes of the removed blocks and of the added headers, for each chain.
+        let mut pruned_bytes = BTreeMap::<ChainId, (u64, u64)>::new();
+        for ((hash, block_key), maybe_block) in hashes.iter().zip(block_keys).zip(blocks) {
+            let Some(block) = maybe_block else {
+                continue;
+            };
+            #[cfg(with_metrics)]
+            PRUNE_CONFIRMED_BLOCK_COUNTER.with_label_values(&[]).inc();
+            let header = &block.block().header;
+            let header_key = bcs::to_bytes(&BaseKey::BlockHeader(*hash))?;
+            batch.put_key_value(header_key, header)?;
+            batch.delete_key(block_key);
+            let (removed, added) = pruned_bytes.entry(header.chain_id).or_default();
+            *removed += bcs::serialized_size(&block)? as u64;
+            *added += bcs::serialized_size(header)? as u64;
+        }
+        for (chain_id, (removed, added)) in pruned_bytes {
+            self.update_storage_usage(&mut batch, chain_id, |counters| {
+                counters.certificate_bytes = counters
+                    .certificate_bytes
+                    .saturating_sub(removed)
+                    .saturating_add(added);
+            })
+            .await?;
+        }
+        self.write_batch(batch).await
+    }
+
     async fn write_blobs_and_certificate(
         &self,
         blobs: &[Blob],
         certificate: &ConfirmedBlockCertificate,
     ) -> Result<(), ViewError> {
+        let blob_keys = blobs
+            .iter()
+            .map(|blob| bcs::to_bytes(&BaseKey::Blob(blob.id())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let existing_blobs = self.store.contains_keys(blob_keys).await?;
+        // Archived certificates are not restored just to be written again.
+        let certificate_keys = Self::get_keys_for_certificates(&[certificate.hash()])?;
+        let certificate_exists = !self
+            .store
+            .contains_keys(certificate_keys)
+            .await?
+            .contains(&false);
         let mut batch = Batch::new();
-        for blob in blobs {
+        let mut blob_bytes = 0;
+        for (blob, exists) in blobs.iter().zip(existing_blobs) {
+            if !exists {
+                blob_bytes += blob.bytes().len() as u64;
+            }
             batch.add_blob(blob)?;
         }
         batch.add_certificate(certificate)?;
+        let certificate_bytes = if certificate_exists {
+            0
+        } else {
+            (bcs::serialized_size(&certificate.lite_certificate())?
+                + bcs::serialized_size(certificate.value())?) as u64
+        };
+        if certificate_bytes > 0 || blob_bytes > 0 {
+            #[cfg(with_metrics)]
+            {
+                CHAIN_STORED_BYTES_COUNTER
+                    .with_label_values(&["certificate"])
+                    .inc_by(certificate_bytes);
+                CHAIN_STORED_BYTES_COUNTER
+                    .with_label_values(&["blob"])
+                    .inc_by(blob_bytes);
+            }
+            let chain_id = certificate.block().header.chain_id;
+            self.update_storage_usage(&mut batch, chain_id, |counters| {
+                counters.certificate_bytes =
+                    counters.certificate_bytes.saturating_add(certificate_bytes);
+                counters.blob_bytes = counters.blob_bytes.saturating_add(blob_bytes);
+            })
+            .await?;
+        }
         self.write_batch(batch).await
     }
 
@@ -784,6 +1376,9 @@ where
         let results = self.store.contains_keys(keys).await?;
         #[cfg(with_metrics)]
         CONTAINS_CERTIFICATE_COUNTER.with_label_values(&[]).inc();
+        if !results[0] && self.restore_certificates([&hash]).await? {
+            return self.contains_certificate(hash).await;
+        }
         Ok(results[0] && results[1])
     }
 
@@ -798,9 +1393,27 @@ where
             READ_CERTIFICATE_COUNTER.with_label_values(&[]).inc();
         }
         let values = values?;
+        if values[0].is_none() && self.restore_certificates([&hash]).await? {
+            return self.read_certificate(hash).await;
+        }
+        if values[0].is_some() && values[1].is_none() {
+            self.check_not_pruned(hash).await?;
+        }
         Self::deserialize_certificate(&values, hash)
     }
 
+    async fn read_lite_certificate(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<LiteCertificate<'static>, ViewError> {
+        let cert_key = bcs::to_bytes(&BaseKey::Certificate(hash))?;
+        let maybe_certificate = self.store.read_value::<LiteCertificate>(&cert_key).await?;
+        if maybe_certificate.is_none() && self.restore_certificates([&hash]).await? {
+            return self.read_lite_certificate(hash).await;
+        }
+        maybe_certificate.ok_or_else(|| ViewError::not_found("certificate for hash", hash))
+    }
+
     async fn read_certificates<I: IntoIterator<Item = CryptoHash> + Send>(
         &self,
         hashes: I,
@@ -816,8 +1429,18 @@ where
             READ_CERTIFICATES_COUNTER.with_label_values(&[]).inc();
         }
         let values = values?;
+        let missing = values
+            .chunks_exact(2)
+            .zip(&hashes)
+            .filter_map(|(pair, hash)| pair[0].is_none().then_some(hash));
+        if self.restore_certificates(missing).await? {
+            return self.read_certificates(hashes).await;
+        }
         let mut certificates = Vec::new();
         for (pair, hash) in values.chunks_exact(2).zip(hashes) {
+            if pair[0].is_some() && pair[1].is_none() {
+                self.check_not_pruned(hash).await?;
+            }
             let certificate = Self::deserialize_certificate(pair, hash)?;
             certificates.push(certificate);
         }
@@ -851,6 +1474,57 @@ where
         self.write_batch(batch).await
     }
 
+    async fn read_events_by_application(
+        &self,
+        application_id: ApplicationId,
+        chain_id: ChainId,
+    ) -> Result<Vec<(EventId, Vec<u8>)>, ViewError> {
+        let mut prefix = vec![INDEX_APPLICATION_EVENT];
+        prefix.extend(bcs::to_bytes(&application_id)?);
+        prefix.extend(bcs::to_bytes(&chain_id)?);
+        let mut event_ids = Vec::new();
+        // The keys are dropped before the next await, as they need not be `Send`.
+        {
+            let keys = self.store.find_keys_by_prefix(&prefix).await?;
+            for key in keys.iterator() {
+                let (stream_name, index) = bcs::from_bytes::<(StreamName, u32)>(key?)?;
+                event_ids.push(EventId {
+                    chain_id,
+                    stream_id: StreamId {
+                        application_id: application_id.into(),
+                        stream_name,
+                    },
+                    index,
+                });
+            }
+        }
+        event_ids.sort_by(|id1, id2| {
+            (&id1.stream_id.stream_name, id1.index).cmp(&(&id2.stream_id.stream_name, id2.index))
+        });
+        let event_keys = event_ids
+            .iter()
+            .map(|event_id| bcs::to_bytes(&BaseKey::Event(event_id.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = self.store.read_multi_values_bytes(event_keys).await?;
+        #[cfg(with_metrics)]
+        READ_EVENT_COUNTER
+            .with_label_values(&[])
+            .inc_by(event_ids.len() as u64);
+        let mut events = Vec::new();
+        let mut missing_event_ids = Vec::new();
+        for (event_id, value) in event_ids.into_iter().zip(values) {
+            match value {
+                Some(value) => events.push((event_id, value)),
+                None => missing_event_ids.push(event_id),
+            }
+        }
+        ensure!(
+            missing_event_ids.is_empty(),
+            ViewError::EventsNotFound(missing_event_ids)
+        );
+        Ok(events)
+    }
+
     async fn read_network_description(&self) -> Result<Option<NetworkDescription>, ViewError> {
         let key = bcs::to_bytes(&BaseKey::NetworkDescription)?;
         let maybe_value = self.store.read_value(&key).await?;
@@ -869,6 +1543,337 @@ where
         Ok(())
     }
 
+    async fn read_shard_addresses(&self) -> Result<Option<Vec<(String, u16)>>, ViewError> {
+        let key = bcs::to_bytes(&BaseKey::ShardAddresses)?;
+        Ok(self.store.read_value(&key).await?)
+    }
+
+    async fn write_shard_addresses(&self, addresses: &[(String, u16)]) -> Result<(), ViewError> {
+        let key = bcs::to_bytes(&BaseKey::ShardAddresses)?;
+        let mut batch = Batch::new();
+        batch.put_key_value(key, &addresses)?;
+        self.write_batch(batch).await
+    }
+
+    async fn write_pending_delivery(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let key = bcs::to_bytes(&BaseKey::PendingDelivery(chain_id))?;
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(key, Vec::new());
+        self.write_batch(batch).await
+    }
+
+    async fn remove_pending_delivery(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let key = bcs::to_bytes(&BaseKey::PendingDelivery(chain_id))?;
+        let mut batch = Batch::new();
+        batch.delete_key(key);
+        self.write_batch(batch).await
+    }
+
+    async fn write_equivocation_evidence(
+        &self,
+        evidence: &EquivocationEvidence,
+    ) -> Result<(), ViewError> {
+        let key = bcs::to_bytes(&BaseKey::EquivocationEvidence(
+            evidence.chain_id(),
+            evidence.height,
+            evidence.validator,
+        ))?;
+        let mut batch = Batch::new();
+        batch.put_key_value(key, evidence)?;
+        self.write_batch(batch).await
+    }
+
+    async fn read_equivocation_evidence(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Vec<EquivocationEvidence>, ViewError> {
+        let mut prefix = vec![INDEX_EQUIVOCATION_EVIDENCE];
+        prefix.extend(bcs::to_bytes(&chain_id)?);
+        let key_values = self.store.find_key_values_by_prefix(&prefix).await?;
+        let mut evidence = Vec::new();
+        for key_value in key_values.iterator() {
+            let (_, value) = key_value?;
+            evidence.push(bcs::from_bytes(value)?);
+        }
+        Ok(evidence)
+    }
+
+    async fn read_pending_deliveries(&self) -> Result<Vec<ChainId>, ViewError> {
+        let keys = self
+            .store
+            .find_keys_by_prefix(&[INDEX_PENDING_DELIVERY])
+            .await?;
+        let mut chain_ids = Vec::new();
+        for key in keys.iterator() {
+            chain_ids.push(bcs::from_bytes(key?)?);
+        }
+        Ok(chain_ids)
+    }
+
+    async fn chain_storage_usage(&self, chain_id: ChainId) -> Result<ChainStorageUsage, ViewError> {
+        let counters = self.read_storage_usage(chain_id).await?;
+        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let state_store = self.root_store(&root_key)?;
+        let mut state_bytes = 0u64;
+        for key_value in state_store.find_key_values_by_prefix(&[]).await?.iterator() {
+            let (key, value) = key_value?;
+            state_bytes += (key.len() + value.len()) as u64;
+        }
+        Ok(ChainStorageUsage {
+            state_bytes,
+            certificate_bytes: counters.certificate_bytes,
+            blob_bytes: counters.blob_bytes,
+        })
+    }
+
+    async fn archive_chain(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let archive_store = self.archive_store()?;
+        let _guard = self.archive_lock.lock().await;
+        let archived_key = bcs::to_bytes(&BaseKey::ArchivedChain(chain_id))?;
+        ensure!(
+            !self.store.contains_key(&archived_key).await?,
+            ViewError::not_found("active chain", chain_id)
+        );
+        let chain = self.load_chain_state(chain_id).await?;
+        ensure!(
+            chain.is_active(),
+            ViewError::not_found("active chain", chain_id)
+        );
+        let hashes = chain.confirmed_log.read(..).await?;
+        drop(chain);
+
+        // The entries are read in batches and written to the archive as they are read, so
+        // that only their keys are kept in memory.
+        let mut archive = ArchiveWriter::new(
+            archive_store
+                .create_object(&Self::archive_key(chain_id))
+                .await?,
+        );
+        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let state_store = self.root_store(&root_key)?;
+        let state_keys = state_store
+            .find_keys_by_prefix(&[])
+            .await?
+            .iterator()
+            .map(|key| key.map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, _>>()?;
+        for keys in state_keys.chunks(ARCHIVE_BATCH_SIZE) {
+            let values = state_store.read_multi_values_bytes(keys.to_vec()).await?;
+            for (key, value) in keys.iter().zip(values) {
+                if let Some(value) = value {
+                    archive
+                        .write(&ArchiveEntry::State(key.clone(), value))
+                        .await?;
+                }
+            }
+        }
+
+        let mut base_keys = Vec::new();
+        let mut markers = BTreeSet::new();
+        let mut blob_ids = BTreeSet::new();
+        for hashes in hashes.chunks(ARCHIVE_BATCH_SIZE) {
+            let keys = hashes
+                .iter()
+                .flat_map(|hash| {
+                    [
+                        BaseKey::Certificate(*hash),
+                        BaseKey::ConfirmedBlock(*hash),
+                        BaseKey::BlockHeader(*hash),
+                    ]
+                })
+                .map(|key| bcs::to_bytes(&key))
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = self.store.read_multi_values_bytes(keys.clone()).await?;
+            for (index, (key, value)) in keys.into_iter().zip(values).enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                // Blocks whose bodies were pruned only have a header, and their blobs stay.
+                if index % 3 == 1 {
+                    let block = bcs::from_bytes::<ConfirmedBlock>(&value)?;
+                    blob_ids.extend(block.block().required_blob_ids());
+                }
+                archive
+                    .write(&ArchiveEntry::Base(key.clone(), value))
+                    .await?;
+                base_keys.push(key);
+                markers.insert(bcs::to_bytes(&BaseKey::ArchivedCertificate(
+                    hashes[index / 3],
+                ))?);
+            }
+        }
+
+        // Other chains may use the same blobs. Those that they used since are kept.
+        let blob_ids = blob_ids.into_iter().collect::<Vec<_>>();
+        for blob_ids in blob_ids.chunks(ARCHIVE_BATCH_SIZE) {
+            let state_keys = blob_ids
+                .iter()
+                .map(|blob_id| bcs::to_bytes(&BaseKey::BlobState(*blob_id)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let states = self
+                .store
+                .read_multi_values_bytes(state_keys.clone())
+                .await?;
+            let mut archived = Vec::new();
+            for ((blob_id, state_key), state) in blob_ids.iter().zip(state_keys).zip(states) {
+                let Some(state) = state else {
+                    continue;
+                };
+                if bcs::from_bytes::<BlobState>(&state)?.chain_id == chain_id {
+                    archived.push((*blob_id, state_key, state));
+                }
+            }
+            let blob_keys = archived
+                .iter()
+                .map(|(blob_id, _, _)| bcs::to_bytes(&BaseKey::Blob(*blob_id)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let blobs = self
+                .store
+                .read_multi_values_bytes(blob_keys.clone())
+                .await?;
+            for ((blob_id, state_key, state), (blob_key, blob)) in
+                archived.into_iter().zip(blob_keys.into_iter().zip(blobs))
+            {
+                let Some(blob) = blob else {
+                    continue;
+                };
+                archive
+                    .write(&ArchiveEntry::Base(blob_key.clone(), blob))
+                    .await?;
+                archive
+                    .write(&ArchiveEntry::Base(state_key.clone(), state))
+                    .await?;
+                base_keys.extend([blob_key, state_key]);
+                markers.insert(bcs::to_bytes(&BaseKey::ArchivedBlob(blob_id))?);
+            }
+        }
+        archive.finish().await?;
+
+        // Only remove anything once the archive is complete. The certificates and blobs are
+        // marked first, so that reading them restores the chain, and the chain last, so that
+        // it is never considered new. Archiving again overwrites the markers of an archival
+        // that was interrupted before.
+        let chain_marker = bcs::to_bytes(&chain_id)?;
+        let markers = markers.into_iter().collect::<Vec<_>>();
+        for markers in markers.chunks(ARCHIVE_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for marker in markers {
+                batch.put_key_value_bytes(marker.clone(), chain_marker.clone());
+            }
+            self.write_batch(batch).await?;
+        }
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(archived_key, Vec::new());
+        self.write_batch(batch).await?;
+        for keys in state_keys.chunks(ARCHIVE_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for key in keys {
+                batch.delete_key(key.clone());
+            }
+            state_store.write_batch(batch).await?;
+        }
+        for keys in base_keys.chunks(ARCHIVE_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for key in keys {
+                batch.delete_key(key.clone());
+            }
+            self.write_batch(batch).await?;
+        }
+        #[cfg(with_metrics)]
+        ARCHIVE_CHAIN_COUNTER.with_label_values(&[]).inc();
+        Ok(())
+    }
+
+    async fn restore_chain(&self, chain_id: ChainId) -> Result<bool, ViewError> {
+        let archived_key = bcs::to_bytes(&BaseKey::ArchivedChain(chain_id))?;
+        if !self.store.contains_key(&archived_key).await? {
+            return Ok(false);
+        }
+        let archive_store = self.archive_store()?;
+        let _guard = self.archive_lock.lock().await;
+        if !self.store.contains_key(&archived_key).await? {
+            // Another task restored the chain meanwhile.
+            return Ok(false);
+        }
+        let archive_key = Self::archive_key(chain_id);
+        let chunks = archive_store
+            .read_object(&archive_key)
+            .await?
+            .ok_or_else(|| ViewError::not_found("archive of chain", chain_id))?;
+        let mut archive = ArchiveReader::new(chunks);
+
+        // If the archival was interrupted while the entries were removed, some are still
+        // in this storage. The state is unchanged, since the chain couldn't be loaded, but
+        // the certificates and blobs may have been written again, and their states updated,
+        // so these are only written if they are missing.
+        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let state_store = self.root_store(&root_key)?;
+        let mut state_batch = Batch::new();
+        let mut base_entries = Vec::new();
+        let mut markers = BTreeSet::new();
+        loop {
+            let entry = archive.next().await?;
+            let done = entry.is_none();
+            match entry {
+                Some(ArchiveEntry::State(key, value)) => {
+                    state_batch.put_key_value_bytes(key, value);
+                }
+                Some(ArchiveEntry::Base(key, value)) => {
+                    let marker = match bcs::from_bytes::<BaseKey>(&key)? {
+                        BaseKey::Certificate(hash)
+                        | BaseKey::ConfirmedBlock(hash)
+                        | BaseKey::BlockHeader(hash) => BaseKey::ArchivedCertificate(hash),
+                        BaseKey::Blob(blob_id) | BaseKey::BlobState(blob_id) => {
+                            BaseKey::ArchivedBlob(blob_id)
+                        }
+                        _ => return Err(ViewError::InconsistentEntries),
+                    };
+                    markers.insert(bcs::to_bytes(&marker)?);
+                    base_entries.push((key, value));
+                }
+                None => {}
+            }
+            if done || state_batch.operations.len() >= ARCHIVE_BATCH_SIZE {
+                state_store
+                    .write_batch(std::mem::take(&mut state_batch))
+                    .await?;
+            }
+            if done || base_entries.len() >= ARCHIVE_BATCH_SIZE {
+                let entries = std::mem::take(&mut base_entries);
+                let keys = entries.iter().map(|(key, _)| key.clone()).collect();
+                let existing = self.store.contains_keys(keys).await?;
+                let mut batch = Batch::new();
+                for ((key, value), exists) in entries.into_iter().zip(existing) {
+                    if !exists {
+                        batch.put_key_value_bytes(key, value);
+                    }
+                }
+                self.write_batch(batch).await?;
+            }
+            if done {
+                break;
+            }
+        }
+
+        // The marker of the chain is removed last, so that an interrupted restoration is
+        // resumed the next time.
+        let markers = markers.into_iter().collect::<Vec<_>>();
+        for markers in markers.chunks(ARCHIVE_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for marker in markers {
+                batch.delete_key(marker.clone());
+            }
+            self.write_batch(batch).await?;
+        }
+        let mut batch = Batch::new();
+        batch.delete_key(archived_key);
+        self.write_batch(batch).await?;
+        archive_store.delete_object(&archive_key).await?;
+        #[cfg(with_metrics)]
+        RESTORE_CHAIN_COUNTER.with_label_values(&[]).inc();
+        Ok(true)
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.wasm_runtime
     }
@@ -878,7 +1883,7 @@ where
         block_exporter_id: u32,
     ) -> Result<Self::BlockExporterContext, ViewError> {
         let root_key = bcs::to_bytes(&BaseKey::BlockExporterState(block_exporter_id))?;
-        let store = self.store.clone_with_root_key(&root_key)?;
+        let store = self.root_store(&root_key)?;
         Ok(ViewContext::create_root_context(store, block_exporter_id).await?)
     }
 }
@@ -900,6 +1905,16 @@ where
             .collect::<Result<_, _>>()?)
     }
 
+    /// Returns an error if the block with the given hash had its body pruned.
+    async fn check_not_pruned(&self, hash: CryptoHash) -> Result<(), ViewError> {
+        let header_key = bcs::to_bytes(&BaseKey::BlockHeader(hash))?;
+        ensure!(
+            !self.store.contains_key(&header_key).await?,
+            ViewError::BlockBodyPruned(hash)
+        );
+        Ok(())
+    }
+
     fn deserialize_certificate(
         pair: &[Option<Vec<u8>>],
         hash: CryptoHash,
@@ -919,11 +1934,141 @@ where
         Ok(certificate)
     }
 
+    /// Returns the store under the given root key, counting its writes towards the write
+    /// pressure.
+    fn root_store(&self, root_key: &[u8]) -> Result<WritePressureStore<Store>, ViewError> {
+        let store = self.store.clone_with_root_key(root_key)?;
+        Ok(WritePressureStore::new(store, self.write_pressure.clone()))
+    }
+
     async fn write_batch(&self, batch: Batch) -> Result<(), ViewError> {
+        let write = self.write_pressure.start_write();
         self.store.write_batch(batch).await?;
+        write.finish();
+        Ok(())
+    }
+
+    /// Loads the view of a chain state, without restoring it from an archive.
+    async fn load_chain_state(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<ChainStateView<<Self as Storage>::Context>, ViewError>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let runtime_context = ChainRuntimeContext {
+            storage: self.clone(),
+            chain_id,
+            execution_runtime_config: self.execution_runtime_config.clone(),
+            user_contracts: self.user_contracts.clone(),
+            user_services: self.user_services.clone(),
+        };
+        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let store = self.root_store(&root_key)?;
+        let context = ViewContext::create_root_context(store, runtime_context).await?;
+        ChainStateView::load(context).await
+    }
+
+    /// Reads the counters of the bytes stored on behalf of the given chain.
+    async fn read_storage_usage(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<StorageUsageCounters, ViewError> {
+        let key = bcs::to_bytes(&BaseKey::StorageUsage(chain_id))?;
+        Ok(self.store.read_value(&key).await?.unwrap_or_default())
+    }
+
+    /// Adds the update of the counters of the bytes stored on behalf of the given chain to
+    /// the batch.
+    ///
+    /// The counters are only written by the chain's worker, so they are not updated
+    /// concurrently.
+    async fn update_storage_usage(
+        &self,
+        batch: &mut Batch,
+        chain_id: ChainId,
+        update: impl FnOnce(&mut StorageUsageCounters) + Send,
+    ) -> Result<(), ViewError> {
+        let mut counters = self.read_storage_usage(chain_id).await?;
+        update(&mut counters);
+        let key = bcs::to_bytes(&BaseKey::StorageUsage(chain_id))?;
+        batch.put_key_value(key, &counters)?;
         Ok(())
     }
 
+    fn archive_store(&self) -> Result<&Arc<dyn ArchiveStore>, ViewError> {
+        self.archive_store
+            .as_ref()
+            .ok_or_else(|| ViewError::StoreError {
+                backend: "archive".to_string(),
+                error: "no archive store is configured".to_string(),
+            })
+    }
+
+    fn archive_key(chain_id: ChainId) -> String {
+        format!("chain-{chain_id}")
+    }
+
+    /// Restores the chains whose archives have any of the certificates or blobs with the
+    /// given markers. Returns whether any chain was restored, in which case reading them
+    /// again finds them.
+    async fn restore_archived(&self, markers: Vec<BaseKey>) -> Result<bool, ViewError>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        if markers.is_empty() {
+            return Ok(false);
+        }
+        let keys = markers
+            .iter()
+            .map(bcs::to_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        let chain_ids = self
+            .store
+            .read_multi_values::<ChainId>(keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect::<BTreeSet<_>>();
+        let mut restored = false;
+        for chain_id in chain_ids {
+            restored |= self.restore_chain(chain_id).await?;
+        }
+        Ok(restored)
+    }
+
+    /// Restores the chains that archived any of the missing certificates. Returns whether
+    /// any chain was restored.
+    async fn restore_certificates<'a>(
+        &self,
+        missing: impl IntoIterator<Item = &'a CryptoHash>,
+    ) -> Result<bool, ViewError>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let markers = missing
+            .into_iter()
+            .map(|hash| BaseKey::ArchivedCertificate(*hash))
+            .collect();
+        self.restore_archived(markers).await
+    }
+
+    /// Restores the chains that archived any of the missing blobs. Returns whether any chain
+    /// was restored.
+    async fn restore_blobs<'a>(
+        &self,
+        missing: impl IntoIterator<Item = &'a BlobId>,
+    ) -> Result<bool, ViewError>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let markers = missing
+            .into_iter()
+            .map(|blob_id| BaseKey::ArchivedBlob(*blob_id))
+            .collect();
+        self.restore_archived(markers).await
+    }
+
     fn new(store: Store, wasm_runtime: Option<WasmRuntime>, clock: C) -> Self {
         Self {
             store: Arc::new(store),
@@ -932,8 +2077,33 @@ where
             user_contracts: Arc::new(DashMap::new()),
             user_services: Arc::new(DashMap::new()),
             execution_runtime_config: ExecutionRuntimeConfig::default(),
+            archive_store: None,
+            archive_lock: Arc::default(),
+            write_pressure: Arc::new(WritePressure::new(WritePressureConfig::default())),
         }
     }
+
+    /// Sets the node-local configuration of the runtime in which applications are executed.
+    pub fn with_execution_runtime_config(
+        mut self,
+        execution_runtime_config: ExecutionRuntimeConfig,
+    ) -> Self {
+        self.execution_runtime_config = execution_runtime_config;
+        self
+    }
+
+    /// Sets the store where chains are archived, and restored from when they are loaded.
+    pub fn with_archive_store(mut self, archive_store: Option<Arc<dyn ArchiveStore>>) -> Self {
+        self.archive_store = archive_store;
+        self
+    }
+
+    /// Sets the writes at which the storage is considered saturated by
+    /// [`Storage::write_pressure`].
+    pub fn with_write_pressure_config(mut self, config: WritePressureConfig) -> Self {
+        self.write_pressure = Arc::new(WritePressure::new(config));
+        self
+    }
 }
 
 impl<Store> DbStorage<Store, WallClock>
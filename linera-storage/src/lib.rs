@@ -2,10 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This module defines the storage abstractions for individual chains and certificates.
+//!
+//! This crate doesn't depend on any storage backend: [`DbStorage`] implements the
+//! [`Storage`] trait on top of any key-value store of `linera-views`, whose backends are
+//! features of that crate. Without the `wasmer`, `wasmtime` and `metrics` features, no Wasm
+//! runtime and no metrics are compiled either.
 
 #![deny(clippy::large_futures)]
 
+mod archive;
 mod db_storage;
+mod write_pressure;
 
 use std::sync::Arc;
 
@@ -21,7 +28,8 @@ use linera_base::{
     vm::VmRuntime,
 };
 use linera_chain::{
-    types::{ConfirmedBlock, ConfirmedBlockCertificate},
+    data_types::EquivocationEvidence,
+    types::{BlockHeader, ConfirmedBlock, ConfirmedBlockCertificate, LiteCertificate},
     ChainError, ChainStateView,
 };
 #[cfg(with_revm)]
@@ -41,17 +49,80 @@ use linera_views::{
 };
 use serde::{Deserialize, Serialize};
 
-#[cfg(with_testing)]
-pub use crate::db_storage::TestClock;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::archive::DirectoryArchiveStore;
+#[cfg(with_s3)]
+pub use crate::archive::S3ArchiveStore;
+pub use crate::archive::{ArchiveStore, ArchiveStoreConfig, ObjectStream, ObjectWriter};
 pub use crate::db_storage::{ChainStatesFirstAssignment, DbStorage, WallClock};
 #[cfg(with_metrics)]
 pub use crate::db_storage::{
     READ_CERTIFICATE_COUNTER, READ_CONFIRMED_BLOCK_COUNTER, WRITE_CERTIFICATE_COUNTER,
 };
+pub use crate::write_pressure::{WritePressureConfig, WritePressureStore};
+#[cfg(with_testing)]
+pub use crate::{archive::MemoryArchiveStore, db_storage::TestClock};
 
 /// The default namespace to be used when none is specified
 pub const DEFAULT_NAMESPACE: &str = "table_linera";
 
+#[cfg(with_metrics)]
+mod metrics {
+    use std::sync::LazyLock;
+
+    use linera_base::{
+        prometheus_util::{exponential_bucket_latencies, register_histogram_vec},
+        time::Instant,
+        vm::VmRuntime,
+    };
+    use prometheus::HistogramVec;
+
+    /// The time to read the bytecode blob of an application from storage.
+    pub static BYTECODE_BLOB_READ_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec(
+            "bytecode_blob_read_latency",
+            "Bytecode blob read latency",
+            &["vm_runtime", "code"],
+            exponential_bucket_latencies(1000.0),
+        )
+    });
+
+    /// The time to decompress the bytecode of an application.
+    pub static BYTECODE_DECOMPRESSION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec(
+            "load_bytecode_decompression_latency",
+            "Bytecode decompression latency when loading an application",
+            &["vm_runtime", "code"],
+            exponential_bucket_latencies(1000.0),
+        )
+    });
+
+    /// The time to construct the module of an application from its bytecode, including
+    /// the hits and misses of the module caches of the runtimes.
+    pub static MODULE_CONSTRUCTION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec(
+            "module_construction_latency",
+            "Module construction latency",
+            &["vm_runtime", "code"],
+            exponential_bucket_latencies(10_000.0),
+        )
+    });
+
+    /// Records the time elapsed since `start` in `metric` and returns the current time.
+    pub fn record_latency(
+        metric: &HistogramVec,
+        vm_runtime: VmRuntime,
+        code: &str,
+        start: Instant,
+    ) -> Instant {
+        let now = Instant::now();
+        metric
+            .with_label_values(&[&vm_runtime.to_string(), code])
+            .observe((now - start).as_secs_f64() * 1000.0);
+        now
+    }
+}
+
 /// Communicate with a persistent storage using the "views" abstraction.
 #[cfg_attr(not(web), async_trait)]
 #[cfg_attr(web, async_trait(?Send))]
@@ -68,6 +139,10 @@ pub trait Storage: Sized {
     /// Returns the current wall clock time.
     fn clock(&self) -> &Self::Clock;
 
+    /// Returns how saturated the storage backend is by writes, from 0 (idle) to 1
+    /// (saturated), so that callers can reject new work instead of queueing it.
+    fn write_pressure(&self) -> f64;
+
     /// Loads the view of a chain state.
     ///
     /// # Notes
@@ -89,6 +164,9 @@ pub trait Storage: Sized {
     /// Reads the hashed certificate value with the given hash.
     async fn read_confirmed_block(&self, hash: CryptoHash) -> Result<ConfirmedBlock, ViewError>;
 
+    /// Reads the header of the block with the given hash, even if its body was pruned.
+    async fn read_block_header(&self, hash: CryptoHash) -> Result<BlockHeader, ViewError>;
+
     /// Reads the blob with the given blob ID.
     async fn read_blob(&self, blob_id: BlobId) -> Result<Blob, ViewError>;
 
@@ -111,6 +189,12 @@ pub trait Storage: Sized {
     /// Writes the given blob.
     async fn write_blob(&self, blob: &Blob) -> Result<(), ViewError>;
 
+    /// Replaces the confirmed blocks with the given hashes by their headers, dropping their
+    /// operations and the payloads of their incoming bundles. The certificates' signatures are
+    /// kept, and reading the blocks or certificates returns [`ViewError::BlockBodyPruned`].
+    /// Blocks that were already pruned are skipped.
+    async fn prune_confirmed_blocks(&self, hashes: &[CryptoHash]) -> Result<(), ViewError>;
+
     /// Writes blobs and certificate
     async fn write_blobs_and_certificate(
         &self,
@@ -150,6 +234,13 @@ pub trait Storage: Sized {
     /// Tests existence of the certificate with the given hash.
     async fn contains_certificate(&self, hash: CryptoHash) -> Result<bool, ViewError>;
 
+    /// Reads the signatures of the certificate with the given hash, even if the block's body
+    /// was pruned.
+    async fn read_lite_certificate(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<LiteCertificate<'static>, ViewError>;
+
     /// Reads the certificate with the given hash.
     async fn read_certificate(
         &self,
@@ -174,6 +265,14 @@ pub trait Storage: Sized {
         events: impl IntoIterator<Item = (EventId, Vec<u8>)> + Send,
     ) -> Result<(), ViewError>;
 
+    /// Reads the events emitted by the given application on the given chain, ordered by
+    /// stream name and index. Events written before this index existed are not included.
+    async fn read_events_by_application(
+        &self,
+        application_id: ApplicationId,
+        chain_id: ChainId,
+    ) -> Result<Vec<(EventId, Vec<u8>)>, ViewError>;
+
     /// Reads the network description.
     async fn read_network_description(&self) -> Result<Option<NetworkDescription>, ViewError>;
 
@@ -183,6 +282,53 @@ pub trait Storage: Sized {
         information: &NetworkDescription,
     ) -> Result<(), ViewError>;
 
+    /// Reads the host and port of each of the validator's shards, as recorded when the
+    /// validator first started or last handed off its chains to new shards.
+    async fn read_shard_addresses(&self) -> Result<Option<Vec<(String, u16)>>, ViewError>;
+
+    /// Records the host and port of each of the validator's shards.
+    async fn write_shard_addresses(&self, addresses: &[(String, u16)]) -> Result<(), ViewError>;
+
+    /// Records that the given chain may have outgoing messages that haven't been delivered.
+    async fn write_pending_delivery(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
+    /// Removes the record that the given chain may have undelivered outgoing messages.
+    async fn remove_pending_delivery(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
+    /// Returns the chains that may have outgoing messages that haven't been delivered.
+    async fn read_pending_deliveries(&self) -> Result<Vec<ChainId>, ViewError>;
+
+    /// Records evidence that a validator signed conflicting blocks.
+    async fn write_equivocation_evidence(
+        &self,
+        evidence: &EquivocationEvidence,
+    ) -> Result<(), ViewError>;
+
+    /// Reads all the recorded evidence of validators signing conflicting blocks of the given
+    /// chain.
+    async fn read_equivocation_evidence(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<Vec<EquivocationEvidence>, ViewError>;
+
+    /// Returns the number of bytes stored on behalf of the given chain.
+    async fn chain_storage_usage(&self, chain_id: ChainId) -> Result<ChainStorageUsage, ViewError>;
+
+    /// Moves the state of a chain to the archive store, with its certificates and the blobs
+    /// that no other chain used since, and removes them from this storage.
+    ///
+    /// # Notes
+    ///
+    /// The chain must not be loaded while it is archived: validators archive their chains
+    /// with `WorkerState::archive_chain`, which checks this. The chain is restored
+    /// automatically the next time it is loaded, or when one of its archived certificates or
+    /// blobs is read.
+    async fn archive_chain(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
+    /// Moves an archived chain back from the archive store. Returns whether the chain was
+    /// archived.
+    async fn restore_chain(&self, chain_id: ChainId) -> Result<bool, ViewError>;
+
     /// Initializes a chain in a simple way (used for testing and to create a genesis state).
     ///
     /// # Notes
@@ -211,12 +357,23 @@ pub trait Storage: Sized {
 
     /// Creates a [`UserContractCode`] instance using the bytecode in storage referenced
     /// by the `application_description`.
+    #[cfg_attr(not(any(with_wasm_runtime, with_revm)), allow(unreachable_code))]
     async fn load_contract(
         &self,
         application_description: &ApplicationDescription,
     ) -> Result<UserContractCode, ExecutionError> {
+        let vm_runtime = application_description.module_id.vm_runtime;
+        #[cfg(with_metrics)]
+        let start = linera_base::time::Instant::now();
         let contract_bytecode_blob_id = application_description.contract_bytecode_blob_id();
         let contract_blob = self.read_blob(contract_bytecode_blob_id).await?;
+        #[cfg(with_metrics)]
+        let start = metrics::record_latency(
+            &metrics::BYTECODE_BLOB_READ_LATENCY,
+            vm_runtime,
+            "contract",
+            start,
+        );
         let compressed_contract_bytecode = CompressedBytecode {
             compressed_bytes: contract_blob.into_bytes().to_vec(),
         };
@@ -228,16 +385,24 @@ pub trait Storage: Sized {
             .await
             .join()
             .await?;
-        match application_description.module_id.vm_runtime {
+        #[cfg(with_metrics)]
+        let start = metrics::record_latency(
+            &metrics::BYTECODE_DECOMPRESSION_LATENCY,
+            vm_runtime,
+            "contract",
+            start,
+        );
+        #[cfg_attr(not(any(with_wasm_runtime, with_revm)), allow(unused_variables))]
+        let contract: UserContractCode = match vm_runtime {
             VmRuntime::Wasm => {
                 cfg_if::cfg_if! {
                     if #[cfg(with_wasm_runtime)] {
                         let Some(wasm_runtime) = self.wasm_runtime() else {
                             panic!("A Wasm runtime is required to load user applications.");
                         };
-                        Ok(WasmContractModule::new(contract_bytecode, wasm_runtime)
+                        WasmContractModule::new(contract_bytecode, wasm_runtime)
                            .await?
-                           .into())
+                           .into()
                     } else {
                         panic!(
                             "A Wasm runtime is required to load user applications. \
@@ -251,9 +416,9 @@ pub trait Storage: Sized {
                 cfg_if::cfg_if! {
                     if #[cfg(with_revm)] {
                         let evm_runtime = EvmRuntime::Revm;
-                        Ok(EvmContractModule::new(contract_bytecode, evm_runtime)
+                        EvmContractModule::new(contract_bytecode, evm_runtime)
                            .await?
-                           .into())
+                           .into()
                     } else {
                         panic!(
                             "An Evm runtime is required to load user applications. \
@@ -263,17 +428,36 @@ pub trait Storage: Sized {
                     }
                 }
             }
-        }
+        };
+        #[cfg(with_metrics)]
+        metrics::record_latency(
+            &metrics::MODULE_CONSTRUCTION_LATENCY,
+            vm_runtime,
+            "contract",
+            start,
+        );
+        Ok(contract)
     }
 
     /// Creates a [`linera-sdk::UserContract`] instance using the bytecode in storage referenced
     /// by the `application_description`.
+    #[cfg_attr(not(any(with_wasm_runtime, with_revm)), allow(unreachable_code))]
     async fn load_service(
         &self,
         application_description: &ApplicationDescription,
     ) -> Result<UserServiceCode, ExecutionError> {
+        let vm_runtime = application_description.module_id.vm_runtime;
+        #[cfg(with_metrics)]
+        let start = linera_base::time::Instant::now();
         let service_bytecode_blob_id = application_description.service_bytecode_blob_id();
         let service_blob = self.read_blob(service_bytecode_blob_id).await?;
+        #[cfg(with_metrics)]
+        let start = metrics::record_latency(
+            &metrics::BYTECODE_BLOB_READ_LATENCY,
+            vm_runtime,
+            "service",
+            start,
+        );
         let compressed_service_bytecode = CompressedBytecode {
             compressed_bytes: service_blob.into_bytes().to_vec(),
         };
@@ -284,16 +468,24 @@ pub trait Storage: Sized {
         .await
         .join()
         .await?;
-        match application_description.module_id.vm_runtime {
+        #[cfg(with_metrics)]
+        let start = metrics::record_latency(
+            &metrics::BYTECODE_DECOMPRESSION_LATENCY,
+            vm_runtime,
+            "service",
+            start,
+        );
+        #[cfg_attr(not(any(with_wasm_runtime, with_revm)), allow(unused_variables))]
+        let service: UserServiceCode = match vm_runtime {
             VmRuntime::Wasm => {
                 cfg_if::cfg_if! {
                     if #[cfg(with_wasm_runtime)] {
                         let Some(wasm_runtime) = self.wasm_runtime() else {
                             panic!("A Wasm runtime is required to load user applications.");
                         };
-                        Ok(WasmServiceModule::new(service_bytecode, wasm_runtime)
+                        WasmServiceModule::new(service_bytecode, wasm_runtime)
                            .await?
-                           .into())
+                           .into()
                     } else {
                         panic!(
                             "A Wasm runtime is required to load user applications. \
@@ -307,9 +499,9 @@ pub trait Storage: Sized {
                 cfg_if::cfg_if! {
                     if #[cfg(with_revm)] {
                         let evm_runtime = EvmRuntime::Revm;
-                        Ok(EvmServiceModule::new(service_bytecode, evm_runtime)
+                        EvmServiceModule::new(service_bytecode, evm_runtime)
                            .await?
-                           .into())
+                           .into()
                     } else {
                         panic!(
                             "An Evm runtime is required to load user applications. \
@@ -319,7 +511,15 @@ pub trait Storage: Sized {
                     }
                 }
             }
-        }
+        };
+        #[cfg(with_metrics)]
+        metrics::record_latency(
+            &metrics::MODULE_CONSTRUCTION_LATENCY,
+            vm_runtime,
+            "service",
+            start,
+        );
+        Ok(service)
     }
 
     async fn block_exporter_context(
@@ -337,6 +537,27 @@ pub struct NetworkDescription {
     pub genesis_timestamp: Timestamp,
 }
 
+/// The number of bytes stored on behalf of a chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainStorageUsage {
+    /// The size of the chain's state.
+    pub state_bytes: u64,
+    /// The size of the chain's certificates, and of its blocks or, if they were pruned,
+    /// their headers.
+    pub certificate_bytes: u64,
+    /// The size of the blobs that the chain's blocks were the first to store.
+    pub blob_bytes: u64,
+}
+
+impl ChainStorageUsage {
+    /// Returns the total number of bytes stored on behalf of the chain.
+    pub fn total_bytes(&self) -> u64 {
+        self.state_bytes
+            .saturating_add(self.certificate_bytes)
+            .saturating_add(self.blob_bytes)
+    }
+}
+
 /// An implementation of `ExecutionRuntimeContext` suitable for the core protocol.
 #[derive(Clone)]
 pub struct ChainRuntimeContext<S> {
@@ -358,7 +579,7 @@ where
     }
 
     fn execution_runtime_config(&self) -> linera_execution::ExecutionRuntimeConfig {
-        self.execution_runtime_config
+        self.execution_runtime_config.clone()
     }
 
     fn user_contracts(&self) -> &Arc<DashMap<ApplicationId, UserContractCode>> {
@@ -0,0 +1,247 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An estimate of how saturated the storage backend is by writes, so that callers can shed
+//! load before their requests queue up behind slow writes.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use linera_base::time::{Duration, Instant};
+use linera_views::{
+    batch::Batch,
+    store::{ReadableKeyValueStore, WithError, WritableKeyValueStore},
+};
+
+/// The weight of a new latency sample in the smoothed write latency.
+const LATENCY_SMOOTHING: f64 = 0.125;
+
+/// How long it takes for the latency part of the score to halve when no writes complete.
+const LATENCY_HALF_LIFE: Duration = Duration::from_secs(1);
+
+/// The writes at which the storage is considered saturated.
+#[derive(Clone, Copy, Debug)]
+pub struct WritePressureConfig {
+    /// The number of writes in flight at which the score is 1.
+    pub max_pending_writes: usize,
+    /// The smoothed write latency at which the score is 1.
+    pub saturated_write_latency: Duration,
+}
+
+impl Default for WritePressureConfig {
+    fn default() -> Self {
+        Self {
+            max_pending_writes: 256,
+            saturated_write_latency: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The smoothed latency of the completed writes.
+#[derive(Debug)]
+struct LatencyEstimate {
+    /// The smoothed latency, in seconds.
+    seconds: f64,
+    /// When the last write completed.
+    updated_at: Instant,
+}
+
+/// Tracks the writes in flight and their latency.
+#[derive(Debug)]
+pub(crate) struct WritePressure {
+    config: WritePressureConfig,
+    pending_writes: AtomicUsize,
+    latency: Mutex<Option<LatencyEstimate>>,
+}
+
+/// A write in flight. It is no longer counted when dropped.
+pub(crate) struct PendingWrite<'a> {
+    pressure: &'a WritePressure,
+    start: Instant,
+}
+
+impl WritePressure {
+    pub(crate) fn new(config: WritePressureConfig) -> Self {
+        Self {
+            config,
+            pending_writes: AtomicUsize::new(0),
+            latency: Mutex::new(None),
+        }
+    }
+
+    /// Counts a write until the returned value is dropped.
+    pub(crate) fn start_write(&self) -> PendingWrite<'_> {
+        self.pending_writes.fetch_add(1, Ordering::Relaxed);
+        PendingWrite {
+            pressure: self,
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the saturation score: 0 if the storage is idle, and 1 if the writes in
+    /// flight or their smoothed latency reach the configured limits.
+    ///
+    /// The latency part decays while no writes complete, so that a single slow write
+    /// doesn't keep the score high once the load has been shed.
+    pub(crate) fn score(&self) -> f64 {
+        let pending = self.pending_writes.load(Ordering::Relaxed) as f64
+            / self.config.max_pending_writes.max(1) as f64;
+        let latency = self
+            .latency
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0.0, |latency| {
+                let idle = latency.updated_at.elapsed().as_secs_f64();
+                latency.seconds * 0.5f64.powf(idle / LATENCY_HALF_LIFE.as_secs_f64())
+            });
+        let saturated_latency = self.config.saturated_write_latency.as_secs_f64();
+        let latency = if saturated_latency > 0.0 {
+            latency / saturated_latency
+        } else {
+            0.0
+        };
+        pending.max(latency).min(1.0)
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        let sample = elapsed.as_secs_f64();
+        let mut latency = self.latency.lock().unwrap();
+        let seconds = match &*latency {
+            None => sample,
+            Some(latency) => latency.seconds + LATENCY_SMOOTHING * (sample - latency.seconds),
+        };
+        *latency = Some(LatencyEstimate {
+            seconds,
+            updated_at: Instant::now(),
+        });
+    }
+}
+
+impl PendingWrite<'_> {
+    /// Records the latency of the write, which completed.
+    pub(crate) fn finish(self) {
+        self.pressure.record_latency(self.start.elapsed());
+    }
+}
+
+impl Drop for PendingWrite<'_> {
+    fn drop(&mut self) {
+        self.pressure.pending_writes.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A key-value store that counts its writes towards a [`WritePressure`].
+#[derive(Clone)]
+pub struct WritePressureStore<S> {
+    /// The underlying store.
+    store: S,
+    /// The pressure shared by all the stores of the same storage.
+    pressure: Arc<WritePressure>,
+}
+
+impl<S> WritePressureStore<S> {
+    pub(crate) fn new(store: S, pressure: Arc<WritePressure>) -> Self {
+        Self { store, pressure }
+    }
+}
+
+impl<S> WithError for WritePressureStore<S>
+where
+    S: WithError,
+{
+    type Error = S::Error;
+}
+
+impl<S> ReadableKeyValueStore for WritePressureStore<S>
+where
+    S: ReadableKeyValueStore + Send + Sync,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    type Keys = S::Keys;
+    type KeyValues = S::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.store.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        self.store.contains_key(key).await
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        self.store.contains_keys(keys).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        self.store.find_key_values_by_prefix(key_prefix).await
+    }
+}
+
+impl<S> WritableKeyValueStore for WritePressureStore<S>
+where
+    S: WritableKeyValueStore + Send + Sync,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let write = self.pressure.start_write();
+        self.store.write_batch(batch).await?;
+        write.finish();
+        Ok(())
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        self.store.clear_journal().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_pressure_score() {
+        let pressure = WritePressure::new(WritePressureConfig {
+            max_pending_writes: 4,
+            saturated_write_latency: Duration::from_secs(10),
+        });
+        assert_eq!(pressure.score(), 0.0);
+
+        let writes = (0..2).map(|_| pressure.start_write()).collect::<Vec<_>>();
+        assert_eq!(pressure.score(), 0.5);
+        let more_writes = (0..4).map(|_| pressure.start_write()).collect::<Vec<_>>();
+        assert_eq!(pressure.score(), 1.0);
+        drop(more_writes);
+        drop(writes);
+        // Cancelled writes don't count towards the latency.
+        assert_eq!(pressure.score(), 0.0);
+
+        pressure.record_latency(Duration::from_secs(5));
+        let score = pressure.score();
+        assert!(score > 0.4 && score <= 0.5, "unexpected score {score}");
+        pressure.record_latency(Duration::from_secs(15));
+        let score = pressure.score();
+        assert!(score > 0.55 && score <= 0.625, "unexpected score {score}");
+    }
+}
@@ -14,6 +14,16 @@ struct StructAttrs {
     context: Option<syn::Type>,
 }
 
+#[derive(Debug, deluxe::ParseAttributes)]
+#[deluxe(attributes(view))]
+struct FieldAttrs {
+    /// Whether the field's hash is only part of the hash of the view when the field doesn't
+    /// have its default value, e.g. while a collection is empty, so that the field can be
+    /// added to a view without changing the hash of existing views.
+    #[deluxe(default)]
+    hash_unless_default: bool,
+}
+
 struct ContextAndConstraints<'a> {
     context: syn::Type,
     context_constraints: Vec<syn::WherePredicate>,
@@ -145,6 +155,18 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
     } else {
         quote! {}
     };
+    let load_size_metrics = if root && cfg!(feature = "metrics") {
+        quote! {
+            #[cfg(not(target_arch = "wasm32"))]
+            linera_views::metrics::record_load_size(
+                stringify!(#struct_name),
+                &context.base_key(),
+                &values,
+            );
+        }
+    } else {
+        quote! {}
+    };
 
     quote! {
         #[linera_views::async_trait]
@@ -184,6 +206,7 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
                 } else {
                     let keys = Self::pre_load(&context)?;
                     let values = context.read_multi_values_bytes(keys).await?;
+                    #load_size_metrics
                     Self::post_load(context, &values)
                 }
             }
@@ -240,6 +263,18 @@ fn generate_root_view_code(input: ItemStruct) -> TokenStream2 {
     } else {
         quote! {}
     };
+    let save_size_metrics = if cfg!(feature = "metrics") {
+        quote! {
+            #[cfg(not(target_arch = "wasm32"))]
+            linera_views::metrics::record_save_size(
+                stringify!(#struct_name),
+                &self.context().base_key(),
+                &batch,
+            );
+        }
+    } else {
+        quote! {}
+    };
 
     quote! {
         #[linera_views::async_trait]
@@ -254,6 +289,7 @@ fn generate_root_view_code(input: ItemStruct) -> TokenStream2 {
                 #increment_counter
                 let mut batch = Batch::new();
                 #(#flushes)*
+                #save_size_metrics
                 if !batch.is_empty() {
                     self.context().write_batch(batch).await?;
                 }
@@ -289,11 +325,58 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     let mut field_hashes = Vec::new();
     for e in &input.fields {
         let name = e.ident.as_ref().unwrap();
-        field_hashes_mut.push(quote! { hasher.write_all(self.#name.hash_mut().await?.as_ref())?; });
-        field_hashes.push(quote! { hasher.write_all(self.#name.hash().await?.as_ref())?; });
+        let name_str = name.to_string();
+        let attrs: FieldAttrs = deluxe::parse_attributes(e).unwrap();
+        if attrs.hash_unless_default {
+            field_hashes_mut.push(quote! {
+                {
+                    let default_hash = linera_views::views::default_hash(&self.#name).await?;
+                    let hash = self.#name.hash_mut().await?;
+                    if hash.as_ref() != default_hash.as_slice() {
+                        let hash = linera_views::views::named_field_hash(#name_str, hash.as_ref())?;
+                        hasher.write_all(hash.as_ref())?;
+                    }
+                }
+            });
+            field_hashes.push(quote! {
+                {
+                    let default_hash = linera_views::views::default_hash(&self.#name).await?;
+                    let hash = self.#name.hash().await?;
+                    if hash.as_ref() != default_hash.as_slice() {
+                        let hash = linera_views::views::named_field_hash(#name_str, hash.as_ref())?;
+                        sink.write_field_hash(#name_str, hash.as_ref())?;
+                    }
+                }
+            });
+        } else {
+            field_hashes_mut
+                .push(quote! { hasher.write_all(self.#name.hash_mut().await?.as_ref())?; });
+            field_hashes.push(quote! {
+                sink.write_field_hash(#name_str, self.#name.hash().await?.as_ref())?;
+            });
+        }
     }
 
     quote! {
+        impl #impl_generics #struct_name #type_generics
+        where
+            #(#input_constraints,)*
+            #(#context_constraints,)*
+            #(#hash_constraints,)*
+            Self: Send + Sync,
+        {
+            /// Writes the names and hashes of the fields to `sink`, in the order in which they
+            /// make up the hash of the view.
+            pub async fn write_field_hashes(
+                &self,
+                sink: &mut (impl linera_views::views::FieldHashSink + Send),
+            ) -> Result<(), linera_views::views::ViewError> {
+                use linera_views::views::HashableView;
+                #(#field_hashes)*
+                Ok(())
+            }
+        }
+
         #[linera_views::async_trait]
         impl #impl_generics linera_views::views::HashableView<#context> for #struct_name #type_generics
         where
@@ -313,10 +396,9 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
             }
 
             async fn hash(&self) -> Result<<Self::Hasher as linera_views::views::Hasher>::Output, linera_views::views::ViewError> {
-                use linera_views::views::{Hasher, HashableView};
-                use std::io::Write;
+                use linera_views::views::Hasher;
                 let mut hasher = Self::Hasher::default();
-                #(#field_hashes)*
+                self.write_field_hashes(&mut hasher).await?;
                 Ok(hasher.finalize())
             }
         }
@@ -7,8 +7,11 @@ fn main() {
         with_testing: { any(test, feature = "test") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
         with_dynamodb: { all(not(target_arch = "wasm32"), feature = "dynamodb") },
+        with_foundationdb: { all(not(target_arch = "wasm32"), feature = "foundationdb") },
         with_indexeddb: { all(web, feature = "indexeddb") },
         with_rocksdb: { all(not(target_arch = "wasm32"), feature = "rocksdb") },
         with_scylladb: { all(not(target_arch = "wasm32"), feature = "scylladb") },
+        with_postgres: { all(not(target_arch = "wasm32"), feature = "postgres") },
+        with_redis: { all(not(target_arch = "wasm32"), feature = "redis") },
     };
 }
@@ -391,6 +391,10 @@ pub enum DualStoreError<E1, E2> {
     /// Second store.
     #[error("Error in second store: {0}")]
     Second(E2),
+
+    /// The new store of a migration is not known to hold the data of the old store.
+    #[error("The new store of the migration was not verified since the last backfill")]
+    MigrationNotVerified,
 }
 
 impl<E1, E2> KeyValueStoreError for DualStoreError<E1, E2>
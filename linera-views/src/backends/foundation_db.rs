@@ -0,0 +1,624 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`crate::store::KeyValueStore`] for the FoundationDB database.
+//!
+//! All the namespaces share the key space of the cluster. Every key of the database starts
+//! with a tag byte, followed by the BCS serialization of the namespace and, for the data,
+//! of the root key, which makes the encoding prefix-free:
+//! * `[NAMESPACE_TAG, namespace]` marks the existence of a namespace,
+//! * `[ROOT_KEY_TAG, namespace, root_key]` marks that a root key was written to,
+//! * `[DATA_TAG, namespace, root_key, key]` contains the value of `key`.
+//!
+//! Each batch is written in a single transaction, which is retried if it conflicts with
+//! another one. Transactions are limited in size, so larger batches go through a
+//! journal, see [`JournalingKeyValueStore`].
+
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use async_lock::{Semaphore, SemaphoreGuard};
+use async_trait::async_trait;
+use foundationdb::{api::NetworkAutoStop, Database, RangeOption, Transaction};
+use futures::{future::try_join_all, TryStreamExt as _};
+use linera_base::ensure;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(with_metrics)]
+use crate::metering::MeteredStore;
+#[cfg(with_testing)]
+use crate::store::TestKeyValueStore;
+use crate::{
+    batch::UnorderedBatch,
+    common::{get_uleb128_size, get_upper_bound_option},
+    journaling::{DirectWritableKeyValueStore, JournalConsistencyError, JournalingKeyValueStore},
+    lru_caching::{LruCachingConfig, LruCachingStore},
+    store::{
+        AdminKeyValueStore, CommonStoreInternalConfig, KeyValueStoreError, ReadableKeyValueStore,
+        WithError,
+    },
+    value_splitting::{ValueSplittingError, ValueSplittingStore},
+};
+
+/// The tag of the keys marking the existing namespaces.
+const NAMESPACE_TAG: u8 = 0;
+
+/// The tag of the keys marking the root keys written to.
+const ROOT_KEY_TAG: u8 = 1;
+
+/// The tag of the keys containing the data.
+const DATA_TAG: u8 = 2;
+
+/// FoundationDB keys have at most 10,000 bytes. We keep 1,808 of them for the tag, the
+/// namespace and the root key.
+const MAX_KEY_SIZE: usize = 8192;
+
+/// FoundationDB values have at most 100,000 bytes.
+const RAW_MAX_VALUE_SIZE: usize = 100_000;
+
+/// The values written by the journal are serialized batches with one entry. As for
+/// ScyllaDB, this leaves room for the serialization of a key and of the value's length.
+const VISIBLE_MAX_VALUE_SIZE: usize = RAW_MAX_VALUE_SIZE
+    - MAX_KEY_SIZE
+    - get_uleb128_size(RAW_MAX_VALUE_SIZE)
+    - get_uleb128_size(MAX_KEY_SIZE)
+    - 1
+    - 1
+    - 1;
+
+/// Transactions are limited to 10 MB, but FoundationDB recommends keeping them under 1 MB.
+const MAX_BATCH_TOTAL_SIZE: usize = 1_000_000;
+
+/// The maximal number of operations in a single transaction.
+const MAX_BATCH_SIZE: usize = 10_000;
+
+/// The FoundationDB client runs a network thread, which can be started only once per
+/// process. It is stopped when the process exits.
+static NETWORK: OnceLock<NetworkAutoStop> = OnceLock::new();
+
+/// Opens the database of the cluster, starting the network thread if needed.
+fn open_database(
+    config: &FoundationDbStoreInternalConfig,
+) -> Result<Database, FoundationDbStoreInternalError> {
+    // SAFETY: `boot` is only called once, and the network is never stopped before the
+    // process exits.
+    NETWORK.get_or_init(|| unsafe { foundationdb::boot() });
+    Ok(Database::new(config.cluster_file.as_deref())?)
+}
+
+/// Returns the prefix of the keys with the given tag in the namespace.
+fn namespace_prefix(tag: u8, namespace: &str) -> Result<Vec<u8>, bcs::Error> {
+    let mut prefix = vec![tag];
+    bcs::serialize_into(&mut prefix, namespace)?;
+    Ok(prefix)
+}
+
+/// Returns the first key after all the keys starting with `prefix`.
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    get_upper_bound_option(prefix).expect("prefixes start with a tag other than 0xff")
+}
+
+/// Returns the range of the keys starting with `prefix`.
+fn prefix_range(prefix: &[u8]) -> RangeOption<'static> {
+    RangeOption::from((prefix.to_vec(), prefix_end(prefix)))
+}
+
+/// The client for FoundationDB:
+/// * The database of the cluster
+/// * The prefix of the data keys of the namespace and root key
+/// * The key marking the root key, written along with the first batch
+struct FoundationDbClient {
+    database: Arc<Database>,
+    data_prefix: Vec<u8>,
+    root_key_marker: Vec<u8>,
+    root_key_written: AtomicBool,
+}
+
+impl FoundationDbClient {
+    fn new(
+        database: Arc<Database>,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, FoundationDbStoreInternalError> {
+        let mut data_prefix = namespace_prefix(DATA_TAG, namespace)?;
+        bcs::serialize_into(&mut data_prefix, root_key)?;
+        let mut root_key_marker = namespace_prefix(ROOT_KEY_TAG, namespace)?;
+        bcs::serialize_into(&mut root_key_marker, root_key)?;
+        Ok(Self {
+            database,
+            data_prefix,
+            root_key_marker,
+            root_key_written: AtomicBool::new(false),
+        })
+    }
+
+    fn full_key(&self, key: &[u8]) -> Result<Vec<u8>, FoundationDbStoreInternalError> {
+        ensure!(
+            key.len() <= MAX_KEY_SIZE,
+            FoundationDbStoreInternalError::KeyTooLong
+        );
+        let mut full_key = self.data_prefix.clone();
+        full_key.extend_from_slice(key);
+        Ok(full_key)
+    }
+
+    async fn read_value_internal(
+        &self,
+        transaction: &Transaction,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, FoundationDbStoreInternalError> {
+        let full_key = self.full_key(key)?;
+        let value = transaction.get(&full_key, true).await?;
+        Ok(value.map(|value| value.to_vec()))
+    }
+
+    async fn read_multi_values_internal(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, FoundationDbStoreInternalError> {
+        let transaction = self.database.create_trx()?;
+        // All the reads of a transaction see the same version of the database.
+        let reads = keys
+            .iter()
+            .map(|key| self.read_value_internal(&transaction, key));
+        try_join_all(reads).await
+    }
+
+    async fn find_key_values_by_prefix_internal(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, FoundationDbStoreInternalError> {
+        let full_prefix = self.full_key(key_prefix)?;
+        let transaction = self.database.create_trx()?;
+        let len = full_prefix.len();
+        transaction
+            .get_ranges_keyvalues(prefix_range(&full_prefix), true)
+            .map_ok(|key_value| (key_value.key()[len..].to_vec(), key_value.value().to_vec()))
+            .map_err(FoundationDbStoreInternalError::from)
+            .try_collect()
+            .await
+    }
+
+    async fn write_batch_internal(
+        &self,
+        batch: UnorderedBatch,
+    ) -> Result<(), FoundationDbStoreInternalError> {
+        ensure!(
+            batch.len() <= MAX_BATCH_SIZE,
+            FoundationDbStoreInternalError::BatchTooLong
+        );
+        let mut deleted_ranges = Vec::new();
+        for key_prefix in &batch.key_prefix_deletions {
+            let full_prefix = self.full_key(key_prefix)?;
+            let end = prefix_end(&full_prefix);
+            deleted_ranges.push((full_prefix, end));
+        }
+        for (start, end) in &batch.key_range_deletions {
+            deleted_ranges.push((self.full_key(start)?, self.full_key(end)?));
+        }
+        let batch = batch.simple_unordered_batch;
+        let deletions = batch
+            .deletions
+            .iter()
+            .map(|key| self.full_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut insertions = Vec::with_capacity(batch.insertions.len());
+        for (key, value) in batch.insertions {
+            ensure!(
+                value.len() <= RAW_MAX_VALUE_SIZE,
+                FoundationDbStoreInternalError::ValueTooLong
+            );
+            insertions.push((self.full_key(&key)?, value));
+        }
+        let write_root_key = !self.root_key_written.load(Ordering::SeqCst);
+        self.database
+            .run(|transaction, _maybe_committed| {
+                let deleted_ranges = &deleted_ranges;
+                let deletions = &deletions;
+                let insertions = &insertions;
+                async move {
+                    // Key-prefix and key-range deletions must happen first.
+                    for (begin, end) in deleted_ranges {
+                        transaction.clear_range(begin, end);
+                    }
+                    for key in deletions {
+                        transaction.clear(key);
+                    }
+                    for (key, value) in insertions {
+                        transaction.set(key, value);
+                    }
+                    if write_root_key {
+                        transaction.set(&self.root_key_marker, &[]);
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+        self.root_key_written.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// The client itself and the keeping of the count of active queries.
+#[derive(Clone)]
+pub struct FoundationDbStoreInternal {
+    store: Arc<FoundationDbClient>,
+    namespace: String,
+    semaphore: Option<Arc<Semaphore>>,
+    max_stream_queries: usize,
+}
+
+/// The error type for [`FoundationDbStoreInternal`]
+#[derive(Error, Debug)]
+pub enum FoundationDbStoreInternalError {
+    /// BCS serialization error.
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
+
+    /// The key must have at most `MAX_KEY_SIZE` bytes
+    #[error("The key must have at most MAX_KEY_SIZE")]
+    KeyTooLong,
+
+    /// The value must have at most `RAW_MAX_VALUE_SIZE` bytes
+    #[error("The value must have at most RAW_MAX_VALUE_SIZE")]
+    ValueTooLong,
+
+    /// The batch is too long to be written
+    #[error("The batch is too long to be written")]
+    BatchTooLong,
+
+    /// Namespace contains forbidden characters
+    #[error("Namespace contains forbidden characters")]
+    InvalidNamespace,
+
+    /// The namespace already exists
+    #[error("The namespace already exists")]
+    NamespaceAlreadyExists,
+
+    /// The journal is not coherent
+    #[error(transparent)]
+    JournalConsistencyError(#[from] JournalConsistencyError),
+
+    /// An error in FoundationDB
+    #[error(transparent)]
+    FoundationDbError(#[from] foundationdb::FdbError),
+
+    /// An error in a FoundationDB transaction, after its retries
+    #[error(transparent)]
+    FoundationDbTransactionError(#[from] foundationdb::FdbBindingError),
+}
+
+impl KeyValueStoreError for FoundationDbStoreInternalError {
+    const BACKEND: &'static str = "foundation_db";
+}
+
+impl WithError for FoundationDbStoreInternal {
+    type Error = FoundationDbStoreInternalError;
+}
+
+impl ReadableKeyValueStore for FoundationDbStoreInternal {
+    const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.max_stream_queries
+    }
+
+    async fn read_value_bytes(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, FoundationDbStoreInternalError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        let transaction = store.database.create_trx()?;
+        store.read_value_internal(&transaction, key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, FoundationDbStoreInternalError> {
+        Ok(self.read_value_bytes(key).await?.is_some())
+    }
+
+    async fn contains_keys(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, FoundationDbStoreInternalError> {
+        let values = self.read_multi_values_bytes(keys).await?;
+        Ok(values.iter().map(Option::is_some).collect())
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, FoundationDbStoreInternalError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store.read_multi_values_internal(keys).await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::Keys, FoundationDbStoreInternalError> {
+        let key_values = self.find_key_values_by_prefix(key_prefix).await?;
+        Ok(key_values.into_iter().map(|(key, _)| key).collect())
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, FoundationDbStoreInternalError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store.find_key_values_by_prefix_internal(key_prefix).await
+    }
+}
+
+#[async_trait]
+impl DirectWritableKeyValueStore for FoundationDbStoreInternal {
+    const MAX_BATCH_SIZE: usize = MAX_BATCH_SIZE;
+    const MAX_BATCH_TOTAL_SIZE: usize = MAX_BATCH_TOTAL_SIZE;
+    const MAX_VALUE_SIZE: usize = VISIBLE_MAX_VALUE_SIZE;
+
+    // The operations of a transaction are applied in order, so the key-prefix deletions
+    // can be kept, as long as they come first.
+    type Batch = UnorderedBatch;
+
+    async fn write_batch(&self, batch: Self::Batch) -> Result<(), FoundationDbStoreInternalError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store.write_batch_internal(batch).await
+    }
+}
+
+/// The type for building a new FoundationDB Key Value Store
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FoundationDbStoreInternalConfig {
+    /// The path of the cluster file. The default cluster file is used if this is not set.
+    pub cluster_file: Option<String>,
+    /// The common configuration of the key value store
+    common_config: CommonStoreInternalConfig,
+}
+
+impl AdminKeyValueStore for FoundationDbStoreInternal {
+    type Config = FoundationDbStoreInternalConfig;
+
+    fn get_name() -> String {
+        "foundationdb internal".to_string()
+    }
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Self, FoundationDbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let database = Arc::new(open_database(config)?);
+        let store = Arc::new(FoundationDbClient::new(database, namespace, &[])?);
+        let semaphore = config
+            .common_config
+            .max_concurrent_queries
+            .map(|n| Arc::new(Semaphore::new(n)));
+        let max_stream_queries = config.common_config.max_stream_queries;
+        Ok(Self {
+            store,
+            namespace: namespace.to_string(),
+            semaphore,
+            max_stream_queries,
+        })
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, FoundationDbStoreInternalError> {
+        let database = self.store.database.clone();
+        let store = Arc::new(FoundationDbClient::new(
+            database,
+            &self.namespace,
+            root_key,
+        )?);
+        Ok(Self {
+            store,
+            namespace: self.namespace.clone(),
+            semaphore: self.semaphore.clone(),
+            max_stream_queries: self.max_stream_queries,
+        })
+    }
+
+    async fn list_all(
+        config: &Self::Config,
+    ) -> Result<Vec<String>, FoundationDbStoreInternalError> {
+        let database = open_database(config)?;
+        let transaction = database.create_trx()?;
+        let key_values = transaction
+            .get_ranges_keyvalues(prefix_range(&[NAMESPACE_TAG]), false)
+            .try_collect::<Vec<_>>()
+            .await?;
+        key_values
+            .iter()
+            .map(|key_value| Ok(bcs::from_bytes(&key_value.key()[1..])?))
+            .collect()
+    }
+
+    async fn list_root_keys(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Vec<Vec<u8>>, FoundationDbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let database = open_database(config)?;
+        let prefix = namespace_prefix(ROOT_KEY_TAG, namespace)?;
+        let transaction = database.create_trx()?;
+        let key_values = transaction
+            .get_ranges_keyvalues(prefix_range(&prefix), false)
+            .try_collect::<Vec<_>>()
+            .await?;
+        key_values
+            .iter()
+            .map(|key_value| Ok(bcs::from_bytes(&key_value.key()[prefix.len()..])?))
+            .collect()
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), FoundationDbStoreInternalError> {
+        let database = open_database(config)?;
+        database
+            .run(|transaction, _maybe_committed| async move {
+                transaction.clear_range(&[NAMESPACE_TAG], &[DATA_TAG + 1]);
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn exists(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<bool, FoundationDbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let database = open_database(config)?;
+        let marker = namespace_prefix(NAMESPACE_TAG, namespace)?;
+        let transaction = database.create_trx()?;
+        Ok(transaction.get(&marker, false).await?.is_some())
+    }
+
+    async fn create(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), FoundationDbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let database = open_database(config)?;
+        let marker = namespace_prefix(NAMESPACE_TAG, namespace)?;
+        let created = database
+            .run(|transaction, _maybe_committed| {
+                let marker = &marker;
+                async move {
+                    if transaction.get(marker, false).await?.is_some() {
+                        return Ok(false);
+                    }
+                    transaction.set(marker, &[]);
+                    Ok(true)
+                }
+            })
+            .await?;
+        ensure!(
+            created,
+            FoundationDbStoreInternalError::NamespaceAlreadyExists
+        );
+        Ok(())
+    }
+
+    async fn delete(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), FoundationDbStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let database = open_database(config)?;
+        let prefixes = [NAMESPACE_TAG, ROOT_KEY_TAG, DATA_TAG]
+            .into_iter()
+            .map(|tag| namespace_prefix(tag, namespace))
+            .collect::<Result<Vec<_>, _>>()?;
+        database
+            .run(|transaction, _maybe_committed| {
+                let prefixes = &prefixes;
+                async move {
+                    // The namespace marker is the first of the prefixes, and contains no
+                    // other key.
+                    transaction.clear(&prefixes[0]);
+                    for prefix in &prefixes[1..] {
+                        transaction.clear_range(prefix, &prefix_end(prefix));
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+impl FoundationDbStoreInternal {
+    /// Obtains the semaphore lock on the database if needed.
+    async fn acquire(&self) -> Option<SemaphoreGuard<'_>> {
+        match &self.semaphore {
+            None => None,
+            Some(count) => Some(count.acquire().await),
+        }
+    }
+
+    fn check_namespace(namespace: &str) -> Result<(), FoundationDbStoreInternalError> {
+        if !namespace.is_empty()
+            && namespace.len() <= 255
+            && namespace
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Ok(());
+        }
+        Err(FoundationDbStoreInternalError::InvalidNamespace)
+    }
+}
+
+/// We limit the number of connections that can be done for tests.
+#[cfg(with_testing)]
+const TEST_FOUNDATION_DB_MAX_CONCURRENT_QUERIES: usize = 10;
+
+/// The number of connections in the stream is limited for tests.
+#[cfg(with_testing)]
+const TEST_FOUNDATION_DB_MAX_STREAM_QUERIES: usize = 10;
+
+#[cfg(with_testing)]
+impl TestKeyValueStore for JournalingKeyValueStore<FoundationDbStoreInternal> {
+    async fn new_test_config(
+    ) -> Result<FoundationDbStoreInternalConfig, FoundationDbStoreInternalError> {
+        let common_config = CommonStoreInternalConfig {
+            max_concurrent_queries: Some(TEST_FOUNDATION_DB_MAX_CONCURRENT_QUERIES),
+            max_stream_queries: TEST_FOUNDATION_DB_MAX_STREAM_QUERIES,
+        };
+        Ok(FoundationDbStoreInternalConfig {
+            cluster_file: None,
+            common_config,
+        })
+    }
+}
+
+/// The `FoundationDbStore` composed type with metrics
+#[cfg(with_metrics)]
+pub type FoundationDbStore = MeteredStore<
+    LruCachingStore<
+        MeteredStore<
+            ValueSplittingStore<MeteredStore<JournalingKeyValueStore<FoundationDbStoreInternal>>>,
+        >,
+    >,
+>;
+
+/// The `FoundationDbStore` composed type
+#[cfg(not(with_metrics))]
+pub type FoundationDbStore =
+    LruCachingStore<ValueSplittingStore<JournalingKeyValueStore<FoundationDbStoreInternal>>>;
+
+/// The `FoundationDbStoreConfig` input type
+pub type FoundationDbStoreConfig = LruCachingConfig<FoundationDbStoreInternalConfig>;
+
+impl FoundationDbStoreConfig {
+    /// Creates a `FoundationDbStoreConfig` from the inputs.
+    pub fn new(
+        cluster_file: Option<String>,
+        common_config: crate::store::CommonStoreConfig,
+    ) -> FoundationDbStoreConfig {
+        let inner_config = FoundationDbStoreInternalConfig {
+            cluster_file,
+            common_config: common_config.reduced(),
+        };
+        FoundationDbStoreConfig {
+            inner_config,
+            storage_cache_config: common_config.storage_cache_config,
+        }
+    }
+}
+
+/// The combined error type for the `FoundationDbStore`.
+pub type FoundationDbStoreError = ValueSplittingError<FoundationDbStoreInternalError>;
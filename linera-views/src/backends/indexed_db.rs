@@ -121,6 +121,20 @@ fn prefix_to_range(prefix: &[u8]) -> Result<web_sys::IdbKeyRange, wasm_bindgen::
     }
 }
 
+fn bounds_to_range(
+    start: &[u8],
+    end: &[u8],
+) -> Result<web_sys::IdbKeyRange, wasm_bindgen::JsValue> {
+    let lower = js_sys::Uint8Array::from(start);
+    let upper = js_sys::Uint8Array::from(end);
+    web_sys::IdbKeyRange::bound_with_lower_open_and_upper_open(
+        &lower.into(),
+        &upper.into(),
+        false,
+        true,
+    )
+}
+
 impl WithError for IndexedDbStore {
     type Error = IndexedDbStoreError;
 }
@@ -248,6 +262,15 @@ impl LocalWritableKeyValueStore for IndexedDbStore {
                         .delete_owned(prefix_to_range(&key_prefix[..])?)?
                         .await?;
                 }
+                WriteOperation::DeleteRange { start, end } => {
+                    if start < end {
+                        let start = self.full_key(&start);
+                        let end = self.full_key(&end);
+                        object_store
+                            .delete_owned(bounds_to_range(&start, &end)?)?
+                            .await?;
+                    }
+                }
             }
         }
         let mut key = self.start_key.clone();
@@ -7,9 +7,11 @@
 use std::sync::LazyLock;
 use std::{
     collections::{btree_map, hash_map::RandomState, BTreeMap},
+    ops::Bound::{self, Excluded, Included},
     sync::{Arc, Mutex},
 };
 
+use linera_base::time::{Duration, Instant};
 use linked_hash_map::LinkedHashMap;
 use serde::{Deserialize, Serialize};
 #[cfg(with_metrics)]
@@ -72,6 +74,10 @@ pub struct StorageCacheConfig {
     pub max_entry_size: usize,
     /// The maximum number of entries in the cache.
     pub max_cache_entries: usize,
+    /// For how long, in milliseconds, the absence of a key may be cached when we don't
+    /// have exclusive access to it. Zero disables such negative caching.
+    #[serde(default)]
+    pub negative_cache_ttl_ms: u64,
 }
 
 /// The maximum number of entries in the cache.
@@ -81,10 +87,14 @@ pub const DEFAULT_STORAGE_CACHE_CONFIG: StorageCacheConfig = StorageCacheConfig
     max_cache_size: 10000000,
     max_entry_size: 1000000,
     max_cache_entries: 1000,
+    negative_cache_ttl_ms: 0,
 };
 
 enum CacheEntry {
     DoesNotExist,
+    /// The entry did not exist, but other processes may have created it since. Only
+    /// trusted until the given instant.
+    DoesNotExistUntil(Instant),
     Exists,
     Value(Vec<u8>),
 }
@@ -137,16 +147,20 @@ impl LruPrefixCache {
     }
 
     /// Inserts an entry into the cache.
-    pub fn insert(&mut self, key: Vec<u8>, cache_entry: CacheEntry) {
+    pub fn insert(&mut self, key: Vec<u8>, mut cache_entry: CacheEntry) {
         let key_value_size = key.len() + cache_entry.size();
-        if (matches!(cache_entry, CacheEntry::DoesNotExist) && !self.has_exclusive_access)
-            || key_value_size > self.storage_cache_config.max_entry_size
-        {
+        if matches!(cache_entry, CacheEntry::DoesNotExist) && !self.has_exclusive_access {
+            match self.negative_cache_ttl() {
+                Some(ttl) => cache_entry = CacheEntry::DoesNotExistUntil(Instant::now() + ttl),
+                None => {
+                    self.remove(&key);
+                    return;
+                }
+            }
+        }
+        if key_value_size > self.storage_cache_config.max_entry_size {
             // Just forget about the entry.
-            if let Some(old_key_value_size) = self.queue.remove(&key) {
-                self.total_size -= old_key_value_size;
-                self.map.remove(&key);
-            };
+            self.remove(&key);
             return;
         }
         match self.map.entry(key.clone()) {
@@ -167,6 +181,32 @@ impl LruPrefixCache {
         self.trim_cache();
     }
 
+    /// Returns for how long the absence of a key may be cached without exclusive access.
+    fn negative_cache_ttl(&self) -> Option<Duration> {
+        let ttl_ms = self.storage_cache_config.negative_cache_ttl_ms;
+        (ttl_ms > 0).then(|| Duration::from_millis(ttl_ms))
+    }
+
+    /// Removes an entry from the cache, if present.
+    fn remove(&mut self, key: &[u8]) {
+        if let Some(key_value_size) = self.queue.remove(key) {
+            self.total_size -= key_value_size;
+            self.map.remove(key);
+        }
+    }
+
+    /// Removes the entry if it is a negative entry that has expired, and returns the
+    /// entry that is still valid, if any.
+    fn get_valid(&mut self, key: &[u8]) -> Option<&CacheEntry> {
+        if let Some(CacheEntry::DoesNotExistUntil(expiry)) = self.map.get(key) {
+            if *expiry <= Instant::now() {
+                self.remove(key);
+                return None;
+            }
+        }
+        self.map.get(key)
+    }
+
     /// Inserts a read_value entry into the cache.
     pub fn insert_read_value(&mut self, key: Vec<u8>, value: &Option<Vec<u8>>) {
         let cache_entry = match value {
@@ -188,8 +228,21 @@ impl LruPrefixCache {
     /// Marks cached keys that match the prefix as deleted. Importantly, this does not
     /// create new entries in the cache.
     pub fn delete_prefix(&mut self, key_prefix: &[u8]) {
+        self.delete_interval(get_interval(key_prefix.to_vec()));
+    }
+
+    /// Marks cached keys `key` with `start <= key < end` as deleted. Importantly, this
+    /// does not create new entries in the cache.
+    pub fn delete_range(&mut self, start: &[u8], end: &[u8]) {
+        if start < end {
+            self.delete_interval((Included(start.to_vec()), Excluded(end.to_vec())));
+        }
+    }
+
+    /// Marks cached keys in the interval as deleted.
+    fn delete_interval(&mut self, interval: (Bound<Vec<u8>>, Bound<Vec<u8>>)) {
         if self.has_exclusive_access {
-            for (key, value) in self.map.range_mut(get_interval(key_prefix.to_vec())) {
+            for (key, value) in self.map.range_mut(interval) {
                 *self.queue.get_mut(key).unwrap() = key.len();
                 self.total_size -= value.size();
                 *value = CacheEntry::DoesNotExist;
@@ -197,7 +250,7 @@ impl LruPrefixCache {
         } else {
             // Just forget about the entries.
             let mut keys = Vec::new();
-            for (key, _) in self.map.range(get_interval(key_prefix.to_vec())) {
+            for (key, _) in self.map.range(interval) {
                 keys.push(key.to_vec());
             }
             for key in keys {
@@ -214,10 +267,10 @@ impl LruPrefixCache {
     /// database. If `None` is returned, the entry might exist in the database but is
     /// not in the cache.
     pub fn query_read_value(&mut self, key: &[u8]) -> Option<Option<Vec<u8>>> {
-        let result = match self.map.get(key) {
+        let result = match self.get_valid(key) {
             None => None,
             Some(entry) => match entry {
-                CacheEntry::DoesNotExist => Some(None),
+                CacheEntry::DoesNotExist | CacheEntry::DoesNotExistUntil(_) => Some(None),
                 CacheEntry::Exists => None,
                 CacheEntry::Value(vec) => Some(Some(vec.clone())),
             },
@@ -233,10 +286,12 @@ impl LruPrefixCache {
     /// Returns `Some(true)` or `Some(false)` if we know that the entry does or does not
     /// exist in the database. Returns `None` if that information is not in the cache.
     pub fn query_contains_key(&mut self, key: &[u8]) -> Option<bool> {
-        let result = self
-            .map
-            .get(key)
-            .map(|entry| !matches!(entry, CacheEntry::DoesNotExist));
+        let result = self.get_valid(key).map(|entry| {
+            !matches!(
+                entry,
+                CacheEntry::DoesNotExist | CacheEntry::DoesNotExistUntil(_)
+            )
+        });
         if result.is_some() {
             // Put back the key on top
             let key_value_size = self.queue.remove(key).expect("key_value_size");
@@ -433,6 +488,9 @@ where
                     WriteOperation::DeletePrefix { key_prefix } => {
                         cache.delete_prefix(key_prefix);
                     }
+                    WriteOperation::DeleteRange { start, end } => {
+                        cache.delete_range(start, end);
+                    }
                 }
             }
         }
@@ -543,6 +601,7 @@ impl<K> LruCachingStore<K> {
                 max_cache_size: 0,
                 max_entry_size: 0,
                 max_cache_entries: 0,
+                negative_cache_ttl_ms: 0,
             },
             Some(cache) => {
                 let cache = cache.lock().unwrap();
@@ -563,3 +622,37 @@ impl<K> LruCachingStore<K> {
 /// A memory store with caching.
 #[cfg(with_testing)]
 pub type LruCachingMemoryStore = LruCachingStore<MemoryStore>;
+
+#[cfg(test)]
+mod tests {
+    use linera_base::time::Duration;
+
+    use super::{LruPrefixCache, StorageCacheConfig, DEFAULT_STORAGE_CACHE_CONFIG};
+
+    #[test]
+    fn test_negative_caching() {
+        let mut cache = LruPrefixCache::new(DEFAULT_STORAGE_CACHE_CONFIG);
+        cache.insert_contains_key(vec![1], false);
+        assert_eq!(cache.query_contains_key(&[1]), None);
+
+        let mut cache = LruPrefixCache::new(StorageCacheConfig {
+            negative_cache_ttl_ms: 50,
+            ..DEFAULT_STORAGE_CACHE_CONFIG
+        });
+        cache.insert_contains_key(vec![1], false);
+        cache.insert_read_value(vec![2], &None);
+        assert_eq!(cache.query_contains_key(&[1]), Some(false));
+        assert_eq!(cache.query_read_value(&[2]), Some(None));
+
+        // Writes replace negative entries.
+        cache.insert_read_value(vec![1], &Some(vec![3]));
+        assert_eq!(cache.query_contains_key(&[1]), Some(true));
+        cache.delete_prefix(&[2]);
+        assert_eq!(cache.query_read_value(&[2]), None);
+
+        cache.insert_contains_key(vec![4], false);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.query_contains_key(&[4]), None);
+        assert_eq!(cache.queue.len(), 1);
+    }
+}
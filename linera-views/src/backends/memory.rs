@@ -252,6 +252,17 @@ impl WritableKeyValueStore for MemoryStore {
                         map.remove(&key);
                     }
                 }
+                WriteOperation::DeleteRange { start, end } => {
+                    if start < end {
+                        let key_list = map
+                            .range(start..end)
+                            .map(|x| x.0.to_vec())
+                            .collect::<Vec<_>>();
+                        for key in key_list {
+                            map.remove(&key);
+                        }
+                    }
+                }
             }
         }
         Ok(())
@@ -0,0 +1,811 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`crate::store::KeyValueStore`] for migrating the data from one store to another
+//! without interrupting the service.
+//!
+//! All the writes go to both stores, while the reads are served by one of them, as given by the
+//! [`MigrationMode`]:
+//! * While the new store is being filled, values are read from the old store. The new store is
+//!   read as well, for point queries, in order to count divergences between the two stores.
+//!   Failing to write to the new store doesn't fail the write, since the old store remains the
+//!   reference, but the keys are recorded in the old store.
+//! * Once the new store has been filled, values are read from the new store. Point queries fall
+//!   back to the old store for the keys that are missing in the new store, or if the new store
+//!   fails. Writes must then succeed in both stores, so that the fallback never returns values
+//!   that are out of date.
+//!
+//! The data written before the migration started is copied by [`MigratingStore::backfill`],
+//! while the store is in use. The backfill ends with [`MigratingStore::verify`], which records
+//! in the new store that it holds the same data as the old one. Connecting in
+//! [`MigrationMode::ReadNew`] is refused until then, or if writes to the new store have failed
+//! since, because the new store alone serves the reads by prefix.
+
+use std::collections::BTreeSet;
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+#[cfg(with_metrics)]
+use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec};
+
+#[cfg(with_testing)]
+use crate::store::TestKeyValueStore;
+use crate::{
+    backends::dual::{DualStoreError, DualStoreKeyValues, DualStoreKeys},
+    batch::{Batch, WriteOperation},
+    common::get_range_prefix,
+    store::{
+        AdminKeyValueStore, KeyIterable as _, KeyValueStore, ReadableKeyValueStore, WithError,
+        WritableKeyValueStore,
+    },
+};
+
+/// The root key under which a migration keeps its records, in both stores. It is not listed
+/// by [`MigratingStore::list_root_keys`].
+pub const MIGRATION_ROOT_KEY: &[u8] = b"\xffmigration";
+
+/// Key tags of the records of a migration, under [`MIGRATION_ROOT_KEY`].
+#[repr(u8)]
+enum RecordTag {
+    /// Prefix for the keys, in the old store, whose write failed in the new store.
+    FailedKey,
+    /// Prefix for the key prefixes, in the old store, whose deletion failed in the new store.
+    FailedKeyPrefix,
+    /// The key, in the new store, that is set once its data was verified.
+    Verified,
+}
+
+#[cfg(with_metrics)]
+/// The number of reads for which the two stores of a migration returned different results.
+static MIGRATION_DIVERGENCE_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "num_migration_divergences",
+        "Number of reads for which the two stores of a migration returned different results",
+        &["operation"],
+    )
+});
+
+#[cfg(with_metrics)]
+/// The number of reads from the new store that were served by the old store instead.
+static MIGRATION_FALLBACK_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "num_migration_fallbacks",
+        "Number of reads from the new store of a migration that were served by the old store",
+        &["operation", "reason"],
+    )
+});
+
+#[cfg(with_metrics)]
+/// The number of operations that failed in the store that is not the reference.
+static MIGRATION_ERROR_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "num_migration_errors",
+        "Number of operations that failed in the store of a migration that is not the reference",
+        &["operation"],
+    )
+});
+
+/// Which store serves the reads during a migration.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MigrationMode {
+    /// Values are read from the old store and compared with the new store.
+    #[default]
+    ReadOld,
+    /// Values are read from the new store, falling back to the old store.
+    ReadNew,
+}
+
+impl std::fmt::Display for MigrationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationMode::ReadOld => write!(f, "read_old"),
+            MigrationMode::ReadNew => write!(f, "read_new"),
+        }
+    }
+}
+
+/// The configuration of a [`MigratingStore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigratingStoreConfig<C1, C2> {
+    /// The config of the store that the data is migrated from.
+    pub old_config: C1,
+    /// The config of the store that the data is migrated to.
+    pub new_config: C2,
+    /// Which store serves the reads.
+    pub mode: MigrationMode,
+}
+
+/// A store writing to two existing stores, in order to migrate the data from the old one to
+/// the new one.
+///
+/// Errors from the old store are reported as [`DualStoreError::First`] and errors from the new
+/// store as [`DualStoreError::Second`].
+#[derive(Clone)]
+pub struct MigratingStore<S1, S2> {
+    /// The store that the data is migrated from.
+    old_store: S1,
+    /// The store that the data is migrated to.
+    new_store: S2,
+    /// The old store, under [`MIGRATION_ROOT_KEY`].
+    old_records: S1,
+    /// The new store, under [`MIGRATION_ROOT_KEY`].
+    new_records: S2,
+    /// The root key of `old_store` and `new_store`.
+    root_key: Vec<u8>,
+    /// Which store serves the reads.
+    mode: MigrationMode,
+}
+
+impl<S1, S2> MigratingStore<S1, S2>
+where
+    S1: AdminKeyValueStore,
+    S2: AdminKeyValueStore,
+{
+    /// Creates a store migrating the data of `old_store` to `new_store`, neither of which has a
+    /// root key.
+    ///
+    /// Unlike [`AdminKeyValueStore::connect`], this does not check that the new store was
+    /// verified in [`MigrationMode::ReadNew`].
+    pub fn new(
+        old_store: S1,
+        new_store: S2,
+        mode: MigrationMode,
+    ) -> Result<Self, DualStoreError<S1::Error, S2::Error>> {
+        let old_records = old_store
+            .clone_with_root_key(MIGRATION_ROOT_KEY)
+            .map_err(DualStoreError::First)?;
+        let new_records = new_store
+            .clone_with_root_key(MIGRATION_ROOT_KEY)
+            .map_err(DualStoreError::Second)?;
+        Ok(Self {
+            old_store,
+            new_store,
+            old_records,
+            new_records,
+            root_key: Vec::new(),
+            mode,
+        })
+    }
+}
+
+impl<S1, S2> WithError for MigratingStore<S1, S2>
+where
+    S1: WithError,
+    S2: WithError,
+{
+    type Error = DualStoreError<S1::Error, S2::Error>;
+}
+
+/// Records that `count` results of `operation` differed between the two stores.
+fn record_divergences(operation: &str, count: usize) {
+    if count == 0 {
+        return;
+    }
+    tracing::debug!("{count} results of {operation} differ between the stores of the migration");
+    #[cfg(with_metrics)]
+    MIGRATION_DIVERGENCE_COUNT
+        .with_label_values(&[operation])
+        .inc_by(count as u64);
+}
+
+/// Records that a result of `operation` was served by the old store, because of `reason`.
+fn record_fallback(operation: &str, reason: &str) {
+    tracing::trace!("A result of {operation} is served by the old store: {reason}");
+    #[cfg(with_metrics)]
+    MIGRATION_FALLBACK_COUNT
+        .with_label_values(&[operation, reason])
+        .inc();
+}
+
+/// Records that `operation` failed in the store that is not the reference.
+fn record_error(operation: &str, error: &impl std::fmt::Display) {
+    tracing::warn!("Operation {operation} failed in the store that is not the reference: {error}");
+    #[cfg(with_metrics)]
+    MIGRATION_ERROR_COUNT.with_label_values(&[operation]).inc();
+}
+
+impl<S1, S2> MigratingStore<S1, S2>
+where
+    S1: ReadableKeyValueStore + Send + Sync,
+    S2: ReadableKeyValueStore + Send + Sync,
+{
+    /// Reads the values of `keys` from the new store, using the old store for the missing
+    /// ones.
+    async fn read_new_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, DualStoreError<S1::Error, S2::Error>> {
+        let mut values = match self.new_store.read_multi_values_bytes(keys.clone()).await {
+            Ok(values) => values,
+            Err(error) => {
+                record_error("read_multi_values_bytes", &error);
+                record_fallback("read_multi_values_bytes", "error");
+                return self
+                    .old_store
+                    .read_multi_values_bytes(keys)
+                    .await
+                    .map_err(DualStoreError::First);
+            }
+        };
+        let (indices, missing_keys): (Vec<_>, Vec<_>) = keys
+            .into_iter()
+            .zip(&values)
+            .enumerate()
+            .filter(|(_, (_, value))| value.is_none())
+            .map(|(index, (key, _))| (index, key))
+            .unzip();
+        if missing_keys.is_empty() {
+            return Ok(values);
+        }
+        let old_values = self
+            .old_store
+            .read_multi_values_bytes(missing_keys)
+            .await
+            .map_err(DualStoreError::First)?;
+        for (index, old_value) in indices.into_iter().zip(old_values) {
+            if old_value.is_some() {
+                record_fallback("read_multi_values_bytes", "missing");
+                values[index] = old_value;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Tells which of `keys` exist in the new store, using the old store for the missing ones.
+    async fn new_contains_keys(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, DualStoreError<S1::Error, S2::Error>> {
+        let mut results = match self.new_store.contains_keys(keys.clone()).await {
+            Ok(results) => results,
+            Err(error) => {
+                record_error("contains_keys", &error);
+                record_fallback("contains_keys", "error");
+                return self
+                    .old_store
+                    .contains_keys(keys)
+                    .await
+                    .map_err(DualStoreError::First);
+            }
+        };
+        let (indices, missing_keys): (Vec<_>, Vec<_>) = keys
+            .into_iter()
+            .zip(&results)
+            .enumerate()
+            .filter(|(_, (_, found))| !**found)
+            .map(|(index, (key, _))| (index, key))
+            .unzip();
+        if missing_keys.is_empty() {
+            return Ok(results);
+        }
+        let old_results = self
+            .old_store
+            .contains_keys(missing_keys)
+            .await
+            .map_err(DualStoreError::First)?;
+        for (index, found) in indices.into_iter().zip(old_results) {
+            if found {
+                record_fallback("contains_keys", "missing");
+                results[index] = true;
+            }
+        }
+        Ok(results)
+    }
+}
+
+impl<S1, S2> MigratingStore<S1, S2>
+where
+    S1: WritableKeyValueStore + WithError,
+    S2: WithError,
+{
+    /// Records in the old store the keys of `batch`, whose write failed in the new store.
+    async fn record_failed_write(
+        &self,
+        batch: &Batch,
+    ) -> Result<(), DualStoreError<S1::Error, S2::Error>> {
+        let mut records = Batch::new();
+        for operation in &batch.operations {
+            let (tag, key) = match operation {
+                WriteOperation::Delete { key } | WriteOperation::Put { key, .. } => {
+                    (RecordTag::FailedKey, key.as_slice())
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    (RecordTag::FailedKeyPrefix, key_prefix.as_slice())
+                }
+                // The keys of the range all have the prefix the bounds share.
+                WriteOperation::DeleteRange { start, end } => (
+                    RecordTag::FailedKeyPrefix,
+                    get_range_prefix(start, Some(end)),
+                ),
+            };
+            let mut record_key = vec![tag as u8];
+            bcs::serialize_into(&mut record_key, &(&self.root_key, key))?;
+            records.put_key_value_bytes(record_key, Vec::new());
+        }
+        self.old_records
+            .write_batch(records)
+            .await
+            .map_err(DualStoreError::First)
+    }
+}
+
+impl<S1, S2> MigratingStore<S1, S2>
+where
+    S1: KeyValueStore + Send + Sync,
+    S2: KeyValueStore + Send + Sync,
+    S1::Error: Send,
+    S2::Error: Send,
+{
+    /// Returns the root keys and keys whose write failed in the new store, since the last
+    /// backfill. Key prefixes whose deletion failed are returned as keys too.
+    pub async fn failed_writes(
+        &self,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DualStoreError<S1::Error, S2::Error>> {
+        let records = self
+            .old_records
+            .find_keys_by_prefix(&[])
+            .await
+            .map_err(DualStoreError::First)?;
+        let mut failed_writes = Vec::new();
+        for record_key in records.iterator() {
+            let record_key = record_key.map_err(DualStoreError::First)?;
+            failed_writes.push(bcs::from_bytes(&record_key[1..])?);
+        }
+        Ok(failed_writes)
+    }
+
+    /// Returns an error unless the new store was verified to hold the same data as the old
+    /// store, without any failed write since.
+    pub async fn check_verified(&self) -> Result<(), DualStoreError<S1::Error, S2::Error>> {
+        let verified = self
+            .new_records
+            .contains_key(&[RecordTag::Verified as u8])
+            .await
+            .map_err(DualStoreError::Second)?;
+        if !verified || !self.failed_writes().await?.is_empty() {
+            return Err(DualStoreError::MigrationNotVerified);
+        }
+        Ok(())
+    }
+
+    /// Copies the data of the given root keys to the new store, where it differs from the old
+    /// store, then verifies it with [`Self::verify`]. Returns the number of keys copied or
+    /// deleted.
+    ///
+    /// The store may be in use meanwhile, in [`MigrationMode::ReadOld`].
+    pub async fn backfill(
+        &self,
+        root_keys: &[Vec<u8>],
+        batch_size: usize,
+    ) -> Result<u64, DualStoreError<S1::Error, S2::Error>> {
+        // The failures recorded from now on are not necessarily repaired by the copy.
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(Vec::new());
+        self.new_records
+            .write_batch(batch.clone())
+            .await
+            .map_err(DualStoreError::Second)?;
+        self.old_records
+            .write_batch(batch)
+            .await
+            .map_err(DualStoreError::First)?;
+        let mut keys_copied = 0;
+        for root_key in root_keys {
+            keys_copied += self.compare_root_key(root_key, batch_size, true).await?;
+        }
+        let divergences = self.verify(root_keys, batch_size).await?;
+        if divergences > 0 {
+            tracing::warn!("{divergences} keys were modified in the old store during the backfill");
+        }
+        Ok(keys_copied)
+    }
+
+    /// Compares the data of the given root keys in the two stores, and returns the number of
+    /// keys that differ. If there are none, and no write to the new store failed since the
+    /// last backfill, this is recorded in the new store, so that it can serve the reads.
+    pub async fn verify(
+        &self,
+        root_keys: &[Vec<u8>],
+        batch_size: usize,
+    ) -> Result<u64, DualStoreError<S1::Error, S2::Error>> {
+        let mut divergences = 0;
+        for root_key in root_keys {
+            divergences += self.compare_root_key(root_key, batch_size, false).await?;
+        }
+        if divergences == 0 && self.failed_writes().await?.is_empty() {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![RecordTag::Verified as u8], Vec::new());
+            self.new_records
+                .write_batch(batch)
+                .await
+                .map_err(DualStoreError::Second)?;
+        }
+        Ok(divergences)
+    }
+
+    /// Returns the number of keys of `root_key` whose values differ between the two stores,
+    /// writing the values of the old store to the new one if `repair` is set.
+    async fn compare_root_key(
+        &self,
+        root_key: &[u8],
+        batch_size: usize,
+        repair: bool,
+    ) -> Result<u64, DualStoreError<S1::Error, S2::Error>> {
+        let old_store = self
+            .old_store
+            .clone_with_root_key(root_key)
+            .map_err(DualStoreError::First)?;
+        let new_store = self
+            .new_store
+            .clone_with_root_key(root_key)
+            .map_err(DualStoreError::Second)?;
+        let mut keys = BTreeSet::new();
+        let old_keys = old_store
+            .find_keys_by_prefix(&[])
+            .await
+            .map_err(DualStoreError::First)?;
+        for key in old_keys.iterator() {
+            keys.insert(key.map_err(DualStoreError::First)?.to_vec());
+        }
+        let new_keys = new_store
+            .find_keys_by_prefix(&[])
+            .await
+            .map_err(DualStoreError::Second)?;
+        for key in new_keys.iterator() {
+            keys.insert(key.map_err(DualStoreError::Second)?.to_vec());
+        }
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        let mut divergences = 0;
+        for keys in keys.chunks(batch_size.max(1)) {
+            let (old_values, new_values) = futures::join!(
+                old_store.read_multi_values_bytes(keys.to_vec()),
+                new_store.read_multi_values_bytes(keys.to_vec())
+            );
+            let old_values = old_values.map_err(DualStoreError::First)?;
+            let new_values = new_values.map_err(DualStoreError::Second)?;
+            let mut batch = Batch::new();
+            for ((key, old_value), new_value) in keys.iter().zip(old_values).zip(new_values) {
+                if old_value == new_value {
+                    continue;
+                }
+                divergences += 1;
+                match old_value {
+                    Some(value) => batch.put_key_value_bytes(key.clone(), value),
+                    None => batch.delete_key(key.clone()),
+                }
+            }
+            if repair && !batch.is_empty() {
+                new_store
+                    .write_batch(batch)
+                    .await
+                    .map_err(DualStoreError::Second)?;
+            }
+        }
+        Ok(divergences)
+    }
+}
+
+impl<S1, S2> ReadableKeyValueStore for MigratingStore<S1, S2>
+where
+    S1: ReadableKeyValueStore + Send + Sync,
+    S2: ReadableKeyValueStore + Send + Sync,
+    // The results of both stores are awaited together.
+    S1::Error: Send,
+    S2::Error: Send,
+{
+    // TODO(#2524): consider changing MAX_KEY_SIZE into a function.
+    const MAX_KEY_SIZE: usize = if S1::MAX_KEY_SIZE < S2::MAX_KEY_SIZE {
+        S1::MAX_KEY_SIZE
+    } else {
+        S2::MAX_KEY_SIZE
+    };
+
+    type Keys = DualStoreKeys<S1::Keys, S2::Keys>;
+    type KeyValues = DualStoreKeyValues<S1::KeyValues, S2::KeyValues>;
+
+    fn max_stream_queries(&self) -> usize {
+        match self.mode {
+            MigrationMode::ReadOld => self.old_store.max_stream_queries(),
+            MigrationMode::ReadNew => self.new_store.max_stream_queries(),
+        }
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.mode {
+            MigrationMode::ReadOld => {
+                let (old_value, new_value) = futures::join!(
+                    self.old_store.read_value_bytes(key),
+                    self.new_store.read_value_bytes(key)
+                );
+                let old_value = old_value.map_err(DualStoreError::First)?;
+                match new_value {
+                    Ok(new_value) => {
+                        record_divergences("read_value_bytes", usize::from(new_value != old_value))
+                    }
+                    Err(error) => record_error("read_value_bytes", &error),
+                }
+                Ok(old_value)
+            }
+            MigrationMode::ReadNew => {
+                let mut values = self.read_new_multi_values_bytes(vec![key.to_vec()]).await?;
+                Ok(values.pop().flatten())
+            }
+        }
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        match self.mode {
+            MigrationMode::ReadOld => {
+                let (old_result, new_result) = futures::join!(
+                    self.old_store.contains_key(key),
+                    self.new_store.contains_key(key)
+                );
+                let old_result = old_result.map_err(DualStoreError::First)?;
+                match new_result {
+                    Ok(new_result) => {
+                        record_divergences("contains_key", usize::from(new_result != old_result))
+                    }
+                    Err(error) => record_error("contains_key", &error),
+                }
+                Ok(old_result)
+            }
+            MigrationMode::ReadNew => {
+                let results = self.new_contains_keys(vec![key.to_vec()]).await?;
+                Ok(results.first().copied().unwrap_or_default())
+            }
+        }
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        match self.mode {
+            MigrationMode::ReadOld => {
+                let (old_results, new_results) = futures::join!(
+                    self.old_store.contains_keys(keys.clone()),
+                    self.new_store.contains_keys(keys)
+                );
+                let old_results = old_results.map_err(DualStoreError::First)?;
+                match new_results {
+                    Ok(new_results) => {
+                        let count = old_results
+                            .iter()
+                            .zip(&new_results)
+                            .filter(|(a, b)| a != b)
+                            .count();
+                        record_divergences("contains_keys", count);
+                    }
+                    Err(error) => record_error("contains_keys", &error),
+                }
+                Ok(old_results)
+            }
+            MigrationMode::ReadNew => self.new_contains_keys(keys).await,
+        }
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        match self.mode {
+            MigrationMode::ReadOld => {
+                let (old_values, new_values) = futures::join!(
+                    self.old_store.read_multi_values_bytes(keys.clone()),
+                    self.new_store.read_multi_values_bytes(keys)
+                );
+                let old_values = old_values.map_err(DualStoreError::First)?;
+                match new_values {
+                    Ok(new_values) => {
+                        let count = old_values
+                            .iter()
+                            .zip(&new_values)
+                            .filter(|(a, b)| a != b)
+                            .count();
+                        record_divergences("read_multi_values_bytes", count);
+                    }
+                    Err(error) => record_error("read_multi_values_bytes", &error),
+                }
+                Ok(old_values)
+            }
+            MigrationMode::ReadNew => self.read_new_multi_values_bytes(keys).await,
+        }
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        if self.mode == MigrationMode::ReadNew {
+            match self.new_store.find_keys_by_prefix(key_prefix).await {
+                Ok(keys) => return Ok(DualStoreKeys::Second(keys)),
+                Err(error) => {
+                    record_error("find_keys_by_prefix", &error);
+                    record_fallback("find_keys_by_prefix", "error");
+                }
+            }
+        }
+        Ok(DualStoreKeys::First(
+            self.old_store
+                .find_keys_by_prefix(key_prefix)
+                .await
+                .map_err(DualStoreError::First)?,
+        ))
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        if self.mode == MigrationMode::ReadNew {
+            match self.new_store.find_key_values_by_prefix(key_prefix).await {
+                Ok(key_values) => return Ok(DualStoreKeyValues::Second(key_values)),
+                Err(error) => {
+                    record_error("find_key_values_by_prefix", &error);
+                    record_fallback("find_key_values_by_prefix", "error");
+                }
+            }
+        }
+        Ok(DualStoreKeyValues::First(
+            self.old_store
+                .find_key_values_by_prefix(key_prefix)
+                .await
+                .map_err(DualStoreError::First)?,
+        ))
+    }
+}
+
+impl<S1, S2> WritableKeyValueStore for MigratingStore<S1, S2>
+where
+    S1: WritableKeyValueStore + WithError + Send + Sync,
+    S2: WritableKeyValueStore + WithError + Send + Sync,
+    S1::Error: Send,
+    S2::Error: Send,
+{
+    const MAX_VALUE_SIZE: usize = if S1::MAX_VALUE_SIZE < S2::MAX_VALUE_SIZE {
+        S1::MAX_VALUE_SIZE
+    } else {
+        S2::MAX_VALUE_SIZE
+    };
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let (old_result, new_result) = futures::join!(
+            self.old_store.write_batch(batch.clone()),
+            self.new_store.write_batch(batch.clone())
+        );
+        old_result.map_err(DualStoreError::First)?;
+        let Err(error) = new_result else {
+            return Ok(());
+        };
+        record_error("write_batch", &error);
+        // The new store may now be out of date for these keys, until they are copied again.
+        self.record_failed_write(&batch).await?;
+        match self.mode {
+            MigrationMode::ReadOld => Ok(()),
+            MigrationMode::ReadNew => Err(DualStoreError::Second(error)),
+        }
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        self.old_store
+            .clear_journal()
+            .await
+            .map_err(DualStoreError::First)?;
+        match (self.mode, self.new_store.clear_journal().await) {
+            (_, Ok(())) => Ok(()),
+            (MigrationMode::ReadOld, Err(error)) => {
+                record_error("clear_journal", &error);
+                Ok(())
+            }
+            (MigrationMode::ReadNew, Err(error)) => Err(DualStoreError::Second(error)),
+        }
+    }
+}
+
+impl<S1, S2> AdminKeyValueStore for MigratingStore<S1, S2>
+where
+    S1: KeyValueStore + Send + Sync,
+    S2: KeyValueStore + Send + Sync,
+    S1::Error: Send,
+    S2::Error: Send,
+{
+    type Config = MigratingStoreConfig<S1::Config, S2::Config>;
+
+    fn get_name() -> String {
+        format!("migrating {} to {}", S1::get_name(), S2::get_name())
+    }
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let old_store = S1::connect(&config.old_config, namespace)
+            .await
+            .map_err(DualStoreError::First)?;
+        // The namespace may not have been copied to the new store yet.
+        let new_store = S2::maybe_create_and_connect(&config.new_config, namespace)
+            .await
+            .map_err(DualStoreError::Second)?;
+        let store = Self::new(old_store, new_store, config.mode)?;
+        if config.mode == MigrationMode::ReadNew {
+            store.check_verified().await?;
+        }
+        Ok(store)
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, Self::Error> {
+        let old_store = self
+            .old_store
+            .clone_with_root_key(root_key)
+            .map_err(DualStoreError::First)?;
+        let new_store = self
+            .new_store
+            .clone_with_root_key(root_key)
+            .map_err(DualStoreError::Second)?;
+        Ok(Self {
+            root_key: root_key.to_vec(),
+            ..Self::new(old_store, new_store, self.mode)?
+        })
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        S1::list_all(&config.old_config)
+            .await
+            .map_err(DualStoreError::First)
+    }
+
+    async fn list_root_keys(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let mut root_keys = S1::list_root_keys(&config.old_config, namespace)
+            .await
+            .map_err(DualStoreError::First)?;
+        root_keys.retain(|root_key| root_key != MIGRATION_ROOT_KEY);
+        Ok(root_keys)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        S1::exists(&config.old_config, namespace)
+            .await
+            .map_err(DualStoreError::First)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        S1::create(&config.old_config, namespace)
+            .await
+            .map_err(DualStoreError::First)?;
+        if !S2::exists(&config.new_config, namespace)
+            .await
+            .map_err(DualStoreError::Second)?
+        {
+            S2::create(&config.new_config, namespace)
+                .await
+                .map_err(DualStoreError::Second)?;
+        }
+        Ok(())
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        S1::delete(&config.old_config, namespace)
+            .await
+            .map_err(DualStoreError::First)?;
+        S2::delete(&config.new_config, namespace)
+            .await
+            .map_err(DualStoreError::Second)?;
+        Ok(())
+    }
+}
+
+#[cfg(with_testing)]
+impl<S1, S2> TestKeyValueStore for MigratingStore<S1, S2>
+where
+    S1: TestKeyValueStore + Send + Sync,
+    S2: TestKeyValueStore + Send + Sync,
+    S1::Error: Send,
+    S2::Error: Send,
+{
+    async fn new_test_config() -> Result<Self::Config, Self::Error> {
+        let old_config = S1::new_test_config().await.map_err(DualStoreError::First)?;
+        let new_config = S2::new_test_config()
+            .await
+            .map_err(DualStoreError::Second)?;
+        Ok(MigratingStoreConfig {
+            old_config,
+            new_config,
+            mode: MigrationMode::ReadOld,
+        })
+    }
+}
@@ -14,6 +14,8 @@ pub mod lru_caching;
 
 pub mod dual;
 
+pub mod migrating;
+
 #[cfg(with_scylladb)]
 pub mod scylla_db;
 
@@ -23,5 +25,14 @@ pub mod rocks_db;
 #[cfg(with_dynamodb)]
 pub mod dynamo_db;
 
+#[cfg(with_foundationdb)]
+pub mod foundation_db;
+
+#[cfg(with_postgres)]
+pub mod postgres;
+
+#[cfg(with_redis)]
+pub mod redis_db;
+
 #[cfg(with_indexeddb)]
 pub mod indexed_db;
@@ -0,0 +1,557 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`crate::store::KeyValueStore`] for Redis, and compatible servers such as
+//! KeyDB.
+//!
+//! The values of a root key are stored in a hash, and its keys are also stored in a sorted
+//! set with a score of zero, so that they can be found by prefix in lexicographic order.
+//! The entries of a Redis server are:
+//! * `linera:namespaces`, the set of the namespaces,
+//! * `linera:{namespace}:root_keys`, the set of the root keys written to,
+//! * `linera:{namespace}:values:{root_key}`, the hash of the values of a root key,
+//! * `linera:{namespace}:keys:{root_key}`, the sorted set of the keys of a root key.
+//!
+//! Each batch is written atomically by a `MULTI`/`EXEC` transaction. Since Redis keeps its
+//! data in memory, this is mostly suited to test networks, unless the server is configured
+//! to persist its data. The `persistent` option enables append-only file persistence when
+//! connecting.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use linera_base::ensure;
+use redis::{aio::ConnectionManager, AsyncCommands as _, Script};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(with_metrics)]
+use crate::metering::MeteredStore;
+#[cfg(with_testing)]
+use crate::store::TestKeyValueStore;
+use crate::{
+    batch::Batch,
+    common::get_upper_bound_option,
+    lru_caching::{LruCachingConfig, LruCachingStore},
+    store::{
+        AdminKeyValueStore, CommonStoreInternalConfig, KeyValueStoreError, ReadableKeyValueStore,
+        WithError, WritableKeyValueStore,
+    },
+    value_splitting::{ValueSplittingError, ValueSplittingStore},
+};
+
+/// The set of the namespaces.
+const NAMESPACES: &str = "linera:namespaces";
+
+/// The maximal size of a key. Redis accepts up to 512 MB, but the keys are also the
+/// members of a sorted set, which are better kept small.
+const MAX_KEY_SIZE: usize = 1024 * 1024;
+
+/// The maximal size of a value. Redis strings have at most 512 MB.
+const MAX_VALUE_SIZE: usize = 512 * 1024 * 1024 - 1;
+
+/// Deletes the keys of a root key within a lexicographic range.
+/// `KEYS`: the sorted set of the keys, and the hash of the values.
+/// `ARGV`: the bounds of the range, in the syntax of `ZRANGEBYLEX`.
+const DELETE_RANGE_SCRIPT: &str = r"
+local keys = redis.call('ZRANGEBYLEX', KEYS[1], ARGV[1], ARGV[2])
+for i = 1, #keys, 1000 do
+    redis.call('HDEL', KEYS[2], unpack(keys, i, math.min(i + 999, #keys)))
+end
+redis.call('ZREMRANGEBYLEX', KEYS[1], ARGV[1], ARGV[2])
+return #keys
+";
+
+/// Returns the keys of a root key within a lexicographic range, each followed by its
+/// value.
+/// `KEYS`: the sorted set of the keys, and the hash of the values.
+/// `ARGV`: the bounds of the range, in the syntax of `ZRANGEBYLEX`.
+const FIND_KEY_VALUES_SCRIPT: &str = r"
+local keys = redis.call('ZRANGEBYLEX', KEYS[1], ARGV[1], ARGV[2])
+local result = {}
+for i, key in ipairs(keys) do
+    result[2 * i - 1] = key
+    result[2 * i] = redis.call('HGET', KEYS[2], key)
+end
+return result
+";
+
+/// Returns the entry containing the given kind of data of a namespace.
+fn namespace_entry(namespace: &str, kind: &str) -> Vec<u8> {
+    format!("linera:{namespace}:{kind}").into_bytes()
+}
+
+/// Returns the entry containing the given kind of data of a root key.
+fn root_key_entry(namespace: &str, kind: &str, root_key: &[u8]) -> Vec<u8> {
+    let mut entry = format!("linera:{namespace}:{kind}:").into_bytes();
+    entry.extend_from_slice(root_key);
+    entry
+}
+
+/// Returns the bounds of the keys starting with `key_prefix`, in the syntax of
+/// `ZRANGEBYLEX`.
+fn prefix_bounds(key_prefix: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut min = vec![b'['];
+    min.extend_from_slice(key_prefix);
+    let max = match get_upper_bound_option(key_prefix) {
+        None => vec![b'+'],
+        Some(upper_bound) => {
+            let mut max = vec![b'('];
+            max.extend(upper_bound);
+            max
+        }
+    };
+    (min, max)
+}
+
+/// Returns the bounds of the keys `key` such that `start <= key < end`, in the syntax of
+/// `ZRANGEBYLEX`.
+fn range_bounds(start: &[u8], end: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut min = vec![b'['];
+    min.extend_from_slice(start);
+    let mut max = vec![b'('];
+    max.extend_from_slice(end);
+    (min, max)
+}
+
+/// The client for Redis:
+/// * The connection, which reconnects if needed
+/// * The entries of the root key
+/// * Whether the root key was written to by this client
+#[derive(Clone)]
+pub struct RedisStoreInternal {
+    connection: ConnectionManager,
+    namespace: String,
+    root_key: Vec<u8>,
+    keys_entry: Vec<u8>,
+    values_entry: Vec<u8>,
+    root_key_written: Arc<AtomicBool>,
+    max_stream_queries: usize,
+}
+
+/// The error type for [`RedisStoreInternal`]
+#[derive(Error, Debug)]
+pub enum RedisStoreInternalError {
+    /// BCS serialization error.
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
+
+    /// The key must have at most `MAX_KEY_SIZE` bytes
+    #[error("The key must have at most MAX_KEY_SIZE")]
+    KeyTooLong,
+
+    /// The value must have at most `MAX_VALUE_SIZE` bytes
+    #[error("The value must have at most MAX_VALUE_SIZE")]
+    ValueTooLong,
+
+    /// Namespace contains forbidden characters
+    #[error("Namespace contains forbidden characters")]
+    InvalidNamespace,
+
+    /// The namespace already exists
+    #[error("The namespace already exists")]
+    NamespaceAlreadyExists,
+
+    /// The server returned keys and values that don't match
+    #[error("The server returned keys and values that don't match")]
+    InvalidResponse,
+
+    /// An error in Redis
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
+}
+
+impl KeyValueStoreError for RedisStoreInternalError {
+    const BACKEND: &'static str = "redis";
+}
+
+impl WithError for RedisStoreInternal {
+    type Error = RedisStoreInternalError;
+}
+
+impl RedisStoreInternal {
+    fn new(
+        connection: ConnectionManager,
+        namespace: &str,
+        root_key: &[u8],
+        max_stream_queries: usize,
+    ) -> Self {
+        Self {
+            connection,
+            namespace: namespace.to_string(),
+            root_key: root_key.to_vec(),
+            keys_entry: root_key_entry(namespace, "keys", root_key),
+            values_entry: root_key_entry(namespace, "values", root_key),
+            root_key_written: Arc::new(AtomicBool::new(false)),
+            max_stream_queries,
+        }
+    }
+
+    fn check_namespace(namespace: &str) -> Result<(), RedisStoreInternalError> {
+        if !namespace.is_empty()
+            && namespace.len() <= 255
+            && namespace
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Ok(());
+        }
+        Err(RedisStoreInternalError::InvalidNamespace)
+    }
+}
+
+fn check_key_size(key: &[u8]) -> Result<(), RedisStoreInternalError> {
+    ensure!(
+        key.len() <= MAX_KEY_SIZE,
+        RedisStoreInternalError::KeyTooLong
+    );
+    Ok(())
+}
+
+impl ReadableKeyValueStore for RedisStoreInternal {
+    const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.max_stream_queries
+    }
+
+    async fn read_value_bytes(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, RedisStoreInternalError> {
+        check_key_size(key)?;
+        let mut connection = self.connection.clone();
+        Ok(connection.hget(&self.values_entry, key).await?)
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, RedisStoreInternalError> {
+        check_key_size(key)?;
+        let mut connection = self.connection.clone();
+        Ok(connection.hexists(&self.values_entry, key).await?)
+    }
+
+    async fn contains_keys(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, RedisStoreInternalError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut pipe = redis::pipe();
+        for key in &keys {
+            check_key_size(key)?;
+            pipe.hexists(&self.values_entry, key);
+        }
+        let mut connection = self.connection.clone();
+        Ok(pipe.query_async(&mut connection).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, RedisStoreInternalError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        for key in &keys {
+            check_key_size(key)?;
+        }
+        let mut connection = self.connection.clone();
+        // `hget` would send `HGET` for a single key, which doesn't return a list.
+        let values = redis::cmd("HMGET")
+            .arg(&self.values_entry)
+            .arg(&keys)
+            .query_async::<Vec<Option<Vec<u8>>>>(&mut connection)
+            .await?;
+        Ok(values)
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::Keys, RedisStoreInternalError> {
+        check_key_size(key_prefix)?;
+        let (min, max) = prefix_bounds(key_prefix);
+        let mut connection = self.connection.clone();
+        let keys: Vec<Vec<u8>> = connection.zrangebylex(&self.keys_entry, min, max).await?;
+        let len = key_prefix.len();
+        Ok(keys.into_iter().map(|key| key[len..].to_vec()).collect())
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, RedisStoreInternalError> {
+        check_key_size(key_prefix)?;
+        let (min, max) = prefix_bounds(key_prefix);
+        let mut connection = self.connection.clone();
+        // The keys and values are read by a script, so that they are consistent.
+        let entries: Vec<Vec<u8>> = Script::new(FIND_KEY_VALUES_SCRIPT)
+            .key(&self.keys_entry)
+            .key(&self.values_entry)
+            .arg(min)
+            .arg(max)
+            .invoke_async(&mut connection)
+            .await?;
+        ensure!(
+            entries.len() % 2 == 0,
+            RedisStoreInternalError::InvalidResponse
+        );
+        let len = key_prefix.len();
+        let mut key_values = Vec::with_capacity(entries.len() / 2);
+        let mut entries = entries.into_iter();
+        while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+            key_values.push((key[len..].to_vec(), value));
+        }
+        Ok(key_values)
+    }
+}
+
+impl WritableKeyValueStore for RedisStoreInternal {
+    const MAX_VALUE_SIZE: usize = MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), RedisStoreInternalError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let batch = batch.simplify();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        // Key-prefix and key-range deletions must happen first.
+        for key_prefix in &batch.key_prefix_deletions {
+            check_key_size(key_prefix)?;
+            let (min, max) = prefix_bounds(key_prefix);
+            pipe.cmd("EVAL")
+                .arg(DELETE_RANGE_SCRIPT)
+                .arg(2)
+                .arg(&self.keys_entry)
+                .arg(&self.values_entry)
+                .arg(min)
+                .arg(max)
+                .ignore();
+        }
+        for (start, end) in &batch.key_range_deletions {
+            check_key_size(start)?;
+            check_key_size(end)?;
+            let (min, max) = range_bounds(start, end);
+            pipe.cmd("EVAL")
+                .arg(DELETE_RANGE_SCRIPT)
+                .arg(2)
+                .arg(&self.keys_entry)
+                .arg(&self.values_entry)
+                .arg(min)
+                .arg(max)
+                .ignore();
+        }
+        let batch = batch.simple_unordered_batch;
+        if !batch.deletions.is_empty() {
+            for key in &batch.deletions {
+                check_key_size(key)?;
+            }
+            pipe.hdel(&self.values_entry, &batch.deletions).ignore();
+            pipe.zrem(&self.keys_entry, &batch.deletions).ignore();
+        }
+        if !batch.insertions.is_empty() {
+            for (key, value) in &batch.insertions {
+                check_key_size(key)?;
+                ensure!(
+                    value.len() <= MAX_VALUE_SIZE,
+                    RedisStoreInternalError::ValueTooLong
+                );
+            }
+            let members = batch
+                .insertions
+                .iter()
+                .map(|(key, _)| (0, key))
+                .collect::<Vec<_>>();
+            pipe.hset_multiple(&self.values_entry, &batch.insertions)
+                .ignore();
+            pipe.zadd_multiple(&self.keys_entry, &members).ignore();
+        }
+        let write_root_key = !self.root_key_written.load(Ordering::SeqCst);
+        if write_root_key {
+            pipe.sadd(
+                namespace_entry(&self.namespace, "root_keys"),
+                &self.root_key,
+            )
+            .ignore();
+        }
+        let mut connection = self.connection.clone();
+        pipe.query_async::<()>(&mut connection).await?;
+        if write_root_key {
+            self.root_key_written.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    async fn clear_journal(&self) -> Result<(), RedisStoreInternalError> {
+        Ok(())
+    }
+}
+
+/// The type for building a new Redis Key Value Store
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RedisStoreInternalConfig {
+    /// The URL of the server, e.g. `redis://localhost:6379`
+    pub uri: String,
+    /// Whether to enable append-only file persistence on the server when connecting
+    pub persistent: bool,
+    /// The common configuration of the key value store
+    common_config: CommonStoreInternalConfig,
+}
+
+impl RedisStoreInternalConfig {
+    async fn connection(&self) -> Result<ConnectionManager, RedisStoreInternalError> {
+        let client = redis::Client::open(self.uri.as_str())?;
+        Ok(ConnectionManager::new(client).await?)
+    }
+}
+
+impl AdminKeyValueStore for RedisStoreInternal {
+    type Config = RedisStoreInternalConfig;
+
+    fn get_name() -> String {
+        "redis internal".to_string()
+    }
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Self, RedisStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut connection = config.connection().await?;
+        if config.persistent {
+            redis::cmd("CONFIG")
+                .arg("SET")
+                .arg("appendonly")
+                .arg("yes")
+                .query_async::<()>(&mut connection)
+                .await?;
+        }
+        let max_stream_queries = config.common_config.max_stream_queries;
+        Ok(Self::new(connection, namespace, &[], max_stream_queries))
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, RedisStoreInternalError> {
+        Ok(Self::new(
+            self.connection.clone(),
+            &self.namespace,
+            root_key,
+            self.max_stream_queries,
+        ))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, RedisStoreInternalError> {
+        let mut connection = config.connection().await?;
+        Ok(connection.smembers(NAMESPACES).await?)
+    }
+
+    async fn list_root_keys(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<Vec<Vec<u8>>, RedisStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut connection = config.connection().await?;
+        let mut root_keys: Vec<Vec<u8>> = connection
+            .smembers(namespace_entry(namespace, "root_keys"))
+            .await?;
+        root_keys.sort();
+        Ok(root_keys)
+    }
+
+    async fn exists(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<bool, RedisStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut connection = config.connection().await?;
+        Ok(connection.sismember(NAMESPACES, namespace).await?)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), RedisStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut connection = config.connection().await?;
+        let added: usize = connection.sadd(NAMESPACES, namespace).await?;
+        ensure!(added == 1, RedisStoreInternalError::NamespaceAlreadyExists);
+        Ok(())
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), RedisStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let mut connection = config.connection().await?;
+        let root_keys_entry = namespace_entry(namespace, "root_keys");
+        let root_keys: Vec<Vec<u8>> = connection.smembers(&root_keys_entry).await?;
+        let mut entries = vec![root_keys_entry];
+        for root_key in &root_keys {
+            entries.push(root_key_entry(namespace, "keys", root_key));
+            entries.push(root_key_entry(namespace, "values", root_key));
+        }
+        redis::pipe()
+            .atomic()
+            .del(entries)
+            .ignore()
+            .srem(NAMESPACES, namespace)
+            .ignore()
+            .query_async::<()>(&mut connection)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The number of connections in the stream is limited for tests.
+#[cfg(with_testing)]
+const TEST_REDIS_MAX_STREAM_QUERIES: usize = 10;
+
+#[cfg(with_testing)]
+impl TestKeyValueStore for RedisStoreInternal {
+    async fn new_test_config() -> Result<RedisStoreInternalConfig, RedisStoreInternalError> {
+        let common_config = CommonStoreInternalConfig {
+            max_concurrent_queries: None,
+            max_stream_queries: TEST_REDIS_MAX_STREAM_QUERIES,
+        };
+        Ok(RedisStoreInternalConfig {
+            uri: "redis://localhost:6379".to_string(),
+            persistent: false,
+            common_config,
+        })
+    }
+}
+
+/// The `RedisStore` composed type with metrics
+#[cfg(with_metrics)]
+pub type RedisStore = MeteredStore<
+    LruCachingStore<MeteredStore<ValueSplittingStore<MeteredStore<RedisStoreInternal>>>>,
+>;
+
+/// The `RedisStore` composed type
+#[cfg(not(with_metrics))]
+pub type RedisStore = LruCachingStore<ValueSplittingStore<RedisStoreInternal>>;
+
+/// The `RedisStoreConfig` input type
+pub type RedisStoreConfig = LruCachingConfig<RedisStoreInternalConfig>;
+
+impl RedisStoreConfig {
+    /// Creates a `RedisStoreConfig` from the inputs.
+    pub fn new(
+        uri: String,
+        persistent: bool,
+        common_config: crate::store::CommonStoreConfig,
+    ) -> RedisStoreConfig {
+        let inner_config = RedisStoreInternalConfig {
+            uri,
+            persistent,
+            common_config: common_config.reduced(),
+        };
+        RedisStoreConfig {
+            inner_config,
+            storage_cache_config: common_config.storage_cache_config,
+        }
+    }
+}
+
+/// The combined error type for the `RedisStore`.
+pub type RedisStoreError = ValueSplittingError<RedisStoreInternalError>;
@@ -6,7 +6,7 @@
 use std::{
     ffi::OsString,
     fmt::Display,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -14,7 +14,10 @@ use std::{
 };
 
 use linera_base::ensure;
-use rocksdb::{BlockBasedOptions, Cache, DBCompactionStyle};
+use rocksdb::{
+    backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions},
+    BlockBasedOptions, Cache, DBCompactionStyle, Env,
+};
 use serde::{Deserialize, Serialize};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 use tempfile::TempDir;
@@ -244,6 +247,17 @@ impl RocksDbStoreExecutor {
                         get_upper_bound_option(&full_key1).expect("the first entry cannot be 255");
                     inner_batch.delete_range(&full_key1, &full_key2);
                 }
+                WriteOperation::DeleteRange { start, end } => {
+                    check_key_size(&start)?;
+                    check_key_size(&end)?;
+                    if start < end {
+                        let mut full_start = self.start_key.to_vec();
+                        full_start.extend(start);
+                        let mut full_end = self.start_key.to_vec();
+                        full_end.extend(end);
+                        inner_batch.delete_range(&full_start, &full_end);
+                    }
+                }
             }
         }
         if write_root_key {
@@ -360,6 +374,135 @@ impl RocksDbStoreInternal {
     }
 }
 
+/// Information about a backup of a RocksDB namespace.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RocksDbBackupInfo {
+    /// The identifier of the backup, increasing with each backup.
+    pub backup_id: u32,
+    /// The time the backup was created, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The total size of the files of the backup, in bytes.
+    pub size: u64,
+    /// The number of files of the backup.
+    pub num_files: u32,
+}
+
+impl From<BackupEngineInfo> for RocksDbBackupInfo {
+    fn from(info: BackupEngineInfo) -> Self {
+        RocksDbBackupInfo {
+            backup_id: info.backup_id,
+            timestamp: info.timestamp,
+            size: info.size,
+            num_files: info.num_files,
+        }
+    }
+}
+
+fn open_backup_engine(backup_path: &Path) -> Result<BackupEngine, RocksDbStoreInternalError> {
+    let options = BackupEngineOptions::new(backup_path)?;
+    let env = Env::new()?;
+    Ok(BackupEngine::open(&options, &env)?)
+}
+
+impl RocksDbStoreInternal {
+    /// Creates a backup of the database of this store in the directory `backup_path`.
+    ///
+    /// The backup is a consistent snapshot taken while the database remains readable and
+    /// writable. Backups in the same directory share their unchanged files, so every backup
+    /// after the first one only copies the files written since the previous one. If
+    /// `max_backups` is set, the oldest backups are then deleted to keep that many.
+    pub async fn create_backup(
+        &self,
+        backup_path: PathBuf,
+        max_backups: Option<usize>,
+    ) -> Result<RocksDbBackupInfo, RocksDbStoreInternalError> {
+        let db = self.executor.db.clone();
+        self.spawn_mode
+            .spawn(
+                move |backup_path| {
+                    let mut engine = open_backup_engine(&backup_path)?;
+                    engine.create_new_backup_flush(&*db, true)?;
+                    if let Some(max_backups) = max_backups {
+                        engine.purge_old_backups(max_backups)?;
+                    }
+                    let info = engine
+                        .get_backup_info()
+                        .into_iter()
+                        .max_by_key(|info| info.backup_id)
+                        .ok_or(RocksDbStoreInternalError::BackupNotFound)?;
+                    Ok(info.into())
+                },
+                backup_path,
+            )
+            .await
+    }
+
+    /// Creates a backup of an existing namespace in the directory `backup_path`. See
+    /// [`RocksDbStoreInternal::create_backup`].
+    pub async fn backup(
+        config: &RocksDbStoreInternalConfig,
+        namespace: &str,
+        backup_path: PathBuf,
+        max_backups: Option<usize>,
+    ) -> Result<RocksDbBackupInfo, RocksDbStoreInternalError> {
+        ensure!(
+            Self::exists(config, namespace).await?,
+            RocksDbStoreInternalError::NamespaceNotFound
+        );
+        let store = Self::connect(config, namespace).await?;
+        store.create_backup(backup_path, max_backups).await
+    }
+
+    /// Restores a namespace, which must not exist, from a backup in the directory
+    /// `backup_path`: the one with the given ID, or else the latest one.
+    pub async fn restore_backup(
+        config: &RocksDbStoreInternalConfig,
+        namespace: &str,
+        backup_path: PathBuf,
+        backup_id: Option<u32>,
+    ) -> Result<(), RocksDbStoreInternalError> {
+        ensure!(
+            !Self::exists(config, namespace).await?,
+            RocksDbStoreInternalError::StoreAlreadyExists
+        );
+        let mut path_buf = config.path_with_guard.path_buf.clone();
+        path_buf.push(namespace);
+        config
+            .spawn_mode
+            .spawn(
+                move |(backup_path, path_buf)| {
+                    let mut engine = open_backup_engine(&backup_path)?;
+                    let options = RestoreOptions::default();
+                    match backup_id {
+                        Some(backup_id) => {
+                            engine.restore_from_backup(&path_buf, &path_buf, &options, backup_id)?
+                        }
+                        None => {
+                            engine.restore_from_latest_backup(&path_buf, &path_buf, &options)?
+                        }
+                    }
+                    Ok(())
+                },
+                (backup_path, path_buf),
+            )
+            .await
+    }
+
+    /// Lists the backups in the directory `backup_path`, from the oldest to the latest.
+    pub fn list_backups(
+        backup_path: &Path,
+    ) -> Result<Vec<RocksDbBackupInfo>, RocksDbStoreInternalError> {
+        let engine = open_backup_engine(backup_path)?;
+        let mut backups = engine
+            .get_backup_info()
+            .into_iter()
+            .map(RocksDbBackupInfo::from)
+            .collect::<Vec<_>>();
+        backups.sort_by_key(|info| info.backup_id);
+        Ok(backups)
+    }
+}
+
 impl WithError for RocksDbStoreInternal {
     type Error = RocksDbStoreInternalError;
 }
@@ -616,6 +759,14 @@ pub enum RocksDbStoreInternalError {
     #[error("The key must have at most 8 MB")]
     KeyTooLong,
 
+    /// The namespace does not exist
+    #[error("Namespace does not exist")]
+    NamespaceNotFound,
+
+    /// The backup directory does not contain any backup
+    #[error("No backup was found")]
+    BackupNotFound,
+
     /// Namespace contains forbidden characters
     #[error("Namespace contains forbidden characters")]
     InvalidNamespace,
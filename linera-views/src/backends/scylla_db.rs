@@ -354,6 +354,13 @@ impl ScyllaDbClient {
                 }
             }
         }
+        for (start, end) in batch.key_range_deletions {
+            Self::check_key_size(&start)?;
+            Self::check_key_size(&end)?;
+            let values = vec![root_key.to_vec(), start, end];
+            batch_values.push(values);
+            batch_query.append_statement(query2.clone());
+        }
         let query3 = &self.write_batch_deletion;
         for key in batch.simple_unordered_batch.deletions {
             Self::check_key_size(&key)?;
@@ -268,6 +268,13 @@ where
                 WriteOperation::DeletePrefix { key_prefix } => {
                     batch_new.delete_key_prefix(key_prefix);
                 }
+                WriteOperation::DeleteRange { mut start, mut end } => {
+                    // The first segments of the keys in the range are exactly the keys in
+                    // this range.
+                    start.extend(&[0, 0, 0, 0]);
+                    end.extend(&[0, 0, 0, 0]);
+                    batch_new.delete_key_range(start, Some(end));
+                }
             }
         }
         Ok(self.store.write_batch(batch_new).await?)
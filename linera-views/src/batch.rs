@@ -2,13 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! A set of functionalities for building batches to be written into the database.
-//! A batch can contain three kinds of operations on a key/value store:
+//! A batch can contain four kinds of operations on a key/value store:
 //! * Insertion of a key with an associated value
 //! * Deletion of a specific key
 //! * Deletion of all keys which contain a specified prefix
+//! * Deletion of all keys in a specified range
 //!
-//! The deletion using prefixes is generally but not always faster than deleting keys
-//! one by one. The only purpose of the batch is to write some transactions into the
+//! The deletion using prefixes or ranges is generally but not always faster than deleting
+//! keys one by one. The only purpose of the batch is to write some transactions into the
 //! database.
 //!
 //! Note that normal users should not have to manipulate batches. The functionality
@@ -19,25 +20,28 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
     fmt::Debug,
     iter::Peekable,
+    mem,
     ops::Bound,
     vec::IntoIter,
 };
 
 use async_trait::async_trait;
 use bcs::serialized_size;
+use linera_base::appended;
 use linera_witty::{WitLoad, WitStore, WitType};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    common::{get_interval, get_uleb128_size},
+    common::{get_interval, get_range_prefix, get_uleb128_size},
     views::ViewError,
 };
 
 /// A write operation as requested by a view when it needs to persist staged changes.
-/// There are 3 possibilities for the batch:
+/// There are 4 possibilities for the batch:
 /// * Deletion of a specific key.
 /// * Deletion of all keys matching a specific prefix.
 /// * Insertion or replacement of a key with a value.
+/// * Deletion of all keys in a specific range.
 #[derive(Clone, Debug, Eq, PartialEq, WitType, WitLoad, WitStore)]
 pub enum WriteOperation {
     /// Delete the given key.
@@ -57,6 +61,13 @@ pub enum WriteOperation {
         /// The value to be inserted on the key.
         value: Vec<u8>,
     },
+    /// Delete all the keys `key` with `start <= key < end`.
+    DeleteRange {
+        /// The first key of the range.
+        start: Vec<u8>,
+        /// The end of the range, which is not part of it.
+        end: Vec<u8>,
+    },
 }
 
 /// A batch of write operations.
@@ -76,19 +87,81 @@ pub struct SimpleUnorderedBatch {
     pub insertions: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
-/// An unordered batch of deletions and insertions, together with a set of key-prefixes to
-/// delete. Key-prefix deletions must happen before the insertions and the deletions.
-#[derive(Default, Serialize, Deserialize)]
+/// An unordered batch of deletions and insertions, together with a set of key-prefixes and
+/// of key ranges to delete. Key-prefix and key-range deletions must happen before the
+/// insertions and the deletions.
+#[derive(Default)]
 pub struct UnorderedBatch {
     /// The key-prefix deletions.
     pub key_prefix_deletions: Vec<Vec<u8>>,
     /// The batch of deletions and insertions.
     pub simple_unordered_batch: SimpleUnorderedBatch,
+    /// The key-range deletions, as pairs `(start, end)` of the first key of each range and
+    /// of its excluded end.
+    pub key_range_deletions: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// The fields of an [`UnorderedBatch`] serialized after those of the batches journaled
+/// before key-range deletions existed. They are only serialized if there are any.
+#[derive(Serialize, Deserialize)]
+enum UnorderedBatchAppended {
+    V1 {
+        key_range_deletions: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+}
+
+/// Returns the size of the serialized fields of an [`UnorderedBatch`] that hold
+/// `count` key-range deletions, besides the deletions themselves.
+fn key_range_deletions_overhead_size(count: usize) -> usize {
+    if count == 0 {
+        0
+    } else {
+        // The tag of the appended fields, the variant and the length of the vector.
+        1 + 1 + get_uleb128_size(count)
+    }
+}
+
+impl Serialize for UnorderedBatch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let fields = (&self.key_prefix_deletions, &self.simple_unordered_batch);
+        if self.key_range_deletions.is_empty() {
+            return fields.serialize(serializer);
+        }
+        let appended = UnorderedBatchAppended::V1 {
+            key_range_deletions: self.key_range_deletions.clone(),
+        };
+        appended::serialize(&fields, &appended, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnorderedBatch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ((key_prefix_deletions, simple_unordered_batch), appended) =
+            appended::deserialize(deserializer)?;
+        let key_range_deletions = match appended {
+            None => Vec::new(),
+            Some(UnorderedBatchAppended::V1 {
+                key_range_deletions,
+            }) => key_range_deletions,
+        };
+        Ok(UnorderedBatch {
+            key_prefix_deletions,
+            simple_unordered_batch,
+            key_range_deletions,
+        })
+    }
 }
 
 impl UnorderedBatch {
     /// From an `UnorderedBatch`, creates a [`SimpleUnorderedBatch`] that does not contain the
-    /// `key_prefix_deletions`. This requires accessing the database to eliminate them.
+    /// `key_prefix_deletions` and the `key_range_deletions`. This requires accessing the
+    /// database to eliminate them.
     pub async fn expand_delete_prefixes<DB: DeletePrefixExpander>(
         self,
         db: &DB,
@@ -99,29 +172,37 @@ impl UnorderedBatch {
         }
         let insertions = self.simple_unordered_batch.insertions;
         let mut deletions = self.simple_unordered_batch.deletions;
+        // The prefixes and ranges may overlap, so the expanded keys are deduplicated.
+        let mut expanded_keys = BTreeSet::new();
         for key_prefix in self.key_prefix_deletions {
             for short_key in db.expand_delete_prefix(&key_prefix).await?.iter() {
                 let mut key = key_prefix.clone();
                 key.extend(short_key);
-                if !insert_set.contains(&key) {
-                    deletions.push(key);
-                }
+                expanded_keys.insert(key);
             }
         }
+        for (start, end) in self.key_range_deletions {
+            expanded_keys.extend(expand_delete_range(db, &start, &end).await?);
+        }
+        deletions.extend(
+            expanded_keys
+                .into_iter()
+                .filter(|key| !insert_set.contains(key)),
+        );
         Ok(SimpleUnorderedBatch {
             deletions,
             insertions,
         })
     }
 
-    /// Modifies an [`UnorderedBatch`] so that the key-prefix deletions do not conflict
-    /// with subsequent insertions. This may require accessing the database to compute
-    /// lists of deleted keys.
+    /// Modifies an [`UnorderedBatch`] so that the key-prefix and key-range deletions do not
+    /// conflict with subsequent insertions. This may require accessing the database to
+    /// compute lists of deleted keys. Key ranges are split around the inserted keys instead.
     pub async fn expand_colliding_prefix_deletions<DB: DeletePrefixExpander>(
         &mut self,
         db: &DB,
     ) -> Result<(), DB::Error> {
-        if self.key_prefix_deletions.is_empty() {
+        if self.key_prefix_deletions.is_empty() && self.key_range_deletions.is_empty() {
             return Ok(());
         }
         let inserted_keys = self
@@ -149,20 +230,57 @@ impl UnorderedBatch {
             }
         }
         self.key_prefix_deletions = key_prefix_deletions;
+        let mut key_range_deletions = Vec::new();
+        for (mut start, end) in mem::take(&mut self.key_range_deletions) {
+            for key in inserted_keys.range(start.clone()..end.clone()) {
+                if start < *key {
+                    key_range_deletions.push((start, key.clone()));
+                }
+                // The smallest key greater than `key`.
+                start = key.clone();
+                start.push(0);
+            }
+            if start < end {
+                key_range_deletions.push((start, end));
+            }
+        }
+        self.key_range_deletions = key_range_deletions;
         Ok(())
     }
 
     /// The total number of entries of the batch.
     pub fn len(&self) -> usize {
-        self.key_prefix_deletions.len() + self.simple_unordered_batch.len()
+        self.key_prefix_deletions.len()
+            + self.key_range_deletions.len()
+            + self.simple_unordered_batch.len()
     }
 
     /// Tests whether the batch is empty or not
     pub fn is_empty(&self) -> bool {
-        self.key_prefix_deletions.is_empty() && self.simple_unordered_batch.is_empty()
+        self.key_prefix_deletions.is_empty()
+            && self.key_range_deletions.is_empty()
+            && self.simple_unordered_batch.is_empty()
     }
 }
 
+/// Returns the keys `key` of the database with `start <= key < end`.
+async fn expand_delete_range<DB: DeletePrefixExpander>(
+    db: &DB,
+    start: &[u8],
+    end: &[u8],
+) -> Result<Vec<Vec<u8>>, DB::Error> {
+    let key_prefix = get_range_prefix(start, Some(end));
+    let mut keys = Vec::new();
+    for short_key in db.expand_delete_prefix(key_prefix).await? {
+        let mut key = key_prefix.to_vec();
+        key.extend(short_key);
+        if start <= key.as_slice() && key.as_slice() < end {
+            keys.push(key);
+        }
+    }
+    Ok(keys)
+}
+
 /// Checks if `key` is matched by any prefix in `key_prefix_set`.
 /// The set `key_prefix_set` must be minimal for the function to work correctly.
 /// That is, there should not be any two prefixes `p1` and `p2` such that `p1 < p2` for
@@ -182,6 +300,13 @@ fn is_prefix_matched(key_prefix_set: &BTreeSet<Vec<u8>>, key: &[u8]) -> bool {
     false
 }
 
+/// Checks if `key` is in any of the ranges `[start, end)` of `key_ranges`.
+fn is_range_matched(key_ranges: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> bool {
+    key_ranges
+        .iter()
+        .any(|(start, end)| start.as_slice() <= key && key < end.as_slice())
+}
+
 impl Batch {
     /// Creates an empty batch.
     pub fn new() -> Self {
@@ -196,6 +321,7 @@ impl Batch {
                 WriteOperation::Delete { key } => key.len(),
                 WriteOperation::Put { key, value } => key.len() + value.len(),
                 WriteOperation::DeletePrefix { key_prefix } => key_prefix.len(),
+                WriteOperation::DeleteRange { start, end } => start.len() + end.len(),
             })
             .sum()
     }
@@ -223,7 +349,7 @@ impl Batch {
     /// Simplifies the batch by removing operations that are overwritten by others.
     ///
     /// A key may appear multiple times in the batch, as an insert, a delete
-    /// or matched by a delete prefix.
+    /// or matched by a delete prefix or a delete range.
     /// ```rust
     /// # use linera_views::batch::Batch;
     /// let mut batch = Batch::new();
@@ -237,12 +363,15 @@ impl Batch {
     pub fn simplify(self) -> UnorderedBatch {
         let mut delete_and_insert_map = BTreeMap::new();
         let mut delete_prefix_set = BTreeSet::new();
+        let mut key_range_deletions = Vec::new();
         for operation in self.operations {
             match operation {
                 WriteOperation::Delete { key } => {
-                    // If `key` is matched by a deleted prefix, then remove any inserted
-                    // value. Otherwise, add the key to the set of deletions.
-                    if is_prefix_matched(&delete_prefix_set, &key) {
+                    // If `key` is matched by a deleted prefix or range, then remove any
+                    // inserted value. Otherwise, add the key to the set of deletions.
+                    if is_prefix_matched(&delete_prefix_set, &key)
+                        || is_range_matched(&key_range_deletions, &key)
+                    {
                         delete_and_insert_map.remove(&key);
                     } else {
                         delete_and_insert_map.insert(key, None);
@@ -278,6 +407,20 @@ impl Batch {
                     // Then, insert the new key prefix.
                     delete_prefix_set.insert(key_prefix);
                 }
+                WriteOperation::DeleteRange { start, end } => {
+                    if start >= end {
+                        continue;
+                    }
+                    // Remove the previous deletions and insertions in the range.
+                    let keys = delete_and_insert_map
+                        .range(start.clone()..end.clone())
+                        .map(|x| x.0.to_vec())
+                        .collect::<Vec<_>>();
+                    for key in keys {
+                        delete_and_insert_map.remove(&key);
+                    }
+                    key_range_deletions.push((start, end));
+                }
             }
         }
         let key_prefix_deletions = delete_prefix_set.into_iter().collect();
@@ -296,6 +439,7 @@ impl Batch {
         UnorderedBatch {
             key_prefix_deletions,
             simple_unordered_batch,
+            key_range_deletions,
         }
     }
 
@@ -361,6 +505,34 @@ impl Batch {
         self.operations
             .push(WriteOperation::DeletePrefix { key_prefix });
     }
+
+    /// Inserts the deletion of the keys `key` with `start <= key < end` into the batch,
+    /// where `None` stands for no upper bound.
+    /// ```rust
+    /// # use linera_views::batch::Batch;
+    /// let mut batch = Batch::new();
+    /// batch.delete_key_range(vec![0, 1], Some(vec![0, 3]));
+    /// ```
+    pub fn delete_key_range(&mut self, start: Vec<u8>, end: Option<Vec<u8>>) {
+        let Some(end) = end else {
+            // The keys from `[255, .., 255]` on are those with that prefix. Otherwise, the
+            // keys from `start` on are those before the next longer such key, and those
+            // with that key as prefix.
+            let count = start.iter().take_while(|byte| **byte == u8::MAX).count();
+            if count < start.len() {
+                let end = vec![u8::MAX; count + 1];
+                self.delete_key_range(start, Some(end.clone()));
+                self.delete_key_prefix(end);
+            } else {
+                self.delete_key_prefix(start);
+            }
+            return;
+        };
+        if start < end {
+            self.operations
+                .push(WriteOperation::DeleteRange { start, end });
+        }
+    }
 }
 
 /// A trait to expand `DeletePrefix` operations.
@@ -545,6 +717,7 @@ impl BatchValueWriter<SimpleUnorderedBatch> for SimpleUnorderedBatchIter {
 /// The iterator that corresponds to a `SimpleUnorderedBatch`
 pub struct UnorderedBatchIter {
     delete_prefix_iter: Peekable<IntoIter<Vec<u8>>>,
+    delete_range_iter: Peekable<IntoIter<(Vec<u8>, Vec<u8>)>>,
     insert_deletion_iter: SimpleUnorderedBatchIter,
 }
 
@@ -554,15 +727,17 @@ impl SimplifiedBatch for UnorderedBatch {
 
     fn into_iter(self) -> Self::Iter {
         let delete_prefix_iter = self.key_prefix_deletions.into_iter().peekable();
+        let delete_range_iter = self.key_range_deletions.into_iter().peekable();
         let insert_deletion_iter = self.simple_unordered_batch.into_iter();
         Self::Iter {
             delete_prefix_iter,
+            delete_range_iter,
             insert_deletion_iter,
         }
     }
 
     fn len(&self) -> usize {
-        self.key_prefix_deletions.len() + self.simple_unordered_batch.len()
+        UnorderedBatch::len(self)
     }
 
     fn num_bytes(&self) -> usize {
@@ -570,12 +745,16 @@ impl SimplifiedBatch for UnorderedBatch {
         for prefix_deletion in &self.key_prefix_deletions {
             total_size += prefix_deletion.len();
         }
+        for (start, end) in &self.key_range_deletions {
+            total_size += start.len() + end.len();
+        }
         total_size
     }
 
     fn overhead_size(&self) -> usize {
         get_uleb128_size(self.key_prefix_deletions.len())
             + self.simple_unordered_batch.overhead_size()
+            + key_range_deletions_overhead_size(self.key_range_deletions.len())
     }
 
     fn add_delete(&mut self, key: Vec<u8>) {
@@ -600,7 +779,9 @@ impl SimplifiedBatch for UnorderedBatch {
 
 impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
     fn is_empty(&self) -> bool {
-        self.delete_prefix_iter.len() == 0 && self.insert_deletion_iter.is_empty()
+        self.delete_prefix_iter.len() == 0
+            && self.delete_range_iter.len() == 0
+            && self.insert_deletion_iter.is_empty()
     }
 
     fn write_next_value(
@@ -612,6 +793,10 @@ impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
             *batch_size += serialized_size(&delete_prefix)?;
             batch.key_prefix_deletions.push(delete_prefix);
             Ok(true)
+        } else if let Some(delete_range) = self.delete_range_iter.next() {
+            *batch_size += serialized_size(&delete_range)?;
+            batch.key_range_deletions.push(delete_range);
+            Ok(true)
         } else {
             self.insert_deletion_iter
                 .write_next_value(&mut batch.simple_unordered_batch, batch_size)
@@ -629,10 +814,22 @@ impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
                 batch_size
                     + next_size
                     + get_uleb128_size(batch.key_prefix_deletions.len() + 1)
-                    + batch.simple_unordered_batch.overhead_size(),
+                    + batch.simple_unordered_batch.overhead_size()
+                    + key_range_deletions_overhead_size(batch.key_range_deletions.len()),
+            ))
+        } else if let Some(delete_range) = self.delete_range_iter.peek() {
+            let next_size = serialized_size(&delete_range)?;
+            Ok(Some(
+                batch_size
+                    + next_size
+                    + get_uleb128_size(batch.key_prefix_deletions.len())
+                    + batch.simple_unordered_batch.overhead_size()
+                    + key_range_deletions_overhead_size(batch.key_range_deletions.len() + 1),
             ))
         } else {
-            let batch_size = batch_size + get_uleb128_size(batch.key_prefix_deletions.len());
+            let batch_size = batch_size
+                + get_uleb128_size(batch.key_prefix_deletions.len())
+                + key_range_deletions_overhead_size(batch.key_range_deletions.len());
             self.insert_deletion_iter
                 .next_batch_size(&batch.simple_unordered_batch, batch_size)
         }
@@ -642,7 +839,9 @@ impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
 #[cfg(test)]
 mod tests {
     use linera_views::{
-        batch::{Batch, SimpleUnorderedBatch, UnorderedBatch},
+        batch::{
+            Batch, SimpleUnorderedBatch, SimplifiedBatch as _, UnorderedBatch, WriteOperation,
+        },
         context::{Context, MemoryContext},
     };
 
@@ -733,6 +932,7 @@ mod tests {
         let mut unordered_batch = UnorderedBatch {
             simple_unordered_batch,
             key_prefix_deletions,
+            key_range_deletions: vec![],
         };
         unordered_batch
             .expand_colliding_prefix_deletions(&context)
@@ -745,4 +945,150 @@ mod tests {
         );
         assert!(unordered_batch.key_prefix_deletions.is_empty());
     }
+
+    #[test]
+    fn test_simplify_batch_with_ranges() {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 2], vec![]);
+        batch.put_key_value_bytes(vec![1, 4], vec![]);
+        batch.delete_key_range(vec![1, 1], Some(vec![1, 3]));
+        batch.delete_key(vec![1, 2, 5]);
+        batch.put_key_value_bytes(vec![1, 2, 6], vec![7]);
+        batch.delete_key_range(vec![2], Some(vec![1]));
+        let unordered_batch = batch.simplify();
+        assert!(unordered_batch.key_prefix_deletions.is_empty());
+        assert_eq!(
+            unordered_batch.key_range_deletions,
+            vec![(vec![1, 1], vec![1, 3])]
+        );
+        assert!(unordered_batch.simple_unordered_batch.deletions.is_empty());
+        assert_eq!(
+            unordered_batch.simple_unordered_batch.insertions,
+            vec![(vec![1, 2, 6], vec![7]), (vec![1, 4], vec![])]
+        );
+    }
+
+    #[test]
+    fn test_delete_key_range_without_end() {
+        let mut batch = Batch::new();
+        batch.delete_key_range(vec![255, 3], None);
+        batch.delete_key_range(vec![255, 255], None);
+        assert_eq!(
+            batch.operations,
+            vec![
+                WriteOperation::DeleteRange {
+                    start: vec![255, 3],
+                    end: vec![255, 255],
+                },
+                WriteOperation::DeletePrefix {
+                    key_prefix: vec![255, 255],
+                },
+                WriteOperation::DeletePrefix {
+                    key_prefix: vec![255, 255],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expand_range_deletions() {
+        let context = MemoryContext::new_for_testing(());
+        let mut batch = Batch::new();
+        for key in [vec![1], vec![1, 2], vec![1, 3, 0], vec![1, 4], vec![2]] {
+            batch.put_key_value_bytes(key, vec![]);
+        }
+        context.write_batch(batch).await.unwrap();
+        let mut batch = Batch::new();
+        batch.delete_key_range(vec![1, 2], Some(vec![1, 4]));
+        batch.delete_key_prefix(vec![1, 3]);
+        batch.put_key_value_bytes(vec![1, 2], vec![5]);
+        let unordered_batch = batch.simplify();
+        let simple_unordered_batch = unordered_batch
+            .expand_delete_prefixes(&context)
+            .await
+            .unwrap();
+        assert_eq!(simple_unordered_batch.deletions, vec![vec![1, 3, 0]]);
+        assert_eq!(
+            simple_unordered_batch.insertions,
+            vec![(vec![1, 2], vec![5])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_colliding_range_deletions() {
+        let context = MemoryContext::new_for_testing(());
+        let mut batch = Batch::new();
+        batch.delete_key_range(vec![1], Some(vec![4]));
+        batch.put_key_value_bytes(vec![1], vec![]);
+        batch.put_key_value_bytes(vec![2, 5], vec![]);
+        let mut unordered_batch = batch.simplify();
+        unordered_batch
+            .expand_colliding_prefix_deletions(&context)
+            .await
+            .unwrap();
+        assert_eq!(
+            unordered_batch.key_range_deletions,
+            vec![(vec![1, 0], vec![2, 5]), (vec![2, 5, 0], vec![4])]
+        );
+        assert!(unordered_batch.simple_unordered_batch.deletions.is_empty());
+    }
+
+    #[test]
+    fn test_serialize_unordered_batch() {
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(vec![1]);
+        batch.delete_key(vec![2]);
+        batch.put_key_value_bytes(vec![3], vec![4]);
+        let unordered_batch = batch.simplify();
+        let bytes = bcs::to_bytes(&unordered_batch).unwrap();
+        // Batches without key-range deletions are serialized as before they existed.
+        let old_bytes = bcs::to_bytes(&(
+            &unordered_batch.key_prefix_deletions,
+            &unordered_batch.simple_unordered_batch,
+        ))
+        .unwrap();
+        assert_eq!(bytes, old_bytes);
+        assert_eq!(
+            bytes.len(),
+            unordered_batch.overhead_size() + serialized_entries_size(&unordered_batch)
+        );
+
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(vec![1]);
+        batch.delete_key_range(vec![2], Some(vec![3, 4]));
+        batch.put_key_value_bytes(vec![5], vec![6]);
+        let unordered_batch = batch.simplify();
+        let bytes = bcs::to_bytes(&unordered_batch).unwrap();
+        assert_eq!(
+            bytes.len(),
+            unordered_batch.overhead_size() + serialized_entries_size(&unordered_batch)
+        );
+        let deserialized = bcs::from_bytes::<UnorderedBatch>(&bytes).unwrap();
+        assert_eq!(
+            deserialized.key_prefix_deletions,
+            unordered_batch.key_prefix_deletions
+        );
+        assert_eq!(
+            deserialized.key_range_deletions,
+            unordered_batch.key_range_deletions
+        );
+        assert_eq!(
+            deserialized.simple_unordered_batch.insertions,
+            unordered_batch.simple_unordered_batch.insertions
+        );
+    }
+
+    /// Returns the size of the serialized entries of the batch, without the overhead.
+    fn serialized_entries_size(batch: &UnorderedBatch) -> usize {
+        fn size<T: serde::Serialize>(entries: &[T]) -> usize {
+            entries
+                .iter()
+                .map(|entry| bcs::serialized_size(entry).unwrap())
+                .sum()
+        }
+        size(&batch.key_prefix_deletions)
+            + size(&batch.key_range_deletions)
+            + size(&batch.simple_unordered_batch.deletions)
+            + size(&batch.simple_unordered_batch.insertions)
+    }
 }
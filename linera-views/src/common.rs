@@ -8,10 +8,11 @@ use std::{
     ops::{
         Bound,
         Bound::{Excluded, Included, Unbounded},
+        RangeBounds,
     },
 };
 
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::views::ViewError;
 
@@ -30,6 +31,8 @@ pub(crate) enum Update<T> {
 pub(crate) struct DeletionSet {
     pub(crate) delete_storage_first: bool,
     pub(crate) deleted_prefixes: BTreeSet<Vec<u8>>,
+    /// The deleted ranges of keys, as their first key and their excluded end, if any.
+    pub(crate) deleted_ranges: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 }
 
 impl DeletionSet {
@@ -37,25 +40,52 @@ impl DeletionSet {
         Self {
             delete_storage_first: false,
             deleted_prefixes: BTreeSet::new(),
+            deleted_ranges: Vec::new(),
         }
     }
 
     pub(crate) fn clear(&mut self) {
         self.delete_storage_first = true;
         self.deleted_prefixes.clear();
+        self.deleted_ranges.clear();
     }
 
     pub(crate) fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.deleted_prefixes.clear();
+        self.deleted_ranges.clear();
     }
 
     pub(crate) fn contains_prefix_of(&self, index: &[u8]) -> bool {
         self.delete_storage_first || contains_prefix_of(&self.deleted_prefixes, index)
     }
 
+    /// Returns whether the key `index` is deleted, by a prefix or by a range.
+    pub(crate) fn is_deleted(&self, index: &[u8]) -> bool {
+        self.contains_prefix_of(index) || self.is_in_deleted_range(&[], index)
+    }
+
+    /// Returns whether the key `prefix + suffix` is in a deleted range.
+    pub(crate) fn is_in_deleted_range(&self, prefix: &[u8], suffix: &[u8]) -> bool {
+        self.find_deleted_range(prefix, suffix).is_some()
+    }
+
+    /// Returns the position of the first deleted range that contains the key
+    /// `prefix + suffix`, if any.
+    pub(crate) fn find_deleted_range(&self, prefix: &[u8], suffix: &[u8]) -> Option<usize> {
+        if self.deleted_ranges.is_empty() {
+            return None;
+        }
+        let key = [prefix, suffix].concat();
+        self.deleted_ranges
+            .iter()
+            .position(|(start, end)| *start <= key && end.as_ref().is_none_or(|end| key < *end))
+    }
+
     pub(crate) fn has_pending_changes(&self) -> bool {
-        self.delete_storage_first || !self.deleted_prefixes.is_empty()
+        self.delete_storage_first
+            || !self.deleted_prefixes.is_empty()
+            || !self.deleted_ranges.is_empty()
     }
 
     pub(crate) fn insert_key_prefix(&mut self, key_prefix: Vec<u8>) {
@@ -63,6 +93,14 @@ impl DeletionSet {
             insert_key_prefix(&mut self.deleted_prefixes, key_prefix);
         }
     }
+
+    /// Deletes the keys `key` with `start <= key < end`, where `None` stands for no upper
+    /// bound.
+    pub(crate) fn insert_key_range(&mut self, start: Vec<u8>, end: Option<Vec<u8>>) {
+        if !self.delete_storage_first && end.as_ref().is_none_or(|end| start < *end) {
+            self.deleted_ranges.push((start, end));
+        }
+    }
 }
 
 /// When wanting to find the entries in a `BTreeMap` with a specific prefix,
@@ -101,6 +139,44 @@ pub(crate) fn get_interval(key_prefix: Vec<u8>) -> (Bound<Vec<u8>>, Bound<Vec<u8
     (Included(key_prefix), upper_bound)
 }
 
+/// Converts a range of keys into the half-open interval `[start, end)` of the keys it
+/// contains, where `None` stands for no upper bound.
+pub(crate) fn get_range_bounds(range: impl RangeBounds<Vec<u8>>) -> (Vec<u8>, Option<Vec<u8>>) {
+    let start = match range.start_bound() {
+        Included(start) => start.clone(),
+        Excluded(start) => {
+            let mut start = start.clone();
+            start.push(0);
+            start
+        }
+        Unbounded => Vec::new(),
+    };
+    let end = match range.end_bound() {
+        Included(end) => {
+            let mut end = end.clone();
+            end.push(0);
+            Some(end)
+        }
+        Excluded(end) => Some(end.clone()),
+        Unbounded => None,
+    };
+    (start, end)
+}
+
+/// Returns the longest prefix shared by all the keys `key` with `start <= key < end`,
+/// where `None` stands for no upper bound.
+pub(crate) fn get_range_prefix<'a>(start: &'a [u8], end: Option<&[u8]>) -> &'a [u8] {
+    let Some(end) = end else {
+        return &[];
+    };
+    let len = start
+        .iter()
+        .zip(end)
+        .take_while(|(start_byte, end_byte)| start_byte == end_byte)
+        .count();
+    &start[..len]
+}
+
 /// Deserializes an optional vector of `u8`
 pub fn from_bytes_option<V: DeserializeOwned, E>(key_opt: &Option<Vec<u8>>) -> Result<Option<V>, E>
 where
@@ -303,6 +379,32 @@ impl CustomSerialize for u128 {
     }
 }
 
+/// Types whose BCS serialization orders their values in the same way as [`Ord`], so that
+/// the values in a range are serialized as the keys in a range. This is not the case for
+/// most types, e.g. multi-byte integers are serialized in little-endian order and vectors
+/// are serialized after their length.
+pub trait BcsOrderedKey: Serialize + Ord {}
+
+impl BcsOrderedKey for bool {}
+
+impl BcsOrderedKey for u8 {}
+
+macro_rules! impl_bcs_ordered_key_for_byte_arrays {
+    ($($len:literal)*) => {
+        $(impl BcsOrderedKey for [u8; $len] {})*
+    };
+}
+
+impl_bcs_ordered_key_for_byte_arrays!(
+    1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+);
+
+/// Types whose custom serialization orders their values in the same way as [`Ord`], so
+/// that the values in a range are serialized as the keys in a range.
+pub trait CustomOrderedKey: CustomSerialize + Ord {}
+
+impl CustomOrderedKey for u128 {}
+
 /// This computes the offset of the BCS serialization of a vector.
 /// The formula that should be satisfied is
 /// `serialized_size(vec![v_1, ...., v_n]) = get_uleb128_size(n)`
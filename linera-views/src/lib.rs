@@ -21,6 +21,9 @@ We provide support for the following databases:
 * `RocksDbStore` is a disk-based key-value store
 * `DynamoDbStore` is the AWS-based DynamoDB service.
 * `ScyllaDbStore` is a cloud-based Cassandra-compatible database.
+* `PostgresStore` is a PostgreSQL database, e.g. a managed one.
+* `FoundationDbStore` is a distributed, strongly consistent FoundationDB cluster.
+* `RedisStore` is an in-memory Redis or KeyDB server, e.g. for test networks.
 * `ServiceStoreClient` is a gRPC-based storage that uses either memory or RocksDB. It is available in `linera-storage-service`.
 
 The corresponding trait in the code is the [`crate::store::KeyValueStore`](https://docs.rs/linera-views/latest/linera_views/store/trait.KeyValueStore.html).
@@ -77,6 +80,9 @@ pub mod views;
 /// Backend implementing the [`crate::store::KeyValueStore`] trait.
 pub mod backends;
 
+/// Transactions writing the changes of several views at once.
+pub mod transaction;
+
 /// Support for metrics.
 #[cfg(with_metrics)]
 pub mod metrics;
@@ -94,18 +100,24 @@ pub mod test_utils;
 
 #[cfg(with_dynamodb)]
 pub use backends::dynamo_db;
+#[cfg(with_foundationdb)]
+pub use backends::foundation_db;
 #[cfg(with_indexeddb)]
 pub use backends::indexed_db;
 #[cfg(with_metrics)]
 pub use backends::metering;
+#[cfg(with_postgres)]
+pub use backends::postgres;
+#[cfg(with_redis)]
+pub use backends::redis_db;
 #[cfg(with_rocksdb)]
 pub use backends::rocks_db;
 #[cfg(with_scylladb)]
 pub use backends::scylla_db;
 pub use backends::{journaling, lru_caching, memory, value_splitting};
 pub use views::{
-    bucket_queue_view, collection_view, hashable_wrapper, key_value_store_view, log_view, map_view,
-    queue_view, reentrant_collection_view, register_view, set_view,
+    bucket_queue_view, collection_view, hashable_wrapper, incremental_hash, key_value_store_view,
+    log_view, map_view, queue_view, reentrant_collection_view, register_view, set_view,
 };
 /// Re-exports used by the derive macros of this library.
 #[doc(hidden)]
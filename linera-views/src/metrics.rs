@@ -8,6 +8,8 @@ use std::sync::LazyLock;
 pub use linera_base::prometheus_util::{self, exponential_bucket_latencies};
 use prometheus::IntCounterVec;
 
+use crate::batch::Batch;
+
 /// Increments the metrics counter with the given name, with the struct and base key as labels.
 pub fn increment_counter(counter: &LazyLock<IntCounterVec>, struct_name: &str, base_key: &[u8]) {
     let base_key = hex::encode(base_key);
@@ -15,6 +17,33 @@ pub fn increment_counter(counter: &LazyLock<IntCounterVec>, struct_name: &str, b
     counter.with_label_values(&labels).inc();
 }
 
+/// Records the number of keys and bytes read when loading a view, with the struct and
+/// base key as labels.
+pub fn record_load_size(struct_name: &str, base_key: &[u8], values: &[Option<Vec<u8>>]) {
+    let bytes = values.iter().flatten().map(Vec::len).sum::<usize>();
+    let base_key = hex::encode(base_key);
+    let labels = [struct_name, &base_key];
+    LOAD_VIEW_KEYS
+        .with_label_values(&labels)
+        .inc_by(values.len() as u64);
+    LOAD_VIEW_BYTES
+        .with_label_values(&labels)
+        .inc_by(bytes as u64);
+}
+
+/// Records the number of write operations and bytes of a batch saving a view, with the
+/// struct and base key as labels.
+pub fn record_save_size(struct_name: &str, base_key: &[u8], batch: &Batch) {
+    let base_key = hex::encode(base_key);
+    let labels = [struct_name, &base_key];
+    SAVE_VIEW_KEYS
+        .with_label_values(&labels)
+        .inc_by(batch.num_operations() as u64);
+    SAVE_VIEW_BYTES
+        .with_label_values(&labels)
+        .inc_by(batch.size() as u64);
+}
+
 /// The metric tracking the latency of the loading of views.
 #[doc(hidden)]
 pub static LOAD_VIEW_LATENCY: LazyLock<prometheus::HistogramVec> = LazyLock::new(|| {
@@ -44,3 +73,43 @@ pub static SAVE_VIEW_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
         &["type", "base_key"],
     )
 });
+
+/// The metric counting the keys read when loading views.
+#[doc(hidden)]
+pub static LOAD_VIEW_KEYS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "load_view_keys",
+        "The number of keys read when loading views",
+        &["type", "base_key"],
+    )
+});
+
+/// The metric counting the bytes read when loading views.
+#[doc(hidden)]
+pub static LOAD_VIEW_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "load_view_bytes",
+        "The number of bytes read when loading views",
+        &["type", "base_key"],
+    )
+});
+
+/// The metric counting the write operations when saving views.
+#[doc(hidden)]
+pub static SAVE_VIEW_KEYS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "save_view_keys",
+        "The number of keys written or deleted when saving views",
+        &["type", "base_key"],
+    )
+});
+
+/// The metric counting the bytes written when saving views.
+#[doc(hidden)]
+pub static SAVE_VIEW_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "save_view_bytes",
+        "The number of bytes of the keys and values written when saving views",
+        &["type", "base_key"],
+    )
+});
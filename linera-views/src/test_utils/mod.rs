@@ -354,6 +354,9 @@ fn update_state_from_batch(kv_state: &mut BTreeMap<Vec<u8>, Vec<u8>>, batch: &Ba
             WriteOperation::DeletePrefix { key_prefix } => {
                 kv_state.retain(|key, _| !key.starts_with(key_prefix));
             }
+            WriteOperation::DeleteRange { start, end } => {
+                kv_state.retain(|key, _| key < start || key >= end);
+            }
         }
     }
 }
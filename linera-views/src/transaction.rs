@@ -0,0 +1,269 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transactions writing the changes of several root views in a single batch.
+//!
+//! The views of a [`MultiViewTransaction`] are loaded with its [`TransactionContext`].
+//! Saving such a view, or writing a batch to the context, only stages the operations, and
+//! reads through the context see the staged operations. [`MultiViewTransaction::commit`]
+//! then writes all of them to the underlying context at once.
+//!
+//! A batch is only atomic within one store: the views of a transaction must use the same
+//! store, e.g. by deriving their contexts from the transaction context with
+//! [`Context::clone_with_base_key`]. If committing fails, the views must be reloaded.
+
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    batch::{Batch, WriteOperation},
+    context::Context,
+    store::{KeyIterable as _, KeyValueIterable as _},
+};
+
+/// A set of changes to several views, written in a single batch.
+pub struct MultiViewTransaction<C> {
+    context: TransactionContext<C>,
+}
+
+impl<C: Context + Send + Sync> MultiViewTransaction<C> {
+    /// Starts a transaction writing to the given context when committed.
+    pub fn new(context: C) -> Self {
+        MultiViewTransaction {
+            context: TransactionContext {
+                inner: context,
+                staged: Arc::default(),
+            },
+        }
+    }
+
+    /// Returns the context to load the views of the transaction with.
+    pub fn context(&self) -> &TransactionContext<C> {
+        &self.context
+    }
+
+    /// Stages the operations of the `batch`, e.g. to write values that are not part of a
+    /// view.
+    pub fn stage_batch(&self, batch: Batch) {
+        self.context.stage_batch(batch);
+    }
+
+    /// Returns whether no operation was staged.
+    pub fn is_empty(&self) -> bool {
+        self.context.staged.lock().unwrap().is_empty()
+    }
+
+    /// Writes all the staged operations to the underlying context.
+    pub async fn commit(self) -> Result<(), C::Error> {
+        let batch = mem::take(&mut *self.context.staged.lock().unwrap());
+        if !batch.is_empty() {
+            self.context.inner.write_batch(batch).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A context staging the writes of a [`MultiViewTransaction`].
+#[derive(Clone, Debug)]
+pub struct TransactionContext<C> {
+    inner: C,
+    /// The operations to write on commit, shared by all the contexts of the transaction.
+    staged: Arc<Mutex<Batch>>,
+}
+
+/// The effect of the staged operations on a key.
+enum StagedValue {
+    /// The key was not written.
+    Unchanged,
+    /// The key was deleted.
+    Deleted,
+    /// The key was set to this value.
+    Put(Vec<u8>),
+}
+
+impl<C> TransactionContext<C> {
+    fn stage_batch(&self, batch: Batch) {
+        self.staged
+            .lock()
+            .unwrap()
+            .operations
+            .extend(batch.operations);
+    }
+
+    fn staged_value(&self, key: &[u8]) -> StagedValue {
+        let staged = self.staged.lock().unwrap();
+        for operation in staged.operations.iter().rev() {
+            match operation {
+                WriteOperation::Put {
+                    key: written_key,
+                    value,
+                } if written_key == key => return StagedValue::Put(value.clone()),
+                WriteOperation::Delete { key: deleted_key } if deleted_key == key => {
+                    return StagedValue::Deleted
+                }
+                WriteOperation::DeletePrefix { key_prefix } if key.starts_with(key_prefix) => {
+                    return StagedValue::Deleted
+                }
+                WriteOperation::DeleteRange { start, end }
+                    if start.as_slice() <= key && key < end.as_slice() =>
+                {
+                    return StagedValue::Deleted
+                }
+                _ => {}
+            }
+        }
+        StagedValue::Unchanged
+    }
+
+    /// Applies the staged operations to the entries of the underlying context under
+    /// the `key_prefix`, indexed by the rest of their keys.
+    fn apply_staged<V>(
+        &self,
+        key_prefix: &[u8],
+        entries: &mut BTreeMap<Vec<u8>, V>,
+        value: impl Fn(&[u8]) -> V,
+    ) {
+        let staged = self.staged.lock().unwrap();
+        for operation in &staged.operations {
+            match operation {
+                WriteOperation::Put { key, value: bytes } => {
+                    if let Some(suffix) = key.strip_prefix(key_prefix) {
+                        entries.insert(suffix.to_vec(), value(bytes));
+                    }
+                }
+                WriteOperation::Delete { key } => {
+                    if let Some(suffix) = key.strip_prefix(key_prefix) {
+                        entries.remove(suffix);
+                    }
+                }
+                WriteOperation::DeletePrefix {
+                    key_prefix: deleted_prefix,
+                } => {
+                    if let Some(deleted_suffix) = deleted_prefix.strip_prefix(key_prefix) {
+                        entries.retain(|suffix, _| !suffix.starts_with(deleted_suffix));
+                    } else if key_prefix.starts_with(deleted_prefix) {
+                        entries.clear();
+                    }
+                }
+                WriteOperation::DeleteRange { start, end } => {
+                    entries.retain(|suffix, _| {
+                        let key = [key_prefix, suffix].concat();
+                        key < *start || key >= *end
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> Context for TransactionContext<C>
+where
+    C: Context + Send + Sync,
+{
+    const MAX_VALUE_SIZE: usize = C::MAX_VALUE_SIZE;
+    const MAX_KEY_SIZE: usize = C::MAX_KEY_SIZE;
+    type Extra = C::Extra;
+    type Error = C::Error;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.inner.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.staged_value(key) {
+            StagedValue::Unchanged => self.inner.read_value_bytes(key).await,
+            StagedValue::Deleted => Ok(None),
+            StagedValue::Put(value) => Ok(Some(value)),
+        }
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        match self.staged_value(key) {
+            StagedValue::Unchanged => self.inner.contains_key(key).await,
+            StagedValue::Deleted => Ok(false),
+            StagedValue::Put(_) => Ok(true),
+        }
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        let values = self.read_multi_values_bytes(keys).await?;
+        Ok(values.iter().map(Option::is_some).collect())
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let mut values = Vec::with_capacity(keys.len());
+        let mut unchanged_indices = Vec::new();
+        let mut unchanged_keys = Vec::new();
+        for (index, key) in keys.into_iter().enumerate() {
+            match self.staged_value(&key) {
+                StagedValue::Unchanged => {
+                    unchanged_indices.push(index);
+                    unchanged_keys.push(key);
+                    values.push(None);
+                }
+                StagedValue::Deleted => values.push(None),
+                StagedValue::Put(value) => values.push(Some(value)),
+            }
+        }
+        let stored_values = self.inner.read_multi_values_bytes(unchanged_keys).await?;
+        for (index, value) in unchanged_indices.into_iter().zip(stored_values) {
+            values[index] = value;
+        }
+        Ok(values)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        let stored_keys = self.inner.find_keys_by_prefix(key_prefix).await?;
+        let mut keys = BTreeMap::new();
+        for key in stored_keys.iterator() {
+            keys.insert(key?.to_vec(), ());
+        }
+        self.apply_staged(key_prefix, &mut keys, |_| ());
+        Ok(keys.into_keys().collect())
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        let stored_key_values = self.inner.find_key_values_by_prefix(key_prefix).await?;
+        let mut key_values = stored_key_values
+            .into_iterator_owned()
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        self.apply_staged(key_prefix, &mut key_values, <[u8]>::to_vec);
+        Ok(key_values.into_iter().collect())
+    }
+
+    /// Stages the operations of the `batch`, to be written when the transaction is
+    /// committed.
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        self.stage_batch(batch);
+        Ok(())
+    }
+
+    fn extra(&self) -> &Self::Extra {
+        self.inner.extra()
+    }
+
+    fn clone_with_base_key(&self, base_key: Vec<u8>) -> Self {
+        TransactionContext {
+            inner: self.inner.clone_with_base_key(base_key),
+            staged: self.staged.clone(),
+        }
+    }
+
+    fn base_key(&self) -> Vec<u8> {
+        self.inner.base_key()
+    }
+}
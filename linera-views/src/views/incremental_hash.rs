@@ -0,0 +1,427 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hashes of collections that are updated with the pending changes instead of being
+//! recomputed from all the entries.
+//!
+//! The hash is derived from an [`LtHash`] accumulator: a homomorphic multiset hash of
+//! the entries, to which entries can be added and from which they can be removed. The
+//! accumulator of the entries in storage is saved with the view, and computing the hash
+//! only reads the entries that were modified or deleted.
+//!
+//! The keys of an [`IncrementallyHashedView`] are laid out like those of a
+//! [`WrappedHashableContainerView`](crate::hashable_wrapper::WrappedHashableContainerView),
+//! so e.g. a `HashedMapView` can be replaced by an `IncrementallyHashedMapView` without
+//! moving its data. Such a view keeps the [`HashVersion::Legacy`] hash, which is the same as
+//! that of the wrapper it replaces, until [`IncrementallyHashedView::switch_hash_version`]
+//! is called. The version is saved with the view, so for states that validators agree on,
+//! the switch can be made at an agreed point, like any other protocol change.
+
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha3::digest::{ExtendableOutput as _, Update as _, XofReader as _};
+
+use crate::{
+    batch::Batch,
+    common::{from_bytes_option, HasherOutput},
+    context::Context,
+    views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
+};
+
+/// The number of 16-bit lanes of an [`LtHash`].
+const LT_HASH_LANES: usize = 1024;
+
+/// A homomorphic multiset hash: the lane-wise sum of the extended hashes of the entries.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LtHash {
+    lanes: Vec<u16>,
+}
+
+impl Default for LtHash {
+    fn default() -> Self {
+        Self {
+            lanes: vec![0; LT_HASH_LANES],
+        }
+    }
+}
+
+impl LtHash {
+    /// Adds the entry with the given key and value bytes.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        for (lane, entry_lane) in self.lanes.iter_mut().zip(Self::entry_lanes(key, value)) {
+            *lane = lane.wrapping_add(entry_lane);
+        }
+    }
+
+    /// Removes the entry with the given key and value bytes, which must have been added.
+    pub fn remove(&mut self, key: &[u8], value: &[u8]) {
+        for (lane, entry_lane) in self.lanes.iter_mut().zip(Self::entry_lanes(key, value)) {
+            *lane = lane.wrapping_sub(entry_lane);
+        }
+    }
+
+    /// Returns the hash of the multiset.
+    pub fn finalize(&self) -> HasherOutput {
+        let mut hasher = sha3::Sha3_256::default();
+        for lane in &self.lanes {
+            sha3::Digest::update(&mut hasher, lane.to_le_bytes());
+        }
+        Hasher::finalize(hasher)
+    }
+
+    fn entry_lanes(key: &[u8], value: &[u8]) -> impl Iterator<Item = u16> {
+        let mut shake = sha3::Shake256::default();
+        shake.update(&(key.len() as u64).to_le_bytes());
+        shake.update(key);
+        shake.update(value);
+        let mut bytes = vec![0; 2 * LT_HASH_LANES];
+        shake.finalize_xof().read(&mut bytes);
+        (0..LT_HASH_LANES).map(move |i| u16::from_le_bytes([bytes[2 * i], bytes[2 * i + 1]]))
+    }
+}
+
+/// A view whose hash can be maintained in an [`LtHash`] of its entries.
+#[async_trait]
+pub trait IncrementalHashView<C>: View<C> {
+    /// Adds all the entries of the view, including the pending changes, to the
+    /// accumulator.
+    async fn accumulate_all(&self, accumulator: &mut LtHash) -> Result<(), ViewError>;
+
+    /// Updates the accumulator of the entries in storage with the pending changes.
+    async fn accumulate_changes(&self, accumulator: &mut LtHash) -> Result<(), ViewError>;
+}
+
+/// The ways the hash of an [`IncrementallyHashedView`] can be computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashVersion {
+    /// The hash is computed from all the entries, like that of a
+    /// [`WrappedHashableContainerView`](crate::hashable_wrapper::WrappedHashableContainerView).
+    Legacy,
+    /// The hash is derived from an [`LtHash`] of the entries, maintained incrementally.
+    Incremental,
+}
+
+/// Key tags to create the sub-keys of an `IncrementallyHashedView` on top of the base key.
+#[repr(u8)]
+enum KeyTag {
+    /// Prefix for the indices of the view.
+    Inner = MIN_VIEW_TAG,
+    /// The memoized hash of the [`HashVersion::Legacy`], stored at the same place as the
+    /// hash of a `WrappedHashableContainerView`.
+    LegacyHash,
+    /// Prefix for the accumulator. The view uses the [`HashVersion::Incremental`] if and
+    /// only if this key is present.
+    Accumulator,
+}
+
+/// The hash of an `IncrementallyHashedView`, if known, for the view's hash version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum HashState {
+    Legacy(Option<HasherOutput>),
+    Incremental(Option<LtHash>),
+}
+
+impl HashState {
+    fn version(&self) -> HashVersion {
+        match self {
+            HashState::Legacy(_) => HashVersion::Legacy,
+            HashState::Incremental(_) => HashVersion::Incremental,
+        }
+    }
+
+    /// Forgets the hash, keeping the version.
+    fn invalidate(&mut self) {
+        *self = match self {
+            HashState::Legacy(_) => HashState::Legacy(None),
+            HashState::Incremental(_) => HashState::Incremental(None),
+        };
+    }
+}
+
+/// A wrapper maintaining the hash of a view incrementally, once switched to the
+/// [`HashVersion::Incremental`].
+#[derive(Debug)]
+pub struct IncrementallyHashedView<C, W> {
+    _phantom: PhantomData<C>,
+    /// The hash of the entries in storage, if known.
+    stored_state: HashState,
+    /// The hash of the current entries, if known.
+    state: Mutex<HashState>,
+    inner: W,
+}
+
+impl<C, W> IncrementallyHashedView<C, W>
+where
+    C: Context + Send + Sync,
+    W: IncrementalHashView<C> + HashableView<C, Hasher = sha3::Sha3_256> + Send + Sync,
+{
+    /// Returns the version of the hash of the view.
+    pub fn hash_version(&self) -> HashVersion {
+        self.state.lock().unwrap().version()
+    }
+
+    /// Makes the hash of the view use the [`HashVersion::Incremental`] from now on. This
+    /// changes the hash, and the accumulator is computed from all the entries once, the next
+    /// time the hash is requested.
+    pub fn switch_hash_version(&mut self) {
+        let state = self.state.get_mut().unwrap();
+        if state.version() == HashVersion::Legacy {
+            *state = HashState::Incremental(None);
+        }
+    }
+
+    /// Returns the accumulator of the current entries.
+    async fn compute_accumulator(&self) -> Result<LtHash, ViewError> {
+        match &self.stored_state {
+            HashState::Incremental(Some(stored_accumulator)) => {
+                let mut accumulator = stored_accumulator.clone();
+                self.inner.accumulate_changes(&mut accumulator).await?;
+                Ok(accumulator)
+            }
+            _ => {
+                let mut accumulator = LtHash::default();
+                self.inner.accumulate_all(&mut accumulator).await?;
+                Ok(accumulator)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C, W> View<C> for IncrementallyHashedView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: IncrementalHashView<C> + HashableView<C, Hasher = sha3::Sha3_256> + Send + Sync,
+{
+    const NUM_INIT_KEYS: usize = 2 + W::NUM_INIT_KEYS;
+
+    fn context(&self) -> &C {
+        self.inner.context()
+    }
+
+    fn pre_load(context: &C) -> Result<Vec<Vec<u8>>, ViewError> {
+        let mut v = vec![
+            context.base_tag(KeyTag::LegacyHash as u8),
+            context.base_tag(KeyTag::Accumulator as u8),
+        ];
+        let base_key = context.base_tag(KeyTag::Inner as u8);
+        let context = context.clone_with_base_key(base_key);
+        v.extend(W::pre_load(&context)?);
+        Ok(v)
+    }
+
+    fn post_load(context: C, values: &[Option<Vec<u8>>]) -> Result<Self, ViewError> {
+        let legacy_hash = from_bytes_option(values.first().ok_or(ViewError::PostLoadValuesError)?)?;
+        let accumulator: Option<Option<LtHash>> =
+            from_bytes_option(values.get(1).ok_or(ViewError::PostLoadValuesError)?)?;
+        let state = match accumulator {
+            Some(accumulator) => HashState::Incremental(accumulator),
+            None => HashState::Legacy(legacy_hash),
+        };
+        let base_key = context.base_tag(KeyTag::Inner as u8);
+        let context = context.clone_with_base_key(base_key);
+        let inner = W::post_load(
+            context,
+            values.get(2..).ok_or(ViewError::PostLoadValuesError)?,
+        )?;
+        Ok(Self {
+            _phantom: PhantomData,
+            stored_state: state.clone(),
+            state: Mutex::new(state),
+            inner,
+        })
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let keys = Self::pre_load(&context)?;
+        let values = context.read_multi_values_bytes(keys).await?;
+        Self::post_load(context, &values)
+    }
+
+    fn rollback(&mut self) {
+        self.inner.rollback();
+        *self.state.get_mut().unwrap() = self.stored_state.clone();
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        if self.inner.has_pending_changes().await {
+            return true;
+        }
+        let state = self.state.lock().unwrap();
+        self.stored_state != *state
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        let mut delete_view = self.inner.flush(batch)?;
+        let state = self.state.get_mut().unwrap();
+        let mut key_prefix = self.inner.context().base_key();
+        key_prefix.pop();
+        if delete_view {
+            if state.version() == HashVersion::Legacy {
+                batch.delete_key_prefix(key_prefix);
+                self.stored_state = HashState::Legacy(None);
+                *state = HashState::Legacy(None);
+                return Ok(true);
+            }
+            // The accumulator is kept so that the view keeps its hash version.
+            *state = HashState::Incremental(Some(LtHash::default()));
+            delete_view = false;
+        }
+        if self.stored_state != *state {
+            let mut legacy_key = key_prefix.clone();
+            legacy_key.push(KeyTag::LegacyHash as u8);
+            match &*state {
+                HashState::Legacy(None) => batch.delete_key(legacy_key),
+                HashState::Legacy(Some(hash)) => batch.put_key_value(legacy_key, hash)?,
+                HashState::Incremental(accumulator) => {
+                    if self.stored_state.version() == HashVersion::Legacy {
+                        batch.delete_key(legacy_key);
+                    }
+                    let mut key = key_prefix;
+                    key.push(KeyTag::Accumulator as u8);
+                    // If the changes were not accumulated, `None` is saved and the
+                    // accumulator will be recomputed from all the entries.
+                    batch.put_key_value(key, accumulator)?;
+                }
+            }
+            self.stored_state = state.clone();
+        }
+        Ok(delete_view)
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+        let state = self.state.get_mut().unwrap();
+        *state = match state {
+            HashState::Legacy(_) => HashState::Legacy(None),
+            HashState::Incremental(_) => HashState::Incremental(Some(LtHash::default())),
+        };
+    }
+}
+
+impl<C, W> ClonableView<C> for IncrementallyHashedView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: IncrementalHashView<C>
+        + HashableView<C, Hasher = sha3::Sha3_256>
+        + ClonableView<C>
+        + Send
+        + Sync,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        Ok(IncrementallyHashedView {
+            _phantom: PhantomData,
+            stored_state: self.stored_state.clone(),
+            state: Mutex::new(self.state.get_mut().unwrap().clone()),
+            inner: self.inner.clone_unchecked()?,
+        })
+    }
+}
+
+#[async_trait]
+impl<C, W> HashableView<C> for IncrementallyHashedView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: IncrementalHashView<C> + HashableView<C, Hasher = sha3::Sha3_256> + Send + Sync,
+{
+    type Hasher = sha3::Sha3_256;
+
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        let version = match self.state.get_mut().unwrap() {
+            HashState::Legacy(Some(hash)) => return Ok(*hash),
+            HashState::Incremental(Some(accumulator)) => return Ok(accumulator.finalize()),
+            state => state.version(),
+        };
+        match version {
+            HashVersion::Legacy => {
+                let hash = self.inner.hash_mut().await?;
+                *self.state.get_mut().unwrap() = HashState::Legacy(Some(hash));
+                Ok(hash)
+            }
+            HashVersion::Incremental => {
+                let accumulator = self.compute_accumulator().await?;
+                let hash = accumulator.finalize();
+                *self.state.get_mut().unwrap() = HashState::Incremental(Some(accumulator));
+                Ok(hash)
+            }
+        }
+    }
+
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        let version = match &*self.state.lock().unwrap() {
+            HashState::Legacy(Some(hash)) => return Ok(*hash),
+            HashState::Incremental(Some(accumulator)) => return Ok(accumulator.finalize()),
+            state => state.version(),
+        };
+        match version {
+            HashVersion::Legacy => {
+                let hash = self.inner.hash().await?;
+                *self.state.lock().unwrap() = HashState::Legacy(Some(hash));
+                Ok(hash)
+            }
+            HashVersion::Incremental => {
+                let accumulator = self.compute_accumulator().await?;
+                let hash = accumulator.finalize();
+                *self.state.lock().unwrap() = HashState::Incremental(Some(accumulator));
+                Ok(hash)
+            }
+        }
+    }
+}
+
+impl<C, W> Deref for IncrementallyHashedView<C, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<C, W> DerefMut for IncrementallyHashedView<C, W> {
+    fn deref_mut(&mut self) -> &mut W {
+        self.state.get_mut().unwrap().invalidate();
+        &mut self.inner
+    }
+}
+
+mod graphql {
+    use std::borrow::Cow;
+
+    use super::IncrementallyHashedView;
+    use crate::context::Context;
+
+    impl<C, W> async_graphql::OutputType for IncrementallyHashedView<C, W>
+    where
+        C: Context + Send + Sync,
+        W: async_graphql::OutputType + Send + Sync,
+    {
+        fn type_name() -> Cow<'static, str> {
+            W::type_name()
+        }
+
+        fn qualified_type_name() -> String {
+            W::qualified_type_name()
+        }
+
+        fn create_type_info(registry: &mut async_graphql::registry::Registry) -> String {
+            W::create_type_info(registry)
+        }
+
+        async fn resolve(
+            &self,
+            ctx: &async_graphql::ContextSelectionSet<'_>,
+            field: &async_graphql::Positioned<async_graphql::parser::types::Field>,
+        ) -> async_graphql::ServerResult<async_graphql::Value> {
+            (**self).resolve(ctx, field).await
+        }
+    }
+}
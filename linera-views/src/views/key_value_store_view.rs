@@ -31,8 +31,9 @@ use {
 use crate::{
     batch::{Batch, WriteOperation},
     common::{
-        from_bytes_option, from_bytes_option_or_default, get_interval, get_upper_bound,
-        DeletionSet, HasherOutput, SuffixClosedSetIterator, Update,
+        from_bytes_option, from_bytes_option_or_default, get_interval, get_range_prefix,
+        get_upper_bound, get_upper_bound_option, DeletionSet, HasherOutput,
+        SuffixClosedSetIterator, Update,
     },
     context::Context,
     map_view::ByteMapView,
@@ -317,6 +318,14 @@ where
                 let key = self.context.base_tag_index(KeyTag::Index as u8, &index);
                 batch.delete_key_prefix(key);
             }
+            for (start, end) in mem::take(&mut self.deletion_set.deleted_ranges) {
+                let start = self.context.base_tag_index(KeyTag::Index as u8, &start);
+                let end = match end {
+                    Some(end) => Some(self.context.base_tag_index(KeyTag::Index as u8, &end)),
+                    None => get_upper_bound_option(&self.context.base_tag(KeyTag::Index as u8)),
+                };
+                batch.delete_key_range(start, end);
+            }
             for (index, update) in mem::take(&mut self.updates) {
                 let key = self.context.base_tag_index(KeyTag::Index as u8, &index);
                 match update {
@@ -451,7 +460,10 @@ where
                             }
                         }
                         _ => {
-                            if !suffix_closed_set.find_key(index) && !f(index)? {
+                            if !suffix_closed_set.find_key(index)
+                                && !self.deletion_set.is_in_deleted_range(index, &[])
+                                && !f(index)?
+                            {
                                 return Ok(());
                             }
                             break;
@@ -555,7 +567,10 @@ where
                             }
                         }
                         _ => {
-                            if !suffix_closed_set.find_key(index) && !f(index, index_val)? {
+                            if !suffix_closed_set.find_key(index)
+                                && !self.deletion_set.is_in_deleted_range(index, &[])
+                                && !f(index, index_val)?
+                            {
                                 return Ok(());
                             }
                             break;
@@ -702,7 +717,7 @@ where
             };
             return Ok(value);
         }
-        if self.deletion_set.contains_prefix_of(index) {
+        if self.deletion_set.is_deleted(index) {
             return Ok(None);
         }
         let key = self.context.base_tag_index(KeyTag::Index as u8, index);
@@ -733,7 +748,7 @@ where
             };
             return Ok(test);
         }
-        if self.deletion_set.contains_prefix_of(index) {
+        if self.deletion_set.is_deleted(index) {
             return Ok(false);
         }
         let key = self.context.base_tag_index(KeyTag::Index as u8, index);
@@ -770,7 +785,7 @@ where
                 results.push(value);
             } else {
                 results.push(false);
-                if !self.deletion_set.contains_prefix_of(&index) {
+                if !self.deletion_set.is_deleted(&index) {
                     missed_indices.push(i);
                     let key = self.context.base_tag_index(KeyTag::Index as u8, &index);
                     vector_query.push(key);
@@ -818,7 +833,7 @@ where
                 result.push(value);
             } else {
                 result.push(None);
-                if !self.deletion_set.contains_prefix_of(&index) {
+                if !self.deletion_set.is_deleted(&index) {
                     missed_indices.push(i);
                     let key = self.context.base_tag_index(KeyTag::Index as u8, &index);
                     vector_query.push(key);
@@ -867,7 +882,7 @@ where
                         self.total_size.sub_assign(entry_size);
                     }
                     self.sizes.remove(key.clone());
-                    if self.deletion_set.contains_prefix_of(&key) {
+                    if self.deletion_set.is_deleted(&key) {
                         // Optimization: No need to mark `short_key` for deletion as we are going to remove all the keys at once.
                         self.updates.remove(&key);
                     } else {
@@ -913,6 +928,36 @@ where
                     self.sizes.remove_by_prefix(key_prefix.clone());
                     self.deletion_set.insert_key_prefix(key_prefix);
                 }
+                WriteOperation::DeleteRange { start, end } => {
+                    ensure!(
+                        start.len() <= max_key_size && end.len() <= max_key_size,
+                        ViewError::KeyTooLong
+                    );
+                    if start >= end {
+                        continue;
+                    }
+                    let key_list = self
+                        .updates
+                        .range(start.clone()..end.clone())
+                        .map(|x| x.0.to_vec())
+                        .collect::<Vec<_>>();
+                    for key in key_list {
+                        self.updates.remove(&key);
+                    }
+                    let key_prefix = get_range_prefix(&start, Some(&end)).to_vec();
+                    let key_values = self.sizes.key_values_by_prefix(key_prefix).await?;
+                    for (key, value) in key_values {
+                        if start <= key && key < end {
+                            let entry_size = SizeData {
+                                key: key.len() as u32,
+                                value,
+                            };
+                            self.total_size.sub_assign(entry_size);
+                        }
+                    }
+                    self.sizes.clear_range(start.clone()..end.clone());
+                    self.deletion_set.insert_key_range(start, Some(end));
+                }
             }
         }
         Ok(())
@@ -1027,7 +1072,9 @@ where
                         _ => {
                             let mut key_with_prefix = key_prefix.to_vec();
                             key_with_prefix.extend_from_slice(key);
-                            if !suffix_closed_set.find_key(&key_with_prefix) {
+                            if !suffix_closed_set.find_key(&key_with_prefix)
+                                && !self.deletion_set.is_in_deleted_range(&key_with_prefix, &[])
+                            {
                                 keys.push(key.to_vec());
                             }
                             break;
@@ -1104,7 +1151,9 @@ where
                         _ => {
                             let mut key_with_prefix = key_prefix.to_vec();
                             key_with_prefix.extend_from_slice(&key);
-                            if !suffix_closed_set.find_key(&key_with_prefix) {
+                            if !suffix_closed_set.find_key(&key_with_prefix)
+                                && !self.deletion_set.is_in_deleted_range(&key_with_prefix, &[])
+                            {
                                 key_values.push((key, value));
                             }
                             break;
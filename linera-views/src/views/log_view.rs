@@ -20,6 +20,7 @@ use crate::{
     common::{from_bytes_option_or_default, HasherOutput},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    incremental_hash::{IncrementalHashView, IncrementallyHashedView, LtHash},
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
 
@@ -363,9 +364,42 @@ where
     }
 }
 
+#[async_trait]
+impl<C, T> IncrementalHashView<C> for LogView<C, T>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    T: Send + Sync + Clone + Serialize + DeserializeOwned,
+{
+    async fn accumulate_all(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        for (index, value) in self.read(..).await?.iter().enumerate() {
+            accumulator.insert(&bcs::to_bytes(&index)?, &bcs::to_bytes(value)?);
+        }
+        Ok(())
+    }
+
+    async fn accumulate_changes(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        let start = if self.delete_storage_first {
+            *accumulator = LtHash::default();
+            0
+        } else {
+            self.stored_count
+        };
+        for (offset, value) in self.new_values.iter().enumerate() {
+            let index = start + offset;
+            accumulator.insert(&bcs::to_bytes(&index)?, &bcs::to_bytes(value)?);
+        }
+        Ok(())
+    }
+}
+
 /// Type wrapping `LogView` while memoizing the hash.
 pub type HashedLogView<C, T> = WrappedHashableContainerView<C, LogView<C, T>, HasherOutput>;
 
+/// Type wrapping `LogView` while maintaining the hash incrementally. Appending values
+/// does not read the log.
+pub type IncrementallyHashedLogView<C, T> = IncrementallyHashedView<C, LogView<C, T>>;
+
 mod graphql {
     use std::borrow::Cow;
 
@@ -43,6 +43,7 @@ use std::{
     collections::{btree_map::Entry, BTreeMap},
     marker::PhantomData,
     mem,
+    ops::{Bound, RangeBounds},
 };
 
 use async_trait::async_trait;
@@ -51,11 +52,13 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     batch::Batch,
     common::{
-        from_bytes_option, get_interval, CustomSerialize, DeletionSet, HasherOutput,
-        SuffixClosedSetIterator, Update,
+        from_bytes_option, get_interval, get_range_bounds, get_range_prefix,
+        get_upper_bound_option, BcsOrderedKey, CustomOrderedKey, CustomSerialize, DeletionSet,
+        HasherOutput, SuffixClosedSetIterator, Update,
     },
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    incremental_hash::{IncrementalHashView, IncrementallyHashedView, LtHash},
     store::{KeyIterable, KeyValueIterable},
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
@@ -157,6 +160,14 @@ where
                 let key = self.context.base_index(&index);
                 batch.delete_key_prefix(key);
             }
+            for (start, end) in mem::take(&mut self.deletion_set.deleted_ranges) {
+                let start = self.context.base_index(&start);
+                let end = match end {
+                    Some(end) => Some(self.context.base_index(&end)),
+                    None => get_upper_bound_option(&self.context.base_key()),
+                };
+                batch.delete_key_range(start, end);
+            }
             for (index, update) in mem::take(&mut self.updates) {
                 let key = self.context.base_index(&index);
                 match update {
@@ -224,7 +235,7 @@ where
     /// # })
     /// ```
     pub fn remove(&mut self, short_key: Vec<u8>) {
-        if self.deletion_set.contains_prefix_of(&short_key) {
+        if self.deletion_set.is_deleted(&short_key) {
             // Optimization: No need to mark `short_key` for deletion as we are going to remove a range of keys containing it.
             self.updates.remove(&short_key);
         } else {
@@ -258,6 +269,38 @@ where
         self.deletion_set.insert_key_prefix(key_prefix);
     }
 
+    /// Removes the values whose keys are in the given range.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![0, 2], String::from("Bonjour"));
+    /// map.insert(vec![1], String::from("Hallo"));
+    /// map.clear_range(vec![0, 2]..);
+    /// assert_eq!(map.keys().await.unwrap(), vec![vec![0, 1]]);
+    /// # })
+    /// ```
+    pub fn clear_range(&mut self, range: impl RangeBounds<Vec<u8>>) {
+        let (start, end) = get_range_bounds(range);
+        if end.as_ref().is_some_and(|end| start >= *end) {
+            return;
+        }
+        let upper_bound = end.clone().map_or(Bound::Unbounded, Bound::Excluded);
+        let key_list = self
+            .updates
+            .range((Bound::Included(start.clone()), upper_bound))
+            .map(|x| x.0.to_vec())
+            .collect::<Vec<_>>();
+        for key in key_list {
+            self.updates.remove(&key);
+        }
+        self.deletion_set.insert_key_range(start, end);
+    }
+
     /// Obtains the extra data.
     pub fn extra(&self) -> &C::Extra {
         self.context.extra()
@@ -284,7 +327,7 @@ where
             };
             return Ok(test);
         }
-        if self.deletion_set.contains_prefix_of(short_key) {
+        if self.deletion_set.is_deleted(short_key) {
             return Ok(false);
         }
         let key = self.context.base_index(short_key);
@@ -318,7 +361,7 @@ where
             };
             return Ok(value);
         }
-        if self.deletion_set.contains_prefix_of(short_key) {
+        if self.deletion_set.is_deleted(short_key) {
             return Ok(None);
         }
         let key = self.context.base_index(short_key);
@@ -348,7 +391,7 @@ where
                 if let Update::Set(value) = update {
                     results[i] = Some(value.clone());
                 }
-            } else if !self.deletion_set.contains_prefix_of(&short_key) {
+            } else if !self.deletion_set.is_deleted(&short_key) {
                 missed_indices.push(i);
                 let key = self.context.base_index(&short_key);
                 vector_query.push(key);
@@ -379,7 +422,7 @@ where
     pub async fn get_mut(&mut self, short_key: &[u8]) -> Result<Option<&mut V>, ViewError> {
         let update = match self.updates.entry(short_key.to_vec()) {
             Entry::Vacant(e) => {
-                if self.deletion_set.contains_prefix_of(short_key) {
+                if self.deletion_set.is_deleted(short_key) {
                     None
                 } else {
                     let key = self.context.base_index(short_key);
@@ -459,7 +502,10 @@ where
                             }
                         }
                         _ => {
-                            if !suffix_closed_set.find_key(index) && !f(index)? {
+                            if !suffix_closed_set.find_key(index)
+                                && !self.deletion_set.is_in_deleted_range(&prefix, index)
+                                && !f(index)?
+                            {
                                 return Ok(());
                             }
                             break;
@@ -657,7 +703,9 @@ where
                             }
                         }
                         _ => {
-                            if !suffix_closed_set.find_key(&index) {
+                            if !suffix_closed_set.find_key(&index)
+                                && !self.deletion_set.is_in_deleted_range(&prefix, &index)
+                            {
                                 let value = ValueOrBytes::Bytes(bytes);
                                 if !f(&index, value)? {
                                     return Ok(());
@@ -881,7 +929,7 @@ where
     /// ```
     pub async fn get_mut_or_default(&mut self, short_key: &[u8]) -> Result<&mut V, ViewError> {
         let update = match self.updates.entry(short_key.to_vec()) {
-            Entry::Vacant(e) if self.deletion_set.contains_prefix_of(short_key) => {
+            Entry::Vacant(e) if self.deletion_set.is_deleted(short_key) => {
                 e.insert(Update::Set(V::default()))
             }
             Entry::Vacant(e) => {
@@ -942,6 +990,89 @@ where
     }
 }
 
+#[async_trait]
+impl<C, V> IncrementalHashView<C> for ByteMapView<C, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    async fn accumulate_all(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        let prefix = Vec::new();
+        self.for_each_key_value_or_bytes(
+            |index, value| {
+                accumulator.insert(index, &value.into_bytes()?);
+                Ok(())
+            },
+            prefix,
+        )
+        .await
+    }
+
+    async fn accumulate_changes(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        if self.deletion_set.delete_storage_first {
+            *accumulator = LtHash::default();
+        } else {
+            for prefix in &self.deletion_set.deleted_prefixes {
+                let key_prefix = self.context.base_index(prefix);
+                for entry in self
+                    .context
+                    .find_key_values_by_prefix(&key_prefix)
+                    .await?
+                    .iterator()
+                {
+                    let (suffix, bytes) = entry?;
+                    let mut index = prefix.clone();
+                    index.extend_from_slice(suffix);
+                    accumulator.remove(&index, bytes);
+                }
+            }
+            for (position, (start, end)) in self.deletion_set.deleted_ranges.iter().enumerate() {
+                let prefix = get_range_prefix(start, end.as_deref());
+                let key_prefix = self.context.base_index(prefix);
+                for entry in self
+                    .context
+                    .find_key_values_by_prefix(&key_prefix)
+                    .await?
+                    .iterator()
+                {
+                    let (suffix, bytes) = entry?;
+                    // Keys in a deleted prefix or in an earlier range are removed already.
+                    if self.deletion_set.find_deleted_range(prefix, suffix) == Some(position) {
+                        let mut index = prefix.to_vec();
+                        index.extend_from_slice(suffix);
+                        if !self.deletion_set.contains_prefix_of(&index) {
+                            accumulator.remove(&index, bytes);
+                        }
+                    }
+                }
+            }
+            // The stored values of the other updated keys are replaced.
+            let indices = self
+                .updates
+                .keys()
+                .filter(|index| !self.deletion_set.is_deleted(index))
+                .collect::<Vec<_>>();
+            let keys = indices
+                .iter()
+                .map(|index| self.context.base_index(index))
+                .collect();
+            let stored_values = self.context.read_multi_values_bytes(keys).await?;
+            for (index, bytes) in indices.into_iter().zip(stored_values) {
+                if let Some(bytes) = bytes {
+                    accumulator.remove(index, &bytes);
+                }
+            }
+        }
+        for (index, update) in &self.updates {
+            if let Update::Set(value) = update {
+                accumulator.insert(index, &bcs::to_bytes(value)?);
+            }
+        }
+        Ok(())
+    }
+}
+
 /// A `View` that has a type for keys. The ordering of the entries
 /// is determined by the serialization of the context.
 #[derive(Debug)]
@@ -1065,6 +1196,32 @@ where
         Ok(())
     }
 
+    /// Removes the values whose indices are in the given range. This is only available
+    /// for indices whose BCS serialization preserves their order, see [`BcsOrderedKey`].
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = MapView::<_, u8, String>::load(context).await.unwrap();
+    /// map.insert(&(3 as u8), String::from("Hello"));
+    /// map.insert(&(37 as u8), String::from("Bonjour"));
+    /// map.clear_range(&(10 as u8)..).unwrap();
+    /// assert_eq!(map.indices().await.unwrap(), vec![3]);
+    /// # })
+    /// ```
+    pub fn clear_range<'a, Q>(&mut self, range: impl RangeBounds<&'a Q>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: BcsOrderedKey + 'a,
+    {
+        let start = serialize_bound(range.start_bound(), |index| C::derive_short_key(*index))?;
+        let end = serialize_bound(range.end_bound(), |index| C::derive_short_key(*index))?;
+        self.map.clear_range((start, end));
+        Ok(())
+    }
+
     /// Obtains the extra data.
     pub fn extra(&self) -> &C::Extra {
         self.map.extra()
@@ -1438,6 +1595,23 @@ where
     }
 }
 
+#[async_trait]
+impl<C, I, V> IncrementalHashView<C> for MapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize + DeserializeOwned,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    async fn accumulate_all(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        self.map.accumulate_all(accumulator).await
+    }
+
+    async fn accumulate_changes(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        self.map.accumulate_changes(accumulator).await
+    }
+}
+
 /// A map view that uses custom serialization
 #[derive(Debug)]
 pub struct CustomMapView<C, I, V> {
@@ -1560,6 +1734,33 @@ where
         Ok(())
     }
 
+    /// Removes the values whose indices are in the given range. This is only available
+    /// for indices whose custom serialization preserves their order, see
+    /// [`CustomOrderedKey`].
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = CustomMapView::<_, u128, String>::load(context).await.unwrap();
+    /// map.insert(&(3 as u128), String::from("Hello"));
+    /// map.insert(&(300 as u128), String::from("Bonjour"));
+    /// map.clear_range(&(10 as u128)..).unwrap();
+    /// assert_eq!(map.indices().await.unwrap(), vec![3]);
+    /// # })
+    /// ```
+    pub fn clear_range<'a, Q>(&mut self, range: impl RangeBounds<&'a Q>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: CustomOrderedKey + 'a,
+    {
+        let start = serialize_bound(range.start_bound(), |index| index.to_custom_bytes())?;
+        let end = serialize_bound(range.end_bound(), |index| index.to_custom_bytes())?;
+        self.map.clear_range((start, end));
+        Ok(())
+    }
+
     /// Obtains the extra data.
     pub fn extra(&self) -> &C::Extra {
         self.map.extra()
@@ -1938,6 +2139,35 @@ where
     }
 }
 
+#[async_trait]
+impl<C, I, V> IncrementalHashView<C> for CustomMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + CustomSerialize,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    async fn accumulate_all(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        self.map.accumulate_all(accumulator).await
+    }
+
+    async fn accumulate_changes(&self, accumulator: &mut LtHash) -> Result<(), ViewError> {
+        self.map.accumulate_changes(accumulator).await
+    }
+}
+
+/// Serializes the index of a range bound.
+fn serialize_bound<T, E>(
+    bound: Bound<&T>,
+    serialize: impl FnOnce(&T) -> Result<Vec<u8>, E>,
+) -> Result<Bound<Vec<u8>>, E> {
+    Ok(match bound {
+        Bound::Included(index) => Bound::Included(serialize(index)?),
+        Bound::Excluded(index) => Bound::Excluded(serialize(index)?),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
 /// Type wrapping `ByteMapView` while memoizing the hash.
 pub type HashedByteMapView<C, V> = WrappedHashableContainerView<C, ByteMapView<C, V>, HasherOutput>;
 
@@ -1948,6 +2178,16 @@ pub type HashedMapView<C, I, V> = WrappedHashableContainerView<C, MapView<C, I,
 pub type HashedCustomMapView<C, I, V> =
     WrappedHashableContainerView<C, CustomMapView<C, I, V>, HasherOutput>;
 
+/// Type wrapping `ByteMapView` while maintaining the hash incrementally.
+pub type IncrementallyHashedByteMapView<C, V> = IncrementallyHashedView<C, ByteMapView<C, V>>;
+
+/// Type wrapping `MapView` while maintaining the hash incrementally.
+pub type IncrementallyHashedMapView<C, I, V> = IncrementallyHashedView<C, MapView<C, I, V>>;
+
+/// Type wrapping `CustomMapView` while maintaining the hash incrementally.
+pub type IncrementallyHashedCustomMapView<C, I, V> =
+    IncrementallyHashedView<C, CustomMapView<C, I, V>>;
+
 mod graphql {
     use std::borrow::Cow;
 
@@ -51,6 +51,9 @@ pub mod key_value_store_view;
 /// Wrapping a view to compute a hash.
 pub mod hashable_wrapper;
 
+/// Wrapping a view to maintain its hash incrementally.
+pub mod incremental_hash;
+
 /// The minimum value for the view tags. Values in `0..MIN_VIEW_TAG` are used for other purposes.
 pub const MIN_VIEW_TAG: u8 = 1;
 
@@ -167,6 +170,10 @@ pub enum ViewError {
     /// Some events were not found.
     #[error("Events not found: {0:?}")]
     EventsNotFound(Vec<EventId>),
+
+    /// The body of the block was pruned: only its header and signatures are kept.
+    #[error("The body of block {0} was pruned")]
+    BlockBodyPruned(CryptoHash),
 }
 
 impl ViewError {
@@ -226,6 +233,43 @@ impl Hasher for sha3::Sha3_256 {
     }
 }
 
+/// A consumer of the hashes of the fields of a view, in the order in which they make up the
+/// hash of the view.
+pub trait FieldHashSink {
+    /// Consumes the hash of the field with the given name.
+    fn write_field_hash(&mut self, name: &'static str, hash: &[u8]) -> Result<(), ViewError>;
+}
+
+impl<H: Hasher> FieldHashSink for H {
+    fn write_field_hash(&mut self, _name: &'static str, hash: &[u8]) -> Result<(), ViewError> {
+        self.update_with_bytes(hash)
+    }
+}
+
+/// Returns the hash that a field marked with `#[view(hash_unless_default)]` has while it has
+/// its default value, i.e. the hash of an empty view of its type. The empty view is built
+/// without accessing storage.
+#[doc(hidden)]
+pub async fn default_hash<C, V>(view: &V) -> Result<Vec<u8>, ViewError>
+where
+    C: Clone + Send + Sync,
+    V: HashableView<C> + Send + Sync,
+{
+    let empty = V::new(view.context().clone())?;
+    Ok(empty.hash().await?.as_ref().to_vec())
+}
+
+/// Returns what a field marked with `#[view(hash_unless_default)]` contributes to the hash of
+/// its view when it doesn't have its default value: the hash of its name and its own hash, so
+/// that such fields are not mistaken for each other.
+#[doc(hidden)]
+pub fn named_field_hash(name: &str, hash: &[u8]) -> Result<HasherOutput, ViewError> {
+    let mut hasher = sha3::Sha3_256::default();
+    hasher.update_with_bytes(name.as_bytes())?;
+    hasher.update_with_bytes(hash)?;
+    Ok(hasher.finalize())
+}
+
 /// A [`View`] whose staged modifications can be saved in storage.
 #[async_trait]
 pub trait RootView<C>: View<C> {
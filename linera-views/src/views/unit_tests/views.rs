@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::VecDeque, fmt::Debug, marker::PhantomData};
+use std::{collections::VecDeque, fmt::Debug, marker::PhantomData, ops::Bound};
 
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
@@ -18,6 +18,9 @@ use crate::store::TestKeyValueStore;
 use crate::{
     batch::Batch,
     context::{Context, MemoryContext},
+    incremental_hash::HashVersion,
+    log_view::IncrementallyHashedLogView,
+    map_view::{ByteMapView, HashedMapView, IncrementallyHashedMapView},
     queue_view::QueueView,
     reentrant_collection_view::ReentrantCollectionView,
     register_view::{HashedRegisterView, RegisterView},
@@ -507,6 +510,134 @@ async fn test_flushing_cleared_view<V: TestView>(_view_type: PhantomData<V>) ->
     Ok(())
 }
 
+/// Checks that the incrementally maintained hash of a map matches the one computed from
+/// all its entries, and that a [`HashedMapView`] keeps its hash until the hash version is
+/// switched.
+#[tokio::test]
+async fn test_incrementally_hashed_map_view() -> anyhow::Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut view = HashedMapView::<_, u32, String>::load(context.clone()).await?;
+    for i in 0..10 {
+        view.insert(&i, i.to_string())?;
+    }
+    let legacy_hash = view.hash().await?;
+    save_view(&context, &mut view).await?;
+
+    let mut view = IncrementallyHashedMapView::<_, u32, String>::load(context.clone()).await?;
+    assert_eq!(view.hash_version(), HashVersion::Legacy);
+    assert_eq!(view.indices().await?.len(), 10);
+    view.insert(&3, "three".to_owned())?;
+    let mut legacy_view = HashedMapView::<_, u32, String>::load(context.clone()).await?;
+    legacy_view.insert(&3, "three".to_owned())?;
+    assert_eq!(view.hash_mut().await?, legacy_view.hash_mut().await?);
+    view.rollback();
+    assert_eq!(view.hash_mut().await?, legacy_hash);
+    view.switch_hash_version();
+    assert_ne!(view.hash_mut().await?, legacy_hash);
+    save_view(&context, &mut view).await?;
+
+    let mut view = IncrementallyHashedMapView::<_, u32, String>::load(context.clone()).await?;
+    assert_eq!(view.hash_version(), HashVersion::Incremental);
+    view.insert(&3, "three".to_owned())?;
+    view.remove(&4)?;
+    view.insert(&10, "10".to_owned())?;
+    let hash = view.hash_mut().await?;
+    save_view(&context, &mut view).await?;
+
+    let expected_context = MemoryContext::new_for_testing(());
+    let mut expected =
+        IncrementallyHashedMapView::<_, u32, String>::load(expected_context.clone()).await?;
+    expected.switch_hash_version();
+    for (index, value) in view.index_values().await? {
+        expected.insert(&index, value)?;
+    }
+    assert_eq!(expected.hash().await?, hash);
+
+    let mut view = IncrementallyHashedMapView::<_, u32, String>::load(context.clone()).await?;
+    assert_eq!(view.hash().await?, hash);
+    view.clear();
+    let mut empty =
+        IncrementallyHashedMapView::<_, u32, String>::load(MemoryContext::new_for_testing(()))
+            .await?;
+    empty.switch_hash_version();
+    let empty_hash = empty.hash_mut().await?;
+    assert_eq!(view.hash_mut().await?, empty_hash);
+    view.rollback();
+    assert_eq!(view.hash().await?, hash);
+
+    view.clear();
+    save_view(&context, &mut view).await?;
+    let mut view = IncrementallyHashedMapView::<_, u32, String>::load(context.clone()).await?;
+    assert_eq!(view.hash_version(), HashVersion::Incremental);
+    assert_eq!(view.hash_mut().await?, empty_hash);
+    Ok(())
+}
+
+/// Checks that the incrementally maintained hash of a log matches the one computed from
+/// all its entries.
+#[tokio::test]
+async fn test_incrementally_hashed_log_view() -> anyhow::Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut view = IncrementallyHashedLogView::<_, u32>::load(context.clone()).await?;
+    view.switch_hash_version();
+    view.push(1);
+    view.push(2);
+    view.hash_mut().await?;
+    save_view(&context, &mut view).await?;
+
+    let mut view = IncrementallyHashedLogView::<_, u32>::load(context.clone()).await?;
+    view.push(3);
+    let hash = view.hash_mut().await?;
+
+    let mut expected =
+        IncrementallyHashedLogView::<_, u32>::load(MemoryContext::new_for_testing(())).await?;
+    expected.switch_hash_version();
+    for value in [1, 2, 3] {
+        expected.push(value);
+    }
+    assert_eq!(expected.hash_mut().await?, hash);
+    Ok(())
+}
+
+/// Checks that clearing a range of a map removes exactly the stored and pending keys in
+/// the range.
+#[tokio::test]
+async fn test_byte_map_view_clear_range() -> anyhow::Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let keys = [
+        vec![],
+        vec![0],
+        vec![1, 2],
+        vec![1, 2, 0],
+        vec![1, 3],
+        vec![1, 255, 7],
+        vec![2],
+        vec![255, 255],
+    ];
+    let mut view = ByteMapView::<_, u8>::load(context.clone()).await?;
+    for (i, key) in keys.iter().enumerate().step_by(2) {
+        view.insert(key.clone(), i as u8);
+    }
+    save_view(&context, &mut view).await?;
+    let mut view = ByteMapView::<_, u8>::load(context.clone()).await?;
+    for (i, key) in keys.iter().enumerate().skip(1).step_by(2) {
+        view.insert(key.clone(), i as u8);
+    }
+    view.clear_range(vec![1, 2]..=vec![1, 255, 7]);
+    let expected = vec![vec![], vec![0], vec![2], vec![255, 255]];
+    assert_eq!(view.keys().await?, expected);
+    save_view(&context, &mut view).await?;
+    let mut view = ByteMapView::<_, u8>::load(context.clone()).await?;
+    assert_eq!(view.keys().await?, expected);
+
+    view.clear_range((Bound::Excluded(vec![0]), Bound::Unbounded));
+    assert_eq!(view.keys().await?, vec![vec![], vec![0]]);
+    save_view(&context, &mut view).await?;
+    let view = ByteMapView::<_, u8>::load(context.clone()).await?;
+    assert_eq!(view.keys().await?, vec![vec![], vec![0]]);
+    Ok(())
+}
+
 /// Saves a [`View`] into the [`MemoryContext<()>`] storage simulation.
 async fn save_view<C>(context: &C, view: &mut impl View<C>) -> anyhow::Result<()>
 where
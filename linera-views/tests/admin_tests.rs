@@ -5,6 +5,12 @@ use std::marker::PhantomData;
 
 #[cfg(with_dynamodb)]
 use linera_views::dynamo_db::DynamoDbStore;
+#[cfg(with_foundationdb)]
+use linera_views::foundation_db::FoundationDbStore;
+#[cfg(with_postgres)]
+use linera_views::postgres::PostgresStore;
+#[cfg(with_redis)]
+use linera_views::redis_db::RedisStore;
 #[cfg(with_rocksdb)]
 use linera_views::rocks_db::RocksDbStore;
 #[cfg(with_scylladb)]
@@ -20,6 +26,12 @@ use test_case::test_case;
 #[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbStore>; "RocksDbStore"))]
 #[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbStore>; "DynamoDbStore"))]
 #[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbStore>; "ScyllaDbStore"))]
+#[cfg_attr(with_postgres, test_case(PhantomData::<PostgresStore>; "PostgresStore"))]
+#[cfg_attr(
+    with_foundationdb,
+    test_case(PhantomData::<FoundationDbStore>; "FoundationDbStore")
+)]
+#[cfg_attr(with_redis, test_case(PhantomData::<RedisStore>; "RedisStore"))]
 #[tokio::test]
 async fn namespace_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomData<K>) {
     namespace_admin_test::<K>().await;
@@ -29,6 +41,12 @@ async fn namespace_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomDat
 #[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbStore>; "RocksDbStore"))]
 #[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbStore>; "DynamoDbStore"))]
 #[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbStore>; "ScyllaDbStore"))]
+#[cfg_attr(with_postgres, test_case(PhantomData::<PostgresStore>; "PostgresStore"))]
+#[cfg_attr(
+    with_foundationdb,
+    test_case(PhantomData::<FoundationDbStore>; "FoundationDbStore")
+)]
+#[cfg_attr(with_redis, test_case(PhantomData::<RedisStore>; "RedisStore"))]
 #[tokio::test]
 async fn root_key_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomData<K>) {
     root_key_admin_test::<K>().await;
@@ -6,8 +6,9 @@ use linera_views::{
     common::HasherOutput,
     context::MemoryContext,
     hashable_wrapper::WrappedHashableContainerView,
+    map_view::HashedMapView,
     register_view::{HashedRegisterView, RegisterView},
-    views::{HashableView, View},
+    views::{HashableView, Hasher as _, View},
 };
 use linera_views_derive::CryptoHashRootView;
 
@@ -17,6 +18,15 @@ struct TestType<C> {
     pub wrap: WrappedHashableContainerView<C, RegisterView<C, String>, HasherOutput>,
 }
 
+#[derive(CryptoHashRootView)]
+struct TestTypeWithDefaultFields<C> {
+    pub inner: RegisterView<C, String>,
+    #[view(hash_unless_default)]
+    pub optional: HashedRegisterView<C, u32>,
+    #[view(hash_unless_default)]
+    pub map: HashedMapView<C, u8, u32>,
+}
+
 // TODO(#560): Implement the same for CryptoHash
 #[tokio::test]
 async fn check_hashable_container_hash() -> Result<()> {
@@ -41,3 +51,37 @@ async fn check_hashable_hash() -> Result<()> {
     assert_eq!(hash0, view.hash().await?);
     Ok(())
 }
+
+#[tokio::test]
+async fn check_hash_unless_default() -> Result<()> {
+    let mut view = TestTypeWithDefaultFields::load(MemoryContext::new_for_testing(())).await?;
+    let mut inner = RegisterView::<_, String>::load(MemoryContext::new_for_testing(())).await?;
+    view.inner.set("hello".to_string());
+    inner.set("hello".to_string());
+    let mut hasher = sha3::Sha3_256::default();
+    hasher.update_with_bytes(inner.hash().await?.as_ref())?;
+    let hash_without_defaults = hasher.finalize();
+    assert_eq!(view.hash().await?, hash_without_defaults);
+    assert_eq!(view.hash_mut().await?, hash_without_defaults);
+
+    view.optional.set(5);
+    let hash = view.hash().await?;
+    assert_ne!(hash, hash_without_defaults);
+    assert_eq!(view.hash_mut().await?, hash);
+
+    view.optional.set(0);
+    assert_eq!(view.hash().await?, hash_without_defaults);
+
+    view.map.insert(&1, 5)?;
+    let hash = view.hash().await?;
+    assert_ne!(hash, hash_without_defaults);
+    assert_eq!(view.hash_mut().await?, hash);
+
+    // Collections only have their default value while they are empty.
+    view.map.insert(&1, 0)?;
+    assert_ne!(view.hash().await?, hash_without_defaults);
+    view.map.remove(&1)?;
+    assert_eq!(view.hash().await?, hash_without_defaults);
+    assert_eq!(view.hash_mut().await?, hash_without_defaults);
+    Ok(())
+}
@@ -2,12 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_views::{
+    backends::{
+        dual::DualStoreError,
+        migrating::{MigratingStore, MigrationMode},
+    },
     batch::Batch,
     context::{Context as _, MemoryContext},
     key_value_store_view::ViewContainer,
     memory::MemoryStore,
     random::make_deterministic_rng,
-    store::TestKeyValueStore as _,
+    store::{
+        AdminKeyValueStore as _, ReadableKeyValueStore as _, TestKeyValueStore as _,
+        WritableKeyValueStore as _,
+    },
     test_utils::{
         big_read_multi_values, get_random_test_scenarios, run_big_write_read, run_reads,
         run_writes_from_blank, run_writes_from_state,
@@ -64,6 +71,88 @@ async fn test_reads_memory() {
     }
 }
 
+#[tokio::test]
+async fn test_reads_migrating_memory() {
+    for mode in [MigrationMode::ReadOld, MigrationMode::ReadNew] {
+        for scenario in get_random_test_scenarios() {
+            let old_store = MemoryStore::new_test_store().await.unwrap();
+            let new_store = MemoryStore::new_test_store().await.unwrap();
+            let store = MigratingStore::new(old_store, new_store, mode).unwrap();
+            run_reads(store, scenario).await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_migrating_store_falls_back_to_old_store() {
+    let old_store = MemoryStore::new_test_store().await.unwrap();
+    let new_store = MemoryStore::new_test_store().await.unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1], vec![10]);
+    old_store.write_batch(batch).await.unwrap();
+
+    let store =
+        MigratingStore::new(old_store.clone(), new_store.clone(), MigrationMode::ReadNew).unwrap();
+    assert_eq!(store.read_value_bytes(&[1]).await.unwrap(), Some(vec![10]));
+    assert!(store.contains_key(&[1]).await.unwrap());
+    assert_eq!(new_store.read_value_bytes(&[1]).await.unwrap(), None);
+
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1], vec![11]);
+    batch.put_key_value_bytes(vec![2], vec![20]);
+    store.write_batch(batch).await.unwrap();
+    for store in [&old_store, &new_store] {
+        assert_eq!(
+            store
+                .read_multi_values_bytes(vec![vec![1], vec![2]])
+                .await
+                .unwrap(),
+            vec![Some(vec![11]), Some(vec![20])]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_migrating_store_backfill() {
+    let old_store = MemoryStore::new_test_store().await.unwrap();
+    let new_store = MemoryStore::new_test_store().await.unwrap();
+    let root_keys = [vec![], vec![7]];
+    for root_key in &root_keys {
+        let old_store = old_store.clone_with_root_key(root_key).unwrap();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], vec![10]);
+        batch.put_key_value_bytes(vec![2], vec![20]);
+        old_store.write_batch(batch).await.unwrap();
+    }
+    // A key that was deleted in the old store only.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![3], vec![30]);
+    new_store.write_batch(batch).await.unwrap();
+
+    let store = MigratingStore::new(old_store, new_store.clone(), MigrationMode::ReadOld).unwrap();
+    assert!(matches!(
+        store.check_verified().await,
+        Err(DualStoreError::MigrationNotVerified)
+    ));
+    assert_eq!(store.verify(&root_keys, 1).await.unwrap(), 5);
+    assert!(store.check_verified().await.is_err());
+
+    assert_eq!(store.backfill(&root_keys, 1).await.unwrap(), 5);
+    store.check_verified().await.unwrap();
+    assert_eq!(store.verify(&root_keys, 1).await.unwrap(), 0);
+    for root_key in &root_keys {
+        let new_store = new_store.clone_with_root_key(root_key).unwrap();
+        assert_eq!(
+            new_store
+                .read_multi_values_bytes(vec![vec![1], vec![2], vec![3]])
+                .await
+                .unwrap(),
+            vec![Some(vec![10]), Some(vec![20]), None]
+        );
+    }
+    assert!(store.failed_writes().await.unwrap().is_empty());
+}
+
 #[cfg(with_rocksdb)]
 #[tokio::test]
 async fn test_reads_rocks_db() {
@@ -114,6 +203,48 @@ async fn test_reads_scylla_db_no_root_key() {
     }
 }
 
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_reads_postgres() {
+    use linera_views::store::AdminKeyValueStore as _;
+
+    for scenario in get_random_test_scenarios() {
+        let store = linera_views::postgres::PostgresStore::new_test_store()
+            .await
+            .unwrap();
+        let store = store.clone_with_root_key(&[]).unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
+#[cfg(with_foundationdb)]
+#[tokio::test]
+async fn test_reads_foundation_db() {
+    use linera_views::store::AdminKeyValueStore as _;
+
+    for scenario in get_random_test_scenarios() {
+        let store = linera_views::foundation_db::FoundationDbStore::new_test_store()
+            .await
+            .unwrap();
+        let store = store.clone_with_root_key(&[]).unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
+#[cfg(with_redis)]
+#[tokio::test]
+async fn test_reads_redis() {
+    use linera_views::store::AdminKeyValueStore as _;
+
+    for scenario in get_random_test_scenarios() {
+        let store = linera_views::redis_db::RedisStore::new_test_store()
+            .await
+            .unwrap();
+        let store = store.clone_with_root_key(&[]).unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_reads_indexed_db() {
@@ -190,6 +321,33 @@ async fn test_scylla_db_writes_from_blank() {
     run_writes_from_blank(&store).await;
 }
 
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_postgres_writes_from_blank() {
+    let store = linera_views::postgres::PostgresStore::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
+#[cfg(with_foundationdb)]
+#[tokio::test]
+async fn test_foundation_db_writes_from_blank() {
+    let store = linera_views::foundation_db::FoundationDbStore::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
+#[cfg(with_redis)]
+#[tokio::test]
+async fn test_redis_writes_from_blank() {
+    let store = linera_views::redis_db::RedisStore::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_indexed_db_writes_from_blank() {
@@ -345,3 +503,55 @@ async fn test_scylladb_access() {
 async fn test_dynamodb_access() {
     access_admin_test::<linera_views::dynamo_db::DynamoDbStore>().await
 }
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_backup_and_restore() -> anyhow::Result<()> {
+    use linera_views::{rocks_db::RocksDbStoreInternal, store::AdminKeyValueStore as _};
+
+    let config = RocksDbStoreInternal::new_test_config().await?;
+    let backup_dir = tempfile::tempdir()?;
+    let backup_path = backup_dir.path().to_path_buf();
+    let store = RocksDbStoreInternal::recreate_and_connect(&config, "original").await?;
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1], vec![10]);
+    store.write_batch(batch).await?;
+    let first_backup = store.create_backup(backup_path.clone(), None).await?;
+
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1], vec![11]);
+    batch.put_key_value_bytes(vec![2], vec![20]);
+    store.write_batch(batch).await?;
+    let second_backup = store.create_backup(backup_path.clone(), None).await?;
+    assert!(first_backup.backup_id < second_backup.backup_id);
+    let backups = RocksDbStoreInternal::list_backups(&backup_path)?;
+    assert_eq!(backups.len(), 2);
+
+    RocksDbStoreInternal::restore_backup(
+        &config,
+        "restored_first",
+        backup_path.clone(),
+        Some(first_backup.backup_id),
+    )
+    .await?;
+    let restored = RocksDbStoreInternal::connect(&config, "restored_first").await?;
+    assert_eq!(restored.read_value_bytes(&[1]).await?, Some(vec![10]));
+    assert_eq!(restored.read_value_bytes(&[2]).await?, None);
+
+    RocksDbStoreInternal::restore_backup(&config, "restored_latest", backup_path.clone(), None)
+        .await?;
+    let restored = RocksDbStoreInternal::connect(&config, "restored_latest").await?;
+    assert_eq!(restored.read_value_bytes(&[1]).await?, Some(vec![11]));
+    assert_eq!(restored.read_value_bytes(&[2]).await?, Some(vec![20]));
+
+    assert!(
+        RocksDbStoreInternal::restore_backup(&config, "original", backup_path.clone(), None)
+            .await
+            .is_err()
+    );
+    let third_backup = store.create_backup(backup_path.clone(), Some(1)).await?;
+    let backups = RocksDbStoreInternal::list_backups(&backup_path)?;
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].backup_id, third_backup.backup_id);
+    Ok(())
+}
@@ -16,25 +16,26 @@ use linera_views::store::AdminKeyValueStore as _;
 use linera_views::{
     batch::{
         Batch, WriteOperation,
-        WriteOperation::{Delete, DeletePrefix, Put},
+        WriteOperation::{Delete, DeletePrefix, DeleteRange, Put},
     },
     collection_view::HashedCollectionView,
     context::{Context, MemoryContext, ViewContext},
     key_value_store_view::{KeyValueStoreView, ViewContainer},
     log_view::HashedLogView,
     lru_caching::{LruCachingMemoryStore, LruCachingStore, DEFAULT_STORAGE_CACHE_CONFIG},
-    map_view::{ByteMapView, HashedMapView},
+    map_view::{ByteMapView, HashedMapView, MapView},
     memory::MemoryStore,
     queue_view::HashedQueueView,
     random::make_deterministic_rng,
     reentrant_collection_view::HashedReentrantCollectionView,
-    register_view::HashedRegisterView,
+    register_view::{HashedRegisterView, RegisterView},
     set_view::HashedSetView,
     store::TestKeyValueStore as _,
     test_utils::{
         get_random_byte_vector, get_random_key_value_operations, get_random_key_values,
         span_random_reordering_put_delete,
     },
+    transaction::MultiViewTransaction,
     views::{CryptoHashRootView, HashableView, Hasher, RootView, View, ViewError},
 };
 use rand::{Rng, RngCore};
@@ -980,6 +981,7 @@ where
                 view.key_value_store.remove(key).await?;
             }
             DeletePrefix { key_prefix: _ } => {}
+            DeleteRange { start: _, end: _ } => {}
         }
         //
         let choice = rng.gen_range(0..10);
@@ -1204,3 +1206,54 @@ async fn check_large_write_memory() -> Result<()> {
     let mut store = MemoryTestStorage::new().await;
     check_large_write(&mut store, vector).await
 }
+
+#[derive(RootView)]
+pub struct CounterView<C> {
+    pub counter: RegisterView<C, u64>,
+}
+
+#[derive(RootView)]
+pub struct IndexView<C> {
+    pub entries: MapView<C, u64, String>,
+}
+
+#[tokio::test]
+async fn check_multi_view_transaction() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let transaction = MultiViewTransaction::new(context.clone());
+    let counter_context = transaction.context().clone_with_base_key(vec![0]);
+    let index_context = transaction.context().clone_with_base_key(vec![1]);
+
+    let mut counter = CounterView::load(counter_context.clone()).await?;
+    let mut index = IndexView::load(index_context.clone()).await?;
+    counter.counter.set(2);
+    index.entries.insert(&1, "first".to_string())?;
+    index.entries.insert(&2, "second".to_string())?;
+    counter.save().await?;
+    index.save().await?;
+    index.entries.remove(&1)?;
+    index.save().await?;
+    let mut batch = Batch::new();
+    batch.put_key_value(vec![2], &"certificate".to_string())?;
+    transaction.stage_batch(batch);
+
+    // The staged changes are visible through the transaction, but not written yet.
+    let counter = CounterView::load(counter_context).await?;
+    assert_eq!(*counter.counter.get(), 2);
+    assert_eq!(index.entries.indices().await?, vec![2]);
+    assert_eq!(index.entries.get(&2).await?, Some("second".to_string()));
+    let index = IndexView::load(index_context).await?;
+    assert_eq!(index.entries.indices().await?, vec![2]);
+    assert!(context.find_keys_by_prefix(&[]).await?.is_empty());
+
+    transaction.commit().await?;
+    let counter = CounterView::load(context.clone_with_base_key(vec![0])).await?;
+    assert_eq!(*counter.counter.get(), 2);
+    let index = IndexView::load(context.clone_with_base_key(vec![1])).await?;
+    assert_eq!(index.entries.indices().await?, vec![2]);
+    assert_eq!(
+        context.read_value::<String>(&[2]).await?,
+        Some("certificate".to_string())
+    );
+    Ok(())
+}
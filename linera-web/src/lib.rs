@@ -0,0 +1,392 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Linera web client: a WebAssembly module, published on npm as `@linera/client`, to use
+//! Linera wallets and chains from web pages and Node.js.
+//!
+//! A [`Faucet`] creates a [`Wallet`] and gives it chains. A [`Client`] connects a wallet to
+//! its network, to transfer tokens, query and call applications, and be notified of the new
+//! blocks and messages of its chains. In browsers, the client saves the wallet in IndexedDB
+//! after each change, and [`Wallet::read`] reads it back.
+//!
+//! The TypeScript declarations of the module, including the ones of the plain objects in
+//! [`types`], are generated from the Rust types by `wasm-pack build`.
+
+#![cfg(target_arch = "wasm32")]
+
+pub mod types;
+
+use std::{future::Future, sync::Arc};
+
+use clap::Parser as _;
+use futures::{lock::Mutex as AsyncMutex, StreamExt as _};
+use linera_base::{
+    crypto::InMemorySigner,
+    identifiers::{Account, AccountOwner, ApplicationId, ChainId},
+};
+use linera_client::{
+    client_options::ClientContextOptions, persistent, persistent::LocalPersist as _,
+    util::wait_for_next_round,
+};
+use linera_core::{
+    client::{ChainClient, ChainClientError},
+    data_types::ClientOutcome,
+};
+use linera_execution::{Query, QueryOutcome, QueryResponse, WasmRuntime, WithWasmDefault as _};
+use linera_rpc::node_provider::NodeProvider;
+use linera_storage::{DbStorage, WallClock};
+use linera_views::memory::{MemoryStore, MemoryStoreConfig};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::types::{GraphQlRequest, Notification};
+
+type WebStorage = DbStorage<MemoryStore, WallClock>;
+type WebEnvironment = linera_core::environment::Impl<WebStorage, NodeProvider>;
+type ClientContext = linera_client::client_context::ClientContext<
+    WebEnvironment,
+    persistent::Memory<linera_client::wallet::Wallet>,
+>;
+type JsResult<T> = Result<T, JsError>;
+
+/// The maximal number of stream queries to the in-memory storage.
+const MAX_STREAM_QUERIES: usize = 10;
+
+/// The IndexedDB key of the saved wallet.
+const WALLET_KEY: &str = "wallet";
+
+/// A wallet and the keys of its chains, as saved in IndexedDB.
+#[derive(Clone, Deserialize, Serialize)]
+struct StoredWallet {
+    wallet: linera_client::wallet::Wallet,
+    signer: InMemorySigner,
+}
+
+/// The chains of a user, and the keys that own them.
+#[wasm_bindgen]
+pub struct Wallet(StoredWallet);
+
+#[wasm_bindgen]
+impl Wallet {
+    /// Reads the wallet that a client saved in IndexedDB, if any.
+    pub async fn read() -> JsResult<Option<Wallet>> {
+        let stored = persistent::IndexedDb::<StoredWallet>::read(WALLET_KEY).await?;
+        Ok(stored.map(|stored| Wallet(stored.into_value())))
+    }
+
+    /// Parses a wallet exported by `toJson`.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> JsResult<Wallet> {
+        Ok(Wallet(serde_json::from_str(json)?))
+    }
+
+    /// Exports the wallet in JSON, including its secret keys.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> JsResult<String> {
+        Ok(serde_json::to_string(&self.0)?)
+    }
+
+    /// Returns the IDs of the chains of the wallet.
+    pub fn chains(&self) -> Vec<String> {
+        chain_id_strings(&self.0.wallet)
+    }
+
+    /// Returns the ID of the default chain of the wallet, if any.
+    #[wasm_bindgen(js_name = defaultChain)]
+    pub fn default_chain(&self) -> Option<String> {
+        self.0
+            .wallet
+            .default_chain()
+            .map(|chain_id| chain_id.to_string())
+    }
+}
+
+fn chain_id_strings(wallet: &linera_client::wallet::Wallet) -> Vec<String> {
+    wallet
+        .chain_ids()
+        .into_iter()
+        .map(|chain_id| chain_id.to_string())
+        .collect()
+}
+
+/// A faucet, which creates wallets for its network and gives them chains.
+#[wasm_bindgen]
+pub struct Faucet(linera_faucet_client::Faucet);
+
+#[wasm_bindgen]
+impl Faucet {
+    /// Creates a faucet for the server at the given URL.
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String) -> Faucet {
+        Faucet(linera_faucet_client::Faucet::new(url))
+    }
+
+    /// Creates a new wallet for the faucet's network, without chains yet.
+    #[wasm_bindgen(js_name = createWallet)]
+    pub async fn create_wallet(&self) -> JsResult<Wallet> {
+        let genesis_config = self.0.genesis_config().await?;
+        Ok(Wallet(StoredWallet {
+            wallet: linera_client::wallet::Wallet::new(genesis_config),
+            signer: InMemorySigner::new(None),
+        }))
+    }
+
+    /// Requests a new chain, owned by a new key of the client's wallet, and returns its ID.
+    /// The first chain becomes the default chain of the wallet.
+    #[wasm_bindgen(js_name = claimChain)]
+    pub async fn claim_chain(&self, client: &Client) -> JsResult<String> {
+        let owner = AccountOwner::from(client.signer.clone().generate_new());
+        let outcome = self.0.claim(&owner).await?;
+        let mut context = client.context.lock().await;
+        context
+            .assign_new_chain_to_key(outcome.chain_id, owner)
+            .await?;
+        client.save(&context).await?;
+        Ok(outcome.chain_id.to_string())
+    }
+}
+
+/// A function called with each [`Notification`] of a chain.
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "(notification: Notification) => void")]
+    pub type NotificationHandler;
+}
+
+/// A connection of a wallet to its network.
+#[wasm_bindgen]
+pub struct Client {
+    context: Arc<AsyncMutex<ClientContext>>,
+    signer: InMemorySigner,
+    /// Whether to save the wallet in IndexedDB after each change.
+    persist: bool,
+}
+
+#[wasm_bindgen]
+impl Client {
+    /// Connects the wallet to its network. Unless `persist` is `false`, e.g. in Node.js,
+    /// the wallet is saved in IndexedDB, now and after each change.
+    pub async fn create(wallet: Wallet, persist: Option<bool>) -> JsResult<Client> {
+        let StoredWallet { wallet, signer } = wallet.0;
+        let mut storage = WebStorage::maybe_create_and_connect(
+            &MemoryStoreConfig::new(MAX_STREAM_QUERIES),
+            "linera",
+            None::<WasmRuntime>.with_wasm_default(),
+        )
+        .await?;
+        wallet
+            .genesis_config()
+            .initialize_storage(&mut storage)
+            .await?;
+        // The default values of the options of the `linera` tool, without progress reports.
+        let options = ClientContextOptions::parse_from(["linera-web", "--quiet"]);
+        let context = ClientContext::new(
+            storage,
+            options,
+            persistent::Memory::new(wallet),
+            Box::new(signer.clone()),
+        );
+        let client = Client {
+            context: Arc::new(AsyncMutex::new(context)),
+            signer,
+            persist: persist.unwrap_or(true),
+        };
+        client.save(&*client.context.lock().await).await?;
+        Ok(client)
+    }
+
+    /// Returns the IDs of the chains of the wallet.
+    pub async fn chains(&self) -> Vec<String> {
+        chain_id_strings(self.context.lock().await.wallet())
+    }
+
+    /// Exports the wallet, with its latest changes.
+    pub async fn wallet(&self) -> Wallet {
+        Wallet(StoredWallet {
+            wallet: self.context.lock().await.wallet().clone(),
+            signer: self.signer.clone(),
+        })
+    }
+
+    /// Synchronizes the chain with the validators, and returns its balance.
+    pub async fn balance(&self, chain_id: String) -> JsResult<String> {
+        let chain_client = self.chain_client(&chain_id).await?;
+        chain_client.synchronize_from_validators().await?;
+        Ok(chain_client.query_balance().await?.to_string())
+    }
+
+    /// Transfers tokens from the chain's balance to an account, e.g. `<chain ID>` or
+    /// `<owner>@<chain ID>`, and returns the hash of the block.
+    pub async fn transfer(
+        &self,
+        chain_id: String,
+        amount: String,
+        recipient: String,
+    ) -> JsResult<String> {
+        let amount = amount.parse()?;
+        let recipient: Account = recipient.parse()?;
+        let certificate = self
+            .commit(&chain_id, |chain_client| async move {
+                chain_client
+                    .transfer_to_account(AccountOwner::CHAIN, amount, recipient)
+                    .await
+            })
+            .await?;
+        Ok(certificate.hash().to_string())
+    }
+
+    /// Runs a GraphQL query against the application's service, on the client's current
+    /// state of the chain, and returns the `data` of the response.
+    #[wasm_bindgen(js_name = queryApplication)]
+    pub async fn query_application(
+        &self,
+        chain_id: String,
+        application_id: String,
+        request: GraphQlRequest,
+    ) -> JsResult<JsValue> {
+        let chain_client = self.chain_client(&chain_id).await?;
+        let (data, _) = query_user_application(&chain_client, &application_id, &request).await?;
+        // Serialize JSON objects as plain objects, not maps.
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        Ok(data.serialize(&serializer)?)
+    }
+
+    /// Runs a GraphQL mutation against the application's service, executes the operations
+    /// that it schedules in a new block, and returns the hash of the block.
+    #[wasm_bindgen(js_name = mutateApplication)]
+    pub async fn mutate_application(
+        &self,
+        chain_id: String,
+        application_id: String,
+        request: GraphQlRequest,
+    ) -> JsResult<String> {
+        let chain_client = self.chain_client(&chain_id).await?;
+        let (_, operations) =
+            query_user_application(&chain_client, &application_id, &request).await?;
+        if operations.is_empty() {
+            return Err(JsError::new("the mutation did not schedule any operation"));
+        }
+        let certificate = self
+            .commit(&chain_id, |chain_client| {
+                let operations = operations.clone();
+                async move { chain_client.execute_operations(operations, vec![]).await }
+            })
+            .await?;
+        Ok(certificate.hash().to_string())
+    }
+
+    /// Calls the handler with each notification of the chain from now on, and keeps the
+    /// chain up to date.
+    #[wasm_bindgen(js_name = onNotification)]
+    pub async fn on_notification(
+        &self,
+        chain_id: String,
+        handler: NotificationHandler,
+    ) -> JsResult<()> {
+        let handler: js_sys::Function = handler.unchecked_into();
+        let chain_client = self.chain_client(&chain_id).await?;
+        let (listener, abort_handle, mut notifications) = chain_client.listen().await?;
+        wasm_bindgen_futures::spawn_local(listener);
+        wasm_bindgen_futures::spawn_local(async move {
+            // The listener runs as long as its abort handle is not dropped.
+            let _abort_handle = abort_handle;
+            while let Some(notification) = notifications.next().await {
+                let result = serde_wasm_bindgen::to_value(&Notification::from(notification))
+                    .map_err(JsValue::from)
+                    .and_then(|notification| handler.call1(&JsValue::NULL, &notification));
+                if let Err(error) = result {
+                    web_sys::console::error_2(&"Notification handler failed:".into(), &error);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn chain_client(&self, chain_id: &str) -> JsResult<ChainClient<WebEnvironment>> {
+        let chain_id: ChainId = chain_id.parse()?;
+        Ok(self
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .await?)
+    }
+
+    /// Applies the given function to the chain client until it does not need to wait for
+    /// the next round anymore, saves the updated wallet, and returns the result.
+    async fn commit<T, F, Fut>(&self, chain_id: &str, mut f: F) -> JsResult<T>
+    where
+        F: FnMut(ChainClient<WebEnvironment>) -> Fut,
+        Fut: Future<Output = Result<ClientOutcome<T>, ChainClientError>>,
+    {
+        let chain_client = self.chain_client(chain_id).await?;
+        let mut notifications = None;
+        let result = loop {
+            chain_client.prepare_chain().await?;
+            let timeout = match f(chain_client.clone()).await? {
+                ClientOutcome::Committed(result) => break result,
+                ClientOutcome::WaitForTimeout(timeout) => timeout,
+            };
+            // Listen to the notifications, to learn about new rounds and blocks.
+            if notifications.is_none() {
+                let (listener, abort_handle, stream) = chain_client.listen().await?;
+                wasm_bindgen_futures::spawn_local(listener);
+                notifications = Some((abort_handle, stream));
+            }
+            let (_, stream) = notifications.as_mut().expect("the listener was started");
+            wait_for_next_round(stream, timeout).await;
+        };
+        let mut context = self.context.lock().await;
+        context.update_wallet_from_client(&chain_client).await?;
+        self.save(&context).await?;
+        Ok(result)
+    }
+
+    /// Saves the wallet in IndexedDB, unless the client does not persist it.
+    async fn save(&self, context: &ClientContext) -> JsResult<()> {
+        if self.persist {
+            let stored = StoredWallet {
+                wallet: context.wallet().clone(),
+                signer: self.signer.clone(),
+            };
+            persistent::IndexedDb::new(WALLET_KEY, stored)
+                .await?
+                .persist()
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a GraphQL request against the application's service, and returns the `data` of the
+/// response and the operations that it scheduled.
+async fn query_user_application(
+    chain_client: &ChainClient<WebEnvironment>,
+    application_id: &str,
+    request: &GraphQlRequest,
+) -> JsResult<(serde_json::Value, Vec<linera_execution::Operation>)> {
+    // Application IDs have no text format of their own, but are serialized as strings.
+    let application_id: ApplicationId =
+        serde_json::from_value(serde_json::Value::String(application_id.to_owned()))?;
+    let query = Query::User {
+        application_id,
+        bytes: serde_json::to_vec(request)?,
+    };
+    let QueryOutcome {
+        response,
+        operations,
+    } = chain_client.query_application(query).await?;
+    let QueryResponse::User(bytes) = response else {
+        unreachable!("a user application query has a user application response");
+    };
+    let mut response: serde_json::Value = serde_json::from_slice(&bytes)?;
+    if let Some(errors) = response
+        .get("errors")
+        .filter(|errors| errors.as_array().is_some_and(|errors| !errors.is_empty()))
+    {
+        return Err(JsError::new(&format!(
+            "the application returned errors: {errors}"
+        )));
+    }
+    Ok((response["data"].take(), operations))
+}
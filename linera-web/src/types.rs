@@ -0,0 +1,76 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The types exchanged with JavaScript as plain objects. Their TypeScript declarations are
+//! generated by `tsify`.
+
+use linera_core::worker::Reason;
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A GraphQL request to the service of an application.
+#[derive(Debug, Deserialize, Serialize, Tsify)]
+#[tsify(from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlRequest {
+    /// The query or mutation.
+    pub query: String,
+    /// The values of the variables of the query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional, type = "Record<string, unknown>")]
+    pub variables: Option<serde_json::Value>,
+    /// The operation to run, if the query has several.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub operation_name: Option<String>,
+}
+
+/// Something that happened on a chain, passed to the handlers of `Client.onNotification`.
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Notification {
+    /// A block was added to the chain.
+    NewBlock {
+        chain_id: String,
+        height: u64,
+        hash: String,
+    },
+    /// Messages were sent to the chain, and are now in its inbox.
+    NewIncomingBundle {
+        chain_id: String,
+        /// The chain that sent the messages.
+        origin: String,
+        /// The height of the block that sent the messages.
+        height: u64,
+    },
+    /// The validators started a new round for the next block of the chain.
+    NewRound { chain_id: String, height: u64 },
+}
+
+impl From<linera_core::worker::Notification> for Notification {
+    fn from(notification: linera_core::worker::Notification) -> Self {
+        let chain_id = notification.chain_id.to_string();
+        match notification.reason {
+            Reason::NewBlock { height, hash, .. } => Notification::NewBlock {
+                chain_id,
+                height: height.into(),
+                hash: hash.to_string(),
+            },
+            Reason::NewIncomingBundle { origin, height } => Notification::NewIncomingBundle {
+                chain_id,
+                origin: origin.to_string(),
+                height: height.into(),
+            },
+            Reason::NewRound { height, .. } => Notification::NewRound {
+                chain_id,
+                height: height.into(),
+            },
+        }
+    }
+}
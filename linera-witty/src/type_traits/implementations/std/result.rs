@@ -40,7 +40,17 @@ where
     type Dependencies = HList![T, E];
 
     fn wit_type_name() -> Cow<'static, str> {
-        format!("result<{}, {}>", T::wit_type_name(), E::wit_type_name()).into()
+        // WIT has no unit type, so unit payloads are left out.
+        let unit = <() as WitType>::wit_type_name();
+        let ok = T::wit_type_name();
+        let err = E::wit_type_name();
+
+        match (ok == unit, err == unit) {
+            (true, true) => "result".into(),
+            (true, false) => format!("result<_, {err}>").into(),
+            (false, true) => format!("result<{ok}>").into(),
+            (false, false) => format!("result<{ok}, {err}>").into(),
+        }
     }
 
     fn wit_type_declaration() -> Cow<'static, str> {
@@ -3,7 +3,7 @@
 
 //! Unit tests for implementations of the custom traits for existing types.
 
-use std::{collections::BTreeMap, fmt::Debug, time::Duration};
+use std::{collections::BTreeMap, fmt::Debug, marker::PhantomData, time::Duration};
 
 use frunk::hlist;
 
@@ -154,6 +154,21 @@ fn large_err() {
     );
 }
 
+/// Test that only unit payloads are left out of the WIT names of `Result` types.
+#[test]
+fn result_type_names() {
+    assert_eq!(<Result<(), ()> as WitType>::wit_type_name(), "result");
+    assert_eq!(
+        <Result<(), u8> as WitType>::wit_type_name(),
+        "result<_, u8>"
+    );
+    assert_eq!(<Result<u8, ()> as WitType>::wit_type_name(), "result<u8>");
+    assert_eq!(
+        <Result<PhantomData<u8>, u8> as WitType>::wit_type_name(),
+        "result<unit, u8>"
+    );
+}
+
 /// Test roundtrip of [`Duration`].
 #[test]
 fn duration() {